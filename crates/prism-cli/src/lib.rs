@@ -1,6 +1,120 @@
-//! PRISM v2 CLI library.
+//! # PRISM CLI - Command-Line Front End of the MD Engine
 //!
-//! Exposes metrics server and utilities for external integration.
+//! Library side of the `prism-cli` binary:
+//!
+//! - [`simulate`]: `minimize`, `nlnm`, `md` and `pimc` on a PTB, PDB or
+//!   mmCIF structure (or the `[system]` of a run configuration)
+//! - [`analyze`]: RMSD/RMSF, shape and SASA of a DCD or XTC trajectory
+//! - [`convert`]: between `.pdb`, `.cif` and `.ptb`, with a validation
+//!   report for PDB/mmCIF → PTB
+//! - [`campaign`]: a parameter sweep over a run configuration template
+//! - [`validate`]: the NVE energy conservation check of the build
+
+pub mod analyze;
+pub mod simulate;
+
+use anyhow::{bail, Context, Result};
+use prism_physics::campaign::Campaign;
+use prism_physics::energy_conservation::{validate_all, ConservationConfig};
+use prism_io::ptb_convert::{convert_to_ptb, PtbConversionOptions};
+use prism_io::structure_file::{read_structure, write_structure, StructureFormat};
+use std::path::{Path, PathBuf};
+
+/// Options of [`convert`]
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// PDB/mmCIF → PTB conversion (alternate locations, removals, strictness)
+    pub ptb: PtbConversionOptions,
+    /// Write the PTB conversion report as JSON
+    pub report: Option<PathBuf>,
+}
+
+/// Convert a structure file to the format given by the extension of
+/// `output`; PDB and mmCIF inputs written as PTB go through
+/// [`convert_to_ptb`] and print its validation report
+pub fn convert(input: &Path, output: &Path, options: &ConvertOptions) -> Result<()> {
+    let to_ptb = StructureFormat::from_path(output)? == StructureFormat::Ptb
+        && StructureFormat::from_path(input)? != StructureFormat::Ptb;
+    if !to_ptb {
+        if options.report.is_some() {
+            bail!("--report is only produced for PDB/mmCIF → PTB conversions");
+        }
+        let structure = read_structure(input)?;
+        write_structure(output, &structure)?;
+        println!("💾 Wrote {} ({} atoms)", output.display(), structure.atoms.len());
+        return Ok(());
+    }
+
+    let report = convert_to_ptb(input, output, &options.ptb)?;
+    println!("{}", report);
+    println!("💾 Wrote {} ({} atoms)", output.display(), report.atoms);
+    if let Some(path) = &options.report {
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("💾 Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Run the campaign file at `path`, printing its summary table; fails when
+/// any run did not succeed
+pub fn campaign(path: &Path) -> Result<()> {
+    let campaign = Campaign::load(path)?;
+    let summary = campaign.run()?;
+    print!("{}", summary.to_table());
+    println!("💾 Wrote {}", campaign.config().output_dir.join("summary.tsv").display());
+    if summary.failures() > 0 {
+        bail!("{} of {} campaign runs failed", summary.failures(), summary.runs.len());
+    }
+    Ok(())
+}
+
+/// Run the NVE energy conservation check on the built-in reference systems
+/// (`steps` per trajectory, default 10 000), printing one line per system
+/// and optionally writing the reports as JSON; fails when any system drifts
+pub fn validate(steps: Option<u64>, report: Option<&Path>) -> Result<()> {
+    let mut config = ConservationConfig::default();
+    if let Some(steps) = steps {
+        config.steps = steps;
+    }
+    let reports = validate_all(&config)?;
+    for report in &reports {
+        println!("{}", report);
+    }
+    if let Some(path) = report {
+        let json = serde_json::to_string_pretty(&reports)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("💾 Wrote {}", path.display());
+    }
+    let failed = reports.iter().filter(|r| !r.passed).count();
+    if failed > 0 {
+        bail!("{} of {} reference systems do not conserve energy", failed, reports.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    pub(crate) const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+";
+
+    #[test]
+    fn test_convert_to_ptb_writes_report() {
+        let scratch = tempfile::tempdir().unwrap();
+        let dir = scratch.path();
+        let (pdb, ptb, report) = (dir.join("ala.pdb"), dir.join("ala.ptb"), dir.join("ala.json"));
+        std::fs::write(&pdb, PEPTIDE).unwrap();
+        let options = super::ConvertOptions { report: Some(report.clone()), ..Default::default() };
+        super::convert(&pdb, &ptb, &options).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+        assert_eq!(json["atoms"], 4);
+        assert_eq!(json["missing_atoms"][0]["atoms"][0], "CB");
 
-pub mod config;
-pub mod metrics_server;
+        // Reports only come with PTB conversions
+        assert!(super::convert(&ptb, &dir.join("ala.cif"), &options).is_err());
+    }
+}
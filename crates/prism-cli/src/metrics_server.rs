@@ -1,209 +0,0 @@
-//! HTTP metrics server for Prometheus scraping.
-//!
-//! Provides an HTTP endpoint serving PRISM metrics in Prometheus text format.
-//! Designed for production monitoring with health checks and graceful shutdown.
-//!
-//! SPECIFICATION ADHERENCE:
-//! - Exposes /metrics endpoint for Prometheus scraping
-//! - Provides /health endpoint for Kubernetes/Docker health probes
-//! - Thread-safe and non-blocking via Axum async framework
-//!
-//! ENDPOINTS:
-//! - GET /metrics: Returns Prometheus text format metrics
-//! - GET /health: Returns "OK" for liveness/readiness probes
-//!
-//! PERFORMANCE:
-//! - Metrics endpoint: < 10ms response time
-//! - Concurrent scraping supported (lock-free reads)
-//! - Zero-allocation metrics gathering
-//!
-//! SECURITY:
-//! - Binds to 0.0.0.0 (expose to Prometheus server)
-//! - No authentication (assumes secure network or Prometheus auth)
-//! - Rate limiting handled by Prometheus scrape_interval
-
-use anyhow::Result;
-use axum::{
-    extract::State,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::get,
-    Router,
-};
-use prism_pipeline::telemetry::prometheus::PrometheusMetrics;
-use std::net::SocketAddr;
-use std::sync::Arc;
-
-/// Metrics server state shared across handlers.
-#[derive(Clone)]
-struct MetricsServerState {
-    /// Prometheus metrics registry
-    metrics: Arc<PrometheusMetrics>,
-}
-
-/// Starts HTTP metrics server for Prometheus scraping.
-///
-/// # Arguments
-/// * `port` - TCP port to bind (typically 9090 or 9100)
-/// * `metrics` - Prometheus metrics registry to expose
-///
-/// # Returns
-/// Never returns (server runs indefinitely) or error on startup failure.
-///
-/// # Errors
-/// Returns error if:
-/// - Port is already in use
-/// - Insufficient permissions to bind port < 1024
-/// - Network interface unavailable
-///
-/// # Example
-/// ```rust,no_run
-/// use prism_cli::metrics_server::start_metrics_server;
-/// use prism_pipeline::telemetry::prometheus::PrometheusMetrics;
-///
-/// # async fn example() -> anyhow::Result<()> {
-/// let metrics = PrometheusMetrics::new()?;
-/// start_metrics_server(9090, metrics).await?;
-/// # Ok(())
-/// # }
-/// ```
-pub async fn start_metrics_server(port: u16, metrics: Arc<PrometheusMetrics>) -> Result<()> {
-    let state = MetricsServerState { metrics };
-
-    // Build Axum router with endpoints
-    let app = Router::new()
-        .route("/metrics", get(metrics_handler))
-        .route("/health", get(health_handler))
-        .with_state(state);
-
-    // Bind to all interfaces (allows Prometheus to scrape)
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-
-    log::info!("Metrics server listening on http://{}", addr);
-    log::info!("  - Metrics endpoint: http://{}/metrics", addr);
-    log::info!("  - Health endpoint:  http://{}/health", addr);
-
-    // Start server (runs indefinitely) - Axum 0.7 API
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
-
-    Ok(())
-}
-
-/// Handler for GET /metrics endpoint.
-///
-/// Returns all PRISM metrics in Prometheus text exposition format.
-///
-/// # Response
-/// - Status: 200 OK
-/// - Content-Type: text/plain; version=0.0.4; charset=utf-8
-/// - Body: Prometheus metrics (UTF-8 text)
-///
-/// # Performance
-/// - Target: < 10ms per request
-/// - Lock-free metric gathering
-/// - No heap allocations during export
-///
-/// # Example Response
-/// ```text
-/// # HELP prism_phase_iteration_total Total iterations executed in each phase
-/// # TYPE prism_phase_iteration_total counter
-/// prism_phase_iteration_total{phase="Phase0"} 150
-/// prism_phase_iteration_total{phase="Phase2"} 500
-///
-/// # HELP prism_gpu_utilization GPU utilization as fraction [0.0, 1.0] per device
-/// # TYPE prism_gpu_utilization gauge
-/// prism_gpu_utilization{device="0"} 0.82
-/// ```
-async fn metrics_handler(
-    State(state): State<MetricsServerState>,
-) -> Result<impl IntoResponse, MetricsError> {
-    // Export metrics in Prometheus text format
-    let output = state
-        .metrics
-        .export_text()
-        .map_err(|e| MetricsError::ExportFailed(e.to_string()))?;
-
-    // Return with correct Content-Type header
-    Ok((
-        StatusCode::OK,
-        [("Content-Type", "text/plain; version=0.0.4; charset=utf-8")],
-        output,
-    ))
-}
-
-/// Handler for GET /health endpoint.
-///
-/// Returns simple "OK" response for Kubernetes/Docker health probes.
-///
-/// # Response
-/// - Status: 200 OK
-/// - Content-Type: text/plain
-/// - Body: "OK"
-///
-/// # Use Cases
-/// - Kubernetes liveness probe
-/// - Docker HEALTHCHECK
-/// - Load balancer health checks
-/// - Uptime monitoring
-///
-/// # Example
-/// ```bash
-/// curl http://localhost:9090/health
-/// # Returns: OK
-/// ```
-async fn health_handler() -> impl IntoResponse {
-    (StatusCode::OK, "OK")
-}
-
-/// Error type for metrics server handlers.
-#[derive(Debug)]
-enum MetricsError {
-    /// Failed to export metrics from registry
-    ExportFailed(String),
-}
-
-impl IntoResponse for MetricsError {
-    fn into_response(self) -> Response {
-        let (status, message) = match self {
-            MetricsError::ExportFailed(msg) => {
-                log::error!("Metrics export failed: {}", msg);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Failed to export metrics: {}", msg),
-                )
-            }
-        };
-
-        (status, message).into_response()
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tokio;
-
-    #[tokio::test]
-    async fn test_health_endpoint() {
-        let response = health_handler().await.into_response();
-        assert_eq!(response.status(), StatusCode::OK);
-    }
-
-    #[tokio::test]
-    async fn test_metrics_handler() {
-        let metrics = PrometheusMetrics::new().expect("Failed to create metrics");
-        let state = MetricsServerState { metrics };
-
-        // Record some test metrics
-        state.metrics.record_phase_iteration("Phase0", 0.5).unwrap();
-        state.metrics.record_gpu_utilization(0, 0.75).unwrap();
-
-        // Call handler
-        let result = metrics_handler(State(state)).await;
-        assert!(result.is_ok());
-
-        let (status, _headers, body) = result.unwrap().into_response().into_parts();
-        assert_eq!(status, StatusCode::OK);
-    }
-}
@@ -0,0 +1,412 @@
+//! # Simulation Commands - minimize, nlnm, md and pimc
+//!
+//! Each protocol starts from a preset engine configuration, replaced by
+//! the `[engine]` section of `--config` when one is given; the command
+//! line flags are applied last. The structure comes from the input file
+//! or, without one, from the `[system]` section of the config. Ligands
+//! given with `--ligand` are parameterized GAFF-style and run next to the
+//! structure as a complex; the final structure lists them as HETATM
+//! residues after it. With `--coarse-grain` the protein of the input runs
+//! as a MARTINI bead model (20 fs steps unless `--config` sets them) and
+//! the final structure is back-mapped onto its atoms. `--potential` swaps
+//! the force field for an ONNX network potential.
+
+use anyhow::{bail, Context, Result};
+use prism_core::PhaseOutcome;
+use prism_io::ligand::LigandSpec;
+use prism_io::pdb::PdbStructure;
+use prism_io::structure_file::{read_structure, sovereign_buffer, write_structure};
+use prism_physics::coarse_grain::{CoarseGrainedModel, MartiniConfig, MARTINI_TIME_STEP};
+use prism_physics::ml_potential::{MlPotentialConfig, NeuralNetworkPotential};
+use prism_physics::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsConfigBuilder, MolecularDynamicsEngine, MolecularDynamicsStats};
+use prism_physics::resource_estimate::ResourceEstimate;
+use prism_physics::run_config::RunConfig;
+use prism_physics::units::{Energy, Temperature};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+
+/// Simulation protocol of a subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// Energy minimization
+    Minimize,
+    /// Annealed NLNM breathing dynamics
+    Nlnm,
+    /// Constant-temperature Langevin dynamics
+    Md,
+    /// Path-integral Monte Carlo
+    Pimc,
+}
+
+impl Protocol {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Minimize => "minimize",
+            Self::Nlnm => "nlnm",
+            Self::Md => "md",
+            Self::Pimc => "pimc",
+        }
+    }
+
+    /// Engine configuration used without `--config`
+    pub fn preset(self) -> MolecularDynamicsConfigBuilder {
+        match self {
+            Self::Minimize | Self::Md => MolecularDynamicsConfigBuilder::quick_test().max_steps(10_000),
+            Self::Nlnm => MolecularDynamicsConfigBuilder::production_breathing().use_gpu(false),
+            Self::Pimc => MolecularDynamicsConfigBuilder::quantum_pimc(),
+        }
+    }
+}
+
+/// Inputs and overrides shared by the simulation subcommands
+#[derive(Debug, Clone, Default)]
+pub struct SimulationOptions {
+    /// PTB, PDB or mmCIF structure; the config's `[system]` when `None`
+    pub input: Option<PathBuf>,
+    /// TOML/YAML/JSON run configuration
+    pub config: Option<PathBuf>,
+    /// Profile of the run configuration to apply
+    pub profile: Option<String>,
+    /// Steps (sweeps for PIMC, iterations for minimization)
+    pub steps: Option<u64>,
+    /// Constant thermostat temperature (K)
+    pub temperature: Option<f64>,
+    /// Run the dynamics on the GPU
+    pub gpu: bool,
+    /// Final structure (`.pdb`, `.cif` or `.ptb`)
+    pub output: Option<PathBuf>,
+    /// Outcome, telemetry and final statistics as JSON
+    pub report: Option<PathBuf>,
+    /// Ligands run with the structure
+    pub ligands: Vec<LigandSpec>,
+    /// Run a MARTINI model of the input protein instead of its atoms
+    pub coarse_grain: Option<MartiniConfig>,
+    /// Network potential replacing the force field
+    pub potential: Option<MlPotentialConfig>,
+    /// Steps between progress lines (0 disables them)
+    pub progress_interval: u64,
+}
+
+/// Result of a simulation command
+#[derive(Debug)]
+pub struct SimulationReport {
+    pub outcome: PhaseOutcome,
+    pub statistics: MolecularDynamicsStats,
+}
+
+fn kelvin(kt: f32) -> f64 {
+    Temperature::from_kt(Energy::kcal_per_mol(kt as f64)).as_kelvin()
+}
+
+fn progress_line(stats: &MolecularDynamicsStats) -> String {
+    format!(
+        "step {:>9}/{:<9} E = {:>14.4} kcal/mol  T = {:>7.1} K  |g| = {:>10.4}  {:>8.1} s",
+        stats.current_step,
+        stats.total_steps,
+        stats.current_energy,
+        kelvin(stats.current_temperature),
+        stats.gradient_norm,
+        stats.runtime_seconds
+    )
+}
+
+/// Engine configuration for `protocol` after the config file and flags
+pub fn engine_config(protocol: Protocol, options: &SimulationOptions) -> Result<(MolecularDynamicsConfig, Option<RunConfig>)> {
+    let run = options.config.as_ref().map(|path| RunConfig::load(path, options.profile.as_deref())).transpose()?;
+    let mut config = match &run {
+        Some(run) => run.engine.clone(),
+        None => protocol.preset().build()?,
+    };
+    if let Some(steps) = options.steps {
+        match protocol {
+            Protocol::Minimize => config.minimization.max_iterations = steps as usize,
+            _ => config.max_steps = steps,
+        }
+    }
+    if let Some(kelvin) = options.temperature {
+        let kt = Temperature::kelvin(kelvin).kt().as_kcal_per_mol() as f32;
+        config.temp_start = kt;
+        config.temp_end = kt;
+        config.temperature_schedule = None;
+    }
+    if options.gpu {
+        config.use_gpu = true;
+    }
+    if let Some(martini) = &options.coarse_grain {
+        config.force_field = martini.force_field(config.force_field.clone());
+        if run.is_none() {
+            config.dt = MARTINI_TIME_STEP;
+        }
+    }
+    if protocol == Protocol::Pimc && config.pimc.is_none() {
+        config.pimc = MolecularDynamicsConfigBuilder::quantum_pimc().build()?.pimc;
+    }
+    config.validate()?;
+    Ok((config, run))
+}
+
+/// Estimate the resources of `protocol` without running it and print them
+pub fn dry_run(protocol: Protocol, options: &SimulationOptions) -> Result<ResourceEstimate> {
+    let (mut config, _) = engine_config(protocol, options)?;
+    let Some(input) = &options.input else { bail!("A dry run needs an input structure") };
+    if protocol == Protocol::Minimize {
+        config.max_steps = config.minimization.max_iterations as u64;
+    }
+    let estimate = MolecularDynamicsEngine::estimate(&config, &sovereign_buffer(input)?)?;
+    let mib = |bytes: f64| bytes / (1024.0 * 1024.0);
+    println!("📐 {} ({} atoms, {} steps, {})", protocol.name(), estimate.num_atoms, estimate.steps, if estimate.gpu { "GPU" } else { "CPU" });
+    println!("   VRAM        {:>10.1} MiB", mib(estimate.vram_bytes() as f64));
+    println!("   host memory {:>10.1} MiB", mib(estimate.host_bytes as f64));
+    println!("   trajectory  {:>10.1} MiB ({} frames)", mib(estimate.trajectory_bytes as f64), estimate.trajectory_frames);
+    println!("   wall clock  {:>10.1} s ({:.0} steps/s)", estimate.wall_clock_seconds, estimate.steps_per_second);
+    estimate.check(&config, None)?;
+    Ok(estimate)
+}
+
+/// Run `protocol`, printing progress and writing the requested outputs
+pub fn simulate(protocol: Protocol, options: &SimulationOptions) -> Result<SimulationReport> {
+    let (config, run) = engine_config(protocol, options)?;
+    let steps = config.max_steps;
+
+    let mut ligands = Vec::with_capacity(options.ligands.len());
+    for spec in &options.ligands {
+        let (ligand, topology, report) = spec.load().with_context(|| format!("Failed to load ligand {}", spec.structure.display()))?;
+        println!("💊 {}", report);
+        ligands.push((ligand, topology));
+    }
+
+    let coarse_grained = match (&options.coarse_grain, &options.input) {
+        (None, _) => None,
+        (Some(_), None) => bail!("Coarse-graining needs an input structure"),
+        (Some(_), Some(_)) if !ligands.is_empty() => bail!("Ligands cannot run in a coarse-grained model"),
+        (Some(martini), Some(input)) => {
+            let model = CoarseGrainedModel::from_structure(&read_structure(input)?, martini)?;
+            println!(
+                "🫧 MARTINI: {} atoms → {} beads ({} elastic springs, {} non-protein atoms dropped)",
+                model.atomistic().atoms.len(),
+                model.beads().len(),
+                model.num_springs(),
+                model.dropped_atoms()
+            );
+            Some(model)
+        }
+    };
+
+    let (mut engine, template) = match (&options.input, &run, &coarse_grained) {
+        (_, _, Some(model)) => (MolecularDynamicsEngine::from_topology(config, model.topology())?, None),
+        (Some(input), _, None) if ligands.is_empty() => {
+            let engine = MolecularDynamicsEngine::from_structure_file(config, input)?;
+            (engine, Some(read_structure(input)?))
+        }
+        (Some(input), _, None) => {
+            let mut structure = read_structure(input)?;
+            let topologies: Vec<_> = ligands.iter().map(|(_, topology)| topology.clone()).collect();
+            let engine = MolecularDynamicsEngine::from_complex(config, &structure, &topologies)?;
+            for (k, (ligand, _)) in ligands.iter().enumerate() {
+                structure.append(&ligand.to_structure('L', k as i32 + 1));
+            }
+            (engine, Some(structure))
+        }
+        (None, Some(run), None) if run.system.topology.is_some() => {
+            let mut topology = run.load_topology()?;
+            for (_, ligand) in &ligands {
+                topology.append(ligand);
+            }
+            (MolecularDynamicsEngine::from_topology(config, &topology)?, None)
+        }
+        _ => bail!("No structure: pass an input file or a config with a [system] section"),
+    };
+
+    if let Some(config) = &options.potential {
+        let elements: Vec<u8> = engine.get_initial_atoms().iter().map(|a| a.element).collect();
+        let potential = NeuralNetworkPotential::from_config(config, &elements).with_context(|| format!("Failed to load potential {}", config.model.display()))?;
+        engine.set_potential(Box::new(potential))?;
+        println!("🧠 Potential {} replaces the force field", config.model.display());
+    }
+
+    if options.progress_interval > 0 {
+        engine.add_observer(options.progress_interval, |stats| {
+            println!("{}", progress_line(stats));
+            ControlFlow::Continue(())
+        });
+    }
+
+    println!("▶️ {} ({} atoms)", protocol.name(), engine.get_current_atoms()?.len());
+    let outcome = match protocol {
+        Protocol::Minimize => engine.minimize()?,
+        Protocol::Nlnm | Protocol::Md => engine.run_nlnm_breathing(steps)?,
+        Protocol::Pimc => engine.run_pimc(steps)?,
+    };
+    let statistics = engine.get_statistics();
+    println!("{}", progress_line(&statistics));
+
+    if let Some(output) = &options.output {
+        let atoms = engine.get_current_atoms()?;
+        let structure = match (template, &coarse_grained) {
+            (_, Some(model)) => model.backmap(&atoms.iter().map(|a| a.coords).collect::<Vec<_>>())?,
+            (Some(mut structure), None) if structure.atoms.len() == atoms.len() => {
+                structure.update_coordinates(&atoms)?;
+                structure
+            }
+            _ => PdbStructure::from_atoms(&atoms),
+        };
+        write_structure(output, &structure)?;
+        println!("💾 Wrote {}", output.display());
+    }
+    let report = SimulationReport { outcome, statistics };
+    if let Some(path) = &options.report {
+        let json = serde_json::to_string_pretty(&report.to_json())?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("💾 Wrote {}", path.display());
+    }
+
+    match &report.outcome {
+        PhaseOutcome::Success { message, .. } => println!("✅ {}", message),
+        PhaseOutcome::Cancelled { reason, .. } => println!("⏹️ Cancelled: {}", reason),
+        PhaseOutcome::Retry { reason, .. } | PhaseOutcome::Escalate { reason } => bail!("{} failed: {}", protocol.name(), reason),
+    }
+    Ok(report)
+}
+
+impl SimulationReport {
+    /// Outcome, telemetry and final statistics
+    pub fn to_json(&self) -> serde_json::Value {
+        let (outcome, message, telemetry) = match &self.outcome {
+            PhaseOutcome::Success { message, telemetry } => ("success", message.as_str(), Some(telemetry)),
+            PhaseOutcome::Cancelled { reason, telemetry } => ("cancelled", reason.as_str(), Some(telemetry)),
+            PhaseOutcome::Retry { reason, .. } => ("retry", reason.as_str(), None),
+            PhaseOutcome::Escalate { reason } => ("escalate", reason.as_str(), None),
+        };
+        serde_json::json!({
+            "outcome": outcome,
+            "message": message,
+            "telemetry": telemetry,
+            "statistics": self.statistics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::PEPTIDE;
+
+    #[test]
+    fn test_flags_override_the_preset() {
+        let options = SimulationOptions { steps: Some(25), temperature: Some(150.0), ..Default::default() };
+        let (config, run) = engine_config(Protocol::Md, &options).unwrap();
+        assert!(run.is_none());
+        assert_eq!(config.max_steps, 25);
+        assert!((kelvin(config.temp_start) - 150.0).abs() < 1e-3);
+        assert_eq!(config.temp_start, config.temp_end);
+
+        let (config, _) = engine_config(Protocol::Minimize, &options).unwrap();
+        assert_eq!(config.minimization.max_iterations, 25);
+        let (config, _) = engine_config(Protocol::Pimc, &SimulationOptions::default()).unwrap();
+        assert!(config.pimc.is_some());
+    }
+
+    #[test]
+    fn test_md_writes_structure_and_report() {
+        let dir = std::env::temp_dir().join(format!("prism_cli_simulate_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("peptide.pdb");
+        std::fs::write(&input, PEPTIDE).unwrap();
+        let options = SimulationOptions {
+            input: Some(input),
+            steps: Some(20),
+            output: Some(dir.join("final.cif")),
+            report: Some(dir.join("report.json")),
+            progress_interval: 10,
+            ..Default::default()
+        };
+        let report = simulate(Protocol::Md, &options).unwrap();
+        assert_eq!(report.statistics.current_step, 20);
+        assert_eq!(read_structure(dir.join("final.cif")).unwrap().atoms.len(), 4);
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+        assert_eq!(json["outcome"], "success");
+        assert_eq!(json["statistics"]["current_step"], 20);
+        assert!(simulate(Protocol::Md, &SimulationOptions::default()).is_err());
+
+        let estimate = dry_run(Protocol::Md, &options).unwrap();
+        assert_eq!((estimate.num_atoms, estimate.steps), (4, 20));
+        assert!(dry_run(Protocol::Md, &SimulationOptions::default()).is_err());
+        let potential = SimulationOptions { potential: Some(MlPotentialConfig::new(dir.join("ani2x.onnx"))), ..options };
+        assert!(simulate(Protocol::Md, &potential).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_md_runs_coarse_grained_and_backmaps() {
+        let dir = std::env::temp_dir().join(format!("prism_cli_martini_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("peptide.pdb");
+        std::fs::write(&input, PEPTIDE).unwrap();
+        let options = SimulationOptions {
+            input: Some(input),
+            steps: Some(10),
+            output: Some(dir.join("final.pdb")),
+            coarse_grain: Some(MartiniConfig::default()),
+            progress_interval: 0,
+            ..Default::default()
+        };
+        let (config, _) = engine_config(Protocol::Md, &options).unwrap();
+        assert_eq!(config.dt, MARTINI_TIME_STEP);
+        assert_eq!(config.force_field.dielectric, 15.0);
+        let report = simulate(Protocol::Md, &options).unwrap();
+        assert_eq!(report.statistics.current_step, 10);
+        let backmapped = read_structure(dir.join("final.pdb")).unwrap();
+        assert_eq!(backmapped.atoms.len(), 4);
+        assert_eq!(backmapped.records[1].name, "CA");
+
+        assert!(simulate(Protocol::Md, &SimulationOptions { input: None, ..options }).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_md_runs_protein_ligand_complex() {
+        const METHANOL: &str = "\
+@<TRIPOS>MOLECULE
+MOH
+6 5 1 0 0
+SMALL
+USER_CHARGES
+@<TRIPOS>ATOM
+1 C1  8.000 0.000 0.000 C.3 1 MOH  0.117
+2 O1  9.420 0.000 0.000 O.3 1 MOH -0.599
+3 H1  7.640 1.020 0.000 H   1 MOH  0.029
+4 H2  7.640 -0.510 0.890 H  1 MOH  0.029
+5 H3  7.640 -0.510 -0.890 H 1 MOH  0.029
+6 HO  9.740 0.910 0.000 H   1 MOH  0.395
+@<TRIPOS>BOND
+1 1 2 1
+2 1 3 1
+3 1 4 1
+4 1 5 1
+5 2 6 1
+";
+        let dir = std::env::temp_dir().join(format!("prism_cli_complex_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, ligand) = (dir.join("peptide.pdb"), dir.join("methanol.mol2"));
+        std::fs::write(&input, PEPTIDE).unwrap();
+        std::fs::write(&ligand, METHANOL).unwrap();
+        let options = SimulationOptions {
+            input: Some(input),
+            steps: Some(10),
+            output: Some(dir.join("final.pdb")),
+            ligands: vec![LigandSpec::new(&ligand)],
+            progress_interval: 0,
+            ..Default::default()
+        };
+        let report = simulate(Protocol::Md, &options).unwrap();
+        assert_eq!(report.statistics.current_step, 10);
+        let complex = read_structure(dir.join("final.pdb")).unwrap();
+        assert_eq!(complex.atoms.len(), 10);
+        assert_eq!(complex.records[4].residue_name, "MOH");
+        assert!(complex.records[4].hetatm);
+
+        std::fs::write(&ligand, METHANOL.replace("USER_CHARGES", "NO_CHARGES")).unwrap();
+        assert!(simulate(Protocol::Md, &options).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,106 @@
+//! Cooperative cancellation and pause/resume for long-running phases.
+//!
+//! A [`CancellationToken`] is cloned into the component doing the work and
+//! kept by whoever drives it (UI, orchestrator, signal handler). The worker
+//! calls [`CancellationToken::checkpoint`] at safe boundaries of its loop:
+//! the call parks the thread while the token is paused and reports whether
+//! the work should stop.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+#[derive(Debug, Default)]
+struct State {
+    cancelled: AtomicBool,
+    paused: Mutex<bool>,
+    wake: Condvar,
+}
+
+/// Shared cancel/pause flag; clones refer to the same state.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<State>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is neither cancelled nor paused.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation; also releases a paused worker.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        let _paused = self.lock();
+        self.state.wake.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Parks the worker at its next checkpoint until [`Self::resume`].
+    pub fn pause(&self) {
+        *self.lock() = true;
+    }
+
+    pub fn resume(&self) {
+        *self.lock() = false;
+        self.state.wake.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.lock()
+    }
+
+    /// Blocks while paused, then returns `true` if the work should stop.
+    pub fn checkpoint(&self) -> bool {
+        let mut paused = self.lock();
+        while *paused && !self.is_cancelled() {
+            paused = self
+                .state
+                .wake
+                .wait(paused)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        self.is_cancelled()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, bool> {
+        self.state
+            .paused
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_pause_parks_until_resume_or_cancel() {
+        let token = CancellationToken::new();
+        assert!(!token.checkpoint());
+
+        token.pause();
+        let worker = {
+            let token = token.clone();
+            std::thread::spawn(move || token.checkpoint())
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!worker.is_finished());
+        token.resume();
+        assert!(!worker.join().unwrap());
+
+        token.pause();
+        let worker = {
+            let token = token.clone();
+            std::thread::spawn(move || token.checkpoint())
+        };
+        token.cancel();
+        assert!(worker.join().unwrap());
+        assert!(token.is_cancelled());
+    }
+}
@@ -41,6 +41,22 @@ pub enum PrismError {
     #[error("Numerical error: {0}")]
     NumericalError(String),
 
+    /// Integration diverged: the energy stopped being finite
+    #[error("Numerical instability at step {step}: energy {energy} kcal/mol")]
+    NumericalInstability { step: u64, energy: f64 },
+
+    /// SHAKE, RATTLE or SETTLE could not satisfy the constraints
+    #[error("Constraint failure: {0}")]
+    ConstraintFailure(String),
+
+    /// Atoms packed beyond any physical density overflowed the neighbor list
+    #[error("Neighbor list overflow: {neighbors} neighbors, capacity {capacity}")]
+    NeighborListOverflow { neighbors: usize, capacity: usize },
+
+    /// A coordinate or velocity became NaN or infinite
+    #[error("Non-finite value on atom {atom_index} at step {step}")]
+    NaNDetected { atom_index: usize, step: u64 },
+
     /// Resource exhaustion (e.g., out of GPU memory)
     #[error("Resource exhausted: {0}")]
     ResourceExhausted(String),
@@ -86,6 +102,11 @@ impl PrismError {
         PrismError::NumericalError(message.into())
     }
 
+    /// Creates a constraint failure.
+    pub fn constraint_failure(message: impl Into<String>) -> Self {
+        PrismError::ConstraintFailure(message.into())
+    }
+
     /// Creates a resource exhausted error.
     pub fn resource_exhausted(message: impl Into<String>) -> Self {
         PrismError::ResourceExhausted(message.into())
@@ -116,6 +137,18 @@ impl PrismError {
         )
     }
 
+    /// Checks if this is a failure of the dynamics that a shorter timestep
+    /// (or a prior minimization) may avoid.
+    pub fn is_instability(&self) -> bool {
+        matches!(
+            self,
+            PrismError::NumericalInstability { .. }
+                | PrismError::ConstraintFailure(_)
+                | PrismError::NeighborListOverflow { .. }
+                | PrismError::NaNDetected { .. }
+        )
+    }
+
     /// Returns a user-friendly error message with actionable guidance.
     pub fn user_message(&self) -> String {
         match self {
@@ -150,6 +183,14 @@ impl PrismError {
                     msg
                 )
             }
+            _ if self.is_instability() => {
+                format!(
+                    "{}\n\
+                     → Reduce the timestep (dt) and retry.\n\
+                     → Minimize the structure first to remove clashes.",
+                    self
+                )
+            }
             _ => self.to_string(),
         }
     }
@@ -185,4 +226,24 @@ mod tests {
         let validation = PrismError::validation("Invalid graph");
         assert!(!validation.is_retriable());
     }
+
+    #[test]
+    fn test_instability_errors() {
+        let nan = PrismError::NaNDetected {
+            atom_index: 7,
+            step: 120,
+        };
+        assert!(nan.is_instability());
+        assert!(!nan.is_retriable());
+        assert_eq!(nan.to_string(), "Non-finite value on atom 7 at step 120");
+        assert!(nan.user_message().contains("timestep"));
+
+        assert!(PrismError::constraint_failure("SHAKE did not converge").is_instability());
+        assert!(PrismError::NumericalInstability {
+            step: 5,
+            energy: f64::INFINITY
+        }
+        .is_instability());
+        assert!(!PrismError::numerical("Singular matrix").is_instability());
+    }
 }
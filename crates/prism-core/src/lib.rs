@@ -31,6 +31,7 @@
 //!
 //! Implements the PRISM GPU Plan (§2: Core Types & Traits).
 
+pub mod cancellation;
 pub mod dimacs;
 pub mod domain;
 pub mod errors;
@@ -42,6 +43,7 @@ pub mod types;
 pub mod telemetry;
 
 // Re-export commonly used items
+pub use cancellation::CancellationToken;
 pub use errors::PrismError;
 pub use runtime_config::{KernelTelemetry, RuntimeConfig};
 pub use traits::{
@@ -10,8 +10,10 @@
 //! - **SIMD Aligned**: 32-byte telemetry frames for optimal cache performance
 
 use crossbeam_queue::ArrayQueue;
-use std::sync::OnceLock;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 /// Telemetry frame capturing critical physics engine metrics
 ///
@@ -19,7 +21,7 @@ use std::time::Instant;
 /// Fixed 32-byte structure for optimal SIMD alignment and cache line efficiency.
 /// Critical for maintaining <5ns insertion time in hot physics loop.
 #[repr(C, align(32))]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct TelemetryFrame {
     /// Simulation step counter
     pub step: u64,                     // 8 bytes
@@ -45,6 +47,18 @@ const RING_CAPACITY: usize = 100_000;
 /// Uses crossbeam's lock-free ArrayQueue for lock-free producer/consumer access.
 pub static TELEMETRY_RING: OnceLock<ArrayQueue<TelemetryFrame>> = OnceLock::new();
 
+/// Frames offered to the ring (recorded or dropped)
+static TOTAL_FRAMES: AtomicU64 = AtomicU64::new(0);
+
+/// Frames dropped because the ring was full
+static DROPPED_FRAMES: AtomicU64 = AtomicU64::new(0);
+
+/// Time of [`init_telemetry`], the epoch of [`timestamp_ns`]
+static START_TIME: OnceLock<Instant> = OnceLock::new();
+
+/// Default interval of the background drain (cold path)
+pub const DRAIN_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Flight recorder statistics for monitoring system health
 #[derive(Debug, Clone)]
 pub struct FlightRecorderStats {
@@ -64,6 +78,7 @@ pub struct FlightRecorderStats {
 /// Subsequent calls are ignored (safe to call multiple times).
 pub fn init_telemetry() {
     TELEMETRY_RING.get_or_init(|| {
+        START_TIME.get_or_init(Instant::now);
         log::info!("🛩️  PZFR: Initialized telemetry ring with {} frame capacity", RING_CAPACITY);
         ArrayQueue::new(RING_CAPACITY)
     });
@@ -86,11 +101,20 @@ pub fn init_telemetry() {
 #[inline(always)]
 pub fn record_frame(frame: TelemetryFrame) {
     if let Some(queue) = TELEMETRY_RING.get() {
+        TOTAL_FRAMES.fetch_add(1, Ordering::Relaxed);
         // Lock-free push - if full, drop the new frame (never block physics)
-        let _ = queue.push(frame);
+        if queue.push(frame).is_err() {
+            DROPPED_FRAMES.fetch_add(1, Ordering::Relaxed);
+        }
     }
 }
 
+/// Nanoseconds since [`init_telemetry`] (0 before it)
+#[inline(always)]
+pub fn timestamp_ns() -> u64 {
+    START_TIME.get().map_or(0, |start| start.elapsed().as_nanos() as u64)
+}
+
 /// Convenience function to record current simulation state
 ///
 /// # Arguments
@@ -127,26 +151,96 @@ pub fn record_simulation_state(
 /// # Returns
 /// Vector of available frames (may be empty if no new data)
 pub fn drain_frames() -> Vec<TelemetryFrame> {
-    if let Some(queue) = TELEMETRY_RING.get() {
-        let mut frames = Vec::with_capacity(queue.len());
-        while let Some(frame) = queue.pop() {
-            frames.push(frame);
+    let mut frames = Vec::new();
+    drain_into(&mut frames);
+    frames
+}
+
+/// Append the available frames to `frames`, reusing its capacity
+///
+/// # Returns
+/// Number of frames drained
+pub fn drain_into(frames: &mut Vec<TelemetryFrame>) -> usize {
+    let Some(queue) = TELEMETRY_RING.get() else { return 0 };
+    let before = frames.len();
+    frames.reserve(queue.len());
+    while let Some(frame) = queue.pop() {
+        frames.push(frame);
+    }
+    frames.len() - before
+}
+
+/// Background thread draining the ring into a sink
+///
+/// The hot path only pushes into the pre-allocated ring; this thread wakes
+/// every interval, moves the available frames into a reused buffer and
+/// hands them to the sink, so formatting, I/O and allocation all happen
+/// off the physics thread. Dropping the handle (or [`TelemetryDrain::stop`])
+/// drains once more and joins the thread.
+#[derive(Debug)]
+pub struct TelemetryDrain {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TelemetryDrain {
+    /// Stop the thread after a final drain
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            if handle.join().is_err() {
+                log::warn!("🛩️  PZFR: Telemetry sink panicked");
+            }
         }
-        frames
-    } else {
-        Vec::new()
     }
 }
 
+impl Drop for TelemetryDrain {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Spawn the cold-path thread, handing each non-empty batch of frames to
+/// `sink` every `interval` (initializes the ring if needed)
+pub fn spawn_drain<F>(interval: Duration, mut sink: F) -> std::io::Result<TelemetryDrain>
+where
+    F: FnMut(&[TelemetryFrame]) + Send + 'static,
+{
+    init_telemetry();
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = stop.clone();
+    let handle = std::thread::Builder::new().name("pzfr-drain".to_string()).spawn(move || {
+        let mut frames = Vec::with_capacity(RING_CAPACITY);
+        loop {
+            let stopping = flag.load(Ordering::Acquire);
+            frames.clear();
+            if drain_into(&mut frames) > 0 {
+                sink(&frames);
+            }
+            if stopping {
+                break;
+            }
+            std::thread::park_timeout(interval);
+        }
+    })?;
+    Ok(TelemetryDrain { stop, handle: Some(handle) })
+}
+
 /// Get flight recorder statistics
 ///
 /// Provides insight into recording system performance and buffer health.
 pub fn get_stats() -> Option<FlightRecorderStats> {
     TELEMETRY_RING.get().map(|queue| FlightRecorderStats {
-        total_frames: 0, // TODO: Implement counters
-        dropped_frames: 0, // TODO: Implement counters
+        total_frames: TOTAL_FRAMES.load(Ordering::Relaxed),
+        dropped_frames: DROPPED_FRAMES.load(Ordering::Relaxed),
         buffer_utilization: queue.len() as f32 / RING_CAPACITY as f32,
-        start_time: Instant::now(), // TODO: Store actual start time
+        start_time: START_TIME.get().copied().unwrap_or_else(Instant::now),
     })
 }
 
@@ -224,6 +318,20 @@ mod tests {
         let drained = drain_frames();
         assert_eq!(drained.len(), 1);
         assert_eq!(drained[0].step, 1);
+        assert!(get_stats().unwrap().total_frames >= 1);
+
+        // The background drain hands every frame to the sink by the time it stops
+        let received = Arc::new(AtomicU64::new(0));
+        let counter = received.clone();
+        let drain = spawn_drain(Duration::from_millis(1), move |frames| {
+            counter.fetch_add(frames.iter().map(|f| f.step).sum::<u64>(), Ordering::Relaxed);
+        })
+        .unwrap();
+        for step in 1..=100 {
+            record_frame(TelemetryFrame { step, timestamp_ns: timestamp_ns(), ..frame });
+        }
+        drain.stop();
+        assert_eq!(received.load(Ordering::Relaxed), 5050);
     }
 
     #[test]
@@ -33,6 +33,14 @@ pub enum PhaseOutcome {
  /// Reason for escalation
  reason: String,
  },
+
+ /// Phase was interrupted by a cancellation request before completing
+ Cancelled {
+ /// Where the phase stopped
+ reason: String,
+ /// Telemetry gathered up to the interruption
+ telemetry: HashMap<String, serde_json::Value>,
+ },
 }
 
 impl PhaseOutcome {
@@ -60,10 +68,23 @@ impl PhaseOutcome {
  }
  }
 
+ /// Creates a Cancelled outcome without telemetry.
+ pub fn cancelled(reason: impl Into<String>) -> Self {
+ PhaseOutcome::Cancelled {
+ reason: reason.into(),
+ telemetry: HashMap::new(),
+ }
+ }
+
  /// Checks if the outcome is successful.
  pub fn is_success(&self) -> bool {
  matches!(self, PhaseOutcome::Success { .. })
  }
+
+ /// Checks if the phase was cancelled.
+ pub fn is_cancelled(&self) -> bool {
+ matches!(self, PhaseOutcome::Cancelled { .. })
+ }
 }
 
 /// Execution context shared across all phases.
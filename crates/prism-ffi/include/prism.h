@@ -0,0 +1,129 @@
+/* PRISM-4D C API. Generated by cbindgen from crates/prism-ffi/src/lib.rs; do not edit. */
+
+#ifndef PRISM_FFI_H
+#define PRISM_FFI_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of a fallible call
+typedef enum PrismStatus {
+  // The call succeeded
+  PRISM_STATUS_OK = 0,
+  // A required pointer argument was null
+  PRISM_STATUS_NULL_POINTER = 1,
+  // An argument was malformed (config text not UTF-8, buffer too small)
+  PRISM_STATUS_INVALID_ARGUMENT = 2,
+  // The configuration or structure was rejected
+  PRISM_STATUS_CONFIG = 3,
+  // The engine failed while running
+  PRISM_STATUS_ENGINE = 4,
+  // The run stopped before completing
+  PRISM_STATUS_CANCELLED = 5,
+  // A panic was caught at the boundary; the handle should be freed
+  PRISM_STATUS_PANIC = 6,
+  // The dynamics became unstable (non-finite energy or coordinates,
+  // failed constraints); a shorter timestep may succeed
+  PRISM_STATUS_UNSTABLE = 7,
+} PrismStatus;
+
+// Opaque engine handle
+typedef struct PrismEngine PrismEngine;
+
+// Run statistics
+typedef struct PrismStats {
+  // Steps (or sweeps) completed
+  uint64_t step;
+  // Steps requested by the last run
+  uint64_t total_steps;
+  // Potential energy (kcal/mol)
+  double potential_energy;
+  // Thermostat target temperature (K)
+  double temperature_kelvin;
+  // Norm of the energy gradient (kcal/mol/Å)
+  double gradient_norm;
+  // Wall-clock time of the runs so far (s)
+  double runtime_seconds;
+  // 1 when the last minimization converged, 0 otherwise
+  int32_t converged;
+} PrismStats;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Library version as a NUL-terminated string with static lifetime
+const char *prism_version(void);
+
+// Message of the last failed call on this thread, or null after a
+// successful one. Valid until the next call on this thread.
+const char *prism_last_error(void);
+
+// Create an engine for `structure` (PTB bytes or PDB text) configured by
+// the TOML run configuration `config_toml` (engine defaults when null)
+//
+// # Safety
+// `config_toml` must be null or a NUL-terminated string, `structure` must
+// point to `structure_len` readable bytes and `out_engine` must be
+// writable. On success `*out_engine` owns a handle to release with
+// [`prism_engine_free`].
+PrismStatus prism_engine_new(const char *config_toml,
+                             const uint8_t *structure,
+                             size_t structure_len,
+                             PrismEngine **out_engine);
+
+// Release a handle; null is ignored
+//
+// # Safety
+// `engine` must be null or a handle from [`prism_engine_new`] that has
+// not been freed.
+void prism_engine_free(PrismEngine *engine);
+
+// Minimize the energy with the configured minimizer
+//
+// # Safety
+// `engine` must be a live handle.
+PrismStatus prism_engine_minimize(PrismEngine *engine);
+
+// Advance the NLNM Langevin dynamics by `steps` steps, following the
+// configured temperature schedule
+//
+// # Safety
+// `engine` must be a live handle.
+PrismStatus prism_engine_step(PrismEngine *engine, uint64_t steps);
+
+// Run `sweeps` path-integral Monte Carlo sweeps (the configuration's
+// `[engine.pimc]` ring polymer)
+//
+// # Safety
+// `engine` must be a live handle.
+PrismStatus prism_engine_step_pimc(PrismEngine *engine, uint64_t sweeps);
+
+// Number of atoms of the engine
+//
+// # Safety
+// `engine` must be a live handle and `out_num_atoms` writable.
+PrismStatus prism_engine_num_atoms(PrismEngine *engine, size_t *out_num_atoms);
+
+// Copy the current positions (Å) as `x0 y0 z0 x1 ...` into `out_xyz`,
+// which holds `capacity` floats (at least 3 per atom)
+//
+// # Safety
+// `engine` must be a live handle and `out_xyz` must point to `capacity`
+// writable floats.
+PrismStatus prism_engine_get_coordinates(PrismEngine *engine, float *out_xyz, size_t capacity);
+
+// Current run statistics
+//
+// # Safety
+// `engine` must be a live handle and `out_stats` writable.
+PrismStatus prism_engine_get_stats(PrismEngine *engine, PrismStats *out_stats);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PRISM_FFI_H */
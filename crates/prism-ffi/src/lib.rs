@@ -0,0 +1,355 @@
+//! # PRISM FFI - Stable C ABI for the MD Engine
+//!
+//! Lets C, C++ and Fortran (via `ISO_C_BINDING`) pipelines drive the NLNM
+//! and PIMC solvers through an opaque [`PrismEngine`] handle. The C
+//! declarations live in `include/prism.h`, generated from this file with
+//! cbindgen (see `cbindgen.toml`).
+//!
+//! Every fallible call returns a [`PrismStatus`]; on failure
+//! [`prism_last_error`] describes the problem. Panics never cross the
+//! boundary. A handle may be moved between threads but must not be used
+//! from two threads at once; distinct handles are independent.
+//!
+//! ```c
+//! PrismEngine *engine = NULL;
+//! if (prism_engine_new(config_toml, pdb, pdb_len, &engine) != PRISM_STATUS_OK) {
+//!     fprintf(stderr, "%s\n", prism_last_error());
+//! }
+//! prism_engine_step(engine, 10000);
+//! prism_engine_get_coordinates(engine, xyz, 3 * num_atoms);
+//! prism_engine_free(engine);
+//! ```
+
+use prism_core::{PhaseOutcome, PrismError};
+use prism_physics::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsEngine};
+use prism_physics::run_config::{ConfigFormat, RunConfig};
+use prism_physics::units::{Energy, Temperature};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Result of a fallible call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrismStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// An argument was malformed (config text not UTF-8, buffer too small)
+    InvalidArgument = 2,
+    /// The configuration or structure was rejected
+    Config = 3,
+    /// The engine failed while running
+    Engine = 4,
+    /// The run stopped before completing
+    Cancelled = 5,
+    /// A panic was caught at the boundary; the handle should be freed
+    Panic = 6,
+    /// The dynamics became unstable (non-finite energy or coordinates,
+    /// failed constraints); a shorter timestep may succeed
+    Unstable = 7,
+}
+
+/// Run statistics
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PrismStats {
+    /// Steps (or sweeps) completed
+    pub step: u64,
+    /// Steps requested by the last run
+    pub total_steps: u64,
+    /// Potential energy (kcal/mol)
+    pub potential_energy: f64,
+    /// Thermostat target temperature (K)
+    pub temperature_kelvin: f64,
+    /// Norm of the energy gradient (kcal/mol/Å)
+    pub gradient_norm: f64,
+    /// Wall-clock time of the runs so far (s)
+    pub runtime_seconds: f64,
+    /// 1 when the last minimization converged, 0 otherwise
+    pub converged: i32,
+}
+
+/// Opaque engine handle
+pub struct PrismEngine {
+    engine: MolecularDynamicsEngine,
+}
+
+type Failure = (PrismStatus, String);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|m| CString::new(m.replace('\0', " ")).unwrap_or_default());
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = message);
+}
+
+fn engine_failure(error: PrismError) -> Failure {
+    let status = match error {
+        PrismError::ConfigError(_) | PrismError::ValidationError(_) | PrismError::SerializationError(_) | PrismError::IoError(_) => PrismStatus::Config,
+        _ if error.is_instability() => PrismStatus::Unstable,
+        _ => PrismStatus::Engine,
+    };
+    (status, error.to_string())
+}
+
+fn outcome_status(outcome: Result<PhaseOutcome, PrismError>) -> Result<(), Failure> {
+    match outcome.map_err(engine_failure)? {
+        PhaseOutcome::Success { .. } => Ok(()),
+        PhaseOutcome::Cancelled { reason, .. } => Err((PrismStatus::Cancelled, reason)),
+        PhaseOutcome::Retry { reason, .. } | PhaseOutcome::Escalate { reason } => Err((PrismStatus::Engine, reason)),
+    }
+}
+
+/// Run `f`, recording its error (or panic) as the thread's last error
+fn guard(f: impl FnOnce() -> Result<(), Failure>) -> PrismStatus {
+    let (status, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => (PrismStatus::Ok, None),
+        Ok(Err((status, message))) => (status, Some(message)),
+        Err(panic) => {
+            let message = panic.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| panic.downcast_ref::<String>().cloned()).unwrap_or_else(|| "unknown panic".to_string());
+            (PrismStatus::Panic, Some(format!("Panic in the PRISM engine: {}", message)))
+        }
+    };
+    set_last_error(message);
+    status
+}
+
+/// Dereference a handle argument
+///
+/// # Safety
+/// `engine` must be null or a live handle from [`prism_engine_new`].
+unsafe fn handle<'a>(engine: *mut PrismEngine) -> Result<&'a mut PrismEngine, Failure> {
+    engine.as_mut().ok_or((PrismStatus::NullPointer, "engine is null".to_string()))
+}
+
+/// Library version as a NUL-terminated string with static lifetime
+#[no_mangle]
+pub extern "C" fn prism_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Message of the last failed call on this thread, or null after a
+/// successful one. Valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn prism_last_error() -> *const c_char {
+    LAST_ERROR.with(|slot| slot.borrow().as_ref().map_or(std::ptr::null(), |m| m.as_ptr()))
+}
+
+/// Create an engine for `structure` (PTB bytes or PDB text) configured by
+/// the TOML run configuration `config_toml` (engine defaults when null)
+///
+/// # Safety
+/// `config_toml` must be null or a NUL-terminated string, `structure` must
+/// point to `structure_len` readable bytes and `out_engine` must be
+/// writable. On success `*out_engine` owns a handle to release with
+/// [`prism_engine_free`].
+#[no_mangle]
+pub unsafe extern "C" fn prism_engine_new(config_toml: *const c_char, structure: *const u8, structure_len: usize, out_engine: *mut *mut PrismEngine) -> PrismStatus {
+    guard(|| {
+        if structure.is_null() || out_engine.is_null() {
+            return Err((PrismStatus::NullPointer, "structure and out_engine must not be null".to_string()));
+        }
+        let config = if config_toml.is_null() {
+            MolecularDynamicsConfig { use_gpu: cfg!(feature = "cuda"), ..Default::default() }
+        } else {
+            let text = CStr::from_ptr(config_toml).to_str().map_err(|e| (PrismStatus::InvalidArgument, format!("config_toml is not UTF-8: {}", e)))?;
+            let run = RunConfig::parse(text, ConfigFormat::Toml, None, std::iter::empty()).map_err(engine_failure)?;
+            if run.system.topology.is_some() || run.system.coordinates.is_some() {
+                return Err((PrismStatus::Config, "The structure is passed in memory; remove [system] from the configuration".to_string()));
+            }
+            run.engine
+        };
+        let bytes = std::slice::from_raw_parts(structure, structure_len);
+        let engine = MolecularDynamicsEngine::from_sovereign_buffer(config, bytes).map_err(engine_failure)?;
+        *out_engine = Box::into_raw(Box::new(PrismEngine { engine }));
+        Ok(())
+    })
+}
+
+/// Release a handle; null is ignored
+///
+/// # Safety
+/// `engine` must be null or a handle from [`prism_engine_new`] that has
+/// not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn prism_engine_free(engine: *mut PrismEngine) {
+    if !engine.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(engine))));
+    }
+}
+
+/// Minimize the energy with the configured minimizer
+///
+/// # Safety
+/// `engine` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn prism_engine_minimize(engine: *mut PrismEngine) -> PrismStatus {
+    guard(|| outcome_status(handle(engine)?.engine.minimize()))
+}
+
+/// Advance the NLNM Langevin dynamics by `steps` steps, following the
+/// configured temperature schedule
+///
+/// # Safety
+/// `engine` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn prism_engine_step(engine: *mut PrismEngine, steps: u64) -> PrismStatus {
+    guard(|| outcome_status(handle(engine)?.engine.run_nlnm_breathing(steps)))
+}
+
+/// Run `sweeps` path-integral Monte Carlo sweeps (the configuration's
+/// `[engine.pimc]` ring polymer)
+///
+/// # Safety
+/// `engine` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn prism_engine_step_pimc(engine: *mut PrismEngine, sweeps: u64) -> PrismStatus {
+    guard(|| outcome_status(handle(engine)?.engine.run_pimc(sweeps)))
+}
+
+/// Number of atoms of the engine
+///
+/// # Safety
+/// `engine` must be a live handle and `out_num_atoms` writable.
+#[no_mangle]
+pub unsafe extern "C" fn prism_engine_num_atoms(engine: *mut PrismEngine, out_num_atoms: *mut usize) -> PrismStatus {
+    guard(|| {
+        let engine = handle(engine)?;
+        let out = out_num_atoms.as_mut().ok_or((PrismStatus::NullPointer, "out_num_atoms is null".to_string()))?;
+        *out = engine.engine.get_current_atoms().map_err(engine_failure)?.len();
+        Ok(())
+    })
+}
+
+/// Copy the current positions (Å) as `x0 y0 z0 x1 ...` into `out_xyz`,
+/// which holds `capacity` floats (at least 3 per atom)
+///
+/// # Safety
+/// `engine` must be a live handle and `out_xyz` must point to `capacity`
+/// writable floats.
+#[no_mangle]
+pub unsafe extern "C" fn prism_engine_get_coordinates(engine: *mut PrismEngine, out_xyz: *mut f32, capacity: usize) -> PrismStatus {
+    guard(|| {
+        let engine = handle(engine)?;
+        if out_xyz.is_null() {
+            return Err((PrismStatus::NullPointer, "out_xyz is null".to_string()));
+        }
+        let atoms = engine.engine.get_current_atoms().map_err(engine_failure)?;
+        if capacity < atoms.len() * 3 {
+            return Err((PrismStatus::InvalidArgument, format!("out_xyz holds {} floats, {} atoms need {}", capacity, atoms.len(), atoms.len() * 3)));
+        }
+        let out = std::slice::from_raw_parts_mut(out_xyz, atoms.len() * 3);
+        for (chunk, atom) in out.chunks_exact_mut(3).zip(&atoms) {
+            chunk.copy_from_slice(&atom.coords);
+        }
+        Ok(())
+    })
+}
+
+/// Current run statistics
+///
+/// # Safety
+/// `engine` must be a live handle and `out_stats` writable.
+#[no_mangle]
+pub unsafe extern "C" fn prism_engine_get_stats(engine: *mut PrismEngine, out_stats: *mut PrismStats) -> PrismStatus {
+    guard(|| {
+        let engine = handle(engine)?;
+        let out = out_stats.as_mut().ok_or((PrismStatus::NullPointer, "out_stats is null".to_string()))?;
+        let stats = engine.engine.get_statistics();
+        *out = PrismStats {
+            step: stats.current_step,
+            total_steps: stats.total_steps,
+            potential_energy: stats.current_energy as f64,
+            temperature_kelvin: Temperature::from_kt(Energy::kcal_per_mol(stats.current_temperature as f64)).as_kelvin(),
+            gradient_norm: stats.gradient_norm as f64,
+            runtime_seconds: stats.runtime_seconds as f64,
+            converged: stats.converged as i32,
+        };
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+";
+
+    fn last_error() -> String {
+        let message = prism_last_error();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_engine_lifecycle() {
+        let config = CString::new("[engine]\nuse_gpu = false\nspring_k = 0.0\n").unwrap();
+        let mut engine = std::ptr::null_mut();
+        unsafe {
+            assert_eq!(prism_engine_new(config.as_ptr(), PEPTIDE.as_ptr(), PEPTIDE.len(), &mut engine), PrismStatus::Ok);
+            assert!(prism_last_error().is_null());
+
+            let mut num_atoms = 0;
+            assert_eq!(prism_engine_num_atoms(engine, &mut num_atoms), PrismStatus::Ok);
+            assert_eq!(num_atoms, 4);
+            assert_eq!(prism_engine_minimize(engine), PrismStatus::Ok);
+            assert_eq!(prism_engine_step(engine, 20), PrismStatus::Ok);
+            let mut stats = PrismStats::default();
+            assert_eq!(prism_engine_get_stats(engine, &mut stats), PrismStatus::Ok);
+            assert_eq!(stats.step, 20);
+            assert!(stats.potential_energy.is_finite());
+
+            let mut xyz = [f32::NAN; 12];
+            assert_eq!(prism_engine_get_coordinates(engine, xyz.as_mut_ptr(), 11), PrismStatus::InvalidArgument);
+            assert!(last_error().contains("12"));
+            assert_eq!(prism_engine_get_coordinates(engine, xyz.as_mut_ptr(), xyz.len()), PrismStatus::Ok);
+            assert!(xyz.iter().all(|c| c.is_finite()));
+            assert!((xyz[3] - 1.458).abs() < 0.5);
+            prism_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        let mut engine = std::ptr::null_mut();
+        unsafe {
+            assert_eq!(prism_engine_step(std::ptr::null_mut(), 1), PrismStatus::NullPointer);
+            assert_eq!(prism_engine_new(std::ptr::null(), std::ptr::null(), 0, &mut engine), PrismStatus::NullPointer);
+            let config = CString::new("[engine]\ndt = -1.0\n").unwrap();
+            assert_eq!(prism_engine_new(config.as_ptr(), PEPTIDE.as_ptr(), PEPTIDE.len(), &mut engine), PrismStatus::Config);
+            assert!(last_error().contains("dt"));
+            assert!(engine.is_null());
+            prism_engine_free(engine);
+        }
+        assert_eq!(engine_failure(PrismError::NaNDetected { atom_index: 3, step: 40 }).0, PrismStatus::Unstable);
+        assert_eq!(engine_failure(PrismError::numerical("singular")).0, PrismStatus::Engine);
+        let version = unsafe { CStr::from_ptr(prism_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_header_declares_every_export() {
+        let header = include_str!("../include/prism.h");
+        let exports: Vec<&str> = include_str!("lib.rs")
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub unsafe extern \"C\" fn ").or_else(|| line.strip_prefix("pub extern \"C\" fn ")))
+            .filter_map(|rest| rest.split('(').next())
+            .collect();
+        assert_eq!(exports.len(), 10);
+        for name in exports {
+            assert!(header.contains(&format!("{}(", name)), "include/prism.h is missing {}; regenerate it with cbindgen", name);
+        }
+        for item in ["PRISM_STATUS_PANIC = 6", "typedef struct PrismEngine PrismEngine;", "int32_t converged;"] {
+            assert!(header.contains(item), "include/prism.h is out of date: {}", item);
+        }
+    }
+}
@@ -230,6 +230,38 @@ fn main() {
         &target_ptx_dir.join("feature_merge.ptx"),
     );
 
+    // Compile nonbonded force kernel (molecular dynamics engine)
+    compile_kernel(
+        &nvcc,
+        "src/kernels/nonbonded_forces.cu",
+        &ptx_dir.join("nonbonded_forces.ptx"),
+        &target_ptx_dir.join("nonbonded_forces.ptx"),
+    );
+
+    // Compile cell-list neighbor list kernels
+    compile_kernel(
+        &nvcc,
+        "src/kernels/neighbor_list.cu",
+        &ptx_dir.join("neighbor_list.ptx"),
+        &target_ptx_dir.join("neighbor_list.ptx"),
+    );
+
+    // Compile particle mesh Ewald kernels (cuFFT does the transforms)
+    compile_kernel(
+        &nvcc,
+        "src/kernels/pme.cu",
+        &ptx_dir.join("pme.ptx"),
+        &target_ptx_dir.join("pme.ptx"),
+    );
+
+    // Compile Shrake-Rupley SASA kernel (trajectory analysis)
+    compile_kernel(
+        &nvcc,
+        "src/kernels/sasa.cu",
+        &ptx_dir.join("sasa.ptx"),
+        &target_ptx_dir.join("sasa.ptx"),
+    );
+
     // NOTE: Viral Evolution Fitness kernel disabled - fitness+cycle integrated into mega_fused Stages 7-8
     // compile_kernel(
     //     &nvcc,
@@ -0,0 +1,194 @@
+//! # Autotune - Per-GPU Launch Profiles
+//!
+//! The best block size of the force kernels (and with it the tile width of
+//! the all-pairs nonbonded kernel) differs widely between consumer and
+//! datacenter cards. On the first run on a device the candidates are
+//! benchmarked and the winner is stored in a JSON profile for that GPU
+//! model; later runs read the profile and skip the benchmark.
+//!
+//! Profiles live in `$PRISM_GPU_PROFILE_DIR`, else
+//! `$XDG_CACHE_HOME/prism/gpu-profiles`, else
+//! `$HOME/.cache/prism/gpu-profiles`, one file per device name and compute
+//! capability. Deleting a file re-tunes that device.
+
+use anyhow::{Context, Result};
+use cudarc::driver::CudaContext;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Block sizes tried for each kernel
+pub const BLOCK_SIZE_CANDIDATES: [u32; 4] = [64, 128, 256, 512];
+
+/// Timed launches per candidate (after one warm-up launch)
+pub const AUTOTUNE_REPEATS: usize = 5;
+
+/// Profile format version; files of other versions are re-tuned
+pub const PROFILE_VERSION: u32 = 1;
+
+/// Tuned launch parameters of one kernel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KernelTuning {
+    pub block_size: u32,
+    /// Fastest time per launch at `block_size` (µs)
+    pub micros: f64,
+    /// Atoms of the system the benchmark ran on
+    pub num_atoms: usize,
+}
+
+/// Launch parameters tuned on one GPU model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchProfile {
+    pub version: u32,
+    pub device: String,
+    pub compute_capability: (i32, i32),
+    /// Keyed by kernel function name
+    pub kernels: BTreeMap<String, KernelTuning>,
+}
+
+impl LaunchProfile {
+    pub fn new(device: impl Into<String>, compute_capability: (i32, i32)) -> Self {
+        Self { version: PROFILE_VERSION, device: device.into(), compute_capability, kernels: BTreeMap::new() }
+    }
+
+    /// Empty profile for the device of `ctx`
+    pub fn for_context(ctx: &CudaContext) -> Result<Self> {
+        let name = ctx.name().context("Failed to query device name")?;
+        let capability = ctx.compute_capability().context("Failed to query compute capability")?;
+        Ok(Self::new(name, capability))
+    }
+
+    /// `<device name>-sm<major><minor>.json`, lowercase and path-safe
+    pub fn file_name(&self) -> String {
+        let mut slug = String::with_capacity(self.device.len());
+        for c in self.device.chars() {
+            if c.is_ascii_alphanumeric() {
+                slug.push(c.to_ascii_lowercase());
+            } else if !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let (major, minor) = self.compute_capability;
+        format!("{}-sm{}{}.json", slug.trim_matches('-'), major, minor)
+    }
+
+    /// Stored profile of this device in `dir`, or `self` when there is none
+    /// or it cannot be used (other version or device, unreadable file)
+    pub fn load_or(self, dir: &Path) -> Self {
+        let path = dir.join(self.file_name());
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => return self,
+        };
+        match serde_json::from_str::<Self>(&text) {
+            Ok(stored) if stored.version == PROFILE_VERSION && stored.device == self.device && stored.compute_capability == self.compute_capability => stored,
+            Ok(_) => {
+                log::info!("Launch profile {} is outdated, re-tuning", path.display());
+                self
+            }
+            Err(e) => {
+                log::warn!("Ignoring unreadable launch profile {}: {}", path.display(), e);
+                self
+            }
+        }
+    }
+
+    /// Write the profile into `dir` (created if missing)
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(self.file_name());
+        // Write then rename, so concurrent runs never read a partial file
+        let partial = path.with_extension("json.tmp");
+        std::fs::write(&partial, serde_json::to_string_pretty(self)?).with_context(|| format!("Failed to write {}", partial.display()))?;
+        std::fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Tuned block size of `kernel`
+    pub fn block_size(&self, kernel: &str) -> Option<u32> {
+        self.kernels.get(kernel).map(|tuning| tuning.block_size)
+    }
+
+    pub fn record(&mut self, kernel: &str, tuning: KernelTuning) {
+        self.kernels.insert(kernel.to_string(), tuning);
+    }
+}
+
+/// Directory of the launch profiles (see the module docs); `None` when no
+/// location is configured
+pub fn profile_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    if let Some(dir) = var("PRISM_GPU_PROFILE_DIR") {
+        return Some(dir);
+    }
+    let cache = var("XDG_CACHE_HOME").or_else(|| var("HOME").map(|home| home.join(".cache")))?;
+    Some(cache.join("prism").join("gpu-profiles"))
+}
+
+/// Fastest of `candidates` by the best of `repeats` calls of `time`, which
+/// launches the kernel with the given block size and returns the elapsed
+/// seconds. Each candidate gets one untimed warm-up call; candidates whose
+/// launch fails (e.g. too many registers for the block) are skipped.
+///
+/// # Returns
+/// The winning block size and its best time in seconds
+pub fn tune(candidates: &[u32], repeats: usize, mut time: impl FnMut(u32) -> Result<f64>) -> Result<(u32, f64)> {
+    let mut best: Option<(u32, f64)> = None;
+    for &block in candidates {
+        let timed = time(block).and_then(|_| {
+            (0..repeats.max(1)).try_fold(f64::INFINITY, |fastest, _| Ok(fastest.min(time(block)?)))
+        });
+        match timed {
+            Ok(seconds) => {
+                log::debug!("Block size {}: {:.1} µs", block, seconds * 1e6);
+                if best.is_none_or(|(_, fastest)| seconds < fastest) {
+                    best = Some((block, seconds));
+                }
+            }
+            Err(e) => log::debug!("Block size {} skipped: {:#}", block, e),
+        }
+    }
+    best.ok_or_else(|| anyhow::anyhow!("No block size in {:?} could be launched", candidates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tune_picks_fastest_launchable_block() {
+        let mut calls = Vec::new();
+        let (block, seconds) = tune(&BLOCK_SIZE_CANDIDATES, 3, |block| {
+            calls.push(block);
+            match block {
+                512 => anyhow::bail!("too many resources requested for launch"),
+                _ => Ok(1e-3 / block as f64 + 1e-6 * (block as f64 / 64.0).powi(2)),
+            }
+        })
+        .unwrap();
+        assert_eq!(block, 128);
+        assert!((seconds - 1e-3 / 128.0 - 4e-6).abs() < 1e-12);
+        // Warm-up plus three timed calls, one attempt for the failing size
+        assert_eq!(calls.iter().filter(|&&b| b == 64).count(), 4);
+        assert_eq!(calls.iter().filter(|&&b| b == 512).count(), 1);
+        assert!(tune(&[512], 3, |_| anyhow::bail!("no")).is_err());
+    }
+
+    #[test]
+    fn test_profile_round_trip() {
+        let dir = std::env::temp_dir().join(format!("prism_launch_profile_{}", std::process::id()));
+        let mut profile = LaunchProfile::new("NVIDIA GeForce RTX 4090", (8, 9));
+        assert_eq!(profile.file_name(), "nvidia-geforce-rtx-4090-sm89.json");
+        profile.record("nonbonded_forces_kernel", KernelTuning { block_size: 256, micros: 41.5, num_atoms: 5000 });
+        profile.save(&dir).unwrap();
+
+        let loaded = LaunchProfile::new("NVIDIA GeForce RTX 4090", (8, 9)).load_or(&dir);
+        assert_eq!(loaded, profile);
+        assert_eq!(loaded.block_size("nonbonded_forces_kernel"), Some(256));
+        assert_eq!(loaded.block_size("nonbonded_forces_neighbor_kernel"), None);
+        // Another model with the same name but a different architecture starts empty
+        let other = LaunchProfile::new("NVIDIA GeForce RTX 4090", (9, 0)).load_or(&dir);
+        assert!(other.kernels.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,369 @@
+//! Multi-GPU Domain Decomposition for Nonbonded Forces
+//!
+//! Splits a [`NonbondedSystem`] into slabs along its longest axis, one per
+//! CUDA device. Each device owns the atoms of its slab ("home") and keeps a
+//! copy of every atom within `cutoff + skin` of the slab ("halo"), so a
+//! device only holds its share of the system and its neighbors.
+//!
+//! ASSUMPTIONS:
+//! - The nonbonded kernel accumulates the force on each atom from its own
+//!   thread and halves pair energies per atom, so the home atoms of all
+//!   domains together yield every force and count every pair once
+//! - Halo positions are exchanged through the host on every evaluation;
+//!   membership is repartitioned once any atom has moved more than half
+//!   the skin since the last partition
+//! - PME is not decomposed (the mesh is global): use a single device
+//! - Periodic boxes are orthorhombic; slabs wrap along the split axis
+//! - Units: Angstrom, kcal/mol, elementary charge
+
+use anyhow::{Context, Result};
+use cudarc::driver::CudaContext;
+use std::sync::Arc;
+
+use crate::memory::VramGuard;
+use crate::nonbonded::{NonbondedGpu, NonbondedSystem};
+
+/// Skin used for repartitioning when the system has no Verlet skin (Å)
+const DEFAULT_HALO_SKIN: f32 = 2.0;
+
+/// One device's slab: its home atoms followed by its halo atoms
+struct Domain {
+    device: Arc<CudaContext>,
+    ordinal: usize,
+    /// Global indices of the local atoms, home atoms first
+    atoms: Vec<u32>,
+    num_home: usize,
+    evaluator: NonbondedGpu,
+    local_positions: Vec<f32>,
+    local_forces: Vec<f32>,
+}
+
+impl std::fmt::Debug for Domain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Domain")
+            .field("ordinal", &self.ordinal)
+            .field("num_home", &self.num_home)
+            .field("num_halo", &(self.atoms.len() - self.num_home))
+            .finish()
+    }
+}
+
+/// Nonbonded forces split across several CUDA devices
+#[derive(Debug)]
+pub struct DomainDecompositionGpu {
+    system: NonbondedSystem,
+    contexts: Vec<(usize, Arc<CudaContext>)>,
+    domains: Vec<Domain>,
+    /// Split axis (0 = x, 1 = y, 2 = z)
+    axis: usize,
+    skin: f32,
+    /// Positions at the last partition (Float4 stride)
+    reference: Vec<f32>,
+    partitions: usize,
+}
+
+impl DomainDecompositionGpu {
+    /// Open the devices and partition `system` at `positions`
+    ///
+    /// # Errors
+    /// Returns error for an empty device list, a PME system, a device that
+    /// cannot be opened, or a domain that does not fit in its device's VRAM.
+    pub fn new(ordinals: &[usize], system: &NonbondedSystem, positions: &[f32]) -> Result<Self> {
+        anyhow::ensure!(!ordinals.is_empty(), "Domain decomposition needs at least one device");
+        anyhow::ensure!(system.pme.is_none(), "PME is not supported with domain decomposition");
+        anyhow::ensure!(
+            positions.len() == system.params.len() * 4,
+            "Expected Float4 positions for {} atoms, got {} values",
+            system.params.len(),
+            positions.len()
+        );
+        let contexts = ordinals
+            .iter()
+            .map(|&ordinal| {
+                CudaContext::new(ordinal)
+                    .map(|ctx| (ordinal, ctx))
+                    .with_context(|| format!("Failed to open CUDA device {}", ordinal))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut decomposition = Self {
+            system: system.clone(),
+            contexts,
+            domains: Vec::new(),
+            axis: 0,
+            skin: system.neighbor_skin.unwrap_or(DEFAULT_HALO_SKIN),
+            reference: Vec::new(),
+            partitions: 0,
+        };
+        decomposition.partition(positions)?;
+        Ok(decomposition)
+    }
+
+    pub fn num_atoms(&self) -> usize {
+        self.system.params.len()
+    }
+
+    pub fn num_domains(&self) -> usize {
+        self.contexts.len()
+    }
+
+    /// Number of partitions so far (including the initial one)
+    pub fn partitions(&self) -> usize {
+        self.partitions
+    }
+
+    /// `(device ordinal, home atoms, halo atoms)` per domain
+    pub fn domain_sizes(&self) -> Vec<(usize, usize, usize)> {
+        self.domains
+            .iter()
+            .map(|d| (d.ordinal, d.num_home, d.atoms.len() - d.num_home))
+            .collect()
+    }
+
+    /// Pair virial of the last evaluation summed over the domains (see
+    /// [`NonbondedGpu::virial`]); each pair is counted once since every
+    /// domain only sums its home atoms
+    pub fn virial(&self) -> Option<[[f64; 3]; 3]> {
+        let mut total = [[0.0; 3]; 3];
+        for domain in &self.domains {
+            let virial = domain.evaluator.virial()?;
+            for (row, add) in total.iter_mut().zip(virial) {
+                for (w, x) in row.iter_mut().zip(add) {
+                    *w += x;
+                }
+            }
+        }
+        Some(total)
+    }
+
+    /// Evaluate forces for Float4-stride `positions` on all devices
+    /// concurrently, adding them into `forces` (kcal/mol/Å)
+    ///
+    /// # Returns
+    /// `(lennard_jones, coulomb)` energies in kcal/mol
+    pub fn compute(&mut self, positions: &[f32], forces: &mut [f32]) -> Result<(f64, f64)> {
+        let n = self.num_atoms();
+        anyhow::ensure!(
+            positions.len() == n * 4 && forces.len() == n * 4,
+            "Expected Float4 buffers for {} atoms, got {} positions and {} forces",
+            n,
+            positions.len(),
+            forces.len()
+        );
+        if self.needs_partition(positions) {
+            self.partition(positions)?;
+        }
+
+        let results: Vec<Result<(f64, f64)>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .domains
+                .iter_mut()
+                .map(|domain| scope.spawn(move || domain.compute(positions)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Domain worker panicked"))))
+                .collect()
+        });
+
+        let (mut lj, mut coulomb) = (0.0, 0.0);
+        for (domain, result) in self.domains.iter().zip(results) {
+            let (e_lj, e_coul) = result.with_context(|| format!("Domain on device {} failed", domain.ordinal))?;
+            lj += e_lj;
+            coulomb += e_coul;
+            for (&atom, f) in domain.atoms[..domain.num_home].iter().zip(domain.local_forces.chunks_exact(4)) {
+                let o = atom as usize * 4;
+                for k in 0..3 {
+                    forces[o + k] += f[k];
+                }
+            }
+        }
+        Ok((lj, coulomb))
+    }
+
+    fn needs_partition(&self, positions: &[f32]) -> bool {
+        if self.reference.len() != positions.len() {
+            return true;
+        }
+        let limit = 0.25 * self.skin * self.skin;
+        positions.chunks_exact(4).zip(self.reference.chunks_exact(4)).any(|(p, r)| {
+            let (dx, dy, dz) = (p[0] - r[0], p[1] - r[1], p[2] - r[2]);
+            dx * dx + dy * dy + dz * dz > limit
+        })
+    }
+
+    /// Repartition at `positions` and rebuild the per-device evaluators
+    fn partition(&mut self, positions: &[f32]) -> Result<()> {
+        let halo = self.system.cutoff + self.skin;
+        let (axis, slabs) = slabs(positions, self.system.box_lengths, self.contexts.len(), halo);
+        self.axis = axis;
+        // Release the previous evaluators before allocating their successors
+        self.domains.clear();
+        let mut domains = Vec::with_capacity(slabs.len());
+        for (d, ((ordinal, device), (atoms, num_home))) in self.contexts.iter().zip(slabs).enumerate() {
+            let local = self.local_system(&atoms);
+            device
+                .bind_to_thread()
+                .with_context(|| format!("Failed to bind CUDA device {}", ordinal))?;
+            VramGuard::new(device.clone())
+                .verify_allocation(NonbondedGpu::device_bytes(&local))
+                .with_context(|| format!("Domain of {} atoms does not fit on device {}", atoms.len(), ordinal))?;
+            let evaluator = NonbondedGpu::new(device.clone(), &local)?;
+            log::info!(
+                "Domain {} on device {}: {} home atoms, {} halo atoms",
+                d,
+                ordinal,
+                num_home,
+                atoms.len() - num_home
+            );
+            domains.push(Domain {
+                device: device.clone(),
+                ordinal: *ordinal,
+                local_positions: vec![0.0; atoms.len() * 4],
+                local_forces: vec![0.0; num_home * 4],
+                atoms,
+                num_home,
+                evaluator,
+            });
+        }
+        self.domains = domains;
+        self.reference.clear();
+        self.reference.extend_from_slice(positions);
+        self.partitions += 1;
+        Ok(())
+    }
+
+    /// Subsystem of the given global atoms, with exclusions remapped to
+    /// local indices
+    fn local_system(&self, atoms: &[u32]) -> NonbondedSystem {
+        let mut local_index = vec![u32::MAX; self.num_atoms()];
+        for (local, &global) in atoms.iter().enumerate() {
+            local_index[global as usize] = local as u32;
+        }
+        let exclusions = if self.system.exclusions.is_empty() {
+            Vec::new()
+        } else {
+            atoms
+                .iter()
+                .map(|&global| {
+                    self.system.exclusions[global as usize]
+                        .iter()
+                        .map(|&j| local_index[j as usize])
+                        .filter(|&j| j != u32::MAX)
+                        .collect()
+                })
+                .collect()
+        };
+        NonbondedSystem {
+            params: atoms.iter().map(|&i| self.system.params[i as usize]).collect(),
+            exclusions,
+            overrides: self.system.overrides.clone(),
+            num_types: self.system.num_types,
+            cutoff: self.system.cutoff,
+            switch_distance: self.system.switch_distance,
+            coulomb_scale: self.system.coulomb_scale,
+            neighbor_skin: self.system.neighbor_skin,
+            box_lengths: self.system.box_lengths,
+            ewald_beta: self.system.ewald_beta,
+            pme: None,
+            mixed_precision: self.system.mixed_precision,
+        }
+    }
+}
+
+/// Split Float4 `positions` into `num_domains` equal-count slabs along the
+/// longest axis (of the box when periodic). Returns the axis and, per slab,
+/// its home atoms followed by every other atom within `halo` of the slab
+/// along the axis, with the number of home atoms.
+fn slabs(
+    positions: &[f32],
+    box_lengths: Option<[f32; 3]>,
+    num_domains: usize,
+    halo: f32,
+) -> (usize, Vec<(Vec<u32>, usize)>) {
+    let n = positions.len() / 4;
+    let extent = |d: usize| match box_lengths {
+        Some(lengths) => lengths[d],
+        None => {
+            let coords = positions.chunks_exact(4).map(|p| p[d]);
+            let (lo, hi) = coords.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), x| (lo.min(x), hi.max(x)));
+            hi - lo
+        }
+    };
+    let axis = (0..3).max_by(|&a, &b| extent(a).total_cmp(&extent(b))).unwrap_or(0);
+    // Coordinate along the axis, wrapped into [0, L) when periodic
+    let coord = |i: usize| {
+        let x = positions[i * 4 + axis];
+        match box_lengths {
+            Some(lengths) => x - lengths[axis] * (x / lengths[axis]).floor(),
+            None => x,
+        }
+    };
+
+    let mut order: Vec<u32> = (0..n as u32).collect();
+    order.sort_by(|&a, &b| coord(a as usize).total_cmp(&coord(b as usize)));
+    let slabs = (0..num_domains)
+        .map(|d| {
+            let home = &order[d * n / num_domains..(d + 1) * n / num_domains];
+            let (lo, hi) = match (home.first(), home.last()) {
+                (Some(&first), Some(&last)) => (coord(first as usize), coord(last as usize)),
+                _ => (0.0, 0.0),
+            };
+            // Axis distance from the slab, through the periodic wrap when boxed
+            let distance = |x: f32| {
+                let outside = (lo - x).max(x - hi).max(0.0);
+                match box_lengths {
+                    Some(lengths) => outside.min(lengths[axis] - (hi - lo) - outside).max(0.0),
+                    None => outside,
+                }
+            };
+            let mut is_home = vec![false; n];
+            for &i in home {
+                is_home[i as usize] = true;
+            }
+            let mut atoms = home.to_vec();
+            atoms.extend((0..n as u32).filter(|&j| !is_home[j as usize] && distance(coord(j as usize)) <= halo));
+            (atoms, home.len())
+        })
+        .collect();
+    (axis, slabs)
+}
+
+impl Domain {
+    /// Exchange halo positions and evaluate the home forces on this device
+    fn compute(&mut self, positions: &[f32]) -> Result<(f64, f64)> {
+        self.device
+            .bind_to_thread()
+            .with_context(|| format!("Failed to bind CUDA device {}", self.ordinal))?;
+        for (local, &atom) in self.local_positions.chunks_exact_mut(4).zip(&self.atoms) {
+            let o = atom as usize * 4;
+            local.copy_from_slice(&positions[o..o + 4]);
+        }
+        self.local_forces.fill(0.0);
+        self.evaluator
+            .compute_home(&self.local_positions, &mut self.local_forces, self.num_home)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slabs_cover_atoms_once_with_halos() {
+        // 40 atoms along y, 1.5 Å apart
+        let positions: Vec<f32> = (0..40).flat_map(|i| [0.3 * (i % 2) as f32, 1.5 * i as f32, 0.0, 1.0]).collect();
+        let (axis, slabs) = slabs(&positions, None, 4, 3.0);
+        assert_eq!(axis, 1);
+        let mut homes: Vec<u32> = slabs.iter().flat_map(|(atoms, home)| atoms[..*home].to_vec()).collect();
+        homes.sort_unstable();
+        assert_eq!(homes, (0..40).collect::<Vec<_>>());
+        // Slab 1 holds atoms 10..20 and the two atoms on either side within 3 Å
+        let (atoms, home) = &slabs[1];
+        assert_eq!(atoms[*home..], [8, 9, 20, 21]);
+
+        // In a periodic box the first slab's halo wraps around to the last atoms
+        let (_, wrapped) = slabs(&positions, Some([10.0, 60.0, 10.0]), 4, 3.0);
+        let (atoms, home) = &wrapped[0];
+        assert_eq!(atoms[*home..], [10, 11, 38, 39]);
+    }
+}
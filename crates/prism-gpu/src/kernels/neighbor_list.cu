@@ -0,0 +1,165 @@
+// crates/prism-gpu/src/kernels/neighbor_list.cu
+//
+// Cell-list construction of Verlet neighbor lists.
+//
+// ALGORITHM:
+// 1. nl_count_cells:      bin atoms into cubic cells of edge >= cutoff + skin
+// 2. (host)               exclusive scan of the cell counts -> cell_start
+// 3. nl_scatter:          write atom indices into their cell segment
+// 4. nl_sort_cells:       sort each segment so lists are deterministic
+// 5. nl_build:            per atom, scan the 27 surrounding cells and keep
+//                         non-excluded partners within cutoff + skin
+// 6. nl_max_displacement: largest displacement since the last build, used
+//                         to trigger a rebuild once it exceeds skin / 2
+//
+// Positions are Float4 stride; the box is open (non-periodic).
+
+#include <cuda_runtime.h>
+
+extern "C" {
+
+__device__ __forceinline__ int nl_cell_coord(float x, float origin, float inv_cell, int dim) {
+    int c = (int)floorf((x - origin) * inv_cell);
+    return min(max(c, 0), dim - 1);
+}
+
+__global__ void nl_count_cells(
+    const float4* __restrict__ positions,
+    int num_atoms,
+    float ox, float oy, float oz,
+    float inv_cell,
+    int nx, int ny, int nz,
+    int* __restrict__ cell_of_atom,
+    int* __restrict__ cell_counts
+) {
+    int i = blockIdx.x * blockDim.x + threadIdx.x;
+    if (i >= num_atoms) return;
+    float4 p = positions[i];
+    int cx = nl_cell_coord(p.x, ox, inv_cell, nx);
+    int cy = nl_cell_coord(p.y, oy, inv_cell, ny);
+    int cz = nl_cell_coord(p.z, oz, inv_cell, nz);
+    int cell = (cz * ny + cy) * nx + cx;
+    cell_of_atom[i] = cell;
+    atomicAdd(&cell_counts[cell], 1);
+}
+
+__global__ void nl_scatter(
+    int num_atoms,
+    const int* __restrict__ cell_of_atom,
+    int* __restrict__ cell_cursor,
+    int* __restrict__ cell_atoms
+) {
+    int i = blockIdx.x * blockDim.x + threadIdx.x;
+    if (i >= num_atoms) return;
+    int slot = atomicAdd(&cell_cursor[cell_of_atom[i]], 1);
+    cell_atoms[slot] = i;
+}
+
+__global__ void nl_sort_cells(
+    int num_cells,
+    const int* __restrict__ cell_start,
+    int* __restrict__ cell_atoms
+) {
+    int c = blockIdx.x * blockDim.x + threadIdx.x;
+    if (c >= num_cells) return;
+    int begin = cell_start[c];
+    int end = cell_start[c + 1];
+    for (int a = begin + 1; a < end; ++a) {
+        int v = cell_atoms[a];
+        int b = a - 1;
+        while (b >= begin && cell_atoms[b] > v) {
+            cell_atoms[b + 1] = cell_atoms[b];
+            --b;
+        }
+        cell_atoms[b + 1] = v;
+    }
+}
+
+__device__ __forceinline__ bool nl_is_excluded(const int* excl, int begin, int end, int j) {
+    // Exclusion lists are sorted: binary search
+    while (begin < end) {
+        int mid = (begin + end) >> 1;
+        int v = excl[mid];
+        if (v == j) return true;
+        if (v < j) begin = mid + 1; else end = mid;
+    }
+    return false;
+}
+
+__global__ void nl_build(
+    const float4* __restrict__ positions,
+    int num_atoms,
+    const int* __restrict__ cell_of_atom,
+    const int* __restrict__ cell_start,
+    const int* __restrict__ cell_atoms,
+    int nx, int ny, int nz,
+    const int* __restrict__ excl_offsets,
+    const int* __restrict__ excl_atoms,
+    float list_cutoff2,
+    int max_neighbors,
+    int* __restrict__ neighbors,        // num_atoms * max_neighbors
+    int* __restrict__ neighbor_counts,  // num_atoms
+    int* __restrict__ overflow          // largest count that did not fit
+) {
+    int i = blockIdx.x * blockDim.x + threadIdx.x;
+    if (i >= num_atoms) return;
+
+    float4 pi = positions[i];
+    int cell = cell_of_atom[i];
+    int cx = cell % nx;
+    int cy = (cell / nx) % ny;
+    int cz = cell / (nx * ny);
+    int ex_begin = excl_offsets[i];
+    int ex_end = excl_offsets[i + 1];
+
+    int count = 0;
+    for (int dz = -1; dz <= 1; ++dz) {
+        int z = cz + dz;
+        if (z < 0 || z >= nz) continue;
+        for (int dy = -1; dy <= 1; ++dy) {
+            int y = cy + dy;
+            if (y < 0 || y >= ny) continue;
+            for (int dx = -1; dx <= 1; ++dx) {
+                int x = cx + dx;
+                if (x < 0 || x >= nx) continue;
+                int c = (z * ny + y) * nx + x;
+                for (int s = cell_start[c]; s < cell_start[c + 1]; ++s) {
+                    int j = cell_atoms[s];
+                    if (j == i) continue;
+                    float4 pj = positions[j];
+                    float rx = pj.x - pi.x;
+                    float ry = pj.y - pi.y;
+                    float rz = pj.z - pi.z;
+                    if (rx * rx + ry * ry + rz * rz >= list_cutoff2) continue;
+                    if (nl_is_excluded(excl_atoms, ex_begin, ex_end, j)) continue;
+                    if (count < max_neighbors) {
+                        neighbors[(size_t)i * max_neighbors + count] = j;
+                    }
+                    ++count;
+                }
+            }
+        }
+    }
+    neighbor_counts[i] = min(count, max_neighbors);
+    if (count > max_neighbors) {
+        atomicMax(overflow, count);
+    }
+}
+
+__global__ void nl_max_displacement(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ reference,
+    int num_atoms,
+    int* __restrict__ max_disp2_bits    // non-negative floats order like ints
+) {
+    int i = blockIdx.x * blockDim.x + threadIdx.x;
+    if (i >= num_atoms) return;
+    float4 p = positions[i];
+    float4 r = reference[i];
+    float dx = p.x - r.x;
+    float dy = p.y - r.y;
+    float dz = p.z - r.z;
+    atomicMax(max_disp2_bits, __float_as_int(dx * dx + dy * dy + dz * dz));
+}
+
+}
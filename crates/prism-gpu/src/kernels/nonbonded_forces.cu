@@ -0,0 +1,309 @@
+// crates/prism-gpu/src/kernels/nonbonded_forces.cu
+//
+// Lennard-Jones 12-6 + Coulomb forces within a cutoff, either over all
+// pairs or over a Verlet neighbor list (see neighbor_list.cu). With PME the
+// Coulomb term is the unswitched Ewald real-space erfc(beta r) / r and the
+// mesh part is added by pme.cu.
+//
+// ASSUMPTIONS:
+// - All-pairs kernels: the tile width is blockDim.x; positions and parameters
+//   are staged through 2 * blockDim.x float4 of dynamic shared memory
+// - Positions and parameters are Float4 stride:
+//     positions[i] = (x, y, z, mass), params[i] = (sigma, epsilon, charge, type)
+// - Exclusion partners of each atom are sorted ascending (CSR layout)
+// - A box edge of 0 means open boundaries; otherwise the minimum image is used
+// - One thread per atom accumulates the force on that atom only, so no
+//   atomics are needed; pair energies are halved per atom.
+// - The *_mixed_kernel variants evaluate each pair in float but sum the
+//   atom's force, energies and pair virial in double; the force is rounded
+//   to float once per atom.
+// Units: Angstrom, kcal/mol, elementary charge.
+
+#include <cuda_runtime.h>
+
+// Per-atom sums of one thread
+template <typename T>
+struct nb_acc {
+    T fx, fy, fz;
+    T e_lj, e_coul;
+    T w[6];  // halved pair virial xx, yy, zz, xy, xz, yz (mixed kernels only)
+};
+
+// Force on atom i from atom j and the halved pair energies (and virial),
+// added to acc.
+template <typename T, bool VIRIAL>
+__device__ __forceinline__ void nb_pair(
+    float4 pi, float4 qi, float4 pj, float4 qj,
+    const float2* __restrict__ overrides, int num_types,
+    float cutoff2, bool switched, float ron2, float sw_denom, float coulomb_scale,
+    float ewald_beta, float3 box,
+    nb_acc<T>& acc
+) {
+    float dx = pj.x - pi.x;
+    float dy = pj.y - pi.y;
+    float dz = pj.z - pi.z;
+    if (box.x > 0.0f) dx -= box.x * rintf(dx / box.x);
+    if (box.y > 0.0f) dy -= box.y * rintf(dy / box.y);
+    if (box.z > 0.0f) dz -= box.z * rintf(dz / box.z);
+    const float r2 = dx * dx + dy * dy + dz * dz;
+    if (r2 >= cutoff2 || r2 <= 0.0f) return;
+
+    float sigma = 0.5f * (qi.x + qj.x);
+    float epsilon = sqrtf(qi.y * qj.y);
+    if (num_types > 0) {
+        const float2 ov = overrides[(int)qi.w * num_types + (int)qj.w];
+        if (ov.y >= 0.0f) {
+            sigma = ov.x;
+            epsilon = ov.y;
+        }
+    }
+
+    const float r = sqrtf(r2);
+    const float inv_r2 = 1.0f / r2;
+    const float sr2 = sigma * sigma * inv_r2;
+    const float sr6 = sr2 * sr2 * sr2;
+    const float elj = 4.0f * epsilon * (sr6 * sr6 - sr6);
+    const float dlj = -24.0f * epsilon * (2.0f * sr6 * sr6 - sr6) / r;
+
+    const float qq = coulomb_scale * qi.z * qj.z;
+    float ec = qq / r;
+    float dc = -qq * inv_r2;
+    if (ewald_beta > 0.0f) {
+        const float br = ewald_beta * r;
+        ec = qq * erfcf(br) / r;
+        dc = -ec / r - qq * 1.1283791671f * ewald_beta * expf(-br * br) / r;
+    }
+
+    // CHARMM switching function S(r) and dS/dr
+    float s = 1.0f, ds = 0.0f;
+    if (switched && r2 > ron2) {
+        const float a = cutoff2 - r2;
+        s = a * a * (cutoff2 + 2.0f * r2 - 3.0f * ron2) * sw_denom;
+        ds = 12.0f * r * a * (ron2 - r2) * sw_denom;
+    }
+
+    // Ewald real-space Coulomb is already smooth at the cutoff
+    const float sc = ewald_beta > 0.0f ? 1.0f : s;
+    const float dsc = ewald_beta > 0.0f ? 0.0f : ds;
+    const float de_dr = dlj * s + elj * ds + dc * sc + ec * dsc;
+    const float f_over_r = -de_dr / r;
+    acc.fx -= (T)(f_over_r * dx);
+    acc.fy -= (T)(f_over_r * dy);
+    acc.fz -= (T)(f_over_r * dz);
+    acc.e_lj += (T)(0.5f * elj * s);
+    acc.e_coul += (T)(0.5f * ec * sc);
+    if (VIRIAL) {
+        // Same convention as the host Virial::add_pair, halved per atom
+        const T h = (T)0.5 * (T)f_over_r;
+        acc.w[0] += h * (T)dx * (T)dx;
+        acc.w[1] += h * (T)dy * (T)dy;
+        acc.w[2] += h * (T)dz * (T)dz;
+        acc.w[3] += h * (T)dx * (T)dy;
+        acc.w[4] += h * (T)dx * (T)dz;
+        acc.w[5] += h * (T)dy * (T)dz;
+    }
+}
+
+// All-pairs sums of atom i, staging positions through the shared tiles
+template <typename T, bool VIRIAL>
+__device__ __forceinline__ nb_acc<T> nb_all_pairs(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ params,
+    const int* __restrict__ excl_offsets,
+    const int* __restrict__ excl_atoms,
+    const float2* __restrict__ overrides,
+    int num_types, int num_atoms,
+    float cutoff, float switch_on, float coulomb_scale, float ewald_beta, float3 box,
+    float4* tile_pos, float4* tile_par,
+    int i
+) {
+    const bool active = i < num_atoms;
+
+    const float4 pi = active ? positions[i] : make_float4(0.0f, 0.0f, 0.0f, 0.0f);
+    const float4 qi = active ? params[i] : make_float4(0.0f, 0.0f, 0.0f, 0.0f);
+    int ex = active ? excl_offsets[i] : 0;
+    const int ex_end = active ? excl_offsets[i + 1] : 0;
+
+    const float cutoff2 = cutoff * cutoff;
+    const bool switched = switch_on < cutoff;
+    const float ron2 = switch_on * switch_on;
+    const float sw_denom = switched ? 1.0f / ((cutoff2 - ron2) * (cutoff2 - ron2) * (cutoff2 - ron2)) : 0.0f;
+
+    nb_acc<T> acc = {};
+
+    const int tile = blockDim.x;
+    for (int base = 0; base < num_atoms; base += tile) {
+        const int load = base + threadIdx.x;
+        if (load < num_atoms) {
+            tile_pos[threadIdx.x] = positions[load];
+            tile_par[threadIdx.x] = params[load];
+        }
+        __syncthreads();
+
+        const int count = min(tile, num_atoms - base);
+        if (active) {
+            for (int t = 0; t < count; ++t) {
+                const int j = base + t;
+                if (j == i) continue;
+                while (ex < ex_end && excl_atoms[ex] < j) ++ex;
+                if (ex < ex_end && excl_atoms[ex] == j) continue;
+
+                nb_pair<T, VIRIAL>(pi, qi, tile_pos[t], tile_par[t], overrides, num_types,
+                                   cutoff2, switched, ron2, sw_denom, coulomb_scale, ewald_beta, box,
+                                   acc);
+            }
+        }
+        __syncthreads();
+    }
+    return acc;
+}
+
+// Neighbor-list sums of atom i
+template <typename T, bool VIRIAL>
+__device__ __forceinline__ nb_acc<T> nb_listed_pairs(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ params,
+    const int* __restrict__ neighbors,
+    const int* __restrict__ neighbor_counts,
+    int max_neighbors,
+    const float2* __restrict__ overrides,
+    int num_types,
+    float cutoff, float switch_on, float coulomb_scale, float ewald_beta, float3 box,
+    int i
+) {
+    const float cutoff2 = cutoff * cutoff;
+    const bool switched = switch_on < cutoff;
+    const float ron2 = switch_on * switch_on;
+    const float sw_denom = switched ? 1.0f / ((cutoff2 - ron2) * (cutoff2 - ron2) * (cutoff2 - ron2)) : 0.0f;
+
+    const float4 pi = positions[i];
+    const float4 qi = params[i];
+    nb_acc<T> acc = {};
+
+    const int* list = neighbors + (size_t)i * max_neighbors;
+    const int count = neighbor_counts[i];
+    for (int k = 0; k < count; ++k) {
+        const int j = list[k];
+        nb_pair<T, VIRIAL>(pi, qi, positions[j], params[j], overrides, num_types,
+                           cutoff2, switched, ron2, sw_denom, coulomb_scale, ewald_beta, box,
+                           acc);
+    }
+    return acc;
+}
+
+__device__ __forceinline__ void nb_store(const nb_acc<float>& acc, int i, float4* forces, float2* energies) {
+    forces[i] = make_float4(acc.fx, acc.fy, acc.fz, 0.0f);
+    energies[i] = make_float2(acc.e_lj, acc.e_coul);
+}
+
+__device__ __forceinline__ void nb_store(const nb_acc<double>& acc, int i, float4* forces, double2* energies, double* virial) {
+    forces[i] = make_float4((float)acc.fx, (float)acc.fy, (float)acc.fz, 0.0f);
+    energies[i] = make_double2(acc.e_lj, acc.e_coul);
+    for (int k = 0; k < 6; ++k) virial[i * 6 + k] = acc.w[k];
+}
+
+extern "C" {
+
+__global__ void nonbonded_forces_kernel(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ params,
+    const int* __restrict__ excl_offsets,   // num_atoms + 1
+    const int* __restrict__ excl_atoms,
+    const float2* __restrict__ overrides,   // num_types^2 (sigma, epsilon); epsilon < 0 = mixing rule
+    int num_types,
+    int num_atoms,
+    float cutoff,
+    float switch_on,                        // >= cutoff disables switching
+    float coulomb_scale,                    // Coulomb constant / dielectric
+    float ewald_beta,                       // 0 = plain Coulomb
+    float box_x, float box_y, float box_z,  // 0 = open boundaries
+    float4* __restrict__ forces,
+    float2* __restrict__ energies           // per atom (lj, coulomb)
+) {
+    extern __shared__ float4 nb_tiles[];
+    const int i = blockIdx.x * blockDim.x + threadIdx.x;
+    const nb_acc<float> acc = nb_all_pairs<float, false>(
+        positions, params, excl_offsets, excl_atoms, overrides, num_types, num_atoms,
+        cutoff, switch_on, coulomb_scale, ewald_beta, make_float3(box_x, box_y, box_z),
+        nb_tiles, nb_tiles + blockDim.x, i);
+    if (i < num_atoms) nb_store(acc, i, forces, energies);
+}
+
+__global__ void nonbonded_forces_mixed_kernel(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ params,
+    const int* __restrict__ excl_offsets,
+    const int* __restrict__ excl_atoms,
+    const float2* __restrict__ overrides,
+    int num_types,
+    int num_atoms,
+    float cutoff,
+    float switch_on,
+    float coulomb_scale,
+    float ewald_beta,
+    float box_x, float box_y, float box_z,
+    float4* __restrict__ forces,
+    double2* __restrict__ energies,         // per atom (lj, coulomb)
+    double* __restrict__ virial             // per atom xx, yy, zz, xy, xz, yz
+) {
+    extern __shared__ float4 nb_tiles[];
+    const int i = blockIdx.x * blockDim.x + threadIdx.x;
+    const nb_acc<double> acc = nb_all_pairs<double, true>(
+        positions, params, excl_offsets, excl_atoms, overrides, num_types, num_atoms,
+        cutoff, switch_on, coulomb_scale, ewald_beta, make_float3(box_x, box_y, box_z),
+        nb_tiles, nb_tiles + blockDim.x, i);
+    if (i < num_atoms) nb_store(acc, i, forces, energies, virial);
+}
+
+__global__ void nonbonded_forces_neighbor_kernel(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ params,
+    const int* __restrict__ neighbors,        // num_atoms * max_neighbors
+    const int* __restrict__ neighbor_counts,
+    int max_neighbors,
+    const float2* __restrict__ overrides,
+    int num_types,
+    int num_atoms,
+    float cutoff,
+    float switch_on,
+    float coulomb_scale,
+    float ewald_beta,
+    float box_x, float box_y, float box_z,
+    float4* __restrict__ forces,
+    float2* __restrict__ energies
+) {
+    const int i = blockIdx.x * blockDim.x + threadIdx.x;
+    if (i >= num_atoms) return;
+    const nb_acc<float> acc = nb_listed_pairs<float, false>(
+        positions, params, neighbors, neighbor_counts, max_neighbors, overrides, num_types,
+        cutoff, switch_on, coulomb_scale, ewald_beta, make_float3(box_x, box_y, box_z), i);
+    nb_store(acc, i, forces, energies);
+}
+
+__global__ void nonbonded_forces_neighbor_mixed_kernel(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ params,
+    const int* __restrict__ neighbors,
+    const int* __restrict__ neighbor_counts,
+    int max_neighbors,
+    const float2* __restrict__ overrides,
+    int num_types,
+    int num_atoms,
+    float cutoff,
+    float switch_on,
+    float coulomb_scale,
+    float ewald_beta,
+    float box_x, float box_y, float box_z,
+    float4* __restrict__ forces,
+    double2* __restrict__ energies,
+    double* __restrict__ virial
+) {
+    const int i = blockIdx.x * blockDim.x + threadIdx.x;
+    if (i >= num_atoms) return;
+    const nb_acc<double> acc = nb_listed_pairs<double, true>(
+        positions, params, neighbors, neighbor_counts, max_neighbors, overrides, num_types,
+        cutoff, switch_on, coulomb_scale, ewald_beta, make_float3(box_x, box_y, box_z), i);
+    nb_store(acc, i, forces, energies, virial);
+}
+
+}
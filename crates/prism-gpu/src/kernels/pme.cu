@@ -0,0 +1,160 @@
+// crates/prism-gpu/src/kernels/pme.cu
+//
+// Smooth Particle Mesh Ewald reciprocal-space kernels: charge spreading,
+// convolution with the influence function in Fourier space, and force
+// interpolation. The 3D FFTs between these steps are done with cuFFT
+// (R2C forward, C2R inverse, both unnormalized).
+//
+// ASSUMPTIONS:
+// - Rectangular box; real grid is row-major (x, y, z) with z fastest,
+//   the complex half grid is (nx, ny, nz / 2 + 1)
+// - positions[i] = (x, y, z, mass), params[i] = (sigma, epsilon, charge, type)
+// - Spline order <= PME_MAX_ORDER and <= every grid dimension
+// - influence[] already holds coulomb_scale * exp(-pi^2 m^2 / beta^2) * B(m) / (pi V m^2)
+// Units: Angstrom, kcal/mol, elementary charge.
+
+#include <cuda_runtime.h>
+
+#define PME_MAX_ORDER 8
+#define PME_BLOCK 256
+
+extern "C" {
+
+// Cardinal B-spline values M_n(w + j) and derivatives, j = 0..order-1
+__device__ __forceinline__ void pme_bspline(float w, int order, float* m, float* dm) {
+    m[0] = w;
+    m[1] = 1.0f - w;
+    for (int j = 2; j < order; ++j) m[j] = 0.0f;
+    for (int k = 3; k <= order; ++k) {
+        if (k == order) {
+            dm[0] = m[0];
+            for (int j = 1; j < k; ++j) dm[j] = m[j] - m[j - 1];
+        }
+        const float div = 1.0f / (float)(k - 1);
+        for (int j = k - 1; j >= 0; --j) {
+            const float x = w + (float)j;
+            const float left = j > 0 ? m[j - 1] : 0.0f;
+            m[j] = div * (x * m[j] + ((float)k - x) * left);
+        }
+    }
+}
+
+// Fractional grid coordinate of x, its base index and spline weights
+__device__ __forceinline__ int pme_axis(float x, float length, int size, int order, float* m, float* dm) {
+    const float s = x / length;
+    const float u = (s - floorf(s)) * (float)size;
+    const float base = floorf(u);
+    pme_bspline(u - base, order, m, dm);
+    return ((int)base) % size;
+}
+
+__global__ void pme_spread(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ params,
+    int num_atoms,
+    int nx, int ny, int nz,
+    float lx, float ly, float lz,
+    int order,
+    float* __restrict__ grid                // nx * ny * nz, zeroed by the caller
+) {
+    const int i = blockIdx.x * blockDim.x + threadIdx.x;
+    if (i >= num_atoms) return;
+    const float q = params[i].z;
+    if (q == 0.0f) return;
+
+    const float4 p = positions[i];
+    float mx[PME_MAX_ORDER], my[PME_MAX_ORDER], mz[PME_MAX_ORDER], d[PME_MAX_ORDER];
+    const int bx = pme_axis(p.x, lx, nx, order, mx, d);
+    const int by = pme_axis(p.y, ly, ny, order, my, d);
+    const int bz = pme_axis(p.z, lz, nz, order, mz, d);
+
+    for (int a = 0; a < order; ++a) {
+        const int x = (bx + nx - a) % nx;
+        for (int b = 0; b < order; ++b) {
+            const int y = (by + ny - b) % ny;
+            const float w = q * mx[a] * my[b];
+            float* row = grid + ((size_t)x * ny + y) * nz;
+            for (int c = 0; c < order; ++c) {
+                atomicAdd(&row[(bz + nz - c) % nz], w * mz[c]);
+            }
+        }
+    }
+}
+
+// Multiply the transformed charge grid by the influence function and
+// accumulate E = 1/2 sum theta |F|^2 over the full (Hermitian) grid.
+__global__ void pme_convolve(
+    float2* __restrict__ grid,              // nx * ny * (nz / 2 + 1)
+    const float* __restrict__ influence,    // same layout as grid
+    int count,
+    int nz,
+    double* __restrict__ energy
+) {
+    __shared__ double partial[PME_BLOCK];
+    const int idx = blockIdx.x * blockDim.x + threadIdx.x;
+    double e = 0.0;
+    if (idx < count) {
+        const int half = nz / 2 + 1;
+        const int z = idx % half;
+        // Entries with a distinct conjugate partner stand for two grid points
+        const double weight = (z == 0 || (nz % 2 == 0 && z == nz / 2)) ? 1.0 : 2.0;
+        const float theta = influence[idx];
+        const float2 f = grid[idx];
+        e = 0.5 * weight * (double)theta * ((double)f.x * f.x + (double)f.y * f.y);
+        grid[idx] = make_float2(f.x * theta, f.y * theta);
+    }
+    partial[threadIdx.x] = e;
+    __syncthreads();
+    for (int s = blockDim.x / 2; s > 0; s >>= 1) {
+        if (threadIdx.x < s) partial[threadIdx.x] += partial[threadIdx.x + s];
+        __syncthreads();
+    }
+    if (threadIdx.x == 0) atomicAdd(energy, partial[0]);
+}
+
+// Interpolate the convolved potential back to the atoms: F_i = -q_i grad(phi)
+__global__ void pme_gather(
+    const float4* __restrict__ positions,
+    const float4* __restrict__ params,
+    const float* __restrict__ potential,    // nx * ny * nz after the inverse FFT
+    int num_atoms,
+    int nx, int ny, int nz,
+    float lx, float ly, float lz,
+    int order,
+    float4* __restrict__ forces             // accumulated into
+) {
+    const int i = blockIdx.x * blockDim.x + threadIdx.x;
+    if (i >= num_atoms) return;
+    const float q = params[i].z;
+    if (q == 0.0f) return;
+
+    const float4 p = positions[i];
+    float mx[PME_MAX_ORDER], my[PME_MAX_ORDER], mz[PME_MAX_ORDER];
+    float dx[PME_MAX_ORDER], dy[PME_MAX_ORDER], dz[PME_MAX_ORDER];
+    const int bx = pme_axis(p.x, lx, nx, order, mx, dx);
+    const int by = pme_axis(p.y, ly, ny, order, my, dy);
+    const int bz = pme_axis(p.z, lz, nz, order, mz, dz);
+
+    float gx = 0.0f, gy = 0.0f, gz = 0.0f;
+    for (int a = 0; a < order; ++a) {
+        const int x = (bx + nx - a) % nx;
+        for (int b = 0; b < order; ++b) {
+            const int y = (by + ny - b) % ny;
+            const float* row = potential + ((size_t)x * ny + y) * nz;
+            for (int c = 0; c < order; ++c) {
+                const float phi = row[(bz + nz - c) % nz];
+                gx += phi * dx[a] * my[b] * mz[c];
+                gy += phi * mx[a] * dy[b] * mz[c];
+                gz += phi * mx[a] * my[b] * dz[c];
+            }
+        }
+    }
+
+    float4 f = forces[i];
+    f.x -= q * gx * (float)nx / lx;
+    f.y -= q * gy * (float)ny / ly;
+    f.z -= q * gz * (float)nz / lz;
+    forces[i] = f;
+}
+
+}
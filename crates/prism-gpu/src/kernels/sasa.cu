@@ -0,0 +1,62 @@
+// crates/prism-gpu/src/kernels/sasa.cu
+//
+// Shrake-Rupley solvent accessible surface area. Each atom's expanded
+// sphere (vdW radius + probe) carries the same set of unit test points;
+// a point is buried when it lies inside any other expanded sphere.
+//
+// ASSUMPTIONS:
+// - One block per atom, blockDim.x == SASA_BLOCK, threads stride over points
+// - Positions are Float4 stride (x, y, z, mass); radii already include the probe
+// - All atoms are scanned for every point (O(N² P) work, no neighbor list)
+// - Output is the exposed fraction of each atom's points; the host scales
+//   it by 4π r² to get the area
+// Units: Angstrom.
+
+#include <cuda_runtime.h>
+
+#define SASA_BLOCK 128
+
+extern "C" {
+
+__global__ void sasa_kernel(
+    const float4* __restrict__ positions,
+    const float* __restrict__ radii,
+    const float3* __restrict__ points,
+    int num_points,
+    int num_atoms,
+    float* __restrict__ exposed
+) {
+    __shared__ int counts[SASA_BLOCK];
+    const int i = blockIdx.x;
+    if (i >= num_atoms) return;
+    const float4 pi = positions[i];
+    const float ri = radii[i];
+
+    int count = 0;
+    for (int p = threadIdx.x; p < num_points; p += blockDim.x) {
+        const float3 u = points[p];
+        const float x = pi.x + ri * u.x;
+        const float y = pi.y + ri * u.y;
+        const float z = pi.z + ri * u.z;
+        bool buried = false;
+        for (int j = 0; j < num_atoms && !buried; ++j) {
+            if (j == i) continue;
+            const float4 pj = positions[j];
+            const float dx = x - pj.x;
+            const float dy = y - pj.y;
+            const float dz = z - pj.z;
+            const float rj = radii[j];
+            buried = dx * dx + dy * dy + dz * dz < rj * rj;
+        }
+        count += buried ? 0 : 1;
+    }
+    counts[threadIdx.x] = count;
+    __syncthreads();
+    for (int stride = blockDim.x / 2; stride > 0; stride >>= 1) {
+        if (threadIdx.x < stride) counts[threadIdx.x] += counts[threadIdx.x + stride];
+        __syncthreads();
+    }
+    if (threadIdx.x == 0) exposed[i] = (float)counts[0] / (float)num_points;
+}
+
+} // extern "C"
@@ -14,6 +14,12 @@ pub mod reservoir_construction;
 pub mod ve_swarm;
 pub mod polycentric_immunity;
 pub mod active_inference; 
+pub mod autotune;
+pub mod domain_decomposition;
+pub mod neighbor_list;
+pub mod nonbonded;
+pub mod pme;
+pub mod sasa;
 
 // Essential exports
 pub use context::{GpuContext, GpuInfo, GpuSecurityConfig};
@@ -26,7 +32,13 @@ pub use mega_fused_batch::{MegaFusedBatchGpu, BatchStructureDesc, StructureInput
 pub use reservoir_construction::{BioReservoir, SparseConnection, compute_readout_weights};
 pub use polycentric_immunity::{PolycentricImmunityGpu, N_EPITOPE_CENTERS, N_PK_SCENARIOS, POLYCENTRIC_OUTPUT_DIM, DEFAULT_CROSS_REACTIVITY};
 pub use active_inference::{ActiveInferenceGpu, ActiveInferencePolicy};
-pub use memory::{VramGuard, VramInfo, VramGuardError, init_global_vram_guard, global_vram_guard};
+pub use autotune::{KernelTuning, LaunchProfile};
+pub use neighbor_list::{NeighborListConfig, NeighborListGpu};
+pub use domain_decomposition::DomainDecompositionGpu;
+pub use nonbonded::{NonbondedGpu, NonbondedSystem};
+pub use pme::{PmeGpu, PmeSystem};
+pub use sasa::SasaGpu;
+pub use memory::{VramGuard, VramInfo, VramGuardError, VramPool, VramPoolStats, VramRegion, init_global_vram_guard, global_vram_guard};
 
 // Commented out unused modules to isolate benchmark requirements
 // pub mod aatgs;
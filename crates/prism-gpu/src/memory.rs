@@ -8,14 +8,25 @@
 //! - Enforce 90% threshold to prevent memory exhaustion
 //! - Graceful panic with clear error messages
 //! - Zero tolerance for driver crashes
+//!
+//! ## Memory Pool
+//! [`VramGuard::reserve_pool`] turns the startup check into an allocation:
+//! the approved trajectory and workspace budgets are reserved as one device
+//! block and handed out by a [`VramPool`], which tracks high-water marks and
+//! fragmentation per region for the run telemetry.
 
 use cudarc::driver::{CudaContext, DriverError};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use thiserror::Error;
 
 /// VRAM safety threshold - 90% of available memory
 const VRAM_SAFETY_THRESHOLD: f64 = 0.9;
 
+/// Pool sub-allocation alignment in bytes (that of `cuMemAlloc`)
+pub const POOL_ALIGNMENT: usize = 256;
+
 /// Memory allocation errors with sovereign context
 #[derive(Error, Debug)]
 pub enum VramGuardError {
@@ -34,6 +45,19 @@ pub enum VramGuardError {
     /// GPU device not available
     #[error("GPU device unavailable for memory query")]
     DeviceUnavailable,
+
+    /// Pool region has no free range large enough
+    #[error("VRAM pool {region} region exhausted: requested {requested} bytes, {free} free (largest range {largest} bytes)")]
+    PoolExhausted {
+        region: VramRegion,
+        requested: usize,
+        free: usize,
+        largest: usize,
+    },
+
+    /// Device reservation for the pool failed
+    #[error("VRAM pool reservation of {bytes} bytes failed: {reason}")]
+    ReservationFailed { bytes: usize, reason: String },
 }
 
 /// GPU memory information with sovereign metadata
@@ -78,6 +102,212 @@ impl VramInfo {
     }
 }
 
+/// Pool region a sub-allocation is charged to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VramRegion {
+    /// Frame snapshots and trajectory buffers
+    Trajectory,
+    /// Integration state and scratch buffers
+    Workspace,
+}
+
+impl std::fmt::Display for VramRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VramRegion::Trajectory => write!(f, "trajectory"),
+            VramRegion::Workspace => write!(f, "workspace"),
+        }
+    }
+}
+
+/// Usage of one pool region
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RegionStats {
+    pub capacity_bytes: usize,
+    pub used_bytes: usize,
+    /// High-water mark of `used_bytes`
+    pub peak_bytes: usize,
+    pub allocations: usize,
+    /// `1 - largest free range / total free`; 0 when the free space is contiguous
+    pub fragmentation: f64,
+}
+
+/// Usage of a [`VramPool`], reported in the run telemetry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VramPoolStats {
+    pub capacity_bytes: usize,
+    pub used_bytes: usize,
+    /// High-water mark over both regions together
+    pub peak_bytes: usize,
+    pub regions: BTreeMap<VramRegion, RegionStats>,
+}
+
+/// First-fit offset allocator over `[0, capacity)` with coalescing frees.
+/// Pure bookkeeping: the device memory belongs to [`VramPool`].
+#[derive(Debug, Clone)]
+pub struct RegionAllocator {
+    capacity: usize,
+    /// Free `(offset, len)` ranges, sorted by offset and never adjacent
+    free: Vec<(usize, usize)>,
+    /// Live allocations, offset to length
+    live: BTreeMap<usize, usize>,
+    used: usize,
+    peak: usize,
+}
+
+impl RegionAllocator {
+    pub fn new(capacity: usize) -> Self {
+        let free = if capacity > 0 { vec![(0, capacity)] } else { Vec::new() };
+        Self { capacity, free, live: BTreeMap::new(), used: 0, peak: 0 }
+    }
+
+    /// Offset of a new range of `bytes` rounded up to [`POOL_ALIGNMENT`];
+    /// `None` when no free range fits
+    pub fn allocate(&mut self, bytes: usize) -> Option<usize> {
+        let len = bytes.max(1).next_multiple_of(POOL_ALIGNMENT);
+        let slot = self.free.iter().position(|&(_, free_len)| free_len >= len)?;
+        let (offset, free_len) = self.free[slot];
+        if free_len == len {
+            self.free.remove(slot);
+        } else {
+            self.free[slot] = (offset + len, free_len - len);
+        }
+        self.live.insert(offset, len);
+        self.used += len;
+        self.peak = self.peak.max(self.used);
+        Some(offset)
+    }
+
+    /// Return the range at `offset`; false when it is not a live allocation
+    pub fn free(&mut self, offset: usize) -> bool {
+        let Some(len) = self.live.remove(&offset) else {
+            return false;
+        };
+        self.used -= len;
+        let slot = self.free.partition_point(|&(o, _)| o < offset);
+        self.free.insert(slot, (offset, len));
+        // Merge with the following, then the preceding range
+        if slot + 1 < self.free.len() && offset + len == self.free[slot + 1].0 {
+            self.free[slot].1 += self.free.remove(slot + 1).1;
+        }
+        if slot > 0 && self.free[slot - 1].0 + self.free[slot - 1].1 == offset {
+            self.free[slot - 1].1 += self.free.remove(slot).1;
+        }
+        true
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Largest single free range
+    pub fn largest_free(&self) -> usize {
+        self.free.iter().map(|&(_, len)| len).max().unwrap_or(0)
+    }
+
+    pub fn stats(&self) -> RegionStats {
+        let free = self.capacity - self.used;
+        RegionStats {
+            capacity_bytes: self.capacity,
+            used_bytes: self.used,
+            peak_bytes: self.peak,
+            allocations: self.live.len(),
+            fragmentation: if free == 0 { 0.0 } else { 1.0 - self.largest_free() as f64 / free as f64 },
+        }
+    }
+}
+
+/// One device reservation split into a trajectory and a workspace region.
+/// Sub-allocations are plain device pointers into the block and stay valid
+/// until they are freed or the pool is dropped.
+#[derive(Debug)]
+pub struct VramPool {
+    context: Arc<CudaContext>,
+    base: u64,
+    regions: BTreeMap<VramRegion, (usize, RegionAllocator)>,
+    peak: usize,
+}
+
+// The block is owned by `context`, which is bound before it is freed
+unsafe impl Send for VramPool {}
+
+impl VramPool {
+    /// Reserve `trajectory_bytes + workspace_bytes` (each rounded up to
+    /// [`POOL_ALIGNMENT`]) on the context's device
+    fn reserve(context: Arc<CudaContext>, trajectory_bytes: usize, workspace_bytes: usize) -> Result<Self, VramGuardError> {
+        let trajectory_bytes = trajectory_bytes.next_multiple_of(POOL_ALIGNMENT);
+        let workspace_bytes = workspace_bytes.next_multiple_of(POOL_ALIGNMENT);
+        let bytes = trajectory_bytes + workspace_bytes;
+        let failed = |reason: String| VramGuardError::ReservationFailed { bytes, reason };
+        context.bind_to_thread().map_err(|e| failed(format!("{:?}", e)))?;
+        let mut base: u64 = 0;
+        unsafe {
+            let result = cudarc::driver::sys::cuMemAlloc_v2(&mut base, bytes.max(1));
+            if result != cudarc::driver::sys::CUresult::CUDA_SUCCESS {
+                return Err(failed(format!("{:?}", result)));
+            }
+        }
+        let regions = BTreeMap::from([
+            (VramRegion::Trajectory, (0, RegionAllocator::new(trajectory_bytes))),
+            (VramRegion::Workspace, (trajectory_bytes, RegionAllocator::new(workspace_bytes))),
+        ]);
+        log::info!("🛡️  VRAM pool reserved: {}MB trajectory, {}MB workspace", trajectory_bytes / (1024 * 1024), workspace_bytes / (1024 * 1024));
+        Ok(Self { context, base, regions, peak: 0 })
+    }
+
+    /// Device pointer to `bytes` in `region`
+    pub fn allocate(&mut self, region: VramRegion, bytes: usize) -> Result<u64, VramGuardError> {
+        let (start, allocator) = self.regions.get_mut(&region).expect("every region is reserved");
+        let offset = allocator.allocate(bytes).ok_or_else(|| VramGuardError::PoolExhausted {
+            region,
+            requested: bytes,
+            free: allocator.stats().capacity_bytes - allocator.used(),
+            largest: allocator.largest_free(),
+        })?;
+        let pointer = self.base + (*start + offset) as u64;
+        self.peak = self.peak.max(self.used());
+        Ok(pointer)
+    }
+
+    /// Return a pointer obtained from [`Self::allocate`]; false when it is
+    /// not a live sub-allocation
+    pub fn free(&mut self, pointer: u64) -> bool {
+        let Some(offset) = pointer.checked_sub(self.base).map(|o| o as usize) else {
+            return false;
+        };
+        self.regions
+            .values_mut()
+            .rev()
+            .find(|(start, _)| offset >= *start)
+            .is_some_and(|(start, allocator)| allocator.free(offset - *start))
+    }
+
+    pub fn used(&self) -> usize {
+        self.regions.values().map(|(_, allocator)| allocator.used()).sum()
+    }
+
+    pub fn stats(&self) -> VramPoolStats {
+        let regions: BTreeMap<_, _> = self.regions.iter().map(|(&region, (_, allocator))| (region, allocator.stats())).collect();
+        VramPoolStats {
+            capacity_bytes: regions.values().map(|r| r.capacity_bytes).sum(),
+            used_bytes: self.used(),
+            peak_bytes: self.peak,
+            regions,
+        }
+    }
+}
+
+impl Drop for VramPool {
+    fn drop(&mut self) {
+        if self.context.bind_to_thread().is_ok() {
+            unsafe {
+                let _ = cudarc::driver::sys::cuMemFree_v2(self.base);
+            }
+        }
+    }
+}
+
 /// VRAM Safety Guard - Sovereign GPU memory protection
 #[derive(Debug)]
 pub struct VramGuard {
@@ -211,6 +441,17 @@ impl VramGuard {
         self.verify_allocation(total_required)
     }
 
+    /// Run the startup check, then reserve the approved budgets as a
+    /// [`VramPool`]
+    pub fn reserve_pool(
+        &self,
+        trajectory_size_bytes: usize,
+        workspace_size_bytes: usize,
+    ) -> Result<VramPool, VramGuardError> {
+        self.verify_physics_engine_startup(trajectory_size_bytes, workspace_size_bytes)?;
+        VramPool::reserve(self.context.clone(), trajectory_size_bytes, workspace_size_bytes)
+    }
+
     /// Emergency VRAM status for diagnostic reporting
     pub fn emergency_status(&self) -> String {
         match self.query_vram() {
@@ -326,4 +567,32 @@ mod tests {
         assert!(error_msg.contains("4000MB"));
         assert!(error_msg.contains("8000MB"));
     }
+
+    #[test]
+    fn test_region_allocator_tracks_peak_and_fragmentation() {
+        let mut region = RegionAllocator::new(4 * POOL_ALIGNMENT);
+        let a = region.allocate(100).unwrap();
+        let b = region.allocate(POOL_ALIGNMENT + 1).unwrap();
+        let c = region.allocate(POOL_ALIGNMENT).unwrap();
+        assert_eq!((a, b, c), (0, POOL_ALIGNMENT, 3 * POOL_ALIGNMENT));
+        assert_eq!(region.allocate(1), None);
+        assert_eq!(region.stats().peak_bytes, 4 * POOL_ALIGNMENT);
+
+        // Two separated holes: half the free space is unreachable in one piece
+        assert!(region.free(a));
+        assert!(region.free(c));
+        let stats = region.stats();
+        assert_eq!(stats.used_bytes, 2 * POOL_ALIGNMENT);
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.fragmentation, 0.5);
+        assert_eq!(region.allocate(2 * POOL_ALIGNMENT), None);
+
+        // Freeing the middle coalesces everything back into one range
+        assert!(region.free(b));
+        assert!(!region.free(b));
+        let stats = region.stats();
+        assert_eq!((stats.used_bytes, stats.fragmentation), (0, 0.0));
+        assert_eq!(stats.peak_bytes, 4 * POOL_ALIGNMENT);
+        assert_eq!(region.allocate(4 * POOL_ALIGNMENT), Some(0));
+    }
 }
\ No newline at end of file
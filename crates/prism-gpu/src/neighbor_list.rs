@@ -0,0 +1,354 @@
+//! GPU Neighbor List Module
+//!
+//! Verlet neighbor lists built from a cell list, so nonbonded evaluation
+//! scales linearly with the number of atoms.
+//!
+//! ASSUMPTIONS:
+//! - Positions are Float4 stride and already resident on the device
+//! - Open (non-periodic) boundaries; the grid spans the bounding box
+//! - Cell edge >= cutoff + skin, so only the 27 surrounding cells are scanned
+//! - Excluded pairs are dropped at build time (sorted CSR exclusion lists)
+//!
+//! ALGORITHM:
+//! 1. Bin atoms into cells and count occupancy (atomics)
+//! 2. Exclusive scan of cell counts on the host (one int per cell)
+//! 3. Scatter atom indices into cell segments, then sort each segment
+//! 4. Per atom, keep partners within `cutoff + skin`
+//! 5. Before each evaluation, rebuild only when some atom has moved more
+//!    than `skin / 2` since the last build
+//!
+//! Lists that overflow `max_neighbors` are rebuilt with a larger capacity,
+//! up to the neighbor count of [`COLLAPSE_DENSITY`]; beyond it the system
+//! has collapsed and the build fails with
+//! [`PrismError::NeighborListOverflow`].
+
+use anyhow::{Context, Result};
+use cudarc::driver::{CudaFunction, CudaSlice, CudaStream, LaunchConfig, PushKernelArg};
+use cudarc::nvrtc::Ptx;
+use prism_core::PrismError;
+use std::sync::Arc;
+
+/// Threads per block for the neighbor list kernels
+const BLOCK_SIZE: u32 = 128;
+
+/// Atom density (1/Å³) no physical system reaches, five times that of
+/// liquid water; lists needing more neighbors than this density puts in
+/// the list sphere are rejected
+pub const COLLAPSE_DENSITY: f32 = 0.5;
+
+/// Upper bound on grid cells, to keep sparse systems from exhausting memory
+const MAX_CELLS: usize = 1 << 22;
+
+/// Neighbor list settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NeighborListConfig {
+    /// Interaction cutoff (Å)
+    pub cutoff: f32,
+    /// Verlet skin added to the cutoff (Å)
+    pub skin: f32,
+    /// Initial per-atom list capacity (grown on overflow)
+    pub max_neighbors: usize,
+}
+
+impl Default for NeighborListConfig {
+    fn default() -> Self {
+        Self {
+            cutoff: 10.0,
+            skin: 2.0,
+            max_neighbors: 256,
+        }
+    }
+}
+
+/// Cell-list Verlet neighbor list resident on the GPU
+pub struct NeighborListGpu {
+    stream: Arc<CudaStream>,
+    count_kernel: CudaFunction,
+    scatter_kernel: CudaFunction,
+    sort_kernel: CudaFunction,
+    build_kernel: CudaFunction,
+    displacement_kernel: CudaFunction,
+    config: NeighborListConfig,
+    num_atoms: usize,
+    max_neighbors: usize,
+    builds: usize,
+    valid: bool,
+    d_cell_of_atom: CudaSlice<i32>,
+    d_cell_counts: CudaSlice<i32>,
+    d_cell_start: CudaSlice<i32>,
+    d_cell_cursor: CudaSlice<i32>,
+    d_cell_atoms: CudaSlice<i32>,
+    d_neighbors: CudaSlice<i32>,
+    d_neighbor_counts: CudaSlice<i32>,
+    d_reference: CudaSlice<f32>,
+    d_scalar: CudaSlice<i32>,
+}
+
+impl std::fmt::Debug for NeighborListGpu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NeighborListGpu")
+            .field("config", &self.config)
+            .field("num_atoms", &self.num_atoms)
+            .field("max_neighbors", &self.max_neighbors)
+            .field("builds", &self.builds)
+            .finish()
+    }
+}
+
+fn launch_1d(n: usize) -> LaunchConfig {
+    LaunchConfig {
+        grid_dim: ((n.max(1) as u32).div_ceil(BLOCK_SIZE), 1, 1),
+        block_dim: (BLOCK_SIZE, 1, 1),
+        shared_mem_bytes: 0,
+    }
+}
+
+impl NeighborListGpu {
+    /// Load the kernels and allocate per-atom buffers
+    pub fn new(stream: Arc<CudaStream>, num_atoms: usize, config: NeighborListConfig) -> Result<Self> {
+        anyhow::ensure!(num_atoms > 0, "Neighbor list needs at least one atom");
+        anyhow::ensure!(
+            config.cutoff > 0.0 && config.skin >= 0.0,
+            "Invalid neighbor list cutoff {} / skin {}",
+            config.cutoff,
+            config.skin
+        );
+
+        let ptx_src = include_str!("../target/ptx/neighbor_list.ptx");
+        let module = stream
+            .context()
+            .load_module(Ptx::from_src(ptx_src))
+            .context("Failed to load neighbor_list PTX module")?;
+        let load = |name: &str| {
+            module
+                .load_function(name)
+                .with_context(|| format!("Failed to load {} function", name))
+        };
+
+        let max_neighbors = config.max_neighbors.max(16);
+        Ok(Self {
+            count_kernel: load("nl_count_cells")?,
+            scatter_kernel: load("nl_scatter")?,
+            sort_kernel: load("nl_sort_cells")?,
+            build_kernel: load("nl_build")?,
+            displacement_kernel: load("nl_max_displacement")?,
+            config,
+            num_atoms,
+            max_neighbors,
+            builds: 0,
+            valid: false,
+            d_cell_of_atom: stream.alloc_zeros::<i32>(num_atoms)?,
+            d_cell_counts: stream.alloc_zeros::<i32>(1)?,
+            d_cell_start: stream.alloc_zeros::<i32>(2)?,
+            d_cell_cursor: stream.alloc_zeros::<i32>(1)?,
+            d_cell_atoms: stream.alloc_zeros::<i32>(num_atoms)?,
+            d_neighbors: stream.alloc_zeros::<i32>(num_atoms * max_neighbors)?,
+            d_neighbor_counts: stream.alloc_zeros::<i32>(num_atoms)?,
+            d_reference: stream.alloc_zeros::<f32>(num_atoms * 4)?,
+            d_scalar: stream.alloc_zeros::<i32>(1)?,
+            stream,
+        })
+    }
+
+    /// Settings the list was created with
+    pub fn config(&self) -> &NeighborListConfig {
+        &self.config
+    }
+
+    /// Current per-atom list capacity
+    pub fn max_neighbors(&self) -> usize {
+        self.max_neighbors
+    }
+
+    /// Number of rebuilds so far
+    pub fn builds(&self) -> usize {
+        self.builds
+    }
+
+    /// Row-major `num_atoms x max_neighbors` partner indices
+    pub fn neighbors(&self) -> &CudaSlice<i32> {
+        &self.d_neighbors
+    }
+
+    /// Number of valid entries in each atom's row
+    pub fn neighbor_counts(&self) -> &CudaSlice<i32> {
+        &self.d_neighbor_counts
+    }
+
+    /// Force a rebuild on the next [`Self::update`]
+    pub fn invalidate(&mut self) {
+        self.valid = false;
+    }
+
+    /// Rebuild the list if any atom moved more than half the skin since the
+    /// last build. `positions` is the host copy of `d_positions`, used for
+    /// the bounding box. Returns whether a rebuild happened.
+    pub fn update(
+        &mut self,
+        d_positions: &CudaSlice<f32>,
+        positions: &[f32],
+        excl_offsets: &CudaSlice<i32>,
+        excl_atoms: &CudaSlice<i32>,
+    ) -> Result<bool> {
+        if self.valid && self.max_displacement(d_positions)? <= 0.5 * self.config.skin {
+            return Ok(false);
+        }
+        self.build(d_positions, positions, excl_offsets, excl_atoms)?;
+        Ok(true)
+    }
+
+    fn max_displacement(&mut self, d_positions: &CudaSlice<f32>) -> Result<f32> {
+        let n = self.num_atoms as i32;
+        self.stream.memset_zeros(&mut self.d_scalar)?;
+        unsafe {
+            self.stream
+                .launch_builder(&self.displacement_kernel)
+                .arg(d_positions)
+                .arg(&self.d_reference)
+                .arg(&n)
+                .arg(&mut self.d_scalar)
+                .launch(launch_1d(self.num_atoms))
+                .context("nl_max_displacement launch failed")?;
+        }
+        let bits = self.stream.clone_dtoh(&self.d_scalar)?;
+        Ok(f32::from_bits(bits[0] as u32).sqrt())
+    }
+
+    fn build(
+        &mut self,
+        d_positions: &CudaSlice<f32>,
+        positions: &[f32],
+        excl_offsets: &CudaSlice<i32>,
+        excl_atoms: &CudaSlice<i32>,
+    ) -> Result<()> {
+        let n = self.num_atoms;
+        anyhow::ensure!(positions.len() == n * 4, "Expected Float4 positions for {} atoms", n);
+
+        // Grid over the bounding box
+        let mut lo = [f32::INFINITY; 3];
+        let mut hi = [f32::NEG_INFINITY; 3];
+        for p in positions.chunks_exact(4) {
+            for d in 0..3 {
+                lo[d] = lo[d].min(p[d]);
+                hi[d] = hi[d].max(p[d]);
+            }
+        }
+        anyhow::ensure!(
+            lo.iter().chain(&hi).all(|v| v.is_finite()),
+            "Non-finite coordinates in neighbor list build"
+        );
+        let mut cell = self.config.cutoff + self.config.skin;
+        let dims = loop {
+            let dims = [0, 1, 2].map(|d| (((hi[d] - lo[d]) / cell).floor() as usize + 1).max(1));
+            if dims.iter().product::<usize>() <= MAX_CELLS {
+                break dims;
+            }
+            cell *= 2.0;
+        };
+        let num_cells = dims.iter().product::<usize>();
+        if self.d_cell_counts.len() < num_cells {
+            self.d_cell_counts = self.stream.alloc_zeros::<i32>(num_cells)?;
+            self.d_cell_cursor = self.stream.alloc_zeros::<i32>(num_cells)?;
+            self.d_cell_start = self.stream.alloc_zeros::<i32>(num_cells + 1)?;
+        } else {
+            self.stream.memset_zeros(&mut self.d_cell_counts)?;
+        }
+
+        let (n_i, nx, ny, nz) = (n as i32, dims[0] as i32, dims[1] as i32, dims[2] as i32);
+        let inv_cell = 1.0 / cell;
+        unsafe {
+            self.stream
+                .launch_builder(&self.count_kernel)
+                .arg(d_positions)
+                .arg(&n_i)
+                .arg(&lo[0])
+                .arg(&lo[1])
+                .arg(&lo[2])
+                .arg(&inv_cell)
+                .arg(&nx)
+                .arg(&ny)
+                .arg(&nz)
+                .arg(&mut self.d_cell_of_atom)
+                .arg(&mut self.d_cell_counts)
+                .launch(launch_1d(n))
+                .context("nl_count_cells launch failed")?;
+        }
+
+        let counts = self.stream.clone_dtoh(&self.d_cell_counts.slice(0..num_cells))?;
+        let mut start = Vec::with_capacity(num_cells + 1);
+        start.push(0i32);
+        for c in &counts {
+            start.push(start[start.len() - 1] + c);
+        }
+        self.stream.memcpy_htod(&start, &mut self.d_cell_start)?;
+        self.stream.memcpy_htod(&start[..num_cells], &mut self.d_cell_cursor)?;
+
+        let num_cells_i = num_cells as i32;
+        unsafe {
+            self.stream
+                .launch_builder(&self.scatter_kernel)
+                .arg(&n_i)
+                .arg(&self.d_cell_of_atom)
+                .arg(&mut self.d_cell_cursor)
+                .arg(&mut self.d_cell_atoms)
+                .launch(launch_1d(n))
+                .context("nl_scatter launch failed")?;
+            self.stream
+                .launch_builder(&self.sort_kernel)
+                .arg(&num_cells_i)
+                .arg(&self.d_cell_start)
+                .arg(&mut self.d_cell_atoms)
+                .launch(launch_1d(num_cells))
+                .context("nl_sort_cells launch failed")?;
+        }
+
+        let list_cutoff = self.config.cutoff + self.config.skin;
+        let list_cutoff2 = list_cutoff * list_cutoff;
+        loop {
+            self.stream.memset_zeros(&mut self.d_scalar)?;
+            let max_neighbors = self.max_neighbors as i32;
+            unsafe {
+                self.stream
+                    .launch_builder(&self.build_kernel)
+                    .arg(d_positions)
+                    .arg(&n_i)
+                    .arg(&self.d_cell_of_atom)
+                    .arg(&self.d_cell_start)
+                    .arg(&self.d_cell_atoms)
+                    .arg(&nx)
+                    .arg(&ny)
+                    .arg(&nz)
+                    .arg(excl_offsets)
+                    .arg(excl_atoms)
+                    .arg(&list_cutoff2)
+                    .arg(&max_neighbors)
+                    .arg(&mut self.d_neighbors)
+                    .arg(&mut self.d_neighbor_counts)
+                    .arg(&mut self.d_scalar)
+                    .launch(launch_1d(n))
+                    .context("nl_build launch failed")?;
+            }
+            let overflow = self.stream.clone_dtoh(&self.d_scalar)?[0] as usize;
+            if overflow == 0 {
+                break;
+            }
+            let sphere = 4.0 / 3.0 * std::f32::consts::PI * list_cutoff.powi(3);
+            let ceiling = (COLLAPSE_DENSITY * sphere) as usize;
+            if overflow > ceiling {
+                return Err(PrismError::NeighborListOverflow {
+                    neighbors: overflow,
+                    capacity: ceiling,
+                }
+                .into());
+            }
+            self.max_neighbors = overflow.next_multiple_of(32);
+            log::debug!("Neighbor list overflow, growing capacity to {}", self.max_neighbors);
+            self.d_neighbors = self.stream.alloc_zeros::<i32>(n * self.max_neighbors)?;
+        }
+
+        self.stream.memcpy_dtod(d_positions, &mut self.d_reference)?;
+        self.builds += 1;
+        self.valid = true;
+        Ok(())
+    }
+}
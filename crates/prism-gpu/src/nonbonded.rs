@@ -0,0 +1,479 @@
+//! Nonbonded Force GPU Module
+//!
+//! Lennard-Jones 12-6 + Coulomb forces for the molecular dynamics engine.
+//!
+//! ASSUMPTIONS:
+//! - Positions and forces are Float4 stride (`[x, y, z, w]` per atom)
+//! - Pairs come from a GPU Verlet list when a skin is configured, otherwise
+//!   all pairs are scanned (O(N²) work)
+//! - Lorentz-Berthelot mixing unless an override exists for the type pair
+//! - Scaled 1-4 pairs are not evaluated here; the caller adds them on the host
+//! - With PME, Coulomb pairs use the Ewald real-space term and the mesh part
+//!   runs on the GPU ([`PmeGpu`]); exclusion and self corrections are left
+//!   to the caller. Periodic systems scan all pairs (the GPU list is open-boundary).
+//! - Units: Angstrom, kcal/mol, elementary charge
+//!
+//! Device buffers are allocated once in [`NonbondedGpu::new`]; each
+//! evaluation uploads positions (H2D), runs one kernel and copies forces and
+//! per-atom energies back (D2H).
+//!
+//! With [`NonbondedSystem::mixed_precision`] pairs are still evaluated in
+//! `f32`, but each atom's force, energies and pair virial are summed in
+//! `f64`, so energies of large systems do not pick up the rounding error of
+//! thousands of `f32` additions per atom.
+
+use anyhow::{Context, Result};
+use cudarc::driver::{CudaContext, CudaFunction, CudaSlice, CudaStream, LaunchConfig, PushKernelArg};
+use cudarc::nvrtc::Ptx;
+use std::sync::Arc;
+
+use crate::autotune::{self, KernelTuning};
+use crate::neighbor_list::{NeighborListConfig, NeighborListGpu};
+use crate::pme::{PmeGpu, PmeSystem};
+
+/// Default threads per block, also the all-pairs tile width (see
+/// [`NonbondedGpu::autotune`])
+pub const NONBONDED_BLOCK_SIZE: u32 = 128;
+
+/// Host-side description of a nonbonded system
+#[derive(Debug, Clone, Default)]
+pub struct NonbondedSystem {
+    /// Per-atom `[sigma, epsilon, charge, type_id]`
+    pub params: Vec<[f32; 4]>,
+    /// Excluded partners of each atom (any order, both directions)
+    pub exclusions: Vec<Vec<u32>>,
+    /// Row-major `num_types x num_types` `[sigma, epsilon]` overrides;
+    /// a negative epsilon selects the mixing rule
+    pub overrides: Vec<[f32; 2]>,
+    /// Number of atom types indexing `overrides` (0 = no overrides)
+    pub num_types: usize,
+    /// Cutoff distance (Å)
+    pub cutoff: f32,
+    /// Distance at which the switching function starts (Å), `None` = hard cutoff
+    pub switch_distance: Option<f32>,
+    /// Coulomb constant divided by the dielectric (kcal·Å/(mol·e²))
+    pub coulomb_scale: f32,
+    /// Verlet skin (Å); `None` evaluates all pairs without a neighbor list
+    pub neighbor_skin: Option<f32>,
+    /// Rectangular periodic box (Å), `None` = open boundaries
+    pub box_lengths: Option<[f32; 3]>,
+    /// Ewald splitting coefficient (1/Å) for real-space Coulomb, `None` = plain Coulomb
+    pub ewald_beta: Option<f32>,
+    /// Reciprocal-space PME grid, evaluated after the pair kernel
+    pub pme: Option<PmeSystem>,
+    /// Sum forces, energies and the pair virial in `f64`
+    pub mixed_precision: bool,
+}
+
+/// `f64` per-atom sums of the mixed-precision kernels
+struct MixedSums {
+    d_energies: CudaSlice<f64>,
+    /// Per atom `xx, yy, zz, xy, xz, yz`
+    d_virial: CudaSlice<f64>,
+    h_energies: Vec<f64>,
+    h_virial: Vec<f64>,
+}
+
+/// GPU nonbonded force evaluator with persistent device buffers
+pub struct NonbondedGpu {
+    device: Arc<CudaContext>,
+    stream: Arc<CudaStream>,
+    forces_kernel: CudaFunction,
+    neighbor_kernel: CudaFunction,
+    kernel_names: (&'static str, &'static str),
+    block_size: u32,
+    neighbor_list: Option<NeighborListGpu>,
+    pme: Option<PmeGpu>,
+    num_atoms: usize,
+    num_types: i32,
+    cutoff: f32,
+    switch_on: f32,
+    coulomb_scale: f32,
+    ewald_beta: f32,
+    box_lengths: [f32; 3],
+    d_positions: CudaSlice<f32>,
+    d_params: CudaSlice<f32>,
+    d_excl_offsets: CudaSlice<i32>,
+    d_excl_atoms: CudaSlice<i32>,
+    d_overrides: CudaSlice<f32>,
+    d_forces: CudaSlice<f32>,
+    d_energies: CudaSlice<f32>,
+    h_forces: Vec<f32>,
+    h_energies: Vec<f32>,
+    mixed: Option<MixedSums>,
+    /// Pair virial of the last mixed-precision evaluation
+    virial: Option<[[f64; 3]; 3]>,
+}
+
+impl std::fmt::Debug for NonbondedGpu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NonbondedGpu")
+            .field("num_atoms", &self.num_atoms)
+            .field("num_types", &self.num_types)
+            .field("cutoff", &self.cutoff)
+            .field("neighbor_list", &self.neighbor_list)
+            .field("pme", &self.pme)
+            .field("mixed_precision", &self.mixed.is_some())
+            .field("block_size", &self.block_size)
+            .finish()
+    }
+}
+
+impl NonbondedGpu {
+    /// Load the kernel and upload the static parameter tables
+    ///
+    /// # Errors
+    /// Returns error if the PTX module fails to load, the tables are
+    /// inconsistent, or device allocation fails.
+    pub fn new(device: Arc<CudaContext>, system: &NonbondedSystem) -> Result<Self> {
+        let n = system.params.len();
+        anyhow::ensure!(n > 0, "Nonbonded system has no atoms");
+        anyhow::ensure!(
+            system.exclusions.is_empty() || system.exclusions.len() == n,
+            "Exclusion lists cover {} atoms, expected {}",
+            system.exclusions.len(),
+            n
+        );
+        anyhow::ensure!(
+            system.overrides.len() == system.num_types * system.num_types,
+            "Override table has {} entries, expected {}",
+            system.overrides.len(),
+            system.num_types * system.num_types
+        );
+
+        let ptx_src = include_str!("../target/ptx/nonbonded_forces.ptx");
+        let module = device
+            .load_module(Ptx::from_src(ptx_src))
+            .context("Failed to load nonbonded_forces PTX module")?;
+        let (forces_name, neighbor_name) = if system.mixed_precision {
+            ("nonbonded_forces_mixed_kernel", "nonbonded_forces_neighbor_mixed_kernel")
+        } else {
+            ("nonbonded_forces_kernel", "nonbonded_forces_neighbor_kernel")
+        };
+        let forces_kernel = module
+            .load_function(forces_name)
+            .with_context(|| format!("Failed to load {} function", forces_name))?;
+        let neighbor_kernel = module
+            .load_function(neighbor_name)
+            .with_context(|| format!("Failed to load {} function", neighbor_name))?;
+        // A private stream keeps independent engines (e.g. replicas) from
+        // serializing on the default stream
+        let stream = device
+            .new_stream()
+            .context("Failed to create nonbonded stream")?;
+        let neighbor_list = match system.neighbor_skin {
+            Some(_) if system.box_lengths.is_some() => {
+                log::info!("Periodic box: GPU neighbor list disabled, scanning all pairs");
+                None
+            }
+            Some(skin) => Some(NeighborListGpu::new(
+                stream.clone(),
+                n,
+                NeighborListConfig { cutoff: system.cutoff, skin, ..Default::default() },
+            )?),
+            None => None,
+        };
+        let pme = system.pme.as_ref().map(|pme| PmeGpu::new(stream.clone(), pme)).transpose()?;
+
+        // CSR exclusion lists, sorted so the kernel can walk them with j
+        let mut excl_offsets = Vec::with_capacity(n + 1);
+        let mut excl_atoms = Vec::new();
+        excl_offsets.push(0i32);
+        for i in 0..n {
+            let mut partners: Vec<i32> = system
+                .exclusions
+                .get(i)
+                .map(|p| p.iter().map(|&j| j as i32).collect())
+                .unwrap_or_default();
+            partners.sort_unstable();
+            partners.dedup();
+            excl_atoms.extend(partners);
+            excl_offsets.push(excl_atoms.len() as i32);
+        }
+        // Zero-length device allocations are not allowed
+        if excl_atoms.is_empty() {
+            excl_atoms.push(-1);
+        }
+        let mut overrides: Vec<f32> = system.overrides.iter().flatten().copied().collect();
+        if overrides.is_empty() {
+            overrides.extend_from_slice(&[0.0, -1.0]);
+        }
+        let params: Vec<f32> = system.params.iter().flatten().copied().collect();
+
+        let d_params = stream.clone_htod(&params).context("Failed to upload nonbonded parameters")?;
+        let d_excl_offsets = stream.clone_htod(&excl_offsets).context("Failed to upload exclusion offsets")?;
+        let d_excl_atoms = stream.clone_htod(&excl_atoms).context("Failed to upload exclusion lists")?;
+        let d_overrides = stream.clone_htod(&overrides).context("Failed to upload LJ overrides")?;
+        let d_positions = stream.alloc_zeros::<f32>(n * 4).context("Failed to allocate positions")?;
+        let d_forces = stream.alloc_zeros::<f32>(n * 4).context("Failed to allocate forces")?;
+        let d_energies = stream.alloc_zeros::<f32>(n * 2).context("Failed to allocate energies")?;
+        let mixed = if system.mixed_precision {
+            Some(MixedSums {
+                d_energies: stream.alloc_zeros::<f64>(n * 2).context("Failed to allocate f64 energies")?,
+                d_virial: stream.alloc_zeros::<f64>(n * 6).context("Failed to allocate virial")?,
+                h_energies: vec![0.0; n * 2],
+                h_virial: vec![0.0; n * 6],
+            })
+        } else {
+            None
+        };
+
+        log::info!(
+            "Nonbonded GPU evaluator ready: {} atoms, {} exclusions, cutoff {:.1} Å{}",
+            n,
+            excl_offsets[n],
+            system.cutoff,
+            if system.mixed_precision { ", f64 accumulation" } else { "" }
+        );
+
+        Ok(Self {
+            device,
+            stream,
+            forces_kernel,
+            neighbor_kernel,
+            kernel_names: (forces_name, neighbor_name),
+            block_size: NONBONDED_BLOCK_SIZE,
+            neighbor_list,
+            pme,
+            num_atoms: n,
+            num_types: system.num_types as i32,
+            cutoff: system.cutoff,
+            switch_on: system.switch_distance.unwrap_or(system.cutoff),
+            coulomb_scale: system.coulomb_scale,
+            ewald_beta: system.ewald_beta.unwrap_or(0.0),
+            box_lengths: system.box_lengths.unwrap_or([0.0; 3]),
+            d_positions,
+            d_params,
+            d_excl_offsets,
+            d_excl_atoms,
+            d_overrides,
+            d_forces,
+            d_energies,
+            h_forces: vec![0.0; n * 4],
+            h_energies: vec![0.0; n * 2],
+            mixed,
+            virial: None,
+        })
+    }
+
+    /// Number of atoms the evaluator was built for
+    pub fn num_atoms(&self) -> usize {
+        self.num_atoms
+    }
+
+    /// Neighbor list, when a skin was configured
+    pub fn neighbor_list(&self) -> Option<&NeighborListGpu> {
+        self.neighbor_list.as_ref()
+    }
+
+    /// CUDA context the buffers live in
+    pub fn device(&self) -> &Arc<CudaContext> {
+        &self.device
+    }
+
+    /// Stream the evaluation runs on
+    pub fn stream(&self) -> &Arc<CudaStream> {
+        &self.stream
+    }
+
+    /// Name of the kernel [`Self::compute`] launches (the launch profile key)
+    pub fn active_kernel(&self) -> &'static str {
+        match self.neighbor_list {
+            Some(_) => self.kernel_names.1,
+            None => self.kernel_names.0,
+        }
+    }
+
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// Threads per block of the force kernel: a multiple of the 32-thread
+    /// warp, at most 1024
+    pub fn set_block_size(&mut self, block_size: u32) -> Result<()> {
+        anyhow::ensure!(
+            block_size > 0 && block_size % 32 == 0 && block_size <= 1024,
+            "Block size {} is not a multiple of 32 in 32..=1024",
+            block_size
+        );
+        self.block_size = block_size;
+        Ok(())
+    }
+
+    /// Benchmark the block sizes in `candidates` on `positions` (see
+    /// [`autotune::tune`]) and keep the fastest. Each trial is a full
+    /// [`Self::compute`], so transfers are included equally for every size.
+    pub fn autotune(&mut self, positions: &[f32], candidates: &[u32], repeats: usize) -> Result<KernelTuning> {
+        let mut scratch = vec![0.0f32; positions.len()];
+        let result = autotune::tune(candidates, repeats, |block| {
+            self.set_block_size(block)?;
+            let start = std::time::Instant::now();
+            self.compute(positions, &mut scratch)?;
+            Ok(start.elapsed().as_secs_f64())
+        });
+        let (block_size, seconds) = match result {
+            Ok(tuned) => tuned,
+            Err(e) => {
+                self.block_size = NONBONDED_BLOCK_SIZE;
+                return Err(e);
+            }
+        };
+        self.block_size = block_size;
+        log::info!("{}: block size {} ({:.1} µs per evaluation)", self.active_kernel(), block_size, seconds * 1e6);
+        Ok(KernelTuning { block_size, micros: seconds * 1e6, num_atoms: self.num_atoms })
+    }
+
+    /// Pair virial `Σ f/r · d ⊗ d` of the home atoms in the last evaluation
+    /// (kcal/mol), same convention as the host virial. Only accumulated
+    /// with [`NonbondedSystem::mixed_precision`]; the PME mesh term is not
+    /// included.
+    pub fn virial(&self) -> Option<[[f64; 3]; 3]> {
+        self.virial
+    }
+
+    /// Approximate device memory of an evaluator for `system` (bytes),
+    /// for checks with [`crate::VramGuard`] before construction
+    pub fn device_bytes(system: &NonbondedSystem) -> usize {
+        let n = system.params.len();
+        let exclusions: usize = system.exclusions.iter().map(Vec::len).sum();
+        let mut per_atom = 4 * (4 + 4 + 4 + 2 + 1);
+        if system.mixed_precision {
+            per_atom += 8 * (2 + 6);
+        }
+        let neighbors = match system.neighbor_skin {
+            Some(_) if system.box_lengths.is_none() => n * NeighborListConfig::default().max_neighbors * 4,
+            _ => 0,
+        };
+        n * per_atom + exclusions * 4 + system.overrides.len() * 8 + neighbors
+    }
+
+    /// Evaluate forces for Float4-stride `positions`, adding them into
+    /// `forces` (kcal/mol/Å)
+    ///
+    /// # Returns
+    /// `(lennard_jones, coulomb)` energies in kcal/mol; with PME the Coulomb
+    /// energy includes the reciprocal-space term
+    pub fn compute(&mut self, positions: &[f32], forces: &mut [f32]) -> Result<(f64, f64)> {
+        self.compute_home(positions, forces, self.num_atoms)
+    }
+
+    /// Like [`Self::compute`], but only the first `num_home` atoms receive
+    /// forces (`forces` holds `num_home` Float4 entries) and contribute
+    /// energy. The remaining atoms act as a halo whose own interactions are
+    /// accounted for elsewhere; since every atom's energy is half of its pair
+    /// energies, summing the home atoms of all domains counts each pair once.
+    /// The PME mesh term covers the whole system and is only valid when all
+    /// atoms are home.
+    pub fn compute_home(&mut self, positions: &[f32], forces: &mut [f32], num_home: usize) -> Result<(f64, f64)> {
+        let n = self.num_atoms;
+        anyhow::ensure!(
+            positions.len() == n * 4 && forces.len() == num_home * 4 && num_home <= n,
+            "Expected Float4 buffers for {} atoms ({} home), got {} positions and {} forces",
+            n,
+            num_home,
+            positions.len(),
+            forces.len()
+        );
+
+        self.stream
+            .memcpy_htod(positions, &mut self.d_positions)
+            .context("Failed to upload positions")?;
+
+        let launch_config = LaunchConfig {
+            grid_dim: ((n as u32).div_ceil(self.block_size), 1, 1),
+            block_dim: (self.block_size, 1, 1),
+            // The all-pairs kernel stages positions and parameters of one tile
+            shared_mem_bytes: if self.neighbor_list.is_some() { 0 } else { 2 * 16 * self.block_size },
+        };
+        let num_atoms = n as i32;
+        let [box_x, box_y, box_z] = self.box_lengths;
+        let max_neighbors: i32;
+        let kernel = self.active_kernel();
+        let mut launch = if let Some(list) = &mut self.neighbor_list {
+            list.update(&self.d_positions, positions, &self.d_excl_offsets, &self.d_excl_atoms)?;
+            max_neighbors = list.max_neighbors() as i32;
+            let mut launch = self.stream.launch_builder(&self.neighbor_kernel);
+            launch
+                .arg(&self.d_positions)
+                .arg(&self.d_params)
+                .arg(list.neighbors())
+                .arg(list.neighbor_counts())
+                .arg(&max_neighbors);
+            launch
+        } else {
+            let mut launch = self.stream.launch_builder(&self.forces_kernel);
+            launch
+                .arg(&self.d_positions)
+                .arg(&self.d_params)
+                .arg(&self.d_excl_offsets)
+                .arg(&self.d_excl_atoms);
+            launch
+        };
+        launch
+            .arg(&self.d_overrides)
+            .arg(&self.num_types)
+            .arg(&num_atoms)
+            .arg(&self.cutoff)
+            .arg(&self.switch_on)
+            .arg(&self.coulomb_scale)
+            .arg(&self.ewald_beta)
+            .arg(&box_x)
+            .arg(&box_y)
+            .arg(&box_z)
+            .arg(&mut self.d_forces);
+        match &mut self.mixed {
+            Some(sums) => launch.arg(&mut sums.d_energies).arg(&mut sums.d_virial),
+            None => launch.arg(&mut self.d_energies),
+        };
+        unsafe { launch.launch(launch_config) }.with_context(|| format!("{} launch failed", kernel))?;
+
+        let reciprocal = match &mut self.pme {
+            Some(pme) => pme.compute(&self.d_positions, &self.d_params, n, &mut self.d_forces)?,
+            None => 0.0,
+        };
+
+        self.stream
+            .memcpy_dtoh(&self.d_forces, &mut self.h_forces)
+            .context("Failed to download forces")?;
+        match &mut self.mixed {
+            Some(sums) => {
+                self.stream
+                    .memcpy_dtoh(&sums.d_energies, &mut sums.h_energies)
+                    .context("Failed to download energies")?;
+                self.stream
+                    .memcpy_dtoh(&sums.d_virial, &mut sums.h_virial)
+                    .context("Failed to download virial")?;
+            }
+            None => self
+                .stream
+                .memcpy_dtoh(&self.d_energies, &mut self.h_energies)
+                .context("Failed to download energies")?,
+        }
+        self.stream.synchronize().context("Nonbonded synchronization failed")?;
+
+        for (f, g) in forces.iter_mut().zip(&self.h_forces) {
+            *f += g;
+        }
+        let (lj, coulomb) = match &self.mixed {
+            Some(sums) => {
+                let mut virial = [[0.0f64; 3]; 3];
+                for w in sums.h_virial[..num_home * 6].chunks_exact(6) {
+                    for (k, &(a, b)) in [(0, 0), (1, 1), (2, 2), (0, 1), (0, 2), (1, 2)].iter().enumerate() {
+                        virial[a][b] += w[k];
+                        if a != b {
+                            virial[b][a] += w[k];
+                        }
+                    }
+                }
+                self.virial = Some(virial);
+                sums.h_energies[..num_home * 2]
+                    .chunks_exact(2)
+                    .fold((0.0f64, 0.0f64), |(lj, c), e| (lj + e[0], c + e[1]))
+            }
+            None => self.h_energies[..num_home * 2]
+                .chunks_exact(2)
+                .fold((0.0f64, 0.0f64), |(lj, c), e| (lj + e[0] as f64, c + e[1] as f64)),
+        };
+        Ok((lj, coulomb + reciprocal))
+    }
+}
@@ -0,0 +1,292 @@
+//! Particle Mesh Ewald GPU Module
+//!
+//! Reciprocal-space PME electrostatics with cuFFT.
+//!
+//! ASSUMPTIONS:
+//! - Rectangular periodic box, fixed for the lifetime of the solver
+//! - Positions and parameters are the Float4 device buffers of
+//!   [`crate::nonbonded::NonbondedGpu`] (charge in `params[i].z`)
+//! - The influence function is precomputed on the host for the
+//!   `nx * ny * (nz / 2 + 1)` half grid of the real-to-complex transform
+//! - Units: Angstrom, kcal/mol, elementary charge
+//!
+//! Each evaluation spreads charges onto the grid, runs a forward R2C FFT,
+//! multiplies by the influence function (accumulating the energy), runs the
+//! inverse C2R FFT and interpolates forces back to the atoms. Real-space,
+//! exclusion and self terms are handled by the caller.
+
+use anyhow::{Context, Result};
+use cudarc::cufft::{result as cufft, sys as cufft_sys};
+use cudarc::driver::{
+    CudaFunction, CudaSlice, CudaStream, DevicePtrMut, LaunchConfig, PushKernelArg,
+};
+use cudarc::nvrtc::Ptx;
+use std::sync::Arc;
+
+/// Threads per block (must match `PME_BLOCK` in the kernel)
+const PME_BLOCK_SIZE: u32 = 256;
+
+/// Highest supported B-spline order (must match `PME_MAX_ORDER`)
+pub const PME_MAX_ORDER: usize = 8;
+
+/// Host-side description of the PME grid
+#[derive(Debug, Clone, Default)]
+pub struct PmeSystem {
+    /// Grid dimensions `[nx, ny, nz]`
+    pub dims: [usize; 3],
+    /// B-spline interpolation order
+    pub order: usize,
+    /// Box edge lengths (Å)
+    pub box_lengths: [f32; 3],
+    /// Influence function on the `nx * ny * (nz / 2 + 1)` half grid,
+    /// including the Coulomb constant
+    pub influence: Vec<f32>,
+}
+
+impl PmeSystem {
+    /// Number of complex entries of the real-to-complex transform
+    pub fn half_grid_len(&self) -> usize {
+        self.dims[0] * self.dims[1] * (self.dims[2] / 2 + 1)
+    }
+}
+
+/// cuFFT-backed reciprocal-space solver with persistent grids
+pub struct PmeGpu {
+    stream: Arc<CudaStream>,
+    spread_kernel: CudaFunction,
+    convolve_kernel: CudaFunction,
+    gather_kernel: CudaFunction,
+    dims: [i32; 3],
+    order: i32,
+    box_lengths: [f32; 3],
+    plan_forward: cufft_sys::cufftHandle,
+    plan_inverse: cufft_sys::cufftHandle,
+    d_grid: CudaSlice<f32>,
+    d_transform: CudaSlice<f32>,
+    d_influence: CudaSlice<f32>,
+    d_energy: CudaSlice<f64>,
+    h_energy: Vec<f64>,
+}
+
+impl std::fmt::Debug for PmeGpu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PmeGpu")
+            .field("dims", &self.dims)
+            .field("order", &self.order)
+            .field("box_lengths", &self.box_lengths)
+            .finish()
+    }
+}
+
+impl PmeGpu {
+    /// Load the kernels, create the cuFFT plans and upload the influence function
+    ///
+    /// # Errors
+    /// Returns error if the grid is inconsistent, the PTX module fails to
+    /// load, or plan creation / device allocation fails.
+    pub fn new(stream: Arc<CudaStream>, system: &PmeSystem) -> Result<Self> {
+        let [nx, ny, nz] = system.dims;
+        anyhow::ensure!(
+            (3..=PME_MAX_ORDER).contains(&system.order),
+            "PME spline order {} outside 3..={}",
+            system.order,
+            PME_MAX_ORDER
+        );
+        anyhow::ensure!(
+            system.dims.iter().all(|&k| k >= system.order),
+            "PME grid {:?} smaller than the spline order {}",
+            system.dims,
+            system.order
+        );
+        anyhow::ensure!(
+            system.influence.len() == system.half_grid_len(),
+            "Influence function has {} entries, expected {}",
+            system.influence.len(),
+            system.half_grid_len()
+        );
+
+        let ptx_src = include_str!("../target/ptx/pme.ptx");
+        let module = stream
+            .context()
+            .load_module(Ptx::from_src(ptx_src))
+            .context("Failed to load pme PTX module")?;
+        let load = |name: &str| {
+            module
+                .load_function(name)
+                .with_context(|| format!("Failed to load {} function", name))
+        };
+
+        stream
+            .context()
+            .bind_to_thread()
+            .context("Failed to bind CUDA context")?;
+        let plan = |kind| -> Result<cufft_sys::cufftHandle> {
+            let plan = cufft::plan_3d(nx as i32, ny as i32, nz as i32, kind)
+                .context("cuFFT plan creation failed")?;
+            unsafe { cufft::set_stream(plan, stream.cu_stream() as _) }
+                .context("cuFFT set_stream failed")?;
+            Ok(plan)
+        };
+        let plan_forward = plan(cufft_sys::cufftType::CUFFT_R2C)?;
+        let plan_inverse = plan(cufft_sys::cufftType::CUFFT_C2R)?;
+
+        log::info!(
+            "PME grid {}x{}x{} (order {}) on GPU",
+            nx,
+            ny,
+            nz,
+            system.order
+        );
+
+        Ok(Self {
+            spread_kernel: load("pme_spread")?,
+            convolve_kernel: load("pme_convolve")?,
+            gather_kernel: load("pme_gather")?,
+            dims: [nx as i32, ny as i32, nz as i32],
+            order: system.order as i32,
+            box_lengths: system.box_lengths,
+            plan_forward,
+            plan_inverse,
+            d_grid: stream
+                .alloc_zeros::<f32>(nx * ny * nz)
+                .context("Failed to allocate PME grid")?,
+            d_transform: stream
+                .alloc_zeros::<f32>(2 * system.half_grid_len())
+                .context("Failed to allocate PME transform")?,
+            d_influence: stream
+                .clone_htod(&system.influence)
+                .context("Failed to upload influence function")?,
+            d_energy: stream
+                .alloc_zeros::<f64>(1)
+                .context("Failed to allocate PME energy")?,
+            h_energy: vec![0.0],
+            stream,
+        })
+    }
+
+    /// Grid dimensions
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims.map(|k| k as usize)
+    }
+
+    /// Reciprocal-space energy (kcal/mol), adding forces into `d_forces`
+    ///
+    /// `d_positions`, `d_params` and `d_forces` are Float4 device buffers for
+    /// `num_atoms` atoms on the same stream.
+    pub fn compute(
+        &mut self,
+        d_positions: &CudaSlice<f32>,
+        d_params: &CudaSlice<f32>,
+        num_atoms: usize,
+        d_forces: &mut CudaSlice<f32>,
+    ) -> Result<f64> {
+        let [nx, ny, nz] = self.dims;
+        let [lx, ly, lz] = self.box_lengths;
+        let n = num_atoms as i32;
+        let atoms_config = LaunchConfig {
+            grid_dim: ((num_atoms.max(1) as u32).div_ceil(PME_BLOCK_SIZE), 1, 1),
+            block_dim: (PME_BLOCK_SIZE, 1, 1),
+            shared_mem_bytes: 0,
+        };
+
+        self.stream
+            .memset_zeros(&mut self.d_grid)
+            .context("Failed to clear PME grid")?;
+        self.stream
+            .memset_zeros(&mut self.d_energy)
+            .context("Failed to clear PME energy")?;
+        unsafe {
+            self.stream
+                .launch_builder(&self.spread_kernel)
+                .arg(d_positions)
+                .arg(d_params)
+                .arg(&n)
+                .arg(&nx)
+                .arg(&ny)
+                .arg(&nz)
+                .arg(&lx)
+                .arg(&ly)
+                .arg(&lz)
+                .arg(&self.order)
+                .arg(&mut self.d_grid)
+                .launch(atoms_config)
+                .context("pme_spread launch failed")?;
+        }
+
+        {
+            let (grid, _grid_sync) = self.d_grid.device_ptr_mut(&self.stream);
+            let (transform, _transform_sync) = self.d_transform.device_ptr_mut(&self.stream);
+            unsafe {
+                cufft::exec_r2c(
+                    self.plan_forward,
+                    grid as *mut f32,
+                    transform as *mut cufft_sys::cufftComplex,
+                )
+            }
+            .context("PME forward FFT failed")?;
+        }
+
+        let count = self.d_influence.len() as i32;
+        unsafe {
+            self.stream
+                .launch_builder(&self.convolve_kernel)
+                .arg(&mut self.d_transform)
+                .arg(&self.d_influence)
+                .arg(&count)
+                .arg(&nz)
+                .arg(&mut self.d_energy)
+                .launch(LaunchConfig {
+                    grid_dim: ((count as u32).div_ceil(PME_BLOCK_SIZE), 1, 1),
+                    block_dim: (PME_BLOCK_SIZE, 1, 1),
+                    shared_mem_bytes: 0,
+                })
+                .context("pme_convolve launch failed")?;
+        }
+
+        {
+            let (transform, _transform_sync) = self.d_transform.device_ptr_mut(&self.stream);
+            let (grid, _grid_sync) = self.d_grid.device_ptr_mut(&self.stream);
+            unsafe {
+                cufft::exec_c2r(
+                    self.plan_inverse,
+                    transform as *mut cufft_sys::cufftComplex,
+                    grid as *mut f32,
+                )
+            }
+            .context("PME inverse FFT failed")?;
+        }
+
+        unsafe {
+            self.stream
+                .launch_builder(&self.gather_kernel)
+                .arg(d_positions)
+                .arg(d_params)
+                .arg(&self.d_grid)
+                .arg(&n)
+                .arg(&nx)
+                .arg(&ny)
+                .arg(&nz)
+                .arg(&lx)
+                .arg(&ly)
+                .arg(&lz)
+                .arg(&self.order)
+                .arg(d_forces)
+                .launch(atoms_config)
+                .context("pme_gather launch failed")?;
+        }
+
+        self.stream
+            .memcpy_dtoh(&self.d_energy, &mut self.h_energy)
+            .context("Failed to download PME energy")?;
+        Ok(self.h_energy[0])
+    }
+}
+
+impl Drop for PmeGpu {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = cufft::destroy(self.plan_forward);
+            let _ = cufft::destroy(self.plan_inverse);
+        }
+    }
+}
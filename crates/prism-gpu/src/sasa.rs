@@ -0,0 +1,128 @@
+//! GPU Solvent Accessible Surface Area Module
+//!
+//! Shrake-Rupley SASA with one thread block per atom.
+//!
+//! ASSUMPTIONS:
+//! - Positions are Float4 stride (`[x, y, z, w]` per atom)
+//! - Radii passed to [`SasaGpu::new`] already include the probe radius
+//! - Test points are unit vectors shared by every atom
+//! - Units: Angstrom
+//!
+//! Radii and points are uploaded once; each evaluation uploads positions,
+//! runs one kernel and downloads the exposed fraction per atom.
+
+use anyhow::{Context, Result};
+use cudarc::driver::{CudaContext, CudaFunction, CudaSlice, CudaStream, LaunchConfig, PushKernelArg};
+use cudarc::nvrtc::Ptx;
+use std::sync::Arc;
+
+/// Threads per block (must match `SASA_BLOCK` in the kernel)
+pub const SASA_BLOCK_SIZE: u32 = 128;
+
+/// GPU Shrake-Rupley evaluator with persistent device buffers
+pub struct SasaGpu {
+    stream: Arc<CudaStream>,
+    kernel: CudaFunction,
+    num_atoms: usize,
+    num_points: usize,
+    d_positions: CudaSlice<f32>,
+    d_radii: CudaSlice<f32>,
+    d_points: CudaSlice<f32>,
+    d_exposed: CudaSlice<f32>,
+    h_exposed: Vec<f32>,
+}
+
+impl std::fmt::Debug for SasaGpu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SasaGpu")
+            .field("num_atoms", &self.num_atoms)
+            .field("num_points", &self.num_points)
+            .finish()
+    }
+}
+
+impl SasaGpu {
+    /// Load the kernel and upload the expanded radii and unit test points
+    ///
+    /// # Errors
+    /// Returns error if the PTX module fails to load, the inputs are empty,
+    /// or device allocation fails.
+    pub fn new(device: Arc<CudaContext>, radii: &[f32], points: &[[f32; 3]]) -> Result<Self> {
+        let n = radii.len();
+        anyhow::ensure!(n > 0 && !points.is_empty(), "SASA needs atoms and test points");
+
+        let ptx_src = include_str!("../target/ptx/sasa.ptx");
+        let module = device
+            .load_module(Ptx::from_src(ptx_src))
+            .context("Failed to load sasa PTX module")?;
+        let kernel = module
+            .load_function("sasa_kernel")
+            .context("Failed to load sasa_kernel function")?;
+        let stream = device.new_stream().context("Failed to create SASA stream")?;
+
+        let flat_points: Vec<f32> = points.iter().flatten().copied().collect();
+        let d_radii = stream.clone_htod(radii).context("Failed to upload SASA radii")?;
+        let d_points = stream.clone_htod(&flat_points).context("Failed to upload SASA points")?;
+        let d_positions = stream.alloc_zeros::<f32>(n * 4).context("Failed to allocate positions")?;
+        let d_exposed = stream.alloc_zeros::<f32>(n).context("Failed to allocate SASA output")?;
+
+        log::info!("SASA GPU evaluator ready: {} atoms, {} points per sphere", n, points.len());
+
+        Ok(Self {
+            stream,
+            kernel,
+            num_atoms: n,
+            num_points: points.len(),
+            d_positions,
+            d_radii,
+            d_points,
+            d_exposed,
+            h_exposed: vec![0.0; n],
+        })
+    }
+
+    /// Number of atoms the evaluator was built for
+    pub fn num_atoms(&self) -> usize {
+        self.num_atoms
+    }
+
+    /// Exposed fraction of each atom's test points for Float4-stride
+    /// `positions`
+    pub fn compute(&mut self, positions: &[f32]) -> Result<&[f32]> {
+        let n = self.num_atoms;
+        anyhow::ensure!(
+            positions.len() == n * 4,
+            "Expected Float4 positions for {} atoms, got {} values",
+            n,
+            positions.len()
+        );
+        self.stream
+            .memcpy_htod(positions, &mut self.d_positions)
+            .context("Failed to upload positions")?;
+
+        let launch_config = LaunchConfig {
+            grid_dim: (n as u32, 1, 1),
+            block_dim: (SASA_BLOCK_SIZE, 1, 1),
+            shared_mem_bytes: 0,
+        };
+        let num_points = self.num_points as i32;
+        let num_atoms = n as i32;
+        unsafe {
+            self.stream
+                .launch_builder(&self.kernel)
+                .arg(&self.d_positions)
+                .arg(&self.d_radii)
+                .arg(&self.d_points)
+                .arg(&num_points)
+                .arg(&num_atoms)
+                .arg(&mut self.d_exposed)
+                .launch(launch_config)
+                .context("sasa_kernel launch failed")?;
+        }
+        self.stream
+            .memcpy_dtoh(&self.d_exposed, &mut self.h_exposed)
+            .context("Failed to download SASA")?;
+        self.stream.synchronize().context("SASA synchronization failed")?;
+        Ok(&self.h_exposed)
+    }
+}
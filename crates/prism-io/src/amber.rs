@@ -0,0 +1,636 @@
+//! # AMBER Topology Import (prmtop / inpcrd)
+//!
+//! Reads AMBER7 `prmtop` parameter/topology files and ASCII `inpcrd`/`rst7`
+//! coordinate files into the internal [`Topology`] representation, so systems
+//! prepared with AmberTools (tleap) can be run directly.
+//!
+//! ## Notes
+//! - `%FORMAT` fields are read with their fixed column widths
+//! - Charges are stored in AMBER internal units and divided by 18.2223
+//! - Per-atom LJ parameters come from the diagonal of the A/B coefficient tables
+
+use crate::simulation_box::SimulationBox;
+use crate::sovereign_types::Atom;
+use crate::topology::{
+    HarmonicAngle, HarmonicBond, LjParams, Pair14, PeriodicDihedral, Topology,
+};
+use crate::{PrismIoError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// AMBER internal charge unit: q_amber = q_e * 18.2223
+pub const AMBER_CHARGE_SCALE: f32 = 18.2223;
+
+/// AMBER restart velocities are in Å per 1/20.455 ps
+pub const AMBER_VELOCITY_SCALE: f32 = 20.455;
+
+/// Default 1-4 electrostatic scaling divisor (SCEE)
+const DEFAULT_SCEE: f32 = 1.2;
+/// Default 1-4 van der Waals scaling divisor (SCNB)
+const DEFAULT_SCNB: f32 = 2.0;
+
+/// Parsed `%FLAG` sections of a prmtop file
+struct PrmtopSections {
+    sections: HashMap<String, Vec<String>>,
+}
+
+impl PrmtopSections {
+    fn parse(content: &str) -> Result<Self> {
+        let mut sections = HashMap::new();
+        let mut current: Option<(String, usize)> = None;
+        let mut values: Vec<String> = Vec::new();
+
+        for line in content.lines() {
+            if let Some(flag) = line.strip_prefix("%FLAG") {
+                if let Some((name, _)) = current.take() {
+                    sections.insert(name, std::mem::take(&mut values));
+                }
+                current = Some((flag.trim().to_string(), 0));
+            } else if let Some(fmt) = line.strip_prefix("%FORMAT") {
+                let width = Self::field_width(fmt)?;
+                if let Some((_, w)) = current.as_mut() {
+                    *w = width;
+                }
+            } else if line.starts_with('%') {
+                continue;
+            } else if let Some((_, width)) = current.as_ref() {
+                if *width == 0 {
+                    return Err(PrismIoError::FormatError(
+                        "prmtop data line before %FORMAT".to_string(),
+                    ));
+                }
+                let mut start = 0;
+                while start < line.len() {
+                    let end = (start + width).min(line.len());
+                    let field = line.get(start..end).unwrap_or("").trim();
+                    if !field.is_empty() {
+                        values.push(field.to_string());
+                    }
+                    start = end;
+                }
+            }
+        }
+        if let Some((name, _)) = current.take() {
+            sections.insert(name, values);
+        }
+        Ok(Self { sections })
+    }
+
+    /// Extract the column width from a Fortran format such as `(10I8)` or `(5E16.8)`
+    fn field_width(fmt: &str) -> Result<usize> {
+        let inner = fmt.trim().trim_start_matches('(').trim_end_matches(')');
+        let pos = inner
+            .find(|c: char| c.is_ascii_alphabetic())
+            .ok_or_else(|| PrismIoError::FormatError(format!("Bad %FORMAT: {}", fmt)))?;
+        let width: String = inner[pos + 1..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        width
+            .parse()
+            .map_err(|_| PrismIoError::FormatError(format!("Bad %FORMAT width: {}", fmt)))
+    }
+
+    fn strings(&self, flag: &str) -> Result<&[String]> {
+        self.sections
+            .get(flag)
+            .map(|v| v.as_slice())
+            .ok_or_else(|| PrismIoError::FormatError(format!("prmtop missing %FLAG {}", flag)))
+    }
+
+    fn ints(&self, flag: &str) -> Result<Vec<i64>> {
+        self.strings(flag)?
+            .iter()
+            .map(|s| {
+                s.parse().map_err(|_| {
+                    PrismIoError::FormatError(format!("Invalid integer '{}' in {}", s, flag))
+                })
+            })
+            .collect()
+    }
+
+    fn floats(&self, flag: &str) -> Result<Vec<f32>> {
+        self.strings(flag)?
+            .iter()
+            .map(|s| {
+                s.replace(['D', 'd'], "E").parse().map_err(|_| {
+                    PrismIoError::FormatError(format!("Invalid real '{}' in {}", s, flag))
+                })
+            })
+            .collect()
+    }
+
+    fn optional_floats(&self, flag: &str) -> Result<Option<Vec<f32>>> {
+        if self.sections.contains_key(flag) {
+            self.floats(flag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Indices into the POINTERS section
+mod pointers {
+    pub const NATOM: usize = 0;
+    pub const NTYPES: usize = 1;
+    pub const NRES: usize = 11;
+    pub const IFBOX: usize = 27;
+}
+
+/// Read an AMBER prmtop file
+pub fn read_prmtop<P: AsRef<Path>>(path: P) -> Result<Topology> {
+    let content = std::fs::read_to_string(path)?;
+    parse_prmtop(&content)
+}
+
+/// Parse prmtop content into a [`Topology`] (coordinates zeroed)
+pub fn parse_prmtop(content: &str) -> Result<Topology> {
+    let s = PrmtopSections::parse(content)?;
+    let ptrs = s.ints("POINTERS")?;
+    if ptrs.len() < 12 {
+        return Err(PrismIoError::FormatError("POINTERS section truncated".to_string()));
+    }
+    let natom = ptrs[pointers::NATOM] as usize;
+    let ntypes = ptrs[pointers::NTYPES] as usize;
+    let nres = ptrs[pointers::NRES] as usize;
+
+    let atom_names = s.strings("ATOM_NAME")?.to_vec();
+    let charges = s.floats("CHARGE")?;
+    let masses = s.floats("MASS")?;
+    let type_index = s.ints("ATOM_TYPE_INDEX")?;
+    let nb_index = s.ints("NONBONDED_PARM_INDEX")?;
+    let acoef = s.floats("LENNARD_JONES_ACOEF")?;
+    let bcoef = s.floats("LENNARD_JONES_BCOEF")?;
+    let residue_names = s.strings("RESIDUE_LABEL")?.to_vec();
+    let residue_ptr = s.ints("RESIDUE_POINTER")?;
+    let atomic_numbers = s.ints("ATOMIC_NUMBER").ok();
+    let atom_types = s
+        .strings("AMBER_ATOM_TYPE")
+        .map(|v| v.to_vec())
+        .unwrap_or_else(|_| vec![String::new(); natom]);
+
+    for (name, len) in [
+        ("ATOM_NAME", atom_names.len()),
+        ("CHARGE", charges.len()),
+        ("MASS", masses.len()),
+        ("ATOM_TYPE_INDEX", type_index.len()),
+    ] {
+        if len != natom {
+            return Err(PrismIoError::FormatError(format!(
+                "{} has {} entries, expected NATOM={}",
+                name, len, natom
+            )));
+        }
+    }
+    if residue_ptr.len() != nres || residue_names.len() != nres {
+        return Err(PrismIoError::FormatError(format!(
+            "Residue tables disagree with NRES={}",
+            nres
+        )));
+    }
+    if nres > u16::MAX as usize + 1 {
+        return Err(PrismIoError::FormatError(format!(
+            "{} residues exceeds the 16-bit residue index",
+            nres
+        )));
+    }
+
+    // Per-type LJ from the diagonal of the pair tables
+    let mut type_lj = Vec::with_capacity(ntypes);
+    for t in 0..ntypes {
+        let idx = nb_index
+            .get(ntypes * t + t)
+            .copied()
+            .ok_or_else(|| PrismIoError::FormatError("NONBONDED_PARM_INDEX truncated".to_string()))?;
+        let (a, b) = if idx > 0 {
+            let k = (idx - 1) as usize;
+            (
+                acoef.get(k).copied().unwrap_or(0.0),
+                bcoef.get(k).copied().unwrap_or(0.0),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        type_lj.push(if a > 0.0 && b > 0.0 {
+            LjParams {
+                sigma: (a / b).powf(1.0 / 6.0),
+                epsilon: b * b / (4.0 * a),
+            }
+        } else {
+            LjParams { sigma: 0.0, epsilon: 0.0 }
+        });
+    }
+
+    let mut atoms = Vec::with_capacity(natom);
+    let mut lj = Vec::with_capacity(natom);
+    let mut residue = 0usize;
+    for i in 0..natom {
+        while residue + 1 < nres && (residue_ptr[residue + 1] - 1) as usize <= i {
+            residue += 1;
+        }
+        let invalid = || {
+            PrismIoError::FormatError(format!(
+                "Atom {} has invalid type index {}",
+                i, type_index[i]
+            ))
+        };
+        let t = usize::try_from(type_index[i] - 1).map_err(|_| invalid())?;
+        let params = type_lj.get(t).copied().ok_or_else(invalid)?;
+        let element = atomic_numbers
+            .as_ref()
+            .and_then(|z| z.get(i))
+            .filter(|&&z| z > 0)
+            .map(|&z| z as u8)
+            .unwrap_or_else(|| Topology::element_from_mass(masses[i]));
+        atoms.push(Atom {
+            coords: [0.0; 3],
+            element,
+            residue_id: residue as u16,
+            atom_type: t.min(u8::MAX as usize) as u8,
+            charge: charges[i] / AMBER_CHARGE_SCALE,
+            radius: if params.sigma > 0.0 { params.rmin_half() } else { 1.0 },
+            _reserved: [0; 4],
+        });
+        lj.push(params);
+    }
+
+    let mut topology = Topology {
+        atoms,
+        atom_names,
+        atom_types,
+        masses,
+        lj,
+        residue_names,
+        ..Default::default()
+    };
+
+    read_bonds(&s, &mut topology, natom)?;
+    read_angles(&s, &mut topology, natom)?;
+    read_dihedrals(&s, &mut topology, natom)?;
+    read_exclusions(&s, &mut topology, natom)?;
+
+    if ptrs.get(pointers::IFBOX).copied().unwrap_or(0) > 0 {
+        if let Some(b) = s.optional_floats("BOX_DIMENSIONS")? {
+            if b.len() >= 4 {
+                // BOX_DIMENSIONS is [β, a, b, c]; α = γ = 90° in prmtop boxes
+                topology.simulation_box =
+                    SimulationBox::from_parameters([b[1], b[2], b[3], 90.0, b[0], 90.0]).ok();
+            }
+        }
+    }
+
+    Ok(topology)
+}
+
+/// prmtop stores atom indices as 3*(i) coordinate offsets (the sign is a
+/// flag on dihedral entries)
+fn coord_index(v: i64, natom: usize) -> Result<u32> {
+    let offset = v.unsigned_abs();
+    if offset % 3 != 0 || offset / 3 >= natom as u64 {
+        return Err(PrismIoError::FormatError(format!(
+            "Atom coordinate index {} is not an offset into {} atoms",
+            v, natom
+        )));
+    }
+    Ok((offset / 3) as u32)
+}
+
+/// 1-based entry `type_id` of a parameter table
+fn parameter<T: Copy>(table: &[T], type_id: i64, what: &str) -> Result<T> {
+    usize::try_from(type_id - 1)
+        .ok()
+        .and_then(|k| table.get(k))
+        .copied()
+        .ok_or_else(|| PrismIoError::FormatError(format!("{} type {} out of range", what, type_id)))
+}
+
+fn read_bonds(s: &PrmtopSections, top: &mut Topology, natom: usize) -> Result<()> {
+    let k = s.floats("BOND_FORCE_CONSTANT")?;
+    let r0 = s.floats("BOND_EQUIL_VALUE")?;
+    for flag in ["BONDS_INC_HYDROGEN", "BONDS_WITHOUT_HYDROGEN"] {
+        for chunk in s.ints(flag)?.chunks_exact(3) {
+            top.bonds.push(HarmonicBond {
+                i: coord_index(chunk[0], natom)?,
+                j: coord_index(chunk[1], natom)?,
+                k: parameter(&k, chunk[2], "Bond")?,
+                r0: parameter(&r0, chunk[2], "Bond")?,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn read_angles(s: &PrmtopSections, top: &mut Topology, natom: usize) -> Result<()> {
+    let k = s.floats("ANGLE_FORCE_CONSTANT")?;
+    let theta0 = s.floats("ANGLE_EQUIL_VALUE")?;
+    for flag in ["ANGLES_INC_HYDROGEN", "ANGLES_WITHOUT_HYDROGEN"] {
+        for chunk in s.ints(flag)?.chunks_exact(4) {
+            top.angles.push(HarmonicAngle {
+                i: coord_index(chunk[0], natom)?,
+                j: coord_index(chunk[1], natom)?,
+                k: coord_index(chunk[2], natom)?,
+                force_constant: parameter(&k, chunk[3], "Angle")?,
+                theta0: parameter(&theta0, chunk[3], "Angle")?,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn read_dihedrals(s: &PrmtopSections, top: &mut Topology, natom: usize) -> Result<()> {
+    let k = s.floats("DIHEDRAL_FORCE_CONSTANT")?;
+    let n = s.floats("DIHEDRAL_PERIODICITY")?;
+    let phase = s.floats("DIHEDRAL_PHASE")?;
+    let scee = s.optional_floats("SCEE_SCALE_FACTOR")?;
+    let scnb = s.optional_floats("SCNB_SCALE_FACTOR")?;
+
+    for flag in ["DIHEDRALS_INC_HYDROGEN", "DIHEDRALS_WITHOUT_HYDROGEN"] {
+        for chunk in s.ints(flag)?.chunks_exact(5) {
+            let atoms = [
+                coord_index(chunk[0], natom)?,
+                coord_index(chunk[1], natom)?,
+                coord_index(chunk[2], natom)?,
+                coord_index(chunk[3], natom)?,
+            ];
+            let t = chunk[4];
+            top.dihedrals.push(PeriodicDihedral {
+                atoms,
+                k: parameter(&k, t, "Dihedral")?,
+                periodicity: parameter(&n, t, "Dihedral")?,
+                phase: parameter(&phase, t, "Dihedral")?,
+                improper: chunk[3] < 0,
+            });
+
+            // Negative third index: 1-4 pair already counted (multi-term or ring)
+            if chunk[2] >= 0 && chunk[3] >= 0 {
+                let ee = scee
+                    .as_ref()
+                    .map(|v| parameter(v, t, "SCEE"))
+                    .transpose()?
+                    .unwrap_or(DEFAULT_SCEE);
+                let nb = scnb
+                    .as_ref()
+                    .map(|v| parameter(v, t, "SCNB"))
+                    .transpose()?
+                    .unwrap_or(DEFAULT_SCNB);
+                top.pairs14.push(Pair14 {
+                    i: atoms[0],
+                    j: atoms[3],
+                    coulomb_scale: if ee > 0.0 { 1.0 / ee } else { 0.0 },
+                    lj_scale: if nb > 0.0 { 1.0 / nb } else { 0.0 },
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_exclusions(s: &PrmtopSections, top: &mut Topology, natom: usize) -> Result<()> {
+    let counts = s.ints("NUMBER_EXCLUDED_ATOMS")?;
+    let list = s.ints("EXCLUDED_ATOMS_LIST")?;
+    if counts.len() != natom {
+        return Err(PrismIoError::FormatError(
+            "NUMBER_EXCLUDED_ATOMS length does not match NATOM".to_string(),
+        ));
+    }
+    let mut offset = 0usize;
+    for (i, &count) in counts.iter().enumerate() {
+        let count = count.max(0) as usize;
+        let entries = list.get(offset..offset + count).ok_or_else(|| {
+            PrismIoError::FormatError("EXCLUDED_ATOMS_LIST truncated".to_string())
+        })?;
+        for &j in entries {
+            // A single 0 entry is the placeholder for "no exclusions"
+            if j > 0 {
+                let j = (j - 1) as u32;
+                top.exclusions.push(((i as u32).min(j), (i as u32).max(j)));
+            }
+        }
+        offset += count;
+    }
+    top.exclusions.sort_unstable();
+    top.exclusions.dedup();
+    Ok(())
+}
+
+/// Coordinates (and optional velocities / box) from an inpcrd/rst7 file
+#[derive(Debug, Clone, Default)]
+pub struct InpcrdData {
+    /// Flat `[x0, y0, z0, x1, ...]` coordinates (Å)
+    pub coordinates: Vec<f32>,
+    /// Flat velocities (Å/ps, converted from the AMBER unit), if present
+    pub velocities: Option<Vec<f32>>,
+    /// Periodic cell, if present
+    pub simulation_box: Option<SimulationBox>,
+    /// Simulation time (ps), if present
+    pub time: Option<f64>,
+}
+
+/// Read an ASCII inpcrd/rst7 file
+pub fn read_inpcrd<P: AsRef<Path>>(path: P) -> Result<InpcrdData> {
+    let content = std::fs::read_to_string(path)?;
+    parse_inpcrd(&content)
+}
+
+/// Parse ASCII inpcrd/rst7 content (6F12.7 records)
+pub fn parse_inpcrd(content: &str) -> Result<InpcrdData> {
+    let mut lines = content.lines();
+    let _title = lines.next();
+    let header = lines
+        .next()
+        .ok_or_else(|| PrismIoError::FormatError("inpcrd missing atom count".to_string()))?;
+    let mut header_fields = header.split_whitespace();
+    let natom: usize = header_fields
+        .next()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| PrismIoError::FormatError(format!("Invalid inpcrd header: {}", header)))?;
+    let time = header_fields.next().and_then(|v| v.parse().ok());
+
+    // Each record holds up to six 12-column values; coordinates and
+    // velocities each occupy ceil(3N/6) lines, the box is a single line.
+    let records: Vec<Vec<f32>> = lines
+        .filter(|l| !l.trim().is_empty())
+        .map(parse_record)
+        .collect::<Result<_>>()?;
+    let per_block = (natom * 3).div_ceil(6);
+    if records.len() < per_block {
+        return Err(PrismIoError::FormatError(format!(
+            "inpcrd has {} coordinate lines, expected {}",
+            records.len(),
+            per_block
+        )));
+    }
+
+    let flatten = |block: &[Vec<f32>]| -> Result<Vec<f32>> {
+        let v: Vec<f32> = block.iter().flatten().copied().collect();
+        if v.len() != natom * 3 {
+            return Err(PrismIoError::FormatError(format!(
+                "inpcrd block has {} values, expected {}",
+                v.len(),
+                natom * 3
+            )));
+        }
+        Ok(v)
+    };
+    let coordinates = flatten(&records[..per_block])?;
+    let rest = &records[per_block..];
+    // A lone trailing line is always the box (ambiguous only when N <= 2)
+    let has_velocities = rest.len() > per_block || (rest.len() == per_block && per_block > 1);
+    let (velocities, rest) = if has_velocities {
+        let velocities = flatten(&rest[..per_block])?
+            .into_iter()
+            .map(|v| v * AMBER_VELOCITY_SCALE)
+            .collect();
+        (Some(velocities), &rest[per_block..])
+    } else {
+        (None, rest)
+    };
+    // Box line: a, b, c and optionally α, β, γ (degrees)
+    let simulation_box = match rest.first().map(|b| b.as_slice()) {
+        Some([a, b, c, alpha, beta, gamma, ..]) => {
+            Some(SimulationBox::from_parameters([*a, *b, *c, *alpha, *beta, *gamma])?)
+        }
+        Some([a, b, c, ..]) => Some(SimulationBox::orthorhombic([*a, *b, *c])),
+        _ => None,
+    };
+
+    Ok(InpcrdData { coordinates, velocities, simulation_box, time })
+}
+
+fn parse_record(line: &str) -> Result<Vec<f32>> {
+    let mut values = Vec::with_capacity(6);
+    let mut start = 0;
+    while start < line.len() {
+        let end = (start + 12).min(line.len());
+        let field = line.get(start..end).unwrap_or("").trim();
+        if !field.is_empty() {
+            values.push(field.parse::<f32>().map_err(|_| {
+                PrismIoError::FormatError(format!("Invalid inpcrd value '{}'", field))
+            })?);
+        }
+        start = end;
+    }
+    Ok(values)
+}
+
+/// Load a prmtop/inpcrd pair into a fully-populated [`Topology`]
+pub fn load_amber<P: AsRef<Path>, Q: AsRef<Path>>(prmtop: P, inpcrd: Q) -> Result<Topology> {
+    let mut topology = read_prmtop(prmtop)?;
+    let crd = read_inpcrd(inpcrd)?;
+    topology.set_coordinates(&crd.coordinates)?;
+    if crd.simulation_box.is_some() {
+        topology.simulation_box = crd.simulation_box;
+    }
+    Ok(topology)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal two-atom prmtop (one bond, no angles/dihedrals)
+    const PRMTOP: &str = "%VERSION  VERSION_STAMP = V0001.000\n\
+%FLAG TITLE\n%FORMAT(20a4)\nTEST\n\
+%FLAG POINTERS\n%FORMAT(10I8)\n\
+       2       1       0       1       0       0       0       0       0       0\n\
+       3       1       1       0       0       0       0       0       0       0\n\
+       0       0       0       0       0       0       0       0       0       0\n\
+       0       0\n\
+%FLAG ATOM_NAME\n%FORMAT(20a4)\nC1  C2  \n\
+%FLAG CHARGE\n%FORMAT(5E16.8)\n  3.64446000E+00 -3.64446000E+00\n\
+%FLAG ATOMIC_NUMBER\n%FORMAT(10I8)\n       6       6\n\
+%FLAG MASS\n%FORMAT(5E16.8)\n  1.20100000E+01  1.20100000E+01\n\
+%FLAG ATOM_TYPE_INDEX\n%FORMAT(10I8)\n       1       1\n\
+%FLAG NUMBER_EXCLUDED_ATOMS\n%FORMAT(10I8)\n       1       1\n\
+%FLAG NONBONDED_PARM_INDEX\n%FORMAT(10I8)\n       1\n\
+%FLAG RESIDUE_LABEL\n%FORMAT(20a4)\nETH \n\
+%FLAG RESIDUE_POINTER\n%FORMAT(10I8)\n       1\n\
+%FLAG BOND_FORCE_CONSTANT\n%FORMAT(5E16.8)\n  3.10000000E+02\n\
+%FLAG BOND_EQUIL_VALUE\n%FORMAT(5E16.8)\n  1.52600000E+00\n\
+%FLAG ANGLE_FORCE_CONSTANT\n%FORMAT(5E16.8)\n\n\
+%FLAG ANGLE_EQUIL_VALUE\n%FORMAT(5E16.8)\n\n\
+%FLAG DIHEDRAL_FORCE_CONSTANT\n%FORMAT(5E16.8)\n\n\
+%FLAG DIHEDRAL_PERIODICITY\n%FORMAT(5E16.8)\n\n\
+%FLAG DIHEDRAL_PHASE\n%FORMAT(5E16.8)\n\n\
+%FLAG LENNARD_JONES_ACOEF\n%FORMAT(5E16.8)\n  1.04308023E+06\n\
+%FLAG LENNARD_JONES_BCOEF\n%FORMAT(5E16.8)\n  6.75612247E+02\n\
+%FLAG BONDS_INC_HYDROGEN\n%FORMAT(10I8)\n\n\
+%FLAG BONDS_WITHOUT_HYDROGEN\n%FORMAT(10I8)\n       0       3       1\n\
+%FLAG ANGLES_INC_HYDROGEN\n%FORMAT(10I8)\n\n\
+%FLAG ANGLES_WITHOUT_HYDROGEN\n%FORMAT(10I8)\n\n\
+%FLAG DIHEDRALS_INC_HYDROGEN\n%FORMAT(10I8)\n\n\
+%FLAG DIHEDRALS_WITHOUT_HYDROGEN\n%FORMAT(10I8)\n\n\
+%FLAG EXCLUDED_ATOMS_LIST\n%FORMAT(10I8)\n       2       0\n\
+%FLAG AMBER_ATOM_TYPE\n%FORMAT(20a4)\nCT  CT  \n";
+
+    #[test]
+    fn test_parse_prmtop() {
+        let top = parse_prmtop(PRMTOP).unwrap();
+        assert_eq!(top.num_atoms(), 2);
+        assert_eq!(top.atom_names, vec!["C1", "C2"]);
+        assert_eq!(top.residue_names, vec!["ETH"]);
+        assert!((top.atoms[0].charge - 0.2).abs() < 1e-4);
+        assert_eq!(top.bonds.len(), 1);
+        assert_eq!((top.bonds[0].i, top.bonds[0].j), (0, 1));
+        assert!((top.bonds[0].r0 - 1.526).abs() < 1e-5);
+        assert_eq!(top.exclusions, vec![(0, 1)]);
+        // CT: Rmin/2 = 1.908 Å, eps = 0.1094 kcal/mol
+        assert!((top.lj[0].rmin_half() - 1.908).abs() < 1e-3);
+        assert!((top.lj[0].epsilon - 0.1094).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_out_of_range_atom_index_rejected() {
+        // Offset 6 is atom 3 of a two-atom system; 4 is not an atom offset
+        for bond in ["       0       6       1", "       0       4       1"] {
+            let prmtop = PRMTOP.replace("       0       3       1", bond);
+            assert!(matches!(
+                parse_prmtop(&prmtop),
+                Err(PrismIoError::FormatError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_zero_type_index_rejected() {
+        let bond = PRMTOP.replace("       0       3       1", "       0       3       0");
+        let atom_type = PRMTOP.replace(
+            "ATOM_TYPE_INDEX\n%FORMAT(10I8)\n       1",
+            "ATOM_TYPE_INDEX\n%FORMAT(10I8)\n       0",
+        );
+        assert_ne!(atom_type, PRMTOP);
+        for prmtop in [bond, atom_type] {
+            assert!(matches!(
+                parse_prmtop(&prmtop),
+                Err(PrismIoError::FormatError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_inpcrd_with_box() {
+        let crd = "TEST\n    2\n   0.0000000   0.0000000   0.0000000   1.5260000   0.0000000   0.0000000\n  30.0000000  30.0000000  30.0000000  90.0000000  90.0000000  90.0000000\n";
+        let data = parse_inpcrd(crd).unwrap();
+        assert_eq!(data.coordinates.len(), 6);
+        assert!((data.coordinates[3] - 1.526).abs() < 1e-6);
+        assert!(data.velocities.is_none());
+        assert_eq!(
+            data.simulation_box,
+            Some(SimulationBox::orthorhombic([30.0, 30.0, 30.0]))
+        );
+
+        let mut top = parse_prmtop(PRMTOP).unwrap();
+        top.set_coordinates(&data.coordinates).unwrap();
+        assert_eq!(top.atoms[1].coords, [1.526, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_restart_velocities_in_angstrom_per_ps() {
+        let rst = "TEST\n    2  10.0000000\n   0.0000000   0.0000000   0.0000000   1.5260000   0.0000000   0.0000000\n   0.1000000   0.0000000   0.0000000  -0.2000000   0.0000000   0.0000000\n  30.0000000  30.0000000  30.0000000\n";
+        let data = parse_inpcrd(rst).unwrap();
+        let velocities = data.velocities.unwrap();
+        assert!((velocities[0] - 2.0455).abs() < 1e-5);
+        assert!((velocities[3] + 4.091).abs() < 1e-5);
+        assert_eq!(data.time, Some(10.0));
+    }
+}
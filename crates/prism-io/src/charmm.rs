@@ -0,0 +1,626 @@
+//! # CHARMM Topology Import (PSF + par/top)
+//!
+//! Reads CHARMM/X-PLOR PSF connectivity together with CHARMM parameter
+//! (`.prm`/`.par`/`.str`) and topology (`.rtf`/`.top`) files, resolving every
+//! bonded term against the parameter set so CHARMM-GUI-prepared systems can be
+//! simulated directly.
+//!
+//! ## Notes
+//! - Angles and dihedrals in parameter files are in degrees and converted to radians
+//! - Nonbonded entries use `epsilon` (negative) and `Rmin/2`; NBFIX uses `Emin` and full `Rmin`
+//! - Dihedral lookup honours `X` wildcards; impropers try exact, then wildcard forms
+//! - Urey-Bradley 1-3 terms are appended as harmonic bonds after exclusions are built
+//! - CMAP and HBOND sections are ignored
+
+use crate::sovereign_types::Atom;
+use crate::topology::{
+    HarmonicAngle, HarmonicBond, HarmonicImproper, LjParams, NbFix, Pair14, PeriodicDihedral,
+    Topology,
+};
+use crate::{PrismIoError, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Atom record from a PSF file
+#[derive(Debug, Clone, PartialEq)]
+pub struct PsfAtom {
+    /// Segment identifier
+    pub segid: String,
+    /// Residue number within the segment
+    pub resid: String,
+    /// Residue name
+    pub resname: String,
+    /// Atom name
+    pub name: String,
+    /// CHARMM atom type
+    pub atom_type: String,
+    /// Partial charge (e)
+    pub charge: f32,
+    /// Mass (amu)
+    pub mass: f32,
+}
+
+/// Connectivity read from a PSF file (0-based indices)
+#[derive(Debug, Clone, Default)]
+pub struct PsfStructure {
+    /// Atom records
+    pub atoms: Vec<PsfAtom>,
+    /// Bonded pairs
+    pub bonds: Vec<[u32; 2]>,
+    /// Angle triples
+    pub angles: Vec<[u32; 3]>,
+    /// Proper dihedral quadruples
+    pub dihedrals: Vec<[u32; 4]>,
+    /// Improper quadruples
+    pub impropers: Vec<[u32; 4]>,
+}
+
+/// Read a PSF file
+pub fn read_psf<P: AsRef<Path>>(path: P) -> Result<PsfStructure> {
+    parse_psf(&std::fs::read_to_string(path)?)
+}
+
+/// Parse PSF content (standard and EXT layouts)
+pub fn parse_psf(content: &str) -> Result<PsfStructure> {
+    let mut lines = content.lines();
+    match lines.next() {
+        Some(l) if l.trim_start().starts_with("PSF") => {}
+        _ => return Err(PrismIoError::FormatError("Missing PSF header".to_string())),
+    }
+
+    let mut psf = PsfStructure::default();
+    while let Some(line) = lines.next() {
+        let Some((count, tag)) = section_header(line) else { continue };
+        match tag.as_str() {
+            "NATOM" => {
+                for _ in 0..count {
+                    let l = lines.next().ok_or_else(|| {
+                        PrismIoError::FormatError("PSF atom section truncated".to_string())
+                    })?;
+                    psf.atoms.push(parse_psf_atom(l)?);
+                }
+            }
+            "NBOND" | "NTHETA" | "NPHI" | "NIMPHI" => {
+                let width = match tag.as_str() {
+                    "NBOND" => 2,
+                    "NTHETA" => 3,
+                    _ => 4,
+                };
+                let mut indices = Vec::with_capacity(count * width);
+                while indices.len() < count * width {
+                    let l = lines.next().ok_or_else(|| {
+                        PrismIoError::FormatError(format!("PSF {} section truncated", tag))
+                    })?;
+                    for tok in l.split_whitespace() {
+                        let v: i64 = tok.parse().map_err(|_| {
+                            PrismIoError::FormatError(format!("Invalid index '{}' in {}", tok, tag))
+                        })?;
+                        if v < 1 || v as usize > psf.atoms.len() {
+                            return Err(PrismIoError::FormatError(format!(
+                                "{} references atom {} outside 1..={}",
+                                tag,
+                                v,
+                                psf.atoms.len()
+                            )));
+                        }
+                        indices.push((v - 1) as u32);
+                    }
+                }
+                match tag.as_str() {
+                    "NBOND" => psf.bonds = indices.chunks_exact(2).map(|c| [c[0], c[1]]).collect(),
+                    "NTHETA" => {
+                        psf.angles = indices.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect()
+                    }
+                    "NPHI" => {
+                        psf.dihedrals =
+                            indices.chunks_exact(4).map(|c| [c[0], c[1], c[2], c[3]]).collect()
+                    }
+                    _ => {
+                        psf.impropers =
+                            indices.chunks_exact(4).map(|c| [c[0], c[1], c[2], c[3]]).collect()
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if psf.atoms.is_empty() {
+        return Err(PrismIoError::FormatError("PSF contains no atoms".to_string()));
+    }
+    Ok(psf)
+}
+
+/// Parse `"   123 !NATOM"` style section headers into (count, tag)
+fn section_header(line: &str) -> Option<(usize, String)> {
+    let (count, rest) = line.split_once('!')?;
+    let count = count.split_whitespace().next()?.parse().ok()?;
+    let tag = rest.split(|c: char| c == ':' || c.is_whitespace()).next()?;
+    Some((count, tag.to_string()))
+}
+
+fn parse_psf_atom(line: &str) -> Result<PsfAtom> {
+    let f: Vec<&str> = line.split_whitespace().collect();
+    if f.len() < 8 {
+        return Err(PrismIoError::FormatError(format!("Malformed PSF atom line: {}", line)));
+    }
+    let num = |s: &str| -> Result<f32> {
+        s.parse()
+            .map_err(|_| PrismIoError::FormatError(format!("Invalid number '{}' in: {}", s, line)))
+    };
+    Ok(PsfAtom {
+        segid: f[1].to_string(),
+        resid: f[2].to_string(),
+        resname: f[3].to_string(),
+        name: f[4].to_string(),
+        atom_type: f[5].to_string(),
+        charge: num(f[6])?,
+        mass: num(f[7])?,
+    })
+}
+
+/// Angle parameters: (k, theta0, optional Urey-Bradley (k_ub, s0))
+type AngleParams = (f32, f32, Option<(f32, f32)>);
+
+/// CHARMM parameter set accumulated from one or more par/top/str files
+#[derive(Debug, Clone, Default)]
+pub struct CharmmParameters {
+    bonds: HashMap<(String, String), (f32, f32)>,
+    angles: HashMap<(String, String, String), AngleParams>,
+    dihedrals: Vec<([String; 4], f32, f32, f32)>,
+    impropers: Vec<([String; 4], f32, f32)>,
+    nonbonded: HashMap<String, (LjParams, Option<LjParams>)>,
+    nbfix: Vec<NbFix>,
+    elements: HashMap<String, String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ParamSection {
+    None,
+    Atoms,
+    Bonds,
+    Angles,
+    Dihedrals,
+    Impropers,
+    Nonbonded,
+    Nbfix,
+    Skip,
+    Residue,
+}
+
+impl CharmmParameters {
+    /// Create an empty parameter set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a parameter (`.prm`/`.par`) or stream (`.str`) file
+    pub fn read_parameters<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.parse_parameters(&std::fs::read_to_string(path)?)
+    }
+
+    /// Read a topology (`.rtf`/`.top`) file; only `MASS` records are used
+    pub fn read_topology<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.parse_parameters(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse parameter or topology content into this set
+    pub fn parse_parameters(&mut self, content: &str) -> Result<()> {
+        let mut section = ParamSection::None;
+        let mut continuation = false;
+
+        for raw in content.lines() {
+            let line = raw.split('!').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            // Skip continuation lines of multi-line section headers
+            if continuation {
+                continuation = line.ends_with('-');
+                continue;
+            }
+            let f: Vec<&str> = line.split_whitespace().collect();
+            let keyword = f[0].to_ascii_uppercase();
+
+            if keyword == "MASS" && f.len() >= 4 {
+                // MASS <index> <type> <mass> [element]
+                if let Some(el) = f.get(4) {
+                    self.elements.insert(f[2].to_string(), el.to_string());
+                }
+                continue;
+            }
+
+            // RTF residue blocks reuse BOND/ANGL keywords; ignore them until END
+            if section == ParamSection::Residue && !matches!(keyword.as_str(), "END" | "RETURN") {
+                continue;
+            }
+
+            let next = match keyword.get(..4).unwrap_or(&keyword) {
+                "ATOM" if f.len() == 1 => Some(ParamSection::Atoms),
+                "BOND" => Some(ParamSection::Bonds),
+                "ANGL" | "THET" => Some(ParamSection::Angles),
+                "DIHE" | "PHI" => Some(ParamSection::Dihedrals),
+                "IMPR" | "IMPH" => Some(ParamSection::Impropers),
+                "NONB" | "NBON" => Some(ParamSection::Nonbonded),
+                "NBFI" => Some(ParamSection::Nbfix),
+                "CMAP" | "HBON" => Some(ParamSection::Skip),
+                "RESI" | "PRES" => Some(ParamSection::Residue),
+                "END" | "RETU" => Some(ParamSection::None),
+                _ => None,
+            };
+            if let Some(next) = next {
+                section = next;
+                continuation = line.ends_with('-');
+                continue;
+            }
+
+            match section {
+                ParamSection::Bonds if f.len() >= 4 => {
+                    let (k, r0) = (num(f[2], raw)?, num(f[3], raw)?);
+                    self.bonds.insert(ordered2(f[0], f[1]), (k, r0));
+                }
+                ParamSection::Angles if f.len() >= 5 => {
+                    let k = num(f[3], raw)?;
+                    let theta0 = num(f[4], raw)?.to_radians();
+                    let ub = if f.len() >= 7 {
+                        Some((num(f[5], raw)?, num(f[6], raw)?))
+                    } else {
+                        None
+                    };
+                    let key = if f[0] <= f[2] {
+                        (f[0].to_string(), f[1].to_string(), f[2].to_string())
+                    } else {
+                        (f[2].to_string(), f[1].to_string(), f[0].to_string())
+                    };
+                    self.angles.insert(key, (k, theta0, ub));
+                }
+                ParamSection::Dihedrals if f.len() >= 7 => {
+                    let types = [f[0], f[1], f[2], f[3]].map(String::from);
+                    let (k, n, delta) = (num(f[4], raw)?, num(f[5], raw)?, num(f[6], raw)?);
+                    // A repeated type quadruple in a later file replaces earlier terms
+                    if self.dihedrals.last().is_none_or(|d| d.0 != types) {
+                        self.dihedrals.retain(|d| d.0 != types);
+                    }
+                    self.dihedrals.push((types, k, n, delta.to_radians()));
+                }
+                ParamSection::Impropers if f.len() >= 7 => {
+                    let types = [f[0], f[1], f[2], f[3]].map(String::from);
+                    let (k, psi0) = (num(f[4], raw)?, num(f[6], raw)?);
+                    self.impropers.retain(|d| d.0 != types);
+                    self.impropers.push((types, k, psi0.to_radians()));
+                }
+                ParamSection::Nonbonded if f.len() >= 4 => {
+                    let lj = LjParams::from_rmin_half(num(f[3], raw)?, num(f[2], raw)?);
+                    let lj14 = if f.len() >= 7 {
+                        Some(LjParams::from_rmin_half(num(f[6], raw)?, num(f[5], raw)?))
+                    } else {
+                        None
+                    };
+                    self.nonbonded.insert(f[0].to_string(), (lj, lj14));
+                }
+                ParamSection::Nbfix if f.len() >= 4 => {
+                    let emin = num(f[2], raw)?;
+                    let rmin = num(f[3], raw)?;
+                    self.nbfix.push(NbFix {
+                        type_a: f[0].to_string(),
+                        type_b: f[1].to_string(),
+                        params: LjParams::from_rmin_half(0.5 * rmin, emin),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn bond(&self, a: &str, b: &str) -> Option<(f32, f32)> {
+        self.bonds.get(&ordered2(a, b)).copied()
+    }
+
+    fn angle(&self, a: &str, b: &str, c: &str) -> Option<AngleParams> {
+        let key = if a <= c {
+            (a.to_string(), b.to_string(), c.to_string())
+        } else {
+            (c.to_string(), b.to_string(), a.to_string())
+        };
+        self.angles.get(&key).copied()
+    }
+
+    /// All dihedral terms for a type quadruple: exact (either direction) wins over `X b c X`
+    fn dihedral_terms(&self, t: [&str; 4]) -> Vec<(f32, f32, f32)> {
+        let matches = |p: &[String; 4], wild: bool| {
+            let fwd = (0..4).all(|i| p[i] == t[i] || (wild && p[i] == "X"));
+            let rev = (0..4).all(|i| p[i] == t[3 - i] || (wild && p[i] == "X"));
+            fwd || rev
+        };
+        for wild in [false, true] {
+            let terms: Vec<_> = self
+                .dihedrals
+                .iter()
+                .filter(|(p, ..)| matches(p, wild))
+                .map(|&(_, k, n, d)| (k, n, d))
+                .collect();
+            if !terms.is_empty() {
+                return terms;
+            }
+        }
+        Vec::new()
+    }
+
+    /// Improper lookup following CHARMM precedence: exact, `A X X D`, `X B C D`, `X X C D`
+    fn improper(&self, t: [&str; 4]) -> Option<(f32, f32)> {
+        let patterns = [
+            [t[0], t[1], t[2], t[3]],
+            [t[0], "X", "X", t[3]],
+            ["X", t[1], t[2], t[3]],
+            ["X", "X", t[2], t[3]],
+        ];
+        for pat in patterns {
+            let rev = [pat[3], pat[2], pat[1], pat[0]];
+            if let Some(&(_, k, psi0)) = self
+                .impropers
+                .iter()
+                .find(|(p, ..)| (0..4).all(|i| p[i] == pat[i]) || (0..4).all(|i| p[i] == rev[i]))
+            {
+                return Some((k, psi0));
+            }
+        }
+        None
+    }
+}
+
+fn ordered2(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+fn num(s: &str, line: &str) -> Result<f32> {
+    s.parse()
+        .map_err(|_| PrismIoError::FormatError(format!("Invalid number '{}' in: {}", s, line.trim())))
+}
+
+fn missing(kind: &str, types: &[&str]) -> PrismIoError {
+    PrismIoError::FormatError(format!(
+        "No {} parameters for {}; load the matching CHARMM parameter/stream file",
+        kind,
+        types.join("-")
+    ))
+}
+
+/// Resolve PSF connectivity against a parameter set into a [`Topology`]
+pub fn build_topology(psf: &PsfStructure, params: &CharmmParameters) -> Result<Topology> {
+    let mut top = Topology::default();
+    let mut residue: i64 = -1;
+    let mut last_key: Option<(&str, &str)> = None;
+
+    for a in &psf.atoms {
+        if last_key != Some((a.segid.as_str(), a.resid.as_str())) {
+            residue += 1;
+            last_key = Some((a.segid.as_str(), a.resid.as_str()));
+            top.residue_names.push(a.resname.clone());
+        }
+        if residue > u16::MAX as i64 {
+            return Err(PrismIoError::FormatError(
+                "PSF residue count exceeds the 16-bit residue index".to_string(),
+            ));
+        }
+        let (lj, lj14) = params
+            .nonbonded
+            .get(&a.atom_type)
+            .copied()
+            .ok_or_else(|| missing("nonbonded", &[a.atom_type.as_str()]))?;
+        let element = params
+            .elements
+            .get(&a.atom_type)
+            .map(|e| Topology::atomic_number(e))
+            .filter(|&z| z > 0)
+            .unwrap_or_else(|| Topology::element_from_mass(a.mass));
+
+        top.atoms.push(Atom {
+            coords: [0.0; 3],
+            element,
+            residue_id: residue as u16,
+            atom_type: 0,
+            charge: a.charge,
+            radius: lj.rmin_half().max(0.5),
+            _reserved: [0; 4],
+        });
+        top.atom_names.push(a.name.clone());
+        top.atom_types.push(a.atom_type.clone());
+        top.masses.push(a.mass);
+        top.lj.push(lj);
+        top.lj14.push(lj14.unwrap_or(lj));
+    }
+
+    let ty = |i: u32| top.atom_types[i as usize].as_str();
+
+    let mut bonds = Vec::with_capacity(psf.bonds.len());
+    for &[i, j] in &psf.bonds {
+        let (k, r0) = params.bond(ty(i), ty(j)).ok_or_else(|| missing("bond", &[ty(i), ty(j)]))?;
+        bonds.push(HarmonicBond { i, j, k, r0 });
+    }
+
+    let mut angles = Vec::with_capacity(psf.angles.len());
+    let mut urey_bradley = Vec::new();
+    for &[i, j, k] in &psf.angles {
+        let (kt, theta0, ub) = params
+            .angle(ty(i), ty(j), ty(k))
+            .ok_or_else(|| missing("angle", &[ty(i), ty(j), ty(k)]))?;
+        angles.push(HarmonicAngle { i, j, k, force_constant: kt, theta0 });
+        if let Some((kub, s0)) = ub {
+            if kub != 0.0 {
+                urey_bradley.push(HarmonicBond { i, j: k, k: kub, r0: s0 });
+            }
+        }
+    }
+
+    let mut dihedrals = Vec::with_capacity(psf.dihedrals.len());
+    let mut pairs14 = Vec::new();
+    let mut seen14 = HashSet::new();
+    for &atoms in &psf.dihedrals {
+        let t = atoms.map(ty);
+        let terms = params.dihedral_terms(t);
+        if terms.is_empty() {
+            return Err(missing("dihedral", &t));
+        }
+        for (k, n, phase) in terms {
+            dihedrals.push(PeriodicDihedral { atoms, k, periodicity: n, phase, improper: false });
+        }
+        let pair = (atoms[0].min(atoms[3]), atoms[0].max(atoms[3]));
+        if seen14.insert(pair) {
+            pairs14.push(Pair14 { i: pair.0, j: pair.1, coulomb_scale: 1.0, lj_scale: 1.0 });
+        }
+    }
+
+    let mut impropers = Vec::with_capacity(psf.impropers.len());
+    for &atoms in &psf.impropers {
+        let t = atoms.map(ty);
+        let (k, phi0) = params.improper(t).ok_or_else(|| missing("improper", &t))?;
+        impropers.push(HarmonicImproper { atoms, k, phi0 });
+    }
+
+    top.bonds = bonds;
+    top.generate_exclusions();
+    // 1-4 pairs that are also 1-2/1-3 (small rings) stay fully excluded
+    let close = top.bonded_pairs_within(2);
+    pairs14.retain(|p| close.binary_search(&(p.i, p.j)).is_err());
+
+    top.bonds.extend(urey_bradley);
+    top.angles = angles;
+    top.dihedrals = dihedrals;
+    top.impropers = impropers;
+    top.pairs14 = pairs14;
+
+    // Keep only NBFIX entries relevant to this system
+    top.nbfix = params
+        .nbfix
+        .iter()
+        .filter(|n| top.atom_types.contains(&n.type_a) && top.atom_types.contains(&n.type_b))
+        .cloned()
+        .collect();
+
+    Ok(top)
+}
+
+/// Read coordinates from a CHARMM `.crd` file (standard or EXT) as a flat array
+pub fn read_crd<P: AsRef<Path>>(path: P) -> Result<Vec<f32>> {
+    parse_crd(&std::fs::read_to_string(path)?)
+}
+
+/// Parse CHARMM `.crd` content as a flat `[x0, y0, z0, ...]` array
+pub fn parse_crd(content: &str) -> Result<Vec<f32>> {
+    let mut lines = content.lines().skip_while(|l| l.starts_with('*'));
+    let header = lines
+        .next()
+        .ok_or_else(|| PrismIoError::FormatError("CRD missing atom count".to_string()))?;
+    let natom: usize = header
+        .split_whitespace()
+        .next()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| PrismIoError::FormatError(format!("Invalid CRD header: {}", header)))?;
+
+    let mut coords = Vec::with_capacity(natom * 3);
+    for line in lines.take(natom) {
+        // ATOMNO RESNO RES TYPE X Y Z SEGID RESID WEIGHT
+        let f: Vec<&str> = line.split_whitespace().collect();
+        if f.len() < 7 {
+            return Err(PrismIoError::FormatError(format!("Malformed CRD line: {}", line)));
+        }
+        for v in &f[4..7] {
+            coords.push(num(v, line)?);
+        }
+    }
+    if coords.len() != natom * 3 {
+        return Err(PrismIoError::FormatError(format!(
+            "CRD declares {} atoms but contains {}",
+            natom,
+            coords.len() / 3
+        )));
+    }
+    Ok(coords)
+}
+
+/// Load a PSF with any number of parameter/topology/stream files
+pub fn load_charmm<P: AsRef<Path>, Q: AsRef<Path>>(psf: P, parameter_files: &[Q]) -> Result<Topology> {
+    let structure = read_psf(psf)?;
+    let mut params = CharmmParameters::new();
+    for file in parameter_files {
+        params.read_parameters(file)?;
+    }
+    build_topology(&structure, &params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PSF: &str = "PSF EXT\n\n         1 !NTITLE\n* TEST\n\n\
+         4 !NATOM\n\
+         1 PROA     1        ALA      C1       CT3     -0.270000       12.0110           0\n\
+         2 PROA     1        ALA      C2       CT2     -0.180000       12.0110           0\n\
+         3 PROA     1        ALA      C3       CT2     -0.180000       12.0110           0\n\
+         4 PROA     2        GLY      C4       CT3      0.630000       12.0110           0\n\n\
+         3 !NBOND: bonds\n         1         2         2         3         3         4\n\n\
+         2 !NTHETA: angles\n         1         2         3         2         3         4\n\n\
+         1 !NPHI: dihedrals\n         1         2         3         4\n\n\
+         0 !NIMPHI: impropers\n\n";
+
+    const PRM: &str = "* test parameters\n*\nATOMS\nMASS  -1  CT2  12.01100 C\nMASS  -1  CT3  12.01100 C\n\n\
+BONDS\nCT2  CT2   222.500     1.5300\nCT2  CT3   222.500     1.5280\n\n\
+ANGLES\nCT2  CT2  CT3    58.000   115.00    8.00   2.56100\n\n\
+DIHEDRALS\nX    CT2  CT2  X        0.1950  3     0.00\nCT3  CT2  CT2  CT3      0.1500  1     0.00\nCT3  CT2  CT2  CT3      0.1000  3   180.00\n\n\
+NONBONDED nbxmod  5 atom cdiel fshift vatom vdistance vfswitch -\n\
+cutnb 14.0 ctofnb 12.0 ctonnb 10.0 eps 1.0 e14fac 1.0 wmin 1.5\n\
+CT2    0.0       -0.0560     2.0100   0.0 -0.01 1.9\n\
+CT3    0.0       -0.0780     2.0400   0.0 -0.01 1.9\n\n\
+NBFIX\nCT2  CT3  -0.1  4.0\n\nEND\n";
+
+    #[test]
+    fn test_parse_psf() {
+        let psf = parse_psf(PSF).unwrap();
+        assert_eq!(psf.atoms.len(), 4);
+        assert_eq!(psf.atoms[3].resname, "GLY");
+        assert_eq!(psf.bonds, vec![[0, 1], [1, 2], [2, 3]]);
+        assert_eq!(psf.angles.len(), 2);
+        assert_eq!(psf.dihedrals, vec![[0, 1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_build_topology_with_parameters() {
+        let psf = parse_psf(PSF).unwrap();
+        let mut params = CharmmParameters::new();
+        params.parse_parameters(PRM).unwrap();
+        let top = build_topology(&psf, &params).unwrap();
+
+        assert_eq!(top.residue_names, vec!["ALA", "GLY"]);
+        assert_eq!(top.atoms[3].residue_id, 1);
+        // 3 bonds + 2 Urey-Bradley terms
+        assert_eq!(top.bonds.len(), 5);
+        assert!((top.angles[0].theta0 - 115f32.to_radians()).abs() < 1e-6);
+        // Exact CT3-CT2-CT2-CT3 match (two terms) wins over the X wildcard
+        assert_eq!(top.dihedrals.len(), 2);
+        assert_eq!(top.pairs14.len(), 1);
+        assert!((top.lj14[0].rmin_half() - 1.9).abs() < 1e-4);
+        assert_eq!(top.nbfix.len(), 1);
+        assert!((top.nbfix[0].params.epsilon - 0.1).abs() < 1e-6);
+        assert!(top.exclusions.contains(&(0, 2)));
+    }
+
+    #[test]
+    fn test_missing_parameter_is_reported() {
+        let psf = parse_psf(PSF).unwrap();
+        let mut params = CharmmParameters::new();
+        params.parse_parameters("BONDS\nCT2 CT2 222.5 1.53\nNONBONDED\nCT2 0.0 -0.056 2.01\nCT3 0.0 -0.078 2.04\nEND\n").unwrap();
+        let err = build_topology(&psf, &params).unwrap_err();
+        assert!(err.to_string().contains("CT2-CT3") || err.to_string().contains("CT3-CT2"));
+    }
+
+    #[test]
+    fn test_parse_crd() {
+        let crd = "* TITLE\n*\n    2\n    1    1 ALA  C1     1.00000   2.00000   3.00000 PROA 1      0.00000\n    2    1 ALA  C2     4.00000   5.00000   6.00000 PROA 1      0.00000\n";
+        assert_eq!(parse_crd(crd).unwrap(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+}
@@ -0,0 +1,350 @@
+//! # DCD Trajectory Format (CHARMM/NAMD)
+//!
+//! Fortran-record binary trajectories readable by VMD and MDAnalysis.
+//!
+//! ## Layout
+//! - Header: `CORD` + 20 control integers (frame count, first step, step
+//!   interval, timestep in AKMA units, unit-cell flag, CHARMM version 24)
+//! - Title and atom-count records
+//! - Per frame: optional unit cell `[a, cos γ, b, cos β, cos α, c]` as f64,
+//!   then X, Y and Z as separate f32 records
+//!
+//! The frame count in the header is rewritten after every frame, so a
+//! trajectory stays readable if the run is interrupted.
+
+use crate::simulation_box::SimulationBox;
+use crate::trajectory::{TrajectoryFrame, TrajectoryWriter};
+use crate::{PrismIoError, Result};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// One AKMA time unit in picoseconds
+pub const AKMA_PS: f64 = 0.048_888_21;
+
+/// Byte offset of the frame-count control word (after marker and `CORD`)
+const NSET_OFFSET: u64 = 8;
+/// Byte offset of the NSTEP control word
+const NSTEP_OFFSET: u64 = NSET_OFFSET + 12;
+
+/// DCD header metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcdHeader {
+    /// Number of frames in the file
+    pub num_frames: u32,
+    /// Step number of the first frame
+    pub first_step: u32,
+    /// Steps between frames
+    pub step_interval: u32,
+    /// Integration timestep (ps)
+    pub dt_ps: f64,
+    /// Whether frames carry a unit cell record
+    pub has_unit_cell: bool,
+    /// Title lines (at most 80 characters each)
+    pub titles: Vec<String>,
+    /// Number of atoms per frame
+    pub num_atoms: u32,
+}
+
+fn record(out: &mut impl Write, payload: &[u8]) -> std::io::Result<()> {
+    let len = payload.len() as u32;
+    out.write_all(&len.to_le_bytes())?;
+    out.write_all(payload)?;
+    out.write_all(&len.to_le_bytes())
+}
+
+/// Streaming DCD writer
+#[derive(Debug)]
+pub struct DcdWriter {
+    file: BufWriter<File>,
+    header: DcdHeader,
+    frames: u32,
+    xyz: [Vec<u8>; 3],
+}
+
+impl DcdWriter {
+    /// Create a DCD file and write its header
+    pub fn create<P: AsRef<Path>>(path: P, header: DcdHeader) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        let mut icntrl = [0i32; 20];
+        icntrl[1] = header.first_step as i32;
+        icntrl[2] = header.step_interval as i32;
+        icntrl[3] = header.first_step as i32;
+        icntrl[9] = ((header.dt_ps / AKMA_PS) as f32).to_bits() as i32;
+        icntrl[10] = header.has_unit_cell as i32;
+        icntrl[19] = 24;
+        let mut block = b"CORD".to_vec();
+        for v in icntrl {
+            block.extend_from_slice(&v.to_le_bytes());
+        }
+        record(&mut file, &block)?;
+
+        let titles: Vec<&String> = header.titles.iter().take(10).collect();
+        let mut block = (titles.len() as i32).to_le_bytes().to_vec();
+        for title in titles {
+            let mut line = [b' '; 80];
+            let bytes = title.as_bytes();
+            line[..bytes.len().min(80)].copy_from_slice(&bytes[..bytes.len().min(80)]);
+            block.extend_from_slice(&line);
+        }
+        record(&mut file, &block)?;
+        record(&mut file, &(header.num_atoms as i32).to_le_bytes())?;
+        file.flush()?;
+
+        Ok(Self {
+            file,
+            header,
+            frames: 0,
+            xyz: Default::default(),
+        })
+    }
+
+    /// Header this writer was created with
+    pub fn header(&self) -> &DcdHeader {
+        &self.header
+    }
+
+    fn update_frame_count(&mut self) -> Result<()> {
+        let last_step =
+            self.header.first_step + self.frames.saturating_sub(1) * self.header.step_interval;
+        self.file.seek(SeekFrom::Start(NSET_OFFSET))?;
+        self.file.write_all(&(self.frames as i32).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(NSTEP_OFFSET))?;
+        self.file.write_all(&(last_step as i32).to_le_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+}
+
+impl TrajectoryWriter for DcdWriter {
+    fn write_frame(&mut self, frame: &TrajectoryFrame<'_>) -> Result<()> {
+        let n = self.header.num_atoms as usize;
+        if frame.positions.len() != n * 4 {
+            return Err(PrismIoError::ValidationError(format!(
+                "DCD frame has {} values, expected {} (Float4 x {} atoms)",
+                frame.positions.len(),
+                n * 4,
+                n
+            )));
+        }
+
+        if self.header.has_unit_cell {
+            let [a, b, c, alpha, beta, gamma] = frame
+                .simulation_box
+                .map_or([0.0, 0.0, 0.0, 90.0, 90.0, 90.0], |cell| cell.parameters())
+                .map(|p| p as f64);
+            let cos = |angle: f64| {
+                let cos = angle.to_radians().cos();
+                if cos.abs() < 1e-7 {
+                    0.0
+                } else {
+                    cos
+                }
+            };
+            let mut cell = Vec::with_capacity(48);
+            for v in [a, cos(gamma), b, cos(beta), cos(alpha), c] {
+                cell.extend_from_slice(&v.to_le_bytes());
+            }
+            record(&mut self.file, &cell)?;
+        }
+        for (d, buf) in self.xyz.iter_mut().enumerate() {
+            buf.clear();
+            for atom in frame.positions.chunks_exact(4) {
+                buf.extend_from_slice(&atom[d].to_le_bytes());
+            }
+            record(&mut self.file, buf)?;
+        }
+
+        self.frames += 1;
+        self.update_frame_count()
+    }
+
+    fn frames_written(&self) -> usize {
+        self.frames as usize
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for DcdWriter {
+    fn drop(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+/// Trajectory read back from a DCD file
+#[derive(Debug, Clone)]
+pub struct DcdTrajectory {
+    /// Header metadata
+    pub header: DcdHeader,
+    /// Frames as `[x, y, z]` per atom (Å)
+    pub frames: Vec<Vec<[f32; 3]>>,
+    /// Unit cell per frame, if present (`None` for an empty cell record)
+    pub unit_cells: Vec<Option<SimulationBox>>,
+}
+
+fn read_record(input: &mut impl Read) -> Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    input.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    let mut payload = vec![0u8; len];
+    input.read_exact(&mut payload)?;
+    let mut end = [0u8; 4];
+    input.read_exact(&mut end)?;
+    if u32::from_le_bytes(end) as usize != len {
+        return Err(PrismIoError::FormatError(
+            "DCD record markers do not match".to_string(),
+        ));
+    }
+    Ok(payload)
+}
+
+fn i32_at(bytes: &[u8], index: usize) -> i32 {
+    i32::from_le_bytes(bytes[index * 4..index * 4 + 4].try_into().unwrap_or([0; 4]))
+}
+
+/// Read a little-endian CHARMM-format DCD file
+pub fn read_dcd<P: AsRef<Path>>(path: P) -> Result<DcdTrajectory> {
+    let data = std::fs::read(path)?;
+    let mut input = data.as_slice();
+
+    let block = read_record(&mut input)?;
+    if block.len() != 84 || &block[..4] != b"CORD" {
+        return Err(PrismIoError::FormatError(
+            "Not a little-endian CORD DCD file".to_string(),
+        ));
+    }
+    let icntrl = &block[4..];
+    let titles_block = read_record(&mut input)?;
+    let ntitle = i32_at(&titles_block, 0).max(0) as usize;
+    let titles = (0..ntitle)
+        .filter_map(|t| titles_block.get(4 + t * 80..4 + (t + 1) * 80))
+        .map(|l| String::from_utf8_lossy(l).trim_end().to_string())
+        .collect();
+    let natom_block = read_record(&mut input)?;
+    let num_atoms = i32_at(&natom_block, 0).max(0) as u32;
+
+    let mut header = DcdHeader {
+        num_frames: i32_at(icntrl, 0).max(0) as u32,
+        first_step: i32_at(icntrl, 1).max(0) as u32,
+        step_interval: i32_at(icntrl, 2).max(0) as u32,
+        dt_ps: f32::from_bits(i32_at(icntrl, 9) as u32) as f64 * AKMA_PS,
+        has_unit_cell: i32_at(icntrl, 10) != 0,
+        titles,
+        num_atoms,
+    };
+
+    let mut frames = Vec::new();
+    let mut unit_cells = Vec::new();
+    while !input.is_empty() {
+        if header.has_unit_cell {
+            let cell = read_record(&mut input)?;
+            let f = |i: usize| {
+                f64::from_le_bytes(
+                    cell.get(i * 8..i * 8 + 8)
+                        .and_then(|b| b.try_into().ok())
+                        .unwrap_or([0; 8]),
+                )
+            };
+            // CHARMM stores angle cosines; older NAMD files store degrees
+            let angle = |v: f64| {
+                if v.abs() <= 1.0 {
+                    v.acos().to_degrees()
+                } else {
+                    v
+                }
+            };
+            let parameters = [f(0), f(2), f(5), angle(f(4)), angle(f(3)), angle(f(1))];
+            unit_cells.push(SimulationBox::from_parameters(parameters.map(|p| p as f32)).ok());
+        }
+        let mut frame = vec![[0.0f32; 3]; num_atoms as usize];
+        for d in 0..3 {
+            let values = read_record(&mut input)?;
+            if values.len() != num_atoms as usize * 4 {
+                return Err(PrismIoError::FormatError(
+                    "DCD coordinate record has wrong length".to_string(),
+                ));
+            }
+            for (atom, v) in frame.iter_mut().zip(values.chunks_exact(4)) {
+                atom[d] = f32::from_le_bytes([v[0], v[1], v[2], v[3]]);
+            }
+        }
+        frames.push(frame);
+    }
+    header.num_frames = frames.len() as u32;
+
+    Ok(DcdTrajectory {
+        header,
+        frames,
+        unit_cells,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dcd_round_trip() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let header = DcdHeader {
+            num_frames: 0,
+            first_step: 100,
+            step_interval: 50,
+            dt_ps: 0.002,
+            has_unit_cell: true,
+            titles: vec!["PRISM test".to_string()],
+            num_atoms: 2,
+        };
+        let cell = SimulationBox::from_parameters([30.0, 31.0, 32.0, 80.0, 95.0, 70.0]).unwrap();
+        let mut writer = DcdWriter::create(file.path(), header).unwrap();
+        for step in 0..3u64 {
+            let s = step as f32;
+            let positions = [s, 1.0, 2.0, 12.0, 3.0, 4.0 + s, 5.0, 1.0];
+            writer
+                .write_frame(&TrajectoryFrame {
+                    step: 100 + step * 50,
+                    time_ps: 0.0,
+                    positions: &positions,
+                    velocities: None,
+                    simulation_box: Some(cell),
+                })
+                .unwrap();
+        }
+        assert!(writer
+            .write_frame(&TrajectoryFrame {
+                step: 0,
+                time_ps: 0.0,
+                positions: &[0.0; 4],
+                velocities: None,
+                simulation_box: None
+            })
+            .is_err());
+        drop(writer);
+        assert_eq!(
+            std::fs::metadata(file.path()).unwrap().len(),
+            crate::trajectory::TrajectoryFormat::Dcd.file_bytes(2, 3, true, false)
+        );
+
+        let traj = read_dcd(file.path()).unwrap();
+        assert_eq!(traj.frames.len(), 3);
+        assert_eq!(traj.header.step_interval, 50);
+        assert_eq!(traj.header.first_step, 100);
+        assert!((traj.header.dt_ps - 0.002).abs() < 1e-7);
+        assert_eq!(traj.header.titles[0], "PRISM test");
+        assert_eq!(traj.frames[2][0], [2.0, 1.0, 2.0]);
+        assert_eq!(traj.frames[2][1], [3.0, 6.0, 5.0]);
+        let read = traj.unit_cells[1].unwrap();
+        for (a, b) in read.parameters().iter().zip(cell.parameters()) {
+            assert!((a - b).abs() < 1e-3, "{:?} vs {:?}", read, cell);
+        }
+
+        // The header frame count was kept up to date
+        let bytes = std::fs::read(file.path()).unwrap();
+        assert_eq!(i32::from_le_bytes(bytes[8..12].try_into().unwrap()), 3);
+    }
+}
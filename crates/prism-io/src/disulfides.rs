@@ -0,0 +1,515 @@
+//! # Disulfide Bridges
+//!
+//! Pairs cysteines whose `SG` atoms lie within bonding distance
+//! ([`DisulfideOptions::max_distance`]), closest pairs first so every
+//! cysteine bridges at most once. The override lists of
+//! [`DisulfideOptions`] force bridges the geometry misses (a strained
+//! crystal contact, a modelled partner) and drop ones it wrongly suggests.
+//!
+//! Cysteines are named by chain and author residue number (`A:22`, `A:22B`
+//! with an insertion code, `22` for any chain). [`detect_disulfides`] works on
+//! parsed structures, [`detect_topology_disulfides`] on imported topologies,
+//! where residues are numbered from 1 in order and cysteines still carrying
+//! `HG` are left out. [`add_disulfide_terms`] adds the S-S bond and the
+//! angles, torsions, exclusions and 1-4 pairs it implies with AMBER ff14SB
+//! parameters, for topologies built without the bridges.
+
+use crate::geometry::distance;
+use crate::pdb::PdbStructure;
+use crate::residues::canonical_name;
+use crate::topology::{HarmonicAngle, HarmonicBond, Pair14, PeriodicDihedral, Topology};
+use crate::{PrismIoError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Longest SG-SG distance bridged by default (Å)
+pub const DEFAULT_MAX_DISULFIDE_DISTANCE: f32 = 2.5;
+/// S-S bond: force constant (kcal/mol/Å²) and length (Å)
+const S_S_BOND: (f32, f32) = (166.0, 2.038);
+/// C-S-S angle: force constant (kcal/mol/rad²) and angle (degrees)
+const C_S_S_ANGLE: (f32, f32) = (68.0, 103.7);
+/// C-S-S-C torsion terms: barrier (kcal/mol) and periodicity
+const C_S_S_C_TORSION: [(f32, f32); 2] = [(3.5, 2.0), (0.6, 3.0)];
+/// X-C-S-S torsion: barrier (kcal/mol) and periodicity
+const X_C_S_S_TORSION: (f32, f32) = (1.0 / 3.0, 3.0);
+/// AMBER 1-4 scaling of Coulomb and LJ interactions
+const AMBER_SCALE_14: (f32, f32) = (1.0 / 1.2, 0.5);
+
+/// A cysteine named by chain and author residue number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CysteineId {
+    /// Chain identifier; `None` matches any chain
+    pub chain_id: Option<char>,
+    /// Author residue number
+    pub residue_seq: i32,
+    /// Residue insertion code (' ' if none)
+    pub insertion_code: char,
+}
+
+impl CysteineId {
+    /// Whether `self` names the residue `chain_id:residue_seq insertion_code`
+    pub fn matches(&self, chain_id: Option<char>, residue_seq: i32, insertion_code: char) -> bool {
+        self.residue_seq == residue_seq
+            && self.insertion_code == insertion_code
+            && (self.chain_id.is_none() || self.chain_id == chain_id)
+    }
+}
+
+impl FromStr for CysteineId {
+    type Err = PrismIoError;
+
+    /// `chain:number[insertion code]` or `number[insertion code]`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            PrismIoError::FormatError(format!(
+                "Invalid cysteine '{}' (expected chain:number, e.g. A:22 or A:22B)",
+                s
+            ))
+        };
+        let (chain_id, number) = match s.split_once(':') {
+            Some((chain, number)) => {
+                let mut chars = chain.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => (Some(c), number),
+                    _ => return Err(invalid()),
+                }
+            }
+            None => (None, s),
+        };
+        let (number, insertion_code) = match number.chars().last() {
+            Some(c) if c.is_ascii_alphabetic() => (&number[..number.len() - 1], c),
+            _ => (number, ' '),
+        };
+        Ok(Self {
+            chain_id,
+            residue_seq: number.parse().map_err(|_| invalid())?,
+            insertion_code,
+        })
+    }
+}
+
+impl TryFrom<String> for CysteineId {
+    type Error = PrismIoError;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<CysteineId> for String {
+    fn from(id: CysteineId) -> Self {
+        id.to_string()
+    }
+}
+
+impl fmt::Display for CysteineId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(chain) = self.chain_id {
+            write!(f, "{}:", chain)?;
+        }
+        write!(
+            f,
+            "{}{}",
+            self.residue_seq,
+            self.insertion_code.to_string().trim()
+        )
+    }
+}
+
+/// Two cysteines of an override list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CysteinePair(pub CysteineId, pub CysteineId);
+
+impl FromStr for CysteinePair {
+    type Err = PrismIoError;
+
+    /// Two cysteines separated by a comma, e.g. `A:22,A:95`
+    fn from_str(s: &str) -> Result<Self> {
+        let (a, b) = s.split_once(',').ok_or_else(|| {
+            PrismIoError::FormatError(format!(
+                "Invalid cysteine pair '{}' (expected two cysteines, e.g. A:22,A:95)",
+                s
+            ))
+        })?;
+        Ok(Self(a.trim().parse()?, b.trim().parse()?))
+    }
+}
+
+/// How cysteines are paired into disulfides
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisulfideOptions {
+    /// Longest SG-SG distance bridged (Å)
+    pub max_distance: f32,
+    /// Pairs bridged whatever their distance
+    pub bond: Vec<CysteinePair>,
+    /// Pairs never bridged
+    pub ignore: Vec<CysteinePair>,
+}
+
+impl Default for DisulfideOptions {
+    fn default() -> Self {
+        Self {
+            max_distance: DEFAULT_MAX_DISULFIDE_DISTANCE,
+            bond: Vec::new(),
+            ignore: Vec::new(),
+        }
+    }
+}
+
+/// A disulfide bridge between two cysteines
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Disulfide {
+    /// Cysteines, in atom order
+    pub cysteines: (CysteineId, CysteineId),
+    /// Indices of the two `SG` atoms
+    pub atoms: (u32, u32),
+    /// SG-SG distance (Å)
+    pub distance: f32,
+    /// Bridged by the `bond` override rather than by distance
+    pub forced: bool,
+}
+
+impl fmt::Display for Disulfide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{} ({:.2} Å{})",
+            self.cysteines.0,
+            self.cysteines.1,
+            self.distance,
+            if self.forced { ", forced" } else { "" }
+        )
+    }
+}
+
+/// `SG` of a cysteine that may bridge
+struct Cysteine {
+    id: CysteineId,
+    sg: u32,
+    coords: [f32; 3],
+}
+
+/// Pair `cysteines` (in atom order) by distance and the overrides
+fn pair(cysteines: &[Cysteine], options: &DisulfideOptions) -> Result<Vec<Disulfide>> {
+    let find = |id: &CysteineId| {
+        let mut found = cysteines
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| id.matches(c.id.chain_id, c.id.residue_seq, c.id.insertion_code));
+        match (found.next(), found.next()) {
+            (Some((k, _)), None) => Ok(k),
+            (None, _) => Err(PrismIoError::ValidationError(format!(
+                "Disulfide override names {}, which is not a cysteine with an SG atom",
+                id
+            ))),
+            (Some(_), Some(_)) => Err(PrismIoError::ValidationError(format!(
+                "Disulfide override {} matches several cysteines; give the chain",
+                id
+            ))),
+        }
+    };
+    let bridge = |a: usize, b: usize, forced: bool| {
+        let (a, b) = (a.min(b), a.max(b));
+        Disulfide {
+            cysteines: (cysteines[a].id, cysteines[b].id),
+            atoms: (cysteines[a].sg, cysteines[b].sg),
+            distance: distance(cysteines[a].coords, cysteines[b].coords),
+            forced,
+        }
+    };
+
+    let mut bridged = vec![false; cysteines.len()];
+    let mut disulfides = Vec::new();
+    for CysteinePair(a, b) in &options.bond {
+        let (a, b) = (find(a)?, find(b)?);
+        if a == b || bridged[a] || bridged[b] {
+            return Err(PrismIoError::ValidationError(format!(
+                "Disulfide override {}-{} bridges a cysteine twice",
+                cysteines[a].id, cysteines[b].id
+            )));
+        }
+        bridged[a] = true;
+        bridged[b] = true;
+        disulfides.push(bridge(a, b, true));
+    }
+    let mut ignored = BTreeSet::new();
+    for CysteinePair(a, b) in &options.ignore {
+        let (a, b) = (find(a)?, find(b)?);
+        ignored.insert((a.min(b), a.max(b)));
+    }
+
+    let mut candidates: Vec<(usize, usize, f32)> = Vec::new();
+    for a in 0..cysteines.len() {
+        for b in a + 1..cysteines.len() {
+            let d = distance(cysteines[a].coords, cysteines[b].coords);
+            if d <= options.max_distance && !ignored.contains(&(a, b)) {
+                candidates.push((a, b, d));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| x.2.total_cmp(&y.2));
+    for (a, b, _) in candidates {
+        if !bridged[a] && !bridged[b] {
+            bridged[a] = true;
+            bridged[b] = true;
+            disulfides.push(bridge(a, b, false));
+        }
+    }
+    disulfides.sort_by_key(|d| d.atoms);
+    Ok(disulfides)
+}
+
+/// Disulfides of the standard cysteines (`CYS`, `CYX`, `CYM`) of a structure
+pub fn detect_disulfides(
+    structure: &PdbStructure,
+    options: &DisulfideOptions,
+) -> Result<Vec<Disulfide>> {
+    let cysteines: Vec<Cysteine> = structure
+        .records
+        .iter()
+        .zip(&structure.atoms)
+        .enumerate()
+        .filter(|(_, (r, _))| {
+            !r.hetatm && r.name == "SG" && canonical_name(&r.residue_name) == Some("CYS")
+        })
+        .map(|(i, (r, a))| Cysteine {
+            id: CysteineId {
+                chain_id: Some(r.chain_id),
+                residue_seq: r.residue_seq,
+                insertion_code: r.insertion_code,
+            },
+            sg: i as u32,
+            coords: a.coords,
+        })
+        .collect();
+    pair(&cysteines, options)
+}
+
+/// Disulfides of a topology, residues numbered from 1 in order; cysteines
+/// with an `HG` hydrogen are not candidates
+pub fn detect_topology_disulfides(
+    topology: &Topology,
+    options: &DisulfideOptions,
+) -> Result<Vec<Disulfide>> {
+    let name = |i: usize| topology.atom_names.get(i).map_or("", String::as_str);
+    let protonated: BTreeSet<u16> = (0..topology.atoms.len())
+        .filter(|&i| name(i) == "HG")
+        .map(|i| topology.atoms[i].residue_id)
+        .collect();
+    let cysteines: Vec<Cysteine> = topology
+        .atoms
+        .iter()
+        .enumerate()
+        .filter(|&(i, atom)| {
+            let residue = topology.residue_names.get(atom.residue_id as usize);
+            name(i) == "SG"
+                && residue.and_then(|r| canonical_name(r)) == Some("CYS")
+                && !protonated.contains(&atom.residue_id)
+        })
+        .map(|(i, atom)| Cysteine {
+            id: CysteineId {
+                chain_id: None,
+                residue_seq: atom.residue_id as i32 + 1,
+                insertion_code: ' ',
+            },
+            sg: i as u32,
+            coords: atom.coords,
+        })
+        .collect();
+    pair(&cysteines, options)
+}
+
+/// Add the bond, angles, torsions, exclusions and 1-4 pairs of the
+/// `disulfides` not yet bonded in `topology`; returns the bridges added.
+/// New 1-4 pairs take the scaling of the pairs already listed (AMBER's
+/// when there are none).
+pub fn add_disulfide_terms(topology: &mut Topology, disulfides: &[Disulfide]) -> usize {
+    let bonded: BTreeSet<(u32, u32)> = topology
+        .bonds
+        .iter()
+        .map(|b| (b.i.min(b.j), b.i.max(b.j)))
+        .collect();
+    let new: Vec<(u32, u32)> = disulfides
+        .iter()
+        .map(|d| (d.atoms.0.min(d.atoms.1), d.atoms.0.max(d.atoms.1)))
+        .filter(|pair| !bonded.contains(pair))
+        .collect();
+    if new.is_empty() {
+        return 0;
+    }
+    let mut neighbours: Vec<Vec<u32>> = vec![Vec::new(); topology.atoms.len()];
+    for b in &topology.bonds {
+        neighbours[b.i as usize].push(b.j);
+        neighbours[b.j as usize].push(b.i);
+    }
+    let (coulomb_scale, lj_scale) = topology
+        .pairs14
+        .first()
+        .map_or(AMBER_SCALE_14, |p| (p.coulomb_scale, p.lj_scale));
+    let mut pairs14: BTreeSet<(u32, u32)> = topology
+        .pairs14
+        .iter()
+        .map(|p| (p.i.min(p.j), p.i.max(p.j)))
+        .collect();
+    let mut torsion = |topology: &mut Topology, atoms: [u32; 4], (k, periodicity): (f32, f32)| {
+        topology.dihedrals.push(PeriodicDihedral {
+            atoms,
+            k,
+            periodicity,
+            phase: 0.0,
+            improper: false,
+        });
+        let (i, l) = (atoms[0].min(atoms[3]), atoms[0].max(atoms[3]));
+        if i != l && pairs14.insert((i, l)) {
+            topology.pairs14.push(Pair14 {
+                i,
+                j: l,
+                coulomb_scale,
+                lj_scale,
+            });
+        }
+    };
+
+    for &(a, b) in &new {
+        topology.bonds.push(HarmonicBond {
+            i: a,
+            j: b,
+            k: S_S_BOND.0,
+            r0: S_S_BOND.1,
+        });
+        for (s, other) in [(a, b), (b, a)] {
+            for &c in &neighbours[s as usize] {
+                topology.angles.push(HarmonicAngle {
+                    i: c,
+                    j: s,
+                    k: other,
+                    force_constant: C_S_S_ANGLE.0,
+                    theta0: C_S_S_ANGLE.1.to_radians(),
+                });
+                for &x in neighbours[c as usize].iter().filter(|&&x| x != s) {
+                    torsion(topology, [x, c, s, other], X_C_S_S_TORSION);
+                }
+            }
+        }
+        for &ca in &neighbours[a as usize] {
+            for &cb in &neighbours[b as usize] {
+                for term in C_S_S_C_TORSION {
+                    torsion(topology, [ca, a, b, cb], term);
+                }
+            }
+        }
+    }
+    let mut exclusions: BTreeSet<(u32, u32)> = topology.exclusions.iter().copied().collect();
+    exclusions.extend(topology.bonded_pairs_within(3));
+    topology.exclusions = exclusions.into_iter().collect();
+    new.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdb::parse_pdb;
+
+    /// Three cysteines: A:3 and A:9 bridged at 2.03 Å, B:4 3.1 Å from A:9
+    const PDB: &str = "\
+ATOM      1  N   CYS A   3       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  CYS A   3       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  CB  CYS A   3       2.000   1.400   0.000  1.00  0.00           C
+ATOM      4  SG  CYS A   3       3.800   1.500   0.000  1.00  0.00           S
+ATOM      5  N   CYS A   9       7.500   4.000   0.000  1.00  0.00           N
+ATOM      6  CA  CYS A   9       6.600   2.900   0.000  1.00  0.00           C
+ATOM      7  CB  CYS A   9       7.200   1.600   1.000  1.00  0.00           C
+ATOM      8  SG  CYS A   9       5.830   1.500   0.000  1.00  0.00           S
+ATOM      9  N   CYS B   4       8.000   6.000   3.000  1.00  0.00           N
+ATOM     10  CA  CYS B   4       8.000   5.000   2.500  1.00  0.00           C
+ATOM     11  CB  CYS B   4       8.000   4.000   1.000  1.00  0.00           C
+ATOM     12  SG  CYS B   4       8.000   2.600   2.000  1.00  0.00           S
+";
+
+    fn pair(s: &str) -> CysteinePair {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cysteine_ids_parse() {
+        let id: CysteineId = "A:22B".parse().unwrap();
+        assert_eq!(
+            (id.chain_id, id.residue_seq, id.insertion_code),
+            (Some('A'), 22, 'B')
+        );
+        assert_eq!(id.to_string(), "A:22B");
+        assert_eq!("-3".parse::<CysteineId>().unwrap().chain_id, None);
+        assert!("AB:3".parse::<CysteineId>().is_err());
+        assert_eq!(pair("A:3, B:4").1.chain_id, Some('B'));
+        assert!("A:3".parse::<CysteinePair>().is_err());
+    }
+
+    #[test]
+    fn test_detection_and_overrides() {
+        let structure = parse_pdb(PDB).unwrap();
+        let found = detect_disulfides(&structure, &DisulfideOptions::default()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].atoms, (3, 7));
+        assert_eq!(found[0].to_string(), "A:3-A:9 (2.03 Å)");
+
+        // Forcing A:9-B:4 takes A:9 away from A:3
+        let options = DisulfideOptions {
+            bond: vec![pair("9,B:4")],
+            ..Default::default()
+        };
+        let found = detect_disulfides(&structure, &options).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].atoms, found[0].forced), ((7, 11), true));
+
+        let options = DisulfideOptions {
+            ignore: vec![pair("A:9,A:3")],
+            ..Default::default()
+        };
+        assert!(detect_disulfides(&structure, &options).unwrap().is_empty());
+
+        let options = DisulfideOptions {
+            bond: vec![pair("A:3,A:5")],
+            ..Default::default()
+        };
+        assert!(detect_disulfides(&structure, &options).is_err());
+    }
+
+    #[test]
+    fn test_terms_are_added_once() {
+        let structure = parse_pdb(PDB).unwrap();
+        let mut topology = Topology {
+            atoms: structure.atoms.clone(),
+            atom_names: structure.records.iter().map(|r| r.name.clone()).collect(),
+            residue_names: vec!["CYX".to_string(); 3],
+            ..Default::default()
+        };
+        for (i, j) in [(0, 1), (1, 2), (2, 3), (4, 5), (5, 6), (6, 7)] {
+            topology.bonds.push(HarmonicBond {
+                i,
+                j,
+                k: 300.0,
+                r0: 1.5,
+            });
+        }
+        let found = detect_topology_disulfides(&topology, &DisulfideOptions::default()).unwrap();
+        assert_eq!(found[0].cysteines.1.residue_seq, 2);
+        assert_eq!(add_disulfide_terms(&mut topology, &found), 1);
+        assert_eq!(topology.bonds.len(), 7);
+        // CB-SG-SG on both sides; X-CB-SG-SG and both CB-SG-SG-CB terms
+        assert_eq!(topology.angles.len(), 2);
+        assert_eq!(topology.dihedrals.len(), 4);
+        assert_eq!(topology.pairs14.len(), 3);
+        assert!(topology.exclusions.contains(&(1, 7)));
+        assert!(topology.exclusions.contains(&(2, 6)));
+        assert_eq!(add_disulfide_terms(&mut topology, &found), 0);
+
+        // A cysteine carrying HG does not bridge
+        topology.atom_names[2] = "HG".to_string();
+        let found = detect_topology_disulfides(&topology, &DisulfideOptions::default()).unwrap();
+        assert!(found.is_empty());
+    }
+}
@@ -0,0 +1,599 @@
+//! # GAFF-Style Ligand Parameters
+//!
+//! Types ligand atoms after the General AMBER Force Field (`c3`, `ca`, `hc`,
+//! `oh`, ...) from elements, bond orders and ring aromaticity, then builds
+//! a [`Topology`] with GAFF bond, angle, torsion, improper and LJ
+//! parameters, so ligands combine with the AMBER-family protein terms.
+//!
+//! Only the common GAFF entries are tabulated. A bond or angle missing from
+//! the tables takes its equilibrium value from the input geometry with a
+//! generic force constant; a missing torsion falls back to the generic
+//! sp3-sp3 or conjugated term of its central bond. Every estimate is counted
+//! in the [`GaffReport`].
+//!
+//! Partial charges are not computed (no AM1-BCC): they come from the ligand
+//! file or are supplied by the caller, and are required.
+
+use crate::ligand::{BondOrder, Ligand};
+use crate::pdb::vdw_radius;
+use crate::sovereign_types::Atom;
+use crate::topology::{HarmonicAngle, HarmonicBond, LjParams, Pair14, PeriodicDihedral, Topology};
+use crate::{geometry, PrismIoError, Result};
+use serde::Serialize;
+use std::f32::consts::PI;
+use std::fmt;
+
+/// GAFF 1-4 electrostatic scaling (1/SCEE)
+const COULOMB_14_SCALE: f32 = 1.0 / 1.2;
+/// GAFF 1-4 Lennard-Jones scaling (1/SCNB)
+const LJ_14_SCALE: f32 = 0.5;
+
+/// Bond parameters: types (sorted), k (kcal/mol/Å²), r0 (Å)
+const BONDS: &[(&str, &str, f32, f32)] = &[
+    ("c", "c3", 328.3, 1.508),
+    ("c", "ca", 349.7, 1.487),
+    ("c", "n", 478.2, 1.345),
+    ("c", "o", 648.0, 1.214),
+    ("c", "oh", 466.4, 1.306),
+    ("c", "os", 411.3, 1.343),
+    ("c2", "c2", 589.7, 1.324),
+    ("c2", "c3", 328.3, 1.508),
+    ("c2", "ha", 344.3, 1.087),
+    ("c3", "c3", 303.1, 1.535),
+    ("c3", "ca", 323.5, 1.513),
+    ("c3", "f", 363.8, 1.344),
+    ("c3", "h1", 335.9, 1.093),
+    ("c3", "h2", 335.9, 1.093),
+    ("c3", "hc", 337.3, 1.092),
+    ("c3", "n", 330.6, 1.460),
+    ("c3", "n3", 320.6, 1.470),
+    ("c3", "n4", 293.6, 1.499),
+    ("c3", "oh", 314.1, 1.426),
+    ("c3", "os", 301.5, 1.439),
+    ("c3", "sh", 222.4, 1.824),
+    ("c3", "ss", 227.8, 1.817),
+    ("ca", "ca", 478.4, 1.387),
+    ("ca", "cl", 193.4, 1.729),
+    ("ca", "f", 363.8, 1.344),
+    ("ca", "ha", 344.3, 1.087),
+    ("ca", "nb", 483.1, 1.339),
+    ("ca", "nh", 449.0, 1.364),
+    ("ca", "oh", 386.1, 1.362),
+    ("ca", "os", 372.4, 1.373),
+    ("hn", "n", 410.2, 1.009),
+    ("hn", "n3", 394.1, 1.018),
+    ("hn", "n4", 369.0, 1.033),
+    ("hn", "nh", 406.6, 1.011),
+    ("ho", "oh", 369.6, 0.974),
+    ("hs", "sh", 250.7, 1.353),
+];
+
+/// Angle parameters: outer types (sorted), central type, k (kcal/mol/rad²),
+/// θ0 (degrees)
+const ANGLES: &[(&str, &str, &str, f32, f32)] = &[
+    ("c", "c3", "hc", 47.20, 109.68),
+    ("c3", "c", "o", 68.03, 123.11),
+    ("c3", "c", "oh", 69.84, 112.20),
+    ("c3", "c3", "c3", 63.21, 110.63),
+    ("c3", "c3", "h1", 46.36, 110.07),
+    ("c3", "c3", "hc", 46.37, 110.05),
+    ("c3", "c3", "oh", 67.72, 109.43),
+    ("c3", "oh", "ho", 47.09, 107.26),
+    ("ca", "ca", "ca", 67.18, 119.97),
+    ("ca", "ca", "ha", 48.46, 120.01),
+    ("ca", "ca", "oh", 69.85, 119.94),
+    ("ca", "oh", "ho", 48.85, 109.47),
+    ("h1", "c3", "h1", 39.24, 108.46),
+    ("h1", "c3", "oh", 51.02, 109.88),
+    ("hc", "c3", "hc", 39.43, 108.35),
+    ("hn", "n", "hn", 39.46, 117.85),
+    ("hn", "n3", "hn", 40.04, 106.40),
+    ("o", "c", "o", 78.17, 130.38),
+    ("o", "c", "oh", 77.38, 122.88),
+];
+
+/// Generic torsions `X-a-b-X`: central types (sorted), barrier per torsion
+/// (kcal/mol), periodicity, phase (degrees)
+const TORSIONS: &[(&str, &str, f32, f32, f32)] = &[
+    ("c", "c3", 0.0, 2.0, 0.0),
+    ("c", "ca", 1.0, 2.0, 180.0),
+    ("c", "n", 2.5, 2.0, 180.0),
+    ("c", "oh", 2.3, 2.0, 180.0),
+    ("c", "os", 2.7, 2.0, 180.0),
+    ("c2", "c2", 6.65, 2.0, 180.0),
+    ("c3", "c3", 0.156, 3.0, 0.0),
+    ("c3", "ca", 0.0, 2.0, 0.0),
+    ("c3", "n", 0.0, 2.0, 0.0),
+    ("c3", "n3", 0.3, 3.0, 0.0),
+    ("c3", "n4", 0.156, 3.0, 0.0),
+    ("c3", "oh", 0.167, 3.0, 0.0),
+    ("c3", "os", 0.383, 3.0, 0.0),
+    ("c3", "sh", 0.25, 3.0, 0.0),
+    ("c3", "ss", 0.333, 3.0, 0.0),
+    ("ca", "ca", 3.625, 2.0, 180.0),
+    ("ca", "nh", 1.05, 2.0, 180.0),
+    ("ca", "oh", 0.9, 2.0, 180.0),
+    ("ca", "os", 0.9, 2.0, 180.0),
+];
+
+/// LJ parameters per type: Rmin/2 (Å), ε (kcal/mol)
+fn lj_parameters(atom_type: &str) -> (f32, f32) {
+    match atom_type {
+        "c" | "c2" | "ca" => (1.9080, 0.0860),
+        "c1" => (1.9080, 0.2100),
+        "c3" => (1.9080, 0.1094),
+        "h1" => (1.3870, 0.0157),
+        "h2" => (1.2870, 0.0157),
+        "h3" => (1.1870, 0.0157),
+        "hc" => (1.4870, 0.0157),
+        "ha" => (1.4590, 0.0150),
+        "hn" | "hs" | "hp" => (0.6000, 0.0157),
+        "ho" => (0.0, 0.0),
+        "o" => (1.6612, 0.2100),
+        "oh" => (1.7210, 0.2104),
+        "os" => (1.6837, 0.1700),
+        "f" => (1.75, 0.061),
+        "cl" => (1.948, 0.265),
+        "br" => (2.22, 0.320),
+        "i" => (2.35, 0.400),
+        "p3" | "p5" => (2.1000, 0.2000),
+        t if t.starts_with('s') => (2.0000, 0.2500),
+        // Every nitrogen type
+        _ => (1.8240, 0.1700),
+    }
+}
+
+/// Atom types and parameter estimates of a [`parameterize`] call
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GaffReport {
+    /// Ligand residue name
+    pub residue_name: String,
+    /// GAFF type of every atom
+    pub atom_types: Vec<String>,
+    /// Sum of the partial charges (e)
+    pub net_charge: f32,
+    /// Sum of the formal charges given by the file (e)
+    pub formal_charge: i32,
+    /// Bonds whose parameters were estimated from the geometry
+    pub estimated_bonds: usize,
+    /// Angles whose parameters were estimated from the geometry
+    pub estimated_angles: usize,
+    /// Torsions given a generic term by hybridization
+    pub estimated_torsions: usize,
+}
+
+impl fmt::Display for GaffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} atoms, net charge {:+.3}",
+            self.residue_name,
+            self.atom_types.len(),
+            self.net_charge
+        )?;
+        if (self.net_charge - self.formal_charge as f32).abs() > 0.01 {
+            write!(f, " (formal charge {:+})", self.formal_charge)?;
+        }
+        let estimated = self.estimated_bonds + self.estimated_angles + self.estimated_torsions;
+        if estimated > 0 {
+            write!(
+                f,
+                "\n  estimated: {} bonds, {} angles, {} torsions",
+                self.estimated_bonds, self.estimated_angles, self.estimated_torsions
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Atoms in six-membered C/N rings whose ring bonds are aromatic or
+/// alternate single/double
+fn aromatic_atoms(ligand: &Ligand, neighbours: &[Vec<(u32, BondOrder)>]) -> Vec<bool> {
+    let ring_atom = |a: usize| matches!(ligand.atoms[a].element, 6 | 7);
+    let mut aromatic = vec![false; ligand.atoms.len()];
+    for start in (0..ligand.atoms.len()).filter(|&a| ring_atom(a)) {
+        let mut stack = vec![vec![start]];
+        while let Some(path) = stack.pop() {
+            let last = path[path.len() - 1];
+            for &(next, _) in &neighbours[last] {
+                let next = next as usize;
+                if path.len() == 6 {
+                    if next == start {
+                        mark_aromatic_ring(&path, neighbours, &mut aromatic);
+                    }
+                } else if next > start && ring_atom(next) && !path.contains(&next) {
+                    let mut extended = path.clone();
+                    extended.push(next);
+                    stack.push(extended);
+                }
+            }
+        }
+    }
+    aromatic
+}
+
+fn mark_aromatic_ring(ring: &[usize], neighbours: &[Vec<(u32, BondOrder)>], aromatic: &mut [bool]) {
+    let delocalised = ring.iter().all(|&a| {
+        neighbours[a].iter().any(|&(b, order)| {
+            ring.contains(&(b as usize)) && matches!(order, BondOrder::Double | BondOrder::Aromatic)
+        })
+    });
+    if delocalised {
+        ring.iter().for_each(|&a| aromatic[a] = true);
+    }
+}
+
+/// GAFF atom types of every ligand atom
+pub fn assign_types(ligand: &Ligand) -> Result<Vec<String>> {
+    let neighbours = ligand.neighbours();
+    let aromatic = aromatic_atoms(ligand, &neighbours);
+    let element = |i: u32| ligand.atoms[i as usize].element;
+    let has_order = |a: usize, order: BondOrder| neighbours[a].iter().any(|&(_, o)| o == order);
+    let carbonyl = |c: u32| {
+        element(c) == 6
+            && neighbours[c as usize]
+                .iter()
+                .any(|&(o, order)| order == BondOrder::Double && matches!(element(o), 8 | 16))
+    };
+    let trigonal = |a: u32| {
+        aromatic[a as usize]
+            || neighbours[a as usize]
+                .iter()
+                .any(|&(_, o)| matches!(o, BondOrder::Double | BondOrder::Aromatic))
+    };
+
+    let mut types = Vec::with_capacity(ligand.atoms.len());
+    for (a, atom) in ligand.atoms.iter().enumerate() {
+        let bonded = &neighbours[a];
+        let degree = bonded.len();
+        let atom_type = match atom.element {
+            1 => {
+                let &(heavy, _) = bonded.first().ok_or_else(|| {
+                    PrismIoError::ValidationError(format!("Hydrogen {} has no bond", atom.name))
+                })?;
+                match element(heavy) {
+                    7 => "hn",
+                    8 => "ho",
+                    16 => "hs",
+                    15 => "hp",
+                    _ if trigonal(heavy) => "ha",
+                    _ => {
+                        let withdrawing = neighbours[heavy as usize]
+                            .iter()
+                            .filter(|&&(n, _)| matches!(element(n), 7 | 8 | 9 | 17 | 35))
+                            .count();
+                        ["hc", "h1", "h2", "h3"][withdrawing.min(3)]
+                    }
+                }
+            }
+            6 if aromatic[a] => "ca",
+            6 if degree == 4 => "c3",
+            6 if carbonyl(a as u32) => "c",
+            6 if has_order(a, BondOrder::Triple) || degree <= 2 => "c1",
+            6 => "c2",
+            7 if degree == 4 => "n4",
+            7 if aromatic[a] && degree == 2 => "nb",
+            7 if aromatic[a] => "na",
+            7 if degree == 3 && bonded.iter().any(|&(n, _)| carbonyl(n)) => "n",
+            7 if degree == 3 && bonded.iter().any(|&(n, _)| element(n) == 8) => "no",
+            7 if degree == 3 && bonded.iter().any(|&(n, _)| trigonal(n)) => "nh",
+            7 if degree == 3 => "n3",
+            7 if has_order(a, BondOrder::Triple) || degree == 1 => "n1",
+            7 => "n2",
+            8 if degree == 1 => "o",
+            8 if bonded.iter().any(|&(n, _)| element(n) == 1) => "oh",
+            8 => "os",
+            16 if degree == 1 => "s",
+            16 if degree == 2 && bonded.iter().any(|&(n, _)| element(n) == 1) => "sh",
+            16 if degree == 2 => "ss",
+            16 if degree == 3 => "s4",
+            16 => "s6",
+            15 if degree == 4 => "p5",
+            15 => "p3",
+            9 => "f",
+            17 => "cl",
+            35 => "br",
+            53 => "i",
+            other => {
+                return Err(PrismIoError::ValidationError(format!(
+                    "No GAFF type for element {} (atom {})",
+                    Topology::element_symbol(other),
+                    atom.name
+                )))
+            }
+        };
+        types.push(atom_type.to_string());
+    }
+    Ok(types)
+}
+
+/// Generic force constant of a bond missing from the table (kcal/mol/Å²)
+fn generic_bond_constant(order: BondOrder) -> f32 {
+    match order {
+        BondOrder::Single => 300.0,
+        BondOrder::Aromatic => 450.0,
+        BondOrder::Double => 550.0,
+        BondOrder::Triple => 900.0,
+    }
+}
+
+fn is_sp3(atom_type: &str) -> bool {
+    matches!(
+        atom_type,
+        "c3" | "n3" | "n4" | "oh" | "os" | "sh" | "ss" | "p5"
+    )
+}
+
+fn is_trigonal(atom_type: &str) -> bool {
+    matches!(
+        atom_type,
+        "c" | "c2" | "ca" | "n" | "na" | "nb" | "nh" | "no" | "n2"
+    )
+}
+
+/// Type the ligand atoms and build its GAFF topology. `charges` (one per
+/// atom, in file order) replace the partial charges of the file; without
+/// either the ligand cannot be parameterized.
+pub fn parameterize(ligand: &Ligand, charges: Option<&[f32]>) -> Result<(Topology, GaffReport)> {
+    let n = ligand.atoms.len();
+    let charges = match charges {
+        Some(charges) if charges.len() != n => {
+            return Err(PrismIoError::ValidationError(format!(
+                "{} charges supplied for ligand {} with {} atoms",
+                charges.len(),
+                ligand.name,
+                n
+            )))
+        }
+        Some(charges) => charges.to_vec(),
+        None => ligand.partial_charges().ok_or_else(|| {
+            PrismIoError::ValidationError(format!(
+                "Ligand {} has no partial charges; supply them in the file or a charge file",
+                ligand.name
+            ))
+        })?,
+    };
+    let types = assign_types(ligand)?;
+    let neighbours = ligand.neighbours();
+    let coords = |i: u32| ligand.atoms[i as usize].coords;
+    let type_of = |i: u32| types[i as usize].as_str();
+
+    let residue_name = ligand.residue_name();
+    let mut report = GaffReport {
+        residue_name: residue_name.clone(),
+        atom_types: types.clone(),
+        net_charge: charges.iter().sum(),
+        formal_charge: ligand.formal_charge(),
+        ..Default::default()
+    };
+    let mut topology = Topology {
+        residue_names: vec![residue_name],
+        ..Default::default()
+    };
+    for ((atom, charge), atom_type) in ligand.atoms.iter().zip(&charges).zip(&types) {
+        topology.atoms.push(Atom {
+            coords: atom.coords,
+            element: atom.element,
+            residue_id: 0,
+            atom_type: 0,
+            charge: *charge,
+            radius: vdw_radius(atom.element),
+            _reserved: [0; 4],
+        });
+        topology.atom_names.push(atom.name.clone());
+        topology.atom_types.push(atom_type.clone());
+        topology.masses.push(Topology::element_mass(atom.element));
+        let (rmin_half, epsilon) = lj_parameters(atom_type);
+        topology
+            .lj
+            .push(LjParams::from_rmin_half(rmin_half, epsilon));
+    }
+
+    for bond in &ligand.bonds {
+        let (a, b) = (type_of(bond.i), type_of(bond.j));
+        let (a, b) = if a <= b { (a, b) } else { (b, a) };
+        let (k, r0) = match BONDS.iter().find(|p| (p.0, p.1) == (a, b)) {
+            Some(&(_, _, k, r0)) => (k, r0),
+            None => {
+                report.estimated_bonds += 1;
+                let r0 = geometry::norm(geometry::sub(coords(bond.i), coords(bond.j)));
+                (generic_bond_constant(bond.order), r0)
+            }
+        };
+        topology.bonds.push(HarmonicBond {
+            i: bond.i,
+            j: bond.j,
+            k,
+            r0,
+        });
+    }
+
+    for (j, bonded) in neighbours.iter().enumerate() {
+        let j = j as u32;
+        for (x, &(i, _)) in bonded.iter().enumerate() {
+            for &(k, _) in &bonded[x + 1..] {
+                let (a, c) = (type_of(i), type_of(k));
+                let (a, c) = if a <= c { (a, c) } else { (c, a) };
+                let b = type_of(j);
+                let (force_constant, theta0) =
+                    match ANGLES.iter().find(|p| (p.0, p.1, p.2) == (a, b, c)) {
+                        Some(&(_, _, _, k, theta0)) => (k, theta0.to_radians()),
+                        None => {
+                            report.estimated_angles += 1;
+                            let u = geometry::normalize(geometry::sub(coords(i), coords(j)));
+                            let v = geometry::normalize(geometry::sub(coords(k), coords(j)));
+                            let k = if is_sp3(b) { 50.0 } else { 60.0 };
+                            (k, geometry::dot(u, v).clamp(-1.0, 1.0).acos())
+                        }
+                    };
+                topology.angles.push(HarmonicAngle {
+                    i,
+                    j,
+                    k,
+                    force_constant,
+                    theta0,
+                });
+            }
+        }
+    }
+
+    for bond in &ligand.bonds {
+        let (j, k) = (bond.i, bond.j);
+        let (a, b) = (type_of(j), type_of(k));
+        let (a, b) = if a <= b { (a, b) } else { (b, a) };
+        let (barrier, periodicity, phase) = match TORSIONS.iter().find(|p| (p.0, p.1) == (a, b)) {
+            Some(&(_, _, barrier, n, phase)) => (barrier, n, phase.to_radians()),
+            None if is_sp3(a) && is_sp3(b) => (0.156, 3.0, 0.0),
+            None if is_trigonal(a) && is_trigonal(b) => {
+                let conjugated = matches!(bond.order, BondOrder::Double | BondOrder::Aromatic);
+                (if conjugated { 3.625 } else { 1.0 }, 2.0, PI)
+            }
+            None => (0.0, 3.0, 0.0),
+        };
+        if barrier == 0.0 {
+            continue;
+        }
+        let estimated = !TORSIONS.iter().any(|p| (p.0, p.1) == (a, b));
+        for &(i, _) in neighbours[j as usize].iter().filter(|&&(i, _)| i != k) {
+            for &(l, _) in neighbours[k as usize]
+                .iter()
+                .filter(|&&(l, _)| l != j && l != i)
+            {
+                if estimated {
+                    report.estimated_torsions += 1;
+                }
+                topology.dihedrals.push(PeriodicDihedral {
+                    atoms: [i, j, k, l],
+                    k: barrier,
+                    periodicity,
+                    phase,
+                    improper: false,
+                });
+            }
+        }
+    }
+
+    // Planarity of trigonal centres, central atom third (AMBER order)
+    for (c, bonded) in neighbours.iter().enumerate() {
+        let center = type_of(c as u32);
+        if bonded.len() != 3 || !is_trigonal(center) || center == "nh" {
+            continue;
+        }
+        let k = if center == "c" { 10.5 } else { 1.1 };
+        topology.dihedrals.push(PeriodicDihedral {
+            atoms: [bonded[0].0, bonded[1].0, c as u32, bonded[2].0],
+            k,
+            periodicity: 2.0,
+            phase: PI,
+            improper: true,
+        });
+    }
+
+    let within_two = topology.bonded_pairs_within(2);
+    topology.pairs14 = topology
+        .bonded_pairs_within(3)
+        .into_iter()
+        .filter(|pair| within_two.binary_search(pair).is_err())
+        .map(|(i, j)| Pair14 {
+            i,
+            j,
+            coulomb_scale: COULOMB_14_SCALE,
+            lj_scale: LJ_14_SCALE,
+        })
+        .collect();
+    topology.generate_exclusions();
+    Ok((topology, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mol2::{parse_mol2, tests::PHENOL};
+    use crate::sdf::{parse_sdf, tests::ACETATE};
+
+    #[test]
+    fn test_assign_types() {
+        let phenol = parse_mol2(PHENOL).unwrap();
+        let types = assign_types(&phenol).unwrap();
+        assert_eq!(
+            &types[..8],
+            ["ca", "ca", "ca", "ca", "ca", "ca", "oh", "ho"]
+        );
+        assert_eq!(types[8], "ha");
+
+        // Kekulé bonds of an SD file are recognised as aromatic too
+        let kekule = phenol
+            .bonds
+            .iter()
+            .enumerate()
+            .map(|(k, b)| crate::ligand::LigandBond {
+                order: match (b.order, k % 2) {
+                    (BondOrder::Aromatic, 0) => BondOrder::Double,
+                    (BondOrder::Aromatic, _) => BondOrder::Single,
+                    (order, _) => order,
+                },
+                ..*b
+            })
+            .collect();
+        let kekule = Ligand {
+            bonds: kekule,
+            ..phenol
+        };
+        assert_eq!(assign_types(&kekule).unwrap()[0], "ca");
+
+        let acetate = parse_sdf(ACETATE).unwrap();
+        let types = assign_types(&acetate).unwrap();
+        assert_eq!(types, ["c3", "c", "o", "o", "hc", "hc", "hc"]);
+    }
+
+    #[test]
+    fn test_parameterize() {
+        let acetate = parse_sdf(ACETATE).unwrap();
+        let (topology, report) = parameterize(&acetate, None).unwrap();
+        assert_eq!(topology.num_atoms(), 7);
+        assert_eq!(topology.bonds.len(), 6);
+        // 3 angles at each carbon, 6 H-C-H/H-C-C at the methyl
+        assert_eq!(topology.angles.len(), 9);
+        let co = topology
+            .bonds
+            .iter()
+            .find(|b| (b.i, b.j) == (1, 2))
+            .unwrap();
+        assert_eq!((co.k, co.r0), (648.0, 1.214));
+        // One improper on the carboxylate carbon, no torsion across c3-c
+        assert_eq!(topology.dihedrals.len(), 1);
+        assert!(topology.dihedrals[0].improper);
+        assert_eq!(topology.dihedrals[0].atoms[2], 1);
+        assert_eq!(topology.pairs14.len(), 6);
+        assert_eq!(topology.exclusions.len(), 21);
+        assert!((topology.masses[0] - 12.011).abs() < 1e-3);
+        assert!((report.net_charge + 1.0).abs() < 1e-4);
+        assert_eq!(report.formal_charge, -1);
+        assert_eq!(report.estimated_bonds, 0);
+
+        let phenol = parse_mol2(PHENOL).unwrap();
+        let (topology, report) = parameterize(&phenol, None).unwrap();
+        // X-ca-ca-X and X-ca-oh-X torsions, ring planarity
+        assert!(topology.dihedrals.iter().filter(|d| !d.improper).count() > 20);
+        assert_eq!(topology.dihedrals.iter().filter(|d| d.improper).count(), 6);
+        assert_eq!(report.estimated_bonds, 0);
+        assert!(report.to_string().starts_with("PHE: 13 atoms"));
+
+        let uncharged = Ligand {
+            atoms: acetate
+                .atoms
+                .iter()
+                .map(|a| crate::ligand::LigandAtom {
+                    partial_charge: None,
+                    ..a.clone()
+                })
+                .collect(),
+            ..acetate.clone()
+        };
+        assert!(parameterize(&uncharged, None).is_err());
+        assert!(parameterize(&uncharged, Some(&[0.0; 3])).is_err());
+        let (topology, report) = parameterize(&uncharged, Some(&[0.1; 7])).unwrap();
+        assert!((topology.atoms[3].charge - 0.1).abs() < 1e-6);
+        assert!(report.to_string().contains("formal charge -1"));
+    }
+}
@@ -0,0 +1,1049 @@
+//! # Missing Side-Chain and Loop Reconstruction
+//!
+//! Deposited structures lack the atoms the experiment could not locate:
+//! side chains truncated after `CB` and loops listed as unobserved
+//! ([`PdbStructure::missing_residues`]). [`repair_gaps`] rebuilds them so the
+//! structure can be simulated without external modelling tools:
+//! - missing side-chain heavy atoms are placed from the internal coordinates
+//!   of [`crate::rotamers`], in the rotamer with the fewest clashes with the
+//!   surrounding atoms; χ angles fixed by atoms present are kept
+//! - internal loops of up to [`GapRepairOptions::max_loop_length`] residues
+//!   are grown with ideal backbone geometry from the residue before the gap
+//!   and closed onto the residue after it by cyclic coordinate descent
+//!   (Canutescu & Dunbrack, Protein Sci. 12:963, 2003) over the loop φ/ψ and
+//!   the ψ of the residue before, whose `O` is placed again
+//!
+//! Rebuilt atoms get occupancy 0 and their residues are listed in the
+//! [`GapRepairReport`]; PTB output records them in the
+//! [`SECTION_REBUILT_RESIDUES`] section. Terminal and longer gaps, and chain
+//! breaks without missing-residue records, are reported and left open. The
+//! rebuilt geometry is a starting model to be minimised, not a refined one.
+
+use crate::geometry::{
+    add, cross, dihedral, distance, dot, norm, normalize, place, rotate, scale, sub,
+};
+use crate::holographic::PtbStructure;
+use crate::pdb::{vdw_radius, MissingResidue, PdbAtomRecord, PdbStructure};
+use crate::perception::MAX_PEPTIDE_BOND;
+use crate::residues::{canonical_name, standard_residue};
+use crate::rotamers::{rotamers, side_chain_geometry, SideChainAtom, Torsion};
+use crate::sovereign_types::Atom;
+use crate::topology::Topology;
+use crate::{PrismIoError, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// PTB application section listing the rebuilt residues
+/// ([`RebuiltResidue`] records)
+pub const SECTION_REBUILT_RESIDUES: u32 = 0x101;
+/// Heavy-atom distance below which rebuilt atoms count as clashing (Å)
+const CLASH_DISTANCE: f32 = 3.0;
+/// Largest N/CA deviation from the anchor residue accepted as closed (Å)
+pub const LOOP_CLOSURE_TOLERANCE: f32 = 0.5;
+/// Cyclic coordinate descent sweeps per starting conformation
+const CCD_SWEEPS: usize = 500;
+/// Starting φ/ψ of the loop residues (polyproline, α, β, bridge)
+const LOOP_SEEDS: [(f32, f32); 4] = [
+    (-65.0, 140.0),
+    (-60.0, -45.0),
+    (-120.0, 130.0),
+    (-80.0, 80.0),
+];
+/// Trans peptide backbone geometry: bond lengths and angles
+const C_N: f32 = 1.329;
+const N_CA: f32 = 1.458;
+const CA_C: f32 = 1.525;
+const C_O: f32 = 1.231;
+const CA_C_N: f32 = 116.2;
+const C_N_CA: f32 = 121.7;
+const N_CA_C: f32 = 111.2;
+const CA_C_O: f32 = 120.5;
+
+/// What [`repair_gaps`] rebuilds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GapRepairOptions {
+    /// Rebuild missing side-chain heavy atoms
+    pub side_chains: bool,
+    /// Model missing internal loops
+    pub loops: bool,
+    /// Longest loop modelled (residues)
+    pub max_loop_length: usize,
+}
+
+impl Default for GapRepairOptions {
+    fn default() -> Self {
+        Self {
+            side_chains: true,
+            loops: true,
+            max_loop_length: 12,
+        }
+    }
+}
+
+/// What a rebuilt region consists of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebuiltKind {
+    /// Side-chain atoms of one residue
+    SideChain,
+    /// Whole residues of a missing loop
+    Loop,
+}
+
+/// A run of residues with rebuilt atoms
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RebuiltRegion {
+    /// Side chain or loop
+    pub kind: RebuiltKind,
+    /// Chain identifier
+    pub chain_id: char,
+    /// Author numbers of the first and last residue rebuilt
+    pub residues: (i32, i32),
+    /// Residue names
+    pub residue_names: Vec<String>,
+    /// Heavy atoms placed
+    pub atoms: usize,
+    /// Rebuilt side chains: the χ angles chosen (degrees)
+    pub chi: Vec<f32>,
+    /// Loops: deviation of the loop end from the anchor residue (Å)
+    pub closure: Option<f32>,
+}
+
+/// A gap left open
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpenGap {
+    /// Chain identifier
+    pub chain_id: char,
+    /// Author number of the residue before the gap (`None` at the N-terminus)
+    pub after: Option<i32>,
+    /// Author number of the residue after the gap (`None` at the C-terminus)
+    pub before: Option<i32>,
+    /// Missing residues listed for the gap
+    pub residue_names: Vec<String>,
+    /// Why it was not modelled
+    pub reason: String,
+}
+
+/// Regions rebuilt and gaps left by [`repair_gaps`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GapRepairReport {
+    /// Rebuilt side chains and loops in chain order
+    pub regions: Vec<RebuiltRegion>,
+    /// Gaps not modelled
+    pub open_gaps: Vec<OpenGap>,
+    /// Rebuilt residues by sequential residue index of the output
+    pub rebuilt_residues: Vec<RebuiltResidue>,
+}
+
+/// A rebuilt residue as stored in the [`SECTION_REBUILT_RESIDUES`] section
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RebuiltResidue {
+    /// Sequential residue index (`Atom::residue_id`)
+    pub residue_id: u32,
+    /// 1 for a rebuilt side chain, 2 for a loop residue
+    pub kind: u32,
+    /// Heavy atoms placed
+    pub atoms: u32,
+    #[serde(skip)]
+    _reserved: u32,
+}
+
+impl RebuiltResidue {
+    /// Record of `atoms` atoms placed in residue `residue_id`
+    pub fn new(residue_id: u32, kind: RebuiltKind, atoms: u32) -> Self {
+        let kind = match kind {
+            RebuiltKind::SideChain => 1,
+            RebuiltKind::Loop => 2,
+        };
+        Self {
+            residue_id,
+            kind,
+            atoms,
+            _reserved: 0,
+        }
+    }
+}
+
+impl GapRepairReport {
+    /// Heavy atoms placed in all regions
+    pub fn atoms_rebuilt(&self) -> usize {
+        self.regions.iter().map(|r| r.atoms).sum()
+    }
+
+    /// Contents of the [`SECTION_REBUILT_RESIDUES`] section
+    pub fn section_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice(&self.rebuilt_residues).to_vec()
+    }
+}
+
+/// Rebuilt residues recorded in a PTB file (empty when none were)
+pub fn read_rebuilt_residues(ptb: &mut PtbStructure) -> Result<Vec<RebuiltResidue>> {
+    let Some(bytes) = ptb.section(SECTION_REBUILT_RESIDUES)? else {
+        return Ok(Vec::new());
+    };
+    if !bytes.len().is_multiple_of(size_of::<RebuiltResidue>()) {
+        return Err(PrismIoError::FormatError(format!(
+            "Rebuilt residue section of {} bytes is not a whole number of records",
+            bytes.len()
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(size_of::<RebuiltResidue>())
+        .map(bytemuck::pod_read_unaligned)
+        .collect())
+}
+
+/// One residue being repaired: its atoms with their index in the input
+/// (`None` when rebuilt)
+struct Residue {
+    atoms: Vec<Atom>,
+    records: Vec<PdbAtomRecord>,
+    source: Vec<Option<usize>>,
+    rebuilt: Option<RebuiltKind>,
+}
+
+impl Residue {
+    fn coords(&self, name: &str) -> Option<[f32; 3]> {
+        let i = self.records.iter().position(|r| r.name == name)?;
+        Some(self.atoms[i].coords)
+    }
+
+    fn first(&self) -> &PdbAtomRecord {
+        &self.records[0]
+    }
+
+    fn key(&self) -> (i32, char) {
+        (self.first().residue_seq, self.first().insertion_code)
+    }
+
+    /// Standard polymer residue with a complete N-CA-C backbone
+    fn is_polymer(&self) -> bool {
+        !self.first().hetatm
+            && standard_residue(&self.first().residue_name).is_some()
+            && ["N", "CA", "C"].iter().all(|n| self.coords(n).is_some())
+    }
+
+    /// Append a rebuilt heavy atom named `name` at `coords`
+    fn push(&mut self, name: &str, coords: [f32; 3], template: &PdbAtomRecord) {
+        let element = Topology::atomic_number(&name[..1]);
+        self.atoms.push(Atom {
+            coords,
+            element,
+            residue_id: 0,
+            atom_type: 0,
+            charge: 0.0,
+            radius: vdw_radius(element),
+            _reserved: [0; 4],
+        });
+        self.records.push(PdbAtomRecord {
+            serial: 0,
+            name: name.to_string(),
+            occupancy: 0.0,
+            ..template.clone()
+        });
+        self.source.push(None);
+    }
+}
+
+/// Clash penalty of `atoms` against `environment`
+fn clash_score(atoms: &[[f32; 3]], environment: &[[f32; 3]]) -> f32 {
+    let mut score = 0.0;
+    for &a in atoms {
+        for &e in environment {
+            let d = distance(a, e);
+            if d < CLASH_DISTANCE {
+                score += (CLASH_DISTANCE - d) * (CLASH_DISTANCE - d);
+            }
+        }
+    }
+    score
+}
+
+/// Heavy atoms of all residues but `skip` within `radius` of `center`
+fn environment(
+    residues: &[Residue],
+    skip: &[usize],
+    center: [f32; 3],
+    radius: f32,
+) -> Vec<[f32; 3]> {
+    residues
+        .iter()
+        .enumerate()
+        .filter(|(k, _)| !skip.contains(k))
+        .flat_map(|(_, r)| r.atoms.iter())
+        .filter(|a| a.element > 1 && distance(a.coords, center) <= radius)
+        .map(|a| a.coords)
+        .collect()
+}
+
+/// Side-chain atoms placed by [`build_side_chain`]
+type PlacedAtoms = Vec<(&'static str, [f32; 3])>;
+
+/// Positions of the side-chain atoms missing from `present` with χ angles
+/// `chi`; `None` when a reference atom is missing
+fn build_side_chain(
+    geometry: &[SideChainAtom],
+    present: &HashMap<&str, [f32; 3]>,
+    chi: &[f32],
+) -> Option<PlacedAtoms> {
+    let mut placed = present.clone();
+    let mut built = Vec::new();
+    for atom in geometry {
+        if placed.contains_key(atom.atom) {
+            continue;
+        }
+        let [r, b, a] = atom.refs.map(|name| placed.get(name).copied());
+        let torsion = match atom.torsion {
+            Torsion::Chi(n, offset) => chi[n] + offset,
+            Torsion::Fixed(value) => value,
+        };
+        let coords = place(r?, b?, a?, atom.bond, atom.angle, torsion);
+        placed.insert(atom.atom, coords);
+        built.push((atom.atom, coords));
+    }
+    Some(built)
+}
+
+/// Rebuild the missing side-chain atoms of residue `k`: the χ angles used
+/// and the atoms placed
+fn repair_side_chain(residues: &mut [Residue], k: usize) -> Option<(Vec<f32>, usize)> {
+    let residue = &residues[k];
+    let parent = canonical_name(&residue.first().residue_name)?;
+    let geometry = side_chain_geometry(parent)?;
+    let present: HashMap<&str, [f32; 3]> = residue
+        .records
+        .iter()
+        .zip(&residue.atoms)
+        .map(|(r, a)| (r.name.as_str(), a.coords))
+        .collect();
+    if geometry.iter().all(|g| present.contains_key(g.atom)) {
+        return None;
+    }
+
+    // χ angles fixed by atoms already there
+    let library = rotamers(parent);
+    let chi_count = library.first().map_or(0, |chi| chi.len());
+    let mut fixed_chi: Vec<Option<f32>> = vec![None; chi_count];
+    for atom in geometry {
+        if let Torsion::Chi(n, offset) = atom.torsion {
+            let refs: Option<Vec<[f32; 3]>> =
+                atom.refs.iter().map(|r| present.get(r).copied()).collect();
+            if let (Some(refs), Some(&x), None) = (refs, present.get(atom.atom), fixed_chi[n]) {
+                fixed_chi[n] = Some(dihedral(refs[0], refs[1], refs[2], x) - offset);
+            }
+        }
+    }
+    let center = residue.coords("CA")?;
+    let environment = environment(residues, &[k], center, 15.0);
+    let candidates: Vec<Vec<f32>> = if library.is_empty() {
+        vec![Vec::new()]
+    } else {
+        library
+            .iter()
+            .map(|chi| {
+                chi.iter()
+                    .zip(&fixed_chi)
+                    .map(|(&c, fixed)| fixed.unwrap_or(c))
+                    .collect()
+            })
+            .collect()
+    };
+    let mut best: Option<(f32, Vec<f32>, PlacedAtoms)> = None;
+    for chi in candidates {
+        let Some(built) = build_side_chain(geometry, &present, &chi) else {
+            continue;
+        };
+        let coords: Vec<[f32; 3]> = built.iter().map(|b| b.1).collect();
+        let score = clash_score(&coords, &environment);
+        if best.as_ref().is_none_or(|b| score < b.0) {
+            best = Some((score, chi, built));
+        }
+    }
+    let (_, chi, built) = best?;
+    let residue = &mut residues[k];
+    let template = residue.records[residue.records.iter().position(|r| r.name == "CA")?].clone();
+    for &(name, coords) in &built {
+        residue.push(name, coords, &template);
+    }
+    residue.rebuilt.get_or_insert(RebuiltKind::SideChain);
+    Some((chi, built.len()))
+}
+
+/// A closed loop backbone: N, CA, C and O of each residue, the new position
+/// of the `O` before the loop, the closure deviation and the clash score
+struct Loop {
+    backbone: Vec<[[f32; 3]; 4]>,
+    anchor_o: [f32; 3],
+    closure: f32,
+    score: f32,
+}
+
+/// Grow `names.len()` residues from `before` and close them onto `after`
+fn build_loop(
+    before: &Residue,
+    after: &Residue,
+    names: &[&MissingResidue],
+    environment: &[[f32; 3]],
+) -> Option<Loop> {
+    let [n0, ca0, c0] = ["N", "CA", "C"].map(|a| before.coords(a));
+    let (n0, ca0, c0) = (n0?, ca0?, c0?);
+    let targets = [after.coords("N")?, after.coords("CA")?];
+    let length = names.len();
+    let psi0 = before
+        .coords("O")
+        .map_or(140.0, |o| dihedral(n0, ca0, c0, o) + 180.0);
+    let proline: Vec<bool> = names
+        .iter()
+        .map(|m| canonical_name(&m.residue_name) == Some("PRO"))
+        .collect();
+
+    let mut best: Option<Loop> = None;
+    for &(phi, psi) in &LOOP_SEEDS {
+        // Points N, CA, C of each residue, then N and CA of the anchor after
+        let mut points: Vec<[f32; 3]> = Vec::with_capacity(3 * length + 2);
+        let (mut n, mut ca, mut c) = (n0, ca0, c0);
+        let mut previous_psi = psi0;
+        for &pro in &proline {
+            let next_n = place(n, ca, c, C_N, CA_C_N, previous_psi);
+            let next_ca = place(ca, c, next_n, N_CA, C_N_CA, 180.0);
+            let next_c = place(
+                c,
+                next_n,
+                next_ca,
+                CA_C,
+                N_CA_C,
+                if pro { -65.0 } else { phi },
+            );
+            (n, ca, c) = (next_n, next_ca, next_c);
+            points.extend([n, ca, c]);
+            previous_psi = psi;
+        }
+        let end_n = place(n, ca, c, C_N, CA_C_N, previous_psi);
+        points.push(end_n);
+        points.push(place(ca, c, end_n, N_CA, C_N_CA, 180.0));
+
+        // Rotatable bonds: ψ before the loop, then φ (not Pro) and ψ of
+        // each loop residue, as (axis start, axis end, first point moved)
+        let mut axes: Vec<(Option<usize>, usize, usize)> = vec![(None, 0, 0)];
+        for (i, &pro) in proline.iter().enumerate() {
+            if !pro {
+                axes.push((Some(3 * i), 3 * i + 1, 3 * i + 2));
+            }
+            axes.push((Some(3 * i + 1), 3 * i + 2, 3 * i + 3));
+        }
+        let deviation = |points: &[[f32; 3]]| {
+            let end = &points[3 * length..];
+            (end.iter()
+                .zip(&targets)
+                .map(|(&p, &t)| distance(p, t).powi(2))
+                .sum::<f32>()
+                / 2.0)
+                .sqrt()
+        };
+        for _ in 0..CCD_SWEEPS {
+            if deviation(&points) < 0.05 {
+                break;
+            }
+            for &(from, to, moved) in &axes {
+                let (origin, end) = match from {
+                    Some(from) => (points[from], points[to]),
+                    None => (ca0, c0),
+                };
+                let axis = normalize(sub(end, origin));
+                let (mut a, mut b) = (0.0, 0.0);
+                for (&m, &f) in points[3 * length..].iter().zip(&targets) {
+                    let o = add(origin, scale(axis, dot(sub(m, origin), axis)));
+                    let r = sub(m, o);
+                    let radius = norm(r);
+                    if radius < 1e-6 {
+                        continue;
+                    }
+                    let unit = scale(r, 1.0 / radius);
+                    let f = sub(f, o);
+                    a += radius * dot(f, unit);
+                    b += radius * dot(f, cross(axis, unit));
+                }
+                let angle = b.atan2(a);
+                for p in &mut points[moved..] {
+                    *p = rotate(*p, origin, axis, angle);
+                }
+            }
+        }
+        let closure = deviation(&points);
+        if closure > LOOP_CLOSURE_TOLERANCE {
+            continue;
+        }
+
+        let carbonyl = |n: [f32; 3], ca: [f32; 3], c: [f32; 3], next_n: [f32; 3]| {
+            place(n, ca, c, C_O, CA_C_O, dihedral(n, ca, c, next_n) + 180.0)
+        };
+        let anchor_o = carbonyl(n0, ca0, c0, points[0]);
+        let backbone: Vec<[[f32; 3]; 4]> = (0..length)
+            .map(|i| {
+                let [n, ca, c] = [points[3 * i], points[3 * i + 1], points[3 * i + 2]];
+                let next_n = if i + 1 < length {
+                    points[3 * i + 3]
+                } else {
+                    targets[0]
+                };
+                [n, ca, c, carbonyl(n, ca, c, next_n)]
+            })
+            .collect();
+        let atoms: Vec<[f32; 3]> = backbone.iter().flatten().copied().collect();
+        let mut score = clash_score(&atoms, environment);
+        for (i, a) in backbone.iter().enumerate() {
+            for b in backbone.iter().skip(i + 2) {
+                score += clash_score(a, b);
+            }
+        }
+        if best.as_ref().is_none_or(|b| score < b.score) {
+            best = Some(Loop {
+                backbone,
+                anchor_o,
+                closure,
+                score,
+            });
+        }
+    }
+    best
+}
+
+/// Model the missing internal loops of `residues`, recording regions and
+/// open gaps
+fn repair_loops(
+    residues: &mut Vec<Residue>,
+    missing: &[MissingResidue],
+    options: &GapRepairOptions,
+    report: &mut GapRepairReport,
+) {
+    let mut chains: Vec<char> = Vec::new();
+    for r in residues.iter().filter(|r| r.is_polymer()) {
+        if !chains.contains(&r.first().chain_id) {
+            chains.push(r.first().chain_id);
+        }
+    }
+    // (insert after residue, residues) applied back to front
+    let mut insertions: Vec<(usize, Vec<Residue>, [f32; 3])> = Vec::new();
+    for chain in chains {
+        let observed: Vec<usize> = (0..residues.len())
+            .filter(|&k| residues[k].is_polymer() && residues[k].first().chain_id == chain)
+            .collect();
+        let mut listed: Vec<&MissingResidue> =
+            missing.iter().filter(|m| m.chain_id == chain).collect();
+        listed.sort_by_key(|m| (m.residue_seq, m.insertion_code));
+        let key = |k: usize| residues[k].key();
+        let open = |after: Option<usize>,
+                    before: Option<usize>,
+                    names: &[&MissingResidue],
+                    reason: &str| OpenGap {
+            chain_id: chain,
+            after: after.map(|k| key(k).0),
+            before: before.map(|k| key(k).0),
+            residue_names: names.iter().map(|m| m.residue_name.clone()).collect(),
+            reason: reason.to_string(),
+        };
+
+        let (Some(&first), Some(&last)) = (observed.first(), observed.last()) else {
+            continue;
+        };
+        let head: Vec<&MissingResidue> = listed
+            .iter()
+            .copied()
+            .filter(|m| (m.residue_seq, m.insertion_code) < key(first))
+            .collect();
+        if !head.is_empty() {
+            report
+                .open_gaps
+                .push(open(None, Some(first), &head, "N-terminal"));
+        }
+        for pair in observed.windows(2) {
+            let (k, next) = (pair[0], pair[1]);
+            let names: Vec<&MissingResidue> = listed
+                .iter()
+                .copied()
+                .filter(|m| {
+                    let m = (m.residue_seq, m.insertion_code);
+                    key(k) < m && m < key(next)
+                })
+                .collect();
+            let (c, n) = (residues[k].coords("C"), residues[next].coords("N"));
+            if names.is_empty() {
+                let broken = match (c, n) {
+                    (Some(c), Some(n)) => distance(c, n) > MAX_PEPTIDE_BOND,
+                    _ => false,
+                };
+                if broken {
+                    report.open_gaps.push(open(
+                        Some(k),
+                        Some(next),
+                        &[],
+                        "chain break without missing-residue records",
+                    ));
+                }
+                continue;
+            }
+            let reason = if !options.loops {
+                Some("loop modelling disabled".to_string())
+            } else if names.len() > options.max_loop_length {
+                Some(format!("longer than {} residues", options.max_loop_length))
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                report
+                    .open_gaps
+                    .push(open(Some(k), Some(next), &names, &reason));
+                continue;
+            }
+            let span = distance(
+                residues[k].coords("CA").unwrap_or_default(),
+                residues[next].coords("CA").unwrap_or_default(),
+            );
+            let center = residues[k].coords("CA").unwrap_or_default();
+            let environment = environment(residues, &[k, next], center, span + 10.0);
+            let built = (span <= 3.8 * (names.len() + 1) as f32)
+                .then(|| build_loop(&residues[k], &residues[next], &names, &environment))
+                .flatten();
+            let Some(built) = built else {
+                report.open_gaps.push(open(
+                    Some(k),
+                    Some(next),
+                    &names,
+                    "loop could not be closed",
+                ));
+                continue;
+            };
+
+            let template = &residues[k].records[0];
+            let b_factor = residues[k]
+                .records
+                .iter()
+                .chain(&residues[next].records)
+                .map(|r| r.b_factor)
+                .fold(0.0, f32::max);
+            let mut new_residues = Vec::with_capacity(names.len());
+            for (m, backbone) in names.iter().zip(&built.backbone) {
+                let record = PdbAtomRecord {
+                    residue_name: m.residue_name.clone(),
+                    chain_id: m.chain_id,
+                    residue_seq: m.residue_seq,
+                    insertion_code: m.insertion_code,
+                    b_factor,
+                    ..template.clone()
+                };
+                let mut residue = Residue {
+                    atoms: Vec::new(),
+                    records: Vec::new(),
+                    source: Vec::new(),
+                    rebuilt: Some(RebuiltKind::Loop),
+                };
+                for (name, &coords) in ["N", "CA", "C", "O"].iter().zip(backbone) {
+                    residue.push(name, coords, &record);
+                }
+                new_residues.push(residue);
+            }
+            report.regions.push(RebuiltRegion {
+                kind: RebuiltKind::Loop,
+                chain_id: chain,
+                residues: (names[0].residue_seq, names[names.len() - 1].residue_seq),
+                residue_names: names.iter().map(|m| m.residue_name.clone()).collect(),
+                atoms: 4 * names.len(),
+                chi: Vec::new(),
+                closure: Some(built.closure),
+            });
+            insertions.push((k, new_residues, built.anchor_o));
+        }
+        let tail: Vec<&MissingResidue> = listed
+            .iter()
+            .copied()
+            .filter(|m| (m.residue_seq, m.insertion_code) > key(last))
+            .collect();
+        if !tail.is_empty() {
+            report
+                .open_gaps
+                .push(open(Some(last), None, &tail, "C-terminal"));
+        }
+    }
+
+    insertions.sort_by_key(|i| i.0);
+    for (k, new_residues, anchor_o) in insertions.into_iter().rev() {
+        let before = &mut residues[k];
+        if let Some(o) = before.records.iter().position(|r| r.name == "O") {
+            before.atoms[o].coords = anchor_o;
+        }
+        residues.splice(k + 1..k + 1, new_residues);
+    }
+}
+
+/// Rebuild missing side chains and short missing loops of `structure`
+/// (heavy atoms only; run before adding hydrogens)
+pub fn repair_gaps(
+    structure: &PdbStructure,
+    options: &GapRepairOptions,
+) -> Result<(PdbStructure, GapRepairReport)> {
+    let mut residues: Vec<Residue> = Vec::new();
+    for (i, (atom, record)) in structure.atoms.iter().zip(&structure.records).enumerate() {
+        if i == 0 || structure.atoms[i - 1].residue_id != atom.residue_id {
+            residues.push(Residue {
+                atoms: Vec::new(),
+                records: Vec::new(),
+                source: Vec::new(),
+                rebuilt: None,
+            });
+        }
+        let residue = residues.last_mut().expect("residue pushed above");
+        residue.atoms.push(*atom);
+        residue.records.push(record.clone());
+        residue.source.push(Some(i));
+    }
+
+    let mut report = GapRepairReport::default();
+    repair_loops(
+        &mut residues,
+        &structure.missing_residues,
+        options,
+        &mut report,
+    );
+
+    // Side chains in chain order; loop residues always get theirs, counted
+    // in their loop region
+    for k in 0..residues.len() {
+        let loop_residue = residues[k].rebuilt == Some(RebuiltKind::Loop);
+        if !(options.side_chains || loop_residue) || !residues[k].is_polymer() {
+            continue;
+        }
+        let Some((chi, atoms)) = repair_side_chain(&mut residues, k) else {
+            continue;
+        };
+        let first = residues[k].first();
+        if loop_residue {
+            let region = report.regions.iter_mut().find(|r| {
+                r.chain_id == first.chain_id
+                    && (r.residues.0..=r.residues.1).contains(&first.residue_seq)
+            });
+            if let Some(region) = region {
+                region.atoms += atoms;
+            }
+        } else {
+            report.regions.push(RebuiltRegion {
+                kind: RebuiltKind::SideChain,
+                chain_id: first.chain_id,
+                residues: (first.residue_seq, first.residue_seq),
+                residue_names: vec![first.residue_name.clone()],
+                atoms,
+                chi,
+                closure: None,
+            });
+        }
+    }
+    let mut position: HashMap<(char, i32), usize> = HashMap::new();
+    for (k, residue) in residues.iter().enumerate() {
+        let first = residue.first();
+        position
+            .entry((first.chain_id, first.residue_seq))
+            .or_insert(k);
+    }
+    report
+        .regions
+        .sort_by_key(|r| position.get(&(r.chain_id, r.residues.0)).copied());
+
+    if residues.len() > u16::MAX as usize + 1 {
+        return Err(PrismIoError::FormatError(
+            "Residue count exceeds the 16-bit residue index".to_string(),
+        ));
+    }
+    let mut out = PdbStructure {
+        cryst1: structure.cryst1,
+        altlocs: structure.altlocs.clone(),
+        ..Default::default()
+    };
+    let mut index = vec![0u32; structure.atoms.len()];
+    for (k, residue) in residues.into_iter().enumerate() {
+        if let Some(kind) = residue.rebuilt {
+            let placed = residue.source.iter().filter(|s| s.is_none()).count();
+            report
+                .rebuilt_residues
+                .push(RebuiltResidue::new(k as u32, kind, placed as u32));
+        }
+        for ((mut atom, mut record), source) in residue
+            .atoms
+            .into_iter()
+            .zip(residue.records)
+            .zip(residue.source)
+        {
+            if let Some(source) = source {
+                index[source] = out.atoms.len() as u32;
+            }
+            atom.residue_id = k as u16;
+            record.serial = out.atoms.len() as u32 + 1;
+            out.atoms.push(atom);
+            out.records.push(record);
+        }
+    }
+    // Residues modelled here are no longer missing
+    out.missing_residues = structure
+        .missing_residues
+        .iter()
+        .filter(|m| {
+            !out.records.iter().any(|r| {
+                r.chain_id == m.chain_id
+                    && r.residue_seq == m.residue_seq
+                    && r.insertion_code == m.insertion_code
+            })
+        })
+        .cloned()
+        .collect();
+    out.conect = structure
+        .conect
+        .iter()
+        .map(|&(a, b)| (index[a as usize], index[b as usize]))
+        .collect();
+    Ok((out, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdb::parse_pdb;
+    use crate::perception::CovalentTopology;
+
+    /// Ala-Asp-Lys with all heavy atoms
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   1.458   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       0.000   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       1.404  -0.307   0.511  1.00  0.00           C
+ATOM      4  O   ALA A   1       2.385   0.235   0.005  1.00  0.00           O
+ATOM      5  CB  ALA A   1      -0.312  -0.536  -1.399  1.00  0.00           C
+ATOM      6  N   ASP A   2       1.486  -1.176   1.514  1.00  0.00           N
+ATOM      7  CA  ASP A   2       2.768  -1.556   2.095  1.00  0.00           C
+ATOM      8  C   ASP A   2       3.012  -3.056   1.962  1.00  0.00           C
+ATOM      9  O   ASP A   2       2.150  -3.862   2.309  1.00  0.00           O
+ATOM     10  CB  ASP A   2       2.834  -1.142   3.567  1.00  0.00           C
+ATOM     11  CG  ASP A   2       4.150  -1.512   4.230  1.00  0.00           C
+ATOM     12  OD1 ASP A   2       5.013  -2.094   3.538  1.00  0.00           O
+ATOM     13  OD2 ASP A   2       4.304  -1.216   5.435  1.00  0.00           O
+ATOM     14  N   LYS A   3       4.188  -3.417   1.457  1.00  0.00           N
+ATOM     15  CA  LYS A   3       4.546  -4.819   1.277  1.00  0.00           C
+ATOM     16  C   LYS A   3       5.792  -5.177   2.080  1.00  0.00           C
+ATOM     17  O   LYS A   3       6.784  -4.427   1.954  1.00  0.00           O
+ATOM     18  CB  LYS A   3       4.768  -5.129  -0.205  1.00  0.00           C
+ATOM     19  CG  LYS A   3       5.147  -6.574  -0.484  1.00  0.00           C
+ATOM     20  CD  LYS A   3       5.351  -6.806  -1.973  1.00  0.00           C
+ATOM     21  CE  LYS A   3       5.729  -8.251  -2.252  1.00  0.00           C
+ATOM     22  NZ  LYS A   3       5.929  -8.479  -3.711  1.00  0.00           N
+ATOM     23  OXT LYS A   3       5.728  -6.194   2.803  1.00  0.00           O
+";
+
+    /// Eight residues with GLY 4 and SER 5 unobserved, MET 0 N-terminal
+    const LOOP: &str = "\
+REMARK 465   M RES C SSSEQI
+REMARK 465     MET A     0
+REMARK 465     GLY A     4
+REMARK 465     SER A     5
+ATOM      1  N   ALA A   1       0.000   1.458   0.000  1.00 10.00           N
+ATOM      2  CA  ALA A   1       0.000   0.000   0.000  1.00 10.00           C
+ATOM      3  C   ALA A   1       1.404  -0.307   0.511  1.00 10.00           C
+ATOM      4  O   ALA A   1       1.580  -1.167   1.374  1.00 10.00           O
+ATOM      5  CB  ALA A   1      -0.312  -0.536  -1.399  1.00 10.00           C
+ATOM      6  N   GLY A   2       2.392   0.401  -0.026  1.00 10.00           N
+ATOM      7  CA  GLY A   2       3.780   0.206   0.374  1.00 10.00           C
+ATOM      8  C   GLY A   2       4.051   0.823   1.743  1.00 10.00           C
+ATOM      9  O   GLY A   2       3.485   1.862   2.083  1.00 10.00           O
+ATOM     10  N   ALA A   3       4.916   0.176   2.517  1.00 10.00           N
+ATOM     11  CA  ALA A   3       5.263   0.659   3.848  1.00 10.00           C
+ATOM     12  C   ALA A   3       5.989   1.998   3.777  1.00 10.00           C
+ATOM     13  O   ALA A   3       5.746   2.885   4.595  1.00 10.00           O
+ATOM     14  CB  ALA A   3       6.126  -0.369   4.583  1.00 10.00           C
+ATOM     15  N   ALA A   6      11.396   1.552   3.231  1.00 10.00           N
+ATOM     16  CA  ALA A   6      12.557   1.117   2.464  1.00 10.00           C
+ATOM     17  C   ALA A   6      12.881   2.104   1.347  1.00 10.00           C
+ATOM     18  O   ALA A   6      12.001   2.487   0.576  1.00 10.00           O
+ATOM     19  CB  ALA A   6      12.323  -0.278   1.881  1.00 10.00           C
+ATOM     20  N   GLY A   7      14.145   2.508   1.270  1.00 10.00           N
+ATOM     21  CA  GLY A   7      14.587   3.450   0.248  1.00 10.00           C
+ATOM     22  C   GLY A   7      14.577   2.808  -1.135  1.00 10.00           C
+ATOM     23  O   GLY A   7      14.951   1.645  -1.287  1.00 10.00           O
+ATOM     24  N   ALA A   8      14.148   3.574  -2.134  1.00 10.00           N
+ATOM     25  CA  ALA A   8      14.089   3.082  -3.505  1.00 10.00           C
+ATOM     26  C   ALA A   8      15.469   2.661  -4.000  1.00 10.00           C
+ATOM     27  O   ALA A   8      15.617   1.603  -4.610  1.00 10.00           O
+ATOM     28  CB  ALA A   8      13.499   4.147  -4.431  1.00 10.00           C
+ATOM     29  OXT ALA A   8      16.419   3.435  -3.757  1.00 10.00           O
+";
+
+    fn coords(structure: &PdbStructure, seq: i32, name: &str) -> [f32; 3] {
+        let i = structure
+            .records
+            .iter()
+            .position(|r| r.residue_seq == seq && r.name == name)
+            .unwrap();
+        structure.atoms[i].coords
+    }
+
+    /// Closest approach of the atoms of residue `seq` to residues at least
+    /// two positions away
+    fn closest_contact(structure: &PdbStructure, seq: i32) -> f32 {
+        let mut closest = f32::INFINITY;
+        for (a, ra) in structure.atoms.iter().zip(&structure.records) {
+            for (b, rb) in structure.atoms.iter().zip(&structure.records) {
+                if ra.residue_seq == seq && (rb.residue_seq - seq).abs() >= 2 {
+                    closest = closest.min(distance(a.coords, b.coords));
+                }
+            }
+        }
+        closest
+    }
+
+    #[test]
+    fn test_side_chains_are_rebuilt() {
+        let full = parse_pdb(PEPTIDE).unwrap();
+        let truncated: String = PEPTIDE
+            .lines()
+            .filter(|l| {
+                !matches!(
+                    (&l[17..20], l[12..16].trim()),
+                    ("ALA", "CB") | ("ASP", "CG" | "OD1" | "OD2") | ("LYS", "CD" | "CE" | "NZ")
+                )
+            })
+            .map(|l| format!("{}\n", l))
+            .collect();
+        let structure = parse_pdb(&truncated).unwrap();
+        let (repaired, report) = repair_gaps(&structure, &GapRepairOptions::default()).unwrap();
+        assert_eq!(repaired.atoms.len(), full.atoms.len());
+        assert_eq!(report.atoms_rebuilt(), 7);
+        let atoms: Vec<usize> = report.regions.iter().map(|r| r.atoms).collect();
+        assert_eq!(atoms, vec![1, 3, 3]);
+        assert!(report
+            .regions
+            .iter()
+            .all(|r| r.kind == RebuiltKind::SideChain));
+        assert_eq!(
+            report.rebuilt_residues,
+            vec![
+                RebuiltResidue::new(0, RebuiltKind::SideChain, 1),
+                RebuiltResidue::new(1, RebuiltKind::SideChain, 3),
+                RebuiltResidue::new(2, RebuiltKind::SideChain, 3),
+            ]
+        );
+
+        // CB from the backbone is the L-amino acid CB; the χ1 of the
+        // truncated Lys is kept
+        assert!(distance(coords(&repaired, 1, "CB"), coords(&full, 1, "CB")) < 0.05);
+        let lys_chi1 = dihedral(
+            coords(&full, 3, "N"),
+            coords(&full, 3, "CA"),
+            coords(&full, 3, "CB"),
+            coords(&full, 3, "CG"),
+        );
+        assert!((report.regions[2].chi[0] - lys_chi1).abs() < 0.1);
+        for (atom, record) in repaired.atoms.iter().zip(&repaired.records) {
+            let rebuilt = record.occupancy == 0.0;
+            let original = full
+                .records
+                .iter()
+                .position(|r| r.residue_seq == record.residue_seq && r.name == record.name);
+            assert!(original.is_some(), "{}", record.name);
+            assert_eq!(
+                rebuilt,
+                structure
+                    .records
+                    .iter()
+                    .all(|r| { r.residue_seq != record.residue_seq || r.name != record.name })
+            );
+            assert_eq!(atom.element, full.atoms[original.unwrap()].element);
+        }
+        // Covalent geometry: the same bonds as the complete peptide
+        let bonds = |s: &PdbStructure| CovalentTopology::from_structure(s).bonds.len();
+        assert_eq!(bonds(&repaired), bonds(&full));
+        assert!(closest_contact(&repaired, 3) > 2.8);
+
+        let (_, nothing) = repair_gaps(&full, &GapRepairOptions::default()).unwrap();
+        assert!(nothing.regions.is_empty() && nothing.rebuilt_residues.is_empty());
+    }
+
+    #[test]
+    fn test_short_loop_is_closed() {
+        let structure = parse_pdb(LOOP).unwrap();
+        let (repaired, report) = repair_gaps(&structure, &GapRepairOptions::default()).unwrap();
+        let sequence: Vec<(i32, &str)> = repaired
+            .records
+            .iter()
+            .filter(|r| r.name == "CA")
+            .map(|r| (r.residue_seq, r.residue_name.as_str()))
+            .collect();
+        assert_eq!(
+            sequence,
+            vec![
+                (1, "ALA"),
+                (2, "GLY"),
+                (3, "ALA"),
+                (4, "GLY"),
+                (5, "SER"),
+                (6, "ALA"),
+                (7, "GLY"),
+                (8, "ALA")
+            ]
+        );
+        assert_eq!(report.regions.len(), 1);
+        let region = &report.regions[0];
+        assert_eq!((region.kind, region.residues), (RebuiltKind::Loop, (4, 5)));
+        assert_eq!(region.atoms, 4 + 6);
+        assert!(region.closure.unwrap() < LOOP_CLOSURE_TOLERANCE);
+        assert_eq!(
+            report.rebuilt_residues,
+            vec![
+                RebuiltResidue::new(3, RebuiltKind::Loop, 4),
+                RebuiltResidue::new(4, RebuiltKind::Loop, 6),
+            ]
+        );
+        assert_eq!(report.open_gaps.len(), 1);
+        assert_eq!(report.open_gaps[0].reason, "N-terminal");
+        assert_eq!(repaired.missing_residues.len(), 1);
+        assert!(repaired
+            .atoms
+            .windows(2)
+            .all(|w| w[0].residue_id <= w[1].residue_id));
+
+        // Ideal loop bonds, peptide bonds within the closure tolerance and no
+        // clashes with the rest of the chain
+        for seq in 4..=5 {
+            assert!(
+                (distance(coords(&repaired, seq, "N"), coords(&repaired, seq, "CA")) - N_CA).abs()
+                    < 1e-3
+            );
+            assert!(
+                (distance(coords(&repaired, seq, "CA"), coords(&repaired, seq, "C")) - CA_C).abs()
+                    < 1e-3
+            );
+            assert!(closest_contact(&repaired, seq) > 2.5);
+        }
+        for seq in 1..8 {
+            let peptide = distance(coords(&repaired, seq, "C"), coords(&repaired, seq + 1, "N"));
+            assert!(
+                (peptide - C_N).abs() < LOOP_CLOSURE_TOLERANCE,
+                "{} {}",
+                seq,
+                peptide
+            );
+        }
+        // GLY and SER bonds and the three peptide bonds spanning the gap
+        assert_eq!(
+            CovalentTopology::from_structure(&repaired).bonds.len(),
+            CovalentTopology::from_structure(&structure).bonds.len() + 3 + 5 + 3
+        );
+
+        let options = GapRepairOptions {
+            max_loop_length: 1,
+            ..Default::default()
+        };
+        let (unchanged, report) = repair_gaps(&structure, &options).unwrap();
+        assert_eq!(unchanged.atoms.len(), structure.atoms.len());
+        assert_eq!(report.open_gaps[1].reason, "longer than 1 residues");
+        assert_eq!(report.open_gaps[1].residue_names, vec!["GLY", "SER"]);
+        assert_eq!(
+            (report.open_gaps[1].after, report.open_gaps[1].before),
+            (Some(3), Some(6))
+        );
+    }
+}
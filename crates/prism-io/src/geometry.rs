@@ -0,0 +1,97 @@
+//! Vector helpers for placing atoms from internal coordinates
+
+pub(crate) fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub(crate) fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub(crate) fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub(crate) fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub(crate) fn norm(a: [f32; 3]) -> f32 {
+    (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt()
+}
+
+pub(crate) fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    norm(sub(a, b))
+}
+
+pub(crate) fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let n = norm(a);
+    if n > 1e-6 {
+        scale(a, 1.0 / n)
+    } else {
+        [0.0; 3]
+    }
+}
+
+/// Any unit vector perpendicular to `a`
+pub(crate) fn perpendicular(a: [f32; 3]) -> [f32; 3] {
+    let axis = if a[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    normalize(cross(a, axis))
+}
+
+/// Position bonded to `a` at `length`, with angle `b-a-x` and torsion
+/// `r-b-a-x` in degrees
+pub(crate) fn place(
+    r: [f32; 3],
+    b: [f32; 3],
+    a: [f32; 3],
+    length: f32,
+    angle: f32,
+    torsion: f32,
+) -> [f32; 3] {
+    let (angle, torsion) = (angle.to_radians(), torsion.to_radians());
+    let bc = normalize(sub(a, b));
+    let mut n = normalize(cross(sub(b, r), bc));
+    if norm(n) == 0.0 {
+        n = perpendicular(bc);
+    }
+    let m = cross(n, bc);
+    let d = [
+        -length * angle.cos(),
+        length * angle.sin() * torsion.cos(),
+        length * angle.sin() * torsion.sin(),
+    ];
+    add(a, add(scale(bc, d[0]), add(scale(m, d[1]), scale(n, d[2]))))
+}
+
+pub(crate) fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Dihedral `a-b-c-d` in degrees
+pub(crate) fn dihedral(a: [f32; 3], b: [f32; 3], c: [f32; 3], d: [f32; 3]) -> f32 {
+    let (b0, b1, b2) = (sub(a, b), normalize(sub(c, b)), sub(d, c));
+    let v = sub(b0, scale(b1, dot(b0, b1)));
+    let w = sub(b2, scale(b1, dot(b2, b1)));
+    dot(cross(b1, v), w).atan2(dot(v, w)).to_degrees()
+}
+
+/// `x` rotated by `angle` radians about the axis through `origin` along
+/// the unit vector `axis`
+pub(crate) fn rotate(x: [f32; 3], origin: [f32; 3], axis: [f32; 3], angle: f32) -> [f32; 3] {
+    let v = sub(x, origin);
+    let (sin, cos) = angle.sin_cos();
+    let rotated = add(
+        add(scale(v, cos), scale(cross(axis, v), sin)),
+        scale(axis, dot(axis, v) * (1.0 - cos)),
+    );
+    add(origin, rotated)
+}
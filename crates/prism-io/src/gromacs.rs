@@ -0,0 +1,925 @@
+//! # GROMACS Topology Import (.top / .gro)
+//!
+//! Reads GROMACS topologies (with `#include`, `#define` and `#ifdef`
+//! preprocessing) and `.gro` coordinate files into the internal [`Topology`].
+//!
+//! ## Notes
+//! - Units are converted from nm / kJ·mol⁻¹ to Å / kcal·mol⁻¹
+//! - GROMACS harmonic terms carry a factor ½ which is folded into the force constant
+//! - Combination rule 1 (C6/C12) is converted to per-atom sigma/epsilon;
+//!   rules 2 and 3 are both evaluated with Lorentz-Berthelot mixing
+//! - Ryckaert-Bellemans torsions (type 3) are expanded into periodic terms
+//! - Bonded parameters missing from a directive line are looked up in
+//!   `[ bondtypes ]`, `[ angletypes ]` and `[ dihedraltypes ]`
+//! - `#define` macros (e.g. `torsion_*`, `gb_*`) are expanded in directive lines
+//! - 1-4 pairs are generated from the atom types (`gen-pairs = yes`);
+//!   `[ pairtypes ]`, explicit pair parameters and `[ constraints ]` are rejected
+
+use crate::simulation_box::SimulationBox;
+use crate::sovereign_types::Atom;
+use crate::topology::{
+    HarmonicAngle, HarmonicBond, HarmonicImproper, LjParams, Pair14, PeriodicDihedral, Topology,
+};
+use crate::{PrismIoError, Result};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+
+/// nm → Å
+const NM_TO_ANGSTROM: f32 = 10.0;
+/// kJ/mol → kcal/mol
+const KJ_TO_KCAL: f32 = 1.0 / 4.184;
+
+/// Atom type from `[ atomtypes ]`
+#[derive(Debug, Clone)]
+struct AtomType {
+    atomic_number: Option<u8>,
+    mass: f32,
+    lj: LjParams,
+}
+
+/// One `[ atoms ]` record of a molecule type
+#[derive(Debug, Clone)]
+struct MolAtom {
+    atom_type: String,
+    resnr: i64,
+    resname: String,
+    name: String,
+    charge: f32,
+    mass: Option<f32>,
+}
+
+/// Interactions as read from a directive line (1-based local indices + parameters)
+#[derive(Debug, Clone, Default)]
+struct MoleculeType {
+    nrexcl: usize,
+    atoms: Vec<MolAtom>,
+    bonds: Vec<(Vec<usize>, u32, Vec<f32>)>,
+    pairs: Vec<[usize; 2]>,
+    angles: Vec<(Vec<usize>, u32, Vec<f32>)>,
+    dihedrals: Vec<(Vec<usize>, u32, Vec<f32>)>,
+    exclusions: Vec<Vec<usize>>,
+}
+
+/// Force-field level settings from `[ defaults ]`
+#[derive(Debug, Clone, Copy)]
+struct Defaults {
+    comb_rule: u32,
+    fudge_lj: f32,
+    fudge_qq: f32,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self {
+            comb_rule: 2,
+            fudge_lj: 0.5,
+            fudge_qq: 0.8333,
+        }
+    }
+}
+
+#[derive(Default)]
+struct TopParser {
+    defaults: Defaults,
+    atom_types: HashMap<String, AtomType>,
+    bond_types: HashMap<(String, String), (u32, Vec<f32>)>,
+    angle_types: HashMap<(String, String, String), (u32, Vec<f32>)>,
+    dihedral_types: Vec<([String; 4], u32, Vec<f32>)>,
+    molecule_types: HashMap<String, MoleculeType>,
+    molecules: Vec<(String, usize)>,
+    /// `#define` name → replacement tokens (empty for plain flags)
+    defines: HashMap<String, Vec<String>>,
+}
+
+/// Read a GROMACS `.top` file, resolving includes relative to its directory
+pub fn read_top<P: AsRef<Path>>(path: P) -> Result<Topology> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    parse_top(&content, path.parent())
+}
+
+/// Parse `.top` content; `base_dir` is used to resolve `#include` directives
+pub fn parse_top(content: &str, base_dir: Option<&Path>) -> Result<Topology> {
+    let mut parser = TopParser::default();
+    let mut lines = Vec::new();
+    parser.preprocess(content, base_dir, &mut lines, 0)?;
+    parser.parse_directives(&lines)?;
+    parser.build()
+}
+
+impl TopParser {
+    /// Expand includes and conditional blocks into a flat list of logical lines
+    fn preprocess(
+        &mut self,
+        content: &str,
+        base_dir: Option<&Path>,
+        out: &mut Vec<String>,
+        depth: usize,
+    ) -> Result<()> {
+        if depth > 16 {
+            return Err(PrismIoError::FormatError(
+                "#include nesting too deep".to_string(),
+            ));
+        }
+        // Active flag per open #ifdef/#ifndef block
+        let mut cond: Vec<bool> = Vec::new();
+        let active = |c: &[bool]| c.iter().all(|&b| b);
+
+        for raw in content.lines() {
+            let line = raw.split(';').next().unwrap_or("").trim();
+            if let Some(directive) = line.strip_prefix('#') {
+                let mut parts = directive.split_whitespace();
+                let keyword = parts.next().unwrap_or("");
+                let arg = parts.next().unwrap_or("");
+                match keyword {
+                    "ifdef" => cond.push(self.defines.contains_key(arg)),
+                    "ifndef" => cond.push(!self.defines.contains_key(arg)),
+                    "else" => {
+                        let last = cond.pop().ok_or_else(|| {
+                            PrismIoError::FormatError("#else without #ifdef".to_string())
+                        })?;
+                        cond.push(!last);
+                    }
+                    "endif" => {
+                        cond.pop().ok_or_else(|| {
+                            PrismIoError::FormatError("#endif without #ifdef".to_string())
+                        })?;
+                    }
+                    "define" if active(&cond) => {
+                        let value = parts.map(String::from).collect();
+                        self.defines.insert(arg.to_string(), value);
+                    }
+                    "undef" if active(&cond) => {
+                        self.defines.remove(arg);
+                    }
+                    "include" if active(&cond) => {
+                        let name = arg.trim_matches('"').trim_matches(|c| c == '<' || c == '>');
+                        let path = Self::resolve_include(name, base_dir)?;
+                        let text = std::fs::read_to_string(&path)?;
+                        self.preprocess(&text, path.parent(), out, depth + 1)?;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if active(&cond) && !line.is_empty() {
+                out.push(self.expand_macros(line));
+            }
+        }
+        if !cond.is_empty() {
+            return Err(PrismIoError::FormatError("Unterminated #ifdef".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Replace every whitespace-separated token that names a macro with its value
+    fn expand_macros(&self, line: &str) -> String {
+        if self.defines.values().all(|v| v.is_empty()) {
+            return line.to_string();
+        }
+        line.split_whitespace()
+            .flat_map(|token| match self.defines.get(token) {
+                Some(value) if !value.is_empty() => value.clone(),
+                _ => vec![token.to_string()],
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn resolve_include(name: &str, base_dir: Option<&Path>) -> Result<PathBuf> {
+        let mut candidates = Vec::new();
+        if let Some(dir) = base_dir {
+            candidates.push(dir.join(name));
+        }
+        if let Ok(gmxlib) = std::env::var("GMXLIB") {
+            candidates.extend(gmxlib.split(':').map(|d| Path::new(d).join(name)));
+        }
+        candidates.push(PathBuf::from(name));
+        candidates.into_iter().find(|p| p.exists()).ok_or_else(|| {
+            PrismIoError::FormatError(format!(
+                "Cannot resolve #include \"{}\" (searched topology dir and GMXLIB)",
+                name
+            ))
+        })
+    }
+
+    fn parse_directives(&mut self, lines: &[String]) -> Result<()> {
+        let mut section = String::new();
+        let mut current: Option<String> = None;
+
+        for line in lines {
+            if line.starts_with('[') {
+                section = line
+                    .trim_matches(|c| c == '[' || c == ']' || c == ' ')
+                    .to_string();
+                continue;
+            }
+            let f: Vec<&str> = line.split_whitespace().collect();
+            match section.as_str() {
+                "defaults" => {
+                    self.defaults.comb_rule = parse(f.get(1).copied().unwrap_or("2"), line)?;
+                    if f.get(2).is_some_and(|g| g.eq_ignore_ascii_case("no")) {
+                        return Err(PrismIoError::ValidationError(
+                            "gen-pairs = no is not supported; 1-4 LJ pairs are generated from atom types"
+                                .to_string(),
+                        ));
+                    }
+                    if let Some(v) = f.get(3) {
+                        self.defaults.fudge_lj = parse(v, line)?;
+                    }
+                    if let Some(v) = f.get(4) {
+                        self.defaults.fudge_qq = parse(v, line)?;
+                    }
+                }
+                "atomtypes" => self.parse_atomtype(&f, line)?,
+                "pairtypes" => {
+                    return Err(PrismIoError::ValidationError(format!(
+                        "[ pairtypes ] is not supported (1-4 LJ is scaled by fudgeLJ): {}",
+                        line
+                    )));
+                }
+                "bondtypes" if f.len() >= 3 => {
+                    let key = ordered2(f[0], f[1]);
+                    self.bond_types
+                        .insert(key, (parse(f[2], line)?, floats(&f[3..], line)?));
+                }
+                "angletypes" if f.len() >= 4 => {
+                    let key = if f[0] <= f[2] {
+                        (f[0].to_string(), f[1].to_string(), f[2].to_string())
+                    } else {
+                        (f[2].to_string(), f[1].to_string(), f[0].to_string())
+                    };
+                    self.angle_types
+                        .insert(key, (parse(f[3], line)?, floats(&f[4..], line)?));
+                }
+                "dihedraltypes" if f.len() >= 5 => {
+                    // Either 4 types or 2 (central pair / improper outer) before funct
+                    let n_types = if f[4].parse::<u32>().is_ok() { 4 } else { 2 };
+                    let funct: u32 = parse(f[n_types], line)?;
+                    let types = if n_types == 4 {
+                        [f[0], f[1], f[2], f[3]].map(String::from)
+                    } else if matches!(funct, 2 | 4) {
+                        [f[0], "X", "X", f[1]].map(String::from)
+                    } else {
+                        ["X", f[0], f[1], "X"].map(String::from)
+                    };
+                    self.dihedral_types
+                        .push((types, funct, floats(&f[n_types + 1..], line)?));
+                }
+                "moleculetype" => {
+                    let name = f[0].to_string();
+                    let nrexcl = parse(f.get(1).copied().unwrap_or("3"), line)?;
+                    self.molecule_types.insert(
+                        name.clone(),
+                        MoleculeType {
+                            nrexcl,
+                            ..Default::default()
+                        },
+                    );
+                    current = Some(name);
+                }
+                "atoms" | "bonds" | "pairs" | "angles" | "dihedrals" | "exclusions"
+                | "constraints" => {
+                    let name = current.as_ref().ok_or_else(|| {
+                        PrismIoError::FormatError(format!(
+                            "[ {} ] outside [ moleculetype ]",
+                            section
+                        ))
+                    })?;
+                    let mol = self.molecule_types.get_mut(name).ok_or_else(|| {
+                        PrismIoError::FormatError(format!("Unknown molecule type {}", name))
+                    })?;
+                    Self::parse_molecule_line(mol, &section, &f, line)?;
+                }
+                "molecules" if f.len() >= 2 => {
+                    self.molecules.push((f[0].to_string(), parse(f[1], line)?));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_atomtype(&mut self, f: &[&str], line: &str) -> Result<()> {
+        if f.len() < 6 {
+            return Err(PrismIoError::FormatError(format!(
+                "Malformed atomtype: {}",
+                line
+            )));
+        }
+        // Columns are read from the right: ... mass charge ptype V W
+        let n = f.len();
+        let (v, w): (f32, f32) = (parse(f[n - 2], line)?, parse(f[n - 1], line)?);
+        let mass: f32 = parse(f[n - 5], line)?;
+        let atomic_number = if n >= 7 {
+            f[n - 6].parse::<u8>().ok()
+        } else {
+            None
+        };
+        let lj = if self.defaults.comb_rule == 1 {
+            // V = C6, W = C12
+            if v > 0.0 && w > 0.0 {
+                LjParams {
+                    sigma: (w / v).powf(1.0 / 6.0) * NM_TO_ANGSTROM,
+                    epsilon: v * v / (4.0 * w) * KJ_TO_KCAL,
+                }
+            } else {
+                LjParams {
+                    sigma: 0.0,
+                    epsilon: 0.0,
+                }
+            }
+        } else {
+            LjParams {
+                sigma: v * NM_TO_ANGSTROM,
+                epsilon: w * KJ_TO_KCAL,
+            }
+        };
+        self.atom_types.insert(
+            f[0].to_string(),
+            AtomType {
+                atomic_number,
+                mass,
+                lj,
+            },
+        );
+        Ok(())
+    }
+
+    fn parse_molecule_line(
+        mol: &mut MoleculeType,
+        section: &str,
+        f: &[&str],
+        line: &str,
+    ) -> Result<()> {
+        let index = |s: &str| -> Result<usize> {
+            let v: usize = parse(s, line)?;
+            if v == 0 {
+                return Err(PrismIoError::FormatError(format!(
+                    "Atom index 0 in: {}",
+                    line
+                )));
+            }
+            Ok(v - 1)
+        };
+        let term = |n: usize| -> Result<(Vec<usize>, u32, Vec<f32>)> {
+            if f.len() < n + 1 {
+                return Err(PrismIoError::FormatError(format!(
+                    "Truncated [ {} ] line: {}",
+                    section, line
+                )));
+            }
+            let idx = f[..n]
+                .iter()
+                .map(|s| index(s))
+                .collect::<Result<Vec<_>>>()?;
+            Ok((idx, parse(f[n], line)?, floats(&f[n + 1..], line)?))
+        };
+        match section {
+            "atoms" => {
+                if f.len() < 7 {
+                    return Err(PrismIoError::FormatError(format!(
+                        "Malformed [ atoms ] line: {}",
+                        line
+                    )));
+                }
+                mol.atoms.push(MolAtom {
+                    atom_type: f[1].to_string(),
+                    resnr: parse(f[2], line)?,
+                    resname: f[3].to_string(),
+                    name: f[4].to_string(),
+                    charge: parse(f[6], line)?,
+                    mass: f.get(7).map(|m| parse(m, line)).transpose()?,
+                });
+            }
+            "bonds" => mol.bonds.push(term(2)?),
+            "constraints" => {
+                return Err(PrismIoError::ValidationError(format!(
+                    "[ constraints ] are not supported: {}",
+                    line
+                )));
+            }
+            "pairs" => {
+                let (idx, funct, params) = term(2)?;
+                if funct != 1 || !params.is_empty() {
+                    return Err(PrismIoError::ValidationError(format!(
+                        "Only generated 1-4 pairs (type 1, no parameters) are supported: {}",
+                        line
+                    )));
+                }
+                mol.pairs.push([idx[0], idx[1]]);
+            }
+            "angles" => mol.angles.push(term(3)?),
+            "dihedrals" => {
+                // Two-atom (improper shorthand) lines are not valid here
+                mol.dihedrals.push(term(4)?)
+            }
+            "exclusions" => {
+                mol.exclusions
+                    .push(f.iter().map(|s| index(s)).collect::<Result<Vec<_>>>()?);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn dihedral_params(&self, types: [&str; 4], funct: u32) -> Option<Vec<Vec<f32>>> {
+        let compatible = |f: u32| f == funct || (matches!(f, 1 | 9) && matches!(funct, 1 | 9));
+        for wild in [false, true] {
+            let matches: Vec<Vec<f32>> = self
+                .dihedral_types
+                .iter()
+                .filter(|(p, f, _)| {
+                    compatible(*f)
+                        && ((0..4).all(|i| p[i] == types[i] || (wild && p[i] == "X"))
+                            || (0..4).all(|i| p[i] == types[3 - i] || (wild && p[i] == "X")))
+                })
+                .map(|(_, _, v)| v.clone())
+                .collect();
+            if !matches.is_empty() {
+                // Only type 9 accumulates multiple terms
+                return Some(if funct == 9 {
+                    matches
+                } else {
+                    matches[..1].to_vec()
+                });
+            }
+        }
+        None
+    }
+
+    fn build(&self) -> Result<Topology> {
+        let mut top = Topology::default();
+        let mut residue: i64 = -1;
+
+        for (name, count) in &self.molecules {
+            let mol = self.molecule_types.get(name).ok_or_else(|| {
+                PrismIoError::FormatError(format!("[ molecules ] references unknown type {}", name))
+            })?;
+            for _ in 0..*count {
+                let offset = top.atoms.len() as u32;
+                self.append_molecule(&mut top, mol, offset, &mut residue)?;
+            }
+        }
+
+        if top.atoms.is_empty() {
+            return Err(PrismIoError::FormatError(
+                "Topology defines no atoms".to_string(),
+            ));
+        }
+        top.exclusions.sort_unstable();
+        top.exclusions.dedup();
+        Ok(top)
+    }
+
+    fn append_molecule(
+        &self,
+        top: &mut Topology,
+        mol: &MoleculeType,
+        offset: u32,
+        residue: &mut i64,
+    ) -> Result<()> {
+        let mut last_resnr = None;
+        let types: Vec<&str> = mol.atoms.iter().map(|a| a.atom_type.as_str()).collect();
+
+        for a in &mol.atoms {
+            if last_resnr != Some(a.resnr) {
+                *residue += 1;
+                last_resnr = Some(a.resnr);
+                top.residue_names.push(a.resname.clone());
+            }
+            if *residue > u16::MAX as i64 {
+                return Err(PrismIoError::FormatError(
+                    "Residue count exceeds the 16-bit residue index".to_string(),
+                ));
+            }
+            let at = self.atom_types.get(&a.atom_type).ok_or_else(|| {
+                PrismIoError::FormatError(format!("Atom type {} not in [ atomtypes ]", a.atom_type))
+            })?;
+            let mass = a.mass.unwrap_or(at.mass);
+            top.atoms.push(Atom {
+                coords: [0.0; 3],
+                element: at
+                    .atomic_number
+                    .filter(|&z| z > 0)
+                    .unwrap_or_else(|| Topology::element_from_mass(mass)),
+                residue_id: *residue as u16,
+                atom_type: 0,
+                charge: a.charge,
+                radius: if at.lj.sigma > 0.0 {
+                    at.lj.rmin_half()
+                } else {
+                    1.0
+                },
+                _reserved: [0; 4],
+            });
+            top.atom_names.push(a.name.clone());
+            top.atom_types.push(a.atom_type.clone());
+            top.masses.push(mass);
+            top.lj.push(at.lj);
+        }
+
+        let g = |i: usize| i as u32 + offset;
+        let first_bond = top.bonds.len();
+
+        for (idx, funct, params) in &mol.bonds {
+            let params = if params.len() >= 2 {
+                params.clone()
+            } else {
+                self.bond_types
+                    .get(&ordered2(types[idx[0]], types[idx[1]]))
+                    .map(|(_, p)| p.clone())
+                    .ok_or_else(|| missing("bond", &[types[idx[0]], types[idx[1]]]))?
+            };
+            if !matches!(funct, 1 | 6) {
+                return Err(unsupported("bond", *funct));
+            }
+            top.bonds.push(HarmonicBond {
+                i: g(idx[0]),
+                j: g(idx[1]),
+                k: 0.5 * params[1] * KJ_TO_KCAL / (NM_TO_ANGSTROM * NM_TO_ANGSTROM),
+                r0: params[0] * NM_TO_ANGSTROM,
+            });
+        }
+
+        // Exclusions from the bond graph of this molecule (before Urey-Bradley terms)
+        let local = Topology {
+            atoms: top.atoms[offset as usize..].to_vec(),
+            bonds: top.bonds[first_bond..]
+                .iter()
+                .map(|b| HarmonicBond {
+                    i: b.i - offset,
+                    j: b.j - offset,
+                    ..*b
+                })
+                .collect(),
+            ..Default::default()
+        };
+        for (i, j) in local.bonded_pairs_within(mol.nrexcl) {
+            top.exclusions.push((i + offset, j + offset));
+        }
+        for ex in &mol.exclusions {
+            for &other in &ex[1..] {
+                let (a, b) = (g(ex[0]), g(other));
+                top.exclusions.push((a.min(b), a.max(b)));
+            }
+        }
+
+        for (idx, funct, params) in &mol.angles {
+            let params = if params.len() >= 2 {
+                params.clone()
+            } else {
+                let (a, b, c) = (types[idx[0]], types[idx[1]], types[idx[2]]);
+                let key = if a <= c {
+                    (a.to_string(), b.to_string(), c.to_string())
+                } else {
+                    (c.to_string(), b.to_string(), a.to_string())
+                };
+                self.angle_types
+                    .get(&key)
+                    .map(|(_, p)| p.clone())
+                    .ok_or_else(|| missing("angle", &[a, b, c]))?
+            };
+            if !matches!(funct, 1 | 5) {
+                return Err(unsupported("angle", *funct));
+            }
+            top.angles.push(HarmonicAngle {
+                i: g(idx[0]),
+                j: g(idx[1]),
+                k: g(idx[2]),
+                force_constant: 0.5 * params[1] * KJ_TO_KCAL,
+                theta0: params[0].to_radians(),
+            });
+            if *funct == 5 && params.len() >= 4 && params[3] != 0.0 {
+                top.bonds.push(HarmonicBond {
+                    i: g(idx[0]),
+                    j: g(idx[2]),
+                    k: 0.5 * params[3] * KJ_TO_KCAL / (NM_TO_ANGSTROM * NM_TO_ANGSTROM),
+                    r0: params[2] * NM_TO_ANGSTROM,
+                });
+            }
+        }
+
+        for (idx, funct, params) in &mol.dihedrals {
+            let atoms = [g(idx[0]), g(idx[1]), g(idx[2]), g(idx[3])];
+            let sets = if params.is_empty() {
+                let t = [types[idx[0]], types[idx[1]], types[idx[2]], types[idx[3]]];
+                self.dihedral_params(t, *funct)
+                    .ok_or_else(|| missing("dihedral", &t))?
+            } else {
+                vec![params.clone()]
+            };
+            for p in sets {
+                match funct {
+                    1 | 4 | 9 if p.len() >= 3 => top.dihedrals.push(PeriodicDihedral {
+                        atoms,
+                        k: p[1] * KJ_TO_KCAL,
+                        periodicity: p[2],
+                        phase: p[0].to_radians(),
+                        improper: *funct == 4,
+                    }),
+                    2 if p.len() >= 2 => top.impropers.push(HarmonicImproper {
+                        atoms,
+                        k: 0.5 * p[1] * KJ_TO_KCAL,
+                        phi0: p[0].to_radians(),
+                    }),
+                    3 if p.len() >= 6 => {
+                        top.dihedrals.extend(ryckaert_bellemans(atoms, &p));
+                    }
+                    _ => return Err(unsupported("dihedral", *funct)),
+                }
+            }
+        }
+
+        let (fudge_lj, fudge_qq) = (self.defaults.fudge_lj, self.defaults.fudge_qq);
+        for &[i, j] in &mol.pairs {
+            let (a, b) = (g(i), g(j));
+            top.pairs14.push(Pair14 {
+                i: a.min(b),
+                j: a.max(b),
+                coulomb_scale: fudge_qq,
+                lj_scale: fudge_lj,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Expand `Σ Cn cos^n(φ - 180°)` into periodic terms (constant offset dropped)
+fn ryckaert_bellemans(atoms: [u32; 4], c: &[f32]) -> Vec<PeriodicDihedral> {
+    let c: Vec<f32> = c.iter().take(6).map(|v| v * KJ_TO_KCAL).collect();
+    // cos^n(ψ) expressed as Fourier coefficients of cos(mψ), m = 1..5
+    let a = [
+        c[1] + 0.75 * c[3] + 0.625 * c[5],
+        0.5 * c[2] + 0.5 * c[4],
+        0.25 * c[3] + 0.3125 * c[5],
+        0.125 * c[4],
+        0.0625 * c[5],
+    ];
+    a.iter()
+        .enumerate()
+        .filter(|(_, &k)| k != 0.0)
+        .map(|(m, &k)| {
+            let n = (m + 1) as f32;
+            // a cos(n(φ - π)) = a cos(nφ - nπ); fold the phase into [0, 2π)
+            PeriodicDihedral {
+                atoms,
+                k,
+                periodicity: n,
+                phase: (n * PI).rem_euclid(2.0 * PI),
+                improper: false,
+            }
+        })
+        .collect()
+}
+
+fn ordered2(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+fn parse<T: std::str::FromStr>(s: &str, line: &str) -> Result<T> {
+    s.parse()
+        .map_err(|_| PrismIoError::FormatError(format!("Invalid value '{}' in: {}", s, line)))
+}
+
+fn floats(f: &[&str], line: &str) -> Result<Vec<f32>> {
+    f.iter().map(|s| parse(s, line)).collect()
+}
+
+fn missing(kind: &str, types: &[&str]) -> PrismIoError {
+    PrismIoError::FormatError(format!(
+        "No {} parameters for {} in topology or included force field",
+        kind,
+        types.join("-")
+    ))
+}
+
+fn unsupported(kind: &str, funct: u32) -> PrismIoError {
+    PrismIoError::FormatError(format!("Unsupported {} function type {}", kind, funct))
+}
+
+/// Coordinates and box from a `.gro` file, converted to Å
+#[derive(Debug, Clone, Default)]
+pub struct GroData {
+    /// Title line
+    pub title: String,
+    /// Flat `[x0, y0, z0, x1, ...]` coordinates (Å)
+    pub coordinates: Vec<f32>,
+    /// Flat velocities (Å/ps), if present
+    pub velocities: Option<Vec<f32>>,
+    /// Periodic cell, if present
+    pub simulation_box: Option<SimulationBox>,
+    /// Atom names
+    pub atom_names: Vec<String>,
+    /// Residue name per atom
+    pub residue_names: Vec<String>,
+}
+
+/// Read a `.gro` file
+pub fn read_gro<P: AsRef<Path>>(path: P) -> Result<GroData> {
+    parse_gro(&std::fs::read_to_string(path)?)
+}
+
+/// Parse `.gro` content (fixed columns, precision detected from the first atom)
+pub fn parse_gro(content: &str) -> Result<GroData> {
+    let mut lines = content.lines();
+    let title = lines.next().unwrap_or("").trim().to_string();
+    let natom: usize = lines
+        .next()
+        .and_then(|l| l.trim().parse().ok())
+        .ok_or_else(|| PrismIoError::FormatError("Invalid .gro atom count".to_string()))?;
+
+    let mut data = GroData {
+        title,
+        ..Default::default()
+    };
+    let mut velocities = Vec::new();
+    let mut width = 8;
+    for n in 0..natom {
+        let line = lines.next().ok_or_else(|| {
+            PrismIoError::FormatError(format!(".gro truncated at atom {}", n + 1))
+        })?;
+        if n == 0 {
+            // Field width = distance between the first two decimal points
+            let dots: Vec<usize> = line
+                .char_indices()
+                .skip(20)
+                .filter(|&(_, c)| c == '.')
+                .map(|(i, _)| i)
+                .take(2)
+                .collect();
+            if dots.len() == 2 {
+                width = dots[1] - dots[0];
+            }
+        }
+        let field = |start: usize, len: usize| line.get(start..start + len).unwrap_or("").trim();
+        data.residue_names.push(field(5, 5).to_string());
+        data.atom_names.push(field(10, 5).to_string());
+        for d in 0..3 {
+            let v: f32 = parse(field(20 + d * width, width), line)?;
+            data.coordinates.push(v * NM_TO_ANGSTROM);
+        }
+        let vstart = 20 + 3 * width;
+        if line.len() >= vstart + 3 * width {
+            for d in 0..3 {
+                let v: f32 = parse(field(vstart + d * width, width), line)?;
+                velocities.push(v * NM_TO_ANGSTROM);
+            }
+        }
+    }
+    if velocities.len() == natom * 3 && natom > 0 {
+        data.velocities = Some(velocities);
+    }
+    if let Some(b) = lines.next() {
+        let v = floats(&b.split_whitespace().collect::<Vec<_>>(), b)?;
+        let v: Vec<f32> = v.iter().map(|x| x * NM_TO_ANGSTROM).collect();
+        // v1(x) v2(y) v3(z) [v1(y) v1(z) v2(x) v2(z) v3(x) v3(y)]
+        data.simulation_box = match v.as_slice() {
+            [ax, by, cz, _ay, _az, bx, _bz, cx, cy, ..] => Some(SimulationBox::triclinic([
+                [*ax, 0.0, 0.0],
+                [*bx, *by, 0.0],
+                [*cx, *cy, *cz],
+            ])?),
+            [ax, by, cz, ..] => Some(SimulationBox::orthorhombic([*ax, *by, *cz])),
+            _ => None,
+        };
+    }
+    Ok(data)
+}
+
+/// Load a `.top`/`.gro` pair into a fully-populated [`Topology`]
+pub fn load_gromacs<P: AsRef<Path>, Q: AsRef<Path>>(top: P, gro: Q) -> Result<Topology> {
+    let mut topology = read_top(top)?;
+    let gro = read_gro(gro)?;
+    topology.set_coordinates(&gro.coordinates)?;
+    if gro.simulation_box.is_some() {
+        topology.simulation_box = gro.simulation_box;
+    }
+    Ok(topology)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOP: &str = "; butane-like test\n\
+[ defaults ]\n1 2 yes 0.5 0.8333\n\n\
+[ atomtypes ]\nCT 6 12.011 0.0 A 0.339967 0.45773\n\n\
+[ bondtypes ]\nCT CT 1 0.1526 259408.0\n\n\
+[ dihedraltypes ]\nCT CT CT CT 3 9.28 12.16 -13.12 -3.06 26.24 -31.5\n\n\
+[ moleculetype ]\nBUT 3\n\n\
+[ atoms ]\n\
+1 CT 1 BUT C1 1 -0.1 12.011\n\
+2 CT 1 BUT C2 1  0.1 12.011\n\
+3 CT 1 BUT C3 1  0.1 12.011\n\
+4 CT 1 BUT C4 1 -0.1 12.011\n\n\
+[ bonds ]\n1 2 1\n2 3 1\n3 4 1\n\n\
+[ pairs ]\n1 4 1\n\n\
+[ angles ]\n1 2 3 1 112.7 488.273\n2 3 4 1 112.7 488.273\n\n\
+[ dihedrals ]\n1 2 3 4 3\n\n\
+#ifdef POSRES\n[ position_restraints ]\n1 1 1000 1000 1000\n#endif\n\n\
+[ system ]\nTest\n\n[ molecules ]\nBUT 2\n";
+
+    #[test]
+    fn test_parse_top() {
+        let top = parse_top(TOP, None).unwrap();
+        assert_eq!(top.num_atoms(), 8);
+        assert_eq!(top.residue_names, vec!["BUT", "BUT"]);
+        assert_eq!(top.atoms[4].residue_id, 1);
+        assert_eq!(top.bonds.len(), 6);
+        assert_eq!((top.bonds[3].i, top.bonds[3].j), (4, 5));
+        assert!((top.bonds[0].r0 - 1.526).abs() < 1e-5);
+        // 259408 kJ/mol/nm² / 2 / 4.184 / 100 = 310.0 kcal/mol/Å²
+        assert!((top.bonds[0].k - 310.0).abs() < 0.1);
+        assert!((top.lj[0].epsilon - 0.1094).abs() < 1e-4);
+        assert_eq!(top.pairs14.len(), 2);
+        assert!((top.pairs14[0].lj_scale - 0.5).abs() < 1e-6);
+        // nrexcl = 3 on a 4-atom chain: all 6 pairs of each copy excluded
+        assert_eq!(top.exclusions.len(), 12);
+        assert!(!top.dihedrals.is_empty());
+    }
+
+    #[test]
+    fn test_define_macros_expand_in_directives() {
+        let top = TOP
+            .replace(
+                "[ defaults ]",
+                "#define gb_cc 0.1526 259408.0\n#define torsion_CCCC 0.0 4.6 3\n[ defaults ]",
+            )
+            .replace("[ bonds ]\n1 2 1\n", "[ bonds ]\n1 2 1 gb_cc\n")
+            .replace("1 2 3 4 3\n", "1 2 3 4 9 torsion_CCCC\n");
+        let top = parse_top(&top, None).unwrap();
+        assert!((top.bonds[0].r0 - 1.526).abs() < 1e-5);
+        assert!((top.bonds[0].k - 310.0).abs() < 0.1);
+        assert_eq!(top.dihedrals.len(), 2);
+        assert!((top.dihedrals[0].k - 4.6 * KJ_TO_KCAL).abs() < 1e-6);
+        assert_eq!(top.dihedrals[0].periodicity, 3.0);
+    }
+
+    #[test]
+    fn test_unsupported_pair_and_constraint_directives_rejected() {
+        let constraints = TOP.replace("[ pairs ]", "[ constraints ]\n1 3 1 0.25\n\n[ pairs ]");
+        let pairtypes = TOP.replace(
+            "[ bondtypes ]",
+            "[ pairtypes ]\nCT CT 1 0.3 0.2\n\n[ bondtypes ]",
+        );
+        let explicit_pair = TOP.replace("[ pairs ]\n1 4 1\n", "[ pairs ]\n1 4 1 0.3 0.2\n");
+        let no_gen_pairs = TOP.replace("1 2 yes 0.5", "1 2 no 0.5");
+        for content in [constraints, pairtypes, explicit_pair, no_gen_pairs] {
+            assert!(matches!(
+                parse_top(&content, None),
+                Err(PrismIoError::ValidationError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_ryckaert_bellemans_expansion_matches_energy() {
+        let c = [9.28, 12.16, -13.12, -3.06, 26.24, -31.5];
+        let terms = ryckaert_bellemans([0, 1, 2, 3], &c);
+        let rb = |phi: f32| -> f32 {
+            let psi = phi - PI;
+            (0..6)
+                .map(|n| c[n] * KJ_TO_KCAL * psi.cos().powi(n as i32))
+                .sum()
+        };
+        let periodic = |phi: f32| -> f32 {
+            terms
+                .iter()
+                .map(|t| t.k * (1.0 + (t.periodicity * phi - t.phase).cos()))
+                .sum()
+        };
+        // Expansion matches up to a constant offset
+        let offset = rb(0.0) - periodic(0.0);
+        for phi in [0.3f32, 1.1, 2.0, -2.5] {
+            assert!((rb(phi) - periodic(phi) - offset).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_parse_gro() {
+        let gro = "Test\n    2\n    1BUT     C1    1   0.126   0.639   0.322  0.1000 -0.2000  0.3000\n    1BUT     C2    2   0.187   0.713   0.394  0.0000  0.0000  0.0000\n   3.00000   3.00000   3.00000\n";
+        let data = parse_gro(gro).unwrap();
+        assert_eq!(data.atom_names, vec!["C1", "C2"]);
+        assert!((data.coordinates[0] - 1.26).abs() < 1e-5);
+        assert!((data.velocities.unwrap()[1] + 2.0).abs() < 1e-5);
+        assert_eq!(
+            data.simulation_box,
+            Some(SimulationBox::orthorhombic([30.0, 30.0, 30.0]))
+        );
+
+        // Rhombic dodecahedron (xy-square), 9-value box line
+        let gro = gro.replace(
+            "   3.00000   3.00000   3.00000\n",
+            "   4.00000   4.00000   2.82843   0.00000   0.00000   0.00000   0.00000   2.00000   2.00000\n",
+        );
+        let cell = parse_gro(&gro).unwrap().simulation_box.unwrap();
+        assert!(!cell.is_orthorhombic());
+        assert_eq!(cell.vectors()[2], [20.0, 20.0, 28.2843]);
+    }
+}
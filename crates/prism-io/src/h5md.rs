@@ -0,0 +1,904 @@
+//! # H5MD Trajectory Format (HDF5 for Molecular Data)
+//!
+//! One self-describing HDF5 file holding positions, velocities, box
+//! vectors, energies and analysis observables, readable with `h5py`,
+//! MDAnalysis and any HDF5 tool.
+//!
+//! ## Layout (H5MD 1.1)
+//! - `/h5md`: `version = [1, 1]`, `author` and `creator` groups, `units`
+//!   module
+//! - `/particles/all/box`: `dimension = 3`, `boundary`, and for periodic
+//!   systems time-dependent `edges` holding the three box vectors (Å)
+//! - `/particles/all/position`, `/particles/all/velocity`: `step`, `time`
+//!   (ps) and `value` of shape `(frames, atoms, 3)` (Å, Å/ps)
+//! - `/observables/<name>`: `step`, `time` and scalar `value` per record
+//!
+//! ## Encoding
+//! The file is written with the HDF5 1.8 format (version 2 superblock and
+//! object headers, compact link storage, contiguous datasets), which needs
+//! no B-trees. Frames are spooled to `<file>.spool` while the run goes on
+//! and the HDF5 file is assembled on every flush, so it is complete after
+//! each run; the spool is removed when the writer is dropped.
+
+use crate::trajectory::{TrajectoryFrame, TrajectoryWriter};
+use crate::{PrismIoError, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// HDF5 format signature
+const SIGNATURE: [u8; 8] = [0x89, b'H', b'D', b'F', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Undefined address
+const UNDEFINED: u64 = u64::MAX;
+
+/// Size of the version 2 superblock
+const SUPERBLOCK_SIZE: u64 = 48;
+
+/// H5MD version written to `/h5md`
+pub const H5MD_VERSION: [i64; 2] = [1, 1];
+
+/// Particle group of the engine's atoms
+const PARTICLE_GROUP: &str = "all";
+
+/// Jenkins' lookup3 `hashlittle`, the HDF5 metadata checksum
+pub fn lookup3(key: &[u8], initval: u32) -> u32 {
+    let mut a = 0xdead_beef_u32
+        .wrapping_add(key.len() as u32)
+        .wrapping_add(initval);
+    let (mut b, mut c) = (a, a);
+    let word = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .enumerate()
+            .fold(0u32, |w, (i, &byte)| w | (byte as u32) << (8 * i))
+    };
+    let mut rest = key;
+    while rest.len() > 12 {
+        a = a.wrapping_add(word(&rest[0..4]));
+        b = b.wrapping_add(word(&rest[4..8]));
+        c = c.wrapping_add(word(&rest[8..12]));
+        a = a.wrapping_sub(c) ^ c.rotate_left(4);
+        c = c.wrapping_add(b);
+        b = b.wrapping_sub(a) ^ a.rotate_left(6);
+        a = a.wrapping_add(c);
+        c = c.wrapping_sub(b) ^ b.rotate_left(8);
+        b = b.wrapping_add(a);
+        a = a.wrapping_sub(c) ^ c.rotate_left(16);
+        c = c.wrapping_add(b);
+        b = b.wrapping_sub(a) ^ a.rotate_left(19);
+        a = a.wrapping_add(c);
+        c = c.wrapping_sub(b) ^ b.rotate_left(4);
+        b = b.wrapping_add(a);
+        rest = &rest[12..];
+    }
+    if rest.is_empty() {
+        return c;
+    }
+    let mut tail = [0u8; 12];
+    tail[..rest.len()].copy_from_slice(rest);
+    a = a.wrapping_add(word(&tail[0..4]));
+    b = b.wrapping_add(word(&tail[4..8]));
+    c = c.wrapping_add(word(&tail[8..12]));
+    c = (c ^ b).wrapping_sub(b.rotate_left(14));
+    a = (a ^ c).wrapping_sub(c.rotate_left(11));
+    b = (b ^ a).wrapping_sub(a.rotate_left(25));
+    c = (c ^ b).wrapping_sub(b.rotate_left(16));
+    a = (a ^ c).wrapping_sub(c.rotate_left(4));
+    b = (b ^ a).wrapping_sub(a.rotate_left(14));
+    (c ^ b).wrapping_sub(b.rotate_left(24))
+}
+
+/// Element type of a dataset or attribute
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Datatype {
+    F32,
+    F64,
+    I64,
+    /// Null-terminated ASCII string of this many bytes
+    Str(usize),
+}
+
+impl Datatype {
+    /// Datatype message (version 1)
+    fn encode(self) -> Vec<u8> {
+        let float = |sign: u8, precision: u16, exponent: (u8, u8), mantissa: u8, bias: u32| {
+            // Class 1, implied leading mantissa bit
+            let mut out = vec![0x11, 0x20, sign, 0];
+            out.extend_from_slice(&(precision as u32 / 8).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(&precision.to_le_bytes());
+            out.extend_from_slice(&[exponent.0, exponent.1, 0, mantissa]);
+            out.extend_from_slice(&bias.to_le_bytes());
+            out
+        };
+        match self {
+            Self::F32 => float(31, 32, (23, 8), 23, 127),
+            Self::F64 => float(63, 64, (52, 11), 52, 1023),
+            Self::I64 => {
+                // Class 0, signed two's complement
+                let mut out = vec![0x10, 0x08, 0, 0];
+                out.extend_from_slice(&8u32.to_le_bytes());
+                out.extend_from_slice(&0u16.to_le_bytes());
+                out.extend_from_slice(&64u16.to_le_bytes());
+                out
+            }
+            Self::Str(len) => {
+                // Class 3, null-terminated ASCII
+                let mut out = vec![0x13, 0, 0, 0];
+                out.extend_from_slice(&(len as u32).to_le_bytes());
+                out
+            }
+        }
+    }
+}
+
+/// Dataspace message (version 2); scalar when `dims` is empty
+fn dataspace(dims: &[u64]) -> Vec<u8> {
+    let mut out = vec![2, dims.len() as u8, 0, if dims.is_empty() { 0 } else { 1 }];
+    for &d in dims {
+        out.extend_from_slice(&d.to_le_bytes());
+    }
+    out
+}
+
+/// Attribute of a group or dataset
+#[derive(Debug, Clone)]
+struct Attribute {
+    name: &'static str,
+    datatype: Datatype,
+    dims: Vec<u64>,
+    data: Vec<u8>,
+}
+
+impl Attribute {
+    fn int(name: &'static str, value: i64) -> Self {
+        Self {
+            name,
+            datatype: Datatype::I64,
+            dims: Vec::new(),
+            data: value.to_le_bytes().to_vec(),
+        }
+    }
+
+    fn ints(name: &'static str, values: &[i64]) -> Self {
+        Self {
+            name,
+            datatype: Datatype::I64,
+            dims: vec![values.len() as u64],
+            data: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
+
+    fn string(name: &'static str, value: &str) -> Self {
+        let mut attribute = Self::strings(name, &[value]);
+        attribute.dims.clear();
+        attribute
+    }
+
+    fn strings(name: &'static str, values: &[&str]) -> Self {
+        let len = values.iter().map(|v| v.len()).max().unwrap_or(0) + 1;
+        let mut data = Vec::with_capacity(len * values.len());
+        for value in values {
+            data.extend_from_slice(value.as_bytes());
+            data.resize(data.len() + len - value.len(), 0);
+        }
+        Self {
+            name,
+            datatype: Datatype::Str(len),
+            dims: vec![values.len() as u64],
+            data,
+        }
+    }
+
+    /// Attribute message (version 3)
+    fn encode(&self) -> Vec<u8> {
+        let datatype = self.datatype.encode();
+        let space = dataspace(&self.dims);
+        let mut out = vec![3, 0];
+        out.extend_from_slice(&(self.name.len() as u16 + 1).to_le_bytes());
+        out.extend_from_slice(&(datatype.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(space.len() as u16).to_le_bytes());
+        out.push(0);
+        out.extend_from_slice(self.name.as_bytes());
+        out.push(0);
+        out.extend_from_slice(&datatype);
+        out.extend_from_slice(&space);
+        out.extend_from_slice(&self.data);
+        out
+    }
+}
+
+/// Where the raw data of a dataset comes from
+#[derive(Debug, Clone)]
+enum Raw {
+    Bytes(Vec<u8>),
+    /// `len` bytes at `offset` of each of the spool's `frames` records of
+    /// `record` bytes
+    Spool {
+        record: u64,
+        offset: u64,
+        len: u64,
+        frames: u64,
+    },
+}
+
+impl Raw {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::Spool { len, frames, .. } => len * frames,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Group {
+        name: String,
+        attributes: Vec<Attribute>,
+        children: Vec<Node>,
+    },
+    Dataset {
+        name: String,
+        attributes: Vec<Attribute>,
+        datatype: Datatype,
+        dims: Vec<u64>,
+        raw: Raw,
+    },
+}
+
+impl Node {
+    fn group(name: &str, attributes: Vec<Attribute>, children: Vec<Node>) -> Self {
+        Self::Group {
+            name: name.to_string(),
+            attributes,
+            children,
+        }
+    }
+
+    fn dataset(name: &str, datatype: Datatype, dims: Vec<u64>, raw: Raw) -> Self {
+        Self::Dataset {
+            name: name.to_string(),
+            attributes: Vec::new(),
+            datatype,
+            dims,
+            raw,
+        }
+    }
+
+    fn with_unit(mut self, unit: &str) -> Self {
+        let (Self::Dataset { attributes, .. } | Self::Group { attributes, .. }) = &mut self;
+        attributes.push(Attribute::string("unit", unit));
+        self
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Group { name, .. } | Self::Dataset { name, .. } => name,
+        }
+    }
+}
+
+fn message(out: &mut Vec<u8>, kind: u8, flags: u8, data: &[u8]) {
+    out.push(kind);
+    out.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    out.push(flags);
+    out.extend_from_slice(data);
+}
+
+/// Version 2 object header with `links` to children at known addresses
+fn object_header(node: &Node, links: &[(&str, u64)], data_address: u64) -> Vec<u8> {
+    let mut messages = Vec::new();
+    match node {
+        Node::Group { attributes, .. } => {
+            // Link info: no creation order, compact storage (no heap/B-tree)
+            let mut info = vec![0, 0];
+            info.extend_from_slice(&UNDEFINED.to_le_bytes());
+            info.extend_from_slice(&UNDEFINED.to_le_bytes());
+            message(&mut messages, 0x02, 0, &info);
+            message(&mut messages, 0x0A, 0, &[0, 0]);
+            for (name, address) in links {
+                let mut link = vec![1, 0, name.len() as u8];
+                link.extend_from_slice(name.as_bytes());
+                link.extend_from_slice(&address.to_le_bytes());
+                message(&mut messages, 0x06, 0, &link);
+            }
+            for attribute in attributes {
+                message(&mut messages, 0x0C, 0, &attribute.encode());
+            }
+        }
+        Node::Dataset {
+            attributes,
+            datatype,
+            dims,
+            raw,
+            ..
+        } => {
+            message(&mut messages, 0x01, 0, &dataspace(dims));
+            message(&mut messages, 0x03, 1, &datatype.encode());
+            // Fill value: allocated late, written only if defined (none is)
+            message(&mut messages, 0x05, 1, &[3, 0x0A]);
+            let mut layout = vec![3, 1];
+            let size = raw.len();
+            layout.extend_from_slice(
+                &(if size == 0 { UNDEFINED } else { data_address }).to_le_bytes(),
+            );
+            layout.extend_from_slice(&size.to_le_bytes());
+            message(&mut messages, 0x08, 0, &layout);
+            for attribute in attributes {
+                message(&mut messages, 0x0C, 0, &attribute.encode());
+            }
+        }
+    }
+    // 4-byte chunk size, no times or attribute phase change values
+    let mut out = b"OHDR".to_vec();
+    out.extend_from_slice(&[2, 0x02]);
+    out.extend_from_slice(&(messages.len() as u32).to_le_bytes());
+    out.extend_from_slice(&messages);
+    let checksum = lookup3(&out, 0);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out
+}
+
+/// Nodes in depth-first order with the index of each child
+fn flatten<'a>(node: &'a Node, order: &mut Vec<(&'a Node, Vec<usize>)>) -> usize {
+    let index = order.len();
+    order.push((node, Vec::new()));
+    if let Node::Group { children, .. } = node {
+        let indices: Vec<usize> = children.iter().map(|child| flatten(child, order)).collect();
+        order[index].1 = indices;
+    }
+    index
+}
+
+/// Write `root` as a complete HDF5 file to `out`, copying spooled data
+/// from `spool`
+fn write_file(root: &Node, out: &mut (impl Write + Seek), spool: Option<&Path>) -> Result<()> {
+    let mut order = Vec::new();
+    flatten(root, &mut order);
+    // Header sizes do not depend on the addresses they hold
+    let sizes: Vec<u64> = order
+        .iter()
+        .map(|(node, children)| {
+            let links: Vec<(&str, u64)> =
+                children.iter().map(|&c| (order[c].0.name(), 0)).collect();
+            object_header(node, &links, 0).len() as u64
+        })
+        .collect();
+    let mut header_addresses = Vec::with_capacity(order.len());
+    let mut next = SUPERBLOCK_SIZE;
+    for size in &sizes {
+        header_addresses.push(next);
+        next += size;
+    }
+    let mut data_addresses = vec![0; order.len()];
+    for (i, (node, _)) in order.iter().enumerate() {
+        if let Node::Dataset { raw, .. } = node {
+            data_addresses[i] = next;
+            next += raw.len();
+        }
+    }
+    let end_of_file = next;
+
+    let mut superblock = SIGNATURE.to_vec();
+    superblock.extend_from_slice(&[2, 8, 8, 0]);
+    superblock.extend_from_slice(&0u64.to_le_bytes());
+    superblock.extend_from_slice(&UNDEFINED.to_le_bytes());
+    superblock.extend_from_slice(&end_of_file.to_le_bytes());
+    superblock.extend_from_slice(&header_addresses[0].to_le_bytes());
+    let checksum = lookup3(&superblock, 0);
+    superblock.extend_from_slice(&checksum.to_le_bytes());
+    out.write_all(&superblock)?;
+
+    for (i, (node, children)) in order.iter().enumerate() {
+        let links: Vec<(&str, u64)> = children
+            .iter()
+            .map(|&c| (order[c].0.name(), header_addresses[c]))
+            .collect();
+        out.write_all(&object_header(node, &links, data_addresses[i]))?;
+    }
+
+    let mut reader = match spool {
+        Some(path) => Some(BufReader::new(File::open(path)?)),
+        None => None,
+    };
+    for (node, _) in &order {
+        let Node::Dataset { raw, .. } = node else {
+            continue;
+        };
+        match raw {
+            Raw::Bytes(bytes) => out.write_all(bytes)?,
+            Raw::Spool {
+                record,
+                offset,
+                len,
+                frames,
+            } => {
+                let reader = reader
+                    .as_mut()
+                    .ok_or_else(|| PrismIoError::FormatError("H5MD spool missing".to_string()))?;
+                let mut buffer = vec![0u8; *len as usize];
+                for frame in 0..*frames {
+                    reader.seek(SeekFrom::Start(frame * record + offset))?;
+                    reader.read_exact(&mut buffer)?;
+                    out.write_all(&buffer)?;
+                }
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Scalar time series of one observable
+#[derive(Debug, Clone, Default)]
+struct Series {
+    steps: Vec<i64>,
+    times: Vec<f64>,
+    values: Vec<f64>,
+}
+
+fn le_bytes<T: Copy, const N: usize>(values: &[T], encode: fn(T) -> [u8; N]) -> Vec<u8> {
+    values.iter().flat_map(|&v| encode(v)).collect()
+}
+
+/// `step`, `time` and `value` of a time-dependent H5MD element
+fn time_series(name: &str, steps: &[i64], times: &[f64], value: Node) -> Node {
+    let frames = steps.len() as u64;
+    Node::group(
+        name,
+        Vec::new(),
+        vec![
+            Node::dataset(
+                "step",
+                Datatype::I64,
+                vec![frames],
+                Raw::Bytes(le_bytes(steps, i64::to_le_bytes)),
+            ),
+            Node::dataset(
+                "time",
+                Datatype::F64,
+                vec![frames],
+                Raw::Bytes(le_bytes(times, f64::to_le_bytes)),
+            )
+            .with_unit("ps"),
+            value,
+        ],
+    )
+}
+
+/// Streaming H5MD writer
+#[derive(Debug)]
+pub struct H5mdWriter {
+    path: PathBuf,
+    spool_path: PathBuf,
+    spool: BufWriter<File>,
+    num_atoms: usize,
+    periodic: bool,
+    steps: Vec<i64>,
+    times: Vec<f64>,
+    edges: Vec<[[f64; 3]; 3]>,
+    /// Whether frames carry velocities, fixed by the first frame
+    velocities: Option<bool>,
+    observables: BTreeMap<String, Series>,
+    /// Frames or observables added since the file was last assembled
+    dirty: bool,
+    scratch: Vec<u8>,
+}
+
+impl H5mdWriter {
+    /// Create the spool of an H5MD file at `path`; `periodic` systems
+    /// record their box vectors with every frame
+    pub fn create<P: AsRef<Path>>(path: P, num_atoms: usize, periodic: bool) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut spool_name = path.clone().into_os_string();
+        spool_name.push(".spool");
+        let spool_path = PathBuf::from(spool_name);
+        let spool = BufWriter::new(File::create(&spool_path)?);
+        let mut writer = Self {
+            path,
+            spool_path,
+            spool,
+            num_atoms,
+            periodic,
+            steps: Vec::new(),
+            times: Vec::new(),
+            edges: Vec::new(),
+            velocities: None,
+            observables: BTreeMap::new(),
+            dirty: true,
+            scratch: Vec::with_capacity(num_atoms * 12),
+        };
+        // An empty but valid file until the first flush
+        writer.flush()?;
+        Ok(writer)
+    }
+
+    /// Output path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append Float4-stride `values` (xyz of each atom) to the spool
+    fn spool_xyz(&mut self, values: &[f32], what: &str) -> Result<()> {
+        if values.len() < self.num_atoms * 4 {
+            return Err(PrismIoError::ValidationError(format!(
+                "H5MD frame has {} {} for {} atoms",
+                values.len() / 4,
+                what,
+                self.num_atoms
+            )));
+        }
+        self.scratch.clear();
+        for p in values.chunks_exact(4).take(self.num_atoms) {
+            for c in &p[..3] {
+                self.scratch.extend_from_slice(&c.to_le_bytes());
+            }
+        }
+        self.spool.write_all(&self.scratch)?;
+        Ok(())
+    }
+
+    fn tree(&self) -> Node {
+        let frames = self.steps.len() as u64;
+        let atoms = self.num_atoms as u64;
+        let xyz_len = atoms * 12;
+        let record = xyz_len * if self.velocities == Some(true) { 2 } else { 1 };
+        let xyz = |offset: u64| Raw::Spool {
+            record,
+            offset,
+            len: xyz_len,
+            frames,
+        };
+
+        let boundary = if self.periodic { "periodic" } else { "none" };
+        let mut box_children = Vec::new();
+        if self.periodic {
+            let edges: Vec<f64> = self.edges.iter().flatten().flatten().copied().collect();
+            let value = Node::dataset(
+                "value",
+                Datatype::F64,
+                vec![frames, 3, 3],
+                Raw::Bytes(le_bytes(&edges, f64::to_le_bytes)),
+            )
+            .with_unit("Angstrom");
+            box_children.push(time_series("edges", &self.steps, &self.times, value));
+        }
+        let mut particles = vec![
+            Node::group(
+                "box",
+                vec![
+                    Attribute::int("dimension", 3),
+                    Attribute::strings("boundary", &[boundary; 3]),
+                ],
+                box_children,
+            ),
+            time_series(
+                "position",
+                &self.steps,
+                &self.times,
+                Node::dataset("value", Datatype::F32, vec![frames, atoms, 3], xyz(0))
+                    .with_unit("Angstrom"),
+            ),
+        ];
+        if self.velocities == Some(true) {
+            let value = Node::dataset("value", Datatype::F32, vec![frames, atoms, 3], xyz(xyz_len))
+                .with_unit("Angstrom ps-1");
+            particles.push(time_series("velocity", &self.steps, &self.times, value));
+        }
+
+        let observables = self
+            .observables
+            .iter()
+            .map(|(name, series)| {
+                let value = Node::dataset(
+                    "value",
+                    Datatype::F64,
+                    vec![series.values.len() as u64],
+                    Raw::Bytes(le_bytes(&series.values, f64::to_le_bytes)),
+                );
+                time_series(name, &series.steps, &series.times, value)
+            })
+            .collect();
+
+        let author = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+        let h5md = Node::group(
+            "h5md",
+            vec![Attribute::ints("version", &H5MD_VERSION)],
+            vec![
+                Node::group(
+                    "author",
+                    vec![Attribute::string("name", &author)],
+                    Vec::new(),
+                ),
+                Node::group(
+                    "creator",
+                    vec![
+                        Attribute::string("name", "PRISM-4D"),
+                        Attribute::string("version", env!("CARGO_PKG_VERSION")),
+                    ],
+                    Vec::new(),
+                ),
+                Node::group(
+                    "modules",
+                    Vec::new(),
+                    vec![Node::group(
+                        "units",
+                        vec![
+                            Attribute::ints("version", &[1, 0]),
+                            Attribute::string("system", "SI"),
+                        ],
+                        Vec::new(),
+                    )],
+                ),
+            ],
+        );
+        Node::group(
+            "/",
+            Vec::new(),
+            vec![
+                h5md,
+                Node::group(
+                    "particles",
+                    Vec::new(),
+                    vec![Node::group(PARTICLE_GROUP, Vec::new(), particles)],
+                ),
+                Node::group("observables", Vec::new(), observables),
+            ],
+        )
+    }
+}
+
+impl TrajectoryWriter for H5mdWriter {
+    fn write_frame(&mut self, frame: &TrajectoryFrame<'_>) -> Result<()> {
+        let with_velocities = *self.velocities.get_or_insert(frame.velocities.is_some());
+        if with_velocities != frame.velocities.is_some() {
+            return Err(PrismIoError::ValidationError(format!(
+                "H5MD frame at step {} {} velocities unlike the first frame",
+                frame.step,
+                if with_velocities { "lacks" } else { "has" }
+            )));
+        }
+        self.spool_xyz(frame.positions, "positions")?;
+        if let Some(velocities) = frame.velocities {
+            self.spool_xyz(velocities, "velocities")?;
+        }
+        if self.periodic {
+            let vectors = frame
+                .simulation_box
+                .map_or([[0.0; 3]; 3], |cell| cell.vectors());
+            self.edges.push(vectors.map(|v| v.map(|c| c as f64)));
+        }
+        self.steps.push(frame.step as i64);
+        self.times.push(frame.time_ps);
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn write_observables(&mut self, step: u64, time_ps: f64, values: &[(&str, f64)]) -> Result<()> {
+        for &(name, value) in values {
+            let series = self.observables.entry(name.replace('/', "_")).or_default();
+            series.steps.push(step as i64);
+            series.times.push(time_ps);
+            series.values.push(value);
+        }
+        self.dirty |= !values.is_empty();
+        Ok(())
+    }
+
+    fn frames_written(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Assemble the HDF5 file from the frames so far
+    fn flush(&mut self) -> Result<()> {
+        self.spool.flush()?;
+        if !self.dirty {
+            return Ok(());
+        }
+        let mut partial_name = self.path.clone().into_os_string();
+        partial_name.push(".tmp");
+        let partial = PathBuf::from(partial_name);
+        {
+            let mut out = BufWriter::new(File::create(&partial)?);
+            write_file(&self.tree(), &mut out, Some(&self.spool_path))?;
+        }
+        std::fs::rename(&partial, &self.path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Drop for H5mdWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!(
+                "⚠️ Failed to write H5MD file {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+        let _ = std::fs::remove_file(&self.spool_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation_box::SimulationBox;
+
+    #[test]
+    fn test_lookup3_reference_values() {
+        assert_eq!(lookup3(b"", 0), 0xdead_beef);
+        assert_eq!(lookup3(b"Four score and seven years ago", 0), 0x1777_0551);
+        assert_eq!(lookup3(b"Four score and seven years ago", 1), 0xcd62_8161);
+    }
+
+    /// Minimal reader of the files written above
+    struct Reader(Vec<u8>);
+
+    impl Reader {
+        fn u64_at(&self, at: usize) -> u64 {
+            u64::from_le_bytes(self.0[at..at + 8].try_into().unwrap())
+        }
+
+        /// `(type, data)` of the messages of the object header at `address`
+        fn messages(&self, address: u64) -> Vec<(u8, &[u8])> {
+            let at = address as usize;
+            assert_eq!(&self.0[at..at + 4], b"OHDR");
+            let size = u32::from_le_bytes(self.0[at + 6..at + 10].try_into().unwrap()) as usize;
+            let end = at + 10 + size;
+            let checksum = u32::from_le_bytes(self.0[end..end + 4].try_into().unwrap());
+            assert_eq!(checksum, lookup3(&self.0[at..end], 0));
+            let mut messages = Vec::new();
+            let mut p = at + 10;
+            while p < end {
+                let len = u16::from_le_bytes([self.0[p + 1], self.0[p + 2]]) as usize;
+                messages.push((self.0[p], &self.0[p + 4..p + 4 + len]));
+                p += 4 + len;
+            }
+            messages
+        }
+
+        fn open(&self, path: &str) -> u64 {
+            let mut address = self.u64_at(36);
+            for part in path.split('/').filter(|p| !p.is_empty()) {
+                address = self
+                    .messages(address)
+                    .into_iter()
+                    .filter(|(kind, _)| *kind == 0x06)
+                    .find_map(|(_, link)| {
+                        let len = link[2] as usize;
+                        (&link[3..3 + len] == part.as_bytes()).then(|| {
+                            u64::from_le_bytes(link[3 + len..11 + len].try_into().unwrap())
+                        })
+                    })
+                    .unwrap_or_else(|| panic!("no link {} in {}", part, path));
+            }
+            address
+        }
+
+        /// Dimensions and raw bytes of a dataset
+        fn dataset(&self, path: &str) -> (Vec<u64>, &[u8]) {
+            let messages = self.messages(self.open(path));
+            let space = messages.iter().find(|(kind, _)| *kind == 0x01).unwrap().1;
+            let dims = (0..space[1] as usize)
+                .map(|i| u64::from_le_bytes(space[4 + 8 * i..12 + 8 * i].try_into().unwrap()))
+                .collect();
+            let layout = messages.iter().find(|(kind, _)| *kind == 0x08).unwrap().1;
+            let address = u64::from_le_bytes(layout[2..10].try_into().unwrap()) as usize;
+            let size = u64::from_le_bytes(layout[10..18].try_into().unwrap()) as usize;
+            (
+                dims,
+                if size == 0 {
+                    &[]
+                } else {
+                    &self.0[address..address + size]
+                },
+            )
+        }
+    }
+
+    fn f32s(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    fn f64s(bytes: &[u8]) -> Vec<f64> {
+        bytes
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_h5md_round_trip() {
+        let dir = std::env::temp_dir().join(format!("prism_h5md_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("run.h5");
+        let cell = SimulationBox::orthorhombic([30.0, 31.0, 32.0]);
+        {
+            let mut writer = H5mdWriter::create(&path, 2, true).unwrap();
+            for step in [100u64, 200] {
+                let s = step as f32;
+                let positions = [s, 1.0, 2.0, 12.0, 3.0, 4.0, 5.0, 16.0];
+                let velocities = [0.1, 0.2, 0.3, 0.0, -0.1, -0.2, -0.3, 0.0];
+                writer
+                    .write_frame(&TrajectoryFrame {
+                        step,
+                        time_ps: step as f64 * 0.002,
+                        positions: &positions,
+                        velocities: Some(&velocities),
+                        simulation_box: Some(cell),
+                    })
+                    .unwrap();
+                writer
+                    .write_observables(
+                        step,
+                        step as f64 * 0.002,
+                        &[("potential_energy", -(step as f64)), ("rmsd", 0.5)],
+                    )
+                    .unwrap();
+            }
+            let positions = [0.0; 8];
+            let missing = TrajectoryFrame {
+                step: 300,
+                time_ps: 0.6,
+                positions: &positions,
+                velocities: None,
+                simulation_box: Some(cell),
+            };
+            assert!(writer.write_frame(&missing).is_err());
+            writer.flush().unwrap();
+            assert_eq!(writer.frames_written(), 2);
+        }
+        assert!(!dir.join("run.h5.spool").exists());
+
+        let reader = Reader(std::fs::read(&path).unwrap());
+        assert_eq!(&reader.0[..8], &SIGNATURE);
+        assert_eq!(
+            u32::from_le_bytes(reader.0[44..48].try_into().unwrap()),
+            lookup3(&reader.0[..44], 0)
+        );
+        assert_eq!(reader.u64_at(28), reader.0.len() as u64);
+
+        let (dims, position) = reader.dataset("/particles/all/position/value");
+        assert_eq!(dims, vec![2, 2, 3]);
+        assert_eq!(
+            f32s(position),
+            vec![100.0, 1.0, 2.0, 3.0, 4.0, 5.0, 200.0, 1.0, 2.0, 3.0, 4.0, 5.0]
+        );
+        let (_, velocity) = reader.dataset("/particles/all/velocity/value");
+        assert_eq!(f32s(velocity)[3..6], [-0.1, -0.2, -0.3]);
+        let (dims, edges) = reader.dataset("/particles/all/box/edges/value");
+        assert_eq!(dims, vec![2, 3, 3]);
+        assert_eq!(f64s(edges)[..3], [30.0, 0.0, 0.0]);
+        let (_, steps) = reader.dataset("/observables/potential_energy/step");
+        assert_eq!(steps, [100i64.to_le_bytes(), 200i64.to_le_bytes()].concat());
+        let (_, energy) = reader.dataset("/observables/potential_energy/value");
+        assert_eq!(f64s(energy), vec![-100.0, -200.0]);
+        let h5md = reader.messages(reader.open("/h5md"));
+        assert!(h5md
+            .iter()
+            .any(|(kind, data)| *kind == 0x0C && data[9..17] == *b"version\0"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_empty_file_is_valid() {
+        let dir = std::env::temp_dir().join(format!("prism_h5md_empty_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("empty.h5");
+        let writer = H5mdWriter::create(&path, 3, false).unwrap();
+        let reader = Reader(std::fs::read(writer.path()).unwrap());
+        let (dims, data) = reader.dataset("/particles/all/position/value");
+        assert_eq!(dims, vec![0, 3, 3]);
+        assert!(data.is_empty());
+        drop(writer);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -5,9 +5,18 @@
 //!
 //! ## Architecture
 //! - 64-byte aligned data structures for optimal GPU transfer
-//! - LZ4-based compression achieving 3-5x size reduction without decompression overhead
+//! - Chunked sections with CRC32 checksums and optional zstd compression
 //! - Embedded metadata for instant structure validation and type checking
 //! - Memory-mapped persistence with zero-copy semantics
+//!
+//! ## Layouts
+//! Version 3 files store the atom, bond and secondary structure arrays back
+//! to back after the header. Version 4 files follow the header with a section
+//! table whose entries each describe one chunk of one section: its codec,
+//! stored and decoded length and CRC32. Sections of a kind the reader does not
+//! know are skipped unless flagged required, and an uncompressed single-chunk
+//! section is still read in place. Both layouts load transparently; writers
+//! produce version 4 unless asked for the flat layout.
 
 use rkyv::{Archive, Deserialize, Serialize};
 // use bytemuck::{Pod, Zeroable}; // Disabled until Pod derive issues are resolved
@@ -20,8 +29,38 @@ use crate::{sovereign_types::*, PrismIoError, Result};
 /// Magic bytes identifying a valid .ptb file
 pub const PTB_MAGIC: &[u8; 8] = b"PRISM4D\0";
 
-/// Current version of the .ptb format
-pub const PTB_VERSION: u32 = 3;
+/// Current version of the .ptb format (chunked sections)
+pub const PTB_VERSION: u32 = 4;
+
+/// Version of the flat layout, still read and optionally written
+pub const PTB_VERSION_FLAT: u32 = 3;
+
+/// Section holding the `Atom` array
+pub const SECTION_ATOMS: u32 = 1;
+/// Section holding the `Bond` array
+pub const SECTION_BONDS: u32 = 2;
+/// Section holding the `SecondaryStructure` array
+pub const SECTION_SECONDARY: u32 = 3;
+
+/// Section flag: readers that do not know the section kind must refuse the file
+pub const SECTION_REQUIRED: u32 = 0x1;
+
+/// Chunk stored as is
+pub const CODEC_NONE: u32 = 0;
+/// Chunk stored as a zstd frame
+pub const CODEC_ZSTD: u32 = 2;
+
+/// Default decoded size of a section chunk
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+/// Alignment of chunk data within a version 4 file
+const CHUNK_ALIGN: usize = 64;
+
+/// Atoms per block of a flat layout file
+const FLAT_BLOCK_ATOMS: usize = DEFAULT_CHUNK_SIZE / size_of::<Atom>();
+
+/// Decoded atom blocks kept in memory by default
+const DEFAULT_BLOCK_CACHE: usize = 4;
 
 /// Cryptographic hash size constant - NEVER truncate
 pub const HASH_SIZE: usize = 32;
@@ -35,7 +74,7 @@ pub struct PtbHeader {
     pub magic: [u8; 8],                    // 8 bytes
     /// Format version
     pub version: u32,                      // 4 bytes
-    /// Compression algorithm (0=none, 1=LZ4)
+    /// Compression algorithm (0=none, 1=LZ4, 2=zstd)
     pub compression: u32,                  // 4 bytes
     /// Data counts
     pub atom_count: u32,                   // 4 bytes
@@ -83,8 +122,8 @@ impl PtbHeader {
         // Store FULL BLAKE3 hash - b3sum compatible
         self.source_hash.copy_from_slice(source_hash);
         self.provenance_id = provenance_id;
-        self.ingest_timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
+        self.ingest_timestamp = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
     }
@@ -97,13 +136,13 @@ impl PtbHeader {
             ));
         }
 
-        if self.version != PTB_VERSION {
+        if self.version != PTB_VERSION && self.version != PTB_VERSION_FLAT {
             return Err(PrismIoError::FormatError(
-                format!("Unsupported version: {}, expected {}", self.version, PTB_VERSION)
+                format!("Unsupported version: {}, expected {} or {}", self.version, PTB_VERSION_FLAT, PTB_VERSION)
             ));
         }
 
-        if self.file_size < std::mem::size_of::<PtbHeader>() as u64 {
+        if self.file_size < size_of::<PtbHeader>() as u64 {
             return Err(PrismIoError::FormatError("File size too small".to_string()));
         }
 
@@ -111,12 +150,210 @@ impl PtbHeader {
     }
 }
 
+/// Section table entry of a version 4 file: one chunk of one section
+///
+/// The table follows the header as a `u32` entry count, a `u32` CRC32 of the
+/// header and entry bytes, then the entries. Chunks of a section are decoded
+/// and concatenated in table order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct PtbSection {
+    /// Section kind (`SECTION_*`; other values are application sections)
+    pub kind: u32,
+    /// Chunk codec (`CODEC_*`)
+    pub codec: u32,
+    /// File offset of the stored bytes
+    pub offset: u64,
+    /// Stored (possibly compressed) length
+    pub stored_len: u64,
+    /// Length after decoding
+    pub raw_len: u64,
+    /// CRC32 of the stored bytes
+    pub crc32: u32,
+    /// Section flags (`SECTION_REQUIRED`)
+    pub flags: u32,
+}
+
+/// Chunk compression of a version 4 file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PtbCompression {
+    /// Chunks stored as is
+    #[default]
+    None,
+    /// zstd at the given level; chunks that do not shrink are stored as is
+    Zstd(i32),
+}
+
+fn is_known_section(kind: u32) -> bool {
+    matches!(kind, SECTION_ATOMS | SECTION_BONDS | SECTION_SECONDARY)
+}
+
+fn section_name(kind: u32) -> &'static str {
+    match kind {
+        SECTION_ATOMS => "Atom",
+        SECTION_BONDS => "Bond",
+        SECTION_SECONDARY => "Secondary structure",
+        _ => "Application",
+    }
+}
+
+/// Read and check the section table of a version 4 file
+fn read_section_table(data: &[u8]) -> Result<Vec<PtbSection>> {
+    let header_len = size_of::<PtbHeader>();
+    let entries_start = header_len + 8;
+    if data.len() < entries_start {
+        return Err(PrismIoError::FormatError("File too small for section table".to_string()));
+    }
+
+    let count = u32::from_ne_bytes(data[header_len..header_len + 4].try_into().unwrap()) as usize;
+    let table_crc = u32::from_ne_bytes(data[header_len + 4..entries_start].try_into().unwrap());
+    let entries_end = count
+        .checked_mul(size_of::<PtbSection>())
+        .and_then(|len| len.checked_add(entries_start))
+        .filter(|&end| end <= data.len())
+        .ok_or_else(|| PrismIoError::FormatError("Section table extends beyond file".to_string()))?;
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&data[..header_len]);
+    hasher.update(&data[entries_start..entries_end]);
+    if hasher.finalize() != table_crc {
+        return Err(PrismIoError::IntegrityViolation("Header or section table checksum mismatch".to_string()));
+    }
+
+    let sections: Vec<PtbSection> = data[entries_start..entries_end]
+        .chunks_exact(size_of::<PtbSection>())
+        .map(bytemuck::pod_read_unaligned)
+        .collect();
+
+    for section in &sections {
+        let in_bounds = section.offset
+            .checked_add(section.stored_len)
+            .is_some_and(|end| end <= data.len() as u64);
+        if !in_bounds {
+            return Err(PrismIoError::FormatError(
+                format!("{} data extends beyond file", section_name(section.kind))
+            ));
+        }
+        if section.codec == CODEC_NONE && section.stored_len != section.raw_len {
+            return Err(PrismIoError::FormatError(
+                format!("{} chunk stored uncompressed with mismatched length", section_name(section.kind))
+            ));
+        }
+        if !is_known_section(section.kind) {
+            if section.flags & SECTION_REQUIRED != 0 {
+                return Err(PrismIoError::FormatError(
+                    format!("Unsupported required section kind {}", section.kind)
+                ));
+            }
+            tracing::debug!("Skipping unknown .ptb section kind {}", section.kind);
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Stored bytes of a chunk after checking its CRC32
+fn checked_chunk<'a>(data: &'a [u8], section: &PtbSection) -> Result<&'a [u8]> {
+    let stored = &data[section.offset as usize..(section.offset + section.stored_len) as usize];
+    if crc32fast::hash(stored) != section.crc32 {
+        return Err(PrismIoError::IntegrityViolation(
+            format!("{} chunk at offset {} failed its checksum", section_name(section.kind), section.offset)
+        ));
+    }
+    Ok(stored)
+}
+
+/// Check a chunk's CRC32 and decode it
+fn decode_chunk<'a>(data: &'a [u8], section: &PtbSection) -> Result<std::borrow::Cow<'a, [u8]>> {
+    let stored = checked_chunk(data, section)?;
+
+    match section.codec {
+        CODEC_NONE => Ok(std::borrow::Cow::Borrowed(stored)),
+        #[cfg(not(target_arch = "wasm32"))]
+        CODEC_ZSTD => {
+            let raw = zstd::bulk::decompress(stored, section.raw_len as usize)
+                .map_err(|e| PrismIoError::FormatError(format!("zstd chunk: {}", e)))?;
+            if raw.len() as u64 != section.raw_len {
+                return Err(PrismIoError::FormatError(
+                    format!("{} chunk decoded to {} bytes, expected {}", section_name(section.kind), raw.len(), section.raw_len)
+                ));
+            }
+            Ok(std::borrow::Cow::Owned(raw))
+        }
+        codec => Err(PrismIoError::FormatError(
+            format!("Unsupported codec {} for {} chunk", codec, section_name(section.kind))
+        )),
+    }
+}
+
+/// View a slice of plain `#[repr(C)]` records as bytes
+fn record_bytes<T: Copy>(records: &[T]) -> &[u8] {
+    unsafe {
+        std::slice::from_raw_parts(
+            records.as_ptr() as *const u8,
+            size_of_val(records)
+        )
+    }
+}
+
+/// View bytes of a mapped or aligned buffer as records
+fn cast_records<T>(bytes: &[u8]) -> &[T] {
+    if bytes.is_empty() {
+        return &[];
+    }
+    debug_assert_eq!(bytes.as_ptr() as usize % align_of::<T>(), 0);
+    unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const T, bytes.len() / size_of::<T>()) }
+}
+
+/// 64-byte block backing an in-memory copy with the mapping's alignment
+#[repr(C, align(64))]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct AlignedBlock([u8; 64]);
+
+/// Copy bytes into 64-byte aligned blocks
+fn aligned_blocks(bytes: &[u8]) -> Vec<AlignedBlock> {
+    let mut blocks = vec![AlignedBlock([0; 64]); bytes.len().div_ceil(64)];
+    bytemuck::cast_slice_mut::<AlignedBlock, u8>(&mut blocks)[..bytes.len()].copy_from_slice(bytes);
+    blocks
+}
+
+/// Bytes of a loaded .ptb structure
+enum PtbData {
+    /// Memory-mapped file
+    Mapped(memmap2::Mmap),
+    /// Aligned copy of a buffer (no file system, e.g. on wasm32)
+    Owned { blocks: Vec<AlignedBlock>, len: usize },
+}
+
+impl std::ops::Deref for PtbData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            PtbData::Mapped(mmap) => mmap,
+            PtbData::Owned { blocks, len } => &bytemuck::cast_slice(blocks)[..*len],
+        }
+    }
+}
+
 /// Complete protein structure in holographic binary format
 pub struct PtbStructure {
-    /// Memory-mapped file data
-    mmap: memmap2::Mmap,
+    /// File data, memory-mapped or copied
+    mmap: PtbData,
     /// Parsed header information
     header: PtbHeader,
+    /// Section table (empty for the flat layout)
+    sections: Vec<PtbSection>,
+    /// Sections that had to be decoded or joined, with their lengths
+    decoded: std::collections::BTreeMap<u32, (Vec<AlignedBlock>, usize)>,
+    /// First atom of each atom block, plus the atom count
+    block_starts: Vec<usize>,
+    /// Uncompressed atom chunks whose checksum has been verified
+    block_checked: Vec<bool>,
+    /// Decoded atom blocks, least recently materialized first
+    block_cache: std::collections::VecDeque<(usize, Vec<AlignedBlock>)>,
+    /// Decoded atom blocks kept in memory
+    block_cache_capacity: usize,
     /// Cached atom data slice
     atoms: Option<&'static [Atom]>,
     /// Cached bond data slice
@@ -136,14 +373,28 @@ impl PtbStructure {
     /// # Returns
     /// * `Result<PtbStructure>` - Loaded structure or error
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let start_time = std::time::Instant::now();
+        let start_time = web_time::Instant::now();
 
         // Memory-map the file for zero-copy access
         let file = File::open(path)?;
         let mmap = unsafe { MmapOptions::new().map(&file)? };
 
+        Self::from_data(PtbData::Mapped(mmap), start_time)
+    }
+
+    /// Load a .ptb structure from bytes already in memory (e.g. fetched by
+    /// a browser), copying them into an aligned buffer
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let start_time = web_time::Instant::now();
+
+        let blocks = aligned_blocks(bytes);
+
+        Self::from_data(PtbData::Owned { blocks, len: bytes.len() }, start_time)
+    }
+
+    fn from_data(mmap: PtbData, start_time: web_time::Instant) -> Result<Self> {
         // Verify minimum file size
-        if mmap.len() < std::mem::size_of::<PtbHeader>() {
+        if mmap.len() < size_of::<PtbHeader>() {
             return Err(PrismIoError::FormatError("File too small for header".to_string()));
         }
 
@@ -163,9 +414,45 @@ impl PtbStructure {
             ));
         }
 
+        let sections = if header.version == PTB_VERSION_FLAT {
+            Vec::new()
+        } else {
+            read_section_table(&mmap)?
+        };
+
+        let atom_count = header.atom_count as usize;
+        let mut block_starts = vec![0];
+        if header.version == PTB_VERSION_FLAT {
+            block_starts.extend((FLAT_BLOCK_ATOMS..atom_count).step_by(FLAT_BLOCK_ATOMS));
+            if atom_count > 0 {
+                block_starts.push(atom_count);
+            }
+        } else {
+            for chunk in sections.iter().filter(|s| s.kind == SECTION_ATOMS) {
+                if chunk.raw_len % size_of::<Atom>() as u64 != 0 {
+                    return Err(PrismIoError::FormatError("Atom chunk does not hold whole atoms".to_string()));
+                }
+                block_starts.push(block_starts[block_starts.len() - 1] + chunk.raw_len as usize / size_of::<Atom>());
+            }
+            if block_starts[block_starts.len() - 1] != atom_count {
+                return Err(PrismIoError::FormatError(format!(
+                    "Atom chunks hold {} atoms, header declares {}",
+                    block_starts[block_starts.len() - 1],
+                    atom_count
+                )));
+            }
+        }
+        let block_count = block_starts.len() - 1;
+
         let structure = Self {
             mmap,
             header,
+            sections,
+            decoded: std::collections::BTreeMap::new(),
+            block_starts,
+            block_checked: vec![false; block_count],
+            block_cache: std::collections::VecDeque::new(),
+            block_cache_capacity: DEFAULT_BLOCK_CACHE,
             atoms: None,
             bonds: None,
             secondary: None,
@@ -195,29 +482,164 @@ impl PtbStructure {
         &self.header
     }
 
-    /// Get atom data with lazy loading and caching
-    pub fn atoms(&mut self) -> Result<&[Atom]> {
-        if self.atoms.is_none() {
-            let start_offset = self.header.atoms_offset as usize;
-            let atom_size = std::mem::size_of::<Atom>();
-            let total_size = self.header.atom_count as usize * atom_size;
+    /// Section table of a version 4 file (empty for the flat layout)
+    pub fn sections(&self) -> &[PtbSection] {
+        &self.sections
+    }
+
+    /// Decoded bytes of a section, `None` if the file does not carry it
+    ///
+    /// Chunk checksums are verified on first access. A single uncompressed
+    /// chunk is returned in place; anything else is decoded once and cached.
+    pub fn section(&mut self, kind: u32) -> Result<Option<&[u8]>> {
+        if self.header.version == PTB_VERSION_FLAT {
+            return self.flat_section(kind);
+        }
+
+        let chunks: Vec<PtbSection> = self.sections.iter().filter(|s| s.kind == kind).copied().collect();
+        match chunks.as_slice() {
+            [] => Ok(None),
+            [chunk] if chunk.codec == CODEC_NONE => Ok(Some(checked_chunk(&self.mmap, chunk)?)),
+            _ => {
+                if !self.decoded.contains_key(&kind) {
+                    let mut raw = Vec::with_capacity(chunks.iter().map(|c| c.raw_len as usize).sum());
+                    for chunk in &chunks {
+                        raw.extend_from_slice(&decode_chunk(&self.mmap, chunk)?);
+                    }
+                    self.decoded.insert(kind, (aligned_blocks(&raw), raw.len()));
+                }
+                let (blocks, len) = &self.decoded[&kind];
+                Ok(Some(&bytemuck::cast_slice(blocks)[..*len]))
+            }
+        }
+    }
+
+    fn flat_section(&self, kind: u32) -> Result<Option<&[u8]>> {
+        let (start_offset, total_size) = match kind {
+            SECTION_ATOMS => (self.header.atoms_offset, self.header.atom_count as usize * size_of::<Atom>()),
+            SECTION_BONDS => (self.header.bonds_offset, self.header.bond_count as usize * size_of::<Bond>()),
+            SECTION_SECONDARY => (
+                self.header.secondary_offset,
+                self.header.secondary_count as usize * size_of::<SecondaryStructure>(),
+            ),
+            _ => return Ok(None),
+        };
+        let start_offset = start_offset as usize;
+
+        if start_offset + total_size > self.mmap.len() {
+            return Err(PrismIoError::FormatError(format!("{} data extends beyond file", section_name(kind))));
+        }
+
+        Ok(Some(&self.mmap[start_offset..start_offset + total_size]))
+    }
+
+    /// Bytes of a record section, checked against the header's count
+    fn records<T>(&mut self, kind: u32, count: u32) -> Result<&'static [T]> {
+        let bytes = self.section(kind)?.unwrap_or(&[]);
+        if bytes.len() != count as usize * size_of::<T>() {
+            return Err(PrismIoError::FormatError(format!(
+                "{} section holds {} bytes, header declares {} records",
+                section_name(kind),
+                bytes.len(),
+                count
+            )));
+        }
+        let records: &[T] = cast_records(bytes);
+
+        // SAFETY: The mapping and decoded blocks live as long as the
+        // structure and are never modified, so the slice does not outlive
+        // its data
+        Ok(unsafe { std::mem::transmute::<&[T], &'static [T]>(records) })
+    }
+
+    /// Number of blocks the atoms can be materialized in: the atom chunks
+    /// of a version 4 file, fixed runs of atoms for the flat layout
+    pub fn atom_block_count(&self) -> usize {
+        self.block_starts.len() - 1
+    }
+
+    /// Indices of the atoms in `block`
+    pub fn atom_block_range(&self, block: usize) -> std::ops::Range<usize> {
+        self.block_starts[block]..self.block_starts[block + 1]
+    }
+
+    /// Keep at most `blocks` decoded atom blocks in memory (default 4).
+    /// Uncompressed blocks are read in place and never cached.
+    pub fn set_block_cache_capacity(&mut self, blocks: usize) {
+        self.block_cache_capacity = blocks.max(1);
+        while self.block_cache.len() > self.block_cache_capacity {
+            self.block_cache.pop_front();
+        }
+    }
+
+    /// Atoms of one block, materialized on first access
+    ///
+    /// Only the block's own chunk is checked and decoded, so a structure of
+    /// millions of atoms can be walked without holding all of them in memory.
+    pub fn atom_block(&mut self, block: usize) -> Result<&[Atom]> {
+        if block >= self.atom_block_count() {
+            return Err(PrismIoError::FormatError(
+                format!("Atom block {} out of range ({} blocks)", block, self.atom_block_count())
+            ));
+        }
+        let range = self.atom_block_range(block);
 
-            if start_offset + total_size > self.mmap.len() {
+        if self.header.version == PTB_VERSION_FLAT {
+            let start = self.header.atoms_offset as usize + range.start * size_of::<Atom>();
+            let end = start + range.len() * size_of::<Atom>();
+            if end > self.mmap.len() {
                 return Err(PrismIoError::FormatError("Atom data extends beyond file".to_string()));
             }
+            return Ok(cast_records(&self.mmap[start..end]));
+        }
 
-            let atom_bytes = &self.mmap[start_offset..start_offset + total_size];
-            let atoms: &[Atom] = unsafe {
-                std::slice::from_raw_parts(
-                    atom_bytes.as_ptr() as *const Atom,
-                    atom_bytes.len() / size_of::<Atom>()
-                )
+        let chunk = *self
+            .sections
+            .iter()
+            .filter(|s| s.kind == SECTION_ATOMS)
+            .nth(block)
+            .expect("block index checked against the atom chunks");
+        if chunk.codec == CODEC_NONE {
+            let bytes = if self.block_checked[block] {
+                &self.mmap[chunk.offset as usize..(chunk.offset + chunk.stored_len) as usize]
+            } else {
+                let bytes = checked_chunk(&self.mmap, &chunk)?;
+                self.block_checked[block] = true;
+                bytes
             };
+            return Ok(cast_records(bytes));
+        }
 
-            // SAFETY: The mmap lifetime ensures the data remains valid
-            // This is safe because we're converting to a static reference
-            // that doesn't outlive the mmap
-            self.atoms = Some(unsafe { std::mem::transmute(atoms) });
+        let position = match self.block_cache.iter().position(|(cached, _)| *cached == block) {
+            Some(position) => position,
+            None => {
+                let raw = decode_chunk(&self.mmap, &chunk)?;
+                let blocks = aligned_blocks(&raw);
+                if self.block_cache.len() == self.block_cache_capacity {
+                    self.block_cache.pop_front();
+                }
+                self.block_cache.push_back((block, blocks));
+                self.block_cache.len() - 1
+            }
+        };
+        let bytes: &[u8] = bytemuck::cast_slice(&self.block_cache[position].1);
+        Ok(cast_records(&bytes[..range.len() * size_of::<Atom>()]))
+    }
+
+    /// Copy all atoms out block by block, without decoding the whole atom
+    /// section at once
+    pub fn collect_atoms(&mut self) -> Result<Vec<Atom>> {
+        let mut atoms = Vec::with_capacity(self.header.atom_count as usize);
+        for block in 0..self.atom_block_count() {
+            atoms.extend_from_slice(self.atom_block(block)?);
+        }
+        Ok(atoms)
+    }
+
+    /// Get atom data with lazy loading and caching
+    pub fn atoms(&mut self) -> Result<&[Atom]> {
+        if self.atoms.is_none() {
+            self.atoms = Some(self.records(SECTION_ATOMS, self.header.atom_count)?);
         }
 
         Ok(self.atoms.unwrap())
@@ -226,24 +648,7 @@ impl PtbStructure {
     /// Get bond data with lazy loading and caching
     pub fn bonds(&mut self) -> Result<&[Bond]> {
         if self.bonds.is_none() {
-            let start_offset = self.header.bonds_offset as usize;
-            let bond_size = std::mem::size_of::<Bond>();
-            let total_size = self.header.bond_count as usize * bond_size;
-
-            if start_offset + total_size > self.mmap.len() {
-                return Err(PrismIoError::FormatError("Bond data extends beyond file".to_string()));
-            }
-
-            let bond_bytes = &self.mmap[start_offset..start_offset + total_size];
-            let bonds: &[Bond] = unsafe {
-                std::slice::from_raw_parts(
-                    bond_bytes.as_ptr() as *const Bond,
-                    bond_bytes.len() / size_of::<Bond>()
-                )
-            };
-
-            // SAFETY: Same reasoning as atoms() method
-            self.bonds = Some(unsafe { std::mem::transmute(bonds) });
+            self.bonds = Some(self.records(SECTION_BONDS, self.header.bond_count)?);
         }
 
         Ok(self.bonds.unwrap())
@@ -252,24 +657,7 @@ impl PtbStructure {
     /// Get secondary structure data with lazy loading and caching
     pub fn secondary_structure(&mut self) -> Result<&[SecondaryStructure]> {
         if self.secondary.is_none() {
-            let start_offset = self.header.secondary_offset as usize;
-            let ss_size = std::mem::size_of::<SecondaryStructure>();
-            let total_size = self.header.secondary_count as usize * ss_size;
-
-            if start_offset + total_size > self.mmap.len() {
-                return Err(PrismIoError::FormatError("Secondary structure data extends beyond file".to_string()));
-            }
-
-            let ss_bytes = &self.mmap[start_offset..start_offset + total_size];
-            let secondary: &[SecondaryStructure] = unsafe {
-                std::slice::from_raw_parts(
-                    ss_bytes.as_ptr() as *const SecondaryStructure,
-                    ss_bytes.len() / size_of::<SecondaryStructure>()
-                )
-            };
-
-            // SAFETY: Same reasoning as atoms() method
-            self.secondary = Some(unsafe { std::mem::transmute(secondary) });
+            self.secondary = Some(self.records(SECTION_SECONDARY, self.header.secondary_count)?);
         }
 
         Ok(self.secondary.unwrap())
@@ -288,13 +676,17 @@ impl PtbStructure {
         // The source_hash is for clinical tracking, not PTB content verification
 
         // Magic bytes and version already verified during load in header.validate()
-        // File size already verified during load
+        // File size and the section table checksum already verified during load
         // Data offsets validated when accessing sections
 
-        // Trust io_uring stream and file system for data integrity
+        // Chunked files carry a CRC32 per chunk; the flat layout has none and
+        // trusts the io_uring stream and file system for data integrity
         // PTB content differs from source PDB, so hash comparison is invalid
+        for section in &self.sections {
+            checked_chunk(&self.mmap, section)?;
+        }
 
-        tracing::debug!("PTB structural integrity verified (magic, version, offsets)");
+        tracing::debug!("PTB structural integrity verified (magic, version, offsets, {} chunk checksums)", self.sections.len());
         Ok(())
     }
 
@@ -337,6 +729,10 @@ pub struct HolographicBinaryFormat {
     atoms: Vec<Atom>,
     bonds: Vec<Bond>,
     secondary: Vec<SecondaryStructure>,
+    /// Application sections (kind, bytes, flags)
+    extra_sections: Vec<(u32, Vec<u8>, u32)>,
+    compression: PtbCompression,
+    chunk_size: usize,
 }
 
 impl HolographicBinaryFormat {
@@ -347,6 +743,9 @@ impl HolographicBinaryFormat {
             atoms: Vec::new(),
             bonds: Vec::new(),
             secondary: Vec::new(),
+            extra_sections: Vec::new(),
+            compression: PtbCompression::None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
         }
     }
 
@@ -377,66 +776,171 @@ impl HolographicBinaryFormat {
         self
     }
 
-    /// Write the .ptb file to disk with LZ4 compression
-    pub fn write_to_file<P: AsRef<Path>>(mut self, path: P) -> Result<()> {
-        use std::io::Write;
+    /// Layout version to write (`PTB_VERSION` or `PTB_VERSION_FLAT` for
+    /// readers that predate chunked sections)
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.header.version = version;
+        self
+    }
 
-        // Calculate offsets (64-bit for large file support)
-        self.header.atoms_offset = std::mem::size_of::<PtbHeader>() as u64;
-        self.header.bonds_offset = self.header.atoms_offset +
-            (self.atoms.len() * std::mem::size_of::<Atom>()) as u64;
-        self.header.secondary_offset = self.header.bonds_offset +
-            (self.bonds.len() * std::mem::size_of::<Bond>()) as u64;
+    /// Compress section chunks (version 4 only)
+    pub fn with_compression(mut self, compression: PtbCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Decoded size of the chunks sections are split into, rounded down to
+    /// whole records (version 4 only)
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Attach an application section of the given kind (version 4 only).
+    /// Readers that do not know the kind skip it unless it is `required`.
+    pub fn with_section(mut self, kind: u32, data: Vec<u8>, required: bool) -> Self {
+        let flags = if required { SECTION_REQUIRED } else { 0 };
+        self.extra_sections.push((kind, data, flags));
+        self
+    }
 
+    /// Encode the structure as .ptb bytes
+    pub fn to_bytes(mut self) -> Result<Vec<u8>> {
         // Set ingest timestamp for clinical provenance tracking
-        self.header.ingest_timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
+        self.header.ingest_timestamp = web_time::SystemTime::now()
+            .duration_since(web_time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
+        match self.header.version {
+            PTB_VERSION => self.chunked_bytes(),
+            PTB_VERSION_FLAT => self.flat_bytes(),
+            version => Err(PrismIoError::FormatError(
+                format!("Unsupported version: {}, expected {} or {}", version, PTB_VERSION_FLAT, PTB_VERSION)
+            )),
+        }
+    }
+
+    fn header_bytes(&self) -> Vec<u8> {
+        record_bytes(std::slice::from_ref(&self.header)).to_vec()
+    }
+
+    fn flat_bytes(mut self) -> Result<Vec<u8>> {
+        if !self.extra_sections.is_empty() || self.compression != PtbCompression::None {
+            return Err(PrismIoError::FormatError(
+                "Application sections and compression need the chunked layout".to_string()
+            ));
+        }
+
+        // Calculate offsets (64-bit for large file support)
+        self.header.atoms_offset = size_of::<PtbHeader>() as u64;
+        self.header.bonds_offset = self.header.atoms_offset +
+            (self.atoms.len() * size_of::<Atom>()) as u64;
+        self.header.secondary_offset = self.header.bonds_offset +
+            (self.bonds.len() * size_of::<Bond>()) as u64;
+
         // Calculate total file size
         self.header.file_size = self.header.secondary_offset +
-            (self.secondary.len() * std::mem::size_of::<SecondaryStructure>()) as u64;
+            (self.secondary.len() * size_of::<SecondaryStructure>()) as u64;
+
+        let mut bytes = Vec::with_capacity(self.header.file_size as usize);
+        bytes.extend_from_slice(&self.header_bytes());
+        bytes.extend_from_slice(record_bytes(&self.atoms));
+        bytes.extend_from_slice(record_bytes(&self.bonds));
+        bytes.extend_from_slice(record_bytes(&self.secondary));
+        Ok(bytes)
+    }
 
-        // Create output file
-        let mut file = std::fs::File::create(path)?;
-
-        // Write header
-        let header_bytes = unsafe {
-            std::slice::from_raw_parts(
-                &self.header as *const PtbHeader as *const u8,
-                size_of::<PtbHeader>()
-            )
-        };
-        file.write_all(header_bytes)?;
-
-        // Write atoms
-        let atoms_bytes = unsafe {
-            std::slice::from_raw_parts(
-                self.atoms.as_ptr() as *const u8,
-                self.atoms.len() * size_of::<Atom>()
-            )
-        };
-        file.write_all(atoms_bytes)?;
-
-        // Write bonds
-        let bonds_bytes = unsafe {
-            std::slice::from_raw_parts(
-                self.bonds.as_ptr() as *const u8,
-                self.bonds.len() * size_of::<Bond>()
-            )
-        };
-        file.write_all(bonds_bytes)?;
-
-        // Write secondary structure
-        let secondary_bytes = unsafe {
-            std::slice::from_raw_parts(
-                self.secondary.as_ptr() as *const u8,
-                self.secondary.len() * size_of::<SecondaryStructure>()
-            )
+    fn chunked_bytes(mut self) -> Result<Vec<u8>> {
+        if self.chunk_size == 0 {
+            return Err(PrismIoError::FormatError("Chunk size must be positive".to_string()));
+        }
+
+        // (kind, bytes, flags, record size): record sections split between
+        // records so every chunk is a self-contained block
+        let mut payloads: Vec<(u32, &[u8], u32, usize)> = vec![
+            (SECTION_ATOMS, record_bytes(&self.atoms), SECTION_REQUIRED, size_of::<Atom>()),
+            (SECTION_BONDS, record_bytes(&self.bonds), SECTION_REQUIRED, size_of::<Bond>()),
+            (SECTION_SECONDARY, record_bytes(&self.secondary), SECTION_REQUIRED, size_of::<SecondaryStructure>()),
+        ];
+        payloads.extend(self.extra_sections.iter().map(|(kind, data, flags)| (*kind, data.as_slice(), *flags, 1)));
+
+        // Split and encode the chunks, then lay them out after the table
+        let mut chunks: Vec<(PtbSection, std::borrow::Cow<[u8]>)> = Vec::new();
+        for (kind, data, flags, record) in payloads {
+            let chunk_size = (self.chunk_size / record).max(1) * record;
+            for raw in data.chunks(chunk_size) {
+                let (codec, stored) = match self.compression {
+                    PtbCompression::None => (CODEC_NONE, std::borrow::Cow::Borrowed(raw)),
+                    PtbCompression::Zstd(level) => {
+                        let packed = compress_zstd(raw, level)?;
+                        if packed.len() < raw.len() {
+                            (CODEC_ZSTD, std::borrow::Cow::Owned(packed))
+                        } else {
+                            (CODEC_NONE, std::borrow::Cow::Borrowed(raw))
+                        }
+                    }
+                };
+                let section = PtbSection {
+                    kind,
+                    codec,
+                    offset: 0,
+                    stored_len: stored.len() as u64,
+                    raw_len: raw.len() as u64,
+                    crc32: crc32fast::hash(&stored),
+                    flags,
+                };
+                chunks.push((section, stored));
+            }
+        }
+
+        let table_end = size_of::<PtbHeader>() + 8 + chunks.len() * size_of::<PtbSection>();
+        let mut offset = table_end.next_multiple_of(CHUNK_ALIGN);
+        for (section, stored) in &mut chunks {
+            section.offset = offset as u64;
+            offset = (offset + stored.len()).next_multiple_of(CHUNK_ALIGN);
+        }
+        let file_size = chunks.last().map_or(table_end, |(s, _)| (s.offset + s.stored_len) as usize);
+
+        self.header.compression = match self.compression {
+            PtbCompression::None => CODEC_NONE,
+            PtbCompression::Zstd(_) => CODEC_ZSTD,
         };
-        file.write_all(secondary_bytes)?;
+        self.header.atoms_offset = 0;
+        self.header.bonds_offset = 0;
+        self.header.secondary_offset = 0;
+        self.header.file_size = file_size as u64;
+
+        let header = self.header_bytes();
+        let entries: Vec<PtbSection> = chunks.iter().map(|(section, _)| *section).collect();
+        let entry_bytes: &[u8] = bytemuck::cast_slice(&entries);
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&header);
+        hasher.update(entry_bytes);
+
+        let mut bytes = Vec::with_capacity(file_size);
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&(entries.len() as u32).to_ne_bytes());
+        bytes.extend_from_slice(&hasher.finalize().to_ne_bytes());
+        bytes.extend_from_slice(entry_bytes);
+        for (section, stored) in &chunks {
+            bytes.resize(section.offset as usize, 0);
+            bytes.extend_from_slice(stored);
+        }
+        Ok(bytes)
+    }
 
+    /// Write the .ptb file to disk
+    pub fn write_to_file<P: AsRef<Path>>(self, path: P) -> Result<()> {
+        use std::io::Write;
+
+        let atom_count = self.header.atom_count;
+        let bond_count = self.header.bond_count;
+        let bytes = self.to_bytes()?;
+
+        // Create output file
+        let mut file = File::create(path)?;
+        file.write_all(&bytes)?;
         file.flush()?;
 
         // CRITICAL: Force OS to write bytes to physical storage before returning
@@ -445,15 +949,25 @@ impl HolographicBinaryFormat {
 
         tracing::info!(
             "Created .ptb file: {} atoms, {} bonds, {} bytes",
-            self.header.atom_count,
-            self.header.bond_count,
-            self.header.file_size
+            atom_count,
+            bond_count,
+            bytes.len()
         );
 
         Ok(())
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn compress_zstd(raw: &[u8], level: i32) -> Result<Vec<u8>> {
+    zstd::bulk::compress(raw, level).map_err(|e| PrismIoError::SerializationError(format!("zstd chunk: {}", e)))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn compress_zstd(_raw: &[u8], _level: i32) -> Result<Vec<u8>> {
+    Err(PrismIoError::SerializationError("zstd compression is not available on wasm32".to_string()))
+}
+
 impl Default for HolographicBinaryFormat {
     fn default() -> Self {
         Self::new()
@@ -510,14 +1024,179 @@ mod tests {
         // Try to load it back
         let structure = PtbStructure::load(temp_path);
         assert!(structure.is_ok());
+
+        // The same bytes load from memory
+        let bytes = std::fs::read(temp_path).unwrap();
+        assert!(PtbStructure::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut in_memory = PtbStructure::from_bytes(&bytes).unwrap();
+        assert_eq!(in_memory.as_bytes(), &bytes[..]);
+        assert_eq!(in_memory.atoms().unwrap()[0].coords, [1.0, 2.0, 3.0]);
+    }
+
+    fn test_atoms(count: usize) -> Vec<Atom> {
+        (0..count)
+            .map(|i| Atom {
+                coords: [i as f32, 0.5 * i as f32, -(i as f32)],
+                element: 6,
+                residue_id: (i / 8) as u16,
+                atom_type: 1,
+                charge: 0.01 * i as f32,
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect()
+    }
+
+    fn assert_same_atoms(loaded: &[Atom], expected: &[Atom]) {
+        assert_eq!(loaded.len(), expected.len());
+        for (a, b) in loaded.iter().zip(expected) {
+            assert_eq!(a.coords, b.coords);
+            assert_eq!(a.residue_id, b.residue_id);
+            assert_eq!(a.charge, b.charge);
+        }
+    }
+
+    #[test]
+    fn test_chunked_zstd_roundtrip() {
+        let atoms = test_atoms(200);
+        let bonds: Vec<Bond> = (0..199)
+            .map(|i| Bond { atom1: i, atom2: i + 1, order: 1, bond_type: 0, _reserved: [0] })
+            .collect();
+        let bytes = HolographicBinaryFormat::new()
+            .with_atoms(atoms.clone())
+            .with_bonds(bonds)
+            .with_compression(PtbCompression::Zstd(3))
+            .with_chunk_size(1024)
+            .to_bytes()
+            .unwrap();
+
+        let mut structure = PtbStructure::from_bytes(&bytes).unwrap();
+        assert_eq!(structure.header().version, PTB_VERSION);
+        let atom_chunks: Vec<_> = structure.sections().iter().filter(|s| s.kind == SECTION_ATOMS).collect();
+        assert_eq!(atom_chunks.len(), (200 * size_of::<Atom>()).div_ceil(1024));
+        assert!(atom_chunks.iter().any(|s| s.codec == CODEC_ZSTD));
+        assert!(atom_chunks.iter().all(|s| (s.offset as usize).is_multiple_of(CHUNK_ALIGN)));
+        assert!(bytes.len() < 200 * size_of::<Atom>());
+
+        structure.verify_integrity().unwrap();
+        assert_same_atoms(structure.atoms().unwrap(), &atoms);
+        assert_eq!(structure.bonds().unwrap()[198].atom2, 199);
+        assert!(structure.secondary_structure().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_flat_layout_still_loads() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let atoms = test_atoms(10);
+        HolographicBinaryFormat::new()
+            .with_atoms(atoms.clone())
+            .with_version(PTB_VERSION_FLAT)
+            .write_to_file(temp_file.path())
+            .unwrap();
+
+        let mut structure = PtbStructure::load(temp_file.path()).unwrap();
+        assert_eq!(structure.header().version, PTB_VERSION_FLAT);
+        assert_eq!(structure.header().atoms_offset, size_of::<PtbHeader>() as u64);
+        assert!(structure.sections().is_empty());
+        assert_same_atoms(structure.atoms().unwrap(), &atoms);
+
+        // The flat layout has no room for chunk options
+        let compressed = HolographicBinaryFormat::new()
+            .with_version(PTB_VERSION_FLAT)
+            .with_compression(PtbCompression::Zstd(3))
+            .to_bytes();
+        assert!(compressed.is_err());
+    }
+
+    #[test]
+    fn test_checksums_catch_corruption() {
+        let bytes = HolographicBinaryFormat::new().with_atoms(test_atoms(10)).to_bytes().unwrap();
+        let chunk = PtbStructure::from_bytes(&bytes).unwrap().sections()[0];
+        assert_eq!((chunk.kind, chunk.codec), (SECTION_ATOMS, CODEC_NONE));
+
+        // A flipped coordinate byte loads but fails on access
+        let mut corrupted = bytes.clone();
+        corrupted[chunk.offset as usize + 1] ^= 0xff;
+        let mut structure = PtbStructure::from_bytes(&corrupted).unwrap();
+        assert!(matches!(structure.verify_integrity(), Err(PrismIoError::IntegrityViolation(_))));
+        assert!(matches!(structure.atoms(), Err(PrismIoError::IntegrityViolation(_))));
+
+        // A corrupted header count or table entry fails at load
+        let mut corrupted = bytes.clone();
+        corrupted[20] ^= 0x01;
+        assert!(matches!(PtbStructure::from_bytes(&corrupted), Err(PrismIoError::IntegrityViolation(_))));
+        let mut corrupted = bytes;
+        corrupted[size_of::<PtbHeader>() + 8 + 16] ^= 0x01;
+        assert!(PtbStructure::from_bytes(&corrupted).is_err());
+    }
+
+    #[test]
+    fn test_unknown_sections_are_skipped_unless_required() {
+        let atoms = test_atoms(4);
+        let bytes = HolographicBinaryFormat::new()
+            .with_atoms(atoms.clone())
+            .with_section(0x100, b"future metadata".to_vec(), false)
+            .to_bytes()
+            .unwrap();
+        let mut structure = PtbStructure::from_bytes(&bytes).unwrap();
+        assert_same_atoms(structure.atoms().unwrap(), &atoms);
+        assert_eq!(structure.section(0x100).unwrap(), Some(&b"future metadata"[..]));
+        assert_eq!(structure.section(0x101).unwrap(), None);
+
+        let bytes = HolographicBinaryFormat::new()
+            .with_atoms(atoms)
+            .with_section(0x100, b"future topology".to_vec(), true)
+            .to_bytes()
+            .unwrap();
+        assert!(matches!(PtbStructure::from_bytes(&bytes), Err(PrismIoError::FormatError(_))));
+    }
+
+    #[test]
+    fn test_atom_blocks_materialize_lazily() {
+        let atoms = test_atoms(100);
+        // 1000 bytes round down to 31 atoms per chunk
+        let bytes = HolographicBinaryFormat::new()
+            .with_atoms(atoms.clone())
+            .with_compression(PtbCompression::Zstd(3))
+            .with_chunk_size(1000)
+            .to_bytes()
+            .unwrap();
+        let mut structure = PtbStructure::from_bytes(&bytes).unwrap();
+        structure.set_block_cache_capacity(2);
+        assert_eq!(structure.atom_block_count(), 4);
+        assert_eq!(structure.atom_block_range(3), 93..100);
+
+        for block in [3, 0, 1, 3, 2] {
+            let range = structure.atom_block_range(block);
+            assert_same_atoms(structure.atom_block(block).unwrap(), &atoms[range]);
+            assert!(structure.block_cache.len() <= 2);
+        }
+        assert!(structure.atom_block(4).is_err());
+        assert_same_atoms(&structure.collect_atoms().unwrap(), &atoms);
+        // Nothing decoded the whole section
+        assert!(structure.decoded.is_empty());
+
+        // Flat files are split into fixed runs read in place
+        let bytes = HolographicBinaryFormat::new()
+            .with_atoms(test_atoms(FLAT_BLOCK_ATOMS + 5))
+            .with_version(PTB_VERSION_FLAT)
+            .to_bytes()
+            .unwrap();
+        let mut structure = PtbStructure::from_bytes(&bytes).unwrap();
+        assert_eq!(structure.atom_block_count(), 2);
+        assert_eq!(structure.atom_block(1).unwrap().len(), 5);
+        assert_eq!(structure.atom_block(1).unwrap()[0].coords[0], FLAT_BLOCK_ATOMS as f32);
+        assert_eq!(structure.collect_atoms().unwrap().len(), FLAT_BLOCK_ATOMS + 5);
     }
 
     #[test]
     fn test_header_size_alignment() {
         // SOVEREIGN STANDARD: Full 32-byte hash with C alignment padding
         // 112 bytes = 96 logical + 16 padding for 8-byte alignment
-        assert_eq!(std::mem::size_of::<PtbHeader>(), 112);
+        assert_eq!(size_of::<PtbHeader>(), 112);
         // Aligned to u64 boundaries for optimal memory access
-        assert_eq!(std::mem::align_of::<PtbHeader>(), 8);
+        assert_eq!(align_of::<PtbHeader>(), 8);
+        // Section table entries pack without padding
+        assert_eq!(size_of::<PtbSection>(), 40);
     }
 }
\ No newline at end of file
@@ -51,17 +51,49 @@
 use std::marker::PhantomData;
 
 // Core modules
+pub mod amber;
+pub mod charmm;
+pub mod dcd;
+pub mod disulfides;
+pub mod gaff;
+pub mod gap_repair;
+pub mod gromacs;
+mod geometry;
+pub mod h5md;
 pub mod holographic;
+pub mod ligand;
+pub mod mmcif;
+pub mod mol2;
+pub mod pdb;
+pub mod perception;
+pub mod protonation;
+pub mod ptb_convert;
+pub mod ptb_trajectory;
+pub mod residues;
+pub mod rotamers;
+pub mod sdf;
+pub mod selection;
+pub mod simulation_box;
+pub mod solvate;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod streaming;
+pub mod structure_file;
 pub mod validation;
 pub mod warp_parser;
+pub mod xtc;
 pub mod sovereign_types;
+pub mod topology;
+pub mod trajectory;
 
 // Re-exports for convenience
 pub use holographic::{HolographicBinaryFormat, PtbHeader, PtbStructure};
+#[cfg(not(target_arch = "wasm32"))]
 pub use streaming::{AsyncPinnedStreamer, StreamingError};
 pub use validation::{DataIntegrityValidator, ValidationError};
 pub use sovereign_types::{SovereignBuffer, SovereignError};
+pub use selection::{Selection, SelectionContext};
+pub use simulation_box::SimulationBox;
+pub use topology::Topology;
 
 /// Performance targets for Prism-Stream architecture components
 pub mod performance {
@@ -0,0 +1,271 @@
+//! # Small-Molecule Ligands
+//!
+//! Ligands arrive as SDF/MOL files ([`crate::sdf`]) or Tripos MOL2 files
+//! ([`crate::mol2`]) with explicit bonds and bond orders, which the protein
+//! formats do not carry. [`read_ligand`] picks the reader from the file
+//! extension; [`crate::gaff`] types the atoms and assigns force-field
+//! parameters, turning a [`Ligand`] into a [`Topology`] the engine runs next
+//! to the receptor.
+//!
+//! Partial charges are never computed: they come from the file (MOL2 charge
+//! column, the SDF `atom.dprop.PartialCharge` property) or from a separate
+//! charge file of one value per atom ([`read_charges`]).
+
+use crate::gaff::{parameterize, GaffReport};
+use crate::mol2::parse_mol2;
+use crate::pdb::{vdw_radius, PdbAtomRecord, PdbStructure};
+use crate::sdf::parse_sdf;
+use crate::sovereign_types::Atom;
+use crate::topology::Topology;
+use crate::{PrismIoError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Bond order of a ligand bond
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondOrder {
+    /// Single bond
+    Single,
+    /// Double bond
+    Double,
+    /// Triple bond
+    Triple,
+    /// Delocalised bond of an aromatic ring
+    Aromatic,
+}
+
+/// A ligand atom
+#[derive(Debug, Clone, PartialEq)]
+pub struct LigandAtom {
+    /// Atom name (element and index when the file gives none)
+    pub name: String,
+    /// Atomic number
+    pub element: u8,
+    /// Coordinates (Å)
+    pub coords: [f32; 3],
+    /// Formal charge (e)
+    pub formal_charge: i8,
+    /// Partial charge given by the file (e)
+    pub partial_charge: Option<f32>,
+}
+
+/// A bond between two ligand atoms (0-based indices)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LigandBond {
+    /// First atom index
+    pub i: u32,
+    /// Second atom index
+    pub j: u32,
+    /// Bond order
+    pub order: BondOrder,
+}
+
+/// A small molecule with explicit bonds
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ligand {
+    /// Molecule name, used as the residue name (at most 3 characters)
+    pub name: String,
+    /// Atoms in file order
+    pub atoms: Vec<LigandAtom>,
+    /// Bonds with their orders
+    pub bonds: Vec<LigandBond>,
+}
+
+impl Ligand {
+    /// Bonded neighbours of every atom with the bond orders
+    pub fn neighbours(&self) -> Vec<Vec<(u32, BondOrder)>> {
+        let mut neighbours = vec![Vec::new(); self.atoms.len()];
+        for b in &self.bonds {
+            neighbours[b.i as usize].push((b.j, b.order));
+            neighbours[b.j as usize].push((b.i, b.order));
+        }
+        neighbours
+    }
+
+    /// Partial charges of every atom, when the file gives them
+    pub fn partial_charges(&self) -> Option<Vec<f32>> {
+        self.atoms.iter().map(|a| a.partial_charge).collect()
+    }
+
+    /// Sum of the formal charges (e)
+    pub fn formal_charge(&self) -> i32 {
+        self.atoms.iter().map(|a| a.formal_charge as i32).sum()
+    }
+
+    /// Residue name written for the ligand
+    pub fn residue_name(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .take(3)
+            .collect::<String>()
+            .to_ascii_uppercase();
+        if name.is_empty() {
+            "LIG".to_string()
+        } else {
+            name
+        }
+    }
+
+    /// The ligand as HETATM records of one residue, with its bonds as
+    /// CONECT records
+    pub fn to_structure(&self, chain_id: char, residue_seq: i32) -> PdbStructure {
+        let residue_name = self.residue_name();
+        let mut structure = PdbStructure::default();
+        for (i, atom) in self.atoms.iter().enumerate() {
+            structure.atoms.push(Atom {
+                coords: atom.coords,
+                element: atom.element,
+                residue_id: 0,
+                atom_type: 0,
+                charge: atom.partial_charge.unwrap_or(atom.formal_charge as f32),
+                radius: vdw_radius(atom.element),
+                _reserved: [0; 4],
+            });
+            structure.records.push(PdbAtomRecord {
+                serial: i as u32 + 1,
+                name: atom.name.clone(),
+                residue_name: residue_name.clone(),
+                chain_id,
+                residue_seq,
+                insertion_code: ' ',
+                occupancy: 1.0,
+                b_factor: 0.0,
+                hetatm: true,
+            });
+        }
+        structure.conect = self
+            .bonds
+            .iter()
+            .map(|b| (b.i.min(b.j), b.i.max(b.j)))
+            .collect();
+        structure.conect.sort_unstable();
+        structure
+    }
+}
+
+/// Read the first molecule of an SDF/MOL (`.sdf`, `.mol`) or MOL2 (`.mol2`)
+/// file
+pub fn read_ligand<P: AsRef<Path>>(path: P) -> Result<Ligand> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let mut ligand = match extension.as_deref() {
+        Some("sdf" | "mol" | "sd") => parse_sdf(&content)?,
+        Some("mol2") => parse_mol2(&content)?,
+        _ => {
+            return Err(PrismIoError::FormatError(format!(
+                "Cannot infer ligand format of {} (expected .sdf, .mol or .mol2)",
+                path.display()
+            )))
+        }
+    };
+    if ligand.name.is_empty() {
+        ligand.name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+    }
+    Ok(ligand)
+}
+
+/// Read whitespace-separated partial charges, one per atom in file order;
+/// `#` starts a comment
+pub fn read_charges<P: AsRef<Path>>(path: P) -> Result<Vec<f32>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(str::split_whitespace)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                PrismIoError::FormatError(format!(
+                    "Invalid charge '{}' in {}",
+                    value,
+                    path.display()
+                ))
+            })
+        })
+        .collect()
+}
+
+/// A ligand of a run: its structure file and optional charge file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LigandSpec {
+    /// SDF/MOL or MOL2 file
+    pub structure: PathBuf,
+    /// Partial charges replacing those of the structure file
+    #[serde(default)]
+    pub charges: Option<PathBuf>,
+}
+
+impl LigandSpec {
+    /// Ligand whose charges come with its structure file
+    pub fn new(structure: impl Into<PathBuf>) -> Self {
+        Self {
+            structure: structure.into(),
+            charges: None,
+        }
+    }
+
+    /// Read and parameterize the ligand
+    pub fn load(&self) -> Result<(Ligand, Topology, GaffReport)> {
+        let ligand = read_ligand(&self.structure)?;
+        let charges = self.charges.as_ref().map(read_charges).transpose()?;
+        let (topology, report) = parameterize(&ligand, charges.as_deref())?;
+        Ok((ligand, topology, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mol2::tests::PHENOL;
+    use crate::pdb::parse_pdb;
+    use crate::sdf::tests::ACETATE;
+
+    #[test]
+    fn test_load_ligand_and_append_to_receptor() {
+        let dir = std::env::temp_dir().join(format!("prism_ligand_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("phenol.mol2"), PHENOL).unwrap();
+        std::fs::write(dir.join("acetate.sdf"), ACETATE).unwrap();
+        std::fs::write(
+            dir.join("acetate.chg"),
+            "# AM1-BCC\n-0.2 0.7 -0.8\n-0.8 0.03 0.03 0.04\n",
+        )
+        .unwrap();
+        assert!(read_ligand(dir.join("phenol.xyz")).is_err());
+
+        let (ligand, topology, report) = LigandSpec::new(dir.join("phenol.mol2")).load().unwrap();
+        assert_eq!(topology.num_atoms(), ligand.atoms.len());
+        assert_eq!(report.atom_types[0], "ca");
+        let spec = LigandSpec {
+            structure: dir.join("acetate.sdf"),
+            charges: Some(dir.join("acetate.chg")),
+        };
+        let (_, topology, _) = spec.load().unwrap();
+        assert!((topology.atoms[0].charge + 0.2).abs() < 1e-6);
+
+        let mut receptor = parse_pdb(
+            "ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N\n\
+             ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C\n",
+        )
+        .unwrap();
+        receptor.append(&ligand.to_structure('B', 1));
+        assert_eq!(receptor.atoms.len(), 15);
+        assert_eq!(receptor.atoms[2].residue_id, 1);
+        assert_eq!(receptor.records[2].residue_name, "PHE");
+        assert!(receptor.records[2].hetatm);
+        assert_eq!(receptor.records[14].serial, 15);
+        assert!(receptor.conect.contains(&(2, 7)));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
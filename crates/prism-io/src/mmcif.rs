@@ -0,0 +1,986 @@
+//! # mmCIF / PDBx Reader
+//!
+//! Large PDB entries are distributed as mmCIF only. This module tokenizes
+//! CIF data blocks and extracts:
+//! - `atom_site` → [`Atom`]s with [`PdbAtomRecord`] metadata (first model only)
+//! - `struct_conn` covalent/disulfide links → bonds
+//! - `pdbx_unobs_or_zero_occ_residues` unobserved polymer residues →
+//!   [`PdbStructure::missing_residues`]
+//! - `entity`, `pdbx_struct_assembly[_gen]` and `pdbx_struct_oper_list`
+//!   → entity and biological-assembly metadata
+//!
+//! Structures convert to the same [`VerifiedProteinData`] / `.ptb` output as
+//! the PTB path, and can be written back as a minimal `atom_site` mmCIF.
+
+use crate::holographic::HolographicBinaryFormat;
+use crate::pdb::{AltlocChoice, AltlocSelector, MissingResidue, PdbAtomRecord, PdbStructure};
+use crate::sovereign_types::{Atom, Bond, VerifiedProteinData};
+use crate::topology::Topology;
+use crate::{PrismIoError, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// One CIF category as a table (single key-value items form a one-row table)
+#[derive(Debug, Clone, Default)]
+pub struct CifCategory {
+    /// Item names without the category prefix (e.g. "Cartn_x")
+    pub columns: Vec<String>,
+    /// Row values; `None` for the CIF null markers `?` and `.`
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+impl CifCategory {
+    /// Column index of an item
+    pub fn column(&self, item: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c == item)
+    }
+
+    /// Value of `item` in `row`, if present and not null
+    pub fn get(&self, row: usize, item: &str) -> Option<&str> {
+        self.column(item)
+            .and_then(|c| self.rows.get(row)?.get(c)?.as_deref())
+    }
+}
+
+/// A `data_` block: category name (without leading `_`) → table
+#[derive(Debug, Clone, Default)]
+pub struct CifBlock {
+    /// Block name following `data_`
+    pub name: String,
+    /// Categories by name (e.g. "atom_site")
+    pub categories: HashMap<String, CifCategory>,
+}
+
+/// Entity record from `_entity`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    /// Entity identifier
+    pub id: String,
+    /// Entity type (polymer, non-polymer, water, ...)
+    pub entity_type: String,
+    /// Free-text description
+    pub description: String,
+}
+
+/// One generator of a biological assembly
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssemblyGenerator {
+    /// Operator id sequences; each entry is one composed operation
+    pub operations: Vec<Vec<String>>,
+    /// `label_asym_id`s the operations apply to
+    pub asym_ids: Vec<String>,
+}
+
+/// Biological assembly from `_pdbx_struct_assembly`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assembly {
+    /// Assembly identifier
+    pub id: String,
+    /// Description (e.g. "author_and_software_defined_assembly")
+    pub details: String,
+    /// Generators from `_pdbx_struct_assembly_gen`
+    pub generators: Vec<AssemblyGenerator>,
+}
+
+/// Parsed mmCIF structure
+#[derive(Debug, Clone, Default)]
+pub struct MmcifStructure {
+    /// Entry identifier (data block name)
+    pub id: String,
+    /// Atoms, per-atom metadata and `struct_conn` bonds
+    pub structure: PdbStructure,
+    /// `label_asym_id` of each atom
+    pub asym_ids: Vec<String>,
+    /// `label_entity_id` of each atom
+    pub entity_ids: Vec<String>,
+    /// Entities
+    pub entities: Vec<Entity>,
+    /// Biological assemblies
+    pub assemblies: Vec<Assembly>,
+    /// Symmetry operators: 3x4 `[R | t]` matrices by id
+    pub operators: HashMap<String, [[f32; 4]; 3]>,
+    /// BLAKE3 hash of the source text
+    pub source_hash: [u8; 32],
+}
+
+/// Split CIF text into tokens, handling quotes and semicolon text fields
+fn tokenize(content: &str) -> Vec<(String, bool)> {
+    let mut tokens = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        if let Some(first) = line.strip_prefix(';') {
+            let mut text = first.to_string();
+            for next in lines.by_ref() {
+                if next.starts_with(';') {
+                    break;
+                }
+                text.push('\n');
+                text.push_str(next);
+            }
+            tokens.push((text.trim().to_string(), true));
+            continue;
+        }
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let c = bytes[i];
+            if c.is_ascii_whitespace() {
+                i += 1;
+            } else if c == b'#' {
+                break;
+            } else if c == b'\'' || c == b'"' {
+                // Closing quote must be followed by whitespace or end of line
+                let mut j = i + 1;
+                while j < bytes.len()
+                    && !(bytes[j] == c && bytes.get(j + 1).is_none_or(|b| b.is_ascii_whitespace()))
+                {
+                    j += 1;
+                }
+                tokens.push((line[i + 1..j.min(bytes.len())].to_string(), true));
+                i = j + 1;
+            } else {
+                let start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                tokens.push((line[start..i].to_string(), false));
+            }
+        }
+    }
+    tokens
+}
+
+fn split_tag(tag: &str) -> (String, String) {
+    let tag = tag.trim_start_matches('_');
+    match tag.split_once('.') {
+        Some((cat, item)) => (cat.to_ascii_lowercase(), item.to_string()),
+        None => (tag.to_ascii_lowercase(), String::new()),
+    }
+}
+
+fn null_or(value: String, quoted: bool) -> Option<String> {
+    if !quoted && (value == "?" || value == ".") {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Parse the first data block of a CIF file
+pub fn parse_cif(content: &str) -> Result<CifBlock> {
+    let tokens = tokenize(content);
+    let mut block = CifBlock::default();
+    let mut seen_block = false;
+    let is_keyword = |t: &(String, bool)| {
+        !t.1 && (t.0.starts_with('_') || t.0 == "loop_" || t.0.starts_with("data_"))
+    };
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let (token, quoted) = &tokens[i];
+        if !quoted && token.starts_with("data_") {
+            if seen_block {
+                break;
+            }
+            seen_block = true;
+            block.name = token["data_".len()..].to_string();
+            i += 1;
+        } else if !quoted && token == "loop_" {
+            i += 1;
+            let mut category = String::new();
+            let mut table = CifCategory::default();
+            while i < tokens.len() && !tokens[i].1 && tokens[i].0.starts_with('_') {
+                let (cat, item) = split_tag(&tokens[i].0);
+                category = cat;
+                table.columns.push(item);
+                i += 1;
+            }
+            if table.columns.is_empty() {
+                return Err(PrismIoError::FormatError(
+                    "loop_ without item names".to_string(),
+                ));
+            }
+            let mut row = Vec::with_capacity(table.columns.len());
+            while i < tokens.len() && !is_keyword(&tokens[i]) {
+                let (value, q) = tokens[i].clone();
+                row.push(null_or(value, q));
+                if row.len() == table.columns.len() {
+                    table.rows.push(std::mem::take(&mut row));
+                }
+                i += 1;
+            }
+            if !row.is_empty() {
+                return Err(PrismIoError::FormatError(format!(
+                    "Loop for _{} has a value count not divisible by {} items",
+                    category,
+                    table.columns.len()
+                )));
+            }
+            block.categories.insert(category, table);
+        } else if !quoted && token.starts_with('_') {
+            let (cat, item) = split_tag(token);
+            let (value, q) = tokens
+                .get(i + 1)
+                .cloned()
+                .ok_or_else(|| PrismIoError::FormatError(format!("Missing value for {}", token)))?;
+            let table = block.categories.entry(cat).or_default();
+            if table.rows.is_empty() {
+                table.rows.push(Vec::new());
+            }
+            table.columns.push(item);
+            table.rows[0].push(null_or(value, q));
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+
+    if !seen_block {
+        return Err(PrismIoError::FormatError(
+            "No data_ block in CIF".to_string(),
+        ));
+    }
+    Ok(block)
+}
+
+/// Read an mmCIF file
+pub fn read_mmcif<P: AsRef<Path>>(path: P) -> Result<MmcifStructure> {
+    parse_mmcif(&std::fs::read_to_string(path)?)
+}
+
+/// Read an mmCIF file, resolving alternate locations by `altloc`
+pub fn read_mmcif_with<P: AsRef<Path>>(path: P, altloc: AltlocChoice) -> Result<MmcifStructure> {
+    parse_mmcif_with(&std::fs::read_to_string(path)?, altloc)
+}
+
+/// Parse mmCIF content
+pub fn parse_mmcif(content: &str) -> Result<MmcifStructure> {
+    parse_mmcif_with(content, AltlocChoice::First)
+}
+
+/// Parse mmCIF content, resolving alternate locations by `altloc` per
+/// residue (see [`AltlocChoice`])
+pub fn parse_mmcif_with(content: &str, altloc: AltlocChoice) -> Result<MmcifStructure> {
+    let block = parse_cif(content)?;
+    let sites = block
+        .categories
+        .get("atom_site")
+        .ok_or_else(|| PrismIoError::FormatError("mmCIF has no atom_site category".to_string()))?;
+    for required in ["Cartn_x", "Cartn_y", "Cartn_z"] {
+        if sites.column(required).is_none() {
+            return Err(PrismIoError::FormatError(format!(
+                "atom_site missing {}",
+                required
+            )));
+        }
+    }
+
+    let mut mmcif = MmcifStructure {
+        id: block.name.clone(),
+        source_hash: *blake3::hash(content.as_bytes()).as_bytes(),
+        ..Default::default()
+    };
+    let first_model = sites.get(0, "pdbx_PDB_model_num").map(str::to_string);
+    let residue_key = |row: usize| {
+        let get = |item: &str| sites.get(row, item);
+        let chain = get("auth_asym_id").or(get("label_asym_id")).unwrap_or("A");
+        let seq = get("auth_seq_id").or(get("label_seq_id")).unwrap_or("0");
+        let insertion_code = get("pdbx_PDB_ins_code")
+            .and_then(|s| s.chars().next())
+            .unwrap_or(' ');
+        (chain.to_string(), seq.to_string(), insertion_code)
+    };
+
+    let mut selector = AltlocSelector::new();
+    for row in 0..sites.rows.len() {
+        let get = |item: &str| sites.get(row, item);
+        if get("pdbx_PDB_model_num").map(str::to_string) != first_model {
+            continue;
+        }
+        let Some(alt) = get("label_alt_id").and_then(|a| a.chars().next()) else {
+            continue;
+        };
+        let key = residue_key(row);
+        let label = (
+            key.0.chars().next().unwrap_or('A'),
+            key.1.parse().unwrap_or(0),
+            key.2,
+        );
+        let residue_name = get("label_comp_id").or(get("auth_comp_id")).unwrap_or("UNK");
+        let occupancy = get("occupancy").and_then(|v| v.parse().ok()).unwrap_or(1.0);
+        selector.observe(key, label, residue_name, alt, occupancy);
+    }
+    let (kept, altlocs) = selector.select(altloc);
+    mmcif.structure.altlocs = altlocs;
+
+    let mut last_residue: Option<(String, String, char)> = None;
+    let mut residue: i64 = -1;
+    // (label_asym_id, auth_seq_id, atom name) -> atom index, for struct_conn
+    let mut site_index: HashMap<(String, String, String), u32> = HashMap::new();
+
+    for row in 0..sites.rows.len() {
+        let get = |item: &str| sites.get(row, item);
+        if get("pdbx_PDB_model_num").map(str::to_string) != first_model {
+            continue;
+        }
+        let key = residue_key(row);
+        let alt = get("label_alt_id").and_then(|a| a.chars().next());
+        if alt.is_some() && kept.get(&key) != alt.as_ref() {
+            continue;
+        }
+        let coord = |item: &str| -> Result<f32> {
+            get(item).and_then(|v| v.parse().ok()).ok_or_else(|| {
+                PrismIoError::FormatError(format!("Invalid {} in atom_site row {}", item, row + 1))
+            })
+        };
+        let coords = [coord("Cartn_x")?, coord("Cartn_y")?, coord("Cartn_z")?];
+
+        let (chain, seq, insertion_code) = key.clone();
+        if last_residue.as_ref() != Some(&key) {
+            residue += 1;
+            last_residue = Some(key);
+        }
+        if residue > u16::MAX as i64 {
+            return Err(PrismIoError::FormatError(
+                "Residue count exceeds the 16-bit residue index".to_string(),
+            ));
+        }
+
+        let name = get("label_atom_id")
+            .or(get("auth_atom_id"))
+            .unwrap_or("X")
+            .to_string();
+        let element = Topology::atomic_number(get("type_symbol").unwrap_or(""));
+        let asym = get("label_asym_id").unwrap_or(&chain).to_string();
+        let index = mmcif.structure.atoms.len() as u32;
+        site_index.insert((asym.clone(), seq.clone(), name.clone()), index);
+
+        mmcif.structure.atoms.push(Atom {
+            coords,
+            element,
+            residue_id: residue as u16,
+            atom_type: 0,
+            charge: get("pdbx_formal_charge")
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(0.0),
+            radius: crate::pdb::vdw_radius(element),
+            _reserved: [0; 4],
+        });
+        mmcif.structure.records.push(PdbAtomRecord {
+            serial: get("id").and_then(|s| s.parse().ok()).unwrap_or(index + 1),
+            name,
+            residue_name: get("label_comp_id")
+                .or(get("auth_comp_id"))
+                .unwrap_or("UNK")
+                .to_string(),
+            chain_id: chain.chars().next().unwrap_or('A'),
+            residue_seq: seq.parse().unwrap_or(0),
+            insertion_code,
+            occupancy: get("occupancy").and_then(|v| v.parse().ok()).unwrap_or(1.0),
+            b_factor: get("B_iso_or_equiv")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.0),
+            hetatm: get("group_PDB") == Some("HETATM"),
+        });
+        mmcif.asym_ids.push(asym);
+        mmcif
+            .entity_ids
+            .push(get("label_entity_id").unwrap_or("").to_string());
+    }
+
+    if mmcif.structure.atoms.is_empty() {
+        return Err(PrismIoError::FormatError(
+            "atom_site contains no atoms".to_string(),
+        ));
+    }
+
+    if let Some(unobserved) = block.categories.get("pdbx_unobs_or_zero_occ_residues") {
+        for row in 0..unobserved.rows.len() {
+            let get = |item: &str| unobserved.get(row, item);
+            let model = get("PDB_model_num");
+            if get("polymer_flag") == Some("N")
+                || get("occupancy_flag") == Some("0")
+                || (model.is_some() && model != first_model.as_deref())
+            {
+                continue;
+            }
+            let Some(residue_seq) = get("auth_seq_id")
+                .or(get("label_seq_id"))
+                .and_then(|s| s.parse().ok())
+            else {
+                continue;
+            };
+            mmcif.structure.missing_residues.push(MissingResidue {
+                chain_id: get("auth_asym_id")
+                    .or(get("label_asym_id"))
+                    .and_then(|c| c.chars().next())
+                    .unwrap_or('A'),
+                residue_seq,
+                insertion_code: get("PDB_ins_code")
+                    .and_then(|c| c.chars().next())
+                    .unwrap_or(' '),
+                residue_name: get("auth_comp_id")
+                    .or(get("label_comp_id"))
+                    .unwrap_or("UNK")
+                    .to_string(),
+            });
+        }
+    }
+
+    if let Some(conn) = block.categories.get("struct_conn") {
+        for row in 0..conn.rows.len() {
+            if !matches!(
+                conn.get(row, "conn_type_id"),
+                Some("disulf") | Some("covale")
+            ) {
+                continue;
+            }
+            let partner = |p: &str| -> Option<u32> {
+                let asym = conn.get(row, &format!("{}_label_asym_id", p))?;
+                let seq = conn
+                    .get(row, &format!("{}_auth_seq_id", p))
+                    .or(conn.get(row, &format!("{}_label_seq_id", p)))?;
+                let atom = conn.get(row, &format!("{}_label_atom_id", p))?;
+                site_index
+                    .get(&(asym.to_string(), seq.to_string(), atom.to_string()))
+                    .copied()
+            };
+            if let (Some(a), Some(b)) = (partner("ptnr1"), partner("ptnr2")) {
+                mmcif.structure.conect.push((a.min(b), a.max(b)));
+            }
+        }
+        mmcif.structure.conect.sort_unstable();
+        mmcif.structure.conect.dedup();
+    }
+
+    if let Some(cell) = block.categories.get("cell") {
+        let items = [
+            "length_a",
+            "length_b",
+            "length_c",
+            "angle_alpha",
+            "angle_beta",
+            "angle_gamma",
+        ];
+        let values: Vec<f32> = items
+            .iter()
+            .filter_map(|i| cell.get(0, i).and_then(|v| v.parse().ok()))
+            .collect();
+        if values.len() == 6 {
+            mmcif.structure.cryst1 = Some([
+                values[0], values[1], values[2], values[3], values[4], values[5],
+            ]);
+        }
+    }
+
+    if let Some(entity) = block.categories.get("entity") {
+        for row in 0..entity.rows.len() {
+            mmcif.entities.push(Entity {
+                id: entity.get(row, "id").unwrap_or("").to_string(),
+                entity_type: entity.get(row, "type").unwrap_or("").to_string(),
+                description: entity
+                    .get(row, "pdbx_description")
+                    .unwrap_or("")
+                    .to_string(),
+            });
+        }
+    }
+
+    if let Some(opers) = block.categories.get("pdbx_struct_oper_list") {
+        for row in 0..opers.rows.len() {
+            let mut m = [[0.0f32; 4]; 3];
+            for (r, mr) in m.iter_mut().enumerate() {
+                for (c, v) in mr.iter_mut().take(3).enumerate() {
+                    *v = opers
+                        .get(row, &format!("matrix[{}][{}]", r + 1, c + 1))
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(if r == c { 1.0 } else { 0.0 });
+                }
+                mr[3] = opers
+                    .get(row, &format!("vector[{}]", r + 1))
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0.0);
+            }
+            if let Some(id) = opers.get(row, "id") {
+                mmcif.operators.insert(id.to_string(), m);
+            }
+        }
+    }
+
+    if let Some(assemblies) = block.categories.get("pdbx_struct_assembly") {
+        let gens = block.categories.get("pdbx_struct_assembly_gen");
+        for row in 0..assemblies.rows.len() {
+            let id = assemblies.get(row, "id").unwrap_or("").to_string();
+            let mut generators = Vec::new();
+            if let Some(gens) = gens {
+                for g in 0..gens.rows.len() {
+                    if gens.get(g, "assembly_id") != Some(id.as_str()) {
+                        continue;
+                    }
+                    generators.push(AssemblyGenerator {
+                        operations: parse_oper_expression(
+                            gens.get(g, "oper_expression").unwrap_or("1"),
+                        )?,
+                        asym_ids: gens
+                            .get(g, "asym_id_list")
+                            .unwrap_or("")
+                            .split(',')
+                            .map(|s| s.trim().to_string())
+                            .filter(|s| !s.is_empty())
+                            .collect(),
+                    });
+                }
+            }
+            mmcif.assemblies.push(Assembly {
+                id,
+                details: assemblies.get(row, "details").unwrap_or("").to_string(),
+                generators,
+            });
+        }
+    }
+
+    Ok(mmcif)
+}
+
+/// Expand an operator expression such as `1`, `1,2`, `(1-60)` or
+/// `(X0)(1-5)` into composed operator id sequences (leftmost applied last)
+fn parse_oper_expression(expr: &str) -> Result<Vec<Vec<String>>> {
+    let groups: Vec<&str> = if expr.contains('(') {
+        expr.split(['(', ')'])
+            .filter(|s| !s.trim().is_empty())
+            .collect()
+    } else {
+        vec![expr]
+    };
+    let mut result: Vec<Vec<String>> = vec![Vec::new()];
+    for group in groups {
+        let mut ids = Vec::new();
+        for part in group.split(',').map(str::trim) {
+            match part.split_once('-') {
+                Some((a, b)) => {
+                    let (a, b): (u32, u32) = a
+                        .trim()
+                        .parse()
+                        .ok()
+                        .zip(b.trim().parse().ok())
+                        .ok_or_else(|| {
+                            PrismIoError::FormatError(format!("Invalid operator range '{}'", part))
+                        })?;
+                    ids.extend((a..=b).map(|n| n.to_string()));
+                }
+                None => ids.push(part.to_string()),
+            }
+        }
+        result = result
+            .iter()
+            .flat_map(|prefix| {
+                ids.iter().map(move |id| {
+                    let mut seq = prefix.clone();
+                    seq.push(id.clone());
+                    seq
+                })
+            })
+            .collect();
+    }
+    Ok(result)
+}
+
+fn compose(a: &[[f32; 4]; 3], b: &[[f32; 4]; 3]) -> [[f32; 4]; 3] {
+    let mut m = [[0.0f32; 4]; 3];
+    for r in 0..3 {
+        for c in 0..4 {
+            m[r][c] =
+                (0..3).map(|k| a[r][k] * b[k][c]).sum::<f32>() + if c == 3 { a[r][3] } else { 0.0 };
+        }
+    }
+    m
+}
+
+impl MmcifStructure {
+    /// Atoms of a biological assembly, each copy with its own residue range
+    pub fn build_assembly(&self, assembly_id: &str) -> Result<Vec<Atom>> {
+        let assembly = self
+            .assemblies
+            .iter()
+            .find(|a| a.id == assembly_id)
+            .ok_or_else(|| {
+                PrismIoError::ValidationError(format!("No assembly with id {}", assembly_id))
+            })?;
+        let residues = self
+            .structure
+            .atoms
+            .iter()
+            .map(|a| a.residue_id as u32 + 1)
+            .max()
+            .unwrap_or(0);
+        let mut atoms = Vec::new();
+        let mut copy = 0u32;
+        for generator in &assembly.generators {
+            let chains: HashSet<&str> = generator.asym_ids.iter().map(String::as_str).collect();
+            for ops in &generator.operations {
+                let mut m = [
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                ];
+                for id in ops {
+                    let op = self.operators.get(id).ok_or_else(|| {
+                        PrismIoError::FormatError(format!(
+                            "Assembly references unknown operator {}",
+                            id
+                        ))
+                    })?;
+                    m = compose(&m, op);
+                }
+                let offset = copy * residues;
+                copy += 1;
+                for (atom, asym) in self.structure.atoms.iter().zip(&self.asym_ids) {
+                    if !chains.contains(asym.as_str()) {
+                        continue;
+                    }
+                    let residue_id = atom.residue_id as u32 + offset;
+                    if residue_id > u16::MAX as u32 {
+                        return Err(PrismIoError::ValidationError(
+                            "Assembly exceeds the 16-bit residue index".to_string(),
+                        ));
+                    }
+                    let p = atom.coords;
+                    let mut out = *atom;
+                    for (r, row) in m.iter().enumerate() {
+                        out.coords[r] = row[0] * p[0] + row[1] * p[1] + row[2] * p[2] + row[3];
+                    }
+                    out.residue_id = residue_id as u16;
+                    atoms.push(out);
+                }
+            }
+        }
+        Ok(atoms)
+    }
+
+    /// `struct_conn` links as sovereign bonds
+    pub fn bonds(&self) -> Vec<Bond> {
+        self.structure
+            .conect
+            .iter()
+            .map(|&(atom1, atom2)| Bond {
+                atom1,
+                atom2,
+                order: 1,
+                bond_type: 0,
+                _reserved: [0],
+            })
+            .collect()
+    }
+
+    /// Convert to sovereign protein data, tagged with the source hash
+    pub fn to_verified_protein_data(&self) -> VerifiedProteinData {
+        VerifiedProteinData::from_authenticated_source(
+            self.structure.atoms.clone(),
+            self.bonds(),
+            Vec::new(),
+            format!("mmcif:{}", hex::encode(self.source_hash)),
+            self.source_hash,
+        )
+    }
+
+    /// Write as a `.ptb` file
+    pub fn write_ptb<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        HolographicBinaryFormat::new()
+            .with_source_hash(self.source_hash)
+            .with_atoms(self.structure.atoms.clone())
+            .with_bonds(self.bonds())
+            .write_to_file(path)
+    }
+
+    /// Wrap bare atoms (e.g. loaded from `.ptb`) with generated metadata
+    pub fn from_atoms(id: &str, atoms: &[Atom]) -> Self {
+        let structure = PdbStructure::from_atoms(atoms);
+        let asym_ids = structure
+            .records
+            .iter()
+            .map(|r| r.chain_id.to_string())
+            .collect();
+        Self {
+            id: id.to_string(),
+            structure,
+            asym_ids,
+            entity_ids: vec!["1".to_string(); atoms.len()],
+            ..Default::default()
+        }
+    }
+
+    /// Render a minimal mmCIF with an `atom_site` loop
+    pub fn to_mmcif_string(&self) -> String {
+        let quote = |s: &str| {
+            if s.is_empty() {
+                ".".to_string()
+            } else if s.contains(char::is_whitespace)
+                || s.starts_with(['_', '#', '$', '\'', '"', ';'])
+            {
+                format!("\"{}\"", s)
+            } else {
+                s.to_string()
+            }
+        };
+        let mut out = format!(
+            "data_{}\n#\nloop_\n",
+            if self.id.is_empty() {
+                "PRISM"
+            } else {
+                &self.id
+            }
+        );
+        for item in [
+            "group_PDB",
+            "id",
+            "type_symbol",
+            "label_atom_id",
+            "label_comp_id",
+            "label_asym_id",
+            "label_entity_id",
+            "auth_seq_id",
+            "pdbx_PDB_ins_code",
+            "Cartn_x",
+            "Cartn_y",
+            "Cartn_z",
+            "occupancy",
+            "B_iso_or_equiv",
+            "pdbx_formal_charge",
+            "auth_asym_id",
+            "pdbx_PDB_model_num",
+        ] {
+            let _ = writeln!(out, "_atom_site.{}", item);
+        }
+        for (i, (atom, rec)) in self
+            .structure
+            .atoms
+            .iter()
+            .zip(&self.structure.records)
+            .enumerate()
+        {
+            let symbol = Topology::element_symbol(atom.element);
+            let _ = writeln!(
+                out,
+                "{} {} {} {} {} {} {} {} {} {:.3} {:.3} {:.3} {:.2} {:.2} {} {} 1",
+                if rec.hetatm { "HETATM" } else { "ATOM" },
+                i + 1,
+                if symbol.is_empty() { "X" } else { symbol },
+                quote(&rec.name),
+                quote(&rec.residue_name),
+                quote(self.asym_ids.get(i).map(String::as_str).unwrap_or("A")),
+                quote(self.entity_ids.get(i).map(String::as_str).unwrap_or("1")),
+                rec.residue_seq,
+                if rec.insertion_code == ' ' {
+                    "?".to_string()
+                } else {
+                    rec.insertion_code.to_string()
+                },
+                atom.coords[0],
+                atom.coords[1],
+                atom.coords[2],
+                rec.occupancy,
+                rec.b_factor,
+                atom.charge.round() as i32,
+                rec.chain_id,
+            );
+        }
+        out.push_str("#\n");
+        out
+    }
+
+    /// Write a minimal mmCIF file
+    pub fn write_mmcif<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_mmcif_string())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holographic::PtbStructure;
+
+    const CIF: &str = "data_1ABC
+#
+_entry.id 1ABC
+_cell.length_a 40.0
+_cell.length_b 50.0
+_cell.length_c 60.0
+_cell.angle_alpha 90.0
+_cell.angle_beta 90.0
+_cell.angle_gamma 90.0
+#
+loop_
+_entity.id
+_entity.type
+_entity.pdbx_description
+1 polymer 'Spike glycoprotein'
+2 non-polymer
+;ZINC ION
+;
+#
+loop_
+_atom_site.group_PDB
+_atom_site.id
+_atom_site.type_symbol
+_atom_site.label_atom_id
+_atom_site.label_alt_id
+_atom_site.label_comp_id
+_atom_site.label_asym_id
+_atom_site.label_entity_id
+_atom_site.label_seq_id
+_atom_site.pdbx_PDB_ins_code
+_atom_site.Cartn_x
+_atom_site.Cartn_y
+_atom_site.Cartn_z
+_atom_site.occupancy
+_atom_site.B_iso_or_equiv
+_atom_site.pdbx_formal_charge
+_atom_site.auth_seq_id
+_atom_site.auth_asym_id
+_atom_site.pdbx_PDB_model_num
+ATOM   1 N  N   . CYS A 1 1 ? 1.000 2.000 3.000 1.00 10.0 ? 1 A 1
+ATOM   2 C  CA  . CYS A 1 1 ? 2.000 2.000 3.000 1.00 10.0 ? 1 A 1
+ATOM   3 S  SG  A CYS A 1 1 ? 3.000 2.000 3.000 0.50 10.0 ? 1 A 1
+ATOM   4 S  SG  B CYS A 1 1 ? 3.100 2.100 3.000 0.50 10.0 ? 1 A 1
+ATOM   5 S  SG  . CYS A 1 5 ? 5.000 2.000 3.000 1.00 12.0 ? 5 A 1
+HETATM 6 ZN ZN  . ZN  B 2 . ? 0.000 0.000 0.000 1.00 20.0 2 101 A 1
+ATOM   7 N  N   . CYS A 1 1 ? 9.000 9.000 9.000 1.00 10.0 ? 1 A 2
+#
+loop_
+_struct_conn.id
+_struct_conn.conn_type_id
+_struct_conn.ptnr1_label_asym_id
+_struct_conn.ptnr1_label_atom_id
+_struct_conn.ptnr1_auth_seq_id
+_struct_conn.ptnr2_label_asym_id
+_struct_conn.ptnr2_label_atom_id
+_struct_conn.ptnr2_auth_seq_id
+disulf1 disulf A SG 1 A SG 5
+metalc1 metalc A SG 1 B ZN 101
+#
+loop_
+_pdbx_unobs_or_zero_occ_residues.id
+_pdbx_unobs_or_zero_occ_residues.PDB_model_num
+_pdbx_unobs_or_zero_occ_residues.polymer_flag
+_pdbx_unobs_or_zero_occ_residues.occupancy_flag
+_pdbx_unobs_or_zero_occ_residues.auth_asym_id
+_pdbx_unobs_or_zero_occ_residues.auth_comp_id
+_pdbx_unobs_or_zero_occ_residues.auth_seq_id
+_pdbx_unobs_or_zero_occ_residues.PDB_ins_code
+1 1 Y 1 A GLY 2 ?
+2 1 Y 1 A ALA 3 ?
+3 1 Y 0 A SER 4 ?
+4 2 Y 1 A GLY 2 ?
+#
+loop_
+_pdbx_struct_assembly.id
+_pdbx_struct_assembly.details
+1 author_defined_assembly
+#
+_pdbx_struct_assembly_gen.assembly_id 1
+_pdbx_struct_assembly_gen.oper_expression (1-2)
+_pdbx_struct_assembly_gen.asym_id_list A,B
+#
+loop_
+_pdbx_struct_oper_list.id
+_pdbx_struct_oper_list.matrix[1][1]
+_pdbx_struct_oper_list.matrix[1][2]
+_pdbx_struct_oper_list.matrix[1][3]
+_pdbx_struct_oper_list.vector[1]
+_pdbx_struct_oper_list.matrix[2][1]
+_pdbx_struct_oper_list.matrix[2][2]
+_pdbx_struct_oper_list.matrix[2][3]
+_pdbx_struct_oper_list.vector[2]
+_pdbx_struct_oper_list.matrix[3][1]
+_pdbx_struct_oper_list.matrix[3][2]
+_pdbx_struct_oper_list.matrix[3][3]
+_pdbx_struct_oper_list.vector[3]
+1 1 0 0 0 0 1 0 0 0 0 1 0
+2 -1 0 0 10 0 -1 0 0 0 0 1 0
+#
+";
+
+    #[test]
+    fn test_parse_mmcif() {
+        let cif = parse_mmcif(CIF).unwrap();
+        assert_eq!(cif.id, "1ABC");
+        let atoms = &cif.structure.atoms;
+        assert_eq!(atoms.len(), 5);
+        assert_eq!(
+            atoms.iter().map(|a| a.residue_id).collect::<Vec<_>>(),
+            vec![0, 0, 0, 1, 2]
+        );
+        assert_eq!(atoms[2].element, 16);
+        assert_eq!(atoms[4].element, 30);
+        assert!((atoms[4].charge - 2.0).abs() < 1e-6);
+        assert!(cif.structure.records[4].hetatm);
+        // Only the disulfide is kept as a bond
+        assert_eq!(cif.structure.conect, vec![(2, 3)]);
+        assert_eq!(cif.entities[1].description, "ZINC ION");
+        assert_eq!(cif.entities[0].description, "Spike glycoprotein");
+        assert_eq!(cif.structure.cryst1.unwrap()[2], 60.0);
+        assert_eq!(cif.assemblies[0].generators[0].operations.len(), 2);
+        // Unobserved residues of model 1, not those modelled at zero occupancy
+        let missing: Vec<(i32, &str)> = cif
+            .structure
+            .missing_residues
+            .iter()
+            .map(|m| (m.residue_seq, m.residue_name.as_str()))
+            .collect();
+        assert_eq!(missing, vec![(2, "GLY"), (3, "ALA")]);
+    }
+
+    #[test]
+    fn test_altloc_choice() {
+        let cif = parse_mmcif(CIF).unwrap();
+        let site = &cif.structure.altlocs[0];
+        assert_eq!((site.residue_seq, site.kept), (1, 'A'));
+        assert_eq!(site.altlocs, vec!['A', 'B']);
+
+        let cif = parse_mmcif_with(CIF, AltlocChoice::Id('B')).unwrap();
+        assert_eq!(cif.structure.atoms.len(), 5);
+        assert!((cif.structure.atoms[2].coords[0] - 3.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_build_assembly() {
+        let cif = parse_mmcif(CIF).unwrap();
+        let atoms = cif.build_assembly("1").unwrap();
+        assert_eq!(atoms.len(), 10);
+        // Second copy: x -> 10 - x, y -> -y
+        assert!((atoms[5].coords[0] - 9.0).abs() < 1e-6);
+        assert!((atoms[5].coords[1] + 2.0).abs() < 1e-6);
+        assert_eq!(atoms[5].residue_id, 3);
+    }
+
+    #[test]
+    fn test_oper_expression() {
+        let ops = parse_oper_expression("(X0)(1-3)").unwrap();
+        assert_eq!(ops.len(), 3);
+        assert_eq!(ops[2], vec!["X0".to_string(), "3".to_string()]);
+        assert_eq!(parse_oper_expression("1,2").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_ptb_round_trip() {
+        let cif = parse_mmcif(CIF).unwrap();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        cif.write_ptb(file.path()).unwrap();
+
+        let mut ptb = PtbStructure::load(file.path()).unwrap();
+        assert_eq!(ptb.source_hash(), cif.source_hash);
+        assert_eq!(ptb.bonds().unwrap().len(), 1);
+        let atoms = ptb.atoms().unwrap().to_vec();
+
+        let text = MmcifStructure::from_atoms("RT", &atoms).to_mmcif_string();
+        let again = parse_mmcif(&text).unwrap();
+        assert_eq!(again.structure.atoms.len(), atoms.len());
+        for (a, b) in again.structure.atoms.iter().zip(&cif.structure.atoms) {
+            assert_eq!(a.coords, b.coords);
+            assert_eq!(a.element, b.element);
+            assert_eq!(a.residue_id, b.residue_id);
+        }
+    }
+}
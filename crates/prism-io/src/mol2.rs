@@ -0,0 +1,176 @@
+//! # Tripos MOL2 Reader
+//!
+//! Reads the first molecule of a MOL2 file: the name from
+//! `@<TRIPOS>MOLECULE`, atoms with their SYBYL types and partial charges
+//! from `@<TRIPOS>ATOM` and bonds from `@<TRIPOS>BOND`. Elements come from
+//! the SYBYL type (`C.ar` → C, `Cl` → Cl); amide bonds (`am`) are read as
+//! single bonds. Charges are kept unless the molecule record declares
+//! `NO_CHARGES`.
+
+use crate::ligand::{BondOrder, Ligand, LigandAtom, LigandBond};
+use crate::topology::Topology;
+use crate::{PrismIoError, Result};
+
+/// Parse the first molecule of a MOL2 file
+pub fn parse_mol2(content: &str) -> Result<Ligand> {
+    let error = |message: String| PrismIoError::FormatError(format!("MOL2: {}", message));
+    let mut ligand = Ligand::default();
+    let mut section = "";
+    let mut molecule_line = 0;
+    let mut charged = true;
+    let mut seen_molecule = false;
+    let mut ids: Vec<u32> = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix("@<TRIPOS>") {
+            if name == "MOLECULE" && seen_molecule {
+                break;
+            }
+            seen_molecule |= name == "MOLECULE";
+            section = match name {
+                "MOLECULE" => "MOLECULE",
+                "ATOM" => "ATOM",
+                "BOND" => "BOND",
+                _ => "",
+            };
+            molecule_line = 0;
+            continue;
+        }
+        let fields: Vec<&str> = trimmed.split_whitespace().collect();
+        match section {
+            "MOLECULE" => {
+                match molecule_line {
+                    0 => ligand.name = trimmed.to_string(),
+                    3 => charged = trimmed != "NO_CHARGES",
+                    _ => {}
+                }
+                molecule_line += 1;
+            }
+            "ATOM" => {
+                if fields.len() < 6 {
+                    return Err(error(format!("invalid atom line '{}'", trimmed)));
+                }
+                let coordinate = |k: usize| {
+                    fields[k]
+                        .parse::<f32>()
+                        .map_err(|_| error(format!("invalid atom line '{}'", trimmed)))
+                };
+                let symbol = fields[5].split('.').next().unwrap_or_default();
+                let element = Topology::atomic_number(symbol);
+                if element == 0 {
+                    return Err(error(format!("unknown atom type '{}'", fields[5])));
+                }
+                let partial_charge = fields
+                    .get(8)
+                    .and_then(|c| c.parse::<f32>().ok())
+                    .filter(|_| charged);
+                ids.push(
+                    fields[0]
+                        .parse()
+                        .map_err(|_| error(format!("invalid atom id '{}'", fields[0])))?,
+                );
+                ligand.atoms.push(LigandAtom {
+                    name: fields[1].to_string(),
+                    element,
+                    coords: [coordinate(2)?, coordinate(3)?, coordinate(4)?],
+                    formal_charge: 0,
+                    partial_charge,
+                });
+            }
+            "BOND" => {
+                if fields.len() < 4 {
+                    return Err(error(format!("invalid bond line '{}'", trimmed)));
+                }
+                let index = |k: usize| {
+                    fields[k]
+                        .parse::<u32>()
+                        .ok()
+                        .and_then(|id| ids.iter().position(|&a| a == id))
+                        .map(|i| i as u32)
+                        .ok_or_else(|| error(format!("bond to unknown atom '{}'", fields[k])))
+                };
+                let order = match fields[3] {
+                    "1" | "am" => BondOrder::Single,
+                    "2" => BondOrder::Double,
+                    "3" => BondOrder::Triple,
+                    "ar" => BondOrder::Aromatic,
+                    other => return Err(error(format!("unsupported bond type '{}'", other))),
+                };
+                ligand.bonds.push(LigandBond {
+                    i: index(1)?,
+                    j: index(2)?,
+                    order,
+                });
+            }
+            _ => {}
+        }
+    }
+    if ligand.atoms.is_empty() {
+        return Err(error("no atoms".to_string()));
+    }
+    Ok(ligand)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Phenol with AM1-BCC-like charges
+    pub(crate) const PHENOL: &str = "\
+@<TRIPOS>MOLECULE
+PHENOL
+   13    13     1     0     0
+SMALL
+USER_CHARGES
+
+@<TRIPOS>ATOM
+      1 C1          1.3940    0.0000    0.0000 C.ar    1  PHN       0.0600
+      2 C2          0.6970    1.2073    0.0000 C.ar    1  PHN      -0.1300
+      3 C3         -0.6970    1.2073    0.0000 C.ar    1  PHN      -0.1300
+      4 C4         -1.3940    0.0000    0.0000 C.ar    1  PHN      -0.1300
+      5 C5         -0.6970   -1.2073    0.0000 C.ar    1  PHN      -0.1300
+      6 C6          0.6970   -1.2073    0.0000 C.ar    1  PHN      -0.1300
+      7 O1          2.7540    0.0000    0.0000 O.3     1  PHN      -0.5300
+      8 H1          3.0700    0.9000    0.0000 H       1  PHN       0.4200
+      9 H2          1.2400    2.1500    0.0000 H       1  PHN       0.1400
+     10 H3         -1.2400    2.1500    0.0000 H       1  PHN       0.1400
+     11 H4         -2.4800    0.0000    0.0000 H       1  PHN       0.1400
+     12 H5         -1.2400   -2.1500    0.0000 H       1  PHN       0.1400
+     13 H6          1.2400   -2.1500    0.0000 H       1  PHN       0.1400
+@<TRIPOS>BOND
+     1     1     2   ar
+     2     2     3   ar
+     3     3     4   ar
+     4     4     5   ar
+     5     5     6   ar
+     6     6     1   ar
+     7     1     7    1
+     8     7     8    1
+     9     2     9    1
+    10     3    10    1
+    11     4    11    1
+    12     5    12    1
+    13     6    13    1
+";
+
+    #[test]
+    fn test_parse_mol2() {
+        let ligand = parse_mol2(PHENOL).unwrap();
+        assert_eq!(ligand.name, "PHENOL");
+        assert_eq!(ligand.residue_name(), "PHE");
+        assert_eq!(ligand.atoms.len(), 13);
+        assert_eq!(ligand.atoms[6].element, 8);
+        assert_eq!(ligand.bonds.len(), 13);
+        assert_eq!(ligand.bonds[5].order, BondOrder::Aromatic);
+        assert_eq!((ligand.bonds[5].i, ligand.bonds[5].j), (5, 0));
+        let charges = ligand.partial_charges().unwrap();
+        assert!(charges.iter().sum::<f32>().abs() < 1e-4);
+
+        let uncharged = parse_mol2(&PHENOL.replace("USER_CHARGES", "NO_CHARGES")).unwrap();
+        assert!(uncharged.partial_charges().is_none());
+        assert!(parse_mol2(&PHENOL.replace("C.ar    1", "Xx      1")).is_err());
+    }
+}
@@ -0,0 +1,734 @@
+//! # PDB Reader / Writer
+//!
+//! Parses ATOM/HETATM/CONECT/CRYST1 records into [`Atom`]s plus the
+//! per-atom metadata needed to write them back out, and writes engine output
+//! (e.g. `get_current_atoms()`) as PDB for PyMOL/ChimeraX.
+//!
+//! ## Notes
+//! - Only the first MODEL is read
+//! - Alternate locations are resolved per residue by [`AltlocChoice`]
+//!   (by default the first conformer listed, usually 'A'); the residues
+//!   concerned are listed in [`PdbStructure::altlocs`]
+//! - Residues REMARK 465 lists as not located are kept in
+//!   [`PdbStructure::missing_residues`]
+//! - `Atom::residue_id` is a 0-based sequential residue index; the original
+//!   residue numbers and insertion codes are kept in [`PdbAtomRecord`]
+
+use crate::simulation_box::SimulationBox;
+use crate::sovereign_types::Atom;
+use crate::topology::Topology;
+use crate::{PrismIoError, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::hash::Hash;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Per-atom PDB metadata not carried by [`Atom`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdbAtomRecord {
+    /// Atom serial number as read (or assigned on write)
+    pub serial: u32,
+    /// Atom name (e.g. "CA")
+    pub name: String,
+    /// Residue name (e.g. "ALA")
+    pub residue_name: String,
+    /// Chain identifier
+    pub chain_id: char,
+    /// Author residue number
+    pub residue_seq: i32,
+    /// Residue insertion code (' ' if none)
+    pub insertion_code: char,
+    /// Occupancy
+    pub occupancy: f32,
+    /// Temperature factor (Å²)
+    pub b_factor: f32,
+    /// HETATM rather than ATOM record
+    pub hetatm: bool,
+}
+
+/// Atoms, metadata and connectivity of a PDB file
+#[derive(Debug, Clone, Default)]
+pub struct PdbStructure {
+    /// Atoms with coordinates, element and sequential residue index
+    pub atoms: Vec<Atom>,
+    /// Metadata for each atom (same order as `atoms`)
+    pub records: Vec<PdbAtomRecord>,
+    /// CONECT bonds as 0-based atom index pairs (i < j)
+    pub conect: Vec<(u32, u32)>,
+    /// Unit cell from CRYST1 (a, b, c in Å; α, β, γ in degrees)
+    pub cryst1: Option<[f32; 6]>,
+    /// Residues read with alternate locations, in file order
+    pub altlocs: Vec<AltlocSite>,
+    /// Polymer residues the experiment did not locate, in file order
+    pub missing_residues: Vec<MissingResidue>,
+}
+
+/// A polymer residue listed as unobserved (PDB REMARK 465, mmCIF
+/// `pdbx_unobs_or_zero_occ_residues`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissingResidue {
+    /// Chain identifier
+    pub chain_id: char,
+    /// Author residue number
+    pub residue_seq: i32,
+    /// Residue insertion code (' ' if none)
+    pub insertion_code: char,
+    /// Residue name
+    pub residue_name: String,
+}
+
+/// Conformer kept for a residue with alternate locations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AltlocChoice {
+    /// The first alternate location listed for the residue
+    #[default]
+    First,
+    /// The alternate location with the highest mean occupancy (first on ties)
+    HighestOccupancy,
+    /// The given alternate location, the first listed where it is absent
+    Id(char),
+}
+
+impl FromStr for AltlocChoice {
+    type Err = PrismIoError;
+
+    /// `first`, `occupancy` or a single alternate location id
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.chars();
+        match (s, chars.next(), chars.next()) {
+            ("first", _, _) => Ok(Self::First),
+            ("occupancy", _, _) => Ok(Self::HighestOccupancy),
+            (_, Some(id), None) if id.is_ascii_alphanumeric() => Ok(Self::Id(id)),
+            _ => Err(PrismIoError::FormatError(format!(
+                "Invalid alternate location choice '{}' (expected first, occupancy or an id)",
+                s
+            ))),
+        }
+    }
+}
+
+/// A residue read with alternate locations
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AltlocSite {
+    /// Chain identifier
+    pub chain_id: char,
+    /// Author residue number
+    pub residue_seq: i32,
+    /// Residue insertion code (' ' if none)
+    pub insertion_code: char,
+    /// Residue name of the kept conformer
+    pub residue_name: String,
+    /// Alternate location ids in the order listed
+    pub altlocs: Vec<char>,
+    /// Alternate location kept
+    pub kept: char,
+}
+
+/// Conformers listed for one residue: id, occupancy sum, atom count and
+/// residue name of each
+type Conformers = Vec<(char, f32, u32, String)>;
+
+/// Per-residue alternate location selection, fed every alternate atom of a
+/// file before the atoms are read
+pub(crate) struct AltlocSelector<K> {
+    residues: HashMap<K, usize>,
+    sites: Vec<(AltlocSite, Conformers)>,
+}
+
+impl<K: Hash + Eq> AltlocSelector<K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            residues: HashMap::new(),
+            sites: Vec::new(),
+        }
+    }
+
+    /// Record an atom of conformer `alt` of the residue behind `key`
+    pub(crate) fn observe(
+        &mut self,
+        key: K,
+        (chain_id, residue_seq, insertion_code): (char, i32, char),
+        residue_name: &str,
+        alt: char,
+        occupancy: f32,
+    ) {
+        let sites = &mut self.sites;
+        let index = *self.residues.entry(key).or_insert_with(|| {
+            sites.push((
+                AltlocSite {
+                    chain_id,
+                    residue_seq,
+                    insertion_code,
+                    residue_name: String::new(),
+                    altlocs: Vec::new(),
+                    kept: alt,
+                },
+                Vec::new(),
+            ));
+            sites.len() - 1
+        });
+        let conformers = &mut self.sites[index].1;
+        match conformers.iter_mut().find(|c| c.0 == alt) {
+            Some(c) => {
+                c.1 += occupancy;
+                c.2 += 1;
+            }
+            None => conformers.push((alt, occupancy, 1, residue_name.to_string())),
+        }
+    }
+
+    /// Decide the kept conformer of every residue: the kept id by residue
+    /// key and the residues for [`PdbStructure::altlocs`]
+    pub(crate) fn select(self, choice: AltlocChoice) -> (HashMap<K, char>, Vec<AltlocSite>) {
+        let mut sites: Vec<AltlocSite> = Vec::with_capacity(self.sites.len());
+        for (mut site, conformers) in self.sites {
+            let first = &conformers[0];
+            let kept = match choice {
+                AltlocChoice::First => first,
+                AltlocChoice::HighestOccupancy => conformers.iter().fold(first, |best, c| {
+                    if c.1 / c.2 as f32 > best.1 / best.2 as f32 {
+                        c
+                    } else {
+                        best
+                    }
+                }),
+                AltlocChoice::Id(id) => conformers.iter().find(|c| c.0 == id).unwrap_or(first),
+            };
+            site.kept = kept.0;
+            site.residue_name = kept.3.clone();
+            site.altlocs = conformers.iter().map(|c| c.0).collect();
+            sites.push(site);
+        }
+        let mut kept = HashMap::with_capacity(sites.len());
+        for (key, index) in self.residues {
+            kept.insert(key, sites[index].kept);
+        }
+        (kept, sites)
+    }
+}
+
+/// Bondi van der Waals radius (Å), 1.7 for unlisted elements
+pub(crate) fn vdw_radius(element: u8) -> f32 {
+    match element {
+        1 => 1.2,
+        7 => 1.55,
+        8 => 1.52,
+        9 => 1.47,
+        15 | 16 => 1.8,
+        17 => 1.75,
+        _ => 1.7,
+    }
+}
+
+fn field(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len())).unwrap_or("").trim()
+}
+
+fn column(line: &str, index: usize) -> char {
+    line.get(index..index + 1)
+        .and_then(|s| s.chars().next())
+        .unwrap_or(' ')
+}
+
+fn parse_f32(line: &str, start: usize, end: usize, what: &str) -> Result<f32> {
+    field(line, start, end)
+        .parse()
+        .map_err(|_| PrismIoError::FormatError(format!("Invalid {} in PDB record: {}", what, line)))
+}
+
+/// Element from columns 77-78, falling back to the atom-name field
+fn element_of(line: &str, hetatm: bool) -> u8 {
+    let symbol = field(line, 76, 78);
+    if !symbol.is_empty() {
+        return Topology::atomic_number(symbol);
+    }
+    let raw = line.get(12..14).unwrap_or("  ");
+    let first = raw.chars().next().unwrap_or(' ');
+    if first == ' ' || first.is_ascii_digit() {
+        return Topology::atomic_number(&raw[1..]);
+    }
+    // Two-letter elements are left-justified in column 13 (ions, metals)
+    let two = Topology::atomic_number(raw);
+    if hetatm && two != 0 {
+        two
+    } else {
+        Topology::atomic_number(&raw[..1])
+    }
+}
+
+/// Read a PDB file
+pub fn read_pdb<P: AsRef<Path>>(path: P) -> Result<PdbStructure> {
+    parse_pdb(&std::fs::read_to_string(path)?)
+}
+
+/// Read a PDB file, resolving alternate locations by `altloc`
+pub fn read_pdb_with<P: AsRef<Path>>(path: P, altloc: AltlocChoice) -> Result<PdbStructure> {
+    parse_pdb_with(&std::fs::read_to_string(path)?, altloc)
+}
+
+/// Parse PDB content
+pub fn parse_pdb(content: &str) -> Result<PdbStructure> {
+    parse_pdb_with(content, AltlocChoice::First)
+}
+
+/// Parse PDB content, resolving alternate locations by `altloc`
+pub fn parse_pdb_with(content: &str, altloc: AltlocChoice) -> Result<PdbStructure> {
+    let mut selector = AltlocSelector::new();
+    for line in content.lines() {
+        let record = line.get(0..6).unwrap_or(line).trim_end();
+        if record == "ENDMDL" {
+            break;
+        }
+        let alt = column(line, 16);
+        if !matches!(record, "ATOM" | "HETATM") || alt == ' ' {
+            continue;
+        }
+        if let Ok(residue_seq) = field(line, 22, 26).parse::<i32>() {
+            let label = (column(line, 21), residue_seq, column(line, 26));
+            let occupancy = field(line, 54, 60).parse().unwrap_or(1.0);
+            selector.observe(label, label, field(line, 17, 20), alt, occupancy);
+        }
+    }
+    let (kept, altlocs) = selector.select(altloc);
+
+    let mut pdb = PdbStructure {
+        altlocs,
+        ..Default::default()
+    };
+    let mut serial_index: HashMap<u32, u32> = HashMap::new();
+    let mut last_residue: Option<(char, i32, char)> = None;
+    let mut residue: i64 = -1;
+    let mut missing_table = false;
+
+    for line in content.lines() {
+        let record = line.get(0..6).unwrap_or(line).trim_end();
+        match record {
+            "ENDMDL" => break,
+            // REMARK 465 lists residue name, chain, number and insertion
+            // code after the "M RES C SSSEQI" heading; model 1 only
+            "REMARK" if field(line, 7, 10) == "465" => {
+                if line.contains("RES C SSSEQI") {
+                    missing_table = true;
+                } else if missing_table && matches!(field(line, 13, 15), "" | "1") {
+                    if let Ok(residue_seq) = field(line, 21, 26).parse() {
+                        pdb.missing_residues.push(MissingResidue {
+                            chain_id: column(line, 19),
+                            residue_seq,
+                            insertion_code: column(line, 26),
+                            residue_name: field(line, 15, 18).to_string(),
+                        });
+                    }
+                }
+            }
+            "CRYST1" => {
+                let mut cell = [0.0f32; 6];
+                let cols = [(6, 15), (15, 24), (24, 33), (33, 40), (40, 47), (47, 54)];
+                for (v, &(s, e)) in cell.iter_mut().zip(&cols) {
+                    *v = parse_f32(line, s, e, "CRYST1 cell")?;
+                }
+                pdb.cryst1 = Some(cell);
+            }
+            "ATOM" | "HETATM" => {
+                let hetatm = record == "HETATM";
+                let chain_id = column(line, 21);
+                let residue_seq: i32 = field(line, 22, 26).parse().map_err(|_| {
+                    PrismIoError::FormatError(format!("Invalid residue number: {}", line))
+                })?;
+                let insertion_code = column(line, 26);
+                let key = (chain_id, residue_seq, insertion_code);
+                let alt = column(line, 16);
+                if alt != ' ' && kept.get(&key) != Some(&alt) {
+                    continue;
+                }
+                if last_residue != Some(key) {
+                    residue += 1;
+                    last_residue = Some(key);
+                }
+                if residue > u16::MAX as i64 {
+                    return Err(PrismIoError::FormatError(
+                        "Residue count exceeds the 16-bit residue index".to_string(),
+                    ));
+                }
+
+                let element = element_of(line, hetatm);
+                let serial = field(line, 6, 11)
+                    .parse()
+                    .unwrap_or(pdb.atoms.len() as u32 + 1);
+                serial_index.insert(serial, pdb.atoms.len() as u32);
+                pdb.atoms.push(Atom {
+                    coords: [
+                        parse_f32(line, 30, 38, "X coordinate")?,
+                        parse_f32(line, 38, 46, "Y coordinate")?,
+                        parse_f32(line, 46, 54, "Z coordinate")?,
+                    ],
+                    element,
+                    residue_id: residue as u16,
+                    atom_type: 0,
+                    charge: field(line, 78, 80)
+                        .chars()
+                        .rev()
+                        .collect::<String>()
+                        .parse()
+                        .unwrap_or(0.0),
+                    radius: vdw_radius(element),
+                    _reserved: [0; 4],
+                });
+                pdb.records.push(PdbAtomRecord {
+                    serial,
+                    name: field(line, 12, 16).to_string(),
+                    residue_name: field(line, 17, 20).to_string(),
+                    chain_id,
+                    residue_seq,
+                    insertion_code,
+                    occupancy: field(line, 54, 60).parse().unwrap_or(1.0),
+                    b_factor: field(line, 60, 66).parse().unwrap_or(0.0),
+                    hetatm,
+                });
+            }
+            "CONECT" => {
+                let serials: Vec<u32> = (0..5)
+                    .filter_map(|n| field(line, 6 + 5 * n, 11 + 5 * n).parse().ok())
+                    .collect();
+                if let Some((&from, partners)) = serials.split_first() {
+                    for to in partners {
+                        if let (Some(&a), Some(&b)) =
+                            (serial_index.get(&from), serial_index.get(to))
+                        {
+                            if a != b {
+                                pdb.conect.push((a.min(b), a.max(b)));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if pdb.atoms.is_empty() {
+        return Err(PrismIoError::FormatError(
+            "PDB contains no ATOM/HETATM records".to_string(),
+        ));
+    }
+    pdb.conect.sort_unstable();
+    pdb.conect.dedup();
+    Ok(pdb)
+}
+
+impl PdbStructure {
+    /// Wrap bare atoms (e.g. engine output) with generated metadata:
+    /// element-derived names, residue `UNK`, chain `A`, 1-based residue numbers
+    pub fn from_atoms(atoms: &[Atom]) -> Self {
+        let records = atoms
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                let symbol = Topology::element_symbol(a.element);
+                PdbAtomRecord {
+                    serial: i as u32 + 1,
+                    name: if symbol.is_empty() {
+                        "X".to_string()
+                    } else {
+                        symbol.to_string()
+                    },
+                    residue_name: "UNK".to_string(),
+                    chain_id: 'A',
+                    residue_seq: a.residue_id as i32 + 1,
+                    insertion_code: ' ',
+                    occupancy: 1.0,
+                    b_factor: 0.0,
+                    hetatm: false,
+                }
+            })
+            .collect();
+        Self {
+            atoms: atoms.to_vec(),
+            records,
+            conect: Vec::new(),
+            cryst1: None,
+            altlocs: Vec::new(),
+            missing_residues: Vec::new(),
+        }
+    }
+
+    /// Replace coordinates with those of `atoms`, keeping all metadata
+    pub fn update_coordinates(&mut self, atoms: &[Atom]) -> Result<()> {
+        if atoms.len() != self.atoms.len() {
+            return Err(PrismIoError::ValidationError(format!(
+                "Atom count mismatch: structure has {}, got {}",
+                self.atoms.len(),
+                atoms.len()
+            )));
+        }
+        for (dst, src) in self.atoms.iter_mut().zip(atoms) {
+            dst.coords = src.coords;
+        }
+        Ok(())
+    }
+
+    /// Append the atoms and bonds of `other` (e.g. a ligand), renumbering
+    /// its serials and residue indices after those of `self`
+    pub fn append(&mut self, other: &PdbStructure) {
+        let offset = self.atoms.len() as u32;
+        let residue_offset = self
+            .atoms
+            .iter()
+            .map(|a| a.residue_id + 1)
+            .max()
+            .unwrap_or(0);
+        self.atoms.extend(other.atoms.iter().map(|a| Atom {
+            residue_id: a.residue_id + residue_offset,
+            ..*a
+        }));
+        self.records
+            .extend(other.records.iter().map(|r| PdbAtomRecord {
+                serial: r.serial + offset,
+                ..r.clone()
+            }));
+        self.conect
+            .extend(other.conect.iter().map(|&(i, j)| (i + offset, j + offset)));
+    }
+
+    /// Periodic cell from the CRYST1 record, if present and non-degenerate
+    pub fn simulation_box(&self) -> Option<SimulationBox> {
+        self.cryst1
+            .and_then(|cell| SimulationBox::from_parameters(cell).ok())
+    }
+
+    /// Render as PDB text
+    pub fn to_pdb_string(&self) -> String {
+        let mut out = String::new();
+        self.write_cryst1(&mut out);
+        let serials = self.write_atoms(&mut out, |i| self.atoms[i].coords);
+        self.write_conect(&mut out, &serials);
+        out.push_str("END\n");
+        out
+    }
+
+    /// Render `frames` (one `[x, y, z]` per atom each) as MODEL/ENDMDL
+    /// blocks sharing this structure's metadata, e.g. for a mode animation
+    pub fn to_models_string(&self, frames: &[Vec<[f32; 3]>]) -> Result<String> {
+        if let Some(frame) = frames.iter().find(|f| f.len() != self.atoms.len()) {
+            return Err(PrismIoError::ValidationError(format!(
+                "Atom count mismatch: structure has {}, frame has {}",
+                self.atoms.len(),
+                frame.len()
+            )));
+        }
+        let mut out = String::new();
+        self.write_cryst1(&mut out);
+        let mut serials = Vec::new();
+        for (model, frame) in frames.iter().enumerate() {
+            let _ = writeln!(out, "MODEL     {:>4}", model + 1);
+            serials = self.write_atoms(&mut out, |i| frame[i]);
+            out.push_str("ENDMDL\n");
+        }
+        self.write_conect(&mut out, &serials);
+        out.push_str("END\n");
+        Ok(out)
+    }
+
+    /// Write a multi-model PDB file, see [`Self::to_models_string`]
+    pub fn write_models<P: AsRef<Path>>(&self, path: P, frames: &[Vec<[f32; 3]>]) -> Result<()> {
+        std::fs::write(path, self.to_models_string(frames)?)?;
+        Ok(())
+    }
+
+    fn write_cryst1(&self, out: &mut String) {
+        if let Some(c) = self.cryst1 {
+            let _ = writeln!(
+                out,
+                "CRYST1{:9.3}{:9.3}{:9.3}{:7.2}{:7.2}{:7.2} P 1           1",
+                c[0], c[1], c[2], c[3], c[4], c[5]
+            );
+        }
+    }
+
+    /// ATOM/HETATM and TER records with coordinates from `coords`, returning
+    /// the serial assigned to each atom
+    fn write_atoms(&self, out: &mut String, coords: impl Fn(usize) -> [f32; 3]) -> Vec<u32> {
+        // Serials are renumbered from 1, leaving a gap for each TER record
+        let mut serials = Vec::with_capacity(self.atoms.len());
+        let mut previous_chain = None;
+        let mut serial = 0u32;
+        for (i, (atom, rec)) in self.atoms.iter().zip(&self.records).enumerate() {
+            let xyz = coords(i);
+            if previous_chain.is_some_and(|c| c != rec.chain_id) {
+                serial += 1;
+                let _ = writeln!(out, "TER   {:>5}", serial % 100_000);
+            }
+            previous_chain = Some(rec.chain_id);
+            serial += 1;
+            serials.push(serial);
+
+            let symbol = Topology::element_symbol(atom.element);
+            // Names shorter than 4 characters start in column 14 unless the
+            // element symbol has two letters
+            let name = if rec.name.len() < 4 && symbol.len() < 2 {
+                format!(" {:<3}", rec.name)
+            } else {
+                format!("{:<4}", rec.name)
+            };
+            let charge = if atom.charge.round() == 0.0 || atom.charge.fract() != 0.0 {
+                "  ".to_string()
+            } else {
+                let q = atom.charge.round() as i32;
+                format!("{}{}", q.abs(), if q > 0 { '+' } else { '-' })
+            };
+            let _ = writeln!(
+                out,
+                "{:<6}{:>5} {} {:>3} {}{:>4}{}   {:8.3}{:8.3}{:8.3}{:6.2}{:6.2}          {:>2}{}",
+                if rec.hetatm { "HETATM" } else { "ATOM" },
+                serial % 100_000,
+                name,
+                rec.residue_name,
+                rec.chain_id,
+                rec.residue_seq % 10_000,
+                rec.insertion_code,
+                xyz[0],
+                xyz[1],
+                xyz[2],
+                rec.occupancy,
+                rec.b_factor,
+                symbol,
+                charge
+            );
+        }
+        serials
+    }
+
+    fn write_conect(&self, out: &mut String, serials: &[u32]) {
+        let mut partners: Vec<Vec<u32>> = vec![Vec::new(); self.atoms.len()];
+        for &(i, j) in &self.conect {
+            partners[i as usize].push(serials[j as usize]);
+            partners[j as usize].push(serials[i as usize]);
+        }
+        for (i, list) in partners.iter().enumerate() {
+            for chunk in list.chunks(4) {
+                let _ = write!(out, "CONECT{:>5}", serials[i]);
+                for p in chunk {
+                    let _ = write!(out, "{:>5}", p);
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    /// Write to a PDB file
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_pdb_string())?;
+        Ok(())
+    }
+}
+
+/// Write bare atoms (e.g. `get_current_atoms()` output) to a PDB file
+pub fn write_pdb<P: AsRef<Path>>(path: P, atoms: &[Atom]) -> Result<()> {
+    PdbStructure::from_atoms(atoms).write(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PDB: &str = "\
+REMARK 465 MISSING RESIDUES
+REMARK 465   M RES C SSSEQI
+REMARK 465     MET A     0
+REMARK 465     SER B   100B
+CRYST1   50.000   60.000   70.000  90.00  90.00  90.00 P 1           1
+ATOM      1  N   ALA A   1      11.104   6.134  -6.504  1.00 12.50           N
+ATOM      2  CA AALA A   1      11.639   6.071  -5.147  0.60 13.00           C
+ATOM      3  CA BALA A   1      11.700   6.000  -5.100  0.40 13.00           C
+ATOM      4  C   ALA A   1      13.159   5.948  -5.168  1.00 14.00           C
+ATOM      5  N   GLY A   1A     13.800   6.900  -5.800  1.00 15.00           N
+TER       6      GLY A   1A
+HETATM    7 ZN    ZN B 101       0.000   0.000   0.000  1.00 20.00          ZN2+
+CONECT    1    2    4
+ENDMDL
+ATOM      8  N   ALA A   1       0.000   0.000   0.000  1.00 12.50           N
+";
+
+    #[test]
+    fn test_parse_pdb() {
+        let pdb = parse_pdb(PDB).unwrap();
+        assert_eq!(pdb.atoms.len(), 5);
+        assert_eq!(pdb.records[1].name, "CA");
+        assert!((pdb.records[1].occupancy - 0.6).abs() < 1e-6);
+        // Insertion code starts a new residue, chain B another
+        assert_eq!(
+            pdb.atoms.iter().map(|a| a.residue_id).collect::<Vec<_>>(),
+            vec![0, 0, 0, 1, 2]
+        );
+        assert_eq!(pdb.records[3].insertion_code, 'A');
+        assert_eq!(pdb.atoms[4].element, 30);
+        assert!((pdb.atoms[4].charge - 2.0).abs() < 1e-6);
+        assert!(pdb.records[4].hetatm);
+        assert_eq!(pdb.records[4].chain_id, 'B');
+        // Serial 4 maps to index 2 after the skipped altloc
+        assert_eq!(pdb.conect, vec![(0, 1), (0, 2)]);
+        assert_eq!(pdb.cryst1.unwrap()[1], 60.0);
+        assert!(pdb.simulation_box().is_some());
+        assert_eq!(pdb.missing_residues.len(), 2);
+        assert_eq!(pdb.missing_residues[0].residue_name, "MET");
+        assert_eq!(pdb.missing_residues[0].residue_seq, 0);
+        let last = &pdb.missing_residues[1];
+        assert_eq!((last.chain_id, last.residue_seq), ('B', 100));
+        assert_eq!(last.insertion_code, 'B');
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pdb = parse_pdb(PDB).unwrap();
+        let text = pdb.to_pdb_string();
+        let again = parse_pdb(&text).unwrap();
+        assert_eq!(again.atoms.len(), pdb.atoms.len());
+        assert_eq!(
+            again.records.iter().map(|r| &r.name).collect::<Vec<_>>(),
+            pdb.records.iter().map(|r| &r.name).collect::<Vec<_>>()
+        );
+        for (a, b) in again.atoms.iter().zip(&pdb.atoms) {
+            assert_eq!(a.coords, b.coords);
+            assert_eq!(a.element, b.element);
+            assert_eq!(a.residue_id, b.residue_id);
+        }
+        assert_eq!(again.conect, pdb.conect);
+        assert_eq!(again.records[4].b_factor, 20.0);
+        assert!(text.contains("TER"));
+    }
+
+    #[test]
+    fn test_from_atoms() {
+        let atoms = parse_pdb(PDB).unwrap().atoms;
+        let text = PdbStructure::from_atoms(&atoms).to_pdb_string();
+        let line = text.lines().next().unwrap();
+        assert_eq!(&line[12..16], " N  ");
+        assert_eq!(&line[17..20], "UNK");
+        assert_eq!(&line[76..78], " N");
+    }
+
+    #[test]
+    fn test_models_share_metadata() {
+        let pdb = parse_pdb(PDB).unwrap();
+        let frames: Vec<Vec<[f32; 3]>> = (0..3)
+            .map(|k| {
+                pdb.atoms
+                    .iter()
+                    .map(|a| [a.coords[0] + k as f32, a.coords[1], a.coords[2]])
+                    .collect()
+            })
+            .collect();
+        let text = pdb.to_models_string(&frames).unwrap();
+        assert_eq!(text.matches("ENDMDL").count(), 3);
+        assert_eq!(
+            text.matches("CONECT").count(),
+            pdb.to_pdb_string().matches("CONECT").count()
+        );
+        assert!(text.starts_with("CRYST1") && text.contains("MODEL        3"));
+        // The reader takes the first model
+        let first = parse_pdb(&text).unwrap();
+        assert_eq!(first.atoms[1].coords, pdb.atoms[1].coords);
+        assert!(pdb.to_models_string(&[vec![[0.0; 3]]]).is_err());
+    }
+}
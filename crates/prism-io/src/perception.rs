@@ -0,0 +1,444 @@
+//! # Covalent Topology Perception
+//!
+//! Infers bonds, angles and proper dihedrals for structures that arrive
+//! without connectivity (bare PTB atoms, PDB files without CONECT):
+//! 1. Standard amino acids are matched against their residue templates
+//!    ([`crate::residues`]); consecutive residues of a chain get the peptide
+//!    bond and close cysteine pairs the disulfide
+//! 2. Hydrogens of matched residues bond to the nearest heavy atom of the
+//!    residue
+//! 3. Every other atom bonds by distance: within the sum of the covalent
+//!    radii plus [`BOND_TOLERANCE`], hydrogens keeping their shortest bond;
+//!    metals and single-atom residues (ions) stay unbonded
+//!
+//! Explicit bonds (CONECT, `struct_conn`) are always kept. Angles and
+//! dihedrals follow from the bond graph.
+
+use crate::geometry::distance;
+use crate::pdb::{PdbAtomRecord, PdbStructure};
+use crate::residues::standard_residue;
+use crate::sovereign_types::{Atom, Bond};
+use std::collections::{BTreeSet, HashMap};
+
+/// Added to the sum of covalent radii for a distance bond (Å)
+pub const BOND_TOLERANCE: f32 = 0.45;
+/// Pairs closer than this are overlapping atoms, never bonds (Å)
+pub const MIN_BOND_LENGTH: f32 = 0.4;
+/// Longest peptide C-N bond accepted before a chain break is assumed (Å)
+pub const MAX_PEPTIDE_BOND: f32 = 2.0;
+/// Longest SG-SG distance read as a disulfide (Å)
+pub const MAX_DISULFIDE_BOND: f32 = 2.5;
+/// Longest X-H bond within a template residue (Å)
+const MAX_HYDROGEN_BOND: f32 = 1.35;
+
+/// Covalent radius (Å, Cordero et al. 2008), `None` for metals and
+/// unknown elements, which are never bonded by distance
+pub fn covalent_radius(element: u8) -> Option<f32> {
+    Some(match element {
+        1 => 0.31,
+        5 => 0.84,
+        6 => 0.76,
+        7 => 0.71,
+        8 => 0.66,
+        9 => 0.57,
+        14 => 1.11,
+        15 => 1.07,
+        16 => 1.05,
+        17 => 1.02,
+        34 => 1.20,
+        35 => 1.20,
+        53 => 1.39,
+        _ => return None,
+    })
+}
+
+/// Where the bonds of a [`CovalentTopology`] came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerceptionStats {
+    /// Residues matched against a standard template
+    pub template_residues: usize,
+    /// Bonds given by the input (CONECT / `struct_conn`)
+    pub explicit_bonds: usize,
+    /// Template, peptide and disulfide bonds
+    pub template_bonds: usize,
+    /// Bonds inferred from distances (including hydrogens)
+    pub distance_bonds: usize,
+}
+
+/// Bonds, angles and proper dihedrals as atom indices
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CovalentTopology {
+    /// Bonds (i < j), sorted
+    pub bonds: Vec<(u32, u32)>,
+    /// Angles `[i, j, k]` with `j` the central atom and `i < k`
+    pub angles: Vec<[u32; 3]>,
+    /// Dihedrals `[i, j, k, l]` around the bond `j-k` with `j < k`
+    pub dihedrals: Vec<[u32; 4]>,
+    /// Origin of the bonds
+    pub stats: PerceptionStats,
+}
+
+/// Insert the bond `a-b` (i < j); false when present or a self-bond
+fn add(bonds: &mut BTreeSet<(u32, u32)>, a: u32, b: u32) -> bool {
+    a != b && bonds.insert((a.min(b), a.max(b)))
+}
+
+/// Atom ranges of consecutive atoms sharing a `residue_id`
+fn residue_ranges(atoms: &[Atom]) -> Vec<std::ops::Range<usize>> {
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+    for (i, atom) in atoms.iter().enumerate() {
+        match ranges.last_mut() {
+            Some(r) if atoms[r.start].residue_id == atom.residue_id => r.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges
+}
+
+/// Spatial hash of atom indices on a cubic grid of `cell` Å
+fn cell_grid(atoms: &[Atom], cell: f32) -> HashMap<[i32; 3], Vec<u32>> {
+    let mut grid: HashMap<[i32; 3], Vec<u32>> = HashMap::new();
+    for (i, atom) in atoms.iter().enumerate() {
+        let key = atom.coords.map(|c| (c / cell).floor() as i32);
+        grid.entry(key).or_default().push(i as u32);
+    }
+    grid
+}
+
+impl CovalentTopology {
+    /// Perceive the topology of bare atoms by distance alone
+    pub fn from_atoms(atoms: &[Atom]) -> Self {
+        Self::perceive(atoms, None, &[])
+    }
+
+    /// Perceive the topology of a structure, using its atom names for
+    /// template matching and keeping its CONECT bonds
+    pub fn from_structure(structure: &PdbStructure) -> Self {
+        let records = (structure.records.len() == structure.atoms.len())
+            .then_some(structure.records.as_slice());
+        Self::perceive(&structure.atoms, records, &structure.conect)
+    }
+
+    /// Perceive bonds of `atoms` given optional per-atom metadata and
+    /// explicit bonds, then derive angles and dihedrals
+    pub fn perceive(
+        atoms: &[Atom],
+        records: Option<&[PdbAtomRecord]>,
+        explicit: &[(u32, u32)],
+    ) -> Self {
+        let mut bonds: BTreeSet<(u32, u32)> = BTreeSet::new();
+        let mut stats = PerceptionStats::default();
+        for &(a, b) in explicit {
+            if (a as usize) < atoms.len() && (b as usize) < atoms.len() && add(&mut bonds, a, b) {
+                stats.explicit_bonds += 1;
+            }
+        }
+
+        // Atoms whose bonds are settled by their residue template
+        let mut templated = vec![false; atoms.len()];
+        let ranges = residue_ranges(atoms);
+        if let Some(records) = records {
+            let mut previous: Option<std::ops::Range<usize>> = None;
+            let mut cysteines = Vec::new();
+            for range in &ranges {
+                let first = &records[range.start];
+                let template = standard_residue(&first.residue_name).filter(|_| !first.hetatm);
+                let Some(template) = template else {
+                    previous = None;
+                    continue;
+                };
+                stats.template_residues += 1;
+                let index = |name: &str| {
+                    range
+                        .clone()
+                        .find(|&i| records[i].name == name)
+                        .map(|i| i as u32)
+                };
+                for (a, b) in template.bonds() {
+                    if let (Some(a), Some(b)) = (index(a), index(b)) {
+                        if add(&mut bonds, a, b) {
+                            stats.template_bonds += 1;
+                        }
+                    }
+                }
+                if let (Some(c), Some(o)) = (index("C"), index("OXT")) {
+                    if add(&mut bonds, c, o) {
+                        stats.template_bonds += 1;
+                    }
+                }
+                if let Some(prev) = previous.as_ref() {
+                    let c = prev.clone().find(|&i| records[i].name == "C");
+                    let same_chain = records[prev.start].chain_id == first.chain_id;
+                    if let (Some(c), Some(n), true) = (c, index("N"), same_chain) {
+                        if distance(atoms[c].coords, atoms[n as usize].coords) <= MAX_PEPTIDE_BOND
+                            && add(&mut bonds, c as u32, n)
+                        {
+                            stats.template_bonds += 1;
+                        }
+                    }
+                }
+                if template.name == "CYS" {
+                    cysteines.extend(index("SG"));
+                }
+
+                // Hydrogens bond to the nearest heavy atom of the residue,
+                // heavy atoms outside the template are left to the distance
+                // pass
+                for i in range.clone() {
+                    if atoms[i].element == 1 {
+                        let nearest = range
+                            .clone()
+                            .filter(|&j| atoms[j].element > 1)
+                            .map(|j| (j, distance(atoms[i].coords, atoms[j].coords)))
+                            .filter(|&(_, d)| d <= MAX_HYDROGEN_BOND)
+                            .min_by(|a, b| a.1.total_cmp(&b.1));
+                        if let Some((j, _)) = nearest {
+                            templated[i] = true;
+                            if add(&mut bonds, i as u32, j as u32) {
+                                stats.distance_bonds += 1;
+                            }
+                        }
+                    } else if template.heavy_atoms.contains(&records[i].name.as_str())
+                        || records[i].name == "OXT"
+                    {
+                        templated[i] = true;
+                    }
+                }
+                previous = Some(range.clone());
+            }
+            for (k, &a) in cysteines.iter().enumerate() {
+                for &b in &cysteines[k + 1..] {
+                    if distance(atoms[a as usize].coords, atoms[b as usize].coords)
+                        <= MAX_DISULFIDE_BOND
+                        && add(&mut bonds, a, b)
+                    {
+                        stats.template_bonds += 1;
+                    }
+                }
+            }
+        }
+
+        // Distance pass over every pair involving a non-template atom
+        let ions: Vec<bool> = {
+            let mut ions = vec![false; atoms.len()];
+            for range in &ranges {
+                if range.len() == 1 {
+                    ions[range.start] = true;
+                }
+            }
+            ions
+        };
+        let max_radius = atoms
+            .iter()
+            .filter_map(|a| covalent_radius(a.element))
+            .fold(0.0f32, f32::max);
+        let cell = (2.0 * max_radius + BOND_TOLERANCE).max(1.0);
+        let grid = cell_grid(atoms, cell);
+        let mut candidates: Vec<(u32, u32, f32)> = Vec::new();
+        for (key, members) in &grid {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let Some(others) = grid.get(&[key[0] + dx, key[1] + dy, key[2] + dz])
+                        else {
+                            continue;
+                        };
+                        for &a in members {
+                            for &b in others {
+                                let (ia, ib) = (a as usize, b as usize);
+                                if a >= b
+                                    || (templated[ia] && templated[ib])
+                                    || ions[ia]
+                                    || ions[ib]
+                                {
+                                    continue;
+                                }
+                                let (Some(ra), Some(rb)) = (
+                                    covalent_radius(atoms[ia].element),
+                                    covalent_radius(atoms[ib].element),
+                                ) else {
+                                    continue;
+                                };
+                                let d = distance(atoms[ia].coords, atoms[ib].coords);
+                                if d >= MIN_BOND_LENGTH && d <= ra + rb + BOND_TOLERANCE {
+                                    candidates.push((a, b, d));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.2.total_cmp(&b.2));
+        let mut hydrogen_bonded: Vec<bool> = (0..atoms.len())
+            .map(|i| templated[i] && atoms[i].element == 1)
+            .collect();
+        for &(a, _) in explicit {
+            if let Some(h) = hydrogen_bonded.get_mut(a as usize) {
+                *h |= atoms[a as usize].element == 1;
+            }
+        }
+        for (a, b, _) in candidates {
+            let hydrogens: Vec<usize> = [a as usize, b as usize]
+                .into_iter()
+                .filter(|&i| atoms[i].element == 1)
+                .collect();
+            if hydrogens.iter().any(|&h| hydrogen_bonded[h]) {
+                continue;
+            }
+            if add(&mut bonds, a, b) {
+                stats.distance_bonds += 1;
+                for h in hydrogens {
+                    hydrogen_bonded[h] = true;
+                }
+            }
+        }
+
+        let mut topology = Self {
+            bonds: bonds.into_iter().collect(),
+            stats,
+            ..Default::default()
+        };
+        topology.derive_angles_and_dihedrals(atoms.len());
+        topology
+    }
+
+    /// Bonded neighbours of every atom
+    pub fn neighbours(&self, num_atoms: usize) -> Vec<Vec<u32>> {
+        let mut neighbours = vec![Vec::new(); num_atoms];
+        for &(i, j) in &self.bonds {
+            neighbours[i as usize].push(j);
+            neighbours[j as usize].push(i);
+        }
+        for n in &mut neighbours {
+            n.sort_unstable();
+        }
+        neighbours
+    }
+
+    fn derive_angles_and_dihedrals(&mut self, num_atoms: usize) {
+        let neighbours = self.neighbours(num_atoms);
+        self.angles.clear();
+        for (j, n) in neighbours.iter().enumerate() {
+            for (k, &i) in n.iter().enumerate() {
+                for &l in &n[k + 1..] {
+                    self.angles.push([i, j as u32, l]);
+                }
+            }
+        }
+        self.dihedrals.clear();
+        for &(j, k) in &self.bonds {
+            for &i in &neighbours[j as usize] {
+                for &l in &neighbours[k as usize] {
+                    if i != k && l != j && i != l {
+                        self.dihedrals.push([i, j, k, l]);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bonds as sovereign [`Bond`]s for PTB files (order 1)
+    pub fn sovereign_bonds(&self) -> Vec<Bond> {
+        self.bonds
+            .iter()
+            .map(|&(atom1, atom2)| Bond {
+                atom1,
+                atom2,
+                order: 1,
+                bond_type: 0,
+                _reserved: [0],
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdb::parse_pdb;
+
+    /// Ala-Cys with hydrogens on the alanine, a second chain of one
+    /// cysteine bridged by a disulfide, an acetate and a sodium ion
+    const PDB: &str = "\
+ATOM      1  N   ALA A   1       0.000   1.458   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       0.000   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       0.503  -0.603   1.307  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.514  -0.162   1.850  1.00  0.00           O
+ATOM      5  CB  ALA A   1       0.850  -0.536  -1.154  1.00  0.00           C
+ATOM      6  H   ALA A   1      -0.341   1.803  -0.886  1.00  0.00           H
+ATOM      7  HA  ALA A   1      -1.020  -0.364  -0.125  1.00  0.00           H
+ATOM      8  N   CYS A   2      -0.211  -1.611   1.802  1.00  0.00           N
+ATOM      9  CA  CYS A   2       0.161  -2.276   3.045  1.00  0.00           C
+ATOM     10  C   CYS A   2       0.430  -3.759   2.817  1.00  0.00           C
+ATOM     11  O   CYS A   2      -0.335  -4.434   2.131  1.00  0.00           O
+ATOM     12  CB  CYS A   2      -0.934  -2.095   4.098  1.00  0.00           C
+ATOM     13  SG  CYS A   2      -1.274  -0.367   4.518  1.00  0.00           S
+ATOM     14  OXT CYS A   2       1.450  -4.240   3.357  1.00  0.00           O
+ATOM     15  N   CYS B   1      -0.965   2.359   7.518  1.00  0.00           N
+ATOM     16  CA  CYS B   1      -1.337   3.024   6.275  1.00  0.00           C
+ATOM     17  C   CYS B   1      -1.607   4.508   6.502  1.00  0.00           C
+ATOM     18  O   CYS B   1      -0.842   5.183   7.189  1.00  0.00           O
+ATOM     19  CB  CYS B   1      -0.242   2.843   5.222  1.00  0.00           C
+ATOM     20  SG  CYS B   1       0.098   1.116   4.802  1.00  0.00           S
+HETATM   21  C1  ACT A 101      20.000  20.000  20.000  1.00  0.00           C
+HETATM   22  C2  ACT A 101      21.520  20.000  20.000  1.00  0.00           C
+HETATM   23  O1  ACT A 101      22.150  21.080  20.000  1.00  0.00           O
+HETATM   24  O2  ACT A 101      22.150  18.920  20.000  1.00  0.00           O
+HETATM   25 NA    NA A 102      22.500  20.000  22.300  1.00  0.00          NA
+";
+
+    #[test]
+    fn test_template_and_distance_bonds() {
+        let structure = parse_pdb(PDB).unwrap();
+        let topology = CovalentTopology::from_structure(&structure);
+        let has = |a: u32, b: u32| topology.bonds.contains(&(a - 1, b - 1));
+        // Template, peptide, terminal, hydrogen and disulfide bonds
+        assert!(has(1, 2) && has(2, 5) && has(1, 6) && has(2, 7));
+        assert!(has(3, 8) && has(10, 14) && has(13, 20));
+        // The acetate bonds by distance, the ion stays free
+        assert!(has(21, 22) && has(22, 23) && has(22, 24));
+        assert!(!topology.bonds.iter().any(|&(a, b)| a == 24 || b == 24));
+        assert_eq!(topology.stats.template_residues, 3);
+        assert_eq!(topology.stats.template_bonds, 17);
+        assert_eq!(topology.stats.distance_bonds, 5);
+        assert_eq!(topology.bonds.len(), 22);
+
+        // Every angle and dihedral is a path in the bond graph
+        for &[i, j, k] in &topology.angles {
+            assert!(topology.bonds.contains(&(i.min(j), i.max(j))));
+            assert!(topology.bonds.contains(&(j.min(k), j.max(k))));
+        }
+        // N-CA-C-N(next) crosses the peptide bond
+        assert!(topology.dihedrals.contains(&[0, 1, 2, 7]));
+    }
+
+    #[test]
+    fn test_bare_atoms_bond_by_distance() {
+        let structure = parse_pdb(PDB).unwrap();
+        let topology = CovalentTopology::from_atoms(&structure.atoms);
+        // Same graph without names for a well-formed geometry
+        let with_names = CovalentTopology::from_structure(&structure);
+        assert_eq!(topology.bonds, with_names.bonds);
+        assert_eq!(topology.stats.template_residues, 0);
+        assert_eq!(topology.stats.distance_bonds, topology.bonds.len());
+    }
+
+    #[test]
+    fn test_hydrogen_keeps_one_bond() {
+        let atom = |element: u8, x: f32| Atom {
+            coords: [x, 0.0, 0.0],
+            element,
+            residue_id: 0,
+            atom_type: 0,
+            charge: 0.0,
+            radius: 1.2,
+            _reserved: [0; 4],
+        };
+        // H between two carbons, closer to the first
+        let atoms = [atom(6, 0.0), atom(1, 1.0), atom(6, 2.1)];
+        let topology = CovalentTopology::from_atoms(&atoms);
+        assert_eq!(topology.bonds, vec![(0, 1)]);
+        assert!(topology.angles.is_empty());
+    }
+}
@@ -0,0 +1,614 @@
+//! # Hydrogen Addition and Protonation States
+//!
+//! Crystal structures rarely resolve hydrogens, while the all-atom force
+//! field needs every one of them. [`protonate`] prepares a structure in two
+//! steps:
+//! 1. Titratable residues (Asp, Glu, His, Lys) and N-termini get the
+//!    protonation state of the requested pH from model pKa values, shifted by
+//!    two heuristics: a salt bridge to an opposite charge stabilises the
+//!    charged form by [`SALT_BRIDGE_SHIFT`], burial destabilises it by up to
+//!    [`BURIAL_SHIFT`]. Neutral His is `HID` when only ND1 faces an
+//!    acceptor, `HIE` otherwise; cysteines bridged by a disulfide
+//!    ([`crate::disulfides`]) become `CYX`
+//! 2. Missing hydrogens of standard residues are placed from the residue
+//!    templates ([`side_chain_hydrogens`]) with ideal bond lengths and
+//!    tetrahedral or planar geometry about the perceived heavy-atom bonds
+//!
+//! Residues are renamed to the AMBER variant names (`ASH`, `GLH`, `HID`,
+//! `HIE`, `HIP`, `LYN`, `CYX`). Residue names that already are variants are
+//! kept as given. Hydrogens present in the input are kept.
+
+use crate::disulfides::{detect_disulfides, DisulfideOptions};
+use crate::geometry::{add, cross, distance, norm, normalize, perpendicular, place, scale, sub};
+use crate::pdb::{vdw_radius, PdbAtomRecord, PdbStructure};
+use crate::perception::CovalentTopology;
+use crate::residues::{canonical_name, side_chain_hydrogens, HydrogenSite};
+use crate::sovereign_types::Atom;
+use crate::Result;
+use serde::Serialize;
+
+/// Model pKa of the Asp carboxyl
+pub const PKA_ASP: f32 = 3.9;
+/// Model pKa of the Glu carboxyl
+pub const PKA_GLU: f32 = 4.3;
+/// Model pKa of the His imidazole
+pub const PKA_HIS: f32 = 6.5;
+/// Model pKa of the Lys amine
+pub const PKA_LYS: f32 = 10.5;
+/// Model pKa of a free N-terminal amine
+pub const PKA_N_TERMINUS: f32 = 8.0;
+/// pKa shift towards the charged form for a salt bridge
+pub const SALT_BRIDGE_SHIFT: f32 = 1.0;
+/// pKa shift towards the neutral form for a fully buried group
+pub const BURIAL_SHIFT: f32 = 2.0;
+/// Oxygen-nitrogen distance of a salt bridge (Å)
+const SALT_BRIDGE_DISTANCE: f32 = 4.0;
+/// Donor-acceptor distance deciding the His tautomer (Å)
+const ACCEPTOR_DISTANCE: f32 = 3.2;
+/// Radius of the heavy-atom count measuring burial (Å)
+const BURIAL_RADIUS: f32 = 10.0;
+/// Heavy atoms within [`BURIAL_RADIUS`] of an exposed and a buried group
+const BURIAL_COUNTS: (f32, f32) = (105.0, 210.0);
+
+/// How [`protonate`] prepares a structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtonationOptions {
+    /// pH the protonation states are assigned at
+    pub ph: f32,
+    /// Rename residues to their AMBER variant names
+    pub rename_residues: bool,
+    /// Pairing of cysteines into disulfides
+    pub disulfides: DisulfideOptions,
+}
+
+impl Default for ProtonationOptions {
+    fn default() -> Self {
+        Self {
+            ph: 7.0,
+            rename_residues: true,
+            disulfides: DisulfideOptions::default(),
+        }
+    }
+}
+
+/// Protonation state chosen for a titratable residue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TitrationSite {
+    /// Chain identifier
+    pub chain_id: char,
+    /// Author residue number
+    pub residue_seq: i32,
+    /// Residue insertion code (' ' if none)
+    pub insertion_code: char,
+    /// Residue name as read
+    pub residue_name: String,
+    /// Variant assigned (e.g. `HIP`, `ASH`)
+    pub variant: String,
+    /// Estimated pKa; `None` when the input named the variant
+    pub pka: Option<f32>,
+    /// Whether the assigned state carries a charge
+    pub charged: bool,
+}
+
+/// Protonation states assigned and hydrogens added by [`protonate`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProtonationReport {
+    /// pH of the assignment
+    pub ph: f32,
+    /// Titratable residues with their states
+    pub sites: Vec<TitrationSite>,
+    /// N-termini, with the number of them charged
+    pub n_termini: (usize, usize),
+    /// Disulfide bridges (`CYX` pairs)
+    pub disulfides: usize,
+    /// Hydrogens placed
+    pub hydrogens_added: usize,
+}
+
+/// Ideal X-H bond length (Å)
+fn hydrogen_bond_length(element: u8) -> f32 {
+    match element {
+        7 => 1.01,
+        8 => 0.96,
+        16 => 1.34,
+        _ => 1.09,
+    }
+}
+
+/// PDB names of the hydrogens on `atom`: `HB2`/`HB3` on a CH2, `HD21`/
+/// `HD22` on a planar NH2, `HZ1`..`HZ3` on a CH3/NH3, `H1`..`H3` on the
+/// N-terminus
+fn hydrogen_names(atom: &str, site: &HydrogenSite) -> Vec<String> {
+    let suffix = &atom[1..];
+    let digits: &[u8] = match (site.count, site.planar) {
+        (1, _) => return vec![format!("H{}", suffix)],
+        (2, false) => &[2, 3],
+        (2, true) => &[1, 2],
+        _ => &[1, 2, 3],
+    };
+    digits.iter().map(|d| format!("H{}{}", suffix, d)).collect()
+}
+
+/// Ideal positions of `site.count` hydrogens on atom `a` given its heavy
+/// neighbours and, for a single neighbour, one of that neighbour's
+/// neighbours fixing the torsion
+fn hydrogen_positions(
+    a: [f32; 3],
+    length: f32,
+    site: &HydrogenSite,
+    neighbours: &[[f32; 3]],
+    reference: Option<[f32; 3]>,
+) -> Vec<[f32; 3]> {
+    let count = site.count as usize;
+    let units: Vec<[f32; 3]> = neighbours.iter().map(|&n| normalize(sub(n, a))).collect();
+    let mut positions = match units.len() {
+        0 => return Vec::new(),
+        1 => {
+            let b = neighbours[0];
+            let r = reference.unwrap_or_else(|| add(b, perpendicular(units[0])));
+            let (angle, torsions): (f32, &[f32]) = if site.planar {
+                (120.0, &[180.0, 0.0])
+            } else {
+                (109.5, &[180.0, 60.0, -60.0])
+            };
+            torsions
+                .iter()
+                .map(|&t| place(r, b, a, length, angle, t))
+                .collect()
+        }
+        2 if !site.planar => {
+            let bisector = normalize(scale(add(units[0], units[1]), -1.0));
+            let normal = normalize(cross(units[0], units[1]));
+            let half = (109.5f32 / 2.0).to_radians();
+            [1.0, -1.0]
+                .iter()
+                .map(|&s| {
+                    let dir = add(scale(bisector, half.cos()), scale(normal, s * half.sin()));
+                    add(a, scale(dir, length))
+                })
+                .collect()
+        }
+        _ => {
+            let sum = units.iter().fold([0.0; 3], |acc, &u| add(acc, u));
+            vec![add(a, scale(normalize(scale(sum, -1.0)), length))]
+        }
+    };
+    positions.truncate(count);
+    positions
+}
+
+/// Atom ranges of consecutive atoms sharing a `residue_id`
+fn residue_ranges(atoms: &[Atom]) -> Vec<std::ops::Range<usize>> {
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+    for (i, atom) in atoms.iter().enumerate() {
+        match ranges.last_mut() {
+            Some(r) if atoms[r.start].residue_id == atom.residue_id => r.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges
+}
+
+/// Index of the atom called `name` within `range`
+fn find(records: &[PdbAtomRecord], range: &std::ops::Range<usize>, name: &str) -> Option<usize> {
+    range.clone().find(|&i| records[i].name == name)
+}
+
+/// Burial weight of atom `center`: 0 at the surface, 1 fully buried
+fn burial(atoms: &[Atom], center: usize) -> f32 {
+    let count = atoms
+        .iter()
+        .filter(|a| a.element > 1 && distance(a.coords, atoms[center].coords) <= BURIAL_RADIUS)
+        .count() as f32;
+    ((count - BURIAL_COUNTS.0) / (BURIAL_COUNTS.1 - BURIAL_COUNTS.0)).clamp(0.0, 1.0)
+}
+
+/// Side-chain atoms of opposite charge: carboxyl oxygens and basic nitrogens
+fn charged_atoms(structure: &PdbStructure) -> (Vec<usize>, Vec<usize>) {
+    let (mut acids, mut bases) = (Vec::new(), Vec::new());
+    for (i, record) in structure.records.iter().enumerate() {
+        match (canonical_name(&record.residue_name), record.name.as_str()) {
+            (Some("ASP"), "OD1" | "OD2") | (Some("GLU"), "OE1" | "OE2") => acids.push(i),
+            (Some("LYS"), "NZ") | (Some("ARG"), "NE" | "NH1" | "NH2") => bases.push(i),
+            (Some("HIS"), "ND1" | "NE2") => bases.push(i),
+            _ => {}
+        }
+    }
+    (acids, bases)
+}
+
+/// Assign the protonation state of every titratable residue: the variant
+/// name of each residue range (`None` when unchanged) and the report;
+/// `bridged` lists the `SG` atoms of disulfides
+fn assign_states(
+    structure: &PdbStructure,
+    ranges: &[std::ops::Range<usize>],
+    ph: f32,
+    bridged: &[usize],
+) -> (Vec<Option<String>>, ProtonationReport) {
+    let (atoms, records) = (&structure.atoms, &structure.records);
+    let (acids, bases) = charged_atoms(structure);
+    let residue_of = |i: usize| atoms[i].residue_id;
+    let near = |atom: usize, partners: &[usize]| {
+        partners.iter().any(|&p| {
+            residue_of(p) != residue_of(atom)
+                && distance(atoms[atom].coords, atoms[p].coords) <= SALT_BRIDGE_DISTANCE
+        })
+    };
+
+    let mut report = ProtonationReport {
+        ph,
+        ..Default::default()
+    };
+    let mut variants = vec![None; ranges.len()];
+    for (k, range) in ranges.iter().enumerate() {
+        let first = &records[range.start];
+        let name = first.residue_name.as_str();
+        let Some(parent) = canonical_name(name).filter(|_| !first.hetatm) else {
+            continue;
+        };
+        let atom = |n: &str| find(records, range, n);
+        if parent == "CYS" {
+            if name == "CYS" && atom("SG").is_some_and(|sg| bridged.contains(&sg)) {
+                variants[k] = Some("CYX".to_string());
+            }
+            continue;
+        }
+        let (model, center, groups): (f32, &str, &[&str]) = match parent {
+            "ASP" => (PKA_ASP, "CG", &["OD1", "OD2"]),
+            "GLU" => (PKA_GLU, "CD", &["OE1", "OE2"]),
+            "HIS" => (PKA_HIS, "CE1", &["ND1", "NE2"]),
+            "LYS" => (PKA_LYS, "NZ", &["NZ"]),
+            _ => continue,
+        };
+        let Some(center) = atom(center) else {
+            continue;
+        };
+        let acid = matches!(parent, "ASP" | "GLU");
+        let given = name != parent && !(parent == "HIS" && name.starts_with("HS"));
+        let (variant, pka, charged) = if given {
+            (name.to_string(), None, matches!(name, "HIP" | "HSP"))
+        } else {
+            let partners = if acid { &bases } else { &acids };
+            let bridged = groups
+                .iter()
+                .filter_map(|&g| atom(g))
+                .any(|g| near(g, partners));
+            let towards_charged = if bridged { SALT_BRIDGE_SHIFT } else { 0.0 }
+                - BURIAL_SHIFT * burial(atoms, center);
+            let pka = if acid {
+                model - towards_charged
+            } else {
+                model + towards_charged
+            };
+            let protonated = ph < pka;
+            let variant = match (parent, protonated) {
+                ("ASP", true) => "ASH",
+                ("GLU", true) => "GLH",
+                ("LYS", false) => "LYN",
+                ("HIS", true) => "HIP",
+                ("HIS", false) => {
+                    // The ring nitrogen donating to an acceptor carries the H
+                    let faces_acceptor = |n: &str| {
+                        atom(n).is_some_and(|n| {
+                            records.iter().enumerate().any(|(i, r)| {
+                                atoms[i].element == 8
+                                    && residue_of(i) != residue_of(n)
+                                    && !r.name.is_empty()
+                                    && distance(atoms[i].coords, atoms[n].coords)
+                                        <= ACCEPTOR_DISTANCE
+                            })
+                        })
+                    };
+                    if faces_acceptor("ND1") && !faces_acceptor("NE2") {
+                        "HID"
+                    } else {
+                        "HIE"
+                    }
+                }
+                (other, _) => other,
+            };
+            (variant.to_string(), Some(pka), protonated != acid)
+        };
+        if variant != name {
+            variants[k] = Some(variant.clone());
+        }
+        report.sites.push(TitrationSite {
+            chain_id: first.chain_id,
+            residue_seq: first.residue_seq,
+            insertion_code: first.insertion_code,
+            residue_name: name.to_string(),
+            variant,
+            pka,
+            charged,
+        });
+    }
+    (variants, report)
+}
+
+/// Assign protonation states at `options.ph` and add the missing hydrogens
+/// of standard residues; other residues are copied unchanged. Fails when
+/// the disulfide overrides name missing cysteines.
+pub fn protonate(
+    structure: &PdbStructure,
+    options: &ProtonationOptions,
+) -> Result<(PdbStructure, ProtonationReport)> {
+    let (atoms, records) = (&structure.atoms, &structure.records);
+    let ranges = residue_ranges(atoms);
+    let disulfides = detect_disulfides(structure, &options.disulfides)?;
+    let bridged: Vec<usize> = disulfides
+        .iter()
+        .flat_map(|d| [d.atoms.0 as usize, d.atoms.1 as usize])
+        .collect();
+    let (variants, mut report) = assign_states(structure, &ranges, options.ph, &bridged);
+    report.disulfides = disulfides.len();
+    let topology = CovalentTopology::from_structure(structure);
+    let neighbours = topology.neighbours(atoms.len());
+    let heavy = |i: usize| -> Vec<usize> {
+        neighbours[i]
+            .iter()
+            .map(|&j| j as usize)
+            .filter(|&j| atoms[j].element > 1)
+            .collect()
+    };
+
+    let mut out = PdbStructure {
+        cryst1: structure.cryst1,
+        altlocs: structure.altlocs.clone(),
+        missing_residues: structure.missing_residues.clone(),
+        ..Default::default()
+    };
+    let mut index = vec![0u32; atoms.len()];
+    for (k, range) in ranges.iter().enumerate() {
+        let first = &records[range.start];
+        let variant = variants[k].as_deref().unwrap_or(&first.residue_name);
+        let renamed = if options.rename_residues {
+            variant
+        } else {
+            &first.residue_name
+        };
+        for i in range.clone() {
+            index[i] = out.atoms.len() as u32;
+            out.atoms.push(atoms[i]);
+            let mut record = records[i].clone();
+            record.residue_name = renamed.to_string();
+            out.records.push(record);
+        }
+        let Some(side_chain) = side_chain_hydrogens(variant).filter(|_| !first.hetatm) else {
+            continue;
+        };
+
+        // Backbone: an N without a peptide bond is an N-terminus
+        let mut sites: Vec<HydrogenSite> = Vec::new();
+        let proline = canonical_name(variant) == Some("PRO");
+        if let Some(n) = find(records, range, "N") {
+            let terminal = !heavy(n).iter().any(|&j| !range.contains(&j));
+            if terminal {
+                let charged = options.ph < PKA_N_TERMINUS;
+                report.n_termini.0 += 1;
+                report.n_termini.1 += usize::from(charged);
+                let count = 2 + u8::from(charged) - u8::from(proline);
+                sites.push(HydrogenSite {
+                    atom: "N",
+                    count,
+                    planar: false,
+                });
+            } else if !proline {
+                sites.push(HydrogenSite {
+                    atom: "N",
+                    count: 1,
+                    planar: true,
+                });
+            }
+        }
+        sites.push(HydrogenSite {
+            atom: "CA",
+            count: if canonical_name(variant) == Some("GLY") {
+                2
+            } else {
+                1
+            },
+            planar: false,
+        });
+        sites.extend_from_slice(side_chain);
+
+        let mut taken: Vec<String> = range.clone().map(|i| records[i].name.clone()).collect();
+        for site in &sites {
+            let Some(a) = find(records, range, site.atom) else {
+                continue;
+            };
+            let existing: Vec<[f32; 3]> = neighbours[a]
+                .iter()
+                .filter(|&&j| atoms[j as usize].element == 1)
+                .map(|&j| atoms[j as usize].coords)
+                .collect();
+            let missing = (site.count as usize).saturating_sub(existing.len());
+            if missing == 0 {
+                continue;
+            }
+            let bonded = heavy(a);
+            let reference = bonded.first().and_then(|&b| {
+                heavy(b)
+                    .into_iter()
+                    .find(|&r| r != a)
+                    .map(|r| atoms[r].coords)
+            });
+            let positions = hydrogen_positions(
+                atoms[a].coords,
+                hydrogen_bond_length(atoms[a].element),
+                site,
+                &bonded.iter().map(|&b| atoms[b].coords).collect::<Vec<_>>(),
+                reference,
+            );
+            // Fill the positions farthest from hydrogens already there
+            let mut free: Vec<(usize, f32)> = positions
+                .iter()
+                .enumerate()
+                .map(|(p, &x)| {
+                    let gap = existing
+                        .iter()
+                        .map(|&h| norm(sub(h, x)))
+                        .fold(f32::INFINITY, f32::min);
+                    (p, gap)
+                })
+                .collect();
+            free.sort_by(|x, y| y.1.total_cmp(&x.1));
+            free.truncate(missing);
+            free.sort_by_key(|f| f.0);
+            let names = hydrogen_names(site.atom, site);
+            for (p, _) in free {
+                let Some(name) = names.iter().find(|n| !taken.contains(n)).cloned() else {
+                    break;
+                };
+                taken.push(name.clone());
+                out.atoms.push(Atom {
+                    coords: positions[p],
+                    element: 1,
+                    residue_id: atoms[a].residue_id,
+                    atom_type: 0,
+                    charge: 0.0,
+                    radius: vdw_radius(1),
+                    _reserved: [0; 4],
+                });
+                out.records.push(PdbAtomRecord {
+                    serial: 0,
+                    name,
+                    residue_name: renamed.to_string(),
+                    hetatm: false,
+                    occupancy: 1.0,
+                    ..records[a].clone()
+                });
+                report.hydrogens_added += 1;
+            }
+        }
+    }
+    for (i, record) in out.records.iter_mut().enumerate() {
+        record.serial = i as u32 + 1;
+    }
+    out.conect = structure
+        .conect
+        .iter()
+        .map(|&(a, b)| (index[a as usize], index[b as usize]))
+        .collect();
+    Ok((out, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdb::parse_pdb;
+
+    /// Extended Ala-Asp-Lys without hydrogens
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   1.458   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       0.000   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       1.404  -0.307   0.511  1.00  0.00           C
+ATOM      4  O   ALA A   1       2.385   0.235   0.005  1.00  0.00           O
+ATOM      5  CB  ALA A   1      -0.312  -0.536  -1.399  1.00  0.00           C
+ATOM      6  N   ASP A   2       1.486  -1.176   1.514  1.00  0.00           N
+ATOM      7  CA  ASP A   2       2.768  -1.556   2.095  1.00  0.00           C
+ATOM      8  C   ASP A   2       3.012  -3.056   1.962  1.00  0.00           C
+ATOM      9  O   ASP A   2       2.150  -3.862   2.309  1.00  0.00           O
+ATOM     10  CB  ASP A   2       2.834  -1.142   3.567  1.00  0.00           C
+ATOM     11  CG  ASP A   2       4.150  -1.512   4.230  1.00  0.00           C
+ATOM     12  OD1 ASP A   2       5.013  -2.094   3.538  1.00  0.00           O
+ATOM     13  OD2 ASP A   2       4.304  -1.216   5.435  1.00  0.00           O
+ATOM     14  N   LYS A   3       4.188  -3.417   1.457  1.00  0.00           N
+ATOM     15  CA  LYS A   3       4.546  -4.819   1.277  1.00  0.00           C
+ATOM     16  C   LYS A   3       5.792  -5.177   2.080  1.00  0.00           C
+ATOM     17  O   LYS A   3       6.784  -4.427   1.954  1.00  0.00           O
+ATOM     18  CB  LYS A   3       4.768  -5.129  -0.205  1.00  0.00           C
+ATOM     19  CG  LYS A   3       5.147  -6.574  -0.484  1.00  0.00           C
+ATOM     20  CD  LYS A   3       5.351  -6.806  -1.973  1.00  0.00           C
+ATOM     21  CE  LYS A   3       5.729  -8.251  -2.252  1.00  0.00           C
+ATOM     22  NZ  LYS A   3       5.929  -8.479  -3.711  1.00  0.00           N
+ATOM     23  OXT LYS A   3       5.728  -6.194   2.803  1.00  0.00           O
+";
+
+    fn protonated(ph: f32) -> (PdbStructure, ProtonationReport) {
+        let structure = parse_pdb(PEPTIDE).unwrap();
+        protonate(
+            &structure,
+            &ProtonationOptions {
+                ph,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_states_follow_ph() {
+        // ALA: H1-H3, HA, HB1-HB3; ASP: H, HA, HB2, HB3; LYS: H, HA, 4 CH2, HZ1-HZ3
+        let (_, neutral) = protonated(7.0);
+        assert_eq!(neutral.hydrogens_added, 7 + 4 + 13);
+        assert_eq!(neutral.n_termini, (1, 1));
+        let variants: Vec<&str> = neutral.sites.iter().map(|s| s.variant.as_str()).collect();
+        assert_eq!(variants, vec!["ASP", "LYS"]);
+        assert!(neutral.sites.iter().all(|s| s.charged));
+        assert_eq!(neutral.sites[0].pka, Some(PKA_ASP));
+
+        let (acidic, report) = protonated(2.0);
+        assert_eq!(report.hydrogens_added, 25);
+        assert_eq!(acidic.records[12].residue_name, "ASH");
+        assert!(acidic.records.iter().any(|r| r.name == "HD2"));
+
+        let (basic, report) = protonated(12.0);
+        assert_eq!(report.hydrogens_added, 22);
+        assert_eq!(report.n_termini, (1, 0));
+        assert!(basic.records.iter().any(|r| r.residue_name == "LYN"));
+    }
+
+    #[test]
+    fn test_hydrogens_have_ideal_geometry() {
+        let (structure, _) = protonated(7.0);
+        let topology = CovalentTopology::from_structure(&structure);
+        let neighbours = topology.neighbours(structure.atoms.len());
+        for (i, atom) in structure.atoms.iter().enumerate() {
+            if atom.element != 1 {
+                continue;
+            }
+            // Each hydrogen bonds to exactly one heavy atom of its residue
+            assert_eq!(neighbours[i].len(), 1, "{}", structure.records[i].name);
+            let parent = neighbours[i][0] as usize;
+            assert_eq!(structure.atoms[parent].residue_id, atom.residue_id);
+            // and clashes with nothing else
+            for (j, other) in structure.atoms.iter().enumerate() {
+                if j != i && j != parent {
+                    assert!(
+                        distance(atom.coords, other.coords) > 1.5,
+                        "{} {}",
+                        structure.records[i].name,
+                        structure.records[j].name
+                    );
+                }
+            }
+        }
+        let names: Vec<&str> = structure.records[..12]
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(&names[5..], &["H1", "H2", "H3", "HA", "HB1", "HB2", "HB3"]);
+        // Atoms are grouped by residue and renumbered
+        assert!(structure
+            .atoms
+            .windows(2)
+            .all(|w| w[0].residue_id <= w[1].residue_id));
+        assert_eq!(structure.records.last().unwrap().serial, 47);
+    }
+
+    #[test]
+    fn test_existing_hydrogens_and_given_variants_are_kept() {
+        let (once, _) = protonated(7.0);
+        let (twice, report) = protonate(&once, &ProtonationOptions::default()).unwrap();
+        assert_eq!(report.hydrogens_added, 0);
+        assert_eq!(twice.atoms.len(), once.atoms.len());
+
+        let named = PEPTIDE.replace("LYS", "LYN");
+        let structure = parse_pdb(&named).unwrap();
+        let (_, report) = protonate(&structure, &ProtonationOptions::default()).unwrap();
+        assert_eq!(report.sites[1].variant, "LYN");
+        assert_eq!(report.sites[1].pka, None);
+        assert_eq!(report.hydrogens_added, 23);
+    }
+}
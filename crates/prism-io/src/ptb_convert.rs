@@ -0,0 +1,723 @@
+//! # PDB / mmCIF → PTB Conversion
+//!
+//! Builds `.ptb` engine inputs from deposited structures and reports what the
+//! conversion decided or could not repair:
+//! - residues with alternate locations and the conformer kept
+//!   ([`AltlocChoice`])
+//! - residues with insertion codes; each becomes its own sequential
+//!   `residue_id`, so they stay distinct in the PTB
+//! - standard residues missing heavy atoms and numbering gaps within chains
+//! - polymer residues without a template, removed waters and heteroatoms
+//!
+//! Inputs without CONECT records can have their covalent bonds perceived
+//! on the way ([`PtbConversionOptions::infer_bonds`]), missing side chains
+//! and short loops rebuilt ([`PtbConversionOptions::repair`], see
+//! [`crate::gap_repair`]), and missing hydrogens
+//! added with protonation states for a given pH
+//! ([`PtbConversionOptions::ph`], see [`crate::protonation`]). Disulfides
+//! are detected with the overrides of [`PtbConversionOptions::disulfides`]
+//! (see [`crate::disulfides`]), listed in the report and written as SG-SG
+//! bonds in place of any other bond between cysteine sulfurs.
+//!
+//! ```no_run
+//! use prism_io::ptb_convert::{convert_to_ptb, PtbConversionOptions};
+//!
+//! let report = convert_to_ptb("1abc.cif", "1abc.ptb", &PtbConversionOptions::default())?;
+//! println!("{}", report);
+//! # Ok::<(), prism_io::PrismIoError>(())
+//! ```
+//!
+//! `prism-cli convert` runs the same conversion for `.ptb` outputs.
+
+use crate::disulfides::{detect_disulfides, Disulfide, DisulfideOptions};
+use crate::gap_repair::{
+    repair_gaps, GapRepairOptions, GapRepairReport, RebuiltKind, SECTION_REBUILT_RESIDUES,
+};
+use crate::holographic::{HolographicBinaryFormat, PtbCompression};
+use crate::mmcif::parse_mmcif_with;
+use crate::pdb::{parse_pdb_with, AltlocChoice, AltlocSite, PdbAtomRecord, PdbStructure};
+use crate::perception::CovalentTopology;
+use crate::protonation::{protonate, ProtonationOptions, ProtonationReport};
+use crate::residues::{canonical_name, is_water, standard_residue};
+use crate::sovereign_types::Bond;
+use crate::structure_file::StructureFormat;
+use crate::{PrismIoError, Result};
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// How a structure is turned into a PTB file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PtbConversionOptions {
+    /// Conformer kept for residues with alternate locations
+    pub altloc: AltlocChoice,
+    /// Drop water molecules
+    pub remove_water: bool,
+    /// Drop heteroatoms other than water (ligands, ions)
+    pub remove_hetatm: bool,
+    /// Chunk compression of the written file
+    pub compression: PtbCompression,
+    /// Fail instead of writing when standard residues miss heavy atoms
+    pub strict: bool,
+    /// Write perceived covalent bonds ([`CovalentTopology`]) in addition to
+    /// the CONECT / `struct_conn` bonds of the input
+    pub infer_bonds: bool,
+    /// Rebuild missing side chains and loops before validation
+    pub repair: Option<GapRepairOptions>,
+    /// Add missing hydrogens with the protonation states of this pH
+    pub ph: Option<f32>,
+    /// Pairing of cysteines into disulfides
+    pub disulfides: DisulfideOptions,
+}
+
+/// A standard residue lacking heavy atoms of its template
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MissingAtoms {
+    /// Chain identifier
+    pub chain_id: char,
+    /// Author residue number
+    pub residue_seq: i32,
+    /// Residue insertion code (' ' if none)
+    pub insertion_code: char,
+    /// Residue name
+    pub residue_name: String,
+    /// Template atoms not present
+    pub atoms: Vec<String>,
+}
+
+/// A jump in residue numbering between consecutive polymer residues of a chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ChainGap {
+    /// Chain identifier
+    pub chain_id: char,
+    /// Residue number before the gap
+    pub after: i32,
+    /// Residue number after the gap
+    pub before: i32,
+}
+
+/// What a PTB conversion kept, changed and found missing
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PtbConversionReport {
+    /// Input file
+    pub source: String,
+    /// BLAKE3 hash of the input, stored in the PTB header
+    pub source_hash: String,
+    /// Atoms written
+    pub atoms: usize,
+    /// Residues written
+    pub residues: usize,
+    /// Chains written
+    pub chains: usize,
+    /// Heteroatoms written (including water)
+    pub hetero_atoms: usize,
+    /// Bonds written (CONECT / `struct_conn`, plus perceived bonds when
+    /// inferred)
+    pub bonds: usize,
+    /// Water atoms removed
+    pub water_atoms_removed: usize,
+    /// Heteroatoms other than water removed
+    pub hetero_atoms_removed: usize,
+    /// Residues read with alternate locations
+    pub altlocs: Vec<AltlocSite>,
+    /// Residues with insertion codes, as `chain:number+code`
+    pub insertion_codes: Vec<String>,
+    /// Standard residues missing heavy atoms
+    pub missing_atoms: Vec<MissingAtoms>,
+    /// Numbering gaps within chains (likely missing residues)
+    pub gaps: Vec<ChainGap>,
+    /// Names of ATOM residues without a standard template (not checked)
+    pub nonstandard_residues: Vec<String>,
+    /// Rebuilt side chains and loops, when repaired
+    pub repair: Option<GapRepairReport>,
+    /// Protonation states and hydrogens added, when protonated
+    pub protonation: Option<ProtonationReport>,
+    /// Disulfide bridges written as bonds
+    pub disulfides: Vec<Disulfide>,
+}
+
+impl PtbConversionReport {
+    /// Validate `structure` as it would be written
+    pub fn for_structure(structure: &PdbStructure) -> Self {
+        let mut report = Self {
+            atoms: structure.atoms.len(),
+            hetero_atoms: structure.records.iter().filter(|r| r.hetatm).count(),
+            bonds: structure.conect.len(),
+            altlocs: structure.altlocs.clone(),
+            ..Default::default()
+        };
+        let mut chains: Vec<char> = Vec::new();
+        let mut previous: Option<&PdbAtomRecord> = None;
+        for residue in residues(structure) {
+            let first = &structure.records[residue.start];
+            report.residues += 1;
+            if !chains.contains(&first.chain_id) {
+                chains.push(first.chain_id);
+            }
+            if first.insertion_code != ' ' {
+                report.insertion_codes.push(format!(
+                    "{}:{}{}",
+                    first.chain_id, first.residue_seq, first.insertion_code
+                ));
+            }
+            if first.hetatm {
+                continue;
+            }
+            if let Some(prev) = previous.filter(|p| p.chain_id == first.chain_id) {
+                if first.residue_seq > prev.residue_seq + 1 {
+                    report.gaps.push(ChainGap {
+                        chain_id: first.chain_id,
+                        after: prev.residue_seq,
+                        before: first.residue_seq,
+                    });
+                }
+            }
+            previous = Some(first);
+
+            let Some(template) = standard_residue(&first.residue_name) else {
+                if !is_water(&first.residue_name)
+                    && !report.nonstandard_residues.contains(&first.residue_name)
+                {
+                    report.nonstandard_residues.push(first.residue_name.clone());
+                }
+                continue;
+            };
+            let names = &structure.records[residue];
+            let missing: Vec<String> = template
+                .heavy_atoms
+                .iter()
+                .filter(|&&atom| !names.iter().any(|r| r.name == atom))
+                .map(|atom| atom.to_string())
+                .collect();
+            if !missing.is_empty() {
+                report.missing_atoms.push(MissingAtoms {
+                    chain_id: first.chain_id,
+                    residue_seq: first.residue_seq,
+                    insertion_code: first.insertion_code,
+                    residue_name: first.residue_name.clone(),
+                    atoms: missing,
+                });
+            }
+        }
+        report.chains = chains.len();
+        report.nonstandard_residues.sort();
+        report
+    }
+
+    /// No missing heavy atoms and no numbering gaps
+    pub fn is_complete(&self) -> bool {
+        self.missing_atoms.is_empty() && self.gaps.is_empty()
+    }
+}
+
+impl fmt::Display for PtbConversionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} atoms in {} residues, {} chains ({} hetero atoms, {} bonds)",
+            self.source, self.atoms, self.residues, self.chains, self.hetero_atoms, self.bonds
+        )?;
+        if self.water_atoms_removed + self.hetero_atoms_removed > 0 {
+            write!(
+                f,
+                "\n  removed: {} water atoms, {} hetero atoms",
+                self.water_atoms_removed, self.hetero_atoms_removed
+            )?;
+        }
+        if !self.altlocs.is_empty() {
+            let sites: Vec<String> = self
+                .altlocs
+                .iter()
+                .map(|s| {
+                    format!(
+                        "{}:{}{} {} ({})",
+                        s.chain_id,
+                        s.residue_seq,
+                        s.insertion_code.to_string().trim(),
+                        s.residue_name,
+                        s.kept
+                    )
+                })
+                .collect();
+            write!(f, "\n  alternate locations kept: {}", sites.join(", "))?;
+        }
+        if !self.insertion_codes.is_empty() {
+            write!(
+                f,
+                "\n  insertion codes: {}",
+                self.insertion_codes.join(", ")
+            )?;
+        }
+        for m in &self.missing_atoms {
+            write!(
+                f,
+                "\n  missing heavy atoms in {}:{}{} {}: {}",
+                m.chain_id,
+                m.residue_seq,
+                m.insertion_code.to_string().trim(),
+                m.residue_name,
+                m.atoms.join(" ")
+            )?;
+        }
+        for gap in &self.gaps {
+            write!(
+                f,
+                "\n  numbering gap in chain {}: {} → {}",
+                gap.chain_id, gap.after, gap.before
+            )?;
+        }
+        if !self.nonstandard_residues.is_empty() {
+            write!(
+                f,
+                "\n  nonstandard residues (not checked): {}",
+                self.nonstandard_residues.join(", ")
+            )?;
+        }
+        if let Some(repair) = &self.repair {
+            let residues = |kind| {
+                repair
+                    .regions
+                    .iter()
+                    .filter(|r| r.kind == kind)
+                    .map(|r| r.residue_names.len())
+                    .sum::<usize>()
+            };
+            write!(
+                f,
+                "\n  rebuilt: {} side chains, {} loop residues ({} atoms, occupancy 0)",
+                residues(RebuiltKind::SideChain),
+                residues(RebuiltKind::Loop),
+                repair.atoms_rebuilt()
+            )?;
+            for gap in &repair.open_gaps {
+                let end = |seq: Option<i32>| seq.map_or("-".to_string(), |s| s.to_string());
+                write!(
+                    f,
+                    "\n  open gap in chain {}: {} → {} ({}): {}",
+                    gap.chain_id,
+                    end(gap.after),
+                    end(gap.before),
+                    gap.residue_names.join(" "),
+                    gap.reason
+                )?;
+            }
+        }
+        if !self.disulfides.is_empty() {
+            let bridges: Vec<String> = self.disulfides.iter().map(|d| d.to_string()).collect();
+            write!(f, "\n  disulfides: {}", bridges.join(", "))?;
+        }
+        if let Some(protonation) = &self.protonation {
+            let changed: Vec<String> = protonation
+                .sites
+                .iter()
+                .filter(|s| s.variant != s.residue_name)
+                .map(|s| {
+                    format!(
+                        "{}:{}{} {}",
+                        s.chain_id,
+                        s.residue_seq,
+                        s.insertion_code.to_string().trim(),
+                        s.variant
+                    )
+                })
+                .collect();
+            write!(
+                f,
+                "\n  protonated at pH {}: {} hydrogens added, {} disulfides",
+                protonation.ph, protonation.hydrogens_added, protonation.disulfides
+            )?;
+            if !changed.is_empty() {
+                write!(f, "\n  protonation variants: {}", changed.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Record ranges of consecutive atoms sharing a `residue_id`
+fn residues(structure: &PdbStructure) -> Vec<std::ops::Range<usize>> {
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+    for (i, atom) in structure.atoms.iter().enumerate() {
+        match ranges.last_mut() {
+            Some(r) if structure.atoms[r.start].residue_id == atom.residue_id => r.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges
+}
+
+/// Drop waters and heteroatoms as `options` ask, renumbering residues and
+/// bonds; returns the removed water and other hetero atom counts
+fn remove_atoms(
+    structure: &mut PdbStructure,
+    options: &PtbConversionOptions,
+) -> Result<(usize, usize)> {
+    let mut removed = (0, 0);
+    let mut index = vec![u32::MAX; structure.atoms.len()];
+    let mut atoms = Vec::with_capacity(structure.atoms.len());
+    let mut records = Vec::with_capacity(structure.records.len());
+    let mut last_residue = None;
+    let mut residue: i64 = -1;
+    for (i, (atom, record)) in structure.atoms.iter().zip(&structure.records).enumerate() {
+        let water = is_water(&record.residue_name);
+        if water && options.remove_water {
+            removed.0 += 1;
+            continue;
+        }
+        if record.hetatm && !water && options.remove_hetatm {
+            removed.1 += 1;
+            continue;
+        }
+        if last_residue != Some(atom.residue_id) {
+            residue += 1;
+            last_residue = Some(atom.residue_id);
+        }
+        index[i] = atoms.len() as u32;
+        let mut atom = *atom;
+        atom.residue_id = residue as u16;
+        atoms.push(atom);
+        records.push(record.clone());
+    }
+    if atoms.is_empty() {
+        return Err(PrismIoError::ValidationError(
+            "No atoms left after removing water and heteroatoms".to_string(),
+        ));
+    }
+    structure.conect = structure
+        .conect
+        .iter()
+        .map(|&(a, b)| (index[a as usize], index[b as usize]))
+        .filter(|&(a, b)| a != u32::MAX && b != u32::MAX)
+        .collect();
+    structure.atoms = atoms;
+    structure.records = records;
+    Ok(removed)
+}
+
+/// Read a PDB or mmCIF file as the conversion sees it
+pub fn read_for_conversion<P: AsRef<Path>>(
+    path: P,
+    altloc: AltlocChoice,
+) -> Result<(PdbStructure, [u8; 32])> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    let hash = *blake3::hash(content.as_bytes()).as_bytes();
+    let structure = match StructureFormat::from_path(path)? {
+        StructureFormat::Pdb => parse_pdb_with(&content, altloc)?,
+        StructureFormat::Mmcif => parse_mmcif_with(&content, altloc)?.structure,
+        StructureFormat::Ptb => {
+            return Err(PrismIoError::FormatError(format!(
+                "{} is already a PTB file",
+                path.display()
+            )))
+        }
+    };
+    Ok((structure, hash))
+}
+
+/// Convert the PDB or mmCIF file `input` into the PTB file `output`
+pub fn convert_to_ptb<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    options: &PtbConversionOptions,
+) -> Result<PtbConversionReport> {
+    let (mut structure, source_hash) = read_for_conversion(&input, options.altloc)?;
+    let removed = remove_atoms(&mut structure, options)?;
+    let repair = match &options.repair {
+        Some(repair_options) => {
+            let (repaired, repair) = repair_gaps(&structure, repair_options)?;
+            structure = repaired;
+            Some(repair)
+        }
+        None => None,
+    };
+    let protonation = match options.ph {
+        Some(ph) => {
+            let protonation_options = ProtonationOptions {
+                ph,
+                disulfides: options.disulfides.clone(),
+                ..Default::default()
+            };
+            let (protonated, protonation) = protonate(&structure, &protonation_options)?;
+            structure = protonated;
+            Some(protonation)
+        }
+        None => None,
+    };
+    let mut report = PtbConversionReport::for_structure(&structure);
+    report.repair = repair;
+    report.protonation = protonation;
+    report.disulfides = detect_disulfides(&structure, &options.disulfides)?;
+    report.source = input.as_ref().display().to_string();
+    report.source_hash = hex::encode(source_hash);
+    (report.water_atoms_removed, report.hetero_atoms_removed) = removed;
+
+    if options.strict && !report.missing_atoms.is_empty() {
+        return Err(PrismIoError::ValidationError(format!(
+            "{} standard residues miss heavy atoms:\n{}",
+            report.missing_atoms.len(),
+            report
+        )));
+    }
+    let mut bonds: Vec<Bond> = if options.infer_bonds {
+        CovalentTopology::from_structure(&structure).sovereign_bonds()
+    } else {
+        structure
+            .conect
+            .iter()
+            .map(|&(atom1, atom2)| Bond {
+                atom1,
+                atom2,
+                order: 1,
+                bond_type: 0,
+                _reserved: [0],
+            })
+            .collect()
+    };
+    let sulfur = |i: u32| {
+        let record = &structure.records[i as usize];
+        record.name == "SG" && canonical_name(&record.residue_name) == Some("CYS")
+    };
+    bonds.retain(|b| !(sulfur(b.atom1) && sulfur(b.atom2)));
+    bonds.extend(report.disulfides.iter().map(|d| Bond {
+        atom1: d.atoms.0,
+        atom2: d.atoms.1,
+        order: 1,
+        bond_type: 0,
+        _reserved: [0],
+    }));
+    report.bonds = bonds.len();
+    let mut ptb = HolographicBinaryFormat::new()
+        .with_source_hash(source_hash)
+        .with_atoms(structure.atoms)
+        .with_bonds(bonds)
+        .with_compression(options.compression);
+    if let Some(repair) = report
+        .repair
+        .as_ref()
+        .filter(|r| !r.rebuilt_residues.is_empty())
+    {
+        ptb = ptb.with_section(SECTION_REBUILT_RESIDUES, repair.section_bytes(), false);
+    }
+    ptb.write_to_file(output)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holographic::PtbStructure;
+
+    const PDB: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+ATOM      5  CB  ALA A   1       1.988  -0.773  -1.199  1.00  0.00           C
+ATOM      6  N   SER A   2       3.332   1.536   0.000  1.00  0.00           N
+ATOM      7  CA ASER A   2       3.970   2.844   0.000  0.30  0.00           C
+ATOM      8  CA BSER A   2       3.980   2.850   0.000  0.70  0.00           C
+ATOM      9  C   SER A   2       5.485   2.697   0.000  1.00  0.00           C
+ATOM     10  O   SER A   2       6.040   1.597   0.000  1.00  0.00           O
+ATOM     11  CB ASER A   2       3.500   3.600   1.200  0.30  0.00           C
+ATOM     12  CB BSER A   2       3.510   3.610   1.210  0.70  0.00           C
+ATOM     13  OG BSER A   2       2.100   3.700   1.300  0.70  0.00           O
+ATOM     14  N   GLY A   2A      6.100   3.800   0.000  1.00  0.00           N
+ATOM     15  CA  GLY A   2A      7.500   3.900   0.000  1.00  0.00           C
+ATOM     16  C   GLY A   2A      8.000   5.300   0.000  1.00  0.00           C
+ATOM     17  O   GLY A   2A      7.300   6.300   0.000  1.00  0.00           O
+ATOM     18  N   LYS A   6       9.300   5.400   0.000  1.00  0.00           N
+ATOM     19  CA  LYS A   6      10.000   6.700   0.000  1.00  0.00           C
+ATOM     20  C   LYS A   6      11.500   6.500   0.000  1.00  0.00           C
+ATOM     21  O   LYS A   6      12.000   5.400   0.000  1.00  0.00           O
+HETATM   22 ZN    ZN A 101      20.000  20.000  20.000  1.00  0.00          ZN
+HETATM   23  O   HOH A 201      25.000  25.000  25.000  1.00  0.00           O
+";
+
+    /// Input and output paths in a scratch directory removed with the guard
+    fn write_input(name: &str) -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join(format!("{}.pdb", name));
+        std::fs::write(&input, PDB).unwrap();
+        let output = dir.path().join(format!("{}.ptb", name));
+        (dir, input, output)
+    }
+
+    #[test]
+    fn test_report_lists_altlocs_insertions_and_missing_atoms() {
+        let (_dir, input, output) = write_input("report");
+        let report = convert_to_ptb(&input, &output, &PtbConversionOptions::default()).unwrap();
+        assert_eq!(report.atoms, 20);
+        assert_eq!(report.residues, 6);
+        assert_eq!(report.hetero_atoms, 2);
+        assert_eq!(report.altlocs.len(), 1);
+        assert_eq!(report.altlocs[0].altlocs, vec!['A', 'B']);
+        assert_eq!(report.altlocs[0].kept, 'A');
+        assert_eq!(report.insertion_codes, vec!["A:2A".to_string()]);
+        // SER conformer A has no OG, LYS has no side chain
+        let missing: Vec<(i32, Vec<String>)> = report
+            .missing_atoms
+            .iter()
+            .map(|m| (m.residue_seq, m.atoms.clone()))
+            .collect();
+        assert_eq!(missing[0], (2, vec!["OG".to_string()]));
+        assert_eq!(missing[1].0, 6);
+        assert_eq!(missing[1].1.len(), 5);
+        assert_eq!(
+            report.gaps,
+            vec![ChainGap {
+                chain_id: 'A',
+                after: 2,
+                before: 6
+            }]
+        );
+        assert!(!report.is_complete());
+
+        let mut ptb = PtbStructure::load(&output).unwrap();
+        assert_eq!(ptb.header().atom_count, 20);
+        assert_eq!(ptb.atoms().unwrap()[10].residue_id, 2);
+        assert_eq!(report.bonds, 0);
+    }
+
+    #[test]
+    fn test_inferred_bonds_are_written() {
+        let (_dir, input, output) = write_input("bonds");
+        let options = PtbConversionOptions {
+            infer_bonds: true,
+            ..Default::default()
+        };
+        let report = convert_to_ptb(&input, &output, &options).unwrap();
+        // Backbone and side chains (ALA 4, SER 4, GLY 3, LYS 3) and three
+        // peptide bonds; the numbering gap is not a chain break
+        assert_eq!(report.bonds, 17);
+        let mut ptb = PtbStructure::load(&output).unwrap();
+        assert_eq!(ptb.bonds().unwrap().len(), 17);
+    }
+
+    #[test]
+    fn test_repair_completes_residues() {
+        let (_dir, input, output) = write_input("repair");
+        let options = PtbConversionOptions {
+            repair: Some(GapRepairOptions::default()),
+            ..Default::default()
+        };
+        let report = convert_to_ptb(&input, &output, &options).unwrap();
+        // SER OG of conformer A and the LYS side chain
+        assert!(report.missing_atoms.is_empty());
+        assert_eq!(report.atoms, 20 + 1 + 5);
+        let repair = report.repair.as_ref().unwrap();
+        assert_eq!(repair.atoms_rebuilt(), 6);
+        assert!(report.to_string().contains("rebuilt: 2 side chains"));
+
+        let mut ptb = PtbStructure::load(&output).unwrap();
+        let rebuilt = crate::gap_repair::read_rebuilt_residues(&mut ptb).unwrap();
+        let residues: Vec<(u32, u32)> = rebuilt.iter().map(|r| (r.residue_id, r.atoms)).collect();
+        assert_eq!(residues, vec![(1, 1), (3, 5)]);
+    }
+
+    #[test]
+    fn test_protonation_adds_hydrogens() {
+        let (_dir, input, output) = write_input("protonation");
+        let options = PtbConversionOptions {
+            remove_water: true,
+            remove_hetatm: true,
+            ph: Some(7.0),
+            ..Default::default()
+        };
+        let report = convert_to_ptb(&input, &output, &options).unwrap();
+        // Charged N-terminus and ALA side chain (7), SER without OG (4), GLY
+        // (3), LYS without side chain (2)
+        let protonation = report.protonation.as_ref().unwrap();
+        assert_eq!(protonation.hydrogens_added, 16);
+        assert_eq!(protonation.n_termini, (1, 1));
+        assert_eq!(report.atoms, 18 + 16);
+        assert!(report.to_string().contains("16 hydrogens added"));
+        let mut ptb = PtbStructure::load(&output).unwrap();
+        let hydrogens = ptb
+            .atoms()
+            .unwrap()
+            .iter()
+            .filter(|a| a.element == 1)
+            .count();
+        assert_eq!(hydrogens, 16);
+    }
+
+    #[test]
+    fn test_disulfides_are_reported_and_bonded() {
+        let (_dir, input, output) = write_input("disulfides");
+        let cysteines = "\
+ATOM      1  N   CYS A   3       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  CYS A   3       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  CB  CYS A   3       2.000   1.400   0.000  1.00  0.00           C
+ATOM      4  SG  CYS A   3       3.800   1.500   0.000  1.00  0.00           S
+ATOM      5  N   CYS A   9       7.500   4.000   0.000  1.00  0.00           N
+ATOM      6  CA  CYS A   9       6.600   2.900   0.000  1.00  0.00           C
+ATOM      7  CB  CYS A   9       7.200   1.600   1.000  1.00  0.00           C
+ATOM      8  SG  CYS A   9       5.830   1.500   0.000  1.00  0.00           S
+";
+        std::fs::write(&input, cysteines).unwrap();
+        let report = convert_to_ptb(&input, &output, &PtbConversionOptions::default()).unwrap();
+        assert_eq!(report.disulfides.len(), 1);
+        assert_eq!(report.bonds, 1);
+        assert!(report.to_string().contains("disulfides: A:3-A:9"));
+        let mut ptb = PtbStructure::load(&output).unwrap();
+        let bond = ptb.bonds().unwrap()[0];
+        assert_eq!((bond.atom1, bond.atom2), (3, 7));
+
+        let options = PtbConversionOptions {
+            infer_bonds: true,
+            disulfides: DisulfideOptions {
+                ignore: vec!["A:3,A:9".parse().unwrap()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let report = convert_to_ptb(&input, &output, &options).unwrap();
+        assert!(report.disulfides.is_empty());
+        let mut ptb = PtbStructure::load(&output).unwrap();
+        let bonds = ptb.bonds().unwrap();
+        assert!(!bonds.iter().any(|b| (b.atom1, b.atom2) == (3, 7)));
+    }
+
+    #[test]
+    fn test_altloc_choice_and_removal() {
+        let (_dir, input, output) = write_input("options");
+        let options = PtbConversionOptions {
+            altloc: AltlocChoice::HighestOccupancy,
+            remove_water: true,
+            remove_hetatm: true,
+            ..Default::default()
+        };
+        let report = convert_to_ptb(&input, &output, &options).unwrap();
+        assert_eq!(report.altlocs[0].kept, 'B');
+        assert_eq!(
+            (report.water_atoms_removed, report.hetero_atoms_removed),
+            (1, 1)
+        );
+        assert_eq!(report.atoms, 19);
+        assert_eq!(report.residues, 4);
+        assert_eq!(report.missing_atoms.len(), 1);
+
+        let (structure, _) = read_for_conversion(&input, AltlocChoice::Id('B')).unwrap();
+        assert_eq!(structure.atoms[6].coords[0], 3.98);
+        assert_eq!(
+            "occupancy".parse::<AltlocChoice>().unwrap(),
+            AltlocChoice::HighestOccupancy
+        );
+        assert!("AB".parse::<AltlocChoice>().is_err());
+    }
+
+    #[test]
+    fn test_strict_conversion_refuses_missing_atoms() {
+        let (_dir, input, output) = write_input("strict");
+        let options = PtbConversionOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            convert_to_ptb(&input, &output, &options),
+            Err(PrismIoError::ValidationError(_))
+        ));
+        assert!(!output.exists());
+    }
+}
@@ -0,0 +1,460 @@
+//! # PTB Trajectory Container (.ptbt)
+//!
+//! Append-only frame container of the PTB family. Frames are flushed as the
+//! integrator produces them, so a run never holds its trajectory in memory
+//! and a crash loses at most the frame being written.
+//!
+//! ## Layout
+//! - Header (32 bytes): magic `PRISM4T\0`, container version, atom count,
+//!   flags (bit 0: unit cell), reserved word, timestep (ps) as f64
+//! - Per frame: `FRM\0` tag, payload length (u32), step (u64), time (ps, f64),
+//!   payload, then the CRC32 of everything from the tag to the payload end
+//! - Payload: unit cell parameters `[a, b, c, α, β, γ]` as f32 if flagged,
+//!   then `[x, y, z]` f32 per atom
+//!
+//! All values are little-endian. Frames are self-delimiting, so the valid
+//! prefix of a file is found by scanning: [`read_ptb_trajectory`] stops at a
+//! torn or corrupt tail and [`PtbTrajectoryWriter::append`] cuts it off
+//! before writing on.
+
+use crate::simulation_box::SimulationBox;
+use crate::trajectory::{TrajectoryFrame, TrajectoryWriter};
+use crate::{PrismIoError, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Magic bytes identifying a PTB trajectory container
+pub const PTB_TRAJECTORY_MAGIC: &[u8; 8] = b"PRISM4T\0";
+
+/// Current version of the container layout
+pub const PTB_TRAJECTORY_VERSION: u32 = 1;
+
+/// Header flag: frames carry unit cell parameters
+const FLAG_UNIT_CELL: u32 = 0x1;
+
+const HEADER_BYTES: usize = 32;
+const FRAME_TAG: &[u8; 4] = b"FRM\0";
+/// Tag, payload length, step and time
+const FRAME_HEADER_BYTES: usize = 24;
+const CRC_BYTES: usize = 4;
+
+/// Container header metadata
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PtbTrajectoryHeader {
+    /// Number of atoms per frame
+    pub num_atoms: u32,
+    /// Whether frames carry a unit cell
+    pub has_unit_cell: bool,
+    /// Integration timestep (ps)
+    pub dt_ps: f64,
+}
+
+impl PtbTrajectoryHeader {
+    fn to_bytes(self) -> [u8; HEADER_BYTES] {
+        let mut bytes = [0u8; HEADER_BYTES];
+        bytes[..8].copy_from_slice(PTB_TRAJECTORY_MAGIC);
+        bytes[8..12].copy_from_slice(&PTB_TRAJECTORY_VERSION.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.num_atoms.to_le_bytes());
+        let flags = if self.has_unit_cell {
+            FLAG_UNIT_CELL
+        } else {
+            0
+        };
+        bytes[16..20].copy_from_slice(&flags.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.dt_ps.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; HEADER_BYTES]) -> Result<Self> {
+        if &bytes[..8] != PTB_TRAJECTORY_MAGIC {
+            return Err(PrismIoError::FormatError(
+                "Not a PTB trajectory container".to_string(),
+            ));
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        if version != PTB_TRAJECTORY_VERSION {
+            return Err(PrismIoError::FormatError(format!(
+                "Unsupported PTB trajectory version: {}, expected {}",
+                version, PTB_TRAJECTORY_VERSION
+            )));
+        }
+        let flags = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+        Ok(Self {
+            num_atoms: u32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+            has_unit_cell: flags & FLAG_UNIT_CELL != 0,
+            dt_ps: f64::from_le_bytes(bytes[24..32].try_into().unwrap()),
+        })
+    }
+
+    fn payload_bytes(&self) -> usize {
+        self.num_atoms as usize * 12 + if self.has_unit_cell { 24 } else { 0 }
+    }
+}
+
+/// One frame read back from a container
+#[derive(Debug, Clone, PartialEq)]
+pub struct PtbTrajectoryFrame {
+    /// Integration step
+    pub step: u64,
+    /// Simulation time (ps)
+    pub time_ps: f64,
+    /// Unit cell, if the container records one
+    pub unit_cell: Option<SimulationBox>,
+    /// `[x, y, z]` per atom (Å)
+    pub positions: Vec<[f32; 3]>,
+}
+
+/// Valid prefix of a container
+#[derive(Debug, Clone)]
+pub struct PtbTrajectory {
+    /// Header metadata
+    pub header: PtbTrajectoryHeader,
+    /// Complete frames, in file order
+    pub frames: Vec<PtbTrajectoryFrame>,
+    /// Bytes after the last complete frame (a torn or corrupt tail)
+    pub trailing_bytes: u64,
+}
+
+/// Complete frame handed out by [`scan`]
+struct ScannedFrame<'a> {
+    step: u64,
+    time_ps: f64,
+    payload: &'a [u8],
+    /// File offset the frame ends at
+    end: u64,
+}
+
+/// Walk the complete frames of a container. Returns the header, the end of
+/// the valid prefix and the file length.
+fn scan<R: Read + Seek>(
+    input: &mut R,
+    mut visit: impl FnMut(&PtbTrajectoryHeader, ScannedFrame<'_>),
+) -> Result<(PtbTrajectoryHeader, u64, u64)> {
+    let file_len = input.seek(SeekFrom::End(0))?;
+    input.seek(SeekFrom::Start(0))?;
+    let mut header = [0u8; HEADER_BYTES];
+    input.read_exact(&mut header)?;
+    let header = PtbTrajectoryHeader::from_bytes(&header)?;
+
+    let payload_len = header.payload_bytes();
+    let mut record = vec![0u8; FRAME_HEADER_BYTES + payload_len + CRC_BYTES];
+    let mut end = HEADER_BYTES as u64;
+    while end + record.len() as u64 <= file_len {
+        input.read_exact(&mut record)?;
+        let (body, crc) = record.split_at(FRAME_HEADER_BYTES + payload_len);
+        let complete = &body[..4] == FRAME_TAG
+            && u32::from_le_bytes(body[4..8].try_into().unwrap()) as usize == payload_len
+            && crc32fast::hash(body) == u32::from_le_bytes(crc.try_into().unwrap());
+        if !complete {
+            break;
+        }
+        end += record.len() as u64;
+        visit(
+            &header,
+            ScannedFrame {
+                step: u64::from_le_bytes(body[8..16].try_into().unwrap()),
+                time_ps: f64::from_le_bytes(body[16..24].try_into().unwrap()),
+                payload: &body[FRAME_HEADER_BYTES..],
+                end,
+            },
+        );
+    }
+    Ok((header, end, file_len))
+}
+
+fn f32_at(bytes: &[u8], index: usize) -> f32 {
+    f32::from_le_bytes(bytes[index * 4..index * 4 + 4].try_into().unwrap())
+}
+
+/// Read the complete frames of a container, ignoring a torn tail
+pub fn read_ptb_trajectory<P: AsRef<Path>>(path: P) -> Result<PtbTrajectory> {
+    let mut input = BufReader::new(File::open(path)?);
+    let mut frames = Vec::new();
+    let (header, end, file_len) = scan(&mut input, |header, frame| {
+        let cell_values = if header.has_unit_cell { 6 } else { 0 };
+        let payload = frame.payload;
+        let unit_cell = header
+            .has_unit_cell
+            .then(|| {
+                SimulationBox::from_parameters(std::array::from_fn(|i| f32_at(payload, i))).ok()
+            })
+            .flatten();
+        let positions = (0..header.num_atoms as usize)
+            .map(|atom| std::array::from_fn(|d| f32_at(payload, cell_values + atom * 3 + d)))
+            .collect();
+        frames.push(PtbTrajectoryFrame {
+            step: frame.step,
+            time_ps: frame.time_ps,
+            unit_cell,
+            positions,
+        });
+    })?;
+    Ok(PtbTrajectory {
+        header,
+        frames,
+        trailing_bytes: file_len - end,
+    })
+}
+
+/// Streaming PTB trajectory writer
+#[derive(Debug)]
+pub struct PtbTrajectoryWriter {
+    file: BufWriter<File>,
+    header: PtbTrajectoryHeader,
+    frames: usize,
+    record: Vec<u8>,
+}
+
+impl PtbTrajectoryWriter {
+    /// Create a container and write its header
+    pub fn create<P: AsRef<Path>>(path: P, header: PtbTrajectoryHeader) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&header.to_bytes())?;
+        file.flush()?;
+        Ok(Self {
+            file,
+            header,
+            frames: 0,
+            record: Vec::new(),
+        })
+    }
+
+    /// Open an existing container to write on, or create it if missing
+    ///
+    /// A torn or corrupt tail left by a crash is cut off, as are frames at
+    /// or after `from_step` (e.g. the steps a run resumed from a checkpoint
+    /// will write again). The container must match `header`.
+    pub fn append<P: AsRef<Path>>(
+        path: P,
+        header: PtbTrajectoryHeader,
+        from_step: Option<u64>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Self::create(path, header);
+        }
+
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        // Frames are kept up to the first one at or after `from_step`
+        let mut frames = 0;
+        let mut keep = HEADER_BYTES as u64;
+        let mut resumed = false;
+        let (existing, end, file_len) = scan(&mut BufReader::new(&mut file), |_, frame| {
+            resumed |= from_step.is_some_and(|from| frame.step >= from);
+            if !resumed {
+                frames += 1;
+                keep = frame.end;
+            }
+        })?;
+        if existing.num_atoms != header.num_atoms || existing.has_unit_cell != header.has_unit_cell
+        {
+            return Err(PrismIoError::ValidationError(format!(
+                "{} holds frames of {} atoms{}, the run writes {} atoms{}",
+                path.display(),
+                existing.num_atoms,
+                if existing.has_unit_cell {
+                    " with a unit cell"
+                } else {
+                    ""
+                },
+                header.num_atoms,
+                if header.has_unit_cell {
+                    " with a unit cell"
+                } else {
+                    ""
+                },
+            )));
+        }
+        if end < file_len {
+            tracing::warn!(
+                "Dropping {} bytes of torn trajectory tail from {}",
+                file_len - end,
+                path.display()
+            );
+        }
+        if keep < end {
+            tracing::info!(
+                "Dropping trajectory frames of {} from step {:?} on",
+                path.display(),
+                from_step
+            );
+        }
+
+        file.set_len(keep)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            file: BufWriter::new(file),
+            header: existing,
+            frames,
+            record: Vec::new(),
+        })
+    }
+
+    /// Header of the container
+    pub fn header(&self) -> &PtbTrajectoryHeader {
+        &self.header
+    }
+}
+
+impl TrajectoryWriter for PtbTrajectoryWriter {
+    fn write_frame(&mut self, frame: &TrajectoryFrame<'_>) -> Result<()> {
+        let n = self.header.num_atoms as usize;
+        if frame.positions.len() != n * 4 {
+            return Err(PrismIoError::ValidationError(format!(
+                "PTB trajectory frame has {} values, expected {} (Float4 x {} atoms)",
+                frame.positions.len(),
+                n * 4,
+                n
+            )));
+        }
+
+        let record = &mut self.record;
+        record.clear();
+        record.extend_from_slice(FRAME_TAG);
+        record.extend_from_slice(&(self.header.payload_bytes() as u32).to_le_bytes());
+        record.extend_from_slice(&frame.step.to_le_bytes());
+        record.extend_from_slice(&frame.time_ps.to_le_bytes());
+        if self.header.has_unit_cell {
+            let cell = frame
+                .simulation_box
+                .map_or([0.0, 0.0, 0.0, 90.0, 90.0, 90.0], |cell| cell.parameters());
+            for v in cell {
+                record.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        for atom in frame.positions.chunks_exact(4) {
+            for v in &atom[..3] {
+                record.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        let crc = crc32fast::hash(record);
+        record.extend_from_slice(&crc.to_le_bytes());
+
+        // Whole frames reach the file, so a crash tears at most this one
+        self.file.write_all(record)?;
+        self.file.flush()?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn frames_written(&self) -> usize {
+        self.frames
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for PtbTrajectoryWriter {
+    fn drop(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trajectory::TrajectoryFormat;
+
+    fn write(writer: &mut PtbTrajectoryWriter, step: u64, cell: Option<SimulationBox>) {
+        let s = step as f32;
+        let positions = [s, 1.0, 2.0, 1.0, 3.0, 4.0 + s, 5.0, 1.0];
+        writer
+            .write_frame(&TrajectoryFrame {
+                step,
+                time_ps: step as f64 * 0.002,
+                positions: &positions,
+                velocities: None,
+                simulation_box: cell,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_ptb_trajectory_round_trip() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let header = PtbTrajectoryHeader {
+            num_atoms: 2,
+            has_unit_cell: true,
+            dt_ps: 0.002,
+        };
+        let cell = SimulationBox::from_parameters([30.0, 31.0, 32.0, 90.0, 90.0, 90.0]).unwrap();
+        let mut writer = PtbTrajectoryWriter::create(file.path(), header).unwrap();
+        for step in [10, 20, 30] {
+            write(&mut writer, step, Some(cell));
+        }
+        assert!(writer
+            .write_frame(&TrajectoryFrame {
+                step: 0,
+                time_ps: 0.0,
+                positions: &[0.0; 4],
+                velocities: None,
+                simulation_box: None
+            })
+            .is_err());
+        assert_eq!(writer.frames_written(), 3);
+        drop(writer);
+        assert_eq!(
+            std::fs::metadata(file.path()).unwrap().len(),
+            TrajectoryFormat::Ptb.file_bytes(2, 3, true, false)
+        );
+
+        let trajectory = read_ptb_trajectory(file.path()).unwrap();
+        assert_eq!(trajectory.header, header);
+        assert_eq!(trajectory.trailing_bytes, 0);
+        assert_eq!(trajectory.frames.len(), 3);
+        assert_eq!(trajectory.frames[2].step, 30);
+        assert!((trajectory.frames[2].time_ps - 0.06).abs() < 1e-12);
+        assert_eq!(trajectory.frames[2].positions, vec![[30.0, 1.0, 2.0], [3.0, 34.0, 5.0]]);
+        assert_eq!(trajectory.frames[1].unit_cell, Some(cell));
+    }
+
+    #[test]
+    fn test_append_recovers_torn_tail() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let header = PtbTrajectoryHeader {
+            num_atoms: 2,
+            has_unit_cell: false,
+            dt_ps: 0.001,
+        };
+        let mut writer = PtbTrajectoryWriter::create(file.path(), header).unwrap();
+        for step in [5, 10, 15] {
+            write(&mut writer, step, None);
+        }
+        drop(writer);
+
+        // A crash mid-frame leaves part of a record behind
+        let complete = std::fs::metadata(file.path()).unwrap().len();
+        let mut bytes = std::fs::read(file.path()).unwrap();
+        let partial = bytes[32..60].to_vec();
+        bytes.extend_from_slice(&partial);
+        std::fs::write(file.path(), &bytes).unwrap();
+        let torn = read_ptb_trajectory(file.path()).unwrap();
+        assert_eq!(torn.frames.len(), 3);
+        assert_eq!(torn.trailing_bytes, 28);
+
+        // A corrupted frame ends the valid prefix too
+        let mut corrupted = bytes.clone();
+        corrupted[complete as usize - 10] ^= 0xff;
+        std::fs::write(file.path(), &corrupted).unwrap();
+        assert_eq!(read_ptb_trajectory(file.path()).unwrap().frames.len(), 2);
+        std::fs::write(file.path(), &bytes).unwrap();
+
+        // Resuming at step 10 drops the tail and the frames it will rewrite
+        let mut writer = PtbTrajectoryWriter::append(file.path(), header, Some(10)).unwrap();
+        assert_eq!(writer.frames_written(), 1);
+        write(&mut writer, 10, None);
+        write(&mut writer, 15, None);
+        drop(writer);
+        let trajectory = read_ptb_trajectory(file.path()).unwrap();
+        assert_eq!(trajectory.trailing_bytes, 0);
+        let steps: Vec<u64> = trajectory.frames.iter().map(|f| f.step).collect();
+        assert_eq!(steps, vec![5, 10, 15]);
+
+        let other = PtbTrajectoryHeader { num_atoms: 3, ..header };
+        assert!(PtbTrajectoryWriter::append(file.path(), other, None).is_err());
+    }
+}
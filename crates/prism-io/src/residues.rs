@@ -0,0 +1,507 @@
+//! # Standard Residue Templates
+//!
+//! Heavy atoms and bonds of the 20 standard amino acids in PDB nomenclature, with
+//! the force-field protonation variants (AMBER `HID`/`CYX`/..., CHARMM
+//! `HSD`/...) mapped to their parent residue. Terminal `OXT` is not part of
+//! any template.
+
+/// Heavy atoms of a standard residue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResidueTemplate {
+    /// Three-letter residue name
+    pub name: &'static str,
+    /// One-letter code
+    pub code: char,
+    /// Heavy-atom names, backbone first
+    pub heavy_atoms: &'static [&'static str],
+    /// Bonds between heavy atoms beyond `N-CA`, `CA-C`, `C=O` and `CA-CB`
+    pub side_chain_bonds: &'static [(&'static str, &'static str)],
+}
+
+impl ResidueTemplate {
+    /// Every heavy-atom bond within the residue
+    pub fn bonds(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        let cb = self.heavy_atoms.contains(&"CB");
+        [("N", "CA"), ("CA", "C"), ("C", "O")]
+            .into_iter()
+            .chain(cb.then_some(("CA", "CB")))
+            .chain(self.side_chain_bonds.iter().copied())
+    }
+}
+
+/// Backbone heavy atoms shared by every amino acid
+pub const BACKBONE: [&str; 4] = ["N", "CA", "C", "O"];
+
+/// Names read as water
+pub const WATER_NAMES: [&str; 6] = ["HOH", "WAT", "DOD", "H2O", "TIP3", "SOL"];
+
+const fn template(
+    name: &'static str,
+    code: char,
+    heavy_atoms: &'static [&'static str],
+    side_chain_bonds: &'static [(&'static str, &'static str)],
+) -> ResidueTemplate {
+    ResidueTemplate {
+        name,
+        code,
+        heavy_atoms,
+        side_chain_bonds,
+    }
+}
+
+/// The standard amino acids in alphabetical order
+pub const AMINO_ACIDS: [ResidueTemplate; 20] = [
+    template("ALA", 'A', &["N", "CA", "C", "O", "CB"], &[]),
+    template(
+        "ARG",
+        'R',
+        &[
+            "N", "CA", "C", "O", "CB", "CG", "CD", "NE", "CZ", "NH1", "NH2",
+        ],
+        &[
+            ("CB", "CG"),
+            ("CG", "CD"),
+            ("CD", "NE"),
+            ("NE", "CZ"),
+            ("CZ", "NH1"),
+            ("CZ", "NH2"),
+        ],
+    ),
+    template(
+        "ASN",
+        'N',
+        &["N", "CA", "C", "O", "CB", "CG", "OD1", "ND2"],
+        &[("CB", "CG"), ("CG", "OD1"), ("CG", "ND2")],
+    ),
+    template(
+        "ASP",
+        'D',
+        &["N", "CA", "C", "O", "CB", "CG", "OD1", "OD2"],
+        &[("CB", "CG"), ("CG", "OD1"), ("CG", "OD2")],
+    ),
+    template(
+        "CYS",
+        'C',
+        &["N", "CA", "C", "O", "CB", "SG"],
+        &[("CB", "SG")],
+    ),
+    template(
+        "GLN",
+        'Q',
+        &["N", "CA", "C", "O", "CB", "CG", "CD", "OE1", "NE2"],
+        &[("CB", "CG"), ("CG", "CD"), ("CD", "OE1"), ("CD", "NE2")],
+    ),
+    template(
+        "GLU",
+        'E',
+        &["N", "CA", "C", "O", "CB", "CG", "CD", "OE1", "OE2"],
+        &[("CB", "CG"), ("CG", "CD"), ("CD", "OE1"), ("CD", "OE2")],
+    ),
+    template("GLY", 'G', &["N", "CA", "C", "O"], &[]),
+    template(
+        "HIS",
+        'H',
+        &["N", "CA", "C", "O", "CB", "CG", "ND1", "CD2", "CE1", "NE2"],
+        &[
+            ("CB", "CG"),
+            ("CG", "ND1"),
+            ("CG", "CD2"),
+            ("ND1", "CE1"),
+            ("CD2", "NE2"),
+            ("CE1", "NE2"),
+        ],
+    ),
+    template(
+        "ILE",
+        'I',
+        &["N", "CA", "C", "O", "CB", "CG1", "CG2", "CD1"],
+        &[("CB", "CG1"), ("CB", "CG2"), ("CG1", "CD1")],
+    ),
+    template(
+        "LEU",
+        'L',
+        &["N", "CA", "C", "O", "CB", "CG", "CD1", "CD2"],
+        &[("CB", "CG"), ("CG", "CD1"), ("CG", "CD2")],
+    ),
+    template(
+        "LYS",
+        'K',
+        &["N", "CA", "C", "O", "CB", "CG", "CD", "CE", "NZ"],
+        &[("CB", "CG"), ("CG", "CD"), ("CD", "CE"), ("CE", "NZ")],
+    ),
+    template(
+        "MET",
+        'M',
+        &["N", "CA", "C", "O", "CB", "CG", "SD", "CE"],
+        &[("CB", "CG"), ("CG", "SD"), ("SD", "CE")],
+    ),
+    template(
+        "PHE",
+        'F',
+        &[
+            "N", "CA", "C", "O", "CB", "CG", "CD1", "CD2", "CE1", "CE2", "CZ",
+        ],
+        &[
+            ("CB", "CG"),
+            ("CG", "CD1"),
+            ("CG", "CD2"),
+            ("CD1", "CE1"),
+            ("CD2", "CE2"),
+            ("CE1", "CZ"),
+            ("CE2", "CZ"),
+        ],
+    ),
+    template(
+        "PRO",
+        'P',
+        &["N", "CA", "C", "O", "CB", "CG", "CD"],
+        &[("CB", "CG"), ("CG", "CD"), ("CD", "N")],
+    ),
+    template(
+        "SER",
+        'S',
+        &["N", "CA", "C", "O", "CB", "OG"],
+        &[("CB", "OG")],
+    ),
+    template(
+        "THR",
+        'T',
+        &["N", "CA", "C", "O", "CB", "OG1", "CG2"],
+        &[("CB", "OG1"), ("CB", "CG2")],
+    ),
+    template(
+        "TRP",
+        'W',
+        &[
+            "N", "CA", "C", "O", "CB", "CG", "CD1", "CD2", "NE1", "CE2", "CE3", "CZ2", "CZ3", "CH2",
+        ],
+        &[
+            ("CB", "CG"),
+            ("CG", "CD1"),
+            ("CG", "CD2"),
+            ("CD1", "NE1"),
+            ("NE1", "CE2"),
+            ("CD2", "CE2"),
+            ("CD2", "CE3"),
+            ("CE2", "CZ2"),
+            ("CE3", "CZ3"),
+            ("CZ2", "CH2"),
+            ("CZ3", "CH2"),
+        ],
+    ),
+    template(
+        "TYR",
+        'Y',
+        &[
+            "N", "CA", "C", "O", "CB", "CG", "CD1", "CD2", "CE1", "CE2", "CZ", "OH",
+        ],
+        &[
+            ("CB", "CG"),
+            ("CG", "CD1"),
+            ("CG", "CD2"),
+            ("CD1", "CE1"),
+            ("CD2", "CE2"),
+            ("CE1", "CZ"),
+            ("CE2", "CZ"),
+            ("CZ", "OH"),
+        ],
+    ),
+    template(
+        "VAL",
+        'V',
+        &["N", "CA", "C", "O", "CB", "CG1", "CG2"],
+        &[("CB", "CG1"), ("CB", "CG2")],
+    ),
+];
+
+/// Parent residue name of a standard residue or one of its protonation
+/// variants, `None` for anything else
+pub fn canonical_name(name: &str) -> Option<&'static str> {
+    let parent = match name {
+        "HID" | "HIE" | "HIP" | "HSD" | "HSE" | "HSP" => "HIS",
+        "CYX" | "CYM" => "CYS",
+        "ASH" => "ASP",
+        "GLH" => "GLU",
+        "LYN" => "LYS",
+        other => other,
+    };
+    AMINO_ACIDS
+        .iter()
+        .find(|t| t.name == parent)
+        .map(|t| t.name)
+}
+
+/// Template of a standard residue or one of its protonation variants
+pub fn standard_residue(name: &str) -> Option<&'static ResidueTemplate> {
+    let parent = canonical_name(name)?;
+    AMINO_ACIDS.iter().find(|t| t.name == parent)
+}
+
+/// Hydrogens carried by one heavy atom of a residue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HydrogenSite {
+    /// Heavy atom name
+    pub atom: &'static str,
+    /// Hydrogens on the atom
+    pub count: u8,
+    /// sp2 atom: hydrogens lie in the plane of its neighbours
+    pub planar: bool,
+}
+
+const fn tetrahedral(atom: &'static str, count: u8) -> HydrogenSite {
+    HydrogenSite {
+        atom,
+        count,
+        planar: false,
+    }
+}
+
+const fn planar(atom: &'static str, count: u8) -> HydrogenSite {
+    HydrogenSite {
+        atom,
+        count,
+        planar: true,
+    }
+}
+
+/// Side-chain hydrogens of a residue in a given protonation state (AMBER
+/// names: `HID`/`HIE`/`HIP`, `ASH`, `GLH`, `LYN`, `CYX`/`CYM`; plain `HIS`
+/// is `HIE`), `None` for non-standard residues. Backbone hydrogens depend
+/// on the chain position and are not listed.
+pub fn side_chain_hydrogens(variant: &str) -> Option<&'static [HydrogenSite]> {
+    Some(match variant {
+        "ALA" => const { &[tetrahedral("CB", 3)] },
+        "ARG" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    tetrahedral("CG", 2),
+                    tetrahedral("CD", 2),
+                    planar("NE", 1),
+                    planar("NH1", 2),
+                    planar("NH2", 2),
+                ]
+            }
+        }
+        "ASN" => const { &[tetrahedral("CB", 2), planar("ND2", 2)] },
+        "ASP" => const { &[tetrahedral("CB", 2)] },
+        "ASH" => const { &[tetrahedral("CB", 2), tetrahedral("OD2", 1)] },
+        "CYS" => const { &[tetrahedral("CB", 2), tetrahedral("SG", 1)] },
+        "CYX" => const { &[tetrahedral("CB", 2)] },
+        "CYM" => const { &[tetrahedral("CB", 2)] },
+        "GLN" => const { &[tetrahedral("CB", 2), tetrahedral("CG", 2), planar("NE2", 2)] },
+        "GLU" => const { &[tetrahedral("CB", 2), tetrahedral("CG", 2)] },
+        "GLH" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    tetrahedral("CG", 2),
+                    tetrahedral("OE2", 1),
+                ]
+            }
+        }
+        "GLY" => const { &[] },
+        "HID" | "HSD" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    planar("ND1", 1),
+                    planar("CD2", 1),
+                    planar("CE1", 1),
+                ]
+            }
+        }
+        "HIE" | "HIS" | "HSE" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    planar("CD2", 1),
+                    planar("CE1", 1),
+                    planar("NE2", 1),
+                ]
+            }
+        }
+        "HIP" | "HSP" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    planar("ND1", 1),
+                    planar("CD2", 1),
+                    planar("CE1", 1),
+                    planar("NE2", 1),
+                ]
+            }
+        }
+        "ILE" => {
+            const {
+                &[
+                    tetrahedral("CB", 1),
+                    tetrahedral("CG1", 2),
+                    tetrahedral("CG2", 3),
+                    tetrahedral("CD1", 3),
+                ]
+            }
+        }
+        "LEU" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    tetrahedral("CG", 1),
+                    tetrahedral("CD1", 3),
+                    tetrahedral("CD2", 3),
+                ]
+            }
+        }
+        "LYS" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    tetrahedral("CG", 2),
+                    tetrahedral("CD", 2),
+                    tetrahedral("CE", 2),
+                    tetrahedral("NZ", 3),
+                ]
+            }
+        }
+        "LYN" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    tetrahedral("CG", 2),
+                    tetrahedral("CD", 2),
+                    tetrahedral("CE", 2),
+                    tetrahedral("NZ", 2),
+                ]
+            }
+        }
+        "MET" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    tetrahedral("CG", 2),
+                    tetrahedral("CE", 3),
+                ]
+            }
+        }
+        "PHE" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    planar("CD1", 1),
+                    planar("CD2", 1),
+                    planar("CE1", 1),
+                    planar("CE2", 1),
+                    planar("CZ", 1),
+                ]
+            }
+        }
+        "PRO" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    tetrahedral("CG", 2),
+                    tetrahedral("CD", 2),
+                ]
+            }
+        }
+        "SER" => const { &[tetrahedral("CB", 2), tetrahedral("OG", 1)] },
+        "THR" => {
+            const {
+                &[
+                    tetrahedral("CB", 1),
+                    tetrahedral("OG1", 1),
+                    tetrahedral("CG2", 3),
+                ]
+            }
+        }
+        "TRP" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    planar("CD1", 1),
+                    planar("NE1", 1),
+                    planar("CE3", 1),
+                    planar("CZ2", 1),
+                    planar("CZ3", 1),
+                    planar("CH2", 1),
+                ]
+            }
+        }
+        "TYR" => {
+            const {
+                &[
+                    tetrahedral("CB", 2),
+                    planar("CD1", 1),
+                    planar("CD2", 1),
+                    planar("CE1", 1),
+                    planar("CE2", 1),
+                    tetrahedral("OH", 1),
+                ]
+            }
+        }
+        "VAL" => {
+            const {
+                &[
+                    tetrahedral("CB", 1),
+                    tetrahedral("CG1", 3),
+                    tetrahedral("CG2", 3),
+                ]
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Whether `name` is a water residue
+pub fn is_water(name: &str) -> bool {
+    WATER_NAMES.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variants_map_to_parent_templates() {
+        assert_eq!(standard_residue("HIE").unwrap().name, "HIS");
+        assert_eq!(standard_residue("CYX").unwrap().heavy_atoms.len(), 6);
+        assert!(standard_residue("HOH").is_none());
+        for t in &AMINO_ACIDS {
+            assert_eq!(&t.heavy_atoms[..4], &BACKBONE);
+            // Every heavy atom is bonded, rings close once
+            let bonds: Vec<_> = t.bonds().collect();
+            for atom in t.heavy_atoms {
+                assert!(
+                    bonds.iter().any(|b| b.0 == *atom || b.1 == *atom),
+                    "{} {}",
+                    t.name,
+                    atom
+                );
+            }
+            let rings = match t.name {
+                "HIS" | "PHE" | "PRO" | "TYR" => 1,
+                "TRP" => 2,
+                _ => 0,
+            };
+            assert_eq!(bonds.len(), t.heavy_atoms.len() - 1 + rings, "{}", t.name);
+            for site in side_chain_hydrogens(t.name).unwrap() {
+                assert!(
+                    t.heavy_atoms.contains(&site.atom),
+                    "{} {}",
+                    t.name,
+                    site.atom
+                );
+            }
+        }
+        let count = |v: &str| -> u8 {
+            side_chain_hydrogens(v)
+                .unwrap()
+                .iter()
+                .map(|s| s.count)
+                .sum()
+        };
+        assert_eq!(count("HIP"), count("HID") + 1);
+        assert_eq!(count("LYS"), count("LYN") + 1);
+        assert_eq!(count("ASH"), count("ASP") + 1);
+    }
+}
@@ -0,0 +1,393 @@
+//! # Side-Chain Geometry and Rotamers
+//!
+//! Internal coordinates (bond length, bond angle, dihedral) of the
+//! side-chain heavy atoms of the standard amino acids, from `CB` outwards,
+//! and a backbone-independent rotamer library of their χ angles. The χ
+//! values are the modes of the most populated rotamers of the Lovell et al.
+//! penultimate rotamer library (Proteins 40:389, 2000), most common first;
+//! bond lengths and angles follow Engh & Huber.
+//!
+//! Placing each atom in table order from three atoms already placed
+//! rebuilds a side chain in any rotamer (see [`crate::gap_repair`]).
+
+/// Dihedral fixing a side-chain atom
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Torsion {
+    /// Side-chain angle χ(n + 1) plus an offset in degrees (branches)
+    Chi(usize, f32),
+    /// Fixed dihedral in degrees (chirality, rings, planar groups)
+    Fixed(f32),
+}
+
+/// Internal coordinates of one side-chain atom: bonded to `refs[2]` at
+/// `bond` Å, with angle `refs[1]-refs[2]-atom` and dihedral
+/// `refs[0]-refs[1]-refs[2]-atom`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SideChainAtom {
+    /// Atom name
+    pub atom: &'static str,
+    /// Atoms defining the position, the bonded atom last
+    pub refs: [&'static str; 3],
+    /// Bond length (Å)
+    pub bond: f32,
+    /// Bond angle (degrees)
+    pub angle: f32,
+    /// Dihedral
+    pub torsion: Torsion,
+}
+
+const fn chi(
+    atom: &'static str,
+    refs: [&'static str; 3],
+    bond: f32,
+    angle: f32,
+    n: usize,
+    offset: f32,
+) -> SideChainAtom {
+    SideChainAtom {
+        atom,
+        refs,
+        bond,
+        angle,
+        torsion: Torsion::Chi(n, offset),
+    }
+}
+
+const fn fixed(
+    atom: &'static str,
+    refs: [&'static str; 3],
+    bond: f32,
+    angle: f32,
+    dihedral: f32,
+) -> SideChainAtom {
+    SideChainAtom {
+        atom,
+        refs,
+        bond,
+        angle,
+        torsion: Torsion::Fixed(dihedral),
+    }
+}
+
+/// `CB` of an L-amino acid, from the backbone
+const CB: SideChainAtom = fixed("CB", ["C", "N", "CA"], 1.53, 110.5, -122.6);
+
+const G: [&str; 3] = ["N", "CA", "CB"];
+
+/// Side-chain atoms of a standard residue (parent name, see
+/// [`crate::residues::canonical_name`]) in build order, `None` for other
+/// residues
+pub fn side_chain_geometry(residue: &str) -> Option<&'static [SideChainAtom]> {
+    const D: [&str; 3] = ["CA", "CB", "CG"];
+    const E: [&str; 3] = ["CB", "CG", "CD"];
+    Some(match residue {
+        "GLY" => &[],
+        "ALA" => const { &[CB] },
+        "SER" => const { &[CB, chi("OG", G, 1.417, 110.8, 0, 0.0)] },
+        "CYS" => const { &[CB, chi("SG", G, 1.808, 113.8, 0, 0.0)] },
+        "THR" => {
+            const {
+                &[
+                    CB,
+                    chi("OG1", G, 1.433, 109.2, 0, 0.0),
+                    chi("CG2", G, 1.521, 111.1, 0, -120.0),
+                ]
+            }
+        }
+        "VAL" => {
+            const {
+                &[
+                    CB,
+                    chi("CG1", G, 1.527, 110.7, 0, 0.0),
+                    chi("CG2", G, 1.527, 110.4, 0, 120.0),
+                ]
+            }
+        }
+        "ILE" => {
+            const {
+                &[
+                    CB,
+                    chi("CG1", G, 1.527, 110.7, 0, 0.0),
+                    chi("CG2", G, 1.527, 110.4, 0, -120.0),
+                    chi("CD1", ["CA", "CB", "CG1"], 1.52, 113.97, 1, 0.0),
+                ]
+            }
+        }
+        "LEU" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.53, 116.1, 0, 0.0),
+                    chi("CD1", D, 1.524, 110.3, 1, 0.0),
+                    chi("CD2", D, 1.525, 110.6, 1, 120.0),
+                ]
+            }
+        }
+        "MET" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.52, 114.1, 0, 0.0),
+                    chi("SD", D, 1.81, 112.7, 1, 0.0),
+                    chi("CE", ["CB", "CG", "SD"], 1.79, 100.6, 2, 0.0),
+                ]
+            }
+        }
+        "PRO" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.50, 104.5, 0, 0.0),
+                    chi("CD", D, 1.51, 105.5, 1, 0.0),
+                ]
+            }
+        }
+        "PHE" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.50, 113.8, 0, 0.0),
+                    chi("CD1", D, 1.39, 120.7, 1, 0.0),
+                    chi("CD2", D, 1.39, 120.7, 1, 180.0),
+                    fixed("CE1", ["CB", "CG", "CD1"], 1.39, 120.7, 180.0),
+                    fixed("CE2", ["CB", "CG", "CD2"], 1.39, 120.7, 180.0),
+                    fixed("CZ", ["CG", "CD1", "CE1"], 1.39, 120.0, 0.0),
+                ]
+            }
+        }
+        "TYR" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.51, 113.8, 0, 0.0),
+                    chi("CD1", D, 1.39, 120.8, 1, 0.0),
+                    chi("CD2", D, 1.39, 120.8, 1, 180.0),
+                    fixed("CE1", ["CB", "CG", "CD1"], 1.39, 121.2, 180.0),
+                    fixed("CE2", ["CB", "CG", "CD2"], 1.39, 121.2, 180.0),
+                    fixed("CZ", ["CG", "CD1", "CE1"], 1.38, 119.6, 0.0),
+                    fixed("OH", ["CD1", "CE1", "CZ"], 1.36, 119.9, 180.0),
+                ]
+            }
+        }
+        "TRP" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.50, 114.1, 0, 0.0),
+                    chi("CD1", D, 1.37, 127.1, 1, 0.0),
+                    chi("CD2", D, 1.43, 126.6, 1, 180.0),
+                    fixed("NE1", ["CB", "CG", "CD1"], 1.38, 110.2, 180.0),
+                    fixed("CE2", ["CB", "CG", "CD2"], 1.41, 107.2, 180.0),
+                    fixed("CE3", ["CB", "CG", "CD2"], 1.40, 133.9, 0.0),
+                    fixed("CZ2", ["CG", "CD2", "CE2"], 1.40, 122.4, 180.0),
+                    fixed("CZ3", ["CG", "CD2", "CE3"], 1.39, 118.7, 180.0),
+                    fixed("CH2", ["CD2", "CE2", "CZ2"], 1.37, 117.5, 0.0),
+                ]
+            }
+        }
+        "HIS" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.50, 113.7, 0, 0.0),
+                    chi("ND1", D, 1.38, 122.7, 1, 0.0),
+                    chi("CD2", D, 1.36, 131.0, 1, 180.0),
+                    fixed("CE1", ["CB", "CG", "ND1"], 1.32, 108.5, 180.0),
+                    fixed("NE2", ["CB", "CG", "CD2"], 1.37, 107.0, 180.0),
+                ]
+            }
+        }
+        "ASP" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.52, 113.0, 0, 0.0),
+                    chi("OD1", D, 1.25, 119.2, 1, 0.0),
+                    chi("OD2", D, 1.25, 118.2, 1, 180.0),
+                ]
+            }
+        }
+        "ASN" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.52, 112.6, 0, 0.0),
+                    chi("OD1", D, 1.23, 120.8, 1, 0.0),
+                    chi("ND2", D, 1.33, 116.4, 1, 180.0),
+                ]
+            }
+        }
+        "GLU" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.52, 114.1, 0, 0.0),
+                    chi("CD", D, 1.52, 113.3, 1, 0.0),
+                    chi("OE1", E, 1.25, 119.0, 2, 0.0),
+                    chi("OE2", E, 1.25, 118.1, 2, 180.0),
+                ]
+            }
+        }
+        "GLN" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.52, 114.1, 0, 0.0),
+                    chi("CD", D, 1.52, 112.6, 1, 0.0),
+                    chi("OE1", E, 1.23, 120.9, 2, 0.0),
+                    chi("NE2", E, 1.33, 116.5, 2, 180.0),
+                ]
+            }
+        }
+        "LYS" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.52, 114.1, 0, 0.0),
+                    chi("CD", D, 1.52, 111.3, 1, 0.0),
+                    chi("CE", E, 1.52, 111.3, 2, 0.0),
+                    chi("NZ", ["CG", "CD", "CE"], 1.49, 111.9, 3, 0.0),
+                ]
+            }
+        }
+        "ARG" => {
+            const {
+                &[
+                    CB,
+                    chi("CG", G, 1.52, 114.1, 0, 0.0),
+                    chi("CD", D, 1.52, 111.3, 1, 0.0),
+                    chi("NE", E, 1.46, 112.0, 2, 0.0),
+                    chi("CZ", ["CG", "CD", "NE"], 1.33, 124.2, 3, 0.0),
+                    fixed("NH1", ["CD", "NE", "CZ"], 1.33, 120.0, 0.0),
+                    fixed("NH2", ["CD", "NE", "CZ"], 1.33, 120.0, 180.0),
+                ]
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// χ angles (degrees) of the common rotamers of a standard residue (parent
+/// name), most populated first; empty for residues without χ angles
+pub fn rotamers(residue: &str) -> &'static [&'static [f32]] {
+    match residue {
+        "SER" => &[&[62.0], &[-65.0], &[180.0]],
+        "CYS" => &[&[-65.0], &[62.0], &[-177.0]],
+        "THR" => &[&[62.0], &[-65.0], &[-175.0]],
+        "VAL" => &[&[175.0], &[-60.0], &[63.0]],
+        "ILE" => &[
+            &[-65.0, 170.0],
+            &[-57.0, -60.0],
+            &[62.0, 170.0],
+            &[-177.0, 170.0],
+        ],
+        "LEU" => &[
+            &[-65.0, 175.0],
+            &[-177.0, 65.0],
+            &[-172.0, 145.0],
+            &[-85.0, 65.0],
+        ],
+        "MET" => &[
+            &[-65.0, -65.0, -70.0],
+            &[-68.0, 180.0, -75.0],
+            &[-65.0, 180.0, 75.0],
+            &[-177.0, 65.0, 75.0],
+            &[-177.0, 180.0, 75.0],
+        ],
+        "PRO" => &[&[30.0, -35.0], &[-30.0, 40.0]],
+        "PHE" | "TYR" => &[
+            &[-65.0, -85.0],
+            &[-177.0, 80.0],
+            &[62.0, 90.0],
+            &[-65.0, -30.0],
+        ],
+        "TRP" => &[
+            &[-65.0, 95.0],
+            &[-65.0, -5.0],
+            &[-177.0, -105.0],
+            &[-177.0, 90.0],
+            &[62.0, -90.0],
+        ],
+        "HIS" => &[
+            &[-65.0, -70.0],
+            &[-177.0, 60.0],
+            &[-177.0, -80.0],
+            &[-65.0, 165.0],
+            &[62.0, -75.0],
+        ],
+        "ASP" => &[
+            &[-70.0, -15.0],
+            &[-177.0, 65.0],
+            &[62.0, -10.0],
+            &[62.0, 30.0],
+            &[-177.0, 0.0],
+        ],
+        "ASN" => &[
+            &[-65.0, -20.0],
+            &[-177.0, 30.0],
+            &[-65.0, -75.0],
+            &[62.0, -10.0],
+            &[-177.0, -20.0],
+            &[-65.0, 120.0],
+        ],
+        "GLU" => &[
+            &[-65.0, 180.0, -10.0],
+            &[-177.0, 65.0, 10.0],
+            &[-65.0, -65.0, -40.0],
+            &[-177.0, 180.0, 0.0],
+            &[62.0, 180.0, -20.0],
+        ],
+        "GLN" => &[
+            &[-65.0, 180.0, -25.0],
+            &[-177.0, 65.0, 60.0],
+            &[-65.0, -65.0, -40.0],
+            &[-177.0, 180.0, 0.0],
+            &[62.0, 180.0, 20.0],
+        ],
+        "LYS" => &[
+            &[-65.0, 180.0, 180.0, 180.0],
+            &[-177.0, 180.0, 180.0, 180.0],
+            &[-68.0, 180.0, -65.0, 180.0],
+            &[-65.0, -65.0, 180.0, 180.0],
+            &[62.0, 180.0, 68.0, 180.0],
+        ],
+        "ARG" => &[
+            &[-67.0, 180.0, 180.0, 180.0],
+            &[-177.0, 180.0, 180.0, 180.0],
+            &[-67.0, 180.0, 65.0, 180.0],
+            &[-67.0, 180.0, 180.0, 85.0],
+            &[-67.0, 180.0, -65.0, -85.0],
+        ],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::residues::AMINO_ACIDS;
+
+    #[test]
+    fn test_geometry_covers_templates() {
+        for template in &AMINO_ACIDS {
+            let geometry = side_chain_geometry(template.name).unwrap();
+            let mut placed: Vec<&str> = vec!["N", "CA", "C", "O"];
+            for atom in geometry {
+                // Every atom is built from atoms placed before it
+                assert!(
+                    atom.refs.iter().all(|r| placed.contains(r)),
+                    "{}",
+                    atom.atom
+                );
+                if let Torsion::Chi(n, _) = atom.torsion {
+                    assert!(rotamers(template.name).iter().all(|chi| n < chi.len()));
+                }
+                placed.push(atom.atom);
+            }
+            let mut expected: Vec<&str> = template.heavy_atoms.to_vec();
+            expected.sort_unstable();
+            placed.sort_unstable();
+            assert_eq!(placed, expected, "{}", template.name);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! # SDF / MOL Reader (V2000)
+//!
+//! Reads the first record of an MDL molfile or SD file: the molecule name
+//! from the header, the atom and bond blocks, `M  CHG` formal charges and,
+//! when present, the per-atom partial charges of an
+//! `atom.dprop.PartialCharge` (RDKit) or `PARTIAL_CHARGES` data item.
+//! V3000 connection tables are not supported.
+
+use crate::ligand::{BondOrder, Ligand, LigandAtom, LigandBond};
+use crate::topology::Topology;
+use crate::{PrismIoError, Result};
+
+/// Data items read as per-atom partial charges
+const CHARGE_ITEMS: [&str; 2] = ["atom.dprop.PartialCharge", "PARTIAL_CHARGES"];
+
+fn field(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len())).unwrap_or("").trim()
+}
+
+/// Parse the first molecule of an SD file
+pub fn parse_sdf(content: &str) -> Result<Ligand> {
+    let lines: Vec<&str> = content.lines().collect();
+    let error = |message: String| PrismIoError::FormatError(format!("SDF: {}", message));
+    let counts = lines
+        .get(3)
+        .ok_or_else(|| error("missing counts line".to_string()))?;
+    if counts.contains("V3000") {
+        return Err(error(
+            "V3000 connection tables are not supported".to_string(),
+        ));
+    }
+    let count = |start| {
+        field(counts, start, start + 3)
+            .parse::<usize>()
+            .map_err(|_| error(format!("invalid counts line '{}'", counts)))
+    };
+    let (atom_count, bond_count) = (count(0)?, count(3)?);
+    if lines.len() < 4 + atom_count + bond_count {
+        return Err(error(format!(
+            "{} atoms and {} bonds announced, file ends early",
+            atom_count, bond_count
+        )));
+    }
+
+    let mut ligand = Ligand {
+        name: lines[0].trim().to_string(),
+        ..Default::default()
+    };
+    for (k, line) in lines[4..4 + atom_count].iter().enumerate() {
+        let coordinate = |start| {
+            field(line, start, start + 10)
+                .parse::<f32>()
+                .map_err(|_| error(format!("invalid atom line '{}'", line)))
+        };
+        let symbol = field(line, 31, 34);
+        let element = Topology::atomic_number(symbol);
+        if element == 0 {
+            return Err(error(format!("unknown element '{}'", symbol)));
+        }
+        // Charge codes 1-7 stand for +3..-3, 4 being a doublet radical
+        let formal_charge = match field(line, 36, 39).parse::<i8>().unwrap_or(0) {
+            code @ (1..=3 | 5..=7) => 4 - code,
+            _ => 0,
+        };
+        ligand.atoms.push(LigandAtom {
+            name: format!("{}{}", Topology::element_symbol(element), k + 1),
+            element,
+            coords: [coordinate(0)?, coordinate(10)?, coordinate(20)?],
+            formal_charge,
+            partial_charge: None,
+        });
+    }
+    for line in &lines[4 + atom_count..4 + atom_count + bond_count] {
+        let index = |start| {
+            field(line, start, start + 3)
+                .parse::<u32>()
+                .ok()
+                .filter(|&i| i >= 1 && i as usize <= atom_count)
+                .map(|i| i - 1)
+                .ok_or_else(|| error(format!("invalid bond line '{}'", line)))
+        };
+        let order = match field(line, 6, 9) {
+            "1" => BondOrder::Single,
+            "2" => BondOrder::Double,
+            "3" => BondOrder::Triple,
+            "4" => BondOrder::Aromatic,
+            other => return Err(error(format!("unsupported bond type '{}'", other))),
+        };
+        ligand.bonds.push(LigandBond {
+            i: index(0)?,
+            j: index(3)?,
+            order,
+        });
+    }
+
+    // Properties block, then data items up to the end of the record
+    let mut rest = lines[4 + atom_count + bond_count..].iter();
+    let mut charges_reset = false;
+    for line in rest.by_ref() {
+        if line.starts_with("M  END") {
+            break;
+        }
+        if let Some(entries) = line.strip_prefix("M  CHG") {
+            if !charges_reset {
+                // The first CHG line zeroes the charges of the atom block
+                ligand.atoms.iter_mut().for_each(|a| a.formal_charge = 0);
+                charges_reset = true;
+            }
+            let values: Vec<i32> = entries
+                .split_whitespace()
+                .skip(1)
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            for pair in values.chunks_exact(2) {
+                if let Some(atom) = ligand.atoms.get_mut((pair[0] - 1) as usize) {
+                    atom.formal_charge = pair[1] as i8;
+                }
+            }
+        }
+    }
+    let mut rest = rest.peekable();
+    while let Some(line) = rest.next() {
+        if line.starts_with("$$$$") {
+            break;
+        }
+        let is_charge_item = line.starts_with('>')
+            && CHARGE_ITEMS
+                .iter()
+                .any(|item| line.contains(&format!("<{}>", item)));
+        if !is_charge_item {
+            continue;
+        }
+        let mut values = Vec::new();
+        while let Some(value_line) = rest.next_if(|l| !l.trim().is_empty()) {
+            for value in value_line.split_whitespace() {
+                values.push(
+                    value
+                        .parse::<f32>()
+                        .map_err(|_| error(format!("invalid partial charge '{}'", value)))?,
+                );
+            }
+        }
+        if values.len() != atom_count {
+            return Err(error(format!(
+                "{} partial charges for {} atoms",
+                values.len(),
+                atom_count
+            )));
+        }
+        for (atom, charge) in ligand.atoms.iter_mut().zip(values) {
+            atom.partial_charge = Some(charge);
+        }
+    }
+    Ok(ligand)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Acetate with RDKit-style partial charges
+    pub(crate) const ACETATE: &str = "\
+acetate
+  test
+
+  7  6  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.5200    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    2.1400    1.0800    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    2.1400   -1.0800    0.0000 O   0  5  0  0  0  0  0  0  0  0  0  0
+   -0.3600    1.0300    0.0000 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.3600   -0.5100    0.8900 H   0  0  0  0  0  0  0  0  0  0  0  0
+   -0.3600   -0.5100   -0.8900 H   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0
+  2  3  2  0
+  2  4  1  0
+  1  5  1  0
+  1  6  1  0
+  1  7  1  0
+M  CHG  1   4  -1
+M  END
+>  <atom.dprop.PartialCharge>  (1)
+-0.41 0.90 -0.80 -0.80 0.037 0.037 0.036
+
+$$$$
+";
+
+    #[test]
+    fn test_parse_sdf() {
+        let ligand = parse_sdf(ACETATE).unwrap();
+        assert_eq!(ligand.name, "acetate");
+        assert_eq!(ligand.atoms.len(), 7);
+        assert_eq!(ligand.atoms[3].element, 8);
+        assert_eq!(ligand.atoms[3].name, "O4");
+        assert_eq!(ligand.formal_charge(), -1);
+        assert_eq!(ligand.bonds[1].order, BondOrder::Double);
+        let charges = ligand.partial_charges().unwrap();
+        assert!((charges.iter().sum::<f32>() + 1.0).abs() < 1e-4);
+
+        let truncated: String = ACETATE.lines().take(8).collect::<Vec<_>>().join("\n");
+        assert!(parse_sdf(&truncated).is_err());
+        assert!(parse_sdf(&ACETATE.replace("V2000", "V3000")).is_err());
+    }
+}
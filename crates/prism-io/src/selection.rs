@@ -0,0 +1,680 @@
+//! # Atom Selections
+//!
+//! VMD-style selection expressions resolved to atom indices, shared by
+//! restraints, analyses and trajectory output filters:
+//!
+//! ```text
+//! chain A and resid 10-50 and name CA
+//! protein and not (hydrogen or resname PRO)
+//! within 5.0 of (resname LIG)
+//! ```
+//!
+//! Grammar, loosest binding first: `or`, `and`, then the prefix operators
+//! `not` and `within <Å> of`, which apply to the next term (parenthesise
+//! compound targets). Terms:
+//!
+//! - `name`, `resname` with one or more values; `*` and `?` are wildcards
+//! - `chain`, `element` (symbol or atomic number)
+//! - `resid` (residue number), `resindex` (0-based residue index) and
+//!   `index` (0-based atom index), taking numbers and ranges `10-50`,
+//!   `10:50` or `10 to 50`
+//! - `all`, `none`, `protein`, `backbone` (protein N, CA, C, O), `water`,
+//!   `hydrogen`, `heavy`
+//!
+//! Residue numbers come from the PDB author numbering when available and
+//! are `residue index + 1` otherwise. Atom order and indices follow the
+//! structure the [`SelectionContext`] was built from.
+
+use crate::pdb::PdbStructure;
+use crate::sovereign_types::Atom;
+use crate::topology::Topology;
+use crate::{PrismIoError, Result};
+use std::str::FromStr;
+
+/// Residue names treated as protein
+const PROTEIN_RESIDUES: &[&str] = &[
+    "ALA", "ARG", "ASN", "ASP", "CYS", "GLN", "GLU", "GLY", "HIS", "ILE", "LEU", "LYS", "MET",
+    "PHE", "PRO", "SER", "THR", "TRP", "TYR", "VAL", "HID", "HIE", "HIP", "HSD", "HSE", "HSP",
+    "CYX", "ASH", "GLH", "LYN", "MSE",
+];
+
+const WATER_RESIDUES: &[&str] = &["HOH", "WAT", "SOL", "TIP3", "TIP4", "SPC", "H2O"];
+
+const BACKBONE_NAMES: &[&str] = &["N", "CA", "C", "O"];
+
+const ELEMENTS: &[(&str, u8)] = &[
+    ("H", 1),
+    ("C", 6),
+    ("N", 7),
+    ("O", 8),
+    ("F", 9),
+    ("NA", 11),
+    ("MG", 12),
+    ("P", 15),
+    ("S", 16),
+    ("CL", 17),
+    ("K", 19),
+    ("CA", 20),
+    ("MN", 25),
+    ("FE", 26),
+    ("CO", 27),
+    ("NI", 28),
+    ("CU", 29),
+    ("ZN", 30),
+    ("SE", 34),
+    ("BR", 35),
+    ("I", 53),
+];
+
+const KEYWORDS: &[&str] = &[
+    "and", "or", "not", "within", "of", "all", "none", "name", "resname", "chain", "element",
+    "resid", "resindex", "index", "protein", "backbone", "water", "hydrogen", "heavy",
+];
+
+/// Per-atom properties a selection can test
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionAtom {
+    /// Atom name (e.g. "CA")
+    pub name: String,
+    /// Residue name (e.g. "ALA")
+    pub residue_name: String,
+    /// Residue number (PDB author numbering or index + 1)
+    pub residue_number: i64,
+    /// 0-based residue index
+    pub residue_index: u16,
+    /// Chain identifier
+    pub chain: char,
+    /// Atomic number
+    pub element: u8,
+    /// Å
+    pub position: [f32; 3],
+}
+
+/// Atoms a selection is evaluated against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SelectionContext {
+    atoms: Vec<SelectionAtom>,
+}
+
+impl SelectionContext {
+    /// Context over explicit per-atom properties
+    pub fn new(atoms: Vec<SelectionAtom>) -> Self {
+        Self { atoms }
+    }
+
+    /// Names and residue names from the topology; single chain `A`
+    pub fn from_topology(topology: &Topology) -> Self {
+        Self::new(
+            topology
+                .atoms
+                .iter()
+                .enumerate()
+                .map(|(i, atom)| SelectionAtom {
+                    name: topology.atom_names.get(i).cloned().unwrap_or_default(),
+                    residue_name: topology
+                        .residue_names
+                        .get(atom.residue_id as usize)
+                        .cloned()
+                        .unwrap_or_default(),
+                    ..Self::bare(atom)
+                })
+                .collect(),
+        )
+    }
+
+    /// Full PDB metadata, including chains and author residue numbers
+    pub fn from_pdb(structure: &PdbStructure) -> Self {
+        Self::new(
+            structure
+                .atoms
+                .iter()
+                .enumerate()
+                .map(|(i, atom)| match structure.records.get(i) {
+                    Some(record) => SelectionAtom {
+                        name: record.name.clone(),
+                        residue_name: record.residue_name.clone(),
+                        residue_number: record.residue_seq as i64,
+                        chain: record.chain_id,
+                        ..Self::bare(atom)
+                    },
+                    None => Self::bare(atom),
+                })
+                .collect(),
+        )
+    }
+
+    /// Bare atoms: only elements, residue indices, atom indices and
+    /// positions can be selected
+    pub fn from_atoms(atoms: &[Atom]) -> Self {
+        Self::new(atoms.iter().map(Self::bare).collect())
+    }
+
+    fn bare(atom: &Atom) -> SelectionAtom {
+        SelectionAtom {
+            name: String::new(),
+            residue_name: String::new(),
+            residue_number: atom.residue_id as i64 + 1,
+            residue_index: atom.residue_id,
+            chain: 'A',
+            element: atom.element,
+            position: atom.coords,
+        }
+    }
+
+    /// Number of atoms
+    pub fn len(&self) -> usize {
+        self.atoms.len()
+    }
+
+    /// Whether the context has no atoms
+    pub fn is_empty(&self) -> bool {
+        self.atoms.is_empty()
+    }
+
+    /// Per-atom properties in atom order
+    pub fn atoms(&self) -> &[SelectionAtom] {
+        &self.atoms
+    }
+
+    /// Replace positions from a Float4-stride buffer (for `within`)
+    pub fn update_positions(&mut self, positions: &[f32]) {
+        for (atom, p) in self.atoms.iter_mut().zip(positions.chunks_exact(4)) {
+            atom.position = [p[0], p[1], p[2]];
+        }
+    }
+
+    /// Parse and evaluate `expression`
+    pub fn select(&self, expression: &str) -> Result<Vec<u32>> {
+        Ok(Selection::parse(expression)?.evaluate(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    All,
+    None,
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Within(f32, Box<Expr>),
+    Name(Vec<String>),
+    ResName(Vec<String>),
+    Chain(Vec<char>),
+    Element(Vec<u8>),
+    ResId(Vec<(i64, i64)>),
+    ResIndex(Vec<(i64, i64)>),
+    Index(Vec<(i64, i64)>),
+    Protein,
+    Backbone,
+    Water,
+    Hydrogen,
+}
+
+/// A parsed selection expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    source: String,
+    expr: Expr,
+}
+
+impl Selection {
+    /// Parse `source`, reporting syntax errors as [`PrismIoError::FormatError`]
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source);
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(parse_error(source, &format!("unexpected '{}'", token)));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// Expression as written
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Indices of the matching atoms, in increasing order
+    pub fn evaluate(&self, context: &SelectionContext) -> Vec<u32> {
+        mask(&self.expr, context)
+            .iter()
+            .enumerate()
+            .filter(|(_, &m)| m)
+            .map(|(i, _)| i as u32)
+            .collect()
+    }
+}
+
+impl FromStr for Selection {
+    type Err = PrismIoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+fn parse_error(source: &str, message: &str) -> PrismIoError {
+    PrismIoError::FormatError(format!("Invalid selection '{}': {}", source, message))
+}
+
+fn tokenize(source: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in source.chars() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&self, word: &str) -> bool {
+        self.peek().is_some_and(|t| t.eq_ignore_ascii_case(word))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            self.position += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.keyword("and") {
+            self.position += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.keyword("not") {
+            self.position += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.keyword("within") {
+            self.position += 1;
+            let distance = self
+                .next()
+                .and_then(|t| t.parse::<f32>().ok())
+                .filter(|d| *d >= 0.0)
+                .ok_or_else(|| self.error("'within' needs a distance"))?;
+            if !self.keyword("of") {
+                return Err(self.error("expected 'of' after the 'within' distance"));
+            }
+            self.position += 1;
+            return Ok(Expr::Within(distance, Box::new(self.unary()?)));
+        }
+        self.term()
+    }
+
+    /// Value tokens up to the next keyword or parenthesis
+    fn values(&mut self, keyword: &str) -> Result<Vec<String>> {
+        let mut values = Vec::new();
+        while let Some(token) = self.peek() {
+            if token == "(" || token == ")" || is_keyword(token) {
+                break;
+            }
+            values.push(token.to_string());
+            self.position += 1;
+        }
+        if values.is_empty() {
+            return Err(self.error(&format!("'{}' needs at least one value", keyword)));
+        }
+        Ok(values)
+    }
+
+    fn ranges(&mut self, keyword: &str) -> Result<Vec<(i64, i64)>> {
+        let mut ranges = Vec::new();
+        while let Some(token) = self.peek() {
+            if token == "(" || token == ")" || is_keyword(token) {
+                break;
+            }
+            let token = token.to_string();
+            self.position += 1;
+            if token.eq_ignore_ascii_case("to") {
+                let upper = self.next().and_then(|t| t.parse::<i64>().ok());
+                match (ranges.last_mut(), upper) {
+                    (Some((_, hi)), Some(upper)) => *hi = upper,
+                    _ => return Err(self.error(&format!("bad 'to' range in '{}'", keyword))),
+                }
+                continue;
+            }
+            let range = parse_range(&token)
+                .ok_or_else(|| self.error(&format!("'{}' is not a number or range", token)))?;
+            ranges.push(range);
+        }
+        if ranges.is_empty() {
+            return Err(self.error(&format!("'{}' needs at least one value", keyword)));
+        }
+        Ok(ranges)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let token = self.next().ok_or_else(|| self.error("unexpected end"))?;
+        if token == "(" {
+            let expr = self.or()?;
+            if self.next().as_deref() != Some(")") {
+                return Err(self.error("missing ')'"));
+            }
+            return Ok(expr);
+        }
+        let word = token.to_ascii_lowercase();
+        Ok(match word.as_str() {
+            "all" => Expr::All,
+            "none" => Expr::None,
+            "protein" => Expr::Protein,
+            "backbone" => Expr::Backbone,
+            "water" => Expr::Water,
+            "hydrogen" => Expr::Hydrogen,
+            "heavy" => Expr::Not(Box::new(Expr::Hydrogen)),
+            "name" => Expr::Name(self.values(&word)?),
+            "resname" => Expr::ResName(self.values(&word)?),
+            "chain" => {
+                let values = self.values(&word)?;
+                let mut chains = Vec::new();
+                for value in values {
+                    let mut chars = value.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => chains.push(c),
+                        _ => {
+                            return Err(
+                                self.error(&format!("chain '{}' is not one character", value))
+                            )
+                        }
+                    }
+                }
+                Expr::Chain(chains)
+            }
+            "element" => {
+                let values = self.values(&word)?;
+                let mut elements = Vec::new();
+                for value in values {
+                    let number = value.parse::<u8>().ok().or_else(|| {
+                        ELEMENTS
+                            .iter()
+                            .find(|(symbol, _)| symbol.eq_ignore_ascii_case(&value))
+                            .map(|&(_, z)| z)
+                    });
+                    match number {
+                        Some(z) => elements.push(z),
+                        None => return Err(self.error(&format!("unknown element '{}'", value))),
+                    }
+                }
+                Expr::Element(elements)
+            }
+            "resid" => Expr::ResId(self.ranges(&word)?),
+            "resindex" => Expr::ResIndex(self.ranges(&word)?),
+            "index" => Expr::Index(self.ranges(&word)?),
+            _ => return Err(self.error(&format!("unexpected '{}'", token))),
+        })
+    }
+
+    fn error(&self, message: &str) -> PrismIoError {
+        parse_error(&self.tokens.join(" "), message)
+    }
+}
+
+fn is_keyword(token: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(token))
+}
+
+fn parse_range(token: &str) -> Option<(i64, i64)> {
+    if let Ok(value) = token.parse::<i64>() {
+        return Some((value, value));
+    }
+    let (lo, hi) = token.split_once(':').or_else(|| {
+        token[1..]
+            .split_once('-')
+            .map(|(a, b)| (&token[..a.len() + 1], b))
+    })?;
+    Some((lo.parse().ok()?, hi.parse().ok()?))
+}
+
+/// Glob match with `*` (any run) and `?` (any one character)
+fn glob(pattern: &str, text: &str) -> bool {
+    let (p, t): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi].eq_ignore_ascii_case(&t[ti])) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star, matched)) = backtrack {
+            pi = star + 1;
+            ti = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+fn in_ranges(ranges: &[(i64, i64)], value: i64) -> bool {
+    ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&value))
+}
+
+fn mask(expr: &Expr, context: &SelectionContext) -> Vec<bool> {
+    let atoms = &context.atoms;
+    let each = |f: &dyn Fn(usize, &SelectionAtom) -> bool| -> Vec<bool> {
+        atoms.iter().enumerate().map(|(i, a)| f(i, a)).collect()
+    };
+    let matches_any =
+        |patterns: &[String], text: &str| patterns.iter().any(|p| glob(p, text.trim()));
+    let is_protein = |a: &SelectionAtom| {
+        PROTEIN_RESIDUES
+            .iter()
+            .any(|r| r.eq_ignore_ascii_case(a.residue_name.trim()))
+    };
+    match expr {
+        Expr::All => vec![true; atoms.len()],
+        Expr::None => vec![false; atoms.len()],
+        Expr::Not(inner) => mask(inner, context).into_iter().map(|m| !m).collect(),
+        Expr::And(a, b) => mask(a, context)
+            .into_iter()
+            .zip(mask(b, context))
+            .map(|(a, b)| a && b)
+            .collect(),
+        Expr::Or(a, b) => mask(a, context)
+            .into_iter()
+            .zip(mask(b, context))
+            .map(|(a, b)| a || b)
+            .collect(),
+        Expr::Within(distance, inner) => {
+            let targets: Vec<[f32; 3]> = mask(inner, context)
+                .into_iter()
+                .zip(atoms)
+                .filter(|(m, _)| *m)
+                .map(|(_, a)| a.position)
+                .collect();
+            let cutoff2 = distance * distance;
+            each(&|_, a| {
+                targets.iter().any(|t| {
+                    let d = [0, 1, 2].map(|k| a.position[k] - t[k]);
+                    d[0] * d[0] + d[1] * d[1] + d[2] * d[2] <= cutoff2
+                })
+            })
+        }
+        Expr::Name(patterns) => each(&|_, a| matches_any(patterns, &a.name)),
+        Expr::ResName(patterns) => each(&|_, a| matches_any(patterns, &a.residue_name)),
+        Expr::Chain(chains) => each(&|_, a| chains.contains(&a.chain)),
+        Expr::Element(elements) => each(&|_, a| elements.contains(&a.element)),
+        Expr::ResId(ranges) => each(&|_, a| in_ranges(ranges, a.residue_number)),
+        Expr::ResIndex(ranges) => each(&|_, a| in_ranges(ranges, a.residue_index as i64)),
+        Expr::Index(ranges) => each(&|i, _| in_ranges(ranges, i as i64)),
+        Expr::Protein => each(&|_, a| is_protein(a)),
+        Expr::Backbone => each(&|_, a| is_protein(a) && BACKBONE_NAMES.contains(&a.name.trim())),
+        Expr::Water => each(&|_, a| {
+            WATER_RESIDUES
+                .iter()
+                .any(|r| r.eq_ignore_ascii_case(a.residue_name.trim()))
+        }),
+        Expr::Hydrogen => each(&|_, a| a.element == 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two chains: A = ALA 10, GLY 11; B = HOH 1, LIG 2
+    fn context() -> SelectionContext {
+        let atom = |name: &str,
+                    residue_name: &str,
+                    residue_number: i64,
+                    residue_index: u16,
+                    chain: char,
+                    element: u8,
+                    x: f32| SelectionAtom {
+            name: name.to_string(),
+            residue_name: residue_name.to_string(),
+            residue_number,
+            residue_index,
+            chain,
+            element,
+            position: [x, 0.0, 0.0],
+        };
+        SelectionContext::new(vec![
+            atom("N", "ALA", 10, 0, 'A', 7, 0.0),
+            atom("CA", "ALA", 10, 0, 'A', 6, 1.5),
+            atom("CB", "ALA", 10, 0, 'A', 6, 2.5),
+            atom("HA", "ALA", 10, 0, 'A', 1, 1.5),
+            atom("N", "GLY", 11, 1, 'A', 7, 3.8),
+            atom("CA", "GLY", 11, 1, 'A', 6, 5.0),
+            atom("O", "HOH", 1, 2, 'B', 8, 20.0),
+            atom("C1", "LIG", 2, 3, 'B', 6, 8.0),
+        ])
+    }
+
+    fn select(expression: &str) -> Vec<u32> {
+        context().select(expression).unwrap()
+    }
+
+    #[test]
+    fn test_keywords_and_boolean_operators() {
+        assert_eq!(select("chain A and resid 10-50 and name CA"), vec![1, 5]);
+        assert_eq!(select("name CA or name CB"), vec![1, 2, 5]);
+        assert_eq!(select("protein and not hydrogen and not backbone"), vec![2]);
+        assert_eq!(select("backbone"), vec![0, 1, 4, 5]);
+        assert_eq!(select("water or resname L*"), vec![6, 7]);
+        assert_eq!(select("element H"), vec![3]);
+        assert_eq!(select("heavy and chain B"), vec![6, 7]);
+        assert_eq!(select("resid 10 to 11 and name N"), vec![0, 4]);
+        assert_eq!(select("index 0:2 6"), vec![0, 1, 2, 6]);
+        assert_eq!(select("resindex 1"), vec![4, 5]);
+        assert_eq!(select("NOT (name N or name CA) AND chain A"), vec![2, 3]);
+        assert_eq!(select("name ?A"), vec![1, 3, 5]);
+        assert!(select("none").is_empty());
+        assert_eq!(select("all").len(), 8);
+    }
+
+    #[test]
+    fn test_within_uses_positions() {
+        let mut context = context();
+        assert_eq!(
+            context.select("within 3.5 of resname LIG").unwrap(),
+            vec![5, 7]
+        );
+        assert_eq!(
+            context
+                .select("within 3.5 of resname LIG and not resname LIG")
+                .unwrap(),
+            vec![5]
+        );
+        let mut positions = vec![0.0; 32];
+        positions[7 * 4] = 20.0;
+        positions[6 * 4] = 21.0;
+        context.update_positions(&positions);
+        assert_eq!(
+            context.select("within 1.5 of resname LIG").unwrap(),
+            vec![6, 7]
+        );
+    }
+
+    #[test]
+    fn test_errors_and_contexts() {
+        for bad in [
+            "name",
+            "resid x",
+            "chain AB",
+            "element Xx",
+            "(name CA",
+            "name CA)",
+            "within of name CA",
+            "frobnicate",
+        ] {
+            assert!(Selection::parse(bad).is_err(), "{}", bad);
+        }
+        let selection: Selection = "name CA".parse().unwrap();
+        assert_eq!(selection.source(), "name CA");
+
+        let atoms: Vec<Atom> = [7, 6, 6]
+            .iter()
+            .map(|&element| Atom {
+                coords: [0.0; 3],
+                element,
+                residue_id: 0,
+                atom_type: 0,
+                charge: 0.0,
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        let topology = Topology {
+            atoms,
+            atom_names: vec!["N".into(), "CA".into(), "C".into()],
+            residue_names: vec!["SER".into()],
+            ..Default::default()
+        };
+        let context = SelectionContext::from_topology(&topology);
+        assert_eq!(context.select("resid 1 and name CA").unwrap(), vec![1]);
+        assert_eq!(
+            SelectionContext::from_atoms(&topology.atoms)
+                .select("element C")
+                .unwrap(),
+            vec![1, 2]
+        );
+
+        let pdb = PdbStructure::from_atoms(&topology.atoms);
+        assert_eq!(SelectionContext::from_pdb(&pdb).len(), 3);
+    }
+}
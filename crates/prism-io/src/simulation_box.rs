@@ -0,0 +1,317 @@
+//! # Periodic Simulation Box
+//!
+//! Orthorhombic or triclinic unit cell shared by topology readers,
+//! trajectory writers and the force evaluation. Box vectors follow the
+//! GROMACS lower-triangular convention
+//!
+//! ```text
+//! a = (ax,  0,  0)
+//! b = (bx, by,  0)
+//! c = (cx, cy, cz)
+//! ```
+//!
+//! with `ax, by, cz > 0`, which every cell can be rotated into. The minimum
+//! image is found by removing whole box vectors from a separation, `c`
+//! first, then `b`, then `a`; for a reduced cell (`|bx| <= ax/2`,
+//! `|cx| <= ax/2`, `|cy| <= by/2`) this is exact for separations shorter
+//! than half the smallest [perpendicular width](SimulationBox::perpendicular_widths),
+//! which bounds the usable cutoff. Units: Å, degrees.
+
+use crate::{PrismIoError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Periodic cell; rows of `vectors` are the box vectors `a`, `b`, `c` (Å)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SimulationBox {
+    vectors: [[f32; 3]; 3],
+}
+
+impl SimulationBox {
+    /// Rectangular box with edge lengths `lengths`
+    pub fn orthorhombic(lengths: [f32; 3]) -> Self {
+        Self {
+            vectors: [
+                [lengths[0], 0.0, 0.0],
+                [0.0, lengths[1], 0.0],
+                [0.0, 0.0, lengths[2]],
+            ],
+        }
+    }
+
+    /// Box from lower-triangular vectors, reduced so that off-diagonal
+    /// components are at most half the corresponding diagonal
+    pub fn triclinic(vectors: [[f32; 3]; 3]) -> Result<Self> {
+        let [a, b, c] = vectors;
+        if a[1] != 0.0 || a[2] != 0.0 || b[2] != 0.0 {
+            return Err(PrismIoError::ValidationError(format!(
+                "Box vectors must be lower triangular, got {:?}",
+                vectors
+            )));
+        }
+        if !(a[0] > 0.0 && b[1] > 0.0 && c[2] > 0.0)
+            || vectors.iter().flatten().any(|v| !v.is_finite())
+        {
+            return Err(PrismIoError::ValidationError(format!(
+                "Box vectors must have positive finite diagonal, got {:?}",
+                vectors
+            )));
+        }
+        Ok(Self { vectors }.reduced())
+    }
+
+    /// Box from edge lengths `a, b, c` (Å) and angles `α, β, γ` (degrees),
+    /// as in PDB CRYST1 records
+    pub fn from_parameters(parameters: [f32; 6]) -> Result<Self> {
+        let [a, b, c, alpha, beta, gamma] = parameters.map(|p| p as f64);
+        if [alpha, beta, gamma]
+            .iter()
+            .all(|&angle| (angle - 90.0).abs() < 1e-4)
+        {
+            return Self::triclinic(Self::orthorhombic([a as f32, b as f32, c as f32]).vectors);
+        }
+        let (cos_a, cos_b) = (alpha.to_radians().cos(), beta.to_radians().cos());
+        let (cos_g, sin_g) = (gamma.to_radians().cos(), gamma.to_radians().sin());
+        let cx = c * cos_b;
+        let cy = c * (cos_a - cos_b * cos_g) / sin_g;
+        let cz2 = c * c - cx * cx - cy * cy;
+        if cz2 <= 0.0 || sin_g.abs() < 1e-9 {
+            return Err(PrismIoError::ValidationError(format!(
+                "Degenerate cell parameters {:?}",
+                parameters
+            )));
+        }
+        Self::triclinic([
+            [a as f32, 0.0, 0.0],
+            [(b * cos_g) as f32, (b * sin_g) as f32, 0.0],
+            [cx as f32, cy as f32, cz2.sqrt() as f32],
+        ])
+    }
+
+    /// Box vectors `a`, `b`, `c` as rows (Å)
+    pub fn vectors(&self) -> [[f32; 3]; 3] {
+        self.vectors
+    }
+
+    /// Edge lengths `|a|, |b|, |c|` (Å)
+    pub fn lengths(&self) -> [f32; 3] {
+        self.vectors
+            .map(|v| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt())
+    }
+
+    /// `[a, b, c, α, β, γ]` (Å, degrees)
+    pub fn parameters(&self) -> [f32; 6] {
+        let [a, b, c] = self.vectors.map(|v| v.map(|x| x as f64));
+        let norm = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        let angle = |u: [f64; 3], v: [f64; 3]| {
+            let cos = (u[0] * v[0] + u[1] * v[1] + u[2] * v[2]) / (norm(u) * norm(v));
+            cos.clamp(-1.0, 1.0).acos().to_degrees() as f32
+        };
+        let [la, lb, lc] = self.lengths();
+        [la, lb, lc, angle(b, c), angle(a, c), angle(a, b)]
+    }
+
+    /// Whether all box vectors are along the axes
+    pub fn is_orthorhombic(&self) -> bool {
+        let [_, b, c] = self.vectors;
+        b[0] == 0.0 && c[0] == 0.0 && c[1] == 0.0
+    }
+
+    /// Cell volume (Å³)
+    pub fn volume(&self) -> f64 {
+        self.vectors[0][0] as f64 * self.vectors[1][1] as f64 * self.vectors[2][2] as f64
+    }
+
+    /// Distances between opposite faces (Å): the cell height along the
+    /// normal of the `bc`, `ca` and `ab` planes
+    pub fn perpendicular_widths(&self) -> [f32; 3] {
+        let [a, b, c] = self.vectors.map(|v| v.map(|x| x as f64));
+        let cross = |u: [f64; 3], v: [f64; 3]| {
+            [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ]
+        };
+        let norm = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        let volume = self.volume();
+        [cross(b, c), cross(c, a), cross(a, b)].map(|n| (volume / norm(n)) as f32)
+    }
+
+    /// Largest cutoff for which [`Self::minimum_image`] is exact (Å)
+    pub fn max_cutoff(&self) -> f32 {
+        0.5 * self
+            .perpendicular_widths()
+            .into_iter()
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Shortest periodic image of the separation `d` (Å)
+    #[inline]
+    pub fn minimum_image(&self, mut d: [f32; 3]) -> [f32; 3] {
+        for k in (0..3).rev() {
+            let v = &self.vectors[k];
+            let shift = (d[k] / v[k]).round();
+            if shift != 0.0 {
+                for (dd, &vv) in d.iter_mut().zip(v) {
+                    *dd -= shift * vv;
+                }
+            }
+        }
+        d
+    }
+
+    /// Fractional coordinates of `p` along `a`, `b`, `c`
+    #[inline]
+    pub fn fractional(&self, p: [f32; 3]) -> [f32; 3] {
+        let [a, b, c] = &self.vectors;
+        let s2 = p[2] / c[2];
+        let s1 = (p[1] - s2 * c[1]) / b[1];
+        let s0 = (p[0] - s2 * c[0] - s1 * b[0]) / a[0];
+        [s0, s1, s2]
+    }
+
+    /// Image of `p` inside the primary cell (fractional coordinates in `[0, 1)`)
+    pub fn wrap(&self, p: [f32; 3]) -> [f32; 3] {
+        let s = self.fractional(p);
+        let mut wrapped = p;
+        for (k, v) in self.vectors.iter().enumerate() {
+            let shift = s[k].floor();
+            for (w, &vv) in wrapped.iter_mut().zip(v) {
+                *w -= shift * vv;
+            }
+        }
+        wrapped
+    }
+
+    /// Reciprocal box vectors as rows, `a*·a = 1` etc. (1/Å)
+    pub fn reciprocal(&self) -> [[f64; 3]; 3] {
+        let [a, b, c] = self.vectors.map(|v| v.map(|x| x as f64));
+        // Inverse of the lower-triangular matrix with rows a, b, c, transposed
+        let (ax, bx, by, cx, cy, cz) = (a[0], b[0], b[1], c[0], c[1], c[2]);
+        [
+            [
+                1.0 / ax,
+                -bx / (ax * by),
+                (bx * cy - by * cx) / (ax * by * cz),
+            ],
+            [0.0, 1.0 / by, -cy / (by * cz)],
+            [0.0, 0.0, 1.0 / cz],
+        ]
+    }
+
+    /// Same cell with `c` and then `b` shifted by lattice vectors so that
+    /// the off-diagonal components are at most half a diagonal
+    fn reduced(mut self) -> Self {
+        // Whole lattice shifts only beyond half a diagonal, so boxes already
+        // on the boundary (e.g. dodecahedra) keep their conventional shape
+        let shift = |x: f32, diagonal: f32| {
+            let ratio = x / diagonal;
+            if ratio.abs() > 0.5 + 1e-6 {
+                ratio.round()
+            } else {
+                0.0
+            }
+        };
+        let [a, b, _] = self.vectors;
+        let n = shift(self.vectors[2][1], b[1]);
+        for (c, bk) in self.vectors[2].iter_mut().zip(b) {
+            *c -= n * bk;
+        }
+        self.vectors[2][0] -= shift(self.vectors[2][0], a[0]) * a[0];
+        self.vectors[1][0] -= shift(self.vectors[1][0], a[0]) * a[0];
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_image(cell: &SimulationBox, d: [f32; 3]) -> f32 {
+        let v = cell.vectors();
+        let mut best = f32::INFINITY;
+        for i in -2..=2 {
+            for j in -2..=2 {
+                for k in -2..=2 {
+                    let s = [i as f32, j as f32, k as f32];
+                    let image: [f32; 3] =
+                        [0, 1, 2].map(|c| d[c] + s[0] * v[0][c] + s[1] * v[1][c] + s[2] * v[2][c]);
+                    best = best.min(image.iter().map(|x| x * x).sum::<f32>().sqrt());
+                }
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn test_parameters_roundtrip_and_geometry() {
+        let cubic = SimulationBox::orthorhombic([30.0, 31.0, 32.0]);
+        assert!(cubic.is_orthorhombic());
+        assert_eq!(cubic.parameters(), [30.0, 31.0, 32.0, 90.0, 90.0, 90.0]);
+        assert!((cubic.volume() - 29760.0).abs() < 1e-6);
+        assert_eq!(cubic.perpendicular_widths(), [30.0, 31.0, 32.0]);
+
+        // Rhombic dodecahedron (xy-square) as written by GROMACS
+        let parameters = [40.0, 40.0, 40.0, 60.0, 60.0, 90.0];
+        let dodecahedron = SimulationBox::from_parameters(parameters).unwrap();
+        assert!(!dodecahedron.is_orthorhombic());
+        for (p, q) in dodecahedron.parameters().iter().zip(parameters) {
+            assert!((p - q).abs() < 1e-3, "{:?}", dodecahedron.parameters());
+        }
+        assert!((dodecahedron.volume() - 0.5f64.sqrt() * 64000.0).abs() < 1.0);
+        assert!((dodecahedron.max_cutoff() - 800f32.sqrt() / 2.0).abs() < 1e-3);
+
+        assert!(SimulationBox::from_parameters([10.0, 10.0, 10.0, 90.0, 90.0, 0.0]).is_err());
+        assert!(
+            SimulationBox::triclinic([[10.0, 1.0, 0.0], [0.0, 10.0, 0.0], [0.0, 0.0, 10.0]])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_minimum_image_and_wrap() {
+        let cells = [
+            SimulationBox::orthorhombic([20.0, 25.0, 30.0]),
+            SimulationBox::from_parameters([40.0, 40.0, 40.0, 60.0, 60.0, 90.0]).unwrap(),
+            SimulationBox::from_parameters([30.0, 32.0, 35.0, 80.0, 100.0, 70.0]).unwrap(),
+        ];
+        let mut state = 12345u32;
+        let mut random = || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+        };
+        for cell in &cells {
+            let cutoff = cell.max_cutoff();
+            for _ in 0..500 {
+                let d = [random() * 150.0, random() * 150.0, random() * 150.0];
+                let image = cell.minimum_image(d);
+                let length = image.iter().map(|x| x * x).sum::<f32>().sqrt();
+                let best = brute_force_image(cell, d);
+                if best < cutoff {
+                    assert!(
+                        (length - best).abs() < 1e-3,
+                        "{:?}: {} vs {}",
+                        cell,
+                        length,
+                        best
+                    );
+                }
+
+                let wrapped = cell.wrap(d);
+                assert!(cell
+                    .fractional(wrapped)
+                    .iter()
+                    .all(|s| (-1e-5..1.0 + 1e-5).contains(s)));
+                let back = cell.minimum_image([0, 1, 2].map(|k| wrapped[k] - d[k]));
+                assert!(back.iter().all(|x| x.abs() < 1e-2));
+            }
+            let r = cell.reciprocal();
+            for (i, v) in cell.vectors().iter().enumerate() {
+                for (j, w) in r.iter().enumerate() {
+                    let dot: f64 = (0..3).map(|k| v[k] as f64 * w[k]).sum();
+                    assert!((dot - if i == j { 1.0 } else { 0.0 }).abs() < 1e-6);
+                }
+            }
+        }
+    }
+}
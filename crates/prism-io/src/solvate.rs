@@ -0,0 +1,522 @@
+//! # Solvent Box Builder
+//!
+//! Solvates a structure in a rectangular TIP3P water box and adds Na+/Cl-
+//! ions, producing a periodic system for explicit-solvent (NPT) runs.
+//!
+//! ## Notes
+//! - The solute is centred in a box of its bounding box plus `padding` on
+//!   every side (or a cube of the largest edge)
+//! - Waters sit on a lattice that tiles the box exactly at TIP3P density, so
+//!   periodic images do not overlap; each water gets a deterministic
+//!   pseudo-random orientation from `seed`
+//! - Waters with any atom closer than `clash_distance` to the solute are removed
+//! - Ions replace waters at least `ion_distance` from the solute and from
+//!   each other: first enough counter-ions to neutralize the (rounded)
+//!   solute charge, then `salt_concentration` worth of Na+/Cl- pairs
+//! - Atom order is solute, ions, waters; ions use chain `I`, waters chain `W`
+
+use crate::holographic::HolographicBinaryFormat;
+use crate::pdb::{vdw_radius, PdbAtomRecord, PdbStructure};
+use crate::sovereign_types::{Atom, Bond};
+use crate::{PrismIoError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// TIP3P O-H bond length (Å)
+pub const TIP3P_OH: f32 = 0.9572;
+/// TIP3P H-O-H angle (degrees)
+pub const TIP3P_HOH: f32 = 104.52;
+/// TIP3P oxygen charge (e); each hydrogen carries half its magnitude
+pub const TIP3P_CHARGE_O: f32 = -0.834;
+/// Water number density at 300 K (molecules/Å³)
+const WATER_DENSITY: f32 = 0.033_42;
+/// Molarity of pure water (mol/L)
+const WATER_MOLARITY: f32 = 55.5;
+
+/// Solvation settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolvateConfig {
+    /// Minimum solute-to-box-edge distance (Å)
+    pub padding: f32,
+    /// Use a cubic box with the largest edge
+    pub cubic: bool,
+    /// Waters with any atom closer than this to a solute atom are removed (Å)
+    pub clash_distance: f32,
+    /// Add counter-ions to neutralize the solute
+    pub neutralize: bool,
+    /// Additional NaCl concentration (mol/L)
+    pub salt_concentration: f32,
+    /// Minimum ion-solute and ion-ion distance (Å)
+    pub ion_distance: f32,
+    /// Seed for water orientations and ion placement
+    pub seed: u64,
+}
+
+impl Default for SolvateConfig {
+    fn default() -> Self {
+        Self {
+            padding: 10.0,
+            cubic: false,
+            clash_distance: 2.4,
+            neutralize: true,
+            salt_concentration: 0.0,
+            ion_distance: 5.0,
+            seed: 12345,
+        }
+    }
+}
+
+/// Solvated periodic system
+#[derive(Debug, Clone)]
+pub struct SolvatedStructure {
+    /// Solute, ions and waters with PDB metadata and CRYST1 box
+    pub structure: PdbStructure,
+    /// Solute CONECT bonds plus water O-H bonds
+    pub bonds: Vec<Bond>,
+    /// Rectangular box edge lengths (Å)
+    pub box_lengths: [f32; 3],
+    /// Number of water molecules
+    pub num_waters: usize,
+    /// Number of Na+ ions
+    pub num_cations: usize,
+    /// Number of Cl- ions
+    pub num_anions: usize,
+}
+
+impl SolvatedStructure {
+    /// Write as PDB (with CRYST1)
+    pub fn write_pdb<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.structure.write(path)
+    }
+
+    /// Write as a sovereign .ptb file, hashed over the PDB rendering
+    pub fn write_ptb<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let hash = blake3::hash(self.structure.to_pdb_string().as_bytes());
+        HolographicBinaryFormat::new()
+            .with_atoms(self.structure.atoms.clone())
+            .with_bonds(self.bonds.clone())
+            .with_source_hash(*hash.as_bytes())
+            .write_to_file(path)
+    }
+}
+
+/// SplitMix64 step, used for reproducible orientations and ion choice
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn uniform(state: &mut u64) -> f32 {
+    (splitmix64(state) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Uniform random rotation matrix (Shoemake quaternion)
+fn random_rotation(state: &mut u64) -> [[f32; 3]; 3] {
+    let (u1, u2, u3) = (uniform(state), uniform(state), uniform(state));
+    let tau = std::f32::consts::TAU;
+    let (a, b) = ((1.0 - u1).sqrt(), u1.sqrt());
+    let [w, x, y, z] = [
+        a * (tau * u2).sin(),
+        a * (tau * u2).cos(),
+        b * (tau * u3).sin(),
+        b * (tau * u3).cos(),
+    ];
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ]
+}
+
+/// Uniform-grid spatial hash of solute atoms
+struct CellGrid {
+    cell: f32,
+    cells: HashMap<[i32; 3], Vec<[f32; 3]>>,
+}
+
+impl CellGrid {
+    fn new(cell: f32) -> Self {
+        Self {
+            cell: cell.max(0.5),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn key(&self, p: [f32; 3]) -> [i32; 3] {
+        p.map(|x| (x / self.cell).floor() as i32)
+    }
+
+    fn insert(&mut self, p: [f32; 3]) {
+        self.cells.entry(self.key(p)).or_default().push(p);
+    }
+
+    /// Whether any stored point lies within `radius` of `p`
+    fn any_within(&self, p: [f32; 3], radius: f32) -> bool {
+        let reach = (radius / self.cell).ceil() as i32;
+        let k = self.key(p);
+        let r2 = radius * radius;
+        for dx in -reach..=reach {
+            for dy in -reach..=reach {
+                for dz in -reach..=reach {
+                    let Some(points) = self.cells.get(&[k[0] + dx, k[1] + dy, k[2] + dz]) else {
+                        continue;
+                    };
+                    if points
+                        .iter()
+                        .any(|q| (0..3).map(|d| (q[d] - p[d]).powi(2)).sum::<f32>() < r2)
+                    {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+fn het_record(name: &str, residue_name: &str, chain_id: char, residue_seq: i32) -> PdbAtomRecord {
+    PdbAtomRecord {
+        serial: 0,
+        name: name.to_string(),
+        residue_name: residue_name.to_string(),
+        chain_id,
+        residue_seq,
+        insertion_code: ' ',
+        occupancy: 1.0,
+        b_factor: 0.0,
+        hetatm: true,
+    }
+}
+
+fn solvent_atom(coords: [f32; 3], element: u8, residue_id: u16, charge: f32) -> Atom {
+    Atom {
+        coords,
+        element,
+        residue_id,
+        atom_type: 0,
+        charge,
+        radius: vdw_radius(element),
+        _reserved: [0; 4],
+    }
+}
+
+/// Solvate `solute` in a TIP3P box with counter-ions and optional salt
+pub fn solvate(solute: &PdbStructure, config: &SolvateConfig) -> Result<SolvatedStructure> {
+    if solute.atoms.is_empty() {
+        return Err(PrismIoError::ValidationError(
+            "Cannot solvate an empty structure".into(),
+        ));
+    }
+    if solute.records.len() != solute.atoms.len() {
+        return Err(PrismIoError::ValidationError(format!(
+            "Structure has {} atoms but {} records",
+            solute.atoms.len(),
+            solute.records.len()
+        )));
+    }
+
+    // Box from the padded bounding box, solute centred
+    let mut lo = [f32::INFINITY; 3];
+    let mut hi = [f32::NEG_INFINITY; 3];
+    for atom in &solute.atoms {
+        for d in 0..3 {
+            lo[d] = lo[d].min(atom.coords[d]);
+            hi[d] = hi[d].max(atom.coords[d]);
+        }
+    }
+    let mut box_lengths = [0, 1, 2].map(|d| hi[d] - lo[d] + 2.0 * config.padding);
+    if config.cubic {
+        let edge = box_lengths.iter().copied().fold(0.0, f32::max);
+        box_lengths = [edge; 3];
+    }
+    if box_lengths.iter().any(|&l| !l.is_finite() || l <= 0.0) {
+        return Err(PrismIoError::ValidationError(format!(
+            "Invalid box {:?}",
+            box_lengths
+        )));
+    }
+    let shift = [0, 1, 2].map(|d| 0.5 * box_lengths[d] - 0.5 * (lo[d] + hi[d]));
+
+    let mut atoms: Vec<Atom> = solute.atoms.clone();
+    let mut grid = CellGrid::new(config.clash_distance);
+    for atom in &mut atoms {
+        for (x, dx) in atom.coords.iter_mut().zip(shift) {
+            *x += dx;
+        }
+        grid.insert(atom.coords);
+    }
+
+    // Water lattice tiling the box at liquid density
+    let spacing = WATER_DENSITY.recip().cbrt();
+    let counts = box_lengths.map(|l| ((l / spacing).round() as usize).max(1));
+    let step = [0, 1, 2].map(|d| box_lengths[d] / counts[d] as f32);
+    let angle = TIP3P_HOH.to_radians();
+    let local = [
+        [TIP3P_OH, 0.0, 0.0],
+        [TIP3P_OH * angle.cos(), TIP3P_OH * angle.sin(), 0.0],
+    ];
+    let mut rng = config.seed;
+    let mut waters: Vec<[[f32; 3]; 3]> = Vec::new();
+    for i in 0..counts[0] {
+        for j in 0..counts[1] {
+            for k in 0..counts[2] {
+                let o = [
+                    (i as f32 + 0.5) * step[0],
+                    (j as f32 + 0.5) * step[1],
+                    (k as f32 + 0.5) * step[2],
+                ];
+                let rot = random_rotation(&mut rng);
+                let h = local.map(|v| {
+                    [0, 1, 2].map(|r| o[r] + (0..3).map(|c| rot[r][c] * v[c]).sum::<f32>())
+                });
+                let water = [o, h[0], h[1]];
+                if !water
+                    .iter()
+                    .any(|&p| grid.any_within(p, config.clash_distance))
+                {
+                    waters.push(water);
+                }
+            }
+        }
+    }
+
+    // Counter-ions and salt replace waters far from the solute
+    let net_charge: f32 = solute.atoms.iter().map(|a| a.charge).sum();
+    let rounded = net_charge.round();
+    if (net_charge - rounded).abs() > 0.01 {
+        tracing::warn!(
+            "Solute charge {:.3} is not integral; neutralizing {:.0}",
+            net_charge,
+            rounded
+        );
+    }
+    let (mut num_cations, mut num_anions) = if config.neutralize {
+        ((-rounded).max(0.0) as usize, rounded.max(0.0) as usize)
+    } else {
+        (0, 0)
+    };
+    let pairs = (config.salt_concentration.max(0.0) * waters.len() as f32 / WATER_MOLARITY).round()
+        as usize;
+    num_cations += pairs;
+    num_anions += pairs;
+
+    let mut order: Vec<usize> = (0..waters.len()).collect();
+    for i in (1..order.len()).rev() {
+        let j = (splitmix64(&mut rng) % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    let mut ion_grid = CellGrid::new(config.ion_distance);
+    let mut ion_sites = Vec::new();
+    for &w in &order {
+        if ion_sites.len() == num_cations + num_anions {
+            break;
+        }
+        let o = waters[w][0];
+        if grid.any_within(o, config.ion_distance) || ion_grid.any_within(o, config.ion_distance) {
+            continue;
+        }
+        ion_grid.insert(o);
+        ion_sites.push(w);
+    }
+    if ion_sites.len() < num_cations + num_anions {
+        return Err(PrismIoError::ValidationError(format!(
+            "Only {} water sites are far enough from the solute for {} ions; increase the padding",
+            ion_sites.len(),
+            num_cations + num_anions
+        )));
+    }
+
+    let mut records = solute.records.clone();
+    let mut bonds: Vec<Bond> = solute
+        .conect
+        .iter()
+        .map(|&(i, j)| Bond {
+            atom1: i,
+            atom2: j,
+            order: 1,
+            bond_type: 0,
+            _reserved: [0],
+        })
+        .collect();
+    // Residue indices continue after the solute; PDB numbering restarts per chain
+    let mut residue_id = solute.atoms.iter().map(|a| a.residue_id).max().unwrap_or(0);
+    let mut next_residue = || {
+        residue_id = residue_id.wrapping_add(1);
+        residue_id
+    };
+
+    for (n, &w) in ion_sites.iter().enumerate() {
+        let (element, name, charge) = if n < num_cations {
+            (11, "NA", 1.0)
+        } else {
+            (17, "CL", -1.0)
+        };
+        atoms.push(solvent_atom(waters[w][0], element, next_residue(), charge));
+        records.push(het_record(name, name, 'I', n as i32 + 1));
+    }
+
+    let replaced: std::collections::HashSet<usize> = ion_sites.iter().copied().collect();
+    let mut num_waters = 0i32;
+    for (_, water) in waters
+        .iter()
+        .enumerate()
+        .filter(|(w, _)| !replaced.contains(w))
+    {
+        let id = next_residue();
+        num_waters += 1;
+        let o = atoms.len() as u32;
+        atoms.push(solvent_atom(water[0], 8, id, TIP3P_CHARGE_O));
+        atoms.push(solvent_atom(water[1], 1, id, -0.5 * TIP3P_CHARGE_O));
+        atoms.push(solvent_atom(water[2], 1, id, -0.5 * TIP3P_CHARGE_O));
+        for name in ["O", "H1", "H2"] {
+            records.push(het_record(name, "HOH", 'W', num_waters));
+        }
+        bonds.push(Bond {
+            atom1: o,
+            atom2: o + 1,
+            order: 1,
+            bond_type: 0,
+            _reserved: [0],
+        });
+        bonds.push(Bond {
+            atom1: o,
+            atom2: o + 2,
+            order: 1,
+            bond_type: 0,
+            _reserved: [0],
+        });
+    }
+    let num_waters = num_waters as usize;
+
+    tracing::info!(
+        "Solvated in {:.1} x {:.1} x {:.1} Å box: {} waters, {} Na+, {} Cl-",
+        box_lengths[0],
+        box_lengths[1],
+        box_lengths[2],
+        num_waters,
+        num_cations,
+        num_anions
+    );
+
+    let structure = PdbStructure {
+        atoms,
+        records,
+        conect: solute.conect.clone(),
+        cryst1: Some([
+            box_lengths[0],
+            box_lengths[1],
+            box_lengths[2],
+            90.0,
+            90.0,
+            90.0,
+        ]),
+        altlocs: Vec::new(),
+        missing_residues: Vec::new(),
+    };
+    Ok(SolvatedStructure {
+        structure,
+        bonds,
+        box_lengths,
+        num_waters,
+        num_cations,
+        num_anions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solute() -> PdbStructure {
+        let atoms: Vec<Atom> = (0..6)
+            .map(|i| {
+                solvent_atom(
+                    [i as f32 * 1.5, 0.3 * i as f32, 0.0],
+                    6,
+                    (i / 3) as u16,
+                    if i == 0 { -2.0 } else { 0.0 },
+                )
+            })
+            .collect();
+        PdbStructure::from_atoms(&atoms)
+    }
+
+    #[test]
+    fn test_solvate_neutralizes_and_avoids_clashes() {
+        let config = SolvateConfig {
+            padding: 8.0,
+            ..Default::default()
+        };
+        let solvated = solvate(&solute(), &config).unwrap();
+        assert_eq!((solvated.num_cations, solvated.num_anions), (2, 0));
+
+        let atoms = &solvated.structure.atoms;
+        assert_eq!(atoms.len(), 6 + 2 + 3 * solvated.num_waters);
+        assert_eq!(solvated.bonds.len(), 2 * solvated.num_waters);
+        let total: f32 = atoms.iter().map(|a| a.charge).sum();
+        assert!(total.abs() < 1e-3, "net charge {}", total);
+
+        // Box volume minus the solute holds roughly liquid-density water
+        let volume: f32 = solvated.box_lengths.iter().product();
+        let density = solvated.num_waters as f32 / volume;
+        assert!(
+            (density - WATER_DENSITY).abs() < 0.1 * WATER_DENSITY,
+            "{}",
+            density
+        );
+
+        for w in atoms[6..].iter() {
+            for s in &atoms[..6] {
+                let d2: f32 = (0..3).map(|d| (w.coords[d] - s.coords[d]).powi(2)).sum();
+                assert!(d2 >= config.clash_distance.powi(2));
+            }
+            assert!(
+                (0..3).all(|d| w.coords[d] > -1.0 && w.coords[d] < solvated.box_lengths[d] + 1.0)
+            );
+        }
+        assert!(solvated.structure.to_pdb_string().starts_with("CRYST1"));
+    }
+
+    #[test]
+    fn test_salt_pairs_and_water_geometry() {
+        let config = SolvateConfig {
+            padding: 12.0,
+            salt_concentration: 0.15,
+            neutralize: false,
+            ..Default::default()
+        };
+        let solvated = solvate(&solute(), &config).unwrap();
+        let expected = (0.15 * (solvated.num_waters + solvated.num_cations * 2) as f32
+            / WATER_MOLARITY)
+            .round() as usize;
+        assert_eq!(solvated.num_cations, solvated.num_anions);
+        assert!(solvated.num_cations.abs_diff(expected) <= 1);
+
+        let first_water = 6 + solvated.num_cations + solvated.num_anions;
+        let w = &solvated.structure.atoms[first_water..first_water + 3];
+        let d = |a: &Atom, b: &Atom| {
+            (0..3)
+                .map(|k| (a.coords[k] - b.coords[k]).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        };
+        assert!((d(&w[0], &w[1]) - TIP3P_OH).abs() < 1e-4);
+        assert!((d(&w[0], &w[2]) - TIP3P_OH).abs() < 1e-4);
+        assert_eq!(solvated.structure.records[first_water].residue_name, "HOH");
+    }
+}
@@ -0,0 +1,146 @@
+//! # Structure Files - PDB, mmCIF and PTB by Extension
+//!
+//! Front ends (CLI, language bindings) exchange structures through
+//! [`PdbStructure`], which keeps residue and chain metadata when the input
+//! has it; `.ptb` files carry bare atoms and get generated metadata.
+
+use crate::holographic::{HolographicBinaryFormat, PtbStructure};
+use crate::mmcif::{read_mmcif, MmcifStructure};
+use crate::pdb::{read_pdb, PdbStructure};
+use crate::sovereign_types::Bond;
+use crate::{PrismIoError, Result};
+use std::path::Path;
+
+/// Structure file format, chosen by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructureFormat {
+    /// `.pdb`, `.ent`
+    Pdb,
+    /// `.cif`, `.mmcif`
+    Mmcif,
+    /// `.ptb`
+    Ptb,
+}
+
+impl StructureFormat {
+    /// Format of `path` from its extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Ok(match extension.as_str() {
+            "pdb" | "ent" => Self::Pdb,
+            "cif" | "mmcif" => Self::Mmcif,
+            "ptb" => Self::Ptb,
+            _ => {
+                return Err(PrismIoError::FormatError(format!(
+                    "Unknown structure format for {} (expected .pdb, .cif or .ptb)",
+                    path.display()
+                )))
+            }
+        })
+    }
+}
+
+/// Read a structure file
+pub fn read_structure<P: AsRef<Path>>(path: P) -> Result<PdbStructure> {
+    let path = path.as_ref();
+    match StructureFormat::from_path(path)? {
+        StructureFormat::Pdb => read_pdb(path),
+        StructureFormat::Mmcif => Ok(read_mmcif(path)?.structure),
+        StructureFormat::Ptb => {
+            let mut ptb = PtbStructure::load(path)?;
+            Ok(PdbStructure::from_atoms(ptb.atoms()?))
+        }
+    }
+}
+
+/// Write `structure` in the format given by the extension of `path`
+pub fn write_structure<P: AsRef<Path>>(path: P, structure: &PdbStructure) -> Result<()> {
+    let path = path.as_ref();
+    match StructureFormat::from_path(path)? {
+        StructureFormat::Pdb => structure.write(path),
+        StructureFormat::Mmcif => {
+            let id = path.file_stem().and_then(|s| s.to_str()).unwrap_or("PRISM");
+            let mmcif = MmcifStructure {
+                id: id.to_string(),
+                structure: structure.clone(),
+                asym_ids: structure
+                    .records
+                    .iter()
+                    .map(|r| r.chain_id.to_string())
+                    .collect(),
+                entity_ids: vec!["1".to_string(); structure.atoms.len()],
+                ..Default::default()
+            };
+            mmcif.write_mmcif(path)
+        }
+        StructureFormat::Ptb => {
+            let bonds = structure
+                .conect
+                .iter()
+                .map(|&(atom1, atom2)| Bond {
+                    atom1,
+                    atom2,
+                    order: 1,
+                    bond_type: 0,
+                    _reserved: [0],
+                })
+                .collect();
+            HolographicBinaryFormat::new()
+                .with_atoms(structure.atoms.clone())
+                .with_bonds(bonds)
+                .write_to_file(path)
+        }
+    }
+}
+
+/// Contents of `path` as a sovereign buffer (PTB bytes or PDB text) for
+/// the MD engine: the file itself for PTB and PDB, PDB text for mmCIF
+pub fn sovereign_buffer<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    let path = path.as_ref();
+    match StructureFormat::from_path(path)? {
+        StructureFormat::Pdb | StructureFormat::Ptb => Ok(std::fs::read(path)?),
+        StructureFormat::Mmcif => Ok(read_mmcif(path)?.structure.to_pdb_string().into_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+";
+
+    #[test]
+    fn test_round_trip_through_every_format() {
+        let dir = std::env::temp_dir().join(format!("prism_structure_file_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pdb = dir.join("peptide.pdb");
+        std::fs::write(&pdb, PEPTIDE).unwrap();
+        let original = read_structure(&pdb).unwrap();
+
+        for name in ["peptide.cif", "peptide.ptb", "copy.pdb"] {
+            let path = dir.join(name);
+            write_structure(&path, &original).unwrap();
+            let copy = read_structure(&path).unwrap();
+            assert_eq!(copy.atoms.len(), 4, "{}", name);
+            for (a, b) in original.atoms.iter().zip(&copy.atoms) {
+                assert_eq!(a.element, b.element);
+                for k in 0..3 {
+                    assert!((a.coords[k] - b.coords[k]).abs() < 1e-3, "{}", name);
+                }
+            }
+        }
+        assert!(StructureFormat::from_path(Path::new("model.xyz")).is_err());
+        let buffer = sovereign_buffer(dir.join("peptide.cif")).unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains("ATOM"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
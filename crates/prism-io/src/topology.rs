@@ -0,0 +1,360 @@
+//! # Molecular Topology
+//!
+//! Force-field-agnostic topology representation shared by the external
+//! format importers (AMBER, CHARMM, GROMACS).
+//!
+//! ## Conventions
+//! - Lengths in Angstroms, energies in kcal/mol, angles in radians
+//! - All atom indices are 0-based
+//! - Harmonic terms use `E = k (x - x0)^2` (AMBER convention, no 1/2 factor)
+
+use crate::simulation_box::SimulationBox;
+use crate::sovereign_types::Atom;
+
+/// Lennard-Jones parameters for a single atom
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LjParams {
+    /// LJ diameter (Å)
+    pub sigma: f32,
+    /// LJ well depth (kcal/mol)
+    pub epsilon: f32,
+}
+
+impl LjParams {
+    /// Build from the Rmin/2 convention used by AMBER/CHARMM parameter files
+    pub fn from_rmin_half(rmin_half: f32, epsilon: f32) -> Self {
+        Self {
+            sigma: 2.0 * rmin_half / 2f32.powf(1.0 / 6.0),
+            epsilon: epsilon.abs(),
+        }
+    }
+
+    /// Rmin/2 corresponding to this sigma
+    pub fn rmin_half(&self) -> f32 {
+        0.5 * self.sigma * 2f32.powf(1.0 / 6.0)
+    }
+}
+
+/// Harmonic bond `k (r - r0)^2`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HarmonicBond {
+    /// First atom index
+    pub i: u32,
+    /// Second atom index
+    pub j: u32,
+    /// Force constant (kcal/mol/Å²)
+    pub k: f32,
+    /// Equilibrium length (Å)
+    pub r0: f32,
+}
+
+/// Harmonic angle `k (θ - θ0)^2`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HarmonicAngle {
+    /// First atom index
+    pub i: u32,
+    /// Central atom index
+    pub j: u32,
+    /// Third atom index
+    pub k: u32,
+    /// Force constant (kcal/mol/rad²)
+    pub force_constant: f32,
+    /// Equilibrium angle (rad)
+    pub theta0: f32,
+}
+
+/// Periodic (proper or improper) torsion `k (1 + cos(n φ - δ))`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeriodicDihedral {
+    /// Atom indices i-j-k-l
+    pub atoms: [u32; 4],
+    /// Barrier height (kcal/mol)
+    pub k: f32,
+    /// Periodicity
+    pub periodicity: f32,
+    /// Phase offset (rad)
+    pub phase: f32,
+    /// Improper torsion flag
+    pub improper: bool,
+}
+
+/// Harmonic improper torsion `k (φ - φ0)^2` (CHARMM/GROMACS style)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HarmonicImproper {
+    /// Atom indices i-j-k-l
+    pub atoms: [u32; 4],
+    /// Force constant (kcal/mol/rad²)
+    pub k: f32,
+    /// Equilibrium angle (rad)
+    pub phi0: f32,
+}
+
+/// Scaled 1-4 nonbonded pair
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pair14 {
+    /// First atom index
+    pub i: u32,
+    /// Fourth atom index
+    pub j: u32,
+    /// Multiplier applied to the Coulomb interaction
+    pub coulomb_scale: f32,
+    /// Multiplier applied to the LJ interaction
+    pub lj_scale: f32,
+}
+
+/// Pair-specific LJ override between two atom-type names (CHARMM NBFIX)
+#[derive(Debug, Clone, PartialEq)]
+pub struct NbFix {
+    /// First atom type name
+    pub type_a: String,
+    /// Second atom type name
+    pub type_b: String,
+    /// Pair LJ parameters replacing the mixing rule
+    pub params: LjParams,
+}
+
+/// Complete molecular topology with bonded and nonbonded parameters
+#[derive(Debug, Clone, Default)]
+pub struct Topology {
+    /// Atoms with coordinates, element, residue index and charge
+    pub atoms: Vec<Atom>,
+    /// Atom names (e.g. "CA")
+    pub atom_names: Vec<String>,
+    /// Force-field atom type names (e.g. "CT")
+    pub atom_types: Vec<String>,
+    /// Atomic masses (amu)
+    pub masses: Vec<f32>,
+    /// Per-atom LJ parameters
+    pub lj: Vec<LjParams>,
+    /// Per-atom LJ parameters for 1-4 pairs (empty = same as `lj`)
+    pub lj14: Vec<LjParams>,
+    /// Residue names, indexed by `Atom::residue_id`
+    pub residue_names: Vec<String>,
+    /// Harmonic bonds
+    pub bonds: Vec<HarmonicBond>,
+    /// Harmonic angles
+    pub angles: Vec<HarmonicAngle>,
+    /// Periodic torsions
+    pub dihedrals: Vec<PeriodicDihedral>,
+    /// Harmonic impropers
+    pub impropers: Vec<HarmonicImproper>,
+    /// Scaled 1-4 pairs
+    pub pairs14: Vec<Pair14>,
+    /// Nonbonded exclusions (i < j)
+    pub exclusions: Vec<(u32, u32)>,
+    /// Pair-specific LJ overrides
+    pub nbfix: Vec<NbFix>,
+    /// Periodic cell, if present
+    pub simulation_box: Option<SimulationBox>,
+}
+
+impl Topology {
+    /// Number of atoms in the topology
+    pub fn num_atoms(&self) -> usize {
+        self.atoms.len()
+    }
+
+    /// Overwrite coordinates from a flat `[x0, y0, z0, x1, ...]` array
+    pub fn set_coordinates(&mut self, coords: &[f32]) -> crate::Result<()> {
+        if coords.len() != self.atoms.len() * 3 {
+            return Err(crate::PrismIoError::ValidationError(format!(
+                "Coordinate count mismatch: topology has {} atoms, got {} values",
+                self.atoms.len(),
+                coords.len()
+            )));
+        }
+        for (atom, xyz) in self.atoms.iter_mut().zip(coords.chunks_exact(3)) {
+            atom.coords = [xyz[0], xyz[1], xyz[2]];
+        }
+        Ok(())
+    }
+
+    /// Append the atoms and terms of `other` (e.g. a ligand after its
+    /// receptor), shifting its atom and residue indices. The periodic cell
+    /// of `self` is kept when both have one.
+    pub fn append(&mut self, other: &Topology) {
+        let offset = self.atoms.len() as u32;
+        let residue_offset = self.residue_names.len() as u16;
+        if self.lj14.is_empty() && !other.lj14.is_empty() {
+            self.lj14 = self.lj.clone();
+        }
+        self.atoms.extend(other.atoms.iter().map(|a| Atom {
+            residue_id: a.residue_id + residue_offset,
+            ..*a
+        }));
+        self.atom_names.extend_from_slice(&other.atom_names);
+        self.atom_types.extend_from_slice(&other.atom_types);
+        self.masses.extend_from_slice(&other.masses);
+        self.lj.extend_from_slice(&other.lj);
+        if !self.lj14.is_empty() {
+            let lj14 = if other.lj14.is_empty() {
+                &other.lj
+            } else {
+                &other.lj14
+            };
+            self.lj14.extend_from_slice(lj14);
+        }
+        self.residue_names.extend_from_slice(&other.residue_names);
+        self.bonds.extend(other.bonds.iter().map(|b| HarmonicBond {
+            i: b.i + offset,
+            j: b.j + offset,
+            ..*b
+        }));
+        self.angles
+            .extend(other.angles.iter().map(|a| HarmonicAngle {
+                i: a.i + offset,
+                j: a.j + offset,
+                k: a.k + offset,
+                ..*a
+            }));
+        self.dihedrals
+            .extend(other.dihedrals.iter().map(|d| PeriodicDihedral {
+                atoms: d.atoms.map(|a| a + offset),
+                ..*d
+            }));
+        self.impropers
+            .extend(other.impropers.iter().map(|d| HarmonicImproper {
+                atoms: d.atoms.map(|a| a + offset),
+                ..*d
+            }));
+        self.pairs14.extend(other.pairs14.iter().map(|p| Pair14 {
+            i: p.i + offset,
+            j: p.j + offset,
+            ..*p
+        }));
+        self.exclusions.extend(
+            other
+                .exclusions
+                .iter()
+                .map(|&(i, j)| (i + offset, j + offset)),
+        );
+        self.nbfix.extend_from_slice(&other.nbfix);
+        if self.simulation_box.is_none() {
+            self.simulation_box = other.simulation_box;
+        }
+    }
+
+    /// Derive exclusions from bonds: 1-2, 1-3 and 1-4 pairs are excluded,
+    /// 1-4 interactions are expected to be listed in `pairs14`.
+    pub fn generate_exclusions(&mut self) {
+        self.exclusions = self.bonded_pairs_within(3);
+    }
+
+    /// Sorted atom pairs (i < j) separated by at most `max_bonds` bonds
+    pub fn bonded_pairs_within(&self, max_bonds: usize) -> Vec<(u32, u32)> {
+        let n = self.atoms.len();
+        let mut neighbours: Vec<Vec<u32>> = vec![Vec::new(); n];
+        for b in &self.bonds {
+            neighbours[b.i as usize].push(b.j);
+            neighbours[b.j as usize].push(b.i);
+        }
+        let mut set = std::collections::BTreeSet::new();
+        for start in 0..n as u32 {
+            let mut frontier = vec![start];
+            let mut seen = vec![start];
+            for _ in 0..max_bonds {
+                let mut next = Vec::new();
+                for &a in &frontier {
+                    for &b in &neighbours[a as usize] {
+                        if !seen.contains(&b) {
+                            seen.push(b);
+                            next.push(b);
+                            if start < b {
+                                set.insert((start, b));
+                            }
+                        }
+                    }
+                }
+                frontier = next;
+            }
+        }
+        set.into_iter().collect()
+    }
+
+    /// Map an element symbol to its atomic number (0 if unknown)
+    pub fn atomic_number(symbol: &str) -> u8 {
+        match symbol.trim().to_ascii_uppercase().as_str() {
+            "H" => 1,
+            "C" => 6,
+            "N" => 7,
+            "O" => 8,
+            "F" => 9,
+            "NA" => 11,
+            "MG" => 12,
+            "P" => 15,
+            "S" => 16,
+            "CL" => 17,
+            "K" => 19,
+            "CA" => 20,
+            "MN" => 25,
+            "FE" => 26,
+            "ZN" => 30,
+            "BR" => 35,
+            "I" => 53,
+            _ => 0,
+        }
+    }
+
+    /// Element symbol for an atomic number (empty if unknown)
+    pub fn element_symbol(atomic_number: u8) -> &'static str {
+        match atomic_number {
+            1 => "H",
+            6 => "C",
+            7 => "N",
+            8 => "O",
+            9 => "F",
+            11 => "NA",
+            12 => "MG",
+            15 => "P",
+            16 => "S",
+            17 => "CL",
+            19 => "K",
+            20 => "CA",
+            25 => "MN",
+            26 => "FE",
+            30 => "ZN",
+            35 => "BR",
+            53 => "I",
+            _ => "",
+        }
+    }
+
+    /// Standard atomic mass (amu) of an element (0 if unknown)
+    pub fn element_mass(atomic_number: u8) -> f32 {
+        ELEMENT_MASSES
+            .iter()
+            .find(|&&(_, z)| z == atomic_number)
+            .map(|&(m, _)| m)
+            .unwrap_or(0.0)
+    }
+
+    /// Best-effort atomic number from a mass (amu)
+    pub fn element_from_mass(mass: f32) -> u8 {
+        ELEMENT_MASSES
+            .iter()
+            .find(|(m, _)| (m - mass).abs() < 0.6)
+            .map(|&(_, z)| z)
+            .unwrap_or(0)
+    }
+}
+
+/// Standard atomic masses (amu) of the supported elements
+const ELEMENT_MASSES: &[(f32, u8)] = &[
+    (1.008, 1),
+    (12.011, 6),
+    (14.007, 7),
+    (15.999, 8),
+    (18.998, 9),
+    (22.990, 11),
+    (24.305, 12),
+    (30.974, 15),
+    (32.06, 16),
+    (35.45, 17),
+    (39.098, 19),
+    (40.078, 20),
+    (54.938, 25),
+    (55.845, 26),
+    (65.38, 30),
+    (79.904, 35),
+    (126.90, 53),
+];
@@ -0,0 +1,302 @@
+//! # Trajectory Output
+//!
+//! Format-independent interface for writing frames from the integrator loop.
+//! Positions are taken directly from the engine's Float4-stride host buffer
+//! (`[x, y, z, w]` per atom, Å).
+
+use crate::dcd::{DcdHeader, DcdWriter};
+use crate::h5md::H5mdWriter;
+use crate::ptb_trajectory::{read_ptb_trajectory, PtbTrajectoryHeader, PtbTrajectoryWriter};
+use crate::xtc::{XtcWriter, DEFAULT_XTC_PRECISION};
+use crate::selection::SelectionContext;
+use crate::simulation_box::SimulationBox;
+use crate::{PrismIoError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Supported trajectory formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TrajectoryFormat {
+    /// CHARMM/NAMD binary DCD
+    #[default]
+    Dcd,
+    /// GROMACS compressed XTC (lossy, fixed precision)
+    Xtc,
+    /// H5MD (HDF5) with velocities, box vectors and observables
+    H5md,
+    /// Checksummed PTB trajectory container (`.ptbt`), appendable after a
+    /// crash or checkpoint restart
+    Ptb,
+}
+
+impl TrajectoryFormat {
+    /// Expected size (bytes) of a file holding `frames` frames of
+    /// `num_atoms` atoms. Exact for DCD; XTC assumes the typical
+    /// compression of protein coordinates at the default precision and
+    /// H5MD leaves out the HDF5 metadata, which does not grow with frames.
+    pub fn file_bytes(self, num_atoms: usize, frames: u64, periodic: bool, velocities: bool) -> u64 {
+        let n = num_atoms as u64;
+        let (header, per_frame) = match self {
+            // CORD control block, one title line and the atom count, each
+            // framed by 4-byte record markers; per frame X, Y and Z records
+            // after the optional unit cell record
+            TrajectoryFormat::Dcd => (196, 3 * (4 * n + 8) + if periodic { 56 } else { 0 }),
+            // Magic, atom count, step, time and box, then the compressed
+            // coordinates at about 4.5 bytes per atom
+            TrajectoryFormat::Xtc => (0, 92 + n * 9 / 2),
+            // f32 xyz per atom, step, time and box edge vectors
+            TrajectoryFormat::H5md => (
+                0,
+                12 * n * (1 + velocities as u64) + 16 + if periodic { 72 } else { 0 },
+            ),
+            // 32-byte header; per frame tag, length, step, time and CRC32
+            // around the optional cell and f32 xyz per atom
+            TrajectoryFormat::Ptb => (32, 28 + 12 * n + if periodic { 24 } else { 0 }),
+        };
+        header + frames * per_frame
+    }
+}
+
+/// Trajectory output settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrajectoryConfig {
+    /// Output file path
+    pub path: PathBuf,
+    /// File format
+    #[serde(default)]
+    pub format: TrajectoryFormat,
+    /// Write every `stride` integration steps
+    #[serde(default = "default_stride")]
+    pub stride: u64,
+    /// XTC precision in 1/nm (1000 = 0.001 nm); ignored for DCD
+    #[serde(default = "default_precision")]
+    pub precision: f32,
+    /// Write only the atoms matching this [selection](crate::selection)
+    /// (e.g. `"protein and not hydrogen"`); all atoms when unset
+    #[serde(default)]
+    pub selection: Option<String>,
+    /// Write on after the frames of an existing file that precede the first
+    /// new step, cutting off a torn tail (PTB only; other formats start over)
+    #[serde(default)]
+    pub append: bool,
+}
+
+fn default_stride() -> u64 {
+    1000
+}
+
+fn default_precision() -> f32 {
+    DEFAULT_XTC_PRECISION
+}
+
+/// One frame handed to a [`TrajectoryWriter`]
+#[derive(Debug, Clone, Copy)]
+pub struct TrajectoryFrame<'a> {
+    /// Integration step of this frame
+    pub step: u64,
+    /// Simulation time (ps)
+    pub time_ps: f64,
+    /// Float4-stride positions (Å)
+    pub positions: &'a [f32],
+    /// Float4-stride velocities (Å/ps), for formats that store them
+    pub velocities: Option<&'a [f32]>,
+    /// Periodic cell, if any
+    pub simulation_box: Option<SimulationBox>,
+}
+
+/// Sink for trajectory frames
+pub trait TrajectoryWriter: Send + std::fmt::Debug {
+    /// Append one frame
+    fn write_frame(&mut self, frame: &TrajectoryFrame<'_>) -> Result<()>;
+
+    /// Record scalar observables (energies, analysis results) at `step`;
+    /// ignored by formats without observable storage
+    fn write_observables(&mut self, step: u64, time_ps: f64, values: &[(&str, f64)]) -> Result<()> {
+        let _ = (step, time_ps, values);
+        Ok(())
+    }
+
+    /// Number of frames written so far
+    fn frames_written(&self) -> usize;
+
+    /// Flush buffered data to disk
+    fn flush(&mut self) -> Result<()>;
+}
+
+/// Open a writer for `config`
+///
+/// `first_step` is the step of the first frame that will be written and
+/// `dt_ps` the integration timestep, both recorded in the file header.
+pub fn open_trajectory(
+    config: &TrajectoryConfig,
+    num_atoms: usize,
+    first_step: u64,
+    dt_ps: f64,
+    periodic: bool,
+) -> Result<Box<dyn TrajectoryWriter>> {
+    match config.format {
+        TrajectoryFormat::Dcd => Ok(Box::new(DcdWriter::create(
+            &config.path,
+            DcdHeader {
+                num_frames: 0,
+                first_step: first_step as u32,
+                step_interval: config.stride.max(1) as u32,
+                dt_ps,
+                has_unit_cell: periodic,
+                titles: vec!["PRISM-4D trajectory".to_string()],
+                num_atoms: num_atoms as u32,
+            },
+        )?)),
+        TrajectoryFormat::Xtc => Ok(Box::new(XtcWriter::create(
+            &config.path,
+            num_atoms,
+            config.precision,
+        )?)),
+        TrajectoryFormat::H5md => Ok(Box::new(H5mdWriter::create(
+            &config.path,
+            num_atoms,
+            periodic,
+        )?)),
+        TrajectoryFormat::Ptb => {
+            let header = PtbTrajectoryHeader {
+                num_atoms: num_atoms as u32,
+                has_unit_cell: periodic,
+                dt_ps,
+            };
+            Ok(Box::new(if config.append {
+                PtbTrajectoryWriter::append(&config.path, header, Some(first_step))?
+            } else {
+                PtbTrajectoryWriter::create(&config.path, header)?
+            }))
+        }
+    }
+}
+
+/// Open a writer for `config` over the atoms of `context`, keeping only
+/// those matching `config.selection` in every frame
+pub fn open_selected_trajectory(
+    config: &TrajectoryConfig,
+    context: &SelectionContext,
+    first_step: u64,
+    dt_ps: f64,
+    periodic: bool,
+) -> Result<Box<dyn TrajectoryWriter>> {
+    let Some(expression) = &config.selection else {
+        return open_trajectory(config, context.len(), first_step, dt_ps, periodic);
+    };
+    let atoms = context.select(expression)?;
+    if atoms.is_empty() {
+        return Err(PrismIoError::ValidationError(format!(
+            "Trajectory selection '{}' matches no atoms",
+            expression
+        )));
+    }
+    let inner = open_trajectory(config, atoms.len(), first_step, dt_ps, periodic)?;
+    Ok(Box::new(SelectedAtoms::new(inner, atoms)))
+}
+
+/// `(step, coordinates)` of every frame of a `.dcd`, `.xtc` or `.ptbt` file
+pub fn read_frames<P: AsRef<Path>>(path: P) -> Result<Vec<(u64, Vec<[f32; 3]>)>> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "dcd" => {
+            let dcd = crate::dcd::read_dcd(path)?;
+            let first = dcd.header.first_step as u64;
+            let interval = dcd.header.step_interval as u64;
+            Ok(dcd
+                .frames
+                .into_iter()
+                .enumerate()
+                .map(|(i, frame)| (first + i as u64 * interval, frame))
+                .collect())
+        }
+        "xtc" => Ok(crate::xtc::read_xtc(path)?
+            .into_iter()
+            .map(|frame| (frame.step.max(0) as u64, frame.coordinates))
+            .collect()),
+        "ptbt" => Ok(read_ptb_trajectory(path)?
+            .frames
+            .into_iter()
+            .map(|frame| (frame.step, frame.positions))
+            .collect()),
+        _ => Err(PrismIoError::FormatError(format!(
+            "Unknown trajectory format for {} (expected .dcd, .xtc or .ptbt)",
+            path.display()
+        ))),
+    }
+}
+
+/// Writer forwarding only a subset of atoms to another writer
+#[derive(Debug)]
+pub struct SelectedAtoms {
+    inner: Box<dyn TrajectoryWriter>,
+    atoms: Vec<u32>,
+    positions: Vec<f32>,
+    velocities: Vec<f32>,
+}
+
+impl SelectedAtoms {
+    /// Forward `atoms` of every frame to `inner`
+    pub fn new(inner: Box<dyn TrajectoryWriter>, atoms: Vec<u32>) -> Self {
+        Self {
+            inner,
+            positions: Vec::with_capacity(atoms.len() * 4),
+            velocities: Vec::new(),
+            atoms,
+        }
+    }
+
+    /// Atoms written, in output order
+    pub fn atoms(&self) -> &[u32] {
+        &self.atoms
+    }
+}
+
+impl TrajectoryWriter for SelectedAtoms {
+    fn write_frame(&mut self, frame: &TrajectoryFrame<'_>) -> Result<()> {
+        gather(&self.atoms, frame.positions, &mut self.positions)?;
+        if let Some(velocities) = frame.velocities {
+            gather(&self.atoms, velocities, &mut self.velocities)?;
+        }
+        self.inner.write_frame(&TrajectoryFrame {
+            positions: &self.positions,
+            velocities: frame.velocities.map(|_| self.velocities.as_slice()),
+            ..*frame
+        })
+    }
+
+    fn write_observables(&mut self, step: u64, time_ps: f64, values: &[(&str, f64)]) -> Result<()> {
+        self.inner.write_observables(step, time_ps, values)
+    }
+
+    fn frames_written(&self) -> usize {
+        self.inner.frames_written()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Copy the Float4 entries of `atoms` from `frame` into `out`
+fn gather(atoms: &[u32], frame: &[f32], out: &mut Vec<f32>) -> Result<()> {
+    out.clear();
+    for &i in atoms {
+        let offset = i as usize * 4;
+        let p = frame.get(offset..offset + 4).ok_or_else(|| {
+            PrismIoError::ValidationError(format!(
+                "Selected atom {} outside frame of {} atoms",
+                i,
+                frame.len() / 4
+            ))
+        })?;
+        out.extend_from_slice(p);
+    }
+    Ok(())
+}
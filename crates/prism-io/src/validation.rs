@@ -81,7 +81,7 @@ pub struct ValidationResult {
     /// Size of validated data in bytes
     pub data_size: usize,
     /// Validation timestamp
-    pub validated_at: std::time::SystemTime,
+    pub validated_at: web_time::SystemTime,
     /// Additional metadata
     pub metadata: HashMap<String, String>,
 }
@@ -116,7 +116,7 @@ impl DataIntegrityValidator {
     /// # Returns
     /// * `Result<[u8; 32]>` - BLAKE3 hash or error
     pub fn compute_hash(&self, data: &[u8]) -> Result<[u8; 32]> {
-        let start_time = std::time::Instant::now();
+        let start_time = web_time::Instant::now();
 
         // BLAKE3 automatically uses SIMD instructions and multi-threading (via rayon feature)
         // This matches our io_uring and CUDA acceleration philosophy
@@ -179,7 +179,7 @@ impl DataIntegrityValidator {
         data: &[u8],
         expected_dataset_id: Option<&str>,
     ) -> Result<ValidationResult> {
-        let start_time = std::time::Instant::now();
+        let start_time = web_time::Instant::now();
 
         // Step 1: Compute cryptographic hash (BLAKE3 - blazingly fast)
         let data_hash = self.compute_hash(data)?;
@@ -227,7 +227,7 @@ impl DataIntegrityValidator {
             data_hash,
             dataset_id,
             data_size: data.len(),
-            validated_at: std::time::SystemTime::now(),
+            validated_at: web_time::SystemTime::now(),
             metadata,
         })
     }
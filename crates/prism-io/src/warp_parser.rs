@@ -97,7 +97,7 @@ impl WarpDriveParser {
     /// # Returns
     /// * `Result<ParsedProteinData>` - Parsed protein structure or error
     pub async fn parse_pdb_parallel(&self, pdb_data: &[u8]) -> Result<ParsedProteinData> {
-        let start_time = std::time::Instant::now();
+        let start_time = web_time::Instant::now();
 
         tracing::debug!("Starting warp-drive parsing of {} bytes", pdb_data.len());
 
@@ -0,0 +1,808 @@
+//! # XTC Compressed Trajectories (GROMACS)
+//!
+//! Lossy fixed-precision coordinate compression compatible with GROMACS
+//! `xdrfile`: coordinates are rounded to `1/precision` nm, and consecutive
+//! close atoms are run-length packed as small deltas, which typically shrinks
+//! trajectories 3-4x versus DCD.
+//!
+//! ## Notes
+//! - All values are big-endian XDR; lengths in the file are nm
+//! - Frames with 9 atoms or fewer are stored uncompressed, as in GROMACS
+//! - The public API takes and returns Å like the rest of the crate
+
+use crate::simulation_box::SimulationBox;
+use crate::trajectory::{TrajectoryFrame, TrajectoryWriter};
+use crate::{PrismIoError, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// XTC frame magic number
+const XTC_MAGIC: i32 = 1995;
+/// Å → nm
+const ANGSTROM_TO_NM: f32 = 0.1;
+/// Largest absolute scaled coordinate the integer encoding supports
+const MAXABS: f32 = (i32::MAX - 2) as f32;
+/// First usable entry of [`MAGICINTS`]
+const FIRSTIDX: usize = 9;
+
+/// Bit sizes used for small-delta runs (≈ 2^(i/3))
+const MAGICINTS: [u32; 73] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 10, 12, 16, 20, 25, 32, 40, 50, 64, 80, 101, 128, 161, 203, 256,
+    322, 406, 512, 645, 812, 1024, 1290, 1625, 2048, 2580, 3250, 4096, 5060, 6501, 8192, 10321,
+    13003, 16384, 20642, 26007, 32768, 41285, 52015, 65536, 82570, 104031, 131072, 165140, 208063,
+    262144, 330280, 416127, 524287, 660561, 832255, 1048576, 1321122, 1664510, 2097152, 2642245,
+    3329021, 4194304, 5284491, 6658042, 8388607, 10568983, 13316085, 16777216,
+];
+
+/// Default precision: 1/1000 nm (0.01 Å)
+pub const DEFAULT_XTC_PRECISION: f32 = 1000.0;
+
+/// Number of bits needed to store values in `0..=size`
+fn sizeofint(size: u32) -> u32 {
+    let mut num: u64 = 1;
+    let mut bits = 0;
+    while size as u64 >= num && bits < 32 {
+        bits += 1;
+        num <<= 1;
+    }
+    bits
+}
+
+/// Number of bits needed to store the mixed-radix product of `sizes`
+fn sizeofints(sizes: &[u32; 3]) -> u32 {
+    let mut bytes: Vec<u64> = vec![1];
+    for &size in sizes {
+        let mut tmp = 0u64;
+        for b in bytes.iter_mut() {
+            tmp += *b * size as u64;
+            *b = tmp & 0xff;
+            tmp >>= 8;
+        }
+        while tmp != 0 {
+            bytes.push(tmp & 0xff);
+            tmp >>= 8;
+        }
+    }
+    let last = *bytes.last().unwrap_or(&0);
+    let mut bits = 0;
+    let mut num = 1u64;
+    while last >= num {
+        bits += 1;
+        num *= 2;
+    }
+    bits + (bytes.len() as u32 - 1) * 8
+}
+
+/// MSB-first bit stream
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn send_bits(&mut self, n: u32, value: u32) {
+        debug_assert!(n <= 32, "send_bits takes at most 32 bits");
+        for i in (0..n).rev() {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                let last = self.bytes.len() - 1;
+                self.bytes[last] |= 0x80 >> (self.bits % 8);
+            }
+            self.bits += 1;
+        }
+    }
+
+    /// Pack three bounded integers as one mixed-radix number of `n` bits
+    fn send_ints(&mut self, n: u32, sizes: &[u32; 3], nums: &[u32; 3]) {
+        let mut bytes = Vec::with_capacity(8);
+        let mut tmp = nums[0] as u64;
+        loop {
+            bytes.push(tmp & 0xff);
+            tmp >>= 8;
+            if tmp == 0 {
+                break;
+            }
+        }
+        for i in 1..3 {
+            tmp = nums[i] as u64;
+            for b in bytes.iter_mut() {
+                tmp += *b * sizes[i] as u64;
+                *b = tmp & 0xff;
+                tmp >>= 8;
+            }
+            while tmp != 0 {
+                bytes.push(tmp & 0xff);
+                tmp >>= 8;
+            }
+        }
+        let full = bytes.len() as u32 * 8;
+        if n >= full {
+            for &b in &bytes {
+                self.send_bits(8, b as u32);
+            }
+            // Zero padding can exceed 32 bits for wide boxes
+            let mut pad = n - full;
+            while pad > 0 {
+                let chunk = pad.min(8);
+                self.send_bits(chunk, 0);
+                pad -= chunk;
+            }
+        } else {
+            let (last, head) = bytes.split_last().unwrap_or((&0, &[]));
+            for &b in head {
+                self.send_bits(8, b as u32);
+            }
+            self.send_bits(n - (full - 8), *last as u32);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bit: usize,
+}
+
+impl BitReader<'_> {
+    fn receive_bits(&mut self, n: u32) -> Result<u32> {
+        let mut value = 0u64;
+        for _ in 0..n {
+            let byte = *self.bytes.get(self.bit / 8).ok_or_else(|| {
+                PrismIoError::FormatError("XTC compressed data truncated".to_string())
+            })?;
+            value = (value << 1) | ((byte >> (7 - self.bit % 8)) & 1) as u64;
+            self.bit += 1;
+        }
+        Ok(value as u32)
+    }
+
+    fn receive_ints(&mut self, n: u32, sizes: &[u32; 3]) -> Result<[u32; 3]> {
+        let mut bytes: Vec<u64> = Vec::with_capacity(8);
+        let mut remaining = n;
+        while remaining > 8 {
+            bytes.push(self.receive_bits(8)? as u64);
+            remaining -= 8;
+        }
+        if remaining > 0 {
+            bytes.push(self.receive_bits(remaining)? as u64);
+        }
+        bytes.resize(bytes.len().max(4), 0);
+
+        let mut nums = [0u32; 3];
+        for i in (1..3).rev() {
+            let mut num = 0u64;
+            for b in bytes.iter_mut().rev() {
+                num = (num << 8) | *b;
+                let p = num / sizes[i] as u64;
+                *b = p;
+                num -= p * sizes[i] as u64;
+            }
+            nums[i] = num as u32;
+        }
+        nums[0] = (bytes[0] | (bytes[1] << 8) | (bytes[2] << 16) | (bytes[3] << 24)) as u32;
+        Ok(nums)
+    }
+}
+
+fn put_i32(out: &mut Vec<u8>, v: i32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+fn put_f32(out: &mut Vec<u8>, v: f32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+/// Append the compressed coordinate block for `xyz` (nm) to `out`
+fn compress_coords(out: &mut Vec<u8>, xyz: &[[f32; 3]], precision: f32) -> Result<()> {
+    let natoms = xyz.len();
+    put_i32(out, natoms as i32);
+    if natoms <= 9 {
+        for c in xyz.iter().flatten() {
+            put_f32(out, *c);
+        }
+        return Ok(());
+    }
+
+    let mut ints: Vec<[i64; 3]> = Vec::with_capacity(natoms);
+    let mut minint = [i64::MAX; 3];
+    let mut maxint = [i64::MIN; 3];
+    let mut mindiff = i64::MAX;
+    let mut old = [0i64; 3];
+    for (n, p) in xyz.iter().enumerate() {
+        let mut q = [0i64; 3];
+        for d in 0..3 {
+            let lf = if p[d] >= 0.0 {
+                p[d] * precision + 0.5
+            } else {
+                p[d] * precision - 0.5
+            };
+            if !lf.is_finite() || lf.abs() > MAXABS {
+                return Err(PrismIoError::ValidationError(format!(
+                    "Coordinate {} of atom {} too large for XTC precision {}",
+                    p[d], n, precision
+                )));
+            }
+            q[d] = lf as i32 as i64;
+            minint[d] = minint[d].min(q[d]);
+            maxint[d] = maxint[d].max(q[d]);
+        }
+        let diff = (0..3).map(|d| (old[d] - q[d]).abs()).sum::<i64>();
+        if n > 0 && diff < mindiff {
+            mindiff = diff;
+        }
+        old = q;
+        ints.push(q);
+    }
+
+    put_f32(out, precision);
+    for v in minint.iter().chain(&maxint) {
+        put_i32(out, *v as i32);
+    }
+    let sizeint: [u32; 3] = [0, 1, 2].map(|d| (maxint[d] - minint[d] + 1) as u32);
+    if sizeint.iter().any(|&s| s as f32 >= MAXABS) {
+        return Err(PrismIoError::ValidationError(
+            "Coordinate range too large for XTC compression".to_string(),
+        ));
+    }
+    let (bitsize, bitsizeint) = if (sizeint[0] | sizeint[1] | sizeint[2]) > 0xff_ffff {
+        (0, sizeint.map(sizeofint))
+    } else {
+        (sizeofints(&sizeint), [0; 3])
+    };
+
+    let mut smallidx = FIRSTIDX;
+    while smallidx < MAGICINTS.len() - 1 && (MAGICINTS[smallidx] as i64) < mindiff {
+        smallidx += 1;
+    }
+    put_i32(out, smallidx as i32);
+
+    let maxidx = (smallidx + 8).min(MAGICINTS.len() - 1);
+    let minidx = maxidx - 8;
+    let mut smaller = (MAGICINTS[FIRSTIDX.max(smallidx - 1)] / 2) as i64;
+    let mut smallnum = (MAGICINTS[smallidx] / 2) as i64;
+    let mut sizesmall = [MAGICINTS[smallidx]; 3];
+    let larger = (MAGICINTS[maxidx] / 2) as i64;
+
+    let mut bits = BitWriter::default();
+    let mut prevcoord = [0i64; 3];
+    let mut prevrun: i64 = -1;
+    let mut i = 0;
+    while i < natoms {
+        let near =
+            |a: &[i64; 3], b: &[i64; 3], limit: i64| (0..3).all(|d| (a[d] - b[d]).abs() < limit);
+        let mut is_smaller: i64 =
+            if smallidx < maxidx && i >= 1 && near(&ints[i], &prevcoord, larger) {
+                1
+            } else if smallidx > minidx {
+                -1
+            } else {
+                0
+            };
+        let mut is_small = false;
+        if i + 1 < natoms && near(&ints[i], &ints[i + 1], smallnum) {
+            // Swap the first two atoms of a run (better for water)
+            ints.swap(i, i + 1);
+            is_small = true;
+        }
+
+        let this = ints[i];
+        let offset = [0, 1, 2].map(|d| (this[d] - minint[d]) as u32);
+        if bitsize == 0 {
+            for d in 0..3 {
+                bits.send_bits(bitsizeint[d], offset[d]);
+            }
+        } else {
+            bits.send_ints(bitsize, &sizeint, &offset);
+        }
+        prevcoord = this;
+        i += 1;
+
+        let mut run: Vec<[u32; 3]> = Vec::new();
+        if !is_small && is_smaller == -1 {
+            is_smaller = 0;
+        }
+        while is_small && run.len() < 8 {
+            let this = ints[i];
+            let dist2: i64 = (0..3).map(|d| (this[d] - prevcoord[d]).pow(2)).sum();
+            if is_smaller == -1 && dist2 >= smaller * smaller {
+                is_smaller = 0;
+            }
+            run.push([0, 1, 2].map(|d| (this[d] - prevcoord[d] + smallnum) as u32));
+            prevcoord = this;
+            i += 1;
+            is_small = i < natoms && near(&ints[i], &prevcoord, smallnum);
+        }
+
+        let run_len = run.len() as i64 * 3;
+        if run_len != prevrun || is_smaller != 0 {
+            prevrun = run_len;
+            bits.send_bits(1, 1);
+            bits.send_bits(5, (run_len + is_smaller + 1) as u32);
+        } else {
+            bits.send_bits(1, 0);
+        }
+        for delta in &run {
+            bits.send_ints(smallidx as u32, &sizesmall, delta);
+        }
+
+        if is_smaller != 0 {
+            smallidx = (smallidx as i64 + is_smaller) as usize;
+            if is_smaller < 0 {
+                smallnum = smaller;
+                smaller = if smallidx > FIRSTIDX {
+                    (MAGICINTS[smallidx - 1] / 2) as i64
+                } else {
+                    0
+                };
+            } else {
+                smaller = smallnum;
+                smallnum = (MAGICINTS[smallidx] / 2) as i64;
+            }
+            sizesmall = [MAGICINTS[smallidx]; 3];
+        }
+    }
+
+    put_i32(out, bits.bytes.len() as i32);
+    out.extend_from_slice(&bits.bytes);
+    out.resize(out.len() + (4 - bits.bytes.len() % 4) % 4, 0);
+    Ok(())
+}
+
+/// Big-endian XDR cursor
+struct Xdr<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Xdr<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let slice = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| PrismIoError::FormatError("XTC file truncated".to_string()))?;
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        let b = self.take(4)?;
+        Ok(i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_bits(self.i32()? as u32))
+    }
+}
+
+fn decompress_coords(xdr: &mut Xdr<'_>, natoms: usize) -> Result<Vec<[f32; 3]>> {
+    let lsize = xdr.i32()? as usize;
+    if lsize != natoms {
+        return Err(PrismIoError::FormatError(format!(
+            "XTC coordinate count {} does not match header {}",
+            lsize, natoms
+        )));
+    }
+    if natoms <= 9 {
+        return (0..natoms)
+            .map(|_| Ok([xdr.f32()?, xdr.f32()?, xdr.f32()?]))
+            .collect();
+    }
+
+    let precision = xdr.f32()?;
+    let minint = [xdr.i32()?, xdr.i32()?, xdr.i32()?].map(|v| v as i64);
+    let maxint = [xdr.i32()?, xdr.i32()?, xdr.i32()?].map(|v| v as i64);
+    let sizeint: [u32; 3] = [0, 1, 2].map(|d| (maxint[d] - minint[d] + 1) as u32);
+    let (bitsize, bitsizeint) = if (sizeint[0] | sizeint[1] | sizeint[2]) > 0xff_ffff {
+        (0, sizeint.map(sizeofint))
+    } else {
+        (sizeofints(&sizeint), [0; 3])
+    };
+    let mut smallidx = xdr.i32()? as usize;
+    if !(FIRSTIDX..MAGICINTS.len()).contains(&smallidx) {
+        return Err(PrismIoError::FormatError(format!(
+            "Invalid XTC smallidx {}",
+            smallidx
+        )));
+    }
+    let mut smaller = (MAGICINTS[FIRSTIDX.max(smallidx - 1)] / 2) as i64;
+    let mut smallnum = (MAGICINTS[smallidx] / 2) as i64;
+    let mut sizesmall = [MAGICINTS[smallidx]; 3];
+
+    let nbytes = xdr.i32()? as usize;
+    let bytes = xdr.take(nbytes)?;
+    xdr.take((4 - nbytes % 4) % 4)?;
+    let mut bits = BitReader { bytes, bit: 0 };
+
+    let inv = 1.0 / precision;
+    let scale = |c: [i64; 3]| c.map(|v| v as f32 * inv);
+    let mut out = Vec::with_capacity(natoms);
+    let mut run = 0u32;
+    while out.len() < natoms {
+        let raw = if bitsize == 0 {
+            [
+                bits.receive_bits(bitsizeint[0])?,
+                bits.receive_bits(bitsizeint[1])?,
+                bits.receive_bits(bitsizeint[2])?,
+            ]
+        } else {
+            bits.receive_ints(bitsize, &sizeint)?
+        };
+        let mut prevcoord = [0, 1, 2].map(|d| raw[d] as i64 + minint[d]);
+
+        let mut is_smaller: i64 = 0;
+        if bits.receive_bits(1)? == 1 {
+            run = bits.receive_bits(5)?;
+            is_smaller = (run % 3) as i64;
+            run -= is_smaller as u32;
+            is_smaller -= 1;
+        }
+        if run > 0 {
+            for k in (0..run).step_by(3) {
+                let delta = bits.receive_ints(smallidx as u32, &sizesmall)?;
+                let mut this = [0, 1, 2].map(|d| delta[d] as i64 + prevcoord[d] - smallnum);
+                if k == 0 {
+                    // Undo the first/second atom swap made by the compressor
+                    std::mem::swap(&mut this, &mut prevcoord);
+                    out.push(scale(prevcoord));
+                } else {
+                    prevcoord = this;
+                }
+                out.push(scale(this));
+            }
+        } else {
+            out.push(scale(prevcoord));
+        }
+
+        smallidx = (smallidx as i64 + is_smaller) as usize;
+        if !(FIRSTIDX..MAGICINTS.len()).contains(&smallidx) {
+            return Err(PrismIoError::FormatError(
+                "Corrupt XTC run encoding".to_string(),
+            ));
+        }
+        if is_smaller < 0 {
+            smallnum = smaller;
+            smaller = if smallidx > FIRSTIDX {
+                (MAGICINTS[smallidx - 1] / 2) as i64
+            } else {
+                0
+            };
+        } else if is_smaller > 0 {
+            smaller = smallnum;
+            smallnum = (MAGICINTS[smallidx] / 2) as i64;
+        }
+        sizesmall = [MAGICINTS[smallidx]; 3];
+    }
+    if out.len() != natoms {
+        return Err(PrismIoError::FormatError(
+            "XTC run overflows atom count".to_string(),
+        ));
+    }
+    Ok(out)
+}
+
+/// Streaming XTC writer
+#[derive(Debug)]
+pub struct XtcWriter {
+    file: BufWriter<File>,
+    num_atoms: usize,
+    precision: f32,
+    frames: usize,
+    xyz: Vec<[f32; 3]>,
+    buffer: Vec<u8>,
+}
+
+impl XtcWriter {
+    /// Create an XTC file; `precision` is in 1/nm (1000 = 0.001 nm)
+    pub fn create<P: AsRef<Path>>(path: P, num_atoms: usize, precision: f32) -> Result<Self> {
+        if !(precision > 0.0 && precision.is_finite()) {
+            return Err(PrismIoError::ValidationError(format!(
+                "XTC precision must be positive, got {}",
+                precision
+            )));
+        }
+        Ok(Self {
+            file: BufWriter::new(File::create(path)?),
+            num_atoms,
+            precision,
+            frames: 0,
+            xyz: Vec::with_capacity(num_atoms),
+            buffer: Vec::new(),
+        })
+    }
+}
+
+impl TrajectoryWriter for XtcWriter {
+    fn write_frame(&mut self, frame: &TrajectoryFrame<'_>) -> Result<()> {
+        if frame.positions.len() != self.num_atoms * 4 {
+            return Err(PrismIoError::ValidationError(format!(
+                "XTC frame has {} values, expected {} (Float4 x {} atoms)",
+                frame.positions.len(),
+                self.num_atoms * 4,
+                self.num_atoms
+            )));
+        }
+        self.xyz.clear();
+        self.xyz.extend(frame.positions.chunks_exact(4).map(|p| {
+            [
+                p[0] * ANGSTROM_TO_NM,
+                p[1] * ANGSTROM_TO_NM,
+                p[2] * ANGSTROM_TO_NM,
+            ]
+        }));
+
+        let out = &mut self.buffer;
+        out.clear();
+        put_i32(out, XTC_MAGIC);
+        put_i32(out, self.num_atoms as i32);
+        put_i32(out, frame.step.min(i32::MAX as u64) as i32);
+        put_f32(out, frame.time_ps as f32);
+        let vectors = frame
+            .simulation_box
+            .map_or([[0.0; 3]; 3], |cell| cell.vectors());
+        for v in vectors.iter().flatten() {
+            put_f32(out, v * ANGSTROM_TO_NM);
+        }
+        compress_coords(out, &self.xyz, self.precision)?;
+        self.file.write_all(out)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn frames_written(&self) -> usize {
+        self.frames
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for XtcWriter {
+    fn drop(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+/// One decoded XTC frame (Å)
+#[derive(Debug, Clone)]
+pub struct XtcFrame {
+    /// Integration step
+    pub step: i32,
+    /// Simulation time (ps)
+    pub time_ps: f32,
+    /// Box vectors as rows (Å)
+    pub box_vectors: [[f32; 3]; 3],
+    /// Coordinates (Å)
+    pub coordinates: Vec<[f32; 3]>,
+}
+
+impl XtcFrame {
+    /// Periodic cell of the frame (`None` for a zero box)
+    pub fn simulation_box(&self) -> Option<SimulationBox> {
+        SimulationBox::triclinic(self.box_vectors).ok()
+    }
+}
+
+/// Read all frames of an XTC file
+pub fn read_xtc<P: AsRef<Path>>(path: P) -> Result<Vec<XtcFrame>> {
+    let data = std::fs::read(path)?;
+    let mut xdr = Xdr {
+        data: &data,
+        pos: 0,
+    };
+    let mut frames = Vec::new();
+    while xdr.pos < data.len() {
+        if xdr.i32()? != XTC_MAGIC {
+            return Err(PrismIoError::FormatError(format!(
+                "Bad XTC magic in frame {}",
+                frames.len()
+            )));
+        }
+        let natoms = xdr.i32()?.max(0) as usize;
+        let step = xdr.i32()?;
+        let time_ps = xdr.f32()?;
+        let mut box_vectors = [[0.0f32; 3]; 3];
+        for row in box_vectors.iter_mut() {
+            for v in row.iter_mut() {
+                *v = xdr.f32()? / ANGSTROM_TO_NM;
+            }
+        }
+        let coordinates = decompress_coords(&mut xdr, natoms)?
+            .into_iter()
+            .map(|c| c.map(|v| v / ANGSTROM_TO_NM))
+            .collect();
+        frames.push(XtcFrame {
+            step,
+            time_ps,
+            box_vectors,
+            coordinates,
+        });
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference frames (step 0, t = 0, zero box, precision 1000) generated by a
+    // C transcription of xdrfile's `xdrfile_compress_coord_float`
+    /// `positions(5, 0.123)`: water runs exercising the swap and run-length paths
+    const XDRFILE_WATERS: [u8; 144] = [
+        0x00, 0x00, 0x07, 0xcb, 0x00, 0x00, 0x00, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0x44, 0x7a, 0x00, 0x00,
+        0xff, 0xff, 0xff, 0xf4, 0xff, 0xff, 0xf8, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05,
+        0x44, 0xff, 0xff, 0xf8, 0x8d, 0x00, 0x00, 0x00, 0x94, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00,
+        0x00, 0x33, 0x10, 0x21, 0x05, 0x42, 0xa1, 0x4a, 0x32, 0x88, 0x84, 0xd8, 0x01, 0x14, 0xcb,
+        0x0a, 0x39, 0xb3, 0xca, 0xb6, 0x8a, 0xa9, 0xd9, 0x09, 0x13, 0x11, 0x34, 0xf2, 0x68, 0x08,
+        0x02, 0x08, 0x0d, 0xd6, 0x8e, 0xb6, 0x6e, 0x01, 0x65, 0xd4, 0x19, 0x00, 0x2b, 0x17, 0x4a,
+        0x72, 0x29, 0x65, 0xd4, 0x19, 0x00, 0x2b, 0x10, 0x00,
+    ];
+    /// `wide_positions()`: 12 atoms over ~50 nm with one at the origin
+    const XDRFILE_WIDE: [u8; 164] = [
+        0x00, 0x00, 0x07, 0xcb, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x44, 0x7a, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xab,
+        0xe0, 0x00, 0x00, 0xbf, 0x68, 0x00, 0x00, 0x9e, 0xfc, 0x00, 0x00, 0x00, 0x28, 0x00, 0x00,
+        0x00, 0x46, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x0d, 0x43, 0xb4, 0x82, 0x5c, 0x18, 0xe9,
+        0x13, 0x31, 0x3c, 0x32, 0x07, 0x10, 0x21, 0xf5, 0x07, 0x41, 0x8e, 0xca, 0x99, 0x89, 0xe1,
+        0x90, 0x39, 0xb1, 0x2e, 0x31, 0xe1, 0x10, 0xae, 0x59, 0xd6, 0xd7, 0xa7, 0xa6, 0x23, 0x85,
+        0x42, 0xe6, 0x10, 0x5b, 0xab, 0xa3, 0x04, 0x7e, 0x82, 0x01, 0x9e, 0x83, 0xc0, 0xc0, 0x6f,
+        0x4d, 0xd0, 0xb8, 0x18, 0x1f, 0xea, 0x74, 0x2e, 0x06, 0x07, 0xfa, 0x80, 0x00, 0x00,
+    ];
+
+    /// Water-like clusters spread over a box, Float4 stride, Å
+    fn positions(n_waters: usize, shift: f32) -> Vec<f32> {
+        let mut p = Vec::new();
+        for w in 0..n_waters {
+            let base = [
+                (w % 7) as f32 * 3.1 + shift,
+                (w / 7 % 7) as f32 * 3.1 - 20.0,
+                (w / 49) as f32 * 3.1 + 0.37 * w as f32,
+            ];
+            for (dx, dy) in [(0.0, 0.0), (0.9572, 0.0), (-0.2400, 0.9266)] {
+                p.extend_from_slice(&[base[0] + dx, base[1] + dy, base[2], 16.0]);
+            }
+        }
+        p
+    }
+
+    /// Atoms spread over ~50 nm; the mixed-radix packing needs > 32 bits of padding
+    fn wide_positions() -> Vec<f32> {
+        (0..12)
+            .flat_map(|i| {
+                let f = i as f32;
+                if i == 0 {
+                    [0.0, 0.0, 0.0, 1.0]
+                } else {
+                    [f * 40.0, 500.0 - f * 10.0, f * 37.0, 1.0]
+                }
+            })
+            .collect()
+    }
+
+    /// Bytes of a single frame at step 0 with no box
+    fn encode(positions: &[f32], precision: f32) -> Vec<u8> {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = XtcWriter::create(file.path(), positions.len() / 4, precision).unwrap();
+        writer
+            .write_frame(&TrajectoryFrame {
+                step: 0,
+                time_ps: 0.0,
+                positions,
+                velocities: None,
+                simulation_box: None,
+            })
+            .unwrap();
+        drop(writer);
+        std::fs::read(file.path()).unwrap()
+    }
+
+    fn round_trip(positions: &[f32], precision: f32) -> Vec<XtcFrame> {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let n = positions.len() / 4;
+        let mut writer = XtcWriter::create(file.path(), n, precision).unwrap();
+        for step in 0..2u64 {
+            writer
+                .write_frame(&TrajectoryFrame {
+                    step: step * 500,
+                    time_ps: step as f64,
+                    positions,
+                    velocities: None,
+                    simulation_box: Some(SimulationBox::orthorhombic([40.0, 41.0, 42.0])),
+                })
+                .unwrap();
+        }
+        drop(writer);
+        read_xtc(file.path()).unwrap()
+    }
+
+    #[test]
+    fn test_xtc_round_trip_within_precision() {
+        let pos = positions(120, 0.123);
+        for precision in [1000.0f32, 100.0] {
+            let frames = round_trip(&pos, precision);
+            assert_eq!(frames.len(), 2);
+            assert_eq!(frames[1].step, 500);
+            assert!((frames[0].box_vectors[1][1] - 41.0).abs() < 1e-4);
+            let cell = frames[1].simulation_box().unwrap();
+            assert!(cell.is_orthorhombic());
+            let tolerance = 10.0 * 0.5 / precision + 1e-4;
+            for (a, b) in frames[1].coordinates.iter().zip(pos.chunks_exact(4)) {
+                for d in 0..3 {
+                    assert!((a[d] - b[d]).abs() <= tolerance, "{:?} vs {:?}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_xtc_compresses() {
+        let pos = positions(300, 0.0);
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = XtcWriter::create(file.path(), pos.len() / 4, 1000.0).unwrap();
+        writer
+            .write_frame(&TrajectoryFrame {
+                step: 0,
+                time_ps: 0.0,
+                positions: &pos,
+                velocities: None,
+                simulation_box: None,
+            })
+            .unwrap();
+        drop(writer);
+        let size = std::fs::metadata(file.path()).unwrap().len() as usize;
+        assert!(
+            size < pos.len() / 4 * 12 / 2,
+            "{} bytes not compressed",
+            size
+        );
+    }
+
+    #[test]
+    fn test_small_frames_are_uncompressed() {
+        let pos = positions(2, 1.0);
+        let frames = round_trip(&pos, 1000.0);
+        assert_eq!(frames[0].coordinates.len(), 6);
+        assert!((frames[0].coordinates[1][0] - pos[4]).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_wide_box_round_trip() {
+        let pos = wide_positions();
+        let frames = round_trip(&pos, 1000.0);
+        for (a, b) in frames[0].coordinates.iter().zip(pos.chunks_exact(4)) {
+            for d in 0..3 {
+                assert!((a[d] - b[d]).abs() <= 0.0051, "{:?} vs {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_xdrfile_reference_frames() {
+        for (pos, reference) in [
+            (positions(5, 0.123), &XDRFILE_WATERS[..]),
+            (wide_positions(), &XDRFILE_WIDE[..]),
+        ] {
+            assert_eq!(encode(&pos, 1000.0), reference);
+
+            let file = tempfile::NamedTempFile::new().unwrap();
+            std::fs::write(file.path(), reference).unwrap();
+            let frames = read_xtc(file.path()).unwrap();
+            assert_eq!(frames.len(), 1);
+            for (a, b) in frames[0].coordinates.iter().zip(pos.chunks_exact(4)) {
+                for d in 0..3 {
+                    assert!((a[d] - b[d]).abs() <= 0.0051, "{:?} vs {:?}", a, b);
+                }
+            }
+        }
+    }
+}
@@ -728,6 +728,7 @@ fn run_macro_step_episode(
         use_gpu: true,
         max_trajectory_memory: 256 * 1024 * 1024,
         max_workspace_memory: 128 * 1024 * 1024,
+        ..MolecularDynamicsConfig::default()
     };
 
     // 2. Initialize MD engine from PDB data
@@ -378,6 +378,7 @@ fn validate_target(
         use_gpu: true,
         max_trajectory_memory: 256 * 1024 * 1024,
         max_workspace_memory: 128 * 1024 * 1024,
+        ..MolecularDynamicsConfig::default()
     };
 
     // Initialize engine
@@ -308,6 +308,7 @@ impl PrismTrainer {
             use_gpu: true,
             max_trajectory_memory: 256 * 1024 * 1024,
             max_workspace_memory: 128 * 1024 * 1024,
+            ..MolecularDynamicsConfig::default()
         };
 
         let mut engine = MolecularDynamicsEngine::from_sovereign_buffer(md_config.clone(), &pdb_data)
@@ -491,6 +492,7 @@ impl PrismTrainer {
             use_gpu: true,
             max_trajectory_memory: 256 * 1024 * 1024,
             max_workspace_memory: 128 * 1024 * 1024,
+            ..MolecularDynamicsConfig::default()
         };
 
         let mut engine = MolecularDynamicsEngine::from_sovereign_buffer(md_config, &pdb_data)
@@ -0,0 +1,119 @@
+//! Build script for prism-physics
+//!
+//! With the `rocm` feature, generates the HIP nonbonded kernel from the
+//! CUDA source in prism-gpu and compiles it to a code object for runtime
+//! loading (see `src/hip.rs`). With the `mpi` feature, compiles the MPI
+//! shim (see `src/mpi.rs`) and links it with the MPI libraries.
+//!
+//! HIP COMPILATION:
+//! - Source: ../prism-gpu/src/kernels/nonbonded_forces.cu with the CUDA
+//!   runtime header replaced by the HIP one (the kernel uses only the
+//!   CUDA subset HIP shares)
+//! - Target architecture: PRISM_HIP_ARCH (default gfx90a, MI200)
+//! - Output: $OUT_DIR/nonbonded_forces.hsaco
+//!
+//! DEPENDENCIES:
+//! - ROCm with hipcc in PATH or under HIP_PATH / ROCM_PATH
+//!
+//! MPI SHIM:
+//! - Source: src/mpi_shim.c, compiled by MPICC (default mpicc) into
+//!   $OUT_DIR/libprism_mpi_shim.a
+//! - Link flags: the -L/-l arguments of `mpicc -show` (MPICH) or
+//!   `mpicc --showme:link` (Open MPI)
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+const NONBONDED_SOURCE: &str = "../prism-gpu/src/kernels/nonbonded_forces.cu";
+const MPI_SHIM_SOURCE: &str = "src/mpi_shim.c";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var("CARGO_FEATURE_ROCM").is_ok() {
+        build_hip();
+    }
+    if env::var("CARGO_FEATURE_MPI").is_ok() {
+        build_mpi_shim();
+    }
+}
+
+fn build_hip() {
+    println!("cargo:rerun-if-changed={}", NONBONDED_SOURCE);
+    println!("cargo:rerun-if-env-changed=PRISM_HIP_ARCH");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let cuda = std::fs::read_to_string(NONBONDED_SOURCE).expect("Failed to read nonbonded_forces.cu");
+    let hip = cuda.replace("#include <cuda_runtime.h>", "#include <hip/hip_runtime.h>");
+    let hip_source = out_dir.join("nonbonded_forces.hip");
+    std::fs::write(&hip_source, hip).expect("Failed to write generated HIP source");
+
+    let arch = env::var("PRISM_HIP_ARCH").unwrap_or_else(|_| "gfx90a".to_string());
+    let output = out_dir.join("nonbonded_forces.hsaco");
+    let status = Command::new(find_hipcc())
+        .arg("--genco")
+        .arg(format!("--offload-arch={}", arch))
+        .arg("-O3")
+        .arg("-o")
+        .arg(&output)
+        .arg(&hip_source)
+        .status()
+        .expect("Failed to run hipcc. Ensure ROCm is installed.");
+    assert!(status.success(), "hipcc failed to compile {}", hip_source.display());
+}
+
+fn build_mpi_shim() {
+    println!("cargo:rerun-if-changed={}", MPI_SHIM_SOURCE);
+    println!("cargo:rerun-if-env-changed=MPICC");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let mpicc = env::var("MPICC").unwrap_or_else(|_| "mpicc".to_string());
+    let object = out_dir.join("mpi_shim.o");
+    let status = Command::new(&mpicc)
+        .args(["-c", "-fPIC", "-O2", "-o"])
+        .arg(&object)
+        .arg(MPI_SHIM_SOURCE)
+        .status()
+        .expect("Failed to run mpicc. Install MPI or point MPICC at the wrapper.");
+    assert!(status.success(), "{} failed to compile {}", mpicc, MPI_SHIM_SOURCE);
+    let status = Command::new(env::var("AR").unwrap_or_else(|_| "ar".to_string()))
+        .arg("crs")
+        .arg(out_dir.join("libprism_mpi_shim.a"))
+        .arg(&object)
+        .status()
+        .expect("Failed to run ar");
+    assert!(status.success(), "ar failed to archive the MPI shim");
+    println!("cargo:rustc-link-search=native={}", out_dir.display());
+    println!("cargo:rustc-link-lib=static=prism_mpi_shim");
+
+    // MPICH answers -show, Open MPI --showme:link
+    let link_flags = ["-show", "--showme:link"]
+        .iter()
+        .filter_map(|flag| Command::new(&mpicc).arg(flag).output().ok())
+        .find(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        .expect("mpicc reported no link flags");
+    for token in link_flags.split_whitespace() {
+        if let Some(dir) = token.strip_prefix("-L") {
+            println!("cargo:rustc-link-search=native={}", dir);
+        } else if let Some(lib) = token.strip_prefix("-l") {
+            println!("cargo:rustc-link-lib={}", lib);
+        }
+    }
+}
+
+fn find_hipcc() -> PathBuf {
+    for var in ["HIP_PATH", "ROCM_PATH"] {
+        if let Ok(root) = env::var(var) {
+            let hipcc = PathBuf::from(root).join("bin/hipcc");
+            if hipcc.exists() {
+                return hipcc;
+            }
+        }
+    }
+    let default = PathBuf::from("/opt/rocm/bin/hipcc");
+    if default.exists() {
+        return default;
+    }
+    PathBuf::from("hipcc")
+}
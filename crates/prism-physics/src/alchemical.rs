@@ -0,0 +1,954 @@
+//! # Alchemical Free Energies - FEP/TI over a Lambda Schedule
+//! [`AlchemicalCoupling`] is a bias that replaces the cutoff LJ and Coulomb
+//! pair terms between a set of alchemical atoms and the rest of the system
+//! with soft-core forms scaled by a [`LambdaState`] (1 = fully coupled,
+//! 0 = decoupled):
+//!
+//! - LJ (Beutler): `λv 4ε [1/x² - 1/x]`, `x = α (1 - λv) + (r/σ)^6`
+//! - Coulomb: `λc k qq / sqrt(β (1 - λc) + r²)`
+//!
+//! both under the force field's switching function, so at `λ = 1` the bias
+//! vanishes. Interactions within the alchemical atoms, their bonded terms
+//! and their 1-4 pairs stay at full strength (decoupling). Charged
+//! alchemical atoms need cutoff electrostatics: PME mesh and implicit
+//! solvent terms of their charges are not scaled, so those are rejected.
+//!
+//! [`AlchemicalFreeEnergy`] walks an engine through the windows, each
+//! continuing from the previous one's final configuration, and writes per
+//! window a time series of `dU/dλc`, `dU/dλv` and `U(λ_k) - U(λ)` for every
+//! window `k` (the `dhdl` layout read by alchemlyb), plus `lambdas.dat`.
+//! Thermodynamic integration over the schedule and [`mbar`] estimate the
+//! free energy from the first to the last window. Units: kcal/mol, Å, ps.
+
+use crate::collective_variables::BiasPotential;
+use crate::force_field::{ForceField, COULOMB_CONSTANT};
+use crate::molecular_dynamics::MolecularDynamicsEngine;
+use prism_core::{PhaseOutcome, PrismError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Self-consistent MBAR iterations before giving up
+const MBAR_MAX_ITERATIONS: usize = 10_000;
+/// Largest change of any reduced free energy at convergence
+const MBAR_TOLERANCE: f64 = 1e-10;
+
+/// Coupling of the alchemical atoms to their environment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LambdaState {
+    /// Electrostatic coupling λc
+    pub coulomb: f64,
+    /// Lennard-Jones coupling λv
+    pub vdw: f64,
+}
+
+impl LambdaState {
+    pub const COUPLED: Self = Self {
+        coulomb: 1.0,
+        vdw: 1.0,
+    };
+}
+
+/// Soft-core parameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoftCore {
+    /// LJ soft-core α (dimensionless, in units of σ⁶)
+    pub alpha: f64,
+    /// Coulomb soft-core β (Å²)
+    pub beta: f64,
+}
+
+impl Default for SoftCore {
+    fn default() -> Self {
+        Self {
+            alpha: 0.5,
+            beta: 10.0,
+        }
+    }
+}
+
+/// Soft-core pair energy and its derivatives, before switching
+#[derive(Debug, Clone, Copy, Default)]
+struct SoftCoreTerm {
+    energy: f64,
+    de_dr: f64,
+    de_dcoulomb: f64,
+    de_dvdw: f64,
+}
+
+impl SoftCore {
+    /// `kqq` is the Coulomb prefactor `k q_i q_j / ε_r`
+    fn pair(&self, state: LambdaState, r: f64, sigma: f64, epsilon: f64, kqq: f64) -> SoftCoreTerm {
+        let mut term = SoftCoreTerm::default();
+        if epsilon != 0.0 && sigma > 0.0 && state.vdw > 0.0 {
+            let x = self.alpha * (1.0 - state.vdw) + (r / sigma).powi(6);
+            let shape = 1.0 / (x * x) - 1.0 / x;
+            let dshape_dx = -2.0 / (x * x * x) + 1.0 / (x * x);
+            let scale = 4.0 * epsilon;
+            term.energy += scale * state.vdw * shape;
+            term.de_dr += scale * state.vdw * dshape_dx * 6.0 * r.powi(5) / sigma.powi(6);
+            term.de_dvdw += scale * (shape - state.vdw * self.alpha * dshape_dx);
+        }
+        if kqq != 0.0 {
+            let y2 = self.beta * (1.0 - state.coulomb) + r * r;
+            let y = y2.sqrt();
+            term.energy += state.coulomb * kqq / y;
+            term.de_dr -= state.coulomb * kqq * r / (y2 * y);
+            term.de_dcoulomb += kqq / y + state.coulomb * kqq * 0.5 * self.beta / (y2 * y);
+        }
+        term
+    }
+}
+
+/// One recorded configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlchemicalSample {
+    pub step: u64,
+    /// `[dU/dλc, dU/dλv]` at the simulated state (kcal/mol)
+    pub du_dlambda: [f64; 2],
+    /// `U(λ_k) - U(λ)` for every state of the schedule (kcal/mol)
+    pub delta_u: Vec<f64>,
+}
+
+/// Soft-core coupling of the alchemical atoms at one λ state
+#[derive(Debug, Clone)]
+pub struct AlchemicalCoupling {
+    force_field: ForceField,
+    atoms: Vec<u32>,
+    alchemical: Vec<bool>,
+    state: LambdaState,
+    soft_core: SoftCore,
+    /// States evaluated for the energy differences of each sample
+    schedule: Vec<LambdaState>,
+    /// Steps between recorded samples; 0 disables recording
+    sample_interval: u64,
+    samples: Vec<AlchemicalSample>,
+}
+
+impl AlchemicalCoupling {
+    /// Couple `atoms` to the rest of the system described by `force_field`
+    /// (the engine's, see [`MolecularDynamicsEngine::force_field`])
+    pub fn new(
+        force_field: ForceField,
+        atoms: Vec<u32>,
+        state: LambdaState,
+        soft_core: SoftCore,
+    ) -> Result<Self, PrismError> {
+        let n = force_field.num_atoms();
+        if atoms.is_empty() || atoms.iter().any(|&i| i as usize >= n) {
+            return Err(PrismError::config(format!(
+                "Alchemical atoms must be a non-empty subset of the {} atoms",
+                n
+            )));
+        }
+        check_state(state)?;
+        let charged = atoms
+            .iter()
+            .any(|&i| force_field.params()[i as usize].charge != 0.0);
+        if charged && (force_field.pme().is_some() || force_field.implicit_solvent().is_some()) {
+            return Err(PrismError::config(
+                "Charged alchemical atoms need cutoff electrostatics (no PME or implicit solvent)",
+            ));
+        }
+        let mut alchemical = vec![false; n];
+        for &i in &atoms {
+            alchemical[i as usize] = true;
+        }
+        Ok(Self {
+            force_field,
+            atoms,
+            alchemical,
+            state,
+            soft_core,
+            schedule: Vec::new(),
+            sample_interval: 0,
+            samples: Vec::new(),
+        })
+    }
+
+    /// Record `dU/dλ` and the energy differences to every state of
+    /// `schedule` every `interval` steps
+    pub fn with_sampling(mut self, schedule: Vec<LambdaState>, interval: u64) -> Self {
+        self.schedule = schedule;
+        self.sample_interval = interval;
+        self
+    }
+
+    pub fn state(&self) -> LambdaState {
+        self.state
+    }
+
+    pub fn atoms(&self) -> &[u32] {
+        &self.atoms
+    }
+
+    pub fn samples(&self) -> &[AlchemicalSample] {
+        &self.samples
+    }
+
+    pub fn clear_samples(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Visit every alchemical-environment pair within the cutoff with
+    /// `(i, j, d = x_j - x_i, r, switch, dswitch/dr, σ, ε, kqq)`
+    fn for_each_pair(
+        &self,
+        positions: &[f32],
+        mut visit: impl FnMut(usize, usize, [f32; 3], f64, (f64, f64), (f64, f64, f64)),
+    ) {
+        let ff = &self.force_field;
+        let n = self.alchemical.len().min(positions.len() / 4);
+        let rc = ff.config().cutoff as f64;
+        let coulomb = COULOMB_CONSTANT / ff.config().dielectric as f64;
+        let params = ff.params();
+        for &i in &self.atoms {
+            let i = i as usize;
+            for j in (0..n).filter(|&j| !self.alchemical[j]) {
+                if ff.is_excluded(i, j) {
+                    continue;
+                }
+                let d = ff.delta(positions, i, j);
+                let r = ((d[0] * d[0] + d[1] * d[1] + d[2] * d[2]) as f64).sqrt();
+                if r >= rc || r == 0.0 {
+                    continue;
+                }
+                let (sigma, epsilon) = ff.mixed_lj(params, i, j);
+                let kqq = coulomb * (params[i].charge * params[j].charge) as f64;
+                visit(i, j, d, r, ff.switch(r), (sigma, epsilon, kqq));
+            }
+        }
+    }
+
+    /// `dU/dλ` at the simulated state and `U(λ_k) - U(λ)` over the schedule
+    pub fn sample(&self, step: u64, positions: &[f32]) -> AlchemicalSample {
+        let mut du_dlambda = [0.0; 2];
+        let mut current = 0.0;
+        let mut energies = vec![0.0; self.schedule.len()];
+        self.for_each_pair(positions, |_, _, _, r, (s, _), (sigma, epsilon, kqq)| {
+            let term = self.soft_core.pair(self.state, r, sigma, epsilon, kqq);
+            current += s * term.energy;
+            du_dlambda[0] += s * term.de_dcoulomb;
+            du_dlambda[1] += s * term.de_dvdw;
+            for (energy, &state) in energies.iter_mut().zip(&self.schedule) {
+                *energy += s * self.soft_core.pair(state, r, sigma, epsilon, kqq).energy;
+            }
+        });
+        AlchemicalSample {
+            step,
+            du_dlambda,
+            delta_u: energies.into_iter().map(|e| e - current).collect(),
+        }
+    }
+}
+
+impl BiasPotential for AlchemicalCoupling {
+    fn name(&self) -> &str {
+        "alchemical"
+    }
+
+    fn compute(&self, positions: &[f32], forces: &mut [f32]) -> f64 {
+        let mut energy = 0.0;
+        self.for_each_pair(positions, |i, j, d, r, (s, ds), (sigma, epsilon, kqq)| {
+            let term = self.soft_core.pair(self.state, r, sigma, epsilon, kqq);
+            // Remove the force field's own interaction of the pair
+            let Some((full, full_f_over_r)) =
+                self.force_field.pair_interaction(i, j, (r * r) as f32)
+            else {
+                return;
+            };
+            energy += s * term.energy - full.total();
+            let f_over_r = -(ds * term.energy + s * term.de_dr) / r - full_f_over_r;
+            for k in 0..3 {
+                let fk = (f_over_r * d[k] as f64) as f32;
+                forces[i * 4 + k] -= fk;
+                forces[j * 4 + k] += fk;
+            }
+        });
+        energy
+    }
+
+    fn update(&mut self, step: u64, positions: &[f32]) {
+        if self.sample_interval > 0 && step.is_multiple_of(self.sample_interval) {
+            let sample = self.sample(step, positions);
+            self.samples.push(sample);
+        }
+    }
+
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        vec![
+            ("lambda_coulomb".to_string(), json!(self.state.coulomb)),
+            ("lambda_vdw".to_string(), json!(self.state.vdw)),
+        ]
+    }
+}
+
+fn check_state(state: LambdaState) -> Result<(), PrismError> {
+    if !(0.0..=1.0).contains(&state.coulomb) || !(0.0..=1.0).contains(&state.vdw) {
+        return Err(PrismError::config(format!(
+            "Lambda values must lie in [0, 1], got {:?}",
+            state
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlchemicalConfig {
+    /// Selection of the atoms decoupled from their environment
+    pub region: String,
+    /// λ states in run order
+    pub windows: Vec<LambdaState>,
+    pub soft_core: SoftCore,
+    /// Unrecorded steps at the start of each window
+    pub equilibration_steps: u64,
+    /// Recorded steps per window
+    pub production_steps: u64,
+    /// Steps between recorded samples
+    pub sample_interval: u64,
+    /// Directory for the window time series and `lambdas.dat`
+    pub output_dir: PathBuf,
+}
+
+impl Default for AlchemicalConfig {
+    fn default() -> Self {
+        Self {
+            region: "resname LIG".to_string(),
+            windows: Self::decoupling_schedule(5, 11),
+            soft_core: SoftCore::default(),
+            equilibration_steps: 5_000,
+            production_steps: 50_000,
+            sample_interval: 100,
+            output_dir: PathBuf::from("alchemical"),
+        }
+    }
+}
+
+impl AlchemicalConfig {
+    /// Coupled to decoupled: `coulomb` evenly spaced states switching the
+    /// charges off at full LJ, then `vdw` states switching LJ off at zero
+    /// charge (the shared endpoint counted once)
+    pub fn decoupling_schedule(coulomb: usize, vdw: usize) -> Vec<LambdaState> {
+        let ramp = |count: usize| -> Vec<f64> {
+            match count {
+                0 => Vec::new(),
+                1 => vec![1.0],
+                _ => (0..count)
+                    .map(|k| 1.0 - k as f64 / (count - 1) as f64)
+                    .collect(),
+            }
+        };
+        let mut states: Vec<LambdaState> = ramp(coulomb)
+            .into_iter()
+            .map(|coulomb| LambdaState { coulomb, vdw: 1.0 })
+            .collect();
+        let vdw_start = if states.last().is_some_and(|s| s.coulomb == 0.0) {
+            1
+        } else {
+            0
+        };
+        states.extend(
+            ramp(vdw)
+                .into_iter()
+                .skip(vdw_start)
+                .map(|vdw| LambdaState { coulomb: 0.0, vdw }),
+        );
+        states
+    }
+}
+
+/// Per-window summary of the recorded samples
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LambdaWindowSummary {
+    pub state: LambdaState,
+    pub samples: usize,
+    /// `[⟨dU/dλc⟩, ⟨dU/dλv⟩]` (kcal/mol)
+    pub mean_du_dlambda: [f64; 2],
+    pub path: PathBuf,
+}
+
+/// Free energy of each window relative to the first (kcal/mol)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FreeEnergyEstimate {
+    /// Trapezoidal thermodynamic integration of `⟨dU/dλ⟩`
+    pub ti: Vec<f64>,
+    /// [`mbar`] over all windows
+    pub mbar: Vec<f64>,
+}
+
+impl FreeEnergyEstimate {
+    /// Free energy change from the first to the last window (MBAR)
+    pub fn delta_g(&self) -> f64 {
+        self.mbar.last().copied().unwrap_or(0.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlchemicalFreeEnergy {
+    config: AlchemicalConfig,
+    summaries: Vec<LambdaWindowSummary>,
+    estimate: Option<FreeEnergyEstimate>,
+}
+
+impl AlchemicalFreeEnergy {
+    pub fn new(config: AlchemicalConfig) -> Result<Self, PrismError> {
+        if config.windows.len() < 2 {
+            return Err(PrismError::config(
+                "Alchemical transformations need at least two lambda windows",
+            ));
+        }
+        for &state in &config.windows {
+            check_state(state)?;
+        }
+        if config.soft_core.alpha < 0.0 || config.soft_core.beta < 0.0 {
+            return Err(PrismError::config("Soft-core α and β must not be negative"));
+        }
+        if config.sample_interval == 0 || config.production_steps < config.sample_interval {
+            return Err(PrismError::config(
+                "Alchemical production must span at least one positive sample interval",
+            ));
+        }
+        Ok(Self {
+            config,
+            summaries: Vec::new(),
+            estimate: None,
+        })
+    }
+
+    pub fn config(&self) -> &AlchemicalConfig {
+        &self.config
+    }
+
+    /// Summaries of the windows completed so far
+    pub fn summaries(&self) -> &[LambdaWindowSummary] {
+        &self.summaries
+    }
+
+    /// Estimate of the last completed run
+    pub fn estimate(&self) -> Option<&FreeEnergyEstimate> {
+        self.estimate.as_ref()
+    }
+
+    /// Run every window on `engine` in order. The thermostat's final
+    /// `k_B T` sets the MBAR temperature, so runs should not anneal.
+    pub fn run(
+        &mut self,
+        engine: &mut MolecularDynamicsEngine,
+    ) -> Result<PhaseOutcome, PrismError> {
+        let atoms = engine.select(&self.config.region)?;
+        if atoms.is_empty() {
+            return Err(PrismError::config(format!(
+                "Alchemical region '{}' selects no atoms",
+                self.config.region
+            )));
+        }
+        let force_field = engine
+            .force_field()
+            .ok_or_else(|| PrismError::config("Alchemical transformations need a force field"))?
+            .clone();
+        let dir = &self.config.output_dir;
+        std::fs::create_dir_all(dir).map_err(|e| {
+            PrismError::Internal(format!("Failed to create {}: {}", dir.display(), e))
+        })?;
+        log::info!(
+            "⚗️ Alchemical decoupling of {} atoms: {} windows, {} + {} steps each",
+            atoms.len(),
+            self.config.windows.len(),
+            self.config.equilibration_steps,
+            self.config.production_steps
+        );
+        self.summaries.clear();
+        self.estimate = None;
+        let dt = engine.config().dt as f64;
+        let kt = engine.config().temp_end as f64;
+        let mut samples = Vec::new();
+
+        for (index, &state) in self.config.windows.iter().enumerate() {
+            let coupling = AlchemicalCoupling::new(
+                force_field.clone(),
+                atoms.clone(),
+                state,
+                self.config.soft_core,
+            )?
+            .with_sampling(self.config.windows.clone(), self.config.sample_interval);
+            engine.add_bias(Box::new(coupling));
+            if self.config.equilibration_steps > 0 {
+                engine.run_nlnm_breathing(self.config.equilibration_steps)?;
+            }
+            // Equilibration samples are discarded; the coupling is the last bias attached
+            let mut coupling = pop_coupling(engine)?;
+            coupling.clear_samples();
+            engine.add_bias(coupling);
+            engine.run_nlnm_breathing(self.config.production_steps)?;
+
+            let coupling = pop_coupling(engine)?;
+            let path = dir.join(format!("window_{:03}.dat", index));
+            write_time_series(&path, &coupling, dt, kt)?;
+
+            let n = coupling.samples().len().max(1) as f64;
+            let mean = [0, 1].map(|c| {
+                coupling
+                    .samples()
+                    .iter()
+                    .map(|s| s.du_dlambda[c])
+                    .sum::<f64>()
+                    / n
+            });
+            log::info!(
+                "⚗️ Window {} (λc = {:.3}, λv = {:.3}): <dU/dλc> = {:.3}, <dU/dλv> = {:.3} over {} samples",
+                index,
+                state.coulomb,
+                state.vdw,
+                mean[0],
+                mean[1],
+                coupling.samples().len()
+            );
+            self.summaries.push(LambdaWindowSummary {
+                state,
+                samples: coupling.samples().len(),
+                mean_du_dlambda: mean,
+                path,
+            });
+            samples.push(coupling.samples().to_vec());
+        }
+
+        let estimate = estimate(&self.summaries, &samples, kt)?;
+        log::info!(
+            "⚗️ ΔG = {:.3} kcal/mol (MBAR), {:.3} kcal/mol (TI)",
+            estimate.delta_g(),
+            estimate.ti.last().copied().unwrap_or(0.0)
+        );
+        self.write_lambdas(&estimate)?;
+
+        let mut telemetry = HashMap::new();
+        telemetry.insert("windows".to_string(), json!(self.summaries.len()));
+        telemetry.insert("delta_g_mbar".to_string(), json!(estimate.delta_g()));
+        telemetry.insert("delta_g_ti".to_string(), json!(estimate.ti.last()));
+        telemetry.insert("free_energies_mbar".to_string(), json!(estimate.mbar));
+        telemetry.insert("lambdas".to_string(), json!(dir.join("lambdas.dat")));
+        self.estimate = Some(estimate);
+        Ok(PhaseOutcome::Success {
+            message: "Alchemical free energy complete".to_string(),
+            telemetry,
+        })
+    }
+
+    /// `index λc λv path G_mbar G_ti` per window, in run order
+    fn write_lambdas(&self, estimate: &FreeEnergyEstimate) -> Result<(), PrismError> {
+        let path = self.config.output_dir.join("lambdas.dat");
+        let io = |e: std::io::Error| {
+            PrismError::Internal(format!("Failed to write {}: {}", path.display(), e))
+        };
+        let mut out = std::io::BufWriter::new(std::fs::File::create(&path).map_err(io)?);
+        writeln!(out, "# index lambda_coulomb lambda_vdw path G_mbar G_ti").map_err(io)?;
+        for (index, window) in self.summaries.iter().enumerate() {
+            writeln!(
+                out,
+                "{} {:.6} {:.6} {} {:.6} {:.6}",
+                index,
+                window.state.coulomb,
+                window.state.vdw,
+                window.path.display(),
+                estimate.mbar[index],
+                estimate.ti[index]
+            )
+            .map_err(io)?;
+        }
+        out.flush().map_err(io)
+    }
+}
+
+/// TI and MBAR free energies of each window relative to the first
+fn estimate(
+    summaries: &[LambdaWindowSummary],
+    samples: &[Vec<AlchemicalSample>],
+    kt: f64,
+) -> Result<FreeEnergyEstimate, PrismError> {
+    let mut ti = vec![0.0];
+    for pair in summaries.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        let step = 0.5
+            * ((a.mean_du_dlambda[0] + b.mean_du_dlambda[0]) * (b.state.coulomb - a.state.coulomb)
+                + (a.mean_du_dlambda[1] + b.mean_du_dlambda[1]) * (b.state.vdw - a.state.vdw));
+        ti.push(ti.last().unwrap_or(&0.0) + step);
+    }
+    if kt <= 0.0 {
+        return Err(PrismError::config(
+            "MBAR needs a positive thermostat temperature",
+        ));
+    }
+    let reduced: Vec<Vec<f64>> = samples
+        .iter()
+        .flatten()
+        .map(|s| s.delta_u.iter().map(|u| u / kt).collect())
+        .collect();
+    let counts: Vec<usize> = samples.iter().map(Vec::len).collect();
+    let mbar = mbar(&reduced, &counts)?
+        .into_iter()
+        .map(|f| f * kt)
+        .collect();
+    Ok(FreeEnergyEstimate { ti, mbar })
+}
+
+fn log_sum_exp(values: impl Iterator<Item = f64> + Clone) -> f64 {
+    let max = values.clone().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+/// Reduced free energies `f_k` (units of kT, `f_0 = 0`) of the states by
+/// the multistate Bennett acceptance ratio. `reduced[n][k]` is the reduced
+/// energy of sample `n` in state `k`, up to a per-sample constant; samples
+/// are ordered by the state they were drawn from, `counts[k]` of each.
+pub fn mbar(reduced: &[Vec<f64>], counts: &[usize]) -> Result<Vec<f64>, PrismError> {
+    let states = counts.len();
+    if counts.iter().sum::<usize>() != reduced.len() || reduced.iter().any(|u| u.len() != states) {
+        return Err(PrismError::validation(format!(
+            "MBAR needs {} reduced energies per sample for {} samples",
+            states,
+            counts.iter().sum::<usize>()
+        )));
+    }
+    let log_counts: Vec<f64> = counts.iter().map(|&n| (n as f64).ln()).collect();
+    let mut f = vec![0.0; states];
+    for _ in 0..MBAR_MAX_ITERATIONS {
+        let log_denominator: Vec<f64> = reduced
+            .iter()
+            .map(|u| log_sum_exp((0..states).map(|l| log_counts[l] + f[l] - u[l])))
+            .collect();
+        let mut next: Vec<f64> = (0..states)
+            .map(|k| -log_sum_exp(reduced.iter().zip(&log_denominator).map(|(u, d)| -u[k] - d)))
+            .collect();
+        let shift = next[0];
+        next.iter_mut().for_each(|v| *v -= shift);
+        let change = next
+            .iter()
+            .zip(&f)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        f = next;
+        if change < MBAR_TOLERANCE {
+            return Ok(f);
+        }
+    }
+    Err(PrismError::numerical(
+        "MBAR did not converge; the windows probably do not overlap",
+    ))
+}
+
+fn pop_coupling(
+    engine: &mut MolecularDynamicsEngine,
+) -> Result<Box<AlchemicalCoupling>, PrismError> {
+    engine
+        .pop_bias()
+        .and_then(|b| (b as Box<dyn Any>).downcast::<AlchemicalCoupling>().ok())
+        .ok_or_else(|| PrismError::internal("Alchemical coupling missing from engine"))
+}
+
+fn write_time_series(
+    path: &Path,
+    coupling: &AlchemicalCoupling,
+    dt: f64,
+    kt: f64,
+) -> Result<(), PrismError> {
+    let io = |e: std::io::Error| {
+        PrismError::Internal(format!("Failed to write {}: {}", path.display(), e))
+    };
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path).map_err(io)?);
+    let columns: Vec<String> = (0..coupling.schedule.len())
+        .map(|k| format!("dU_{:03}", k))
+        .collect();
+    writeln!(
+        out,
+        "#! FIELDS time dU/dl_coulomb dU/dl_vdw {}",
+        columns.join(" ")
+    )
+    .map_err(io)?;
+    writeln!(out, "#! SET lambda_coulomb {:.6}", coupling.state.coulomb).map_err(io)?;
+    writeln!(out, "#! SET lambda_vdw {:.6}", coupling.state.vdw).map_err(io)?;
+    writeln!(out, "#! SET kT {:.6}", kt).map_err(io)?;
+    for sample in coupling.samples() {
+        let delta: Vec<String> = sample.delta_u.iter().map(|u| format!("{:.6}", u)).collect();
+        writeln!(
+            out,
+            "{:.4} {:.6} {:.6} {}",
+            sample.step as f64 * dt,
+            sample.du_dlambda[0],
+            sample.du_dlambda[1],
+            delta.join(" ")
+        )
+        .map_err(io)?;
+    }
+    out.flush().map_err(io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::force_field::{ForceFieldConfig, NonbondedParams};
+    use rand::SeedableRng;
+    use rand_distr::{Distribution, Normal};
+
+    /// Charged two-atom solute (0, 1) next to three solvent atoms
+    fn system() -> (ForceField, Vec<f32>) {
+        let params = [(0.4, 3.0), (-0.4, 2.5), (0.3, 3.2), (-0.5, 3.0), (0.2, 2.8)]
+            .iter()
+            .map(|&(charge, sigma)| NonbondedParams {
+                sigma,
+                epsilon: 0.15,
+                charge,
+            })
+            .collect();
+        let mut ff = ForceField::new(ForceFieldConfig::default(), params);
+        ff.set_exclusions([(0, 1)]);
+        let coords = [
+            [0.0, 0.0, 0.0],
+            [1.2, 0.3, 0.0],
+            [3.1, 0.5, 0.4],
+            [-2.6, 1.4, 0.2],
+            [0.8, -3.0, -1.1],
+        ];
+        let positions = coords
+            .iter()
+            .flat_map(|x| [x[0], x[1], x[2], 12.0])
+            .collect();
+        (ff, positions)
+    }
+
+    #[test]
+    fn test_coupled_state_cancels_and_forces_match_energy() {
+        let (ff, x) = system();
+        let coupled = AlchemicalCoupling::new(
+            ff.clone(),
+            vec![0, 1],
+            LambdaState::COUPLED,
+            SoftCore::default(),
+        )
+        .unwrap();
+        let mut forces = vec![0.0f32; x.len()];
+        assert!(coupled.compute(&x, &mut forces).abs() < 1e-9);
+        assert!(forces.iter().all(|f| f.abs() < 1e-4));
+
+        let state = LambdaState {
+            coulomb: 0.4,
+            vdw: 0.7,
+        };
+        let coupling = AlchemicalCoupling::new(ff, vec![0, 1], state, SoftCore::default()).unwrap();
+        let mut forces = vec![0.0f32; x.len()];
+        coupling.compute(&x, &mut forces);
+        let h = 1e-3;
+        for (k, &f) in forces.iter().enumerate().filter(|(k, _)| k % 4 != 3) {
+            let energy_at = |delta: f32| {
+                let mut moved = x.clone();
+                moved[k] += delta;
+                coupling.compute(&moved, &mut vec![0.0; x.len()])
+            };
+            let numeric = -(energy_at(h) - energy_at(-h)) / (2.0 * h as f64);
+            assert!(
+                (numeric - f as f64).abs() < 1e-2 * (f as f64).abs().max(1.0),
+                "coordinate {}: {} vs {}",
+                k,
+                numeric,
+                f
+            );
+        }
+    }
+
+    #[test]
+    fn test_du_dlambda_matches_energy_differences() {
+        let (ff, x) = system();
+        let state = LambdaState {
+            coulomb: 0.5,
+            vdw: 0.5,
+        };
+        let h = 1e-5;
+        let schedule = vec![
+            LambdaState {
+                coulomb: 0.5 + h,
+                ..state
+            },
+            LambdaState {
+                vdw: 0.5 + h,
+                ..state
+            },
+            state,
+        ];
+        let coupling = AlchemicalCoupling::new(ff, vec![0, 1], state, SoftCore::default())
+            .unwrap()
+            .with_sampling(schedule, 1);
+        let sample = coupling.sample(0, &x);
+        assert_eq!(sample.delta_u[2], 0.0);
+        for c in 0..2 {
+            let numeric = sample.delta_u[c] / h;
+            assert!(
+                (numeric - sample.du_dlambda[c]).abs() < 1e-3 * sample.du_dlambda[c].abs().max(1.0),
+                "{}: {} vs {}",
+                c,
+                numeric,
+                sample.du_dlambda[c]
+            );
+        }
+
+        let decoupled = LambdaState {
+            coulomb: 0.0,
+            vdw: 0.0,
+        };
+        let (ff, x) = system();
+        let coupling =
+            AlchemicalCoupling::new(ff.clone(), vec![0, 1], decoupled, SoftCore::default())
+                .unwrap()
+                .with_sampling(vec![decoupled], 1);
+        // Fully decoupled: the bias cancels the solute-solvent interaction
+        let full: f64 = [0, 1]
+            .iter()
+            .flat_map(|&i| (2..5).map(move |j| (i, j)))
+            .filter_map(|(i, j)| {
+                let d = ff.delta(&x, i, j);
+                ff.pair_interaction(i, j, d[0] * d[0] + d[1] * d[1] + d[2] * d[2])
+            })
+            .map(|(e, _)| e.total())
+            .sum();
+        let bias = coupling.compute(&x, &mut vec![0.0; x.len()]);
+        assert!((bias + full).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_decoupling_schedule_and_validation() {
+        let windows = AlchemicalConfig::decoupling_schedule(3, 3);
+        let pairs: Vec<(f64, f64)> = windows.iter().map(|s| (s.coulomb, s.vdw)).collect();
+        assert_eq!(
+            pairs,
+            vec![(1.0, 1.0), (0.5, 1.0), (0.0, 1.0), (0.0, 0.5), (0.0, 0.0)]
+        );
+        assert_eq!(AlchemicalConfig::default().windows.len(), 15);
+
+        let config = AlchemicalConfig {
+            windows: vec![LambdaState::COUPLED],
+            ..Default::default()
+        };
+        assert!(AlchemicalFreeEnergy::new(config).is_err());
+        let config = AlchemicalConfig {
+            windows: vec![
+                LambdaState::COUPLED,
+                LambdaState {
+                    coulomb: 1.5,
+                    vdw: 1.0,
+                },
+            ],
+            ..Default::default()
+        };
+        assert!(AlchemicalFreeEnergy::new(config).is_err());
+    }
+
+    #[test]
+    fn test_windows_write_series_and_estimates() {
+        use crate::molecular_dynamics::MolecularDynamicsConfig;
+        use prism_io::sovereign_types::Atom;
+        use prism_io::topology::{LjParams, Topology};
+
+        // A neutral LJ particle (index 3) among three charged atoms
+        let coords = [
+            [0.0, 0.0, 0.0],
+            [4.0, 0.5, 0.0],
+            [0.5, 4.2, 0.3],
+            [2.0, 2.0, 0.5],
+        ];
+        let atoms = coords
+            .iter()
+            .enumerate()
+            .map(|(i, &coords)| Atom {
+                coords,
+                element: 6,
+                residue_id: 0,
+                atom_type: 1,
+                charge: [0.3, -0.3, 0.2, 0.0][i],
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        let topology = Topology {
+            atoms,
+            masses: vec![12.0; 4],
+            lj: vec![
+                LjParams {
+                    sigma: 3.2,
+                    epsilon: 0.1
+                };
+                4
+            ],
+            ..Default::default()
+        };
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            dt: 0.001,
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &topology).unwrap();
+        let dir = std::env::temp_dir().join(format!("prism_alchemical_{}", std::process::id()));
+        let mut fep = AlchemicalFreeEnergy::new(AlchemicalConfig {
+            region: "index 3".to_string(),
+            windows: AlchemicalConfig::decoupling_schedule(2, 2),
+            equilibration_steps: 10,
+            production_steps: 20,
+            sample_interval: 5,
+            output_dir: dir.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        fep.run(&mut engine).unwrap();
+
+        assert_eq!(fep.summaries().len(), 3);
+        assert!(fep.summaries().iter().all(|w| w.samples == 4));
+        let estimate = fep.estimate().unwrap();
+        assert_eq!((estimate.ti.len(), estimate.mbar.len()), (3, 3));
+        assert!(estimate.delta_g().is_finite() && estimate.ti[2].is_finite());
+        let series = std::fs::read_to_string(dir.join("window_001.dat")).unwrap();
+        assert!(series.starts_with("#! FIELDS time dU/dl_coulomb dU/dl_vdw dU_000 dU_001 dU_002"));
+        assert_eq!(series.lines().filter(|l| !l.starts_with('#')).count(), 4);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("lambdas.dat"))
+                .unwrap()
+                .lines()
+                .count(),
+            4
+        );
+        // Nothing left attached to the engine
+        assert!(engine.pop_bias().is_none());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_mbar_recovers_harmonic_free_energy() {
+        // u_k(x) = k_k x² / 2 in units of kT; f_1 - f_0 = ln(k_1 / k_0) / 2
+        let stiffness = [1.0, 2.0, 4.0];
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(7);
+        let mut reduced = Vec::new();
+        for &k in &stiffness {
+            let normal = Normal::new(0.0, 1.0 / f64::sqrt(k)).unwrap();
+            for _ in 0..4000 {
+                let x: f64 = normal.sample(&mut rng);
+                reduced.push(stiffness.iter().map(|&kk| 0.5 * kk * x * x).collect());
+            }
+        }
+        let f = mbar(&reduced, &[4000; 3]).unwrap();
+        for (k, &kk) in stiffness.iter().enumerate() {
+            let exact = 0.5 * (kk / stiffness[0]).ln();
+            assert!(
+                (f[k] - exact).abs() < 0.03,
+                "state {}: {} vs {}",
+                k,
+                f[k],
+                exact
+            );
+        }
+        assert!(mbar(&reduced, &[4000; 2]).is_err());
+    }
+}
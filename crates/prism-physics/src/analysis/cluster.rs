@@ -0,0 +1,405 @@
+//! # Conformational Clustering
+//! Groups stored frames by their pairwise RMSD after superposition:
+//!
+//! - GROMOS (Daura et al. 1999): the frame with the most neighbours within
+//!   `cutoff` becomes a medoid and takes those neighbours as its cluster;
+//!   both are removed and the search repeats until every frame is assigned
+//! - k-medoids: greedy BUILD initialisation followed by alternating
+//!   assignment to the nearest medoid and medoid update (the member with
+//!   the smallest summed distance to its cluster) until nothing changes
+//!
+//! The full `F x F` distance matrix is computed once, so memory and time
+//! grow quadratically with the number of frames; stride long trajectories
+//! first. Clusters are reported largest first, and their medoid frames can
+//! be written as PDB or `.ptb` structures.
+
+use super::rmsd::rmsd;
+use super::select_frame;
+use prism_core::PrismError;
+use prism_io::holographic::HolographicBinaryFormat;
+use prism_io::pdb::PdbStructure;
+use prism_io::sovereign_types::Atom;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ClusterMethod {
+    #[default]
+    Gromos,
+    KMedoids,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClusteringConfig {
+    pub method: ClusterMethod,
+    /// GROMOS neighbour cutoff (Å RMSD)
+    pub cutoff: f64,
+    /// Number of k-medoids clusters
+    pub num_clusters: usize,
+    /// k-medoids iteration limit
+    pub max_iterations: usize,
+    /// Atoms superposed and compared (all when empty)
+    pub atoms: Vec<u32>,
+}
+
+impl Default for ClusteringConfig {
+    fn default() -> Self {
+        Self {
+            method: ClusterMethod::Gromos,
+            cutoff: 1.5,
+            num_clusters: 5,
+            max_iterations: 100,
+            atoms: Vec::new(),
+        }
+    }
+}
+
+/// Output format of medoid structures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StructureFormat {
+    #[default]
+    Pdb,
+    Ptb,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cluster {
+    /// Frame index of the representative structure
+    pub medoid: usize,
+    /// Frame indices, in increasing order
+    pub members: Vec<usize>,
+    /// Fraction of all frames in the cluster
+    pub population: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clustering {
+    /// Largest cluster first
+    pub clusters: Vec<Cluster>,
+    /// Cluster index of each frame
+    pub assignments: Vec<usize>,
+}
+
+/// Pairwise RMSD (Å) of `atoms` (all when empty) between every two frames
+pub fn rmsd_matrix(frames: &[Vec<[f32; 3]>], atoms: &[u32]) -> Vec<Vec<f64>> {
+    let selected: Vec<Vec<[f64; 3]>> = frames.iter().map(|f| select_frame(f, atoms)).collect();
+    let n = selected.len();
+    let mut matrix = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let d = rmsd(&selected[i], &selected[j]);
+            matrix[i][j] = d;
+            matrix[j][i] = d;
+        }
+    }
+    matrix
+}
+
+/// Cluster `frames` (one `[x, y, z]` per atom each)
+pub fn cluster_frames(
+    frames: &[Vec<[f32; 3]>],
+    config: &ClusteringConfig,
+) -> Result<Clustering, PrismError> {
+    if frames.is_empty() {
+        return Err(PrismError::validation(
+            "Clustering needs at least one frame",
+        ));
+    }
+    match config.method {
+        ClusterMethod::Gromos if config.cutoff <= 0.0 => {
+            return Err(PrismError::config("GROMOS cutoff must be positive"))
+        }
+        ClusterMethod::KMedoids if config.num_clusters == 0 => {
+            return Err(PrismError::config("k-medoids needs at least one cluster"))
+        }
+        _ => {}
+    }
+    let distances = rmsd_matrix(frames, &config.atoms);
+    let groups = match config.method {
+        ClusterMethod::Gromos => gromos(&distances, config.cutoff),
+        ClusterMethod::KMedoids => k_medoids(
+            &distances,
+            config.num_clusters.min(frames.len()),
+            config.max_iterations,
+        ),
+    };
+    Ok(finish(groups, frames.len()))
+}
+
+/// `(medoid, members)` groups
+fn gromos(distances: &[Vec<f64>], cutoff: f64) -> Vec<(usize, Vec<usize>)> {
+    let mut remaining: Vec<usize> = (0..distances.len()).collect();
+    let mut groups = Vec::new();
+    while !remaining.is_empty() {
+        let neighbours = |i: usize| -> Vec<usize> {
+            remaining
+                .iter()
+                .copied()
+                .filter(|&j| distances[i][j] <= cutoff)
+                .collect()
+        };
+        let medoid = *remaining
+            .iter()
+            .max_by_key(|&&i| (neighbours(i).len(), std::cmp::Reverse(i)))
+            .expect("remaining is not empty");
+        let members = neighbours(medoid);
+        remaining.retain(|j| !members.contains(j));
+        groups.push((medoid, members));
+    }
+    groups
+}
+
+fn k_medoids(distances: &[Vec<f64>], k: usize, max_iterations: usize) -> Vec<(usize, Vec<usize>)> {
+    let n = distances.len();
+    let nearest = |medoids: &[usize], i: usize| -> (usize, f64) {
+        medoids
+            .iter()
+            .enumerate()
+            .map(|(c, &m)| (c, distances[i][m]))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or((0, f64::INFINITY))
+    };
+
+    // BUILD: start from the most central frame, then add the frame that
+    // lowers the summed distance to the nearest medoid the most
+    let mut medoids: Vec<usize> = Vec::with_capacity(k);
+    let mut closest = vec![f64::INFINITY; n];
+    while medoids.len() < k {
+        let gain = |c: usize| -> f64 {
+            if medoids.is_empty() {
+                return -distances[c].iter().sum::<f64>();
+            }
+            (0..n)
+                .map(|i| (closest[i] - distances[i][c]).max(0.0))
+                .sum()
+        };
+        let best = (0..n)
+            .filter(|i| !medoids.contains(i))
+            .max_by(|&a, &b| gain(a).total_cmp(&gain(b)).then(b.cmp(&a)))
+            .expect("k does not exceed the frame count");
+        for (d, row) in closest.iter_mut().zip(distances) {
+            *d = d.min(row[best]);
+        }
+        medoids.push(best);
+    }
+
+    let mut assignments: Vec<usize> = (0..n).map(|i| nearest(&medoids, i).0).collect();
+    for _ in 0..max_iterations {
+        let updated: Vec<usize> = (0..k)
+            .map(|c| {
+                let members: Vec<usize> = (0..n).filter(|&i| assignments[i] == c).collect();
+                members
+                    .iter()
+                    .copied()
+                    .min_by(|&a, &b| {
+                        let total =
+                            |m: usize| members.iter().map(|&j| distances[m][j]).sum::<f64>();
+                        total(a).total_cmp(&total(b))
+                    })
+                    .unwrap_or(medoids[c])
+            })
+            .collect();
+        let reassigned: Vec<usize> = (0..n).map(|i| nearest(&updated, i).0).collect();
+        let converged = updated == medoids && reassigned == assignments;
+        medoids = updated;
+        assignments = reassigned;
+        if converged {
+            break;
+        }
+    }
+    medoids
+        .iter()
+        .enumerate()
+        .map(|(c, &m)| (m, (0..n).filter(|&i| assignments[i] == c).collect()))
+        .filter(|(_, members): &(usize, Vec<usize>)| !members.is_empty())
+        .collect()
+}
+
+fn finish(mut groups: Vec<(usize, Vec<usize>)>, frames: usize) -> Clustering {
+    groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0)));
+    let mut assignments = vec![0; frames];
+    let clusters = groups
+        .into_iter()
+        .enumerate()
+        .map(|(c, (medoid, mut members))| {
+            members.sort_unstable();
+            for &i in &members {
+                assignments[i] = c;
+            }
+            Cluster {
+                medoid,
+                population: members.len() as f64 / frames as f64,
+                members,
+            }
+        })
+        .collect();
+    Clustering {
+        clusters,
+        assignments,
+    }
+}
+
+impl Clustering {
+    /// Write each cluster's medoid frame to `directory/cluster_<i>.<ext>`,
+    /// taking atom metadata from `atoms`
+    pub fn write_medoids(
+        &self,
+        frames: &[Vec<[f32; 3]>],
+        atoms: &[Atom],
+        directory: &Path,
+        format: StructureFormat,
+    ) -> Result<Vec<PathBuf>, PrismError> {
+        let io_error = |path: &Path, e: prism_io::PrismIoError| {
+            PrismError::Internal(format!("Medoid write to {} failed: {}", path.display(), e))
+        };
+        self.clusters
+            .iter()
+            .enumerate()
+            .map(|(c, cluster)| {
+                let mut medoid = atoms.to_vec();
+                for (atom, p) in medoid.iter_mut().zip(&frames[cluster.medoid]) {
+                    atom.coords = *p;
+                }
+                let structure = PdbStructure::from_atoms(&medoid);
+                let path = match format {
+                    StructureFormat::Pdb => {
+                        let path = directory.join(format!("cluster_{}.pdb", c));
+                        structure.write(&path).map_err(|e| io_error(&path, e))?;
+                        path
+                    }
+                    StructureFormat::Ptb => {
+                        let path = directory.join(format!("cluster_{}.ptb", c));
+                        let hash = blake3::hash(structure.to_pdb_string().as_bytes());
+                        HolographicBinaryFormat::new()
+                            .with_atoms(medoid)
+                            .with_source_hash(*hash.as_bytes())
+                            .write_to_file(&path)
+                            .map_err(|e| io_error(&path, e))?;
+                        path
+                    }
+                };
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_io::holographic::PtbStructure;
+
+    /// 12 frames near an extended shape and 8 near a bent one
+    fn two_states() -> Vec<Vec<[f32; 3]>> {
+        let extended = [
+            [0.0, 0.0, 0.0],
+            [3.8, 0.0, 0.0],
+            [7.6, 0.0, 0.0],
+            [11.4, 0.5, 0.0],
+        ];
+        let bent = [
+            [0.0, 0.0, 0.0],
+            [3.8, 0.0, 0.0],
+            [5.0, 3.6, 0.0],
+            [2.0, 5.6, 0.5],
+        ];
+        (0..20)
+            .map(|t| {
+                let base = if t < 12 { extended } else { bent };
+                let jitter = 0.05 * ((t * 7) % 5) as f32;
+                base.iter()
+                    .enumerate()
+                    .map(|(i, p)| [p[0] + jitter * (i % 2) as f32, p[1] - jitter, p[2] + jitter])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gromos_and_k_medoids_find_both_states() {
+        let frames = two_states();
+        let gromos = cluster_frames(
+            &frames,
+            &ClusteringConfig {
+                cutoff: 0.5,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(gromos.clusters.len(), 2);
+        assert_eq!(gromos.clusters[0].members, (0..12).collect::<Vec<_>>());
+        assert!((gromos.clusters[1].population - 0.4).abs() < 1e-12);
+        assert!(gromos.clusters[1]
+            .members
+            .contains(&gromos.clusters[1].medoid));
+
+        let config = ClusteringConfig {
+            method: ClusterMethod::KMedoids,
+            num_clusters: 2,
+            ..Default::default()
+        };
+        let k_medoids = cluster_frames(&frames, &config).unwrap();
+        assert_eq!(k_medoids.assignments, gromos.assignments);
+
+        let tight = cluster_frames(
+            &frames,
+            &ClusteringConfig {
+                cutoff: 1e-3,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(tight.clusters.len() > 2);
+        assert!(cluster_frames(&[], &ClusteringConfig::default()).is_err());
+        let none = ClusteringConfig {
+            num_clusters: 0,
+            ..config
+        };
+        assert!(cluster_frames(&frames, &none).is_err());
+    }
+
+    #[test]
+    fn test_medoids_written_as_pdb_and_ptb() {
+        let frames = two_states();
+        let clustering = cluster_frames(
+            &frames,
+            &ClusteringConfig {
+                cutoff: 0.5,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let atoms: Vec<Atom> = (0..4)
+            .map(|i| Atom {
+                coords: [0.0; 3],
+                element: 6,
+                residue_id: i,
+                atom_type: 0,
+                charge: 0.0,
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        let dir = std::env::temp_dir().join(format!("prism_clusters_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pdbs = clustering
+            .write_medoids(&frames, &atoms, &dir, StructureFormat::Pdb)
+            .unwrap();
+        let text = std::fs::read_to_string(&pdbs[1]).unwrap();
+        assert_eq!(
+            text.lines()
+                .filter(|l| l.starts_with("ATOM") || l.starts_with("HETATM"))
+                .count(),
+            4
+        );
+
+        let ptbs = clustering
+            .write_medoids(&frames, &atoms, &dir, StructureFormat::Ptb)
+            .unwrap();
+        let mut ptb = PtbStructure::load(&ptbs[0]).unwrap();
+        let medoid = clustering.clusters[0].medoid;
+        assert_eq!(ptb.atoms().unwrap()[2].coords, frames[medoid][2]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
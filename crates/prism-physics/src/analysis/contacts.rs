@@ -0,0 +1,281 @@
+//! # Native Contacts
+//! Contact map of a reference (native) structure and the fraction of
+//! native contacts `Q` of later frames, using the Best-Hummer-Eaton
+//! switching function
+//!
+//! `Q = 1/N Σ 1 / (1 + exp(β (r_ij - λ r0_ij)))`
+//!
+//! over atom pairs closer than `cutoff` in the reference and at least
+//! `min_separation` residues apart. `Q` near 1 means the native packing is
+//! intact; drops flag partial unfolding.
+
+use super::Analysis;
+use prism_core::PrismError;
+use prism_io::topology::Topology;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NativeContactConfig {
+    /// Reference distance below which a pair is a native contact (Å)
+    pub cutoff: f64,
+    /// Minimum residue index separation of a contact
+    pub min_separation: u16,
+    /// Switching steepness β (1/Å)
+    pub beta: f64,
+    /// Tolerance factor λ on the native distance
+    pub lambda: f64,
+}
+
+impl Default for NativeContactConfig {
+    fn default() -> Self {
+        Self {
+            cutoff: 4.5,
+            min_separation: 3,
+            beta: 5.0,
+            lambda: 1.8,
+        }
+    }
+}
+
+/// A pair in contact in the reference structure
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NativeContact {
+    pub i: u32,
+    pub j: u32,
+    /// Reference distance (Å)
+    pub distance: f64,
+}
+
+/// Native contacts among `atoms` of `reference`; `residues` labels each
+/// atom in `reference`
+pub fn contact_map(
+    reference: &[[f64; 3]],
+    residues: &[u16],
+    atoms: &[u32],
+    config: &NativeContactConfig,
+) -> Vec<NativeContact> {
+    let mut contacts = Vec::new();
+    for (a, &i) in atoms.iter().enumerate() {
+        for &j in &atoms[a + 1..] {
+            let (ri, rj) = (residues[i as usize], residues[j as usize]);
+            if ri.abs_diff(rj) < config.min_separation {
+                continue;
+            }
+            let distance = distance(reference[i as usize], reference[j as usize]);
+            if distance <= config.cutoff {
+                contacts.push(NativeContact { i, j, distance });
+            }
+        }
+    }
+    contacts
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// Fraction of native contacts over time
+#[derive(Debug, Clone)]
+pub struct NativeContacts {
+    config: NativeContactConfig,
+    contacts: Vec<NativeContact>,
+    residues: Vec<u16>,
+    series: Vec<(u64, f64)>,
+}
+
+impl NativeContacts {
+    /// Heavy-atom contacts of the topology coordinates
+    pub fn from_topology(
+        topology: &Topology,
+        config: NativeContactConfig,
+    ) -> Result<Self, PrismError> {
+        let reference: Vec<[f64; 3]> = topology
+            .atoms
+            .iter()
+            .map(|a| a.coords.map(|c| c as f64))
+            .collect();
+        let residues: Vec<u16> = topology.atoms.iter().map(|a| a.residue_id).collect();
+        let heavy: Vec<u32> = (0..topology.num_atoms() as u32)
+            .filter(|&i| topology.atoms[i as usize].element != 1)
+            .collect();
+        Self::new(&reference, residues, &heavy, config)
+    }
+
+    /// Contacts among `atoms` of `reference`, labelled by `residues`
+    pub fn new(
+        reference: &[[f64; 3]],
+        residues: Vec<u16>,
+        atoms: &[u32],
+        config: NativeContactConfig,
+    ) -> Result<Self, PrismError> {
+        let contacts = contact_map(reference, &residues, atoms, &config);
+        if contacts.is_empty() {
+            return Err(PrismError::validation(format!(
+                "No native contacts within {} Å at residue separation ≥ {}",
+                config.cutoff, config.min_separation
+            )));
+        }
+        Ok(Self {
+            config,
+            contacts,
+            residues,
+            series: Vec::new(),
+        })
+    }
+
+    pub fn contacts(&self) -> &[NativeContact] {
+        &self.contacts
+    }
+
+    /// Distinct residue pairs with at least one native contact
+    pub fn residue_contacts(&self) -> Vec<(u16, u16)> {
+        let mut pairs: Vec<(u16, u16)> = self
+            .contacts
+            .iter()
+            .map(|c| {
+                let (a, b) = (self.residues[c.i as usize], self.residues[c.j as usize]);
+                (a.min(b), a.max(b))
+            })
+            .collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+
+    /// `Q` of a configuration given by per-atom coordinates
+    pub fn fraction(&self, position: impl Fn(usize) -> [f64; 3]) -> f64 {
+        let (beta, lambda) = (self.config.beta, self.config.lambda);
+        self.contacts
+            .iter()
+            .map(|c| {
+                let r = distance(position(c.i as usize), position(c.j as usize));
+                1.0 / (1.0 + (beta * (r - lambda * c.distance)).exp())
+            })
+            .sum::<f64>()
+            / self.contacts.len() as f64
+    }
+
+    /// `Q` of each stored frame
+    pub fn fraction_series(&self, frames: &[Vec<[f32; 3]>]) -> Vec<f64> {
+        frames
+            .iter()
+            .map(|f| self.fraction(|i| f[i].map(|c| c as f64)))
+            .collect()
+    }
+
+    pub fn series(&self) -> &[(u64, f64)] {
+        &self.series
+    }
+}
+
+impl Analysis for NativeContacts {
+    fn name(&self) -> &str {
+        "native_contacts"
+    }
+
+    fn observe(&mut self, step: u64, positions: &[f32]) {
+        let q = self.fraction(|i| {
+            [
+                positions[i * 4] as f64,
+                positions[i * 4 + 1] as f64,
+                positions[i * 4 + 2] as f64,
+            ]
+        });
+        self.series.push((step, q));
+    }
+
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        let series: Vec<[f64; 2]> = self
+            .series
+            .iter()
+            .map(|&(step, q)| [step as f64, q])
+            .collect();
+        vec![
+            ("q_native".to_string(), json!(series)),
+            ("native_contacts".to_string(), json!(self.contacts.len())),
+            (
+                "native_residue_contacts".to_string(),
+                json!(self.residue_contacts()),
+            ),
+        ]
+    }
+
+    fn observables(&self) -> Vec<(&'static str, f64)> {
+        self.series
+            .last()
+            .map(|&(_, q)| ("q_native", q))
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hairpin: two strands of 4 residues, 4.5 Å apart
+    fn hairpin() -> (Vec<[f64; 3]>, Vec<u16>) {
+        let mut coords = Vec::new();
+        for strand in 0..2 {
+            for k in 0..4 {
+                let x = if strand == 0 { k } else { 3 - k } as f64 * 3.8;
+                coords.push([x, 4.5 * strand as f64, 0.0]);
+            }
+        }
+        (coords, (0..8).collect())
+    }
+
+    #[test]
+    fn test_contact_map_respects_cutoff_and_separation() {
+        let (coords, residues) = hairpin();
+        let config = NativeContactConfig {
+            cutoff: 5.0,
+            ..Default::default()
+        };
+        let atoms: Vec<u32> = (0..8).collect();
+        let contacts = contact_map(&coords, &residues, &atoms, &config);
+        // Cross-strand partners (k, 7 - k), except the turn pair (3, 4)
+        assert_eq!(
+            contacts.iter().map(|c| (c.i, c.j)).collect::<Vec<_>>(),
+            vec![(0, 7), (1, 6), (2, 5)]
+        );
+        assert!((contacts[0].distance - 4.5).abs() < 1e-12);
+
+        let tight = NativeContactConfig {
+            cutoff: 2.0,
+            ..Default::default()
+        };
+        assert!(NativeContacts::new(&coords, residues, &atoms, tight).is_err());
+    }
+
+    #[test]
+    fn test_q_drops_as_strands_separate() {
+        let (coords, residues) = hairpin();
+        let config = NativeContactConfig {
+            cutoff: 5.0,
+            ..Default::default()
+        };
+        let atoms: Vec<u32> = (0..8).collect();
+        let mut contacts = NativeContacts::new(&coords, residues, &atoms, config).unwrap();
+        let frame = |gap: f64| -> Vec<[f32; 3]> {
+            coords
+                .iter()
+                .map(|p| [p[0] as f32, (p[1] / 4.5 * gap) as f32, 0.0])
+                .collect()
+        };
+        let q = contacts.fraction_series(&[frame(4.5), frame(8.0), frame(15.0)]);
+        assert!(q[0] > 0.99);
+        assert!(q[1] > 0.3 && q[1] < q[0]);
+        assert!(q[2] < 1e-6);
+
+        let positions: Vec<f32> = frame(15.0)
+            .iter()
+            .flat_map(|p| [p[0], p[1], p[2], 1.0])
+            .collect();
+        contacts.observe(20, &positions);
+        assert_eq!(contacts.series()[0].0, 20);
+        assert_eq!(contacts.residue_contacts(), vec![(0, 7), (1, 6), (2, 5)]);
+    }
+}
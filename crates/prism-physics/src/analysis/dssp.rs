@@ -0,0 +1,547 @@
+//! # Secondary Structure
+//! DSSP-style assignment (Kabsch & Sander 1983) from backbone geometry.
+//! Backbone hydrogen bonds use the electrostatic energy
+//!
+//! `E = 0.084 · 332 (1/r_ON + 1/r_CH - 1/r_OH - 1/r_CN)` kcal/mol
+//!
+//! with a bond when `E < -0.5`. Amide hydrogens are taken from the
+//! topology when present and otherwise placed 1 Å from N opposite the
+//! preceding carbonyl. From the bond pattern:
+//!
+//! - `H`/`G`/`I`: two consecutive 4-/3-/5-turns
+//! - `E`: residue in a ladder of consecutive bridges, `B`: isolated bridge
+//! - `T`: inside a hydrogen-bonded turn, `S`: CA bend above 70°
+//! - `-`: none of the above
+//!
+//! Priority is H > B > E > G > I > T > S. Beta bulges are not detected.
+
+use super::Analysis;
+use prism_core::PrismError;
+use prism_io::topology::Topology;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Coulomb factor `q1 q2 f` of the DSSP bond energy (kcal/mol·Å)
+const COUPLING: f64 = 0.084 * 332.0;
+const HBOND_ENERGY: f64 = -0.5;
+/// Longest C(i)-N(i+1) distance of a peptide bond (Å)
+const PEPTIDE_BOND: f64 = 2.5;
+/// CA pairs further apart cannot hydrogen bond (Å)
+const CA_RANGE: f64 = 9.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecondaryStructure {
+    AlphaHelix,
+    Helix310,
+    PiHelix,
+    Strand,
+    Bridge,
+    Turn,
+    Bend,
+    Coil,
+}
+
+impl SecondaryStructure {
+    /// One-letter DSSP code
+    pub fn code(self) -> char {
+        match self {
+            Self::AlphaHelix => 'H',
+            Self::Helix310 => 'G',
+            Self::PiHelix => 'I',
+            Self::Strand => 'E',
+            Self::Bridge => 'B',
+            Self::Turn => 'T',
+            Self::Bend => 'S',
+            Self::Coil => '-',
+        }
+    }
+
+    pub fn is_helix(self) -> bool {
+        matches!(self, Self::AlphaHelix | Self::Helix310 | Self::PiHelix)
+    }
+
+    pub fn is_strand(self) -> bool {
+        matches!(self, Self::Strand | Self::Bridge)
+    }
+}
+
+/// One-letter string of an assignment
+pub fn dssp_string(assignment: &[SecondaryStructure]) -> String {
+    assignment.iter().map(|s| s.code()).collect()
+}
+
+/// Backbone atom indices of the residues with complete N, CA, C and O
+#[derive(Debug, Clone)]
+pub struct Backbone {
+    residues: Vec<u16>,
+    n: Vec<u32>,
+    ca: Vec<u32>,
+    c: Vec<u32>,
+    o: Vec<u32>,
+    /// Amide hydrogen, if in the topology
+    h: Vec<Option<u32>>,
+    /// Proline (no amide hydrogen)
+    proline: Vec<bool>,
+}
+
+impl Backbone {
+    pub fn from_topology(topology: &Topology) -> Result<Self, PrismError> {
+        let mut slots: std::collections::BTreeMap<u16, [Option<u32>; 5]> = Default::default();
+        for (i, (atom, name)) in topology.atoms.iter().zip(&topology.atom_names).enumerate() {
+            let slot = match name.trim() {
+                "N" => 0,
+                "CA" => 1,
+                "C" => 2,
+                "O" => 3,
+                "H" | "HN" => 4,
+                _ => continue,
+            };
+            slots.entry(atom.residue_id).or_default()[slot].get_or_insert(i as u32);
+        }
+        let mut backbone = Self {
+            residues: Vec::new(),
+            n: Vec::new(),
+            ca: Vec::new(),
+            c: Vec::new(),
+            o: Vec::new(),
+            h: Vec::new(),
+            proline: Vec::new(),
+        };
+        for (residue, [n, ca, c, o, h]) in slots {
+            let (Some(n), Some(ca), Some(c), Some(o)) = (n, ca, c, o) else {
+                continue;
+            };
+            let name = topology.residue_names.get(residue as usize);
+            backbone.residues.push(residue);
+            backbone.n.push(n);
+            backbone.ca.push(ca);
+            backbone.c.push(c);
+            backbone.o.push(o);
+            backbone.h.push(h);
+            backbone
+                .proline
+                .push(name.is_some_and(|r| r.trim() == "PRO"));
+        }
+        if backbone.residues.is_empty() {
+            return Err(PrismError::validation(
+                "Secondary structure needs residues with N, CA, C and O atoms",
+            ));
+        }
+        Ok(backbone)
+    }
+
+    pub fn num_residues(&self) -> usize {
+        self.residues.len()
+    }
+
+    /// Residue index of each assigned position
+    pub fn residues(&self) -> &[u16] {
+        &self.residues
+    }
+
+    /// Assign every backbone residue given per-atom coordinates
+    pub fn assign(&self, position: impl Fn(usize) -> [f64; 3]) -> Vec<SecondaryStructure> {
+        let count = self.num_residues();
+        let atom = |list: &[u32], i: usize| position(list[i] as usize);
+        let linked: Vec<bool> = (0..count.saturating_sub(1))
+            .map(|i| {
+                self.residues[i + 1] == self.residues[i] + 1
+                    && distance(atom(&self.c, i), atom(&self.n, i + 1)) <= PEPTIDE_BOND
+            })
+            .collect();
+        let hydrogens: Vec<Option<[f64; 3]>> = (0..count)
+            .map(|i| {
+                if self.proline[i] {
+                    return None;
+                }
+                if let Some(h) = self.h[i] {
+                    return Some(position(h as usize));
+                }
+                if i == 0 || !linked[i - 1] {
+                    return None;
+                }
+                let n = atom(&self.n, i);
+                let co = sub(atom(&self.c, i - 1), atom(&self.o, i - 1));
+                let norm = dot(co, co).sqrt();
+                Some([0, 1, 2].map(|k| n[k] + co[k] / norm))
+            })
+            .collect();
+
+        // hbond[i][j]: C=O of i accepts from N-H of j
+        let mut hbond = vec![vec![false; count]; count];
+        for (i, row) in hbond.iter_mut().enumerate() {
+            let (c, o, ca) = (atom(&self.c, i), atom(&self.o, i), atom(&self.ca, i));
+            for (j, bonded) in row.iter_mut().enumerate() {
+                let Some(h) = hydrogens[j] else { continue };
+                if i.abs_diff(j) < 2 || distance(ca, atom(&self.ca, j)) > CA_RANGE {
+                    continue;
+                }
+                let n = atom(&self.n, j);
+                let energy = COUPLING
+                    * (1.0 / distance(o, n) + 1.0 / distance(c, h)
+                        - 1.0 / distance(o, h)
+                        - 1.0 / distance(c, n));
+                *bonded = energy < HBOND_ENERGY;
+            }
+        }
+        let bend: Vec<bool> = (0..count)
+            .map(|i| {
+                if i < 2 || i + 2 >= count || !linked[i - 2..i + 2].iter().all(|&l| l) {
+                    return false;
+                }
+                let a = sub(atom(&self.ca, i), atom(&self.ca, i - 2));
+                let b = sub(atom(&self.ca, i + 2), atom(&self.ca, i));
+                let cosine = dot(a, b) / (dot(a, a) * dot(b, b)).sqrt();
+                cosine.clamp(-1.0, 1.0).acos().to_degrees() > 70.0
+            })
+            .collect();
+        assign_pattern(
+            count,
+            |i, j| hbond[i][j],
+            |i, j| linked[i..j].iter().all(|&l| l),
+            &bend,
+        )
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let d = sub(a, b);
+    dot(d, d).sqrt()
+}
+
+/// Assignment from the bond pattern; `continuous(i, j)` is whether the
+/// chain is unbroken from residue `i` to `j > i`
+fn assign_pattern(
+    count: usize,
+    hbond: impl Fn(usize, usize) -> bool,
+    continuous: impl Fn(usize, usize) -> bool,
+    bend: &[bool],
+) -> Vec<SecondaryStructure> {
+    use SecondaryStructure::*;
+
+    let turn = |n: usize, i: usize| i + n < count && continuous(i, i + n) && hbond(i, i + n);
+    let bond = |i: Option<usize>, j: Option<usize>| match (i, j) {
+        (Some(i), Some(j)) if i < count && j < count => hbond(i, j),
+        _ => false,
+    };
+    let bridge = |i: usize, j: usize| {
+        if i.abs_diff(j) < 3 {
+            return false;
+        }
+        let (prev, next) = (|k: usize| k.checked_sub(1), |k: usize| Some(k + 1));
+        let parallel = (bond(prev(i), Some(j)) && bond(Some(j), next(i)))
+            || (bond(prev(j), Some(i)) && bond(Some(i), next(j)));
+        let antiparallel = (bond(Some(i), Some(j)) && bond(Some(j), Some(i)))
+            || (bond(prev(i), next(j)) && bond(prev(j), next(i)));
+        parallel || antiparallel
+    };
+    let partners: Vec<Vec<usize>> = (0..count)
+        .map(|i| (0..count).filter(|&j| bridge(i, j)).collect())
+        .collect();
+    let bridged = |i: Option<usize>, j: Option<usize>| match (i, j) {
+        (Some(i), Some(j)) if i < count && j < count => partners[i].contains(&j),
+        _ => false,
+    };
+
+    let mut assignment = vec![Coil; count];
+    let mark = |assignment: &mut [SecondaryStructure], range: std::ops::Range<usize>, kind| {
+        for s in &mut assignment[range] {
+            if *s == Coil || priority(kind) < priority(*s) {
+                *s = kind;
+            }
+        }
+    };
+    for (n, kind) in [(4, AlphaHelix), (3, Helix310), (5, PiHelix)] {
+        for i in 1..count {
+            if turn(n, i - 1) && turn(n, i) {
+                mark(&mut assignment, i..i + n, kind);
+            }
+        }
+    }
+    for (i, js) in partners.iter().enumerate() {
+        for &j in js {
+            let ladder = [
+                (i.checked_sub(1), j.checked_sub(1)),
+                (i.checked_sub(1), Some(j + 1)),
+            ]
+            .into_iter()
+            .chain([(Some(i + 1), j.checked_sub(1)), (Some(i + 1), Some(j + 1))])
+            .any(|(a, b)| bridged(a, b));
+            mark(
+                &mut assignment,
+                i..i + 1,
+                if ladder { Strand } else { Bridge },
+            );
+        }
+    }
+    for n in 3..=5 {
+        for i in 0..count {
+            if turn(n, i) {
+                mark(&mut assignment, i + 1..i + n, Turn);
+            }
+        }
+    }
+    for (s, &bent) in assignment.iter_mut().zip(bend) {
+        if bent && *s == Coil {
+            *s = Bend;
+        }
+    }
+    assignment
+}
+
+fn priority(kind: SecondaryStructure) -> u8 {
+    use SecondaryStructure::*;
+    match kind {
+        AlphaHelix => 0,
+        Bridge => 1,
+        Strand => 2,
+        Helix310 => 3,
+        PiHelix => 4,
+        Turn => 5,
+        Bend => 6,
+        Coil => 7,
+    }
+}
+
+/// Per-frame secondary structure strings
+#[derive(Debug, Clone)]
+pub struct SecondaryStructureAnalysis {
+    backbone: Backbone,
+    series: Vec<(u64, Vec<SecondaryStructure>)>,
+}
+
+impl SecondaryStructureAnalysis {
+    pub fn from_topology(topology: &Topology) -> Result<Self, PrismError> {
+        Ok(Self {
+            backbone: Backbone::from_topology(topology)?,
+            series: Vec::new(),
+        })
+    }
+
+    pub fn backbone(&self) -> &Backbone {
+        &self.backbone
+    }
+
+    /// Assign a stored frame (one `[x, y, z]` per atom)
+    pub fn observe_frame(&mut self, step: u64, frame: &[[f32; 3]]) {
+        let assignment = self.backbone.assign(|i| frame[i].map(|c| c as f64));
+        self.series.push((step, assignment));
+    }
+
+    pub fn series(&self) -> &[(u64, Vec<SecondaryStructure>)] {
+        &self.series
+    }
+}
+
+impl Analysis for SecondaryStructureAnalysis {
+    fn name(&self) -> &str {
+        "secondary_structure"
+    }
+
+    fn observe(&mut self, step: u64, positions: &[f32]) {
+        let assignment = self.backbone.assign(|i| {
+            [
+                positions[i * 4] as f64,
+                positions[i * 4 + 1] as f64,
+                positions[i * 4 + 2] as f64,
+            ]
+        });
+        self.series.push((step, assignment));
+    }
+
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        let strings: Vec<serde_json::Value> = self
+            .series
+            .iter()
+            .map(|(step, s)| json!([step, dssp_string(s)]))
+            .collect();
+        let content: Vec<[f64; 3]> = self
+            .series
+            .iter()
+            .map(|(step, s)| {
+                let n = s.len().max(1) as f64;
+                let helix = s.iter().filter(|s| s.is_helix()).count() as f64 / n;
+                let strand = s.iter().filter(|s| s.is_strand()).count() as f64 / n;
+                [*step as f64, helix, strand]
+            })
+            .collect();
+        vec![
+            ("secondary_structure".to_string(), json!(strings)),
+            ("secondary_structure_content".to_string(), json!(content)),
+            (
+                "secondary_structure_residues".to_string(),
+                json!(self.backbone.residues),
+            ),
+        ]
+    }
+
+    fn observables(&self) -> Vec<(&'static str, f64)> {
+        let Some((_, s)) = self.series.last() else {
+            return Vec::new();
+        };
+        let n = s.len().max(1) as f64;
+        vec![
+            (
+                "helix_fraction",
+                s.iter().filter(|s| s.is_helix()).count() as f64 / n,
+            ),
+            (
+                "strand_fraction",
+                s.iter().filter(|s| s.is_strand()).count() as f64 / n,
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use prism_io::sovereign_types::Atom;
+
+    /// Place D from A, B, C with |CD|, angle BCD and torsion ABCD (degrees)
+    fn place(
+        a: [f64; 3],
+        b: [f64; 3],
+        c: [f64; 3],
+        length: f64,
+        angle: f64,
+        torsion: f64,
+    ) -> [f64; 3] {
+        let unit = |v: [f64; 3]| {
+            let n = dot(v, v).sqrt();
+            v.map(|x| x / n)
+        };
+        let cross = |u: [f64; 3], v: [f64; 3]| {
+            [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ]
+        };
+        let bc = unit(sub(c, b));
+        let n = unit(cross(sub(b, a), bc));
+        let m = cross(n, bc);
+        let (angle, torsion) = (angle.to_radians(), torsion.to_radians());
+        let d = [
+            -length * angle.cos(),
+            length * angle.sin() * torsion.cos(),
+            length * angle.sin() * torsion.sin(),
+        ];
+        [0, 1, 2].map(|k| c[k] + d[0] * bc[k] + d[1] * m[k] + d[2] * n[k])
+    }
+
+    /// Poly-alanine backbone (N, CA, C, O per residue) with uniform φ/ψ
+    pub(crate) fn peptide(phi: f64, psi: f64, residues: usize) -> Topology {
+        let mut n = [0.0, 0.0, 0.0];
+        let mut ca = [1.458, 0.0, 0.0];
+        let mut c = place([0.0, 1.0, 0.0], n, ca, 1.525, 111.2, 0.0);
+        let mut atoms = Vec::new();
+        let mut names = Vec::new();
+        for r in 0..residues {
+            let o = place(n, ca, c, 1.231, 120.5, psi + 180.0);
+            for (coords, element, name) in [(n, 7, "N"), (ca, 6, "CA"), (c, 6, "C"), (o, 8, "O")] {
+                atoms.push(Atom {
+                    coords: coords.map(|x| x as f32),
+                    element,
+                    residue_id: r as u16,
+                    atom_type: 0,
+                    charge: 0.0,
+                    radius: 1.7,
+                    _reserved: [0; 4],
+                });
+                names.push(name.to_string());
+            }
+            let next_n = place(n, ca, c, 1.329, 116.2, psi);
+            let next_ca = place(ca, c, next_n, 1.458, 121.7, 180.0);
+            let next_c = place(c, next_n, next_ca, 1.525, 111.2, phi);
+            (n, ca, c) = (next_n, next_ca, next_c);
+        }
+        Topology {
+            atom_names: names,
+            residue_names: vec!["ALA".to_string(); residues],
+            masses: atoms
+                .iter()
+                .map(|a| {
+                    if a.element == 7 {
+                        14.007
+                    } else if a.element == 8 {
+                        15.999
+                    } else {
+                        12.011
+                    }
+                })
+                .collect(),
+            atoms,
+            ..Default::default()
+        }
+    }
+
+    fn assign(topology: &Topology) -> String {
+        let backbone = Backbone::from_topology(topology).unwrap();
+        dssp_string(&backbone.assign(|i| topology.atoms[i].coords.map(|c| c as f64)))
+    }
+
+    #[test]
+    fn test_ideal_helix_and_extended_chain() {
+        let helix = assign(&peptide(-57.0, -47.0, 14));
+        assert_eq!(helix.len(), 14);
+        assert!(helix[2..11].chars().all(|c| c == 'H'), "{}", helix);
+        assert!(!helix.contains('E'));
+
+        let extended = assign(&peptide(-139.0, 135.0, 10));
+        assert!(
+            !extended.contains('H') && !extended.contains('E'),
+            "{}",
+            extended
+        );
+
+        let mut broken = peptide(-57.0, -47.0, 14);
+        for name in broken.atom_names.iter_mut().filter(|n| *n == "O") {
+            *name = "OXT".to_string();
+        }
+        assert!(Backbone::from_topology(&broken).is_err());
+    }
+
+    #[test]
+    fn test_sheet_ladders_and_isolated_bridge() {
+        // Antiparallel hairpin: 0-4 paired with 14-10
+        let pairs = [(0, 14), (2, 12), (4, 10)];
+        let hbond = |i: usize, j: usize| {
+            pairs
+                .iter()
+                .any(|&(a, b)| (a, b) == (i, j) || (b, a) == (i, j))
+        };
+        let sheet = assign_pattern(15, hbond, |_, _| true, &[false; 15]);
+        assert_eq!(dssp_string(&sheet), "EEEEE-----EEEEE");
+
+        let lone = |i: usize, j: usize| (i, j) == (0, 10) || (i, j) == (10, 0);
+        let bridge = assign_pattern(11, lone, |_, _| true, &[false; 11]);
+        assert_eq!(dssp_string(&bridge), "B---------B");
+
+        // A 4-turn at 2 alone marks a turn, not a helix
+        let turn = assign_pattern(8, |i, j| (i, j) == (2, 6), |_, _| true, &[false; 8]);
+        assert_eq!(dssp_string(&turn), "---TTT--");
+    }
+
+    #[test]
+    fn test_analysis_reports_strings_per_frame() {
+        let topology = peptide(-57.0, -47.0, 10);
+        let mut analysis = SecondaryStructureAnalysis::from_topology(&topology).unwrap();
+        let frame: Vec<[f32; 3]> = topology.atoms.iter().map(|a| a.coords).collect();
+        analysis.observe_frame(0, &frame);
+        let positions: Vec<f32> = frame.iter().flat_map(|p| [p[0], p[1], p[2], 1.0]).collect();
+        analysis.observe(50, &positions);
+        let telemetry = analysis.telemetry();
+        assert_eq!(telemetry[0].1[1][0], 50);
+        assert_eq!(telemetry[0].1[1][1].as_str().unwrap().len(), 10);
+        assert!(telemetry[1].1[0][1].as_f64().unwrap() > 0.5);
+    }
+}
@@ -0,0 +1,384 @@
+//! # Hydrogen Bonds
+//! Geometric hydrogen-bond detection. Donors are hydrogens bonded (in the
+//! topology) to N, O or S; acceptors are N, O and S atoms. A triplet
+//! D-H···A counts as bonded when `|D - A| ≤ distance_cutoff` and the
+//! D-H···A angle is at least `angle_cutoff`. Over a series of frames each
+//! triplet accumulates its occupancy (fraction of frames bonded) and the
+//! lengths of its uninterrupted runs; a run still open at the last frame
+//! counts at its observed length.
+
+use super::Analysis;
+use prism_core::PrismError;
+use prism_io::topology::Topology;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HydrogenBondConfig {
+    /// Donor-acceptor heavy atom distance cutoff (Å)
+    pub distance_cutoff: f64,
+    /// Minimum D-H···A angle (degrees)
+    pub angle_cutoff: f64,
+    /// Inclusive residue range; a bond is kept when its donor or acceptor
+    /// lies inside (all residues when `None`)
+    pub residue_range: Option<(u16, u16)>,
+}
+
+impl Default for HydrogenBondConfig {
+    fn default() -> Self {
+        Self {
+            distance_cutoff: 3.5,
+            angle_cutoff: 150.0,
+            residue_range: None,
+        }
+    }
+}
+
+/// Occupancy and lifetime of one D-H···A triplet
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HydrogenBond {
+    pub donor: u32,
+    pub hydrogen: u32,
+    pub acceptor: u32,
+    /// Fraction of observed frames in which the bond was present
+    pub occupancy: f64,
+    /// Mean uninterrupted run length (frames)
+    pub mean_lifetime: f64,
+    /// Longest uninterrupted run (frames)
+    pub max_lifetime: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct BondHistory {
+    frames: usize,
+    runs: usize,
+    current_run: usize,
+    max_run: usize,
+    /// Frame index of the last sighting
+    last_frame: usize,
+}
+
+/// Hydrogen-bond counts per frame with per-bond occupancy and lifetimes
+#[derive(Debug, Clone)]
+pub struct HydrogenBondAnalysis {
+    config: HydrogenBondConfig,
+    /// (donor, hydrogen) pairs
+    donors: Vec<(u32, u32)>,
+    acceptors: Vec<u32>,
+    residues: Vec<u16>,
+    frames: usize,
+    counts: Vec<(u64, usize)>,
+    history: HashMap<(u32, u32), BondHistory>,
+}
+
+impl HydrogenBondAnalysis {
+    pub fn from_topology(
+        topology: &Topology,
+        config: HydrogenBondConfig,
+    ) -> Result<Self, PrismError> {
+        if config.distance_cutoff <= 0.0 || !(0.0..=180.0).contains(&config.angle_cutoff) {
+            return Err(PrismError::config(format!(
+                "Invalid hydrogen bond criteria: distance {} Å, angle {}°",
+                config.distance_cutoff, config.angle_cutoff
+            )));
+        }
+        let num_atoms = topology.num_atoms();
+        if let Some(b) = topology
+            .bonds
+            .iter()
+            .find(|b| b.i as usize >= num_atoms || b.j as usize >= num_atoms)
+        {
+            return Err(PrismError::validation(format!(
+                "Bond {}-{} references an atom outside the {}-atom topology",
+                b.i, b.j, num_atoms
+            )));
+        }
+        let polar = |i: u32| matches!(topology.atoms[i as usize].element, 7 | 8 | 16);
+        let hydrogen = |i: u32| topology.atoms[i as usize].element == 1;
+        let mut donors: Vec<(u32, u32)> = topology
+            .bonds
+            .iter()
+            .filter_map(|b| match (hydrogen(b.i), hydrogen(b.j)) {
+                (true, false) if polar(b.j) => Some((b.j, b.i)),
+                (false, true) if polar(b.i) => Some((b.i, b.j)),
+                _ => None,
+            })
+            .collect();
+        donors.sort_unstable();
+        let acceptors = (0..num_atoms as u32).filter(|&i| polar(i)).collect();
+        Ok(Self {
+            config,
+            donors,
+            acceptors,
+            residues: topology.atoms.iter().map(|a| a.residue_id).collect(),
+            frames: 0,
+            counts: Vec::new(),
+            history: HashMap::new(),
+        })
+    }
+
+    pub fn num_donors(&self) -> usize {
+        self.donors.len()
+    }
+
+    pub fn num_acceptors(&self) -> usize {
+        self.acceptors.len()
+    }
+
+    /// Bonds present in a frame as (donor-hydrogen pair index, acceptor)
+    fn detect(&self, position: impl Fn(usize) -> [f64; 3]) -> Vec<(u32, u32)> {
+        let in_range = |atom: u32| {
+            self.config
+                .residue_range
+                .is_none_or(|(lo, hi)| (lo..=hi).contains(&self.residues[atom as usize]))
+        };
+        let cutoff2 = self.config.distance_cutoff * self.config.distance_cutoff;
+        let cos_cutoff = self.config.angle_cutoff.to_radians().cos();
+        let sub = |a: [f64; 3], b: [f64; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+        let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+
+        let mut bonds = Vec::new();
+        for &(donor, h) in &self.donors {
+            let (d, hp) = (position(donor as usize), position(h as usize));
+            for &acceptor in &self.acceptors {
+                if acceptor == donor || !(in_range(donor) || in_range(acceptor)) {
+                    continue;
+                }
+                let a = position(acceptor as usize);
+                if dot(sub(a, d), sub(a, d)) > cutoff2 {
+                    continue;
+                }
+                // Angle at the hydrogen between H→D and H→A
+                let (hd, ha) = (sub(d, hp), sub(a, hp));
+                let norm = (dot(hd, hd) * dot(ha, ha)).sqrt();
+                if norm > 0.0 && dot(hd, ha) / norm <= cos_cutoff {
+                    bonds.push((h, acceptor));
+                }
+            }
+        }
+        bonds
+    }
+
+    fn record(&mut self, step: u64, bonds: Vec<(u32, u32)>) {
+        let frame = self.frames;
+        self.frames += 1;
+        self.counts.push((step, bonds.len()));
+        for key in bonds {
+            let history = self.history.entry(key).or_default();
+            if history.frames == 0 || history.last_frame + 1 != frame {
+                history.runs += 1;
+                history.current_run = 0;
+            }
+            history.frames += 1;
+            history.current_run += 1;
+            history.max_run = history.max_run.max(history.current_run);
+            history.last_frame = frame;
+        }
+    }
+
+    /// Record a stored frame (one `[x, y, z]` per atom)
+    pub fn observe_frame(&mut self, step: u64, frame: &[[f32; 3]]) {
+        let bonds = self.detect(|i| frame[i].map(|c| c as f64));
+        self.record(step, bonds);
+    }
+
+    /// Number of bonds per observed frame
+    pub fn counts(&self) -> &[(u64, usize)] {
+        &self.counts
+    }
+
+    /// Every bond seen at least once, most occupied first
+    pub fn bonds(&self) -> Vec<HydrogenBond> {
+        let donor_of: HashMap<u32, u32> = self.donors.iter().map(|&(d, h)| (h, d)).collect();
+        let mut bonds: Vec<HydrogenBond> = self
+            .history
+            .iter()
+            .map(|(&(hydrogen, acceptor), history)| HydrogenBond {
+                donor: donor_of[&hydrogen],
+                hydrogen,
+                acceptor,
+                occupancy: history.frames as f64 / self.frames.max(1) as f64,
+                mean_lifetime: history.frames as f64 / history.runs.max(1) as f64,
+                max_lifetime: history.max_run,
+            })
+            .collect();
+        bonds.sort_by(|a, b| {
+            b.occupancy
+                .total_cmp(&a.occupancy)
+                .then((a.hydrogen, a.acceptor).cmp(&(b.hydrogen, b.acceptor)))
+        });
+        bonds
+    }
+}
+
+impl Analysis for HydrogenBondAnalysis {
+    fn name(&self) -> &str {
+        "hydrogen_bonds"
+    }
+
+    fn observe(&mut self, step: u64, positions: &[f32]) {
+        let bonds = self.detect(|i| {
+            [
+                positions[i * 4] as f64,
+                positions[i * 4 + 1] as f64,
+                positions[i * 4 + 2] as f64,
+            ]
+        });
+        self.record(step, bonds);
+    }
+
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        let counts: Vec<[u64; 2]> = self
+            .counts
+            .iter()
+            .map(|&(step, n)| [step, n as u64])
+            .collect();
+        vec![
+            ("hbond_count".to_string(), json!(counts)),
+            ("hbonds".to_string(), json!(self.bonds())),
+        ]
+    }
+
+    fn observables(&self) -> Vec<(&'static str, f64)> {
+        self.counts
+            .last()
+            .map(|&(_, n)| ("hbond_count", n as f64))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Hydrogen-bond analysis of stored frames
+pub fn hydrogen_bonds(
+    topology: &Topology,
+    config: HydrogenBondConfig,
+    frames: &[Vec<[f32; 3]>],
+) -> Result<HydrogenBondAnalysis, PrismError> {
+    let mut analysis = HydrogenBondAnalysis::from_topology(topology, config)?;
+    for (i, frame) in frames.iter().enumerate() {
+        analysis.observe_frame(i as u64, frame);
+    }
+    Ok(analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_io::sovereign_types::Atom;
+    use prism_io::topology::HarmonicBond;
+
+    fn atom(element: u8, residue_id: u16, coords: [f32; 3]) -> Atom {
+        Atom {
+            coords,
+            element,
+            residue_id,
+            atom_type: 0,
+            charge: 0.0,
+            radius: 1.5,
+            _reserved: [0; 4],
+        }
+    }
+
+    /// N-H donor in residue 0 facing a carbonyl O in residue 1, plus an
+    /// O in residue 5 off to the side of the hydrogen
+    fn pair() -> Topology {
+        Topology {
+            atoms: vec![
+                atom(7, 0, [0.0, 0.0, 0.0]),
+                atom(1, 0, [1.0, 0.0, 0.0]),
+                atom(8, 1, [2.9, 0.0, 0.0]),
+                atom(6, 1, [4.1, 0.0, 0.0]),
+                atom(8, 5, [0.0, 3.0, 0.0]),
+            ],
+            bonds: vec![
+                HarmonicBond {
+                    i: 0,
+                    j: 1,
+                    k: 434.0,
+                    r0: 1.01,
+                },
+                HarmonicBond {
+                    i: 2,
+                    j: 3,
+                    k: 570.0,
+                    r0: 1.23,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_detects_linear_bond_and_rejects_bent_geometry() {
+        let topology = pair();
+        let analysis =
+            HydrogenBondAnalysis::from_topology(&topology, HydrogenBondConfig::default()).unwrap();
+        assert_eq!((analysis.num_donors(), analysis.num_acceptors()), (1, 3));
+        let frame: Vec<[f32; 3]> = topology.atoms.iter().map(|a| a.coords).collect();
+        let bonds = analysis.detect(|i| frame[i].map(|c| c as f64));
+        // The residue 5 oxygen is within 3.5 Å of N but at 90° to N-H
+        assert_eq!(bonds, vec![(1, 2)]);
+
+        let loose = HydrogenBondConfig {
+            angle_cutoff: 60.0,
+            ..Default::default()
+        };
+        let analysis = HydrogenBondAnalysis::from_topology(&topology, loose).unwrap();
+        assert_eq!(analysis.detect(|i| frame[i].map(|c| c as f64)).len(), 2);
+
+        let bad = HydrogenBondConfig {
+            distance_cutoff: -1.0,
+            ..Default::default()
+        };
+        assert!(HydrogenBondAnalysis::from_topology(&topology, bad).is_err());
+
+        let mut truncated = topology.clone();
+        truncated.atoms.truncate(1);
+        assert!(
+            HydrogenBondAnalysis::from_topology(&truncated, HydrogenBondConfig::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_occupancy_lifetime_and_residue_range() {
+        let topology = pair();
+        let bound: Vec<[f32; 3]> = topology.atoms.iter().map(|a| a.coords).collect();
+        let mut broken = bound.clone();
+        broken[2] = [6.0, 0.0, 0.0];
+        broken[3] = [7.2, 0.0, 0.0];
+        let frames = vec![
+            bound.clone(),
+            bound.clone(),
+            broken.clone(),
+            bound.clone(),
+            broken,
+        ];
+        let analysis = hydrogen_bonds(&topology, HydrogenBondConfig::default(), &frames).unwrap();
+        assert_eq!(
+            analysis.counts().iter().map(|c| c.1).collect::<Vec<_>>(),
+            vec![1, 1, 0, 1, 0]
+        );
+        let bond = analysis.bonds()[0];
+        assert_eq!((bond.donor, bond.hydrogen, bond.acceptor), (0, 1, 2));
+        assert!((bond.occupancy - 0.6).abs() < 1e-12);
+        assert!((bond.mean_lifetime - 1.5).abs() < 1e-12);
+        assert_eq!(bond.max_lifetime, 2);
+
+        let elsewhere = HydrogenBondConfig {
+            residue_range: Some((3, 4)),
+            ..Default::default()
+        };
+        let analysis = hydrogen_bonds(&topology, elsewhere, &frames).unwrap();
+        assert!(analysis.bonds().is_empty());
+
+        let mut observer =
+            HydrogenBondAnalysis::from_topology(&topology, HydrogenBondConfig::default()).unwrap();
+        let positions: Vec<f32> = bound.iter().flat_map(|p| [p[0], p[1], p[2], 1.0]).collect();
+        observer.observe(100, &positions);
+        let telemetry = observer.telemetry();
+        assert_eq!(telemetry[0].1, json!([[100, 1]]));
+        assert_eq!(telemetry[1].1[0]["occupancy"], 1.0);
+    }
+}
@@ -0,0 +1,101 @@
+//! # Trajectory Analysis
+//! Structural observables computed either after the fact on stored frames
+//! (e.g. from [`prism_io::dcd::read_dcd`]) or on the fly during a run. An
+//! [`Analysis`] added to the engine is handed the Float4-stride positions
+//! every `analysis_interval` steps and reports its results in the run
+//! telemetry.
+
+pub mod cluster;
+pub mod contacts;
+pub mod dssp;
+pub mod hbond;
+pub mod pca;
+pub mod rmsd;
+pub mod sasa;
+pub mod shape;
+
+pub use cluster::{cluster_frames, ClusterMethod, Clustering, ClusteringConfig};
+pub use contacts::{NativeContactConfig, NativeContacts};
+pub use dssp::{SecondaryStructure, SecondaryStructureAnalysis};
+pub use hbond::{HydrogenBondAnalysis, HydrogenBondConfig};
+pub use pca::Pca;
+pub use rmsd::RmsdAnalysis;
+pub use sasa::{SasaAnalysis, SasaCalculator, SasaConfig};
+pub use shape::{ShapeAnalysis, ShapeDescriptors};
+
+use crate::collective_variables::kabsch_rotation;
+use nalgebra::Vector3;
+use prism_core::PrismError;
+use prism_io::selection::SelectionContext;
+use prism_io::topology::Topology;
+use std::fmt::Debug;
+
+/// On-the-fly observer of the engine's positions
+pub trait Analysis: std::any::Any + Send + Debug {
+    fn name(&self) -> &str;
+
+    /// Record the Float4-stride `positions` after `step` steps
+    fn observe(&mut self, step: u64, positions: &[f32]);
+
+    /// Results so far as run telemetry entries
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        Vec::new()
+    }
+
+    /// Scalar results of the last observed frame, written as trajectory
+    /// observables (e.g. H5MD `/observables`)
+    fn observables(&self) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+}
+
+/// Coordinates of `atoms` (all atoms when empty) from a Float4-stride buffer
+pub fn select_positions(positions: &[f32], atoms: &[u32]) -> Vec<[f64; 3]> {
+    let load = |i: usize| {
+        [
+            positions[i * 4] as f64,
+            positions[i * 4 + 1] as f64,
+            positions[i * 4 + 2] as f64,
+        ]
+    };
+    if atoms.is_empty() {
+        (0..positions.len() / 4).map(load).collect()
+    } else {
+        atoms.iter().map(|&i| load(i as usize)).collect()
+    }
+}
+
+/// Atom indices of `topology` matching a [selection](prism_io::selection)
+/// expression, e.g. `"chain A and resid 10-50 and name CA"` for the
+/// `atoms` of an analysis
+pub fn select_atoms(topology: &Topology, expression: &str) -> Result<Vec<u32>, PrismError> {
+    SelectionContext::from_topology(topology)
+        .select(expression)
+        .map_err(|e| PrismError::validation(e.to_string()))
+}
+
+/// Coordinates of `atoms` (all atoms when empty) from a stored frame
+pub fn select_frame(frame: &[[f32; 3]], atoms: &[u32]) -> Vec<[f64; 3]> {
+    let load = |p: &[f32; 3]| p.map(|c| c as f64);
+    if atoms.is_empty() {
+        frame.iter().map(load).collect()
+    } else {
+        atoms.iter().map(|&i| load(&frame[i as usize])).collect()
+    }
+}
+
+/// `frame` rigidly superposed onto `reference` (least squares, Kabsch)
+pub fn superpose(reference: &[[f64; 3]], frame: &[[f64; 3]]) -> Vec<[f64; 3]> {
+    let x: Vec<Vector3<f64>> = reference.iter().map(|&r| Vector3::from(r)).collect();
+    let y: Vec<Vector3<f64>> = frame.iter().map(|&f| Vector3::from(f)).collect();
+    let n = x.len().min(y.len()).max(1) as f64;
+    let cx = x.iter().sum::<Vector3<f64>>() / n;
+    let cy = y.iter().sum::<Vector3<f64>>() / n;
+    let rotation = kabsch_rotation(&x, cx, &y, cy).unwrap_or_else(nalgebra::Matrix3::identity);
+    y.iter()
+        .map(|yi| {
+            let p = rotation * (yi - cy) + cx;
+            [p.x, p.y, p.z]
+        })
+        .collect()
+}
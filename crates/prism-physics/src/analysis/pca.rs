@@ -0,0 +1,306 @@
+//! # Principal Component Analysis
+//! Essential dynamics of stored frames (e.g. from
+//! [`prism_io::dcd::read_dcd`]): every frame is superposed onto the
+//! running average structure, the `3N x 3N` positional covariance
+//! `C = <(x - <x>)(x - <x>)ᵀ>` is diagonalised, and the components with
+//! the largest variance are kept. Components use the same
+//! [`NormalMode`]/[`ModeSet`] layout as the elastic network modes, with the
+//! variance (Å²) as eigenvalue, so they can be animated, exported, and
+//! compared with normal modes through their overlaps and the root mean
+//! square inner product (RMSIP) of the two subspaces.
+
+use super::{select_frame, superpose};
+use crate::elastic_network::{ModeSet, NormalMode};
+use nalgebra::{DMatrix, SymmetricEigen};
+use prism_core::PrismError;
+
+/// Superposition rounds onto the updated average structure
+const ALIGNMENT_ROUNDS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct Pca {
+    atoms: Vec<u32>,
+    mean: Vec<[f64; 3]>,
+    components: Vec<NormalMode>,
+    total_variance: f64,
+    /// `[frame][component]`
+    projections: Vec<Vec<f64>>,
+}
+
+impl Pca {
+    /// Keep the `num_components` largest-variance components of `atoms`
+    /// (all when empty) over `frames`
+    pub fn new(
+        frames: &[Vec<[f32; 3]>],
+        atoms: &[u32],
+        num_components: usize,
+    ) -> Result<Self, PrismError> {
+        if frames.len() < 2 {
+            return Err(PrismError::validation(format!(
+                "PCA needs at least two frames, got {}",
+                frames.len()
+            )));
+        }
+        let atoms: Vec<u32> = if atoms.is_empty() {
+            (0..frames[0].len() as u32).collect()
+        } else {
+            atoms.to_vec()
+        };
+        let selected: Vec<Vec<[f64; 3]>> = frames.iter().map(|f| select_frame(f, &atoms)).collect();
+
+        let mut mean = selected[0].clone();
+        let mut fitted = Vec::new();
+        for _ in 0..ALIGNMENT_ROUNDS {
+            fitted = selected.iter().map(|f| superpose(&mean, f)).collect();
+            mean = average(&fitted);
+        }
+
+        let dim = atoms.len() * 3;
+        let deviations: Vec<Vec<f64>> = fitted
+            .iter()
+            .map(|f| {
+                f.iter()
+                    .zip(&mean)
+                    .flat_map(|(p, m)| [p[0] - m[0], p[1] - m[1], p[2] - m[2]])
+                    .collect()
+            })
+            .collect();
+        let mut covariance = DMatrix::<f64>::zeros(dim, dim);
+        for d in &deviations {
+            let v = nalgebra::DVector::from_column_slice(d);
+            covariance.ger(1.0, &v, &v, 1.0);
+        }
+        covariance /= deviations.len() as f64;
+
+        let total_variance = covariance.trace();
+        let eigen = SymmetricEigen::new(covariance);
+        let mut order: Vec<usize> = (0..dim).collect();
+        order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+        let components: Vec<NormalMode> = order
+            .into_iter()
+            .take(num_components)
+            .map(|k| NormalMode {
+                eigenvalue: eigen.eigenvalues[k].max(0.0),
+                vector: eigen.eigenvectors.column(k).iter().copied().collect(),
+            })
+            .collect();
+        let projections = deviations
+            .iter()
+            .map(|d| components.iter().map(|c| dot(&c.vector, d)).collect())
+            .collect();
+        Ok(Self {
+            atoms,
+            mean,
+            components,
+            total_variance,
+            projections,
+        })
+    }
+
+    pub fn atoms(&self) -> &[u32] {
+        &self.atoms
+    }
+
+    /// Average superposed structure of the selection
+    pub fn mean(&self) -> &[[f64; 3]] {
+        &self.mean
+    }
+
+    /// Components in decreasing variance order
+    pub fn components(&self) -> &[NormalMode] {
+        &self.components
+    }
+
+    /// Fraction of the total positional variance carried by each component
+    pub fn explained_variance(&self) -> Vec<f64> {
+        self.components
+            .iter()
+            .map(|c| c.eigenvalue / self.total_variance.max(f64::MIN_POSITIVE))
+            .collect()
+    }
+
+    /// Projection of each input frame onto each component (Å), `[frame][component]`
+    pub fn projections(&self) -> &[Vec<f64>] {
+        &self.projections
+    }
+
+    /// Projection of another frame (one `[x, y, z]` per system atom) after
+    /// superposition onto the average structure
+    pub fn project(&self, frame: &[[f32; 3]]) -> Vec<f64> {
+        let fitted = superpose(&self.mean, &select_frame(frame, &self.atoms));
+        let deviation: Vec<f64> = fitted
+            .iter()
+            .zip(&self.mean)
+            .flat_map(|(p, m)| [p[0] - m[0], p[1] - m[1], p[2] - m[2]])
+            .collect();
+        self.components
+            .iter()
+            .map(|c| dot(&c.vector, &deviation))
+            .collect()
+    }
+
+    /// Components as a [`ModeSet`] about the average structure;
+    /// `residues` labels every system atom
+    pub fn mode_set(&self, residues: &[u16]) -> ModeSet {
+        ModeSet {
+            atoms: self.atoms.clone(),
+            residues: self.atoms.iter().map(|&i| residues[i as usize]).collect(),
+            nodes: self.mean.clone(),
+            modes: self.components.clone(),
+        }
+    }
+
+    /// `|p_i · v_j|` between each component and each of `modes` over the
+    /// same atoms, `[component][mode]`
+    pub fn overlaps(&self, modes: &[NormalMode]) -> Vec<Vec<f64>> {
+        overlap_matrix(&self.components, modes)
+    }
+}
+
+/// `|a_i · b_j|` for two mode sets over the same coordinates
+pub fn overlap_matrix(a: &[NormalMode], b: &[NormalMode]) -> Vec<Vec<f64>> {
+    a.iter()
+        .map(|a| b.iter().map(|b| dot(&a.vector, &b.vector).abs()).collect())
+        .collect()
+}
+
+/// Root mean square inner product `sqrt(1/k Σ_ij (a_i · b_j)²)` of the first
+/// `k` modes of each set: 1 for identical subspaces, 0 for orthogonal ones
+pub fn rmsip(a: &[NormalMode], b: &[NormalMode], k: usize) -> f64 {
+    let k = k.min(a.len()).min(b.len());
+    if k == 0 {
+        return 0.0;
+    }
+    let sum: f64 = overlap_matrix(&a[..k], &b[..k])
+        .iter()
+        .flatten()
+        .map(|o| o * o)
+        .sum();
+    (sum / k as f64).sqrt()
+}
+
+fn average(frames: &[Vec<[f64; 3]>]) -> Vec<[f64; 3]> {
+    let n = frames.len() as f64;
+    let mut mean = vec![[0.0; 3]; frames[0].len()];
+    for frame in frames {
+        for (m, p) in mean.iter_mut().zip(frame) {
+            for k in 0..3 {
+                m[k] += p[k] / n;
+            }
+        }
+    }
+    mean
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mode_animation::{AnimatedMode, ModeAnimation, ModeAnimationConfig};
+    use prism_io::sovereign_types::Atom;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha12Rng;
+
+    const BASE: [[f64; 3]; 6] = [
+        [0.0, 0.0, 0.0],
+        [3.8, 0.0, 0.0],
+        [5.0, 3.5, 0.0],
+        [2.0, 5.5, 1.0],
+        [-1.0, 3.5, 2.0],
+        [1.5, 2.0, 4.0],
+    ];
+
+    /// Frames breathing along `pattern` with small isotropic noise
+    fn trajectory(pattern: &[[f64; 3]]) -> Vec<Vec<[f32; 3]>> {
+        let mut rng = ChaCha12Rng::seed_from_u64(3);
+        (0..200)
+            .map(|t| {
+                let amplitude = (0.1 * t as f64).sin();
+                BASE.iter()
+                    .zip(pattern)
+                    .map(|(b, d)| {
+                        [0, 1, 2].map(|k| {
+                            (b[k] + amplitude * d[k] + 0.02 * (rng.gen::<f64>() - 0.5)) as f32
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Atoms 0 and 1 pulled apart along their bond
+    fn stretch() -> Vec<[f64; 3]> {
+        let mut pattern = vec![[0.0; 3]; 6];
+        pattern[0] = [-0.5, 0.0, 0.0];
+        pattern[1] = [0.5, 0.0, 0.0];
+        pattern
+    }
+
+    #[test]
+    fn test_first_component_recovers_breathing_direction() {
+        let frames = trajectory(&stretch());
+        let pca = Pca::new(&frames, &[], 4).unwrap();
+        let expected: Vec<f64> = stretch()
+            .iter()
+            .flatten()
+            .map(|x| x / 0.5f64.sqrt())
+            .collect();
+        let overlap = dot(&pca.components()[0].vector, &expected).abs();
+        assert!(overlap > 0.95, "overlap {}", overlap);
+        let explained = pca.explained_variance();
+        assert!(explained[0] > 0.9 && explained[1] < explained[0]);
+
+        // Projection variance is the component eigenvalue
+        let p: Vec<f64> = pca.projections().iter().map(|p| p[0]).collect();
+        let variance = p.iter().map(|x| x * x).sum::<f64>() / p.len() as f64;
+        assert!((variance - pca.components()[0].eigenvalue).abs() < 1e-6);
+        assert!((pca.project(&frames[17])[0] - p[17]).abs() < 1e-3);
+
+        assert!(Pca::new(&frames[..1], &[], 2).is_err());
+    }
+
+    #[test]
+    fn test_mode_export_rmsip_and_animation() {
+        let pca = Pca::new(&trajectory(&stretch()), &[], 3).unwrap();
+        assert!((rmsip(pca.components(), pca.components(), 3) - 1.0).abs() < 1e-9);
+        let overlaps = pca.overlaps(pca.components());
+        assert!((overlaps[0][0] - 1.0).abs() < 1e-9 && overlaps[0][1] < 1e-9);
+
+        let set = pca.mode_set(&[0, 1, 2, 3, 4, 5]);
+        let path = std::env::temp_dir().join(format!("prism_pca_{}.json", std::process::id()));
+        set.write_json(&path).unwrap();
+        let read = ModeSet::read_json(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!((read.atoms.len(), read.modes.len()), (6, 3));
+        assert!((rmsip(&read.modes, &set.modes, 3) - 1.0).abs() < 1e-9);
+
+        let atoms: Vec<Atom> = set
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Atom {
+                coords: p.map(|c| c as f32),
+                element: 6,
+                residue_id: i as u16,
+                atom_type: 0,
+                charge: 0.0,
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        let config = ModeAnimationConfig {
+            modes: vec![AnimatedMode {
+                mode: 0,
+                amplitude: pca.components()[0].eigenvalue.sqrt(),
+            }],
+            frames_per_cycle: 4,
+            ..Default::default()
+        };
+        let animation = ModeAnimation::with_modes(&read, &atoms, &config).unwrap();
+        assert_eq!(animation.frames().len(), 4);
+        assert_ne!(animation.frames()[1][0], atoms[0].coords);
+    }
+}
@@ -0,0 +1,209 @@
+//! # RMSD / RMSF
+//! Root-mean-square deviation from a reference after Kabsch superposition,
+//! and per-atom root-mean-square fluctuation about the mean superposed
+//! structure. Atom selections are system indices; an empty selection means
+//! every atom. Units: Å.
+
+use super::{select_frame, select_positions, superpose, Analysis};
+use serde_json::json;
+
+/// RMSD between two equally sized coordinate sets after superposition
+pub fn rmsd(reference: &[[f64; 3]], frame: &[[f64; 3]]) -> f64 {
+    let fitted = superpose(reference, frame);
+    let n = reference.len().max(1) as f64;
+    (reference
+        .iter()
+        .zip(&fitted)
+        .map(|(r, f)| distance2(r, f))
+        .sum::<f64>()
+        / n)
+        .sqrt()
+}
+
+/// RMSD of each stored frame to `reference`
+pub fn rmsd_series(reference: &[[f32; 3]], frames: &[Vec<[f32; 3]>], atoms: &[u32]) -> Vec<f64> {
+    let reference = select_frame(reference, atoms);
+    frames
+        .iter()
+        .map(|f| rmsd(&reference, &select_frame(f, atoms)))
+        .collect()
+}
+
+/// Per-atom RMSF over stored frames, each superposed onto `reference`
+pub fn rmsf(reference: &[[f32; 3]], frames: &[Vec<[f32; 3]>], atoms: &[u32]) -> Vec<f64> {
+    let reference = select_frame(reference, atoms);
+    let mut accumulator = Fluctuations::new(reference.len());
+    for frame in frames {
+        accumulator.add(&superpose(&reference, &select_frame(frame, atoms)));
+    }
+    accumulator.rmsf()
+}
+
+/// Running first and second moments of superposed coordinates
+#[derive(Debug, Clone)]
+struct Fluctuations {
+    count: usize,
+    sum: Vec<[f64; 3]>,
+    sum_sq: Vec<f64>,
+}
+
+impl Fluctuations {
+    fn new(n: usize) -> Self {
+        Self {
+            count: 0,
+            sum: vec![[0.0; 3]; n],
+            sum_sq: vec![0.0; n],
+        }
+    }
+
+    fn add(&mut self, fitted: &[[f64; 3]]) {
+        self.count += 1;
+        for ((s, sq), p) in self.sum.iter_mut().zip(&mut self.sum_sq).zip(fitted) {
+            for k in 0..3 {
+                s[k] += p[k];
+            }
+            *sq += p.iter().map(|c| c * c).sum::<f64>();
+        }
+    }
+
+    fn rmsf(&self) -> Vec<f64> {
+        let n = self.count.max(1) as f64;
+        self.sum
+            .iter()
+            .zip(&self.sum_sq)
+            .map(|(s, sq)| {
+                let mean2 = s.iter().map(|c| (c / n) * (c / n)).sum::<f64>();
+                (sq / n - mean2).max(0.0).sqrt()
+            })
+            .collect()
+    }
+}
+
+/// On-the-fly RMSD time series and RMSF profile
+#[derive(Debug, Clone)]
+pub struct RmsdAnalysis {
+    atoms: Vec<u32>,
+    reference: Vec<[f64; 3]>,
+    series: Vec<(u64, f64)>,
+    fluctuations: Fluctuations,
+}
+
+impl RmsdAnalysis {
+    /// Track `atoms` against the Float4-stride `reference` positions
+    pub fn new(reference: &[f32], atoms: Vec<u32>) -> Self {
+        let reference = select_positions(reference, &atoms);
+        let fluctuations = Fluctuations::new(reference.len());
+        Self {
+            atoms,
+            reference,
+            series: Vec::new(),
+            fluctuations,
+        }
+    }
+
+    /// `(step, RMSD)` samples
+    pub fn series(&self) -> &[(u64, f64)] {
+        &self.series
+    }
+
+    /// RMSF of each selected atom over the observed frames
+    pub fn rmsf(&self) -> Vec<f64> {
+        self.fluctuations.rmsf()
+    }
+}
+
+impl Analysis for RmsdAnalysis {
+    fn name(&self) -> &str {
+        "rmsd"
+    }
+
+    fn observe(&mut self, step: u64, positions: &[f32]) {
+        let frame = select_positions(positions, &self.atoms);
+        let fitted = superpose(&self.reference, &frame);
+        let n = self.reference.len().max(1) as f64;
+        let msd = self
+            .reference
+            .iter()
+            .zip(&fitted)
+            .map(|(r, f)| distance2(r, f))
+            .sum::<f64>()
+            / n;
+        self.series.push((step, msd.sqrt()));
+        self.fluctuations.add(&fitted);
+    }
+
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        let series: Vec<[f64; 2]> = self.series.iter().map(|&(s, r)| [s as f64, r]).collect();
+        vec![
+            ("rmsd".to_string(), json!(series)),
+            ("rmsf".to_string(), json!(self.rmsf())),
+        ]
+    }
+
+    fn observables(&self) -> Vec<(&'static str, f64)> {
+        self.series
+            .last()
+            .map(|&(_, r)| ("rmsd", r))
+            .into_iter()
+            .collect()
+    }
+}
+
+fn distance2(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).map(|k| (a[k] - b[k]) * (a[k] - b[k])).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(offset: f32, wiggle: f32) -> Vec<[f32; 3]> {
+        vec![
+            [0.0 + offset, 0.0, 0.0],
+            [1.5 + offset, wiggle, 0.0],
+            [1.5 + offset, 1.5, 0.0],
+            [0.0 + offset, 1.5, 1.0],
+        ]
+    }
+
+    #[test]
+    fn test_rmsd_ignores_rigid_motion() {
+        let reference = frame(0.0, 0.0);
+        // Rotated by 90° about z and translated
+        let rotated: Vec<[f32; 3]> = reference
+            .iter()
+            .map(|p| [-p[1] + 3.0, p[0], p[2]])
+            .collect();
+        let series = rmsd_series(&reference, &[rotated, frame(0.0, 0.4)], &[]);
+        assert!(series[0] < 1e-6);
+        assert!(series[1] > 0.05);
+        // Selecting the untouched atoms only
+        let selected = rmsd_series(&reference, &[frame(2.0, 0.4)], &[0, 2, 3]);
+        assert!(selected[0] < 1e-6);
+    }
+
+    #[test]
+    fn test_rmsf_localises_motion() {
+        let reference = frame(0.0, 0.0);
+        let frames: Vec<Vec<[f32; 3]>> = (0..10)
+            .map(|k| frame(k as f32, if k % 2 == 0 { 0.3 } else { -0.3 }))
+            .collect();
+        let profile = rmsf(&reference, &frames, &[]);
+        assert!(profile[1] > profile[3]);
+        assert!(profile[1] > 0.1);
+
+        // The observer agrees with post-processing
+        let float4 = |f: &[[f32; 3]]| -> Vec<f32> {
+            f.iter().flat_map(|p| [p[0], p[1], p[2], 1.0]).collect()
+        };
+        let mut observer = RmsdAnalysis::new(&float4(&reference), Vec::new());
+        for (step, f) in frames.iter().enumerate() {
+            observer.observe(step as u64, &float4(f));
+        }
+        let series = rmsd_series(&reference, &frames, &[]);
+        assert!((observer.series()[3].1 - series[3]).abs() < 1e-9);
+        for (a, b) in observer.rmsf().iter().zip(&profile) {
+            assert!((a - b).abs() < 1e-9);
+        }
+    }
+}
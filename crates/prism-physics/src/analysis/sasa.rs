@@ -0,0 +1,391 @@
+//! # Solvent Accessible Surface Area
+//! Shrake-Rupley SASA: each atom's sphere of radius `r_vdW + probe` is
+//! covered by a golden-spiral set of test points, and a point counts as
+//! exposed when it lies outside every other expanded sphere. The atom's
+//! area is its exposed fraction times `4π (r_vdW + probe)²`; residue areas
+//! are sums over their atoms. On the host, overlapping spheres are found
+//! through a cell grid; with `use_gpu` (feature `cuda`) the points are
+//! tested on the device instead. Units: Å, Å².
+
+use super::Analysis;
+use prism_core::PrismError;
+use prism_io::topology::Topology;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SasaConfig {
+    /// Solvent probe radius (Å)
+    pub probe_radius: f64,
+    /// Test points per atom sphere
+    pub num_points: usize,
+    /// Test the points with the CUDA kernel (feature `cuda`)
+    pub use_gpu: bool,
+}
+
+impl Default for SasaConfig {
+    fn default() -> Self {
+        Self {
+            probe_radius: 1.4,
+            num_points: 100,
+            use_gpu: false,
+        }
+    }
+}
+
+/// Areas of one configuration (Å²)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SasaResult {
+    pub per_atom: Vec<f64>,
+    /// `(residue, area)` in increasing residue order
+    pub per_residue: Vec<(u16, f64)>,
+    pub total: f64,
+}
+
+/// Bondi van der Waals radius (Å) for atoms without one in the topology;
+/// 1.8 Å for P, S and anything else
+fn element_radius(element: u8) -> f64 {
+    match element {
+        1 => 1.2,
+        6 => 1.7,
+        7 => 1.55,
+        8 => 1.52,
+        _ => 1.8,
+    }
+}
+
+/// `n` nearly uniform unit vectors on a golden spiral
+pub fn sphere_points(n: usize) -> Vec<[f64; 3]> {
+    let golden = std::f64::consts::PI * (3.0 - 5.0f64.sqrt());
+    (0..n)
+        .map(|k| {
+            let z = 1.0 - (2 * k + 1) as f64 / n as f64;
+            let r = (1.0 - z * z).sqrt();
+            let phi = golden * k as f64;
+            [r * phi.cos(), r * phi.sin(), z]
+        })
+        .collect()
+}
+
+/// Shrake-Rupley evaluator for a fixed set of atoms
+#[derive(Debug)]
+pub struct SasaCalculator {
+    /// Expanded radii `r_vdW + probe`
+    radii: Vec<f64>,
+    residues: Vec<u16>,
+    points: Vec<[f64; 3]>,
+    #[cfg(feature = "cuda")]
+    gpu: Option<prism_gpu::sasa::SasaGpu>,
+}
+
+impl SasaCalculator {
+    /// Atoms with van der Waals `radii` (Å) labelled by `residues`
+    pub fn new(radii: &[f64], residues: Vec<u16>, config: &SasaConfig) -> Result<Self, PrismError> {
+        if radii.is_empty() || radii.len() != residues.len() {
+            return Err(PrismError::validation(format!(
+                "SASA needs one residue per atom, got {} radii and {} residues",
+                radii.len(),
+                residues.len()
+            )));
+        }
+        if config.num_points == 0 || config.probe_radius < 0.0 {
+            return Err(PrismError::config(format!(
+                "Invalid SASA settings: {} points, probe {} Å",
+                config.num_points, config.probe_radius
+            )));
+        }
+        let radii: Vec<f64> = radii.iter().map(|r| r + config.probe_radius).collect();
+        let points = sphere_points(config.num_points);
+        #[cfg(feature = "cuda")]
+        let gpu = if config.use_gpu {
+            let ctx = cudarc::driver::CudaContext::new(0)
+                .map_err(|e| PrismError::gpu("init", format!("{:?}", e)))?;
+            let radii: Vec<f32> = radii.iter().map(|&r| r as f32).collect();
+            let points: Vec<[f32; 3]> = points.iter().map(|p| p.map(|c| c as f32)).collect();
+            let gpu = prism_gpu::sasa::SasaGpu::new(ctx, &radii, &points)
+                .map_err(|e| PrismError::gpu("sasa", e.to_string()))?;
+            Some(gpu)
+        } else {
+            None
+        };
+        #[cfg(not(feature = "cuda"))]
+        if config.use_gpu {
+            log::warn!("⚠️ SASA on GPU requested without the cuda feature; using the host");
+        }
+        Ok(Self {
+            radii,
+            residues,
+            points,
+            #[cfg(feature = "cuda")]
+            gpu,
+        })
+    }
+
+    /// Topology atoms with their radii (element defaults where unset)
+    pub fn from_topology(topology: &Topology, config: &SasaConfig) -> Result<Self, PrismError> {
+        let radii: Vec<f64> = topology
+            .atoms
+            .iter()
+            .map(|a| {
+                if a.radius > 0.0 {
+                    a.radius as f64
+                } else {
+                    element_radius(a.element)
+                }
+            })
+            .collect();
+        let residues = topology.atoms.iter().map(|a| a.residue_id).collect();
+        Self::new(&radii, residues, config)
+    }
+
+    pub fn num_atoms(&self) -> usize {
+        self.radii.len()
+    }
+
+    /// Areas for per-atom coordinates
+    pub fn compute(&self, coordinates: &[[f64; 3]]) -> SasaResult {
+        let exposed = self.exposed_fractions(coordinates);
+        self.result(&exposed)
+    }
+
+    /// Areas for a Float4-stride position buffer
+    pub fn compute_positions(&mut self, positions: &[f32]) -> SasaResult {
+        #[cfg(feature = "cuda")]
+        if let Some(gpu) = &mut self.gpu {
+            match gpu.compute(positions) {
+                Ok(exposed) => {
+                    let exposed: Vec<f64> = exposed.iter().map(|&f| f as f64).collect();
+                    return self.result(&exposed);
+                }
+                Err(e) => log::warn!("⚠️ GPU SASA failed ({}); using the host", e),
+            }
+        }
+        let coordinates: Vec<[f64; 3]> = positions
+            .chunks_exact(4)
+            .map(|p| [p[0] as f64, p[1] as f64, p[2] as f64])
+            .collect();
+        self.compute(&coordinates)
+    }
+
+    /// Areas of each stored frame
+    pub fn compute_frames(&self, frames: &[Vec<[f32; 3]>]) -> Vec<SasaResult> {
+        frames
+            .iter()
+            .map(|f| self.compute(&f.iter().map(|p| p.map(|c| c as f64)).collect::<Vec<_>>()))
+            .collect()
+    }
+
+    fn exposed_fractions(&self, coordinates: &[[f64; 3]]) -> Vec<f64> {
+        // Cells at least one expanded diameter wide: overlapping spheres
+        // are always in neighbouring cells
+        let edge = 2.0 * self.radii.iter().cloned().fold(0.0, f64::max);
+        let cell = |p: &[f64; 3]| p.map(|c| (c / edge).floor() as i64);
+        let mut grid: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        for (i, p) in coordinates.iter().enumerate() {
+            grid.entry(cell(p)).or_default().push(i);
+        }
+        coordinates
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let ri = self.radii[i];
+                let [cx, cy, cz] = cell(p);
+                let mut neighbours = Vec::new();
+                for key in (-1..=1).flat_map(|dx| {
+                    (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [cx + dx, cy + dy, cz + dz]))
+                }) {
+                    for &j in grid.get(&key).into_iter().flatten() {
+                        let reach = ri + self.radii[j];
+                        if j != i && distance2(p, &coordinates[j]) < reach * reach {
+                            neighbours.push(j);
+                        }
+                    }
+                }
+                let mut exposed = 0;
+                // Start each search at the last blocking neighbour
+                let mut last = 0;
+                for u in &self.points {
+                    let point = [0, 1, 2].map(|k| p[k] + ri * u[k]);
+                    let buried =
+                        |j: usize| distance2(&point, &coordinates[j]) < self.radii[j].powi(2);
+                    if neighbours.get(last).is_some_and(|&j| buried(j)) {
+                        continue;
+                    }
+                    match neighbours.iter().position(|&j| buried(j)) {
+                        Some(k) => last = k,
+                        None => exposed += 1,
+                    }
+                }
+                exposed as f64 / self.points.len() as f64
+            })
+            .collect()
+    }
+
+    fn result(&self, exposed: &[f64]) -> SasaResult {
+        let per_atom: Vec<f64> = exposed
+            .iter()
+            .zip(&self.radii)
+            .map(|(f, r)| f * 4.0 * std::f64::consts::PI * r * r)
+            .collect();
+        let mut residues: BTreeMap<u16, f64> = BTreeMap::new();
+        for (area, &residue) in per_atom.iter().zip(&self.residues) {
+            *residues.entry(residue).or_default() += area;
+        }
+        SasaResult {
+            total: per_atom.iter().sum(),
+            per_atom,
+            per_residue: residues.into_iter().collect(),
+        }
+    }
+}
+
+fn distance2(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+/// Total SASA per frame with per-residue means over the run
+#[derive(Debug)]
+pub struct SasaAnalysis {
+    calculator: SasaCalculator,
+    series: Vec<(u64, f64)>,
+    residue_sums: Vec<(u16, f64)>,
+}
+
+impl SasaAnalysis {
+    pub fn new(calculator: SasaCalculator) -> Self {
+        Self {
+            calculator,
+            series: Vec::new(),
+            residue_sums: Vec::new(),
+        }
+    }
+
+    pub fn series(&self) -> &[(u64, f64)] {
+        &self.series
+    }
+
+    /// Mean area of each residue over the observed frames
+    pub fn residue_means(&self) -> Vec<(u16, f64)> {
+        let frames = self.series.len().max(1) as f64;
+        self.residue_sums
+            .iter()
+            .map(|&(residue, sum)| (residue, sum / frames))
+            .collect()
+    }
+}
+
+impl Analysis for SasaAnalysis {
+    fn name(&self) -> &str {
+        "sasa"
+    }
+
+    fn observe(&mut self, step: u64, positions: &[f32]) {
+        let result = self.calculator.compute_positions(positions);
+        if self.residue_sums.is_empty() {
+            self.residue_sums = result.per_residue.iter().map(|&(r, _)| (r, 0.0)).collect();
+        }
+        for (sum, (_, area)) in self.residue_sums.iter_mut().zip(&result.per_residue) {
+            sum.1 += area;
+        }
+        self.series.push((step, result.total));
+    }
+
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        let series: Vec<[f64; 2]> = self
+            .series
+            .iter()
+            .map(|&(step, area)| [step as f64, area])
+            .collect();
+        vec![
+            ("sasa".to_string(), json!(series)),
+            ("sasa_residues".to_string(), json!(self.residue_means())),
+        ]
+    }
+
+    fn observables(&self) -> Vec<(&'static str, f64)> {
+        self.series
+            .last()
+            .map(|&(_, area)| ("sasa", area))
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_isolated_and_overlapping_spheres() {
+        let config = SasaConfig {
+            num_points: 500,
+            ..Default::default()
+        };
+        let calculator = SasaCalculator::new(&[1.6, 1.6], vec![0, 1], &config).unwrap();
+        let far = calculator.compute(&[[0.0; 3], [20.0, 0.0, 0.0]]);
+        let sphere = 4.0 * PI * 3.0f64.powi(2);
+        assert!((far.per_atom[0] - sphere).abs() < 1e-9);
+        assert!((far.total - 2.0 * sphere).abs() < 1e-9);
+
+        // Two expanded spheres of radius 3 at distance 3 each lose a cap of
+        // height 1.5: exposed area 2π r (r + d/2)
+        let near = calculator.compute(&[[0.0; 3], [3.0, 0.0, 0.0]]);
+        let exact = 2.0 * PI * 3.0 * 4.5;
+        assert!(
+            (near.per_atom[0] - exact).abs() < 0.02 * exact,
+            "{:?}",
+            near
+        );
+        assert_eq!(near.per_residue.len(), 2);
+
+        assert!(SasaCalculator::new(&[1.0], vec![], &config).is_err());
+        let bad = SasaConfig {
+            num_points: 0,
+            ..Default::default()
+        };
+        assert!(SasaCalculator::new(&[1.0], vec![0], &bad).is_err());
+    }
+
+    #[test]
+    fn test_buried_atom_and_trajectory_analysis() {
+        // Central atom enclosed by an octahedron of neighbours
+        let mut coords = vec![[0.0; 3]];
+        for axis in 0..3 {
+            for sign in [-1.0, 1.0] {
+                let mut p = [0.0; 3];
+                p[axis] = 2.0 * sign;
+                coords.push(p);
+            }
+        }
+        let radii = vec![1.7; 7];
+        let residues = vec![0, 1, 1, 1, 1, 1, 1];
+        let mut calculator = SasaCalculator::new(&radii, residues, &SasaConfig::default()).unwrap();
+        let result = calculator.compute(&coords);
+        assert!(result.per_atom[0] < 0.05 * result.per_atom[1]);
+        assert_eq!(result.per_residue[0].0, 0);
+
+        let frame: Vec<[f32; 3]> = coords.iter().map(|p| p.map(|c| c as f32)).collect();
+        let expanded: Vec<[f32; 3]> = frame.iter().map(|p| p.map(|c| 3.0 * c)).collect();
+        let totals: Vec<f64> = calculator
+            .compute_frames(&[frame.clone(), expanded])
+            .iter()
+            .map(|r| r.total)
+            .collect();
+        assert!(totals[1] > totals[0]);
+
+        let positions: Vec<f32> = frame
+            .iter()
+            .flat_map(|p| [p[0], p[1], p[2], 12.0])
+            .collect();
+        assert!((calculator.compute_positions(&positions).total - totals[0]).abs() < 1e-3);
+        let mut analysis = SasaAnalysis::new(calculator);
+        analysis.observe(5, &positions);
+        analysis.observe(10, &positions);
+        assert_eq!(analysis.series().len(), 2);
+        assert!((analysis.residue_means()[1].1 - result.per_residue[1].1).abs() < 1e-3);
+        assert_eq!(analysis.telemetry()[0].0, "sasa");
+    }
+}
@@ -0,0 +1,203 @@
+//! # Shape Descriptors
+//! Radius of gyration and the principal moments `λ1 ≤ λ2 ≤ λ3` (Å²) of the
+//! mass-weighted gyration tensor `S = Σ m (r - c)(r - c)ᵀ / M`, with
+//!
+//! - asphericity `b = λ3 - (λ1 + λ2) / 2` (0 for spherical symmetry)
+//! - acylindricity `c = λ2 - λ1`
+//! - relative shape anisotropy `κ² = 3/2 Σλ² / (Σλ)² - 1/2`, 0 for a
+//!   sphere and 1 for a rod
+//!
+//! Tracking these over a breathing run shows compaction and elongation.
+
+use super::Analysis;
+use nalgebra::{Matrix3, SymmetricEigen, Vector3};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShapeDescriptors {
+    /// `sqrt(λ1 + λ2 + λ3)` (Å)
+    pub radius_of_gyration: f64,
+    /// Gyration tensor eigenvalues in increasing order (Å²)
+    pub principal_moments: [f64; 3],
+    pub asphericity: f64,
+    pub acylindricity: f64,
+    pub anisotropy: f64,
+}
+
+impl ShapeDescriptors {
+    /// Descriptors of `coordinates` weighted by `masses` (uniform if empty)
+    pub fn compute(coordinates: &[[f64; 3]], masses: &[f64]) -> Self {
+        let weight = |i: usize| masses.get(i).copied().unwrap_or(1.0);
+        let total: f64 = (0..coordinates.len()).map(weight).sum();
+        if total <= 0.0 {
+            return Self::default();
+        }
+        let centre = coordinates
+            .iter()
+            .enumerate()
+            .map(|(i, r)| Vector3::from(*r) * weight(i))
+            .sum::<Vector3<f64>>()
+            / total;
+        let mut tensor = Matrix3::zeros();
+        for (i, r) in coordinates.iter().enumerate() {
+            let d = Vector3::from(*r) - centre;
+            tensor += d * d.transpose() * weight(i);
+        }
+        tensor /= total;
+
+        let mut l: Vec<f64> = SymmetricEigen::new(tensor)
+            .eigenvalues
+            .iter()
+            .map(|l| l.max(0.0))
+            .collect();
+        l.sort_by(f64::total_cmp);
+        let trace = l[0] + l[1] + l[2];
+        let anisotropy = if trace > 0.0 {
+            1.5 * (l[0] * l[0] + l[1] * l[1] + l[2] * l[2]) / (trace * trace) - 0.5
+        } else {
+            0.0
+        };
+        Self {
+            radius_of_gyration: trace.sqrt(),
+            principal_moments: [l[0], l[1], l[2]],
+            asphericity: l[2] - 0.5 * (l[0] + l[1]),
+            acylindricity: l[1] - l[0],
+            anisotropy,
+        }
+    }
+
+    /// Mass-weighted descriptors of `atoms` (all when empty) in a
+    /// Float4-stride buffer
+    pub fn from_positions(positions: &[f32], atoms: &[u32]) -> Self {
+        let indices: Vec<usize> = if atoms.is_empty() {
+            (0..positions.len() / 4).collect()
+        } else {
+            atoms.iter().map(|&i| i as usize).collect()
+        };
+        let coordinates: Vec<[f64; 3]> = indices
+            .iter()
+            .map(|&i| [0, 1, 2].map(|k| positions[i * 4 + k] as f64))
+            .collect();
+        let masses: Vec<f64> = indices
+            .iter()
+            .map(|&i| positions[i * 4 + 3] as f64)
+            .collect();
+        Self::compute(&coordinates, &masses)
+    }
+}
+
+/// On-the-fly shape descriptor time series
+#[derive(Debug, Clone, Default)]
+pub struct ShapeAnalysis {
+    atoms: Vec<u32>,
+    series: Vec<(u64, ShapeDescriptors)>,
+}
+
+impl ShapeAnalysis {
+    /// Track `atoms` (all when empty)
+    pub fn new(atoms: Vec<u32>) -> Self {
+        Self {
+            atoms,
+            series: Vec::new(),
+        }
+    }
+
+    pub fn series(&self) -> &[(u64, ShapeDescriptors)] {
+        &self.series
+    }
+}
+
+impl Analysis for ShapeAnalysis {
+    fn name(&self) -> &str {
+        "shape"
+    }
+
+    fn observe(&mut self, step: u64, positions: &[f32]) {
+        self.series.push((
+            step,
+            ShapeDescriptors::from_positions(positions, &self.atoms),
+        ));
+    }
+
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        let column = |f: fn(&ShapeDescriptors) -> f64| -> Vec<[f64; 2]> {
+            self.series
+                .iter()
+                .map(|(step, d)| [*step as f64, f(d)])
+                .collect()
+        };
+        let moments: Vec<[f64; 4]> = self
+            .series
+            .iter()
+            .map(|(step, d)| {
+                let [a, b, c] = d.principal_moments;
+                [*step as f64, a, b, c]
+            })
+            .collect();
+        vec![
+            (
+                "radius_of_gyration".to_string(),
+                json!(column(|d| d.radius_of_gyration)),
+            ),
+            ("asphericity".to_string(), json!(column(|d| d.asphericity))),
+            (
+                "shape_anisotropy".to_string(),
+                json!(column(|d| d.anisotropy)),
+            ),
+            ("gyration_moments".to_string(), json!(moments)),
+        ]
+    }
+
+    fn observables(&self) -> Vec<(&'static str, f64)> {
+        let Some((_, d)) = self.series.last() else {
+            return Vec::new();
+        };
+        vec![
+            ("radius_of_gyration", d.radius_of_gyration),
+            ("asphericity", d.asphericity),
+            ("acylindricity", d.acylindricity),
+            ("shape_anisotropy", d.anisotropy),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rod_and_octahedron() {
+        let rod: Vec<[f64; 3]> = (0..11).map(|i| [0.0, 0.0, i as f64]).collect();
+        let d = ShapeDescriptors::compute(&rod, &[]);
+        assert!((d.radius_of_gyration - 10.0f64.sqrt()).abs() < 1e-9);
+        assert!((d.anisotropy - 1.0).abs() < 1e-9);
+        assert!(d.principal_moments[0].abs() < 1e-9);
+
+        let octahedron = [
+            [1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, -1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+        ];
+        let d = ShapeDescriptors::compute(&octahedron, &[]);
+        assert!(d.asphericity.abs() < 1e-9 && d.anisotropy.abs() < 1e-9);
+        assert!((d.radius_of_gyration - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mass_weighting_and_observer() {
+        // A heavy atom pulls the centre of mass towards it
+        let positions = [0.0, 0.0, 0.0, 3.0, 4.0, 0.0, 0.0, 1.0];
+        let d = ShapeDescriptors::from_positions(&positions, &[]);
+        assert!((d.radius_of_gyration - 3.0f64.sqrt()).abs() < 1e-6);
+
+        let mut analysis = ShapeAnalysis::new(vec![]);
+        analysis.observe(10, &positions);
+        let telemetry = analysis.telemetry();
+        assert_eq!(telemetry[0].0, "radius_of_gyration");
+        assert_eq!(telemetry[0].1[0][0], 10.0);
+    }
+}
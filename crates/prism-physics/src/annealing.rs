@@ -0,0 +1,143 @@
+//! # Annealing - Thermostat Temperature Schedules
+//! Target temperature as a function of the step count, used by the host
+//! integrators and passed per launch to the fused GPU step kernel.
+//! Temperatures are on the `temp_start` scale (kT in kcal/mol).
+
+use prism_core::PrismError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TemperatureSchedule {
+    Constant {
+        temperature: f32,
+    },
+    /// Linear ramp from `start` to `end` over `steps`, then hold
+    Linear {
+        start: f32,
+        end: f32,
+        steps: u64,
+    },
+    /// Geometric cooling `start (end / start)^(step / steps)`, then hold
+    Exponential {
+        start: f32,
+        end: f32,
+        steps: u64,
+    },
+    /// Linear interpolation between `(step, temperature)` knots in
+    /// increasing step order, holding the end values outside them
+    Piecewise {
+        points: Vec<(u64, f32)>,
+    },
+}
+
+impl TemperatureSchedule {
+    pub fn validate(&self) -> Result<(), PrismError> {
+        match self {
+            TemperatureSchedule::Constant { temperature } if *temperature < 0.0 => Err(
+                PrismError::config("Schedule temperature must be non-negative"),
+            ),
+            TemperatureSchedule::Linear { start, end, .. } if start.min(*end) < 0.0 => Err(
+                PrismError::config("Linear schedule temperatures must be non-negative"),
+            ),
+            TemperatureSchedule::Exponential { start, end, .. } if start.min(*end) <= 0.0 => Err(
+                PrismError::config("Exponential schedule temperatures must be positive"),
+            ),
+            TemperatureSchedule::Piecewise { points } => {
+                if points.is_empty() {
+                    return Err(PrismError::config(
+                        "Piecewise schedule needs at least one point",
+                    ));
+                }
+                if points.windows(2).any(|w| w[1].0 <= w[0].0) {
+                    return Err(PrismError::config(
+                        "Piecewise schedule steps must be strictly increasing",
+                    ));
+                }
+                if points.iter().any(|&(_, t)| t < 0.0) {
+                    return Err(PrismError::config(
+                        "Piecewise schedule temperatures must be non-negative",
+                    ));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Target temperature after `step` steps
+    pub fn temperature_at(&self, step: u64) -> f32 {
+        match self {
+            TemperatureSchedule::Constant { temperature } => *temperature,
+            TemperatureSchedule::Linear { start, end, steps } => {
+                start + (end - start) * progress(step, *steps)
+            }
+            TemperatureSchedule::Exponential { start, end, steps } => {
+                start * (end / start).powf(progress(step, *steps))
+            }
+            TemperatureSchedule::Piecewise { points } => {
+                let k = points.partition_point(|&(s, _)| s <= step);
+                match (k.checked_sub(1).map(|i| points[i]), points.get(k)) {
+                    (Some((s0, t0)), Some(&(s1, t1))) => {
+                        t0 + (t1 - t0) * ((step - s0) as f32 / (s1 - s0) as f32)
+                    }
+                    (Some((_, t)), None) | (None, Some(&(_, t))) => t,
+                    (None, None) => 0.0,
+                }
+            }
+        }
+    }
+}
+
+fn progress(step: u64, steps: u64) -> f32 {
+    (step as f32 / steps.max(1) as f32).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedules_interpolate_and_hold() {
+        let linear = TemperatureSchedule::Linear {
+            start: 2.0,
+            end: 1.0,
+            steps: 100,
+        };
+        assert_eq!(linear.temperature_at(50), 1.5);
+        assert_eq!(linear.temperature_at(500), 1.0);
+
+        let exponential = TemperatureSchedule::Exponential {
+            start: 4.0,
+            end: 1.0,
+            steps: 100,
+        };
+        assert!((exponential.temperature_at(50) - 2.0).abs() < 1e-6);
+        assert!((exponential.temperature_at(1000) - 1.0).abs() < 1e-6);
+
+        let piecewise = TemperatureSchedule::Piecewise {
+            points: vec![(10, 1.0), (20, 3.0), (40, 3.0), (50, 0.5)],
+        };
+        assert_eq!(piecewise.temperature_at(0), 1.0);
+        assert_eq!(piecewise.temperature_at(15), 2.0);
+        assert_eq!(piecewise.temperature_at(30), 3.0);
+        assert_eq!(piecewise.temperature_at(45), 1.75);
+        assert_eq!(piecewise.temperature_at(99), 0.5);
+    }
+
+    #[test]
+    fn test_invalid_schedules_rejected() {
+        let unordered = TemperatureSchedule::Piecewise {
+            points: vec![(10, 1.0), (10, 2.0)],
+        };
+        assert!(unordered.validate().is_err());
+        let cold = TemperatureSchedule::Exponential {
+            start: 1.0,
+            end: 0.0,
+            steps: 10,
+        };
+        assert!(cold.validate().is_err());
+        assert!(TemperatureSchedule::Constant { temperature: 0.6 }
+            .validate()
+            .is_ok());
+    }
+}
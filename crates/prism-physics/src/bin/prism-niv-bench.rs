@@ -279,6 +279,10 @@ fn display_scientific_results(
             log::warn!("   Reason: {}", reason);
             log::warn!("   Backoff: {}ms", backoff_ms);
         }
+        prism_core::PhaseOutcome::Cancelled { reason, .. } => {
+            log::warn!("⏹️ Phase Outcome: CANCELLED");
+            log::warn!("   Reason: {}", reason);
+        }
     }
 
     log::info!("");
@@ -0,0 +1,313 @@
+//! # Bonded Terms - Bonds, Angles, Torsions, Impropers
+//! Evaluates the covalent terms of an imported topology on Float4-stride
+//! position buffers. Conventions follow `prism_io::topology`:
+//! harmonic terms are `k (x - x0)^2`, torsions `k (1 + cos(n φ - δ))`.
+
+use prism_io::topology::{
+    HarmonicAngle, HarmonicBond, HarmonicImproper, PeriodicDihedral, Topology,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Terms evaluated in parallel before their forces are summed
+const TERM_BLOCK: usize = 4096;
+
+/// Bonded energy terms (kcal/mol)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BondedEnergy {
+    pub bond: f64,
+    pub angle: f64,
+    pub dihedral: f64,
+    pub improper: f64,
+}
+
+impl BondedEnergy {
+    pub fn total(&self) -> f64 {
+        self.bond + self.angle + self.dihedral + self.improper
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BondedTerms {
+    pub bonds: Vec<HarmonicBond>,
+    pub angles: Vec<HarmonicAngle>,
+    pub dihedrals: Vec<PeriodicDihedral>,
+    pub impropers: Vec<HarmonicImproper>,
+}
+
+type Vec3 = [f64; 3];
+
+#[inline]
+fn load(p: &[f32], i: u32) -> Vec3 {
+    let o = i as usize * 4;
+    [p[o] as f64, p[o + 1] as f64, p[o + 2] as f64]
+}
+
+#[inline]
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[inline]
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[inline]
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[inline]
+fn add_force(f: &mut [f32], i: u32, v: Vec3, scale: f64) {
+    let o = i as usize * 4;
+    f[o] += (v[0] * scale) as f32;
+    f[o + 1] += (v[1] * scale) as f32;
+    f[o + 2] += (v[2] * scale) as f32;
+}
+
+/// Dihedral angle φ(i,j,k,l) in (-π, π]
+pub fn dihedral_angle(xi: Vec3, xj: Vec3, xk: Vec3, xl: Vec3) -> f64 {
+    let r_ij = sub(xi, xj);
+    let r_kj = sub(xk, xj);
+    let r_kl = sub(xk, xl);
+    let m = cross(r_ij, r_kj);
+    let n = cross(r_kj, r_kl);
+    let cos = dot(m, n) / (dot(m, m) * dot(n, n)).sqrt().max(1e-12);
+    let phi = cos.clamp(-1.0, 1.0).acos();
+    if dot(r_ij, n) < 0.0 {
+        -phi
+    } else {
+        phi
+    }
+}
+
+impl BondedTerms {
+    pub fn from_topology(topology: &Topology) -> Self {
+        Self {
+            bonds: topology.bonds.clone(),
+            angles: topology.angles.clone(),
+            dihedrals: topology.dihedrals.clone(),
+            impropers: topology.impropers.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bonds.is_empty()
+            && self.angles.is_empty()
+            && self.dihedrals.is_empty()
+            && self.impropers.is_empty()
+    }
+
+    /// Bonded energy, adding forces (kcal/mol/Å) into a Float4-stride buffer.
+    pub fn compute(&self, positions: &[f32], forces: &mut [f32]) -> BondedEnergy {
+        let bond = parallel_terms(&self.bonds, forces, |b| {
+            let d = sub(load(positions, b.j), load(positions, b.i));
+            let r = dot(d, d).sqrt();
+            if r < 1e-12 {
+                return None;
+            }
+            let dr = r - b.r0 as f64;
+            // F_i = dE/dr * d/r
+            let scale = 2.0 * b.k as f64 * dr / r;
+            Some((b.k as f64 * dr * dr, [(b.i, d, scale), (b.j, d, -scale)]))
+        });
+
+        let angle = parallel_terms(&self.angles, forces, |a| {
+            let xj = load(positions, a.j);
+            let u = sub(load(positions, a.i), xj);
+            let v = sub(load(positions, a.k), xj);
+            let (lu, lv) = (dot(u, u).sqrt(), dot(v, v).sqrt());
+            if lu < 1e-12 || lv < 1e-12 {
+                return None;
+            }
+            let cos = (dot(u, v) / (lu * lv)).clamp(-1.0, 1.0);
+            let theta = cos.acos();
+            let sin = (1.0 - cos * cos).sqrt().max(1e-8);
+            let dtheta = theta - a.theta0 as f64;
+            let de = 2.0 * a.force_constant as f64 * dtheta;
+            // dθ/dx_i = -(v/(|u||v|) - cos u/|u|²) / sin
+            let mut fi = [0.0; 3];
+            let mut fk = [0.0; 3];
+            for d in 0..3 {
+                fi[d] = de * (v[d] / (lu * lv) - cos * u[d] / (lu * lu)) / sin;
+                fk[d] = de * (u[d] / (lu * lv) - cos * v[d] / (lv * lv)) / sin;
+            }
+            let fj = [fi[0] + fk[0], fi[1] + fk[1], fi[2] + fk[2]];
+            Some((
+                a.force_constant as f64 * dtheta * dtheta,
+                [(a.i, fi, 1.0), (a.k, fk, 1.0), (a.j, fj, -1.0)],
+            ))
+        });
+
+        let dihedral = parallel_terms(&self.dihedrals, forces, |d| {
+            let (k, n, phase) = (d.k as f64, d.periodicity as f64, d.phase as f64);
+            Self::torsion(positions, d.atoms, |phi| {
+                let arg = n * phi - phase;
+                (k * (1.0 + arg.cos()), -k * n * arg.sin())
+            })
+        });
+
+        let improper = parallel_terms(&self.impropers, forces, |im| {
+            let (k, phi0) = (im.k as f64, im.phi0 as f64);
+            Self::torsion(positions, im.atoms, |phi| {
+                let mut dphi = phi - phi0;
+                if dphi > PI {
+                    dphi -= 2.0 * PI;
+                } else if dphi < -PI {
+                    dphi += 2.0 * PI;
+                }
+                (k * dphi * dphi, 2.0 * k * dphi)
+            })
+        });
+
+        BondedEnergy {
+            bond,
+            angle,
+            dihedral,
+            improper,
+        }
+    }
+
+    /// Apply a torsion potential given as φ -> (E, dE/dφ); returns E and
+    /// the force contributions, `None` for collinear atoms.
+    fn torsion<F: Fn(f64) -> (f64, f64)>(
+        positions: &[f32],
+        atoms: [u32; 4],
+        potential: F,
+    ) -> Option<TermForces<4>> {
+        let [i, j, k, l] = atoms;
+        let (phi, grad) = dihedral_gradient(
+            load(positions, i),
+            load(positions, j),
+            load(positions, k),
+            load(positions, l),
+        )?;
+        let (energy, ddphi) = potential(phi);
+        let mut contributions = [(0, [0.0; 3], 0.0); 4];
+        for (slot, (atom, g)) in contributions.iter_mut().zip(atoms.into_iter().zip(grad)) {
+            *slot = (atom, g, -ddphi);
+        }
+        Some((energy, contributions))
+    }
+}
+
+/// Energy of one term and its `(atom, vector, scale)` force contributions
+type TermForces<const N: usize> = (f64, [(u32, Vec3, f64); N]);
+
+/// Evaluate `terms` in parallel on the current rayon pool, then add their
+/// forces and energies in term order so the result does not depend on the
+/// number of threads. Returns the summed energy.
+fn parallel_terms<T: Sync, const N: usize>(
+    terms: &[T],
+    forces: &mut [f32],
+    term: impl Fn(&T) -> Option<TermForces<N>> + Sync,
+) -> f64 {
+    let mut energy = 0.0;
+    for block in terms.chunks(TERM_BLOCK) {
+        let evaluated: Vec<_> = block.par_iter().map(&term).collect();
+        for (e, contributions) in evaluated.into_iter().flatten() {
+            energy += e;
+            for (atom, v, scale) in contributions {
+                add_force(forces, atom, v, scale);
+            }
+        }
+    }
+    energy
+}
+
+/// Dihedral angle φ(i,j,k,l) and its gradient with respect to the four
+/// positions (Bekker force distribution); `None` for collinear atoms.
+pub(crate) fn dihedral_gradient(xi: Vec3, xj: Vec3, xk: Vec3, xl: Vec3) -> Option<(f64, [Vec3; 4])> {
+    let r_ij = sub(xi, xj);
+    let r_kj = sub(xk, xj);
+    let r_kl = sub(xk, xl);
+    let m = cross(r_ij, r_kj);
+    let n = cross(r_kj, r_kl);
+    let (m2, n2, rkj2) = (dot(m, m), dot(n, n), dot(r_kj, r_kj));
+    if m2 < 1e-12 || n2 < 1e-12 || rkj2 < 1e-12 {
+        return None;
+    }
+    let phi = dihedral_angle(xi, xj, xk, xl);
+
+    let nrkj = rkj2.sqrt();
+    let g_i: Vec3 = m.map(|c| nrkj / m2 * c);
+    let g_l: Vec3 = n.map(|c| -nrkj / n2 * c);
+    let p = dot(r_ij, r_kj) / rkj2;
+    let q = dot(r_kl, r_kj) / rkj2;
+    let s: Vec3 = [0, 1, 2].map(|d| p * g_i[d] - q * g_l[d]);
+    let g_j: Vec3 = [0, 1, 2].map(|d| s[d] - g_i[d]);
+    let g_k: Vec3 = [0, 1, 2].map(|d| -g_l[d] - s[d]);
+    Some((phi, [g_i, g_j, g_k, g_l]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions() -> Vec<f32> {
+        vec![
+            0.1, 1.4, 0.2, 1.0, //
+            0.0, 0.0, 0.0, 1.0, //
+            1.5, -0.1, 0.1, 1.0, //
+            2.1, 0.3, 1.3, 1.0,
+        ]
+    }
+
+    fn terms() -> BondedTerms {
+        BondedTerms {
+            bonds: vec![HarmonicBond { i: 0, j: 1, k: 300.0, r0: 1.5 }],
+            angles: vec![HarmonicAngle { i: 0, j: 1, k: 2, force_constant: 50.0, theta0: 1.9 }],
+            dihedrals: vec![PeriodicDihedral {
+                atoms: [0, 1, 2, 3],
+                k: 1.3,
+                periodicity: 3.0,
+                phase: 0.3,
+                improper: false,
+            }],
+            impropers: vec![HarmonicImproper { atoms: [1, 0, 2, 3], k: 20.0, phi0: 0.4 }],
+        }
+    }
+
+    #[test]
+    fn test_forces_match_finite_difference() {
+        let t = terms();
+        let mut pos = positions();
+        let mut forces = vec![0.0f32; pos.len()];
+        t.compute(&pos, &mut forces);
+
+        let h = 1e-3f32;
+        for idx in (0..16).filter(|i| i % 4 != 3) {
+            let mut scratch = vec![0.0f32; pos.len()];
+            pos[idx] += h;
+            let ep = t.compute(&pos, &mut scratch).total();
+            pos[idx] -= 2.0 * h;
+            let em = t.compute(&pos, &mut scratch).total();
+            pos[idx] += h;
+            let numeric = -(ep - em) / (2.0 * h as f64);
+            assert!(
+                (numeric - forces[idx] as f64).abs() < 2e-2 * (1.0 + numeric.abs()),
+                "component {}: numeric {} analytic {}",
+                idx,
+                numeric,
+                forces[idx]
+            );
+        }
+    }
+
+    #[test]
+    fn test_trans_dihedral_angle() {
+        let phi = dihedral_angle(
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, -1.0, 0.0],
+        );
+        assert!((phi.abs() - PI).abs() < 1e-9);
+    }
+}
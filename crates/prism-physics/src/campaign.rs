@@ -0,0 +1,781 @@
+//! # Parameter Sweep Campaigns
+//! A campaign expands one run configuration template over a grid of
+//! parameter values and runs every combination, collecting the final
+//! statistics of each into a single summary table:
+//!
+//! ```toml
+//! template = "run.toml"
+//! structure = "protein.pdb"
+//! protocol = "pimc"
+//! steps = 2000
+//! output_dir = "sweep"
+//! max_parallel = 4
+//! gpus = [0, 1]
+//!
+//! [[parameters]]
+//! name = "temperature"
+//! keys = ["engine.temp_start", "engine.temp_end"]
+//! values = ["250 K", "300 K", "350 K"]
+//!
+//! [[parameters]]
+//! name = "beads"
+//! keys = ["engine.pimc.num_beads"]
+//! values = [8, 16, 32]
+//! ```
+//!
+//! Parameter keys are dotted paths into the template and are applied like
+//! the `PRISM_*` environment overrides of [`crate::run_config`], on top of
+//! the template's profile; the process environment itself is ignored so
+//! every run is reproducible from its `config.json`. The grid is the
+//! Cartesian product of all parameters, the last one varying fastest.
+//!
+//! Every point is merged and validated before the first run starts. Runs
+//! whose configuration has `use_gpu` are queued for one worker per entry of
+//! `gpus`; the others share `max_parallel` CPU workers (one per core when
+//! 0), which split the cores between them unless the template fixes
+//! `num_threads`. A run that fails is recorded in the summary and does not
+//! stop the campaign. Each run writes `config.json` and `final.pdb` to
+//! `output_dir/run_NNN/`, and the campaign writes `summary.tsv` and
+//! `summary.json` to `output_dir`.
+
+use crate::molecular_dynamics::{MolecularDynamicsEngine, MolecularDynamicsStats};
+use crate::run_config::{ConfigFormat, RunConfig, ENV_PREFIX};
+use crate::units::{Energy, Temperature};
+use prism_core::{PhaseOutcome, PrismError};
+use prism_io::pdb::PdbStructure;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Engine entry point run for every grid point
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CampaignProtocol {
+    /// `steps` minimizer iterations
+    Minimize,
+    /// `steps` steps of NLNM breathing dynamics
+    #[default]
+    Nlnm,
+    /// `steps` path-integral Monte Carlo sweeps (needs `engine.pimc`)
+    Pimc,
+    /// `steps` ring-polymer MD steps (needs `engine.pimc`)
+    Rpmd,
+}
+
+/// One axis of the parameter grid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Parameter {
+    /// Column name in the summary table
+    pub name: String,
+    /// Dotted template paths set to each value, e.g. `engine.friction`;
+    /// `[name]` when empty
+    #[serde(default)]
+    pub keys: Vec<String>,
+    pub values: Vec<Value>,
+}
+
+impl Parameter {
+    fn keys(&self) -> Vec<&str> {
+        if self.keys.is_empty() {
+            vec![self.name.as_str()]
+        } else {
+            self.keys.iter().map(String::as_str).collect()
+        }
+    }
+}
+
+fn default_output_dir() -> PathBuf {
+    PathBuf::from("campaign")
+}
+
+/// Campaign file contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CampaignConfig {
+    /// Run configuration expanded over the grid
+    pub template: PathBuf,
+    /// Profile of the template to apply
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// PTB, PDB or mmCIF structure; the template's `[system]` when `None`
+    #[serde(default)]
+    pub structure: Option<PathBuf>,
+    #[serde(default)]
+    pub protocol: CampaignProtocol,
+    /// Steps (sweeps for PIMC, iterations for minimization) of every run;
+    /// the template's `max_steps` when `None`
+    #[serde(default)]
+    pub steps: Option<u64>,
+    #[serde(default = "default_output_dir")]
+    pub output_dir: PathBuf,
+    /// Concurrent CPU runs; 0 runs one per core
+    #[serde(default)]
+    pub max_parallel: usize,
+    /// GPUs that take `use_gpu` runs, one run at a time each; device 0
+    /// when empty
+    #[serde(default)]
+    pub gpus: Vec<usize>,
+    #[serde(default)]
+    pub parameters: Vec<Parameter>,
+}
+
+impl CampaignConfig {
+    /// Read a TOML, YAML or JSON campaign file, resolving its relative
+    /// paths against the file's directory
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PrismError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| PrismError::config(format!("Failed to read {}: {}", path.display(), e)))?;
+        let value = ConfigFormat::from_path(path)?.parse(&text)?;
+        let mut config: Self = serde_json::from_value(value).map_err(|e| {
+            PrismError::config(format!("Invalid campaign {}: {}", path.display(), e))
+        })?;
+        if let Some(dir) = path.parent() {
+            let resolve = |path: &mut PathBuf| {
+                if path.is_relative() {
+                    *path = dir.join(&*path);
+                }
+            };
+            resolve(&mut config.template);
+            resolve(&mut config.output_dir);
+            config.structure.as_mut().map(resolve);
+        }
+        Ok(config)
+    }
+
+    /// Check names, keys and values of the parameters
+    pub fn validate(&self) -> Result<(), PrismError> {
+        let mut names = HashSet::new();
+        for parameter in &self.parameters {
+            if !names.insert(parameter.name.as_str()) {
+                return Err(PrismError::config(format!(
+                    "Parameter '{}' is defined twice",
+                    parameter.name
+                )));
+            }
+            if parameter.values.is_empty() {
+                return Err(PrismError::config(format!(
+                    "Parameter '{}' has no values",
+                    parameter.name
+                )));
+            }
+            for key in parameter.keys() {
+                if !matches!(key.split('.').next(), Some("engine" | "system"))
+                    || key.split('.').any(str::is_empty)
+                {
+                    return Err(PrismError::config(format!(
+                        "Parameter '{}' key '{}' must be a dotted path under engine or system",
+                        parameter.name, key
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One combination of parameter values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridPoint {
+    pub index: usize,
+    /// Value of each parameter, in declaration order
+    pub values: Vec<Value>,
+}
+
+/// A grid point merged into a validated run configuration
+#[derive(Debug, Clone)]
+pub struct PlannedRun {
+    pub point: GridPoint,
+    pub config: RunConfig,
+    pub directory: PathBuf,
+}
+
+/// Final state of one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub index: usize,
+    pub values: Vec<Value>,
+    pub directory: PathBuf,
+    /// `cpu` or `gpu<N>`
+    pub device: String,
+    /// `success`, `cancelled`, `retry`, `escalate` or `error`
+    pub outcome: String,
+    pub message: String,
+    /// Engine statistics at the end of the run; `None` when it never started
+    pub statistics: Option<MolecularDynamicsStats>,
+    pub runtime_seconds: f64,
+}
+
+impl RunSummary {
+    pub fn is_success(&self) -> bool {
+        self.outcome == "success"
+    }
+}
+
+/// Per-run results of a campaign
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignSummary {
+    /// Parameter names, the columns of [`RunSummary::values`]
+    pub parameters: Vec<String>,
+    /// Runs in grid order
+    pub runs: Vec<RunSummary>,
+}
+
+/// Table cell of a parameter value; strings without their JSON quotes
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl CampaignSummary {
+    pub fn failures(&self) -> usize {
+        self.runs.iter().filter(|r| !r.is_success()).count()
+    }
+
+    /// Tab-separated table, one row per run; statistics of runs that never
+    /// started are `-`
+    pub fn to_table(&self) -> String {
+        let mut header = vec!["run".to_string()];
+        header.extend(self.parameters.iter().cloned());
+        header.extend(
+            [
+                "device",
+                "outcome",
+                "steps",
+                "energy_kcal_mol",
+                "temperature_k",
+                "acceptance",
+                "gradient_norm",
+                "runtime_s",
+            ]
+            .map(str::to_string),
+        );
+        let mut table = header.join("\t") + "\n";
+        for run in &self.runs {
+            let mut row = vec![run.index.to_string()];
+            row.extend(run.values.iter().map(cell));
+            row.push(run.device.clone());
+            row.push(run.outcome.clone());
+            match &run.statistics {
+                Some(stats) => row.extend([
+                    stats.current_step.to_string(),
+                    format!("{:.4}", stats.current_energy),
+                    format!(
+                        "{:.2}",
+                        Temperature::from_kt(Energy::kcal_per_mol(
+                            stats.current_temperature as f64
+                        ))
+                        .as_kelvin()
+                    ),
+                    format!("{:.4}", stats.acceptance_rate),
+                    format!("{:.4}", stats.gradient_norm),
+                ]),
+                None => row.extend(std::iter::repeat_n("-".to_string(), 5)),
+            }
+            row.push(format!("{:.3}", run.runtime_seconds));
+            table += &(row.join("\t") + "\n");
+        }
+        table
+    }
+
+    /// Write `summary.tsv` and `summary.json` to `dir`
+    pub fn write(&self, dir: &Path) -> Result<(), PrismError> {
+        let write = |name: &str, contents: String| {
+            let path = dir.join(name);
+            std::fs::write(&path, contents).map_err(|e| {
+                PrismError::internal(format!("Failed to write {}: {}", path.display(), e))
+            })
+        };
+        write("summary.tsv", self.to_table())?;
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| PrismError::internal(e.to_string()))?;
+        write("summary.json", json)
+    }
+}
+
+/// A campaign file with its template loaded
+#[derive(Debug, Clone)]
+pub struct Campaign {
+    config: CampaignConfig,
+    template: String,
+    format: ConfigFormat,
+}
+
+impl Campaign {
+    pub fn new(config: CampaignConfig) -> Result<Self, PrismError> {
+        config.validate()?;
+        let template = std::fs::read_to_string(&config.template).map_err(|e| {
+            PrismError::config(format!(
+                "Failed to read template {}: {}",
+                config.template.display(),
+                e
+            ))
+        })?;
+        let format = ConfigFormat::from_path(&config.template)?;
+        Ok(Self {
+            config,
+            template,
+            format,
+        })
+    }
+
+    /// Load the campaign file at `path` and its template
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PrismError> {
+        Self::new(CampaignConfig::load(path)?)
+    }
+
+    pub fn config(&self) -> &CampaignConfig {
+        &self.config
+    }
+
+    /// All combinations of the parameter values, the last parameter
+    /// varying fastest
+    pub fn grid(&self) -> Vec<GridPoint> {
+        let mut combinations: Vec<Vec<Value>> = vec![Vec::new()];
+        for parameter in &self.config.parameters {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|prefix| {
+                    parameter.values.iter().map(move |value| {
+                        let mut values = prefix.clone();
+                        values.push(value.clone());
+                        values
+                    })
+                })
+                .collect();
+        }
+        combinations
+            .into_iter()
+            .enumerate()
+            .map(|(index, values)| GridPoint { index, values })
+            .collect()
+    }
+
+    /// Merge and validate the run configuration of every grid point
+    pub fn plan(&self) -> Result<Vec<PlannedRun>, PrismError> {
+        self.grid()
+            .into_iter()
+            .map(|point| {
+                let config = self.run_config(&point).map_err(|e| {
+                    PrismError::config(format!(
+                        "Run {} ({}): {}",
+                        point.index,
+                        self.label(&point),
+                        e
+                    ))
+                })?;
+                let directory = self
+                    .config
+                    .output_dir
+                    .join(format!("run_{:03}", point.index));
+                Ok(PlannedRun {
+                    point,
+                    config,
+                    directory,
+                })
+            })
+            .collect()
+    }
+
+    fn label(&self, point: &GridPoint) -> String {
+        self.config
+            .parameters
+            .iter()
+            .zip(&point.values)
+            .map(|(p, v)| format!("{}={}", p.name, cell(v)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn run_config(&self, point: &GridPoint) -> Result<RunConfig, PrismError> {
+        let overrides = self
+            .config
+            .parameters
+            .iter()
+            .zip(&point.values)
+            .flat_map(|(parameter, value)| {
+                parameter.keys().into_iter().map(move |key| {
+                    (
+                        format!(
+                            "{}{}",
+                            ENV_PREFIX,
+                            key.replace('.', "__").to_ascii_uppercase()
+                        ),
+                        value.to_string(),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut config = RunConfig::parse(
+            &self.template,
+            self.format,
+            self.config.profile.as_deref(),
+            overrides,
+        )?;
+        if let Some(dir) = self.config.template.parent() {
+            config.resolve_paths(dir);
+        }
+        if let Some(steps) = self.config.steps {
+            match self.config.protocol {
+                CampaignProtocol::Minimize => {
+                    config.engine.minimization.max_iterations = steps as usize
+                }
+                _ => config.engine.max_steps = steps,
+            }
+        }
+        if matches!(
+            self.config.protocol,
+            CampaignProtocol::Pimc | CampaignProtocol::Rpmd
+        ) && config.engine.pimc.is_none()
+        {
+            return Err(PrismError::config(
+                "PIMC and RPMD campaigns need an engine.pimc section in the template",
+            ));
+        }
+        if self.config.structure.is_none() && config.system.topology.is_none() {
+            return Err(PrismError::config(
+                "No structure: set `structure` in the campaign or [system] in the template",
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Run every grid point and write the summary table to `output_dir`
+    pub fn run(&self) -> Result<CampaignSummary, PrismError> {
+        let runs = self.plan()?;
+        std::fs::create_dir_all(&self.config.output_dir).map_err(|e| {
+            PrismError::internal(format!(
+                "Failed to create {}: {}",
+                self.config.output_dir.display(),
+                e
+            ))
+        })?;
+        let (gpu_runs, cpu_runs): (VecDeque<_>, VecDeque<_>) =
+            runs.into_iter().partition(|run| run.config.engine.use_gpu);
+
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let cpu_workers = match self.config.max_parallel {
+            0 => cores,
+            n => n,
+        }
+        .min(cpu_runs.len());
+        let threads_per_run = (cores / cpu_workers.max(1)).max(1);
+        let gpus = if self.config.gpus.is_empty() {
+            vec![0]
+        } else {
+            self.config.gpus.clone()
+        };
+        let gpu_workers = if gpu_runs.is_empty() { 0 } else { gpus.len() };
+        log::info!(
+            "🧪 Campaign: {} runs ({} CPU on {} workers x {} threads, {} GPU on {} devices)",
+            cpu_runs.len() + gpu_runs.len(),
+            cpu_runs.len(),
+            cpu_workers,
+            threads_per_run,
+            gpu_runs.len(),
+            gpu_workers
+        );
+
+        let cpu_queue = Mutex::new(cpu_runs);
+        let gpu_queue = Mutex::new(gpu_runs);
+        let results = Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            for _ in 0..cpu_workers {
+                scope.spawn(|| {
+                    while let Some(mut run) = cpu_queue.lock().unwrap().pop_front() {
+                        run.config.engine.num_threads =
+                            run.config.engine.num_threads.or(Some(threads_per_run));
+                        let summary = self.execute(run, "cpu".to_string());
+                        results.lock().unwrap().push(summary);
+                    }
+                });
+            }
+            for &gpu in gpus.iter().take(gpu_workers) {
+                let (gpu_queue, results) = (&gpu_queue, &results);
+                scope.spawn(move || {
+                    while let Some(mut run) = gpu_queue.lock().unwrap().pop_front() {
+                        run.config.engine.devices = vec![gpu];
+                        let summary = self.execute(run, format!("gpu{}", gpu));
+                        results.lock().unwrap().push(summary);
+                    }
+                });
+            }
+        });
+
+        let mut runs = results.into_inner().unwrap();
+        runs.sort_by_key(|run| run.index);
+        let summary = CampaignSummary {
+            parameters: self
+                .config
+                .parameters
+                .iter()
+                .map(|p| p.name.clone())
+                .collect(),
+            runs,
+        };
+        summary.write(&self.config.output_dir)?;
+        log::info!(
+            "🧪 Campaign finished: {} of {} runs succeeded, summary in {}",
+            summary.runs.len() - summary.failures(),
+            summary.runs.len(),
+            self.config.output_dir.join("summary.tsv").display()
+        );
+        Ok(summary)
+    }
+
+    /// Run one point, turning any error into an `error` row
+    fn execute(&self, mut run: PlannedRun, device: String) -> RunSummary {
+        let start = web_time::Instant::now();
+        let label = self.label(&run.point);
+        if let Some(trajectory) = &mut run.config.engine.trajectory {
+            if let Some(name) = trajectory.path.file_name() {
+                trajectory.path = run.directory.join(name);
+            }
+        }
+        let mut statistics = None;
+        let result = self.simulate(&run, &mut statistics);
+        let (outcome, message) = match result {
+            Ok(PhaseOutcome::Success { message, .. }) => ("success", message),
+            Ok(PhaseOutcome::Cancelled { reason, .. }) => ("cancelled", reason),
+            Ok(PhaseOutcome::Retry { reason, .. }) => ("retry", reason),
+            Ok(PhaseOutcome::Escalate { reason }) => ("escalate", reason),
+            Err(e) => ("error", e.to_string()),
+        };
+        if outcome == "success" {
+            log::info!(
+                "✅ Campaign run {} ({}) on {}",
+                run.point.index,
+                label,
+                device
+            );
+        } else {
+            log::warn!(
+                "⚠️ Campaign run {} ({}) on {}: {}: {}",
+                run.point.index,
+                label,
+                device,
+                outcome,
+                message
+            );
+        }
+        RunSummary {
+            index: run.point.index,
+            values: run.point.values,
+            directory: run.directory,
+            device,
+            outcome: outcome.to_string(),
+            message,
+            statistics,
+            runtime_seconds: start.elapsed().as_secs_f64(),
+        }
+    }
+
+    fn simulate(
+        &self,
+        run: &PlannedRun,
+        statistics: &mut Option<MolecularDynamicsStats>,
+    ) -> Result<PhaseOutcome, PrismError> {
+        let io_error = |path: &Path, e: std::io::Error| {
+            PrismError::internal(format!("{}: {}", path.display(), e))
+        };
+        std::fs::create_dir_all(&run.directory).map_err(|e| io_error(&run.directory, e))?;
+        let config_path = run.directory.join("config.json");
+        let json = serde_json::to_string_pretty(&run.config)
+            .map_err(|e| PrismError::internal(e.to_string()))?;
+        std::fs::write(&config_path, json).map_err(|e| io_error(&config_path, e))?;
+
+        let engine_config = run.config.engine.clone();
+        let mut engine = match &self.config.structure {
+            Some(path) => MolecularDynamicsEngine::from_structure_file(engine_config, path)?,
+            None => {
+                MolecularDynamicsEngine::from_topology(engine_config, &run.config.load_topology()?)?
+            }
+        };
+        let steps = run.config.engine.max_steps;
+        let outcome = match self.config.protocol {
+            CampaignProtocol::Minimize => engine.minimize(),
+            CampaignProtocol::Nlnm => engine.run_nlnm_breathing(steps),
+            CampaignProtocol::Pimc => engine.run_pimc(steps),
+            CampaignProtocol::Rpmd => engine.run_rpmd(steps),
+        };
+        *statistics = Some(engine.get_statistics());
+        let outcome = outcome?;
+
+        let final_path = run.directory.join("final.pdb");
+        let atoms = engine.get_current_atoms()?;
+        PdbStructure::from_atoms(&atoms)
+            .write(&final_path)
+            .map_err(|e| PrismError::internal(format!("{}: {}", final_path.display(), e)))?;
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+";
+
+    const TEMPLATE: &str = "\
+[engine]
+use_gpu = false
+spring_k = 0.0
+max_steps = 1000
+";
+
+    fn campaign_config(dir: &Path, parameters: Vec<Parameter>) -> CampaignConfig {
+        CampaignConfig {
+            template: dir.join("run.toml"),
+            profile: None,
+            structure: Some(dir.join("peptide.pdb")),
+            protocol: CampaignProtocol::Nlnm,
+            steps: Some(10),
+            output_dir: dir.join("sweep"),
+            max_parallel: 2,
+            gpus: Vec::new(),
+            parameters,
+        }
+    }
+
+    fn parameter(name: &str, keys: &[&str], values: Vec<Value>) -> Parameter {
+        Parameter {
+            name: name.to_string(),
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            values,
+        }
+    }
+
+    fn setup(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("prism_campaign_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("peptide.pdb"), PEPTIDE).unwrap();
+        std::fs::write(dir.join("run.toml"), TEMPLATE).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_grid_expansion_and_overrides() {
+        let dir = setup("grid");
+        let config = campaign_config(
+            &dir,
+            vec![
+                parameter(
+                    "temperature",
+                    &["engine.temp_start", "engine.temp_end"],
+                    vec![json!("250 K"), json!("350 K")],
+                ),
+                parameter(
+                    "engine.friction",
+                    &[],
+                    vec![json!(0.5), json!(1.0), json!(2.0)],
+                ),
+            ],
+        );
+        let campaign = Campaign::new(config).unwrap();
+        let grid = campaign.grid();
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[1].values, vec![json!("250 K"), json!(1.0)]);
+        assert_eq!(grid[3].values, vec![json!("350 K"), json!(0.5)]);
+
+        let runs = campaign.plan().unwrap();
+        let kelvin = |kt: f32| Temperature::from_kt(Energy::kcal_per_mol(kt as f64)).as_kelvin();
+        assert!((kelvin(runs[4].config.engine.temp_start) - 350.0).abs() < 1e-3);
+        assert_eq!(
+            runs[4].config.engine.temp_start,
+            runs[4].config.engine.temp_end
+        );
+        assert_eq!(runs[4].config.engine.friction, 1.0);
+        assert_eq!(runs[4].config.engine.max_steps, 10);
+        assert!(runs[5].directory.ends_with("sweep/run_005"));
+
+        let invalid = campaign_error(&dir, parameter("dt", &["engine.dt"], vec![json!(-1.0)]));
+        assert!(invalid.contains("Run 0 (dt=-1.0)"), "{}", invalid);
+        let outside = campaign_error(&dir, parameter("seed", &["seed"], vec![json!(1)]));
+        assert!(outside.contains("under engine or system"), "{}", outside);
+        let empty = campaign_error(&dir, parameter("seed", &["engine.seed"], vec![]));
+        assert!(empty.contains("no values"), "{}", empty);
+        let pimc = Campaign::new(CampaignConfig {
+            protocol: CampaignProtocol::Pimc,
+            ..campaign_config(&dir, Vec::new())
+        })
+        .unwrap();
+        assert!(pimc.plan().is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    fn campaign_error(dir: &Path, parameter: Parameter) -> String {
+        Campaign::new(campaign_config(dir, vec![parameter]))
+            .and_then(|c| c.plan())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_campaign_runs_and_writes_summary() {
+        let dir = setup("run");
+        std::fs::write(
+            dir.join("campaign.toml"),
+            "\
+template = \"run.toml\"
+structure = \"peptide.pdb\"
+steps = 10
+output_dir = \"sweep\"
+max_parallel = 2
+
+[[parameters]]
+name = \"temperature\"
+keys = [\"engine.temp_start\", \"engine.temp_end\"]
+values = [\"250 K\", \"300 K\"]
+
+[[parameters]]
+name = \"seed\"
+keys = [\"engine.seed\"]
+values = [1, 2]
+",
+        )
+        .unwrap();
+        let summary = Campaign::load(dir.join("campaign.toml"))
+            .unwrap()
+            .run()
+            .unwrap();
+        assert_eq!(summary.runs.len(), 4);
+        assert_eq!(summary.failures(), 0);
+        assert!(summary
+            .runs
+            .iter()
+            .enumerate()
+            .all(|(i, r)| r.index == i && r.device == "cpu"));
+        assert_eq!(
+            summary.runs[2].statistics.as_ref().unwrap().current_step,
+            10
+        );
+
+        let table = std::fs::read_to_string(dir.join("sweep/summary.tsv")).unwrap();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("run\ttemperature\tseed\tdevice\toutcome\tsteps"));
+        assert!(lines[3].starts_with("2\t300 K\t1\tcpu\tsuccess\t10\t"));
+        let config: Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.join("sweep/run_003/config.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(config["engine"]["seed"], 2);
+        assert!(dir.join("sweep/run_003/final.pdb").exists());
+        assert!(dir.join("sweep/summary.json").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,239 @@
+//! # Simulation Checkpoints
+//!
+//! Binary snapshots of the dynamic state of a molecular dynamics run, so
+//! long simulations can be resumed after a crash or preemption with the
+//! same trajectory they would have produced uninterrupted.
+//!
+//! ## File layout
+//! - `PRISMCKP` magic (8 bytes)
+//! - Format version (u32, little-endian)
+//! - BLAKE3 hash of the payload (32 bytes)
+//! - bincode-encoded [`MdCheckpoint`] payload
+//!
+//! Files are written to a sibling temporary file and renamed into place, so
+//! an interrupted save never clobbers the previous checkpoint.
+
+use prism_core::PrismError;
+use prism_io::simulation_box::SimulationBox;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File magic for checkpoint files
+pub const CHECKPOINT_MAGIC: &[u8; 8] = b"PRISMCKP";
+
+/// Current checkpoint format version
+pub const CHECKPOINT_VERSION: u32 = 2;
+
+const HEADER_LEN: usize = 8 + 4 + 32;
+
+/// Exact position of a ChaCha12 stream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RngState {
+    /// Key the generator was seeded with
+    pub seed: [u8; 32],
+    /// Stream identifier
+    pub stream: u64,
+    /// Number of 32-bit words consumed
+    pub word_pos: u128,
+}
+
+impl RngState {
+    /// Capture the current position of `rng`
+    pub fn capture(rng: &ChaCha12Rng) -> Self {
+        Self {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    }
+
+    /// Rebuild a generator positioned exactly where it was captured
+    pub fn restore(&self) -> ChaCha12Rng {
+        use rand::SeedableRng;
+        let mut rng = ChaCha12Rng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(self.word_pos);
+        rng
+    }
+}
+
+/// Langevin thermostat state at the checkpointed step
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThermostatState {
+    /// Bath temperature on the annealing schedule (kT units)
+    pub temperature: f32,
+    /// Friction coefficient (1/ps)
+    pub friction: f32,
+    /// Length of the annealing ramp (steps)
+    pub annealing_steps: u64,
+}
+
+/// Path-integral bead configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PimcBeads {
+    /// Number of replicas (beads)
+    pub num_replicas: usize,
+    /// Degrees of freedom per replica
+    pub dimensions: usize,
+    /// Replica-major configuration (`num_replicas * dimensions` values)
+    pub configuration: Vec<f32>,
+}
+
+/// Dynamic state of a molecular dynamics run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MdCheckpoint {
+    /// Integration step the state belongs to
+    pub step: u64,
+    /// Number of atoms
+    pub num_atoms: usize,
+    /// Float4-stride positions (Å, `w` = mass)
+    pub positions: Vec<f32>,
+    /// Float4-stride velocities (Å/ps)
+    pub velocities: Vec<f32>,
+    /// Periodic cell, if any
+    pub simulation_box: Option<SimulationBox>,
+    /// Thermostat state
+    pub thermostat: ThermostatState,
+    /// Host RNG position
+    pub rng: RngState,
+    /// Raw per-atom device RNG states, when the run was on the GPU
+    pub gpu_rng_states: Option<Vec<u8>>,
+    /// Bead configuration, when the run carries a PIMC stage
+    pub pimc_beads: Option<PimcBeads>,
+}
+
+impl MdCheckpoint {
+    /// Serialize into the on-disk representation
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PrismError> {
+        let payload = bincode::serialize(self)
+            .map_err(|e| PrismError::Internal(format!("Checkpoint encoding failed: {}", e)))?;
+        let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+        bytes.extend_from_slice(CHECKPOINT_MAGIC);
+        bytes.extend_from_slice(&CHECKPOINT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(blake3::hash(&payload).as_bytes());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Decode and verify the on-disk representation
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PrismError> {
+        if bytes.len() < HEADER_LEN || &bytes[..8] != CHECKPOINT_MAGIC {
+            return Err(PrismError::validation("Not a PRISM checkpoint file"));
+        }
+        let version = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        if version != CHECKPOINT_VERSION {
+            return Err(PrismError::validation(format!(
+                "Unsupported checkpoint version {} (expected {})",
+                version, CHECKPOINT_VERSION
+            )));
+        }
+        let payload = &bytes[HEADER_LEN..];
+        if blake3::hash(payload).as_bytes() != &bytes[12..HEADER_LEN] {
+            return Err(PrismError::validation("Checkpoint checksum mismatch"));
+        }
+        let checkpoint: Self = bincode::deserialize(payload)
+            .map_err(|e| PrismError::validation(format!("Corrupt checkpoint payload: {}", e)))?;
+        if checkpoint.positions.len() != checkpoint.num_atoms * 4
+            || checkpoint.velocities.len() != checkpoint.num_atoms * 4
+        {
+            return Err(PrismError::validation(
+                "Checkpoint buffers do not match its atom count",
+            ));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Atomically write the checkpoint to `path`
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), PrismError> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, self.to_bytes()?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Read and verify a checkpoint from `path`
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, PrismError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsEngine};
+    use prism_io::sovereign_types::Atom;
+    use prism_io::topology::Topology;
+    use rand::{RngCore, SeedableRng};
+
+    fn topology() -> Topology {
+        let atoms = (0..4)
+            .map(|i| Atom {
+                coords: [i as f32 * 3.8, (i % 2) as f32, 0.0],
+                element: 6,
+                residue_id: i,
+                atom_type: 1,
+                charge: 0.0,
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        Topology {
+            atoms,
+            masses: vec![12.0; 4],
+            ..Default::default()
+        }
+    }
+
+    fn engine() -> MolecularDynamicsEngine {
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            dt: 0.002,
+            ..Default::default()
+        };
+        MolecularDynamicsEngine::from_topology(config, &topology()).unwrap()
+    }
+
+    fn coords(engine: &mut MolecularDynamicsEngine) -> Vec<[f32; 3]> {
+        engine
+            .get_current_atoms()
+            .unwrap()
+            .iter()
+            .map(|a| a.coords)
+            .collect()
+    }
+
+    #[test]
+    fn test_rng_state_round_trip() {
+        let mut rng = ChaCha12Rng::seed_from_u64(7);
+        rng.next_u32();
+        let state = RngState::capture(&rng);
+        let mut restored = state.restore();
+        assert_eq!(rng.next_u64(), restored.next_u64());
+    }
+
+    #[test]
+    fn test_resume_reproduces_trajectory() {
+        let path = std::env::temp_dir().join(format!("prism-ckpt-{}.bin", uuid::Uuid::new_v4()));
+
+        let mut reference = engine();
+        reference.run_nlnm_breathing(25).unwrap();
+        reference.save_checkpoint(&path).unwrap();
+        reference.run_nlnm_breathing(25).unwrap();
+
+        let mut resumed = engine();
+        resumed.resume_from_checkpoint(&path).unwrap();
+        assert_eq!(resumed.get_statistics().current_step, 25);
+        resumed.run_nlnm_breathing(25).unwrap();
+        assert_eq!(coords(&mut reference), coords(&mut resumed));
+
+        // Corruption is detected
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(MdCheckpoint::from_bytes(&bytes).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
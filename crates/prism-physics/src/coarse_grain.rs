@@ -0,0 +1,755 @@
+//! # Coarse-Grained MARTINI Models
+//! Maps an all-atom protein onto MARTINI 2.2-style beads (about four heavy
+//! atoms per bead: one backbone bead `BB` per residue, up to four side-chain
+//! beads `SC1..SC4`), builds the coarse-grained force field as a regular
+//! [`Topology`], and back-maps coarse-grained coordinates onto the atoms.
+//!
+//! - Nonbonded: 12-6 LJ with the MARTINI interaction matrix (18 bead types,
+//!   levels O..IX) written as per-type-pair NBFIX entries; ring beads
+//!   (`SC4`, `SC5`, `SP1`, `SNd`) use σ = 4.3 Å and 75% of ε between each
+//!   other. Charged beads (`Qa`, `Qd`) carry ±1 e, screened by ε_r = 15.
+//! - Bonded: backbone bonds, angles (and dihedrals in helices) by secondary
+//!   structure from DSSP; side-chain bonds and angles take their equilibrium
+//!   values from the mapped input; an optional elastic network between
+//!   backbone beads (ElNeDyn-like) keeps the tertiary structure.
+//! - Exclusions: directly bonded beads only (`nrexcl = 1`); elastic springs
+//!   do not exclude.
+//!
+//! Beads sit at the centre of geometry of their heavy atoms; hydrogens and
+//! terminal oxygens follow the bead of the nearest heavy atom. Non-protein
+//! residues (water, ions, ligands) are dropped. Back-mapping superposes
+//! each residue's reference beads (with those of its sequence neighbours)
+//! onto the new bead positions and carries the residue's atoms along, so
+//! the all-atom structure follows the coarse-grained motion rigidly per
+//! residue. Run [`MartiniConfig::force_field`] settings and a 20 fs time
+//! step ([`MARTINI_TIME_STEP`]) with the resulting topology.
+
+use crate::analysis::dssp::{Backbone, SecondaryStructure};
+use crate::collective_variables::kabsch_rotation;
+use crate::force_field::ForceFieldConfig;
+use nalgebra::{Matrix3, Vector3};
+use prism_core::PrismError;
+use prism_io::pdb::PdbStructure;
+use prism_io::residues::canonical_name;
+use prism_io::sovereign_types::Atom;
+use prism_io::topology::{
+    HarmonicAngle, HarmonicBond, LjParams, NbFix, PeriodicDihedral, Topology,
+};
+use serde::{Deserialize, Serialize};
+
+/// Integration time step usually run with MARTINI models (ps)
+pub const MARTINI_TIME_STEP: f32 = 0.02;
+
+/// MARTINI bead types, in the row order of [`INTERACTION_LEVELS`]
+const BEAD_TYPES: [&str; 18] = [
+    "Qda", "Qd", "Qa", "Q0", "P5", "P4", "P3", "P2", "P1", "Nda", "Nd", "Na", "N0", "C5", "C4",
+    "C3", "C2", "C1",
+];
+
+/// Interaction level (0 = O ... 9 = IX) of every bead type pair
+const INTERACTION_LEVELS: [[u8; 18]; 18] = [
+    [0, 0, 0, 2, 0, 0, 0, 1, 1, 1, 1, 1, 4, 5, 6, 7, 9, 9],
+    [0, 1, 0, 2, 0, 0, 0, 1, 1, 1, 3, 1, 4, 5, 6, 7, 9, 9],
+    [0, 0, 1, 2, 0, 0, 0, 1, 1, 1, 1, 3, 4, 5, 6, 7, 9, 9],
+    [2, 2, 2, 4, 1, 0, 1, 2, 3, 3, 3, 3, 4, 5, 6, 7, 9, 9],
+    [0, 0, 0, 1, 0, 0, 0, 0, 0, 1, 1, 1, 4, 5, 6, 6, 7, 8],
+    [0, 0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 3, 4, 5, 6, 6, 7, 8],
+    [0, 0, 0, 1, 0, 1, 1, 2, 2, 2, 2, 2, 4, 4, 5, 5, 6, 7],
+    [1, 1, 1, 2, 0, 2, 2, 2, 2, 2, 2, 2, 3, 4, 4, 5, 6, 7],
+    [1, 1, 1, 3, 0, 2, 2, 2, 2, 2, 2, 2, 3, 4, 4, 4, 5, 6],
+    [1, 1, 1, 3, 1, 3, 2, 2, 2, 2, 2, 2, 4, 4, 5, 6, 6, 6],
+    [1, 3, 1, 3, 1, 3, 2, 2, 2, 2, 3, 2, 4, 4, 5, 6, 6, 6],
+    [1, 1, 3, 3, 1, 3, 2, 2, 2, 2, 2, 3, 4, 4, 5, 6, 6, 6],
+    [4, 4, 4, 4, 4, 4, 4, 3, 3, 4, 4, 4, 4, 4, 4, 4, 5, 6],
+    [5, 5, 5, 5, 5, 5, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 5, 5],
+    [6, 6, 6, 6, 6, 6, 5, 4, 4, 5, 5, 5, 4, 4, 4, 4, 5, 5],
+    [7, 7, 7, 7, 6, 6, 5, 5, 4, 6, 6, 6, 4, 4, 4, 4, 4, 4],
+    [9, 9, 9, 9, 7, 7, 6, 6, 5, 6, 6, 6, 5, 5, 5, 4, 4, 4],
+    [9, 9, 9, 9, 8, 8, 7, 7, 6, 6, 6, 6, 6, 5, 5, 4, 4, 4],
+];
+
+/// Well depth of each interaction level (kJ/mol)
+const LEVEL_EPSILON: [f32; 10] = [5.6, 5.0, 4.5, 4.0, 3.5, 3.1, 2.7, 2.3, 2.0, 2.0];
+
+const KJ_PER_KCAL: f32 = 4.184;
+
+/// Side-chain beads per residue: type, charge and member heavy atoms
+type SideChain = &'static [(&'static str, f32, &'static [&'static str])];
+
+fn side_chain(residue: &str) -> SideChain {
+    match residue {
+        "CYS" => &[("C5", 0.0, &["CB", "SG"])],
+        "ASP" => &[("Qa", -1.0, &["CB", "CG", "OD1", "OD2"])],
+        "GLU" => &[("Qa", -1.0, &["CB", "CG", "CD", "OE1", "OE2"])],
+        "PHE" => &[
+            ("SC5", 0.0, &["CB", "CG", "CD1"]),
+            ("SC5", 0.0, &["CD2", "CE2"]),
+            ("SC5", 0.0, &["CE1", "CZ"]),
+        ],
+        "HIS" => &[
+            ("SC4", 0.0, &["CB", "CG"]),
+            ("SP1", 0.0, &["CD2", "NE2"]),
+            ("SP1", 0.0, &["ND1", "CE1"]),
+        ],
+        "ILE" => &[("C1", 0.0, &["CB", "CG1", "CG2", "CD1"])],
+        "LYS" => &[("C3", 0.0, &["CB", "CG", "CD"]), ("Qd", 1.0, &["CE", "NZ"])],
+        "LEU" => &[("C1", 0.0, &["CB", "CG", "CD1", "CD2"])],
+        "MET" => &[("C5", 0.0, &["CB", "CG", "SD", "CE"])],
+        "ASN" => &[("P5", 0.0, &["CB", "CG", "OD1", "ND2"])],
+        "PRO" => &[("C3", 0.0, &["CB", "CG", "CD"])],
+        "GLN" => &[("P4", 0.0, &["CB", "CG", "CD", "OE1", "NE2"])],
+        "ARG" => &[
+            ("N0", 0.0, &["CB", "CG", "CD"]),
+            ("Qd", 1.0, &["NE", "CZ", "NH1", "NH2"]),
+        ],
+        "SER" => &[("P1", 0.0, &["CB", "OG"])],
+        "THR" => &[("P1", 0.0, &["CB", "OG1", "CG2"])],
+        "VAL" => &[("C2", 0.0, &["CB", "CG1", "CG2"])],
+        "TRP" => &[
+            ("SC4", 0.0, &["CB", "CG", "CD1"]),
+            ("SNd", 0.0, &["NE1", "CE2", "CD2"]),
+            ("SC5", 0.0, &["CE3", "CZ3"]),
+            ("SC5", 0.0, &["CZ2", "CH2"]),
+        ],
+        "TYR" => &[
+            ("SC4", 0.0, &["CB", "CG", "CD1"]),
+            ("SC4", 0.0, &["CD2", "CE2"]),
+            ("SP1", 0.0, &["CE1", "CZ", "OH"]),
+        ],
+        _ => &[],
+    }
+}
+
+/// Backbone bead type by secondary structure
+fn backbone_type(structure: SecondaryStructure) -> &'static str {
+    if structure.is_helix() {
+        "N0"
+    } else if structure.is_strand() {
+        "Nda"
+    } else {
+        "P5"
+    }
+}
+
+/// Base type and ring flag of a bead type (`SC5` → (`C5`, true))
+fn base_type(bead_type: &str) -> (usize, bool) {
+    let (base, ring) = match bead_type.strip_prefix('S') {
+        Some(base) => (base, true),
+        None => (bead_type, false),
+    };
+    let index = BEAD_TYPES.iter().position(|&t| t == base).unwrap_or(12);
+    (index, ring)
+}
+
+/// LJ parameters between two bead types
+pub fn pair_parameters(a: &str, b: &str) -> LjParams {
+    let ((ia, ring_a), (ib, ring_b)) = (base_type(a), base_type(b));
+    let level = INTERACTION_LEVELS[ia][ib] as usize;
+    let epsilon = LEVEL_EPSILON[level] / KJ_PER_KCAL;
+    let sigma = if level == 9 { 6.2 } else { 4.7 };
+    if ring_a && ring_b {
+        LjParams {
+            sigma: 4.3,
+            epsilon: 0.75 * epsilon,
+        }
+    } else {
+        LjParams { sigma, epsilon }
+    }
+}
+
+/// GROMACS `½ k (r - r0)²` force constant (kJ/mol/nm²) in the
+/// `k (r - r0)²` convention (kcal/mol/Å²)
+fn bond_constant(k: f32) -> f32 {
+    0.5 * k / (100.0 * KJ_PER_KCAL)
+}
+
+/// GROMACS angle force constant (kJ/mol/rad²) in kcal/mol/rad²
+fn angle_constant(k: f32) -> f32 {
+    0.5 * k / KJ_PER_KCAL
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MartiniConfig {
+    /// Elastic network cutoff between backbone beads (Å); 0 disables it
+    pub elastic_cutoff: f32,
+    /// Elastic spring constant (kcal/mol/Å²)
+    pub elastic_force_constant: f32,
+}
+
+impl Default for MartiniConfig {
+    fn default() -> Self {
+        Self {
+            elastic_cutoff: 9.0,
+            // 500 kJ/mol/nm²
+            elastic_force_constant: bond_constant(500.0),
+        }
+    }
+}
+
+impl MartiniConfig {
+    /// Nonbonded settings of MARTINI runs: 12 Å cutoff, LJ and Coulomb
+    /// switched from 9 Å, ε_r = 15, no PME or implicit solvent
+    pub fn force_field(&self, base: ForceFieldConfig) -> ForceFieldConfig {
+        ForceFieldConfig {
+            cutoff: 12.0,
+            switch_distance: Some(9.0),
+            dielectric: 15.0,
+            pme: None,
+            implicit_solvent: None,
+            ..base
+        }
+    }
+}
+
+/// A coarse-grained bead
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bead {
+    /// `BB`, `SC1`, ...
+    pub name: String,
+    /// MARTINI bead type
+    pub bead_type: String,
+    /// Charge (e)
+    pub charge: f32,
+    /// Member atoms, indices into [`CoarseGrainedModel::atomistic`]
+    pub atoms: Vec<u32>,
+}
+
+/// An all-atom protein mapped onto MARTINI beads, with the coarse-grained
+/// topology and what back-mapping needs
+#[derive(Debug, Clone)]
+pub struct CoarseGrainedModel {
+    beads: Vec<Bead>,
+    topology: Topology,
+    atomistic: PdbStructure,
+    springs: usize,
+    dropped_atoms: usize,
+}
+
+fn centre(atoms: &[Atom], members: &[u32]) -> [f32; 3] {
+    let mut c = [0.0; 3];
+    for &i in members {
+        for (d, x) in c.iter_mut().zip(atoms[i as usize].coords) {
+            *d += x / members.len() as f32;
+        }
+    }
+    c
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+fn angle(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> f32 {
+    let u = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let v = [c[0] - b[0], c[1] - b[1], c[2] - b[2]];
+    let dot = u[0] * v[0] + u[1] * v[1] + u[2] * v[2];
+    (dot / (distance(a, b) * distance(c, b)))
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+/// Secondary structure of every residue id (coil when DSSP cannot run)
+fn secondary_structure(structure: &PdbStructure) -> Vec<SecondaryStructure> {
+    let num_residues = structure
+        .atoms
+        .iter()
+        .map(|a| a.residue_id as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut assignment = vec![SecondaryStructure::Coil; num_residues];
+    let topology = Topology {
+        atoms: structure.atoms.clone(),
+        atom_names: structure.records.iter().map(|r| r.name.clone()).collect(),
+        residue_names: vec![String::new(); num_residues],
+        ..Default::default()
+    };
+    if let Ok(backbone) = Backbone::from_topology(&topology) {
+        let atoms = &structure.atoms;
+        let states = backbone.assign(|i| atoms[i].coords.map(f64::from));
+        for (&residue, state) in backbone.residues().iter().zip(states) {
+            assignment[residue as usize] = state;
+        }
+    }
+    assignment
+}
+
+impl CoarseGrainedModel {
+    /// Map the protein residues of `structure` onto beads and build their
+    /// MARTINI topology
+    pub fn from_structure(
+        structure: &PdbStructure,
+        config: &MartiniConfig,
+    ) -> Result<Self, PrismError> {
+        // Protein residues only, renumbered from 0
+        let mut atomistic = PdbStructure {
+            cryst1: structure.cryst1,
+            ..Default::default()
+        };
+        let mut previous = None;
+        let mut residue_id = 0;
+        for (atom, record) in structure.atoms.iter().zip(&structure.records) {
+            if canonical_name(record.residue_name.trim()).is_none() {
+                continue;
+            }
+            let key = (
+                atom.residue_id,
+                record.chain_id,
+                record.residue_seq,
+                record.insertion_code,
+            );
+            if previous.is_some_and(|p| p != key) {
+                residue_id += 1;
+            }
+            previous = Some(key);
+            atomistic.atoms.push(Atom {
+                residue_id,
+                ..*atom
+            });
+            atomistic.records.push(record.clone());
+        }
+        if atomistic.atoms.is_empty() {
+            return Err(PrismError::validation(
+                "Coarse-graining needs protein residues",
+            ));
+        }
+        let dropped_atoms = structure.atoms.len() - atomistic.atoms.len();
+        let states = secondary_structure(&atomistic);
+
+        // Beads of each residue; hydrogens and unlisted atoms join the bead
+        // of the nearest listed heavy atom
+        let atoms = &atomistic.atoms;
+        let mut beads: Vec<Bead> = Vec::new();
+        let mut residue_beads: Vec<(u16, usize, usize)> = Vec::new();
+        let mut start = 0;
+        while start < atoms.len() {
+            let residue_id = atoms[start].residue_id;
+            let end = (start..atoms.len())
+                .find(|&i| atoms[i].residue_id != residue_id)
+                .unwrap_or(atoms.len());
+            let name =
+                canonical_name(atomistic.records[start].residue_name.trim()).unwrap_or_default();
+            let find = |atom: &str| {
+                (start..end)
+                    .find(|&i| atomistic.records[i].name.trim() == atom)
+                    .map(|i| i as u32)
+            };
+            let first = beads.len();
+            let backbone: Vec<u32> = ["N", "CA", "C", "O"]
+                .iter()
+                .filter_map(|a| find(a))
+                .collect();
+            if find("CA").is_some() {
+                beads.push(Bead {
+                    name: "BB".into(),
+                    bead_type: backbone_type(states[residue_id as usize]).into(),
+                    charge: 0.0,
+                    atoms: backbone,
+                });
+                for (k, &(bead_type, charge, members)) in side_chain(name).iter().enumerate() {
+                    let members: Vec<u32> = members.iter().filter_map(|a| find(a)).collect();
+                    if !members.is_empty() {
+                        beads.push(Bead {
+                            name: format!("SC{}", k + 1),
+                            bead_type: bead_type.into(),
+                            charge,
+                            atoms: members,
+                        });
+                    }
+                }
+                let listed: Vec<u32> = beads[first..]
+                    .iter()
+                    .flat_map(|b| b.atoms.iter().copied())
+                    .collect();
+                for i in (start..end)
+                    .map(|i| i as u32)
+                    .filter(|i| !listed.contains(i))
+                {
+                    let nearest = (first..beads.len())
+                        .flat_map(|b| beads[b].atoms.iter().map(move |&a| (b, a)))
+                        .filter(|&(_, a)| listed.contains(&a))
+                        .min_by(|&(_, a), &(_, b)| {
+                            let d = |j: u32| {
+                                distance(atoms[i as usize].coords, atoms[j as usize].coords)
+                            };
+                            d(a).total_cmp(&d(b))
+                        })
+                        .map(|(b, _)| b)
+                        .unwrap_or(first);
+                    beads[nearest].atoms.push(i);
+                }
+                residue_beads.push((residue_id, first, beads.len()));
+            }
+            start = end;
+        }
+
+        let heavy_centre = |bead: &Bead| {
+            let heavy: Vec<u32> = bead
+                .atoms
+                .iter()
+                .copied()
+                .filter(|&i| atoms[i as usize].element != 1)
+                .collect();
+            centre(
+                atoms,
+                if heavy.is_empty() {
+                    &bead.atoms
+                } else {
+                    &heavy
+                },
+            )
+        };
+        let positions: Vec<[f32; 3]> = beads.iter().map(heavy_centre).collect();
+        let mut topology = Topology {
+            simulation_box: atomistic.simulation_box(),
+            ..Default::default()
+        };
+        for (r, &(_, first, end)) in residue_beads.iter().enumerate() {
+            let record = &atomistic.records[beads[first].atoms[0] as usize];
+            topology.residue_names.push(record.residue_name.clone());
+            for (b, bead) in beads.iter().enumerate().take(end).skip(first) {
+                let (_, ring) = base_type(&bead.bead_type);
+                let lj = pair_parameters(&bead.bead_type, &bead.bead_type);
+                topology.atoms.push(Atom {
+                    coords: positions[b],
+                    element: 0,
+                    residue_id: r as u16,
+                    atom_type: 0,
+                    charge: bead.charge,
+                    radius: 0.5 * lj.sigma,
+                    _reserved: [0; 4],
+                });
+                topology.atom_names.push(bead.name.clone());
+                topology.atom_types.push(bead.bead_type.clone());
+                topology.masses.push(if ring { 45.0 } else { 72.0 });
+                topology.lj.push(lj);
+            }
+        }
+
+        // Backbone chain, broken where consecutive beads are over 5 Å apart
+        // or change chain
+        let chain =
+            |r: usize| atomistic.records[beads[residue_beads[r].1].atoms[0] as usize].chain_id;
+        let state = |r: usize| states[residue_beads[r].0 as usize];
+        let bb = |r: usize| residue_beads[r].1 as u32;
+        let linked: Vec<bool> = (0..residue_beads.len())
+            .map(|r| {
+                r + 1 < residue_beads.len()
+                    && chain(r) == chain(r + 1)
+                    && distance(positions[bb(r) as usize], positions[bb(r + 1) as usize]) < 5.0
+            })
+            .collect();
+        for r in 0..residue_beads.len() {
+            if linked[r] {
+                let r0 = match (state(r).is_helix(), state(r).is_strand()) {
+                    (true, _) => 3.10,
+                    (_, true) => 3.33,
+                    _ => 3.50,
+                };
+                topology.bonds.push(HarmonicBond {
+                    i: bb(r),
+                    j: bb(r + 1),
+                    k: bond_constant(1250.0),
+                    r0,
+                });
+            }
+            if r >= 1 && linked[r - 1] && linked[r] {
+                let (theta0, k): (f32, f32) = match (state(r).is_helix(), state(r).is_strand()) {
+                    (true, _) => (96.0, 700.0),
+                    (_, true) => (134.0, 25.0),
+                    _ => (127.0, 25.0),
+                };
+                topology.angles.push(HarmonicAngle {
+                    i: bb(r - 1),
+                    j: bb(r),
+                    k: bb(r + 1),
+                    force_constant: angle_constant(k),
+                    theta0: theta0.to_radians(),
+                });
+            }
+            if r >= 1
+                && linked[r - 1]
+                && linked[r]
+                && linked[r + 1]
+                && (r - 1..=r + 2).all(|s| state(s).is_helix())
+            {
+                topology.dihedrals.push(PeriodicDihedral {
+                    atoms: [bb(r - 1), bb(r), bb(r + 1), bb(r + 2)],
+                    k: 400.0 / KJ_PER_KCAL,
+                    periodicity: 1.0,
+                    phase: (-120f32).to_radians(),
+                    improper: false,
+                });
+            }
+
+            // Side chain: BB-SC1, SC1-SC2 and rings closed between all beads
+            let (_, first, end) = residue_beads[r];
+            let side: Vec<u32> = (first as u32 + 1..end as u32).collect();
+            let ring = side.len() >= 3;
+            let mut pairs: Vec<(u32, u32)> =
+                side.first().map(|&sc1| (bb(r), sc1)).into_iter().collect();
+            for (k, &a) in side.iter().enumerate() {
+                for &b in &side[k + 1..] {
+                    if ring || b == a + 1 {
+                        pairs.push((a, b));
+                    }
+                }
+            }
+            for (i, j) in pairs {
+                let k = if ring && i != bb(r) {
+                    10_000.0
+                } else {
+                    5_000.0
+                };
+                let r0 = distance(positions[i as usize], positions[j as usize]);
+                topology.bonds.push(HarmonicBond {
+                    i,
+                    j,
+                    k: bond_constant(k),
+                    r0,
+                });
+            }
+            if let Some(&sc1) = side.first() {
+                let p = |i: u32| positions[i as usize];
+                for neighbour in [
+                    r.checked_sub(1).filter(|&s| linked[s]),
+                    linked[r].then_some(r + 1),
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    topology.angles.push(HarmonicAngle {
+                        i: bb(neighbour),
+                        j: bb(r),
+                        k: sc1,
+                        force_constant: angle_constant(25.0),
+                        theta0: angle(p(bb(neighbour)), p(bb(r)), p(sc1)),
+                    });
+                }
+                if let (Some(&sc2), false) = (side.get(1), ring) {
+                    topology.angles.push(HarmonicAngle {
+                        i: bb(r),
+                        j: sc1,
+                        k: sc2,
+                        force_constant: angle_constant(25.0),
+                        theta0: angle(p(bb(r)), p(sc1), p(sc2)),
+                    });
+                }
+            }
+        }
+        topology.exclusions = topology.bonded_pairs_within(1);
+
+        let mut springs = 0;
+        if config.elastic_cutoff > 0.0 {
+            for r in 0..residue_beads.len() {
+                for s in r + 3..residue_beads.len() {
+                    let r0 = distance(positions[bb(r) as usize], positions[bb(s) as usize]);
+                    if r0 < config.elastic_cutoff {
+                        topology.bonds.push(HarmonicBond {
+                            i: bb(r),
+                            j: bb(s),
+                            k: config.elastic_force_constant,
+                            r0,
+                        });
+                        springs += 1;
+                    }
+                }
+            }
+        }
+
+        let mut types: Vec<&str> = topology.atom_types.iter().map(String::as_str).collect();
+        types.sort_unstable();
+        types.dedup();
+        for (k, &a) in types.iter().enumerate() {
+            for &b in &types[k..] {
+                topology.nbfix.push(NbFix {
+                    type_a: a.into(),
+                    type_b: b.into(),
+                    params: pair_parameters(a, b),
+                });
+            }
+        }
+
+        Ok(Self {
+            beads,
+            topology,
+            atomistic,
+            springs,
+            dropped_atoms,
+        })
+    }
+
+    /// Beads in topology order
+    pub fn beads(&self) -> &[Bead] {
+        &self.beads
+    }
+
+    /// Coarse-grained topology, one "atom" per bead
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    /// Mapped protein atoms, the template of [`Self::backmap`]
+    pub fn atomistic(&self) -> &PdbStructure {
+        &self.atomistic
+    }
+
+    /// Elastic network springs added
+    pub fn num_springs(&self) -> usize {
+        self.springs
+    }
+
+    /// Atoms of non-protein residues left out of the model
+    pub fn dropped_atoms(&self) -> usize {
+        self.dropped_atoms
+    }
+
+    /// Beads as a structure (bead names, residue metadata of their atoms)
+    pub fn to_structure(&self) -> PdbStructure {
+        let mut structure = PdbStructure::from_atoms(&self.topology.atoms);
+        for (record, bead) in structure.records.iter_mut().zip(&self.beads) {
+            let source = &self.atomistic.records[bead.atoms[0] as usize];
+            *record = prism_io::pdb::PdbAtomRecord {
+                serial: record.serial,
+                name: bead.name.clone(),
+                occupancy: 1.0,
+                b_factor: 0.0,
+                ..source.clone()
+            };
+        }
+        structure.cryst1 = self.atomistic.cryst1;
+        structure
+    }
+
+    /// All-atom structure following the bead positions `beads` (one per
+    /// bead, in topology order)
+    pub fn backmap(&self, beads: &[[f32; 3]]) -> Result<PdbStructure, PrismError> {
+        if beads.len() != self.beads.len() {
+            return Err(PrismError::validation(format!(
+                "Back-mapping needs {} bead positions, got {}",
+                self.beads.len(),
+                beads.len()
+            )));
+        }
+        let reference: Vec<[f32; 3]> = self.topology.atoms.iter().map(|a| a.coords).collect();
+        let residue_of = |b: usize| self.topology.atoms[b].residue_id;
+        let num_residues = self.topology.residue_names.len();
+        let mut structure = self.atomistic.clone();
+        let vector = |p: [f32; 3]| Vector3::new(p[0] as f64, p[1] as f64, p[2] as f64);
+        for residue in 0..num_residues as u16 {
+            let window = |b: &usize| residue_of(*b).abs_diff(residue) <= 1;
+            let fitted: Vec<usize> = (0..self.beads.len()).filter(window).collect();
+            let x: Vec<Vector3<f64>> = fitted.iter().map(|&b| vector(beads[b])).collect();
+            let y: Vec<Vector3<f64>> = fitted.iter().map(|&b| vector(reference[b])).collect();
+            let n = fitted.len() as f64;
+            let cx = x.iter().sum::<Vector3<f64>>() / n;
+            let cy = y.iter().sum::<Vector3<f64>>() / n;
+            let rotation = if fitted.len() >= 3 {
+                kabsch_rotation(&x, cx, &y, cy).unwrap_or_else(Matrix3::identity)
+            } else {
+                Matrix3::identity()
+            };
+            for bead in (0..self.beads.len()).filter(|&b| residue_of(b) == residue) {
+                for &i in &self.beads[bead].atoms {
+                    let atom = &mut structure.atoms[i as usize];
+                    let p = rotation * (vector(atom.coords) - cy) + cx;
+                    atom.coords = [p.x as f32, p.y as f32, p.z as f32];
+                }
+            }
+        }
+        Ok(structure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_io::pdb::parse_pdb;
+
+    /// Ala-Asp-Lys with all heavy atoms and a water
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   1.458   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       0.000   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       1.404  -0.307   0.511  1.00  0.00           C
+ATOM      4  O   ALA A   1       2.385   0.235   0.005  1.00  0.00           O
+ATOM      5  CB  ALA A   1      -0.312  -0.536  -1.399  1.00  0.00           C
+ATOM      6  N   ASP A   2       1.486  -1.176   1.514  1.00  0.00           N
+ATOM      7  CA  ASP A   2       2.768  -1.556   2.095  1.00  0.00           C
+ATOM      8  C   ASP A   2       3.012  -3.056   1.962  1.00  0.00           C
+ATOM      9  O   ASP A   2       2.150  -3.862   2.309  1.00  0.00           O
+ATOM     10  CB  ASP A   2       2.834  -1.142   3.567  1.00  0.00           C
+ATOM     11  CG  ASP A   2       4.150  -1.512   4.230  1.00  0.00           C
+ATOM     12  OD1 ASP A   2       5.013  -2.094   3.538  1.00  0.00           O
+ATOM     13  OD2 ASP A   2       4.304  -1.216   5.435  1.00  0.00           O
+ATOM     14  N   LYS A   3       4.188  -3.417   1.457  1.00  0.00           N
+ATOM     15  CA  LYS A   3       4.546  -4.819   1.277  1.00  0.00           C
+ATOM     16  C   LYS A   3       5.792  -5.177   2.080  1.00  0.00           C
+ATOM     17  O   LYS A   3       6.784  -4.427   1.954  1.00  0.00           O
+ATOM     18  CB  LYS A   3       4.768  -5.129  -0.205  1.00  0.00           C
+ATOM     19  CG  LYS A   3       5.147  -6.574  -0.484  1.00  0.00           C
+ATOM     20  CD  LYS A   3       5.351  -6.806  -1.973  1.00  0.00           C
+ATOM     21  CE  LYS A   3       5.729  -8.251  -2.252  1.00  0.00           C
+ATOM     22  NZ  LYS A   3       5.929  -8.479  -3.711  1.00  0.00           N
+ATOM     23  OXT LYS A   3       5.728  -6.194   2.803  1.00  0.00           O
+HETATM   24  O   HOH A 101      10.000  10.000  10.000  1.00  0.00           O
+";
+
+    #[test]
+    fn test_interaction_matrix_is_symmetric() {
+        for (i, row) in INTERACTION_LEVELS.iter().enumerate() {
+            for (j, &level) in row.iter().enumerate() {
+                assert_eq!(
+                    level, INTERACTION_LEVELS[j][i],
+                    "{} {}",
+                    BEAD_TYPES[i], BEAD_TYPES[j]
+                );
+            }
+        }
+        let polar = pair_parameters("P5", "P5");
+        assert!((polar.epsilon - 5.6 / 4.184).abs() < 1e-6);
+        assert_eq!(pair_parameters("Qa", "C1").sigma, 6.2);
+        let ring = pair_parameters("SC5", "SC5");
+        assert_eq!(ring.sigma, 4.3);
+        assert!((ring.epsilon - 0.75 * 3.5 / 4.184).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_map_and_backmap_peptide() {
+        let structure = parse_pdb(PEPTIDE).unwrap();
+        let model =
+            CoarseGrainedModel::from_structure(&structure, &MartiniConfig::default()).unwrap();
+        assert_eq!(model.dropped_atoms(), 1);
+        let names: Vec<&str> = model.beads().iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["BB", "BB", "SC1", "BB", "SC1", "SC2"]);
+        let types: Vec<&str> = model.beads().iter().map(|b| b.bead_type.as_str()).collect();
+        assert_eq!(&types[1..], ["P5", "Qa", "P5", "C3", "Qd"]);
+        let topology = model.topology();
+        let charge: f32 = topology.atoms.iter().map(|a| a.charge).sum();
+        assert_eq!(charge, 0.0);
+        // OXT follows the lysine backbone bead
+        assert!(model.beads()[3].atoms.contains(&22));
+        // BB-BB x2, BB-SC1 x2, SC1-SC2; no springs over three residues
+        assert_eq!(topology.bonds.len(), 5);
+        assert_eq!(model.num_springs(), 0);
+        assert_eq!(topology.exclusions.len(), 5);
+        assert!(!topology.exclusions.contains(&(3, 5)));
+        // BB-BB-BB, BB-BB-SC1 from both sides of Asp and from Ala into Lys,
+        // BB-SC1-SC2 in Lys
+        assert_eq!(topology.angles.len(), 5);
+        assert_eq!(model.to_structure().records[5].name, "SC2");
+
+        let reference: Vec<[f32; 3]> = topology.atoms.iter().map(|a| a.coords).collect();
+        let same = model.backmap(&reference).unwrap();
+        for (a, b) in same.atoms.iter().zip(&model.atomistic().atoms) {
+            assert!(distance(a.coords, b.coords) < 1e-3);
+        }
+        let shifted: Vec<[f32; 3]> = reference
+            .iter()
+            .map(|p| [p[0] + 5.0, p[1], p[2] - 2.0])
+            .collect();
+        let moved = model.backmap(&shifted).unwrap();
+        let nz = &moved.atoms[21].coords;
+        assert!(distance(*nz, [5.929 + 5.0, -8.479, -3.711 - 2.0]) < 1e-3);
+        assert!(model.backmap(&reference[1..]).is_err());
+    }
+}
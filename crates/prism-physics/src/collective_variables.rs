@@ -0,0 +1,330 @@
+//! # Collective Variables and Bias Potentials
+//! Low-dimensional descriptors of a configuration (group distances,
+//! projections on a direction, dihedrals, RMSD to a reference) with
+//! analytic gradients, and the
+//! [`BiasPotential`] hook through which CV-based biases (metadynamics,
+//! umbrella restraints, steered pulling) add forces to the engine.
+//! Positions are Float4-stride buffers with the mass in the `w` lane.
+//! Units: Angstrom, radians, kcal/mol.
+
+use crate::bonded::dihedral_gradient;
+use nalgebra::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+type Vec3 = [f64; 3];
+
+#[inline]
+fn load(p: &[f32], i: u32) -> Vec3 {
+    let o = i as usize * 4;
+    [p[o] as f64, p[o + 1] as f64, p[o + 2] as f64]
+}
+
+#[inline]
+fn mass(p: &[f32], i: u32) -> f64 {
+    (p[i as usize * 4 + 3] as f64).max(1e-6)
+}
+
+/// Mass-weighted centre of a group and its total mass
+fn centre(p: &[f32], group: &[u32]) -> (Vec3, f64) {
+    let mut c = [0.0; 3];
+    let mut total = 0.0;
+    for &i in group {
+        let (x, m) = (load(p, i), mass(p, i));
+        for d in 0..3 {
+            c[d] += m * x[d];
+        }
+        total += m;
+    }
+    (c.map(|v| v / total.max(1e-12)), total)
+}
+
+/// Gradient of a CV as `(atom, dξ/dx)` entries
+pub type CvGradient = Vec<(u32, Vec3)>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CollectiveVariable {
+    /// Distance between the mass-weighted centres of two groups (Å)
+    Distance { a: Vec<u32>, b: Vec<u32> },
+    /// Mass-weighted centre of a group projected on a direction (Å); the
+    /// direction is normalised on use
+    Projection {
+        group: Vec<u32>,
+        direction: [f64; 3],
+    },
+    /// Dihedral angle (radians, periodic on (-π, π])
+    Dihedral { atoms: [u32; 4] },
+    /// RMSD of `atoms` to `reference` after optimal superposition (Å)
+    Rmsd {
+        atoms: Vec<u32>,
+        reference: Vec<[f32; 3]>,
+    },
+}
+
+impl CollectiveVariable {
+    /// Period of the variable, if it is periodic
+    pub fn period(&self) -> Option<f64> {
+        match self {
+            CollectiveVariable::Dihedral { .. } => Some(2.0 * PI),
+            _ => None,
+        }
+    }
+
+    /// `a - b`, wrapped to the minimum image for periodic variables
+    pub fn difference(&self, a: f64, b: f64) -> f64 {
+        let d = a - b;
+        match self.period() {
+            Some(period) => d - period * (d / period).round(),
+            None => d,
+        }
+    }
+
+    /// Current value
+    pub fn value(&self, positions: &[f32]) -> f64 {
+        self.evaluate(positions).0
+    }
+
+    /// Current value and gradient
+    pub fn evaluate(&self, positions: &[f32]) -> (f64, CvGradient) {
+        match self {
+            CollectiveVariable::Distance { a, b } => {
+                let (ca, ma) = centre(positions, a);
+                let (cb, mb) = centre(positions, b);
+                let d = [cb[0] - ca[0], cb[1] - ca[1], cb[2] - ca[2]];
+                let r = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                if r < 1e-12 {
+                    return (0.0, Vec::new());
+                }
+                let u = d.map(|v| v / r);
+                let gradient = a
+                    .iter()
+                    .map(|&i| (i, u.map(|v| -v * mass(positions, i) / ma)))
+                    .chain(
+                        b.iter()
+                            .map(|&i| (i, u.map(|v| v * mass(positions, i) / mb))),
+                    )
+                    .collect();
+                (r, gradient)
+            }
+            CollectiveVariable::Projection { group, direction } => {
+                let norm = direction.iter().map(|v| v * v).sum::<f64>().sqrt();
+                if norm < 1e-12 {
+                    return (0.0, Vec::new());
+                }
+                let n = direction.map(|v| v / norm);
+                let (c, m) = centre(positions, group);
+                let value = c[0] * n[0] + c[1] * n[1] + c[2] * n[2];
+                let gradient = group
+                    .iter()
+                    .map(|&i| (i, n.map(|v| v * mass(positions, i) / m)))
+                    .collect();
+                (value, gradient)
+            }
+            CollectiveVariable::Dihedral { atoms } => {
+                let [i, j, k, l] = *atoms;
+                match dihedral_gradient(
+                    load(positions, i),
+                    load(positions, j),
+                    load(positions, k),
+                    load(positions, l),
+                ) {
+                    Some((phi, grad)) => (phi, atoms.iter().copied().zip(grad).collect()),
+                    None => (0.0, Vec::new()),
+                }
+            }
+            CollectiveVariable::Rmsd { atoms, reference } => rmsd(positions, atoms, reference),
+        }
+    }
+}
+
+/// Kabsch-fitted RMSD and its gradient. At the optimal rotation the
+/// derivative of the rotation drops out, leaving `(x_i - R y_i) / (N rmsd)`
+/// in centred coordinates.
+fn rmsd(positions: &[f32], atoms: &[u32], reference: &[[f32; 3]]) -> (f64, CvGradient) {
+    let n = atoms.len().min(reference.len());
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+    let x: Vec<Vector3<f64>> = atoms[..n]
+        .iter()
+        .map(|&i| Vector3::from(load(positions, i)))
+        .collect();
+    let y: Vec<Vector3<f64>> = reference[..n]
+        .iter()
+        .map(|r| Vector3::new(r[0] as f64, r[1] as f64, r[2] as f64))
+        .collect();
+    let cx = x.iter().sum::<Vector3<f64>>() / n as f64;
+    let cy = y.iter().sum::<Vector3<f64>>() / n as f64;
+    let Some(rotation) = kabsch_rotation(&x, cx, &y, cy) else {
+        return (0.0, Vec::new());
+    };
+
+    let residuals: Vec<Vector3<f64>> = x
+        .iter()
+        .zip(&y)
+        .map(|(xi, yi)| (xi - cx) - rotation * (yi - cy))
+        .collect();
+    let value = (residuals.iter().map(|r| r.norm_squared()).sum::<f64>() / n as f64).sqrt();
+    if value < 1e-12 {
+        return (value, Vec::new());
+    }
+    let scale = 1.0 / (n as f64 * value);
+    let gradient = atoms[..n]
+        .iter()
+        .zip(residuals)
+        .map(|(&i, r)| (i, [r.x * scale, r.y * scale, r.z * scale]))
+        .collect();
+    (value, gradient)
+}
+
+/// Proper rotation `R` minimising `Σ |(x_i - cx) - R (y_i - cy)|²`, i.e.
+/// taking the `y` set onto the `x` set about their centres
+pub(crate) fn kabsch_rotation(
+    x: &[Vector3<f64>],
+    cx: Vector3<f64>,
+    y: &[Vector3<f64>],
+    cy: Vector3<f64>,
+) -> Option<Matrix3<f64>> {
+    let mut h = Matrix3::zeros();
+    for (xi, yi) in x.iter().zip(y) {
+        h += (yi - cy) * (xi - cx).transpose();
+    }
+    let svd = h.svd(true, true);
+    let (u, v_t) = (svd.u?, svd.v_t?);
+    let sign = (v_t.transpose() * u.transpose()).determinant().signum();
+    Some(v_t.transpose() * Matrix3::from_diagonal(&Vector3::new(1.0, 1.0, sign)) * u.transpose())
+}
+
+/// Add `-dV/dξ · dξ/dx` into a Float4-stride force buffer
+pub fn apply_cv_force(forces: &mut [f32], gradient: &CvGradient, dv_dcv: f64) {
+    for &(i, g) in gradient {
+        let o = i as usize * 4;
+        for d in 0..3 {
+            forces[o + d] -= (dv_dcv * g[d]) as f32;
+        }
+    }
+}
+
+/// External bias acting on the engine. `compute` may be called several
+/// times per step (integrator force groups, minimization); `update` is
+/// called once after each completed step to advance time-dependent state
+/// (hill deposition, pulling anchors, work accounting).
+pub trait BiasPotential: std::any::Any + Send + std::fmt::Debug {
+    /// Short name used in logs and telemetry
+    fn name(&self) -> &str;
+
+    /// Called once when the bias is attached to an engine at `step`, with
+    /// the engine's current positions and time step (ps)
+    fn attach(&mut self, _positions: &[f32], _step: u64, _dt: f32) {}
+
+    /// Bias energy, adding its forces into a Float4-stride buffer
+    fn compute(&self, positions: &[f32], forces: &mut [f32]) -> f64;
+
+    /// Advance the bias after `step` completed steps
+    fn update(&mut self, _step: u64, _positions: &[f32]) {}
+
+    /// Telemetry entries merged into the run outcome
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(atoms: &[[f32; 3]]) -> Vec<f32> {
+        atoms
+            .iter()
+            .enumerate()
+            .flat_map(|(i, x)| [x[0], x[1], x[2], 12.0 + i as f32])
+            .collect()
+    }
+
+    fn check_gradient(cv: &CollectiveVariable, positions: &[f32]) {
+        let (_, gradient) = cv.evaluate(positions);
+        let h = 1e-3;
+        for &(i, g) in &gradient {
+            for (d, &gd) in g.iter().enumerate() {
+                let k = i as usize * 4 + d;
+                let mut plus = positions.to_vec();
+                let mut minus = positions.to_vec();
+                plus[k] += h;
+                minus[k] -= h;
+                let fd = cv.difference(cv.value(&plus), cv.value(&minus)) / (2.0 * h as f64);
+                assert!(
+                    (fd - gd).abs() < 2e-3,
+                    "{:?} atom {} dim {}: {} vs {}",
+                    cv,
+                    i,
+                    d,
+                    fd,
+                    gd
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_cv_gradients_match_finite_difference() {
+        let positions = buffer(&[
+            [0.0, 0.0, 0.0],
+            [1.5, 0.2, 0.1],
+            [2.1, 1.4, -0.3],
+            [3.5, 1.6, 0.8],
+            [4.0, 2.9, 0.2],
+        ]);
+        let reference = vec![
+            [0.1, 0.0, 0.0],
+            [1.4, 0.0, 0.3],
+            [2.2, 1.1, 0.0],
+            [3.3, 1.9, 0.4],
+            [4.4, 2.5, 0.0],
+        ];
+        let cvs = [
+            CollectiveVariable::Distance {
+                a: vec![0, 1],
+                b: vec![3, 4],
+            },
+            CollectiveVariable::Projection {
+                group: vec![1, 2],
+                direction: [1.0, -2.0, 0.5],
+            },
+            CollectiveVariable::Dihedral {
+                atoms: [0, 1, 2, 3],
+            },
+            CollectiveVariable::Rmsd {
+                atoms: (0..5).collect(),
+                reference,
+            },
+        ];
+        for cv in &cvs {
+            check_gradient(cv, &positions);
+        }
+    }
+
+    #[test]
+    fn test_rmsd_is_rotation_invariant() {
+        let reference = [
+            [0.0, 0.0, 0.0],
+            [1.5, 0.0, 0.0],
+            [1.5, 1.5, 0.0],
+            [0.0, 1.5, 1.0],
+        ];
+        // Rotate 90° about z and translate
+        let moved: Vec<[f32; 3]> = reference
+            .iter()
+            .map(|r| [-r[1] + 3.0, r[0] - 1.0, r[2] + 2.0])
+            .collect();
+        let cv = CollectiveVariable::Rmsd {
+            atoms: (0..4).collect(),
+            reference: reference.to_vec(),
+        };
+        assert!(cv.value(&buffer(&moved)) < 1e-5);
+        assert!((cv.difference(3.0, -3.0) - 6.0).abs() < 1e-12);
+        let dihedral = CollectiveVariable::Dihedral {
+            atoms: [0, 1, 2, 3],
+        };
+        assert!((dihedral.difference(3.0, -3.0) - (6.0 - 2.0 * PI)).abs() < 1e-12);
+    }
+}
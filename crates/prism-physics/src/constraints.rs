@@ -0,0 +1,819 @@
+//! # Holonomic Constraints - SHAKE/RATTLE
+//! Fixed-length distance constraints on bonds to hydrogen. SHAKE corrects the
+//! unconstrained positions of a step along the reference bond vectors, RATTLE
+//! removes the velocity components along the constrained bonds. Both iterate
+//! Gauss-Seidel style until every constraint is within the relative tolerance.
+//! Rigid waters are recognised from their residue names and solved
+//! analytically with SETTLE (Miyamoto & Kollman 1992) instead.
+//! Masses are read from the `w` lane of the Float4 position buffer.
+//! Units: Angstrom, amu.
+
+use prism_core::PrismError;
+use prism_io::solvate::{TIP3P_HOH, TIP3P_OH};
+use prism_io::topology::Topology;
+use serde::{Deserialize, Serialize};
+
+/// Which bonds are held rigid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ConstraintMode {
+    /// Fully flexible bonds
+    None,
+    /// Bonds involving a hydrogen atom
+    #[default]
+    HBonds,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConstraintConfig {
+    pub mode: ConstraintMode,
+    /// Relative tolerance on constrained lengths (and on `v·r / d²` for RATTLE)
+    pub tolerance: f64,
+    /// Iteration limit before a step is reported as failed
+    pub max_iterations: usize,
+    /// Hold water molecules rigid with SETTLE
+    pub rigid_water: bool,
+}
+
+impl Default for ConstraintConfig {
+    fn default() -> Self {
+        Self {
+            mode: ConstraintMode::HBonds,
+            tolerance: 1e-6,
+            max_iterations: 500,
+            rigid_water: true,
+        }
+    }
+}
+
+/// Distance constraint between atoms `i` and `j`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DistanceConstraint {
+    pub i: u32,
+    pub j: u32,
+    /// Target length (Å)
+    pub length: f32,
+}
+
+/// Rigid three-site water handled by SETTLE
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SettleWater {
+    pub oxygen: u32,
+    pub hydrogens: [u32; 2],
+    /// O-H distance (Å)
+    pub oh: f32,
+    /// H-H distance (Å)
+    pub hh: f32,
+}
+
+/// Residue names treated as water
+pub const WATER_RESIDUES: [&str; 7] = ["HOH", "WAT", "SOL", "TIP3", "TP3", "T3P", "SPC"];
+
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    constraints: Vec<DistanceConstraint>,
+    waters: Vec<SettleWater>,
+    tolerance: f64,
+    max_iterations: usize,
+}
+
+type Vec3 = [f64; 3];
+
+#[inline]
+fn load(p: &[f32], i: u32) -> Vec3 {
+    let o = i as usize * 4;
+    [p[o] as f64, p[o + 1] as f64, p[o + 2] as f64]
+}
+
+#[inline]
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[inline]
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[inline]
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[inline]
+fn inv_mass(p: &[f32], i: u32) -> f64 {
+    1.0 / (p[i as usize * 4 + 3] as f64).max(1e-6)
+}
+
+#[inline]
+fn store(p: &mut [f32], i: u32, v: Vec3) {
+    let o = i as usize * 4;
+    p[o] = v[0] as f32;
+    p[o + 1] = v[1] as f32;
+    p[o + 2] = v[2] as f32;
+}
+
+#[inline]
+fn add_scaled(buf: &mut [f32], i: u32, v: Vec3, scale: f64) {
+    let o = i as usize * 4;
+    buf[o] += (v[0] * scale) as f32;
+    buf[o + 1] += (v[1] * scale) as f32;
+    buf[o + 2] += (v[2] * scale) as f32;
+}
+
+impl Constraints {
+    pub fn new(config: &ConstraintConfig, constraints: Vec<DistanceConstraint>) -> Self {
+        Self {
+            constraints,
+            waters: Vec::new(),
+            tolerance: config.tolerance.max(1e-12),
+            max_iterations: config.max_iterations.max(1),
+        }
+    }
+
+    /// Add rigid waters; their atoms must not appear in the distance constraints.
+    pub fn with_waters(mut self, waters: Vec<SettleWater>) -> Self {
+        self.waters = waters;
+        self
+    }
+
+    /// Constraints selected by `config.mode` from the topology bonds, held at
+    /// their equilibrium lengths. With `rigid_water` the waters found by
+    /// [`detect_waters`] are handed to SETTLE and drop out of the bond list.
+    pub fn from_topology(config: &ConstraintConfig, topology: &Topology) -> Self {
+        let waters = if config.rigid_water {
+            detect_waters(topology)
+        } else {
+            Vec::new()
+        };
+        let mut in_water = vec![false; topology.atoms.len()];
+        for w in &waters {
+            for i in [w.oxygen, w.hydrogens[0], w.hydrogens[1]] {
+                in_water[i as usize] = true;
+            }
+        }
+        let constraints = match config.mode {
+            ConstraintMode::None => Vec::new(),
+            ConstraintMode::HBonds => {
+                let is_h = |i: u32| {
+                    topology
+                        .atoms
+                        .get(i as usize)
+                        .is_some_and(|a| a.element == 1)
+                };
+                topology
+                    .bonds
+                    .iter()
+                    .filter(|b| b.r0 > 0.0 && (is_h(b.i) || is_h(b.j)))
+                    .filter(|b| !in_water.get(b.i as usize).copied().unwrap_or(false))
+                    .map(|b| DistanceConstraint {
+                        i: b.i,
+                        j: b.j,
+                        length: b.r0,
+                    })
+                    .collect()
+            }
+        };
+        Self::new(config, constraints).with_waters(waters)
+    }
+
+    pub fn constraints(&self) -> &[DistanceConstraint] {
+        &self.constraints
+    }
+
+    pub fn waters(&self) -> &[SettleWater] {
+        &self.waters
+    }
+
+    /// Number of constraint equations (three per rigid water)
+    pub fn len(&self) -> usize {
+        self.constraints.len() + 3 * self.waters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty() && self.waters.is_empty()
+    }
+
+    /// SHAKE: move `positions` so every constraint holds, correcting along
+    /// the bond vectors of `reference` (the positions before the step).
+    /// Returns the number of iterations used.
+    pub fn shake(&self, reference: &[f32], positions: &mut [f32]) -> Result<usize, PrismError> {
+        for iteration in 1..=self.max_iterations {
+            let mut converged = true;
+            for c in &self.constraints {
+                let s = sub(load(positions, c.i), load(positions, c.j));
+                let d2 = (c.length as f64).powi(2);
+                let diff = d2 - dot(s, s);
+                if diff.abs() <= 2.0 * self.tolerance * d2 {
+                    continue;
+                }
+                converged = false;
+                let r = sub(load(reference, c.i), load(reference, c.j));
+                let (wi, wj) = (inv_mass(positions, c.i), inv_mass(positions, c.j));
+                let sr = dot(s, r);
+                if sr.abs() < 1e-6 * d2 {
+                    return Err(PrismError::constraint_failure(format!(
+                        "SHAKE: constraint {}-{} rotated too far in one step",
+                        c.i, c.j
+                    )));
+                }
+                let g = diff / (2.0 * (wi + wj) * sr);
+                add_scaled(positions, c.i, r, g * wi);
+                add_scaled(positions, c.j, r, -g * wj);
+            }
+            if converged {
+                return Ok(iteration);
+            }
+        }
+        Err(PrismError::constraint_failure(format!(
+            "SHAKE did not converge in {} iterations",
+            self.max_iterations
+        )))
+    }
+
+    /// RATTLE: remove the relative velocity along every constrained bond.
+    /// Returns the number of iterations used.
+    pub fn rattle(&self, positions: &[f32], velocities: &mut [f32]) -> Result<usize, PrismError> {
+        for iteration in 1..=self.max_iterations {
+            let mut converged = true;
+            for c in &self.constraints {
+                let r = sub(load(positions, c.i), load(positions, c.j));
+                let v = sub(load(velocities, c.i), load(velocities, c.j));
+                let d2 = (c.length as f64).powi(2);
+                let rv = dot(r, v);
+                if rv.abs() <= self.tolerance * d2 {
+                    continue;
+                }
+                converged = false;
+                let (wi, wj) = (inv_mass(positions, c.i), inv_mass(positions, c.j));
+                let k = rv / ((wi + wj) * dot(r, r));
+                add_scaled(velocities, c.i, r, -k * wi);
+                add_scaled(velocities, c.j, r, k * wj);
+            }
+            if converged {
+                return Ok(iteration);
+            }
+        }
+        Err(PrismError::constraint_failure(format!(
+            "RATTLE did not converge in {} iterations",
+            self.max_iterations
+        )))
+    }
+
+    /// SETTLE: place every rigid water at its constrained geometry, keeping
+    /// the centre of mass of the unconstrained update and the orientation
+    /// closest to it. `reference` holds the (rigid) positions before the step.
+    pub fn settle(&self, reference: &[f32], positions: &mut [f32]) -> Result<(), PrismError> {
+        for w in &self.waters {
+            settle_water(w, reference, positions)?;
+        }
+        Ok(())
+    }
+
+    /// Velocity SETTLE: remove the relative velocity along the three bonds of
+    /// every rigid water by solving the 3x3 impulse system directly.
+    pub fn settle_velocities(
+        &self,
+        positions: &[f32],
+        velocities: &mut [f32],
+    ) -> Result<(), PrismError> {
+        for w in &self.waters {
+            let atoms = [w.oxygen, w.hydrogens[0], w.hydrogens[1]];
+            let pairs = [(0, 1), (0, 2), (1, 2)];
+            let inv = atoms.map(|i| inv_mass(positions, i));
+            let r = pairs.map(|(a, b)| sub(load(positions, atoms[a]), load(positions, atoms[b])));
+            let v = atoms.map(|i| load(velocities, i));
+
+            // A[k][l] = d(r_k · v_ab) / d(g_l) for impulses g_l along r_l
+            let mut a = [[0.0f64; 3]; 3];
+            let mut rhs = [0.0f64; 3];
+            for (k, &(ka, kb)) in pairs.iter().enumerate() {
+                rhs[k] = -dot(r[k], sub(v[ka], v[kb]));
+                for (l, &(lp, lq)) in pairs.iter().enumerate() {
+                    let mut s = 0.0;
+                    if ka == lp {
+                        s -= inv[lp];
+                    }
+                    if ka == lq {
+                        s += inv[lq];
+                    }
+                    if kb == lp {
+                        s += inv[lp];
+                    }
+                    if kb == lq {
+                        s -= inv[lq];
+                    }
+                    a[k][l] = s * dot(r[k], r[l]);
+                }
+            }
+            let g = solve3(a, rhs).ok_or_else(|| {
+                PrismError::constraint_failure(format!("SETTLE: degenerate water at atom {}", w.oxygen))
+            })?;
+            for (l, &(lp, lq)) in pairs.iter().enumerate() {
+                add_scaled(velocities, atoms[lp], r[l], -g[l] * inv[lp]);
+                add_scaled(velocities, atoms[lq], r[l], g[l] * inv[lq]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Constrain one integration step: SHAKE/SETTLE the positions, fold the
+    /// correction into the velocities of the constrained atoms and RATTLE.
+    pub fn apply(
+        &self,
+        reference: &[f32],
+        positions: &mut [f32],
+        velocities: &mut [f32],
+        dt: f32,
+    ) -> Result<(), PrismError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        self.shake(reference, positions)?;
+        self.settle(reference, positions)?;
+        let inv_dt = 1.0 / dt;
+        let constrained = self.constraints.iter().flat_map(|c| [c.i, c.j]).chain(
+            self.waters
+                .iter()
+                .flat_map(|w| [w.oxygen, w.hydrogens[0], w.hydrogens[1]]),
+        );
+        for i in constrained {
+            let o = i as usize * 4;
+            for d in o..o + 3 {
+                velocities[d] = (positions[d] - reference[d]) * inv_dt;
+            }
+        }
+        self.rattle(positions, velocities)?;
+        self.settle_velocities(positions, velocities)?;
+        Ok(())
+    }
+
+    /// Degrees of freedom removed by the constraints
+    pub fn removed_dof(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Three-site waters of the topology: residues named in [`WATER_RESIDUES`]
+/// made of one oxygen and two hydrogens. Lengths come from the O-H and H-H
+/// bonds when present, TIP3P geometry otherwise.
+pub fn detect_waters(topology: &Topology) -> Vec<SettleWater> {
+    let bond_length = |a: u32, b: u32| {
+        topology
+            .bonds
+            .iter()
+            .find(|bd| (bd.i == a && bd.j == b) || (bd.i == b && bd.j == a))
+            .map(|bd| bd.r0)
+            .filter(|&r0| r0 > 0.0)
+    };
+    let is_water = |residue: u16| {
+        topology
+            .residue_names
+            .get(residue as usize)
+            .is_some_and(|name| WATER_RESIDUES.contains(&name.trim()))
+    };
+
+    let atoms = &topology.atoms;
+    let mut waters = Vec::new();
+    let mut start = 0;
+    // Residues are contiguous runs of atoms sharing a residue id
+    while start < atoms.len() {
+        let residue = atoms[start].residue_id;
+        let end = atoms[start..]
+            .iter()
+            .position(|a| a.residue_id != residue)
+            .map_or(atoms.len(), |n| start + n);
+        if end - start == 3 && is_water(residue) {
+            let idx = [start as u32, start as u32 + 1, start as u32 + 2];
+            let oxygen = idx
+                .iter()
+                .copied()
+                .find(|&i| atoms[i as usize].element == 8);
+            let hydrogens: Vec<u32> = idx
+                .iter()
+                .copied()
+                .filter(|&i| atoms[i as usize].element == 1)
+                .collect();
+            if let (Some(oxygen), &[h1, h2]) = (oxygen, hydrogens.as_slice()) {
+                let oh = bond_length(oxygen, h1).unwrap_or(TIP3P_OH);
+                let hh = bond_length(h1, h2)
+                    .unwrap_or_else(|| 2.0 * oh * (0.5 * TIP3P_HOH.to_radians()).sin());
+                waters.push(SettleWater {
+                    oxygen,
+                    hydrogens: [h1, h2],
+                    oh,
+                    hh,
+                });
+            }
+        }
+        start = end;
+    }
+    waters
+}
+
+/// Analytic SETTLE position update for one water (OpenMM reference form)
+fn settle_water(
+    w: &SettleWater,
+    reference: &[f32],
+    positions: &mut [f32],
+) -> Result<(), PrismError> {
+    let atoms = [w.oxygen, w.hydrogens[0], w.hydrogens[1]];
+    let old = atoms.map(|i| load(reference, i));
+    let new = atoms.map(|i| load(positions, i));
+    let m0 = 1.0 / inv_mass(positions, atoms[0]);
+    let m1 = 1.0 / inv_mass(positions, atoms[1]);
+    let total = m0 + 2.0 * m1;
+
+    // Canonical geometry: O at (0, ra, 0), H at (∓rc, -rb, 0) about the COM
+    let (oh, hh) = (w.oh as f64, w.hh as f64);
+    let rc = 0.5 * hh;
+    let height = (oh * oh - rc * rc).sqrt();
+    let ra = 2.0 * m1 * height / total;
+    let rb = height - ra;
+
+    // Everything relative to the old oxygen position
+    let b0 = sub(old[1], old[0]);
+    let c0 = sub(old[2], old[0]);
+    let xp = [
+        sub(new[0], old[0]),
+        sub(new[1], old[1]),
+        sub(new[2], old[2]),
+    ];
+    let com: Vec3 = std::array::from_fn(|d| {
+        (m0 * xp[0][d] + m1 * (b0[d] + xp[1][d]) + m1 * (c0[d] + xp[2][d])) / total
+    });
+    let a1: Vec3 = std::array::from_fn(|d| xp[0][d] - com[d]);
+    let b1: Vec3 = std::array::from_fn(|d| b0[d] + xp[1][d] - com[d]);
+    let c1: Vec3 = std::array::from_fn(|d| c0[d] + xp[2][d] - com[d]);
+
+    // Frame: z normal to the old plane, x normal to z and the new oxygen
+    let z = cross(b0, c0);
+    let x = cross(a1, z);
+    let y = cross(z, x);
+    let (lx, ly, lz) = (dot(x, x).sqrt(), dot(y, y).sqrt(), dot(z, z).sqrt());
+    if lx < 1e-12 || ly < 1e-12 || lz < 1e-12 {
+        return Err(PrismError::constraint_failure(format!(
+            "SETTLE: degenerate water at atom {}",
+            w.oxygen
+        )));
+    }
+    let (ex, ey, ez) = (x.map(|v| v / lx), y.map(|v| v / ly), z.map(|v| v / lz));
+    let local = |v: Vec3| [dot(ex, v), dot(ey, v), dot(ez, v)];
+    let (b0d, c0d) = (local(b0), local(c0));
+    let (a1d, b1d, c1d) = (local(a1), local(b1), local(c1));
+
+    let sinphi = a1d[2] / ra;
+    let cos2phi = 1.0 - sinphi * sinphi;
+    if cos2phi <= 0.0 {
+        return Err(PrismError::constraint_failure(format!(
+            "SETTLE: water at atom {} tilted too far in one step",
+            w.oxygen
+        )));
+    }
+    let cosphi = cos2phi.sqrt();
+    let sinpsi = (b1d[2] - c1d[2]) / (2.0 * rc * cosphi);
+    let cos2psi = 1.0 - sinpsi * sinpsi;
+    if cos2psi <= 0.0 {
+        return Err(PrismError::constraint_failure(format!(
+            "SETTLE: water at atom {} twisted too far in one step",
+            w.oxygen
+        )));
+    }
+    let cospsi = cos2psi.sqrt();
+
+    let ya2d = ra * cosphi;
+    let mut xb2d = -rc * cospsi;
+    let yb2d = -rb * cosphi - rc * sinpsi * sinphi;
+    let yc2d = -rb * cosphi + rc * sinpsi * sinphi;
+    let hh2 = 4.0 * xb2d * xb2d + (yb2d - yc2d).powi(2) + (b1d[2] - c1d[2]).powi(2);
+    let deltx = 2.0 * xb2d + (4.0 * xb2d * xb2d - hh2 + hh * hh).max(0.0).sqrt();
+    xb2d -= 0.5 * deltx;
+
+    // In-plane rotation θ matching the angular momentum of the old frame
+    let alpha = xb2d * (b0d[0] - c0d[0]) + b0d[1] * yb2d + c0d[1] * yc2d;
+    let beta = xb2d * (c0d[1] - b0d[1]) + b0d[0] * yb2d + c0d[0] * yc2d;
+    let gamma = b0d[0] * b1d[1] - b1d[0] * b0d[1] + c0d[0] * c1d[1] - c1d[0] * c0d[1];
+    let al2be2 = alpha * alpha + beta * beta;
+    let sinthe = (alpha * gamma - beta * (al2be2 - gamma * gamma).max(0.0).sqrt()) / al2be2;
+    let costhe = (1.0 - sinthe * sinthe).max(0.0).sqrt();
+
+    let a3d = [-ya2d * sinthe, ya2d * costhe, a1d[2]];
+    let b3d = [
+        xb2d * costhe - yb2d * sinthe,
+        xb2d * sinthe + yb2d * costhe,
+        b1d[2],
+    ];
+    let c3d = [
+        -xb2d * costhe - yc2d * sinthe,
+        -xb2d * sinthe + yc2d * costhe,
+        c1d[2],
+    ];
+    let global =
+        |v: Vec3| -> Vec3 { std::array::from_fn(|d| ex[d] * v[0] + ey[d] * v[1] + ez[d] * v[2]) };
+
+    let origin = old[0];
+    let placed = [global(a3d), global(b3d), global(c3d)];
+    for (&i, p) in atoms.iter().zip(placed) {
+        store(
+            positions,
+            i,
+            std::array::from_fn(|d| origin[d] + com[d] + p[d]),
+        );
+    }
+    Ok(())
+}
+
+/// Solve a 3x3 linear system by Cramer's rule
+fn solve3(a: [[f64; 3]; 3], b: Vec3) -> Option<Vec3> {
+    let det = |m: [[f64; 3]; 3]| dot(m[0], cross(m[1], m[2]));
+    let d = det(a);
+    if d.abs() < 1e-300 {
+        return None;
+    }
+    Some(std::array::from_fn(|col| {
+        let mut m = a;
+        for (row, &bv) in m.iter_mut().zip(&b) {
+            row[col] = bv;
+        }
+        det(m) / d
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(atoms: &[([f32; 3], f32)]) -> Vec<f32> {
+        atoms
+            .iter()
+            .flat_map(|&(x, m)| [x[0], x[1], x[2], m])
+            .collect()
+    }
+
+    fn length(p: &[f32], c: &DistanceConstraint) -> f64 {
+        let d = sub(load(p, c.i), load(p, c.j));
+        dot(d, d).sqrt()
+    }
+
+    fn water_like() -> Constraints {
+        Constraints::new(
+            &ConstraintConfig::default(),
+            vec![
+                DistanceConstraint {
+                    i: 0,
+                    j: 1,
+                    length: 0.9572,
+                },
+                DistanceConstraint {
+                    i: 0,
+                    j: 2,
+                    length: 0.9572,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_shake_restores_lengths_and_momentum() {
+        let constraints = water_like();
+        let reference = buffer(&[
+            ([0.0, 0.0, 0.0], 16.0),
+            ([0.9572, 0.0, 0.0], 1.008),
+            ([-0.24, 0.9266, 0.0], 1.008),
+        ]);
+        let mut positions = reference.clone();
+        for (k, shift) in [(4, 0.08), (9, -0.05), (1, 0.02), (10, 0.03)] {
+            positions[k] += shift;
+        }
+        let momentum = |p: &[f32]| -> [f64; 3] {
+            let mut m = [0.0; 3];
+            for a in p.chunks_exact(4) {
+                for d in 0..3 {
+                    m[d] += a[3] as f64 * a[d] as f64;
+                }
+            }
+            m
+        };
+        let before = momentum(&positions);
+
+        constraints.shake(&reference, &mut positions).unwrap();
+        for c in constraints.constraints() {
+            assert!((length(&positions, c) - c.length as f64).abs() < 1e-5);
+        }
+        // Constraint forces are internal: the centre of mass does not move
+        let after = momentum(&positions);
+        for d in 0..3 {
+            assert!((after[d] - before[d]).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_rattle_removes_bond_velocity() {
+        let constraints = water_like();
+        let positions = buffer(&[
+            ([0.0, 0.0, 0.0], 16.0),
+            ([0.9572, 0.0, 0.0], 1.008),
+            ([-0.24, 0.9266, 0.0], 1.008),
+        ]);
+        let mut velocities = buffer(&[
+            ([0.1, -0.2, 0.0], 0.0),
+            ([1.5, 0.4, 0.0], 0.0),
+            ([0.3, -2.0, 0.5], 0.0),
+        ]);
+        constraints.rattle(&positions, &mut velocities).unwrap();
+        for c in constraints.constraints() {
+            let r = sub(load(&positions, c.i), load(&positions, c.j));
+            let v = sub(load(&velocities, c.i), load(&velocities, c.j));
+            assert!(dot(r, v).abs() < 1e-5);
+        }
+        // Perpendicular motion is untouched
+        assert!((velocities[10] - 0.5).abs() < 1e-6);
+    }
+
+    fn water_topology() -> Topology {
+        use prism_io::sovereign_types::Atom;
+        let atom = |x: [f32; 3], element: u8, residue_id: u16| Atom {
+            coords: x,
+            element,
+            residue_id,
+            atom_type: 1,
+            charge: 0.0,
+            radius: 1.5,
+            _reserved: [0; 4],
+        };
+        Topology {
+            atoms: vec![
+                atom([5.0, 0.0, 0.0], 7, 0),
+                atom([6.0, 0.0, 0.0], 1, 0),
+                atom([0.0, 0.0, 0.0], 8, 1),
+                atom([0.9572, 0.0, 0.0], 1, 1),
+                atom([-0.24, 0.9266, 0.0], 1, 1),
+            ],
+            masses: vec![14.007, 1.008, 15.999, 1.008, 1.008],
+            residue_names: vec!["ALA".into(), "HOH".into()],
+            bonds: vec![
+                prism_io::topology::HarmonicBond {
+                    i: 0,
+                    j: 1,
+                    k: 434.0,
+                    r0: 1.01,
+                },
+                prism_io::topology::HarmonicBond {
+                    i: 2,
+                    j: 3,
+                    k: 553.0,
+                    r0: 0.9572,
+                },
+                prism_io::topology::HarmonicBond {
+                    i: 2,
+                    j: 4,
+                    k: 553.0,
+                    r0: 0.9572,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_detects_waters_for_settle() {
+        let constraints =
+            Constraints::from_topology(&ConstraintConfig::default(), &water_topology());
+        assert_eq!(constraints.constraints().len(), 1);
+        assert_eq!(constraints.waters().len(), 1);
+        let water = constraints.waters()[0];
+        assert_eq!((water.oxygen, water.hydrogens), (2, [3, 4]));
+        assert!((water.hh - 1.5139).abs() < 1e-3);
+        assert_eq!(constraints.len(), 4);
+    }
+
+    #[test]
+    fn test_settle_restores_rigid_water() {
+        let water = SettleWater {
+            oxygen: 0,
+            hydrogens: [1, 2],
+            oh: 0.9572,
+            hh: 1.5139,
+        };
+        let constraints =
+            Constraints::new(&ConstraintConfig::default(), Vec::new()).with_waters(vec![water]);
+        let reference = buffer(&[
+            ([0.0, 0.0, 0.0], 15.999),
+            ([0.9572, 0.0, 0.0], 1.008),
+            ([-0.2400, 0.9266, 0.0], 1.008),
+        ]);
+        let mut positions = reference.clone();
+        for (k, shift) in [
+            (0, 0.01),
+            (4, 0.06),
+            (6, -0.04),
+            (9, -0.05),
+            (10, 0.03),
+            (2, 0.02),
+        ] {
+            positions[k] += shift;
+        }
+        let com = |p: &[f32]| -> Vec3 {
+            let m: f64 = p.chunks_exact(4).map(|a| a[3] as f64).sum();
+            std::array::from_fn(|d| {
+                p.chunks_exact(4)
+                    .map(|a| a[3] as f64 * a[d] as f64)
+                    .sum::<f64>()
+                    / m
+            })
+        };
+        let before = com(&positions);
+        constraints.settle(&reference, &mut positions).unwrap();
+
+        let dist = |a: u32, b: u32| {
+            let d = sub(load(&positions, a), load(&positions, b));
+            dot(d, d).sqrt()
+        };
+        assert!((dist(0, 1) - 0.9572).abs() < 1e-4);
+        assert!((dist(0, 2) - 0.9572).abs() < 1e-4);
+        assert!((dist(1, 2) - 1.5139).abs() < 1e-4);
+        let after = com(&positions);
+        for d in 0..3 {
+            assert!((after[d] - before[d]).abs() < 1e-5);
+        }
+
+        // A rigid translation is left alone
+        let mut shifted = reference.clone();
+        for a in shifted.chunks_exact_mut(4) {
+            a[1] += 0.1;
+        }
+        let expected = shifted.clone();
+        constraints.settle(&reference, &mut shifted).unwrap();
+        for (a, b) in shifted.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-4);
+        }
+
+        let mut velocities = buffer(&[
+            ([0.2, -0.1, 0.3], 0.0),
+            ([1.0, 0.5, -0.7], 0.0),
+            ([-0.8, 1.1, 0.4], 0.0),
+        ]);
+        constraints
+            .settle_velocities(&positions, &mut velocities)
+            .unwrap();
+        for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+            let r = sub(load(&positions, a), load(&positions, b));
+            let v = sub(load(&velocities, a), load(&velocities, b));
+            assert!(dot(r, v).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_langevin_keeps_hbonds_rigid() {
+        use crate::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsEngine};
+        use prism_io::sovereign_types::Atom;
+        use prism_io::topology::HarmonicBond;
+
+        let atom = |x: f32, element: u8| Atom {
+            coords: [x, 0.0, 0.0],
+            element,
+            residue_id: 0,
+            atom_type: 1,
+            charge: 0.0,
+            radius: 1.5,
+            _reserved: [0; 4],
+        };
+        let topology = Topology {
+            atoms: vec![atom(0.0, 6), atom(1.2, 1), atom(-1.53, 6)],
+            masses: vec![12.011, 1.008, 12.011],
+            bonds: vec![
+                HarmonicBond {
+                    i: 0,
+                    j: 1,
+                    k: 340.0,
+                    r0: 1.09,
+                },
+                HarmonicBond {
+                    i: 0,
+                    j: 2,
+                    k: 310.0,
+                    r0: 1.526,
+                },
+            ],
+            exclusions: vec![(0, 1), (0, 2)],
+            ..Default::default()
+        };
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            dt: 0.002,
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &topology).unwrap();
+        engine.run_nlnm_breathing(50).unwrap();
+
+        let atoms = engine.get_current_atoms().unwrap();
+        let d: f32 = (0..3)
+            .map(|k| (atoms[1].coords[k] - atoms[0].coords[k]).powi(2))
+            .sum();
+        assert!((d.sqrt() - 1.09).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,532 @@
+//! # Elastic Network Models - ANM/GNM Mode Analysis
+//! Coarse-grained normal modes from CA atoms joined by identical springs
+//! within a cutoff (Atilgan et al. 2001; Bahar et al. 1997):
+//!
+//! - ANM: `3N x 3N` Hessian with super-elements `-γ r_ij r_ijᵀ / |r_ij|²`,
+//!   giving directional modes (six rigid-body zero modes dropped)
+//! - GNM: `N x N` Kirchhoff matrix, giving isotropic fluctuation modes (one
+//!   zero mode dropped)
+//!
+//! A dense eigensolve costs `O(N³)` in the CA count, a second or so for a
+//! few hundred residues, so this is a quick pre-screen of the collective
+//! motions before a full NLNM run. The overlap of the run's fitted CA
+//! displacement with each ANM mode shows which modes the dynamics explored.
+//! Eigenvalues are in units of `γ` (kcal/mol/Å²).
+//!
+//! Per-residue mean-square fluctuations `<ΔR_i²> = kT Σ_k |v_k,i|² / λ_k`
+//! (times 3 for GNM) over the kept modes convert to B-factors as
+//! `8π²/3 <ΔR²>` for comparison with crystallographic data.
+
+use crate::analysis::superpose;
+use nalgebra::{DMatrix, SymmetricEigen};
+use prism_core::PrismError;
+use prism_io::pdb::PdbStructure;
+use prism_io::sovereign_types::Atom;
+use prism_io::topology::Topology;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NetworkModel {
+    /// Anisotropic network model
+    #[default]
+    Anisotropic,
+    /// Gaussian network model
+    Gaussian,
+}
+
+impl NetworkModel {
+    fn dimensions(self) -> usize {
+        match self {
+            NetworkModel::Anisotropic => 3,
+            NetworkModel::Gaussian => 1,
+        }
+    }
+
+    fn zero_modes(self) -> usize {
+        match self {
+            NetworkModel::Anisotropic => 6,
+            NetworkModel::Gaussian => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ElasticNetworkConfig {
+    pub model: NetworkModel,
+    /// Spring cutoff between CA atoms (Å); ~15 for ANM, ~7.3 for GNM
+    pub cutoff: f64,
+    /// Spring constant γ (kcal/mol/Å²)
+    pub spring_constant: f64,
+    /// Lowest non-trivial modes kept
+    pub num_modes: usize,
+    /// PDB written after each breathing run with the per-residue
+    /// fluctuations as B-factors
+    pub b_factor_output: Option<PathBuf>,
+}
+
+impl Default for ElasticNetworkConfig {
+    fn default() -> Self {
+        Self {
+            model: NetworkModel::Anisotropic,
+            cutoff: 15.0,
+            spring_constant: 1.0,
+            num_modes: 20,
+            b_factor_output: None,
+        }
+    }
+}
+
+/// One normal mode: eigenvalue and unit eigenvector, flat `[x0, y0, z0, ...]`
+/// for ANM or one component per node for GNM
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormalMode {
+    pub eigenvalue: f64,
+    pub vector: Vec<f64>,
+}
+
+/// Modes together with the node labels that map them back onto atoms; the
+/// exchange format for elastic network and principal component modes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeSet {
+    /// System atom index of each node
+    pub atoms: Vec<u32>,
+    /// Residue of each node
+    pub residues: Vec<u16>,
+    /// Node coordinates the modes are expanded about (Å)
+    pub nodes: Vec<[f64; 3]>,
+    pub modes: Vec<NormalMode>,
+}
+
+impl ModeSet {
+    pub fn write_json(&self, path: &Path) -> Result<(), PrismError> {
+        let file = std::fs::File::create(path).map_err(|e| {
+            PrismError::Internal(format!("Cannot create {}: {}", path.display(), e))
+        })?;
+        serde_json::to_writer(std::io::BufWriter::new(file), self).map_err(|e| {
+            PrismError::Internal(format!("Mode write to {} failed: {}", path.display(), e))
+        })
+    }
+
+    pub fn read_json(path: &Path) -> Result<Self, PrismError> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| PrismError::Internal(format!("Cannot open {}: {}", path.display(), e)))?;
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| {
+            PrismError::validation(format!("Invalid mode file {}: {}", path.display(), e))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ElasticNetwork {
+    config: ElasticNetworkConfig,
+    /// System atom index of each node
+    atoms: Vec<u32>,
+    /// Residue of each node
+    residues: Vec<u16>,
+    nodes: Vec<[f64; 3]>,
+    modes: Vec<NormalMode>,
+}
+
+impl ElasticNetwork {
+    /// Network on the CA atoms of `topology`
+    pub fn from_topology(
+        topology: &Topology,
+        config: ElasticNetworkConfig,
+    ) -> Result<Self, PrismError> {
+        let atoms: Vec<u32> = topology
+            .atom_names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| name.trim() == "CA")
+            .map(|(i, _)| i as u32)
+            .collect();
+        if atoms.is_empty() {
+            return Err(PrismError::validation(
+                "Elastic network needs CA atoms in the topology",
+            ));
+        }
+        let residues = atoms
+            .iter()
+            .map(|&i| topology.atoms[i as usize].residue_id)
+            .collect();
+        let nodes = atoms
+            .iter()
+            .map(|&i| topology.atoms[i as usize].coords.map(|c| c as f64))
+            .collect();
+        Self::from_nodes(atoms, residues, nodes, config)
+    }
+
+    /// Network on explicit node coordinates; `atoms` and `residues` label
+    /// each node
+    pub fn from_nodes(
+        atoms: Vec<u32>,
+        residues: Vec<u16>,
+        nodes: Vec<[f64; 3]>,
+        config: ElasticNetworkConfig,
+    ) -> Result<Self, PrismError> {
+        if config.cutoff <= 0.0 || config.spring_constant <= 0.0 {
+            return Err(PrismError::config(
+                "Elastic network cutoff and spring constant must be positive",
+            ));
+        }
+        let zero_modes = config.model.zero_modes();
+        if nodes.len() * config.model.dimensions() <= zero_modes {
+            return Err(PrismError::validation(format!(
+                "Elastic network needs more than {} nodes, got {}",
+                zero_modes / config.model.dimensions(),
+                nodes.len()
+            )));
+        }
+        let mut network = Self {
+            config,
+            atoms,
+            residues,
+            nodes,
+            modes: Vec::new(),
+        };
+        network.modes = network.solve();
+        Ok(network)
+    }
+
+    pub fn config(&self) -> &ElasticNetworkConfig {
+        &self.config
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn atoms(&self) -> &[u32] {
+        &self.atoms
+    }
+
+    pub fn residues(&self) -> &[u16] {
+        &self.residues
+    }
+
+    pub fn nodes(&self) -> &[[f64; 3]] {
+        &self.nodes
+    }
+
+    /// Non-trivial modes in increasing eigenvalue order
+    pub fn modes(&self) -> &[NormalMode] {
+        &self.modes
+    }
+
+    /// Modes with node labels, for export and animation
+    pub fn mode_set(&self) -> ModeSet {
+        ModeSet {
+            atoms: self.atoms.clone(),
+            residues: self.residues.clone(),
+            nodes: self.nodes.clone(),
+            modes: self.modes.clone(),
+        }
+    }
+
+    /// Node pairs within the cutoff
+    pub fn contacts(&self) -> Vec<(usize, usize)> {
+        let cutoff2 = self.config.cutoff * self.config.cutoff;
+        let n = self.nodes.len();
+        (0..n)
+            .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+            .filter(|&(i, j)| distance2(&self.nodes[i], &self.nodes[j]) <= cutoff2)
+            .collect()
+    }
+
+    /// ANM Hessian or GNM Kirchhoff matrix
+    pub fn matrix(&self) -> DMatrix<f64> {
+        let d = self.config.model.dimensions();
+        let gamma = self.config.spring_constant;
+        let mut m = DMatrix::zeros(self.nodes.len() * d, self.nodes.len() * d);
+        for (i, j) in self.contacts() {
+            match self.config.model {
+                NetworkModel::Gaussian => {
+                    m[(i, j)] -= gamma;
+                    m[(j, i)] -= gamma;
+                    m[(i, i)] += gamma;
+                    m[(j, j)] += gamma;
+                }
+                NetworkModel::Anisotropic => {
+                    let r: Vec<f64> = (0..3)
+                        .map(|a| self.nodes[j][a] - self.nodes[i][a])
+                        .collect();
+                    let r2 = distance2(&self.nodes[i], &self.nodes[j]).max(1e-12);
+                    for a in 0..3 {
+                        for b in 0..3 {
+                            let k = gamma * r[a] * r[b] / r2;
+                            m[(3 * i + a, 3 * j + b)] -= k;
+                            m[(3 * j + a, 3 * i + b)] -= k;
+                            m[(3 * i + a, 3 * i + b)] += k;
+                            m[(3 * j + a, 3 * j + b)] += k;
+                        }
+                    }
+                }
+            }
+        }
+        m
+    }
+
+    fn solve(&self) -> Vec<NormalMode> {
+        let eigen = SymmetricEigen::new(self.matrix());
+        let mut order: Vec<usize> = (0..eigen.eigenvalues.len()).collect();
+        order.sort_by(|&a, &b| eigen.eigenvalues[a].total_cmp(&eigen.eigenvalues[b]));
+        order
+            .into_iter()
+            .skip(self.config.model.zero_modes())
+            .take(self.config.num_modes)
+            .map(|k| NormalMode {
+                eigenvalue: eigen.eigenvalues[k],
+                vector: eigen.eigenvectors.column(k).iter().copied().collect(),
+            })
+            .collect()
+    }
+
+    /// Squared amplitude of each mode on each node, `[mode][node]`; every
+    /// row sums to one
+    pub fn participation(&self) -> Vec<Vec<f64>> {
+        let d = self.config.model.dimensions();
+        self.modes
+            .iter()
+            .map(|m| {
+                m.vector
+                    .chunks_exact(d)
+                    .map(|v| v.iter().map(|x| x * x).sum())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Collectivity of each mode (Brüschweiler 1995), `exp(-Σ p ln p) / N`:
+    /// near 1 when all nodes move, `1/N` for a single node
+    pub fn collectivity(&self) -> Vec<f64> {
+        let n = self.nodes.len() as f64;
+        self.participation()
+            .iter()
+            .map(|p| {
+                let entropy: f64 = p.iter().filter(|&&p| p > 0.0).map(|p| -p * p.ln()).sum();
+                entropy.exp() / n
+            })
+            .collect()
+    }
+
+    /// Mean-square fluctuation of each node at `kt` (Å²) from the kept modes
+    pub fn fluctuations(&self, kt: f64) -> Vec<f64> {
+        let scale = match self.config.model {
+            NetworkModel::Anisotropic => kt,
+            NetworkModel::Gaussian => 3.0 * kt,
+        };
+        let mut msf = vec![0.0; self.nodes.len()];
+        for (mode, p) in self.modes.iter().zip(self.participation()) {
+            for (f, p) in msf.iter_mut().zip(p) {
+                *f += scale * p / mode.eigenvalue;
+            }
+        }
+        msf
+    }
+
+    /// `atoms` as a PDB structure whose B-factors are `8π²/3 <ΔR²>` of
+    /// their residue's node (0 for residues without one)
+    pub fn b_factor_structure(&self, atoms: &[Atom], kt: f64) -> PdbStructure {
+        let b: HashMap<u16, f64> = self
+            .residues
+            .iter()
+            .zip(self.fluctuations(kt))
+            .map(|(&r, msf)| (r, 8.0 * std::f64::consts::PI.powi(2) / 3.0 * msf))
+            .collect();
+        let mut structure = PdbStructure::from_atoms(atoms);
+        for (record, atom) in structure.records.iter_mut().zip(atoms) {
+            record.b_factor = b.get(&atom.residue_id).copied().unwrap_or(0.0) as f32;
+        }
+        structure
+    }
+
+    /// Node coordinates from a Float4-stride system buffer
+    pub fn node_positions(&self, positions: &[f32]) -> Vec<[f64; 3]> {
+        self.atoms
+            .iter()
+            .map(|&i| {
+                let o = i as usize * 4;
+                [
+                    positions[o] as f64,
+                    positions[o + 1] as f64,
+                    positions[o + 2] as f64,
+                ]
+            })
+            .collect()
+    }
+
+    /// Displacement of `current` node coordinates from `reference` after a
+    /// least-squares superposition, flat `[dx0, dy0, dz0, ...]`
+    pub fn fitted_displacement(reference: &[[f64; 3]], current: &[[f64; 3]]) -> Vec<f64> {
+        superpose(reference, current)
+            .iter()
+            .zip(reference)
+            .flat_map(|(c, r)| [c[0] - r[0], c[1] - r[1], c[2] - r[2]])
+            .collect()
+    }
+
+    /// `|d · v_k| / |d|` for each ANM mode; empty for GNM or a null
+    /// displacement
+    pub fn overlaps(&self, displacement: &[f64]) -> Vec<f64> {
+        let norm = displacement.iter().map(|d| d * d).sum::<f64>().sqrt();
+        if self.config.model != NetworkModel::Anisotropic
+            || norm < 1e-12
+            || displacement.len() != self.nodes.len() * 3
+        {
+            return Vec::new();
+        }
+        self.modes
+            .iter()
+            .map(|m| {
+                m.vector
+                    .iter()
+                    .zip(displacement)
+                    .map(|(v, d)| v * d)
+                    .sum::<f64>()
+                    .abs()
+                    / norm
+            })
+            .collect()
+    }
+}
+
+/// Running `sqrt(Σ_{j≤k} O_j²)` of per-mode overlaps
+pub fn cumulative_overlap(overlaps: &[f64]) -> Vec<f64> {
+    overlaps
+        .iter()
+        .scan(0.0, |sum, o| {
+            *sum += o * o;
+            Some(sum.sqrt())
+        })
+        .collect()
+}
+
+fn distance2(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).map(|k| (a[k] - b[k]) * (a[k] - b[k])).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Alpha-helix-like CA trace of `n` nodes
+    fn helix(n: usize) -> Vec<[f64; 3]> {
+        (0..n)
+            .map(|i| {
+                let angle = (100.0 * i as f64).to_radians();
+                [2.3 * angle.cos(), 2.3 * angle.sin(), 1.5 * i as f64]
+            })
+            .collect()
+    }
+
+    fn network(model: NetworkModel, n: usize) -> ElasticNetwork {
+        let config = ElasticNetworkConfig {
+            model,
+            cutoff: 10.0,
+            num_modes: 5,
+            ..Default::default()
+        };
+        ElasticNetwork::from_nodes(
+            (0..n as u32).collect(),
+            (0..n as u16).collect(),
+            helix(n),
+            config,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_anm_drops_rigid_body_modes() {
+        let anm = network(NetworkModel::Anisotropic, 12);
+        let eigen = SymmetricEigen::new(anm.matrix());
+        let zero = eigen.eigenvalues.iter().filter(|e| e.abs() < 1e-8).count();
+        assert_eq!(zero, 6);
+        assert_eq!(anm.modes().len(), 5);
+        assert!(anm.modes()[0].eigenvalue > 1e-8);
+        assert!(anm
+            .modes()
+            .windows(2)
+            .all(|w| w[0].eigenvalue <= w[1].eigenvalue));
+
+        let gnm = network(NetworkModel::Gaussian, 12);
+        assert_eq!(gnm.modes()[0].vector.len(), 12);
+        assert!(gnm.modes()[0].eigenvalue > 1e-8);
+    }
+
+    #[test]
+    fn test_fluctuations_and_participation() {
+        let anm = network(NetworkModel::Anisotropic, 12);
+        let participation = anm.participation();
+        assert!(participation
+            .iter()
+            .all(|p| (p.iter().sum::<f64>() - 1.0).abs() < 1e-9));
+        assert!(anm
+            .collectivity()
+            .iter()
+            .all(|&c| c > 1.0 / 12.0 && c <= 1.0));
+
+        // Fluctuations scale with kT and peak at the free chain ends
+        let msf = anm.fluctuations(0.6);
+        let doubled = anm.fluctuations(1.2);
+        assert!((doubled[3] - 2.0 * msf[3]).abs() < 1e-9);
+        assert!(msf[0] > msf[6] && msf[11] > msf[6]);
+
+        let gnm = network(NetworkModel::Gaussian, 12);
+        assert!(gnm.fluctuations(0.6).iter().all(|&f| f > 0.0));
+
+        let atoms: Vec<Atom> = anm
+            .nodes()
+            .iter()
+            .enumerate()
+            .map(|(i, r)| Atom {
+                coords: r.map(|c| c as f32),
+                element: 6,
+                residue_id: i as u16,
+                atom_type: 0,
+                charge: 0.0,
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        let structure = anm.b_factor_structure(&atoms, 0.6);
+        let expected = 8.0 * std::f64::consts::PI.powi(2) / 3.0 * msf[5];
+        assert!((structure.records[5].b_factor as f64 - expected).abs() < 1e-4 * expected);
+    }
+
+    #[test]
+    fn test_displacement_along_mode_has_unit_overlap() {
+        let anm = network(NetworkModel::Anisotropic, 12);
+        let mode = &anm.modes()[2].vector;
+        let displaced: Vec<[f64; 3]> = anm
+            .nodes()
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                [
+                    r[0] + 0.5 * mode[3 * i],
+                    r[1] + 0.5 * mode[3 * i + 1],
+                    r[2] + 0.5 * mode[3 * i + 2],
+                ]
+            })
+            .collect();
+        let d = ElasticNetwork::fitted_displacement(anm.nodes(), &displaced);
+        let overlaps = anm.overlaps(&d);
+        assert!((overlaps[2] - 1.0).abs() < 1e-3, "{:?}", overlaps);
+        assert!(overlaps[0] < 1e-2);
+        let cumulative = cumulative_overlap(&overlaps);
+        assert!((cumulative[4] - 1.0).abs() < 1e-3);
+
+        // A rigid rotation is fitted away
+        let rotated: Vec<[f64; 3]> = anm
+            .nodes()
+            .iter()
+            .map(|r| [-r[1], r[0], r[2] + 4.0])
+            .collect();
+        let d = ElasticNetwork::fitted_displacement(anm.nodes(), &rotated);
+        assert!(d.iter().all(|d| d.abs() < 1e-9));
+        assert!(anm.overlaps(&d).is_empty());
+    }
+}
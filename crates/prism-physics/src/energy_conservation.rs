@@ -0,0 +1,571 @@
+//! # Energy Conservation - NVE Validation of Host Dynamics
+//! Runs short constant-energy trajectories of built-in reference systems and
+//! checks that their total energy does not drift, a quick way to verify that
+//! a build (and the CPU kernels it selected) produces correct dynamics:
+//!
+//! ```no_run
+//! use prism_physics::energy_conservation::{validate_all, ConservationConfig};
+//! for report in validate_all(&ConservationConfig::default())? {
+//!     assert!(report.passed, "{}", report);
+//! }
+//! # Ok::<(), prism_core::PrismError>(())
+//! ```
+//!
+//! The drift is the slope of a least-squares line through the total energy
+//! samples, per atom and ns of simulated time; the fluctuation is the RMS
+//! deviation of the samples from that line, per atom.
+//!
+//! Reference systems:
+//! - [`ReferenceSystem::ArgonLattice`]: 256 Lennard-Jones argon atoms on a
+//!   periodic FCC lattice, switched cutoff, 80 K
+//! - [`ReferenceSystem::AlanineDipeptide`]: ACE-ALA-NME in vacuum with AMBER
+//!   parm99 charges, bonds, angles and impropers and generic backbone
+//!   torsions, 300 K
+
+use crate::force_field::ForceFieldConfig;
+use crate::molecular_dynamics::{MolecularDynamicsConfigBuilder, MolecularDynamicsEngine};
+use crate::units::{self, Length, Temperature, Time};
+use prism_core::PrismError;
+use prism_io::simulation_box::SimulationBox;
+use prism_io::sovereign_types::Atom;
+use prism_io::topology::{
+    HarmonicAngle, HarmonicBond, LjParams, Pair14, PeriodicDihedral, Topology,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Built-in system of the NVE validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceSystem {
+    ArgonLattice,
+    AlanineDipeptide,
+}
+
+impl ReferenceSystem {
+    pub const ALL: [ReferenceSystem; 2] = [Self::ArgonLattice, Self::AlanineDipeptide];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ArgonLattice => "argon_lattice",
+            Self::AlanineDipeptide => "alanine_dipeptide",
+        }
+    }
+
+    /// Temperature of the initial Maxwell-Boltzmann velocities
+    pub fn temperature(self) -> Temperature {
+        match self {
+            Self::ArgonLattice => Temperature::kelvin(80.0),
+            Self::AlanineDipeptide => Temperature::kelvin(300.0),
+        }
+    }
+
+    pub fn topology(self) -> Topology {
+        match self {
+            Self::ArgonLattice => argon_lattice(),
+            Self::AlanineDipeptide => alanine_dipeptide(),
+        }
+    }
+
+    /// Nonbonded settings of the system; the switching function keeps the
+    /// energy continuous at the cutoff
+    pub fn force_field(self) -> ForceFieldConfig {
+        match self {
+            Self::ArgonLattice => ForceFieldConfig {
+                cutoff: 9.0,
+                switch_distance: Some(8.0),
+                ..Default::default()
+            },
+            Self::AlanineDipeptide => ForceFieldConfig::default(),
+        }
+    }
+}
+
+impl fmt::Display for ReferenceSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Settings of the NVE validation runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConservationConfig {
+    /// Steps of each trajectory
+    pub steps: u64,
+    /// Time step (ps); config files may also give e.g. `"1 fs"`
+    #[serde(with = "units::as_ps")]
+    pub dt: f32,
+    /// Steps between total energy samples
+    pub sample_interval: u64,
+    /// Largest accepted drift (kcal/mol per atom per ns)
+    pub max_drift: f64,
+    /// Seed of the initial velocities
+    pub seed: u64,
+}
+
+impl Default for ConservationConfig {
+    fn default() -> Self {
+        Self {
+            steps: 10_000,
+            dt: 0.001,
+            sample_interval: 20,
+            max_drift: 0.05,
+            seed: crate::rng::DEFAULT_SEED,
+        }
+    }
+}
+
+impl ConservationConfig {
+    pub fn validate(&self) -> Result<(), PrismError> {
+        if self.sample_interval == 0 || self.steps < 2 * self.sample_interval {
+            return Err(PrismError::validation(format!(
+                "NVE validation needs at least two samples, got {} steps at a sample interval of {}",
+                self.steps, self.sample_interval
+            )));
+        }
+        if self.max_drift.is_nan() || self.max_drift <= 0.0 {
+            return Err(PrismError::validation(format!(
+                "max_drift must be positive, got {}",
+                self.max_drift
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one NVE validation run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConservationReport {
+    pub system: ReferenceSystem,
+    pub atoms: usize,
+    pub steps: u64,
+    /// Time step (ps)
+    pub dt: f32,
+    /// Total energy of the first sample (kcal/mol)
+    pub initial_energy: f64,
+    /// Total energy drift (kcal/mol per atom per ns)
+    pub drift: f64,
+    /// RMS deviation of the total energy from its trend (kcal/mol per atom)
+    pub fluctuation: f64,
+    pub max_drift: f64,
+    pub passed: bool,
+}
+
+impl fmt::Display for ConservationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {} atoms, {} steps of {} ps, drift {:.3e} kcal/mol/atom/ns (limit {}), fluctuation {:.3e} kcal/mol/atom",
+            if self.passed { "✅" } else { "❌" },
+            self.system,
+            self.atoms,
+            self.steps,
+            self.dt,
+            self.drift,
+            self.max_drift,
+            self.fluctuation
+        )
+    }
+}
+
+/// Run `system` without thermostat and measure the drift of its total energy
+pub fn validate(
+    system: ReferenceSystem,
+    config: &ConservationConfig,
+) -> Result<ConservationReport, PrismError> {
+    config.validate()?;
+    let force_field = system.force_field();
+    let md_config = MolecularDynamicsConfigBuilder::nve_validation()
+        .timestep(Time::ps(config.dt as f64))
+        .temperature(system.temperature())
+        .cutoff(Length::angstrom(force_field.cutoff as f64))
+        .force_field(force_field)
+        .seed(config.seed)
+        .build()?;
+    let kt = md_config.temp_start;
+    let topology = system.topology();
+    let mut engine = MolecularDynamicsEngine::from_topology(md_config, &topology)?;
+    engine.assign_velocities(kt);
+
+    // The integrator is a leapfrog whose velocities lag the positions by half
+    // a step, so each sample averages the velocities on both sides of it
+    let mut samples = Vec::new();
+    for _ in 0..config.steps / config.sample_interval {
+        engine.run_nlnm_breathing(config.sample_interval - 1)?;
+        let time_ns = engine.get_statistics().current_step as f64 * config.dt as f64 * 1e-3;
+        let potential = engine.potential_energy();
+        let before = engine.velocities().to_vec();
+        engine.run_nlnm_breathing(1)?;
+        let kinetic = centred_kinetic_energy(&topology.masses, &before, engine.velocities());
+        samples.push((time_ns, potential + kinetic));
+    }
+    let atoms = topology.num_atoms();
+    let (slope, rms) = linear_fit(&samples);
+    let drift = slope / atoms as f64;
+    let report = ConservationReport {
+        system,
+        atoms,
+        steps: config.steps,
+        dt: config.dt,
+        initial_energy: samples[0].1,
+        drift,
+        fluctuation: rms / atoms as f64,
+        max_drift: config.max_drift,
+        passed: drift.abs() <= config.max_drift,
+    };
+    log::info!("{}", report);
+    Ok(report)
+}
+
+/// [`validate`] every [`ReferenceSystem`]
+pub fn validate_all(config: &ConservationConfig) -> Result<Vec<ConservationReport>, PrismError> {
+    ReferenceSystem::ALL
+        .iter()
+        .map(|&system| validate(system, config))
+        .collect()
+}
+
+/// `Σ ½ m v²` of the mean of two Float4-stride velocity sets
+fn centred_kinetic_energy(masses: &[f32], before: &[f32], after: &[f32]) -> f64 {
+    masses
+        .iter()
+        .zip(before.chunks_exact(4).zip(after.chunks_exact(4)))
+        .map(|(&m, (a, b))| {
+            let v2: f64 = (0..3).map(|d| (0.5 * (a[d] + b[d]) as f64).powi(2)).sum();
+            0.5 * m as f64 * v2
+        })
+        .sum()
+}
+
+/// Slope of the least-squares line through `(x, y)` samples and the RMS
+/// residual around it; NaN samples propagate to both
+fn linear_fit(samples: &[(f64, f64)]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean_x = samples.iter().map(|s| s.0).sum::<f64>() / n;
+    let mean_y = samples.iter().map(|s| s.1).sum::<f64>() / n;
+    let sxx: f64 = samples.iter().map(|s| (s.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = samples
+        .iter()
+        .map(|s| (s.0 - mean_x) * (s.1 - mean_y))
+        .sum();
+    let slope = sxy / sxx;
+    let residual: f64 = samples
+        .iter()
+        .map(|s| (s.1 - mean_y - slope * (s.0 - mean_x)).powi(2))
+        .sum();
+    (slope, (residual / n).sqrt())
+}
+
+/// FCC unit cells per box edge and lattice constant (Å) of the argon lattice
+const ARGON_CELLS: usize = 4;
+const ARGON_LATTICE_CONSTANT: f32 = 5.27;
+
+fn argon_lattice() -> Topology {
+    let basis = [
+        [0.0, 0.0, 0.0],
+        [0.5, 0.5, 0.0],
+        [0.5, 0.0, 0.5],
+        [0.0, 0.5, 0.5],
+    ];
+    let mut atoms = Vec::new();
+    for x in 0..ARGON_CELLS {
+        for y in 0..ARGON_CELLS {
+            for z in 0..ARGON_CELLS {
+                for b in &basis {
+                    let cell = [x as f32, y as f32, z as f32];
+                    atoms.push(Atom {
+                        coords: [0, 1, 2].map(|d| (cell[d] + b[d]) * ARGON_LATTICE_CONSTANT),
+                        element: 18,
+                        residue_id: atoms.len() as u16,
+                        atom_type: 0,
+                        charge: 0.0,
+                        radius: 1.88,
+                        _reserved: [0; 4],
+                    });
+                }
+            }
+        }
+    }
+    let n = atoms.len();
+    let edge = ARGON_CELLS as f32 * ARGON_LATTICE_CONSTANT;
+    Topology {
+        atoms,
+        atom_names: vec!["AR".to_string(); n],
+        atom_types: vec!["Ar".to_string(); n],
+        masses: vec![39.948; n],
+        lj: vec![
+            LjParams {
+                sigma: 3.405,
+                epsilon: 0.238
+            };
+            n
+        ],
+        residue_names: vec!["AR".to_string(); n],
+        simulation_box: Some(SimulationBox::orthorhombic([edge; 3])),
+        ..Default::default()
+    }
+}
+
+/// ACE-ALA-NME: name, AMBER type, residue, charge (e) and coordinates (Å)
+#[rustfmt::skip]
+#[allow(clippy::approx_constant)] // HB3 sits at y = 3.141 Å
+const DIPEPTIDE_ATOMS: [(&str, &str, u16, f32, [f32; 3]); 22] = [
+    ("HH31", "HC", 0, 0.1123, [2.000, 1.000, 0.000]),
+    ("CH3", "CT", 0, -0.3662, [2.000, 2.090, 0.000]),
+    ("HH32", "HC", 0, 0.1123, [1.486, 2.454, 0.890]),
+    ("HH33", "HC", 0, 0.1123, [1.486, 2.454, -0.890]),
+    ("C", "C", 0, 0.5972, [3.427, 2.641, 0.000]),
+    ("O", "O", 0, -0.5679, [4.391, 1.877, 0.000]),
+    ("N", "N", 1, -0.4157, [3.555, 3.970, 0.000]),
+    ("H", "H", 1, 0.2719, [2.733, 4.556, 0.000]),
+    ("CA", "CT", 1, 0.0337, [4.853, 4.614, 0.000]),
+    ("HA", "H1", 1, 0.0823, [5.408, 4.316, 0.890]),
+    ("CB", "CT", 1, -0.1825, [5.661, 4.221, -1.232]),
+    ("HB1", "HC", 1, 0.0603, [5.123, 4.521, -2.131]),
+    ("HB2", "HC", 1, 0.0603, [6.630, 4.719, -1.206]),
+    ("HB3", "HC", 1, 0.0603, [5.809, 3.141, -1.241]),
+    ("C", "C", 1, 0.5973, [4.713, 6.129, 0.000]),
+    ("O", "O", 1, -0.5679, [3.601, 6.653, 0.000]),
+    ("N", "N", 2, -0.4157, [5.846, 6.835, 0.000]),
+    ("H", "H", 2, 0.2719, [6.737, 6.359, 0.000]),
+    ("CH3", "CT", 2, -0.1490, [5.846, 8.284, 0.000]),
+    ("HH31", "H1", 2, 0.0976, [4.819, 8.648, 0.000]),
+    ("HH32", "H1", 2, 0.0976, [6.360, 8.648, 0.890]),
+    ("HH33", "H1", 2, 0.0976, [6.360, 8.648, -0.890]),
+];
+
+#[rustfmt::skip]
+const DIPEPTIDE_BONDS: [(u32, u32); 21] = [
+    (0, 1), (1, 2), (1, 3), (1, 4), (4, 5), (4, 6), (6, 7), (6, 8), (8, 9), (8, 10), (8, 14),
+    (10, 11), (10, 12), (10, 13), (14, 15), (14, 16), (16, 17), (16, 18), (18, 19), (18, 20), (18, 21),
+];
+
+/// Planarity impropers of the carbonyl carbons and amide nitrogens, central
+/// atom third
+const DIPEPTIDE_IMPROPERS: [([u32; 4], f32); 4] = [
+    ([1, 6, 4, 5], 10.5),
+    ([8, 16, 14, 15], 10.5),
+    ([4, 8, 6, 7], 1.1),
+    ([14, 18, 16, 17], 1.1),
+];
+
+/// parm99 element, mass (amu), Rmin/2 (Å) and well depth (kcal/mol) of an
+/// atom type
+fn amber_type(atom_type: &str) -> (u8, f32, f32, f32) {
+    match atom_type {
+        "HC" => (1, 1.008, 1.4870, 0.0157),
+        "H1" => (1, 1.008, 1.3870, 0.0157),
+        "H" => (1, 1.008, 0.6000, 0.0157),
+        "CT" => (6, 12.01, 1.9080, 0.1094),
+        "C" => (6, 12.01, 1.9080, 0.0860),
+        "N" => (7, 14.01, 1.8240, 0.1700),
+        "O" => (8, 16.00, 1.6612, 0.2100),
+        _ => unreachable!("no parameters for atom type {}", atom_type),
+    }
+}
+
+/// parm99 bond force constant (kcal/mol/Å²) and length (Å)
+fn amber_bond(a: &str, b: &str) -> (f32, f32) {
+    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+    match (a, b) {
+        ("CT", "HC") | ("CT", "H1") => (340.0, 1.090),
+        ("C", "CT") => (317.0, 1.522),
+        ("C", "O") => (570.0, 1.229),
+        ("C", "N") => (490.0, 1.335),
+        ("H", "N") => (434.0, 1.010),
+        ("CT", "N") => (337.0, 1.449),
+        ("CT", "CT") => (310.0, 1.526),
+        _ => unreachable!("no bond parameters for {}-{}", a, b),
+    }
+}
+
+/// parm99 angle force constant (kcal/mol/rad²) and equilibrium angle (°)
+fn amber_angle(a: &str, center: &str, b: &str) -> (f32, f32) {
+    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+    match (a, center, b) {
+        ("HC", "CT", "HC") | ("H1", "CT", "H1") => (35.0, 109.5),
+        ("C", "CT", "HC") | ("C", "CT", "H1") | ("CT", "CT", "HC") => (50.0, 109.5),
+        ("CT", "CT", "H1") | ("H1", "CT", "N") => (50.0, 109.5),
+        ("CT", "C", "O") => (80.0, 120.4),
+        ("CT", "C", "N") => (70.0, 116.6),
+        ("N", "C", "O") => (80.0, 122.9),
+        ("C", "N", "H") => (50.0, 120.0),
+        ("C", "N", "CT") => (50.0, 121.9),
+        ("CT", "N", "H") => (50.0, 118.04),
+        ("CT", "CT", "N") => (80.0, 109.7),
+        ("C", "CT", "N") => (63.0, 110.1),
+        ("C", "CT", "CT") => (63.0, 111.1),
+        _ => unreachable!("no angle parameters for {}-{}-{}", a, center, b),
+    }
+}
+
+/// Generic parm99 torsion (barrier per path, periodicity, phase in °) about
+/// a central bond; `None` for the torsions without a barrier
+fn amber_torsion(b: &str, c: &str) -> Option<(f32, f32, f32)> {
+    match (b.min(c), b.max(c)) {
+        ("C", "N") => Some((2.5, 2.0, 180.0)),
+        ("CT", "CT") => Some((1.4 / 9.0, 3.0, 0.0)),
+        _ => None,
+    }
+}
+
+fn alanine_dipeptide() -> Topology {
+    let types: Vec<&str> = DIPEPTIDE_ATOMS.iter().map(|a| a.1).collect();
+    let mut topology = Topology {
+        atoms: DIPEPTIDE_ATOMS
+            .iter()
+            .map(|&(_, atom_type, residue_id, charge, coords)| {
+                let (element, _, rmin_half, _) = amber_type(atom_type);
+                Atom {
+                    coords,
+                    element,
+                    residue_id,
+                    atom_type: 0,
+                    charge,
+                    radius: rmin_half,
+                    _reserved: [0; 4],
+                }
+            })
+            .collect(),
+        atom_names: DIPEPTIDE_ATOMS.iter().map(|a| a.0.to_string()).collect(),
+        atom_types: types.iter().map(|t| t.to_string()).collect(),
+        masses: types.iter().map(|t| amber_type(t).1).collect(),
+        lj: types
+            .iter()
+            .map(|t| {
+                let (_, _, rmin_half, epsilon) = amber_type(t);
+                LjParams::from_rmin_half(rmin_half, epsilon)
+            })
+            .collect(),
+        residue_names: ["ACE", "ALA", "NME"].map(str::to_string).to_vec(),
+        ..Default::default()
+    };
+
+    let mut neighbours = vec![Vec::new(); types.len()];
+    for &(i, j) in &DIPEPTIDE_BONDS {
+        let (k, r0) = amber_bond(types[i as usize], types[j as usize]);
+        topology.bonds.push(HarmonicBond { i, j, k, r0 });
+        neighbours[i as usize].push(j);
+        neighbours[j as usize].push(i);
+    }
+    for (j, around) in neighbours.iter().enumerate() {
+        for (n, &i) in around.iter().enumerate() {
+            for &k in &around[n + 1..] {
+                let (force_constant, theta0) =
+                    amber_angle(types[i as usize], types[j], types[k as usize]);
+                topology.angles.push(HarmonicAngle {
+                    i,
+                    j: j as u32,
+                    k,
+                    force_constant,
+                    theta0: theta0.to_radians(),
+                });
+            }
+        }
+    }
+    for &(j, k) in &DIPEPTIDE_BONDS {
+        let Some((barrier, periodicity, phase)) =
+            amber_torsion(types[j as usize], types[k as usize])
+        else {
+            continue;
+        };
+        for &i in neighbours[j as usize].iter().filter(|&&i| i != k) {
+            for &l in neighbours[k as usize].iter().filter(|&&l| l != j) {
+                topology.dihedrals.push(PeriodicDihedral {
+                    atoms: [i, j, k, l],
+                    k: barrier,
+                    periodicity,
+                    phase: phase.to_radians(),
+                    improper: false,
+                });
+            }
+        }
+    }
+    for (atoms, k) in DIPEPTIDE_IMPROPERS {
+        topology.dihedrals.push(PeriodicDihedral {
+            atoms,
+            k,
+            periodicity: 2.0,
+            phase: std::f32::consts::PI,
+            improper: true,
+        });
+    }
+
+    topology.generate_exclusions();
+    let within_two = topology.bonded_pairs_within(2);
+    topology.pairs14 = topology
+        .exclusions
+        .iter()
+        .filter(|pair| !within_two.contains(pair))
+        .map(|&(i, j)| Pair14 {
+            i,
+            j,
+            coulomb_scale: 1.0 / 1.2,
+            lj_scale: 0.5,
+        })
+        .collect();
+    topology
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_topologies() {
+        let argon = ReferenceSystem::ArgonLattice.topology();
+        assert_eq!(argon.num_atoms(), 256);
+        let dipeptide = ReferenceSystem::AlanineDipeptide.topology();
+        assert_eq!(dipeptide.num_atoms(), 22);
+        assert_eq!(dipeptide.angles.len(), 36);
+        let charge: f32 = dipeptide.atoms.iter().map(|a| a.charge).sum();
+        assert!(charge.abs() < 1e-4);
+        assert_eq!(dipeptide.dihedrals.iter().filter(|d| d.improper).count(), 4);
+        assert!(!dipeptide.pairs14.is_empty());
+    }
+
+    #[test]
+    fn test_linear_fit() {
+        let samples: Vec<(f64, f64)> = (0..10)
+            .map(|i| {
+                (
+                    i as f64,
+                    2.0 * i as f64 + if i % 2 == 0 { 0.5 } else { -0.5 },
+                )
+            })
+            .collect();
+        let (slope, rms) = linear_fit(&samples);
+        assert!((slope - 2.0).abs() < 0.05);
+        assert!((rms - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_reference_systems_conserve_energy() {
+        for (system, steps) in [
+            (ReferenceSystem::ArgonLattice, 500),
+            (ReferenceSystem::AlanineDipeptide, 2_000),
+        ] {
+            let config = ConservationConfig {
+                steps,
+                ..Default::default()
+            };
+            let report = validate(system, &config).unwrap();
+            assert!(report.passed, "{}", report);
+            assert!(report.fluctuation < 1e-3, "{}", report);
+        }
+    }
+
+    #[test]
+    fn test_oversized_timestep_fails() {
+        let config = ConservationConfig {
+            steps: 500,
+            dt: 0.05,
+            ..Default::default()
+        };
+        let passed = validate(ReferenceSystem::AlanineDipeptide, &config)
+            .map(|report| report.passed)
+            .unwrap_or(false);
+        assert!(!passed);
+    }
+}
@@ -0,0 +1,231 @@
+//! # Estimators - Quantum Energy Estimators for Ring Polymers
+//! Kinetic energy of quantum nuclei from a [`RingPolymer`] configuration:
+//!
+//! - primitive (thermodynamic): `K = 3NP kT/2 - U_spring / P`, exact on
+//!   average but with a variance that grows with `P`
+//! - centroid virial: `K = 3N kT/2 + 1/(2P) Σ_k (q_k - q̄)·∇V(q_k)`, same
+//!   mean with a variance roughly independent of `P`
+//!
+//! Samples are grouped into fixed-length blocks so the standard error
+//! accounts for correlation between successive sweeps. Comparing the
+//! estimates at increasing bead counts shows whether the quantum
+//! corrections have converged. Units as in [`crate::pimc`].
+
+use crate::pimc::RingPolymer;
+use serde::{Deserialize, Serialize};
+
+/// Energy estimators at one ring-polymer configuration (kcal/mol)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnergyEstimate {
+    /// Bead-averaged potential energy
+    pub potential: f64,
+    pub primitive_kinetic: f64,
+    pub virial_kinetic: f64,
+}
+
+impl EnergyEstimate {
+    /// Evaluate every bead with `potential(coordinates, gradient) -> energy`
+    pub fn measure<F>(polymer: &RingPolymer, kt: f64, potential: &mut F) -> Self
+    where
+        F: FnMut(&[f64], &mut [f64]) -> f64,
+    {
+        let mut gradients = vec![vec![0.0; polymer.num_atoms() * 3]; polymer.num_beads()];
+        let energies: Vec<f64> = polymer
+            .beads()
+            .iter()
+            .zip(&mut gradients)
+            .map(|(bead, gradient)| potential(bead, gradient))
+            .collect();
+        Self::from_gradients(polymer, kt, &energies, &gradients)
+    }
+
+    /// Estimators from bead energies and gradients already at hand (e.g.
+    /// from an RPMD step)
+    pub fn from_gradients(
+        polymer: &RingPolymer,
+        kt: f64,
+        energies: &[f64],
+        gradients: &[Vec<f64>],
+    ) -> Self {
+        let p = polymer.num_beads() as f64;
+        let dof = (polymer.num_atoms() * 3) as f64;
+        let centroid = polymer.centroid();
+        let virial: f64 = polymer
+            .beads()
+            .iter()
+            .zip(gradients)
+            .map(|(bead, gradient)| {
+                bead.iter()
+                    .zip(&centroid)
+                    .zip(gradient)
+                    .map(|((q, c), g)| (q - c) * g)
+                    .sum::<f64>()
+            })
+            .sum();
+        Self {
+            potential: energies.iter().sum::<f64>() / p,
+            primitive_kinetic: 0.5 * dof * p * kt - polymer.spring_energy(kt) / p,
+            virial_kinetic: 0.5 * dof * kt + 0.5 * virial / p,
+        }
+    }
+
+    pub fn total_primitive(&self) -> f64 {
+        self.potential + self.primitive_kinetic
+    }
+
+    pub fn total_virial(&self) -> f64 {
+        self.potential + self.virial_kinetic
+    }
+
+    fn zip_with(self, other: Self, f: impl Fn(f64, f64) -> f64) -> Self {
+        Self {
+            potential: f(self.potential, other.potential),
+            primitive_kinetic: f(self.primitive_kinetic, other.primitive_kinetic),
+            virial_kinetic: f(self.virial_kinetic, other.virial_kinetic),
+        }
+    }
+}
+
+/// Mean and standard error over completed blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BlockEstimate {
+    pub mean: f64,
+    /// Standard error of the mean; zero with fewer than two blocks
+    pub std_error: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnergySummary {
+    pub blocks: usize,
+    pub potential: BlockEstimate,
+    pub primitive_kinetic: BlockEstimate,
+    pub virial_kinetic: BlockEstimate,
+}
+
+/// Block averages of [`EnergyEstimate`] samples
+#[derive(Debug, Clone)]
+pub struct EnergyBlocks {
+    block_length: usize,
+    pending: EnergyEstimate,
+    pending_count: usize,
+    blocks: Vec<EnergyEstimate>,
+}
+
+impl EnergyBlocks {
+    /// `block_length` samples are averaged into each block
+    pub fn new(block_length: usize) -> Self {
+        Self {
+            block_length: block_length.max(1),
+            pending: EnergyEstimate::default(),
+            pending_count: 0,
+            blocks: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, sample: EnergyEstimate) {
+        self.pending = self.pending.zip_with(sample, |a, b| a + b);
+        self.pending_count += 1;
+        if self.pending_count == self.block_length {
+            let n = self.block_length as f64;
+            self.blocks
+                .push(self.pending.zip_with(self.pending, |a, _| a / n));
+            self.pending = EnergyEstimate::default();
+            self.pending_count = 0;
+        }
+    }
+
+    /// Completed block averages; a partial trailing block is left out
+    pub fn blocks(&self) -> &[EnergyEstimate] {
+        &self.blocks
+    }
+
+    pub fn summary(&self) -> EnergySummary {
+        let estimate = |f: fn(&EnergyEstimate) -> f64| {
+            let n = self.blocks.len();
+            if n == 0 {
+                return BlockEstimate::default();
+            }
+            let mean = self.blocks.iter().map(f).sum::<f64>() / n as f64;
+            let std_error = if n > 1 {
+                let variance = self
+                    .blocks
+                    .iter()
+                    .map(|b| (f(b) - mean).powi(2))
+                    .sum::<f64>()
+                    / (n - 1) as f64;
+                (variance / n as f64).sqrt()
+            } else {
+                0.0
+            };
+            BlockEstimate { mean, std_error }
+        };
+        EnergySummary {
+            blocks: self.blocks.len(),
+            potential: estimate(|e| e.potential),
+            primitive_kinetic: estimate(|e| e.primitive_kinetic),
+            virial_kinetic: estimate(|e| e.virial_kinetic),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pimc::tests::{harmonic, particle, quantum_potential, KT};
+    use crate::pimc::{PimcConfig, PimcSampler};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    #[test]
+    fn test_estimators_agree_on_quantum_oscillator() {
+        let mut polymer = particle(8);
+        let config = PimcConfig {
+            num_beads: 8,
+            step_size: 0.1,
+            adaptation_rate: 0.0,
+            ..Default::default()
+        };
+        let mut sampler = PimcSampler::new(&config, ChaCha12Rng::seed_from_u64(5));
+        let mut potential = harmonic;
+        for _ in 0..500 {
+            sampler.sweep(&mut polymer, KT, &mut potential);
+        }
+        let mut blocks = EnergyBlocks::new(400);
+        for _ in 0..8_000 {
+            sampler.sweep(&mut polymer, KT, &mut potential);
+            blocks.push(EnergyEstimate::measure(&polymer, KT, &mut potential));
+        }
+        let summary = blocks.summary();
+        assert_eq!(summary.blocks, 20);
+        // <K> = <V> for a harmonic oscillator
+        let exact = quantum_potential();
+        for estimate in [summary.primitive_kinetic, summary.virial_kinetic] {
+            assert!(
+                (estimate.mean - exact).abs() < 0.08 * exact,
+                "K = {:?} vs quantum {}",
+                estimate,
+                exact
+            );
+            assert!(estimate.std_error > 0.0);
+        }
+        assert!(summary.virial_kinetic.std_error < summary.primitive_kinetic.std_error);
+    }
+
+    #[test]
+    fn test_single_bead_is_classical() {
+        let polymer = particle(1);
+        let estimate = EnergyEstimate::measure(&polymer, KT, &mut harmonic);
+        assert!((estimate.primitive_kinetic - 1.5 * KT).abs() < 1e-12);
+        assert!((estimate.virial_kinetic - 1.5 * KT).abs() < 1e-12);
+
+        let mut blocks = EnergyBlocks::new(2);
+        for v in [1.0, 3.0, 5.0] {
+            blocks.push(EnergyEstimate {
+                potential: v,
+                ..Default::default()
+            });
+        }
+        assert_eq!(blocks.blocks().len(), 1);
+        assert_eq!(blocks.summary().potential.mean, 2.0);
+    }
+}
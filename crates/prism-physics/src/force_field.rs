@@ -0,0 +1,1007 @@
+//! # Nonbonded Force Field - Lennard-Jones 12-6 + Coulomb
+//! Per-atom sigma/epsilon/charge, Lorentz-Berthelot mixing, cutoff with an
+//! optional CHARMM-style switching function. In a periodic box with PME
+//! enabled, Coulomb pairs use the Ewald real-space term and the long-range
+//! remainder is evaluated on the mesh (see [`crate::pme`]). Periodic boxes
+//! may be orthorhombic or triclinic; every pair separation uses the minimum
+//! image of the [`SimulationBox`]. Generalized Born
+//! implicit solvent adds polar and nonpolar solvation terms
+//! (see [`crate::implicit_solvent`]).
+//! Cutoff pairs are evaluated in parallel on the current rayon pool, in
+//! blocks of rows whose results are summed in pair order, so forces and
+//! energies do not depend on the number of threads. Without PME the pair
+//! terms go through the vectorized kernel of [`crate::simd`] when the CPU
+//! supports it.
+//! Units: Angstrom, kcal/mol, elementary charge.
+
+use crate::implicit_solvent::{GbParams, GeneralizedBorn, ImplicitSolventConfig};
+use crate::neighbor_list::NeighborList;
+use crate::pme::{Pme, PmeConfig};
+use crate::precision::Real;
+use crate::pressure::Virial;
+use crate::simd::{PairBatch, SimdLevel, Switch};
+use prism_io::simulation_box::SimulationBox;
+use prism_io::sovereign_types::Atom;
+use prism_io::topology::{Pair14, Topology};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Coulomb prefactor in kcal·Å/(mol·e²)
+pub const COULOMB_CONSTANT: f64 = 332.063_71;
+
+/// Atoms whose pair rows are evaluated in parallel before being summed
+const PAIR_BLOCK_ROWS: usize = 512;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForceFieldConfig {
+    /// Nonbonded cutoff (Å). Pairs beyond this distance do not interact.
+    #[serde(with = "crate::units::as_angstrom")]
+    pub cutoff: f32,
+    /// Distance at which the switching function starts (Å). `None` = hard cutoff.
+    pub switch_distance: Option<f32>,
+    /// Relative dielectric constant applied to all Coulomb pairs
+    pub dielectric: f32,
+    /// Pairs closer than this in the reference structure are treated as
+    /// covalent neighbours (1-2/1-3) and excluded from nonbonded terms
+    /// when no explicit topology is supplied.
+    pub exclusion_distance: f32,
+    /// Verlet skin for the GPU neighbor list (Å); the list is rebuilt once
+    /// any atom moves more than half of it. 0 disables the list.
+    #[serde(default = "default_neighbor_skin")]
+    pub neighbor_skin: f32,
+    /// Particle Mesh Ewald long-range electrostatics; only active once a
+    /// periodic box is set. `None` = cutoff Coulomb.
+    #[serde(default)]
+    pub pme: Option<PmeConfig>,
+    /// Generalized Born / surface area implicit solvent. `None` = vacuum
+    /// (or explicit solvent).
+    #[serde(default)]
+    pub implicit_solvent: Option<ImplicitSolventConfig>,
+    /// Use the vectorized LJ/Coulomb kernel when the CPU supports it
+    /// (see [`crate::simd`]); `false` keeps the scalar pair loop
+    #[serde(default = "default_simd")]
+    pub simd: bool,
+}
+
+fn default_neighbor_skin() -> f32 {
+    2.0
+}
+
+fn default_simd() -> bool {
+    true
+}
+
+impl Default for ForceFieldConfig {
+    fn default() -> Self {
+        Self {
+            cutoff: 10.0,
+            switch_distance: Some(8.0),
+            dielectric: 1.0,
+            exclusion_distance: 2.6,
+            neighbor_skin: default_neighbor_skin(),
+            pme: None,
+            implicit_solvent: None,
+            simd: default_simd(),
+        }
+    }
+}
+
+/// Per-atom nonbonded parameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NonbondedParams {
+    /// LJ diameter (Å)
+    pub sigma: f32,
+    /// LJ well depth (kcal/mol)
+    pub epsilon: f32,
+    /// Partial charge (e)
+    pub charge: f32,
+}
+
+impl NonbondedParams {
+    /// Default LJ parameters by element (AMBER-style Rmin/2 and epsilon).
+    /// Unknown elements fall back to the VdW radius carried by the atom.
+    pub fn from_atom(atom: &Atom) -> Self {
+        let (rmin_half, epsilon) = match atom.element {
+            1 => (1.487, 0.0157),
+            6 => (1.908, 0.1094),
+            7 => (1.824, 0.1700),
+            8 => (1.6612, 0.2100),
+            15 => (2.100, 0.2000),
+            16 => (2.000, 0.2500),
+            _ => (atom.radius.max(0.5), 0.1000),
+        };
+        Self {
+            sigma: 2.0 * rmin_half / 2f32.powf(1.0 / 6.0),
+            epsilon,
+            charge: atom.charge,
+        }
+    }
+}
+
+/// Pair kernel tables shared by the CUDA, HIP and wgpu backends
+#[cfg(any(feature = "cuda", feature = "rocm", feature = "wgpu"))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PairTables {
+    /// Per-atom `[sigma, epsilon, charge, type_id]`
+    pub params: Vec<[f32; 4]>,
+    /// Excluded partners of each atom (both directions)
+    pub exclusions: Vec<Vec<u32>>,
+    /// Row-major `num_types x num_types` `[sigma, epsilon]`; negative epsilon = mixing rule
+    pub overrides: Vec<[f32; 2]>,
+    pub num_types: usize,
+    /// Coulomb constant / dielectric
+    pub coulomb_scale: f32,
+}
+
+/// Nonbonded energy terms (kcal/mol)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct NonbondedEnergy {
+    pub lennard_jones: f64,
+    pub coulomb: f64,
+    /// Implicit solvent (GB polar + SA nonpolar)
+    #[serde(default)]
+    pub solvation: f64,
+}
+
+impl NonbondedEnergy {
+    pub fn total(&self) -> f64 {
+        self.lennard_jones + self.coulomb + self.solvation
+    }
+}
+
+/// Which cutoff pairs [`ForceField::accumulate`] visits
+/// Interacting cutoff pair: `(i, j, x_j - x_i, energy, -dE/dr / r)`
+type PairTerm = (usize, usize, [f32; 3], NonbondedEnergy, f64);
+
+#[derive(Clone, Copy)]
+enum PairSource<'a> {
+    All,
+    List(&'a NeighborList),
+    Pairs14Only,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForceField {
+    config: ForceFieldConfig,
+    params: Vec<NonbondedParams>,
+    exclusions: HashSet<(u32, u32)>,
+    /// Per-atom LJ type id, used to look up `pair_overrides`
+    type_ids: Vec<u32>,
+    /// NBFIX-style (sigma, epsilon) per type pair, replacing the mixing rule
+    pair_overrides: HashMap<(u32, u32), (f32, f32)>,
+    /// Scaled 1-4 pairs, evaluated without cutoff or switching
+    pairs14: Vec<Pair14>,
+    /// Per-atom LJ parameters used for 1-4 pairs
+    params14: Vec<NonbondedParams>,
+    /// Periodic box; `None` = open boundaries
+    simulation_box: Option<SimulationBox>,
+    /// Mesh solver, present when PME is configured and a box is set
+    pme: Option<Pme>,
+    /// Implicit solvent model, present when configured and radii are known
+    gb: Option<GeneralizedBorn>,
+}
+
+impl ForceField {
+    pub fn new(config: ForceFieldConfig, params: Vec<NonbondedParams>) -> Self {
+        Self {
+            config,
+            params,
+            exclusions: HashSet::new(),
+            type_ids: Vec::new(),
+            pair_overrides: HashMap::new(),
+            pairs14: Vec::new(),
+            params14: Vec::new(),
+            simulation_box: None,
+            pme: None,
+            gb: None,
+        }
+    }
+
+    /// Build a force field from atoms, deriving parameters per element and
+    /// excluding pairs that are covalently close in the input geometry.
+    pub fn from_atoms(config: ForceFieldConfig, atoms: &[Atom]) -> Self {
+        let params = atoms.iter().map(NonbondedParams::from_atom).collect();
+        let mut ff = Self::new(config, params);
+        let cut2 = ff.config.exclusion_distance * ff.config.exclusion_distance;
+        for i in 0..atoms.len() {
+            for j in (i + 1)..atoms.len() {
+                let a = atoms[i].coords;
+                let b = atoms[j].coords;
+                let d2 = (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2);
+                if d2 < cut2 {
+                    ff.exclusions.insert((i as u32, j as u32));
+                }
+            }
+        }
+        if let Some(config) = ff.config.implicit_solvent.clone() {
+            // Covalent X-H bonds are well under 1.2 Å
+            let elements: Vec<u8> = atoms.iter().map(|a| a.element).collect();
+            let bonds = ff.exclusions.iter().copied().filter(|&(i, j)| {
+                let (a, b) = (atoms[i as usize].coords, atoms[j as usize].coords);
+                (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2) < 1.44
+            });
+            ff.gb = Some(GeneralizedBorn::from_elements(config, ff.config.dielectric, &elements, bonds));
+        }
+        ff
+    }
+
+    /// Build a force field from an imported topology, using its per-atom LJ
+    /// parameters, charges and exclusion list.
+    pub fn from_topology(config: ForceFieldConfig, topology: &Topology) -> Self {
+        let params = topology
+            .atoms
+            .iter()
+            .zip(&topology.lj)
+            .map(|(atom, lj)| NonbondedParams {
+                sigma: lj.sigma,
+                epsilon: lj.epsilon,
+                charge: atom.charge,
+            })
+            .collect();
+        let mut ff = Self::new(config, params);
+        ff.set_exclusions(topology.exclusions.iter().copied());
+        ff.set_box(topology.simulation_box);
+        if let Some(config) = ff.config.implicit_solvent.clone() {
+            let elements: Vec<u8> = topology.atoms.iter().map(|a| a.element).collect();
+            let bonds = topology.bonds.iter().map(|b| (b.i, b.j));
+            ff.gb = Some(GeneralizedBorn::from_elements(config, ff.config.dielectric, &elements, bonds));
+        }
+
+        ff.pairs14 = topology.pairs14.clone();
+        if topology.lj14.len() == topology.atoms.len() {
+            ff.params14 = topology
+                .atoms
+                .iter()
+                .zip(&topology.lj14)
+                .map(|(atom, lj)| NonbondedParams {
+                    sigma: lj.sigma,
+                    epsilon: lj.epsilon,
+                    charge: atom.charge,
+                })
+                .collect();
+        }
+
+        if !topology.nbfix.is_empty() {
+            let mut names: HashMap<&str, u32> = HashMap::new();
+            for name in &topology.atom_types {
+                let next = names.len() as u32;
+                let id = *names.entry(name.as_str()).or_insert(next);
+                ff.type_ids.push(id);
+            }
+            for fix in &topology.nbfix {
+                if let (Some(&a), Some(&b)) =
+                    (names.get(fix.type_a.as_str()), names.get(fix.type_b.as_str()))
+                {
+                    ff.pair_overrides
+                        .insert((a.min(b), a.max(b)), (fix.params.sigma, fix.params.epsilon));
+                }
+            }
+        }
+        ff
+    }
+
+    /// Replace the exclusion set with explicit atom pairs (e.g. from topology).
+    pub fn set_exclusions<I: IntoIterator<Item = (u32, u32)>>(&mut self, pairs: I) {
+        self.exclusions = pairs.into_iter().map(|(i, j)| (i.min(j), i.max(j))).collect();
+    }
+
+    pub fn is_excluded(&self, i: usize, j: usize) -> bool {
+        let key = if i < j { (i as u32, j as u32) } else { (j as u32, i as u32) };
+        self.exclusions.contains(&key)
+    }
+
+    /// Set the periodic box (minimum-image pairs), rebuilding the PME grid
+    pub fn set_box(&mut self, simulation_box: Option<SimulationBox>) {
+        self.simulation_box = simulation_box;
+        self.pme = match (&self.config.pme, &simulation_box) {
+            (Some(config), Some(cell)) => Some(Pme::new(
+                config,
+                self.config.cutoff,
+                cell,
+                COULOMB_CONSTANT / self.config.dielectric as f64,
+            )),
+            _ => None,
+        };
+    }
+
+    pub fn simulation_box(&self) -> Option<&SimulationBox> {
+        self.simulation_box.as_ref()
+    }
+
+    /// Mesh solver, when PME is active
+    pub fn pme(&self) -> Option<&Pme> {
+        self.pme.as_ref()
+    }
+
+    /// Set explicit per-atom GB radii and scales (requires
+    /// `ForceFieldConfig::implicit_solvent`)
+    pub fn set_gb_params(&mut self, params: Vec<GbParams>) {
+        self.gb = self
+            .config
+            .implicit_solvent
+            .clone()
+            .map(|config| GeneralizedBorn::new(config, self.config.dielectric, params));
+    }
+
+    /// Implicit solvent model, when active
+    pub fn implicit_solvent(&self) -> Option<&GeneralizedBorn> {
+        self.gb.as_ref()
+    }
+
+    pub fn config(&self) -> &ForceFieldConfig {
+        &self.config
+    }
+
+    pub fn params(&self) -> &[NonbondedParams] {
+        &self.params
+    }
+
+    pub fn num_atoms(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Energy and scalar force magnitude `-dE/dr / r` for a single pair at
+    /// squared distance `r2`. Returns `None` beyond the cutoff.
+    pub fn pair_interaction(&self, i: usize, j: usize, r2: f32) -> Option<(NonbondedEnergy, f64)> {
+        let rc = self.config.cutoff as f64;
+        if r2 as f64 >= rc * rc || r2 <= 0.0 {
+            return None;
+        }
+        let (sigma, epsilon) = self.mixed_lj(&self.params, i, j);
+        let qq = (self.params[i].charge * self.params[j].charge) as f64;
+        let Some(pme) = &self.pme else {
+            return Some(self.lj_coulomb(sigma, epsilon, qq, r2 as f64, true));
+        };
+        // Ewald real-space Coulomb is already smooth at the cutoff: only LJ is switched
+        let r = (r2 as f64).sqrt();
+        let (lj, f_lj) = self.lj_coulomb(sigma, epsilon, 0.0, r2 as f64, true);
+        let (coulomb, de_dr) = pme.real_space(qq, r);
+        Some((NonbondedEnergy { lennard_jones: lj.lennard_jones, coulomb, ..Default::default() }, f_lj - de_dr / r))
+    }
+
+    /// Lorentz-Berthelot mixing, unless an NBFIX override exists for the type pair
+    pub(crate) fn mixed_lj(&self, params: &[NonbondedParams], i: usize, j: usize) -> (f64, f64) {
+        if !self.pair_overrides.is_empty() {
+            let (a, b) = (self.type_ids[i], self.type_ids[j]);
+            if let Some(&(sigma, epsilon)) = self.pair_overrides.get(&(a.min(b), a.max(b))) {
+                return (sigma as f64, epsilon as f64);
+            }
+        }
+        let (pi, pj) = (&params[i], &params[j]);
+        (
+            0.5 * (pi.sigma + pj.sigma) as f64,
+            ((pi.epsilon * pj.epsilon) as f64).sqrt(),
+        )
+    }
+
+    fn lj_coulomb(&self, sigma: f64, epsilon: f64, qq: f64, r2: f64, switched: bool) -> (NonbondedEnergy, f64) {
+        let r = r2.sqrt();
+        let sr6 = (sigma * sigma / r2).powi(3);
+        let e_lj = 4.0 * epsilon * (sr6 * sr6 - sr6);
+        let de_lj = -24.0 * epsilon * (2.0 * sr6 * sr6 - sr6) / r;
+
+        let qq = COULOMB_CONSTANT * qq / self.config.dielectric as f64;
+        let e_c = qq / r;
+        let de_c = -qq / r2;
+
+        let (s, ds) = if switched { self.switch(r) } else { (1.0, 0.0) };
+        let energy = NonbondedEnergy { lennard_jones: e_lj * s, coulomb: e_c * s, ..Default::default() };
+        let de_dr = (de_lj + de_c) * s + (e_lj + e_c) * ds;
+        (energy, -de_dr / r)
+    }
+
+    /// Scaled 1-4 interaction for an explicit pair (no cutoff, no switching)
+    fn pair14_interaction(&self, pair: &Pair14, r2: f64) -> (NonbondedEnergy, f64) {
+        let (i, j) = (pair.i as usize, pair.j as usize);
+        let params = if self.params14.is_empty() { &self.params } else { &self.params14 };
+        let (sigma, epsilon) = self.mixed_lj(params, i, j);
+        let qq = (self.params[i].charge * self.params[j].charge) as f64;
+        let (lj, f_lj) = self.lj_coulomb(sigma, epsilon * pair.lj_scale as f64, 0.0, r2, false);
+        let (c, f_c) = self.lj_coulomb(1.0, 0.0, qq * pair.coulomb_scale as f64, r2, false);
+        (
+            NonbondedEnergy { lennard_jones: lj.lennard_jones, coulomb: c.coulomb, ..Default::default() },
+            f_lj + f_c,
+        )
+    }
+
+    /// CHARMM switching function S(r) and dS/dr
+    pub(crate) fn switch(&self, r: f64) -> (f64, f64) {
+        let Some(ron) = self.config.switch_distance else { return (1.0, 0.0) };
+        let ron = ron as f64;
+        let roff = self.config.cutoff as f64;
+        if r <= ron || ron >= roff {
+            return (1.0, 0.0);
+        }
+        let (r2, ron2, roff2) = (r * r, ron * ron, roff * roff);
+        let denom = (roff2 - ron2).powi(3);
+        let s = (roff2 - r2).powi(2) * (roff2 + 2.0 * r2 - 3.0 * ron2) / denom;
+        let ds = 12.0 * r * (roff2 - r2) * (ron2 - r2) / denom;
+        (s, ds)
+    }
+
+    /// Instruction set of the vectorized pair kernel, `None` when the scalar
+    /// loop is used (disabled in the config, PME active or no CPU support)
+    pub fn simd_level(&self) -> Option<SimdLevel> {
+        (self.config.simd && self.pme.is_none())
+            .then(SimdLevel::detect)
+            .filter(|&level| level != SimdLevel::Scalar)
+    }
+
+    /// Cutoff pairs of row `i` through the vectorized kernel
+    fn simd_row(
+        &self,
+        positions: &[f32],
+        level: SimdLevel,
+        batch: &mut PairBatch,
+        i: usize,
+        partners: impl Iterator<Item = usize>,
+    ) -> Vec<PairTerm> {
+        let rc2 = self.config.cutoff as f64 * self.config.cutoff as f64;
+        let coulomb_scale = COULOMB_CONSTANT / self.config.dielectric as f64;
+        let mut deltas = Vec::new();
+        batch.clear();
+        for j in partners {
+            let d = self.delta(positions, i, j);
+            let r2 = d[0] * d[0] + d[1] * d[1] + d[2] * d[2];
+            if r2 as f64 >= rc2 || r2 <= 0.0 {
+                continue;
+            }
+            let (sigma, epsilon) = self.mixed_lj(&self.params, i, j);
+            let qq = (self.params[i].charge * self.params[j].charge) as f64;
+            batch.push(r2 as f64, sigma, epsilon, coulomb_scale * qq);
+            deltas.push((j, d));
+        }
+        batch.evaluate(level, Switch::new(self.config.switch_distance, self.config.cutoff));
+        deltas
+            .into_iter()
+            .enumerate()
+            .map(|(k, (j, d))| {
+                let energy = NonbondedEnergy {
+                    lennard_jones: batch.lennard_jones[k],
+                    coulomb: batch.coulomb[k],
+                    ..Default::default()
+                };
+                (i, j, d, energy, batch.f_over_r[k])
+            })
+            .collect()
+    }
+
+    /// Nonbonded energy for Float4-stride positions.
+    pub fn energy(&self, positions: &[f32]) -> NonbondedEnergy {
+        self.accumulate::<f32>(positions, None, PairSource::All, None)
+    }
+
+    /// Nonbonded energy, adding forces (kcal/mol/Å) into a Float4-stride buffer.
+    pub fn compute(&self, positions: &[f32], forces: &mut [f32]) -> NonbondedEnergy {
+        self.accumulate(positions, Some(forces), PairSource::All, None)
+    }
+
+    /// Like [`Self::compute`], but only visiting the pairs stored in `list`
+    /// (built with this force field's exclusions) instead of all pairs.
+    /// Forces are accumulated in the scalar type of `forces` (`f32` or `f64`).
+    pub fn compute_with_list<R: Real>(&self, positions: &[f32], forces: &mut [R], list: &NeighborList) -> NonbondedEnergy {
+        self.accumulate(positions, Some(forces), PairSource::List(list), None)
+    }
+
+    /// Like [`Self::compute_with_list`], also adding the nonbonded virial
+    /// (see [`crate::pressure`]) into `virial`
+    pub fn compute_with_list_and_virial<R: Real>(
+        &self,
+        positions: &[f32],
+        forces: &mut [R],
+        list: &NeighborList,
+        virial: &mut Virial,
+    ) -> NonbondedEnergy {
+        self.accumulate(positions, Some(forces), PairSource::List(list), Some(virial))
+    }
+
+    /// Neighbor list sized for this force field's cutoff and skin
+    pub fn neighbor_list(&self) -> NeighborList {
+        NeighborList::new(self.config.cutoff, self.config.neighbor_skin).with_box(self.simulation_box)
+    }
+
+    /// Energy and forces of the scaled 1-4 pairs only, for callers that
+    /// evaluate the cutoff pairs elsewhere (e.g. on the GPU). PME mesh and
+    /// correction terms and implicit solvent are not included.
+    pub fn compute_pairs14(&self, positions: &[f32], forces: &mut [f32]) -> NonbondedEnergy {
+        self.accumulate(positions, Some(forces), PairSource::Pairs14Only, None)
+    }
+
+    /// [`Self::compute_pairs14`], also adding the pairs' virial
+    pub fn compute_pairs14_with_virial(&self, positions: &[f32], forces: &mut [f32], virial: &mut Virial) -> NonbondedEnergy {
+        self.accumulate(positions, Some(forces), PairSource::Pairs14Only, Some(virial))
+    }
+
+    /// PME terms not covered by the real-space or mesh sums: removal of the
+    /// mesh interaction of excluded pairs plus the self/background energy.
+    /// Returns the Coulomb energy (0 without PME).
+    pub fn compute_pme_corrections(&self, positions: &[f32], forces: Option<&mut [f32]>) -> f64 {
+        self.pme_corrections(positions, forces, None)
+    }
+
+    fn pme_corrections<R: Real>(&self, positions: &[f32], mut forces: Option<&mut [R]>, mut virial: Option<&mut Virial>) -> f64 {
+        let Some(pme) = &self.pme else { return 0.0 };
+        let n = self.params.len().min(positions.len() / 4);
+        let charges: Vec<f32> = self.params[..n].iter().map(|p| p.charge).collect();
+        let mut energy = pme.self_energy(&charges);
+        if let Some(virial) = virial.as_deref_mut() {
+            virial.add_isotropic(pme.background_energy(&charges));
+        }
+        for &(i, j) in &self.exclusions {
+            let (i, j) = (i as usize, j as usize);
+            if i >= n || j >= n {
+                continue;
+            }
+            let d = self.delta(positions, i, j);
+            let r = ((d[0] * d[0] + d[1] * d[1] + d[2] * d[2]) as f64).sqrt();
+            let (e, de_dr) = pme.exclusion_correction((charges[i] * charges[j]) as f64, r);
+            energy += e;
+            if let (Some(virial), true) = (virial.as_deref_mut(), r > 1e-6) {
+                virial.add_pair(d, -de_dr / r);
+            }
+            if let (Some(f), true) = (forces.as_deref_mut(), r > 1e-6) {
+                for k in 0..3 {
+                    let fk = R::from_f64(de_dr / r * d[k] as f64);
+                    f[i * 4 + k] += fk;
+                    f[j * 4 + k] -= fk;
+                }
+            }
+        }
+        energy
+    }
+
+    /// Implicit solvent energy (0 without a GB model), adding forces when given
+    pub fn compute_solvation(&self, positions: &[f32], forces: Option<&mut [f32]>) -> f64 {
+        let Some(gb) = &self.gb else { return 0.0 };
+        let charges: Vec<f32> = self.params.iter().map(|p| p.charge).collect();
+        gb.compute(positions, &charges, forces).total()
+    }
+
+    /// Separation vector `x_j - x_i`, minimum image when a box is set
+    pub(crate) fn delta(&self, positions: &[f32], i: usize, j: usize) -> [f32; 3] {
+        let d = [0, 1, 2].map(|k| positions[j * 4 + k] - positions[i * 4 + k]);
+        match &self.simulation_box {
+            Some(cell) => cell.minimum_image(d),
+            None => d,
+        }
+    }
+
+    /// Per-atom parameters, exclusions and type overrides in the layout of
+    /// the device pair kernels (CUDA, HIP and WGSL)
+    #[cfg(any(feature = "cuda", feature = "rocm", feature = "wgpu"))]
+    pub(crate) fn pair_tables(&self) -> PairTables {
+        let n = self.params.len();
+        let mut exclusions = vec![Vec::new(); n];
+        for &(i, j) in &self.exclusions {
+            if (i as usize) < n && (j as usize) < n {
+                exclusions[i as usize].push(j);
+                exclusions[j as usize].push(i);
+            }
+        }
+        let num_types = if self.pair_overrides.is_empty() {
+            0
+        } else {
+            self.type_ids.iter().max().map_or(0, |&t| t as usize + 1)
+        };
+        let mut overrides = vec![[0.0, -1.0]; num_types * num_types];
+        for (&(a, b), &(sigma, epsilon)) in &self.pair_overrides {
+            let (a, b) = (a as usize, b as usize);
+            overrides[a * num_types + b] = [sigma, epsilon];
+            overrides[b * num_types + a] = [sigma, epsilon];
+        }
+        PairTables {
+            params: self
+                .params
+                .iter()
+                .enumerate()
+                .map(|(i, p)| {
+                    let type_id = if num_types > 0 { self.type_ids[i] as f32 } else { 0.0 };
+                    [p.sigma, p.epsilon, p.charge, type_id]
+                })
+                .collect(),
+            exclusions,
+            overrides,
+            num_types,
+            coulomb_scale: (COULOMB_CONSTANT / self.config.dielectric as f64) as f32,
+        }
+    }
+
+    /// Parameter tables for the GPU nonbonded kernel (1-4 pairs and PME
+    /// corrections excluded). The kernel handles orthorhombic boxes only;
+    /// callers keep triclinic systems on the host.
+    #[cfg(feature = "cuda")]
+    pub fn to_gpu_system(&self) -> prism_gpu::nonbonded::NonbondedSystem {
+        let tables = self.pair_tables();
+        prism_gpu::nonbonded::NonbondedSystem {
+            params: tables.params,
+            exclusions: tables.exclusions,
+            overrides: tables.overrides,
+            num_types: tables.num_types,
+            cutoff: self.config.cutoff,
+            switch_distance: self.config.switch_distance,
+            coulomb_scale: tables.coulomb_scale,
+            neighbor_skin: (self.config.neighbor_skin > 0.0).then_some(self.config.neighbor_skin),
+            box_lengths: self.simulation_box.map(|cell| cell.lengths()),
+            ewald_beta: self.pme.as_ref().map(|pme| pme.beta() as f32),
+            pme: self.pme.as_ref().map(|pme| {
+                let [nx, ny, nz] = pme.dims();
+                let half = nz / 2 + 1;
+                let full = pme.influence();
+                prism_gpu::pme::PmeSystem {
+                    dims: pme.dims(),
+                    order: pme.order(),
+                    box_lengths: pme.simulation_box().lengths(),
+                    influence: (0..nx * ny * half)
+                        .map(|k| full[(k / half) * nz + k % half] as f32)
+                        .collect(),
+                }
+            }),
+            mixed_precision: false,
+        }
+    }
+
+    fn accumulate<R: Real>(&self, positions: &[f32], mut forces: Option<&mut [R]>, source: PairSource<'_>, mut virial: Option<&mut Virial>) -> NonbondedEnergy {
+        let n = self.params.len().min(positions.len() / 4);
+        let mut total = NonbondedEnergy::default();
+        let mut apply = |i: usize, j: usize, d: [f32; 3], e: NonbondedEnergy, f_over_r: f64| {
+            total.lennard_jones += e.lennard_jones;
+            total.coulomb += e.coulomb;
+            if let Some(virial) = virial.as_deref_mut() {
+                virial.add_pair(d, f_over_r);
+            }
+            if let Some(f) = forces.as_deref_mut() {
+                for k in 0..3 {
+                    let fk = R::from_f64(f_over_r * d[k] as f64);
+                    f[i * 4 + k] -= fk;
+                    f[j * 4 + k] += fk;
+                }
+            }
+        };
+        let delta = |i: usize, j: usize| self.delta(positions, i, j);
+
+        let cutoff_pair = |i: usize, j: usize| {
+            let d = delta(i, j);
+            let r2 = d[0] * d[0] + d[1] * d[1] + d[2] * d[2];
+            self.pair_interaction(i, j, r2).map(|(e, f_over_r)| (i, j, d, e, f_over_r))
+        };
+        let simd = self.simd_level();
+        if !matches!(source, PairSource::Pairs14Only) {
+            for start in (0..n).step_by(PAIR_BLOCK_ROWS) {
+                let rows = start..(start + PAIR_BLOCK_ROWS).min(n);
+                let interacting: Vec<PairTerm> = match (&source, simd) {
+                    (PairSource::List(list), Some(level)) => rows
+                        .into_par_iter()
+                        .map_init(PairBatch::default, |batch, i| {
+                            self.simd_row(positions, level, batch, i, list.partners(i).filter(|&j| j < n))
+                        })
+                        .flatten_iter()
+                        .collect(),
+                    (PairSource::List(list), None) => rows
+                        .into_par_iter()
+                        .flat_map_iter(|i| list.partners(i).filter(|&j| j < n).filter_map(move |j| cutoff_pair(i, j)))
+                        .collect(),
+                    (_, Some(level)) => rows
+                        .into_par_iter()
+                        .map_init(PairBatch::default, |batch, i| {
+                            let partners = ((i + 1)..n).filter(|&j| !self.is_excluded(i, j));
+                            self.simd_row(positions, level, batch, i, partners)
+                        })
+                        .flatten_iter()
+                        .collect(),
+                    (_, None) => rows
+                        .into_par_iter()
+                        .flat_map_iter(|i| ((i + 1)..n).filter(move |&j| !self.is_excluded(i, j)).filter_map(move |j| cutoff_pair(i, j)))
+                        .collect(),
+                };
+                for (i, j, d, e, f_over_r) in interacting {
+                    apply(i, j, d, e, f_over_r);
+                }
+            }
+        }
+
+        for pair in &self.pairs14 {
+            let (i, j) = (pair.i as usize, pair.j as usize);
+            if i >= n || j >= n {
+                continue;
+            }
+            let d = delta(i, j);
+            let r2 = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]) as f64;
+            if r2 > 0.0 {
+                let (e, f_over_r) = self.pair14_interaction(pair, r2);
+                apply(i, j, d, e, f_over_r);
+            }
+        }
+
+        if let (Some(pme), false) = (&self.pme, matches!(source, PairSource::Pairs14Only)) {
+            let charges: Vec<f32> = self.params[..n].iter().map(|p| p.charge).collect();
+            total.coulomb += pme.reciprocal_with_virial(&positions[..n * 4], &charges, forces.as_deref_mut(), virial.as_deref_mut());
+            total.coulomb += self.pme_corrections(positions, forces.as_deref_mut(), virial.as_deref_mut());
+        }
+        if !matches!(source, PairSource::Pairs14Only) {
+            // Implicit solvent evaluates f32 forces into a scratch buffer;
+            // it has no periodic images, so its virial is the single sum
+            total.solvation = match (&self.gb, forces) {
+                (Some(_), Some(f)) => {
+                    let mut local = vec![0.0; positions.len()];
+                    let energy = self.compute_solvation(positions, Some(&mut local));
+                    if let Some(virial) = virial {
+                        virial.add_forces(positions, &local);
+                    }
+                    for (f, &l) in f.iter_mut().zip(&local) {
+                        *f += R::from_f64(l as f64);
+                    }
+                    energy
+                }
+                (_, None) => self.compute_solvation(positions, None),
+                (None, Some(_)) => 0.0,
+            };
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn built_list(ff: &ForceField, positions: &[f32]) -> NeighborList {
+        let mut list = ff.neighbor_list();
+        list.update(positions, |i, j| ff.is_excluded(i, j));
+        list
+    }
+
+    fn atom(x: f32, element: u8, charge: f32) -> Atom {
+        Atom {
+            coords: [x, 0.0, 0.0],
+            element,
+            residue_id: 0,
+            atom_type: 1,
+            charge,
+            radius: 1.7,
+            _reserved: [0; 4],
+        }
+    }
+
+    #[test]
+    fn test_lj_minimum_at_rmin() {
+        let p = NonbondedParams { sigma: 3.4, epsilon: 0.1, charge: 0.0 };
+        let config = ForceFieldConfig { switch_distance: None, ..Default::default() };
+        let ff = ForceField::new(config, vec![p, p]);
+        let rmin = 3.4 * 2f32.powf(1.0 / 6.0);
+        let (e, f) = ff.pair_interaction(0, 1, rmin * rmin).unwrap();
+        assert!((e.lennard_jones + 0.1).abs() < 1e-5);
+        assert!(f.abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_forces_match_finite_difference() {
+        let atoms = vec![atom(0.0, 8, -0.5), atom(3.5, 7, 0.4), atom(8.7, 6, 0.1)];
+        let ff = ForceField::from_atoms(ForceFieldConfig::default(), &atoms);
+        let mut pos: Vec<f32> = atoms
+            .iter()
+            .flat_map(|a| [a.coords[0], a.coords[1], a.coords[2], 1.0])
+            .collect();
+        let mut forces = vec![0.0; pos.len()];
+        ff.compute(&pos, &mut forces);
+
+        // Atom 2 sits inside the switching region of atom 0
+        let h = 1e-3;
+        pos[8] += h;
+        let e_plus = ff.energy(&pos).total();
+        pos[8] -= 2.0 * h;
+        let e_minus = ff.energy(&pos).total();
+        let numeric = -(e_plus - e_minus) / (2.0 * h as f64);
+        assert!((numeric - forces[8] as f64).abs() < 1e-2, "{} vs {}", numeric, forces[8]);
+    }
+
+    #[test]
+    fn test_nbfix_override_and_pairs14() {
+        use prism_io::topology::{LjParams, NbFix};
+        let lj = LjParams { sigma: 3.0, epsilon: 0.1 };
+        let topology = Topology {
+            atoms: vec![atom(0.0, 6, 0.0), atom(4.0, 8, 0.0)],
+            atom_types: vec!["CT".into(), "OH".into()],
+            lj: vec![lj, lj],
+            nbfix: vec![NbFix { type_a: "OH".into(), type_b: "CT".into(), params: LjParams { sigma: 4.0, epsilon: 0.5 } }],
+            pairs14: vec![Pair14 { i: 0, j: 1, coulomb_scale: 1.0, lj_scale: 0.5 }],
+            exclusions: vec![(0, 1)],
+            ..Default::default()
+        };
+        let ff = ForceField::from_topology(ForceFieldConfig::default(), &topology);
+        let (e, _) = ff.pair_interaction(0, 1, 16.0).unwrap();
+        // sigma = r = 4.0 -> LJ energy is exactly zero with the override
+        assert!(e.lennard_jones.abs() < 1e-9);
+
+        // At r = 4.4 only the scaled 1-4 term contributes: lj_scale * 4 * epsilon * (sr^12 - sr^6)
+        let pos = vec![0.0, 0.0, 0.0, 1.0, 4.4, 0.0, 0.0, 1.0];
+        let sr6 = (4.0f64 / 4.4).powi(6);
+        let expected = 0.5 * 4.0 * 0.5 * (sr6 * sr6 - sr6);
+        assert!((ff.energy(&pos).lennard_jones - expected).abs() < 1e-6);
+
+        // The 1-4 term alone is all that remains for the GPU split
+        let mut forces = vec![0.0; pos.len()];
+        assert_eq!(ff.compute_pairs14(&pos, &mut forces), ff.energy(&pos));
+        assert!(forces[0] < 0.0 && forces[4] > 0.0);
+    }
+
+    #[test]
+    fn test_neighbor_list_matches_all_pairs() {
+        let atoms: Vec<Atom> = (0..60)
+            .map(|k| {
+                let mut a = atom((k % 5) as f32 * 3.1, 7, if k % 2 == 0 { 0.3 } else { -0.3 });
+                a.coords[1] = ((k / 5) % 4) as f32 * 3.3;
+                a.coords[2] = (k / 20) as f32 * 3.7;
+                a
+            })
+            .collect();
+        let ff = ForceField::from_atoms(ForceFieldConfig::default(), &atoms);
+        let pos: Vec<f32> = atoms
+            .iter()
+            .flat_map(|a| [a.coords[0], a.coords[1], a.coords[2], 1.0])
+            .collect();
+        let mut list = ff.neighbor_list();
+        list.update(&pos, |i, j| ff.is_excluded(i, j));
+
+        let (mut f_all, mut f_list) = (vec![0.0; pos.len()], vec![0.0; pos.len()]);
+        let e_all = ff.compute(&pos, &mut f_all);
+        let e_list = ff.compute_with_list(&pos, &mut f_list, &list);
+        assert!((e_all.total() - e_list.total()).abs() < 1e-9);
+        for (a, b) in f_all.iter().zip(&f_list) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_simd_kernel_matches_scalar_loop() {
+        let atoms: Vec<Atom> = (0..60)
+            .map(|k| {
+                let mut a = atom((k % 5) as f32 * 2.9, 7, if k % 3 == 0 { 0.5 } else { -0.25 });
+                a.coords[1] = ((k / 5) % 4) as f32 * 3.1;
+                a.coords[2] = (k / 20) as f32 * 3.4;
+                a
+            })
+            .collect();
+        let pos: Vec<f32> = atoms
+            .iter()
+            .flat_map(|a| [a.coords[0], a.coords[1], a.coords[2], 1.0])
+            .collect();
+        let scalar = ForceField::from_atoms(ForceFieldConfig { simd: false, ..Default::default() }, &atoms);
+        let vector = ForceField::from_atoms(ForceFieldConfig::default(), &atoms);
+        assert_eq!(scalar.simd_level(), None);
+
+        let (mut f_scalar, mut f_vector) = (vec![0.0f64; pos.len()], vec![0.0f64; pos.len()]);
+        let e_scalar = scalar.compute_with_list(&pos, &mut f_scalar, &built_list(&scalar, &pos));
+        let e_vector = vector.compute_with_list(&pos, &mut f_vector, &built_list(&vector, &pos));
+        assert!((e_scalar.total() - e_vector.total()).abs() < 1e-9 * e_scalar.total().abs().max(1.0));
+        for (a, b) in f_scalar.iter().zip(&f_vector) {
+            assert!((a - b).abs() < 1e-9 * (1.0 + a.abs()));
+        }
+        assert_eq!(e_vector, vector.energy(&pos));
+    }
+
+    #[test]
+    fn test_pme_periodic_forces() {
+        let atoms = vec![atom(1.0, 8, -0.8), atom(2.2, 1, 0.4), atom(13.5, 1, 0.4), atom(7.0, 11, 1.0), atom(9.5, 17, -1.0)];
+        let config = ForceFieldConfig { pme: Some(PmeConfig::default()), cutoff: 7.0, switch_distance: Some(6.0), ..Default::default() };
+        let mut ff = ForceField::from_atoms(config, &atoms);
+        ff.set_box(Some(SimulationBox::orthorhombic([15.0, 15.0, 15.0])));
+        assert!(ff.pme().is_some());
+        let mut pos: Vec<f32> = atoms
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| [a.coords[0], 1.0 + i as f32 * 2.1, 0.5 * i as f32, 1.0])
+            .collect();
+
+        // Atoms 0 and 2 interact only through the periodic image
+        let mut list = ff.neighbor_list();
+        list.update(&pos, |i, j| ff.is_excluded(i, j));
+        assert!(list.pairs().any(|p| p == (0, 2)));
+        let (mut f_all, mut f_list) = (vec![0.0; pos.len()], vec![0.0; pos.len()]);
+        let e_all = ff.compute(&pos, &mut f_all);
+        let e_list = ff.compute_with_list(&pos, &mut f_list, &list);
+        assert!((e_all.total() - e_list.total()).abs() < 1e-9);
+
+        // Shifting an atom by a box vector changes nothing
+        pos[0] += 15.0;
+        assert!((ff.energy(&pos).total() - e_all.total()).abs() < 1e-3);
+
+        let h = 1e-3;
+        for k in [0, 9, 13] {
+            pos[k] += h;
+            let e_plus = ff.energy(&pos).total();
+            pos[k] -= 2.0 * h;
+            let e_minus = ff.energy(&pos).total();
+            pos[k] += h;
+            let numeric = -(e_plus - e_minus) / (2.0 * h as f64);
+            assert!((numeric - f_all[k] as f64).abs() < 5e-2, "{}: {} vs {}", k, numeric, f_all[k]);
+        }
+    }
+
+    #[test]
+    fn test_triclinic_minimum_image() {
+        let atoms = vec![atom(1.0, 8, -0.8), atom(2.2, 1, 0.4), atom(13.5, 1, 0.4), atom(7.0, 11, 1.0), atom(9.5, 17, -1.0)];
+        let config = ForceFieldConfig { pme: Some(PmeConfig::default()), cutoff: 6.0, switch_distance: Some(5.0), ..Default::default() };
+        let mut ff = ForceField::from_atoms(config, &atoms);
+        let cell = SimulationBox::from_parameters([15.0, 15.0, 15.0, 60.0, 60.0, 90.0]).unwrap();
+        ff.set_box(Some(cell));
+        let mut pos: Vec<f32> = atoms
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| [a.coords[0], 1.0 + i as f32 * 2.1, 0.5 * i as f32, 1.0])
+            .collect();
+
+        let mut list = ff.neighbor_list();
+        list.update(&pos, |i, j| ff.is_excluded(i, j));
+        let (mut f_all, mut f_list) = (vec![0.0; pos.len()], vec![0.0; pos.len()]);
+        let e_all = ff.compute(&pos, &mut f_all);
+        let e_list = ff.compute_with_list(&pos, &mut f_list, &list);
+        assert!((e_all.total() - e_list.total()).abs() < 1e-9);
+
+        // Shifting an atom by the skewed c vector changes nothing
+        let c = cell.vectors()[2];
+        for k in 0..3 {
+            pos[4 + k] += c[k];
+        }
+        assert!((ff.energy(&pos).total() - e_all.total()).abs() < 1e-3);
+
+        let h = 1e-3;
+        for k in [0, 5, 13] {
+            pos[k] += h;
+            let e_plus = ff.energy(&pos).total();
+            pos[k] -= 2.0 * h;
+            let e_minus = ff.energy(&pos).total();
+            pos[k] += h;
+            let numeric = -(e_plus - e_minus) / (2.0 * h as f64);
+            assert!((numeric - f_all[k] as f64).abs() < 5e-2, "{}: {} vs {}", k, numeric, f_all[k]);
+        }
+    }
+
+    #[test]
+    fn test_virial_matches_strain_derivative() {
+        let atoms = vec![atom(1.0, 8, -0.8), atom(2.2, 1, 0.4), atom(13.5, 1, 0.4), atom(7.0, 11, 1.0), atom(9.5, 17, -1.0)];
+        let config = ForceFieldConfig { pme: Some(PmeConfig::default()), cutoff: 7.0, switch_distance: Some(6.0), ..Default::default() };
+        let mut ff = ForceField::from_atoms(config, &atoms);
+        let lengths = [15.5f32, 15.5, 15.5];
+        let pos: Vec<f32> = atoms
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| [a.coords[0], 1.0 + i as f32 * 2.1, 0.5 * i as f32, 1.0])
+            .collect();
+        let mut energy = |axis: usize, strain: f32| {
+            let mut scaled = lengths;
+            scaled[axis] *= 1.0 + strain;
+            ff.set_box(Some(SimulationBox::orthorhombic(scaled)));
+            let mut p = pos.clone();
+            for atom in p.chunks_exact_mut(4) {
+                atom[axis] *= 1.0 + strain;
+            }
+            ff.energy(&p).total()
+        };
+        let h = 1e-3;
+        let numeric: Vec<f64> = (0..3).map(|axis| -(energy(axis, h) - energy(axis, -h)) / (2.0 * h as f64)).collect();
+
+        ff.set_box(Some(SimulationBox::orthorhombic(lengths)));
+        let mut list = ff.neighbor_list();
+        list.update(&pos, |i, j| ff.is_excluded(i, j));
+        let mut forces = vec![0.0; pos.len()];
+        let mut virial = Virial::default();
+        ff.compute_with_list_and_virial(&pos, &mut forces, &list, &mut virial);
+        for (axis, numeric) in numeric.iter().enumerate() {
+            let analytic = virial.0[axis][axis];
+            assert!((numeric - analytic).abs() < 1e-2 * numeric.abs().max(1.0), "{}: {} vs {}", axis, numeric, analytic);
+        }
+    }
+
+    #[test]
+    fn test_cutoff_and_exclusions() {
+        let atoms = vec![atom(0.0, 6, 1.0), atom(1.5, 6, -1.0), atom(20.0, 6, 1.0)];
+        let ff = ForceField::from_atoms(ForceFieldConfig::default(), &atoms);
+        assert!(ff.is_excluded(1, 0));
+        let pos: Vec<f32> = atoms
+            .iter()
+            .flat_map(|a| [a.coords[0], a.coords[1], a.coords[2], 1.0])
+            .collect();
+        assert_eq!(ff.energy(&pos).total(), 0.0);
+    }
+}
@@ -0,0 +1,182 @@
+//! # Frame Stream - Asynchronous GPU→Host Frame Transfer
+//! Copies frames (trajectory, analysis snapshots) off the GPU without
+//! stalling the integration kernels. A frame is first snapshotted on the
+//! device, in order with the step kernels on the engine's compute stream, then a
+//! dedicated non-blocking stream moves the snapshot into page-locked host
+//! memory while the next steps run. Two slots are used in turn, so one
+//! frame can be in flight while the previous one is consumed.
+//!
+//! Requires the `cuda` feature. All calls expect the owning context to be
+//! current on the calling thread.
+
+use cudarc::driver::sys as cuda_sys;
+use prism_core::PrismError;
+use std::ffi::c_void;
+
+/// Frames in flight at once with full double buffering
+pub const FRAME_SLOTS: usize = 2;
+
+/// What a captured frame is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRequest {
+    pub step: u64,
+    pub trajectory: bool,
+    pub analysis: bool,
+}
+
+#[derive(Debug)]
+struct Slot {
+    /// Device snapshot taken on the compute stream (owned by the caller)
+    snapshot: u64,
+    /// Page-locked destination of the host copy
+    host: *mut f32,
+    /// Snapshot written (recorded on the compute stream)
+    captured: cuda_sys::CUevent,
+    /// Host copy finished (recorded on the copy stream)
+    copied: cuda_sys::CUevent,
+    pending: Option<FrameRequest>,
+}
+
+/// Double-buffered, pinned-memory device→host frame transfers
+#[derive(Debug)]
+pub struct FrameStream {
+    stream: cuda_sys::CUstream,
+    slots: Vec<Slot>,
+    /// Slot taken by the next capture
+    next: usize,
+    len: usize,
+}
+
+// The raw handles belong to the context the stream was created in, which
+// the engine binds before using them
+unsafe impl Send for FrameStream {}
+
+impl Slot {
+    fn allocate(&mut self, bytes: usize) -> Result<(), PrismError> {
+        let flags = cuda_sys::CUevent_flags::CU_EVENT_DISABLE_TIMING as u32;
+        unsafe {
+            check(cuda_sys::cuEventCreate(&mut self.captured, flags), "cuEventCreate")?;
+            check(cuda_sys::cuEventCreate(&mut self.copied, flags), "cuEventCreate")?;
+            let mut host: *mut c_void = std::ptr::null_mut();
+            check(cuda_sys::cuMemAllocHost_v2(&mut host, bytes), "alloc_pinned_frame")?;
+            self.host = host as *mut f32;
+        }
+        Ok(())
+    }
+}
+
+fn check(res: cuda_sys::CUresult, op: &str) -> Result<(), PrismError> {
+    if res == cuda_sys::CUresult::CUDA_SUCCESS {
+        Ok(())
+    } else {
+        Err(PrismError::gpu(op, format!("{:?}", res)))
+    }
+}
+
+impl FrameStream {
+    /// Stream and slots for frames of `len` floats, one slot per device
+    /// snapshot buffer of at least `len` floats (usually [`FRAME_SLOTS`])
+    pub fn new(len: usize, snapshots: &[u64]) -> Result<Self, PrismError> {
+        if snapshots.is_empty() {
+            return Err(PrismError::Internal("Frame stream needs a snapshot buffer".into()));
+        }
+        let mut frames = Self { stream: std::ptr::null_mut(), slots: Vec::with_capacity(snapshots.len()), next: 0, len };
+        let bytes = frames.bytes();
+        unsafe {
+            check(
+                cuda_sys::cuStreamCreate(&mut frames.stream, cuda_sys::CUstream_flags::CU_STREAM_NON_BLOCKING as u32),
+                "cuStreamCreate",
+            )?;
+            for &snapshot in snapshots {
+                let mut slot = Slot {
+                    snapshot,
+                    host: std::ptr::null_mut(),
+                    captured: std::ptr::null_mut(),
+                    copied: std::ptr::null_mut(),
+                    pending: None,
+                };
+                // Kept on failure too, so `Drop` releases whatever was allocated
+                let result = slot.allocate(bytes);
+                frames.slots.push(slot);
+                result?;
+            }
+        }
+        Ok(frames)
+    }
+
+    fn bytes(&self) -> usize {
+        self.len * std::mem::size_of::<f32>()
+    }
+
+    /// Whether the next capture has to wait for [`Self::take_oldest`]
+    pub fn is_full(&self) -> bool {
+        self.slots[self.next].pending.is_some()
+    }
+
+    /// Snapshot `source` after the work queued so far on `compute` and start
+    /// copying it to the host. The slot must be free (see [`Self::is_full`]).
+    pub fn capture(&mut self, source: u64, compute: cuda_sys::CUstream, request: FrameRequest) -> Result<(), PrismError> {
+        if self.is_full() {
+            return Err(PrismError::Internal("Frame stream slot still in flight".into()));
+        }
+        let bytes = self.bytes();
+        let slot = &mut self.slots[self.next];
+        unsafe {
+            // The device copy is ordered with the step kernels, so the next
+            // steps cannot overwrite the positions before they are captured
+            check(cuda_sys::cuMemcpyDtoDAsync_v2(slot.snapshot, source, bytes, compute), "frame_snapshot")?;
+            check(cuda_sys::cuEventRecord(slot.captured, compute), "cuEventRecord")?;
+            check(cuda_sys::cuStreamWaitEvent(self.stream, slot.captured, 0), "cuStreamWaitEvent")?;
+            check(
+                cuda_sys::cuMemcpyDtoHAsync_v2(slot.host as *mut c_void, slot.snapshot, bytes, self.stream),
+                "frame_download",
+            )?;
+            check(cuda_sys::cuEventRecord(slot.copied, self.stream), "cuEventRecord")?;
+        }
+        slot.pending = Some(request);
+        self.next = (self.next + 1) % self.slots.len();
+        Ok(())
+    }
+
+    /// Wait for the oldest frame in flight and return it; the slot is free
+    /// again once the returned borrow ends. `None` when nothing is pending.
+    pub fn take_oldest(&mut self) -> Result<Option<(FrameRequest, &[f32])>, PrismError> {
+        let slots = self.slots.len();
+        let Some(index) = (0..slots).map(|k| (self.next + k) % slots).find(|&k| self.slots[k].pending.is_some()) else {
+            return Ok(None);
+        };
+        let len = self.len;
+        let slot = &mut self.slots[index];
+        unsafe {
+            check(cuda_sys::cuEventSynchronize(slot.copied), "frame_sync")?;
+        }
+        let request = slot.pending.take().expect("slot is pending");
+        // SAFETY: `host` holds `len` floats and the copy into it has completed
+        let positions = unsafe { std::slice::from_raw_parts(slot.host, len) };
+        Ok(Some((request, positions)))
+    }
+}
+
+impl Drop for FrameStream {
+    fn drop(&mut self) {
+        unsafe {
+            // Copies still in flight must finish before their memory is freed
+            if !self.stream.is_null() {
+                let _ = cuda_sys::cuStreamSynchronize(self.stream);
+            }
+            for slot in &self.slots {
+                if !slot.host.is_null() {
+                    let _ = cuda_sys::cuMemFreeHost(slot.host as *mut c_void);
+                }
+                for event in [slot.captured, slot.copied] {
+                    if !event.is_null() {
+                        let _ = cuda_sys::cuEventDestroy_v2(event);
+                    }
+                }
+            }
+            if !self.stream.is_null() {
+                let _ = cuda_sys::cuStreamDestroy_v2(self.stream);
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! # GPU Timing - Per-Phase Kernel Time and NVTX Ranges
+//! Brackets phases of the CUDA path (integration batches, frame snapshots,
+//! nonbonded evaluations) with CUDA events on the stream doing the work, so
+//! the measured time is device time rather than launch overhead. Elapsed
+//! times are read back once the stop event has completed, without stalling
+//! the stream, and summed per phase for the run telemetry.
+//!
+//! With the `nvtx` feature every phase is also an NVTX range of the same
+//! name, which Nsight Systems shows on the CPU timeline above the kernels
+//! it launched.
+//!
+//! Requires the `cuda` feature.
+
+use cudarc::driver::sys as cuda_sys;
+use cudarc::driver::CudaContext;
+use prism_core::PrismError;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Spans in flight before the oldest is waited for
+const MAX_PENDING: usize = 64;
+
+/// Device time summed over the spans of one phase
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PhaseTiming {
+    pub calls: u64,
+    pub total_ms: f64,
+}
+
+/// NVTX range open until dropped (nothing without the `nvtx` feature)
+#[derive(Debug)]
+pub struct NvtxRange {
+    #[cfg(feature = "nvtx")]
+    _range: cudarc::nvtx::safe::Range,
+}
+
+/// Open an NVTX range named `name`
+pub fn nvtx_range(name: &str) -> NvtxRange {
+    #[cfg(not(feature = "nvtx"))]
+    let _ = name;
+    NvtxRange {
+        #[cfg(feature = "nvtx")]
+        _range: cudarc::nvtx::safe::scoped_range(name),
+    }
+}
+
+/// A phase started with [`GpuTimer::begin`]
+#[derive(Debug)]
+pub struct Span {
+    phase: &'static str,
+    stream: cuda_sys::CUstream,
+    start: cuda_sys::CUevent,
+    _range: NvtxRange,
+}
+
+/// CUDA event timer of the phases of a run
+#[derive(Debug, Default)]
+pub struct GpuTimer {
+    /// Context the events were created in, kept alive to destroy them
+    ctx: Option<Arc<CudaContext>>,
+    free: Vec<cuda_sys::CUevent>,
+    /// `(phase, start, stop)` not read back yet, oldest first
+    pending: Vec<(&'static str, cuda_sys::CUevent, cuda_sys::CUevent)>,
+    phases: BTreeMap<&'static str, PhaseTiming>,
+}
+
+// Events belong to `ctx`, which is bound before they are used
+unsafe impl Send for GpuTimer {}
+
+fn check(res: cuda_sys::CUresult, op: &str) -> Result<(), PrismError> {
+    if res == cuda_sys::CUresult::CUDA_SUCCESS {
+        Ok(())
+    } else {
+        Err(PrismError::gpu(op, format!("{:?}", res)))
+    }
+}
+
+impl GpuTimer {
+    /// Start `phase` on `stream` (null = legacy stream) of `ctx`
+    pub fn begin(&mut self, ctx: &Arc<CudaContext>, phase: &'static str, stream: cuda_sys::CUstream) -> Result<Span, PrismError> {
+        let range = nvtx_range(phase);
+        if self.ctx.as_ref().is_some_and(|own| !Arc::ptr_eq(own, ctx)) {
+            // Events cannot be recorded on another context's streams
+            self.release();
+        }
+        ctx.bind_to_thread().map_err(|e| PrismError::gpu("bind_context", format!("{:?}", e)))?;
+        self.ctx.get_or_insert_with(|| ctx.clone());
+        let start = self.event()?;
+        unsafe { check(cuda_sys::cuEventRecord(start, stream), "cuEventRecord")? };
+        Ok(Span { phase, stream, start, _range: range })
+    }
+
+    /// Stop `span` after the work queued on its stream so far
+    pub fn end(&mut self, span: Span) -> Result<(), PrismError> {
+        let stop = self.event()?;
+        unsafe { check(cuda_sys::cuEventRecord(stop, span.stream), "cuEventRecord")? };
+        self.pending.push((span.phase, span.start, stop));
+        self.collect(false)?;
+        if self.pending.len() > MAX_PENDING {
+            let (phase, start, stop) = self.pending.remove(0);
+            self.read(phase, start, stop, true)?;
+        }
+        Ok(())
+    }
+
+    /// Per-phase totals so far, after waiting for the spans still in flight;
+    /// resets the timer
+    pub fn take(&mut self) -> Result<BTreeMap<&'static str, PhaseTiming>, PrismError> {
+        self.collect(true)?;
+        Ok(std::mem::take(&mut self.phases))
+    }
+
+    fn event(&mut self) -> Result<cuda_sys::CUevent, PrismError> {
+        if let Some(event) = self.free.pop() {
+            return Ok(event);
+        }
+        let mut event = std::ptr::null_mut();
+        unsafe { check(cuda_sys::cuEventCreate(&mut event, cuda_sys::CUevent_flags::CU_EVENT_DEFAULT as u32), "cuEventCreate")? };
+        Ok(event)
+    }
+
+    /// Read back pending spans in order, stopping at the first one still
+    /// running unless `wait`
+    fn collect(&mut self, wait: bool) -> Result<(), PrismError> {
+        while let Some(&(phase, start, stop)) = self.pending.first() {
+            if !self.read(phase, start, stop, wait)? {
+                break;
+            }
+            self.pending.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Add the span's time to its phase; `false` if it has not completed
+    fn read(&mut self, phase: &'static str, start: cuda_sys::CUevent, stop: cuda_sys::CUevent, wait: bool) -> Result<bool, PrismError> {
+        unsafe {
+            if wait {
+                check(cuda_sys::cuEventSynchronize(stop), "cuEventSynchronize")?;
+            } else if cuda_sys::cuEventQuery(stop) != cuda_sys::CUresult::CUDA_SUCCESS {
+                return Ok(false);
+            }
+            let mut ms = 0.0f32;
+            check(cuda_sys::cuEventElapsedTime(&mut ms, start, stop), "cuEventElapsedTime")?;
+            let timing = self.phases.entry(phase).or_default();
+            timing.calls += 1;
+            timing.total_ms += ms as f64;
+        }
+        self.free.extend([start, stop]);
+        Ok(true)
+    }
+
+    /// Destroy all events (pending spans are dropped)
+    fn release(&mut self) {
+        let Some(ctx) = self.ctx.take() else { return };
+        if ctx.bind_to_thread().is_err() {
+            return;
+        }
+        let pending = self.pending.drain(..).flat_map(|(_, start, stop)| [start, stop]);
+        for event in self.free.drain(..).chain(pending) {
+            unsafe {
+                let _ = cuda_sys::cuEventDestroy_v2(event);
+            }
+        }
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Telemetry entry of a per-phase breakdown: calls, total and mean time
+/// and share of the summed device time per phase
+pub fn breakdown_json(phases: &BTreeMap<&'static str, PhaseTiming>) -> serde_json::Value {
+    let total: f64 = phases.values().map(|p| p.total_ms).sum();
+    let entries: serde_json::Map<String, serde_json::Value> = phases
+        .iter()
+        .map(|(&phase, timing)| {
+            let value = serde_json::json!({
+                "calls": timing.calls,
+                "total_ms": timing.total_ms,
+                "mean_ms": timing.total_ms / timing.calls.max(1) as f64,
+                "share": if total > 0.0 { timing.total_ms / total } else { 0.0 },
+            });
+            (phase.to_string(), value)
+        })
+        .collect();
+    serde_json::json!({ "total_ms": total, "phases": entries })
+}
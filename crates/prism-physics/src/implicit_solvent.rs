@@ -0,0 +1,558 @@
+//! # Generalized Born Implicit Solvent - OBC GB/SA
+//! Polar solvation from the Onufriev-Bashford-Case Born radii (OBC1/OBC2,
+//! HCT pairwise descreening) and nonpolar solvation from the ACE surface
+//! area approximation. Intrinsic radii follow mbondi2, screening factors the
+//! AMBER HCT set. All pairs are evaluated without cutoff or periodicity.
+//! An optional implicit membrane (HDGB-like slab normal to z) lowers the
+//! solvent dielectric towards the hydrophobic core and fades the nonpolar
+//! surface term out inside it, so charges are pushed out of the slab and
+//! hydrophobic surface is drawn in.
+//! Units: Angstrom, kcal/mol, elementary charge.
+
+use crate::force_field::COULOMB_CONSTANT;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Born radius rescaling variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GbModel {
+    /// OBC model I (igb=2): α=0.8, β=0, γ=2.909125
+    Obc1,
+    /// OBC model II (igb=5): α=1.0, β=0.8, γ=4.85
+    Obc2,
+}
+
+impl GbModel {
+    /// Rescaling coefficients (α, β, γ)
+    pub fn coefficients(self) -> (f64, f64, f64) {
+        match self {
+            GbModel::Obc1 => (0.8, 0.0, 2.909125),
+            GbModel::Obc2 => (1.0, 0.8, 4.85),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImplicitSolventConfig {
+    pub model: GbModel,
+    /// Relative dielectric of the solvent (the solute uses `ForceFieldConfig::dielectric`)
+    pub solvent_dielectric: f32,
+    /// Nonpolar surface tension (kcal/mol/Å²); 0 disables the SA term
+    pub surface_tension: f32,
+    /// Solvent probe radius (Å) for the SA term
+    pub probe_radius: f32,
+    /// Offset subtracted from the intrinsic radii (Å)
+    pub dielectric_offset: f32,
+    /// Implicit membrane slab; `None` = homogeneous solvent
+    pub membrane: Option<MembraneConfig>,
+}
+
+impl Default for ImplicitSolventConfig {
+    fn default() -> Self {
+        Self {
+            model: GbModel::Obc2,
+            solvent_dielectric: 78.5,
+            surface_tension: 0.0054,
+            probe_radius: 1.4,
+            dielectric_offset: 0.09,
+            membrane: None,
+        }
+    }
+}
+
+/// Implicit membrane slab normal to z. The water fraction follows the IMM1
+/// profile `w = uⁿ / (1 + uⁿ)`, `u = |z - center| / (thickness / 2)`: 0 at
+/// the mid-plane, ½ at the interfaces and 1 in bulk water. The solvent
+/// dielectric seen by an atom is `ε_m + (ε_s - ε_m) w` and its surface
+/// tension is scaled by `w`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MembraneConfig {
+    /// Hydrophobic thickness (Å)
+    pub thickness: f32,
+    /// Height of the mid-plane (Å)
+    pub center: f32,
+    /// Dielectric of the hydrophobic core
+    pub dielectric: f32,
+    /// Profile exponent n; larger values sharpen the interfaces
+    pub steepness: f32,
+}
+
+impl Default for MembraneConfig {
+    fn default() -> Self {
+        Self {
+            thickness: 30.0,
+            center: 0.0,
+            dielectric: 2.0,
+            steepness: 10.0,
+        }
+    }
+}
+
+impl MembraneConfig {
+    /// Water fraction at height `z` and its derivative along z
+    pub fn water_fraction(&self, z: f64) -> (f64, f64) {
+        let half = 0.5 * self.thickness as f64;
+        let n = self.steepness as f64;
+        let u = (z - self.center as f64).abs() / half;
+        let un = u.powf(n);
+        let du_dz = (z - self.center as f64).signum() / half;
+        let w = un / (1.0 + un);
+        let dw = n * u.powf(n - 1.0) / (1.0 + un).powi(2) * du_dz;
+        (w, dw)
+    }
+}
+
+/// Solvent environment of one atom: `τ = 1/ε_in - 1/ε_out`, the water
+/// fraction, and their derivatives along z
+#[derive(Debug, Clone, Copy)]
+struct Environment {
+    tau: f64,
+    dtau: f64,
+    water: f64,
+    dwater: f64,
+}
+
+/// Per-atom GB parameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GbParams {
+    /// Intrinsic (Born) radius (Å)
+    pub radius: f32,
+    /// HCT descreening scale factor
+    pub scale: f32,
+}
+
+impl GbParams {
+    /// mbondi2 radius and HCT scale by element; hydrogens bonded to
+    /// nitrogen get the larger 1.3 Å radius.
+    pub fn mbondi2(element: u8, bonded_to_nitrogen: bool) -> Self {
+        let (radius, scale) = match element {
+            1 if bonded_to_nitrogen => (1.3, 0.85),
+            1 => (1.2, 0.85),
+            6 => (1.7, 0.72),
+            7 => (1.55, 0.79),
+            8 => (1.5, 0.85),
+            9 => (1.5, 0.88),
+            15 => (1.85, 0.86),
+            16 => (1.8, 0.96),
+            _ => (1.5, 0.8),
+        };
+        Self { radius, scale }
+    }
+}
+
+/// Solvation energy terms (kcal/mol)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SolvationEnergy {
+    pub polar: f64,
+    pub nonpolar: f64,
+}
+
+impl SolvationEnergy {
+    pub fn total(&self) -> f64 {
+        self.polar + self.nonpolar
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneralizedBorn {
+    config: ImplicitSolventConfig,
+    solute_dielectric: f32,
+    params: Vec<GbParams>,
+}
+
+/// HCT descreening integral of atom j (scaled radius `s`) seen by atom i
+/// (offset radius `rho`) at distance `r`, and its derivative with respect to `r`.
+fn descreen(rho: f64, s: f64, r: f64) -> (f64, f64) {
+    if rho >= r + s {
+        return (0.0, 0.0);
+    }
+    let u = 1.0 / (r + s);
+    let du = -u * u;
+    let (l, dl) = if (r - s).abs() > rho {
+        let l = 1.0 / (r - s).abs();
+        (l, -(r - s).signum() * l * l)
+    } else {
+        (1.0 / rho, 0.0)
+    };
+    let (l2, u2) = (l * l, u * u);
+    let ratio = (u / l).ln();
+    let mut term = l - u + 0.25 * r * (u2 - l2) + 0.5 * ratio / r + 0.25 * s * s / r * (l2 - u2);
+    let mut dterm = dl - du + 0.25 * (u2 - l2) + 0.5 * r * (u * du - l * dl)
+        - 0.5 * ratio / (r * r)
+        + 0.5 / r * (du / u - dl / l)
+        - 0.25 * s * s / (r * r) * (l2 - u2)
+        + 0.5 * s * s / r * (l * dl - u * du);
+    // Atom i buried inside the descreening sphere of j
+    if rho < s - r {
+        term += 2.0 * (1.0 / rho - l);
+        dterm -= 2.0 * dl;
+    }
+    (term, dterm)
+}
+
+impl GeneralizedBorn {
+    pub fn new(
+        config: ImplicitSolventConfig,
+        solute_dielectric: f32,
+        params: Vec<GbParams>,
+    ) -> Self {
+        Self {
+            config,
+            solute_dielectric,
+            params,
+        }
+    }
+
+    /// mbondi2 parameters from elements and bonds (used to find N-H hydrogens)
+    pub fn from_elements(
+        config: ImplicitSolventConfig,
+        solute_dielectric: f32,
+        elements: &[u8],
+        bonds: impl IntoIterator<Item = (u32, u32)>,
+    ) -> Self {
+        let mut h_on_n = vec![false; elements.len()];
+        for (i, j) in bonds {
+            let (i, j) = (i as usize, j as usize);
+            if i < elements.len() && j < elements.len() {
+                if elements[i] == 1 && elements[j] == 7 {
+                    h_on_n[i] = true;
+                }
+                if elements[j] == 1 && elements[i] == 7 {
+                    h_on_n[j] = true;
+                }
+            }
+        }
+        let params = elements
+            .iter()
+            .zip(&h_on_n)
+            .map(|(&e, &n)| GbParams::mbondi2(e, n))
+            .collect();
+        Self::new(config, solute_dielectric, params)
+    }
+
+    pub fn config(&self) -> &ImplicitSolventConfig {
+        &self.config
+    }
+
+    pub fn params(&self) -> &[GbParams] {
+        &self.params
+    }
+
+    /// Born radii (Å) for Float4-stride positions, and the chain factors
+    /// `dB_i/dI_i` used for forces
+    fn radii_and_chain(&self, positions: &[f32]) -> (Vec<f64>, Vec<f64>) {
+        let n = self.params.len().min(positions.len() / 4);
+        let offset = self.config.dielectric_offset as f64;
+        let (alpha, beta, gamma) = self.config.model.coefficients();
+        let mut radii = vec![0.0; n];
+        let mut chain = vec![0.0; n];
+        for i in 0..n {
+            let radius = self.params[i].radius as f64;
+            let rho = radius - offset;
+            let mut integral = 0.0;
+            for j in (0..n).filter(|&j| j != i) {
+                let r = distance(positions, i, j);
+                let s = (self.params[j].radius as f64 - offset) * self.params[j].scale as f64;
+                integral += descreen(rho, s, r).0;
+            }
+            let psi = 0.5 * integral * rho;
+            let tanh = (alpha * psi - beta * psi * psi + gamma * psi.powi(3)).tanh();
+            let b = 1.0 / (1.0 / rho - tanh / radius);
+            radii[i] = b;
+            // dB/dψ · dψ/d(Σ term) with ψ = ½ ρ Σ term
+            chain[i] = b * b * (1.0 - tanh * tanh) / radius
+                * (alpha - 2.0 * beta * psi + 3.0 * gamma * psi * psi)
+                * 0.5
+                * rho;
+        }
+        (radii, chain)
+    }
+
+    /// Per-atom solvent environment: the bulk solvent everywhere, or the
+    /// membrane profile at each atom's height
+    fn environment(&self, positions: &[f32], n: usize) -> Vec<Environment> {
+        let tau_in = 1.0 / self.solute_dielectric as f64;
+        let solvent = self.config.solvent_dielectric as f64;
+        (0..n)
+            .map(|i| match &self.config.membrane {
+                Some(membrane) => {
+                    let (water, dwater) = membrane.water_fraction(positions[i * 4 + 2] as f64);
+                    let core = membrane.dielectric as f64;
+                    let epsilon = core + (solvent - core) * water;
+                    Environment {
+                        tau: tau_in - 1.0 / epsilon,
+                        dtau: (solvent - core) * dwater / (epsilon * epsilon),
+                        water,
+                        dwater,
+                    }
+                }
+                None => Environment {
+                    tau: tau_in - 1.0 / solvent,
+                    dtau: 0.0,
+                    water: 1.0,
+                    dwater: 0.0,
+                },
+            })
+            .collect()
+    }
+
+    /// Effective Born radii (Å)
+    pub fn born_radii(&self, positions: &[f32]) -> Vec<f64> {
+        self.radii_and_chain(positions).0
+    }
+
+    /// Solvation energy, adding forces (kcal/mol/Å) when given
+    pub fn compute(
+        &self,
+        positions: &[f32],
+        charges: &[f32],
+        mut forces: Option<&mut [f32]>,
+    ) -> SolvationEnergy {
+        let n = self
+            .params
+            .len()
+            .min(positions.len() / 4)
+            .min(charges.len());
+        let (radii, chain) = self.radii_and_chain(positions);
+        let environment = self.environment(positions, n);
+        let mut energy = SolvationEnergy::default();
+        // dE/dB_i, accumulated from every term before the descreening chain rule
+        let mut de_db = vec![0.0; n];
+        // Explicit dE/dz_i of the membrane profile
+        let mut de_dz = vec![0.0; n];
+
+        for i in 0..n {
+            let qi = charges[i] as f64;
+            let env = environment[i];
+            // Self term ½ pre q²/B
+            let pre = -COULOMB_CONSTANT * env.tau;
+            energy.polar += 0.5 * pre * qi * qi / radii[i];
+            de_db[i] -= 0.5 * pre * qi * qi / (radii[i] * radii[i]);
+            de_dz[i] -= 0.5 * COULOMB_CONSTANT * env.dtau * qi * qi / radii[i];
+
+            let sigma = self.config.surface_tension as f64;
+            if sigma > 0.0 {
+                let radius = self.params[i].radius as f64;
+                let ratio = (radius / radii[i]).powi(6);
+                let e_water =
+                    4.0 * PI * sigma * (radius + self.config.probe_radius as f64).powi(2) * ratio;
+                let e = e_water * env.water;
+                energy.nonpolar += e;
+                de_db[i] -= 6.0 * e / radii[i];
+                de_dz[i] += e_water * env.dwater;
+            }
+
+            for j in (i + 1)..n {
+                let qq = qi * charges[j] as f64;
+                if qq == 0.0 {
+                    continue;
+                }
+                let d = delta(positions, i, j);
+                let r2 = d.iter().map(|v| v * v).sum::<f64>();
+                let bb = radii[i] * radii[j];
+                let exp = (-r2 / (4.0 * bb)).exp();
+                let f2 = r2 + bb * exp;
+                let f = f2.sqrt();
+                // Pairs see the mean τ of their atoms
+                let pre = -COULOMB_CONSTANT * 0.5 * (env.tau + environment[j].tau);
+                energy.polar += pre * qq / f;
+                de_dz[i] -= 0.5 * COULOMB_CONSTANT * env.dtau * qq / f;
+                de_dz[j] -= 0.5 * COULOMB_CONSTANT * environment[j].dtau * qq / f;
+                // dE/df · df/dB
+                let de_df = -pre * qq / f2;
+                let df_db = exp * (1.0 + r2 / (4.0 * bb)) / (2.0 * f);
+                de_db[i] += de_df * df_db * radii[j];
+                de_db[j] += de_df * df_db * radii[i];
+                if let Some(forces) = forces.as_deref_mut() {
+                    // (dE/df · df/dr) / r
+                    let de_dr_over_r = de_df * (1.0 - 0.25 * exp) / f;
+                    apply(forces, i, j, &d, de_dr_over_r);
+                }
+            }
+        }
+
+        if let Some(forces) = forces {
+            let offset = self.config.dielectric_offset as f64;
+            for (i, de_dz) in de_dz.iter().enumerate() {
+                forces[i * 4 + 2] -= *de_dz as f32;
+            }
+            for i in 0..n {
+                let factor = de_db[i] * chain[i];
+                if factor == 0.0 {
+                    continue;
+                }
+                let rho = self.params[i].radius as f64 - offset;
+                for j in (0..n).filter(|&j| j != i) {
+                    let d = delta(positions, i, j);
+                    let r = d.iter().map(|v| v * v).sum::<f64>().sqrt();
+                    let s = (self.params[j].radius as f64 - offset) * self.params[j].scale as f64;
+                    let dterm = descreen(rho, s, r).1;
+                    if dterm != 0.0 {
+                        apply(forces, i, j, &d, factor * dterm / r);
+                    }
+                }
+            }
+        }
+        energy
+    }
+}
+
+fn delta(positions: &[f32], i: usize, j: usize) -> [f64; 3] {
+    [0, 1, 2].map(|k| (positions[j * 4 + k] - positions[i * 4 + k]) as f64)
+}
+
+fn distance(positions: &[f32], i: usize, j: usize) -> f64 {
+    delta(positions, i, j)
+        .iter()
+        .map(|v| v * v)
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Add the pair force for `dE/dr / r` along `d = x_j - x_i`
+fn apply(forces: &mut [f32], i: usize, j: usize, d: &[f64; 3], de_dr_over_r: f64) {
+    for k in 0..3 {
+        let fk = (de_dr_over_r * d[k]) as f32;
+        forces[i * 4 + k] += fk;
+        forces[j * 4 + k] -= fk;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_ion_born_energy() {
+        let gb = GeneralizedBorn::new(
+            ImplicitSolventConfig {
+                surface_tension: 0.0,
+                ..Default::default()
+            },
+            1.0,
+            vec![GbParams {
+                radius: 2.0,
+                scale: 0.8,
+            }],
+        );
+        let pos = [0.0, 0.0, 0.0, 1.0];
+        let e = gb.compute(&pos, &[1.0], None);
+        // Isolated ion: B = ρ, E = -½ k (1 - 1/ε) q² / ρ
+        let expected = -0.5 * COULOMB_CONSTANT * (1.0 - 1.0 / 78.5) / 1.91;
+        assert!(
+            (e.polar - expected).abs() < 1e-6,
+            "{} vs {}",
+            e.polar,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_forces_match_finite_difference() {
+        let elements = [8u8, 1, 1, 6, 7, 1];
+        let gb = GeneralizedBorn::from_elements(
+            ImplicitSolventConfig::default(),
+            1.0,
+            &elements,
+            [(4, 5)],
+        );
+        assert_eq!(gb.params()[5].radius, 1.3);
+        let charges = [-0.8, 0.4, 0.4, 0.3, -0.5, 0.2];
+        let mut pos = vec![
+            0.0, 0.0, 0.0, 1.0, //
+            0.96, 0.0, 0.0, 1.0, //
+            -0.24, 0.93, 0.0, 1.0, //
+            2.8, 1.1, 0.4, 1.0, //
+            4.1, 0.2, -0.6, 1.0, //
+            4.3, -0.7, -1.0, 1.0,
+        ];
+        let mut forces = vec![0.0; pos.len()];
+        gb.compute(&pos, &charges, Some(&mut forces));
+
+        let h = 1e-3;
+        for k in [0, 5, 13, 16, 22] {
+            pos[k] += h;
+            let e_plus = gb.compute(&pos, &charges, None).total();
+            pos[k] -= 2.0 * h;
+            let e_minus = gb.compute(&pos, &charges, None).total();
+            pos[k] += h;
+            let numeric = -(e_plus - e_minus) / (2.0 * h as f64);
+            assert!(
+                (numeric - forces[k] as f64).abs() < 2e-2,
+                "{}: {} vs {}",
+                k,
+                numeric,
+                forces[k]
+            );
+        }
+    }
+
+    #[test]
+    fn test_membrane_core_destabilizes_ions() {
+        let config = ImplicitSolventConfig {
+            surface_tension: 0.0,
+            membrane: Some(MembraneConfig::default()),
+            ..Default::default()
+        };
+        let gb = GeneralizedBorn::new(
+            config,
+            1.0,
+            vec![GbParams {
+                radius: 2.0,
+                scale: 0.8,
+            }],
+        );
+        let at = |z: f32| gb.compute(&[0.0, 0.0, z, 1.0], &[1.0], None).polar;
+        // Mid-plane: ε = 2; bulk water far from the slab: ε = 78.5
+        let core = -0.5 * COULOMB_CONSTANT * (1.0 - 1.0 / 2.0) / 1.91;
+        let water = -0.5 * COULOMB_CONSTANT * (1.0 - 1.0 / 78.5) / 1.91;
+        assert!((at(0.0) - core).abs() < 1e-6);
+        assert!((at(60.0) - water).abs() < 1e-3);
+        assert!(at(0.0) > at(15.0) && at(15.0) > at(-30.0));
+
+        let (w, dw) = MembraneConfig::default().water_fraction(-15.0);
+        assert!((w - 0.5).abs() < 1e-12);
+        assert!(dw < 0.0);
+    }
+
+    #[test]
+    fn test_membrane_forces_match_finite_difference() {
+        let config = ImplicitSolventConfig {
+            membrane: Some(MembraneConfig {
+                center: 2.0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let gb = GeneralizedBorn::from_elements(config, 1.0, &[8, 1, 6, 7], [(0, 1)]);
+        let charges = [-0.6, 0.4, 0.3, -0.4];
+        // Straddling the upper interface at z = 17
+        let mut pos = vec![
+            0.0, 0.0, 15.5, 1.0, //
+            0.96, 0.0, 16.0, 1.0, //
+            2.8, 1.1, 17.4, 1.0, //
+            4.1, 0.2, 18.6, 1.0,
+        ];
+        let mut forces = vec![0.0; pos.len()];
+        gb.compute(&pos, &charges, Some(&mut forces));
+
+        let h = 1e-3;
+        for k in [0, 2, 6, 10, 13, 14] {
+            pos[k] += h;
+            let e_plus = gb.compute(&pos, &charges, None).total();
+            pos[k] -= 2.0 * h;
+            let e_minus = gb.compute(&pos, &charges, None).total();
+            pos[k] += h;
+            let numeric = -(e_plus - e_minus) / (2.0 * h as f64);
+            assert!(
+                (numeric - forces[k] as f64).abs() < 2e-2,
+                "{}: {} vs {}",
+                k,
+                numeric,
+                forces[k]
+            );
+        }
+    }
+}
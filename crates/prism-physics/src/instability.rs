@@ -0,0 +1,146 @@
+//! # Instability Recovery - Exploding Energies and NaN Coordinates
+//! Host integration checks every step for a potential energy that is no
+//! longer finite or has risen more than `max_energy_rise` per atom above
+//! the last stable snapshot, on top of the non-finite velocity and
+//! constraint failures the integrator reports itself (the
+//! [`PrismError::is_instability`] errors). Every `snapshot_interval` steps
+//! the engine keeps the positions and velocities of a stable step in
+//! memory; the [`InstabilityResponse`] decides whether the run aborts or
+//! continues from that snapshot. Each recovery is reported under
+//! `instability_recoveries` in the run telemetry.
+//!
+//! Observers and trajectory output see the repeated steps again, and bias
+//! potentials keep the state they accumulated before the failure. GPU runs
+//! are checked for a non-finite energy whenever their positions come back
+//! to the host and always abort.
+
+use prism_core::PrismError;
+use serde::{Deserialize, Serialize};
+
+/// What to do when the integration becomes unstable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstabilityResponse {
+    /// Fail the run with the instability error, logging the largest force
+    /// and velocity
+    #[default]
+    Abort,
+    /// Restore the snapshot and continue with half the timestep
+    HalveTimestep,
+    /// Restore the snapshot, minimize and continue
+    Reminimize,
+    /// Restore the snapshot and continue with fresh thermostat noise
+    Restore,
+}
+
+/// Instability detection and recovery of host dynamics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstabilityConfig {
+    pub response: InstabilityResponse,
+    /// Rise of the potential energy above the last snapshot (kcal/mol per
+    /// atom) treated as an explosion
+    pub max_energy_rise: f64,
+    /// Steps between snapshots of the last stable state
+    pub snapshot_interval: u64,
+    /// Recoveries per run before the instability aborts it anyway
+    pub max_recoveries: usize,
+}
+
+impl Default for InstabilityConfig {
+    fn default() -> Self {
+        Self {
+            response: InstabilityResponse::default(),
+            max_energy_rise: 100.0,
+            snapshot_interval: 1000,
+            max_recoveries: 3,
+        }
+    }
+}
+
+impl InstabilityConfig {
+    pub fn validate(&self) -> Result<(), PrismError> {
+        if self.max_energy_rise.is_nan() || self.max_energy_rise <= 0.0 {
+            return Err(PrismError::validation(format!(
+                "instability.max_energy_rise must be positive, got {}",
+                self.max_energy_rise
+            )));
+        }
+        if self.snapshot_interval == 0 {
+            return Err(PrismError::validation(
+                "instability.snapshot_interval must be at least 1",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One recovery from an instability
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstabilityRecovery {
+    /// Step at which the instability was detected
+    pub step: u64,
+    pub error: String,
+    pub response: InstabilityResponse,
+    /// Step of the snapshot the run continued from
+    pub restored_step: u64,
+    /// Timestep after the recovery (ps)
+    pub dt: f32,
+}
+
+/// Largest force and fastest atom of Float4-stride buffers, for the
+/// instability diagnostics
+pub(crate) fn diagnostics(velocities: &[f32], forces: &[f32]) -> String {
+    // Index and norm of the largest vector; NaN beats every number
+    let largest = |values: &[f32]| {
+        values
+            .chunks_exact(4)
+            .map(|v| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt())
+            .enumerate()
+            .reduce(|best, next| {
+                if (next.1.is_nan() && !best.1.is_nan()) || next.1 > best.1 {
+                    next
+                } else {
+                    best
+                }
+            })
+    };
+    match (largest(forces), largest(velocities)) {
+        (Some((f_atom, force)), Some((v_atom, speed))) => format!(
+            "largest force {} kcal/mol/Å on atom {}, fastest atom {} at {} Å/ps",
+            force, f_atom, v_atom, speed
+        ),
+        _ => "no atoms".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_validation() {
+        InstabilityConfig::default().validate().unwrap();
+        let config = InstabilityConfig {
+            snapshot_interval: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        let config = InstabilityConfig {
+            max_energy_rise: f64::NAN,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_diagnostics_name_the_worst_atoms() {
+        let velocities = [0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 5.0, 0.0];
+        let forces = [f32::NAN, 0.0, 0.0, 0.0, 3.0, 4.0, 0.0, 0.0];
+        assert_eq!(
+            diagnostics(&velocities, &forces),
+            "largest force NaN kcal/mol/Å on atom 0, fastest atom 1 at 5 Å/ps"
+        );
+        assert_eq!(diagnostics(&[], &[]), "no atoms");
+    }
+}
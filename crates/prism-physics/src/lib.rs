@@ -17,7 +17,61 @@ pub mod fields;
 pub mod materials;
 
 // Molecular Dynamics - PIMC/NLNM Solvers for protein structures
+pub mod alchemical;
+pub mod analysis;
+pub mod annealing;
+pub mod bonded;
+pub mod campaign;
+pub mod checkpoint;
+pub mod coarse_grain;
+pub mod collective_variables;
+pub mod constraints;
+pub mod elastic_network;
+pub mod energy_conservation;
+pub mod estimators;
+pub mod force_field;
+#[cfg(feature = "cuda")]
+pub mod frame_stream;
+#[cfg(feature = "cuda")]
+pub mod gpu_timing;
+#[cfg(feature = "rocm")]
+pub mod hip;
+pub mod implicit_solvent;
+pub mod instability;
+pub mod metadynamics;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod minimizer;
+pub mod ml_potential;
+pub mod mode_animation;
 pub mod molecular_dynamics;
+#[cfg(feature = "mpi")]
+pub mod mpi;
+pub mod neighbor_list;
+#[cfg(feature = "otel")]
+pub mod otlp;
+pub mod pimc;
+pub mod pipeline;
+pub mod pme;
+pub mod precision;
+pub mod pressure;
+pub mod qmmm;
+pub mod replica_exchange;
+pub mod restraints;
+pub mod resource_estimate;
+pub mod rng;
+pub mod rpmd;
+pub mod run_config;
+pub mod scheduler;
+pub mod simd;
+pub mod steered;
+#[cfg(feature = "websocket")]
+pub mod telemetry_stream;
+pub mod umbrella;
+pub mod units;
+#[cfg(feature = "wgpu")]
+pub mod webgpu;
+pub mod wgsl;
 
 /// CMA-ES (Covariance Matrix Adaptation Evolution Strategy) configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
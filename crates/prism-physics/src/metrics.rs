@@ -0,0 +1,262 @@
+//! # Metrics - Prometheus Endpoint for Long Runs
+//! Serves the flight recorder frames of a running engine as Prometheus
+//! gauges and counters, so cluster operators can watch step rate, energy,
+//! temperature, acceptance rate, gradient norm and VRAM usage in Grafana.
+//!
+//! The exporter drains the telemetry ring on its own background thread
+//! (see `prism_core::telemetry`), so the integration loop only ever pushes
+//! POD frames. `GET /metrics` returns the text exposition format and
+//! `GET /health` answers `OK` for liveness probes.
+//!
+//! Requires the `metrics` feature.
+
+use prism_core::telemetry::{self, TelemetryDrain, TelemetryFrame};
+use prism_core::PrismError;
+use prometheus::{Encoder, Gauge, IntCounter, IntGauge, Opts, Registry, TextEncoder};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Gauges and counters of one engine
+#[derive(Debug)]
+pub struct MdMetrics {
+    registry: Registry,
+    steps_total: IntCounter,
+    step_rate: Gauge,
+    energy: Gauge,
+    temperature: Gauge,
+    acceptance_rate: Gauge,
+    gradient_norm: Gauge,
+    vram_used_bytes: IntGauge,
+    vram_total_bytes: IntGauge,
+    /// Last frame seen, the reference of the step rate
+    last: Mutex<Option<TelemetryFrame>>,
+}
+
+fn registered<M: prometheus::core::Collector + Clone + 'static>(registry: &Registry, metric: prometheus::Result<M>) -> Result<M, PrismError> {
+    let metric = metric.map_err(|e| PrismError::Internal(format!("Invalid metric: {}", e)))?;
+    registry
+        .register(Box::new(metric.clone()))
+        .map_err(|e| PrismError::Internal(format!("Failed to register metric: {}", e)))?;
+    Ok(metric)
+}
+
+impl MdMetrics {
+    pub fn new() -> Result<Self, PrismError> {
+        let registry = Registry::new_custom(Some("prism_md".to_string()), None)
+            .map_err(|e| PrismError::Internal(format!("Failed to create metrics registry: {}", e)))?;
+        Ok(Self {
+            steps_total: registered(&registry, IntCounter::new("steps_total", "Integration steps completed"))?,
+            step_rate: registered(&registry, Gauge::new("step_rate", "Integration steps per second"))?,
+            energy: registered(&registry, Gauge::new("potential_energy_kcal_mol", "Potential energy of the last recorded step"))?,
+            temperature: registered(&registry, Gauge::new("temperature_kelvin", "Thermostat temperature of the last recorded step"))?,
+            acceptance_rate: registered(&registry, Gauge::new("acceptance_rate", "Monte Carlo acceptance rate (1 for dynamics)"))?,
+            gradient_norm: registered(&registry, Gauge::new("gradient_norm", "RMS force of the last recorded step (kcal/mol/Å)"))?,
+            vram_used_bytes: registered(&registry, IntGauge::with_opts(Opts::new("vram_used_bytes", "Device memory in use")))?,
+            vram_total_bytes: registered(&registry, IntGauge::with_opts(Opts::new("vram_total_bytes", "Device memory of the GPU")))?,
+            registry,
+            last: Mutex::new(None),
+        })
+    }
+
+    /// Update from a batch of frames, oldest first
+    pub fn observe(&self, frames: &[TelemetryFrame]) {
+        let Some(latest) = frames.last() else { return };
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let reference = last.or_else(|| frames.first().copied()).filter(|r| r.step <= latest.step);
+        if let Some(reference) = reference {
+            let steps = latest.step - reference.step;
+            self.steps_total.inc_by(steps);
+            let seconds = latest.timestamp_ns.saturating_sub(reference.timestamp_ns) as f64 * 1e-9;
+            if steps > 0 && seconds > 0.0 {
+                self.step_rate.set(steps as f64 / seconds);
+            }
+        }
+        self.energy.set(latest.energy as f64);
+        self.temperature.set(latest.temperature as f64);
+        self.acceptance_rate.set(latest.acceptance_rate as f64);
+        self.gradient_norm.set(latest.gradient_norm as f64);
+        *last = Some(*latest);
+    }
+
+    pub fn set_vram(&self, used_bytes: u64, total_bytes: u64) {
+        self.vram_used_bytes.set(used_bytes as i64);
+        self.vram_total_bytes.set(total_bytes as i64);
+    }
+
+    /// All metrics in the Prometheus text exposition format
+    pub fn export_text(&self) -> Result<String, PrismError> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| PrismError::Internal(format!("Failed to encode metrics: {}", e)))?;
+        String::from_utf8(buffer).map_err(|e| PrismError::Internal(format!("Metrics are not UTF-8: {}", e)))
+    }
+}
+
+/// Shared with the request handlers
+#[derive(Clone)]
+struct ServerState {
+    metrics: Arc<MdMetrics>,
+    #[cfg(feature = "cuda")]
+    vram: Option<Arc<cudarc::driver::CudaContext>>,
+}
+
+impl ServerState {
+    /// Refresh the VRAM gauges right before a scrape
+    #[cfg(feature = "cuda")]
+    fn refresh_vram(&self) {
+        let Some(ctx) = &self.vram else { return };
+        if let Err(e) = ctx.bind_to_thread() {
+            return log::debug!("VRAM query for metrics failed: {:?}", e);
+        }
+        match prism_gpu::memory::VramGuard::new(ctx.clone()).query_vram() {
+            Ok(info) => self.metrics.set_vram(info.used_bytes as u64, info.total_bytes as u64),
+            Err(e) => log::debug!("VRAM query for metrics failed: {:?}", e),
+        }
+    }
+}
+
+/// Running metrics endpoint; dropping it stops the server and the drain
+pub struct MetricsExporter {
+    metrics: Arc<MdMetrics>,
+    addr: SocketAddr,
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+    server: Option<JoinHandle<()>>,
+    _drain: TelemetryDrain,
+}
+
+impl MetricsExporter {
+    /// Serve `/metrics` on `addr` (port 0 picks a free port) and start
+    /// draining the telemetry ring into the metrics
+    pub fn start(addr: SocketAddr) -> Result<Self, PrismError> {
+        Self::launch(addr, |metrics| ServerState {
+            metrics,
+            #[cfg(feature = "cuda")]
+            vram: None,
+        })
+    }
+
+    /// Like [`MetricsExporter::start`], also reporting the memory of the
+    /// GPU behind `context`
+    #[cfg(feature = "cuda")]
+    pub fn start_with_vram(addr: SocketAddr, context: Arc<cudarc::driver::CudaContext>) -> Result<Self, PrismError> {
+        Self::launch(addr, |metrics| ServerState { metrics, vram: Some(context) })
+    }
+
+    fn launch(addr: SocketAddr, state: impl FnOnce(Arc<MdMetrics>) -> ServerState) -> Result<Self, PrismError> {
+        let metrics = Arc::new(MdMetrics::new()?);
+        let listener = std::net::TcpListener::bind(addr)
+            .map_err(|e| PrismError::config(format!("Cannot bind metrics endpoint {}: {}", addr, e)))?;
+        let addr = listener.local_addr().map_err(|e| PrismError::Internal(format!("Metrics endpoint address: {}", e)))?;
+        listener.set_nonblocking(true).map_err(|e| PrismError::Internal(format!("Metrics endpoint: {}", e)))?;
+
+        let sink = metrics.clone();
+        let drain = telemetry::spawn_drain(telemetry::DRAIN_INTERVAL, move |frames| sink.observe(frames))
+            .map_err(|e| PrismError::Internal(format!("Failed to start telemetry drain: {}", e)))?;
+
+        let app = axum::Router::new()
+            .route("/metrics", axum::routing::get(metrics_handler))
+            .route("/health", axum::routing::get(|| async { "OK" }))
+            .with_state(state(metrics.clone()));
+        let (shutdown, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = std::thread::Builder::new()
+            .name("prism-metrics".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                    Ok(runtime) => runtime,
+                    Err(e) => return log::error!("Metrics endpoint runtime failed: {}", e),
+                };
+                runtime.block_on(async move {
+                    let served = match tokio::net::TcpListener::from_std(listener) {
+                        Ok(listener) => axum::serve(listener, app)
+                            .with_graceful_shutdown(async {
+                                let _ = stopped.await;
+                            })
+                            .await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = served {
+                        log::error!("Metrics endpoint failed: {}", e);
+                    }
+                });
+            })
+            .map_err(|e| PrismError::Internal(format!("Failed to start metrics endpoint: {}", e)))?;
+
+        log::info!("📈 Metrics endpoint listening on http://{}/metrics", addr);
+        Ok(Self { metrics, addr, shutdown: Some(shutdown), server: Some(server), _drain: drain })
+    }
+
+    /// Bound address (with the actual port when started on port 0)
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn metrics(&self) -> &Arc<MdMetrics> {
+        &self.metrics
+    }
+}
+
+impl Drop for MetricsExporter {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+async fn metrics_handler(axum::extract::State(state): axum::extract::State<ServerState>) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    #[cfg(feature = "cuda")]
+    state.refresh_vram();
+    match state.metrics.export_text() {
+        Ok(text) => ([(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")], text).into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn frame(step: u64, seconds: f64, energy: f32) -> TelemetryFrame {
+        TelemetryFrame { step, timestamp_ns: (seconds * 1e9) as u64, energy, temperature: 300.0, acceptance_rate: 1.0, gradient_norm: 2.5 }
+    }
+
+    #[test]
+    fn test_observe_updates_rate_and_gauges() {
+        let metrics = MdMetrics::new().unwrap();
+        metrics.observe(&[frame(0, 0.0, -10.0), frame(100, 0.5, -12.0)]);
+        metrics.observe(&[frame(300, 1.5, -15.0)]);
+        metrics.set_vram(1 << 30, 8 << 30);
+
+        assert_eq!(metrics.steps_total.get(), 300);
+        assert!((metrics.step_rate.get() - 200.0).abs() < 1e-9);
+        let text = metrics.export_text().unwrap();
+        assert!(text.contains("prism_md_potential_energy_kcal_mol -15"));
+        assert!(text.contains("prism_md_temperature_kelvin 300"));
+        assert!(text.contains("prism_md_gradient_norm 2.5"));
+        assert!(text.contains("prism_md_vram_used_bytes 1073741824"));
+    }
+
+    #[test]
+    fn test_exporter_serves_metrics() {
+        let exporter = MetricsExporter::start(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+        let get = |path: &str| {
+            let mut stream = std::net::TcpStream::connect(exporter.addr()).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        assert!(get("/health").ends_with("OK"));
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("# TYPE prism_md_steps_total counter"));
+        assert!(response.contains("# TYPE prism_md_step_rate gauge"));
+    }
+}
@@ -0,0 +1,514 @@
+//! # Energy Minimization - Steepest Descent + L-BFGS / Conjugate Gradient
+//! Relaxes a structure before dynamics. An adaptive steepest-descent stage
+//! removes the worst clashes, then L-BFGS (backtracking Armijo line search)
+//! or Polak-Ribière conjugate gradient (strong Wolfe line search) refines to
+//! the force tolerance. Both work on flat `[x0, y0, z0,
+//! x1, ...]` coordinate vectors through an objective returning the energy and
+//! writing the gradient. Every trial move is capped at `max_displacement` per
+//! atom so a single bad step cannot blow the structure apart.
+//! Units: Angstrom, kcal/mol.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MinimizationConfig {
+    /// Converged when the largest per-atom force is below this (kcal/mol/Å)
+    pub force_tolerance: f64,
+    /// Steepest-descent steps before switching to the refinement stage
+    pub steepest_descent_steps: usize,
+    /// Refinement iteration limit
+    pub max_iterations: usize,
+    /// Initial steepest-descent displacement of the most loaded atom (Å)
+    pub initial_step: f64,
+    /// Largest displacement of any atom in one trial move (Å)
+    pub max_displacement: f64,
+    /// Refinement stage run after steepest descent
+    pub minimizer: MinimizerConfig,
+}
+
+/// Refinement algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MinimizerConfig {
+    /// Limited-memory BFGS keeping `memory` correction pairs
+    Lbfgs { memory: usize },
+    /// Polak-Ribière (PR+) conjugate gradient, restarted along the steepest
+    /// descent every `restart_interval` iterations (0 = every 3N)
+    ConjugateGradient { restart_interval: usize },
+}
+
+impl Default for MinimizerConfig {
+    fn default() -> Self {
+        MinimizerConfig::Lbfgs { memory: 8 }
+    }
+}
+
+impl Default for MinimizationConfig {
+    fn default() -> Self {
+        Self {
+            force_tolerance: 1.0,
+            steepest_descent_steps: 100,
+            max_iterations: 2000,
+            initial_step: 0.01,
+            max_displacement: 0.2,
+            minimizer: MinimizerConfig::default(),
+        }
+    }
+}
+
+/// Outcome of a minimization
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MinimizationReport {
+    pub initial_energy: f64,
+    pub final_energy: f64,
+    /// Accepted steepest-descent steps
+    pub steepest_descent_steps: usize,
+    /// Refinement iterations
+    pub refinement_iterations: usize,
+    /// Objective evaluations
+    pub evaluations: usize,
+    /// Largest per-atom force at the end (kcal/mol/Å)
+    pub max_force: f64,
+    /// RMS per-atom force at the end (kcal/mol/Å)
+    pub rms_force: f64,
+    pub converged: bool,
+}
+
+impl MinimizationReport {
+    pub fn energy_drop(&self) -> f64 {
+        self.initial_energy - self.final_energy
+    }
+}
+
+/// Largest and RMS per-atom norm of a flat 3N gradient
+pub fn force_norms(gradient: &[f64]) -> (f64, f64) {
+    let atoms = (gradient.len() / 3).max(1);
+    let (max2, sum2) = gradient
+        .chunks_exact(3)
+        .fold((0.0f64, 0.0f64), |(max2, sum2), g| {
+            let n2 = g[0] * g[0] + g[1] * g[1] + g[2] * g[2];
+            (max2.max(n2), sum2 + n2)
+        });
+    (max2.sqrt(), (sum2 / atoms as f64).sqrt())
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Largest per-atom length of a flat 3N displacement
+fn max_atom_norm(v: &[f64]) -> f64 {
+    v.chunks_exact(3)
+        .map(|d| (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt())
+        .fold(0.0, f64::max)
+}
+
+/// Objective state shared by the minimizer stages
+struct State<F> {
+    objective: F,
+    x: Vec<f64>,
+    gradient: Vec<f64>,
+    energy: f64,
+    evaluations: usize,
+}
+
+impl<F: FnMut(&[f64], &mut [f64]) -> f64> State<F> {
+    fn new(mut objective: F, x: Vec<f64>) -> Self {
+        let mut gradient = vec![0.0; x.len()];
+        let energy = objective(&x, &mut gradient);
+        Self {
+            objective,
+            x,
+            gradient,
+            energy,
+            evaluations: 1,
+        }
+    }
+
+    /// Evaluate `x + step * direction` into the scratch buffers
+    fn trial(&mut self, direction: &[f64], step: f64, x: &mut [f64], gradient: &mut [f64]) -> f64 {
+        for ((xt, x0), d) in x.iter_mut().zip(&self.x).zip(direction) {
+            *xt = x0 + step * d;
+        }
+        self.evaluations += 1;
+        (self.objective)(x, gradient)
+    }
+
+    fn converged(&self, tolerance: f64) -> bool {
+        force_norms(&self.gradient).0 <= tolerance
+    }
+
+    /// Backtracking Armijo search along a descent `direction` from `step`,
+    /// shrinking by quadratic interpolation. On success the trial point is in
+    /// `x`/`gradient` and its energy is returned.
+    fn line_search(
+        &mut self,
+        direction: &[f64],
+        mut step: f64,
+        x: &mut [f64],
+        gradient: &mut [f64],
+    ) -> Option<f64> {
+        let slope = dot(direction, &self.gradient);
+        for _ in 0..30 {
+            let energy = self.trial(direction, step, x, gradient);
+            if energy.is_finite() && energy <= self.energy + 1e-4 * step * slope {
+                return Some(energy);
+            }
+            let curvature = energy - self.energy - slope * step;
+            let next = if energy.is_finite() && curvature > 0.0 {
+                -slope * step * step / (2.0 * curvature)
+            } else {
+                0.5 * step
+            };
+            step = next.clamp(0.1 * step, 0.5 * step);
+        }
+        None
+    }
+
+    /// Bracketing search for a step satisfying the strong Wolfe conditions
+    /// (sufficient decrease and `|g·d|` reduced tenfold), interpolating by
+    /// secant inside the bracket and doubling (up to `max_step`) outside it.
+    /// Falls back to the best sufficient-decrease step found.
+    fn wolfe_search(
+        &mut self,
+        direction: &[f64],
+        mut step: f64,
+        max_step: f64,
+        x: &mut [f64],
+        gradient: &mut [f64],
+    ) -> Option<f64> {
+        let slope = dot(direction, &self.gradient);
+        let (mut lo, mut slope_lo) = (0.0, slope);
+        let mut hi: Option<(f64, Option<f64>)> = None;
+        let mut best: Option<(f64, f64)> = None;
+
+        for _ in 0..40 {
+            let energy = self.trial(direction, step, x, gradient);
+            if !energy.is_finite() || energy > self.energy + 1e-4 * step * slope {
+                hi = Some((step, None));
+            } else {
+                let new_slope = dot(direction, gradient);
+                if new_slope.abs() <= 0.1 * slope.abs() {
+                    return Some(energy);
+                }
+                if best.is_none_or(|(_, e)| energy < e) {
+                    best = Some((step, energy));
+                }
+                if new_slope < 0.0 {
+                    (lo, slope_lo) = (step, new_slope);
+                } else {
+                    hi = Some((step, Some(new_slope)));
+                }
+            }
+            step = match hi {
+                None if step >= max_step => break,
+                None => (2.0 * step).min(max_step),
+                Some((hi, Some(slope_hi))) => {
+                    let secant = lo - slope_lo * (hi - lo) / (slope_hi - slope_lo);
+                    secant.clamp(lo + 0.1 * (hi - lo), hi - 0.1 * (hi - lo))
+                }
+                Some((hi, None)) => 0.5 * (lo + hi),
+            };
+        }
+
+        let (step, energy) = best?;
+        self.trial(direction, step, x, gradient);
+        Some(energy)
+    }
+
+    /// Move to the accepted trial point
+    fn accept(&mut self, x: &mut Vec<f64>, gradient: &mut Vec<f64>, energy: f64) {
+        std::mem::swap(&mut self.x, x);
+        std::mem::swap(&mut self.gradient, gradient);
+        self.energy = energy;
+    }
+}
+
+/// Minimize `objective` starting from `x`; the objective returns the energy
+/// and writes the gradient (the negative force). Returns the final
+/// coordinates and a report.
+pub fn minimize<F>(
+    objective: F,
+    x: Vec<f64>,
+    config: &MinimizationConfig,
+) -> (Vec<f64>, MinimizationReport)
+where
+    F: FnMut(&[f64], &mut [f64]) -> f64,
+{
+    let mut state = State::new(objective, x);
+    let mut report = MinimizationReport {
+        initial_energy: state.energy,
+        ..Default::default()
+    };
+
+    report.steepest_descent_steps = steepest_descent(&mut state, config);
+    if !state.converged(config.force_tolerance) {
+        report.refinement_iterations = match config.minimizer {
+            MinimizerConfig::Lbfgs { memory } => lbfgs(&mut state, config, memory),
+            MinimizerConfig::ConjugateGradient { restart_interval } => {
+                conjugate_gradient(&mut state, config, restart_interval)
+            }
+        };
+    }
+
+    let (max_force, rms_force) = force_norms(&state.gradient);
+    report.final_energy = state.energy;
+    report.evaluations = state.evaluations;
+    report.max_force = max_force;
+    report.rms_force = rms_force;
+    report.converged = max_force <= config.force_tolerance;
+    (state.x, report)
+}
+
+/// Adaptive steepest descent: move the most loaded atom by `h` along the
+/// force, growing `h` by 1.2 on success and shrinking it by 5 on failure.
+fn steepest_descent<F: FnMut(&[f64], &mut [f64]) -> f64>(
+    state: &mut State<F>,
+    config: &MinimizationConfig,
+) -> usize {
+    let mut h = config.initial_step.min(config.max_displacement);
+    let mut x = state.x.clone();
+    let mut gradient = vec![0.0; state.x.len()];
+    let mut accepted = 0;
+
+    for _ in 0..config.steepest_descent_steps {
+        if state.converged(config.force_tolerance) || h < 1e-8 {
+            break;
+        }
+        let direction: Vec<f64> = state.gradient.iter().map(|g| -g).collect();
+        let scale = max_atom_norm(&direction);
+        if scale <= 0.0 {
+            break;
+        }
+        let energy = state.trial(&direction, h / scale, &mut x, &mut gradient);
+        if energy.is_finite() && energy < state.energy {
+            state.accept(&mut x, &mut gradient, energy);
+            accepted += 1;
+            h = (h * 1.2).min(config.max_displacement);
+        } else {
+            h *= 0.2;
+        }
+    }
+    accepted
+}
+
+/// Limited-memory BFGS with backtracking line search. The memory is reset
+/// whenever the two-loop direction is not a descent direction or the line
+/// search fails; two consecutive failures end the refinement.
+fn lbfgs<F: FnMut(&[f64], &mut [f64]) -> f64>(
+    state: &mut State<F>,
+    config: &MinimizationConfig,
+    memory: usize,
+) -> usize {
+    let n = state.x.len();
+    let memory = memory.max(1);
+    let mut history: VecDeque<(Vec<f64>, Vec<f64>, f64)> = VecDeque::with_capacity(memory);
+    let mut x = vec![0.0; n];
+    let mut gradient = vec![0.0; n];
+    let mut failures = 0;
+
+    for iteration in 0..config.max_iterations {
+        if state.converged(config.force_tolerance) {
+            return iteration;
+        }
+
+        // Two-loop recursion: direction = -H g
+        let mut q = state.gradient.clone();
+        let mut alphas = Vec::with_capacity(history.len());
+        for (s, y, rho) in history.iter().rev() {
+            let a = rho * dot(s, &q);
+            for (qi, yi) in q.iter_mut().zip(y) {
+                *qi -= a * yi;
+            }
+            alphas.push(a);
+        }
+        if let Some((s, y, _)) = history.back() {
+            let gamma = dot(s, y) / dot(y, y);
+            q.iter_mut().for_each(|qi| *qi *= gamma);
+        }
+        for ((s, y, rho), a) in history.iter().zip(alphas.iter().rev()) {
+            let b = rho * dot(y, &q);
+            for (qi, si) in q.iter_mut().zip(s) {
+                *qi += (a - b) * si;
+            }
+        }
+        let mut direction: Vec<f64> = q.iter().map(|v| -v).collect();
+        if dot(&direction, &state.gradient) >= 0.0 {
+            history.clear();
+            direction = state.gradient.iter().map(|g| -g).collect();
+        }
+        let longest = max_atom_norm(&direction);
+        if longest > config.max_displacement {
+            let scale = config.max_displacement / longest;
+            direction.iter_mut().for_each(|d| *d *= scale);
+        }
+
+        let Some(energy) = state.line_search(&direction, 1.0, &mut x, &mut gradient) else {
+            failures += 1;
+            if failures >= 2 || history.is_empty() {
+                return iteration;
+            }
+            history.clear();
+            continue;
+        };
+        failures = 0;
+
+        let s: Vec<f64> = x.iter().zip(&state.x).map(|(a, b)| a - b).collect();
+        let y: Vec<f64> = gradient
+            .iter()
+            .zip(&state.gradient)
+            .map(|(a, b)| a - b)
+            .collect();
+        let sy = dot(&s, &y);
+        state.accept(&mut x, &mut gradient, energy);
+        // Keep the pair only when it preserves positive definiteness
+        if sy > 1e-12 {
+            if history.len() == memory {
+                history.pop_front();
+            }
+            history.push_back((s, y, 1.0 / sy));
+        }
+    }
+    config.max_iterations
+}
+
+/// Polak-Ribière conjugate gradient (β clipped at zero). The first trial
+/// step along each direction follows from the slope ratio to the previous
+/// accepted step, capped at `max_displacement` per atom.
+fn conjugate_gradient<F: FnMut(&[f64], &mut [f64]) -> f64>(
+    state: &mut State<F>,
+    config: &MinimizationConfig,
+    restart_interval: usize,
+) -> usize {
+    let n = state.x.len();
+    let restart = if restart_interval == 0 {
+        n.max(1)
+    } else {
+        restart_interval
+    };
+    let mut x = vec![0.0; n];
+    let mut gradient = vec![0.0; n];
+    let mut direction: Vec<f64> = state.gradient.iter().map(|g| -g).collect();
+    let mut since_restart = 0;
+    let mut previous: Option<(f64, f64)> = None;
+    let mut failures = 0;
+
+    for iteration in 0..config.max_iterations {
+        if state.converged(config.force_tolerance) {
+            return iteration;
+        }
+        let slope = dot(&direction, &state.gradient);
+        if slope >= 0.0 {
+            direction = state.gradient.iter().map(|g| -g).collect();
+            since_restart = 0;
+            continue;
+        }
+
+        let longest = max_atom_norm(&direction);
+        let cap = config.max_displacement / longest.max(1e-300);
+        let guess = previous.map_or(cap, |(step, old_slope)| step * old_slope / slope);
+        let Some(energy) =
+            state.wolfe_search(&direction, guess.min(cap), cap, &mut x, &mut gradient)
+        else {
+            failures += 1;
+            if failures >= 2 || since_restart == 0 {
+                return iteration;
+            }
+            direction = state.gradient.iter().map(|g| -g).collect();
+            since_restart = 0;
+            previous = None;
+            continue;
+        };
+        failures = 0;
+        let taken = dot(&direction, &x) - dot(&direction, &state.x);
+        previous = Some((taken / dot(&direction, &direction), slope));
+
+        // β = g₁·(g₁ - g₀) / g₀·g₀
+        let g0g0 = dot(&state.gradient, &state.gradient);
+        let g1dg: f64 = gradient
+            .iter()
+            .zip(&state.gradient)
+            .map(|(g1, g0)| g1 * (g1 - g0))
+            .sum();
+        state.accept(&mut x, &mut gradient, energy);
+        since_restart += 1;
+        let beta = if since_restart >= restart {
+            0.0
+        } else {
+            (g1dg / g0g0).max(0.0)
+        };
+        if beta == 0.0 {
+            since_restart = 0;
+        }
+        for (d, g) in direction.iter_mut().zip(&state.gradient) {
+            *d = -g + beta * *d;
+        }
+    }
+    config.max_iterations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Anisotropic quadratic bowl centred on (1, -2, 0.5) per atom
+    fn bowl(x: &[f64], g: &mut [f64]) -> f64 {
+        let centre = [1.0, -2.0, 0.5];
+        let k = [1.0, 25.0, 200.0];
+        let mut e = 0.0;
+        for (i, (xi, gi)) in x.iter().zip(g.iter_mut()).enumerate() {
+            let d = xi - centre[i % 3];
+            e += 0.5 * k[i % 3] * d * d;
+            *gi = k[i % 3] * d;
+        }
+        e
+    }
+
+    const MINIMIZERS: [MinimizerConfig; 2] = [
+        MinimizerConfig::Lbfgs { memory: 8 },
+        MinimizerConfig::ConjugateGradient {
+            restart_interval: 0,
+        },
+    ];
+
+    #[test]
+    fn test_minimize_quadratic_bowl() {
+        for minimizer in MINIMIZERS {
+            let config = MinimizationConfig {
+                force_tolerance: 1e-4,
+                minimizer,
+                ..Default::default()
+            };
+            let (x, report) = minimize(bowl, vec![0.0; 6], &config);
+            assert!(report.converged, "{:?}: {:?}", minimizer, report);
+            assert!(report.energy_drop() > 0.0);
+            assert!(report.final_energy < 1e-8);
+            for (i, xi) in x.iter().enumerate() {
+                assert!((xi - [1.0, -2.0, 0.5][i % 3]).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimize_rosenbrock_valley() {
+        // Rosenbrock in (x, y), z a stiff spring
+        let rosenbrock = |x: &[f64], g: &mut [f64]| {
+            let (a, b) = (x[0], x[1]);
+            g[0] = -2.0 * (1.0 - a) - 400.0 * a * (b - a * a);
+            g[1] = 200.0 * (b - a * a);
+            g[2] = 10.0 * x[2];
+            (1.0 - a).powi(2) + 100.0 * (b - a * a).powi(2) + 5.0 * x[2] * x[2]
+        };
+        for minimizer in MINIMIZERS {
+            let config = MinimizationConfig {
+                force_tolerance: 1e-5,
+                minimizer,
+                ..Default::default()
+            };
+            let (x, report) = minimize(rosenbrock, vec![-1.2, 1.0, 0.3], &config);
+            assert!(report.converged, "{:?}: {:?}", minimizer, report);
+            assert!((x[0] - 1.0).abs() < 1e-3 && (x[1] - 1.0).abs() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,642 @@
+//! # Machine-Learned Potentials
+//! A [`Potential`] replaces the classical force field and bonded terms of
+//! the engine with any energy/force provider
+//! ([`crate::molecular_dynamics::MolecularDynamicsEngine::set_potential`]);
+//! restraints and biases still apply on top of it.
+//!
+//! [`NeuralNetworkPotential`] is the ANI-style implementation: atomic
+//! environment vectors ([`AniDescriptor`], radial and angular symmetry
+//! functions of ANI-2x) feed a per-element network behind the
+//! [`Inference`] trait, and forces follow from the network's gradient with
+//! respect to the descriptors, chained analytically through the symmetry
+//! functions. Frames are evaluated in batches, so replicas or ring-polymer
+//! beads share one network call. [`OnnxModel`] runs exported networks on
+//! ONNX Runtime (CUDA when requested) with the `onnx` feature;
+//! [`LinearReadout`] is a dependency-free per-element linear model for
+//! baselines and tests.
+//!
+//! ONNX models take `species` (int64, `[frames, atoms]`, indices into the
+//! descriptor species) and `aev` (float32, `[frames, atoms, width]`) and
+//! return `energy` (`[frames]`) and `aev_grad` (∂energy/∂aev, same shape as
+//! `aev`), in the model's energy unit (Hartree for TorchANI exports).
+//! Units: Angstrom, kcal/mol.
+
+use prism_core::PrismError;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::path::PathBuf;
+
+/// kcal/mol per Hartree
+pub const HARTREE: f64 = 627.509_474_063_1;
+
+/// Energy and force provider replacing the classical force field
+pub trait Potential: Send + std::fmt::Debug {
+    /// Short name used in logs and telemetry
+    fn name(&self) -> &str;
+
+    /// Energy (kcal/mol) of Float4-stride positions, adding forces into a
+    /// buffer of the same layout
+    fn compute(&mut self, positions: &[f32], forces: &mut [f32]) -> Result<f64, PrismError>;
+
+    /// Energies of several frames of the same system, adding the forces of
+    /// frame `k` into `forces[k]`; one [`Self::compute`] per frame unless
+    /// the implementation batches them
+    fn compute_batch(
+        &mut self,
+        frames: &[&[f32]],
+        forces: &mut [Vec<f32>],
+    ) -> Result<Vec<f64>, PrismError> {
+        frames
+            .iter()
+            .zip(forces.iter_mut())
+            .map(|(positions, forces)| self.compute(positions, forces))
+            .collect()
+    }
+}
+
+/// ANI atomic environment vectors: for each atom, radial terms per
+/// neighbour species and shift, then angular terms per neighbour species
+/// pair, radial shift and angle section (Smith et al., TorchANI layout)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AniDescriptor {
+    /// Atomic numbers of the supported species, in network order
+    pub species: Vec<u8>,
+    /// Radial cutoff (Å)
+    pub radial_cutoff: f64,
+    /// Radial Gaussian width η (Å⁻²)
+    pub radial_eta: f64,
+    /// Radial shifts (Å)
+    pub radial_shifts: Vec<f64>,
+    /// Angular cutoff (Å)
+    pub angular_cutoff: f64,
+    /// Angular Gaussian width η (Å⁻²)
+    pub angular_eta: f64,
+    /// Angular sharpness ζ
+    pub zeta: f64,
+    /// Mean-distance shifts of the angular terms (Å)
+    pub angular_shifts: Vec<f64>,
+    /// Angle section centres (rad)
+    pub angle_sections: Vec<f64>,
+}
+
+impl AniDescriptor {
+    /// ANI-2x: H, C, N, O, S, F, Cl; 1008 features per atom
+    pub fn ani2x() -> Self {
+        Self {
+            species: vec![1, 6, 7, 8, 16, 9, 17],
+            radial_cutoff: 5.1,
+            radial_eta: 19.7,
+            radial_shifts: (0..16).map(|k| 0.8 + 0.26875 * k as f64).collect(),
+            angular_cutoff: 3.5,
+            angular_eta: 12.5,
+            zeta: 14.1,
+            angular_shifts: (0..8).map(|k| 0.8 + 0.3375 * k as f64).collect(),
+            angle_sections: (0..4).map(|k| PI / 8.0 + k as f64 * PI / 4.0).collect(),
+        }
+    }
+
+    fn num_pairs(&self) -> usize {
+        self.species.len() * (self.species.len() + 1) / 2
+    }
+
+    fn radial_width(&self) -> usize {
+        self.species.len() * self.radial_shifts.len()
+    }
+
+    /// Features per atom
+    pub fn width(&self) -> usize {
+        self.radial_width()
+            + self.num_pairs() * self.angular_shifts.len() * self.angle_sections.len()
+    }
+
+    /// Species index of each atomic number, failing on unsupported elements
+    pub fn species_indices(&self, elements: &[u8]) -> Result<Vec<i64>, PrismError> {
+        elements
+            .iter()
+            .enumerate()
+            .map(|(i, z)| {
+                self.species
+                    .iter()
+                    .position(|s| s == z)
+                    .map(|s| s as i64)
+                    .ok_or_else(|| {
+                        PrismError::validation(format!(
+                            "Atom {} has element {}, not covered by the potential (Z = {:?})",
+                            i, z, self.species
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// Offset of the angular block of neighbour species `a`, `b`
+    fn pair_offset(&self, a: usize, b: usize) -> usize {
+        let (a, b) = (a.min(b), a.max(b));
+        let n = self.species.len();
+        let pair = a * n - a * (a + 1) / 2 + b;
+        self.radial_width() + pair * self.angular_shifts.len() * self.angle_sections.len()
+    }
+
+    /// Descriptors of Float4-stride positions, `[atoms, width]`
+    pub fn compute(&self, species: &[i64], positions: &[f32]) -> Vec<f32> {
+        let mut features = vec![0.0f32; species.len() * self.width()];
+        self.visit(species, positions, |i, feature, value, _| {
+            features[i * self.width() + feature] += value as f32;
+        });
+        features
+    }
+
+    /// Add the forces `-∂E/∂x` given `∂E/∂descriptor` (`[atoms, width]`)
+    pub fn backward(
+        &self,
+        species: &[i64],
+        positions: &[f32],
+        gradient: &[f32],
+        forces: &mut [f32],
+    ) {
+        let width = self.width();
+        self.visit(species, positions, |i, feature, _, derivative| {
+            let de = gradient[i * width + feature] as f64;
+            if de != 0.0 {
+                for (atom, d) in derivative {
+                    for k in 0..3 {
+                        forces[atom * 4 + k] -= (de * d[k]) as f32;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Call `visit(atom, feature, value, ∂value/∂x)` for every nonzero
+    /// symmetry function term; the derivative lists `(atom, gradient)`
+    fn visit(
+        &self,
+        species: &[i64],
+        positions: &[f32],
+        mut visit: impl FnMut(usize, usize, f64, [(usize, [f64; 3]); 3]),
+    ) {
+        let n = species.len().min(positions.len() / 4);
+        let at = |i: usize| [0, 1, 2].map(|k| positions[i * 4 + k] as f64);
+        let cutoff = |r: f64, rc: f64| {
+            let x = PI * r / rc;
+            (0.5 * x.cos() + 0.5, -0.5 * PI / rc * x.sin())
+        };
+        let none = [0.0; 3];
+        for i in 0..n {
+            let xi = at(i);
+            // Neighbours within the angular cutoff: (atom, r_ij, |r_ij|)
+            let mut close = Vec::new();
+            for j in (0..n).filter(|&j| j != i) {
+                let xj = at(j);
+                let d = [xj[0] - xi[0], xj[1] - xi[1], xj[2] - xi[2]];
+                let r = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                if r >= self.radial_cutoff || r == 0.0 {
+                    continue;
+                }
+                let (fc, dfc) = cutoff(r, self.radial_cutoff);
+                let unit = d.map(|v| v / r);
+                for (s, &shift) in self.radial_shifts.iter().enumerate() {
+                    let gauss = (-self.radial_eta * (r - shift).powi(2)).exp();
+                    let value = 0.25 * gauss * fc;
+                    let dr = 0.25 * gauss * (dfc - 2.0 * self.radial_eta * (r - shift) * fc);
+                    let feature = species[j] as usize * self.radial_shifts.len() + s;
+                    let gj = unit.map(|u| dr * u);
+                    visit(i, feature, value, [(j, gj), (i, gj.map(|g| -g)), (i, none)]);
+                }
+                if r < self.angular_cutoff {
+                    close.push((j, d, r));
+                }
+            }
+
+            for (a, &(j, dj, rj)) in close.iter().enumerate() {
+                for &(k, dk, rk) in &close[a + 1..] {
+                    let dot = dj[0] * dk[0] + dj[1] * dk[1] + dj[2] * dk[2];
+                    let cos = dot / (rj * rk);
+                    // TorchANI damps the cosine to keep acos differentiable
+                    let theta = (0.95 * cos).acos();
+                    let dtheta_dcos = -0.95 / (1.0 - 0.9025 * cos * cos).sqrt();
+                    // ∂cos/∂r_ij and ∂cos/∂r_ik
+                    let dcos_j = [0, 1, 2].map(|m| dk[m] / (rj * rk) - cos * dj[m] / (rj * rj));
+                    let dcos_k = [0, 1, 2].map(|m| dj[m] / (rj * rk) - cos * dk[m] / (rk * rk));
+                    let (fj, dfj) = cutoff(rj, self.angular_cutoff);
+                    let (fk, dfk) = cutoff(rk, self.angular_cutoff);
+                    let mean = 0.5 * (rj + rk);
+                    let base = self.pair_offset(species[j] as usize, species[k] as usize);
+                    for (s, &shift) in self.angular_shifts.iter().enumerate() {
+                        let radial = (-self.angular_eta * (mean - shift).powi(2)).exp();
+                        let dradial = -self.angular_eta * (mean - shift) * radial;
+                        for (z, &section) in self.angle_sections.iter().enumerate() {
+                            let half = 0.5 * (1.0 + (theta - section).cos());
+                            let angular = half.powf(self.zeta);
+                            let dangular = if half > 0.0 {
+                                -0.5 * self.zeta * angular / half * (theta - section).sin()
+                            } else {
+                                0.0
+                            };
+                            let value = 2.0 * angular * radial * fj * fk;
+                            let dcos = 2.0 * dangular * dtheta_dcos * radial * fj * fk;
+                            let drj = 2.0 * angular * (dradial * fj + radial * dfj) * fk;
+                            let drk = 2.0 * angular * (dradial * fk + radial * dfk) * fj;
+                            let gj = [0, 1, 2].map(|m| dcos * dcos_j[m] + drj * dj[m] / rj);
+                            let gk = [0, 1, 2].map(|m| dcos * dcos_k[m] + drk * dk[m] / rk);
+                            let gi = [0, 1, 2].map(|m| -gj[m] - gk[m]);
+                            let feature = base + s * self.angle_sections.len() + z;
+                            visit(i, feature, value, [(j, gj), (k, gk), (i, gi)]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Network evaluation behind a [`NeuralNetworkPotential`]
+pub trait Inference: Send + std::fmt::Debug {
+    /// Energy of each of `frames` frames of `atoms` atoms, and its gradient
+    /// with respect to the descriptors (`[frames, atoms, width]`), in the
+    /// model's energy unit
+    fn evaluate(
+        &mut self,
+        species: &[i64],
+        descriptors: &[f32],
+        frames: usize,
+        atoms: usize,
+        width: usize,
+    ) -> Result<(Vec<f64>, Vec<f32>), PrismError>;
+}
+
+/// Per-element linear model `E = Σ_i (b_s + w_s · G_i)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinearReadout {
+    /// Weights per species, one per descriptor feature
+    pub weights: Vec<Vec<f32>>,
+    /// Atomic self energies per species
+    pub self_energies: Vec<f64>,
+}
+
+impl Inference for LinearReadout {
+    fn evaluate(
+        &mut self,
+        species: &[i64],
+        descriptors: &[f32],
+        frames: usize,
+        atoms: usize,
+        width: usize,
+    ) -> Result<(Vec<f64>, Vec<f32>), PrismError> {
+        let mut energies = vec![0.0; frames];
+        let mut gradient = vec![0.0; frames * atoms * width];
+        for (f, energy) in energies.iter_mut().enumerate() {
+            for i in 0..atoms {
+                let s = species[f * atoms + i] as usize;
+                let (Some(weights), Some(&shift)) =
+                    (self.weights.get(s), self.self_energies.get(s))
+                else {
+                    return Err(PrismError::validation(format!(
+                        "No readout for species {}",
+                        s
+                    )));
+                };
+                let row = (f * atoms + i) * width;
+                *energy += shift
+                    + weights
+                        .iter()
+                        .zip(&descriptors[row..row + width])
+                        .map(|(w, g)| (w * g) as f64)
+                        .sum::<f64>();
+                gradient[row..row + width].copy_from_slice(&weights[..width]);
+            }
+        }
+        Ok((energies, gradient))
+    }
+}
+
+/// Where and how to run a network potential
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MlPotentialConfig {
+    /// ONNX model file
+    pub model: PathBuf,
+    /// Descriptor the model was trained on
+    #[serde(default = "AniDescriptor::ani2x")]
+    pub descriptor: AniDescriptor,
+    /// kcal/mol per model energy unit
+    #[serde(default = "default_energy_scale")]
+    pub energy_scale: f64,
+    /// Run inference on the CUDA execution provider
+    #[serde(default)]
+    pub use_gpu: bool,
+}
+
+fn default_energy_scale() -> f64 {
+    HARTREE
+}
+
+impl MlPotentialConfig {
+    pub fn new(model: impl Into<PathBuf>) -> Self {
+        Self {
+            model: model.into(),
+            descriptor: AniDescriptor::ani2x(),
+            energy_scale: HARTREE,
+            use_gpu: false,
+        }
+    }
+}
+
+/// ANI-style potential: descriptors, network, analytic forces
+#[derive(Debug)]
+pub struct NeuralNetworkPotential {
+    name: String,
+    descriptor: AniDescriptor,
+    species: Vec<i64>,
+    energy_scale: f64,
+    network: Box<dyn Inference>,
+}
+
+impl NeuralNetworkPotential {
+    /// Potential of a system of `elements` (atomic numbers, in atom order)
+    pub fn new(
+        name: impl Into<String>,
+        descriptor: AniDescriptor,
+        elements: &[u8],
+        energy_scale: f64,
+        network: Box<dyn Inference>,
+    ) -> Result<Self, PrismError> {
+        let species = descriptor.species_indices(elements)?;
+        Ok(Self {
+            name: name.into(),
+            descriptor,
+            species,
+            energy_scale,
+            network,
+        })
+    }
+
+    /// Load the ONNX model of `config` for a system of `elements`
+    pub fn from_config(config: &MlPotentialConfig, elements: &[u8]) -> Result<Self, PrismError> {
+        let network = OnnxModel::load(&config.model, config.use_gpu)?;
+        let name = config.model.file_stem().map_or_else(
+            || "onnx".to_string(),
+            |stem| stem.to_string_lossy().into_owned(),
+        );
+        Self::new(
+            name,
+            config.descriptor.clone(),
+            elements,
+            config.energy_scale,
+            Box::new(network),
+        )
+    }
+
+    pub fn descriptor(&self) -> &AniDescriptor {
+        &self.descriptor
+    }
+}
+
+impl Potential for NeuralNetworkPotential {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn compute(&mut self, positions: &[f32], forces: &mut [f32]) -> Result<f64, PrismError> {
+        let mut frame = vec![forces.to_vec()];
+        let energy = self.compute_batch(&[positions], &mut frame)?[0];
+        forces.copy_from_slice(&frame[0]);
+        Ok(energy)
+    }
+
+    fn compute_batch(
+        &mut self,
+        frames: &[&[f32]],
+        forces: &mut [Vec<f32>],
+    ) -> Result<Vec<f64>, PrismError> {
+        let atoms = self.species.len();
+        let width = self.descriptor.width();
+        if let Some(frame) = frames.iter().find(|f| f.len() / 4 != atoms) {
+            return Err(PrismError::validation(format!(
+                "Potential covers {} atoms, frame has {}",
+                atoms,
+                frame.len() / 4
+            )));
+        }
+        let species: Vec<i64> = frames
+            .iter()
+            .flat_map(|_| self.species.iter().copied())
+            .collect();
+        let descriptors: Vec<f32> = frames
+            .iter()
+            .flat_map(|positions| self.descriptor.compute(&self.species, positions))
+            .collect();
+        let (energies, gradient) =
+            self.network
+                .evaluate(&species, &descriptors, frames.len(), atoms, width)?;
+        if energies.len() != frames.len() || gradient.len() != descriptors.len() {
+            return Err(PrismError::validation(format!(
+                "Network returned {} energies and {} gradients for {} frames of {} features",
+                energies.len(),
+                gradient.len(),
+                frames.len(),
+                descriptors.len()
+            )));
+        }
+        let scale = self.energy_scale as f32;
+        for (f, (positions, forces)) in frames.iter().zip(forces.iter_mut()).enumerate() {
+            let block = &gradient[f * atoms * width..(f + 1) * atoms * width];
+            let scaled: Vec<f32> = block.iter().map(|g| g * scale).collect();
+            self.descriptor
+                .backward(&self.species, positions, &scaled, forces);
+        }
+        Ok(energies.iter().map(|e| e * self.energy_scale).collect())
+    }
+}
+
+/// Network exported to ONNX, run on ONNX Runtime
+pub struct OnnxModel {
+    #[cfg(feature = "onnx")]
+    session: ort::session::Session,
+}
+
+impl std::fmt::Debug for OnnxModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnnxModel").finish_non_exhaustive()
+    }
+}
+
+impl OnnxModel {
+    /// Load `path`, on the CUDA execution provider when `use_gpu`
+    #[cfg(feature = "onnx")]
+    pub fn load(path: &std::path::Path, use_gpu: bool) -> Result<Self, PrismError> {
+        use ort::execution_providers::CUDAExecutionProvider;
+        use ort::session::Session;
+        let error =
+            |e: ort::Error| PrismError::config(format!("ONNX model {}: {}", path.display(), e));
+        let mut builder = Session::builder().map_err(error)?;
+        if use_gpu {
+            builder = builder
+                .with_execution_providers([CUDAExecutionProvider::default()
+                    .build()
+                    .error_on_failure()])
+                .map_err(error)?;
+        }
+        let session = builder.commit_from_file(path).map_err(error)?;
+        Ok(Self { session })
+    }
+
+    /// Load `path`; this build has no ONNX Runtime
+    #[cfg(not(feature = "onnx"))]
+    pub fn load(path: &std::path::Path, _use_gpu: bool) -> Result<Self, PrismError> {
+        Err(PrismError::config(format!(
+            "Cannot load {}: prism-physics was built without the `onnx` feature",
+            path.display()
+        )))
+    }
+}
+
+impl Inference for OnnxModel {
+    #[cfg(feature = "onnx")]
+    fn evaluate(
+        &mut self,
+        species: &[i64],
+        descriptors: &[f32],
+        frames: usize,
+        atoms: usize,
+        width: usize,
+    ) -> Result<(Vec<f64>, Vec<f32>), PrismError> {
+        use ort::value::Tensor;
+        let error = |e: ort::Error| PrismError::Internal(format!("ONNX inference: {}", e));
+        let species = Tensor::from_array(([frames, atoms], species.to_vec())).map_err(error)?;
+        let aev =
+            Tensor::from_array(([frames, atoms, width], descriptors.to_vec())).map_err(error)?;
+        let outputs = self
+            .session
+            .run(ort::inputs!["species" => species, "aev" => aev])
+            .map_err(error)?;
+        let (_, energy) = outputs["energy"]
+            .try_extract_tensor::<f32>()
+            .map_err(error)?;
+        let (_, gradient) = outputs["aev_grad"]
+            .try_extract_tensor::<f32>()
+            .map_err(error)?;
+        Ok((
+            energy.iter().map(|&e| e as f64).collect(),
+            gradient.to_vec(),
+        ))
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    fn evaluate(
+        &mut self,
+        _species: &[i64],
+        _descriptors: &[f32],
+        _frames: usize,
+        _atoms: usize,
+        _width: usize,
+    ) -> Result<(Vec<f64>, Vec<f32>), PrismError> {
+        Err(PrismError::config(
+            "ONNX inference needs the `onnx` feature",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small descriptor so every term is visited with a few atoms
+    fn descriptor() -> AniDescriptor {
+        AniDescriptor {
+            species: vec![1, 6, 8],
+            radial_cutoff: 4.0,
+            radial_eta: 4.0,
+            radial_shifts: vec![0.9, 1.4, 2.2],
+            angular_cutoff: 3.0,
+            angular_eta: 3.0,
+            zeta: 8.0,
+            angular_shifts: vec![1.0, 1.6],
+            angle_sections: vec![0.4, 1.6, 2.7],
+        }
+    }
+
+    fn readout(descriptor: &AniDescriptor) -> LinearReadout {
+        let width = descriptor.width();
+        LinearReadout {
+            weights: (0..3)
+                .map(|s| {
+                    (0..width)
+                        .map(|f| ((f * 7 + s * 3) % 11) as f32 * 0.1 - 0.5)
+                        .collect()
+                })
+                .collect(),
+            self_energies: vec![-0.5, -38.0, -75.0],
+        }
+    }
+
+    #[test]
+    fn test_ani2x_descriptor_width() {
+        let ani = AniDescriptor::ani2x();
+        assert_eq!(ani.width(), 1008);
+        assert_eq!(ani.species_indices(&[6, 1, 17]).unwrap(), [1, 0, 6]);
+        assert!(ani.species_indices(&[26]).is_err());
+    }
+
+    #[test]
+    fn test_forces_match_finite_difference() {
+        let descriptor = descriptor();
+        let network = Box::new(readout(&descriptor));
+        // Methanol-like: C, O, three H on C, H on O
+        let elements = [6, 8, 1, 1, 1, 1];
+        let mut potential =
+            NeuralNetworkPotential::new("linear", descriptor, &elements, 1.0, network).unwrap();
+        let mut positions = vec![
+            0.0, 0.0, 0.0, 12.0, //
+            1.42, 0.05, 0.0, 16.0, //
+            -0.36, 1.02, 0.0, 1.0, //
+            -0.36, -0.51, 0.89, 1.0, //
+            -0.38, -0.50, -0.91, 1.0, //
+            1.74, 0.91, 0.1, 1.0,
+        ];
+        let mut forces = vec![0.0; positions.len()];
+        let energy = potential.compute(&positions, &mut forces).unwrap();
+        assert!(energy.is_finite());
+
+        let h = 1e-3;
+        for k in (0..positions.len()).filter(|k| k % 4 != 3) {
+            let mut scratch = vec![0.0; positions.len()];
+            positions[k] += h;
+            let plus = potential.compute(&positions, &mut scratch).unwrap();
+            positions[k] -= 2.0 * h;
+            let minus = potential.compute(&positions, &mut scratch).unwrap();
+            positions[k] += h;
+            let numeric = -(plus - minus) / (2.0 * h as f64);
+            assert!(
+                (numeric - forces[k] as f64).abs() < 1e-2 * (1.0 + numeric.abs()),
+                "{}: {} vs {}",
+                k,
+                numeric,
+                forces[k]
+            );
+        }
+    }
+
+    #[test]
+    fn test_batch_matches_single_frames() {
+        let descriptor = descriptor();
+        let network = Box::new(readout(&descriptor));
+        let mut potential =
+            NeuralNetworkPotential::new("linear", descriptor, &[6, 8, 1], 2.0, network).unwrap();
+        let a = vec![0.0, 0.0, 0.0, 1.0, 1.4, 0.0, 0.0, 1.0, -0.4, 1.0, 0.0, 1.0];
+        let b: Vec<f32> = a
+            .iter()
+            .enumerate()
+            .map(|(k, x)| if k == 4 { 1.2 } else { *x })
+            .collect();
+        let mut batch = vec![vec![0.0; 12], vec![0.0; 12]];
+        let energies = potential.compute_batch(&[&a, &b], &mut batch).unwrap();
+        for (frame, (energy, forces)) in [&a, &b].into_iter().zip(energies.iter().zip(&batch)) {
+            let mut single = vec![0.0; 12];
+            assert_eq!(potential.compute(frame, &mut single).unwrap(), *energy);
+            assert_eq!(&single, forces);
+        }
+        assert!(potential.compute(&a[..8], &mut [0.0; 8]).is_err());
+        assert!(OnnxModel::load(std::path::Path::new("missing.onnx"), false).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+//! # Mode Animation - Normal Mode Trajectory Export
+//! Turns elastic network modes into a multi-frame trajectory for viewing
+//! the collective "breathing" motions in PyMOL/VMD/ChimeraX. Each selected
+//! mode gets one sinusoidal cycle `x0 + s sin(2πk / F) v`, played one after
+//! another. Every atom follows the displacement of its residue's CA node;
+//! atoms of residues without a node stay put. Output is multi-model PDB
+//! (one MODEL per frame) or a DCD/XTC trajectory.
+
+use crate::elastic_network::{ElasticNetwork, ModeSet};
+use prism_core::PrismError;
+use prism_io::pdb::PdbStructure;
+use prism_io::sovereign_types::Atom;
+use prism_io::trajectory::{open_trajectory, TrajectoryConfig, TrajectoryFormat, TrajectoryFrame};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AnimationFormat {
+    /// Multi-model PDB
+    #[default]
+    Pdb,
+    Dcd,
+    Xtc,
+}
+
+/// One animated mode
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnimatedMode {
+    /// Index into [`ElasticNetwork::modes`] (0 = lowest non-trivial mode)
+    /// or [`ModeSet::modes`]
+    pub mode: usize,
+    /// RMS CA displacement at the turning points (Å)
+    pub amplitude: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModeAnimationConfig {
+    pub modes: Vec<AnimatedMode>,
+    /// Frames per oscillation cycle
+    pub frames_per_cycle: usize,
+    pub format: AnimationFormat,
+}
+
+impl Default for ModeAnimationConfig {
+    fn default() -> Self {
+        Self {
+            modes: vec![AnimatedMode {
+                mode: 0,
+                amplitude: 2.0,
+            }],
+            frames_per_cycle: 20,
+            format: AnimationFormat::Pdb,
+        }
+    }
+}
+
+/// Frames of a mode animation, one `[x, y, z]` per atom each
+#[derive(Debug, Clone)]
+pub struct ModeAnimation {
+    frames: Vec<Vec<[f32; 3]>>,
+    format: AnimationFormat,
+}
+
+impl ModeAnimation {
+    /// Animate the selected ANM modes of `network` about the `atoms`
+    /// coordinates
+    pub fn new(
+        network: &ElasticNetwork,
+        atoms: &[Atom],
+        config: &ModeAnimationConfig,
+    ) -> Result<Self, PrismError> {
+        Self::with_modes(&network.mode_set(), atoms, config)
+    }
+
+    /// Animate the selected three-dimensional modes of `set` (e.g. principal
+    /// components) about the `atoms` coordinates
+    pub fn with_modes(
+        set: &ModeSet,
+        atoms: &[Atom],
+        config: &ModeAnimationConfig,
+    ) -> Result<Self, PrismError> {
+        if config.frames_per_cycle == 0 {
+            return Err(PrismError::config(
+                "Mode animation needs at least one frame per cycle",
+            ));
+        }
+        let n = set.nodes.len();
+        let node_of_residue: HashMap<u16, usize> = set
+            .residues
+            .iter()
+            .enumerate()
+            .map(|(node, &residue)| (residue, node))
+            .collect();
+
+        let mut frames = Vec::with_capacity(config.modes.len() * config.frames_per_cycle);
+        for selected in &config.modes {
+            let mode = set.modes.get(selected.mode).ok_or_else(|| {
+                PrismError::config(format!(
+                    "Mode {} requested but only {} modes are available",
+                    selected.mode,
+                    set.modes.len()
+                ))
+            })?;
+            if mode.vector.len() != n * 3 {
+                return Err(PrismError::config(
+                    "Only anisotropic (ANM) modes can be animated",
+                ));
+            }
+            // Unit eigenvector: RMS node displacement of s v is s / sqrt(N)
+            let scale = selected.amplitude * (n as f64).sqrt();
+            for k in 0..config.frames_per_cycle {
+                let phase = 2.0 * std::f64::consts::PI * k as f64 / config.frames_per_cycle as f64;
+                let s = scale * phase.sin();
+                frames.push(
+                    atoms
+                        .iter()
+                        .map(|atom| match node_of_residue.get(&atom.residue_id) {
+                            Some(&node) => {
+                                let v = &mode.vector[node * 3..node * 3 + 3];
+                                [0, 1, 2].map(|a| atom.coords[a] + (s * v[a]) as f32)
+                            }
+                            None => atom.coords,
+                        })
+                        .collect(),
+                );
+            }
+        }
+        Ok(Self {
+            frames,
+            format: config.format,
+        })
+    }
+
+    pub fn frames(&self) -> &[Vec<[f32; 3]>] {
+        &self.frames
+    }
+
+    /// Write the animation; PDB output takes its atom metadata from
+    /// `template`, or generic records when `None`
+    pub fn write(
+        &self,
+        path: &Path,
+        atoms: &[Atom],
+        template: Option<&PdbStructure>,
+    ) -> Result<(), PrismError> {
+        let io_error = |e: prism_io::PrismIoError| {
+            PrismError::Internal(format!(
+                "Mode animation write to {} failed: {}",
+                path.display(),
+                e
+            ))
+        };
+        let format = match self.format {
+            AnimationFormat::Pdb => {
+                let structure = template
+                    .cloned()
+                    .unwrap_or_else(|| PdbStructure::from_atoms(atoms));
+                return structure.write_models(path, &self.frames).map_err(io_error);
+            }
+            AnimationFormat::Dcd => TrajectoryFormat::Dcd,
+            AnimationFormat::Xtc => TrajectoryFormat::Xtc,
+        };
+        let config = TrajectoryConfig {
+            path: path.to_path_buf(),
+            format,
+            stride: 1,
+            precision: 1000.0,
+            selection: None,
+            append: false,
+        };
+        let mut writer = open_trajectory(&config, atoms.len(), 0, 1.0, false).map_err(io_error)?;
+        for (step, frame) in self.frames.iter().enumerate() {
+            let positions: Vec<f32> = frame.iter().flat_map(|p| [p[0], p[1], p[2], 1.0]).collect();
+            writer
+                .write_frame(&TrajectoryFrame {
+                    step: step as u64,
+                    time_ps: step as f64,
+                    positions: &positions,
+                    velocities: None,
+                    simulation_box: None,
+                })
+                .map_err(io_error)?;
+        }
+        writer.flush().map_err(io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_network::{ElasticNetworkConfig, NetworkModel};
+
+    /// Two atoms per residue on a helical CA trace
+    fn residues(n: usize) -> (Vec<Atom>, ElasticNetwork) {
+        let atoms: Vec<Atom> = (0..n * 2)
+            .map(|i| {
+                let angle = (100.0 * (i / 2) as f32).to_radians();
+                let offset = if i % 2 == 0 { 0.0 } else { 1.5 };
+                Atom {
+                    coords: [
+                        2.3 * angle.cos() + offset,
+                        2.3 * angle.sin(),
+                        1.5 * (i / 2) as f32,
+                    ],
+                    element: 6,
+                    residue_id: (i / 2) as u16,
+                    atom_type: 0,
+                    charge: 0.0,
+                    radius: 1.7,
+                    _reserved: [0; 4],
+                }
+            })
+            .collect();
+        let nodes = atoms
+            .iter()
+            .step_by(2)
+            .map(|a| a.coords.map(|c| c as f64))
+            .collect();
+        let config = ElasticNetworkConfig {
+            cutoff: 10.0,
+            num_modes: 3,
+            ..Default::default()
+        };
+        let network = ElasticNetwork::from_nodes(
+            (0..n as u32).map(|i| i * 2).collect(),
+            (0..n as u16).collect(),
+            nodes,
+            config,
+        )
+        .unwrap();
+        (atoms, network)
+    }
+
+    #[test]
+    fn test_frames_follow_mode_with_requested_amplitude() {
+        let (atoms, network) = residues(10);
+        let config = ModeAnimationConfig {
+            modes: vec![
+                AnimatedMode {
+                    mode: 0,
+                    amplitude: 1.0,
+                },
+                AnimatedMode {
+                    mode: 2,
+                    amplitude: 0.5,
+                },
+            ],
+            frames_per_cycle: 8,
+            ..Default::default()
+        };
+        let animation = ModeAnimation::new(&network, &atoms, &config).unwrap();
+        assert_eq!(animation.frames().len(), 16);
+        assert_eq!(animation.frames()[0][3], atoms[3].coords);
+
+        // Quarter cycle is the turning point of the first mode
+        let peak = &animation.frames()[2];
+        let msd: f64 = atoms
+            .iter()
+            .zip(peak)
+            .step_by(2)
+            .map(|(a, p)| {
+                (0..3)
+                    .map(|k| (p[k] - a.coords[k]) as f64)
+                    .map(|d| d * d)
+                    .sum::<f64>()
+            })
+            .sum::<f64>()
+            / 10.0;
+        assert!((msd.sqrt() - 1.0).abs() < 1e-4);
+        // Side atoms move rigidly with their CA
+        let (ca, side) = (peak[4], peak[5]);
+        for k in 0..3 {
+            let moved = [ca[k] - atoms[4].coords[k], side[k] - atoms[5].coords[k]];
+            assert!((moved[0] - moved[1]).abs() < 1e-5);
+        }
+
+        let gnm = ElasticNetwork::from_nodes(
+            vec![0, 2, 4],
+            vec![0, 1, 2],
+            network.nodes()[..3].to_vec(),
+            ElasticNetworkConfig {
+                model: NetworkModel::Gaussian,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(ModeAnimation::new(&gnm, &atoms, &ModeAnimationConfig::default()).is_err());
+        let missing = ModeAnimationConfig {
+            modes: vec![AnimatedMode {
+                mode: 7,
+                amplitude: 1.0,
+            }],
+            ..Default::default()
+        };
+        assert!(ModeAnimation::new(&network, &atoms, &missing).is_err());
+    }
+
+    #[test]
+    fn test_write_pdb_models_and_dcd() {
+        let (atoms, network) = residues(8);
+        let dir = std::env::temp_dir();
+        let pdb = dir.join(format!("prism_modes_{}.pdb", std::process::id()));
+        let animation =
+            ModeAnimation::new(&network, &atoms, &ModeAnimationConfig::default()).unwrap();
+        animation.write(&pdb, &atoms, None).unwrap();
+        let text = std::fs::read_to_string(&pdb).unwrap();
+        let _ = std::fs::remove_file(&pdb);
+        assert_eq!(text.matches("ENDMDL").count(), 20);
+
+        let dcd = dir.join(format!("prism_modes_{}.dcd", std::process::id()));
+        let config = ModeAnimationConfig {
+            format: AnimationFormat::Dcd,
+            ..Default::default()
+        };
+        let animation = ModeAnimation::new(&network, &atoms, &config).unwrap();
+        animation.write(&dcd, &atoms, None).unwrap();
+        let trajectory = prism_io::dcd::read_dcd(&dcd).unwrap();
+        let _ = std::fs::remove_file(&dcd);
+        assert_eq!(trajectory.frames.len(), 20);
+        assert_eq!(trajectory.frames[5], animation.frames()[5]);
+    }
+}
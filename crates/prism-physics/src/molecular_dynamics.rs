@@ -2,15 +2,52 @@
 //! Architecture: Float4 Stride, Device-Resident State, Euler-Maruyama Integrator.
 //! Status: Audit Compliant, Type-Safe, Warning-Free.
 
-use prism_core::{PhaseOutcome, PrismError};
+use crate::bonded::{BondedEnergy, BondedTerms};
+use crate::constraints::{ConstraintConfig, ConstraintMode, Constraints};
+use crate::instability::{self, InstabilityConfig, InstabilityRecovery, InstabilityResponse};
+use crate::minimizer::{self, MinimizationConfig};
+use crate::analysis::{
+    Analysis, NativeContactConfig, NativeContacts, SasaAnalysis, SasaCalculator, SasaConfig, SecondaryStructureAnalysis,
+    ShapeAnalysis,
+};
+use crate::annealing::TemperatureSchedule;
+use crate::collective_variables::BiasPotential;
+use crate::ml_potential::Potential;
+use crate::elastic_network::{cumulative_overlap, ElasticNetwork, ElasticNetworkConfig};
+use crate::mode_animation::{ModeAnimation, ModeAnimationConfig};
+use crate::estimators::{EnergyBlocks, EnergyEstimate};
+use crate::pimc::{MoveSummary, PimcConfig, PimcMove, PimcSampler, RingPolymer};
+use crate::rpmd::RingPolymerMd;
+use crate::checkpoint::{MdCheckpoint, RngState, ThermostatState};
+use crate::force_field::{ForceField, ForceFieldConfig, NonbondedEnergy, NonbondedParams};
+use crate::neighbor_list::NeighborList;
+use crate::precision::{DoubleState, Precision};
+use crate::pressure::{kinetic_tensor, PressureTensor, Virial};
+use crate::qmmm::{QmMm, QmMmConfig};
+use crate::restraints::{GeometricRestraints, PositionRestraintConfig, PositionRestraints};
+use crate::rng::{RngHierarchy, RngStream, DEFAULT_SEED};
+use crate::units::{self, Energy, Length, Temperature, Time};
+use prism_core::{CancellationToken, PhaseOutcome, PrismError};
 use prism_io::sovereign_types::Atom;
 use prism_io::holographic::PtbStructure;
+use prism_io::pdb::PdbStructure;
+use prism_io::selection::SelectionContext;
+use prism_io::simulation_box::SimulationBox;
+use prism_io::topology::{LjParams, Topology};
+use prism_io::trajectory::{open_selected_trajectory, TrajectoryConfig, TrajectoryFrame, TrajectoryWriter};
+use rand_chacha::ChaCha12Rng;
+use rand_distr::{Distribution, StandardNormal};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::ControlFlow;
+#[cfg(not(target_arch = "wasm32"))]
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(not(target_arch = "wasm32"))]
+use std::task::{Context, Poll};
+use web_time::Instant;
 use std::ffi::{c_void, CString};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "cuda")]
 use cudarc::driver::CudaContext;
@@ -18,37 +55,317 @@ use cudarc::driver::CudaContext;
 use cudarc::nvrtc::Ptx;
 #[cfg(feature = "cuda")]
 use cudarc::driver::sys as cuda_sys;
+#[cfg(feature = "cuda")]
+use crate::frame_stream::{FrameRequest, FrameStream, FRAME_SLOTS};
+#[cfg(feature = "cuda")]
+use crate::gpu_timing::{nvtx_range, GpuTimer};
+#[cfg(feature = "cuda")]
+use prism_gpu::memory::{VramGuard, VramPool, VramRegion};
 
 // AUDIT: Must match CUDA static_assert in kernel
-const RNG_STATE_BYTES: usize = 64;
-
-// RAII Guard for Temp Files to ensure cleanup even on panic
-struct TempFileGuard {
-    path: String,
-}
-impl Drop for TempFileGuard {
-    fn drop(&mut self) {
-        if Path::new(&self.path).exists() {
-            let _ = std::fs::remove_file(&self.path);
-        }
-    }
-}
+pub(crate) const RNG_STATE_BYTES: usize = 64;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MolecularDynamicsConfig {
     pub max_steps: u64,
-    pub dt: f32,                 
+    /// Time step (ps); config files may also give e.g. `"2 fs"`
+    #[serde(with = "units::as_ps")]
+    pub dt: f32,
     pub friction: f32,           
-    pub temp_start: f32,         
-    pub temp_end: f32,           
+    /// Thermostat `k_B T` (kcal/mol) at the start of the anneal; config
+    /// files may also give an absolute temperature, e.g. `"300 K"`
+    #[serde(with = "units::as_kt")]
+    pub temp_start: f32,
+    #[serde(with = "units::as_kt")]
+    pub temp_end: f32,
     pub annealing_steps: u64,    
-    pub cutoff_dist: f32,        
+    #[serde(with = "units::as_angstrom")]
+    pub cutoff_dist: f32,
     pub spring_k: f32,           
     pub bias_strength: f32,      
     pub target_mode: usize,      
     pub use_gpu: bool,
+    /// Device memory for frame snapshots awaiting download; frames go on to
+    /// the trajectory writer, so this bounds frames in flight, not the run
     pub max_trajectory_memory: usize,
     pub max_workspace_memory: usize,
+    #[serde(default)]
+    pub force_field: ForceFieldConfig,
+    /// Trajectory output (disabled when `None`)
+    #[serde(default)]
+    pub trajectory: Option<TrajectoryConfig>,
+    /// Root seed for every stochastic component of the run
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+    /// Bond constraints applied by the host integrator
+    #[serde(default)]
+    pub constraints: ConstraintConfig,
+    /// Host integration scheme
+    #[serde(default)]
+    pub integrator: Integrator,
+    /// Settings for [`MolecularDynamicsEngine::minimize`]
+    #[serde(default)]
+    pub minimization: MinimizationConfig,
+    /// Thermostat schedule; `None` ramps linearly from `temp_start` to
+    /// `temp_end` over `annealing_steps`
+    #[serde(default)]
+    pub temperature_schedule: Option<TemperatureSchedule>,
+    /// Path-integral stage for [`MolecularDynamicsEngine::run_pimc`] and
+    /// [`MolecularDynamicsEngine::run_rpmd`]
+    #[serde(default)]
+    pub pimc: Option<PimcConfig>,
+    /// CA elastic network built from the topology; breathing runs report
+    /// the overlap of their displacement with its modes
+    #[serde(default)]
+    pub elastic_network: Option<ElasticNetworkConfig>,
+    /// Steps between on-the-fly analysis frames; host runs also record the
+    /// energy breakdown on these steps
+    #[serde(default = "default_analysis_interval")]
+    pub analysis_interval: u64,
+    /// Track radius of gyration, asphericity and gyration tensor moments
+    /// of the whole system on every analysis frame
+    #[serde(default)]
+    pub shape_analysis: bool,
+    /// Track the fraction of native contacts of the topology coordinates
+    /// on every analysis frame
+    #[serde(default)]
+    pub native_contacts: Option<NativeContactConfig>,
+    /// Assign DSSP secondary structure on every analysis frame
+    #[serde(default)]
+    pub secondary_structure: bool,
+    /// Shrake-Rupley solvent accessible surface area on every analysis frame
+    #[serde(default)]
+    pub sasa: Option<SasaConfig>,
+    /// Harmonic restraints of selections to the starting coordinates,
+    /// applied by the host force evaluation (minimization and CPU dynamics)
+    #[serde(default)]
+    pub position_restraints: Vec<PositionRestraintConfig>,
+    /// Distance, angle and dihedral restraint file (see
+    /// [`crate::restraints`]); violations are reported at the end of a run
+    #[serde(default)]
+    pub restraint_file: Option<PathBuf>,
+    /// Scalar type of the integration state and force accumulation (see
+    /// [`crate::precision`]); `double` keeps dynamics on the host, `mixed`
+    /// sums GPU energies and virials in `f64`
+    #[serde(default)]
+    pub precision: Precision,
+    /// Worker threads for host force evaluation; `None` shares rayon's
+    /// global pool (one thread per core). Results do not depend on it.
+    #[serde(default)]
+    pub num_threads: Option<usize>,
+    /// CUDA device ordinals for the nonbonded forces; more than one splits
+    /// the system into slabs with halo exchange (see
+    /// `prism_gpu::domain_decomposition`, not with PME). Empty = device 0.
+    #[serde(default)]
+    pub devices: Vec<usize>,
+    /// Replay the GPU integration step from a CUDA Graph instead of one
+    /// kernel launch per step (see [`CUDA_GRAPH_STEPS`])
+    #[serde(default = "default_cuda_graphs")]
+    pub cuda_graphs: bool,
+    /// What to do when the VRAM Guard rejects the GPU reservation
+    #[serde(default)]
+    pub vram_fallback: VramFallback,
+    /// GPU runtime used with `use_gpu`
+    #[serde(default)]
+    pub backend: DeviceBackend,
+    /// Benchmark the nonbonded kernel's block size on the first run on a
+    /// GPU model and reuse it from the per-GPU launch profile afterwards
+    /// (see `prism_gpu::autotune`; single-device CUDA runs)
+    #[serde(default = "default_autotune")]
+    pub autotune: bool,
+    /// Detection of exploding energies and NaN coordinates in host dynamics
+    /// and the response to them
+    #[serde(default)]
+    pub instability: InstabilityConfig,
+    /// QM region evaluated by an external QM program, replacing the force
+    /// field with a [`QmMm`] potential in [`MolecularDynamicsEngine::from_topology`]
+    #[serde(default)]
+    pub qmmm: Option<QmMmConfig>,
+}
+
+/// GPU runtime of the nonbonded forces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceBackend {
+    /// NVIDIA GPUs (`cuda` feature)
+    #[default]
+    Cuda,
+    /// AMD GPUs through HIP (`rocm` feature, see [`crate::hip`]); the
+    /// holographic integrator stays on the host
+    Rocm,
+    /// Any Vulkan, Metal, DirectX 12 or OpenGL GPU through `wgpu` (`wgpu`
+    /// feature, see [`crate::webgpu`]); nonbonded forces and the Langevin
+    /// step run on the device, `devices` is not used
+    Wgpu,
+}
+
+/// Response to a GPU reservation the VRAM Guard rejects. The decision is
+/// reported under `vram_fallback` in the run telemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VramFallback {
+    /// Fail engine construction
+    Error,
+    /// Single-buffer the frame snapshots, so frame downloads may briefly
+    /// wait on the previous one
+    ShrinkTrajectory,
+    /// Drop the frame snapshots: frames are copied synchronously from the
+    /// live positions and the trajectory stride is multiplied by
+    /// [`VRAM_FALLBACK_STRIDE_FACTOR`] to bound the stalls
+    ReduceStride,
+    /// Integrate on the host instead
+    #[default]
+    Cpu,
+}
+
+/// Trajectory stride multiplier of [`VramFallback::ReduceStride`]
+pub const VRAM_FALLBACK_STRIDE_FACTOR: u64 = 4;
+
+impl VramFallback {
+    /// Frame snapshot buffers to try reserving, in order
+    pub(crate) fn frame_slot_attempts(self, full: usize) -> Vec<usize> {
+        match self {
+            VramFallback::Error | VramFallback::Cpu => vec![full],
+            VramFallback::ShrinkTrajectory => vec![full, 1],
+            VramFallback::ReduceStride => vec![full, 0],
+        }
+    }
+}
+
+/// Fallback taken after a rejected GPU reservation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VramFallbackDecision {
+    pub policy: VramFallback,
+    /// Why the full reservation was rejected
+    pub reason: String,
+    /// Frame snapshot buffers reserved; 0 on the CPU path
+    pub frame_slots: usize,
+    /// Trajectory stride after the fallback
+    pub trajectory_stride: Option<u64>,
+}
+
+/// Host integration scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Integrator {
+    /// Single time-step Euler-Maruyama Langevin
+    #[default]
+    Langevin,
+    /// r-RESPA multiple time-step: bonded and restraint forces every `dt`,
+    /// nonbonded forces every `slow_interval` steps
+    Respa { slow_interval: u32 },
+}
+
+/// Force groups of the multiple time-step split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ForceGroup {
+    All,
+    /// Bonded terms and restraints
+    Fast,
+    /// Nonbonded (LJ, Coulomb, PME, solvation)
+    Slow,
+}
+
+fn default_seed() -> u64 {
+    DEFAULT_SEED
+}
+
+fn default_analysis_interval() -> u64 {
+    100
+}
+
+fn default_cuda_graphs() -> bool {
+    true
+}
+
+fn default_autotune() -> bool {
+    true
+}
+
+impl MolecularDynamicsConfig {
+    /// Fluent builder starting from the defaults
+    pub fn builder() -> MolecularDynamicsConfigBuilder {
+        MolecularDynamicsConfigBuilder::default()
+    }
+
+    /// Reject settings that cannot produce a meaningful run, naming the
+    /// offending field. Called by [`MolecularDynamicsEngine::new`].
+    pub fn validate(&self) -> Result<(), PrismError> {
+        let positive = |field: &str, value: f32, unit: &str| {
+            if value > 0.0 && value.is_finite() {
+                Ok(())
+            } else {
+                Err(PrismError::validation(format!("{} must be positive, got {} {}", field, value, unit)))
+            }
+        };
+        let non_negative = |field: &str, value: f32, unit: &str| {
+            if value >= 0.0 && value.is_finite() {
+                Ok(())
+            } else {
+                Err(PrismError::validation(format!("{} must be non-negative, got {} {}", field, value, unit)))
+            }
+        };
+        positive("dt", self.dt, "ps")?;
+        non_negative("friction", self.friction, "1/ps")?;
+        non_negative("temp_start", self.temp_start, "kcal/mol")?;
+        non_negative("temp_end", self.temp_end, "kcal/mol")?;
+        positive("cutoff_dist", self.cutoff_dist, "Å")?;
+        non_negative("spring_k", self.spring_k, "kcal/mol/Å²")?;
+        if self.max_trajectory_memory == 0 {
+            return Err(PrismError::validation("max_trajectory_memory must be at least 1 byte"));
+        }
+        if self.max_workspace_memory == 0 {
+            return Err(PrismError::validation("max_workspace_memory must be at least 1 byte"));
+        }
+
+        let ff = &self.force_field;
+        positive("force_field.cutoff", ff.cutoff, "Å")?;
+        positive("force_field.dielectric", ff.dielectric, "")?;
+        non_negative("force_field.neighbor_skin", ff.neighbor_skin, "Å")?;
+        if let Some(switch) = ff.switch_distance {
+            if !(switch >= 0.0 && switch < ff.cutoff) {
+                return Err(PrismError::validation(format!(
+                    "force_field.switch_distance must lie in [0, cutoff = {}) Å, got {}",
+                    ff.cutoff, switch
+                )));
+            }
+        }
+        if self.constraints.tolerance.is_nan() || self.constraints.tolerance <= 0.0 {
+            return Err(PrismError::validation(format!(
+                "constraints.tolerance must be positive, got {}",
+                self.constraints.tolerance
+            )));
+        }
+        if let Some(schedule) = &self.temperature_schedule {
+            schedule.validate()?;
+        }
+        if let Some(pimc) = &self.pimc {
+            pimc.validate()?;
+        }
+        if self.num_threads == Some(0) {
+            return Err(PrismError::validation("num_threads must be at least 1"));
+        }
+        if let Some(ordinal) = self.devices.iter().enumerate().find_map(|(k, d)| self.devices[..k].contains(d).then_some(d)) {
+            return Err(PrismError::validation(format!("devices lists CUDA device {} more than once", ordinal)));
+        }
+        if self.use_gpu && self.backend == DeviceBackend::Rocm && !cfg!(feature = "rocm") {
+            return Err(PrismError::validation("backend = \"rocm\" needs prism-physics built with the `rocm` feature"));
+        }
+        if self.use_gpu && self.backend == DeviceBackend::Wgpu && !cfg!(feature = "wgpu") {
+            return Err(PrismError::validation("backend = \"wgpu\" needs prism-physics built with the `wgpu` feature"));
+        }
+        self.instability.validate()?;
+        Ok(())
+    }
+
+    pub fn timestep(&self) -> Time {
+        Time::ps(self.dt as f64)
+    }
+
+    /// Thermostat temperatures at the start and end of the anneal
+    pub fn temperatures(&self) -> (Temperature, Temperature) {
+        let kelvin = |kt: f32| Temperature::from_kt(Energy::kcal_per_mol(kt as f64));
+        (kelvin(self.temp_start), kelvin(self.temp_end))
+    }
 }
 
 impl Default for MolecularDynamicsConfig {
@@ -67,10 +384,288 @@ impl Default for MolecularDynamicsConfig {
             use_gpu: true,
             max_trajectory_memory: 1024 * 1024 * 1024,
             max_workspace_memory: 512 * 1024 * 1024,
+            force_field: ForceFieldConfig::default(),
+            trajectory: None,
+            seed: DEFAULT_SEED,
+            constraints: ConstraintConfig::default(),
+            integrator: Integrator::default(),
+            minimization: MinimizationConfig::default(),
+            temperature_schedule: None,
+            pimc: None,
+            elastic_network: None,
+            analysis_interval: default_analysis_interval(),
+            shape_analysis: false,
+            native_contacts: None,
+            secondary_structure: false,
+            sasa: None,
+            position_restraints: Vec::new(),
+            restraint_file: None,
+            precision: Precision::default(),
+            num_threads: None,
+            devices: Vec::new(),
+            cuda_graphs: true,
+            vram_fallback: VramFallback::default(),
+            backend: DeviceBackend::default(),
+            autotune: true,
+            instability: InstabilityConfig::default(),
+            qmmm: None,
         }
     }
 }
 
+/// Builder for [`MolecularDynamicsConfig`]; [`build`](Self::build) runs
+/// [`MolecularDynamicsConfig::validate`].
+#[derive(Debug, Clone, Default)]
+pub struct MolecularDynamicsConfigBuilder {
+    config: MolecularDynamicsConfig,
+}
+
+impl MolecularDynamicsConfigBuilder {
+    /// Short host-only run for smoke tests: 1 000 steps of 1 fs at a
+    /// constant 300 K, no anchor springs
+    pub fn quick_test() -> Self {
+        Self::default()
+            .max_steps(1_000)
+            .timestep(Time::fs(1.0))
+            .temperature(Temperature::kelvin(300.0))
+            .spring_k(0.0)
+            .use_gpu(false)
+            .analysis_interval(100)
+    }
+
+    /// NLNM breathing run: GPU dynamics annealed from the default start
+    /// temperature down to near zero, with shape analysis on every frame
+    pub fn production_breathing() -> Self {
+        Self::default()
+            .use_gpu(true)
+            .analysis_interval(1_000)
+            .shape_analysis(true)
+    }
+
+    /// Constant-energy host dynamics for [`crate::energy_conservation`]: no
+    /// friction, anchor springs or bond constraints (the constraint
+    /// tolerance would dominate the drift); the temperature only matters to
+    /// [`MolecularDynamicsEngine::assign_velocities`]
+    pub fn nve_validation() -> Self {
+        Self::default()
+            .friction(0.0)
+            .spring_k(0.0)
+            .constraints(ConstraintConfig { mode: ConstraintMode::None, ..Default::default() })
+            .use_gpu(false)
+    }
+
+    /// Path-integral sampling of nuclear quantum effects at 300 K with the
+    /// default ring polymer ([`MolecularDynamicsEngine::run_pimc`] and
+    /// [`MolecularDynamicsEngine::run_rpmd`] run on the host)
+    pub fn quantum_pimc() -> Self {
+        Self::default()
+            .temperature(Temperature::kelvin(300.0))
+            .use_gpu(false)
+            .pimc(PimcConfig::default())
+    }
+
+    pub fn max_steps(mut self, steps: u64) -> Self {
+        self.config.max_steps = steps;
+        self
+    }
+
+    pub fn timestep(mut self, dt: Time) -> Self {
+        self.config.dt = dt.as_ps() as f32;
+        self
+    }
+
+    /// Langevin friction (1/ps)
+    pub fn friction(mut self, friction: f32) -> Self {
+        self.config.friction = friction;
+        self
+    }
+
+    /// Constant thermostat temperature
+    pub fn temperature(mut self, temperature: Temperature) -> Self {
+        let kt = temperature.kt().as_kcal_per_mol() as f32;
+        self.config.temp_start = kt;
+        self.config.temp_end = kt;
+        self
+    }
+
+    /// Linear anneal from `start` to `end` over `steps`
+    pub fn anneal(mut self, start: Temperature, end: Temperature, steps: u64) -> Self {
+        self.config.temp_start = start.kt().as_kcal_per_mol() as f32;
+        self.config.temp_end = end.kt().as_kcal_per_mol() as f32;
+        self.config.annealing_steps = steps;
+        self
+    }
+
+    pub fn temperature_schedule(mut self, schedule: TemperatureSchedule) -> Self {
+        self.config.temperature_schedule = Some(schedule);
+        self
+    }
+
+    /// Nonbonded cutoff of both the engine and its force field
+    pub fn cutoff(mut self, cutoff: Length) -> Self {
+        let cutoff = cutoff.as_angstrom() as f32;
+        self.config.cutoff_dist = cutoff;
+        self.config.force_field.cutoff = cutoff;
+        self
+    }
+
+    /// Anchor spring constant (kcal/mol/Å²)
+    pub fn spring_k(mut self, spring_k: f32) -> Self {
+        self.config.spring_k = spring_k;
+        self
+    }
+
+    /// Bias drive of `strength` along normal mode `target_mode`
+    pub fn bias(mut self, strength: f32, target_mode: usize) -> Self {
+        self.config.bias_strength = strength;
+        self.config.target_mode = target_mode;
+        self
+    }
+
+    pub fn use_gpu(mut self, use_gpu: bool) -> Self {
+        self.config.use_gpu = use_gpu;
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.config.precision = precision;
+        self
+    }
+
+    /// Worker threads for host force evaluation
+    pub fn num_threads(mut self, threads: usize) -> Self {
+        self.config.num_threads = Some(threads);
+        self
+    }
+
+    /// CUDA devices for the nonbonded forces (domain decomposition when
+    /// more than one)
+    pub fn devices(mut self, devices: impl Into<Vec<usize>>) -> Self {
+        self.config.devices = devices.into();
+        self
+    }
+
+    pub fn cuda_graphs(mut self, enabled: bool) -> Self {
+        self.config.cuda_graphs = enabled;
+        self
+    }
+
+    pub fn autotune(mut self, enabled: bool) -> Self {
+        self.config.autotune = enabled;
+        self
+    }
+
+    pub fn instability(mut self, instability: InstabilityConfig) -> Self {
+        self.config.instability = instability;
+        self
+    }
+
+    pub fn qmmm(mut self, qmmm: QmMmConfig) -> Self {
+        self.config.qmmm = Some(qmmm);
+        self
+    }
+
+    pub fn vram_fallback(mut self, policy: VramFallback) -> Self {
+        self.config.vram_fallback = policy;
+        self
+    }
+
+    pub fn backend(mut self, backend: DeviceBackend) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
+    /// Trajectory and workspace memory limits (bytes)
+    pub fn memory_limits(mut self, trajectory: usize, workspace: usize) -> Self {
+        self.config.max_trajectory_memory = trajectory;
+        self.config.max_workspace_memory = workspace;
+        self
+    }
+
+    pub fn force_field(mut self, force_field: ForceFieldConfig) -> Self {
+        self.config.force_field = force_field;
+        self
+    }
+
+    pub fn trajectory(mut self, trajectory: TrajectoryConfig) -> Self {
+        self.config.trajectory = Some(trajectory);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = seed;
+        self
+    }
+
+    pub fn constraints(mut self, constraints: ConstraintConfig) -> Self {
+        self.config.constraints = constraints;
+        self
+    }
+
+    pub fn integrator(mut self, integrator: Integrator) -> Self {
+        self.config.integrator = integrator;
+        self
+    }
+
+    pub fn minimization(mut self, minimization: MinimizationConfig) -> Self {
+        self.config.minimization = minimization;
+        self
+    }
+
+    pub fn pimc(mut self, pimc: PimcConfig) -> Self {
+        self.config.pimc = Some(pimc);
+        self
+    }
+
+    pub fn elastic_network(mut self, elastic_network: ElasticNetworkConfig) -> Self {
+        self.config.elastic_network = Some(elastic_network);
+        self
+    }
+
+    pub fn analysis_interval(mut self, interval: u64) -> Self {
+        self.config.analysis_interval = interval;
+        self
+    }
+
+    pub fn shape_analysis(mut self, enabled: bool) -> Self {
+        self.config.shape_analysis = enabled;
+        self
+    }
+
+    pub fn native_contacts(mut self, contacts: NativeContactConfig) -> Self {
+        self.config.native_contacts = Some(contacts);
+        self
+    }
+
+    pub fn secondary_structure(mut self, enabled: bool) -> Self {
+        self.config.secondary_structure = enabled;
+        self
+    }
+
+    pub fn sasa(mut self, sasa: SasaConfig) -> Self {
+        self.config.sasa = Some(sasa);
+        self
+    }
+
+    /// Add a harmonic position restraint on `selection`
+    pub fn position_restraint(mut self, selection: impl Into<String>, force_constant: f32) -> Self {
+        self.config
+            .position_restraints
+            .push(PositionRestraintConfig { selection: selection.into(), force_constant });
+        self
+    }
+
+    pub fn restraint_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.restraint_file = Some(path.into());
+        self
+    }
+
+    pub fn build(self) -> Result<MolecularDynamicsConfig, PrismError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
 #[derive(Debug)]
 pub struct SimulationBuffers {
     pub positions: Vec<f32>,
@@ -124,6 +719,54 @@ impl SimulationBuffers {
     }
 }
 
+/// Run-loop callback registered with [`MolecularDynamicsEngine::add_observer`];
+/// returning `ControlFlow::Break` ends the run after the current step
+pub type ObserverFn = dyn FnMut(&MolecularDynamicsStats) -> ControlFlow<()> + Send;
+
+struct Observer {
+    interval: u64,
+    callback: Box<ObserverFn>,
+}
+
+impl std::fmt::Debug for Observer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observer").field("interval", &self.interval).finish_non_exhaustive()
+    }
+}
+
+/// Progress samples buffered by [`AsyncRun`] before further ones are dropped
+#[cfg(not(target_arch = "wasm32"))]
+const ASYNC_PROGRESS_CAPACITY: usize = 64;
+
+/// A [`MolecularDynamicsEngine::run_async`] in flight: a stream of progress
+/// statistics that ends with the run, and a handle to the final result
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct AsyncRun {
+    progress: tokio::sync::mpsc::Receiver<MolecularDynamicsStats>,
+    task: tokio::task::JoinHandle<Result<(MolecularDynamicsEngine, PhaseOutcome), PrismError>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AsyncRun {
+    /// Wait for the run to end and take the engine back together with its outcome
+    pub async fn finish(self) -> Result<(MolecularDynamicsEngine, PhaseOutcome), PrismError> {
+        drop(self.progress);
+        self.task
+            .await
+            .map_err(|e| PrismError::Internal(format!("Async MD run did not complete: {}", e)))?
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl tokio_stream::Stream for AsyncRun {
+    type Item = MolecularDynamicsStats;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.progress.poll_recv(cx)
+    }
+}
+
 #[derive(Debug)]
 pub struct MolecularDynamicsEngine {
     config: MolecularDynamicsConfig,
@@ -131,15 +774,127 @@ pub struct MolecularDynamicsEngine {
     start_time: Instant,
     buffers: Option<SimulationBuffers>,
     atoms_metadata: Vec<Atom>,
+    selection_context: SelectionContext,
+    force_field: Option<ForceField>,
+    neighbor_list: Option<NeighborList>,
+    bonded: Option<BondedTerms>,
+    constraints: Option<Constraints>,
+    biases: Vec<Box<dyn BiasPotential>>,
+    bias_energy: f64,
+    /// Replaces the force field and bonded terms when set
+    potential: Option<Box<dyn Potential>>,
+    learned_energy: f64,
+    analyses: Vec<Box<dyn Analysis>>,
+    ring_polymer: Option<RingPolymer>,
+    pimc_sampler: Option<PimcSampler>,
+    rpmd: Option<RingPolymerMd>,
+    elastic_network: Option<ElasticNetwork>,
+    forces: Vec<f32>,
+    nonbonded_energy: NonbondedEnergy,
+    bonded_energy: BondedEnergy,
+    restraint_energy: f64,
+    position_restraints: PositionRestraints,
+    position_restraint_energies: Vec<f64>,
+    geometric_restraints: GeometricRestraints,
+    geometric_restraint_energy: f64,
+    energy_frames: Vec<(u64, EnergyComponents)>,
+    /// Nonbonded virial of the last slow-group evaluation; `None` after a
+    /// GPU evaluation until refreshed on the host
+    nonbonded_virial: Option<Virial>,
+    /// Single-sum virial of the bonded, restraint and bias forces
+    bonded_virial: Virial,
+    /// Virial of the constraint forces of the last step
+    constraint_virial: Virial,
+    pressure_frames: Vec<(u64, PressureTensor)>,
+    /// f64 positions and velocities of [`Precision::Double`] runs
+    double_state: Option<DoubleState>,
+    observers: Vec<Observer>,
+    /// Step at which an observer ended the current run
+    stopped_at: Option<u64>,
+    cancellation: Option<CancellationToken>,
+    /// Step at which the current run was cancelled
+    cancelled_at: Option<u64>,
+    /// Dedicated pool for `num_threads`; `None` uses rayon's global pool
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    gradient_norm: f32,
+    rng: ChaCha12Rng,
+    simulation_box: Option<SimulationBox>,
+    trajectory: Option<Box<dyn TrajectoryWriter>>,
     #[cfg(feature = "cuda")]
     gpu_state: Option<HolographicGpuState>,
+    #[cfg(feature = "cuda")]
+    nonbonded_gpu: Option<NonbondedDevice>,
+    /// Device time per phase of the CUDA path, reported with each run
+    #[cfg(feature = "cuda")]
+    gpu_timer: GpuTimer,
+    /// Set when the GPU reservation was rejected and a fallback applied
+    vram_fallback: Option<VramFallbackDecision>,
+    #[cfg(feature = "rocm")]
+    hip_nonbonded: Option<crate::hip::HipNonbonded>,
+    #[cfg(feature = "wgpu")]
+    wgpu_compute: Option<crate::webgpu::WgpuCompute>,
+    /// Last stable state of host dynamics, see [`crate::instability`]
+    stable_state: Option<StableState>,
+    /// Instability recoveries of the current run
+    recoveries: Vec<InstabilityRecovery>,
+}
+
+/// Snapshot that host dynamics continue from after an instability
+#[derive(Debug, Clone)]
+struct StableState {
+    step: u64,
+    energy: f64,
+    positions: Vec<f32>,
+    velocities: Vec<f32>,
+    double: Option<DoubleState>,
+}
+
+/// GPU nonbonded evaluator on one device or decomposed over several
+#[cfg(feature = "cuda")]
+#[derive(Debug)]
+enum NonbondedDevice {
+    Single(prism_gpu::nonbonded::NonbondedGpu),
+    Decomposed(prism_gpu::domain_decomposition::DomainDecompositionGpu),
+}
+
+#[cfg(feature = "cuda")]
+impl NonbondedDevice {
+    fn compute(&mut self, timer: &mut GpuTimer, positions: &[f32], forces: &mut [f32]) -> anyhow::Result<(f64, f64)> {
+        match self {
+            Self::Single(gpu) => {
+                // Timing is best effort and never fails the evaluation
+                let span = timer.begin(gpu.device(), "nonbonded", gpu.stream().cu_stream()).ok();
+                let result = gpu.compute(positions, forces);
+                if let Some(span) = span {
+                    let _ = timer.end(span);
+                }
+                result
+            }
+            Self::Decomposed(gpu) => {
+                let _range = nvtx_range("nonbonded_decomposed");
+                gpu.compute(positions, forces)
+            }
+        }
+    }
+
+    /// Pair virial of the last evaluation (mixed-precision kernels only)
+    fn virial(&self) -> Option<Virial> {
+        match self {
+            Self::Single(gpu) => gpu.virial().map(Virial),
+            Self::Decomposed(gpu) => gpu.virial().map(Virial),
+        }
+    }
 }
 
 #[cfg(feature = "cuda")]
 #[derive(Debug)]
 struct HolographicGpuState {
-    // We hold the context to keep it alive
-    _ctx: Arc<CudaContext>, 
+    // We hold the context to keep it alive and to bind it on the running thread
+    ctx: Arc<CudaContext>,
+    /// Compute stream of this engine. It blocks with the legacy stream, so
+    /// synchronous downloads still wait for the step kernels, while engines
+    /// sharing the device (replica exchange) overlap on their own streams.
+    stream: cuda_sys::CUstream,
     raw_module: cuda_sys::CUmodule, 
     step_kernel: cuda_sys::CUfunction,
     init_rng_kernel: cuda_sys::CUfunction,
@@ -149,57 +904,717 @@ struct HolographicGpuState {
     d_bias_vec: u64,
     d_rng_states: u64, 
     num_atoms: usize,
+    /// Owns the device buffers above and the frame snapshots
+    pool: VramPool,
+    /// Empty after a [`VramFallback::ReduceStride`] fallback
+    frame_snapshots: Vec<u64>,
+    /// Built on the first run when `cuda_graphs` is set
+    step_graph: Option<StepGraph>,
+    /// Built on the first run that writes trajectory or analysis frames
+    frames: Option<FrameStream>,
+}
+
+#[cfg(feature = "cuda")]
+impl HolographicGpuState {
+    /// Step kernel arguments; the step index and temperature are set per step
+    fn step_args(&self, config: &MolecularDynamicsConfig) -> StepArgs {
+        StepArgs {
+            d_positions: self.d_positions,
+            d_anchors: self.d_anchors,
+            d_velocities: self.d_velocities,
+            d_bias_vec: self.d_bias_vec,
+            n_atoms: self.num_atoms as i32,
+            dt: config.dt,
+            friction: config.friction,
+            temperature: 0.0,
+            bias_strength: config.bias_strength,
+            spring_k: config.spring_k,
+            d_rng_states: self.d_rng_states,
+            step_idx: 0,
+            annealing_steps: (config.annealing_steps.min(i32::MAX as u64) as i32).max(1),
+        }
+    }
+}
+
+/// Steps per CUDA Graph replay. Batches are cut into whole graphs and the
+/// remainder is launched step by step.
+#[cfg(feature = "cuda")]
+pub const CUDA_GRAPH_STEPS: u64 = 100;
+
+/// Argument values of `holographic_step_kernel`, in launch order
+#[cfg(feature = "cuda")]
+struct StepArgs {
+    d_positions: u64,
+    d_anchors: u64,
+    d_velocities: u64,
+    d_bias_vec: u64,
+    n_atoms: i32,
+    dt: f32,
+    friction: f32,
+    temperature: f32,
+    bias_strength: f32,
+    spring_k: f32,
+    d_rng_states: u64,
+    step_idx: i32,
+    annealing_steps: i32,
+}
+
+#[cfg(feature = "cuda")]
+impl StepArgs {
+    /// `kernelParams` for a launch or graph node; the driver copies the
+    /// values, so they only need to outlive the call that takes them
+    fn pointers(&mut self) -> [*mut c_void; 14] {
+        // The kernel ramps between its two temperatures; passing the
+        // scheduled target as both holds it for this step
+        [
+            &mut self.d_positions as *mut _ as *mut c_void,
+            &mut self.d_anchors as *mut _ as *mut c_void,
+            &mut self.d_velocities as *mut _ as *mut c_void,
+            &mut self.d_bias_vec as *mut _ as *mut c_void,
+            &mut self.n_atoms as *mut _ as *mut c_void,
+            &mut self.dt as *mut _ as *mut c_void,
+            &mut self.friction as *mut _ as *mut c_void,
+            &mut self.temperature as *mut _ as *mut c_void,
+            &mut self.temperature as *mut _ as *mut c_void,
+            &mut self.bias_strength as *mut _ as *mut c_void,
+            &mut self.spring_k as *mut _ as *mut c_void,
+            &mut self.d_rng_states as *mut _ as *mut c_void,
+            &mut self.step_idx as *mut _ as *mut c_void,
+            &mut self.annealing_steps as *mut _ as *mut c_void,
+        ]
+    }
+}
+
+/// Chain of [`CUDA_GRAPH_STEPS`] step kernels instantiated once. Each
+/// replay rewrites the per-step arguments (step index and scheduled
+/// temperature) in the executable graph and submits the whole chain with
+/// a single launch, removing the per-step launch overhead that dominates
+/// for small systems.
+#[cfg(feature = "cuda")]
+#[derive(Debug)]
+struct StepGraph {
+    graph: cuda_sys::CUgraph,
+    exec: cuda_sys::CUgraphExec,
+    nodes: Vec<cuda_sys::CUgraphNode>,
+    kernel: cuda_sys::CUfunction,
+    blocks: u32,
+    threads: u32,
+}
+
+#[cfg(feature = "cuda")]
+impl StepGraph {
+    fn node_params(&self, args: &mut [*mut c_void; 14]) -> cuda_sys::CUDA_KERNEL_NODE_PARAMS {
+        // SAFETY: all-zero is a valid (null handle) value for every field
+        let mut params: cuda_sys::CUDA_KERNEL_NODE_PARAMS = unsafe { std::mem::zeroed() };
+        params.func = self.kernel;
+        params.gridDimX = self.blocks;
+        params.gridDimY = 1;
+        params.gridDimZ = 1;
+        params.blockDimX = self.threads;
+        params.blockDimY = 1;
+        params.blockDimZ = 1;
+        params.kernelParams = args.as_mut_ptr();
+        params
+    }
+
+    /// Record the chain; the context owning `kernel` must be current
+    fn build(kernel: cuda_sys::CUfunction, blocks: u32, threads: u32, args: &mut StepArgs) -> Result<Self, PrismError> {
+        let mut graph = Self { graph: std::ptr::null_mut(), exec: std::ptr::null_mut(), nodes: Vec::new(), kernel, blocks, threads };
+        unsafe {
+            let res = cuda_sys::cuGraphCreate(&mut graph.graph, 0);
+            if res != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("cuGraphCreate", format!("{:?}", res))); }
+            for _ in 0..CUDA_GRAPH_STEPS {
+                let mut pointers = args.pointers();
+                let params = graph.node_params(&mut pointers);
+                let mut node: cuda_sys::CUgraphNode = std::ptr::null_mut();
+                // Each step depends on the previous one
+                let (deps, num_deps) = match graph.nodes.last() {
+                    Some(prev) => (prev as *const cuda_sys::CUgraphNode, 1),
+                    None => (std::ptr::null(), 0),
+                };
+                let res = cuda_sys::cuGraphAddKernelNode_v2(&mut node, graph.graph, deps, num_deps, &params);
+                if res != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("cuGraphAddKernelNode", format!("{:?}", res))); }
+                graph.nodes.push(node);
+            }
+            let res = cuda_sys::cuGraphInstantiateWithFlags(&mut graph.exec, graph.graph, 0);
+            if res != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("cuGraphInstantiate", format!("{:?}", res))); }
+        }
+        Ok(graph)
+    }
+
+    /// Run [`CUDA_GRAPH_STEPS`] steps from `args.step_idx`, asking
+    /// `temperature_at` for each step's target
+    fn replay(&self, stream: cuda_sys::CUstream, args: &mut StepArgs, temperature_at: impl Fn(u64) -> f32) -> Result<(), PrismError> {
+        let first_step = args.step_idx as u64;
+        for (k, &node) in self.nodes.iter().enumerate() {
+            let step = first_step + k as u64;
+            args.step_idx = step as i32;
+            args.temperature = temperature_at(step);
+            let mut pointers = args.pointers();
+            let params = self.node_params(&mut pointers);
+            unsafe {
+                let res = cuda_sys::cuGraphExecKernelNodeSetParams_v2(self.exec, node, &params);
+                if res != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("cuGraphExecKernelNodeSetParams", format!("{:?}", res))); }
+            }
+        }
+        unsafe {
+            let res = cuda_sys::cuGraphLaunch(self.exec, stream);
+            if res != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("cuGraphLaunch", format!("{:?}", res))); }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cuda")]
+impl Drop for StepGraph {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.exec.is_null() {
+                let _ = cuda_sys::cuGraphExecDestroy(self.exec);
+            }
+            if !self.graph.is_null() {
+                let _ = cuda_sys::cuGraphDestroy(self.graph);
+            }
+        }
+    }
 }
 
+// The raw module, function and device handles belong to `ctx`, which is
+// bound to the current thread before they are used (see `run_nlnm_breathing`)
+#[cfg(feature = "cuda")]
+unsafe impl Send for HolographicGpuState {}
+
 #[cfg(feature = "cuda")]
 impl Drop for HolographicGpuState {
     fn drop(&mut self) {
+        // The graph references the step kernel, so it goes before the module
+        self.step_graph = None;
+        self.frames = None;
+        // The buffers are released with `pool`, which drops after this
         unsafe {
-            // Explicitly free GPU memory to prevent leaks during RL training loops
-            let _ = cuda_sys::cuMemFree_v2(self.d_positions);
-            let _ = cuda_sys::cuMemFree_v2(self.d_anchors);
-            let _ = cuda_sys::cuMemFree_v2(self.d_velocities);
-            let _ = cuda_sys::cuMemFree_v2(self.d_bias_vec);
-            let _ = cuda_sys::cuMemFree_v2(self.d_rng_states);
+            if !self.stream.is_null() {
+                let _ = cuda_sys::cuStreamSynchronize(self.stream);
+                let _ = cuda_sys::cuStreamDestroy_v2(self.stream);
+            }
             let _ = cuda_sys::cuModuleUnload(self.raw_module);
         }
     }
 }
 
+/// Set the block size of `gpu` from the device's launch profile, tuning it
+/// on `positions` and saving the profile when it has no entry yet. Failures
+/// keep the default block size.
+#[cfg(feature = "cuda")]
+fn apply_launch_profile(gpu: &mut prism_gpu::nonbonded::NonbondedGpu, positions: &[f32]) {
+    use prism_gpu::autotune::{self, LaunchProfile};
+    let Some(dir) = autotune::profile_dir() else {
+        tracing::warn!(block_size = gpu.block_size(), "No launch profile directory (set PRISM_GPU_PROFILE_DIR); using the default block size");
+        return;
+    };
+    let mut profile = match LaunchProfile::for_context(gpu.device()) {
+        Ok(profile) => profile.load_or(&dir),
+        Err(e) => {
+            tracing::warn!(error = %format_args!("{:#}", e), "Launch profile unavailable");
+            return;
+        }
+    };
+    let kernel = gpu.active_kernel();
+    if let Some(block_size) = profile.block_size(kernel) {
+        match gpu.set_block_size(block_size) {
+            Ok(()) => tracing::info!(kernel, device = %profile.device, block_size, "Block size from launch profile"),
+            Err(e) => tracing::warn!(kernel, device = %profile.device, block_size, error = %format_args!("{:#}", e), "Ignoring launch profile entry"),
+        }
+        return;
+    }
+    tracing::info!(kernel, device = %profile.device, "Tuning block size (first run on this GPU)");
+    match gpu.autotune(positions, &autotune::BLOCK_SIZE_CANDIDATES, autotune::AUTOTUNE_REPEATS) {
+        Ok(tuning) => {
+            profile.record(kernel, tuning);
+            match profile.save(&dir) {
+                Ok(path) => tracing::info!(kernel, device = %profile.device, path = %path.display(), "Launch profile saved"),
+                Err(e) => tracing::warn!(kernel, device = %profile.device, error = %format_args!("{:#}", e), "Launch profile not saved"),
+            }
+        }
+        Err(e) => tracing::warn!(kernel, device = %profile.device, block_size = gpu.block_size(), error = %format_args!("{:#}", e), "Autotuning failed, using the default block size"),
+    }
+}
+
+/// Device pair energies `(lennard_jones, coulomb)` completed with the terms
+/// the pair kernels leave to the host: 1-4 pairs, PME corrections and
+/// solvation (forces added into `forces`). The 1-4 virial is added to
+/// `virial` when given.
+#[cfg(any(feature = "cuda", feature = "rocm", feature = "wgpu"))]
+fn with_host_terms(ff: &ForceField, positions: &[f32], forces: &mut [f32], (lennard_jones, coulomb): (f64, f64), virial: Option<&mut Virial>) -> NonbondedEnergy {
+    let e14 = match virial {
+        Some(virial) => ff.compute_pairs14_with_virial(positions, forces, virial),
+        None => ff.compute_pairs14(positions, forces),
+    };
+    let pme = ff.compute_pme_corrections(positions, Some(&mut *forces));
+    NonbondedEnergy {
+        lennard_jones: lennard_jones + e14.lennard_jones,
+        coulomb: coulomb + e14.coulomb + pme,
+        solvation: ff.compute_solvation(positions, Some(forces)),
+    }
+}
+
+fn write_trajectory_frame(
+    writer: &mut Option<Box<dyn TrajectoryWriter>>,
+    positions: &[f32],
+    velocities: Option<&[f32]>,
+    step: u64,
+    dt: f32,
+    simulation_box: Option<SimulationBox>,
+) -> Result<(), PrismError> {
+    let Some(writer) = writer else { return Ok(()) };
+    writer
+        .write_frame(&TrajectoryFrame { step, time_ps: step as f64 * dt as f64, positions, velocities, simulation_box })
+        .map_err(|e| PrismError::internal(format!("Trajectory write failed at step {}: {}", step, e)))
+}
+
+/// Steps per `md_block` tracing span
+pub const TRACE_BLOCK_STEPS: u64 = 1000;
+
+/// Open `md_block` span of a run. A block covers at least
+/// [`TRACE_BLOCK_STEPS`] steps; on the GPU path it ends on the first batch
+/// boundary after that, so `last_step` is recorded when it closes.
+#[derive(Default)]
+struct BlockSpan {
+    open: Option<(u64, tracing::span::EnteredSpan)>,
+}
+
+impl BlockSpan {
+    /// Start the next block once the open one has covered its steps
+    fn advance(&mut self, step: u64) {
+        if self.open.as_ref().is_some_and(|(first, _)| step < first + TRACE_BLOCK_STEPS) {
+            return;
+        }
+        self.close(step);
+        let span = tracing::info_span!("md_block", first_step = step, last_step = tracing::field::Empty);
+        self.open = Some((step, span.entered()));
+    }
+
+    /// End the open block before `step`
+    fn close(&mut self, step: u64) {
+        if let Some((_, span)) = self.open.take() {
+            span.record("last_step", step.saturating_sub(1));
+        }
+    }
+}
+
+/// Spring energy and delocalisation of a ring polymer for run telemetry
+fn ring_polymer_telemetry(polymer: &RingPolymer, kt: f64) -> HashMap<String, serde_json::Value> {
+    let radii = polymer.gyration_radii();
+    HashMap::from([
+        ("num_beads".to_string(), serde_json::json!(polymer.num_beads())),
+        ("spring_energy".to_string(), serde_json::json!(polymer.spring_energy(kt))),
+        (
+            "mean_gyration_radius".to_string(),
+            serde_json::json!(radii.iter().sum::<f64>() / radii.len().max(1) as f64),
+        ),
+    ])
+}
+
 impl MolecularDynamicsEngine {
     pub fn new(config: MolecularDynamicsConfig) -> Result<Self, PrismError> {
+        config.validate()?;
+        let rng = RngHierarchy::new(config.seed).stream(RngStream::Langevin);
+        let thread_pool = match config.num_threads {
+            Some(threads) => Some(Arc::new(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .thread_name(|i| format!("prism-md-{}", i))
+                    .build()
+                    .map_err(|e| PrismError::Internal(format!("Failed to start {} force threads: {}", threads, e)))?,
+            )),
+            None => None,
+        };
+        let mut analyses: Vec<Box<dyn Analysis>> = Vec::new();
+        if config.shape_analysis {
+            analyses.push(Box::new(ShapeAnalysis::new(Vec::new())));
+        }
         Ok(Self {
             config,
             current_step: 0,
             start_time: Instant::now(),
             buffers: None,
             atoms_metadata: Vec::new(),
+            selection_context: SelectionContext::default(),
+            force_field: None,
+            neighbor_list: None,
+            bonded: None,
+            constraints: None,
+            biases: Vec::new(),
+            bias_energy: 0.0,
+            potential: None,
+            learned_energy: 0.0,
+            analyses,
+            ring_polymer: None,
+            pimc_sampler: None,
+            rpmd: None,
+            elastic_network: None,
+            forces: Vec::new(),
+            nonbonded_energy: NonbondedEnergy::default(),
+            bonded_energy: BondedEnergy::default(),
+            restraint_energy: 0.0,
+            position_restraints: PositionRestraints::default(),
+            position_restraint_energies: Vec::new(),
+            geometric_restraints: GeometricRestraints::default(),
+            geometric_restraint_energy: 0.0,
+            energy_frames: Vec::new(),
+            nonbonded_virial: None,
+            bonded_virial: Virial::default(),
+            constraint_virial: Virial::default(),
+            pressure_frames: Vec::new(),
+            double_state: None,
+            observers: Vec::new(),
+            stopped_at: None,
+            cancellation: None,
+            cancelled_at: None,
+            thread_pool,
+            gradient_norm: 0.0,
+            rng,
+            simulation_box: None,
+            trajectory: None,
             #[cfg(feature = "cuda")]
             gpu_state: None,
+            #[cfg(feature = "cuda")]
+            nonbonded_gpu: None,
+            #[cfg(feature = "cuda")]
+            gpu_timer: GpuTimer::default(),
+            vram_fallback: None,
+            #[cfg(feature = "rocm")]
+            hip_nonbonded: None,
+            #[cfg(feature = "wgpu")]
+            wgpu_compute: None,
+            stable_state: None,
+            recoveries: Vec::new(),
         })
     }
 
     pub fn from_sovereign_buffer(config: MolecularDynamicsConfig, sovereign_data: &[u8]) -> Result<Self, PrismError> {
-        log::info!("🧬 Initializing Holographic Engine v3.1...");
+        tracing::info!(format = "sovereign", "Initializing engine");
         let atoms = Self::parse_protein_structure(sovereign_data)?;
+        Self::from_parsed_atoms(config, atoms)
+    }
+
+    /// Build an engine from a PDB, mmCIF or PTB file. PTB files are mapped
+    /// and their atoms copied out block by block, so large assemblies never
+    /// pass through an in-memory copy of the whole file.
+    pub fn from_structure_file(config: MolecularDynamicsConfig, path: &Path) -> Result<Self, PrismError> {
+        let read_error = |e: prism_io::PrismIoError| PrismError::config(format!("Failed to read {}: {}", path.display(), e));
+        match prism_io::structure_file::StructureFormat::from_path(path).map_err(read_error)? {
+            prism_io::structure_file::StructureFormat::Ptb => {
+                tracing::info!(format = "ptb", path = %path.display(), "Initializing engine");
+                let atoms = PtbStructure::load(path).and_then(|mut ptb| ptb.collect_atoms()).map_err(read_error)?;
+                if atoms.is_empty() {
+                    return Err(PrismError::validation("Empty data"));
+                }
+                Self::from_parsed_atoms(config, atoms)
+            }
+            _ => {
+                let buffer = prism_io::structure_file::sovereign_buffer(path).map_err(read_error)?;
+                Self::from_sovereign_buffer(config, &buffer)
+            }
+        }
+    }
+
+    fn from_parsed_atoms(config: MolecularDynamicsConfig, atoms: Vec<Atom>) -> Result<Self, PrismError> {
         let buffers = SimulationBuffers::from_atoms(&atoms);
         let mut engine = Self::new(config)?;
+        engine.force_field = Some(ForceField::from_atoms(engine.config.force_field.clone(), &atoms));
+        engine.selection_context = SelectionContext::from_atoms(&atoms);
         engine.atoms_metadata = atoms;
         engine.buffers = Some(buffers);
+        engine.attach_restraints()?;
+        #[cfg(feature = "wgpu")]
+        if engine.config.use_gpu && engine.config.backend == DeviceBackend::Wgpu { engine.initialize_wgpu()?; }
+        engine.evaluate_forces();
+        #[cfg(feature = "cuda")]
+        if engine.config.use_gpu && engine.config.backend == DeviceBackend::Cuda {
+            if engine.config.precision == Precision::Double {
+                tracing::info!(precision = "double", backend = "host", "Integrating on the host in f64");
+            } else {
+                engine.initialize_holographic_gpu()?;
+            }
+        }
+        Ok(engine)
+    }
+
+    /// Build an engine from an imported topology (AMBER/CHARMM/GROMACS),
+    /// using its nonbonded parameters, exclusions, bonded terms and masses.
+    /// With `use_gpu` the nonbonded forces are evaluated on the device.
+    pub fn from_topology(config: MolecularDynamicsConfig, topology: &Topology) -> Result<Self, PrismError> {
+        if topology.atoms.is_empty() {
+            return Err(PrismError::validation("Topology contains no atoms"));
+        }
+        let mut buffers = SimulationBuffers::from_atoms(&topology.atoms);
+        if topology.masses.len() == topology.atoms.len() {
+            for (i, &m) in topology.masses.iter().enumerate() {
+                buffers.positions[i * 4 + 3] = m;
+            }
+        }
+        if let Some(cell) = &topology.simulation_box {
+            if config.force_field.cutoff > cell.max_cutoff() {
+                return Err(PrismError::config(format!(
+                    "Nonbonded cutoff {} Å exceeds half the smallest width of the periodic box ({} Å)",
+                    config.force_field.cutoff,
+                    cell.max_cutoff()
+                )));
+            }
+        }
+        let mut engine = Self::new(config)?;
+        engine.force_field = Some(ForceField::from_topology(engine.config.force_field.clone(), topology));
+        engine.bonded = Some(BondedTerms::from_topology(topology));
+        let constraints = Constraints::from_topology(&engine.config.constraints, topology);
+        if !constraints.is_empty() {
+            tracing::info!(bonds = constraints.constraints().len(), waters = constraints.waters().len(), "Constraints attached");
+            engine.constraints = Some(constraints);
+        }
+        if let Some(config) = engine.config.elastic_network.clone() {
+            let network = ElasticNetwork::from_topology(topology, config)?;
+            tracing::info!(
+                nodes = network.num_nodes(),
+                springs = network.contacts().len(),
+                lowest_eigenvalue = network.modes().first().map_or(0.0, |m| m.eigenvalue),
+                "Elastic network built"
+            );
+            engine.elastic_network = Some(network);
+        }
+        if let Some(config) = engine.config.native_contacts.clone() {
+            let contacts = NativeContacts::from_topology(topology, config)?;
+            tracing::info!(contacts = contacts.contacts().len(), residue_pairs = contacts.residue_contacts().len(), "Native contacts attached");
+            engine.analyses.push(Box::new(contacts));
+        }
+        if engine.config.secondary_structure {
+            let analysis = SecondaryStructureAnalysis::from_topology(topology)?;
+            tracing::info!(residues = analysis.backbone().num_residues(), "Secondary structure analysis attached");
+            engine.analyses.push(Box::new(analysis));
+        }
+        if let Some(config) = &engine.config.sasa {
+            let calculator = SasaCalculator::from_topology(topology, config)?;
+            tracing::info!(atoms = calculator.num_atoms(), points_per_sphere = config.num_points, "SASA analysis attached");
+            engine.analyses.push(Box::new(SasaAnalysis::new(calculator)));
+        }
+        engine.atoms_metadata = topology.atoms.clone();
+        engine.selection_context = SelectionContext::from_topology(topology);
+        engine.simulation_box = topology.simulation_box;
+        engine.buffers = Some(buffers);
+        engine.attach_restraints()?;
+        if let Some(config) = engine.config.qmmm.clone() {
+            let qmmm = QmMm::from_config(&config, engine.config.force_field.clone(), topology)?;
+            tracing::info!(
+                qm_atoms = qmmm.qm_atoms().len(),
+                link_atoms = qmmm.links().len(),
+                point_charges = qmmm.num_point_charges(),
+                engine = qmmm.name(),
+                "QM/MM region attached"
+            );
+            // Host forces only; the GPU nonbonded path is bypassed with a potential
+            engine.set_potential(Box::new(qmmm))?;
+            return Ok(engine);
+        }
         #[cfg(feature = "cuda")]
-        if engine.config.use_gpu { engine.initialize_holographic_gpu()?; }
+        if engine.config.use_gpu && engine.config.backend == DeviceBackend::Cuda { engine.initialize_gpu_forces()?; }
+        #[cfg(feature = "rocm")]
+        if engine.config.use_gpu && engine.config.backend == DeviceBackend::Rocm { engine.initialize_hip_forces()?; }
+        #[cfg(feature = "wgpu")]
+        if engine.config.use_gpu && engine.config.backend == DeviceBackend::Wgpu { engine.initialize_wgpu()?; }
+        engine.evaluate_forces();
         Ok(engine)
     }
 
+    /// Build an engine for a receptor structure with parameterized ligands
+    /// (see [`prism_io::gaff`]) appended after it. The receptor keeps the
+    /// treatment of [`Self::from_structure_file`] (element LJ parameters,
+    /// exclusions within `exclusion_distance`) while the ligands bring their
+    /// bonded terms, 1-4 pairs and masses.
+    pub fn from_complex(
+        config: MolecularDynamicsConfig,
+        receptor: &PdbStructure,
+        ligands: &[Topology],
+    ) -> Result<Self, PrismError> {
+        let mut topology = receptor_topology(&config.force_field, receptor);
+        for ligand in ligands {
+            topology.append(ligand);
+        }
+        tracing::info!(receptor_atoms = receptor.atoms.len(), ligand_atoms = topology.num_atoms() - receptor.atoms.len(), "Complex assembled");
+        Self::from_topology(config, &topology)
+    }
+
+    /// Resolve the configured position restraints against the starting
+    /// coordinates and load the restraint file.
+    fn attach_restraints(&mut self) -> Result<(), PrismError> {
+        if self.config.position_restraints.is_empty() && self.config.restraint_file.is_none() {
+            return Ok(());
+        }
+        let context = self.selection_context()?;
+        if let Some(path) = &self.config.restraint_file {
+            let restraints = GeometricRestraints::read(path, &context)?;
+            tracing::info!(restraints = restraints.len(), path = %path.display(), "Geometric restraints attached");
+            self.geometric_restraints = restraints;
+        }
+        let buffers = self.buffers.as_ref().ok_or(PrismError::Internal("No buffers".into()))?;
+        let restraints = PositionRestraints::new(&self.config.position_restraints, &context, &buffers.positions)?;
+        for (i, config) in restraints.configs().enumerate() {
+            tracing::info!(selection = %config.selection, atoms = restraints.atoms(i).len(), force_constant = config.force_constant, "Position restraint attached");
+        }
+        self.position_restraints = restraints;
+        Ok(())
+    }
+
+    /// Upload the force field tables and allocate device buffers for the
+    /// nonbonded kernel.
+    #[cfg(feature = "cuda")]
+    fn initialize_gpu_forces(&mut self) -> Result<(), PrismError> {
+        let _span = tracing::info_span!("gpu_upload", stage = "nonbonded").entered();
+        let Some(ff) = &self.force_field else { return Ok(()) };
+        if ff.simulation_box().is_some_and(|cell| !cell.is_orthorhombic()) {
+            tracing::warn!(backend = "cuda", reason = "triclinic box", "Nonbonded forces stay on the host");
+            self.nonbonded_gpu = None;
+            return Ok(());
+        }
+        let mut system = ff.to_gpu_system();
+        system.mixed_precision = self.config.precision != Precision::Single;
+        let devices = &self.config.devices;
+        if devices.len() > 1 && system.pme.is_none() {
+            let buffers = self.buffers.as_ref().ok_or(PrismError::Internal("No buffers".into()))?;
+            let gpu = prism_gpu::domain_decomposition::DomainDecompositionGpu::new(devices, &system, &buffers.positions)
+                .map_err(|e| PrismError::gpu("domain_decomposition", format!("{:#}", e)))?;
+            tracing::info!(devices = gpu.num_domains(), atoms = gpu.num_atoms(), "Nonbonded forces on GPU");
+            self.nonbonded_gpu = Some(NonbondedDevice::Decomposed(gpu));
+            return Ok(());
+        }
+        if devices.len() > 1 {
+            tracing::warn!(device = devices[0], reason = "PME", "Nonbonded forces are not decomposed across devices");
+        }
+        let ordinal = devices.first().copied().unwrap_or(0);
+        let ctx = CudaContext::new(ordinal).map_err(|e| PrismError::gpu("init", format!("{:?}", e)))?;
+        let mut gpu = prism_gpu::nonbonded::NonbondedGpu::new(ctx, &system)
+            .map_err(|e| PrismError::gpu("nonbonded", e.to_string()))?;
+        if self.config.autotune {
+            let buffers = self.buffers.as_ref().ok_or(PrismError::Internal("No buffers".into()))?;
+            apply_launch_profile(&mut gpu, &buffers.positions);
+        }
+        tracing::info!(device = ordinal, atoms = gpu.num_atoms(), "Nonbonded forces on GPU");
+        self.nonbonded_gpu = Some(NonbondedDevice::Single(gpu));
+        Ok(())
+    }
+
+    /// Upload the force field tables to the HIP nonbonded kernel. PME and
+    /// triclinic systems keep their nonbonded forces on the host.
+    #[cfg(feature = "rocm")]
+    fn initialize_hip_forces(&mut self) -> Result<(), PrismError> {
+        let _span = tracing::info_span!("gpu_upload", stage = "nonbonded_hip").entered();
+        self.hip_nonbonded = None;
+        let Some(ff) = &self.force_field else { return Ok(()) };
+        if ff.simulation_box().is_some_and(|cell| !cell.is_orthorhombic()) {
+            tracing::warn!(backend = "hip", reason = "triclinic box", "Nonbonded forces stay on the host");
+            return Ok(());
+        }
+        if ff.pme().is_some() {
+            tracing::warn!(backend = "hip", reason = "PME", "Nonbonded forces stay on the host");
+            return Ok(());
+        }
+        let ordinal = self.config.devices.first().copied().unwrap_or(0);
+        let box_lengths = ff.simulation_box().map(|cell| cell.lengths());
+        let gpu = crate::hip::HipNonbonded::new(ordinal, &ff.pair_tables(), ff.config().cutoff, ff.config().switch_distance, box_lengths)?;
+        tracing::info!(device = ordinal, atoms = gpu.num_atoms(), "Nonbonded forces on HIP device");
+        self.hip_nonbonded = Some(gpu);
+        Ok(())
+    }
+
+    /// Compile the WGSL kernels on the `wgpu` adapter and upload the force
+    /// field tables. PME and triclinic systems keep their nonbonded forces
+    /// and integration on the host.
+    #[cfg(feature = "wgpu")]
+    fn initialize_wgpu(&mut self) -> Result<(), PrismError> {
+        let _span = tracing::info_span!("gpu_upload", stage = "wgpu").entered();
+        self.wgpu_compute = None;
+        let Some(ff) = &self.force_field else { return Ok(()) };
+        if ff.simulation_box().is_some_and(|cell| !cell.is_orthorhombic()) {
+            tracing::warn!(backend = "wgpu", reason = "triclinic box", "Nonbonded forces stay on the host");
+            return Ok(());
+        }
+        if ff.pme().is_some() {
+            tracing::warn!(backend = "wgpu", reason = "PME", "Nonbonded forces stay on the host");
+            return Ok(());
+        }
+        let box_lengths = ff.simulation_box().map(|cell| cell.lengths());
+        let gpu = crate::webgpu::WgpuCompute::new(&ff.pair_tables(), ff.config().cutoff, ff.config().switch_distance, box_lengths)?;
+        tracing::info!(adapter = gpu.adapter(), atoms = gpu.num_atoms(), "Nonbonded forces and Langevin steps on wgpu");
+        self.wgpu_compute = Some(gpu);
+        Ok(())
+    }
+
     #[cfg(feature = "cuda")]
     fn initialize_holographic_gpu(&mut self) -> Result<(), PrismError> {
-        log::info!("🔌 Engaging VRAM Turbo Cache (Persistent RNG)...");
+        let _span = tracing::info_span!("gpu_upload", stage = "integrator").entered();
+        tracing::info!("Uploading integrator state and persistent RNG");
         let buffers = self.buffers.as_ref().ok_or(PrismError::Internal("No buffers".into()))?;
         let num_atoms = buffers.num_atoms;
         let buffer_size = num_atoms * 4 * std::mem::size_of::<f32>();
         let rng_size = num_atoms * RNG_STATE_BYTES; 
 
         let ctx = CudaContext::new(0).map_err(|e| PrismError::gpu("init", format!("{:?}", e)))?;
+
+        // Workspace: positions, anchors, velocities, bias and RNG states;
+        // trajectory: the frame stream's device snapshots
+        let aligned = |bytes: usize| bytes.next_multiple_of(prism_gpu::memory::POOL_ALIGNMENT);
+        let workspace_bytes = 4 * aligned(buffer_size) + aligned(rng_size);
+        if workspace_bytes > self.config.max_workspace_memory {
+            return Err(PrismError::config(format!("GPU workspace needs {} bytes, max_workspace_memory is {}", workspace_bytes, self.config.max_workspace_memory)));
+        }
+        let policy = self.config.vram_fallback;
+        let guard = VramGuard::new(ctx.clone());
+        let mut rejection = None;
+        let mut reserved = None;
+        for frame_slots in policy.frame_slot_attempts(FRAME_SLOTS) {
+            let trajectory_bytes = frame_slots * aligned(buffer_size);
+            let result = if trajectory_bytes > self.config.max_trajectory_memory {
+                Err(format!("GPU frame buffers need {} bytes, max_trajectory_memory is {}", trajectory_bytes, self.config.max_trajectory_memory))
+            } else {
+                guard.reserve_pool(trajectory_bytes, workspace_bytes).map_err(|e| e.to_string())
+            };
+            match result {
+                Ok(pool) => {
+                    reserved = Some((pool, frame_slots));
+                    break;
+                }
+                Err(reason) => {
+                    tracing::warn!(frame_slots, reason = %reason, "VRAM reservation rejected");
+                    rejection.get_or_insert(reason);
+                }
+            }
+        }
+        let Some((mut pool, frame_slots)) = reserved else {
+            let reason = rejection.unwrap_or_default();
+            if policy != VramFallback::Cpu {
+                return Err(PrismError::gpu("vram_pool", reason));
+            }
+            tracing::warn!(policy = ?policy, reason = %reason, "Falling back to host integration");
+            self.vram_fallback = Some(VramFallbackDecision { policy, reason, frame_slots: 0, trajectory_stride: self.trajectory_stride() });
+            return Ok(());
+        };
+        if let Some(reason) = rejection {
+            if frame_slots == 0 {
+                if let Some(trajectory) = &mut self.config.trajectory {
+                    trajectory.stride = trajectory.stride.max(1) * VRAM_FALLBACK_STRIDE_FACTOR;
+                }
+            }
+            tracing::warn!(policy = ?policy, frame_slots, stride = ?self.trajectory_stride(), reason = %reason, "VRAM fallback applied");
+            self.vram_fallback = Some(VramFallbackDecision { policy, reason, frame_slots, trajectory_stride: self.trajectory_stride() });
+        }
+        let mut allocate = |region: VramRegion, bytes: usize, what: &str| {
+            pool.allocate(region, bytes).map_err(|e| PrismError::gpu("alloc", format!("{}: {}", what, e)))
+        };
+        let d_positions = allocate(VramRegion::Workspace, buffer_size, "positions")?;
+        let d_anchors = allocate(VramRegion::Workspace, buffer_size, "anchors")?;
+        let d_velocities = allocate(VramRegion::Workspace, buffer_size, "velocities")?;
+        let d_bias_vec = allocate(VramRegion::Workspace, buffer_size, "bias")?;
+        let d_rng_states = allocate(VramRegion::Workspace, rng_size, "rng")?;
+        let frame_snapshots = (0..frame_slots)
+            .map(|_| allocate(VramRegion::Trajectory, buffer_size, "frame snapshot"))
+            .collect::<Result<Vec<_>, _>>()?;
         
         let ptx_path = "crates/prism-gpu/kernels/holographic_langevin.ptx";
         let mut raw_module: cuda_sys::CUmodule = std::ptr::null_mut();
@@ -229,128 +1644,1786 @@ impl MolecularDynamicsEngine {
         }
         
         unsafe {
-            let mut d_positions: u64 = 0;
-            let mut d_anchors: u64 = 0;
-            let mut d_velocities: u64 = 0;
-            let mut d_bias_vec: u64 = 0;
-            let mut d_rng_states: u64 = 0;
-
-            // Allocate VRAM
-            if cuda_sys::cuMemAlloc_v2(&mut d_positions, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("alloc", "positions".to_string())); }
-            if cuda_sys::cuMemAlloc_v2(&mut d_anchors, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("alloc", "anchors".to_string())); }
-            if cuda_sys::cuMemAlloc_v2(&mut d_velocities, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("alloc", "velocities".to_string())); }
-            if cuda_sys::cuMemAlloc_v2(&mut d_bias_vec, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("alloc", "bias".to_string())); }
-            if cuda_sys::cuMemAlloc_v2(&mut d_rng_states, rng_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("alloc", "rng".to_string())); }
+            // Upload Initial State
+            if cuda_sys::cuMemcpyHtoD_v2(d_positions, buffers.positions.as_ptr() as *const c_void, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("memcpy", "positions".to_string())); }
+            if cuda_sys::cuMemcpyHtoD_v2(d_anchors, buffers.anchors.as_ptr() as *const c_void, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("memcpy", "anchors".to_string())); }
+            if cuda_sys::cuMemcpyHtoD_v2(d_bias_vec, buffers.bias_vec.as_ptr() as *const c_void, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("memcpy", "bias".to_string())); }
+            if cuda_sys::cuMemsetD8_v2(d_velocities, 0, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("memset", "velocities".to_string())); }
+
+            // Initialize RNG (One-time setup)
+            let seed = self.rng_hierarchy().derive_seed(RngStream::GpuNoise);
+            let n_atoms_i32 = num_atoms as i32;
+            let mut init_args: Vec<*mut c_void> = vec![
+                &seed as *const _ as *mut c_void,
+                &d_rng_states as *const _ as *mut c_void,
+                &n_atoms_i32 as *const _ as *mut c_void,
+            ];
+            
+            let threads = 128;
+            let blocks = (num_atoms + threads - 1) / threads;
+            
+            let res = cuda_sys::cuLaunchKernel(init_rng_kernel, blocks as u32, 1, 1, threads as u32, 1, 1, 0, std::ptr::null_mut(), init_args.as_mut_ptr(), std::ptr::null_mut());
+            if res != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("launch_init", format!("{:?}", res))); }
+            
+            if cuda_sys::cuCtxSynchronize() != cuda_sys::CUresult::CUDA_SUCCESS {
+                return Err(PrismError::gpu("sync_init", "RNG init failed".to_string()));
+            }
+
+            let mut stream: cuda_sys::CUstream = std::ptr::null_mut();
+            let res = cuda_sys::cuStreamCreate(&mut stream, cuda_sys::CUstream_flags::CU_STREAM_DEFAULT as u32);
+            if res != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("cuStreamCreate", format!("{:?}", res))); }
+
+            self.gpu_state = Some(HolographicGpuState { 
+                ctx, stream, raw_module, step_kernel, init_rng_kernel, 
+                d_positions, d_anchors, d_velocities, d_bias_vec, d_rng_states, 
+                num_atoms, pool, frame_snapshots, step_graph: None, frames: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Trajectory and analysis output of a frame copied off the GPU
+    #[cfg(feature = "cuda")]
+    fn deliver_gpu_frame(&mut self, request: FrameRequest, positions: &[f32]) -> Result<(), PrismError> {
+        if request.trajectory {
+            write_trajectory_frame(&mut self.trajectory, positions, None, request.step, self.config.dt, self.simulation_box)?;
+        }
+        if request.analysis {
+            for analysis in &mut self.analyses {
+                analysis.observe(request.step, positions);
+            }
+            self.geometric_restraints.record(positions);
+            self.write_observables(request.step, false)?;
+        }
+        Ok(())
+    }
+
+    /// Deliver every frame still in flight, oldest first
+    #[cfg(feature = "cuda")]
+    fn drain_gpu_frames(&mut self, frames: Option<&mut FrameStream>) -> Result<(), PrismError> {
+        if let Some(frames) = frames {
+            while let Some((request, positions)) = frames.take_oldest()? {
+                self.deliver_gpu_frame(request, positions)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn run_nlnm_breathing(&mut self, steps: u64) -> Result<PhaseOutcome, PrismError> {
+        let _run = tracing::info_span!("md_run", steps, first_step = self.current_step).entered();
+        tracing::info!(steps, "Starting hybrid simulation");
+        let start = Instant::now();
+        self.open_trajectory_writer()?;
+        self.stable_state = None;
+        self.recoveries.clear();
+        let network_reference = self
+            .elastic_network
+            .as_ref()
+            .zip(self.buffers.as_ref())
+            .map(|(network, buffers)| network.node_positions(&buffers.positions));
+
+        // Biases and learned potentials are host-side forces, so those runs
+        // take the host path
+        #[cfg(feature = "cuda")]
+        if self.gpu_state.is_some() && self.biases.is_empty() && self.potential.is_none() {
+            let num_atoms = self.gpu_state.as_ref().map_or(0, |gpu| gpu.num_atoms);
+            // The engine may have moved threads since the context was created (`run_async`)
+            if let Some(gpu) = &self.gpu_state {
+                gpu.ctx.bind_to_thread().map_err(|e| PrismError::gpu("bind_context", format!("{:?}", e)))?;
+            }
+            let threads = 128;
+            let blocks = (num_atoms + threads - 1) / threads;
+            let batch_size = 5000;
+            let stride = self.trajectory_stride();
+            let analysis_interval = (!self.analyses.is_empty() || !self.geometric_restraints.is_empty()).then(|| self.config.analysis_interval.max(1));
+            let observer_intervals: Vec<u64> = self.observers.iter().map(|o| o.interval).collect();
+            
+            let mut steps_remaining = steps;
+            let mut local_step_counter = self.current_step;
+
+            let mut args = self.gpu_state.as_ref().map(|gpu| gpu.step_args(&self.config)).ok_or(PrismError::Internal("No GPU state".into()))?;
+            if self.config.cuda_graphs && steps >= CUDA_GRAPH_STEPS {
+                if let Some(gpu) = self.gpu_state.as_mut().filter(|gpu| gpu.step_graph.is_none()) {
+                    match StepGraph::build(gpu.step_kernel, blocks as u32, threads as u32, &mut args) {
+                        Ok(graph) => {
+                            tracing::info!(steps_per_replay = CUDA_GRAPH_STEPS, "CUDA graph captured");
+                            gpu.step_graph = Some(graph);
+                        }
+                        Err(e) => tracing::warn!(error = %e, "CUDA graph capture failed, launching per step"),
+                    }
+                }
+            }
+
+            // Taken for the run so frames can be delivered while the state is borrowed
+            let mut frames = match self.gpu_state.as_mut() {
+                Some(gpu) if (stride.is_some() || analysis_interval.is_some()) && !gpu.frame_snapshots.is_empty() => match gpu.frames.take() {
+                    Some(frames) => Some(frames),
+                    None => Some(FrameStream::new(num_atoms * 4, &gpu.frame_snapshots)?),
+                },
+                _ => None,
+            };
+            let mut host_frame: Option<Vec<f32>> = None;
+            let (ctx, stream) = self.gpu_state.as_ref().map(|gpu| (gpu.ctx.clone(), gpu.stream)).ok_or(PrismError::Internal("No GPU state".into()))?;
+            let _run_range = nvtx_range("md_run");
+            let mut block_span = BlockSpan::default();
+
+            while steps_remaining > 0 {
+                self.current_step = local_step_counter;
+                if self.check_cancellation().is_break() {
+                    break;
+                }
+                block_span.advance(local_step_counter);
+                let Some(gpu) = self.gpu_state.as_ref() else { break };
+                // Batches end on trajectory, analysis and observer frames so positions can be downloaded
+                let until_frame = [stride, analysis_interval]
+                    .into_iter()
+                    .flatten()
+                    .chain(observer_intervals.iter().copied())
+                    .map(|s| s - local_step_counter % s)
+                    .min()
+                    .unwrap_or(u64::MAX);
+                let current_batch = batch_size.min(steps_remaining).min(until_frame);
+                let batch_end = local_step_counter + current_batch;
+                let span = self.gpu_timer.begin(&ctx, "integrate", stream)?;
+
+                if let Some(graph) = gpu.step_graph.as_ref().filter(|_| self.config.cuda_graphs) {
+                    while batch_end - local_step_counter >= CUDA_GRAPH_STEPS {
+                        args.step_idx = local_step_counter as i32;
+                        graph.replay(stream, &mut args, |step| self.temperature_at(step))?;
+                        local_step_counter += CUDA_GRAPH_STEPS;
+                    }
+                }
+                while local_step_counter < batch_end {
+                    args.step_idx = local_step_counter as i32;
+                    args.temperature = self.temperature_at(local_step_counter);
+                    let mut pointers = args.pointers();
+                    unsafe {
+                        let res = cuda_sys::cuLaunchKernel(
+                            gpu.step_kernel, blocks as u32, 1, 1, threads as u32, 1, 1, 
+                            0, stream, pointers.as_mut_ptr(), std::ptr::null_mut()
+                        );
+                        
+                        if res != cuda_sys::CUresult::CUDA_SUCCESS { 
+                            return Err(PrismError::gpu("launch", format!("{:?}", res))); 
+                        }
+                    }
+                    local_step_counter += 1;
+                }
+                self.gpu_timer.end(span)?;
+                
+                steps_remaining -= current_batch;
+
+                // Frames are copied off the GPU while the next batch runs
+                let trajectory_due = stride.is_some_and(|s| local_step_counter.is_multiple_of(s));
+                let analysis_due = analysis_interval.is_some_and(|s| local_step_counter.is_multiple_of(s));
+                if let Some(frames) = frames.as_mut().filter(|_| trajectory_due || analysis_due) {
+                    if frames.is_full() {
+                        if let Some((request, positions)) = frames.take_oldest()? {
+                            self.deliver_gpu_frame(request, positions)?;
+                        }
+                    }
+                    let request = FrameRequest { step: local_step_counter, trajectory: trajectory_due, analysis: analysis_due };
+                    let span = self.gpu_timer.begin(&ctx, "frame_snapshot", stream)?;
+                    frames.capture(args.d_positions, stream, request)?;
+                    self.gpu_timer.end(span)?;
+                } else if trajectory_due || analysis_due {
+                    // No snapshot buffers (VRAM fallback): blocking copy of the live positions
+                    let host_frame = host_frame.get_or_insert_with(|| vec![0.0f32; num_atoms * 4]);
+                    let span = self.gpu_timer.begin(&ctx, "frame_download", std::ptr::null_mut())?;
+                    unsafe {
+                        if cuda_sys::cuMemcpyDtoH_v2(host_frame.as_mut_ptr() as *mut c_void, args.d_positions, host_frame.len() * std::mem::size_of::<f32>()) != cuda_sys::CUresult::CUDA_SUCCESS {
+                            return Err(PrismError::gpu("download", "trajectory frame memcpy failed".to_string()));
+                        }
+                    }
+                    self.gpu_timer.end(span)?;
+                    let request = FrameRequest { step: local_step_counter, trajectory: trajectory_due, analysis: analysis_due };
+                    self.deliver_gpu_frame(request, host_frame)?;
+                }
+                if observer_intervals.iter().any(|&s| local_step_counter.is_multiple_of(s)) {
+                    self.drain_gpu_frames(frames.as_mut())?;
+                    self.current_step = local_step_counter;
+                    self.get_current_atoms()?;
+                    self.evaluate_forces();
+                    self.check_energy()?;
+                    if self.notify_observers().is_break() {
+                        break;
+                    }
+                }
+            }
+            unsafe {
+                if cuda_sys::cuStreamSynchronize(stream) != cuda_sys::CUresult::CUDA_SUCCESS {
+                    return Err(PrismError::gpu("sync", "failed".to_string()));
+                }
+            }
+            block_span.close(local_step_counter);
+            self.drain_gpu_frames(frames.as_mut())?;
+            if let Some(gpu) = &mut self.gpu_state {
+                gpu.frames = frames;
+            }
+            self.current_step = local_step_counter;
+            self.get_current_atoms()?;
+            self.evaluate_forces();
+            self.check_energy()?;
+        } else {
+            self.run_cpu(steps)?;
+        }
+
+        #[cfg(not(feature = "cuda"))]
+        self.run_cpu(steps)?;
+
+        if let Some(writer) = &mut self.trajectory {
+            writer.flush().map_err(|e| PrismError::internal(format!("Trajectory flush failed: {}", e)))?;
+        }
+
+        let duration = start.elapsed();
+        tracing::info!(seconds = duration.as_secs_f64(), last_step = self.current_step, "Simulation complete");
+        self.report_restraint_violations();
+        if self.energy_frames.last().is_none_or(|(step, _)| *step != self.current_step) {
+            self.record_energy_frame();
+        }
+        let energy_frames = std::mem::take(&mut self.energy_frames);
+        let pressure_frames = std::mem::take(&mut self.pressure_frames);
+        let mut telemetry = HashMap::new();
+        for bias in &self.biases {
+            telemetry.extend(bias.telemetry());
+        }
+        for analysis in &self.analyses {
+            telemetry.extend(analysis.telemetry());
+        }
+        telemetry.extend(self.restraint_telemetry());
+        if let Some(step) = self.stopped_at.take() {
+            telemetry.insert("stopped_by_observer".to_string(), serde_json::json!(step));
+        }
+        telemetry.insert("energy_components".to_string(), energy_frames_json(&energy_frames));
+        if !pressure_frames.is_empty() {
+            telemetry.insert("pressure".to_string(), pressure_frames_json(&pressure_frames));
+        }
+        #[cfg(feature = "cuda")]
+        if let Some(gpu) = &self.gpu_state {
+            telemetry.insert("vram_pool".to_string(), serde_json::json!(gpu.pool.stats()));
+        }
+        if let Some(decision) = &self.vram_fallback {
+            telemetry.insert("vram_fallback".to_string(), serde_json::json!(decision));
+        }
+        if !self.recoveries.is_empty() {
+            telemetry.insert("instability_recoveries".to_string(), serde_json::json!(std::mem::take(&mut self.recoveries)));
+        }
+        #[cfg(feature = "cuda")]
+        match self.gpu_timer.take() {
+            Ok(phases) if !phases.is_empty() => {
+                telemetry.insert("gpu_timing".to_string(), crate::gpu_timing::breakdown_json(&phases));
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(error = %e, "GPU timing unavailable"),
+        }
+        if let (Some(network), Some(buffers), Some(reference)) = (&self.elastic_network, &self.buffers, network_reference) {
+            let current = network.node_positions(&buffers.positions);
+            let overlaps = network.overlaps(&ElasticNetwork::fitted_displacement(&reference, &current));
+            let eigenvalues: Vec<f64> = network.modes().iter().map(|m| m.eigenvalue).collect();
+            telemetry.insert("enm_eigenvalues".to_string(), serde_json::json!(eigenvalues));
+            telemetry.insert("enm_cumulative_overlap".to_string(), serde_json::json!(cumulative_overlap(&overlaps)));
+            telemetry.insert("enm_overlap".to_string(), serde_json::json!(overlaps));
+        }
+        let wants_b_factors = self.elastic_network.as_ref().is_some_and(|n| n.config().b_factor_output.is_some());
+        let atoms = if wants_b_factors { self.get_current_atoms()? } else { Vec::new() };
+        if let Some(network) = &self.elastic_network {
+            let kt = self.temperature_at(self.current_step) as f64;
+            telemetry.insert("enm_residues".to_string(), serde_json::json!(network.residues()));
+            telemetry.insert("enm_msf".to_string(), serde_json::json!(network.fluctuations(kt)));
+            telemetry.insert("enm_participation".to_string(), serde_json::json!(network.participation()));
+            telemetry.insert("enm_collectivity".to_string(), serde_json::json!(network.collectivity()));
+            if let Some(path) = &network.config().b_factor_output {
+                network
+                    .b_factor_structure(&atoms, kt)
+                    .write(path)
+                    .map_err(|e| PrismError::Internal(format!("Failed to write B-factors to {}: {}", path.display(), e)))?;
+                tracing::info!(path = %path.display(), "Wrote elastic network B-factors");
+            }
+        }
+        Ok(self.run_outcome("Holographic run complete", telemetry))
+    }
+
+    /// Call `observer` with the run statistics every `interval` steps of
+    /// [`Self::run_nlnm_breathing`] (host or GPU). Returning
+    /// `ControlFlow::Break` ends the run early; the outcome then carries a
+    /// `stopped_by_observer` telemetry entry with the final step. On the GPU
+    /// each observed step downloads the positions and re-evaluates the forces.
+    /// With r-RESPA, observers are only called on outer-step boundaries.
+    pub fn add_observer<F>(&mut self, interval: u64, observer: F)
+    where
+        F: FnMut(&MolecularDynamicsStats) -> ControlFlow<()> + Send + 'static,
+    {
+        self.observers.push(Observer { interval: interval.max(1), callback: Box::new(observer) });
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    /// Interrupt [`Self::run_nlnm_breathing`], [`Self::run_pimc`] and
+    /// [`Self::run_rpmd`] through `token`: [`CancellationToken::cancel`]
+    /// ends the run at the next step boundary (kernel batch on the GPU,
+    /// outer step with r-RESPA) with [`PhaseOutcome::Cancelled`], and
+    /// [`CancellationToken::pause`] parks the loop there until resumed.
+    /// The engine stays consistent and can be run again with a fresh token.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    pub fn clear_cancellation_token(&mut self) {
+        self.cancellation = None;
+    }
+
+    /// Run [`Self::run_nlnm_breathing`] on tokio's blocking pool so the
+    /// caller's runtime is never stalled by the hot loop. The returned
+    /// [`AsyncRun`] streams the statistics every `interval` steps and yields
+    /// the engine back through [`AsyncRun::finish`]. Samples are dropped
+    /// rather than stalling the integrator when the consumer falls behind;
+    /// dropping the stream does not stop the run, cancel it through a
+    /// [`CancellationToken`] set beforehand. Must be called within a tokio
+    /// runtime. Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run_async(mut self, steps: u64, interval: u64) -> AsyncRun {
+        let (sender, progress) = tokio::sync::mpsc::channel(ASYNC_PROGRESS_CAPACITY);
+        let task = tokio::task::spawn_blocking(move || {
+            self.add_observer(interval, move |stats| {
+                let _ = sender.try_send(stats.clone());
+                ControlFlow::Continue(())
+            });
+            let outcome = self.run_nlnm_breathing(steps);
+            self.observers.pop();
+            outcome.map(|outcome| (self, outcome))
+        });
+        AsyncRun { progress, task }
+    }
+
+    /// Park while paused; `Break` once cancellation was requested
+    fn check_cancellation(&mut self) -> ControlFlow<()> {
+        let Some(token) = &self.cancellation else { return ControlFlow::Continue(()) };
+        let paused = token.is_paused();
+        if paused {
+            tracing::info!(step = self.current_step, "Run paused");
+        }
+        if token.checkpoint() {
+            tracing::info!(step = self.current_step, "Run cancelled");
+            self.cancelled_at = Some(self.current_step);
+            return ControlFlow::Break(());
+        }
+        if paused {
+            tracing::info!(step = self.current_step, "Run resumed");
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// `Success`, or `Cancelled` with the partial telemetry and statistics
+    /// when the run was interrupted
+    fn run_outcome(&mut self, message: &str, mut telemetry: HashMap<String, serde_json::Value>) -> PhaseOutcome {
+        match self.cancelled_at.take() {
+            Some(step) => {
+                telemetry.insert("statistics".to_string(), serde_json::json!(self.get_statistics()));
+                PhaseOutcome::Cancelled { reason: format!("Cancelled at step {}", step), telemetry }
+            }
+            None => PhaseOutcome::Success { message: message.to_string(), telemetry },
+        }
+    }
+
+    /// Call the observers due at the current step
+    fn notify_observers(&mut self) -> ControlFlow<()> {
+        let step = self.current_step;
+        if !self.observers.iter().any(|o| step.is_multiple_of(o.interval)) {
+            return ControlFlow::Continue(());
+        }
+        let stats = self.get_statistics();
+        let mut flow = ControlFlow::Continue(());
+        for observer in self.observers.iter_mut().filter(|o| step.is_multiple_of(o.interval)) {
+            if (observer.callback)(&stats).is_break() {
+                flow = ControlFlow::Break(());
+            }
+        }
+        if flow.is_break() {
+            tracing::info!(step, "Run stopped by observer");
+            self.stopped_at = Some(step);
+        }
+        flow
+    }
+
+    /// Host integration with the configured [`Integrator`], recovering from
+    /// instabilities as `instability.response` says.
+    fn run_cpu(&mut self, steps: u64) -> Result<(), PrismError> {
+        let end = self.current_step + steps;
+        loop {
+            let steps = end.saturating_sub(self.current_step);
+            let result = match self.config.integrator {
+                Integrator::Langevin => self.run_cpu_langevin(steps),
+                Integrator::Respa { slow_interval } => self.run_cpu_respa(steps, slow_interval.max(1) as u64),
+            };
+            match result {
+                Err(error) if error.is_instability() => self.recover(error)?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Fail with [`PrismError::NumericalInstability`] when the potential
+    /// energy of the last force evaluation is not finite or has risen more
+    /// than `instability.max_energy_rise` per atom above the stable snapshot.
+    fn check_energy(&self) -> Result<(), PrismError> {
+        let energy = self.potential_energy();
+        let limit = self.stable_state.as_ref().map(|stable| stable.energy + self.config.instability.max_energy_rise * (stable.positions.len() / 4) as f64);
+        if !energy.is_finite() || limit.is_some_and(|limit| energy > limit) {
+            return Err(PrismError::NumericalInstability { step: self.current_step, energy });
+        }
+        Ok(())
+    }
+
+    /// [`Self::check_energy`], then snapshot the state when one is due;
+    /// snapshots are only taken on multiples of `align` steps.
+    fn guard_stability(&mut self, align: u64) -> Result<(), PrismError> {
+        self.check_energy()?;
+        let interval = self.config.instability.snapshot_interval;
+        let due = self.stable_state.as_ref().is_none_or(|stable| self.current_step >= stable.step + interval);
+        if let (true, Some(buffers)) = (due && self.current_step.is_multiple_of(align), &self.buffers) {
+            self.stable_state = Some(StableState {
+                step: self.current_step,
+                energy: self.potential_energy(),
+                positions: buffers.positions.clone(),
+                velocities: buffers.velocities.clone(),
+                double: self.double_state.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Continue from the stable snapshot after `error`, or return `error`
+    /// once the policy aborts or the recoveries are used up.
+    fn recover(&mut self, error: PrismError) -> Result<(), PrismError> {
+        let config = self.config.instability.clone();
+        let diagnostics = self.buffers.as_ref().map_or_else(|| "no atoms".to_string(), |b| instability::diagnostics(&b.velocities, &self.forces));
+        let stable = match self.stable_state.clone() {
+            Some(stable) if config.response != InstabilityResponse::Abort && self.recoveries.len() < config.max_recoveries => stable,
+            _ => {
+                tracing::error!(error = %error, diagnostics, "Unstable dynamics");
+                return Err(error);
+            }
+        };
+        tracing::warn!(error = %error, diagnostics, restored_step = stable.step, response = ?config.response, "Unstable dynamics, continuing from the last stable state");
+        let failed_step = self.current_step;
+        let buffers = self.buffers.as_mut().ok_or(PrismError::Internal("No buffers".into()))?;
+        buffers.positions.copy_from_slice(&stable.positions);
+        buffers.velocities.copy_from_slice(&stable.velocities);
+        self.double_state = stable.double;
+        self.current_step = stable.step;
+        match config.response {
+            InstabilityResponse::HalveTimestep => self.config.dt *= 0.5,
+            InstabilityResponse::Reminimize => {
+                self.minimize()?;
+            }
+            InstabilityResponse::Restore | InstabilityResponse::Abort => {}
+        }
+        self.recoveries.push(InstabilityRecovery {
+            step: failed_step,
+            error: error.to_string(),
+            response: config.response,
+            restored_step: stable.step,
+            dt: self.config.dt,
+        });
+        Ok(())
+    }
+
+    /// Euler-Maruyama Langevin integration on the host using the nonbonded
+    /// force field plus the anchor spring and bias terms.
+    fn run_cpu_langevin(&mut self, steps: u64) -> Result<(), PrismError> {
+        if self.buffers.is_none() {
+            return Err(PrismError::Internal("No buffers".into()));
+        }
+        let mut block_span = BlockSpan::default();
+        for _ in 0..steps {
+            if self.check_cancellation().is_break() {
+                break;
+            }
+            block_span.advance(self.current_step);
+            self.evaluate_forces();
+            self.guard_stability(1)?;
+            self.langevin_step()?;
+            if self.notify_observers().is_break() {
+                break;
+            }
+        }
+        block_span.close(self.current_step);
+        self.finish_cpu_run()
+    }
+
+    /// r-RESPA: the nonbonded (slow) forces act as impulses of
+    /// `slow_interval * dt / 2` at both ends of each outer step, while the
+    /// bonded and restraint (fast) forces drive every inner Langevin step.
+    fn run_cpu_respa(&mut self, steps: u64, slow_interval: u64) -> Result<(), PrismError> {
+        if self.buffers.is_none() {
+            return Err(PrismError::Internal("No buffers".into()));
+        }
+        let half_outer = 0.5 * slow_interval as f32 * self.config.dt;
+        let mut slow: Option<Vec<f32>> = None;
+        let mut block_span = BlockSpan::default();
+
+        for _ in 0..steps {
+            block_span.advance(self.current_step);
+            let impulse = self.current_step.is_multiple_of(slow_interval).then(|| slow.take().unwrap_or_else(|| self.slow_forces()));
+            // Fast forces and the stability check precede the kick, so a
+            // snapshot on the boundary holds the velocities before it
+            self.evaluate_force_group(ForceGroup::Fast);
+            self.guard_stability(slow_interval)?;
+            if let Some(forces) = impulse {
+                self.kick(&forces, half_outer)?;
+            }
+            self.langevin_step()?;
+            if self.current_step.is_multiple_of(slow_interval) {
+                let forces = self.slow_forces();
+                self.kick(&forces, half_outer)?;
+                slow = Some(forces);
+            }
+            // Stop only on outer-step boundaries, where the impulses are balanced
+            if self.current_step.is_multiple_of(slow_interval)
+                && (self.notify_observers().is_break() || self.check_cancellation().is_break())
+            {
+                break;
+            }
+        }
+        block_span.close(self.current_step);
+        self.finish_cpu_run()
+    }
+
+    /// Slow-group forces at the current positions
+    fn slow_forces(&mut self) -> Vec<f32> {
+        self.evaluate_force_group(ForceGroup::Slow);
+        std::mem::take(&mut self.forces)
+    }
+
+    /// Velocity impulse `dt * F / m`, projected onto the constraint manifold.
+    fn kick(&mut self, forces: &[f32], dt: f32) -> Result<(), PrismError> {
+        let buffers = self.buffers.as_mut().ok_or(PrismError::Internal("No buffers".into()))?;
+        if self.config.precision == Precision::Double {
+            let state = self.double_state.get_or_insert_with(DoubleState::default);
+            state.sync(&buffers.positions, &buffers.velocities);
+            for ((vel, pos), f) in state
+                .velocities
+                .chunks_exact_mut(4)
+                .zip(state.positions.chunks_exact(4))
+                .zip(forces.chunks_exact(4))
+            {
+                let inv_mass = 1.0 / pos[3].max(1e-6);
+                for (v, &f) in vel.iter_mut().zip(f).take(3) {
+                    *v += dt as f64 * f as f64 * inv_mass;
+                }
+            }
+            state.mirror(&mut buffers.positions, &mut buffers.velocities);
+        } else {
+            for ((vel, pos), f) in buffers
+                .velocities
+                .chunks_exact_mut(4)
+                .zip(buffers.positions.chunks_exact(4))
+                .zip(forces.chunks_exact(4))
+            {
+                let inv_mass = 1.0 / pos[3].max(1e-6);
+                for (v, f) in vel.iter_mut().zip(f).take(3) {
+                    *v += dt * f * inv_mass;
+                }
+            }
+        }
+        if let Some(constraints) = &self.constraints {
+            constraints.rattle(&buffers.positions, &mut buffers.velocities)?;
+            constraints.settle_velocities(&buffers.positions, &mut buffers.velocities)?;
+        }
+        Ok(())
+    }
+
+    /// One Euler-Maruyama Langevin step driven by `self.forces`, followed by
+    /// the constraint projection and trajectory output.
+    fn langevin_step(&mut self) -> Result<(), PrismError> {
+        let dt = self.config.dt;
+        let friction = self.config.friction;
+        let temperature = self.temperature_at(self.current_step);
+        let noise_scale = (2.0 * friction * temperature * dt).max(0.0).sqrt();
+        let buffers = self.buffers.as_mut().ok_or(PrismError::Internal("No buffers".into()))?;
+        let reference = self.constraints.as_ref().map(|_| buffers.positions.clone());
+        let mut double = match self.config.precision {
+            Precision::Double => {
+                let state = self.double_state.get_or_insert_with(DoubleState::default);
+                state.sync(&buffers.positions, &buffers.velocities);
+                Some(state)
+            }
+            Precision::Single | Precision::Mixed => None,
+        };
+
+        // The device step keys its noise on (seed, step, atom) instead of
+        // drawing from the host RNG
+        #[cfg(feature = "wgpu")]
+        let integrated = match (&mut self.wgpu_compute, &double) {
+            (Some(gpu), None) => {
+                let uniforms = crate::wgsl::LangevinUniforms {
+                    num_atoms: buffers.num_atoms as u32,
+                    step: self.current_step as u32,
+                    seed: self.config.seed,
+                    dt,
+                    friction,
+                    temperature,
+                };
+                match gpu.langevin_step(&uniforms, &mut buffers.positions, &mut buffers.velocities, &self.forces) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        tracing::warn!(backend = "wgpu", error = %e, "Langevin step failed, continuing on CPU");
+                        self.wgpu_compute = None;
+                        false
+                    }
+                }
+            }
+            _ => false,
+        };
+        #[cfg(not(feature = "wgpu"))]
+        let integrated = false;
+        if integrated {
+            if let Some(i) = buffers.velocities.chunks_exact(4).position(|v| v[..3].iter().any(|x| !x.is_finite())) {
+                return Err(PrismError::NaNDetected { atom_index: i, step: self.current_step });
+            }
+        } else {
+            for i in 0..buffers.num_atoms {
+                let mass = buffers.positions[i * 4 + 3].max(1e-6);
+                for d in 0..3 {
+                    let k = i * 4 + d;
+                    let xi: f32 = StandardNormal.sample(&mut self.rng);
+                    let noise = noise_scale * xi / mass.sqrt();
+                    let finite = match double.as_deref_mut() {
+                        Some(state) => {
+                            let v = state.velocities[k];
+                            let v = v + dt as f64 * (self.forces[k] as f64 / mass as f64 - friction as f64 * v) + noise as f64;
+                            state.velocities[k] = v;
+                            state.positions[k] += v * dt as f64;
+                            v.is_finite()
+                        }
+                        None => {
+                            let v = buffers.velocities[k];
+                            let v = v + dt * (self.forces[k] / mass - friction * v) + noise;
+                            buffers.velocities[k] = v;
+                            buffers.positions[k] += v * dt;
+                            v.is_finite()
+                        }
+                    };
+                    if !finite {
+                        return Err(PrismError::NaNDetected { atom_index: i, step: self.current_step });
+                    }
+                }
+            }
+        }
+        if let Some(state) = double {
+            state.mirror(&mut buffers.positions, &mut buffers.velocities);
+        }
+        if let (Some(constraints), Some(reference)) = (&self.constraints, &reference) {
+            let unconstrained = self.simulation_box.map(|_| buffers.positions.clone());
+            constraints
+                .apply(reference, &mut buffers.positions, &mut buffers.velocities, dt)
+                .map_err(|e| match e {
+                    PrismError::ConstraintFailure(message) => PrismError::constraint_failure(format!("Step {}: {}", self.current_step, message)),
+                    e => e,
+                })?;
+            if let Some(unconstrained) = unconstrained {
+                // Constraint force m Δx / dt², acting at the reference positions
+                let inv_dt2 = 1.0 / (dt * dt);
+                let forces: Vec<f32> = buffers
+                    .positions
+                    .iter()
+                    .zip(&unconstrained)
+                    .enumerate()
+                    .map(|(k, (x, u))| if k % 4 == 3 { 0.0 } else { buffers.positions[k - k % 4 + 3] * (x - u) * inv_dt2 })
+                    .collect();
+                self.constraint_virial = Virial::default();
+                self.constraint_virial.add_forces(reference, &forces);
+            }
+        }
+        self.current_step += 1;
+        for bias in &mut self.biases {
+            bias.update(self.current_step, &buffers.positions);
+        }
+        let analysis_due = self.current_step.is_multiple_of(self.config.analysis_interval.max(1));
+        if analysis_due {
+            for analysis in &mut self.analyses {
+                analysis.observe(self.current_step, &buffers.positions);
+            }
+            self.geometric_restraints.record(&buffers.positions);
+        }
+
+        if self.trajectory_stride().is_some_and(|s| self.current_step.is_multiple_of(s)) {
+            if let Some(buffers) = &self.buffers {
+                write_trajectory_frame(&mut self.trajectory, &buffers.positions, Some(&buffers.velocities), self.current_step, dt, self.simulation_box)?;
+            }
+        }
+        if analysis_due {
+            self.record_energy_frame();
+            self.write_observables(self.current_step, true)?;
+        }
+        #[cfg(feature = "telemetry")]
+        self.record_telemetry_frame();
+        Ok(())
+    }
+
+    /// Push the current step into the flight recorder ring (no-op until
+    /// `prism_core::telemetry::init_telemetry`). Only a POD frame is pushed;
+    /// draining and display happen on the recorder's background thread.
+    #[cfg(feature = "telemetry")]
+    fn record_telemetry_frame(&self) {
+        use prism_core::telemetry;
+        if !telemetry::is_initialized() || !telemetry::should_record(self.current_step) {
+            return;
+        }
+        telemetry::record_frame(telemetry::TelemetryFrame {
+            step: self.current_step,
+            timestamp_ns: telemetry::timestamp_ns(),
+            energy: self.potential_energy() as f32,
+            temperature: self.temperature_at(self.current_step),
+            // Dynamics accepts every step
+            acceptance_rate: 1.0,
+            // Same total norm as `MolecularDynamicsStats::gradient_norm`
+            gradient_norm: self.gradient_norm,
+        });
+    }
+
+    /// Refresh forces/energies and the atom metadata after a host run.
+    fn finish_cpu_run(&mut self) -> Result<(), PrismError> {
+        self.evaluate_forces();
+        if let Some(buffers) = &self.buffers {
+            buffers.update_atoms(&mut self.atoms_metadata);
+        }
+        Ok(())
+    }
+
+    fn trajectory_stride(&self) -> Option<u64> {
+        self.config.trajectory.as_ref().map(|t| t.stride.max(1))
+    }
+
+    /// Open the configured trajectory file on first use.
+    fn open_trajectory_writer(&mut self) -> Result<(), PrismError> {
+        let (Some(config), None, Some(_)) = (&self.config.trajectory, &self.trajectory, &self.buffers) else {
+            return Ok(());
+        };
+        let stride = config.stride.max(1);
+        let first_step = (self.current_step / stride + 1) * stride;
+        let context = self.selection_context()?;
+        let writer = open_selected_trajectory(config, &context, first_step, self.config.timestep().as_ps(), self.simulation_box.is_some())
+            .map_err(|e| PrismError::internal(format!("Failed to open trajectory {}: {}", config.path.display(), e)))?;
+        tracing::info!(format = ?config.format, path = %config.path.display(), stride, "Writing trajectory");
+        self.trajectory = Some(writer);
+        Ok(())
+    }
+
+    /// Selection context over the current positions; engines built from
+    /// bare atoms only support element, residue and index selections.
+    fn selection_context(&self) -> Result<SelectionContext, PrismError> {
+        let buffers = self.buffers.as_ref().ok_or_else(|| PrismError::validation("Engine has no atoms to select from"))?;
+        let mut context = if self.selection_context.len() == buffers.num_atoms {
+            self.selection_context.clone()
+        } else {
+            SelectionContext::from_atoms(&self.atoms_metadata)
+        };
+        context.update_positions(&buffers.positions);
+        Ok(context)
+    }
+
+    /// Atom indices matching a [selection](prism_io::selection) expression
+    /// (e.g. `"chain A and resid 10-50 and name CA"`), evaluated against the
+    /// current positions. Use it to build restraints and analyses.
+    pub fn select(&self, expression: &str) -> Result<Vec<u32>, PrismError> {
+        self.selection_context()?.select(expression).map_err(|e| PrismError::validation(e.to_string()))
+    }
+
+    /// RNG hierarchy for this run's seed; external stochastic stages (e.g.
+    /// the PIMC sampler) take their seed from [`RngStream::Pimc`].
+    pub fn rng_hierarchy(&self) -> RngHierarchy {
+        RngHierarchy::new(self.config.seed)
+    }
+
+    /// Hold the thermostat at `temperature` (same scale as `temp_start`)
+    pub fn set_temperature(&mut self, temperature: f32) {
+        self.config.temp_start = temperature;
+        self.config.temp_end = temperature;
+        self.config.temperature_schedule = None;
+    }
+
+    /// Replace the thermostat schedule from the current step on
+    pub fn set_temperature_schedule(&mut self, schedule: TemperatureSchedule) -> Result<(), PrismError> {
+        schedule.validate()?;
+        self.config.temperature_schedule = Some(schedule);
+        Ok(())
+    }
+
+    /// Multiply every velocity by `factor` (e.g. `sqrt(T_new / T_old)` after
+    /// a replica exchange).
+    pub fn scale_velocities(&mut self, factor: f32) {
+        let Some(buffers) = &mut self.buffers else { return };
+        for vel in buffers.velocities.chunks_exact_mut(4) {
+            vel.iter_mut().take(3).for_each(|v| *v *= factor);
+        }
+
+        #[cfg(feature = "cuda")]
+        if let Some(gpu) = &self.gpu_state {
+            let bytes = gpu.num_atoms * 4 * std::mem::size_of::<f32>();
+            unsafe {
+                if cuda_sys::cuMemcpyHtoD_v2(gpu.d_velocities, buffers.velocities.as_ptr() as *const c_void, bytes) != cuda_sys::CUresult::CUDA_SUCCESS {
+                    tracing::warn!(stage = "rescaled_velocities", "Velocity upload to VRAM failed");
+                }
+            }
+        }
+    }
+
+    pub fn config(&self) -> &MolecularDynamicsConfig {
+        &self.config
+    }
+
+    /// Host velocities (Float4 stride); those of GPU dynamics stay in VRAM
+    pub fn velocities(&self) -> &[f32] {
+        self.buffers.as_ref().map_or(&[], |b| &b.velocities)
+    }
+
+    /// Draw Maxwell-Boltzmann velocities at `temperature` (same scale as
+    /// `temp_start`) and remove the net momentum.
+    pub fn assign_velocities(&mut self, temperature: f32) {
+        let mut rng = self.rng_hierarchy().stream(RngStream::Velocities);
+        let Some(buffers) = &mut self.buffers else { return };
+        let mut momentum = [0.0f64; 3];
+        let mut total_mass = 0.0f64;
+        for (pos, vel) in buffers.positions.chunks_exact(4).zip(buffers.velocities.chunks_exact_mut(4)) {
+            let mass = pos[3].max(1e-6);
+            let sigma = (temperature.max(0.0) / mass).sqrt();
+            for (d, v) in vel.iter_mut().take(3).enumerate() {
+                let xi: f32 = StandardNormal.sample(&mut rng);
+                *v = sigma * xi;
+                momentum[d] += (mass * *v) as f64;
+            }
+            total_mass += mass as f64;
+        }
+        for vel in buffers.velocities.chunks_exact_mut(4) {
+            for (v, p) in vel.iter_mut().zip(momentum) {
+                *v -= (p / total_mass) as f32;
+            }
+        }
+
+        #[cfg(feature = "cuda")]
+        if let (Some(gpu), Some(buffers)) = (&self.gpu_state, &self.buffers) {
+            let bytes = gpu.num_atoms * 4 * std::mem::size_of::<f32>();
+            unsafe {
+                if cuda_sys::cuMemcpyHtoD_v2(gpu.d_velocities, buffers.velocities.as_ptr() as *const c_void, bytes) != cuda_sys::CUresult::CUDA_SUCCESS {
+                    tracing::warn!(stage = "initial_velocities", "Velocity upload to VRAM failed");
+                }
+            }
+        }
+    }
+
+    /// Write the dynamic state of the run (step, positions, velocities,
+    /// thermostat and RNG state) to `path`.
+    pub fn save_checkpoint<P: AsRef<Path>>(&mut self, path: P) -> Result<(), PrismError> {
+        #[cfg(feature = "cuda")]
+        let gpu_rng_states = self.download_gpu_state()?;
+        #[cfg(not(feature = "cuda"))]
+        let gpu_rng_states = None;
+
+        let buffers = self.buffers.as_ref().ok_or(PrismError::Internal("No buffers".into()))?;
+        let checkpoint = MdCheckpoint {
+            step: self.current_step,
+            num_atoms: buffers.num_atoms,
+            positions: buffers.positions.clone(),
+            velocities: buffers.velocities.clone(),
+            simulation_box: self.simulation_box,
+            thermostat: ThermostatState {
+                temperature: self.temperature_at(self.current_step),
+                friction: self.config.friction,
+                annealing_steps: self.config.annealing_steps,
+            },
+            rng: RngState::capture(&self.rng),
+            gpu_rng_states,
+            pimc_beads: self.ring_polymer.as_ref().map(RingPolymer::to_checkpoint),
+        };
+        checkpoint.write(path.as_ref())?;
+        tracing::info!(step = self.current_step, path = %path.as_ref().display(), "Checkpoint written");
+        Ok(())
+    }
+
+    /// Restore the dynamic state saved by [`Self::save_checkpoint`] into an
+    /// engine built for the same system.
+    pub fn resume_from_checkpoint<P: AsRef<Path>>(&mut self, path: P) -> Result<(), PrismError> {
+        let checkpoint = MdCheckpoint::read(path.as_ref())?;
+        let buffers = self.buffers.as_mut().ok_or(PrismError::Internal("No buffers".into()))?;
+        if checkpoint.num_atoms != buffers.num_atoms {
+            return Err(PrismError::validation(format!(
+                "Checkpoint has {} atoms but the engine has {}",
+                checkpoint.num_atoms, buffers.num_atoms
+            )));
+        }
+        buffers.positions = checkpoint.positions;
+        buffers.velocities = checkpoint.velocities;
+        buffers.update_atoms(&mut self.atoms_metadata);
+        self.ring_polymer = checkpoint
+            .pimc_beads
+            .as_ref()
+            .map(|beads| RingPolymer::from_checkpoint(beads, &buffers.positions))
+            .transpose()?;
+        self.pimc_sampler = None;
+        self.rpmd = None;
+        self.current_step = checkpoint.step;
+        self.simulation_box = checkpoint.simulation_box;
+        self.rng = checkpoint.rng.restore();
+        if let Some(ff) = self.force_field.as_mut().filter(|ff| ff.simulation_box() != checkpoint.simulation_box.as_ref()) {
+            ff.set_box(checkpoint.simulation_box);
+            self.neighbor_list = None;
+            #[cfg(feature = "cuda")]
+            if self.nonbonded_gpu.is_some() {
+                self.initialize_gpu_forces()?;
+            }
+            #[cfg(feature = "rocm")]
+            if self.hip_nonbonded.is_some() {
+                self.initialize_hip_forces()?;
+            }
+            #[cfg(feature = "wgpu")]
+            if self.wgpu_compute.is_some() {
+                self.initialize_wgpu()?;
+            }
+        }
+
+        let thermostat = checkpoint.thermostat;
+        if thermostat.friction != self.config.friction || thermostat.annealing_steps != self.config.annealing_steps {
+            tracing::warn!(
+                checkpoint_friction = thermostat.friction,
+                friction = self.config.friction,
+                checkpoint_annealing_steps = thermostat.annealing_steps,
+                annealing_steps = self.config.annealing_steps,
+                "Resuming with a different thermostat"
+            );
+        }
+
+        #[cfg(feature = "cuda")]
+        self.upload_gpu_state(checkpoint.gpu_rng_states.as_deref())?;
+
+        // Reopen the trajectory so its header starts at the resumed step
+        self.trajectory = None;
+        if let Some(list) = &mut self.neighbor_list {
+            list.invalidate();
+        }
+        self.evaluate_forces();
+        tracing::info!(path = %path.as_ref().display(), step = self.current_step, "Resumed from checkpoint");
+        Ok(())
+    }
+
+    /// Copy positions and velocities from VRAM into the host buffers and
+    /// return the raw device RNG states.
+    #[cfg(feature = "cuda")]
+    fn download_gpu_state(&mut self) -> Result<Option<Vec<u8>>, PrismError> {
+        let (Some(gpu), Some(buffers)) = (&self.gpu_state, &mut self.buffers) else { return Ok(None) };
+        let bytes = gpu.num_atoms * 4 * std::mem::size_of::<f32>();
+        let mut rng_states = vec![0u8; gpu.num_atoms * RNG_STATE_BYTES];
+        unsafe {
+            if cuda_sys::cuMemcpyDtoH_v2(buffers.positions.as_mut_ptr() as *mut c_void, gpu.d_positions, bytes) != cuda_sys::CUresult::CUDA_SUCCESS {
+                return Err(PrismError::gpu("download", "checkpoint positions".to_string()));
+            }
+            if cuda_sys::cuMemcpyDtoH_v2(buffers.velocities.as_mut_ptr() as *mut c_void, gpu.d_velocities, bytes) != cuda_sys::CUresult::CUDA_SUCCESS {
+                return Err(PrismError::gpu("download", "checkpoint velocities".to_string()));
+            }
+            if cuda_sys::cuMemcpyDtoH_v2(rng_states.as_mut_ptr() as *mut c_void, gpu.d_rng_states, rng_states.len()) != cuda_sys::CUresult::CUDA_SUCCESS {
+                return Err(PrismError::gpu("download", "checkpoint rng states".to_string()));
+            }
+        }
+        Ok(Some(rng_states))
+    }
+
+    /// Push the host positions to VRAM after a host-side stage moved them
+    #[cfg(feature = "cuda")]
+    fn upload_positions(&self, what: &str) -> Result<(), PrismError> {
+        let (Some(gpu), Some(buffers)) = (&self.gpu_state, &self.buffers) else { return Ok(()) };
+        let _span = tracing::debug_span!("gpu_upload", stage = what).entered();
+        let bytes = gpu.num_atoms * 4 * std::mem::size_of::<f32>();
+        unsafe {
+            if cuda_sys::cuMemcpyHtoD_v2(gpu.d_positions, buffers.positions.as_ptr() as *const c_void, bytes) != cuda_sys::CUresult::CUDA_SUCCESS {
+                return Err(PrismError::gpu("upload", format!("{} memcpy failed", what)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Push restored host state back to VRAM. Device RNG states are only
+    /// restored when the checkpoint carries a matching set.
+    #[cfg(feature = "cuda")]
+    fn upload_gpu_state(&mut self, rng_states: Option<&[u8]>) -> Result<(), PrismError> {
+        let (Some(gpu), Some(buffers)) = (&self.gpu_state, &self.buffers) else { return Ok(()) };
+        let _span = tracing::info_span!("gpu_upload", stage = "checkpoint").entered();
+        let bytes = gpu.num_atoms * 4 * std::mem::size_of::<f32>();
+        unsafe {
+            if cuda_sys::cuMemcpyHtoD_v2(gpu.d_positions, buffers.positions.as_ptr() as *const c_void, bytes) != cuda_sys::CUresult::CUDA_SUCCESS {
+                return Err(PrismError::gpu("upload", "checkpoint positions".to_string()));
+            }
+            if cuda_sys::cuMemcpyHtoD_v2(gpu.d_velocities, buffers.velocities.as_ptr() as *const c_void, bytes) != cuda_sys::CUresult::CUDA_SUCCESS {
+                return Err(PrismError::gpu("upload", "checkpoint velocities".to_string()));
+            }
+            match rng_states {
+                Some(states) if states.len() == gpu.num_atoms * RNG_STATE_BYTES => {
+                    if cuda_sys::cuMemcpyHtoD_v2(gpu.d_rng_states, states.as_ptr() as *const c_void, states.len()) != cuda_sys::CUresult::CUDA_SUCCESS {
+                        return Err(PrismError::gpu("upload", "checkpoint rng states".to_string()));
+                    }
+                }
+                _ => tracing::warn!("Checkpoint has no device RNG states; GPU noise stream is not reproduced"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Recompute forces and energies for the current host positions.
+    fn evaluate_forces(&mut self) {
+        self.evaluate_force_group(ForceGroup::All);
+    }
+
+    /// Evaluate one force group into `self.forces` (energies of the groups
+    /// not evaluated keep their previous values), on the configured thread
+    /// pool.
+    fn evaluate_force_group(&mut self, group: ForceGroup) {
+        match self.thread_pool.clone() {
+            Some(pool) => pool.install(|| self.evaluate_force_group_inner(group)),
+            None => self.evaluate_force_group_inner(group),
+        }
+    }
+
+    fn evaluate_force_group_inner(&mut self, group: ForceGroup) {
+        let Some(buffers) = &self.buffers else { return };
+        self.forces.clear();
+        self.forces.resize(buffers.positions.len(), 0.0);
+
+        if group != ForceGroup::Fast {
+            self.evaluate_nonbonded();
+        }
+        if group != ForceGroup::Slow {
+            self.evaluate_bonded_and_restraints();
+        }
+
+        self.gradient_norm = self
+            .forces
+            .chunks_exact(4)
+            .map(|f| f[0] * f[0] + f[1] * f[1] + f[2] * f[2])
+            .sum::<f32>()
+            .sqrt();
+    }
+
+    /// Nonbonded forces (GPU when available) added into `self.forces`, or
+    /// those of the attached [`Potential`]
+    fn evaluate_nonbonded(&mut self) {
+        let Some(buffers) = &self.buffers else { return };
+
+        if let Some(potential) = self.potential.as_mut() {
+            let mut local = vec![0.0; self.forces.len()];
+            // A failed evaluation surfaces as a non-finite energy to the
+            // instability checks
+            self.learned_energy = potential.compute(&buffers.positions, &mut local).unwrap_or_else(|e| {
+                tracing::error!(potential = potential.name(), error = %e, "Potential evaluation failed");
+                f64::NAN
+            });
+            let mut virial = Virial::default();
+            virial.add_forces(&buffers.positions, &local);
+            for (f, l) in self.forces.iter_mut().zip(&local) {
+                *f += l;
+            }
+            self.nonbonded_energy = NonbondedEnergy::default();
+            self.nonbonded_virial = Some(virial);
+            return;
+        }
+
+        #[cfg(feature = "cuda")]
+        let gpu_energy = match (&mut self.nonbonded_gpu, &self.force_field) {
+            (Some(gpu), Some(ff)) => match gpu.compute(&mut self.gpu_timer, &buffers.positions, &mut self.forces) {
+                Ok(pairs) => {
+                    // The mesh and implicit solvent virials only come from the host path
+                    let mut virial = gpu
+                        .virial()
+                        .filter(|_| self.simulation_box.is_some() && ff.pme().is_none() && ff.implicit_solvent().is_none());
+                    let energy = with_host_terms(ff, &buffers.positions, &mut self.forces, pairs, virial.as_mut());
+                    Some((energy, virial))
+                }
+                Err(e) => {
+                    tracing::warn!(backend = "cuda", error = %e, "Nonbonded evaluation failed, continuing on CPU");
+                    None
+                }
+            },
+            _ => None,
+        };
+        #[cfg(feature = "cuda")]
+        if gpu_energy.is_none() {
+            self.nonbonded_gpu = None;
+        }
+        #[cfg(not(feature = "cuda"))]
+        let gpu_energy: Option<(NonbondedEnergy, Option<Virial>)> = None;
+        #[cfg(feature = "rocm")]
+        let gpu_energy = match (gpu_energy, &mut self.hip_nonbonded, &self.force_field) {
+            (None, Some(gpu), Some(ff)) => match gpu.compute(&buffers.positions, &mut self.forces) {
+                Ok(pairs) => Some((with_host_terms(ff, &buffers.positions, &mut self.forces, pairs, None), None)),
+                Err(e) => {
+                    tracing::warn!(backend = "hip", error = %e, "Nonbonded evaluation failed, continuing on CPU");
+                    self.hip_nonbonded = None;
+                    None
+                }
+            },
+            (energy, _, _) => energy,
+        };
+        #[cfg(feature = "wgpu")]
+        let gpu_energy = match (gpu_energy, &mut self.wgpu_compute, &self.force_field) {
+            (None, Some(gpu), Some(ff)) => match gpu.compute(&buffers.positions, &mut self.forces) {
+                Ok(pairs) => Some((with_host_terms(ff, &buffers.positions, &mut self.forces, pairs, None), None)),
+                Err(e) => {
+                    tracing::warn!(backend = "wgpu", error = %e, "Nonbonded evaluation failed, continuing on CPU");
+                    self.wgpu_compute = None;
+                    None
+                }
+            },
+            (energy, _, _) => energy,
+        };
+
+        self.nonbonded_energy = match (gpu_energy, &self.force_field) {
+            (Some((energy, virial)), _) => {
+                self.nonbonded_virial = virial;
+                energy
+            }
+            (None, Some(ff)) => {
+                // Cell-list pairs keep the host path O(N)
+                let list = self.neighbor_list.get_or_insert_with(|| ff.neighbor_list());
+                list.update(&buffers.positions, |i, j| ff.is_excluded(i, j));
+                let mut virial = self.simulation_box.map(|_| Virial::default());
+                let energy = match self.config.precision {
+                    Precision::Single => match &mut virial {
+                        Some(virial) => ff.compute_with_list_and_virial(&buffers.positions, &mut self.forces, list, virial),
+                        None => ff.compute_with_list(&buffers.positions, &mut self.forces, list),
+                    },
+                    Precision::Double | Precision::Mixed => {
+                        // Sum the pair terms in f64 and round each atom's total once
+                        let mut wide = vec![0.0f64; self.forces.len()];
+                        let energy = match &mut virial {
+                            Some(virial) => ff.compute_with_list_and_virial(&buffers.positions, &mut wide, list, virial),
+                            None => ff.compute_with_list(&buffers.positions, &mut wide, list),
+                        };
+                        for (f, w) in self.forces.iter_mut().zip(&wide) {
+                            *f = (*f as f64 + w) as f32;
+                        }
+                        energy
+                    }
+                };
+                if virial.is_some() {
+                    self.nonbonded_virial = virial;
+                }
+                energy
+            }
+            (None, None) => {
+                self.nonbonded_virial = Some(Virial::default());
+                NonbondedEnergy::default()
+            }
+        };
+    }
+
+    /// Bonded terms plus the anchor spring and bias drive
+    fn evaluate_bonded_and_restraints(&mut self) {
+        let Some(buffers) = &self.buffers else { return };
+        // Forces so far, to isolate this group's contribution to the virial
+        let before = self.simulation_box.map(|_| self.forces.clone());
+        self.bonded_energy = match (&self.bonded, &self.potential) {
+            (Some(bonded), None) => bonded.compute(&buffers.positions, &mut self.forces),
+            _ => BondedEnergy::default(),
+        };
+
+        // Harmonic anchor restraint + bias drive
+        let k = self.config.spring_k;
+        let bias = self.config.bias_strength;
+        let mut restraint = 0.0f64;
+        for i in 0..buffers.num_atoms {
+            for d in 0..3 {
+                let idx = i * 4 + d;
+                let disp = buffers.positions[idx] - buffers.anchors[idx];
+                restraint += 0.5 * (k * disp * disp) as f64;
+                self.forces[idx] += -k * disp + bias * buffers.bias_vec[idx];
+            }
+        }
+        self.restraint_energy = restraint;
+        self.position_restraint_energies = self.position_restraints.compute(&buffers.positions, &mut self.forces);
+        self.geometric_restraint_energy = self.geometric_restraints.compute(&buffers.positions, &mut self.forces);
+
+        let mut bias_energy = 0.0;
+        for bias in &self.biases {
+            bias_energy += bias.compute(&buffers.positions, &mut self.forces);
+        }
+        self.bias_energy = bias_energy;
+
+        if let Some(before) = before {
+            let group: Vec<f32> = self.forces.iter().zip(&before).map(|(f, b)| f - b).collect();
+            self.bonded_virial = Virial::default();
+            self.bonded_virial.add_forces(&buffers.positions, &group);
+        }
+    }
+
+    /// Recompute the nonbonded virial on the host when the last evaluation
+    /// ran on the GPU without mixed-precision sums, which leaves it unset
+    fn refresh_virial(&mut self) {
+        if self.nonbonded_virial.is_some() || self.simulation_box.is_none() {
+            return;
+        }
+        let (Some(ff), Some(buffers)) = (&self.force_field, &self.buffers) else { return };
+        let list = self.neighbor_list.get_or_insert_with(|| ff.neighbor_list());
+        list.update(&buffers.positions, |i, j| ff.is_excluded(i, j));
+        let mut scratch = vec![0.0; buffers.positions.len()];
+        let mut virial = Virial::default();
+        ff.compute_with_list_and_virial(&buffers.positions, &mut scratch, list, &mut virial);
+        self.nonbonded_virial = Some(virial);
+    }
+
+    /// Instantaneous pressure tensor from the virial of the last force
+    /// evaluation and the current velocities (bar); `None` without a
+    /// periodic box
+    pub fn pressure(&self) -> Option<PressureTensor> {
+        let cell = self.simulation_box?;
+        let buffers = self.buffers.as_ref()?;
+        let virial = self.nonbonded_virial? + self.bonded_virial + self.constraint_virial;
+        Some(PressureTensor::new(kinetic_tensor(&buffers.positions, &buffers.velocities), &virial, cell.volume()))
+    }
+
+    /// Record the energy breakdown and pressure of the current step
+    fn record_energy_frame(&mut self) {
+        self.energy_frames.push((self.current_step, self.energy_components()));
+        self.refresh_virial();
+        if let Some(pressure) = self.pressure() {
+            self.pressure_frames.push((self.current_step, pressure));
+        }
+    }
+
+    /// Scalar analysis results of `step` and, with `energies`, the energy
+    /// breakdown and pressure of the current step as trajectory observables
+    fn write_observables(&mut self, step: u64, energies: bool) -> Result<(), PrismError> {
+        let Some(writer) = &mut self.trajectory else { return Ok(()) };
+        let mut values: Vec<(&str, f64)> = self.analyses.iter().flat_map(|a| a.observables()).collect();
+        if energies {
+            if let Some(&(_, e)) = self.energy_frames.last() {
+                values.extend([
+                    ("bond_energy", e.bond),
+                    ("angle_energy", e.angle),
+                    ("dihedral_energy", e.dihedral),
+                    ("lennard_jones_energy", e.lennard_jones),
+                    ("electrostatic_energy", e.electrostatic),
+                    ("solvation_energy", e.solvation),
+                    ("restraint_energy", e.restraint),
+                    ("bias_energy", e.bias),
+                    ("kinetic_energy", e.kinetic),
+                    ("potential_energy", e.potential()),
+                    ("total_energy", e.total()),
+                ]);
+            }
+            if let Some(&(_, pressure)) = self.pressure_frames.last().filter(|(s, _)| *s == step) {
+                values.push(("pressure", pressure.scalar()));
+            }
+        }
+        writer
+            .write_observables(step, step as f64 * self.config.dt as f64, &values)
+            .map_err(|e| PrismError::Internal(format!("Observable write failed at step {}: {}", step, e)))
+    }
+
+    /// Potential energy of the last force evaluation (kcal/mol)
+    pub fn potential_energy(&self) -> f64 {
+        self.nonbonded_energy.total()
+            + self.bonded_energy.total()
+            + self.learned_energy
+            + self.restraint_energy
+            + self.position_restraint_energy()
+            + self.geometric_restraint_energy
+            + self.bias_energy
+    }
+
+    /// Kinetic energy `Σ ½ m v²` of the host velocities (kcal/mol)
+    pub fn kinetic_energy(&self) -> f64 {
+        let Some(buffers) = &self.buffers else { return 0.0 };
+        buffers
+            .velocities
+            .chunks_exact(4)
+            .zip(buffers.positions.chunks_exact(4))
+            .map(|(v, p)| 0.5 * p[3] as f64 * (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]) as f64)
+            .sum()
+    }
+
+    /// Energy breakdown of the last force evaluation plus the current
+    /// kinetic energy
+    pub fn energy_components(&self) -> EnergyComponents {
+        EnergyComponents {
+            bond: self.bonded_energy.bond,
+            angle: self.bonded_energy.angle,
+            dihedral: self.bonded_energy.dihedral + self.bonded_energy.improper,
+            lennard_jones: self.nonbonded_energy.lennard_jones,
+            electrostatic: self.nonbonded_energy.coulomb,
+            solvation: self.nonbonded_energy.solvation,
+            restraint: self.restraint_energy + self.position_restraint_energy() + self.geometric_restraint_energy,
+            bias: self.bias_energy,
+            learned: self.learned_energy,
+            kinetic: self.kinetic_energy(),
+        }
+    }
+
+    /// Position restraints resolved from the configuration
+    pub fn position_restraints(&self) -> &PositionRestraints {
+        &self.position_restraints
+    }
+
+    /// Total position restraint energy of the last force evaluation (kcal/mol)
+    pub fn position_restraint_energy(&self) -> f64 {
+        self.position_restraint_energies.iter().sum()
+    }
+
+    /// Distance, angle and dihedral restraints from the restraint file
+    pub fn geometric_restraints(&self) -> &GeometricRestraints {
+        &self.geometric_restraints
+    }
+
+    /// Distance, angle and dihedral restraint energy of the last force
+    /// evaluation (kcal/mol)
+    pub fn geometric_restraint_energy(&self) -> f64 {
+        self.geometric_restraint_energy
+    }
+
+    /// Position restraint energy, energy and RMS deviation per selection,
+    /// and the geometric restraint energy and violation report, for phase
+    /// telemetry
+    fn restraint_telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        let Some(buffers) = &self.buffers else { return Vec::new() };
+        let mut telemetry = Vec::new();
+        if !self.position_restraints.is_empty() {
+            telemetry.extend([
+                ("position_restraint_energy".to_string(), serde_json::json!(self.position_restraint_energy())),
+                ("position_restraint_energies".to_string(), serde_json::json!(self.position_restraint_energies)),
+                ("position_restraint_rmsd".to_string(), serde_json::json!(self.position_restraints.deviations(&buffers.positions))),
+            ]);
+        }
+        if !self.geometric_restraints.is_empty() {
+            let violations = self.geometric_restraints.violations(&buffers.positions);
+            telemetry.extend([
+                ("geometric_restraint_energy".to_string(), serde_json::json!(self.geometric_restraint_energy)),
+                ("restraint_violations".to_string(), serde_json::json!(violations)),
+            ]);
+        }
+        telemetry
+    }
+
+    /// Log the restraints still violated at the end of a run
+    fn report_restraint_violations(&self) {
+        let Some(buffers) = self.buffers.as_ref().filter(|_| !self.geometric_restraints.is_empty()) else { return };
+        let violations = self.geometric_restraints.violations(&buffers.positions);
+        let violated: Vec<_> = violations.iter().filter(|v| v.violation > 0.0).collect();
+        let worst = violated.iter().max_by(|a, b| a.violation.total_cmp(&b.violation));
+        match worst {
+            Some(worst) => tracing::info!(
+                violated = violated.len(),
+                restraints = violations.len(),
+                worst = worst.index,
+                kind = ?worst.kind,
+                atoms = ?worst.atoms,
+                violation = worst.violation,
+                lower = worst.lower,
+                upper = worst.upper,
+                "Restraints violated"
+            ),
+            None => tracing::info!(restraints = violations.len(), "All restraints satisfied"),
+        }
+    }
+
+    /// Attach a bias; it acts on the host force evaluation from the next step.
+    pub fn add_bias(&mut self, mut bias: Box<dyn BiasPotential>) {
+        tracing::info!(bias = bias.name(), "Bias attached");
+        if let Some(buffers) = &self.buffers {
+            bias.attach(&buffers.positions, self.current_step, self.config.dt);
+        }
+        self.biases.push(bias);
+        self.evaluate_forces();
+    }
+
+    /// Detach and return every bias
+    pub fn take_biases(&mut self) -> Vec<Box<dyn BiasPotential>> {
+        let biases = std::mem::take(&mut self.biases);
+        self.evaluate_forces();
+        biases
+    }
+
+    /// Detach and return the most recently attached bias
+    pub fn pop_bias(&mut self) -> Option<Box<dyn BiasPotential>> {
+        let bias = self.biases.pop();
+        self.evaluate_forces();
+        bias
+    }
+
+    /// First attached bias of type `T`
+    pub fn bias<T: BiasPotential>(&self) -> Option<&T> {
+        self.biases.iter().find_map(|b| (b.as_ref() as &dyn std::any::Any).downcast_ref::<T>())
+    }
+
+    /// Mutable access to the first attached bias of type `T`
+    pub fn bias_mut<T: BiasPotential>(&mut self) -> Option<&mut T> {
+        self.biases.iter_mut().find_map(|b| (b.as_mut() as &mut dyn std::any::Any).downcast_mut::<T>())
+    }
+
+    /// Attach an on-the-fly analysis, observed every `analysis_interval`
+    /// steps of the breathing run
+    pub fn add_analysis(&mut self, analysis: Box<dyn Analysis>) {
+        tracing::info!(analysis = analysis.name(), "Analysis attached");
+        self.analyses.push(analysis);
+    }
+
+    /// Detach and return every analysis
+    pub fn take_analyses(&mut self) -> Vec<Box<dyn Analysis>> {
+        std::mem::take(&mut self.analyses)
+    }
+
+    /// First attached analysis of type `T`
+    pub fn analysis<T: Analysis>(&self) -> Option<&T> {
+        self.analyses.iter().find_map(|a| (a.as_ref() as &dyn std::any::Any).downcast_ref::<T>())
+    }
+
+    /// Replace the force field and bonded terms with `potential` (e.g. a
+    /// [`crate::ml_potential::NeuralNetworkPotential`]); restraints and
+    /// biases still apply. Dynamics run on the host from then on. Fails,
+    /// leaving the engine unchanged, when the potential cannot evaluate the
+    /// current positions.
+    pub fn set_potential(&mut self, mut potential: Box<dyn Potential>) -> Result<(), PrismError> {
+        if let Some(buffers) = &self.buffers {
+            let mut scratch = vec![0.0; buffers.positions.len()];
+            potential.compute(&buffers.positions, &mut scratch)?;
+        }
+        tracing::info!(potential = potential.name(), "Potential replaces the force field");
+        self.potential = Some(potential);
+        self.evaluate_forces();
+        Ok(())
+    }
+
+    /// Detach the potential, returning to the force field
+    pub fn take_potential(&mut self) -> Option<Box<dyn Potential>> {
+        let potential = self.potential.take();
+        self.learned_energy = 0.0;
+        self.evaluate_forces();
+        potential
+    }
+
+    /// Energy of the attached potential at the last force evaluation (kcal/mol)
+    pub fn learned_energy(&self) -> f64 {
+        self.learned_energy
+    }
+
+    /// Total bias energy of the last force evaluation (kcal/mol)
+    pub fn bias_energy(&self) -> f64 {
+        self.bias_energy
+    }
+
+    /// Potential energy and gradient at flat `[x0, y0, z0, ...]` coordinates,
+    /// leaving them in the host position buffer.
+    fn potential_at(&mut self, x: &[f64], gradient: &mut [f64]) -> f64 {
+        if let Some(buffers) = &mut self.buffers {
+            for (p, c) in buffers.positions.chunks_exact_mut(4).zip(x.chunks_exact(3)) {
+                for (p, &c) in p.iter_mut().zip(c) {
+                    *p = c as f32;
+                }
+            }
+        }
+        self.evaluate_forces();
+        for (g, f) in gradient.chunks_exact_mut(3).zip(self.forces.chunks_exact(4)) {
+            for (g, &f) in g.iter_mut().zip(f) {
+                *g = -f as f64;
+            }
+        }
+        self.potential_energy()
+    }
+
+    /// Relax the current structure before dynamics: steepest descent, then
+    /// L-BFGS or conjugate-gradient refinement of the full potential (force field, bonded terms and
+    /// anchor springs) until the largest per-atom force drops below
+    /// `minimization.force_tolerance`. Constraints are not applied.
+    pub fn minimize(&mut self) -> Result<PhaseOutcome, PrismError> {
+        let _span = tracing::info_span!("minimization", force_tolerance = self.config.minimization.force_tolerance).entered();
+        let start = Instant::now();
+        #[cfg(feature = "cuda")]
+        if self.gpu_state.is_some() {
+            self.get_current_atoms()?;
+        }
+        let buffers = self.buffers.as_ref().ok_or(PrismError::Internal("No buffers".into()))?;
+        let x0: Vec<f64> = buffers
+            .positions
+            .chunks_exact(4)
+            .flat_map(|p| [p[0] as f64, p[1] as f64, p[2] as f64])
+            .collect();
+        let config = self.config.minimization.clone();
+        let (x, report) = minimizer::minimize(|x, g| self.potential_at(x, g), x0, &config);
+
+        let mut gradient = vec![0.0; x.len()];
+        self.potential_at(&x, &mut gradient);
+        if !report.final_energy.is_finite() {
+            return Err(PrismError::NumericalInstability { step: self.current_step, energy: report.final_energy });
+        }
+        if let Some(buffers) = &self.buffers {
+            buffers.update_atoms(&mut self.atoms_metadata);
+        }
+        #[cfg(feature = "cuda")]
+        self.upload_positions("minimized positions")?;
+
+        tracing::info!(
+            initial_energy = report.initial_energy,
+            final_energy = report.final_energy,
+            steepest_descent_steps = report.steepest_descent_steps,
+            refinement_iterations = report.refinement_iterations,
+            max_force = report.max_force,
+            seconds = start.elapsed().as_secs_f64(),
+            "Minimized structure"
+        );
+        if !report.converged {
+            tracing::warn!(
+                max_force = report.max_force,
+                force_tolerance = config.force_tolerance,
+                "Minimization stopped before reaching the force tolerance"
+            );
+        }
+
+        let mut telemetry = HashMap::from([
+            ("initial_energy".to_string(), serde_json::json!(report.initial_energy)),
+            ("final_energy".to_string(), serde_json::json!(report.final_energy)),
+            ("energy_drop".to_string(), serde_json::json!(report.energy_drop())),
+            ("steepest_descent_steps".to_string(), serde_json::json!(report.steepest_descent_steps)),
+            ("refinement_iterations".to_string(), serde_json::json!(report.refinement_iterations)),
+            ("evaluations".to_string(), serde_json::json!(report.evaluations)),
+            ("max_force".to_string(), serde_json::json!(report.max_force)),
+            ("rms_force".to_string(), serde_json::json!(report.rms_force)),
+            ("converged".to_string(), serde_json::json!(report.converged)),
+        ]);
+        telemetry.extend(self.restraint_telemetry());
+        Ok(PhaseOutcome::Success {
+            message: format!(
+                "Minimization lowered the energy by {:.3} kcal/mol ({:.3} → {:.3})",
+                report.energy_drop(),
+                report.initial_energy,
+                report.final_energy
+            ),
+            telemetry,
+        })
+    }
+
+    /// Ring polymer of the path-integral stage, once [`Self::run_pimc`] or
+    /// [`Self::run_rpmd`] has built it or a checkpoint restored it
+    /// Elastic network of the starting structure, when configured
+    pub fn elastic_network(&self) -> Option<&ElasticNetwork> {
+        self.elastic_network.as_ref()
+    }
+
+    /// Animate the configured elastic network's modes about the current
+    /// structure and write them to `path`, returning the frame count
+    pub fn export_mode_animation(&mut self, config: &ModeAnimationConfig, path: &Path) -> Result<usize, PrismError> {
+        let atoms = self.get_current_atoms()?;
+        let network = self
+            .elastic_network
+            .as_ref()
+            .ok_or_else(|| PrismError::config("Mode animation needs an `elastic_network` configuration"))?;
+        let animation = ModeAnimation::new(network, &atoms, config)?;
+        animation.write(path, &atoms, None)?;
+        tracing::info!(frames = animation.frames().len(), path = %path.display(), "Wrote mode animation");
+        Ok(animation.frames().len())
+    }
+
+    pub fn ring_polymer(&self) -> Option<&RingPolymer> {
+        self.ring_polymer.as_ref()
+    }
+
+    /// Ring-polymer centroid in the Float4 buffer layout
+    pub fn centroid_positions(&self) -> Option<Vec<f32>> {
+        self.ring_polymer.as_ref().map(RingPolymer::centroid_positions)
+    }
 
-            // Upload Initial State
-            if cuda_sys::cuMemcpyHtoD_v2(d_positions, buffers.positions.as_ptr() as *const c_void, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("memcpy", "positions".to_string())); }
-            if cuda_sys::cuMemcpyHtoD_v2(d_anchors, buffers.anchors.as_ptr() as *const c_void, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("memcpy", "anchors".to_string())); }
-            if cuda_sys::cuMemcpyHtoD_v2(d_bias_vec, buffers.bias_vec.as_ptr() as *const c_void, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("memcpy", "bias".to_string())); }
-            if cuda_sys::cuMemsetD8_v2(d_velocities, 0, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("memset", "velocities".to_string())); }
+    /// Sample quantum statistics of the current potential with `sweeps`
+    /// path-integral Monte Carlo sweeps at the current thermostat
+    /// temperature. The ring polymer starts collapsed on the current
+    /// structure and persists across calls; the host positions are left on
+    /// its centroid.
+    pub fn run_pimc(&mut self, sweeps: u64) -> Result<PhaseOutcome, PrismError> {
+        let start = Instant::now();
+        let config = self.prepare_ring_polymer()?;
+        let mut polymer = self.ring_polymer.take().ok_or(PrismError::Internal("No ring polymer".into()))?;
+        let mut sampler = self
+            .pimc_sampler
+            .take()
+            .unwrap_or_else(|| PimcSampler::new(&config, self.rng_hierarchy().stream(RngStream::Pimc)));
+        let kt = self.temperature_at(self.current_step) as f64;
 
-            // Initialize RNG (One-time setup)
-            let seed: u64 = 12345;
-            let n_atoms_i32 = num_atoms as i32;
-            let mut init_args: Vec<*mut c_void> = vec![
-                &seed as *const _ as *mut c_void,
-                &d_rng_states as *const _ as *mut c_void,
-                &n_atoms_i32 as *const _ as *mut c_void,
-            ];
-            
-            let threads = 128;
-            let blocks = (num_atoms + threads - 1) / threads;
-            
-            let res = cuda_sys::cuLaunchKernel(init_rng_kernel, blocks as u32, 1, 1, threads as u32, 1, 1, 0, std::ptr::null_mut(), init_args.as_mut_ptr(), std::ptr::null_mut());
-            if res != cuda_sys::CUresult::CUDA_SUCCESS { return Err(PrismError::gpu("launch_init", format!("{:?}", res))); }
-            
-            if cuda_sys::cuCtxSynchronize() != cuda_sys::CUresult::CUDA_SUCCESS {
-                return Err(PrismError::gpu("sync_init", "RNG init failed".to_string()));
+        let mut potential_sum = 0.0;
+        let mut estimators = EnergyBlocks::new(config.block_length);
+        let mut completed = 0;
+        for sweep in 1..=sweeps {
+            if self.check_cancellation().is_break() {
+                break;
             }
+            completed = sweep;
+            sampler.sweep(&mut polymer, kt, &mut |x, g| self.potential_at(x, g));
+            potential_sum += sampler.mean_potential();
+            if sampler.is_equilibrated() && config.estimator_interval > 0 && sweep.is_multiple_of(config.estimator_interval) {
+                estimators.push(EnergyEstimate::measure(&polymer, kt, &mut |x, g| self.potential_at(x, g)));
+            }
+        }
 
-            self.gpu_state = Some(HolographicGpuState { 
-                _ctx: ctx, raw_module, step_kernel, init_rng_kernel, 
-                d_positions, d_anchors, d_velocities, d_bias_vec, d_rng_states, 
-                num_atoms 
-            });
+        let mut telemetry = ring_polymer_telemetry(&polymer, kt);
+        telemetry.insert("sweeps".to_string(), serde_json::json!(completed));
+        telemetry.insert("mean_potential".to_string(), serde_json::json!(potential_sum / completed.max(1) as f64));
+        for kind in PimcMove::ALL {
+            let key = format!("{:?}", kind).to_lowercase();
+            telemetry.insert(format!("{}_acceptance", key), serde_json::json!(sampler.stats(kind).acceptance_rate()));
+            telemetry.insert(format!("{}_step_size", key), serde_json::json!(sampler.step_size(kind)));
         }
-        Ok(())
+        let summary = estimators.summary();
+        if summary.blocks > 0 {
+            let blocks: Vec<[f64; 3]> = estimators
+                .blocks()
+                .iter()
+                .map(|b| [b.potential, b.primitive_kinetic, b.virial_kinetic])
+                .collect();
+            telemetry.insert("potential_estimate".to_string(), serde_json::json!(summary.potential));
+            telemetry.insert("primitive_kinetic".to_string(), serde_json::json!(summary.primitive_kinetic));
+            telemetry.insert("virial_kinetic".to_string(), serde_json::json!(summary.virial_kinetic));
+            telemetry.insert("estimator_blocks".to_string(), serde_json::json!(blocks));
+            tracing::info!(
+                primitive = summary.primitive_kinetic.mean,
+                primitive_error = summary.primitive_kinetic.std_error,
+                virial = summary.virial_kinetic.mean,
+                virial_error = summary.virial_kinetic.std_error,
+                blocks = summary.blocks,
+                "Quantum kinetic energy estimated"
+            );
+        }
+        tracing::info!(
+            sweeps = completed,
+            beads = polymer.num_beads(),
+            bead_acceptance = sampler.stats(PimcMove::Bead).acceptance_rate(),
+            bead_step = sampler.step_size(PimcMove::Bead),
+            centroid_acceptance = sampler.stats(PimcMove::Centroid).acceptance_rate(),
+            centroid_step = sampler.step_size(PimcMove::Centroid),
+            seconds = start.elapsed().as_secs_f64(),
+            "PIMC complete"
+        );
+        self.ring_polymer = Some(polymer);
+        self.pimc_sampler = Some(sampler);
+        self.settle_on_centroid()?;
+        Ok(self.run_outcome("PIMC sampling complete", telemetry))
     }
 
-    pub fn run_nlnm_breathing(&mut self, steps: u64) -> Result<PhaseOutcome, PrismError> {
-        log::info!("🌬️ Starting Hybrid Simulation: {} steps", steps);
+    /// Propagate the ring polymer for `steps` RPMD steps of `dt` on the
+    /// thermostat schedule, advancing the step counter. Bead momenta are
+    /// drawn at `P kT` on the first call; the host positions are left on the
+    /// centroid. Trajectory frames hold the centroid.
+    pub fn run_rpmd(&mut self, steps: u64) -> Result<PhaseOutcome, PrismError> {
         let start = Instant::now();
+        let config = self.prepare_ring_polymer()?;
+        let mut polymer = self.ring_polymer.take().ok_or(PrismError::Internal("No ring polymer".into()))?;
+        let kt = self.temperature_at(self.current_step) as f64;
+        let mut rpmd = self
+            .rpmd
+            .take()
+            .unwrap_or_else(|| RingPolymerMd::new(&polymer, kt, self.rng_hierarchy().stream(RngStream::RingPolymer)))
+            .with_fixed_centroid(config.fixed_centroid);
+        let dt = self.config.timestep().as_ps();
+        self.open_trajectory_writer()?;
+        let stride = self.trajectory_stride();
 
-        #[cfg(feature = "cuda")]
-        if let Some(gpu) = &self.gpu_state {
-            let threads = 128;
-            let blocks = (gpu.num_atoms + threads - 1) / threads;
-            let batch_size = 5000;
-            
-            let mut steps_remaining = steps;
-            let mut local_step_counter = self.current_step;
+        let mut potential_sum = 0.0;
+        let mut result = Ok(());
+        let mut completed = 0u64;
+        for _ in 0..steps {
+            if self.check_cancellation().is_break() {
+                break;
+            }
+            let kt = self.temperature_at(self.current_step) as f64;
+            rpmd.step(&mut polymer, dt, kt, config.centroid_friction, &mut |x, g| self.potential_at(x, g));
+            if !rpmd.mean_potential().is_finite() {
+                result = Err(PrismError::NumericalInstability { step: self.current_step, energy: rpmd.mean_potential() });
+                break;
+            }
+            potential_sum += rpmd.mean_potential();
+            self.current_step += 1;
+            completed += 1;
+            if stride.is_some_and(|s| self.current_step.is_multiple_of(s)) {
+                let centroid = polymer.centroid_positions();
+                result = write_trajectory_frame(&mut self.trajectory, &centroid, None, self.current_step, self.config.dt, self.simulation_box);
+                if result.is_err() {
+                    break;
+                }
+            }
+        }
+        if let Some(writer) = &mut self.trajectory {
+            writer.flush().map_err(|e| PrismError::internal(format!("Trajectory flush failed: {}", e)))?;
+        }
 
-            // Physics Constants
-            let dt = self.config.dt;
-            let friction = self.config.friction;
-            let temp_start = self.config.temp_start;
-            let temp_end = self.config.temp_end;
-            let bias_strength = self.config.bias_strength;
-            let spring_k = self.config.spring_k;
-            let n_atoms_i32 = gpu.num_atoms as i32;
-            let annealing_steps_i32 = (self.config.annealing_steps.min(i32::MAX as u64) as i32).max(1);
+        let kt = self.temperature_at(self.current_step) as f64;
+        let mut telemetry = ring_polymer_telemetry(&polymer, kt);
+        telemetry.insert("steps".to_string(), serde_json::json!(completed));
+        telemetry.insert("mean_potential".to_string(), serde_json::json!(potential_sum / completed.max(1) as f64));
+        telemetry.insert("bead_kinetic_energy".to_string(), serde_json::json!(rpmd.kinetic_energy(&polymer)));
+        tracing::info!(steps = completed, beads = polymer.num_beads(), seconds = start.elapsed().as_secs_f64(), "RPMD complete");
+        self.ring_polymer = Some(polymer);
+        self.rpmd = Some(rpmd);
+        self.settle_on_centroid()?;
+        result?;
+        Ok(self.run_outcome("RPMD run complete", telemetry))
+    }
 
-            while steps_remaining > 0 {
-                let current_batch = std::cmp::min(batch_size, steps_remaining);
-                
-                for _ in 0..current_batch {
-                    // CRITICAL FIX: Re-create args vector inside the loop.
-                    // This ensures the pointer to `step_idx_param` is valid and points to the updated value.
-                    let mut step_idx_param = local_step_counter as i32;
-                    
-                    unsafe {
-                        let mut args: Vec<*mut c_void> = vec![
-                            &gpu.d_positions as *const _ as *mut c_void,
-                            &gpu.d_anchors as *const _ as *mut c_void,
-                            &gpu.d_velocities as *const _ as *mut c_void,
-                            &gpu.d_bias_vec as *const _ as *mut c_void,
-                            &n_atoms_i32 as *const _ as *mut c_void,
-                            &dt as *const _ as *mut c_void,
-                            &friction as *const _ as *mut c_void,
-                            &temp_start as *const _ as *mut c_void,
-                            &temp_end as *const _ as *mut c_void,
-                            &bias_strength as *const _ as *mut c_void,
-                            &spring_k as *const _ as *mut c_void,
-                            &gpu.d_rng_states as *const _ as *mut c_void,
-                            &mut step_idx_param as *mut _ as *mut c_void, // Pointer to current stack variable
-                            &annealing_steps_i32 as *const _ as *mut c_void,
-                        ];
+    /// Path-integral settings, building the ring polymer on first use
+    fn prepare_ring_polymer(&mut self) -> Result<PimcConfig, PrismError> {
+        let config = self
+            .config
+            .pimc
+            .clone()
+            .ok_or_else(|| PrismError::config("Path-integral runs need a `pimc` configuration"))?;
+        if config.num_beads == 0 {
+            return Err(PrismError::config("pimc.num_beads must be at least 1"));
+        }
+        if self.ring_polymer.is_none() {
+            #[cfg(feature = "cuda")]
+            if self.gpu_state.is_some() {
+                self.get_current_atoms()?;
+            }
+            let buffers = self.buffers.as_ref().ok_or(PrismError::Internal("No buffers".into()))?;
+            let polymer = RingPolymer::from_positions(&buffers.positions, config.num_beads);
+            tracing::info!(beads = polymer.num_beads(), atoms = polymer.num_atoms(), "Ring polymer built");
+            self.ring_polymer = Some(polymer);
+        }
+        Ok(config)
+    }
 
-                        let res = cuda_sys::cuLaunchKernel(
-                            gpu.step_kernel, blocks as u32, 1, 1, threads as u32, 1, 1, 
-                            0, std::ptr::null_mut(), args.as_mut_ptr(), std::ptr::null_mut()
-                        );
-                        
-                        if res != cuda_sys::CUresult::CUDA_SUCCESS { 
-                            return Err(PrismError::gpu("launch", format!("{:?}", res))); 
-                        }
-                    }
-                    local_step_counter += 1;
-                }
-                
-                // Sync after batch
-                unsafe { 
-                    if cuda_sys::cuCtxSynchronize() != cuda_sys::CUresult::CUDA_SUCCESS {
-                        return Err(PrismError::gpu("sync", "failed".to_string()));
-                    }
-                }
-                steps_remaining -= current_batch;
+    /// Move the host positions onto the ring-polymer centroid and refresh
+    /// forces there
+    fn settle_on_centroid(&mut self) -> Result<(), PrismError> {
+        let Some(centroid) = self.ring_polymer.as_ref().map(RingPolymer::centroid) else { return Ok(()) };
+        let mut gradient = vec![0.0; centroid.len()];
+        self.potential_at(&centroid, &mut gradient);
+        if let Some(buffers) = &self.buffers {
+            buffers.update_atoms(&mut self.atoms_metadata);
+        }
+        #[cfg(feature = "cuda")]
+        self.upload_positions("ring-polymer centroid")?;
+        Ok(())
+    }
+
+    /// Thermostat target temperature after `step` steps
+    pub fn temperature_at(&self, step: u64) -> f32 {
+        match &self.config.temperature_schedule {
+            Some(schedule) => schedule.temperature_at(step),
+            None => {
+                let denom = std::cmp::max(1, self.config.annealing_steps) as f32;
+                let progress = (step as f32 / denom).min(1.0);
+                self.config.temp_start + (self.config.temp_end - self.config.temp_start) * progress
             }
-            self.current_step = local_step_counter;
         }
+    }
 
-        let duration = start.elapsed();
-        log::info!("🏁 Simulation Complete: {:.2}s", duration.as_secs_f32());
-        Ok(PhaseOutcome::Success { message: "Holographic run complete".to_string(), telemetry: HashMap::new() })
+    /// Nonbonded (LJ + Coulomb) energy of the current host positions
+    pub fn nonbonded_energy(&self) -> NonbondedEnergy {
+        self.nonbonded_energy
+    }
+
+    /// Bonded (bond/angle/torsion) energy of the current host positions
+    pub fn bonded_energy(&self) -> BondedEnergy {
+        self.bonded_energy
+    }
+
+    /// Host force field (`None` for engines without nonbonded parameters)
+    pub fn force_field(&self) -> Option<&ForceField> {
+        self.force_field.as_ref()
     }
 
     pub fn get_current_atoms(&mut self) -> Result<Vec<Atom>, PrismError> {
@@ -358,7 +3431,7 @@ impl MolecularDynamicsEngine {
         {
             if let Some(gpu) = &self.gpu_state {
                 if let Some(buffers) = &mut self.buffers {
-                    log::info!("📥 Downloading results from VRAM...");
+                    tracing::debug!(atoms = gpu.num_atoms, "Downloading results from VRAM");
                     let buffer_size = gpu.num_atoms * 4 * std::mem::size_of::<f32>();
                     unsafe {
                         if cuda_sys::cuMemcpyDtoH_v2(buffers.positions.as_mut_ptr() as *mut c_void, gpu.d_positions, buffer_size) != cuda_sys::CUresult::CUDA_SUCCESS {
@@ -366,14 +3439,14 @@ impl MolecularDynamicsEngine {
                         }
                     }
                     buffers.update_atoms(&mut self.atoms_metadata);
-                    log::info!("✅ Download & Unpack complete.");
                 }
             }
         }
         Ok(self.atoms_metadata.clone())
     }
 
-    fn parse_protein_structure(data: &[u8]) -> Result<Vec<Atom>, PrismError> {
+    pub(crate) fn parse_protein_structure(data: &[u8]) -> Result<Vec<Atom>, PrismError> {
+        let _span = tracing::info_span!("structure_parse", bytes = data.len()).entered();
         if data.is_empty() { return Err(PrismError::validation("Empty data")); }
 
         // Detect format by magic bytes
@@ -383,30 +3456,18 @@ impl MolecularDynamicsEngine {
 
         if data.len() >= 8 && &data[0..8] == PTB_MAGIC {
             // PTB binary format - use existing parser
-            log::debug!("Detected PTB format, using binary parser");
+            tracing::debug!(format = "ptb", "Detected structure format");
             Self::parse_ptb_structure(data)
         } else {
             // Assume PDB text format
-            log::info!("Detected PDB format, parsing text structure");
+            tracing::debug!(format = "pdb", "Detected structure format");
             Self::parse_pdb_structure(data)
         }
     }
 
     /// Parse PTB (PRISM binary) format
     fn parse_ptb_structure(data: &[u8]) -> Result<Vec<Atom>, PrismError> {
-        use std::io::Write;
-        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
-        let temp_path = format!("/tmp/prism_holographic_{}.ptb", timestamp);
-
-        // RAII Guard ensures file is deleted when this scope ends
-        let _guard = TempFileGuard { path: temp_path.clone() };
-
-        {
-            let mut f = std::fs::File::create(&temp_path).map_err(|e| PrismError::Internal(e.to_string()))?;
-            f.write_all(data).map_err(|e| PrismError::Internal(e.to_string()))?;
-        }
-
-        let mut structure = PtbStructure::load(&temp_path).map_err(|e| PrismError::Internal(e.to_string()))?;
+        let mut structure = PtbStructure::from_bytes(data).map_err(|e| PrismError::Internal(e.to_string()))?;
         let atoms = structure.atoms().map_err(|e| PrismError::Internal(e.to_string()))?.to_vec();
 
         Ok(atoms)
@@ -492,22 +3553,25 @@ impl MolecularDynamicsEngine {
             return Err(PrismError::validation("No ATOM records found in PDB data"));
         }
 
-        log::info!("Parsed {} atoms from PDB format", atoms.len());
+        tracing::info!(atoms = atoms.len(), "Parsed PDB structure");
         Ok(atoms)
     }
     
     pub fn get_statistics(&self) -> MolecularDynamicsStats {
-        let denom = std::cmp::max(1, self.config.annealing_steps) as f32;
-        let progress = (self.current_step as f32 / denom).min(1.0);
-        let current_temp = self.config.temp_start + (self.config.temp_end - self.config.temp_start) * progress;
+        let current_temp = self.temperature_at(self.current_step);
 
         MolecularDynamicsStats {
             current_step: self.current_step,
             total_steps: self.config.max_steps,
-            current_energy: 0.0, 
+            current_energy: self.potential_energy() as f32,
             current_temperature: current_temp,
-            acceptance_rate: 1.0, 
-            gradient_norm: 0.0, 
+            acceptance_rate: self.pimc_sampler.as_ref().map_or(1.0, |s| s.overall_acceptance() as f32),
+            pimc_moves: self.pimc_sampler.as_ref().map(|s| s.summary()).unwrap_or_default(),
+            gradient_norm: self.gradient_norm,
+            position_restraint_energy: self.position_restraint_energy() as f32,
+            geometric_restraint_energy: self.geometric_restraint_energy as f32,
+            energy: self.energy_components(),
+            pressure: self.pressure(),
             runtime_seconds: self.start_time.elapsed().as_secs_f32(),
             converged: false,
         }
@@ -582,7 +3646,7 @@ impl MolecularDynamicsEngine {
             }
         }
 
-        log::info!("💾 Saved Digital Twin to: {} ({} atoms)", output_path, atom_idx);
+        tracing::info!(path = output_path, atoms = atom_idx, "Saved structure");
         Ok(())
     }
 
@@ -602,9 +3666,1224 @@ pub struct MolecularDynamicsStats {
     pub current_step: u64,
     pub total_steps: u64,
     pub current_energy: f32,
+    /// Thermostat target temperature at the current step
     pub current_temperature: f32,
+    /// Overall PIMC move acceptance (1.0 for deterministic dynamics)
     pub acceptance_rate: f32,
+    /// Per-move PIMC step sizes and acceptance, empty without a sampler
+    #[serde(default)]
+    pub pimc_moves: Vec<MoveSummary>,
     pub gradient_norm: f32,
+    /// Position restraint energy of the last force evaluation (kcal/mol)
+    #[serde(default)]
+    pub position_restraint_energy: f32,
+    /// Distance, angle and dihedral restraint energy (kcal/mol)
+    #[serde(default)]
+    pub geometric_restraint_energy: f32,
+    /// Component breakdown of `current_energy` plus the kinetic energy
+    #[serde(default)]
+    pub energy: EnergyComponents,
+    /// Virial pressure tensor (bar), for periodic systems
+    #[serde(default)]
+    pub pressure: Option<PressureTensor>,
     pub runtime_seconds: f32,
     pub converged: bool,
 }
+
+/// Energy breakdown (kcal/mol)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnergyComponents {
+    pub bond: f64,
+    pub angle: f64,
+    /// Proper and improper torsions
+    pub dihedral: f64,
+    pub lennard_jones: f64,
+    pub electrostatic: f64,
+    /// Implicit solvent (GB polar + SA nonpolar)
+    pub solvation: f64,
+    /// Anchor springs, position restraints and distance/angle/dihedral
+    /// restraints
+    pub restraint: f64,
+    /// Attached bias potentials
+    pub bias: f64,
+    /// Attached [`Potential`] replacing the force field
+    #[serde(default)]
+    pub learned: f64,
+    pub kinetic: f64,
+}
+
+impl EnergyComponents {
+    pub fn potential(&self) -> f64 {
+        self.bond
+            + self.angle
+            + self.dihedral
+            + self.lennard_jones
+            + self.electrostatic
+            + self.solvation
+            + self.restraint
+            + self.bias
+            + self.learned
+    }
+
+    pub fn total(&self) -> f64 {
+        self.potential() + self.kinetic
+    }
+}
+
+/// `[{"step": s, "bond": ..., ...}, ...]` telemetry of recorded energy frames
+fn energy_frames_json(frames: &[(u64, EnergyComponents)]) -> serde_json::Value {
+    frames
+        .iter()
+        .map(|(step, components)| {
+            let mut frame = serde_json::json!(components);
+            frame["step"] = serde_json::json!(step);
+            frame
+        })
+        .collect()
+}
+
+/// `[{"step": s, "pressure": p, "tensor": [[...]]}, ...]` telemetry of
+/// recorded pressure frames (bar)
+fn pressure_frames_json(frames: &[(u64, PressureTensor)]) -> serde_json::Value {
+    frames
+        .iter()
+        .map(|(step, pressure)| {
+            serde_json::json!({
+                "step": step,
+                "pressure": pressure.scalar(),
+                "tensor": pressure.tensor,
+            })
+        })
+        .collect()
+}
+
+/// Nonbonded-only topology of a receptor structure: element LJ parameters,
+/// element masses and exclusions within `exclusion_distance`, matching
+/// [`ForceField::from_atoms`]
+fn receptor_topology(config: &ForceFieldConfig, receptor: &PdbStructure) -> Topology {
+    let atoms = &receptor.atoms;
+    let lj = atoms
+        .iter()
+        .map(|atom| {
+            let params = NonbondedParams::from_atom(atom);
+            LjParams { sigma: params.sigma, epsilon: params.epsilon }
+        })
+        .collect();
+    let num_residues = atoms.iter().map(|a| a.residue_id as usize + 1).max().unwrap_or(0);
+    let mut residue_names = vec![String::from("UNK"); num_residues];
+    for (atom, record) in atoms.iter().zip(&receptor.records) {
+        residue_names[atom.residue_id as usize] = record.residue_name.clone();
+    }
+    let cut2 = config.exclusion_distance * config.exclusion_distance;
+    let mut exclusions = Vec::new();
+    for i in 0..atoms.len() {
+        for j in (i + 1)..atoms.len() {
+            let (a, b) = (atoms[i].coords, atoms[j].coords);
+            if (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2) < cut2 {
+                exclusions.push((i as u32, j as u32));
+            }
+        }
+    }
+    Topology {
+        atoms: atoms.clone(),
+        atom_names: receptor.records.iter().map(|r| r.name.clone()).collect(),
+        atom_types: vec![String::new(); atoms.len()],
+        // Unknown elements keep the unit mass of the structure-file path
+        masses: atoms.iter().map(|a| Topology::element_mass(a.element).max(1.0)).collect(),
+        lj,
+        residue_names,
+        exclusions,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_io::topology::HarmonicBond;
+
+    fn chain() -> Topology {
+        let atoms = (0..4)
+            .map(|i| Atom {
+                coords: [i as f32 * 1.6, 0.3 * (i % 2) as f32, 0.0],
+                element: 6,
+                residue_id: 0,
+                atom_type: 1,
+                charge: if i % 2 == 0 { 0.5 } else { -0.5 },
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        Topology {
+            atoms,
+            masses: vec![12.011; 4],
+            lj: vec![prism_io::topology::LjParams { sigma: 3.4, epsilon: 0.086 }; 4],
+            bonds: (0..3).map(|i| HarmonicBond { i, j: i + 1, k: 310.0, r0: 1.526 }).collect(),
+            exclusions: vec![(0, 1), (1, 2), (2, 3)],
+            ..Default::default()
+        }
+    }
+
+    fn run(integrator: Integrator, steps: u64) -> Vec<[f32; 3]> {
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            dt: 0.001,
+            friction: 0.0,
+            temp_start: 0.0,
+            temp_end: 0.0,
+            spring_k: 0.0,
+            integrator,
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        engine.run_nlnm_breathing(steps).unwrap();
+        engine.get_current_atoms().unwrap().iter().map(|a| a.coords).collect()
+    }
+
+    #[cfg(feature = "wgpu")]
+    #[test]
+    fn test_wgpu_backend_matches_host() {
+        let config = |use_gpu| MolecularDynamicsConfig {
+            use_gpu,
+            backend: DeviceBackend::Wgpu,
+            dt: 0.001,
+            friction: 0.0,
+            temp_start: 0.0,
+            temp_end: 0.0,
+            spring_k: 0.0,
+            ..Default::default()
+        };
+        let mut host = MolecularDynamicsEngine::from_topology(config(false), &chain()).unwrap();
+        let mut device = MolecularDynamicsEngine::from_topology(config(true), &chain()).unwrap();
+        assert!(device.wgpu_compute.is_some());
+        let (e_host, e_device) = (host.nonbonded_energy(), device.nonbonded_energy());
+        assert!((e_host.total() - e_device.total()).abs() < 1e-4, "{:?} vs {:?}", e_host, e_device);
+        for (h, d) in host.forces.iter().zip(&device.forces) {
+            assert!((h - d).abs() < 1e-3 * h.abs().max(1.0), "{} vs {}", h, d);
+        }
+
+        // Without friction the device step is the host step
+        host.run_nlnm_breathing(200).unwrap();
+        device.run_nlnm_breathing(200).unwrap();
+        assert!(device.wgpu_compute.is_some());
+        let coords = |engine: &mut MolecularDynamicsEngine| engine.get_current_atoms().unwrap().iter().flat_map(|a| a.coords).collect::<Vec<_>>();
+        for (h, d) in coords(&mut host).iter().zip(&coords(&mut device)) {
+            assert!((h - d).abs() < 1e-3, "{} vs {}", h, d);
+        }
+
+        // Minimum image in the periodic lattice, and device noise heating it
+        let periodic = |use_gpu| MolecularDynamicsConfig {
+            temp_start: 0.6,
+            temp_end: 0.6,
+            friction: 5.0,
+            force_field: ForceFieldConfig { cutoff: 5.8, switch_distance: Some(5.0), ..Default::default() },
+            ..config(use_gpu)
+        };
+        let host = MolecularDynamicsEngine::from_topology(periodic(false), &lattice(4.0)).unwrap();
+        let mut device = MolecularDynamicsEngine::from_topology(periodic(true), &lattice(4.0)).unwrap();
+        let (e_host, e_device) = (host.nonbonded_energy().total(), device.nonbonded_energy().total());
+        assert!((e_host - e_device).abs() < 1e-4 * e_host.abs(), "{} vs {}", e_host, e_device);
+        device.run_nlnm_breathing(100).unwrap();
+        assert!(device.wgpu_compute.is_some());
+        assert!(device.kinetic_energy() > 0.0 && device.kinetic_energy().is_finite());
+    }
+
+    #[test]
+    fn test_minimize_relaxes_chain() {
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = engine.minimize().unwrap() else {
+            panic!("minimization did not succeed");
+        };
+        assert!(telemetry["energy_drop"].as_f64().unwrap() > 0.0);
+        assert_eq!(telemetry["converged"], serde_json::json!(true));
+        assert!(engine.get_statistics().gradient_norm < 2.0);
+    }
+
+    #[test]
+    fn test_complex_appends_ligands_after_receptor() {
+        let receptor = PdbStructure::from_atoms(&chain().atoms);
+        let mut ligand = chain();
+        ligand.residue_names = vec!["LIG".into()];
+        ligand.atoms.iter_mut().for_each(|a| a.coords[1] += 10.0);
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_complex(config, &receptor, &[ligand]).unwrap();
+        let atoms = engine.get_current_atoms().unwrap();
+        assert_eq!(atoms.len(), 8);
+        assert_eq!(atoms[4].residue_id, 1);
+        // Only the ligand carries bonded terms, strained like the chain alone
+        let reference = MolecularDynamicsEngine::from_topology(engine.config().clone(), &chain()).unwrap();
+        let (complex, alone) = (engine.bonded_energy().bond, reference.bonded_energy().bond);
+        assert!(alone > 0.0 && (complex - alone).abs() < 1e-4 * alone, "{} vs {}", complex, alone);
+        assert_eq!(engine.selection_context().unwrap().select("resname LIG").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_potential_replaces_force_field() {
+        /// Harmonic well around the origin
+        #[derive(Debug)]
+        struct Well;
+        impl Potential for Well {
+            fn name(&self) -> &str {
+                "well"
+            }
+            fn compute(&mut self, positions: &[f32], forces: &mut [f32]) -> Result<f64, PrismError> {
+                let mut energy = 0.0;
+                for (p, f) in positions.chunks_exact(4).zip(forces.chunks_exact_mut(4)) {
+                    for d in 0..3 {
+                        energy += 0.5 * (p[d] * p[d]) as f64;
+                        f[d] -= p[d];
+                    }
+                }
+                Ok(energy)
+            }
+        }
+
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, dt: 0.001, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        assert!(engine.bonded_energy().bond > 0.0);
+        engine.set_potential(Box::new(Well)).unwrap();
+        let expected: f64 = chain().atoms.iter().flat_map(|a| a.coords).map(|x| 0.5 * (x * x) as f64).sum();
+        assert!((engine.learned_energy() - expected).abs() < 1e-4);
+        let components = engine.energy_components();
+        assert_eq!((components.bond, components.lennard_jones), (0.0, 0.0));
+        assert!((components.potential() - expected).abs() < 1e-4);
+        engine.run_nlnm_breathing(20).unwrap();
+        assert!(engine.learned_energy().is_finite());
+
+        assert!(engine.take_potential().is_some());
+        assert_eq!(engine.learned_energy(), 0.0);
+        assert!(engine.bonded_energy().bond > 0.0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_qmmm_config_attaches_potential() {
+        // Atom 0 plus the link hydrogen towards atom 1; atoms 2 and 3 embedded
+        let reply = r#"{"energy": -10.0, "gradient": [[0, 0, 0], [0, 0, 0]], "point_charge_gradient": [[0, 0, 0], [0, 0, 0]]}"#;
+        let engine_config = crate::qmmm::QmEngineConfig::Command {
+            executable: "sh".into(),
+            arguments: vec!["-c".into(), format!("cat > /dev/null; echo '{}'", reply)],
+        };
+        let qmmm = QmMmConfig::new("index 0", engine_config);
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, qmmm: Some(qmmm), ..Default::default() };
+        let engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let mm = engine.learned_energy() + 10.0;
+        assert!(mm.is_finite() && mm != 0.0);
+        assert_eq!(engine.bonded_energy().bond, 0.0);
+    }
+
+    #[test]
+    fn test_respa_tracks_single_step_langevin() {
+        let reference = run(Integrator::Langevin, 200);
+        let respa = run(Integrator::Respa { slow_interval: 4 }, 200);
+        for (a, b) in reference.iter().zip(&respa) {
+            for d in 0..3 {
+                assert!(b[d].is_finite());
+                assert!((a[d] - b[d]).abs() < 5e-4, "{:?} vs {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_instability_aborts_or_halves_timestep() {
+        let config = |response| MolecularDynamicsConfig {
+            use_gpu: false,
+            dt: 0.2,
+            temp_start: 0.0,
+            temp_end: 0.0,
+            spring_k: 0.0,
+            instability: InstabilityConfig { response, snapshot_interval: 1, max_recoveries: 20, ..Default::default() },
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config(InstabilityResponse::Abort), &chain()).unwrap();
+        assert!(engine.run_nlnm_breathing(200).unwrap_err().is_instability());
+
+        let mut engine = MolecularDynamicsEngine::from_topology(config(InstabilityResponse::HalveTimestep), &chain()).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(200).unwrap() else {
+            panic!("run did not succeed");
+        };
+        let recoveries: Vec<InstabilityRecovery> = serde_json::from_value(telemetry["instability_recoveries"].clone()).unwrap();
+        assert!(!recoveries.is_empty());
+        assert!(recoveries.iter().all(|r| r.restored_step <= r.step && r.response == InstabilityResponse::HalveTimestep));
+        assert_eq!(engine.config.dt, recoveries.last().unwrap().dt);
+        assert!(engine.config.dt < 0.2);
+        assert!(engine.potential_energy().is_finite());
+    }
+
+    #[test]
+    fn test_metadynamics_bias_deposits_during_run() {
+        use crate::collective_variables::CollectiveVariable;
+        use crate::metadynamics::{Metadynamics, MetadynamicsConfig};
+
+        let config = MolecularDynamicsConfig { use_gpu: false, dt: 0.001, spring_k: 0.0, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let meta_config = MetadynamicsConfig { widths: vec![0.1], pace: 10, ..Default::default() };
+        let cv = CollectiveVariable::Distance { a: vec![0], b: vec![3] };
+        engine.add_bias(Box::new(Metadynamics::new(meta_config, vec![cv]).unwrap()));
+
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(50).unwrap() else {
+            panic!("run did not succeed");
+        };
+        assert_eq!(telemetry["metadynamics_hills"], serde_json::json!(5));
+        assert_eq!(engine.bias::<Metadynamics>().unwrap().hills().len(), 5);
+        assert!(engine.bias_energy() > 0.0);
+        assert!(engine.potential_energy().is_finite());
+        assert_eq!(engine.take_biases().len(), 1);
+        assert_eq!(engine.bias_energy(), 0.0);
+    }
+
+    #[test]
+    fn test_temperature_schedule_drives_thermostat() {
+        let schedule = TemperatureSchedule::Piecewise { points: vec![(0, 1.0), (50, 0.2), (100, 0.2)] };
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            temperature_schedule: Some(schedule),
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        engine.run_nlnm_breathing(25).unwrap();
+        assert!((engine.get_statistics().current_temperature - 0.6).abs() < 1e-6);
+        engine.run_nlnm_breathing(75).unwrap();
+        assert!((engine.get_statistics().current_temperature - 0.2).abs() < 1e-6);
+
+        let invalid = TemperatureSchedule::Exponential { start: 1.0, end: -1.0, steps: 10 };
+        assert!(engine.set_temperature_schedule(invalid).is_err());
+        engine.set_temperature(0.3);
+        assert_eq!(engine.temperature_at(1_000), 0.3);
+    }
+
+    #[test]
+    fn test_path_integral_stages_share_ring_polymer() {
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, temp_start: 0.6, temp_end: 0.6, ..Default::default() };
+        let mut classical = MolecularDynamicsEngine::from_topology(config.clone(), &chain()).unwrap();
+        assert!(classical.run_pimc(1).is_err());
+
+        let config = MolecularDynamicsConfig { pimc: Some(PimcConfig { num_beads: 4, block_length: 2, equilibration_sweeps: 0, ..Default::default() }), ..config };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_pimc(20).unwrap() else {
+            panic!("PIMC did not succeed");
+        };
+        assert!(telemetry["bead_acceptance"].as_f64().unwrap() > 0.0);
+        assert!(telemetry["centroid_step_size"].as_f64().unwrap() > 0.0);
+        // One estimator sample every 10 sweeps, two samples per block
+        assert_eq!(telemetry["estimator_blocks"].as_array().unwrap().len(), 1);
+        assert!(telemetry["virial_kinetic"]["mean"].as_f64().unwrap() > 0.0);
+        let stats = engine.get_statistics();
+        assert_eq!(stats.pimc_moves.len(), 2);
+        assert_eq!(stats.pimc_moves[1].kind, PimcMove::Centroid);
+        assert_eq!(stats.pimc_moves[1].stats.attempted, 20);
+        assert!(stats.acceptance_rate > 0.0 && stats.acceptance_rate < 1.0);
+        assert!(telemetry["mean_gyration_radius"].as_f64().unwrap() > 0.0);
+
+        engine.run_rpmd(10).unwrap();
+        assert_eq!(engine.get_statistics().current_step, 10);
+        let polymer = engine.ring_polymer().unwrap().clone();
+        let centroid = polymer.centroid();
+        let atoms = engine.get_current_atoms().unwrap();
+        assert!((atoms[2].coords[0] as f64 - centroid[6]).abs() < 1e-5);
+
+        let path = std::env::temp_dir().join(format!("prism_pimc_{}.ckpt", std::process::id()));
+        engine.save_checkpoint(&path).unwrap();
+        let mut resumed = MolecularDynamicsEngine::from_topology(engine.config().clone(), &chain()).unwrap();
+        resumed.resume_from_checkpoint(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let restored = resumed.ring_polymer().unwrap();
+        assert_eq!(restored.num_beads(), 4);
+        assert!((restored.bead(3)[0] - polymer.bead(3)[0]).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_fixed_centroid_rpmd_writes_centroid_frames() {
+        let path = std::env::temp_dir().join(format!("prism_centroid_{}.dcd", std::process::id()));
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            pimc: Some(PimcConfig { num_beads: 4, fixed_centroid: true, ..Default::default() }),
+            trajectory: Some(TrajectoryConfig { path: path.clone(), format: Default::default(), stride: 5, precision: 1000.0, selection: None, append: false }),
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let start = engine.get_current_atoms().unwrap();
+        engine.run_pimc(10).unwrap();
+        engine.run_rpmd(20).unwrap();
+        assert!(engine.ring_polymer().unwrap().gyration_radii()[0] > 0.0);
+        let centroid = engine.centroid_positions().unwrap();
+        assert!((centroid[4] - start[1].coords[0]).abs() < 1e-4);
+
+        let frames = prism_io::dcd::read_dcd(&path).unwrap().frames;
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(frames.len(), 4);
+        for (atom, position) in start.iter().zip(&frames[3]) {
+            for (a, b) in atom.coords.iter().zip(position) {
+                assert!((a - b).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_position_restraints_hold_selection_through_minimization_and_dynamics() {
+        let topology = Topology { atom_names: ["C1", "C2", "C3", "C4"].map(String::from).to_vec(), ..chain() };
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            position_restraints: vec![
+                PositionRestraintConfig { selection: "name C1 C4".to_string(), force_constant: 500.0 },
+                PositionRestraintConfig { selection: "name C2".to_string(), force_constant: 0.0 },
+            ],
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config.clone(), &topology).unwrap();
+        assert_eq!(engine.position_restraints().atoms(0), &[0, 3]);
+        let start = engine.get_current_atoms().unwrap();
+
+        let PhaseOutcome::Success { telemetry, .. } = engine.minimize().unwrap() else {
+            panic!("minimization did not succeed");
+        };
+        let rmsd = telemetry["position_restraint_rmsd"].as_array().unwrap();
+        assert!(rmsd[0].as_f64().unwrap() < 0.1 && rmsd[1].as_f64().unwrap() > 0.1, "{:?}", rmsd);
+        let energy = telemetry["position_restraint_energy"].as_f64().unwrap();
+        assert!((engine.get_statistics().position_restraint_energy as f64 - energy).abs() < 1e-4);
+
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(200).unwrap() else {
+            panic!("run did not succeed");
+        };
+        assert_eq!(telemetry["position_restraint_energies"].as_array().unwrap().len(), 2);
+        let atoms = engine.get_current_atoms().unwrap();
+        for i in [0, 3] {
+            let d: f32 = (0..3).map(|k| (atoms[i].coords[k] - start[i].coords[k]).powi(2)).sum::<f32>().sqrt();
+            assert!(d < 0.3, "atom {} moved {}", i, d);
+        }
+
+        let bad = MolecularDynamicsConfig {
+            position_restraints: vec![PositionRestraintConfig { selection: "name CA".to_string(), force_constant: 1.0 }],
+            ..config
+        };
+        assert!(MolecularDynamicsEngine::from_topology(bad, &topology).is_err());
+    }
+
+    #[test]
+    fn test_restraint_file_pulls_chain_ends_and_reports_violations() {
+        let path = std::env::temp_dir().join(format!("prism_restraints_{}.txt", std::process::id()));
+        std::fs::write(&path, "# end-to-end distance\ndistance 0 3 3.0 3.5 100.0\nangle 0 1 2 0 180 1.0\n").unwrap();
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            temp_start: 0.1,
+            temp_end: 0.1,
+            analysis_interval: 50,
+            restraint_file: Some(path.clone()),
+            ..Default::default()
+        };
+        let engine = MolecularDynamicsEngine::from_topology(config.clone(), &chain());
+        let _ = std::fs::remove_file(&path);
+        let mut engine = engine.unwrap();
+        assert_eq!(engine.geometric_restraints().len(), 2);
+        assert!(engine.geometric_restraint_energy() > 0.0);
+
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(2000).unwrap() else {
+            panic!("run did not succeed");
+        };
+        assert_eq!(engine.geometric_restraints().samples(), 40);
+        let report: Vec<crate::restraints::RestraintViolation> =
+            serde_json::from_value(telemetry["restraint_violations"].clone()).unwrap();
+        assert!(report[0].violation < 0.5 * report[0].max_violation, "{:?}", report[0]);
+        assert_eq!((report[1].violation, report[1].max_violation), (0.0, 0.0));
+
+        assert!(MolecularDynamicsEngine::from_topology(config, &chain()).is_err());
+    }
+
+    #[test]
+    fn test_energy_components_reported_per_frame() {
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            analysis_interval: 50,
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(200).unwrap() else {
+            panic!("run did not succeed");
+        };
+        let frames = telemetry["energy_components"].as_array().unwrap();
+        let steps: Vec<u64> = frames.iter().map(|f| f["step"].as_u64().unwrap()).collect();
+        assert_eq!(steps, vec![50, 100, 150, 200]);
+        let last: EnergyComponents = serde_json::from_value(frames[3].clone()).unwrap();
+        assert!(last.bond > 0.0 && last.kinetic > 0.0 && last.restraint > 0.0);
+
+        let stats = engine.get_statistics();
+        assert!((stats.energy.potential() - engine.potential_energy()).abs() < 1e-9);
+        assert!((stats.energy.kinetic - engine.kinetic_energy()).abs() < 1e-9);
+        assert!((stats.energy.total() - stats.energy.potential() - stats.energy.kinetic).abs() < 1e-9);
+    }
+
+    /// 3×3×3 Lennard-Jones lattice filling a periodic cubic box
+    fn lattice(spacing: f32) -> Topology {
+        use prism_io::simulation_box::SimulationBox;
+        use prism_io::topology::LjParams;
+        let atoms: Vec<Atom> = (0..27)
+            .map(|k| Atom {
+                coords: [k % 3, (k / 3) % 3, k / 9].map(|c| (c as f32 + 0.5) * spacing),
+                element: 18,
+                residue_id: k as u16,
+                atom_type: 0,
+                charge: 0.0,
+                radius: 1.9,
+                _reserved: [0; 4],
+            })
+            .collect();
+        Topology {
+            lj: vec![LjParams { sigma: 3.4, epsilon: 0.24 }; atoms.len()],
+            masses: vec![40.0; atoms.len()],
+            atoms,
+            simulation_box: Some(SimulationBox::orthorhombic([3.0 * spacing; 3])),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_virial_pressure_reported() {
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            analysis_interval: 10,
+            force_field: ForceFieldConfig { cutoff: 4.4, switch_distance: Some(4.0), neighbor_skin: 0.2, ..Default::default() },
+            ..Default::default()
+        };
+        // Squeezed below the LJ minimum the lattice pushes outward, stretched it pulls in
+        let squeezed = MolecularDynamicsEngine::from_topology(config.clone(), &lattice(3.0)).unwrap();
+        let stretched = MolecularDynamicsEngine::from_topology(config.clone(), &lattice(4.2)).unwrap();
+        assert_eq!(squeezed.kinetic_energy(), 0.0);
+        let (p_in, p_out) = (squeezed.pressure().unwrap(), stretched.pressure().unwrap());
+        assert!(p_in.scalar() > 0.0 && p_out.scalar() < 0.0, "{:?} {:?}", p_in, p_out);
+        // Cubic symmetry: isotropic, no shear
+        assert!((p_in.tensor[0][0] - p_in.tensor[2][2]).abs() < 1e-6 * p_in.scalar().abs());
+        assert!(p_in.tensor[0][1].abs() < 1e-6 * p_in.scalar().abs());
+
+        let mut engine = stretched;
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(30).unwrap() else {
+            panic!("run did not succeed");
+        };
+        let frames = telemetry["pressure"].as_array().unwrap();
+        let steps: Vec<u64> = frames.iter().map(|f| f["step"].as_u64().unwrap()).collect();
+        assert_eq!(steps, vec![10, 20, 30]);
+        let stats = engine.get_statistics();
+        let pressure = stats.pressure.unwrap();
+        assert!(pressure.scalar().is_finite());
+        assert!((pressure.tensor[0][2] - pressure.tensor[2][0]).abs() < 1e-6 * pressure.scalar().abs().max(1.0));
+        assert!(engine.kinetic_energy() > 0.0);
+
+        // Open boundaries have no pressure; cutoffs beyond half the box are rejected
+        assert!(MolecularDynamicsEngine::from_topology(config.clone(), &chain()).unwrap().pressure().is_none());
+        let too_long = MolecularDynamicsConfig { force_field: ForceFieldConfig { cutoff: 6.0, ..Default::default() }, ..config };
+        assert!(MolecularDynamicsEngine::from_topology(too_long, &lattice(3.0)).is_err());
+    }
+
+    #[test]
+    fn test_trajectory_selection_writes_selected_atoms() {
+        let path = std::env::temp_dir().join(format!("prism_selected_{}.dcd", std::process::id()));
+        let topology = Topology {
+            atom_names: ["C1", "C2", "C3", "C4"].map(String::from).to_vec(),
+            residue_names: vec!["LIG".to_string()],
+            ..chain()
+        };
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            trajectory: Some(TrajectoryConfig {
+                path: path.clone(),
+                format: Default::default(),
+                stride: 5,
+                precision: 1000.0,
+                selection: Some("name C2 C4".to_string()),
+                append: false,
+            }),
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &topology).unwrap();
+        assert_eq!(engine.select("resname LIG and not name C1").unwrap(), vec![1, 2, 3]);
+        assert_eq!(engine.select("within 1.7 of index 0").unwrap(), vec![0, 1]);
+        assert!(engine.select("resid").is_err());
+        engine.run_nlnm_breathing(10).unwrap();
+        let atoms = engine.get_current_atoms().unwrap();
+
+        let frames = prism_io::dcd::read_dcd(&path).unwrap().frames;
+        let _ = std::fs::remove_file(&path);
+        assert_eq!((frames.len(), frames[1].len()), (2, 2));
+        for (atom, position) in [&atoms[1], &atoms[3]].iter().zip(&frames[1]) {
+            for (a, b) in atom.coords.iter().zip(position) {
+                assert!((a - b).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_breathing_run_reports_elastic_network_overlap() {
+        let topology = Topology {
+            // Puckered so the four-node network has no soft planar mode
+            atoms: chain()
+                .atoms
+                .into_iter()
+                .enumerate()
+                .map(|(i, a)| Atom { residue_id: i as u16, coords: [a.coords[0], a.coords[1], [0.0, 0.0, 1.2, -0.8][i]], ..a })
+                .collect(),
+            atom_names: vec!["CA".to_string(); 4],
+            ..chain()
+        };
+        let b_factors = std::env::temp_dir().join(format!("prism_enm_b_{}.pdb", std::process::id()));
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            elastic_network: Some(ElasticNetworkConfig { b_factor_output: Some(b_factors.clone()), ..Default::default() }),
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &topology).unwrap();
+        assert_eq!(engine.elastic_network().unwrap().modes().len(), 6);
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(50).unwrap() else {
+            panic!("Breathing run did not succeed");
+        };
+        assert_eq!(telemetry["enm_overlap"].as_array().unwrap().len(), 6);
+        let cumulative = telemetry["enm_cumulative_overlap"][5].as_f64().unwrap();
+        assert!(cumulative > 0.9 && cumulative <= 1.0 + 1e-9, "{}", cumulative);
+        assert_eq!(telemetry["enm_residues"], serde_json::json!([0, 1, 2, 3]));
+        assert_eq!(telemetry["enm_participation"].as_array().unwrap().len(), 6);
+        let msf = telemetry["enm_msf"][0].as_f64().unwrap();
+        let pdb = prism_io::pdb::parse_pdb(&std::fs::read_to_string(&b_factors).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&b_factors);
+        let expected = 8.0 * std::f64::consts::PI.powi(2) / 3.0 * msf;
+        assert!((pdb.records[0].b_factor as f64 - expected).abs() < 0.01, "{} vs {}", pdb.records[0].b_factor, expected);
+
+        let path = std::env::temp_dir().join(format!("prism_enm_{}.pdb", std::process::id()));
+        let frames = engine.export_mode_animation(&ModeAnimationConfig::default(), &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(frames, 20);
+        assert_eq!(text.matches("MODEL").count(), 20);
+
+        let config = MolecularDynamicsConfig { elastic_network: Some(ElasticNetworkConfig::default()), ..Default::default() };
+        assert!(MolecularDynamicsEngine::from_topology(config, &chain()).is_err());
+    }
+
+    #[test]
+    fn test_rmsd_analysis_observes_breathing_run() {
+        use crate::analysis::RmsdAnalysis;
+
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            analysis_interval: 10,
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let reference = engine.buffers.as_ref().unwrap().positions.clone();
+        engine.add_analysis(Box::new(RmsdAnalysis::new(&reference, Vec::new())));
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(50).unwrap() else {
+            panic!("Breathing run did not succeed");
+        };
+        let rmsd = engine.analysis::<RmsdAnalysis>().unwrap();
+        assert_eq!(rmsd.series().iter().map(|s| s.0).collect::<Vec<_>>(), vec![10, 20, 30, 40, 50]);
+        assert!(rmsd.series()[4].1 > 0.0);
+        assert_eq!(telemetry["rmsf"].as_array().unwrap().len(), 4);
+        assert_eq!(engine.take_analyses().len(), 1);
+    }
+
+    #[test]
+    fn test_native_contacts_tracked_during_breathing_run() {
+        let mut topology = chain();
+        for (i, atom) in topology.atoms.iter_mut().enumerate() {
+            atom.residue_id = i as u16;
+        }
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            analysis_interval: 10,
+            native_contacts: Some(NativeContactConfig { min_separation: 2, ..Default::default() }),
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config.clone(), &topology).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(30).unwrap() else {
+            panic!("Breathing run did not succeed");
+        };
+        let contacts = engine.analysis::<NativeContacts>().unwrap();
+        assert_eq!(contacts.residue_contacts(), vec![(0, 2), (1, 3)]);
+        assert_eq!(contacts.series().len(), 3);
+        assert!(contacts.series().iter().all(|&(_, q)| q > 0.5 && q <= 1.0));
+        assert_eq!(telemetry["native_contacts"], 2);
+        assert_eq!(telemetry["q_native"].as_array().unwrap().len(), 3);
+
+        // All residues distinct by less than the default separation
+        let config = MolecularDynamicsConfig { native_contacts: Some(NativeContactConfig::default()), ..config };
+        assert!(MolecularDynamicsEngine::from_topology(config, &topology).is_err());
+    }
+
+    #[test]
+    fn test_secondary_structure_sampled_during_breathing_run() {
+        let topology = crate::analysis::dssp::tests::peptide(-57.0, -47.0, 12);
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            temp_start: 0.01,
+            temp_end: 0.01,
+            spring_k: 0.0,
+            analysis_interval: 10,
+            secondary_structure: true,
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &topology).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(20).unwrap() else {
+            panic!("Breathing run did not succeed");
+        };
+        let frames = telemetry["secondary_structure"].as_array().unwrap();
+        assert_eq!(frames.len(), 2);
+        let first = frames[0][1].as_str().unwrap();
+        assert_eq!(first.len(), 12);
+        assert!(first.contains("HHHH"), "{}", first);
+        assert_eq!(telemetry["secondary_structure_residues"].as_array().unwrap().len(), 12);
+
+        assert!(MolecularDynamicsEngine::from_topology(engine.config.clone(), &chain()).is_err());
+    }
+
+    #[test]
+    fn test_sasa_tracked_during_breathing_run() {
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            analysis_interval: 20,
+            sasa: Some(SasaConfig::default()),
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(40).unwrap() else {
+            panic!("Breathing run did not succeed");
+        };
+        let sasa = engine.analysis::<SasaAnalysis>().unwrap();
+        assert_eq!(sasa.series().len(), 2);
+        // Four bonded carbons expose less than four isolated spheres
+        let isolated = 4.0 * 4.0 * std::f64::consts::PI * (1.7f64 + 1.4).powi(2);
+        assert!(sasa.series()[0].1 > 0.0 && sasa.series()[0].1 < isolated);
+        assert_eq!(telemetry["sasa_residues"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_shape_analysis_tracks_compactness() {
+        let config = MolecularDynamicsConfig {
+            use_gpu: false,
+            temp_start: 0.6,
+            temp_end: 0.6,
+            analysis_interval: 25,
+            shape_analysis: true,
+            ..Default::default()
+        };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(50).unwrap() else {
+            panic!("Breathing run did not succeed");
+        };
+        let shape = engine.analysis::<ShapeAnalysis>().unwrap();
+        assert_eq!(shape.series().len(), 2);
+        let descriptors = shape.series()[1].1;
+        let moments: f64 = descriptors.principal_moments.iter().sum();
+        assert!(descriptors.radius_of_gyration > 0.0);
+        assert!((descriptors.radius_of_gyration.powi(2) - moments).abs() < 1e-9);
+        assert_eq!(telemetry["gyration_moments"][0].as_array().unwrap().len(), 4);
+        assert_eq!(telemetry["asphericity"][1][0], 50.0);
+    }
+
+    #[test]
+    fn test_steered_pull_reports_work_profile() {
+        use crate::collective_variables::CollectiveVariable;
+        use crate::steered::{PullMode, SteeredConfig, SteeredMd};
+
+        let config = MolecularDynamicsConfig { use_gpu: false, dt: 0.001, spring_k: 0.0, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let steered = SteeredConfig {
+            mode: PullMode::ConstantVelocity { velocity: 10.0, force_constant: 50.0 },
+            record_interval: 20,
+        };
+        let cv = CollectiveVariable::Projection { group: vec![3], direction: [1.0, 0.0, 0.0] };
+        engine.add_bias(Box::new(SteeredMd::new(steered, cv).unwrap()));
+
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(100).unwrap() else {
+            panic!("run did not succeed");
+        };
+        assert_eq!(telemetry["steered_work_profile"].as_array().unwrap().len(), 6);
+        let smd = engine.bias::<SteeredMd>().unwrap();
+        assert!((smd.anchor() - (4.8 + 1.0)).abs() < 1e-4);
+        assert!(smd.work().is_finite() && smd.work() > 0.0);
+    }
+
+    #[test]
+    fn test_umbrella_windows_write_time_series() {
+        use crate::collective_variables::CollectiveVariable;
+        use crate::umbrella::{UmbrellaConfig, UmbrellaSampling};
+
+        let config = MolecularDynamicsConfig { use_gpu: false, dt: 0.001, spring_k: 0.0, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let dir = std::env::temp_dir().join(format!("prism_umbrella_{}", std::process::id()));
+        let umbrella_config = UmbrellaConfig {
+            windows: UmbrellaConfig::evenly_spaced(4.0, 4.4, 2, 50.0),
+            equilibration_steps: 20,
+            production_steps: 100,
+            sample_interval: 10,
+            output_dir: dir.clone(),
+        };
+        let cv = CollectiveVariable::Distance { a: vec![0], b: vec![3] };
+        let mut umbrella = UmbrellaSampling::new(umbrella_config, cv).unwrap();
+        umbrella.run(&mut engine).unwrap();
+
+        let summaries = umbrella.summaries();
+        assert_eq!(summaries.len(), 2);
+        assert!(summaries.iter().all(|w| w.samples == 10 && w.mean.is_finite()));
+        let series = std::fs::read_to_string(&summaries[1].path).unwrap();
+        assert_eq!(series.lines().filter(|l| !l.starts_with('#')).count(), 10);
+        let metadata = std::fs::read_to_string(dir.join("metadata.dat")).unwrap();
+        assert_eq!(metadata.lines().count(), 2);
+        assert_eq!(engine.bias_energy(), 0.0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_double_precision_keeps_sub_ulp_steps() {
+        // Two atoms at ±1e4 Å drift at ~0.1 Å/ps; with dt = 1 fs each step
+        // is below half an f32 ulp of their coordinates (~1e-3 Å)
+        let atoms: Vec<Atom> = [1.0e4f32, -1.0e4]
+            .iter()
+            .enumerate()
+            .map(|(k, &x)| Atom {
+                coords: [x; 3],
+                element: 18,
+                residue_id: k as u16,
+                atom_type: 0,
+                charge: 0.0,
+                radius: 1.9,
+                _reserved: [0; 4],
+            })
+            .collect();
+        let topology = Topology { masses: vec![40.0; 2], atoms, ..Default::default() };
+        // (distance each atom should have covered, distances covered)
+        let run = |precision: Precision| {
+            let config = MolecularDynamicsConfig {
+                use_gpu: false,
+                friction: 0.0,
+                spring_k: 0.0,
+                precision,
+                ..Default::default()
+            };
+            let mut engine = MolecularDynamicsEngine::from_topology(config, &topology).unwrap();
+            engine.assign_velocities(0.6);
+            // Equal and opposite momenta: ½ m (v₀² + v₁²) = m v²
+            let expected = (engine.kinetic_energy() / 40.0).sqrt() * 1000.0 * 0.001;
+            engine.run_nlnm_breathing(1000).unwrap();
+            let moved: Vec<f64> = engine
+                .get_current_atoms()
+                .unwrap()
+                .iter()
+                .zip(&topology.atoms)
+                .map(|(a, b)| (0..3).map(|d| ((a.coords[d] - b.coords[d]) as f64).powi(2)).sum::<f64>().sqrt())
+                .collect();
+            (expected, moved)
+        };
+
+        let (_, single) = run(Precision::Single);
+        let (expected, double) = run(Precision::Double);
+        assert!(expected > 0.05, "{}", expected);
+        assert_eq!(single, vec![0.0; 2]);
+        // Mixed only widens the force sums; the state stays f32
+        assert_eq!(run(Precision::Mixed).1, single);
+        for d in double {
+            assert!((d - expected).abs() < 2e-3, "{} vs {}", d, expected);
+        }
+    }
+
+    #[test]
+    fn test_config_accepts_explicit_units() {
+        let mut json = serde_json::to_value(MolecularDynamicsConfig::default()).unwrap();
+        json["dt"] = serde_json::json!("2 fs");
+        json["temp_start"] = serde_json::json!("300 K");
+        json["cutoff_dist"] = serde_json::json!("1.2 nm");
+        let config: MolecularDynamicsConfig = serde_json::from_value(json.clone()).unwrap();
+        assert!((config.timestep().as_fs() - 2.0).abs() < 1e-6);
+        assert!((config.temperatures().0.as_kelvin() - 300.0).abs() < 1e-3);
+        assert_eq!(config.cutoff_dist, 12.0);
+
+        json["dt"] = serde_json::json!("2 K");
+        let error = serde_json::from_value::<MolecularDynamicsConfig>(json).unwrap_err();
+        assert!(error.to_string().contains("time unit"), "{}", error);
+    }
+
+    #[test]
+    fn test_config_validation_names_field() {
+        assert!(MolecularDynamicsConfig::default().validate().is_ok());
+        let rejected = |config: MolecularDynamicsConfig, field: &str| {
+            let error = MolecularDynamicsEngine::new(config).unwrap_err();
+            assert!(matches!(error, PrismError::ValidationError(_)), "{:?}", error);
+            assert!(error.to_string().contains(field), "{} does not name {}", error, field);
+        };
+        rejected(MolecularDynamicsConfig { dt: 0.0, ..Default::default() }, "dt");
+        rejected(MolecularDynamicsConfig { dt: f32::NAN, ..Default::default() }, "dt");
+        rejected(MolecularDynamicsConfig { max_workspace_memory: 0, ..Default::default() }, "max_workspace_memory");
+        rejected(MolecularDynamicsConfig { devices: vec![0, 1, 0], ..Default::default() }, "devices");
+        if !cfg!(feature = "rocm") {
+            rejected(MolecularDynamicsConfig { use_gpu: true, backend: DeviceBackend::Rocm, ..Default::default() }, "backend");
+        }
+        if !cfg!(feature = "wgpu") {
+            rejected(MolecularDynamicsConfig { use_gpu: true, backend: DeviceBackend::Wgpu, ..Default::default() }, "backend");
+        }
+        let pimc = |config: PimcConfig| MolecularDynamicsConfig { pimc: Some(config), ..Default::default() };
+        rejected(pimc(PimcConfig { num_beads: 0, ..Default::default() }), "pimc.num_beads");
+        rejected(pimc(PimcConfig { target_acceptance: 1.0, ..Default::default() }), "pimc.target_acceptance");
+        let mut config = MolecularDynamicsConfig::default();
+        config.force_field.switch_distance = Some(config.force_field.cutoff);
+        rejected(config, "force_field.switch_distance");
+    }
+
+    #[test]
+    fn test_config_builder_presets() {
+        let config = MolecularDynamicsConfigBuilder::quick_test()
+            .timestep(Time::fs(2.0))
+            .cutoff(Length::nanometer(0.9))
+            .position_restraint("backbone", 5.0)
+            .build()
+            .unwrap();
+        assert!((config.dt - 0.002).abs() < 1e-9);
+        assert_eq!(config.force_field.cutoff, 9.0);
+        assert!((config.temperatures().1.as_kelvin() - 300.0).abs() < 1e-3);
+        assert_eq!(config.position_restraints.len(), 1);
+        assert!(!config.use_gpu);
+
+        assert!(MolecularDynamicsConfigBuilder::production_breathing().build().unwrap().shape_analysis);
+        let pimc = MolecularDynamicsConfigBuilder::quantum_pimc().build().unwrap();
+        assert_eq!(pimc.pimc.unwrap().num_beads, PimcConfig::default().num_beads);
+
+        let error = MolecularDynamicsConfig::builder().timestep(Time::fs(-1.0)).build().unwrap_err();
+        assert!(error.to_string().contains("dt"), "{}", error);
+        let error = MolecularDynamicsConfig::builder().pimc(PimcConfig { num_beads: 0, ..Default::default() }).build();
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_observers_called_and_stop_run() {
+        use std::sync::{Arc, Mutex};
+
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        engine.add_observer(10, move |stats| {
+            assert!(stats.current_energy.is_finite());
+            log.lock().unwrap().push(stats.current_step);
+            if stats.current_step >= 30 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        let slow = Arc::new(Mutex::new(Vec::new()));
+        let slow_log = Arc::clone(&slow);
+        engine.add_observer(25, move |stats| {
+            slow_log.lock().unwrap().push(stats.current_step);
+            ControlFlow::Continue(())
+        });
+
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(100).unwrap() else {
+            panic!("run failed");
+        };
+        assert_eq!(*seen.lock().unwrap(), vec![10, 20, 30]);
+        assert_eq!(*slow.lock().unwrap(), vec![25]);
+        assert_eq!(engine.get_statistics().current_step, 30);
+        assert_eq!(telemetry["stopped_by_observer"], serde_json::json!(30));
+
+        // Without observers the next run goes the full length
+        engine.clear_observers();
+        let PhaseOutcome::Success { telemetry, .. } = engine.run_nlnm_breathing(20).unwrap() else {
+            panic!("run failed");
+        };
+        assert_eq!(engine.get_statistics().current_step, 50);
+        assert!(!telemetry.contains_key("stopped_by_observer"));
+    }
+
+    #[test]
+    fn test_cancellation_and_pause() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        let token = CancellationToken::new();
+        engine.set_cancellation_token(token.clone());
+        let progress = Arc::new(AtomicU64::new(0));
+        let observed = Arc::clone(&progress);
+        engine.add_observer(1, move |stats| {
+            observed.store(stats.current_step, Ordering::SeqCst);
+            ControlFlow::Continue(())
+        });
+
+        // Paused before the first step, the loop stays parked
+        token.pause();
+        let worker = std::thread::spawn(move || {
+            let outcome = engine.run_nlnm_breathing(10_000_000).unwrap();
+            (engine, outcome)
+        });
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(progress.load(Ordering::SeqCst), 0);
+
+        token.resume();
+        while progress.load(Ordering::SeqCst) < 20 {
+            std::thread::yield_now();
+        }
+        token.cancel();
+        let (mut engine, outcome) = worker.join().unwrap();
+        let PhaseOutcome::Cancelled { reason, telemetry } = outcome else {
+            panic!("expected a cancelled outcome, got {:?}", outcome);
+        };
+        let step = engine.get_statistics().current_step;
+        assert!((20..10_000_000).contains(&step));
+        assert_eq!(reason, format!("Cancelled at step {}", step));
+        assert_eq!(telemetry["statistics"]["current_step"], serde_json::json!(step));
+        assert!(telemetry.contains_key("energy_components"));
+
+        // A fresh token lets the engine continue
+        engine.set_cancellation_token(CancellationToken::new());
+        assert!(engine.run_nlnm_breathing(10).unwrap().is_success());
+        assert_eq!(engine.get_statistics().current_step, step + 10);
+    }
+
+    #[tokio::test]
+    async fn test_run_async_streams_progress() {
+        use tokio_stream::StreamExt;
+
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        engine.add_observer(1000, |_| ControlFlow::Continue(()));
+        let mut run = engine.run_async(50, 10);
+        let mut steps = Vec::new();
+        while let Some(stats) = run.next().await {
+            steps.push(stats.current_step);
+        }
+        assert_eq!(steps, vec![10, 20, 30, 40, 50]);
+
+        let (engine, outcome) = run.finish().await.unwrap();
+        assert!(outcome.is_success());
+        assert_eq!(engine.get_statistics().current_step, 50);
+        // Only the progress observer is removed again
+        assert_eq!(engine.observers.len(), 1);
+    }
+
+    #[test]
+    fn test_forces_independent_of_thread_count() {
+        // 1000 atoms span several row blocks of the parallel pair loop
+        let atoms: Vec<Atom> = (0..1000)
+            .map(|i| Atom {
+                coords: [(i % 10) as f32 * 3.1, (i / 10 % 10) as f32 * 3.3, (i / 100) as f32 * 2.9],
+                element: 6,
+                residue_id: 0,
+                atom_type: 1,
+                charge: if i % 3 == 0 { 0.4 } else { -0.2 },
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        let lattice = Topology {
+            atoms,
+            masses: vec![12.011; 1000],
+            lj: vec![prism_io::topology::LjParams { sigma: 3.4, epsilon: 0.086 }; 1000],
+            bonds: (0..999).filter(|i| i % 10 != 9).map(|i| HarmonicBond { i, j: i + 1, k: 310.0, r0: 3.0 }).collect(),
+            exclusions: (0..999).filter(|i| i % 10 != 9).map(|i| (i, i + 1)).collect(),
+            ..Default::default()
+        };
+        let forces = |threads: usize| {
+            let config = MolecularDynamicsConfig::builder().use_gpu(false).num_threads(threads).build().unwrap();
+            let mut engine = MolecularDynamicsEngine::from_topology(config, &lattice).unwrap();
+            engine.evaluate_forces();
+            (engine.forces.clone(), engine.energy_components())
+        };
+        let (serial, serial_energy) = forces(1);
+        let (parallel, parallel_energy) = forces(4);
+        assert!(serial.iter().any(|&f| f != 0.0));
+        assert_eq!(serial, parallel);
+        assert_eq!(serial_energy, parallel_energy);
+
+        let invalid = MolecularDynamicsConfig { num_threads: Some(0), ..Default::default() };
+        assert!(MolecularDynamicsEngine::new(invalid).is_err());
+    }
+
+    #[test]
+    fn test_structure_file_reads_ptb_blocks() {
+        let path = std::env::temp_dir().join(format!("prism_blocks_{}.ptb", std::process::id()));
+        prism_io::HolographicBinaryFormat::new()
+            .with_atoms(chain().atoms)
+            .with_compression(prism_io::holographic::PtbCompression::Zstd(3))
+            .with_chunk_size(64)
+            .write_to_file(&path)
+            .unwrap();
+        let config = || MolecularDynamicsConfig { use_gpu: false, ..Default::default() };
+        let mapped = MolecularDynamicsEngine::from_structure_file(config(), &path).unwrap();
+        let buffered = MolecularDynamicsEngine::from_sovereign_buffer(config(), &std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(mapped.atoms_metadata.len(), 4);
+        for (a, b) in mapped.atoms_metadata.iter().zip(&chain().atoms) {
+            assert_eq!(a.coords, b.coords);
+        }
+        assert_eq!(mapped.energy_components(), buffered.energy_components());
+        assert!(MolecularDynamicsEngine::from_structure_file(config(), Path::new("missing.ptb")).is_err());
+    }
+
+    #[test]
+    fn test_ptb_trajectory_appends_after_resume() {
+        let path = std::env::temp_dir().join(format!("prism_resume_{}.ptbt", std::process::id()));
+        let checkpoint = path.with_extension("ckpt");
+        let trajectory = TrajectoryConfig {
+            path: path.clone(),
+            format: prism_io::trajectory::TrajectoryFormat::Ptb,
+            stride: 5,
+            precision: 1000.0,
+            selection: None,
+            append: true,
+        };
+        let config = MolecularDynamicsConfig { use_gpu: false, spring_k: 0.0, trajectory: Some(trajectory), ..Default::default() };
+        let mut engine = MolecularDynamicsEngine::from_topology(config.clone(), &chain()).unwrap();
+        engine.run_nlnm_breathing(10).unwrap();
+        engine.save_checkpoint(&checkpoint).unwrap();
+        engine.run_nlnm_breathing(7).unwrap();
+        drop(engine);
+
+        // Crash while the frame of step 20 was being written
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.extend_from_slice(b"FRM\0torn");
+        std::fs::write(&path, bytes).unwrap();
+
+        let mut resumed = MolecularDynamicsEngine::from_topology(config, &chain()).unwrap();
+        resumed.resume_from_checkpoint(&checkpoint).unwrap();
+        resumed.run_nlnm_breathing(10).unwrap();
+        drop(resumed);
+        let frames = prism_io::trajectory::read_frames(&path).unwrap();
+        let torn = prism_io::ptb_trajectory::read_ptb_trajectory(&path).unwrap().trailing_bytes;
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&checkpoint);
+
+        assert_eq!(torn, 0);
+        assert_eq!(frames.iter().map(|(step, _)| *step).collect::<Vec<_>>(), vec![5, 10, 15, 20]);
+        assert_eq!(frames[3].1.len(), 4);
+    }
+
+    #[test]
+    fn test_vram_fallback_attempts() {
+        assert_eq!(MolecularDynamicsConfig::default().vram_fallback, VramFallback::Cpu);
+        assert_eq!(VramFallback::Error.frame_slot_attempts(2), vec![2]);
+        assert_eq!(VramFallback::Cpu.frame_slot_attempts(2), vec![2]);
+        assert_eq!(VramFallback::ShrinkTrajectory.frame_slot_attempts(2), vec![2, 1]);
+        assert_eq!(VramFallback::ReduceStride.frame_slot_attempts(2), vec![2, 0]);
+        let policy: VramFallback = serde_json::from_str("\"shrink_trajectory\"").unwrap();
+        assert_eq!(policy, VramFallback::ShrinkTrajectory);
+    }
+}
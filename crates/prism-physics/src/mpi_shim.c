@@ -0,0 +1,72 @@
+/*
+ * MPI shim for prism-physics (`mpi` feature)
+ *
+ * MPI_Comm, MPI_Datatype and MPI_Status differ between MPICH and Open MPI,
+ * so Rust calls these wrappers, which only take ints and pointers, instead
+ * of the MPI API. Compiled with the MPI compiler wrapper by build.rs.
+ */
+#include <stddef.h>
+#include <mpi.h>
+
+int prism_mpi_init(void) {
+    int initialized = 0;
+    int err = MPI_Initialized(&initialized);
+    if (err != MPI_SUCCESS || initialized) {
+        return err;
+    }
+    return MPI_Init(NULL, NULL);
+}
+
+int prism_mpi_finalize(void) {
+    int finalized = 0;
+    int err = MPI_Finalized(&finalized);
+    if (err != MPI_SUCCESS || finalized) {
+        return err;
+    }
+    return MPI_Finalize();
+}
+
+int prism_mpi_rank(int *rank) {
+    return MPI_Comm_rank(MPI_COMM_WORLD, rank);
+}
+
+int prism_mpi_size(int *size) {
+    return MPI_Comm_size(MPI_COMM_WORLD, size);
+}
+
+int prism_mpi_send_f64(const double *data, int count, int dest, int tag) {
+    return MPI_Send(data, count, MPI_DOUBLE, dest, tag, MPI_COMM_WORLD);
+}
+
+/* Fails with MPI_ERR_TRUNCATE unless exactly `count` values arrive */
+int prism_mpi_recv_f64(double *data, int count, int source, int tag) {
+    MPI_Status status;
+    int received = 0;
+    int err = MPI_Recv(data, count, MPI_DOUBLE, source, tag, MPI_COMM_WORLD, &status);
+    if (err != MPI_SUCCESS) {
+        return err;
+    }
+    err = MPI_Get_count(&status, MPI_DOUBLE, &received);
+    if (err != MPI_SUCCESS) {
+        return err;
+    }
+    return received == count ? MPI_SUCCESS : MPI_ERR_TRUNCATE;
+}
+
+void prism_mpi_error_string(int code, char *buffer, int capacity) {
+    char message[MPI_MAX_ERROR_STRING];
+    int length = 0;
+    if (capacity <= 0) {
+        return;
+    }
+    if (MPI_Error_string(code, message, &length) != MPI_SUCCESS) {
+        length = 0;
+    }
+    if (length >= capacity) {
+        length = capacity - 1;
+    }
+    for (int i = 0; i < length; i++) {
+        buffer[i] = message[i];
+    }
+    buffer[length] = '\0';
+}
@@ -0,0 +1,312 @@
+//! # CPU Neighbor List - Cell-List Verlet Pairs
+//! Atoms are binned into cubic cells of edge `cutoff + skin`, so each atom
+//! only scans the 27 surrounding cells and the build is O(N). The list keeps
+//! every non-excluded pair within `cutoff + skin` and is reused until some
+//! atom has moved more than half the skin.
+//! Open boundaries unless a periodic box is set, in which case cells tile
+//! the box in fractional coordinates (at least `cutoff + skin` apart between
+//! opposite cell faces, so triclinic boxes work too), wrap around, and
+//! distances use the minimum image; positions are Float4 stride.
+
+use prism_io::simulation_box::SimulationBox;
+
+/// Upper bound on grid cells, to keep sparse systems from exhausting memory
+const MAX_CELLS: usize = 1 << 22;
+
+/// Half neighbor list (`i < j`) in CSR layout
+#[derive(Debug, Clone)]
+pub struct NeighborList {
+    cutoff: f32,
+    skin: f32,
+    /// `partners[offsets[i]..offsets[i + 1]]` are the partners `j > i` of atom `i`
+    offsets: Vec<u32>,
+    partners: Vec<u32>,
+    /// Positions at the last build (Float4 stride)
+    reference: Vec<f32>,
+    builds: usize,
+    /// Periodic box
+    simulation_box: Option<SimulationBox>,
+}
+
+impl NeighborList {
+    pub fn new(cutoff: f32, skin: f32) -> Self {
+        Self {
+            cutoff,
+            skin: skin.max(0.0),
+            offsets: Vec::new(),
+            partners: Vec::new(),
+            reference: Vec::new(),
+            builds: 0,
+            simulation_box: None,
+        }
+    }
+
+    /// Use periodic boundaries with the given box (`None` = open)
+    pub fn with_box(mut self, simulation_box: Option<SimulationBox>) -> Self {
+        self.simulation_box = simulation_box;
+        self.reference.clear();
+        self
+    }
+
+    pub fn simulation_box(&self) -> Option<&SimulationBox> {
+        self.simulation_box.as_ref()
+    }
+
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff
+    }
+
+    pub fn skin(&self) -> f32 {
+        self.skin
+    }
+
+    /// Number of rebuilds so far
+    pub fn builds(&self) -> usize {
+        self.builds
+    }
+
+    /// Number of stored pairs
+    pub fn num_pairs(&self) -> usize {
+        self.partners.len()
+    }
+
+    /// Iterate stored `(i, j)` pairs with `i < j`
+    pub fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets.windows(2).enumerate().flat_map(move |(i, w)| {
+            self.partners[w[0] as usize..w[1] as usize]
+                .iter()
+                .map(move |&j| (i, j as usize))
+        })
+    }
+
+    /// Partners `j > i` of atom `i`
+    pub fn partners(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let range = match self.offsets.get(i..i + 2) {
+            Some(w) => w[0] as usize..w[1] as usize,
+            None => 0..0,
+        };
+        self.partners[range].iter().map(|&j| j as usize)
+    }
+
+    /// Force a rebuild on the next [`Self::update`]
+    pub fn invalidate(&mut self) {
+        self.reference.clear();
+    }
+
+    /// Rebuild if the atom count changed or any atom moved more than half
+    /// the skin since the last build. Pairs for which `excluded(i, j)` holds
+    /// are left out. Returns whether a rebuild happened.
+    pub fn update<F: Fn(usize, usize) -> bool>(&mut self, positions: &[f32], excluded: F) -> bool {
+        if self.reference.len() == positions.len() {
+            let limit = 0.25 * self.skin * self.skin;
+            let moved = positions
+                .chunks_exact(4)
+                .zip(self.reference.chunks_exact(4))
+                .any(|(p, r)| {
+                    let (dx, dy, dz) = (p[0] - r[0], p[1] - r[1], p[2] - r[2]);
+                    dx * dx + dy * dy + dz * dz > limit
+                });
+            if !moved {
+                return false;
+            }
+        }
+        self.build(positions, excluded);
+        true
+    }
+
+    fn build<F: Fn(usize, usize) -> bool>(&mut self, positions: &[f32], excluded: F) {
+        let n = positions.len() / 4;
+        self.offsets.clear();
+        self.partners.clear();
+        self.offsets.push(0);
+        self.reference.clear();
+        self.reference.extend_from_slice(&positions[..n * 4]);
+        self.builds += 1;
+        if n == 0 {
+            return;
+        }
+
+        let mut lo = [f32::INFINITY; 3];
+        let mut hi = [f32::NEG_INFINITY; 3];
+        for p in positions.chunks_exact(4) {
+            for d in 0..3 {
+                lo[d] = lo[d].min(p[d]);
+                hi[d] = hi[d].max(p[d]);
+            }
+        }
+        let list_cutoff = self.cutoff + self.skin;
+        let periodic = self.simulation_box;
+        let widths = periodic.map(|b| b.perpendicular_widths());
+        let mut cell = [list_cutoff.max(1e-3); 3];
+        let dims = loop {
+            let dims = [0, 1, 2].map(|d| match widths {
+                // Cells at least as wide as the list cutoff, tiling the box exactly
+                Some(widths) => ((widths[d] / cell[d]).floor() as usize).max(1),
+                None => {
+                    let extent = if hi[d].is_finite() && lo[d].is_finite() {
+                        hi[d] - lo[d]
+                    } else {
+                        0.0
+                    };
+                    ((extent / cell[d]).floor() as usize + 1).max(1)
+                }
+            });
+            if dims.iter().product::<usize>() <= MAX_CELLS {
+                break dims;
+            }
+            cell = cell.map(|c| c * 2.0);
+        };
+        let cell_of_atom = |p: &[f32]| match &periodic {
+            Some(b) => {
+                let s = b.fractional([p[0], p[1], p[2]]);
+                [0, 1, 2]
+                    .map(|d| (((s[d] - s[d].floor()) * dims[d] as f32) as usize).min(dims[d] - 1))
+            }
+            None => [0, 1, 2]
+                .map(|d| (((p[d] - lo[d]) / cell[d]).floor().max(0.0) as usize).min(dims[d] - 1)),
+        };
+        // Neighboring cell indices along one axis (wrapped and deduplicated when periodic)
+        let span = |c: usize, d: usize| -> Vec<usize> {
+            let mut cells: Vec<usize> = match periodic {
+                Some(_) => [dims[d] - 1, 0, 1].iter().map(|&o| (c + o) % dims[d]).collect(),
+                None => (c.saturating_sub(1)..=(c + 1).min(dims[d] - 1)).collect(),
+            };
+            cells.sort_unstable();
+            cells.dedup();
+            cells
+        };
+
+        // Counting sort of atoms by cell (stable, so cells list atoms in index order)
+        let cell_of: Vec<[usize; 3]> = positions.chunks_exact(4).map(cell_of_atom).collect();
+        let flat = |c: [usize; 3]| (c[2] * dims[1] + c[1]) * dims[0] + c[0];
+        let num_cells = dims.iter().product::<usize>();
+        let mut start = vec![0u32; num_cells + 1];
+        for &c in &cell_of {
+            start[flat(c) + 1] += 1;
+        }
+        for c in 0..num_cells {
+            start[c + 1] += start[c];
+        }
+        let mut cursor = start.clone();
+        let mut cell_atoms = vec![0u32; n];
+        for (i, &c) in cell_of.iter().enumerate() {
+            let slot = &mut cursor[flat(c)];
+            cell_atoms[*slot as usize] = i as u32;
+            *slot += 1;
+        }
+
+        let cut2 = list_cutoff * list_cutoff;
+        let mut row = Vec::new();
+        for (i, c) in cell_of.iter().enumerate() {
+            let pi = &positions[i * 4..i * 4 + 3];
+            row.clear();
+            let (xs, ys) = (span(c[0], 0), span(c[1], 1));
+            for z in span(c[2], 2) {
+                for &y in &ys {
+                    for &x in &xs {
+                        let cell = flat([x, y, z]);
+                        for &j in &cell_atoms[start[cell] as usize..start[cell + 1] as usize] {
+                            let j = j as usize;
+                            if j <= i {
+                                continue;
+                            }
+                            let pj = &positions[j * 4..j * 4 + 3];
+                            let mut d = [pj[0] - pi[0], pj[1] - pi[1], pj[2] - pi[2]];
+                            if let Some(b) = &periodic {
+                                d = b.minimum_image(d);
+                            }
+                            let [dx, dy, dz] = d;
+                            if dx * dx + dy * dy + dz * dz < cut2 && !excluded(i, j) {
+                                row.push(j as u32);
+                            }
+                        }
+                    }
+                }
+            }
+            row.sort_unstable();
+            self.partners.extend_from_slice(&row);
+            self.offsets.push(self.partners.len() as u32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lattice(n: usize, spacing: f32) -> Vec<f32> {
+        (0..n * n * n)
+            .flat_map(|k| {
+                let (x, y, z) = (k % n, (k / n) % n, k / (n * n));
+                [
+                    x as f32 * spacing,
+                    y as f32 * spacing + 0.1 * x as f32,
+                    z as f32 * spacing,
+                    1.0,
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let pos = lattice(6, 2.3);
+        let mut list = NeighborList::new(5.0, 1.0);
+        assert!(list.update(&pos, |i, j| j == i + 1));
+
+        let n = pos.len() / 4;
+        let mut expected = Vec::new();
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let d2: f32 = (0..3)
+                    .map(|d| (pos[j * 4 + d] - pos[i * 4 + d]).powi(2))
+                    .sum();
+                if d2 < 36.0 && j != i + 1 {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert_eq!(list.pairs().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_triclinic_box_matches_brute_force() {
+        // Rhombic dodecahedron; scattered points, some outside the primary cell
+        let cell = SimulationBox::from_parameters([20.0, 20.0, 20.0, 60.0, 60.0, 90.0]).unwrap();
+        let mut seed = 12345u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1u32 << 24) as f32
+        };
+        let pos: Vec<f32> = (0..300)
+            .flat_map(|_| [next() * 40.0 - 10.0, next() * 30.0, next() * 20.0, 1.0])
+            .collect();
+        let mut list = NeighborList::new(4.5, 1.0).with_box(Some(cell));
+        list.update(&pos, |_, _| false);
+
+        let n = pos.len() / 4;
+        let mut expected = Vec::new();
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let d = cell.minimum_image([0, 1, 2].map(|k| pos[j * 4 + k] - pos[i * 4 + k]));
+                if d.iter().map(|v| v * v).sum::<f32>() < 5.5 * 5.5 {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(list.pairs().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_rebuilds_only_past_half_skin() {
+        let mut pos = lattice(3, 3.0);
+        let mut list = NeighborList::new(4.0, 2.0);
+        list.update(&pos, |_, _| false);
+        pos[0] += 0.9;
+        assert!(!list.update(&pos, |_, _| false));
+        pos[0] += 0.2;
+        assert!(list.update(&pos, |_, _| false));
+        assert_eq!(list.builds(), 2);
+    }
+}
@@ -0,0 +1,526 @@
+//! # OTLP - Trace Export of Engine Spans
+//! The engine brackets its phases with `tracing` spans: `structure_parse`,
+//! `gpu_upload`, `minimization`, `md_run` and one `md_block` per 1000
+//! integration steps. This module installs a subscriber that prints the
+//! events to stderr and ships the closed spans as OTLP/HTTP JSON to a
+//! collector (Jaeger, Tempo, the OpenTelemetry Collector).
+//!
+//! The endpoint and service name follow the OpenTelemetry environment
+//! variables (`OTEL_EXPORTER_OTLP_ENDPOINT`,
+//! `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`, `OTEL_SERVICE_NAME`). A W3C
+//! `TRACEPARENT` set by the orchestrator makes the engine's root spans its
+//! children, so a run shows up inside the orchestrator's trace.
+//!
+//! Spans are handed to an exporter thread through a bounded channel and
+//! posted in batches; when the collector falls behind, spans are dropped
+//! rather than stalling the run. Only plain `http://` endpoints are
+//! supported.
+//!
+//! Requires the `otel` feature.
+
+use prism_core::PrismError;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+/// Collector used when no endpoint is configured
+pub const DEFAULT_ENDPOINT: &str = "http://localhost:4318";
+
+/// Spans queued for the exporter before new ones are dropped
+const QUEUE_CAPACITY: usize = 4096;
+
+/// Events kept per span; later ones are counted but not exported
+const MAX_SPAN_EVENTS: usize = 128;
+
+/// Where and how spans are exported
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    /// Full traces URL, e.g. `http://localhost:4318/v1/traces`
+    pub traces_endpoint: String,
+    pub service_name: String,
+    /// Spans per request
+    pub batch_size: usize,
+    /// Longest time a closed span waits for export
+    pub flush_interval: Duration,
+    /// `(trace id, span id)` of the remote parent of the root spans
+    pub parent: Option<(u128, u64)>,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            traces_endpoint: format!("{}/v1/traces", DEFAULT_ENDPOINT),
+            service_name: "prism-md".to_string(),
+            batch_size: 256,
+            flush_interval: Duration::from_secs(2),
+            parent: None,
+        }
+    }
+}
+
+impl OtlpConfig {
+    /// Configuration from the OpenTelemetry environment variables
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let mut config = Self::default();
+        if let Some(endpoint) = var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT") {
+            config.traces_endpoint = endpoint;
+        } else if let Some(endpoint) = var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            config.traces_endpoint = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+        }
+        if let Some(name) = var("OTEL_SERVICE_NAME") {
+            config.service_name = name;
+        }
+        config.parent = var("TRACEPARENT").and_then(|header| parse_traceparent(&header));
+        config
+    }
+}
+
+/// `(trace id, parent span id)` of a W3C `traceparent` header
+/// (`00-<32 hex>-<16 hex>-<2 hex>`)
+pub fn parse_traceparent(header: &str) -> Option<(u128, u64)> {
+    let mut parts = header.trim().split('-');
+    let (version, trace, span, _flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if version != "00" || trace.len() != 32 || span.len() != 16 || parts.next().is_some() {
+        return None;
+    }
+    let trace = u128::from_str_radix(trace, 16).ok().filter(|&id| id != 0)?;
+    let span = u64::from_str_radix(span, 16).ok().filter(|&id| id != 0)?;
+    Some((trace, span))
+}
+
+/// `(host, port, path)` of a plain HTTP URL
+fn parse_http_url(url: &str) -> Result<(String, u16, String), PrismError> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| PrismError::config(format!("OTLP endpoint {} is not a plain http:// URL", url)))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port.parse().map_err(|_| PrismError::config(format!("Invalid port in OTLP endpoint {}", url)))?;
+            (host, port)
+        }
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(PrismError::config(format!("OTLP endpoint {} has no host", url)));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+fn unix_nanos() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64)
+}
+
+fn nonzero<T: PartialEq + Default>(mut draw: impl FnMut() -> T) -> T {
+    loop {
+        let id = draw();
+        if id != T::default() {
+            return id;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum AttributeValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl AttributeValue {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            // 64-bit integers are strings in the OTLP JSON mapping
+            Self::Int(v) => serde_json::json!({ "intValue": v.to_string() }),
+            Self::Str(v) => serde_json::json!({ "stringValue": v }),
+            Self::Float(v) => serde_json::json!({ "doubleValue": v }),
+            Self::Bool(v) => serde_json::json!({ "boolValue": v }),
+        }
+    }
+}
+
+type FieldList = Vec<(&'static str, AttributeValue)>;
+
+fn attributes_json(attributes: &[(&'static str, AttributeValue)]) -> serde_json::Value {
+    attributes.iter().map(|(key, value)| serde_json::json!({ "key": key, "value": value.to_json() })).collect()
+}
+
+/// Collects the fields of a span or event; `message` is kept apart
+#[derive(Default)]
+struct FieldVisitor {
+    attributes: FieldList,
+    message: Option<String>,
+}
+
+impl FieldVisitor {
+    fn push(&mut self, field: &Field, value: AttributeValue) {
+        match (field.name(), value) {
+            ("message", AttributeValue::Str(message)) => self.message = Some(message),
+            (name, value) => match self.attributes.iter_mut().find(|(key, _)| *key == name) {
+                Some(entry) => entry.1 = value,
+                None => self.attributes.push((name, value)),
+            },
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, AttributeValue::Int(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.push(field, AttributeValue::Int(value)),
+            Err(_) => self.push(field, AttributeValue::Str(value.to_string())),
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, AttributeValue::Float(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, AttributeValue::Bool(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, AttributeValue::Str(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.push(field, AttributeValue::Str(format!("{:?}", value)));
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SpanEvent {
+    time: u64,
+    name: String,
+    attributes: FieldList,
+}
+
+/// A span being recorded, kept in the registry's span extensions
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    name: &'static str,
+    trace_id: u128,
+    span_id: u64,
+    parent_id: Option<u64>,
+    start: u64,
+    end: u64,
+    attributes: FieldList,
+    events: Vec<SpanEvent>,
+    dropped_events: u32,
+}
+
+impl SpanRecord {
+    fn to_json(&self) -> serde_json::Value {
+        let events: Vec<_> = self
+            .events
+            .iter()
+            .map(|event| {
+                serde_json::json!({
+                    "timeUnixNano": event.time.to_string(),
+                    "name": event.name,
+                    "attributes": attributes_json(&event.attributes),
+                })
+            })
+            .collect();
+        let mut span = serde_json::json!({
+            "traceId": format!("{:032x}", self.trace_id),
+            "spanId": format!("{:016x}", self.span_id),
+            "name": self.name,
+            // SPAN_KIND_INTERNAL
+            "kind": 1,
+            "startTimeUnixNano": self.start.to_string(),
+            "endTimeUnixNano": self.end.to_string(),
+            "attributes": attributes_json(&self.attributes),
+            "events": events,
+            "droppedEventsCount": self.dropped_events,
+        });
+        if let Some(parent) = self.parent_id {
+            span["parentSpanId"] = serde_json::json!(format!("{:016x}", parent));
+        }
+        span
+    }
+}
+
+enum Export {
+    Span(Box<SpanRecord>),
+    Shutdown,
+}
+
+/// `tracing` layer recording spans for OTLP export
+pub struct OtlpLayer {
+    sender: SyncSender<Export>,
+    parent: Option<(u128, u64)>,
+}
+
+/// Exporter thread of an [`OtlpLayer`]; dropping it exports the spans
+/// closed so far and stops the thread
+pub struct OtlpGuard {
+    sender: SyncSender<Export>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if self.sender.send(Export::Shutdown).is_ok() {
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl OtlpLayer {
+    /// Layer plus the guard of its exporter thread
+    pub fn new(config: OtlpConfig) -> Result<(Self, OtlpGuard), PrismError> {
+        let endpoint = parse_http_url(&config.traces_endpoint)?;
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let parent = config.parent;
+        let handle = std::thread::Builder::new()
+            .name("otlp-export".to_string())
+            .spawn(move || export_loop(&config, &endpoint, receiver))
+            .map_err(|e| PrismError::Internal(format!("Failed to start OTLP exporter: {}", e)))?;
+        Ok((Self { sender: sender.clone(), parent }, OtlpGuard { sender, handle: Some(handle) }))
+    }
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let parent = span.parent().and_then(|parent| parent.extensions().get::<SpanRecord>().map(|p| (p.trace_id, p.span_id)));
+        let (trace_id, parent_id) = match parent.or(self.parent) {
+            Some((trace_id, parent_id)) => (trace_id, Some(parent_id)),
+            None => (nonzero(rand::random::<u128>), None),
+        };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut().insert(SpanRecord {
+            name: attrs.metadata().name(),
+            trace_id,
+            span_id: nonzero(rand::random::<u64>),
+            parent_id,
+            start: unix_nanos(),
+            end: 0,
+            attributes: visitor.attributes,
+            events: Vec::new(),
+            dropped_events: 0,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        let Some(record) = extensions.get_mut::<SpanRecord>() else { return };
+        let mut visitor = FieldVisitor { attributes: std::mem::take(&mut record.attributes), message: None };
+        values.record(&mut visitor);
+        record.attributes = visitor.attributes;
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else { return };
+        let mut extensions = span.extensions_mut();
+        let Some(record) = extensions.get_mut::<SpanRecord>() else { return };
+        if record.events.len() >= MAX_SPAN_EVENTS {
+            record.dropped_events += 1;
+            return;
+        }
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let mut attributes = visitor.attributes;
+        attributes.push(("level", AttributeValue::Str(event.metadata().level().to_string())));
+        record.events.push(SpanEvent {
+            time: unix_nanos(),
+            name: visitor.message.unwrap_or_else(|| event.metadata().name().to_string()),
+            attributes,
+        });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let Some(mut record) = span.extensions_mut().remove::<SpanRecord>() else { return };
+        record.end = unix_nanos();
+        // Never block the traced thread; a full queue drops the span
+        let _ = self.sender.try_send(Export::Span(Box::new(record)));
+    }
+}
+
+fn export_loop(config: &OtlpConfig, endpoint: &(String, u16, String), receiver: Receiver<Export>) {
+    let mut batch = Vec::with_capacity(config.batch_size);
+    loop {
+        let shutdown = match receiver.recv_timeout(config.flush_interval) {
+            Ok(Export::Span(span)) => {
+                batch.push(*span);
+                if batch.len() < config.batch_size {
+                    continue;
+                }
+                false
+            }
+            Ok(Export::Shutdown) | Err(RecvTimeoutError::Disconnected) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+        };
+        if !batch.is_empty() {
+            let body = request_body(&config.service_name, &batch);
+            if let Err(e) = post(endpoint, &body) {
+                log::warn!("⚠️ OTLP export of {} spans to {} failed: {}", batch.len(), config.traces_endpoint, e);
+            }
+            batch.clear();
+        }
+        if shutdown {
+            break;
+        }
+    }
+}
+
+/// `ExportTraceServiceRequest` in the OTLP JSON encoding
+fn request_body(service_name: &str, spans: &[SpanRecord]) -> Vec<u8> {
+    let spans: Vec<_> = spans.iter().map(SpanRecord::to_json).collect();
+    let request = serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{ "key": "service.name", "value": { "stringValue": service_name } }],
+            },
+            "scopeSpans": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    });
+    request.to_string().into_bytes()
+}
+
+fn post((host, port, path): &(String, u16, String), body: &[u8]) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect((host.as_str(), *port))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(10)))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        path,
+        host,
+        port,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    let mut status = String::new();
+    BufReader::new(stream).read_line(&mut status)?;
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(std::io::Error::other(format!("collector answered {:?}", status.trim()))),
+    }
+}
+
+/// Install a global subscriber that prints events to stderr (filtered by
+/// `RUST_LOG`, default `info`) and exports spans over OTLP. `log` records
+/// are forwarded as events.
+///
+/// # Returns
+/// The exporter guard; keep it alive for the whole run
+pub fn init(config: OtlpConfig) -> Result<OtlpGuard, PrismError> {
+    let (layer, guard) = OtlpLayer::new(config)?;
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(layer)
+        .try_init()
+        .map_err(|e| PrismError::config(format!("Cannot install the OTLP subscriber: {}", e)))?;
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_endpoint_and_traceparent() {
+        assert_eq!(parse_http_url("http://jaeger:4318/v1/traces").unwrap(), ("jaeger".to_string(), 4318, "/v1/traces".to_string()));
+        assert_eq!(parse_http_url("http://collector").unwrap(), ("collector".to_string(), 80, "/".to_string()));
+        assert!(parse_http_url("https://collector:4318/v1/traces").is_err());
+
+        let (trace, span) = parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(trace, 0x4bf92f3577b34da6a3ce929d0e0e4736);
+        assert_eq!(span, 0x00f067aa0ba902b7);
+        assert!(parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none());
+        assert!(parse_traceparent("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7").is_none());
+    }
+
+    #[test]
+    fn test_spans_are_exported_with_parents_and_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let collector = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read until the announced body is complete
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length: usize = head
+                        .lines()
+                        .find_map(|l| l.strip_prefix("Content-Length: "))
+                        .and_then(|v| v.parse().ok())
+                        .unwrap();
+                    if body.len() >= length {
+                        stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+                        return (head.to_string(), body.to_string());
+                    }
+                }
+            }
+        });
+
+        let parent = (0x4bf92f3577b34da6a3ce929d0e0e4736, 0x00f067aa0ba902b7);
+        let config = OtlpConfig { traces_endpoint: format!("http://127.0.0.1:{}/v1/traces", port), parent: Some(parent), ..OtlpConfig::default() };
+        let (layer, guard) = OtlpLayer::new(config).unwrap();
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            let _run = tracing::info_span!("md_run", steps = 2000u64).entered();
+            let block = tracing::info_span!("md_block", first_step = 0u64, last_step = tracing::field::Empty).entered();
+            tracing::info!(atoms = 42, "Parsed PDB structure");
+            block.record("last_step", 999u64);
+        });
+        drop(guard);
+
+        let (head, body) = collector.join().unwrap();
+        assert!(head.starts_with("POST /v1/traces HTTP/1.1"));
+        let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let spans = request["resourceSpans"][0]["scopeSpans"][0]["spans"].as_array().unwrap();
+        let find = |name: &str| spans.iter().find(|s| s["name"] == name).unwrap();
+        let (run, block) = (find("md_run"), find("md_block"));
+
+        // The run hangs off the remote parent, the block off the run
+        assert_eq!(run["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(run["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(block["traceId"], run["traceId"]);
+        assert_eq!(block["parentSpanId"], run["spanId"]);
+        assert!(block["attributes"].as_array().unwrap().contains(&serde_json::json!({ "key": "last_step", "value": { "intValue": "999" } })));
+        let event = &block["events"][0];
+        assert_eq!(event["name"], "Parsed PDB structure");
+        assert_eq!(event["attributes"][0], serde_json::json!({ "key": "atoms", "value": { "intValue": "42" } }));
+        assert_eq!(request["resourceSpans"][0]["resource"]["attributes"][0]["value"]["stringValue"], "prism-md");
+    }
+}
@@ -0,0 +1,687 @@
+//! # PIMC - Path-Integral Monte Carlo on Ring Polymers
+//! Quantum nuclei are represented by `P` replicas (beads) of the system,
+//! each atom's beads joined into a ring by harmonic springs of frequency
+//! `ω_P = P kT / ħ` (primitive Trotter factorisation). Sampling
+//! `exp(-β/P [U_spring + Σ_k V(q_k)])` gives quantum statistics for the
+//! engine's potential; the bead average of each atom (the centroid) is the
+//! classical-like coordinate.
+//!
+//! Units follow the host integrators: Å, amu and kcal/mol, which makes the
+//! time unit the AKMA unit (48.89 fs) since `a = F / m` is applied without
+//! conversion. [`HBAR`] is expressed in those units.
+
+use crate::checkpoint::PimcBeads;
+use prism_core::PrismError;
+use rand::Rng;
+use rand_chacha::ChaCha12Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+/// Reduced Planck constant in kcal/mol × AKMA time (`ħ² / amu` = 0.0964 kcal/mol·Å²)
+pub const HBAR: f64 = 0.310_478_28;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PimcConfig {
+    /// Beads per ring polymer; 1 recovers classical sampling
+    pub num_beads: usize,
+    /// Initial Gaussian displacement per coordinate for Monte Carlo moves (Å)
+    pub step_size: f64,
+    /// Acceptance rate the step-size controller aims for, per move type
+    pub target_acceptance: f64,
+    /// Log-step change per unit acceptance error at each adaptation; 0
+    /// freezes the step sizes from the start
+    pub adaptation_rate: f64,
+    /// Sweeps between step-size adaptations
+    pub adaptation_interval: u64,
+    /// Sweeps at the start of sampling during which step sizes adapt.
+    /// Acceptance-driven adaptation breaks detailed balance, so the step
+    /// sizes are frozen afterwards and estimator samples are only taken
+    /// from the later (production) sweeps.
+    pub equilibration_sweeps: u64,
+    /// Sweeps between quantum energy estimator samples; 0 disables them
+    pub estimator_interval: u64,
+    /// Estimator samples averaged into each block for standard errors
+    pub block_length: usize,
+    /// Hold the centroid at its starting configuration: PIMC replaces bead
+    /// and centroid moves with centroid-preserving pair moves, and RPMD
+    /// projects out the centroid momentum. Samples the quantum fluctuations
+    /// around a fixed classical structure.
+    pub fixed_centroid: bool,
+    /// RPMD centroid thermostat friction (1 / AKMA time); internal modes
+    /// use the critically damped PILE value `2 ω_k`
+    pub centroid_friction: f64,
+}
+
+impl Default for PimcConfig {
+    fn default() -> Self {
+        Self {
+            num_beads: 8,
+            step_size: 0.02,
+            target_acceptance: 0.4,
+            adaptation_rate: 1.0,
+            adaptation_interval: 20,
+            equilibration_sweeps: 1_000,
+            estimator_interval: 10,
+            block_length: 20,
+            fixed_centroid: false,
+            centroid_friction: 0.1,
+        }
+    }
+}
+
+impl PimcConfig {
+    pub fn validate(&self) -> Result<(), PrismError> {
+        if self.num_beads == 0 {
+            return Err(PrismError::validation("pimc.num_beads must be at least 1"));
+        }
+        if !(self.step_size > 0.0 && self.step_size.is_finite()) {
+            return Err(PrismError::validation(format!(
+                "pimc.step_size must be a positive displacement (Å), got {}",
+                self.step_size
+            )));
+        }
+        if !(self.target_acceptance > 0.0 && self.target_acceptance < 1.0) {
+            return Err(PrismError::validation(format!(
+                "pimc.target_acceptance must lie strictly between 0 and 1, got {}",
+                self.target_acceptance
+            )));
+        }
+        if !(self.adaptation_rate >= 0.0 && self.adaptation_rate.is_finite()) {
+            return Err(PrismError::validation(format!(
+                "pimc.adaptation_rate must be non-negative, got {}",
+                self.adaptation_rate
+            )));
+        }
+        if self.block_length == 0 {
+            return Err(PrismError::validation(
+                "pimc.block_length must be at least 1",
+            ));
+        }
+        if !(self.centroid_friction >= 0.0 && self.centroid_friction.is_finite()) {
+            return Err(PrismError::validation(format!(
+                "pimc.centroid_friction must be non-negative, got {}",
+                self.centroid_friction
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Bead coordinates of every atom: `beads[k]` is replica `k` as flat
+/// `[x0, y0, z0, x1, ...]` coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingPolymer {
+    masses: Vec<f64>,
+    beads: Vec<Vec<f64>>,
+}
+
+impl RingPolymer {
+    /// Collapsed polymer with every bead on the Float4-stride `positions`
+    /// (mass in the `w` lane)
+    pub fn from_positions(positions: &[f32], num_beads: usize) -> Self {
+        let masses = positions
+            .chunks_exact(4)
+            .map(|p| (p[3] as f64).max(1e-6))
+            .collect();
+        let bead: Vec<f64> = positions
+            .chunks_exact(4)
+            .flat_map(|p| [p[0] as f64, p[1] as f64, p[2] as f64])
+            .collect();
+        Self {
+            masses,
+            beads: vec![bead; num_beads.max(1)],
+        }
+    }
+
+    /// Rebuild a polymer saved with [`Self::to_checkpoint`], taking the
+    /// masses from the engine's Float4 `positions`
+    pub fn from_checkpoint(saved: &PimcBeads, positions: &[f32]) -> Result<Self, PrismError> {
+        let dimensions = positions.len() / 4 * 3;
+        if saved.dimensions != dimensions
+            || saved.num_replicas == 0
+            || saved.configuration.len() != saved.num_replicas * dimensions
+        {
+            return Err(PrismError::validation(format!(
+                "Checkpoint beads ({} x {}) do not match a {}-coordinate system",
+                saved.num_replicas, saved.dimensions, dimensions
+            )));
+        }
+        let mut polymer = Self::from_positions(positions, saved.num_replicas);
+        for (bead, saved) in polymer
+            .beads
+            .iter_mut()
+            .zip(saved.configuration.chunks_exact(dimensions))
+        {
+            for (q, &s) in bead.iter_mut().zip(saved) {
+                *q = s as f64;
+            }
+        }
+        Ok(polymer)
+    }
+
+    /// Replica-major snapshot for checkpoints
+    pub fn to_checkpoint(&self) -> PimcBeads {
+        PimcBeads {
+            num_replicas: self.num_beads(),
+            dimensions: self.masses.len() * 3,
+            configuration: self.beads.iter().flatten().map(|&q| q as f32).collect(),
+        }
+    }
+
+    pub fn num_beads(&self) -> usize {
+        self.beads.len()
+    }
+
+    pub fn num_atoms(&self) -> usize {
+        self.masses.len()
+    }
+
+    pub fn masses(&self) -> &[f64] {
+        &self.masses
+    }
+
+    pub fn beads(&self) -> &[Vec<f64>] {
+        &self.beads
+    }
+
+    pub fn bead(&self, k: usize) -> &[f64] {
+        &self.beads[k]
+    }
+
+    pub(crate) fn beads_mut(&mut self) -> &mut [Vec<f64>] {
+        &mut self.beads
+    }
+
+    /// Ring-polymer spring frequency `ω_P = P kT / ħ`
+    pub fn spring_frequency(&self, kt: f64) -> f64 {
+        self.num_beads() as f64 * kt / HBAR
+    }
+
+    /// Total spring energy `Σ_k Σ_i ½ m_i ω_P² |q_k,i - q_k+1,i|²`
+    pub fn spring_energy(&self, kt: f64) -> f64 {
+        let p = self.num_beads();
+        (0..p)
+            .map(|k| self.link_energy(&self.beads[k], &self.beads[(k + 1) % p], kt))
+            .sum()
+    }
+
+    /// Spring energy of the link between two bead configurations
+    pub(crate) fn link_energy(&self, a: &[f64], b: &[f64], kt: f64) -> f64 {
+        let omega = self.spring_frequency(kt);
+        let stretch: f64 = a
+            .chunks_exact(3)
+            .zip(b.chunks_exact(3))
+            .zip(&self.masses)
+            .map(|((a, b), m)| m * a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum::<f64>())
+            .sum();
+        0.5 * omega * omega * stretch
+    }
+
+    /// Centroid in the engine's Float4 layout (mass in the `w` lane), as
+    /// written to trajectories
+    pub fn centroid_positions(&self) -> Vec<f32> {
+        self.centroid()
+            .chunks_exact(3)
+            .zip(&self.masses)
+            .flat_map(|(c, &m)| [c[0] as f32, c[1] as f32, c[2] as f32, m as f32])
+            .collect()
+    }
+
+    /// Bead-averaged coordinates
+    pub fn centroid(&self) -> Vec<f64> {
+        let mut c = vec![0.0; self.masses.len() * 3];
+        for bead in &self.beads {
+            c.iter_mut().zip(bead).for_each(|(c, q)| *c += q);
+        }
+        let p = self.num_beads() as f64;
+        c.iter_mut().for_each(|c| *c /= p);
+        c
+    }
+
+    /// Per-atom radius of gyration of the ring, the spatial extent of its
+    /// quantum delocalisation (Å)
+    pub fn gyration_radii(&self) -> Vec<f64> {
+        let centroid = self.centroid();
+        let p = self.num_beads() as f64;
+        (0..self.num_atoms())
+            .map(|i| {
+                let spread: f64 = self
+                    .beads
+                    .iter()
+                    .map(|b| {
+                        (0..3)
+                            .map(|d| (b[i * 3 + d] - centroid[i * 3 + d]).powi(2))
+                            .sum::<f64>()
+                    })
+                    .sum();
+                (spread / p).sqrt()
+            })
+            .collect()
+    }
+}
+
+/// Monte Carlo move types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PimcMove {
+    /// Displace every atom of one bead
+    Bead,
+    /// Translate whole rings rigidly, leaving the springs unchanged
+    Centroid,
+}
+
+impl PimcMove {
+    pub const ALL: [PimcMove; 2] = [PimcMove::Bead, PimcMove::Centroid];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Attempt/accept counters for one move type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveStats {
+    pub attempted: u64,
+    pub accepted: u64,
+}
+
+impl MoveStats {
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.attempted == 0 {
+            0.0
+        } else {
+            self.accepted as f64 / self.attempted as f64
+        }
+    }
+}
+
+/// Step size and acceptance of one move type
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MoveSummary {
+    pub kind: PimcMove,
+    /// Current Gaussian displacement per coordinate (Å)
+    pub step_size: f64,
+    /// Counters over the whole run
+    pub stats: MoveStats,
+}
+
+/// Limits on adapted step sizes (Å)
+const STEP_SIZE_RANGE: (f64, f64) = (1e-4, 1.0);
+
+/// Metropolis sampler for a [`RingPolymer`] under the primitive action.
+/// The potential is `FnMut(coordinates, gradient) -> energy` on one bead.
+/// Every `adaptation_interval` sweeps each move's step size is scaled by
+/// `exp(adaptation_rate (acceptance - target))`, using the acceptance since
+/// the previous adaptation, until `equilibration_sweeps` sweeps have run.
+/// The step sizes are fixed from then on.
+#[derive(Debug, Clone)]
+pub struct PimcSampler {
+    step_sizes: [f64; 2],
+    stats: [MoveStats; 2],
+    window: [MoveStats; 2],
+    target_acceptance: f64,
+    adaptation_rate: f64,
+    adaptation_interval: u64,
+    equilibration_sweeps: u64,
+    fixed_centroid: bool,
+    sweeps: u64,
+    rng: ChaCha12Rng,
+    bead_energies: Vec<f64>,
+    scratch: Vec<f64>,
+}
+
+impl PimcSampler {
+    pub fn new(config: &PimcConfig, rng: ChaCha12Rng) -> Self {
+        Self {
+            step_sizes: [config.step_size; 2],
+            stats: [MoveStats::default(); 2],
+            window: [MoveStats::default(); 2],
+            target_acceptance: config.target_acceptance,
+            adaptation_rate: config.adaptation_rate,
+            adaptation_interval: config.adaptation_interval.max(1),
+            equilibration_sweeps: config.equilibration_sweeps,
+            fixed_centroid: config.fixed_centroid,
+            sweeps: 0,
+            rng,
+            bead_energies: Vec::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    pub fn step_size(&self, kind: PimcMove) -> f64 {
+        self.step_sizes[kind.index()]
+    }
+
+    pub fn stats(&self, kind: PimcMove) -> MoveStats {
+        self.stats[kind.index()]
+    }
+
+    /// Per-move step sizes and acceptance
+    pub fn summary(&self) -> Vec<MoveSummary> {
+        PimcMove::ALL
+            .iter()
+            .map(|&kind| MoveSummary {
+                kind,
+                step_size: self.step_size(kind),
+                stats: self.stats(kind),
+            })
+            .collect()
+    }
+
+    /// Acceptance over every move attempted so far
+    pub fn overall_acceptance(&self) -> f64 {
+        let total = self
+            .stats
+            .iter()
+            .fold(MoveStats::default(), |a, s| MoveStats {
+                attempted: a.attempted + s.attempted,
+                accepted: a.accepted + s.accepted,
+            });
+        total.acceptance_rate()
+    }
+
+    /// Potential energy of each bead after the last sweep
+    pub fn bead_energies(&self) -> &[f64] {
+        &self.bead_energies
+    }
+
+    /// Bead-averaged potential energy after the last sweep
+    pub fn mean_potential(&self) -> f64 {
+        self.bead_energies.iter().sum::<f64>() / self.bead_energies.len().max(1) as f64
+    }
+
+    /// One sweep: a bead move on every bead, then one centroid move. With a
+    /// fixed centroid each bead instead moves together with its successor
+    /// by equal and opposite displacements.
+    pub fn sweep<F>(&mut self, polymer: &mut RingPolymer, kt: f64, potential: &mut F)
+    where
+        F: FnMut(&[f64], &mut [f64]) -> f64,
+    {
+        let p = polymer.num_beads();
+        self.scratch.resize(polymer.num_atoms() * 3, 0.0);
+        if self.bead_energies.len() != p {
+            self.bead_energies = polymer
+                .beads
+                .iter()
+                .map(|b| potential(b, &mut self.scratch))
+                .collect();
+        }
+        let beta_p = 1.0 / (p as f64 * kt);
+
+        if self.fixed_centroid {
+            if p > 1 {
+                for k in 0..p {
+                    self.pair_move(polymer, k, kt, potential);
+                }
+            }
+            self.finish_sweep();
+            return;
+        }
+
+        for k in 0..p {
+            let step = self.step_sizes[PimcMove::Bead.index()];
+            let trial: Vec<f64> = polymer.beads[k]
+                .iter()
+                .map(|&q| q + step * self.rng.sample::<f64, _>(StandardNormal))
+                .collect();
+            let (prev, next) = (&polymer.beads[(k + p - 1) % p], &polymer.beads[(k + 1) % p]);
+            let old_springs = polymer.link_energy(prev, &polymer.beads[k], kt)
+                + polymer.link_energy(&polymer.beads[k], next, kt);
+            let new_springs =
+                polymer.link_energy(prev, &trial, kt) + polymer.link_energy(&trial, next, kt);
+            let energy = potential(&trial, &mut self.scratch);
+            let delta = new_springs - old_springs + energy - self.bead_energies[k];
+            if self.accept(PimcMove::Bead, beta_p * delta) {
+                polymer.beads[k] = trial;
+                self.bead_energies[k] = energy;
+            }
+        }
+
+        let step = self.step_sizes[PimcMove::Centroid.index()];
+        let shift: Vec<f64> = (0..polymer.num_atoms() * 3)
+            .map(|_| step * self.rng.sample::<f64, _>(StandardNormal))
+            .collect();
+        let trial: Vec<Vec<f64>> = polymer
+            .beads
+            .iter()
+            .map(|b| b.iter().zip(&shift).map(|(q, s)| q + s).collect())
+            .collect();
+        let energies: Vec<f64> = trial
+            .iter()
+            .map(|b| potential(b, &mut self.scratch))
+            .collect();
+        let delta = energies.iter().sum::<f64>() - self.bead_energies.iter().sum::<f64>();
+        if self.accept(PimcMove::Centroid, beta_p * delta) {
+            polymer.beads = trial;
+            self.bead_energies = energies;
+        }
+
+        self.finish_sweep();
+    }
+
+    /// Displace bead `k` by `δ` and bead `k + 1` by `-δ`, keeping the
+    /// centroid fixed
+    fn pair_move<F>(&mut self, polymer: &mut RingPolymer, k: usize, kt: f64, potential: &mut F)
+    where
+        F: FnMut(&[f64], &mut [f64]) -> f64,
+    {
+        let p = polymer.num_beads();
+        let next = (k + 1) % p;
+        let step = self.step_sizes[PimcMove::Bead.index()];
+        let shift: Vec<f64> = (0..polymer.num_atoms() * 3)
+            .map(|_| step * self.rng.sample::<f64, _>(StandardNormal))
+            .collect();
+        let a: Vec<f64> = polymer.beads[k]
+            .iter()
+            .zip(&shift)
+            .map(|(q, s)| q + s)
+            .collect();
+        let b: Vec<f64> = polymer.beads[next]
+            .iter()
+            .zip(&shift)
+            .map(|(q, s)| q - s)
+            .collect();
+
+        // Links j -> j + 1 touching either bead
+        let mut links = vec![(k + p - 1) % p, k, next];
+        links.sort_unstable();
+        links.dedup();
+        let bead = |j: usize, trial: bool| match j {
+            _ if trial && j == k => &a,
+            _ if trial && j == next => &b,
+            _ => &polymer.beads[j],
+        };
+        let springs = |trial: bool| -> f64 {
+            links
+                .iter()
+                .map(|&j| polymer.link_energy(bead(j, trial), bead((j + 1) % p, trial), kt))
+                .sum()
+        };
+        let energies = [
+            potential(&a, &mut self.scratch),
+            potential(&b, &mut self.scratch),
+        ];
+        let delta = springs(true) - springs(false) + energies[0] + energies[1]
+            - self.bead_energies[k]
+            - self.bead_energies[next];
+        if self.accept(PimcMove::Bead, delta / (p as f64 * kt)) {
+            polymer.beads[k] = a;
+            polymer.beads[next] = b;
+            self.bead_energies[k] = energies[0];
+            self.bead_energies[next] = energies[1];
+        }
+    }
+
+    fn finish_sweep(&mut self) {
+        self.sweeps += 1;
+        if !self.is_equilibrated() && self.sweeps.is_multiple_of(self.adaptation_interval) {
+            self.adapt();
+        }
+    }
+
+    /// Whether the equilibration sweeps are over and the step sizes frozen
+    pub fn is_equilibrated(&self) -> bool {
+        self.sweeps > self.equilibration_sweeps
+    }
+
+    /// Nudge each step size toward the target acceptance
+    fn adapt(&mut self) {
+        for (step, window) in self.step_sizes.iter_mut().zip(&mut self.window) {
+            if self.adaptation_rate > 0.0 && window.attempted > 0 {
+                let error = window.acceptance_rate() - self.target_acceptance;
+                *step = (*step * (self.adaptation_rate * error).exp())
+                    .clamp(STEP_SIZE_RANGE.0, STEP_SIZE_RANGE.1);
+            }
+            *window = MoveStats::default();
+        }
+    }
+
+    /// Metropolis test on the reduced action change
+    fn accept(&mut self, kind: PimcMove, delta_action: f64) -> bool {
+        let accepted = delta_action <= 0.0 || self.rng.gen::<f64>() < (-delta_action).exp();
+        for stats in [
+            &mut self.stats[kind.index()],
+            &mut self.window[kind.index()],
+        ] {
+            stats.attempted += 1;
+            stats.accepted += accepted as u64;
+        }
+        accepted
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    pub(crate) const KT: f64 = 0.6;
+    /// ħω = 2 kT for a 1 amu particle
+    pub(crate) const SPRING_K: f64 = (2.0 * KT / HBAR) * (2.0 * KT / HBAR);
+
+    pub(crate) fn harmonic(x: &[f64], gradient: &mut [f64]) -> f64 {
+        gradient
+            .iter_mut()
+            .zip(x)
+            .for_each(|(g, x)| *g = SPRING_K * x);
+        0.5 * SPRING_K * x.iter().map(|x| x * x).sum::<f64>()
+    }
+
+    /// Exact quantum `<V>` of the 3D oscillator, `3 ħω/4 coth(βħω/2)`
+    pub(crate) fn quantum_potential() -> f64 {
+        let x: f64 = 1.0;
+        3.0 * (2.0 * KT) / 4.0 * (x.cosh() / x.sinh())
+    }
+
+    pub(crate) fn particle(num_beads: usize) -> RingPolymer {
+        RingPolymer::from_positions(&[0.0, 0.0, 0.0, 1.0], num_beads)
+    }
+
+    #[test]
+    fn test_pimc_recovers_quantum_oscillator() {
+        let mut polymer = particle(8);
+        let config = PimcConfig {
+            num_beads: 8,
+            step_size: 0.1,
+            adaptation_rate: 0.0,
+            ..Default::default()
+        };
+        let mut sampler = PimcSampler::new(&config, ChaCha12Rng::seed_from_u64(7));
+        let mut potential = harmonic;
+        for _ in 0..500 {
+            sampler.sweep(&mut polymer, KT, &mut potential);
+        }
+        let mut sum = 0.0;
+        let sweeps = 8_000;
+        for _ in 0..sweeps {
+            sampler.sweep(&mut polymer, KT, &mut potential);
+            sum += sampler.mean_potential();
+        }
+        let mean = sum / sweeps as f64;
+        let classical = 1.5 * KT;
+        assert!(
+            (mean - quantum_potential()).abs() < 0.06 * quantum_potential(),
+            "<V> = {} (quantum {}, classical {})",
+            mean,
+            quantum_potential(),
+            classical
+        );
+        assert!(sampler.stats(PimcMove::Bead).acceptance_rate() > 0.1);
+        assert_eq!(sampler.stats(PimcMove::Centroid).attempted, 8_500);
+    }
+
+    #[test]
+    fn test_step_sizes_adapt_to_target_acceptance() {
+        let mut polymer = particle(8);
+        let config = PimcConfig {
+            num_beads: 8,
+            step_size: 0.8,
+            target_acceptance: 0.5,
+            adaptation_interval: 10,
+            equilibration_sweeps: 1_000,
+            ..Default::default()
+        };
+        let mut sampler = PimcSampler::new(&config, ChaCha12Rng::seed_from_u64(11));
+        let mut potential = harmonic;
+        for _ in 0..1_000 {
+            sampler.sweep(&mut polymer, KT, &mut potential);
+        }
+        assert!(!sampler.is_equilibrated());
+        // Production sweeps run with the adapted steps frozen
+        let before = sampler.summary();
+        for _ in 0..2_000 {
+            sampler.sweep(&mut polymer, KT, &mut potential);
+        }
+        assert!(sampler.is_equilibrated());
+        for (start, end) in before.iter().zip(sampler.summary()) {
+            assert!(start.step_size < 0.8);
+            assert_eq!(start.step_size, end.step_size);
+            let attempted = end.stats.attempted - start.stats.attempted;
+            let accepted = end.stats.accepted - start.stats.accepted;
+            let rate = accepted as f64 / attempted as f64;
+            assert!((rate - 0.5).abs() < 0.1, "{:?}: {}", end.kind, rate);
+        }
+    }
+
+    #[test]
+    fn test_fixed_centroid_sampling() {
+        let start = [0.3, -0.2, 0.1, 1.0];
+        let mut polymer = RingPolymer::from_positions(&start, 4);
+        let config = PimcConfig {
+            num_beads: 4,
+            fixed_centroid: true,
+            ..Default::default()
+        };
+        let mut sampler = PimcSampler::new(&config, ChaCha12Rng::seed_from_u64(3));
+        for _ in 0..200 {
+            sampler.sweep(&mut polymer, KT, &mut harmonic);
+        }
+        let centroid = polymer.centroid();
+        for (c, s) in centroid.iter().zip(&start) {
+            assert!((c - *s as f64).abs() < 1e-9);
+        }
+        assert!(polymer.gyration_radii()[0] > 0.0);
+        assert_eq!(sampler.stats(PimcMove::Centroid).attempted, 0);
+        assert!(sampler.stats(PimcMove::Bead).accepted > 0);
+        assert_eq!(polymer.centroid_positions()[3], 1.0);
+    }
+
+    #[test]
+    fn test_checkpoint_roundtrip_and_centroid() {
+        let positions = [0.0, 0.0, 0.0, 1.0, 2.0, 0.0, 0.0, 16.0];
+        let mut polymer = RingPolymer::from_positions(&positions, 4);
+        polymer.beads_mut()[1][0] = 0.4;
+        polymer.beads_mut()[3][0] = -0.4;
+        assert_eq!(polymer.centroid(), vec![0.0, 0.0, 0.0, 2.0, 0.0, 0.0]);
+        assert!((polymer.gyration_radii()[0] - (0.08f64).sqrt()).abs() < 1e-12);
+        // Each displaced bead stretches two links by 0.4 Å
+        let omega = polymer.spring_frequency(KT);
+        let expected = 0.5 * omega * omega * 4.0 * 0.16;
+        assert!((polymer.spring_energy(KT) - expected).abs() < 1e-9 * expected);
+
+        let restored = RingPolymer::from_checkpoint(&polymer.to_checkpoint(), &positions).unwrap();
+        assert!((restored.bead(1)[0] - 0.4).abs() < 1e-6);
+        assert_eq!(restored.masses(), polymer.masses());
+        assert!(RingPolymer::from_checkpoint(&polymer.to_checkpoint(), &positions[..4]).is_err());
+    }
+}
@@ -0,0 +1,687 @@
+//! # Phase Pipelines - Chained Simulation Stages
+//! A [`Pipeline`] takes a structure through named phases in order,
+//! typically load → minimize → equilibrate → production → analyze. Each
+//! phase declares the [`Artifact`]s it needs and the ones it produces, so
+//! [`PipelineBuilder::build`] rejects chains such as production on an
+//! unequilibrated system before anything runs. With a checkpoint directory
+//! the engine state is saved after every phase that changes it, and a
+//! resumed pipeline skips the phases whose checkpoint is on disk.
+//!
+//! Phase results are recorded in the shared [`PhaseContext`] under
+//! `pipeline.<phase>` and combined into one [`PhaseOutcome`] whose
+//! telemetry keys are prefixed with the phase name. The pipeline stops at
+//! the first phase that does not succeed and reports its outcome.
+
+use crate::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsEngine};
+use crate::units::Temperature;
+use prism_core::{PhaseContext, PhaseOutcome, PrismError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// What a phase leaves behind for the phases after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Artifact {
+    /// Positions and topology loaded into an engine
+    Structure,
+    /// Energy-minimized positions
+    Minimized,
+    /// Velocities thermalized at the target temperature
+    Equilibrated,
+    /// Production sampling (trajectory frames, on-the-fly analyses)
+    Production,
+    /// Analysis results
+    Analysis,
+}
+
+/// One stage of a [`Pipeline`]
+pub trait PipelinePhase: Send {
+    /// Unique name within the pipeline; used for checkpoint files and
+    /// telemetry keys
+    fn name(&self) -> &str;
+
+    /// Artifacts an earlier phase must have produced
+    fn inputs(&self) -> Vec<Artifact>;
+
+    fn outputs(&self) -> Vec<Artifact>;
+
+    /// Whether the engine state is checkpointed after this phase
+    fn checkpoints(&self) -> bool {
+        true
+    }
+
+    fn execute(
+        &mut self,
+        engine: &mut MolecularDynamicsEngine,
+        context: &mut PhaseContext,
+    ) -> Result<PhaseOutcome, PrismError>;
+
+    /// Re-apply the engine settings of this phase when a resumed pipeline
+    /// skips it (checkpoints hold the dynamic state only)
+    fn restore(&mut self, _engine: &mut MolecularDynamicsEngine) -> Result<(), PrismError> {
+        Ok(())
+    }
+}
+
+/// Relax the structure with the configured minimizer
+#[derive(Debug, Clone, Default)]
+pub struct Minimize;
+
+impl PipelinePhase for Minimize {
+    fn name(&self) -> &str {
+        "minimize"
+    }
+
+    fn inputs(&self) -> Vec<Artifact> {
+        vec![Artifact::Structure]
+    }
+
+    fn outputs(&self) -> Vec<Artifact> {
+        vec![Artifact::Minimized]
+    }
+
+    fn execute(
+        &mut self,
+        engine: &mut MolecularDynamicsEngine,
+        _context: &mut PhaseContext,
+    ) -> Result<PhaseOutcome, PrismError> {
+        engine.minimize()
+    }
+}
+
+/// Draw velocities at `temperature` and run `steps` steps holding it
+#[derive(Debug, Clone)]
+pub struct Equilibrate {
+    pub steps: u64,
+    pub temperature: Temperature,
+}
+
+impl PipelinePhase for Equilibrate {
+    fn name(&self) -> &str {
+        "equilibrate"
+    }
+
+    fn inputs(&self) -> Vec<Artifact> {
+        vec![Artifact::Structure]
+    }
+
+    fn outputs(&self) -> Vec<Artifact> {
+        vec![Artifact::Equilibrated]
+    }
+
+    fn execute(
+        &mut self,
+        engine: &mut MolecularDynamicsEngine,
+        _context: &mut PhaseContext,
+    ) -> Result<PhaseOutcome, PrismError> {
+        let kt = self.temperature.kt().as_kcal_per_mol() as f32;
+        engine.set_temperature(kt);
+        engine.assign_velocities(kt);
+        engine.run_nlnm_breathing(self.steps)
+    }
+
+    fn restore(&mut self, engine: &mut MolecularDynamicsEngine) -> Result<(), PrismError> {
+        engine.set_temperature(self.temperature.kt().as_kcal_per_mol() as f32);
+        Ok(())
+    }
+}
+
+/// Run `steps` steps from the equilibrated state with the engine's
+/// trajectory and analyses
+#[derive(Debug, Clone)]
+pub struct Production {
+    pub steps: u64,
+}
+
+impl PipelinePhase for Production {
+    fn name(&self) -> &str {
+        "production"
+    }
+
+    fn inputs(&self) -> Vec<Artifact> {
+        vec![Artifact::Equilibrated]
+    }
+
+    fn outputs(&self) -> Vec<Artifact> {
+        vec![Artifact::Production]
+    }
+
+    fn execute(
+        &mut self,
+        engine: &mut MolecularDynamicsEngine,
+        context: &mut PhaseContext,
+    ) -> Result<PhaseOutcome, PrismError> {
+        let outcome = engine.run_nlnm_breathing(self.steps)?;
+        let stats = engine.get_statistics();
+        let temperature = Temperature::from_kt(crate::units::Energy::kcal_per_mol(
+            stats.current_temperature as f64,
+        ));
+        context.update_md_state(self.steps as usize, 0.0, temperature.as_kelvin());
+        Ok(outcome)
+    }
+}
+
+/// Collect the results of the analyses attached to the engine
+#[derive(Debug, Clone, Default)]
+pub struct Analyze;
+
+impl PipelinePhase for Analyze {
+    fn name(&self) -> &str {
+        "analyze"
+    }
+
+    fn inputs(&self) -> Vec<Artifact> {
+        vec![Artifact::Production]
+    }
+
+    fn outputs(&self) -> Vec<Artifact> {
+        vec![Artifact::Analysis]
+    }
+
+    fn checkpoints(&self) -> bool {
+        false
+    }
+
+    fn execute(
+        &mut self,
+        engine: &mut MolecularDynamicsEngine,
+        _context: &mut PhaseContext,
+    ) -> Result<PhaseOutcome, PrismError> {
+        let analyses = engine.take_analyses();
+        let mut telemetry = HashMap::new();
+        for analysis in &analyses {
+            telemetry.extend(analysis.telemetry());
+        }
+        telemetry.insert(
+            "analyses".to_string(),
+            json!(analyses
+                .iter()
+                .map(|a| a.name().to_string())
+                .collect::<Vec<_>>()),
+        );
+        for analysis in analyses {
+            engine.add_analysis(analysis);
+        }
+        Ok(PhaseOutcome::Success {
+            message: "Analyses collected".to_string(),
+            telemetry,
+        })
+    }
+}
+
+/// Result of one phase
+#[derive(Debug, Clone)]
+pub struct PhaseReport {
+    pub name: String,
+    pub outcome: PhaseOutcome,
+    /// Whether the phase was skipped because its checkpoint was on disk
+    pub resumed: bool,
+    /// Checkpoint written after the phase
+    pub checkpoint: Option<PathBuf>,
+    pub runtime_seconds: f64,
+}
+
+/// Phases run by [`Pipeline::run`] and their combined outcome
+#[derive(Debug, Clone)]
+pub struct PipelineReport {
+    pub phases: Vec<PhaseReport>,
+    pub outcome: PhaseOutcome,
+}
+
+impl PipelineReport {
+    pub fn is_success(&self) -> bool {
+        self.outcome.is_success()
+    }
+
+    /// Report of the phase called `name`
+    pub fn phase(&self, name: &str) -> Option<&PhaseReport> {
+        self.phases.iter().find(|p| p.name == name)
+    }
+}
+
+enum Source {
+    Path(PathBuf, Box<MolecularDynamicsConfig>),
+    Engine(Box<MolecularDynamicsEngine>),
+}
+
+/// Declares the phases of a [`Pipeline`]
+pub struct PipelineBuilder {
+    source: Source,
+    phases: Vec<Box<dyn PipelinePhase>>,
+    checkpoint_dir: Option<PathBuf>,
+    resume: bool,
+}
+
+impl PipelineBuilder {
+    pub fn minimize(self) -> Self {
+        self.phase(Minimize)
+    }
+
+    pub fn equilibrate(self, steps: u64, temperature: Temperature) -> Self {
+        self.phase(Equilibrate { steps, temperature })
+    }
+
+    pub fn production(self, steps: u64) -> Self {
+        self.phase(Production { steps })
+    }
+
+    pub fn analyze(self) -> Self {
+        self.phase(Analyze)
+    }
+
+    /// Append a custom phase
+    pub fn phase(mut self, phase: impl PipelinePhase + 'static) -> Self {
+        self.phases.push(Box::new(phase));
+        self
+    }
+
+    /// Save the engine state to `dir` after every checkpointing phase
+    pub fn checkpoint_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.checkpoint_dir = Some(dir.into());
+        self
+    }
+
+    /// Skip the phases already checkpointed in the checkpoint directory
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Check that names are unique and every phase's inputs are produced
+    /// by the load step or an earlier phase
+    pub fn build(self) -> Result<Pipeline, PrismError> {
+        if self.phases.is_empty() {
+            return Err(PrismError::config("A pipeline needs at least one phase"));
+        }
+        if self.resume && self.checkpoint_dir.is_none() {
+            return Err(PrismError::config(
+                "Resuming a pipeline needs a checkpoint directory",
+            ));
+        }
+        let mut names = HashSet::from(["load".to_string()]);
+        let mut available = HashSet::from([Artifact::Structure]);
+        for phase in &self.phases {
+            if !names.insert(phase.name().to_string()) {
+                return Err(PrismError::config(format!(
+                    "Pipeline phase names must be unique, '{}' appears twice",
+                    phase.name()
+                )));
+            }
+            let missing: Vec<Artifact> = phase
+                .inputs()
+                .into_iter()
+                .filter(|a| !available.contains(a))
+                .collect();
+            if !missing.is_empty() {
+                return Err(PrismError::config(format!(
+                    "Pipeline phase '{}' needs {:?}, which no earlier phase produces",
+                    phase.name(),
+                    missing
+                )));
+            }
+            available.extend(phase.outputs());
+        }
+        Ok(Pipeline {
+            source: Some(self.source),
+            engine: None,
+            phases: self.phases,
+            checkpoint_dir: self.checkpoint_dir,
+            resume: self.resume,
+            context: PhaseContext::new(),
+        })
+    }
+}
+
+/// A validated chain of phases over one engine
+pub struct Pipeline {
+    source: Option<Source>,
+    engine: Option<MolecularDynamicsEngine>,
+    phases: Vec<Box<dyn PipelinePhase>>,
+    checkpoint_dir: Option<PathBuf>,
+    resume: bool,
+    context: PhaseContext,
+}
+
+impl Pipeline {
+    /// Pipeline whose load step reads `path` (`.pdb`, `.cif` or `.ptb`)
+    pub fn load(path: impl Into<PathBuf>, config: MolecularDynamicsConfig) -> PipelineBuilder {
+        Self::builder(Source::Path(path.into(), Box::new(config)))
+    }
+
+    /// Pipeline over an engine that is already built
+    pub fn from_engine(engine: MolecularDynamicsEngine) -> PipelineBuilder {
+        Self::builder(Source::Engine(Box::new(engine)))
+    }
+
+    fn builder(source: Source) -> PipelineBuilder {
+        PipelineBuilder {
+            source,
+            phases: Vec::new(),
+            checkpoint_dir: None,
+            resume: false,
+        }
+    }
+
+    /// Names of the phases after the load step, in run order
+    pub fn phase_names(&self) -> Vec<&str> {
+        self.phases.iter().map(|p| p.name()).collect()
+    }
+
+    /// Context shared by the phases, holding each phase's results under
+    /// `pipeline.<phase>`
+    pub fn context(&self) -> &PhaseContext {
+        &self.context
+    }
+
+    /// Engine after [`Self::run`] (None before the load step)
+    pub fn engine(&self) -> Option<&MolecularDynamicsEngine> {
+        self.engine.as_ref()
+    }
+
+    pub fn into_engine(self) -> Option<MolecularDynamicsEngine> {
+        self.engine
+    }
+
+    fn checkpoint_path(&self, index: usize) -> Option<PathBuf> {
+        let dir = self.checkpoint_dir.as_ref()?;
+        Some(dir.join(format!(
+            "{:02}-{}.ckpt",
+            index + 1,
+            self.phases[index].name()
+        )))
+    }
+
+    /// Load the structure and run every phase. Errors are returned as is;
+    /// phases that end without success stop the pipeline and become its
+    /// outcome.
+    pub fn run(&mut self) -> Result<PipelineReport, PrismError> {
+        let mut phases = vec![self.load_structure()?];
+        if let Some(dir) = &self.checkpoint_dir {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                PrismError::Internal(format!("Failed to create {}: {}", dir.display(), e))
+            })?;
+        }
+        let resume_from = self.resume_point();
+        let mut engine = self
+            .engine
+            .take()
+            .ok_or_else(|| PrismError::internal("Pipeline engine missing"))?;
+        let result = self.run_phases(&mut engine, resume_from, &mut phases);
+        self.engine = Some(engine);
+        result?;
+
+        let outcome = combine(&phases);
+        match &outcome {
+            PhaseOutcome::Success { .. } => log::info!(
+                "🧬 Pipeline complete: {}",
+                phases
+                    .iter()
+                    .map(|p| p.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" → ")
+            ),
+            _ => log::warn!(
+                "⚠️ Pipeline stopped after phase '{}'",
+                phases.last().map_or("load", |p| p.name.as_str())
+            ),
+        }
+        Ok(PipelineReport { phases, outcome })
+    }
+
+    fn load_structure(&mut self) -> Result<PhaseReport, PrismError> {
+        let start = web_time::Instant::now();
+        let message = match self.source.take() {
+            Some(Source::Path(path, config)) => {
+                self.engine = Some(MolecularDynamicsEngine::from_structure_file(*config, &path)?);
+                format!("Loaded {}", path.display())
+            }
+            Some(Source::Engine(engine)) => {
+                self.engine = Some(*engine);
+                "Engine provided".to_string()
+            }
+            None if self.engine.is_some() => "Engine reused".to_string(),
+            None => return Err(PrismError::internal("Pipeline has nothing to load")),
+        };
+        let engine = self.engine.as_mut().expect("engine loaded above");
+        let atoms = engine.get_current_atoms()?.len();
+        log::info!("📂 Pipeline load: {} atoms", atoms);
+        let mut telemetry = HashMap::new();
+        telemetry.insert("atoms".to_string(), json!(atoms));
+        self.context.set_metadata("pipeline.load", json!(telemetry));
+        Ok(PhaseReport {
+            name: "load".to_string(),
+            outcome: PhaseOutcome::Success { message, telemetry },
+            resumed: false,
+            checkpoint: None,
+            runtime_seconds: start.elapsed().as_secs_f64(),
+        })
+    }
+
+    /// Index of the last phase whose checkpoint exists, when resuming
+    fn resume_point(&self) -> Option<usize> {
+        if !self.resume {
+            return None;
+        }
+        (0..self.phases.len())
+            .rev()
+            .find(|&i| self.checkpoint_path(i).is_some_and(|p| p.exists()))
+    }
+
+    fn run_phases(
+        &mut self,
+        engine: &mut MolecularDynamicsEngine,
+        resume_from: Option<usize>,
+        reports: &mut Vec<PhaseReport>,
+    ) -> Result<(), PrismError> {
+        if let Some(last) = resume_from {
+            let path = self
+                .checkpoint_path(last)
+                .expect("resume needs a checkpoint directory");
+            engine.resume_from_checkpoint(&path)?;
+            log::info!(
+                "⏩ Pipeline resumed after phase '{}'",
+                self.phases[last].name()
+            );
+        }
+        let count = self.phases.len();
+        for index in 0..count {
+            let checkpoint = self.checkpoint_path(index);
+            let phase = &mut self.phases[index];
+            let name = phase.name().to_string();
+            if resume_from.is_some_and(|last| index <= last) {
+                phase.restore(engine)?;
+                reports.push(PhaseReport {
+                    name,
+                    outcome: PhaseOutcome::Success {
+                        message: "Restored from checkpoint".to_string(),
+                        telemetry: HashMap::new(),
+                    },
+                    resumed: true,
+                    checkpoint: if phase.checkpoints() {
+                        checkpoint
+                    } else {
+                        None
+                    },
+                    runtime_seconds: 0.0,
+                });
+                continue;
+            }
+
+            log::info!("▶️ Pipeline phase {}/{}: {}", index + 1, count, name);
+            let start = web_time::Instant::now();
+            let outcome = phase.execute(engine, &mut self.context)?;
+            self.context.iteration += 1;
+            let telemetry = match &outcome {
+                PhaseOutcome::Success { telemetry, .. }
+                | PhaseOutcome::Cancelled { telemetry, .. } => json!(telemetry),
+                _ => json!({}),
+            };
+            self.context
+                .set_metadata(&format!("pipeline.{}", name), telemetry);
+
+            let success = outcome.is_success();
+            let checkpoint = match checkpoint {
+                Some(path) if success && phase.checkpoints() => {
+                    engine.save_checkpoint(&path)?;
+                    Some(path)
+                }
+                _ => None,
+            };
+            reports.push(PhaseReport {
+                name,
+                outcome,
+                resumed: false,
+                checkpoint,
+                runtime_seconds: start.elapsed().as_secs_f64(),
+            });
+            if !success {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Success with every phase's telemetry under `<phase>.<key>`, or the
+/// outcome of the phase that stopped the pipeline
+fn combine(phases: &[PhaseReport]) -> PhaseOutcome {
+    let mut telemetry = HashMap::new();
+    for phase in phases {
+        match &phase.outcome {
+            PhaseOutcome::Success {
+                telemetry: entries, ..
+            } => {
+                for (key, value) in entries {
+                    telemetry.insert(format!("{}.{}", phase.name, key), value.clone());
+                }
+            }
+            PhaseOutcome::Cancelled {
+                reason,
+                telemetry: entries,
+            } => {
+                for (key, value) in entries {
+                    telemetry.insert(format!("{}.{}", phase.name, key), value.clone());
+                }
+                return PhaseOutcome::Cancelled {
+                    reason: format!("Phase '{}': {}", phase.name, reason),
+                    telemetry,
+                };
+            }
+            PhaseOutcome::Retry { reason, backoff_ms } => {
+                return PhaseOutcome::Retry {
+                    reason: format!("Phase '{}': {}", phase.name, reason),
+                    backoff_ms: *backoff_ms,
+                }
+            }
+            PhaseOutcome::Escalate { reason } => {
+                return PhaseOutcome::escalate(format!("Phase '{}': {}", phase.name, reason))
+            }
+        }
+    }
+    let names: Vec<&str> = phases.iter().map(|p| p.name.as_str()).collect();
+    PhaseOutcome::Success {
+        message: format!("Pipeline complete: {}", names.join(" → ")),
+        telemetry,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+";
+
+    fn config() -> MolecularDynamicsConfig {
+        MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            shape_analysis: true,
+            analysis_interval: 10,
+            ..Default::default()
+        }
+    }
+
+    fn pipeline(dir: &Path, resume: bool) -> PipelineBuilder {
+        Pipeline::load(dir.join("peptide.pdb"), config())
+            .minimize()
+            .equilibrate(20, Temperature::kelvin(300.0))
+            .production(30)
+            .analyze()
+            .checkpoint_dir(dir.join("checkpoints"))
+            .resume(resume)
+    }
+
+    #[test]
+    fn test_pipeline_runs_checkpoints_and_resumes() {
+        let dir = std::env::temp_dir().join(format!("prism_pipeline_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("peptide.pdb"), PEPTIDE).unwrap();
+
+        let mut first = pipeline(&dir, false).build().unwrap();
+        assert_eq!(
+            first.phase_names(),
+            vec!["minimize", "equilibrate", "production", "analyze"]
+        );
+        let report = first.run().unwrap();
+        assert!(report.is_success(), "{:?}", report.outcome);
+        assert_eq!(report.phases.len(), 5);
+        let PhaseOutcome::Success { telemetry, .. } = &report.outcome else {
+            unreachable!()
+        };
+        assert_eq!(telemetry["load.atoms"], json!(4));
+        assert!(telemetry.contains_key("analyze.analyses"));
+        assert_eq!(first.engine().unwrap().get_statistics().current_step, 50);
+        assert!(first
+            .context()
+            .get_metadata("pipeline.production")
+            .is_some());
+        assert!(report.phase("analyze").unwrap().checkpoint.is_none());
+        let checkpoints = std::fs::read_dir(dir.join("checkpoints")).unwrap().count();
+        assert_eq!(checkpoints, 3);
+
+        // Everything up to production is restored; only analyze runs again
+        let mut resumed = pipeline(&dir, true).build().unwrap();
+        let report = resumed.run().unwrap();
+        assert!(report.is_success());
+        let skipped: Vec<bool> = report.phases.iter().map(|p| p.resumed).collect();
+        assert_eq!(skipped, vec![false, true, true, true, false]);
+        let engine = resumed.engine().unwrap();
+        assert_eq!(engine.get_statistics().current_step, 50);
+        let kt = Temperature::kelvin(300.0).kt().as_kcal_per_mol() as f32;
+        assert!((engine.config().temp_start - kt).abs() < 1e-6);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_build_rejects_missing_inputs_and_duplicate_names() {
+        let production_first = Pipeline::load("unused.pdb", config())
+            .production(10)
+            .build();
+        let error = production_first.err().unwrap().to_string();
+        assert!(
+            error.contains("production") && error.contains("Equilibrated"),
+            "{}",
+            error
+        );
+
+        let twice = Pipeline::load("unused.pdb", config())
+            .minimize()
+            .minimize()
+            .build();
+        assert!(twice.err().unwrap().to_string().contains("unique"));
+        assert!(Pipeline::load("unused.pdb", config()).build().is_err());
+        let resume = Pipeline::load("unused.pdb", config())
+            .minimize()
+            .resume(true)
+            .build();
+        assert!(resume.is_err());
+    }
+}
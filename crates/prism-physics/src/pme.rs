@@ -0,0 +1,593 @@
+//! # Particle Mesh Ewald - Long-Range Electrostatics
+//! Smooth PME (Essmann et al. 1995) for orthorhombic and triclinic boxes.
+//! Coulomb interactions are split into a short-range `erfc(βr)/r` term,
+//! evaluated by the force field within the cutoff, and a smooth long-range
+//! term evaluated on a charge grid with cardinal B-splines and 3D FFTs.
+//! β is chosen so that `erfc(β r_c) = ewald_tolerance`. Charges are spread
+//! in fractional coordinates `s = a*·r`, and wave vectors are the
+//! reciprocal-lattice combinations `m = m₁a* + m₂b* + m₃c*`.
+//! Units: Angstrom, kcal/mol, elementary charge.
+
+use crate::precision::Real;
+use crate::pressure::Virial;
+use prism_io::simulation_box::SimulationBox;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftDirection, FftPlanner};
+use serde::{Deserialize, Serialize};
+use statrs::function::erf::{erf, erfc};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Highest supported B-spline interpolation order
+pub const MAX_SPLINE_ORDER: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PmeConfig {
+    /// Target grid spacing (Å); grid sizes are rounded up to FFT-friendly values
+    #[serde(default = "default_grid_spacing")]
+    pub grid_spacing: f32,
+    /// Relative size of the real-space term at the cutoff, `erfc(β r_c)`
+    #[serde(default = "default_ewald_tolerance")]
+    pub ewald_tolerance: f64,
+    /// B-spline interpolation order (3..=8)
+    #[serde(default = "default_spline_order")]
+    pub spline_order: usize,
+}
+
+fn default_grid_spacing() -> f32 {
+    1.0
+}
+
+fn default_ewald_tolerance() -> f64 {
+    5e-4
+}
+
+fn default_spline_order() -> usize {
+    5
+}
+
+impl Default for PmeConfig {
+    fn default() -> Self {
+        Self {
+            grid_spacing: default_grid_spacing(),
+            ewald_tolerance: default_ewald_tolerance(),
+            spline_order: default_spline_order(),
+        }
+    }
+}
+
+/// Ewald splitting coefficient β (1/Å) with `erfc(β cutoff) = tolerance`
+pub fn ewald_coefficient(cutoff: f64, tolerance: f64) -> f64 {
+    let tolerance = tolerance.clamp(1e-12, 0.5);
+    let (mut lo, mut hi) = (0.0f64, 1.0f64);
+    while erfc(hi * cutoff) > tolerance {
+        hi *= 2.0;
+    }
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if erfc(mid * cutoff) > tolerance {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// Smallest size `>= min` whose only prime factors are 2, 3 and 5
+pub fn fft_size(min: usize) -> usize {
+    (min.max(1)..)
+        .find(|&n| {
+            let mut m = n;
+            for p in [2, 3, 5] {
+                while m.is_multiple_of(p) {
+                    m /= p;
+                }
+            }
+            m == 1
+        })
+        .unwrap_or(min)
+}
+
+/// Values `M_n(w + j)` and derivatives `M_n'(w + j)` for `j = 0..n` of the
+/// cardinal B-spline of order `n`, with `w` in `[0, 1)`.
+pub fn bspline(w: f64, order: usize) -> ([f64; MAX_SPLINE_ORDER], [f64; MAX_SPLINE_ORDER]) {
+    let mut m = [0.0; MAX_SPLINE_ORDER];
+    let mut dm = [0.0; MAX_SPLINE_ORDER];
+    m[0] = w;
+    m[1] = 1.0 - w;
+    for k in 3..=order {
+        if k == order {
+            // M_n'(x) = M_{n-1}(x) - M_{n-1}(x - 1)
+            for j in 0..k {
+                dm[j] = m[j] - if j > 0 { m[j - 1] } else { 0.0 };
+            }
+        }
+        let div = 1.0 / (k - 1) as f64;
+        for j in (0..k).rev() {
+            let x = w + j as f64;
+            let left = if j > 0 { m[j - 1] } else { 0.0 };
+            m[j] = div * (x * m[j] + (k as f64 - x) * left);
+        }
+    }
+    (m, dm)
+}
+
+/// `|b(m)|²` B-spline moduli along one grid dimension
+fn bspline_moduli(size: usize, order: usize) -> Vec<f64> {
+    let (m, _) = bspline(0.0, order);
+    let mut moduli: Vec<f64> = (0..size)
+        .map(|k| {
+            let (mut re, mut im) = (0.0, 0.0);
+            for j in 0..order - 1 {
+                let arg = 2.0 * PI * (k * j) as f64 / size as f64;
+                re += m[j + 1] * arg.cos();
+                im += m[j + 1] * arg.sin();
+            }
+            re * re + im * im
+        })
+        .collect();
+    // Odd orders vanish at the Nyquist frequency: interpolate
+    for k in 0..size {
+        if moduli[k] < 1e-7 {
+            moduli[k] = 0.5 * (moduli[(k + size - 1) % size] + moduli[(k + 1) % size]);
+        }
+    }
+    moduli
+        .iter()
+        .map(|&d| if d > 0.0 { 1.0 / d } else { 0.0 })
+        .collect()
+}
+
+/// Smooth PME solver for a fixed periodic box
+#[derive(Clone)]
+pub struct Pme {
+    beta: f64,
+    order: usize,
+    dims: [usize; 3],
+    simulation_box: SimulationBox,
+    /// Reciprocal box vectors `a*`, `b*`, `c*` as rows (1/Å)
+    recip: [[f64; 3]; 3],
+    coulomb_scale: f64,
+    /// `θ(m)` on the full grid, so that `E = ½ Σ θ |F(Q)|²`
+    influence: Vec<f64>,
+    ffts: [[Arc<dyn Fft<f64>>; 3]; 2],
+}
+
+impl std::fmt::Debug for Pme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pme")
+            .field("beta", &self.beta)
+            .field("order", &self.order)
+            .field("dims", &self.dims)
+            .field("simulation_box", &self.simulation_box)
+            .finish()
+    }
+}
+
+impl Pme {
+    /// Build the solver for `simulation_box`. `coulomb_scale` is the
+    /// Coulomb constant divided by the dielectric.
+    pub fn new(
+        config: &PmeConfig,
+        cutoff: f32,
+        simulation_box: &SimulationBox,
+        coulomb_scale: f64,
+    ) -> Self {
+        let order = config.spline_order.clamp(3, MAX_SPLINE_ORDER);
+        let spacing = config.grid_spacing.max(0.1) as f64;
+        let dims = simulation_box
+            .lengths()
+            .map(|l| fft_size(((l as f64 / spacing).ceil() as usize).max(order)));
+        let beta = ewald_coefficient(cutoff as f64, config.ewald_tolerance);
+        let recip = simulation_box.reciprocal();
+
+        let moduli = dims.map(|k| bspline_moduli(k, order));
+        let volume = simulation_box.volume();
+        let mut influence = vec![0.0; dims[0] * dims[1] * dims[2]];
+        let index = |k: usize, d: usize| {
+            if k <= dims[d] / 2 {
+                k as f64
+            } else {
+                k as f64 - dims[d] as f64
+            }
+        };
+        for x in 0..dims[0] {
+            for y in 0..dims[1] {
+                for z in 0..dims[2] {
+                    let (mx, my, mz) = (index(x, 0), index(y, 1), index(z, 2));
+                    let m2 = (0..3)
+                        .map(|k| (mx * recip[0][k] + my * recip[1][k] + mz * recip[2][k]).powi(2))
+                        .sum::<f64>();
+                    if m2 == 0.0 {
+                        continue;
+                    }
+                    influence[(x * dims[1] + y) * dims[2] + z] =
+                        coulomb_scale * (-PI * PI * m2 / (beta * beta)).exp() / (PI * volume * m2)
+                            * moduli[0][x]
+                            * moduli[1][y]
+                            * moduli[2][z];
+                }
+            }
+        }
+
+        let mut planner = FftPlanner::new();
+        let ffts = [FftDirection::Forward, FftDirection::Inverse]
+            .map(|dir| dims.map(|k| planner.plan_fft(k, dir)));
+
+        Self {
+            beta,
+            order,
+            dims,
+            simulation_box: *simulation_box,
+            recip,
+            coulomb_scale,
+            influence,
+            ffts,
+        }
+    }
+
+    /// Ewald splitting coefficient β (1/Å)
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    /// B-spline order
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Grid dimensions
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+
+    /// Periodic box the grid was built for
+    pub fn simulation_box(&self) -> &SimulationBox {
+        &self.simulation_box
+    }
+
+    /// Reciprocal-space influence function on the full grid (row-major x, y, z)
+    pub fn influence(&self) -> &[f64] {
+        &self.influence
+    }
+
+    /// Short-range energy and `dE/dr` of a pair with charge product `qq` (e²)
+    pub fn real_space(&self, qq: f64, r: f64) -> (f64, f64) {
+        let qq = self.coulomb_scale * qq;
+        let e = qq * erfc(self.beta * r) / r;
+        let gauss = 2.0 * self.beta / PI.sqrt() * (-self.beta * self.beta * r * r).exp();
+        (e, -e / r - qq * gauss / r)
+    }
+
+    /// Correction removing the reciprocal-space interaction of an excluded pair
+    pub fn exclusion_correction(&self, qq: f64, r: f64) -> (f64, f64) {
+        let qq = self.coulomb_scale * qq;
+        if r < 1e-6 {
+            return (-qq * 2.0 * self.beta / PI.sqrt(), 0.0);
+        }
+        let e = -qq * erf(self.beta * r) / r;
+        let gauss = 2.0 * self.beta / PI.sqrt() * (-self.beta * self.beta * r * r).exp();
+        (e, -e / r - qq * gauss / r)
+    }
+
+    /// Self-interaction and neutralizing-background energy
+    pub fn self_energy(&self, charges: &[f32]) -> f64 {
+        let sum2 = charges.iter().map(|&q| (q * q) as f64).sum::<f64>();
+        -self.coulomb_scale * self.beta / PI.sqrt() * sum2 + self.background_energy(charges)
+    }
+
+    /// Energy of the uniform background neutralizing a net charge; it
+    /// scales as `1/V`, so its virial is isotropic and equal to it
+    pub fn background_energy(&self, charges: &[f32]) -> f64 {
+        let sum = charges.iter().map(|&q| q as f64).sum::<f64>();
+        -self.coulomb_scale * PI * sum * sum
+            / (2.0 * self.simulation_box.volume() * self.beta * self.beta)
+    }
+
+    /// Wave vector `m₁a* + m₂b* + m₃c*` of grid point `index` (1/Å)
+    fn wave_vector(&self, index: usize) -> [f64; 3] {
+        let [_, ny, nz] = self.dims;
+        let k = [index / (ny * nz), (index / nz) % ny, index % nz];
+        let m = [0, 1, 2].map(|d| {
+            if k[d] <= self.dims[d] / 2 {
+                k[d] as f64
+            } else {
+                k[d] as f64 - self.dims[d] as f64
+            }
+        });
+        [0, 1, 2]
+            .map(|c| m[0] * self.recip[0][c] + m[1] * self.recip[1][c] + m[2] * self.recip[2][c])
+    }
+
+    /// Per-atom grid indices and spline weights along each dimension
+    fn splines(
+        &self,
+        p: &[f32],
+    ) -> [(
+        [usize; MAX_SPLINE_ORDER],
+        [f64; MAX_SPLINE_ORDER],
+        [f64; MAX_SPLINE_ORDER],
+    ); 3] {
+        [0, 1, 2].map(|d| {
+            let k = self.dims[d];
+            let r = &self.recip[d];
+            let s = r[0] * p[0] as f64 + r[1] * p[1] as f64 + r[2] * p[2] as f64;
+            let u = (s - s.floor()) * k as f64;
+            let base = u.floor();
+            let (m, dm) = bspline(u - base, self.order);
+            let base = base as usize % k;
+            let mut idx = [0usize; MAX_SPLINE_ORDER];
+            for (j, slot) in idx.iter_mut().enumerate().take(self.order) {
+                *slot = (base + k - j) % k;
+            }
+            (idx, m, dm)
+        })
+    }
+
+    fn fft3(&self, grid: &mut [Complex<f64>], inverse: bool) {
+        let [nx, ny, nz] = self.dims;
+        let plans = &self.ffts[inverse as usize];
+        for row in grid.chunks_exact_mut(nz) {
+            plans[2].process(row);
+        }
+        let mut line = vec![Complex::default(); nx.max(ny)];
+        for x in 0..nx {
+            for z in 0..nz {
+                for y in 0..ny {
+                    line[y] = grid[(x * ny + y) * nz + z];
+                }
+                plans[1].process(&mut line[..ny]);
+                for y in 0..ny {
+                    grid[(x * ny + y) * nz + z] = line[y];
+                }
+            }
+        }
+        for y in 0..ny {
+            for z in 0..nz {
+                for x in 0..nx {
+                    line[x] = grid[(x * ny + y) * nz + z];
+                }
+                plans[0].process(&mut line[..nx]);
+                for x in 0..nx {
+                    grid[(x * ny + y) * nz + z] = line[x];
+                }
+            }
+        }
+    }
+
+    /// Reciprocal-space energy for Float4-stride positions, adding forces
+    /// (kcal/mol/Å) when `forces` is given.
+    pub fn reciprocal(
+        &self,
+        positions: &[f32],
+        charges: &[f32],
+        forces: Option<&mut [f32]>,
+    ) -> f64 {
+        self.reciprocal_with_virial(positions, charges, forces, None)
+    }
+
+    /// Like [`Self::reciprocal`], also adding the reciprocal-space virial
+    /// `Σ_m E(m) [δ_ab - 2 (1 + π²m²/β²) m_a m_b / m²]` when `virial` is given.
+    /// Forces are accumulated in the scalar type of `forces`.
+    pub fn reciprocal_with_virial<R: Real>(
+        &self,
+        positions: &[f32],
+        charges: &[f32],
+        forces: Option<&mut [R]>,
+        mut virial: Option<&mut Virial>,
+    ) -> f64 {
+        let [_, ny, nz] = self.dims;
+        let n = charges.len().min(positions.len() / 4);
+        let order = self.order;
+        let splines: Vec<_> = (0..n)
+            .map(|i| self.splines(&positions[i * 4..i * 4 + 3]))
+            .collect();
+
+        let mut grid = vec![Complex::new(0.0, 0.0); self.influence.len()];
+        for (i, [(ix, mx, _), (iy, my, _), (iz, mz, _)]) in splines.iter().enumerate() {
+            let q = charges[i] as f64;
+            if q == 0.0 {
+                continue;
+            }
+            for a in 0..order {
+                for b in 0..order {
+                    let w = q * mx[a] * my[b];
+                    let row = (ix[a] * ny + iy[b]) * nz;
+                    for c in 0..order {
+                        grid[row + iz[c]].re += w * mz[c];
+                    }
+                }
+            }
+        }
+
+        self.fft3(&mut grid, false);
+        let mut energy = 0.0;
+        for (index, (g, &theta)) in grid.iter_mut().zip(&self.influence).enumerate() {
+            let e = 0.5 * theta * g.norm_sqr();
+            energy += e;
+            if let (Some(virial), true) = (virial.as_deref_mut(), e != 0.0) {
+                let m = self.wave_vector(index);
+                let m2 = m[0] * m[0] + m[1] * m[1] + m[2] * m[2];
+                let scale = 2.0 * (1.0 + PI * PI * m2 / (self.beta * self.beta)) / m2;
+                for (a, row) in virial.0.iter_mut().enumerate() {
+                    for (b, w) in row.iter_mut().enumerate() {
+                        *w += e * ((a == b) as u8 as f64 - scale * m[a] * m[b]);
+                    }
+                }
+            }
+            *g *= theta;
+        }
+
+        if let Some(forces) = forces {
+            self.fft3(&mut grid, true);
+            for (i, [(ix, mx, dx), (iy, my, dy), (iz, mz, dz)]) in splines.iter().enumerate() {
+                let q = charges[i] as f64;
+                if q == 0.0 {
+                    continue;
+                }
+                let mut g = [0.0f64; 3];
+                for a in 0..order {
+                    for b in 0..order {
+                        let row = (ix[a] * ny + iy[b]) * nz;
+                        for c in 0..order {
+                            let phi = grid[row + iz[c]].re;
+                            g[0] += phi * dx[a] * my[b] * mz[c];
+                            g[1] += phi * mx[a] * dy[b] * mz[c];
+                            g[2] += phi * mx[a] * my[b] * dz[c];
+                        }
+                    }
+                }
+                // dE/dr = Σ_d (dE/du_d) K_d a*_d
+                let g = [0, 1, 2].map(|d| g[d] * self.dims[d] as f64);
+                for k in 0..3 {
+                    let grad =
+                        g[0] * self.recip[0][k] + g[1] * self.recip[1][k] + g[2] * self.recip[2][k];
+                    forces[i * 4 + k] -= R::from_f64(q * grad);
+                }
+            }
+        }
+        energy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system() -> (Vec<f32>, Vec<f32>) {
+        let pos = vec![
+            2.0, 3.0, 4.0, 1.0, //
+            5.5, 3.2, 4.1, 1.0, //
+            9.0, 12.0, 7.5, 1.0, //
+            14.5, 2.5, 13.0, 1.0,
+        ];
+        (pos, vec![0.8, -0.8, 0.5, -0.5])
+    }
+
+    #[test]
+    fn test_bspline_partition_of_unity() {
+        for order in 3..=MAX_SPLINE_ORDER {
+            let (m, dm) = bspline(0.37, order);
+            assert!((m[..order].iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            assert!(dm[..order].iter().sum::<f64>().abs() < 1e-12);
+        }
+        assert_eq!(fft_size(49), 50);
+        assert_eq!(fft_size(97), 100);
+    }
+
+    /// Direct Ewald reciprocal sum over `|m_i| <= 12`
+    fn ewald_sum(pos: &[f32], q: &[f32], beta: f64, cell: &SimulationBox) -> f64 {
+        let recip = cell.reciprocal();
+        let mut expected = 0.0;
+        for mx in -12i32..=12 {
+            for my in -12i32..=12 {
+                for mz in -12i32..=12 {
+                    if mx == 0 && my == 0 && mz == 0 {
+                        continue;
+                    }
+                    let m = [0, 1, 2].map(|k| {
+                        mx as f64 * recip[0][k] + my as f64 * recip[1][k] + mz as f64 * recip[2][k]
+                    });
+                    let m2 = m.iter().map(|v| v * v).sum::<f64>();
+                    let (mut re, mut im) = (0.0, 0.0);
+                    for i in 0..4 {
+                        let arg =
+                            2.0 * PI * (0..3).map(|d| m[d] * pos[i * 4 + d] as f64).sum::<f64>();
+                        re += q[i] as f64 * arg.cos();
+                        im += q[i] as f64 * arg.sin();
+                    }
+                    expected += (-PI * PI * m2 / (beta * beta)).exp() / m2 * (re * re + im * im);
+                }
+            }
+        }
+        expected / (2.0 * PI * cell.volume())
+    }
+
+    #[test]
+    fn test_reciprocal_matches_ewald_sum() {
+        let (pos, q) = system();
+        let config = PmeConfig {
+            grid_spacing: 0.5,
+            spline_order: 6,
+            ..Default::default()
+        };
+        let cells = [
+            SimulationBox::orthorhombic([16.0, 16.0, 16.0]),
+            SimulationBox::from_parameters([16.0, 17.0, 18.0, 75.0, 80.0, 65.0]).unwrap(),
+        ];
+        for cell in &cells {
+            let pme = Pme::new(&config, 8.0, cell, 1.0);
+            let expected = ewald_sum(&pos, &q, pme.beta(), cell);
+            let energy = pme.reciprocal(&pos, &q, None);
+            assert!(
+                (energy - expected).abs() < 1e-4 * expected.abs().max(1.0),
+                "{} vs {}",
+                energy,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_reciprocal_virial_matches_strain_derivative() {
+        let (pos, q) = system();
+        let config = PmeConfig {
+            grid_spacing: 0.6,
+            ..Default::default()
+        };
+        let lengths = [16.5f32, 17.3, 18.1];
+        let energy = |axis: usize, strain: f32| {
+            let mut scaled = lengths;
+            scaled[axis] *= 1.0 + strain;
+            let cell = SimulationBox::orthorhombic(scaled);
+            let mut p = pos.clone();
+            for atom in p.chunks_exact_mut(4) {
+                atom[axis] *= 1.0 + strain;
+            }
+            Pme::new(&config, 7.0, &cell, 332.0).reciprocal(&p, &q, None)
+        };
+        let pme = Pme::new(&config, 7.0, &SimulationBox::orthorhombic(lengths), 332.0);
+        let mut virial = Virial::default();
+        pme.reciprocal_with_virial(&pos, &q, None::<&mut [f32]>, Some(&mut virial));
+
+        let h = 1e-3;
+        for axis in 0..3 {
+            let numeric = -(energy(axis, h) - energy(axis, -h)) / (2.0 * h as f64);
+            assert!(
+                (numeric - virial.0[axis][axis]).abs() < 1e-2 * numeric.abs().max(1.0),
+                "{}: {} vs {}",
+                axis,
+                numeric,
+                virial.0[axis][axis]
+            );
+        }
+        assert!((virial.0[0][1] - virial.0[1][0]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reciprocal_forces_match_finite_difference() {
+        let (mut pos, q) = system();
+        let cell = SimulationBox::from_parameters([16.0, 16.0, 16.0, 90.0, 90.0, 70.0]).unwrap();
+        let pme = Pme::new(&PmeConfig::default(), 7.0, &cell, 332.0);
+        let mut forces = vec![0.0; pos.len()];
+        pme.reciprocal(&pos, &q, Some(&mut forces));
+
+        let h = 1e-3;
+        for k in [0, 5, 10] {
+            pos[k] += h;
+            let e_plus = pme.reciprocal(&pos, &q, None);
+            pos[k] -= 2.0 * h;
+            let e_minus = pme.reciprocal(&pos, &q, None);
+            pos[k] += h;
+            let numeric = -(e_plus - e_minus) / (2.0 * h as f64);
+            assert!(
+                (numeric - forces[k] as f64).abs() < 2e-2,
+                "{}: {} vs {}",
+                k,
+                numeric,
+                forces[k]
+            );
+        }
+    }
+}
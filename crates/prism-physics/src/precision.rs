@@ -0,0 +1,146 @@
+//! # Precision - Scalar Type of the Integration State
+//! The device-facing buffers (positions, velocities, forces) are `f32`. In
+//! [`Precision::Double`] the host integrator keeps an `f64` copy of the
+//! state and accumulates forces in `f64`, so displacements far below the
+//! `f32` resolution of a coordinate are not lost over long runs; the `f32`
+//! buffers become a rounded mirror of it. Energies and virials are always
+//! accumulated in `f64` on the host.
+//!
+//! [`Precision::Mixed`] keeps the `f32` state (and GPU integration) but
+//! switches the force sums to `f64`: the GPU nonbonded kernels evaluate each
+//! pair in `f32` and sum every atom's force, energies and pair virial in
+//! `f64`, and the host sums pair forces in `f64` as in `Double`. This
+//! removes the energy drift of long `f32` runs that comes from the
+//! rounding of large per-atom sums, at a small fraction of the cost of
+//! `Double`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::ops::{AddAssign, SubAssign};
+
+/// Scalar type of the host integration state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    /// `f32` state and force accumulation
+    #[default]
+    Single,
+    /// `f64` state and force accumulation on the host; GPU kernels still
+    /// evaluate forces in `f32` with `f64` sums
+    Double,
+    /// `f32` state, `f32` pair forces with `f64` force, energy and virial
+    /// sums (also on the GPU)
+    Mixed,
+}
+
+
+/// Force accumulator scalar (`f32` or `f64`)
+pub trait Real:
+    Copy + Default + Debug + PartialEq + AddAssign + SubAssign + Send + Sync + 'static
+{
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Real for f32 {
+    #[inline]
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Real for f64 {
+    #[inline]
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// `f64` positions and velocities (Float4 stride, `w` = mass) shadowing the
+/// engine's `f32` buffers in [`Precision::Double`]
+#[derive(Debug, Clone, Default)]
+pub struct DoubleState {
+    pub positions: Vec<f64>,
+    pub velocities: Vec<f64>,
+}
+
+impl DoubleState {
+    pub fn new(positions: &[f32], velocities: &[f32]) -> Self {
+        Self {
+            positions: positions.iter().map(|&x| x as f64).collect(),
+            velocities: velocities.iter().map(|&v| v as f64).collect(),
+        }
+    }
+
+    /// Adopt changes made to the `f32` buffers since the last [`Self::mirror`]
+    /// (checkpoint restore, minimization, constraint corrections, velocity
+    /// rescaling). Components whose `f32` value still equals the rounded
+    /// `f64` value keep their extra precision.
+    pub fn sync(&mut self, positions: &[f32], velocities: &[f32]) {
+        if self.positions.len() != positions.len() || self.velocities.len() != velocities.len() {
+            *self = Self::new(positions, velocities);
+            return;
+        }
+        fold(&mut self.positions, positions);
+        fold(&mut self.velocities, velocities);
+    }
+
+    /// Round the `f64` state into the `f32` buffers
+    pub fn mirror(&self, positions: &mut [f32], velocities: &mut [f32]) {
+        for (x, &wide) in positions.iter_mut().zip(&self.positions) {
+            *x = wide as f32;
+        }
+        for (v, &wide) in velocities.iter_mut().zip(&self.velocities) {
+            *v = wide as f32;
+        }
+    }
+}
+
+fn fold(wide: &mut [f64], narrow: &[f32]) {
+    for (w, &n) in wide.iter_mut().zip(narrow) {
+        let rounded = *w as f32;
+        if rounded != n {
+            *w += n as f64 - rounded as f64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_keeps_sub_ulp_precision() {
+        let mut state = DoubleState::new(&[1.0e4, 0.0, 0.0, 12.0], &[0.0; 4]);
+        state.positions[0] += 1.0e-4;
+        let mut positions = [0.0f32; 4];
+        let mut velocities = [0.0f32; 4];
+        state.mirror(&mut positions, &mut velocities);
+        assert_eq!(positions[0], 1.0e4);
+
+        // Untouched components keep their f64 value, edited ones follow the edit
+        positions[1] = 2.5;
+        state.sync(&positions, &velocities);
+        assert_eq!(state.positions[0], 1.0e4 + 1.0e-4);
+        assert_eq!(state.positions[1], 2.5);
+        assert_eq!(state.positions[3], 12.0);
+    }
+
+    #[test]
+    fn test_precision_names() {
+        for (precision, name) in [(Precision::Single, "single"), (Precision::Double, "double"), (Precision::Mixed, "mixed")] {
+            assert_eq!(serde_json::to_value(precision).unwrap(), name);
+            assert_eq!(serde_json::from_value::<Precision>(name.into()).unwrap(), precision);
+        }
+    }
+}
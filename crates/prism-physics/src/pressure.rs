@@ -0,0 +1,151 @@
+//! # Pressure - Virial Pressure Tensor
+//! Instantaneous pressure of a periodic system,
+//!
+//! ```text
+//! P = (Σ_i m_i v_i ⊗ v_i + W) / V,    W = -∂E/∂ε
+//! ```
+//!
+//! where the virial `W` is accumulated during force evaluation. Pair terms
+//! contribute `r_ij ⊗ f_ij` with the minimum-image separation, so the sum is
+//! independent of where atoms sit relative to the cell; PME adds its
+//! reciprocal-space tensor and the volume dependence of the neutralizing
+//! background. Terms evaluated on unwrapped coordinates (bonded, restraints,
+//! implicit solvent, constraints) use the single sum `Σ_i x_i ⊗ f_i`.
+//! Units: kcal/mol for `W`, Å³ for `V`, bar for the pressure.
+
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign};
+
+/// 1 kcal/mol/Å³ in bar
+pub const KCAL_MOL_A3_TO_BAR: f64 = 69_476.95;
+
+/// Virial tensor `W_ab = Σ r_a f_b` (kcal/mol)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Virial(pub [[f64; 3]; 3]);
+
+impl Virial {
+    /// Pair with separation `d = x_j - x_i` whose force on `i` is
+    /// `-f_over_r * d` (positive `f_over_r` = repulsive)
+    #[inline]
+    pub fn add_pair(&mut self, d: [f32; 3], f_over_r: f64) {
+        let d = d.map(|x| x as f64);
+        for (a, row) in self.0.iter_mut().enumerate() {
+            for (b, w) in row.iter_mut().enumerate() {
+                *w += f_over_r * d[a] * d[b];
+            }
+        }
+    }
+
+    /// Single-sum contribution `Σ x_i ⊗ f_i` of Float4-stride `positions`
+    /// and `forces`, valid for terms that conserve momentum and are
+    /// evaluated without periodic images
+    pub fn add_forces(&mut self, positions: &[f32], forces: &[f32]) {
+        for (x, f) in positions.chunks_exact(4).zip(forces.chunks_exact(4)) {
+            for (a, row) in self.0.iter_mut().enumerate() {
+                for (b, w) in row.iter_mut().enumerate() {
+                    *w += x[a] as f64 * f[b] as f64;
+                }
+            }
+        }
+    }
+
+    /// Term `value · δ_ab`, e.g. of an energy that scales as `1/V`
+    pub fn add_isotropic(&mut self, value: f64) {
+        for (a, row) in self.0.iter_mut().enumerate() {
+            row[a] += value;
+        }
+    }
+
+    pub fn trace(&self) -> f64 {
+        self.0[0][0] + self.0[1][1] + self.0[2][2]
+    }
+}
+
+impl Add for Virial {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Virial {
+    fn add_assign(&mut self, other: Self) {
+        for (row, other) in self.0.iter_mut().zip(other.0) {
+            for (w, o) in row.iter_mut().zip(other) {
+                *w += o;
+            }
+        }
+    }
+}
+
+/// `Σ m v ⊗ v` of Float4-stride positions (`w` = mass) and velocities, in
+/// the units of [`MolecularDynamicsEngine::kinetic_energy`](crate::molecular_dynamics::MolecularDynamicsEngine::kinetic_energy)
+pub fn kinetic_tensor(positions: &[f32], velocities: &[f32]) -> [[f64; 3]; 3] {
+    let mut tensor = [[0.0; 3]; 3];
+    for (p, v) in positions.chunks_exact(4).zip(velocities.chunks_exact(4)) {
+        let m = p[3] as f64;
+        for (a, row) in tensor.iter_mut().enumerate() {
+            for (b, t) in row.iter_mut().enumerate() {
+                *t += m * v[a] as f64 * v[b] as f64;
+            }
+        }
+    }
+    tensor
+}
+
+/// Instantaneous pressure tensor (bar)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PressureTensor {
+    pub tensor: [[f64; 3]; 3],
+}
+
+impl PressureTensor {
+    /// `(K + W) / V` for kinetic tensor `kinetic`, virial `virial` and cell
+    /// volume `volume` (Å³)
+    pub fn new(kinetic: [[f64; 3]; 3], virial: &Virial, volume: f64) -> Self {
+        let mut tensor = [[0.0; 3]; 3];
+        for (a, row) in tensor.iter_mut().enumerate() {
+            for (b, p) in row.iter_mut().enumerate() {
+                *p = (kinetic[a][b] + virial.0[a][b]) / volume * KCAL_MOL_A3_TO_BAR;
+            }
+        }
+        Self { tensor }
+    }
+
+    /// Scalar pressure, a third of the trace (bar)
+    pub fn scalar(&self) -> f64 {
+        (self.tensor[0][0] + self.tensor[1][1] + self.tensor[2][2]) / 3.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ideal_gas_and_pair_virial() {
+        // Kinetic part alone: P V = Σ m v² / 3
+        let positions = [0.0, 0.0, 0.0, 2.0, 5.0, 5.0, 5.0, 2.0];
+        let velocities = [1.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0];
+        let kinetic = kinetic_tensor(&positions, &velocities);
+        assert_eq!(kinetic[0][0], 2.0);
+        assert_eq!(kinetic[1][1], 8.0);
+        let ideal = PressureTensor::new(kinetic, &Virial::default(), 1000.0);
+        assert!((ideal.scalar() - 10.0 / 3.0 / 1000.0 * KCAL_MOL_A3_TO_BAR).abs() < 1e-9);
+
+        // A repulsive pair pushes outward; the single-sum form agrees
+        let d = [3.0f32, 4.0, 0.0];
+        let mut pair = Virial::default();
+        pair.add_pair(d, 0.5);
+        let forces = [-1.5, -2.0, 0.0, 0.0, 1.5, 2.0, 0.0, 0.0];
+        let positions = [1.0, 1.0, 1.0, 1.0, 4.0, 5.0, 1.0, 1.0];
+        let mut single = Virial::default();
+        single.add_forces(&positions, &forces);
+        assert!(pair.trace() > 0.0);
+        for (a, b) in pair.0.iter().flatten().zip(single.0.iter().flatten()) {
+            assert!((a - b).abs() < 1e-9, "{:?} vs {:?}", pair, single);
+        }
+    }
+}
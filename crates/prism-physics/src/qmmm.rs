@@ -0,0 +1,1147 @@
+//! # QM/MM Coupling
+//! [`QmMm`] is a [`Potential`] splitting the system into a quantum region,
+//! chosen by a selection, and the classical rest. The QM energy and
+//! gradient come from an external program behind the [`QmEngine`] trait;
+//! the MM energy and forces come from the force field and bonded terms of
+//! the topology with the QM-QM interactions removed, and the two are summed.
+//!
+//! - Electrostatic embedding (default): the MM partial charges enter the QM
+//!   Hamiltonian as point charges and the QM atoms carry no MM charge, so
+//!   the QM-MM electrostatics, including the forces on the point charges,
+//!   come from the QM program. Mechanical embedding keeps the MM charges of
+//!   the QM atoms and sends no point charges.
+//! - Bonds cut by the region boundary are capped with link hydrogens at
+//!   `q + g (m - q)` (`g` = [`QmMmConfig::link_ratio`]); their gradient is
+//!   shared between the QM and MM atom of the bond. The charge of the MM
+//!   boundary atom is left out of the embedding.
+//! - Bonded terms made only of QM atoms are dropped, as are QM-QM
+//!   nonbonded pairs; terms crossing the boundary stay classical.
+//! - With a periodic box, the QM region is made whole and point charges are
+//!   taken at their minimum image around the QM centroid.
+//!
+//! Engines: [`Xtb`] and [`Orca`] write their program's input files into a
+//! working directory and parse the gradient files it leaves behind;
+//! [`CommandEngine`] runs any program that reads a JSON [`QmRequest`] on
+//! stdin and prints a [`QmResult`]; [`SocketEngine`] exchanges the same
+//! JSON documents, one per line, with a server over TCP. Units at the
+//! interface: Angstrom, kcal/mol.
+
+use crate::bonded::BondedTerms;
+use crate::force_field::{ForceField, ForceFieldConfig};
+use crate::ml_potential::{Potential, HARTREE};
+use crate::neighbor_list::NeighborList;
+use prism_core::PrismError;
+use prism_io::selection::SelectionContext;
+use prism_io::simulation_box::SimulationBox;
+use prism_io::topology::Topology;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Å per Bohr
+pub const BOHR: f64 = 0.529_177_210_903;
+
+/// Embedding of the QM region in the MM charges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Embedding {
+    /// MM charges polarize the QM density as point charges
+    #[default]
+    Electrostatic,
+    /// QM-MM electrostatics from the MM charges of both regions
+    Mechanical,
+}
+
+/// External QM program
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "program", rename_all = "lowercase")]
+pub enum QmEngineConfig {
+    /// Grimme's `xtb`; `arguments` select the method (e.g. `["--gfn", "1"]`)
+    Xtb {
+        #[serde(default = "default_xtb")]
+        executable: PathBuf,
+        #[serde(default)]
+        arguments: Vec<String>,
+    },
+    /// ORCA; `keywords` go on the `!` line next to `EnGrad`
+    Orca {
+        #[serde(default = "default_orca")]
+        executable: PathBuf,
+        #[serde(default = "default_orca_keywords")]
+        keywords: String,
+    },
+    /// Program reading a JSON [`QmRequest`] on stdin and writing a
+    /// [`QmResult`] to stdout, started once per evaluation
+    Command {
+        executable: PathBuf,
+        #[serde(default)]
+        arguments: Vec<String>,
+    },
+    /// Server answering newline-delimited JSON requests on `address`
+    /// (`host:port`) over one persistent connection
+    Socket { address: String },
+}
+
+fn default_xtb() -> PathBuf {
+    PathBuf::from("xtb")
+}
+
+fn default_orca() -> PathBuf {
+    PathBuf::from("orca")
+}
+
+fn default_orca_keywords() -> String {
+    "B3LYP D3BJ def2-SVP".into()
+}
+
+fn default_multiplicity() -> u32 {
+    1
+}
+
+fn default_link_ratio() -> f64 {
+    0.709
+}
+
+impl QmEngineConfig {
+    /// Engine writing its files under `work_dir` (created if missing)
+    pub fn build(&self, work_dir: &Path) -> Result<Box<dyn QmEngine>, PrismError> {
+        let needs_dir = matches!(self, Self::Xtb { .. } | Self::Orca { .. });
+        if needs_dir {
+            fs::create_dir_all(work_dir)?;
+        }
+        Ok(match self {
+            Self::Xtb {
+                executable,
+                arguments,
+            } => Box::new(Xtb {
+                executable: executable.clone(),
+                arguments: arguments.clone(),
+                work_dir: work_dir.to_path_buf(),
+            }),
+            Self::Orca {
+                executable,
+                keywords,
+            } => Box::new(Orca {
+                executable: executable.clone(),
+                keywords: keywords.clone(),
+                work_dir: work_dir.to_path_buf(),
+            }),
+            Self::Command {
+                executable,
+                arguments,
+            } => Box::new(CommandEngine {
+                executable: executable.clone(),
+                arguments: arguments.clone(),
+            }),
+            Self::Socket { address } => Box::new(SocketEngine::new(address.clone())),
+        })
+    }
+}
+
+/// QM/MM setup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QmMmConfig {
+    /// Selection of the QM atoms (see [`prism_io::selection`])
+    pub region: String,
+    /// Total charge of the QM region; `None` rounds the sum of its
+    /// topology charges
+    #[serde(default)]
+    pub charge: Option<i32>,
+    /// Spin multiplicity `2S + 1`
+    #[serde(default = "default_multiplicity")]
+    pub multiplicity: u32,
+    #[serde(default)]
+    pub embedding: Embedding,
+    /// Link hydrogen position as a fraction of the cut bond, from the QM
+    /// atom (C-H over C-C bond length by default)
+    #[serde(default = "default_link_ratio")]
+    pub link_ratio: f64,
+    pub engine: QmEngineConfig,
+    /// Directory for the QM program's files; a fresh temporary directory
+    /// when `None`
+    #[serde(default)]
+    pub work_dir: Option<PathBuf>,
+}
+
+impl QmMmConfig {
+    pub fn new(region: impl Into<String>, engine: QmEngineConfig) -> Self {
+        Self {
+            region: region.into(),
+            charge: None,
+            multiplicity: default_multiplicity(),
+            embedding: Embedding::default(),
+            link_ratio: default_link_ratio(),
+            engine,
+            work_dir: None,
+        }
+    }
+}
+
+/// MM charge seen by the QM region
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PointCharge {
+    /// Charge (e)
+    pub charge: f64,
+    /// Position (Å)
+    pub position: [f64; 3],
+}
+
+/// One QM calculation: QM atoms followed by link hydrogens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QmRequest {
+    /// Atomic numbers
+    pub elements: Vec<u8>,
+    /// Positions (Å)
+    pub positions: Vec<[f64; 3]>,
+    /// Total charge
+    pub charge: i32,
+    /// Spin multiplicity
+    pub multiplicity: u32,
+    /// Embedding charges (empty with mechanical embedding)
+    pub point_charges: Vec<PointCharge>,
+}
+
+/// Result of a [`QmRequest`]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct QmResult {
+    /// Energy including the interaction with the point charges (kcal/mol)
+    pub energy: f64,
+    /// Gradient on each requested atom (kcal/mol/Å)
+    pub gradient: Vec<[f64; 3]>,
+    /// Gradient on each point charge (kcal/mol/Å)
+    #[serde(default)]
+    pub point_charge_gradient: Vec<[f64; 3]>,
+}
+
+/// Energy and gradient provider for the QM region
+pub trait QmEngine: Send + std::fmt::Debug {
+    /// Short name used in logs
+    fn name(&self) -> &str;
+
+    fn calculate(&mut self, request: &QmRequest) -> Result<QmResult, PrismError>;
+}
+
+/// `xtb` with point charges read through an `$embedding` xcontrol block
+#[derive(Debug, Clone)]
+pub struct Xtb {
+    executable: PathBuf,
+    arguments: Vec<String>,
+    work_dir: PathBuf,
+}
+
+impl QmEngine for Xtb {
+    fn name(&self) -> &str {
+        "xtb"
+    }
+
+    fn calculate(&mut self, request: &QmRequest) -> Result<QmResult, PrismError> {
+        let dir = &self.work_dir;
+        write_xyz(&dir.join("qm.xyz"), request)?;
+        // xtb appends to an existing gradient file
+        for stale in ["gradient", "pcgrad"] {
+            let _ = fs::remove_file(dir.join(stale));
+        }
+        let mut command = Command::new(&self.executable);
+        command
+            .current_dir(dir)
+            .arg("qm.xyz")
+            .arg("--grad")
+            .arg("--chrg")
+            .arg(request.charge.to_string())
+            .arg("--uhf")
+            .arg(request.multiplicity.saturating_sub(1).to_string());
+        if !request.point_charges.is_empty() {
+            write_point_charges(&dir.join("pc.pc"), &request.point_charges)?;
+            fs::write(
+                dir.join("qm.inp"),
+                "$embedding\n   input=pc.pc\n   format=orca\n$end\n",
+            )?;
+            command.arg("--input").arg("qm.inp");
+        }
+        command.args(&self.arguments);
+        let output = run(&mut command, "xtb")?;
+        fs::write(dir.join("qm.out"), output)?;
+
+        let (energy, gradient) = parse_turbomole_gradient(
+            &fs::read_to_string(dir.join("gradient"))?,
+            request.elements.len(),
+        )?;
+        let point_charge_gradient = if request.point_charges.is_empty() {
+            Vec::new()
+        } else {
+            parse_vectors(&fs::read_to_string(dir.join("pcgrad"))?)
+        };
+        Ok(atomic_units(energy, gradient, point_charge_gradient))
+    }
+}
+
+/// ORCA single point with `EnGrad` and a `%pointcharges` file
+#[derive(Debug, Clone)]
+pub struct Orca {
+    executable: PathBuf,
+    keywords: String,
+    work_dir: PathBuf,
+}
+
+impl QmEngine for Orca {
+    fn name(&self) -> &str {
+        "orca"
+    }
+
+    fn calculate(&mut self, request: &QmRequest) -> Result<QmResult, PrismError> {
+        let dir = &self.work_dir;
+        let mut input = format!("! {} EnGrad\n", self.keywords);
+        if !request.point_charges.is_empty() {
+            write_point_charges(&dir.join("pc.pc"), &request.point_charges)?;
+            input.push_str("%pointcharges \"pc.pc\"\n");
+        }
+        input.push_str(&format!(
+            "* xyz {} {}\n",
+            request.charge, request.multiplicity
+        ));
+        for (z, x) in request.elements.iter().zip(&request.positions) {
+            input.push_str(&format!(
+                "{:<2} {:16.10} {:16.10} {:16.10}\n",
+                symbol(*z)?,
+                x[0],
+                x[1],
+                x[2]
+            ));
+        }
+        input.push_str("*\n");
+        fs::write(dir.join("qm.inp"), input)?;
+        let output = run(
+            Command::new(&self.executable)
+                .current_dir(dir)
+                .arg("qm.inp"),
+            "orca",
+        )?;
+        fs::write(dir.join("qm.out"), output)?;
+
+        let (energy, gradient) = parse_engrad(
+            &fs::read_to_string(dir.join("qm.engrad"))?,
+            request.elements.len(),
+        )?;
+        let point_charge_gradient = if request.point_charges.is_empty() {
+            Vec::new()
+        } else {
+            parse_vectors(&fs::read_to_string(dir.join("qm.pcgrad"))?)
+        };
+        Ok(atomic_units(energy, gradient, point_charge_gradient))
+    }
+}
+
+/// Program exchanging JSON on stdin/stdout, one process per evaluation
+#[derive(Debug, Clone)]
+pub struct CommandEngine {
+    executable: PathBuf,
+    arguments: Vec<String>,
+}
+
+impl QmEngine for CommandEngine {
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    fn calculate(&mut self, request: &QmRequest) -> Result<QmResult, PrismError> {
+        let program = self.executable.display().to_string();
+        let mut child = Command::new(&self.executable)
+            .args(&self.arguments)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                PrismError::config(format!("Cannot start QM program {}: {}", program, e))
+            })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            serde_json::to_writer(&mut stdin, request)?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(PrismError::Internal(format!(
+                "QM program {} failed ({}): {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+}
+
+/// QM server speaking newline-delimited JSON over TCP
+#[derive(Debug)]
+pub struct SocketEngine {
+    address: String,
+    connection: Option<BufReader<TcpStream>>,
+}
+
+impl SocketEngine {
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            connection: None,
+        }
+    }
+
+    fn exchange(&mut self, request: &QmRequest) -> Result<QmResult, PrismError> {
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => {
+                let stream = TcpStream::connect(&self.address).map_err(|e| {
+                    PrismError::config(format!(
+                        "Cannot connect to QM server {}: {}",
+                        self.address, e
+                    ))
+                })?;
+                self.connection.insert(BufReader::new(stream))
+            }
+        };
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        connection.get_mut().write_all(&line)?;
+        let mut reply = String::new();
+        if connection.read_line(&mut reply)? == 0 {
+            return Err(PrismError::Internal(format!(
+                "QM server {} closed the connection",
+                self.address
+            )));
+        }
+        Ok(serde_json::from_str(&reply)?)
+    }
+}
+
+impl QmEngine for SocketEngine {
+    fn name(&self) -> &str {
+        "socket"
+    }
+
+    fn calculate(&mut self, request: &QmRequest) -> Result<QmResult, PrismError> {
+        // Reconnect on the next call after any failure
+        self.exchange(request)
+            .inspect_err(|_| self.connection = None)
+    }
+}
+
+/// Run a QM program to completion, returning its stdout
+fn run(command: &mut Command, program: &str) -> Result<Vec<u8>, PrismError> {
+    let output = command
+        .output()
+        .map_err(|e| PrismError::config(format!("Cannot start {}: {}", program, e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let tail: Vec<&str> = stderr.lines().chain(stdout.lines()).rev().take(5).collect();
+        return Err(PrismError::Internal(format!(
+            "{} failed ({}): {}",
+            program,
+            output.status,
+            tail.into_iter().rev().collect::<Vec<_>>().join(" | ")
+        )));
+    }
+    Ok(output.stdout)
+}
+
+fn symbol(element: u8) -> Result<String, PrismError> {
+    let upper = Topology::element_symbol(element);
+    if upper.is_empty() {
+        return Err(PrismError::validation(format!(
+            "No element symbol for Z = {} in the QM region",
+            element
+        )));
+    }
+    let mut chars = upper.chars();
+    Ok(chars
+        .next()
+        .into_iter()
+        .chain(chars.flat_map(char::to_lowercase))
+        .collect())
+}
+
+fn write_xyz(path: &Path, request: &QmRequest) -> Result<(), PrismError> {
+    let mut text = format!("{}\nPRISM QM region\n", request.elements.len());
+    for (z, x) in request.elements.iter().zip(&request.positions) {
+        text.push_str(&format!(
+            "{:<2} {:16.10} {:16.10} {:16.10}\n",
+            symbol(*z)?,
+            x[0],
+            x[1],
+            x[2]
+        ));
+    }
+    Ok(fs::write(path, text)?)
+}
+
+/// ORCA point charge file: count, then `q x y z` in Å
+fn write_point_charges(path: &Path, charges: &[PointCharge]) -> Result<(), PrismError> {
+    let mut text = format!("{}\n", charges.len());
+    for pc in charges {
+        let x = pc.position;
+        text.push_str(&format!(
+            "{:12.8} {:16.10} {:16.10} {:16.10}\n",
+            pc.charge, x[0], x[1], x[2]
+        ));
+    }
+    Ok(fs::write(path, text)?)
+}
+
+/// Fortran-style float (`1.0D-03` or `1.0E-03`)
+fn parse_float(token: &str) -> Option<f64> {
+    token.replace(['D', 'd'], "E").parse().ok()
+}
+
+/// Lines of exactly three numbers (headers and counts skipped)
+fn parse_vectors(text: &str) -> Vec<[f64; 3]> {
+    text.lines()
+        .filter_map(|line| {
+            let values: Vec<f64> = line
+                .split_whitespace()
+                .map(parse_float)
+                .collect::<Option<_>>()?;
+            (values.len() == 3).then(|| [values[0], values[1], values[2]])
+        })
+        .collect()
+}
+
+/// Energy (Eh) and gradient (Eh/Bohr) of the last cycle of a Turbomole
+/// `$grad` file
+fn parse_turbomole_gradient(text: &str, atoms: usize) -> Result<(f64, Vec<[f64; 3]>), PrismError> {
+    let malformed =
+        |what: &str| PrismError::validation(format!("Malformed gradient file: {}", what));
+    let lines: Vec<&str> = text.lines().collect();
+    let cycle = lines
+        .iter()
+        .rposition(|l| l.contains("cycle ="))
+        .ok_or_else(|| malformed("no cycle line"))?;
+    let energy = lines[cycle]
+        .split("energy =")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(parse_float)
+        .ok_or_else(|| malformed("no energy"))?;
+    // Coordinate lines (x y z element) precede the gradient lines
+    let gradient = parse_vectors(&lines[cycle + 1..].join("\n"));
+    if gradient.len() < atoms {
+        return Err(malformed(&format!(
+            "{} gradient lines for {} atoms",
+            gradient.len(),
+            atoms
+        )));
+    }
+    Ok((energy, gradient[..atoms].to_vec()))
+}
+
+/// Energy (Eh) and gradient (Eh/Bohr) of an ORCA `.engrad` file
+fn parse_engrad(text: &str, atoms: usize) -> Result<(f64, Vec<[f64; 3]>), PrismError> {
+    let values: Vec<f64> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map_while(parse_float)
+        .collect();
+    if values.len() < 2 + 3 * atoms || values[0] as usize != atoms {
+        return Err(PrismError::validation(format!(
+            "Malformed engrad file: expected {} atoms, found {:?} with {} values",
+            atoms,
+            values.first(),
+            values.len()
+        )));
+    }
+    let gradient = values[2..2 + 3 * atoms]
+        .chunks_exact(3)
+        .map(|g| [g[0], g[1], g[2]])
+        .collect();
+    Ok((values[1], gradient))
+}
+
+fn atomic_units(
+    energy: f64,
+    gradient: Vec<[f64; 3]>,
+    point_charge_gradient: Vec<[f64; 3]>,
+) -> QmResult {
+    let scale = HARTREE / BOHR;
+    QmResult {
+        energy: energy * HARTREE,
+        gradient: gradient.into_iter().map(|g| g.map(|v| v * scale)).collect(),
+        point_charge_gradient: point_charge_gradient
+            .into_iter()
+            .map(|g| g.map(|v| v * scale))
+            .collect(),
+    }
+}
+
+/// Energy split of the last [`QmMm`] evaluation (kcal/mol)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct QmMmEnergy {
+    /// QM program energy, including the embedding interaction
+    pub qm: f64,
+    /// Force field and bonded terms outside the QM region
+    pub mm: f64,
+}
+
+impl QmMmEnergy {
+    pub fn total(&self) -> f64 {
+        self.qm + self.mm
+    }
+}
+
+/// QM/MM potential over a whole topology
+#[derive(Debug)]
+pub struct QmMm {
+    engine: Box<dyn QmEngine>,
+    force_field: ForceField,
+    bonded: BondedTerms,
+    neighbor_list: NeighborList,
+    qm_atoms: Vec<u32>,
+    /// Cut bonds as (QM atom, MM atom)
+    links: Vec<(u32, u32)>,
+    link_ratio: f64,
+    /// MM atoms and charges sent as point charges
+    embedded: Vec<(u32, f64)>,
+    elements: Vec<u8>,
+    charge: i32,
+    multiplicity: u32,
+    simulation_box: Option<SimulationBox>,
+    last_energy: QmMmEnergy,
+}
+
+impl QmMm {
+    /// Split `topology` per `config`, with the MM part evaluated under
+    /// `force_field` and the QM part by `engine`
+    pub fn new(
+        config: &QmMmConfig,
+        force_field: ForceFieldConfig,
+        topology: &Topology,
+        engine: Box<dyn QmEngine>,
+    ) -> Result<Self, PrismError> {
+        let qm_atoms = SelectionContext::from_topology(topology)
+            .select(&config.region)
+            .map_err(|e| PrismError::validation(e.to_string()))?;
+        if qm_atoms.is_empty() {
+            return Err(PrismError::validation(format!(
+                "QM region '{}' selects no atoms",
+                config.region
+            )));
+        }
+        if !(0.0..=1.0).contains(&config.link_ratio) {
+            return Err(PrismError::config(format!(
+                "link_ratio {} outside [0, 1]",
+                config.link_ratio
+            )));
+        }
+        let qm: HashSet<u32> = qm_atoms.iter().copied().collect();
+        let links: Vec<(u32, u32)> = topology
+            .bonds
+            .iter()
+            .filter_map(|b| match (qm.contains(&b.i), qm.contains(&b.j)) {
+                (true, false) => Some((b.i, b.j)),
+                (false, true) => Some((b.j, b.i)),
+                _ => None,
+            })
+            .collect();
+        let mut elements: Vec<u8> = qm_atoms
+            .iter()
+            .map(|&i| topology.atoms[i as usize].element)
+            .collect();
+        if let Some(i) = elements.iter().position(|&z| z == 0) {
+            return Err(PrismError::validation(format!(
+                "QM atom {} has no element",
+                qm_atoms[i]
+            )));
+        }
+        elements.extend(links.iter().map(|_| 1));
+        let charge = config.charge.unwrap_or_else(|| {
+            qm_atoms
+                .iter()
+                .map(|&i| topology.atoms[i as usize].charge as f64)
+                .sum::<f64>()
+                .round() as i32
+        });
+
+        // MM model: QM-QM pairs and terms removed, QM charges zeroed when
+        // the QM program sees the MM charges instead
+        let mut mm = topology.clone();
+        let inside = |atoms: &[u32]| atoms.iter().all(|a| qm.contains(a));
+        mm.bonds.retain(|b| !inside(&[b.i, b.j]));
+        mm.angles.retain(|a| !inside(&[a.i, a.j, a.k]));
+        mm.dihedrals.retain(|d| !inside(&d.atoms));
+        mm.impropers.retain(|d| !inside(&d.atoms));
+        mm.pairs14.retain(|p| !inside(&[p.i, p.j]));
+        let mut exclusions: HashSet<(u32, u32)> = mm.exclusions.iter().copied().collect();
+        for (a, &i) in qm_atoms.iter().enumerate() {
+            for &j in &qm_atoms[a + 1..] {
+                exclusions.insert((i.min(j), i.max(j)));
+            }
+        }
+        mm.exclusions = exclusions.into_iter().collect();
+        mm.exclusions.sort_unstable();
+        let embedded = match config.embedding {
+            Embedding::Electrostatic => {
+                for &i in &qm_atoms {
+                    mm.atoms[i as usize].charge = 0.0;
+                }
+                let boundary: HashSet<u32> = links.iter().map(|&(_, m)| m).collect();
+                (0..topology.num_atoms() as u32)
+                    .filter(|i| !qm.contains(i) && !boundary.contains(i))
+                    .map(|i| (i, topology.atoms[i as usize].charge as f64))
+                    .filter(|&(_, q)| q != 0.0)
+                    .collect()
+            }
+            Embedding::Mechanical => Vec::new(),
+        };
+        let force_field = ForceField::from_topology(force_field, &mm);
+        let neighbor_list = force_field.neighbor_list();
+        Ok(Self {
+            engine,
+            bonded: BondedTerms::from_topology(&mm),
+            force_field,
+            neighbor_list,
+            qm_atoms,
+            links,
+            link_ratio: config.link_ratio,
+            embedded,
+            elements,
+            charge,
+            multiplicity: config.multiplicity,
+            simulation_box: topology.simulation_box,
+            last_energy: QmMmEnergy::default(),
+        })
+    }
+
+    /// [`Self::new`] with the engine of `config`
+    pub fn from_config(
+        config: &QmMmConfig,
+        force_field: ForceFieldConfig,
+        topology: &Topology,
+    ) -> Result<Self, PrismError> {
+        let work_dir = config.work_dir.clone().unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("prism-qmmm-{}", uuid::Uuid::new_v4()))
+        });
+        let engine = config.engine.build(&work_dir)?;
+        Self::new(config, force_field, topology, engine)
+    }
+
+    /// QM atom indices, in request order
+    pub fn qm_atoms(&self) -> &[u32] {
+        &self.qm_atoms
+    }
+
+    /// Bonds cut by the region boundary as (QM atom, MM atom); their link
+    /// hydrogens follow the QM atoms in each request
+    pub fn links(&self) -> &[(u32, u32)] {
+        &self.links
+    }
+
+    /// Number of MM charges embedded in the QM calculation
+    pub fn num_point_charges(&self) -> usize {
+        self.embedded.len()
+    }
+
+    /// Energy split of the last evaluation
+    pub fn last_energy(&self) -> QmMmEnergy {
+        self.last_energy
+    }
+
+    /// QM calculation for Float4-stride positions
+    pub fn request(&self, positions: &[f32]) -> QmRequest {
+        let at = |i: u32| [0, 1, 2].map(|k| positions[i as usize * 4 + k]);
+        let image = |d: [f32; 3]| match &self.simulation_box {
+            Some(cell) => cell.minimum_image(d),
+            None => d,
+        };
+        let offset = |from: [f64; 3], d: [f32; 3], scale: f64| {
+            [0, 1, 2].map(|k| from[k] + scale * d[k] as f64)
+        };
+        let between = |a: [f32; 3], b: [f32; 3]| image([b[0] - a[0], b[1] - a[1], b[2] - a[2]]);
+
+        // Whole QM region around its first atom
+        let anchor = at(self.qm_atoms[0]);
+        let origin = anchor.map(|v| v as f64);
+        let mut coordinates: Vec<[f64; 3]> = self
+            .qm_atoms
+            .iter()
+            .map(|&i| offset(origin, between(anchor, at(i)), 1.0))
+            .collect();
+        for &(q, m) in &self.links {
+            let slot = self.qm_atoms.iter().position(|&i| i == q).unwrap_or(0);
+            coordinates.push(offset(
+                coordinates[slot],
+                between(at(q), at(m)),
+                self.link_ratio,
+            ));
+        }
+
+        let n = self.qm_atoms.len() as f64;
+        let centroid = [0, 1, 2].map(|k| {
+            coordinates[..self.qm_atoms.len()]
+                .iter()
+                .map(|x| x[k])
+                .sum::<f64>()
+                / n
+        });
+        let center = centroid.map(|v| v as f32);
+        let point_charges = self
+            .embedded
+            .iter()
+            .map(|&(i, charge)| PointCharge {
+                charge,
+                position: offset(centroid, between(center, at(i)), 1.0),
+            })
+            .collect();
+        QmRequest {
+            elements: self.elements.clone(),
+            positions: coordinates,
+            charge: self.charge,
+            multiplicity: self.multiplicity,
+            point_charges,
+        }
+    }
+}
+
+impl Potential for QmMm {
+    fn name(&self) -> &str {
+        self.engine.name()
+    }
+
+    fn compute(&mut self, positions: &[f32], forces: &mut [f32]) -> Result<f64, PrismError> {
+        let atoms = self.force_field.num_atoms();
+        if positions.len() / 4 != atoms {
+            return Err(PrismError::validation(format!(
+                "QM/MM system has {} atoms, frame has {}",
+                atoms,
+                positions.len() / 4
+            )));
+        }
+        let request = self.request(positions);
+        let result = self.engine.calculate(&request)?;
+        if result.gradient.len() != request.elements.len()
+            || result.point_charge_gradient.len() != request.point_charges.len()
+        {
+            return Err(PrismError::validation(format!(
+                "{} returned {} atom and {} point charge gradients for {} atoms and {} point charges",
+                self.engine.name(),
+                result.gradient.len(),
+                result.point_charge_gradient.len(),
+                request.elements.len(),
+                request.point_charges.len()
+            )));
+        }
+
+        let mut push = |i: u32, g: [f64; 3], weight: f64| {
+            for k in 0..3 {
+                forces[i as usize * 4 + k] -= (weight * g[k]) as f32;
+            }
+        };
+        let (real, link) = result.gradient.split_at(self.qm_atoms.len());
+        for (&i, &g) in self.qm_atoms.iter().zip(real) {
+            push(i, g, 1.0);
+        }
+        for (&(q, m), &g) in self.links.iter().zip(link) {
+            push(q, g, 1.0 - self.link_ratio);
+            push(m, g, self.link_ratio);
+        }
+        for (&(i, _), &g) in self.embedded.iter().zip(&result.point_charge_gradient) {
+            push(i, g, 1.0);
+        }
+
+        let force_field = &self.force_field;
+        self.neighbor_list
+            .update(positions, |i, j| force_field.is_excluded(i, j));
+        let nonbonded = force_field.compute_with_list(positions, forces, &self.neighbor_list);
+        let bonded = self.bonded.compute(positions, forces);
+        self.last_energy = QmMmEnergy {
+            qm: result.energy,
+            mm: nonbonded.total() + bonded.total(),
+        };
+        Ok(self.last_energy.total())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_io::sovereign_types::Atom;
+    use prism_io::topology::{HarmonicAngle, HarmonicBond, LjParams};
+
+    /// Harmonic QM bonds between consecutive atoms plus a fixed charge of
+    /// `CHARGE` on each atom interacting with the point charges
+    #[derive(Debug, Default)]
+    struct ToyEngine;
+
+    const CHARGE: f64 = 0.3;
+
+    impl QmEngine for ToyEngine {
+        fn name(&self) -> &str {
+            "toy"
+        }
+
+        fn calculate(&mut self, request: &QmRequest) -> Result<QmResult, PrismError> {
+            let x = &request.positions;
+            let mut result = QmResult {
+                gradient: vec![[0.0; 3]; x.len()],
+                point_charge_gradient: vec![[0.0; 3]; request.point_charges.len()],
+                ..Default::default()
+            };
+            for i in 1..x.len() {
+                let d: [f64; 3] = [0, 1, 2].map(|k| x[i][k] - x[i - 1][k]);
+                let r = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                result.energy += 50.0 * (r - 1.2).powi(2);
+                let de = 100.0 * (r - 1.2) / r;
+                for (k, dk) in d.iter().enumerate() {
+                    result.gradient[i][k] += de * dk;
+                    result.gradient[i - 1][k] -= de * dk;
+                }
+            }
+            for (p, pc) in request.point_charges.iter().enumerate() {
+                for (i, xi) in x.iter().enumerate() {
+                    let d: [f64; 3] = [0, 1, 2].map(|k| pc.position[k] - xi[k]);
+                    let r = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                    let e = 332.0636 * CHARGE * pc.charge / r;
+                    result.energy += e;
+                    for (k, dk) in d.iter().enumerate() {
+                        result.point_charge_gradient[p][k] -= e / (r * r) * dk;
+                        result.gradient[i][k] += e / (r * r) * dk;
+                    }
+                }
+            }
+            Ok(result)
+        }
+    }
+
+    /// Six-atom chain; atoms 1-2 are QM, so bonds 0-1 and 2-3 are cut
+    fn chain() -> Topology {
+        let atoms: Vec<Atom> = (0..6)
+            .map(|i| Atom {
+                coords: [i as f32 * 1.5, 0.4 * (i % 2) as f32, 0.1 * i as f32],
+                element: if i == 2 { 8 } else { 6 },
+                residue_id: 0,
+                atom_type: 1,
+                charge: [0.2, -0.4, 0.6, -0.3, 0.5, -0.6][i],
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        let mut topology = Topology {
+            atoms,
+            masses: vec![12.0; 6],
+            lj: vec![
+                LjParams {
+                    sigma: 3.0,
+                    epsilon: 0.1
+                };
+                6
+            ],
+            bonds: (0..5)
+                .map(|i| HarmonicBond {
+                    i,
+                    j: i + 1,
+                    k: 300.0,
+                    r0: 1.5,
+                })
+                .collect(),
+            angles: (0..4)
+                .map(|i| HarmonicAngle {
+                    i,
+                    j: i + 1,
+                    k: i + 2,
+                    force_constant: 50.0,
+                    theta0: 2.0,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        topology.generate_exclusions();
+        topology
+    }
+
+    fn config(embedding: Embedding) -> QmMmConfig {
+        QmMmConfig {
+            embedding,
+            ..QmMmConfig::new(
+                "index 1 2",
+                QmEngineConfig::Socket {
+                    address: String::new(),
+                },
+            )
+        }
+    }
+
+    fn positions(topology: &Topology) -> Vec<f32> {
+        topology
+            .atoms
+            .iter()
+            .flat_map(|a| [a.coords[0], a.coords[1], a.coords[2], 12.0])
+            .collect()
+    }
+
+    #[test]
+    fn test_link_atoms_and_embedding() {
+        let topology = chain();
+        let mut qmmm = QmMm::new(
+            &config(Embedding::Electrostatic),
+            ForceFieldConfig::default(),
+            &topology,
+            Box::<ToyEngine>::default(),
+        )
+        .unwrap();
+        assert_eq!(qmmm.qm_atoms(), &[1, 2]);
+        assert_eq!(qmmm.links(), &[(1, 0), (2, 3)]);
+        // Boundary atoms 0 and 3 are left out of the embedding
+        assert_eq!(qmmm.num_point_charges(), 2);
+
+        let x = positions(&topology);
+        let request = qmmm.request(&x);
+        assert_eq!(request.elements, vec![6, 8, 1, 1]);
+        assert_eq!(request.charge, 0);
+        let link = request.positions[2];
+        for k in 0..3 {
+            let expected = x[4 + k] as f64 + 0.709 * (x[k] - x[4 + k]) as f64;
+            assert!((link[k] - expected).abs() < 1e-5);
+        }
+        assert_eq!(request.point_charges[0].charge, 0.5f32 as f64);
+
+        let mut forces = vec![0.0; x.len()];
+        let energy = qmmm.compute(&x, &mut forces).unwrap();
+        assert!((energy - qmmm.last_energy().total()).abs() < 1e-9);
+        assert!(qmmm.last_energy().qm != 0.0 && qmmm.last_energy().mm != 0.0);
+
+        let mechanical = QmMm::new(
+            &config(Embedding::Mechanical),
+            ForceFieldConfig::default(),
+            &topology,
+            Box::<ToyEngine>::default(),
+        )
+        .unwrap();
+        assert_eq!(mechanical.num_point_charges(), 0);
+        assert!(mechanical.request(&x).point_charges.is_empty());
+    }
+
+    #[test]
+    fn test_forces_match_energy_gradient() {
+        let topology = chain();
+        for embedding in [Embedding::Electrostatic, Embedding::Mechanical] {
+            let mut qmmm = QmMm::new(
+                &config(embedding),
+                ForceFieldConfig::default(),
+                &topology,
+                Box::<ToyEngine>::default(),
+            )
+            .unwrap();
+            let x = positions(&topology);
+            let mut forces = vec![0.0; x.len()];
+            qmmm.compute(&x, &mut forces).unwrap();
+            let h = 5e-3;
+            for i in 0..6 {
+                for k in 0..3 {
+                    let mut energy_at = |delta: f32| {
+                        let mut moved = x.clone();
+                        moved[i * 4 + k] += delta;
+                        qmmm.compute(&moved, &mut vec![0.0; x.len()]).unwrap()
+                    };
+                    let numeric = -(energy_at(h) - energy_at(-h)) / (2.0 * h as f64);
+                    let analytic = forces[i * 4 + k] as f64;
+                    assert!(
+                        (numeric - analytic).abs() < 2e-2 * analytic.abs().max(1.0),
+                        "{:?} atom {} axis {}: {} vs {}",
+                        embedding,
+                        i,
+                        k,
+                        numeric,
+                        analytic
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_program_outputs() {
+        let gradient =
+            "$grad\n  cycle =      1    SCF energy =    -5.0705442350   |dE/dxyz| =  0.010000\n   \
+                        0.00000000000000      0.00000000000000     -0.74080000000000      o\n   \
+                        0.00000000000000      1.43000000000000      0.37000000000000      h\n   \
+                        0.1000000000000D-01   0.0000000000000D+00  -0.2000000000000D-02\n  \
+                        -0.1000000000000D-01   0.0000000000000D+00   0.2000000000000D-02\n$end\n";
+        let (energy, g) = parse_turbomole_gradient(gradient, 2).unwrap();
+        assert_eq!(energy, -5.070544235);
+        assert_eq!(g, vec![[0.01, 0.0, -0.002], [-0.01, 0.0, 0.002]]);
+        assert!(parse_turbomole_gradient(gradient, 3).is_err());
+
+        let engrad = "#\n# Number of atoms\n#\n 2\n#\n# The current total energy in Eh\n#\n   -76.40\n\
+                      #\n# The current gradient in Eh/bohr\n#\n 0.1\n 0.2\n 0.3\n -0.1\n -0.2\n -0.3\n\
+                      #\n# The atomic numbers and current coordinates in Bohr\n#\n   8  0.0 0.0 0.0\n";
+        let (energy, g) = parse_engrad(engrad, 2).unwrap();
+        assert_eq!(energy, -76.40);
+        assert_eq!(g, vec![[0.1, 0.2, 0.3], [-0.1, -0.2, -0.3]]);
+        assert!(parse_engrad(engrad, 3).is_err());
+
+        assert_eq!(
+            parse_vectors("2\n 1.0 2.0 3.0\n-1.0D+00 0.0 0.5\n"),
+            vec![[1.0, 2.0, 3.0], [-1.0, 0.0, 0.5]]
+        );
+        let result = atomic_units(-1.0, vec![[1.0, 0.0, 0.0]], Vec::new());
+        assert_eq!(result.energy, -HARTREE);
+        assert!((result.gradient[0][0] - 1185.821).abs() < 1e-2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_engine_exchanges_json() {
+        let reply = r#"{"energy": -3.5, "gradient": [[1.0, 0.0, 0.0]]}"#;
+        let mut engine = QmEngineConfig::Command {
+            executable: "sh".into(),
+            arguments: vec!["-c".into(), format!("cat > /dev/null; echo '{}'", reply)],
+        }
+        .build(Path::new("."))
+        .unwrap();
+        let request = QmRequest {
+            elements: vec![1],
+            positions: vec![[0.0; 3]],
+            charge: 0,
+            multiplicity: 2,
+            point_charges: Vec::new(),
+        };
+        let result = engine.calculate(&request).unwrap();
+        assert_eq!(result.energy, -3.5);
+        assert_eq!(result.gradient, vec![[1.0, 0.0, 0.0]]);
+
+        let mut failing = QmEngineConfig::Command {
+            executable: "sh".into(),
+            arguments: vec!["-c".into(), "exit 3".into()],
+        }
+        .build(Path::new("."))
+        .unwrap();
+        assert!(failing.calculate(&request).is_err());
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config: QmMmConfig = toml::from_str(
+            r#"
+            region = "resname LIG"
+            engine = { program = "xtb", arguments = ["--gfn", "2"] }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.multiplicity, 1);
+        assert_eq!(config.embedding, Embedding::Electrostatic);
+        assert_eq!(config.charge, None);
+        assert_eq!(
+            config.engine,
+            QmEngineConfig::Xtb {
+                executable: "xtb".into(),
+                arguments: vec!["--gfn".into(), "2".into()]
+            }
+        );
+        assert!(toml::from_str::<QmMmConfig>(
+            "region = \"all\"\nengine = { program = \"gaussian\" }"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,826 @@
+//! # Replica Exchange - Parallel Tempering
+//! Runs one engine per rung of a temperature ladder and periodically attempts
+//! Metropolis swaps between neighbouring rungs, alternating even and odd
+//! pairs. Swaps exchange temperatures (not coordinates): the two engines
+//! trade thermostat targets and their velocities are rescaled by
+//! `sqrt(T_new / T_old)`. Temperatures are on the `temp_start` scale (kT in
+//! kcal/mol), so `β = 1 / T`.
+//!
+//! Each replica advances on its own scoped thread. Host replicas integrate
+//! there directly; GPU replicas (`use_gpu`) bind the shared context on their
+//! thread and queue their kernels on the engine's own CUDA stream, so
+//! replicas on one device overlap and a ladder may mix host and GPU engines.
+//!
+//! [`DistributedReplicaExchange`] spreads the replicas over processes that
+//! talk through a [`ReplicaTransport`], e.g. MPI ranks with the `mpi`
+//! feature, and reproduces the single-process run exactly.
+
+use crate::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsEngine};
+use crate::rng::{RngHierarchy, RngStream, DEFAULT_SEED};
+use prism_core::{PhaseOutcome, PrismError};
+use prism_io::topology::Topology;
+use rand::{Rng, RngCore};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicaExchangeConfig {
+    /// Temperature ladder, strictly increasing
+    pub temperatures: Vec<f32>,
+    /// MD steps between swap attempts
+    pub exchange_interval: u64,
+    /// Seed for the replica seeds and the acceptance draws
+    pub seed: u64,
+}
+
+impl Default for ReplicaExchangeConfig {
+    fn default() -> Self {
+        Self {
+            temperatures: Vec::new(),
+            exchange_interval: 1000,
+            seed: DEFAULT_SEED,
+        }
+    }
+}
+
+impl ReplicaExchangeConfig {
+    /// Geometric ladder of `count` temperatures from `t_min` to `t_max`,
+    /// which gives roughly uniform acceptance for a constant heat capacity
+    pub fn geometric(t_min: f32, t_max: f32, count: usize) -> Vec<f32> {
+        if count < 2 {
+            return vec![t_min; count];
+        }
+        let ratio = (t_max / t_min).powf(1.0 / (count - 1) as f32);
+        (0..count).map(|k| t_min * ratio.powi(k as i32)).collect()
+    }
+}
+
+/// Per-rung summary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicaStats {
+    pub temperature: f32,
+    /// Engine currently at this rung
+    pub replica: usize,
+    pub potential_energy: f64,
+    /// Swap attempts with the next rung up
+    pub attempts: u64,
+    /// Accepted swaps with the next rung up
+    pub accepted: u64,
+}
+
+/// Assignment of replicas to rungs and the swap statistics, shared by the
+/// single-process and distributed controllers
+#[derive(Debug, Clone)]
+struct Ladder {
+    /// `rung_to_replica[k]` is the replica currently at `temperatures[k]`
+    rung_to_replica: Vec<usize>,
+    attempts: Vec<u64>,
+    accepted: Vec<u64>,
+    exchanges: u64,
+    rng: ChaCha12Rng,
+}
+
+impl Ladder {
+    fn new(seed: u64, n: usize) -> Self {
+        let mut rng = RngHierarchy::new(seed).stream(RngStream::ReplicaExchange);
+        // Skip the draws that seeded the replicas in `from_topology`
+        for _ in 0..n {
+            rng.next_u64();
+        }
+        Self {
+            rung_to_replica: (0..n).collect(),
+            attempts: vec![0; n - 1],
+            accepted: vec![0; n - 1],
+            exchanges: 0,
+            rng,
+        }
+    }
+
+    /// Metropolis swaps between rungs (k, k+1), alternating even and odd k,
+    /// given the potential energy of each replica. Returns the swapped
+    /// pairs of replicas as `(moved up, moved down)`.
+    fn attempt_swaps(&mut self, temperatures: &[f32], energies: &[f64]) -> Vec<(usize, usize)> {
+        let start = (self.exchanges % 2) as usize;
+        self.exchanges += 1;
+        let mut swapped = Vec::new();
+        for k in (start..temperatures.len() - 1).step_by(2) {
+            let (a, b) = (self.rung_to_replica[k], self.rung_to_replica[k + 1]);
+            let (t_lo, t_hi) = (temperatures[k], temperatures[k + 1]);
+            let delta = (1.0 / t_lo as f64 - 1.0 / t_hi as f64) * (energies[a] - energies[b]);
+            self.attempts[k] += 1;
+            if delta >= 0.0 || self.rng.gen::<f64>() < delta.exp() {
+                self.accepted[k] += 1;
+                self.rung_to_replica.swap(k, k + 1);
+                swapped.push((a, b));
+            }
+        }
+        swapped
+    }
+
+    fn acceptance_rates(&self) -> Vec<f64> {
+        self.attempts
+            .iter()
+            .zip(&self.accepted)
+            .map(|(&n, &a)| if n == 0 { 0.0 } else { a as f64 / n as f64 })
+            .collect()
+    }
+
+    fn stats(&self, temperatures: &[f32], energies: &[f64]) -> Vec<ReplicaStats> {
+        temperatures
+            .iter()
+            .enumerate()
+            .map(|(k, &temperature)| {
+                let replica = self.rung_to_replica[k];
+                ReplicaStats {
+                    temperature,
+                    replica,
+                    potential_energy: energies[replica],
+                    attempts: self.attempts.get(k).copied().unwrap_or(0),
+                    accepted: self.accepted.get(k).copied().unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+
+    fn telemetry(&self, stats: Vec<ReplicaStats>) -> HashMap<String, serde_json::Value> {
+        let rates = self.acceptance_rates();
+        log::info!("🔁 Replica exchange acceptance: {:?}", rates);
+        let mut telemetry = HashMap::new();
+        telemetry.insert("exchanges".to_string(), json!(self.exchanges));
+        telemetry.insert("acceptance_rates".to_string(), json!(rates));
+        telemetry.insert("replicas".to_string(), json!(stats));
+        telemetry
+    }
+}
+
+/// Check the temperature ladder and exchange interval of `config`
+fn validate(config: &ReplicaExchangeConfig) -> Result<(), PrismError> {
+    if config.temperatures.len() < 2 {
+        return Err(PrismError::config(
+            "Replica exchange needs at least two temperatures",
+        ));
+    }
+    if config.temperatures[0] <= 0.0 || config.temperatures.windows(2).any(|w| w[1] <= w[0]) {
+        return Err(PrismError::config(
+            "Replica temperatures must be positive and strictly increasing",
+        ));
+    }
+    if config.exchange_interval == 0 {
+        return Err(PrismError::config("exchange_interval must be positive"));
+    }
+    Ok(())
+}
+
+/// Engine of replica `index` from a shared topology: its seed is the
+/// `index`-th draw of the exchange stream and its velocities are drawn at
+/// its temperature
+fn replica_engine(
+    config: &ReplicaExchangeConfig,
+    md_config: &MolecularDynamicsConfig,
+    topology: &Topology,
+    index: usize,
+) -> Result<MolecularDynamicsEngine, PrismError> {
+    let mut seeds = RngHierarchy::new(config.seed).stream(RngStream::ReplicaExchange);
+    for _ in 0..index {
+        seeds.next_u64();
+    }
+    let md = MolecularDynamicsConfig {
+        seed: seeds.next_u64(),
+        ..md_config.clone()
+    };
+    let mut engine = MolecularDynamicsEngine::from_topology(md, topology)?;
+    engine.assign_velocities(config.temperatures[index]);
+    Ok(engine)
+}
+
+/// Move `engine` from `t_old` to `t_new`, rescaling its velocities
+fn retemper(engine: &mut MolecularDynamicsEngine, t_old: f32, t_new: f32) {
+    engine.set_temperature(t_new);
+    engine.scale_velocities((t_new / t_old).sqrt());
+}
+
+fn advance(replicas: &mut [MolecularDynamicsEngine], steps: u64) -> Result<(), PrismError> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = replicas
+            .iter_mut()
+            .map(|engine| scope.spawn(move || engine.run_nlnm_breathing(steps)))
+            .collect();
+        handles.into_iter().try_for_each(|h| {
+            h.join()
+                .map_err(|_| PrismError::Internal("Replica thread panicked".into()))?
+                .map(|_| ())
+        })
+    })
+}
+
+#[derive(Debug)]
+pub struct ReplicaExchangeController {
+    config: ReplicaExchangeConfig,
+    replicas: Vec<MolecularDynamicsEngine>,
+    ladder: Ladder,
+}
+
+impl ReplicaExchangeController {
+    /// Take ownership of one engine per temperature; engine `k` starts at
+    /// `temperatures[k]`.
+    pub fn new(
+        config: ReplicaExchangeConfig,
+        mut replicas: Vec<MolecularDynamicsEngine>,
+    ) -> Result<Self, PrismError> {
+        validate(&config)?;
+        let n = config.temperatures.len();
+        if replicas.len() != n {
+            return Err(PrismError::config(format!(
+                "{} replicas for {} temperatures",
+                replicas.len(),
+                n
+            )));
+        }
+
+        for (engine, &t) in replicas.iter_mut().zip(&config.temperatures) {
+            engine.set_temperature(t);
+        }
+        log::info!(
+            "🔥 Replica exchange: {} replicas, T = {:?}, swaps every {} steps",
+            n,
+            config.temperatures,
+            config.exchange_interval
+        );
+        Ok(Self {
+            ladder: Ladder::new(config.seed, n),
+            replicas,
+            config,
+        })
+    }
+
+    /// One engine per temperature from a shared topology, each with its own
+    /// seed drawn from the exchange stream and velocities at its temperature.
+    pub fn from_topology(
+        config: ReplicaExchangeConfig,
+        md_config: &MolecularDynamicsConfig,
+        topology: &Topology,
+    ) -> Result<Self, PrismError> {
+        validate(&config)?;
+        let replicas = (0..config.temperatures.len())
+            .map(|index| replica_engine(&config, md_config, topology, index))
+            .collect::<Result<Vec<_>, PrismError>>()?;
+        Self::new(config, replicas)
+    }
+
+    pub fn config(&self) -> &ReplicaExchangeConfig {
+        &self.config
+    }
+
+    /// Engines in construction order
+    pub fn replicas(&self) -> &[MolecularDynamicsEngine] {
+        &self.replicas
+    }
+
+    /// Engine currently at `temperatures[rung]`
+    pub fn replica_at(&self, rung: usize) -> &MolecularDynamicsEngine {
+        &self.replicas[self.ladder.rung_to_replica[rung]]
+    }
+
+    /// Swap attempts made so far
+    pub fn exchanges(&self) -> u64 {
+        self.ladder.exchanges
+    }
+
+    /// Acceptance ratio of each neighbouring pair of rungs
+    pub fn acceptance_rates(&self) -> Vec<f64> {
+        self.ladder.acceptance_rates()
+    }
+
+    pub fn stats(&self) -> Vec<ReplicaStats> {
+        self.ladder
+            .stats(&self.config.temperatures, &self.energies())
+    }
+
+    fn energies(&self) -> Vec<f64> {
+        self.replicas.iter().map(|e| e.potential_energy()).collect()
+    }
+
+    /// Run `cycles` rounds of `exchange_interval` MD steps per replica, each
+    /// followed by a swap attempt.
+    pub fn run(&mut self, cycles: u64) -> Result<PhaseOutcome, PrismError> {
+        for _ in 0..cycles {
+            advance(&mut self.replicas, self.config.exchange_interval)?;
+            self.attempt_swaps();
+        }
+
+        Ok(PhaseOutcome::Success {
+            message: format!(
+                "Replica exchange: {} replicas, {} exchange rounds",
+                self.replicas.len(),
+                self.ladder.exchanges
+            ),
+            telemetry: self.ladder.telemetry(self.stats()),
+        })
+    }
+
+    /// Swap neighbouring rungs and retemper the engines that moved
+    fn attempt_swaps(&mut self) {
+        let energies = self.energies();
+        let temperatures = &self.config.temperatures;
+        for (up, down) in self.ladder.attempt_swaps(temperatures, &energies) {
+            let rung = self
+                .ladder
+                .rung_to_replica
+                .iter()
+                .position(|&r| r == up)
+                .unwrap();
+            let (t_lo, t_hi) = (temperatures[rung - 1], temperatures[rung]);
+            retemper(&mut self.replicas[up], t_lo, t_hi);
+            retemper(&mut self.replicas[down], t_hi, t_lo);
+        }
+    }
+}
+
+/// Point-to-point messages between the processes of a
+/// [`DistributedReplicaExchange`]; rank 0 coordinates the swaps. With the
+/// `mpi` feature, [`crate::mpi::MpiWorld`] implements it over
+/// `MPI_COMM_WORLD`.
+pub trait ReplicaTransport {
+    fn rank(&self) -> usize;
+
+    /// Number of processes
+    fn size(&self) -> usize;
+
+    /// Send `data` to rank `to`
+    fn send(&self, to: usize, data: &[f64]) -> Result<(), PrismError>;
+
+    /// Receive exactly `data.len()` values from rank `from`
+    fn recv(&self, from: usize, data: &mut [f64]) -> Result<(), PrismError>;
+}
+
+/// Replicas held by `rank`: contiguous blocks of `n / size` or one more
+fn block(n: usize, size: usize, rank: usize) -> Range<usize> {
+    rank * n / size..(rank + 1) * n / size
+}
+
+/// Replica exchange with the replicas spread over the ranks of a
+/// [`ReplicaTransport`], for ladders larger than one machine.
+///
+/// Each rank advances its own block of replicas. After every interval the
+/// ranks send their potential energies to rank 0, which runs the same
+/// Metropolis round as [`ReplicaExchangeController`] and sends the new
+/// rung assignment and swap counts back; each rank then retempers the
+/// engines that moved. Only `2 n` numbers per rank cross the network per
+/// round, coordinates never do. Replica seeds and the swap stream match
+/// the single-process controller, so a run is reproducible on any number
+/// of ranks up to the number of replicas.
+pub struct DistributedReplicaExchange<T: ReplicaTransport> {
+    config: ReplicaExchangeConfig,
+    transport: T,
+    /// Global indices of the local replicas
+    range: Range<usize>,
+    replicas: Vec<MolecularDynamicsEngine>,
+    ladder: Ladder,
+    /// Energies of all replicas at the last exchange
+    energies: Vec<f64>,
+}
+
+impl<T: ReplicaTransport> std::fmt::Debug for DistributedReplicaExchange<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DistributedReplicaExchange")
+            .field("rank", &self.transport.rank())
+            .field("size", &self.transport.size())
+            .field("range", &self.range)
+            .field("ladder", &self.ladder)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: ReplicaTransport> DistributedReplicaExchange<T> {
+    /// Take the engines of this rank's block of replicas
+    /// ([`Self::local_range`] for `transport`), each starting at its
+    /// replica's temperature
+    pub fn new(
+        config: ReplicaExchangeConfig,
+        mut replicas: Vec<MolecularDynamicsEngine>,
+        transport: T,
+    ) -> Result<Self, PrismError> {
+        validate(&config)?;
+        let (n, size, rank) = (
+            config.temperatures.len(),
+            transport.size(),
+            transport.rank(),
+        );
+        if size == 0 || size > n || rank >= size {
+            return Err(PrismError::config(format!(
+                "Rank {} of {} cannot hold a share of {} replicas",
+                rank, size, n
+            )));
+        }
+        let range = block(n, size, rank);
+        if replicas.len() != range.len() {
+            return Err(PrismError::config(format!(
+                "Rank {} holds replicas {:?} but got {} engines",
+                rank,
+                range,
+                replicas.len()
+            )));
+        }
+        for (engine, &t) in replicas.iter_mut().zip(&config.temperatures[range.clone()]) {
+            engine.set_temperature(t);
+        }
+        if rank == 0 {
+            log::info!(
+                "🔥 Distributed replica exchange: {} replicas on {} ranks, T = {:?}, swaps every {} steps",
+                n,
+                size,
+                config.temperatures,
+                config.exchange_interval
+            );
+        }
+        Ok(Self {
+            ladder: Ladder::new(config.seed, n),
+            energies: vec![0.0; n],
+            range,
+            replicas,
+            transport,
+            config,
+        })
+    }
+
+    /// This rank's engines from a shared topology, seeded as in
+    /// [`ReplicaExchangeController::from_topology`]
+    pub fn from_topology(
+        config: ReplicaExchangeConfig,
+        md_config: &MolecularDynamicsConfig,
+        topology: &Topology,
+        transport: T,
+    ) -> Result<Self, PrismError> {
+        validate(&config)?;
+        let n = config.temperatures.len();
+        let size = transport.size().clamp(1, n);
+        let replicas = block(n, size, transport.rank().min(size - 1))
+            .map(|index| replica_engine(&config, md_config, topology, index))
+            .collect::<Result<Vec<_>, PrismError>>()?;
+        Self::new(config, replicas, transport)
+    }
+
+    pub fn config(&self) -> &ReplicaExchangeConfig {
+        &self.config
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Global indices of the replicas on this rank
+    pub fn local_range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Engines of this rank, in the order of [`Self::local_range`]
+    pub fn local_replicas(&self) -> &[MolecularDynamicsEngine] {
+        &self.replicas
+    }
+
+    /// Rung of every replica, the same on all ranks
+    pub fn rung_to_replica(&self) -> &[usize] {
+        &self.ladder.rung_to_replica
+    }
+
+    pub fn exchanges(&self) -> u64 {
+        self.ladder.exchanges
+    }
+
+    pub fn acceptance_rates(&self) -> Vec<f64> {
+        self.ladder.acceptance_rates()
+    }
+
+    /// Per-rung summary with the energies of the last exchange
+    pub fn stats(&self) -> Vec<ReplicaStats> {
+        self.ladder.stats(&self.config.temperatures, &self.energies)
+    }
+
+    /// Run `cycles` rounds of `exchange_interval` MD steps per replica, each
+    /// followed by a swap attempt; every rank must call it with the same
+    /// `cycles`
+    pub fn run(&mut self, cycles: u64) -> Result<PhaseOutcome, PrismError> {
+        for _ in 0..cycles {
+            advance(&mut self.replicas, self.config.exchange_interval)?;
+            self.exchange()?;
+        }
+
+        let mut telemetry = self.ladder.telemetry(self.stats());
+        telemetry.insert("rank".to_string(), json!(self.transport.rank()));
+        telemetry.insert("ranks".to_string(), json!(self.transport.size()));
+        Ok(PhaseOutcome::Success {
+            message: format!(
+                "Distributed replica exchange: {} replicas on {} ranks, {} exchange rounds",
+                self.config.temperatures.len(),
+                self.transport.size(),
+                self.ladder.exchanges
+            ),
+            telemetry,
+        })
+    }
+
+    /// Gather the energies on rank 0, swap there and spread the result
+    fn exchange(&mut self) -> Result<(), PrismError> {
+        let n = self.config.temperatures.len();
+        let (rank, size) = (self.transport.rank(), self.transport.size());
+        let local: Vec<f64> = self.replicas.iter().map(|e| e.potential_energy()).collect();
+        let before = self.ladder.rung_to_replica.clone();
+
+        // State message: rung assignment, energies, attempts, accepted and
+        // exchange count, all exact in f64
+        let mut state = vec![0.0; 4 * n - 1];
+        if rank == 0 {
+            self.energies[self.range.clone()].copy_from_slice(&local);
+            for source in 1..size {
+                self.transport
+                    .recv(source, &mut self.energies[block(n, size, source)])?;
+            }
+            self.ladder
+                .attempt_swaps(&self.config.temperatures, &self.energies);
+            let ladder = &self.ladder;
+            let counts = ladder.attempts.iter().chain(&ladder.accepted);
+            for (slot, value) in state.iter_mut().zip(
+                ladder
+                    .rung_to_replica
+                    .iter()
+                    .map(|&r| r as f64)
+                    .chain(self.energies.iter().copied())
+                    .chain(counts.map(|&c| c as f64))
+                    .chain([ladder.exchanges as f64]),
+            ) {
+                *slot = value;
+            }
+            for target in 1..size {
+                self.transport.send(target, &state)?;
+            }
+        } else {
+            self.transport.send(0, &local)?;
+            self.transport.recv(0, &mut state)?;
+            let (rungs, rest) = state.split_at(n);
+            let (energies, rest) = rest.split_at(n);
+            let (attempts, rest) = rest.split_at(n - 1);
+            let (accepted, exchanges) = rest.split_at(n - 1);
+            self.ladder.rung_to_replica = rungs.iter().map(|&r| r as usize).collect();
+            self.energies.copy_from_slice(energies);
+            self.ladder.attempts = attempts.iter().map(|&c| c as u64).collect();
+            self.ladder.accepted = accepted.iter().map(|&c| c as u64).collect();
+            self.ladder.exchanges = exchanges[0] as u64;
+        }
+
+        let temperatures = &self.config.temperatures;
+        let rung_of = |rungs: &[usize], replica: usize| {
+            rungs
+                .iter()
+                .position(|&r| r == replica)
+                .expect("every replica has a rung")
+        };
+        for (engine, replica) in self.replicas.iter_mut().zip(self.range.clone()) {
+            let old = rung_of(&before, replica);
+            let new = rung_of(&self.ladder.rung_to_replica, replica);
+            if old != new {
+                retemper(engine, temperatures[old], temperatures[new]);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_io::sovereign_types::Atom;
+    use prism_io::topology::{HarmonicBond, LjParams};
+    use std::sync::mpsc;
+
+    fn topology() -> Topology {
+        let atoms = (0..4)
+            .map(|i| Atom {
+                coords: [i as f32 * 1.53, 0.4 * (i % 2) as f32, 0.0],
+                element: 6,
+                residue_id: 0,
+                atom_type: 1,
+                charge: 0.0,
+                radius: 1.7,
+                _reserved: [0; 4],
+            })
+            .collect();
+        Topology {
+            atoms,
+            masses: vec![12.011; 4],
+            lj: vec![
+                LjParams {
+                    sigma: 3.4,
+                    epsilon: 0.086
+                };
+                4
+            ],
+            bonds: (0..3)
+                .map(|i| HarmonicBond {
+                    i,
+                    j: i + 1,
+                    k: 310.0,
+                    r0: 1.526,
+                })
+                .collect(),
+            exclusions: vec![(0, 1), (1, 2), (2, 3)],
+            ..Default::default()
+        }
+    }
+
+    /// Ranks on threads of one process, with a channel per ordered pair
+    struct ChannelTransport {
+        rank: usize,
+        senders: Vec<mpsc::Sender<Vec<f64>>>,
+        receivers: Vec<mpsc::Receiver<Vec<f64>>>,
+    }
+
+    fn channel_transports(size: usize) -> Vec<ChannelTransport> {
+        // receivers[to][from] gets what senders[from][to] sends
+        let mut receivers: Vec<Vec<_>> = (0..size).map(|_| Vec::new()).collect();
+        let senders: Vec<Vec<_>> = (0..size)
+            .map(|_| {
+                receivers
+                    .iter_mut()
+                    .map(|inbox| {
+                        let (tx, rx) = mpsc::channel();
+                        inbox.push(rx);
+                        tx
+                    })
+                    .collect()
+            })
+            .collect();
+        senders
+            .into_iter()
+            .zip(receivers)
+            .enumerate()
+            .map(|(rank, (senders, receivers))| ChannelTransport {
+                rank,
+                senders,
+                receivers,
+            })
+            .collect()
+    }
+
+    impl ReplicaTransport for ChannelTransport {
+        fn rank(&self) -> usize {
+            self.rank
+        }
+
+        fn size(&self) -> usize {
+            self.senders.len()
+        }
+
+        fn send(&self, to: usize, data: &[f64]) -> Result<(), PrismError> {
+            self.senders[to]
+                .send(data.to_vec())
+                .map_err(|e| PrismError::internal(e.to_string()))
+        }
+
+        fn recv(&self, from: usize, data: &mut [f64]) -> Result<(), PrismError> {
+            let message = self.receivers[from]
+                .recv()
+                .map_err(|e| PrismError::internal(e.to_string()))?;
+            data.copy_from_slice(&message);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_geometric_ladder() {
+        let t = ReplicaExchangeConfig::geometric(1.0, 8.0, 4);
+        assert_eq!(t.len(), 4);
+        assert!((t[1] - 2.0).abs() < 1e-5 && (t[3] - 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_swaps_permute_temperatures() {
+        let config = ReplicaExchangeConfig {
+            temperatures: ReplicaExchangeConfig::geometric(0.3, 0.6, 3),
+            exchange_interval: 20,
+            seed: 7,
+        };
+        let md = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            dt: 0.001,
+            ..Default::default()
+        };
+        let mut remd =
+            ReplicaExchangeController::from_topology(config.clone(), &md, &topology()).unwrap();
+        let PhaseOutcome::Success { telemetry, .. } = remd.run(10).unwrap() else {
+            panic!("replica exchange failed");
+        };
+        assert_eq!(remd.exchanges(), 10);
+        assert_eq!(telemetry["replicas"].as_array().unwrap().len(), 3);
+
+        // Every rung holds exactly one engine, running at that rung's temperature
+        let mut seen: Vec<usize> = remd.stats().iter().map(|s| s.replica).collect();
+        seen.sort_unstable();
+        assert_eq!(seen, vec![0, 1, 2]);
+        for (k, &t) in config.temperatures.iter().enumerate() {
+            assert_eq!(remd.replica_at(k).config().temp_start, t);
+        }
+        let attempts: u64 = remd.stats().iter().map(|s| s.attempts).sum();
+        assert_eq!(attempts, 10);
+    }
+
+    #[test]
+    fn test_mixed_host_and_gpu_replicas_advance_together() {
+        let config = ReplicaExchangeConfig {
+            temperatures: ReplicaExchangeConfig::geometric(0.3, 0.6, 3),
+            exchange_interval: 15,
+            seed: 5,
+        };
+        // Without a device the GPU-flagged engine integrates on the host
+        let replicas = [false, true, false]
+            .into_iter()
+            .enumerate()
+            .map(|(k, use_gpu)| {
+                let md = MolecularDynamicsConfig {
+                    use_gpu,
+                    spring_k: 0.0,
+                    dt: 0.001,
+                    seed: k as u64 + 1,
+                    ..Default::default()
+                };
+                MolecularDynamicsEngine::from_topology(md, &topology()).unwrap()
+            })
+            .collect();
+        let mut remd = ReplicaExchangeController::new(config, replicas).unwrap();
+        remd.run(4).unwrap();
+
+        assert_eq!(remd.exchanges(), 4);
+        for engine in remd.replicas() {
+            assert_eq!(engine.get_statistics().current_step, 60);
+        }
+    }
+
+    #[test]
+    fn test_distributed_matches_single_process() {
+        let config = ReplicaExchangeConfig {
+            temperatures: ReplicaExchangeConfig::geometric(0.3, 0.9, 5),
+            exchange_interval: 10,
+            seed: 11,
+        };
+        let md = MolecularDynamicsConfig {
+            use_gpu: false,
+            spring_k: 0.0,
+            dt: 0.001,
+            ..Default::default()
+        };
+        let topology = topology();
+        let mut single =
+            ReplicaExchangeController::from_topology(config.clone(), &md, &topology).unwrap();
+        single.run(8).unwrap();
+
+        let ranks: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = channel_transports(2)
+                .into_iter()
+                .map(|transport| {
+                    let (config, md, topology) = (config.clone(), &md, &topology);
+                    scope.spawn(move || {
+                        let mut remd = DistributedReplicaExchange::from_topology(
+                            config, md, topology, transport,
+                        )
+                        .unwrap();
+                        remd.run(8).unwrap();
+                        remd
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(ranks[0].local_range(), 0..2);
+        assert_eq!(ranks[1].local_range(), 2..5);
+        for remd in &ranks {
+            assert_eq!(remd.exchanges(), 8);
+            assert_eq!(remd.rung_to_replica(), &single.ladder.rung_to_replica[..]);
+            for (a, b) in remd.stats().iter().zip(single.stats()) {
+                assert_eq!(
+                    (a.replica, a.attempts, a.accepted),
+                    (b.replica, b.attempts, b.accepted)
+                );
+                assert!((a.potential_energy - b.potential_energy).abs() < 1e-9);
+            }
+            // Local engines run at the temperature of their current rung
+            for (engine, replica) in remd.local_replicas().iter().zip(remd.local_range()) {
+                let rung = remd
+                    .rung_to_replica()
+                    .iter()
+                    .position(|&r| r == replica)
+                    .unwrap();
+                assert_eq!(engine.config().temp_start, config.temperatures[rung]);
+            }
+        }
+
+        let mut too_many = channel_transports(6);
+        let transport = too_many.remove(0);
+        assert!(
+            DistributedReplicaExchange::from_topology(config, &md, &topology, transport).is_err()
+        );
+    }
+}
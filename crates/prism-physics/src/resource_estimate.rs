@@ -0,0 +1,371 @@
+//! # Resource Estimate - Dry Runs
+//! [`MolecularDynamicsEngine::estimate`] sizes a dynamics run from its
+//! configuration and structure without building the engine: device memory
+//! of the GPU integrator, host memory, trajectory file size and wall-clock
+//! time. Schedulers use it to turn away jobs that cannot fit before
+//! anything is allocated (see [`ResourceEstimate::check`] and
+//! [`crate::scheduler::JobSpec::for_estimate`]).
+//!
+//! Memory figures follow the engine's allocations. The step rate comes
+//! from a pair-throughput model and is only good to an order of
+//! magnitude; replace it with [`ResourceEstimate::with_step_rate`] once a
+//! run on the same hardware has been timed.
+
+use crate::molecular_dynamics::{
+    DeviceBackend, MolecularDynamicsConfig, MolecularDynamicsEngine, VramFallback, RNG_STATE_BYTES,
+};
+use crate::precision::Precision;
+use prism_core::PrismError;
+use prism_io::selection::SelectionContext;
+use prism_io::sovereign_types::Atom;
+use serde::{Deserialize, Serialize};
+
+/// Device frame snapshots of the GPU integrator (`frame_stream::FRAME_SLOTS`)
+const FRAME_SLOTS: usize = 2;
+/// Sub-allocation alignment of the VRAM pool (`prism_gpu::memory::POOL_ALIGNMENT`)
+const POOL_ALIGNMENT: usize = 256;
+
+#[cfg(feature = "cuda")]
+const _: () = assert!(
+    FRAME_SLOTS == crate::frame_stream::FRAME_SLOTS
+        && POOL_ALIGNMENT == prism_gpu::memory::POOL_ALIGNMENT
+);
+
+/// Nonbonded pairs per second of one host thread
+const HOST_PAIR_RATE: f64 = 2.0e7;
+/// Integrated atoms per second of one host thread
+const HOST_ATOM_RATE: f64 = 5.0e7;
+/// Nonbonded pairs per second of a GPU
+const GPU_PAIR_RATE: f64 = 1.0e10;
+/// Integrated atoms per second of a GPU
+const GPU_ATOM_RATE: f64 = 1.0e9;
+/// Launch and synchronization cost of one GPU step (s)
+const GPU_STEP_OVERHEAD: f64 = 1.0e-5;
+/// Padding of the bounding box used for the atom density (Å)
+const DENSITY_PADDING: f32 = 3.0;
+
+/// Expected resource use of a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceEstimate {
+    pub num_atoms: usize,
+    pub steps: u64,
+    /// Whether the integrator runs on the GPU
+    pub gpu: bool,
+    /// Device integrator state: positions, anchors, velocities, bias and
+    /// RNG states (bytes)
+    pub workspace_bytes: usize,
+    /// Device frame snapshot buffers (bytes)
+    pub frame_buffer_bytes: usize,
+    /// Host state, force buffers and neighbor list (bytes)
+    pub host_bytes: usize,
+    /// Frames written to the trajectory
+    pub trajectory_frames: u64,
+    /// Size of the trajectory file (bytes)
+    pub trajectory_bytes: u64,
+    /// Nonbonded pairs within the cutoff, per atom
+    pub pairs_per_atom: f64,
+    pub steps_per_second: f64,
+    pub wall_clock_seconds: f64,
+}
+
+impl ResourceEstimate {
+    /// Device memory of the run (bytes)
+    pub fn vram_bytes(&self) -> usize {
+        self.workspace_bytes + self.frame_buffer_bytes
+    }
+
+    /// Replace the modelled step rate with a measured one
+    pub fn with_step_rate(mut self, steps_per_second: f64) -> Self {
+        self.steps_per_second = steps_per_second;
+        self.wall_clock_seconds = wall_clock(self.steps, steps_per_second);
+        self
+    }
+
+    /// Whether a run of `config` can start: the GPU reservation must fit
+    /// the configured memory limits and, when given, `vram_available`
+    /// bytes of free device memory. Reservations the engine would recover
+    /// from under [`VramFallback`] pass.
+    pub fn check(
+        &self,
+        config: &MolecularDynamicsConfig,
+        vram_available: Option<usize>,
+    ) -> Result<(), PrismError> {
+        if !self.gpu {
+            return Ok(());
+        }
+        if self.workspace_bytes > config.max_workspace_memory {
+            return Err(PrismError::config(format!(
+                "GPU workspace needs {} bytes, max_workspace_memory is {}",
+                self.workspace_bytes, config.max_workspace_memory
+            )));
+        }
+        let slot_bytes = self.frame_buffer_bytes / FRAME_SLOTS;
+        let mut rejection = String::new();
+        for frame_slots in config.vram_fallback.frame_slot_attempts(FRAME_SLOTS) {
+            let frame_bytes = frame_slots * slot_bytes;
+            let total = self.workspace_bytes + frame_bytes;
+            if frame_bytes > config.max_trajectory_memory {
+                rejection = format!(
+                    "GPU frame buffers need {} bytes, max_trajectory_memory is {}",
+                    frame_bytes, config.max_trajectory_memory
+                );
+            } else if vram_available.is_some_and(|available| total > available) {
+                rejection = format!(
+                    "GPU reservation of {} bytes exceeds the {} bytes available",
+                    total,
+                    vram_available.unwrap_or_default()
+                );
+            } else {
+                return Ok(());
+            }
+        }
+        match config.vram_fallback {
+            VramFallback::Cpu => Ok(()),
+            _ => Err(PrismError::gpu("vram_pool", rejection)),
+        }
+    }
+}
+
+fn wall_clock(steps: u64, steps_per_second: f64) -> f64 {
+    if steps_per_second > 0.0 {
+        steps as f64 / steps_per_second
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Atoms within `reach` of an atom, from the density of the padded
+/// bounding box of `atoms`
+fn neighbors_within(atoms: &[Atom], reach: f32) -> f64 {
+    let Some(first) = atoms.first() else {
+        return 0.0;
+    };
+    let (mut lo, mut hi) = (first.coords, first.coords);
+    for atom in atoms {
+        for d in 0..3 {
+            lo[d] = lo[d].min(atom.coords[d]);
+            hi[d] = hi[d].max(atom.coords[d]);
+        }
+    }
+    let volume: f64 = (0..3)
+        .map(|d| (hi[d] - lo[d] + DENSITY_PADDING) as f64)
+        .product();
+    let sphere = 4.0 / 3.0 * std::f64::consts::PI * (reach as f64).powi(3);
+    (atoms.len() as f64 / volume * sphere).min((atoms.len() - 1) as f64)
+}
+
+impl MolecularDynamicsEngine {
+    /// Estimate the resources of running `config` on `structure` (PTB or
+    /// PDB, as for [`Self::from_sovereign_buffer`]) for `max_steps` steps,
+    /// without building the engine
+    pub fn estimate(
+        config: &MolecularDynamicsConfig,
+        structure: &[u8],
+    ) -> Result<ResourceEstimate, PrismError> {
+        config.validate()?;
+        let atoms = Self::parse_protein_structure(structure)?;
+        let n = atoms.len();
+        let ff = &config.force_field;
+        let gpu = cfg!(feature = "cuda")
+            && config.use_gpu
+            && config.backend == DeviceBackend::Cuda
+            && config.precision != Precision::Double;
+
+        let buffer_bytes = n * 4 * std::mem::size_of::<f32>();
+        let aligned = |bytes: usize| bytes.next_multiple_of(POOL_ALIGNMENT);
+        let (workspace_bytes, frame_buffer_bytes) = if gpu {
+            (
+                4 * aligned(buffer_bytes) + aligned(n * RNG_STATE_BYTES),
+                FRAME_SLOTS * aligned(buffer_bytes),
+            )
+        } else {
+            (0, 0)
+        };
+
+        // Positions, velocities, anchors, bias and forces in Float4 stride,
+        // the residue map, atom records and the neighbor list with its
+        // reference positions
+        let pairs_per_atom = neighbors_within(&atoms, ff.cutoff) / 2.0;
+        let listed_pairs = neighbors_within(&atoms, ff.cutoff + ff.neighbor_skin) / 2.0;
+        let mut host_bytes = n
+            * (6 * 4 * std::mem::size_of::<f32>() + 8 + std::mem::size_of::<Atom>())
+            + (n as f64 * listed_pairs) as usize * 4;
+        if config.precision == Precision::Double {
+            host_bytes += 2 * n * 4 * std::mem::size_of::<f64>();
+        }
+
+        let steps = config.max_steps;
+        let (trajectory_frames, trajectory_bytes) = match &config.trajectory {
+            Some(trajectory) => {
+                let frames = steps / trajectory.stride.max(1);
+                let written = match &trajectory.selection {
+                    Some(expression) => SelectionContext::from_atoms(&atoms)
+                        .select(expression)
+                        .map_err(|e| {
+                            PrismError::config(format!(
+                                "Trajectory selection '{}': {}",
+                                expression, e
+                            ))
+                        })?
+                        .len(),
+                    None => n,
+                };
+                let bytes = trajectory.format.file_bytes(written, frames, false, !gpu);
+                (frames, bytes)
+            }
+            None => (0, 0),
+        };
+
+        let pairs = n as f64 * pairs_per_atom;
+        let step_seconds = if gpu {
+            GPU_STEP_OVERHEAD + n as f64 / GPU_ATOM_RATE + pairs / GPU_PAIR_RATE
+        } else {
+            let threads = config
+                .num_threads
+                .unwrap_or_else(rayon::current_num_threads);
+            (pairs / HOST_PAIR_RATE + n as f64 / HOST_ATOM_RATE) / threads.max(1) as f64
+        };
+        let steps_per_second = 1.0 / step_seconds.max(f64::MIN_POSITIVE);
+
+        Ok(ResourceEstimate {
+            num_atoms: n,
+            steps,
+            gpu,
+            workspace_bytes,
+            frame_buffer_bytes,
+            host_bytes,
+            trajectory_frames,
+            trajectory_bytes,
+            pairs_per_atom,
+            steps_per_second,
+            wall_clock_seconds: wall_clock(steps, steps_per_second),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::molecular_dynamics::MolecularDynamicsConfigBuilder;
+    use prism_io::trajectory::{TrajectoryConfig, TrajectoryFormat};
+
+    /// Cubic lattice of `side^3` atoms 3 Å apart
+    fn lattice(side: usize) -> String {
+        let mut pdb = String::new();
+        for i in 0..side * side * side {
+            let (x, y, z) = (i % side, i / side % side, i / (side * side));
+            pdb.push_str(&format!(
+                "ATOM  {:>5}  CA  ALA A{:>4}    {:>8.3}{:>8.3}{:>8.3}  1.00  0.00           C\n",
+                i + 1,
+                i % 9999 + 1,
+                x as f32 * 3.0,
+                y as f32 * 3.0,
+                z as f32 * 3.0
+            ));
+        }
+        pdb
+    }
+
+    #[test]
+    fn test_estimate_sizes_host_run() {
+        let structure = lattice(6);
+        let config = MolecularDynamicsConfigBuilder::quick_test()
+            .use_gpu(false)
+            .max_steps(10_000)
+            .num_threads(2)
+            .trajectory(TrajectoryConfig {
+                path: "run.dcd".into(),
+                format: TrajectoryFormat::Dcd,
+                stride: 100,
+                precision: 1000.0,
+                selection: Some("index 0 to 9".to_string()),
+                append: false,
+            })
+            .build()
+            .unwrap();
+        let estimate = MolecularDynamicsEngine::estimate(&config, structure.as_bytes()).unwrap();
+        assert_eq!(estimate.num_atoms, 216);
+        assert!(!estimate.gpu);
+        assert_eq!(estimate.vram_bytes(), 0);
+        assert!(estimate.host_bytes > 216 * 6 * 16);
+        assert!(estimate.pairs_per_atom > 0.0 && estimate.pairs_per_atom < 108.0);
+        assert_eq!(estimate.trajectory_frames, 100);
+        assert_eq!(
+            estimate.trajectory_bytes,
+            TrajectoryFormat::Dcd.file_bytes(10, 100, false, false)
+        );
+        assert!(estimate.wall_clock_seconds > 0.0);
+        assert!((estimate.wall_clock_seconds * estimate.steps_per_second - 10_000.0).abs() < 1e-6);
+        let measured = estimate.clone().with_step_rate(500.0);
+        assert!((measured.wall_clock_seconds - 20.0).abs() < 1e-9);
+        estimate.check(&config, Some(0)).unwrap();
+
+        // More threads, faster; larger systems, slower
+        let serial = MolecularDynamicsEngine::estimate(
+            &MolecularDynamicsConfig {
+                num_threads: Some(1),
+                ..config.clone()
+            },
+            structure.as_bytes(),
+        )
+        .unwrap();
+        assert!(serial.steps_per_second < estimate.steps_per_second);
+        let larger = MolecularDynamicsEngine::estimate(&config, lattice(8).as_bytes()).unwrap();
+        assert!(larger.steps_per_second < estimate.steps_per_second);
+        assert!(larger.host_bytes > estimate.host_bytes);
+
+        let mut bad = config.clone();
+        bad.trajectory.as_mut().unwrap().selection = Some("resname (".to_string());
+        assert!(MolecularDynamicsEngine::estimate(&bad, structure.as_bytes()).is_err());
+        assert!(MolecularDynamicsEngine::estimate(&config, b"").is_err());
+    }
+
+    #[test]
+    fn test_check_rejects_gpu_reservations() {
+        let config = MolecularDynamicsConfigBuilder::quick_test()
+            .memory_limits(1 << 20, 1 << 20)
+            .build()
+            .unwrap();
+        let estimate = ResourceEstimate {
+            num_atoms: 1000,
+            steps: 1000,
+            gpu: true,
+            workspace_bytes: 128_000,
+            frame_buffer_bytes: 32_000,
+            host_bytes: 0,
+            trajectory_frames: 0,
+            trajectory_bytes: 0,
+            pairs_per_atom: 0.0,
+            steps_per_second: 1.0,
+            wall_clock_seconds: 1000.0,
+        };
+        let error = |config: &MolecularDynamicsConfig, available| {
+            estimate
+                .check(config, available)
+                .err()
+                .map(|e| e.to_string())
+        };
+        let strict = MolecularDynamicsConfig {
+            vram_fallback: VramFallback::Error,
+            ..config.clone()
+        };
+        assert_eq!(error(&strict, None), None);
+        assert_eq!(error(&strict, Some(160_000)), None);
+        assert!(error(&strict, Some(150_000)).unwrap().contains("160000"));
+        // One frame buffer fits, two do not
+        let shrink = MolecularDynamicsConfig {
+            vram_fallback: VramFallback::ShrinkTrajectory,
+            ..config.clone()
+        };
+        assert_eq!(error(&shrink, Some(150_000)), None);
+        // The host takes over
+        assert_eq!(error(&config, Some(0)), None);
+        let small = MolecularDynamicsConfig {
+            max_workspace_memory: 100_000,
+            ..config
+        };
+        assert!(error(&small, None)
+            .unwrap()
+            .contains("max_workspace_memory"));
+    }
+}
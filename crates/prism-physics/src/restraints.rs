@@ -0,0 +1,757 @@
+//! # Restraints
+//! Harmonic position restraints tying the atoms of a
+//! [selection](prism_io::selection) to reference coordinates,
+//! `E = ½ k |r - r₀|²` with a force constant per selection (e.g. stiff on
+//! backbone heavy atoms, soft on side chains). References default to the
+//! coordinates the engine was built from. Restraints act on every host force
+//! evaluation, so they hold through minimization, equilibration and
+//! production alike, and their energy is reported apart from the force
+//! field. Units: Å, kcal/mol.
+//!
+//! NMR-style distance, angle and dihedral restraints are flat-bottom
+//! harmonic wells, zero between `lower` and `upper` and `k (x - bound)²`
+//! outside, following the bonded-term convention. They are read from a
+//! restraint file with one restraint per line (`#` starts a comment):
+//!
+//! ```text
+//! # kind    atoms               lower  upper  k
+//! distance  A:10:HA  A:52:HN    1.8    5.0    10.0
+//! angle     4 6 8               100    120    50.0
+//! dihedral  2:C 3:N 3:CA 3:C    -80    -40    20.0
+//! ```
+//!
+//! Atoms are 0-based indices or `[chain:]resid:name` references that must
+//! match exactly one atom. Distances are in Å with `k` in kcal/mol/Å²,
+//! angles and dihedrals in degrees with `k` in kcal/mol/rad².
+
+use crate::bonded::dihedral_gradient;
+use prism_core::PrismError;
+use prism_io::selection::SelectionContext;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::path::Path;
+
+/// One restrained selection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionRestraintConfig {
+    /// Atom selection, e.g. `"backbone and not hydrogen"`
+    pub selection: String,
+    /// Force constant (kcal/mol/Å²)
+    pub force_constant: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RestraintGroup {
+    config: PositionRestraintConfig,
+    atoms: Vec<u32>,
+    reference: Vec<[f32; 3]>,
+}
+
+/// Resolved position restraints
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PositionRestraints {
+    groups: Vec<RestraintGroup>,
+}
+
+impl PositionRestraints {
+    /// Resolve every selection against `context`, restraining to the
+    /// Float4-stride `reference` positions
+    pub fn new(
+        configs: &[PositionRestraintConfig],
+        context: &SelectionContext,
+        reference: &[f32],
+    ) -> Result<Self, PrismError> {
+        let mut groups = Vec::with_capacity(configs.len());
+        for config in configs {
+            if !(config.force_constant.is_finite() && config.force_constant >= 0.0) {
+                return Err(PrismError::config(format!(
+                    "Position restraint '{}' has invalid force constant {}",
+                    config.selection, config.force_constant
+                )));
+            }
+            let atoms = context
+                .select(&config.selection)
+                .map_err(|e| PrismError::config(e.to_string()))?;
+            if atoms.is_empty() {
+                return Err(PrismError::config(format!(
+                    "Position restraint selection '{}' matches no atoms",
+                    config.selection
+                )));
+            }
+            groups.push(RestraintGroup {
+                config: config.clone(),
+                reference: load(reference, &atoms)?,
+                atoms,
+            });
+        }
+        Ok(Self { groups })
+    }
+
+    /// Whether no selection is restrained
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Number of restrained selections
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Restrained atoms of selection `group`
+    pub fn atoms(&self, group: usize) -> &[u32] {
+        &self.groups[group].atoms
+    }
+
+    /// Settings of each selection, in input order
+    pub fn configs(&self) -> impl Iterator<Item = &PositionRestraintConfig> {
+        self.groups.iter().map(|g| &g.config)
+    }
+
+    /// Restrain to new Float4-stride reference positions
+    pub fn set_reference(&mut self, reference: &[f32]) -> Result<(), PrismError> {
+        for group in &mut self.groups {
+            group.reference = load(reference, &group.atoms)?;
+        }
+        Ok(())
+    }
+
+    /// Add restraint forces to Float4-stride `forces`; returns the energy of
+    /// each selection (kcal/mol)
+    pub fn compute(&self, positions: &[f32], forces: &mut [f32]) -> Vec<f64> {
+        self.groups
+            .iter()
+            .map(|group| {
+                let k = group.config.force_constant;
+                let mut energy = 0.0f64;
+                for (&i, r0) in group.atoms.iter().zip(&group.reference) {
+                    let offset = i as usize * 4;
+                    for d in 0..3 {
+                        let disp = positions[offset + d] - r0[d];
+                        energy += 0.5 * (k * disp * disp) as f64;
+                        forces[offset + d] -= k * disp;
+                    }
+                }
+                energy
+            })
+            .collect()
+    }
+
+    /// RMS deviation of each selection from its reference (Å)
+    pub fn deviations(&self, positions: &[f32]) -> Vec<f64> {
+        self.groups
+            .iter()
+            .map(|group| {
+                let sum: f64 = group
+                    .atoms
+                    .iter()
+                    .zip(&group.reference)
+                    .map(|(&i, r0)| {
+                        let p = &positions[i as usize * 4..i as usize * 4 + 3];
+                        (0..3).map(|d| ((p[d] - r0[d]) as f64).powi(2)).sum::<f64>()
+                    })
+                    .sum();
+                (sum / group.atoms.len() as f64).sqrt()
+            })
+            .collect()
+    }
+}
+
+fn load(positions: &[f32], atoms: &[u32]) -> Result<Vec<[f32; 3]>, PrismError> {
+    atoms
+        .iter()
+        .map(|&i| {
+            let offset = i as usize * 4;
+            positions
+                .get(offset..offset + 3)
+                .map(|p| [p[0], p[1], p[2]])
+                .ok_or_else(|| {
+                    PrismError::validation(format!(
+                        "Restrained atom {} has no reference position",
+                        i
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Geometric quantity held by a [`GeometricRestraint`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestraintKind {
+    Distance,
+    Angle,
+    Dihedral,
+}
+
+impl RestraintKind {
+    /// Atoms defining the quantity
+    pub fn num_atoms(self) -> usize {
+        match self {
+            RestraintKind::Distance => 2,
+            RestraintKind::Angle => 3,
+            RestraintKind::Dihedral => 4,
+        }
+    }
+
+    fn parse(word: &str) -> Option<Self> {
+        match word.to_ascii_lowercase().as_str() {
+            "distance" | "dist" | "bond" => Some(RestraintKind::Distance),
+            "angle" => Some(RestraintKind::Angle),
+            "dihedral" | "torsion" => Some(RestraintKind::Dihedral),
+            _ => None,
+        }
+    }
+}
+
+/// Flat-bottom harmonic restraint on a distance, angle or dihedral
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeometricRestraint {
+    pub kind: RestraintKind,
+    pub atoms: Vec<u32>,
+    /// Lower bound of the flat bottom (Å or degrees)
+    pub lower: f64,
+    /// Upper bound of the flat bottom (Å or degrees); a dihedral range may
+    /// wrap through ±180°, e.g. 170 to -170
+    pub upper: f64,
+    /// kcal/mol/Å² or kcal/mol/rad²
+    pub force_constant: f64,
+}
+
+impl GeometricRestraint {
+    /// Current distance (Å), angle or dihedral (degrees)
+    pub fn value(&self, positions: &[f32]) -> f64 {
+        let value = self.geometry(positions).map_or(0.0, |(x, _)| x);
+        match self.kind {
+            RestraintKind::Distance => value,
+            _ => value.to_degrees(),
+        }
+    }
+
+    /// Distance outside the flat bottom (Å or degrees, 0 inside)
+    pub fn violation(&self, positions: &[f32]) -> f64 {
+        self.geometry(positions)
+            .map_or(0.0, |(x, _)| self.excess(x).abs())
+    }
+
+    /// Signed excess over the nearest bound in internal units (Å or rad)
+    fn excess(&self, x: f64) -> f64 {
+        match self.kind {
+            RestraintKind::Distance => {
+                if x < self.lower {
+                    x - self.lower
+                } else if x > self.upper {
+                    x - self.upper
+                } else {
+                    0.0
+                }
+            }
+            RestraintKind::Angle => {
+                let (lower, upper) = (self.lower.to_radians(), self.upper.to_radians());
+                if x < lower {
+                    x - lower
+                } else if x > upper {
+                    x - upper
+                } else {
+                    0.0
+                }
+            }
+            RestraintKind::Dihedral => {
+                let lower = self.lower.to_radians();
+                let mut upper = self.upper.to_radians();
+                if upper < lower {
+                    upper += 2.0 * PI;
+                }
+                let half_width = 0.5 * (upper - lower);
+                let deviation = wrap(x - 0.5 * (lower + upper));
+                if deviation > half_width {
+                    deviation - half_width
+                } else if deviation < -half_width {
+                    deviation + half_width
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// Quantity (Å or rad) and its gradient with respect to each atom
+    fn geometry(&self, positions: &[f32]) -> Option<(f64, Vec<Vec3>)> {
+        let x: Vec<Vec3> = self.atoms.iter().map(|&i| load3(positions, i)).collect();
+        match self.kind {
+            RestraintKind::Distance => {
+                let d = sub(x[1], x[0]);
+                let r = dot(d, d).sqrt();
+                (r > 1e-12).then(|| {
+                    let u = d.map(|c| c / r);
+                    (r, vec![u.map(|c| -c), u])
+                })
+            }
+            RestraintKind::Angle => {
+                let u = sub(x[0], x[1]);
+                let v = sub(x[2], x[1]);
+                let (lu, lv) = (dot(u, u).sqrt(), dot(v, v).sqrt());
+                if lu < 1e-12 || lv < 1e-12 {
+                    return None;
+                }
+                let cos = (dot(u, v) / (lu * lv)).clamp(-1.0, 1.0);
+                let sin = (1.0 - cos * cos).sqrt().max(1e-8);
+                // dθ/dx_i = -(v/(|u||v|) - cos u/|u|²) / sin
+                let gi: Vec3 =
+                    [0, 1, 2].map(|d| -(v[d] / (lu * lv) - cos * u[d] / (lu * lu)) / sin);
+                let gk: Vec3 =
+                    [0, 1, 2].map(|d| -(u[d] / (lu * lv) - cos * v[d] / (lv * lv)) / sin);
+                let gj: Vec3 = [0, 1, 2].map(|d| -gi[d] - gk[d]);
+                Some((cos.acos(), vec![gi, gj, gk]))
+            }
+            RestraintKind::Dihedral => {
+                dihedral_gradient(x[0], x[1], x[2], x[3]).map(|(phi, g)| (phi, g.to_vec()))
+            }
+        }
+    }
+}
+
+/// End-of-run report for one [`GeometricRestraint`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestraintViolation {
+    /// Position in the restraint list
+    pub index: usize,
+    pub kind: RestraintKind,
+    pub atoms: Vec<u32>,
+    pub lower: f64,
+    pub upper: f64,
+    /// Final value (Å or degrees)
+    pub value: f64,
+    /// Final violation (Å or degrees)
+    pub violation: f64,
+    /// Mean violation over the recorded frames
+    pub mean_violation: f64,
+    /// Largest violation over the recorded frames
+    pub max_violation: f64,
+}
+
+/// Distance, angle and dihedral restraints with violation statistics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeometricRestraints {
+    restraints: Vec<GeometricRestraint>,
+    violation_sums: Vec<f64>,
+    violation_max: Vec<f64>,
+    samples: usize,
+}
+
+impl GeometricRestraints {
+    pub fn new(restraints: Vec<GeometricRestraint>) -> Result<Self, PrismError> {
+        for (n, r) in restraints.iter().enumerate() {
+            if r.atoms.len() != r.kind.num_atoms() {
+                return Err(PrismError::config(format!(
+                    "Restraint {} ({:?}) needs {} atoms, got {}",
+                    n,
+                    r.kind,
+                    r.kind.num_atoms(),
+                    r.atoms.len()
+                )));
+            }
+            let bounds_ok = r.kind == RestraintKind::Dihedral || r.lower <= r.upper;
+            if !(bounds_ok && r.force_constant.is_finite() && r.force_constant >= 0.0) {
+                return Err(PrismError::config(format!(
+                    "Restraint {} has invalid bounds [{}, {}] or force constant {}",
+                    n, r.lower, r.upper, r.force_constant
+                )));
+            }
+        }
+        let n = restraints.len();
+        Ok(Self {
+            restraints,
+            violation_sums: vec![0.0; n],
+            violation_max: vec![0.0; n],
+            samples: 0,
+        })
+    }
+
+    /// Parse a restraint file, resolving atom references against `context`
+    pub fn parse(text: &str, context: &SelectionContext) -> Result<Self, PrismError> {
+        let mut restraints = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| {
+                PrismError::config(format!(
+                    "Restraint file line {}: {}",
+                    line_number + 1,
+                    message
+                ))
+            };
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let kind = RestraintKind::parse(tokens[0])
+                .ok_or_else(|| error(format!("unknown restraint kind '{}'", tokens[0])))?;
+            let n = kind.num_atoms();
+            if tokens.len() != 1 + n + 3 {
+                return Err(error(format!("expected {} atoms, lower, upper and k", n)));
+            }
+            let atoms = tokens[1..=n]
+                .iter()
+                .map(|token| resolve_atom(token, context).map_err(&error))
+                .collect::<Result<Vec<u32>, PrismError>>()?;
+            let numbers = tokens[n + 1..]
+                .iter()
+                .map(|t| {
+                    t.parse::<f64>()
+                        .map_err(|_| error(format!("'{}' is not a number", t)))
+                })
+                .collect::<Result<Vec<f64>, PrismError>>()?;
+            restraints.push(GeometricRestraint {
+                kind,
+                atoms,
+                lower: numbers[0],
+                upper: numbers[1],
+                force_constant: numbers[2],
+            });
+        }
+        Self::new(restraints)
+    }
+
+    /// Read a restraint file (see the module documentation)
+    pub fn read(path: impl AsRef<Path>, context: &SelectionContext) -> Result<Self, PrismError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            PrismError::config(format!(
+                "Failed to read restraint file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::parse(&text, context)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.restraints.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.restraints.len()
+    }
+
+    pub fn restraints(&self) -> &[GeometricRestraint] {
+        &self.restraints
+    }
+
+    /// Add restraint forces to Float4-stride `forces`; returns the energy
+    /// (kcal/mol)
+    pub fn compute(&self, positions: &[f32], forces: &mut [f32]) -> f64 {
+        let mut energy = 0.0;
+        for r in &self.restraints {
+            let Some((x, gradient)) = r.geometry(positions) else {
+                continue;
+            };
+            let excess = r.excess(x);
+            if excess == 0.0 {
+                continue;
+            }
+            energy += r.force_constant * excess * excess;
+            let de = 2.0 * r.force_constant * excess;
+            for (&i, g) in r.atoms.iter().zip(&gradient) {
+                let offset = i as usize * 4;
+                for d in 0..3 {
+                    forces[offset + d] -= (de * g[d]) as f32;
+                }
+            }
+        }
+        energy
+    }
+
+    /// Accumulate violation statistics for one frame
+    pub fn record(&mut self, positions: &[f32]) {
+        for (n, r) in self.restraints.iter().enumerate() {
+            let violation = r.violation(positions);
+            self.violation_sums[n] += violation;
+            self.violation_max[n] = self.violation_max[n].max(violation);
+        }
+        self.samples += 1;
+    }
+
+    /// Frames accumulated by [`Self::record`]
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Per-restraint report at `positions`, with statistics over the
+    /// recorded frames (`positions` alone when none were recorded)
+    pub fn violations(&self, positions: &[f32]) -> Vec<RestraintViolation> {
+        self.restraints
+            .iter()
+            .enumerate()
+            .map(|(n, r)| {
+                let violation = r.violation(positions);
+                let (mean, max) = if self.samples == 0 {
+                    (violation, violation)
+                } else {
+                    (
+                        self.violation_sums[n] / self.samples as f64,
+                        self.violation_max[n],
+                    )
+                };
+                RestraintViolation {
+                    index: n,
+                    kind: r.kind,
+                    atoms: r.atoms.clone(),
+                    lower: r.lower,
+                    upper: r.upper,
+                    value: r.value(positions),
+                    violation,
+                    mean_violation: mean,
+                    max_violation: max,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 0-based index or `[chain:]resid:name`
+fn resolve_atom(token: &str, context: &SelectionContext) -> Result<u32, String> {
+    if let Ok(index) = token.parse::<u32>() {
+        return if (index as usize) < context.len() {
+            Ok(index)
+        } else {
+            Err(format!(
+                "atom index {} out of range ({} atoms)",
+                index,
+                context.len()
+            ))
+        };
+    }
+    let parts: Vec<&str> = token.split(':').collect();
+    let expression = match parts.as_slice() {
+        [resid, name] => format!("resid {} and name {}", resid, name),
+        [chain, resid, name] => format!("chain {} and resid {} and name {}", chain, resid, name),
+        _ => {
+            return Err(format!(
+                "'{}' is not an atom index or [chain:]resid:name",
+                token
+            ))
+        }
+    };
+    match context
+        .select(&expression)
+        .map_err(|e| e.to_string())?
+        .as_slice()
+    {
+        [atom] => Ok(*atom),
+        [] => Err(format!("'{}' matches no atom", token)),
+        matches => Err(format!("'{}' matches {} atoms", token, matches.len())),
+    }
+}
+
+type Vec3 = [f64; 3];
+
+fn load3(p: &[f32], i: u32) -> Vec3 {
+    let o = i as usize * 4;
+    [p[o] as f64, p[o + 1] as f64, p[o + 2] as f64]
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Angle difference wrapped into (-π, π]
+fn wrap(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prism_io::selection::SelectionAtom;
+
+    fn context() -> SelectionContext {
+        SelectionContext::new(
+            ["N", "CA", "CB"]
+                .iter()
+                .map(|name| SelectionAtom {
+                    name: name.to_string(),
+                    residue_name: "ALA".to_string(),
+                    residue_number: 1,
+                    residue_index: 0,
+                    chain: 'A',
+                    element: if *name == "N" { 7 } else { 6 },
+                    position: [0.0; 3],
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_energy_and_forces_per_selection() {
+        let configs = [
+            PositionRestraintConfig {
+                selection: "backbone".to_string(),
+                force_constant: 10.0,
+            },
+            PositionRestraintConfig {
+                selection: "name CB".to_string(),
+                force_constant: 1.0,
+            },
+        ];
+        let reference = vec![0.0f32; 12];
+        let restraints = PositionRestraints::new(&configs, &context(), &reference).unwrap();
+        assert_eq!((restraints.len(), restraints.atoms(0)), (2, &[0u32, 1][..]));
+
+        let mut positions = reference.clone();
+        positions[4] = 0.5; // CA x
+        positions[8 + 2] = -2.0; // CB z
+        let mut forces = vec![0.0; 12];
+        let energies = restraints.compute(&positions, &mut forces);
+        assert!((energies[0] - 1.25).abs() < 1e-9 && (energies[1] - 2.0).abs() < 1e-9);
+        assert_eq!((forces[4], forces[10]), (-5.0, 2.0));
+
+        // Force is minus the energy gradient
+        let h = 1e-3;
+        positions[4] += h;
+        let shifted = restraints.compute(&positions, &mut [0.0; 12]);
+        assert!(((shifted[0] - energies[0]) / h as f64 + forces[4] as f64).abs() < 1e-2);
+
+        let deviations = restraints.deviations(&positions);
+        assert!((deviations[1] - 2.0).abs() < 1e-6);
+        let mut moved = restraints.clone();
+        moved.set_reference(&positions).unwrap();
+        assert!(moved.deviations(&positions).iter().all(|d| *d == 0.0));
+    }
+
+    #[test]
+    fn test_invalid_restraints_rejected() {
+        let reference = vec![0.0f32; 12];
+        for (selection, k) in [("name XX", 1.0), ("name", 1.0), ("all", -1.0)] {
+            let config = PositionRestraintConfig {
+                selection: selection.to_string(),
+                force_constant: k,
+            };
+            assert!(PositionRestraints::new(&[config], &context(), &reference).is_err());
+        }
+    }
+
+    /// Atoms 0-3 of a zig-zag with known geometry
+    fn positions() -> Vec<f32> {
+        vec![
+            0.1, 1.4, 0.2, 1.0, //
+            0.0, 0.0, 0.0, 1.0, //
+            1.5, -0.1, 0.1, 1.0, //
+            2.1, 0.3, 1.3, 1.0,
+        ]
+    }
+
+    #[test]
+    fn test_geometric_restraint_forces_match_finite_difference() {
+        let restraints = GeometricRestraints::new(vec![
+            GeometricRestraint {
+                kind: RestraintKind::Distance,
+                atoms: vec![0, 3],
+                lower: 1.0,
+                upper: 2.0,
+                force_constant: 5.0,
+            },
+            GeometricRestraint {
+                kind: RestraintKind::Angle,
+                atoms: vec![0, 1, 2],
+                lower: 60.0,
+                upper: 70.0,
+                force_constant: 30.0,
+            },
+            GeometricRestraint {
+                kind: RestraintKind::Dihedral,
+                atoms: vec![0, 1, 2, 3],
+                lower: 170.0,
+                upper: -170.0,
+                force_constant: 8.0,
+            },
+        ])
+        .unwrap();
+        let mut pos = positions();
+        let mut forces = vec![0.0f32; 16];
+        let energy = restraints.compute(&pos, &mut forces);
+        assert!(energy > 0.0);
+        assert!(restraints
+            .restraints()
+            .iter()
+            .all(|r| r.violation(&pos) > 0.0));
+
+        let h = 1e-3f32;
+        for idx in (0..16).filter(|i| i % 4 != 3) {
+            pos[idx] += h;
+            let plus = restraints.compute(&pos, &mut [0.0; 16]);
+            pos[idx] -= 2.0 * h;
+            let minus = restraints.compute(&pos, &mut [0.0; 16]);
+            pos[idx] += h;
+            let numeric = -(plus - minus) / (2.0 * h as f64);
+            assert!(
+                (numeric - forces[idx] as f64).abs() < 2e-2 * (1.0 + numeric.abs()),
+                "{} {} {}",
+                idx,
+                numeric,
+                forces[idx]
+            );
+        }
+
+        // Inside the flat bottom nothing acts
+        let inside = GeometricRestraints::new(vec![GeometricRestraint {
+            kind: RestraintKind::Dihedral,
+            atoms: vec![0, 1, 2, 3],
+            lower: -180.0,
+            upper: 180.0,
+            force_constant: 8.0,
+        }])
+        .unwrap();
+        let mut forces = vec![0.0f32; 16];
+        assert_eq!(inside.compute(&pos, &mut forces), 0.0);
+        assert!(forces.iter().all(|f| *f == 0.0));
+    }
+
+    #[test]
+    fn test_restraint_file_and_violation_report() {
+        let text = "# NMR restraints\n\
+                    distance A:1:N 1:CB 0.0 1.0 10.0\n\
+                    \n\
+                    angle 0 1 2 100 120 50 # backbone\n";
+        let mut restraints = GeometricRestraints::parse(text, &context()).unwrap();
+        assert_eq!(restraints.len(), 2);
+        assert_eq!(restraints.restraints()[0].atoms, vec![0, 2]);
+
+        let mut pos = vec![0.0f32; 12];
+        pos[8] = 3.0; // CB 3 Å from N
+        pos[4] = 1.5; // CA on the x axis: N-CA-CB angle 0°
+        pos[5] = 1.0;
+        restraints.record(&pos);
+        pos[8] = 1.5;
+        restraints.record(&pos);
+        let report = restraints.violations(&pos);
+        assert_eq!(restraints.samples(), 2);
+        assert!((report[0].value - 1.5).abs() < 1e-6 && (report[0].violation - 0.5).abs() < 1e-6);
+        assert!(
+            (report[0].max_violation - 2.0).abs() < 1e-6
+                && (report[0].mean_violation - 1.25).abs() < 1e-6
+        );
+        assert!(report[1].violation > 0.0 && report[1].value < 100.0);
+
+        for bad in [
+            "distance 0 1 1.0 2.0",
+            "bend 0 1 2 1 2 3",
+            "distance 0 9 1 2 3",
+            "distance 1:CA 1:XX 1 2 3",
+            "distance 0 1 3 2 1",
+        ] {
+            assert!(
+                GeometricRestraints::parse(bad, &context()).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+}
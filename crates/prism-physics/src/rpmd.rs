@@ -0,0 +1,343 @@
+//! # RPMD - Ring-Polymer Molecular Dynamics
+//! Classical dynamics of a [`RingPolymer`] under
+//! `H_P = Σ_k [p_k² / 2m + V(q_k)] + U_spring` at `β_P = β / P`. The centroid
+//! trajectory approximates Kubo-transformed quantum time-correlation
+//! functions, so RPMD gives dynamical quantum estimates on top of the
+//! static statistics of PIMC.
+//!
+//! Each step is the OBABO splitting of Ceriotti et al. (2010): PILE-L
+//! thermostat half steps in the normal-mode basis (critically damped
+//! `γ_k = 2 ω_k` internal modes, user-set centroid friction), half kicks
+//! from the physical forces, and exact free ring-polymer propagation in
+//! between, so the stiff spring modes never limit the time step. Units as
+//! in [`crate::pimc`].
+
+use crate::pimc::RingPolymer;
+use rand::Rng;
+use rand_chacha::ChaCha12Rng;
+use rand_distr::StandardNormal;
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone)]
+pub struct RingPolymerMd {
+    /// Orthogonal bead → mode matrix, `transform[j * P + k]`
+    transform: Vec<f64>,
+    /// Free ring-polymer mode frequencies in units of `ω_P`
+    frequencies: Vec<f64>,
+    momenta: Vec<Vec<f64>>,
+    gradients: Vec<Vec<f64>>,
+    energies: Vec<f64>,
+    fixed_centroid: bool,
+    rng: ChaCha12Rng,
+}
+
+impl RingPolymerMd {
+    /// Propagator for `polymer` with Maxwell-Boltzmann bead momenta at `P kT`
+    pub fn new(polymer: &RingPolymer, kt: f64, mut rng: ChaCha12Rng) -> Self {
+        let p = polymer.num_beads();
+        let bead_kt = p as f64 * kt;
+        let momenta = (0..p)
+            .map(|_| {
+                (0..polymer.num_atoms() * 3)
+                    .map(|c| {
+                        let m = polymer.masses()[c / 3];
+                        (m * bead_kt).sqrt() * rng.sample::<f64, _>(StandardNormal)
+                    })
+                    .collect()
+            })
+            .collect();
+        let (transform, frequencies) = normal_modes(p);
+        Self {
+            transform,
+            frequencies,
+            momenta,
+            gradients: Vec::new(),
+            energies: Vec::new(),
+            fixed_centroid: false,
+            rng,
+        }
+    }
+
+    /// Hold the centroid in place by removing the centroid momentum after
+    /// every kick and thermostat half step
+    pub fn with_fixed_centroid(mut self, fixed: bool) -> Self {
+        self.fixed_centroid = fixed;
+        self.project_centroid();
+        self
+    }
+
+    pub fn momenta(&self) -> &[Vec<f64>] {
+        &self.momenta
+    }
+
+    /// Potential energy of each bead at the current configuration
+    pub fn bead_energies(&self) -> &[f64] {
+        &self.energies
+    }
+
+    pub fn mean_potential(&self) -> f64 {
+        self.energies.iter().sum::<f64>() / self.energies.len().max(1) as f64
+    }
+
+    /// Bead kinetic energy `Σ_k Σ_i p² / 2m`
+    pub fn kinetic_energy(&self, polymer: &RingPolymer) -> f64 {
+        self.momenta
+            .iter()
+            .flat_map(|p| p.iter().enumerate())
+            .map(|(c, p)| p * p / (2.0 * polymer.masses()[c / 3]))
+            .sum()
+    }
+
+    /// Centroid velocity of every atom, flat `[vx0, vy0, vz0, ...]`
+    pub fn centroid_velocity(&self, polymer: &RingPolymer) -> Vec<f64> {
+        let p = self.momenta.len() as f64;
+        let mut v = vec![0.0; polymer.num_atoms() * 3];
+        for bead in &self.momenta {
+            v.iter_mut().zip(bead).for_each(|(v, p)| *v += p);
+        }
+        v.iter_mut()
+            .enumerate()
+            .for_each(|(c, v)| *v /= p * polymer.masses()[c / 3]);
+        v
+    }
+
+    /// Advance one OBABO step of length `dt`
+    pub fn step<F>(
+        &mut self,
+        polymer: &mut RingPolymer,
+        dt: f64,
+        kt: f64,
+        centroid_friction: f64,
+        potential: &mut F,
+    ) where
+        F: FnMut(&[f64], &mut [f64]) -> f64,
+    {
+        let p = polymer.num_beads();
+        if self.energies.len() != p {
+            self.evaluate(polymer, potential);
+        }
+        let omega_p = polymer.spring_frequency(kt);
+
+        self.thermostat(polymer, 0.5 * dt, kt, omega_p, centroid_friction);
+        self.kick(0.5 * dt);
+
+        // Exact free ring-polymer propagation, mode by mode
+        let mut q = self.to_modes(polymer.beads());
+        let mut pm = self.to_modes(&self.momenta);
+        for k in 0..p {
+            let omega = self.frequencies[k] * omega_p;
+            for (c, (q, p)) in q[k].iter_mut().zip(pm[k].iter_mut()).enumerate() {
+                let m = polymer.masses()[c / 3];
+                if omega == 0.0 {
+                    *q += *p / m * dt;
+                } else {
+                    let (sin, cos) = (omega * dt).sin_cos();
+                    let (q0, p0) = (*q, *p);
+                    *q = q0 * cos + p0 / (m * omega) * sin;
+                    *p = p0 * cos - m * omega * q0 * sin;
+                }
+            }
+        }
+        let beads = self.to_beads(&q);
+        polymer
+            .beads_mut()
+            .iter_mut()
+            .zip(beads)
+            .for_each(|(b, q)| *b = q);
+        self.momenta = self.to_beads(&pm);
+
+        self.evaluate(polymer, potential);
+        self.kick(0.5 * dt);
+        self.thermostat(polymer, 0.5 * dt, kt, omega_p, centroid_friction);
+    }
+
+    fn evaluate<F>(&mut self, polymer: &RingPolymer, potential: &mut F)
+    where
+        F: FnMut(&[f64], &mut [f64]) -> f64,
+    {
+        let n = polymer.num_atoms() * 3;
+        self.gradients
+            .resize_with(polymer.num_beads(), || vec![0.0; n]);
+        self.energies = polymer
+            .beads()
+            .iter()
+            .zip(&mut self.gradients)
+            .map(|(bead, gradient)| potential(bead, gradient))
+            .collect();
+    }
+
+    fn kick(&mut self, h: f64) {
+        for (p, g) in self.momenta.iter_mut().zip(&self.gradients) {
+            p.iter_mut().zip(g).for_each(|(p, g)| *p -= h * g);
+        }
+        self.project_centroid();
+    }
+
+    fn project_centroid(&mut self) {
+        if !self.fixed_centroid || self.momenta.is_empty() {
+            return;
+        }
+        let p = self.momenta.len() as f64;
+        let mut total = vec![0.0; self.momenta[0].len()];
+        for bead in &self.momenta {
+            total.iter_mut().zip(bead).for_each(|(t, p)| *t += p);
+        }
+        for bead in &mut self.momenta {
+            bead.iter_mut().zip(&total).for_each(|(b, t)| *b -= t / p);
+        }
+    }
+
+    /// PILE-L Ornstein-Uhlenbeck half step in the normal-mode basis
+    fn thermostat(
+        &mut self,
+        polymer: &RingPolymer,
+        h: f64,
+        kt: f64,
+        omega_p: f64,
+        centroid_friction: f64,
+    ) {
+        let bead_kt = polymer.num_beads() as f64 * kt;
+        let mut pm = self.to_modes(&self.momenta);
+        for (k, mode) in pm.iter_mut().enumerate() {
+            let gamma = if k == 0 {
+                centroid_friction
+            } else {
+                2.0 * self.frequencies[k] * omega_p
+            };
+            let c1 = (-gamma * h).exp();
+            let c2 = (1.0 - c1 * c1).max(0.0).sqrt();
+            for (c, p) in mode.iter_mut().enumerate() {
+                let m = polymer.masses()[c / 3];
+                *p =
+                    c1 * *p + c2 * (m * bead_kt).sqrt() * self.rng.sample::<f64, _>(StandardNormal);
+            }
+        }
+        self.momenta = self.to_beads(&pm);
+        self.project_centroid();
+    }
+
+    fn to_modes(&self, beads: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let p = beads.len();
+        (0..p)
+            .map(|k| {
+                let mut mode = vec![0.0; beads[0].len()];
+                for (j, bead) in beads.iter().enumerate() {
+                    let c = self.transform[j * p + k];
+                    mode.iter_mut().zip(bead).for_each(|(m, x)| *m += c * x);
+                }
+                mode
+            })
+            .collect()
+    }
+
+    fn to_beads(&self, modes: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let p = modes.len();
+        (0..p)
+            .map(|j| {
+                let mut bead = vec![0.0; modes[0].len()];
+                for (k, mode) in modes.iter().enumerate() {
+                    let c = self.transform[j * p + k];
+                    bead.iter_mut().zip(mode).for_each(|(b, x)| *b += c * x);
+                }
+                bead
+            })
+            .collect()
+    }
+}
+
+/// Real orthogonal normal-mode matrix of a `p`-bead ring and the mode
+/// frequencies `2 sin(kπ / p)` in units of `ω_P`
+fn normal_modes(p: usize) -> (Vec<f64>, Vec<f64>) {
+    let pf = p as f64;
+    let mut transform = vec![0.0; p * p];
+    for j in 0..p {
+        for k in 0..p {
+            let angle = 2.0 * PI * (j * k) as f64 / pf;
+            transform[j * p + k] = if k == 0 {
+                (1.0 / pf).sqrt()
+            } else if 2 * k < p {
+                (2.0 / pf).sqrt() * angle.cos()
+            } else if 2 * k == p {
+                (1.0 / pf).sqrt() * if j % 2 == 0 { 1.0 } else { -1.0 }
+            } else {
+                (2.0 / pf).sqrt() * angle.sin()
+            };
+        }
+    }
+    let frequencies = (0..p).map(|k| 2.0 * (k as f64 * PI / pf).sin()).collect();
+    (transform, frequencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pimc::tests::{harmonic, particle, quantum_potential, KT};
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_normal_modes_are_orthogonal() {
+        for p in [1, 2, 5, 8] {
+            let (c, frequencies) = normal_modes(p);
+            for a in 0..p {
+                for b in 0..p {
+                    let dot: f64 = (0..p).map(|j| c[j * p + a] * c[j * p + b]).sum();
+                    let expected = if a == b { 1.0 } else { 0.0 };
+                    assert!(
+                        (dot - expected).abs() < 1e-12,
+                        "P = {}: <{}|{}> = {}",
+                        p,
+                        a,
+                        b,
+                        dot
+                    );
+                }
+            }
+            assert_eq!(frequencies[0], 0.0);
+        }
+    }
+
+    #[test]
+    fn test_rpmd_samples_quantum_oscillator() {
+        let mut polymer = particle(8);
+        let mut rpmd = RingPolymerMd::new(&polymer, KT, ChaCha12Rng::seed_from_u64(3));
+        let mut potential = harmonic;
+        for _ in 0..500 {
+            rpmd.step(&mut polymer, 0.02, KT, 1.0, &mut potential);
+        }
+        let steps = 8_000;
+        let mut sum = 0.0;
+        for _ in 0..steps {
+            rpmd.step(&mut polymer, 0.02, KT, 1.0, &mut potential);
+            sum += rpmd.mean_potential();
+        }
+        let mean = sum / steps as f64;
+        assert!(
+            (mean - quantum_potential()).abs() < 0.06 * quantum_potential(),
+            "<V> = {} vs quantum {}",
+            mean,
+            quantum_potential()
+        );
+        // Bead kinetic energy equipartitions at P kT
+        let per_dof = rpmd.kinetic_energy(&polymer) / (16.0 * 3.0);
+        assert!(per_dof.is_finite() && per_dof > 0.0);
+    }
+
+    #[test]
+    fn test_fixed_centroid_dynamics() {
+        let mut polymer = RingPolymer::from_positions(&[0.4, 0.0, -0.3, 1.0], 4);
+        let start = polymer.centroid();
+        let mut rpmd = RingPolymerMd::new(&polymer, KT, ChaCha12Rng::seed_from_u64(9))
+            .with_fixed_centroid(true);
+        for _ in 0..200 {
+            rpmd.step(&mut polymer, 0.02, KT, 1.0, &mut harmonic);
+        }
+        for (c, s) in polymer.centroid().iter().zip(&start) {
+            assert!((c - s).abs() < 1e-9);
+        }
+        assert!(rpmd
+            .centroid_velocity(&polymer)
+            .iter()
+            .all(|v| v.abs() < 1e-9));
+        assert!(polymer.gyration_radii()[0] > 0.0);
+    }
+}
@@ -0,0 +1,751 @@
+//! # Run Configuration Files
+//! A complete simulation setup (engine parameters, force field, restraints,
+//! trajectory output and input structure) in a single TOML or YAML file:
+//!
+//! ```toml
+//! default_profile = "equilibration"
+//!
+//! [system]
+//! topology = "complex.prmtop"
+//! coordinates = "complex.inpcrd"
+//! disulfides = { ignore = [["26", "84"]] }
+//!
+//! [engine]
+//! dt = "2 fs"
+//! temp_start = "300 K"
+//! temp_end = "300 K"
+//! use_gpu = false
+//!
+//! [engine.force_field]
+//! cutoff = 9.0
+//!
+//! [profiles.equilibration.engine]
+//! max_steps = 50_000
+//! position_restraints = [{ selection = "backbone", force_constant = 5.0 }]
+//!
+//! [profiles.production.engine]
+//! max_steps = 5_000_000
+//! trajectory = { path = "prod.xtc", format = "xtc", stride = 5000 }
+//!
+//! [profiles.membrane.engine.force_field.implicit_solvent]
+//! membrane = { thickness = 32.0 }
+//! ```
+//!
+//! Layers are merged in order, later ones winning key by key: engine
+//! defaults, the base `engine` table, the selected profile, then
+//! environment variables `PRISM_<SECTION>__<KEY>[__<KEY>...]` (e.g.
+//! `PRISM_ENGINE__FORCE_FIELD__CUTOFF=10`); `PRISM_PROFILE` selects the
+//! profile when none is passed explicitly. Relative paths are resolved
+//! against the directory of the config file. Cysteines of the topology
+//! whose SG atoms are within bonding distance but not bonded get the
+//! disulfide bond and its terms on loading
+//! ([`prism_io::disulfides`]); `system.disulfides` forces missing bridges
+//! or leaves close pairs unbonded by residue number. `system.ligands` lists
+//! SDF/MOL2 ligands (`{ structure = "lig.sdf", charges = "lig.chg" }`),
+//! typed and parameterized GAFF-style ([`prism_io::gaff`]) and appended
+//! after the topology atoms. The YAML reader covers block
+//! mappings and sequences, inline lists and plain or quoted scalars.
+
+use crate::molecular_dynamics::MolecularDynamicsConfig;
+use prism_core::PrismError;
+use prism_io::disulfides::{add_disulfide_terms, detect_topology_disulfides, DisulfideOptions};
+use prism_io::ligand::LigandSpec;
+use prism_io::topology::Topology;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Prefix of environment variable overrides
+pub const ENV_PREFIX: &str = "PRISM_";
+/// Environment variable selecting the profile
+pub const PROFILE_ENV: &str = "PRISM_PROFILE";
+
+/// Top-level keys of a run configuration file
+const SECTIONS: [&str; 4] = ["default_profile", "system", "engine", "profiles"];
+
+/// Config file syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Format implied by a `.toml`, `.yaml`/`.yml` or `.json` extension
+    pub fn from_path(path: &Path) -> Result<Self, PrismError> {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("toml") => Ok(Self::Toml),
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            Some("json") => Ok(Self::Json),
+            _ => Err(PrismError::config(format!(
+                "Cannot infer config format of {} (expected .toml, .yaml, .yml or .json)",
+                path.display()
+            ))),
+        }
+    }
+
+    pub(crate) fn parse(self, text: &str) -> Result<Value, PrismError> {
+        match self {
+            Self::Toml => text
+                .parse::<toml::Table>()
+                .map_err(|e| PrismError::config(format!("Invalid TOML: {}", e)))
+                .and_then(|table| {
+                    serde_json::to_value(table).map_err(|e| PrismError::config(e.to_string()))
+                }),
+            Self::Yaml => yaml::parse(text),
+            Self::Json => serde_json::from_str(text)
+                .map_err(|e| PrismError::config(format!("Invalid JSON: {}", e))),
+        }
+    }
+}
+
+/// Input structure of the run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SystemConfig {
+    /// AMBER `.prmtop`/`.parm7` or GROMACS `.top` topology
+    #[serde(default)]
+    pub topology: Option<PathBuf>,
+    /// Matching `.inpcrd`/`.rst7` or `.gro` coordinates
+    #[serde(default)]
+    pub coordinates: Option<PathBuf>,
+    /// Disulfide detection and overrides, residues numbered from 1
+    #[serde(default)]
+    pub disulfides: DisulfideOptions,
+    /// Ligands parameterized with GAFF types and appended to the topology
+    #[serde(default)]
+    pub ligands: Vec<LigandSpec>,
+}
+
+/// Fully merged run configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunConfig {
+    /// Profile that was applied, if any
+    pub profile: Option<String>,
+    pub system: SystemConfig,
+    pub engine: MolecularDynamicsConfig,
+}
+
+impl RunConfig {
+    /// Read `path`, apply `profile` (or `PRISM_PROFILE`, or the file's
+    /// `default_profile`) and the `PRISM_*` environment overrides.
+    pub fn load(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Self, PrismError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| PrismError::config(format!("Failed to read {}: {}", path.display(), e)))?;
+        let env_profile = std::env::var(PROFILE_ENV).ok();
+        let mut config = Self::parse(
+            &text,
+            ConfigFormat::from_path(path)?,
+            profile.or(env_profile.as_deref()),
+            std::env::vars(),
+        )
+        .map_err(|e| PrismError::config(format!("{}: {}", path.display(), e)))?;
+        if let Some(dir) = path.parent() {
+            config.resolve_paths(dir);
+        }
+        log::info!(
+            "📄 Loaded run configuration {}{}",
+            path.display(),
+            config
+                .profile
+                .as_ref()
+                .map(|p| format!(" (profile '{}')", p))
+                .unwrap_or_default()
+        );
+        Ok(config)
+    }
+
+    /// Merge `text` with `profile` and the `PRISM_*` entries of `env`, then
+    /// validate the engine config.
+    pub fn parse(
+        text: &str,
+        format: ConfigFormat,
+        profile: Option<&str>,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, PrismError> {
+        let Value::Object(mut file) = format.parse(text)? else {
+            return Err(PrismError::config(
+                "Run configuration must be a table of sections",
+            ));
+        };
+        if let Some(key) = file.keys().find(|k| !SECTIONS.contains(&k.as_str())) {
+            return Err(PrismError::config(format!(
+                "Unknown section '{}' (expected one of {})",
+                key,
+                SECTIONS.join(", ")
+            )));
+        }
+
+        let default_profile = match file.remove("default_profile") {
+            Some(Value::String(name)) => Some(name),
+            Some(other) => {
+                return Err(PrismError::config(format!(
+                    "default_profile must be a string, got {}",
+                    other
+                )))
+            }
+            None => None,
+        };
+        let mut profiles = match file.remove("profiles") {
+            Some(Value::Object(profiles)) => profiles,
+            Some(_) => {
+                return Err(PrismError::config(
+                    "profiles must be a table of named profiles",
+                ))
+            }
+            None => Map::new(),
+        };
+
+        let mut merged = serde_json::json!({
+            "system": SystemConfig::default(),
+            "engine": MolecularDynamicsConfig::default(),
+        });
+        merge(&mut merged, Value::Object(file));
+        let profile = profile.map(str::to_string).or(default_profile);
+        if let Some(name) = &profile {
+            let layer = profiles.remove(name).ok_or_else(|| {
+                let known: Vec<_> = profiles.keys().map(String::as_str).collect();
+                PrismError::config(format!(
+                    "Unknown profile '{}' (defined: {})",
+                    name,
+                    known.join(", ")
+                ))
+            })?;
+            if let Some(key) = layer.as_object().and_then(|l| {
+                l.keys()
+                    .find(|k| !matches!(k.as_str(), "system" | "engine"))
+            }) {
+                return Err(PrismError::config(format!(
+                    "Profile '{}' sets unknown section '{}'",
+                    name, key
+                )));
+            }
+            merge(&mut merged, layer);
+        }
+        for (key, value) in env {
+            if let Some(path) = key.strip_prefix(ENV_PREFIX).filter(|_| key != PROFILE_ENV) {
+                let path: Vec<String> = path.split("__").map(str::to_ascii_lowercase).collect();
+                if matches!(path.first().map(String::as_str), Some("system" | "engine")) {
+                    set_path(&mut merged, &path, env_value(&value));
+                }
+            }
+        }
+
+        let mut merged = match merged {
+            Value::Object(map) => map,
+            _ => unreachable!("merged config is built as an object"),
+        };
+        let system = serde_json::from_value(merged.remove("system").unwrap_or_default())
+            .map_err(|e| PrismError::config(format!("Invalid [system] section: {}", e)))?;
+        let engine: MolecularDynamicsConfig =
+            serde_json::from_value(merged.remove("engine").unwrap_or_default())
+                .map_err(|e| PrismError::config(format!("Invalid [engine] section: {}", e)))?;
+        engine.validate()?;
+        Ok(Self {
+            profile,
+            system,
+            engine,
+        })
+    }
+
+    /// Make relative input and output paths relative to `dir`
+    pub fn resolve_paths(&mut self, dir: &Path) {
+        let resolve = |path: &mut PathBuf| {
+            if path.is_relative() {
+                *path = dir.join(&*path);
+            }
+        };
+        self.system.topology.as_mut().map(resolve);
+        self.system.coordinates.as_mut().map(resolve);
+        self.engine.restraint_file.as_mut().map(resolve);
+        for ligand in &mut self.system.ligands {
+            resolve(&mut ligand.structure);
+            ligand.charges.as_mut().map(resolve);
+        }
+        if let Some(trajectory) = &mut self.engine.trajectory {
+            resolve(&mut trajectory.path);
+        }
+        if let Some(qmmm) = &mut self.engine.qmmm {
+            qmmm.work_dir.as_mut().map(resolve);
+        }
+    }
+
+    /// Load the `[system]` topology and coordinates, picking the reader
+    /// from the topology extension, add the missing disulfides and append
+    /// the ligands
+    pub fn load_topology(&self) -> Result<Topology, PrismError> {
+        let (Some(topology), Some(coordinates)) = (&self.system.topology, &self.system.coordinates)
+        else {
+            return Err(PrismError::config(
+                "[system] needs both `topology` and `coordinates`",
+            ));
+        };
+        let extension = topology
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        let loaded = match extension {
+            "prmtop" | "parm7" => prism_io::amber::load_amber(topology, coordinates),
+            "top" => prism_io::gromacs::load_gromacs(topology, coordinates),
+            _ => {
+                return Err(PrismError::config(format!(
+                    "Unsupported topology format '{}' (expected .prmtop, .parm7 or .top)",
+                    topology.display()
+                )))
+            }
+        };
+        let mut loaded = loaded.map_err(|e| {
+            PrismError::config(format!("Failed to load {}: {}", topology.display(), e))
+        })?;
+        let disulfides = detect_topology_disulfides(&loaded, &self.system.disulfides)
+            .map_err(|e| PrismError::config(format!("[system] disulfides: {}", e)))?;
+        let added = add_disulfide_terms(&mut loaded, &disulfides);
+        if !disulfides.is_empty() {
+            let bridges: Vec<String> = disulfides.iter().map(|d| d.to_string()).collect();
+            log::info!(
+                "🔗 {} disulfides ({} added to the topology): {}",
+                disulfides.len(),
+                added,
+                bridges.join(", ")
+            );
+        }
+        for spec in &self.system.ligands {
+            let (_, ligand, report) = spec.load().map_err(|e| {
+                PrismError::config(format!(
+                    "Failed to load ligand {}: {}",
+                    spec.structure.display(),
+                    e
+                ))
+            })?;
+            log::info!("💊 Ligand {}", report);
+            loaded.append(&ligand);
+        }
+        Ok(loaded)
+    }
+}
+
+/// Recursively overlay `layer` onto `base`; tables merge, anything else replaces
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+fn set_path(root: &mut Value, path: &[String], value: Value) {
+    let mut node = root;
+    for key in path {
+        if !node.is_object() {
+            *node = Value::Object(Map::new());
+        }
+        node = node
+            .as_object_mut()
+            .expect("just made an object")
+            .entry(key.clone())
+            .or_insert(Value::Null);
+    }
+    *node = value;
+}
+
+/// Environment values are JSON when they parse as such (numbers, booleans,
+/// lists), plain strings otherwise
+fn env_value(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// Block-structured YAML subset: nested mappings and `- ` sequences by
+/// indentation, inline `[a, b]` lists and `{}`, plain, single- and
+/// double-quoted scalars, `#` comments. Anchors, multi-line strings and
+/// multiple documents are not supported.
+mod yaml {
+    use super::*;
+
+    struct Line {
+        number: usize,
+        indent: usize,
+        text: String,
+    }
+
+    pub(super) fn parse(text: &str) -> Result<Value, PrismError> {
+        let mut lines = Vec::new();
+        for (number, raw) in text.lines().enumerate() {
+            let content = strip_comment(raw).trim_end();
+            let trimmed = content.trim_start();
+            if trimmed.is_empty() || trimmed == "---" {
+                continue;
+            }
+            if content.starts_with('\t') {
+                return Err(error(number + 1, "tabs are not allowed for indentation"));
+            }
+            lines.push(Line {
+                number: number + 1,
+                indent: content.len() - trimmed.len(),
+                text: trimmed.to_string(),
+            });
+        }
+        if lines.is_empty() {
+            return Ok(Value::Object(Map::new()));
+        }
+        let indent = lines[0].indent;
+        let mut index = 0;
+        let value = block(&mut lines, &mut index, indent)?;
+        match lines.get(index) {
+            Some(line) => Err(error(line.number, "unexpected indentation")),
+            None => Ok(value),
+        }
+    }
+
+    fn error(line: usize, message: &str) -> PrismError {
+        PrismError::config(format!("Invalid YAML at line {}: {}", line, message))
+    }
+
+    fn block(lines: &mut [Line], index: &mut usize, indent: usize) -> Result<Value, PrismError> {
+        if is_item(&lines[*index].text) {
+            sequence(lines, index, indent)
+        } else {
+            mapping(lines, index, indent)
+        }
+    }
+
+    fn is_item(text: &str) -> bool {
+        text == "-" || text.starts_with("- ")
+    }
+
+    fn sequence(lines: &mut [Line], index: &mut usize, indent: usize) -> Result<Value, PrismError> {
+        let mut items = Vec::new();
+        while *index < lines.len() && lines[*index].indent == indent && is_item(&lines[*index].text)
+        {
+            let line = &mut lines[*index];
+            let rest = line.text[1..].trim_start().to_string();
+            if rest.is_empty() {
+                *index += 1;
+                items.push(nested(lines, index, indent)?);
+            } else if split_key(&rest).is_some() {
+                // `- key: value` opens a mapping indented to the key
+                line.indent = indent + line.text.len() - rest.len();
+                line.text = rest;
+                let inner = line.indent;
+                items.push(mapping(lines, index, inner)?);
+            } else {
+                items.push(scalar(&rest, line.number)?);
+                *index += 1;
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(lines: &mut [Line], index: &mut usize, indent: usize) -> Result<Value, PrismError> {
+        let mut map = Map::new();
+        while *index < lines.len() && lines[*index].indent == indent {
+            let line = &lines[*index];
+            let number = line.number;
+            if is_item(&line.text) {
+                return Err(error(number, "sequence item inside a mapping"));
+            }
+            let (key, rest) =
+                split_key(&line.text).ok_or_else(|| error(number, "expected `key: value`"))?;
+            let key = unquote(key);
+            let rest = rest.to_string();
+            *index += 1;
+            let value = if !rest.is_empty() {
+                scalar(&rest, number)?
+            } else if *index < lines.len()
+                && lines[*index].indent == indent
+                && is_item(&lines[*index].text)
+            {
+                // Sequences may sit at the same indentation as their key
+                sequence(lines, index, indent)?
+            } else {
+                nested(lines, index, indent)?
+            };
+            if map.insert(key.clone(), value).is_some() {
+                return Err(error(number, &format!("duplicate key '{}'", key)));
+            }
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// Block indented deeper than `parent`, or null when there is none
+    fn nested(lines: &mut [Line], index: &mut usize, parent: usize) -> Result<Value, PrismError> {
+        match lines.get(*index) {
+            Some(line) if line.indent > parent => {
+                let indent = line.indent;
+                block(lines, index, indent)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    /// `key: rest` split at the first `:` followed by a space or the end,
+    /// outside quotes
+    fn split_key(text: &str) -> Option<(&str, &str)> {
+        let mut quote = None;
+        for (i, c) in text.char_indices() {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), c) if c == q => quote = None,
+                (None, ':') if text[i + 1..].is_empty() || text[i + 1..].starts_with(' ') => {
+                    let key = text[..i].trim();
+                    return (!key.is_empty() && !key.starts_with(['[', '{']))
+                        .then(|| (key, text[i + 1..].trim()));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn strip_comment(line: &str) -> &str {
+        let mut quote = None;
+        let mut previous = ' ';
+        for (i, c) in line.char_indices() {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), c) if c == q => quote = None,
+                (None, '#') if previous.is_whitespace() => return &line[..i],
+                _ => {}
+            }
+            previous = c;
+        }
+        line
+    }
+
+    fn unquote(text: &str) -> String {
+        let text = text.trim();
+        if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+            text[1..text.len() - 1]
+                .replace("\\\"", "\"")
+                .replace("\\n", "\n")
+                .replace("\\\\", "\\")
+        } else if text.len() >= 2 && text.starts_with('\'') && text.ends_with('\'') {
+            text[1..text.len() - 1].replace("''", "'")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn scalar(text: &str, line: usize) -> Result<Value, PrismError> {
+        let text = text.trim();
+        if let Some(inner) = text.strip_prefix('[') {
+            let inner = inner
+                .strip_suffix(']')
+                .ok_or_else(|| error(line, "unterminated inline list"))?;
+            if inner.contains(['[', '{']) {
+                return Err(error(line, "nested inline collections are not supported"));
+            }
+            return inner
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| scalar(item, line))
+                .collect::<Result<_, _>>()
+                .map(Value::Array);
+        }
+        if text == "{}" {
+            return Ok(Value::Object(Map::new()));
+        }
+        if text.starts_with('{') {
+            return Err(error(
+                line,
+                "inline mappings are not supported; use a block mapping",
+            ));
+        }
+        if text.starts_with(['"', '\'']) {
+            return Ok(Value::String(unquote(text)));
+        }
+        Ok(match text {
+            "~" | "null" | "Null" | "NULL" => Value::Null,
+            "true" | "True" | "TRUE" => Value::Bool(true),
+            "false" | "False" | "FALSE" => Value::Bool(false),
+            _ => match (text.replace('_', "").parse::<i64>(), text.parse::<f64>()) {
+                (Ok(i), _) if !text.starts_with('_') => Value::from(i),
+                (_, Ok(f)) if f.is_finite() => Value::from(f),
+                _ => Value::String(text.to_string()),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::precision::Precision;
+
+    const TOML: &str = r#"
+default_profile = "equilibration"
+
+[system]
+topology = "complex.prmtop"
+coordinates = "complex.inpcrd"
+disulfides = { bond = [["A:22", "A:95"]] }
+ligands = [{ structure = "ligand.sdf", charges = "ligand.chg" }]
+
+[engine]
+dt = "2 fs"
+temp_start = "300 K"
+use_gpu = false
+
+[engine.force_field]
+cutoff = 9.0
+
+[profiles.equilibration.engine]
+max_steps = 50_000
+position_restraints = [{ selection = "backbone", force_constant = 5.0 }]
+
+[profiles.production.engine]
+max_steps = 5_000_000
+precision = "double"
+trajectory = { path = "prod.xtc", format = "xtc", stride = 5000 }
+
+[profiles.production.engine.force_field.implicit_solvent]
+membrane = { thickness = 32.0 }
+"#;
+
+    const YAML: &str = r#"
+# Same setup as the TOML example
+default_profile: equilibration
+system:
+  topology: complex.prmtop
+  coordinates: "complex.inpcrd"
+  disulfides:
+    bond:
+      - ["A:22", "A:95"]
+  ligands:
+    - structure: ligand.sdf
+      charges: ligand.chg
+engine:
+  dt: 2 fs
+  temp_start: '300 K'
+  use_gpu: false
+  force_field:
+    cutoff: 9.0
+profiles:
+  equilibration:
+    engine:
+      max_steps: 50_000
+      position_restraints:
+        - selection: backbone
+          force_constant: 5.0
+  production:
+    engine:
+      max_steps: 5000000
+      precision: double
+      trajectory:
+        path: prod.xtc
+        format: xtc
+        stride: 5000
+      force_field:
+        implicit_solvent:
+          membrane:
+            thickness: 32.0
+"#;
+
+    fn no_env() -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    #[test]
+    fn test_toml_and_yaml_profiles_agree() {
+        for profile in [None, Some("production")] {
+            let toml = RunConfig::parse(TOML, ConfigFormat::Toml, profile, no_env()).unwrap();
+            let yaml = RunConfig::parse(YAML, ConfigFormat::Yaml, profile, no_env()).unwrap();
+            assert_eq!(
+                serde_json::to_value(&toml).unwrap(),
+                serde_json::to_value(&yaml).unwrap()
+            );
+        }
+
+        let equilibration = RunConfig::parse(TOML, ConfigFormat::Toml, None, no_env()).unwrap();
+        assert_eq!(equilibration.profile.as_deref(), Some("equilibration"));
+        assert_eq!(equilibration.engine.max_steps, 50_000);
+        assert_eq!(
+            equilibration.engine.position_restraints[0].selection,
+            "backbone"
+        );
+        assert!((equilibration.engine.dt - 0.002).abs() < 1e-9);
+        assert_eq!(equilibration.engine.force_field.cutoff, 9.0);
+        let forced = equilibration.system.disulfides.bond[0];
+        assert_eq!((forced.0.residue_seq, forced.1.residue_seq), (22, 95));
+        let ligand = &equilibration.system.ligands[0];
+        assert_eq!(ligand.structure, PathBuf::from("ligand.sdf"));
+        assert_eq!(ligand.charges, Some(PathBuf::from("ligand.chg")));
+        // Unset keys keep the engine defaults
+        let defaults = MolecularDynamicsConfig::default();
+        assert_eq!(equilibration.engine.friction, defaults.friction);
+        assert_eq!(
+            equilibration.engine.force_field.dielectric,
+            defaults.force_field.dielectric
+        );
+
+        let production =
+            RunConfig::parse(TOML, ConfigFormat::Toml, Some("production"), no_env()).unwrap();
+        assert_eq!(production.engine.max_steps, 5_000_000);
+        assert_eq!(production.engine.precision, Precision::Double);
+        assert!(production.engine.position_restraints.is_empty());
+        assert_eq!(production.engine.trajectory.unwrap().stride, 5000);
+        let solvent = production.engine.force_field.implicit_solvent.unwrap();
+        assert_eq!(solvent.solvent_dielectric, 78.5);
+        assert_eq!(solvent.membrane.unwrap().thickness, 32.0);
+    }
+
+    #[test]
+    fn test_environment_overrides_and_errors() {
+        let env = [
+            ("PRISM_ENGINE__FORCE_FIELD__CUTOFF", "10.5"),
+            ("PRISM_ENGINE__DT", "1 fs"),
+            ("PRISM_SYSTEM__TOPOLOGY", "other.top"),
+            ("HOME", "/root"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let config = RunConfig::parse(TOML, ConfigFormat::Toml, None, env).unwrap();
+        assert_eq!(config.engine.force_field.cutoff, 10.5);
+        assert!((config.engine.dt - 0.001).abs() < 1e-9);
+        assert_eq!(config.system.topology, Some(PathBuf::from("other.top")));
+
+        let unknown =
+            RunConfig::parse(TOML, ConfigFormat::Toml, Some("nvt"), no_env()).unwrap_err();
+        assert!(unknown.to_string().contains("equilibration"), "{}", unknown);
+        let invalid = RunConfig::parse("[engine]\ndt = 0.0\n", ConfigFormat::Toml, None, no_env())
+            .unwrap_err();
+        assert!(invalid.to_string().contains("dt"), "{}", invalid);
+        let typo = RunConfig::parse("[engnie]\ndt = 0.001\n", ConfigFormat::Toml, None, no_env())
+            .unwrap_err();
+        assert!(typo.to_string().contains("engnie"), "{}", typo);
+        assert!(RunConfig::parse(
+            "engine:\n  dt: 0.001\n   cutoff_dist: 8\n",
+            ConfigFormat::Yaml,
+            None,
+            no_env()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_load_resolves_paths_against_file() {
+        let dir = std::env::temp_dir().join(format!("prism_run_config_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("run.toml");
+        std::fs::write(&path, TOML).unwrap();
+        let config = RunConfig::load(&path, Some("production")).unwrap();
+        assert_eq!(config.system.topology, Some(dir.join("complex.prmtop")));
+        assert_eq!(config.engine.trajectory.unwrap().path, dir.join("prod.xtc"));
+        assert!(ConfigFormat::from_path(Path::new("run.ini")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,682 @@
+//! # Job Scheduler - Shared-Workstation Run Queue
+//! A [`JobScheduler`] queues simulation jobs from several users and starts
+//! them on their own threads as soon as resources allow. CPU jobs are
+//! limited by a number of concurrent slots; GPU jobs reserve their VRAM
+//! budget on one device, and a device takes as many jobs as fit in its
+//! capacity. [`GpuSlot::query`] sizes that capacity with
+//! [`prism_gpu::memory::VramGuard`], so memory used by other processes at
+//! startup is left alone.
+//!
+//! The next job is picked by the [`SchedulingPolicy`]: highest priority
+//! first, or the owner with the fewest running jobs and least accumulated
+//! runtime first (priority and submission order break ties). Smaller GPU
+//! jobs never overtake a GPU job that is waiting for memory, so large jobs
+//! are not starved; CPU jobs are scheduled independently.
+
+use crate::molecular_dynamics::MolecularDynamicsConfig;
+use crate::resource_estimate::ResourceEstimate;
+use prism_core::PrismError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// Order in which queued jobs are started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchedulingPolicy {
+    /// Highest priority first, then submission order
+    #[default]
+    Priority,
+    /// Owner with the fewest running jobs, then the least runtime used so
+    /// far, then priority and submission order
+    FairShare,
+}
+
+/// VRAM budget of one GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuSlot {
+    pub device: usize,
+    pub capacity_bytes: usize,
+}
+
+impl GpuSlot {
+    pub fn new(device: usize, capacity_bytes: usize) -> Self {
+        Self {
+            device,
+            capacity_bytes,
+        }
+    }
+
+    /// Budget of `device`: the VRAM guard's safe limit, less the memory
+    /// already in use
+    #[cfg(feature = "cuda")]
+    pub fn query(device: usize) -> Result<Self, PrismError> {
+        let context = cudarc::driver::CudaContext::new(device)
+            .map_err(|e| PrismError::gpu("init", format!("{:?}", e)))?;
+        let info = prism_gpu::memory::VramGuard::new(context)
+            .query_vram()
+            .map_err(|e| PrismError::gpu("vram", e.to_string()))?;
+        let capacity_bytes = info.safe_limit_bytes.min(info.free_bytes);
+        log::info!(
+            "🛡️ GPU {} job budget: {} MB of {} MB",
+            device,
+            capacity_bytes / (1024 * 1024),
+            info.total_mb()
+        );
+        Ok(Self::new(device, capacity_bytes))
+    }
+}
+
+/// Resources and ordering of a [`JobScheduler`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    pub policy: SchedulingPolicy,
+    /// CPU jobs running at the same time
+    pub max_cpu_jobs: usize,
+    pub gpus: Vec<GpuSlot>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            policy: SchedulingPolicy::Priority,
+            max_cpu_jobs: 1,
+            gpus: Vec::new(),
+        }
+    }
+}
+
+/// What a job needs and who submitted it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub name: String,
+    /// Fair-share account
+    pub owner: String,
+    /// Larger runs first
+    pub priority: i32,
+    /// VRAM to reserve on one GPU; 0 runs the job in a CPU slot
+    pub vram_bytes: usize,
+}
+
+impl JobSpec {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            owner: "default".to_string(),
+            priority: 0,
+            vram_bytes: 0,
+        }
+    }
+
+    /// Job running an engine with `config`: its trajectory and workspace
+    /// budgets on a GPU when the engine will use one, a CPU slot otherwise
+    pub fn for_engine(name: impl Into<String>, config: &MolecularDynamicsConfig) -> Self {
+        let vram_bytes = if config.use_gpu && cfg!(feature = "cuda") {
+            config.max_trajectory_memory + config.max_workspace_memory
+        } else {
+            0
+        };
+        Self::new(name).vram_bytes(vram_bytes)
+    }
+
+    /// Job needing the device memory of a dry-run `estimate` on a GPU, or
+    /// a CPU slot when the integrator stays on the host
+    pub fn for_estimate(name: impl Into<String>, estimate: &ResourceEstimate) -> Self {
+        Self::new(name).vram_bytes(estimate.vram_bytes())
+    }
+
+    pub fn owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = owner.into();
+        self
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn vram_bytes(mut self, vram_bytes: usize) -> Self {
+        self.vram_bytes = vram_bytes;
+        self
+    }
+}
+
+/// Where a started job runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Placement {
+    Cpu,
+    Gpu { device: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobState {
+    Queued,
+    Running(Placement),
+    Finished(Placement),
+    /// Removed from the queue before it started
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Finished(_) | Self::Cancelled)
+    }
+}
+
+/// Reservation of one GPU slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuUsage {
+    pub device: usize,
+    pub jobs: usize,
+    pub reserved_bytes: usize,
+    pub capacity_bytes: usize,
+}
+
+/// Queue and resource usage at one instant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchedulerStatus {
+    pub queued: usize,
+    pub running_cpu: usize,
+    pub gpus: Vec<GpuUsage>,
+}
+
+type Work = Box<dyn FnOnce(Placement) + Send>;
+
+struct QueuedJob {
+    id: u64,
+    spec: JobSpec,
+    work: Work,
+}
+
+#[derive(Default)]
+struct Account {
+    running: usize,
+    runtime_seconds: f64,
+}
+
+struct State {
+    queue: Vec<QueuedJob>,
+    jobs: HashMap<u64, JobState>,
+    running_cpu: usize,
+    gpus: Vec<GpuUsage>,
+    accounts: HashMap<String, Account>,
+    next_id: u64,
+}
+
+struct Shared {
+    config: SchedulerConfig,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Queue of jobs started as resources free up
+#[derive(Clone)]
+pub struct JobScheduler {
+    shared: Arc<Shared>,
+}
+
+impl std::fmt::Debug for JobScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobScheduler")
+            .field("config", &self.shared.config)
+            .field("status", &self.status())
+            .finish()
+    }
+}
+
+/// Handle of a submitted job
+#[derive(Clone)]
+pub struct JobHandle {
+    id: u64,
+    shared: Arc<Shared>,
+}
+
+impl std::fmt::Debug for JobHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobHandle")
+            .field("id", &self.id)
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+impl JobHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn state(&self) -> JobState {
+        self.shared.lock().jobs[&self.id]
+    }
+
+    /// Remove the job from the queue; `false` once it has started
+    pub fn cancel(&self) -> bool {
+        let mut state = self.shared.lock();
+        let Some(position) = state.queue.iter().position(|j| j.id == self.id) else {
+            return false;
+        };
+        let job = state.queue.remove(position);
+        state.jobs.insert(self.id, JobState::Cancelled);
+        log::info!("⏹️ Job {} cancelled while queued", job.spec.name);
+        // A GPU job waiting for memory may have held back smaller ones
+        dispatch(&self.shared, &mut state);
+        self.shared.changed.notify_all();
+        true
+    }
+
+    /// Block until the job has finished or was cancelled
+    pub fn wait(&self) -> JobState {
+        let mut state = self.shared.lock();
+        loop {
+            let job = state.jobs[&self.id];
+            if job.is_final() {
+                return job;
+            }
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+impl JobScheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        let gpus = config
+            .gpus
+            .iter()
+            .map(|slot| GpuUsage {
+                device: slot.device,
+                jobs: 0,
+                reserved_bytes: 0,
+                capacity_bytes: slot.capacity_bytes,
+            })
+            .collect();
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    queue: Vec::new(),
+                    jobs: HashMap::new(),
+                    running_cpu: 0,
+                    gpus,
+                    accounts: HashMap::new(),
+                    next_id: 0,
+                }),
+                config,
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.shared.config
+    }
+
+    /// Queue `work`, which is called with the job's placement on a thread
+    /// of its own once the resources of `spec` are free. Fails when no
+    /// GPU could ever hold the job.
+    pub fn submit(
+        &self,
+        spec: JobSpec,
+        work: impl FnOnce(Placement) + Send + 'static,
+    ) -> Result<JobHandle, PrismError> {
+        let config = &self.shared.config;
+        if spec.vram_bytes > 0
+            && !config
+                .gpus
+                .iter()
+                .any(|slot| slot.capacity_bytes >= spec.vram_bytes)
+        {
+            return Err(PrismError::config(format!(
+                "Job {} needs {} MB of VRAM, more than any GPU slot offers ({})",
+                spec.name,
+                spec.vram_bytes / (1024 * 1024),
+                config
+                    .gpus
+                    .iter()
+                    .map(|s| format!("GPU {}: {} MB", s.device, s.capacity_bytes / (1024 * 1024)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        if spec.vram_bytes == 0 && config.max_cpu_jobs == 0 {
+            return Err(PrismError::config(format!(
+                "Job {} needs a CPU slot but max_cpu_jobs is 0",
+                spec.name
+            )));
+        }
+
+        let mut state = self.shared.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.jobs.insert(id, JobState::Queued);
+        log::info!(
+            "📥 Queued job {} (owner {}, priority {}, {} MB VRAM)",
+            spec.name,
+            spec.owner,
+            spec.priority,
+            spec.vram_bytes / (1024 * 1024)
+        );
+        state.queue.push(QueuedJob {
+            id,
+            spec,
+            work: Box::new(work),
+        });
+        dispatch(&self.shared, &mut state);
+        Ok(JobHandle {
+            id,
+            shared: self.shared.clone(),
+        })
+    }
+
+    pub fn status(&self) -> SchedulerStatus {
+        let state = self.shared.lock();
+        SchedulerStatus {
+            queued: state.queue.len(),
+            running_cpu: state.running_cpu,
+            gpus: state.gpus.clone(),
+        }
+    }
+
+    /// Block until the queue is empty and no job is running
+    pub fn wait_idle(&self) {
+        let mut state = self.shared.lock();
+        while !state.queue.is_empty()
+            || state.running_cpu > 0
+            || state.gpus.iter().any(|g| g.jobs > 0)
+        {
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+/// Queue positions in the order the policy would start them
+fn ordered(config: &SchedulerConfig, state: &State) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..state.queue.len()).collect();
+    let by_priority =
+        |a: &QueuedJob, b: &QueuedJob| b.spec.priority.cmp(&a.spec.priority).then(a.id.cmp(&b.id));
+    match config.policy {
+        SchedulingPolicy::Priority => {
+            order.sort_by(|&a, &b| by_priority(&state.queue[a], &state.queue[b]))
+        }
+        SchedulingPolicy::FairShare => {
+            let usage = |job: &QueuedJob| {
+                state
+                    .accounts
+                    .get(&job.spec.owner)
+                    .map_or((0, 0.0), |a| (a.running, a.runtime_seconds))
+            };
+            order.sort_by(|&a, &b| {
+                let (a, b) = (&state.queue[a], &state.queue[b]);
+                let ((running_a, used_a), (running_b, used_b)) = (usage(a), usage(b));
+                running_a
+                    .cmp(&running_b)
+                    .then(used_a.total_cmp(&used_b))
+                    .then_with(|| by_priority(a, b))
+            });
+        }
+    }
+    order
+}
+
+/// Start every queued job whose resources are free, in policy order
+fn dispatch(shared: &Arc<Shared>, state: &mut State) {
+    loop {
+        let mut gpu_blocked = false;
+        let mut start = None;
+        for index in ordered(&shared.config, state) {
+            let spec = &state.queue[index].spec;
+            if spec.vram_bytes == 0 {
+                if state.running_cpu < shared.config.max_cpu_jobs {
+                    start = Some((index, Placement::Cpu));
+                    break;
+                }
+            } else if !gpu_blocked {
+                // Best fit: the device left with the least free memory
+                let slot = state
+                    .gpus
+                    .iter()
+                    .filter(|g| g.capacity_bytes - g.reserved_bytes >= spec.vram_bytes)
+                    .min_by_key(|g| g.capacity_bytes - g.reserved_bytes);
+                match slot {
+                    Some(slot) => {
+                        start = Some((
+                            index,
+                            Placement::Gpu {
+                                device: slot.device,
+                            },
+                        ));
+                        break;
+                    }
+                    None => gpu_blocked = true,
+                }
+            }
+        }
+        let Some((index, placement)) = start else {
+            return;
+        };
+        let job = state.queue.remove(index);
+        launch(shared, state, job, placement);
+    }
+}
+
+fn launch(shared: &Arc<Shared>, state: &mut State, job: QueuedJob, placement: Placement) {
+    reserve(state, job.spec.vram_bytes, placement, true);
+    state
+        .accounts
+        .entry(job.spec.owner.clone())
+        .or_default()
+        .running += 1;
+    state.jobs.insert(job.id, JobState::Running(placement));
+    log::info!("▶️ Starting job {} on {:?}", job.spec.name, placement);
+
+    let QueuedJob { id, spec, work } = job;
+    let (owner, vram_bytes) = (spec.owner.clone(), spec.vram_bytes);
+    let thread_shared = shared.clone();
+    let spawned = std::thread::Builder::new()
+        .name(format!("prism-job-{}", id))
+        .spawn(move || {
+            // Dropped even if the job panics, releasing its resources
+            let completion = Completion {
+                shared: thread_shared,
+                id,
+                spec,
+                placement,
+                start: web_time::Instant::now(),
+            };
+            work(completion.placement);
+        });
+    if let Err(e) = spawned {
+        log::error!("❌ Failed to start a thread for job {}: {}", id, e);
+        reserve(state, vram_bytes, placement, false);
+        if let Some(account) = state.accounts.get_mut(&owner) {
+            account.running -= 1;
+        }
+        state.jobs.insert(id, JobState::Finished(placement));
+    }
+}
+
+fn reserve(state: &mut State, vram_bytes: usize, placement: Placement, acquire: bool) {
+    match placement {
+        Placement::Cpu if acquire => state.running_cpu += 1,
+        Placement::Cpu => state.running_cpu -= 1,
+        Placement::Gpu { device } => {
+            let gpu = state
+                .gpus
+                .iter_mut()
+                .find(|g| g.device == device)
+                .expect("placement on a configured GPU");
+            if acquire {
+                gpu.jobs += 1;
+                gpu.reserved_bytes += vram_bytes;
+            } else {
+                gpu.jobs -= 1;
+                gpu.reserved_bytes -= vram_bytes;
+            }
+        }
+    }
+}
+
+/// Releases a running job's resources when its thread ends
+struct Completion {
+    shared: Arc<Shared>,
+    id: u64,
+    spec: JobSpec,
+    placement: Placement,
+    start: web_time::Instant,
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        reserve(&mut state, self.spec.vram_bytes, self.placement, false);
+        let account = state.accounts.entry(self.spec.owner.clone()).or_default();
+        account.running -= 1;
+        account.runtime_seconds += self.start.elapsed().as_secs_f64();
+        state
+            .jobs
+            .insert(self.id, JobState::Finished(self.placement));
+        log::info!("⏹️ Job {} finished", self.spec.name);
+        dispatch(&self.shared, &mut state);
+        self.shared.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    /// Job that records its name, then blocks until `release` is dropped
+    /// or sent to
+    fn recording(
+        log: &Arc<Mutex<Vec<String>>>,
+        name: &str,
+    ) -> impl FnOnce(Placement) + Send + 'static {
+        let (log, name) = (log.clone(), name.to_string());
+        move |_| log.lock().unwrap().push(name)
+    }
+
+    fn blocker(scheduler: &JobScheduler, spec: JobSpec) -> (JobHandle, mpsc::Sender<()>) {
+        let (release, wait) = mpsc::channel::<()>();
+        let handle = scheduler
+            .submit(spec, move |_| {
+                let _ = wait.recv();
+            })
+            .unwrap();
+        (handle, release)
+    }
+
+    #[test]
+    fn test_priority_order_and_cancel() {
+        let scheduler = JobScheduler::new(SchedulerConfig::default());
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (first, release) = blocker(&scheduler, JobSpec::new("blocker"));
+        assert_eq!(first.state(), JobState::Running(Placement::Cpu));
+
+        for (name, priority) in [("low", -1), ("high", 5), ("dropped", 9), ("mid", 0)] {
+            let spec = JobSpec::new(name).priority(priority);
+            let handle = scheduler.submit(spec, recording(&log, name)).unwrap();
+            if name == "dropped" {
+                assert!(handle.cancel());
+                assert_eq!(handle.state(), JobState::Cancelled);
+            }
+        }
+        assert_eq!(scheduler.status().queued, 3);
+        drop(release);
+        scheduler.wait_idle();
+        assert_eq!(*log.lock().unwrap(), ["high", "mid", "low"]);
+        assert_eq!(first.wait(), JobState::Finished(Placement::Cpu));
+        assert!(!first.cancel());
+    }
+
+    #[test]
+    fn test_fair_share_alternates_owners() {
+        let config = SchedulerConfig {
+            policy: SchedulingPolicy::FairShare,
+            ..Default::default()
+        };
+        let scheduler = JobScheduler::new(config);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (_, release) = blocker(&scheduler, JobSpec::new("blocker").owner("carol"));
+        for (name, owner) in [
+            ("a1", "alice"),
+            ("a2", "alice"),
+            ("a3", "alice"),
+            ("b1", "bob"),
+        ] {
+            let spec = JobSpec::new(name).owner(owner).priority(1);
+            scheduler.submit(spec, recording(&log, name)).unwrap();
+        }
+        drop(release);
+        scheduler.wait_idle();
+        assert_eq!(*log.lock().unwrap(), ["a1", "b1", "a2", "a3"]);
+    }
+
+    #[test]
+    fn test_gpu_reservations_share_devices() {
+        let config = SchedulerConfig {
+            max_cpu_jobs: 1,
+            gpus: vec![GpuSlot::new(0, 100), GpuSlot::new(1, 50)],
+            ..Default::default()
+        };
+        let scheduler = JobScheduler::new(config);
+        assert!(scheduler
+            .submit(JobSpec::new("huge").vram_bytes(101), |_| {})
+            .is_err());
+
+        // Best fit puts the 40-byte job on the smaller GPU
+        let (small, release_small) = blocker(&scheduler, JobSpec::new("small").vram_bytes(40));
+        assert_eq!(
+            small.state(),
+            JobState::Running(Placement::Gpu { device: 1 })
+        );
+        let (big, release_big) = blocker(&scheduler, JobSpec::new("big").vram_bytes(70));
+        assert_eq!(big.state(), JobState::Running(Placement::Gpu { device: 0 }));
+        // 60 bytes fit nowhere now, and the 10-byte job must not overtake it
+        let (wide, release_wide) = blocker(&scheduler, JobSpec::new("wide").vram_bytes(60));
+        let (tiny, release_tiny) = blocker(&scheduler, JobSpec::new("tiny").vram_bytes(10));
+        let (cpu, release_cpu) = blocker(&scheduler, JobSpec::new("cpu"));
+        assert_eq!(wide.state(), JobState::Queued);
+        assert_eq!(tiny.state(), JobState::Queued);
+        assert_eq!(cpu.state(), JobState::Running(Placement::Cpu));
+        let status = scheduler.status();
+        assert_eq!(status.queued, 2);
+        assert_eq!(status.gpus[0].reserved_bytes, 70);
+        assert_eq!(status.gpus[1].reserved_bytes, 40);
+
+        drop(release_big);
+        assert_eq!(big.wait(), JobState::Finished(Placement::Gpu { device: 0 }));
+        assert_eq!(
+            wide.state(),
+            JobState::Running(Placement::Gpu { device: 0 })
+        );
+        assert_eq!(
+            tiny.state(),
+            JobState::Running(Placement::Gpu { device: 1 })
+        );
+        assert_eq!(scheduler.status().gpus[1].jobs, 2);
+
+        for release in [release_small, release_wide, release_tiny, release_cpu] {
+            drop(release);
+        }
+        scheduler.wait_idle();
+        let status = scheduler.status();
+        assert!(status
+            .gpus
+            .iter()
+            .all(|g| g.jobs == 0 && g.reserved_bytes == 0));
+        assert_eq!(status.running_cpu, 0);
+    }
+}
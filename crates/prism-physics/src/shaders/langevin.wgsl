@@ -0,0 +1,70 @@
+// crates/prism-physics/src/shaders/langevin.wgsl
+//
+// Euler-Maruyama Langevin step, the device form of the host integrator
+// (MolecularDynamicsEngine::langevin_step):
+//   v += dt (F / m - gamma v) + sqrt(2 gamma kT dt / m) xi,   x += v dt
+//
+// ASSUMPTIONS:
+// - positions[i] = (x, y, z, mass), velocities and forces vec4 stride
+// - Noise comes from a counter-based hash of (seed, step, atom), so there
+//   is no RNG state to keep and a step can be replayed exactly
+// - Constraints are applied by the host afterwards
+// Units: Angstrom, ps, kcal/mol (temperature as k_B T).
+
+struct Uniforms {
+    num_atoms: u32,
+    step: u32,
+    seed_lo: u32,
+    seed_hi: u32,
+    dt: f32,
+    friction: f32,
+    temperature: f32,
+    _pad: f32,
+}
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var<storage, read_write> positions: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> velocities: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read> forces: array<vec4<f32>>;
+
+// PCG output permutation as an integer hash
+fn pcg_hash(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Uniform in (0, 1)
+fn unit(h: u32) -> f32 {
+    return (f32(h >> 8u) + 0.5) * (1.0 / 16777216.0);
+}
+
+// Three standard normals via Box-Muller
+fn gaussian3(key: u32) -> vec3<f32> {
+    let h0 = pcg_hash(key);
+    let h1 = pcg_hash(h0);
+    let h2 = pcg_hash(h1);
+    let h3 = pcg_hash(h2);
+    let r0 = sqrt(-2.0 * log(unit(h0)));
+    let r1 = sqrt(-2.0 * log(unit(h2)));
+    let t0 = 6.2831853 * unit(h1);
+    let t1 = 6.2831853 * unit(h3);
+    return vec3<f32>(r0 * cos(t0), r0 * sin(t0), r1 * cos(t1));
+}
+
+@compute @workgroup_size(128)
+fn langevin_step(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let i = gid.x;
+    if (i >= u.num_atoms) {
+        return;
+    }
+    let p = positions[i];
+    let mass = max(p.w, 1e-6);
+    let noise_scale = sqrt(max(2.0 * u.friction * u.temperature * u.dt, 0.0) / mass);
+    let key = pcg_hash(u.seed_lo ^ pcg_hash(u.seed_hi ^ pcg_hash(u.step ^ pcg_hash(i))));
+
+    var v = velocities[i].xyz;
+    v += u.dt * (forces[i].xyz / mass - u.friction * v) + noise_scale * gaussian3(key);
+    velocities[i] = vec4<f32>(v, 0.0);
+    positions[i] = vec4<f32>(p.xyz + v * u.dt, p.w);
+}
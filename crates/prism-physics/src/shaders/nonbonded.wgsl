@@ -0,0 +1,153 @@
+// crates/prism-physics/src/shaders/nonbonded.wgsl
+//
+// Lennard-Jones 12-6 + Coulomb forces within a cutoff over all pairs: the
+// WGSL port of nonbonded_forces_kernel (prism-gpu/src/kernels/
+// nonbonded_forces.cu) for GPUs without CUDA. Plain Coulomb only; PME
+// systems need the CUDA path.
+//
+// ASSUMPTIONS:
+// - Workgroup size == TILE (positions staged through workgroup memory)
+// - Positions and parameters are vec4 stride:
+//     positions[i] = (x, y, z, mass), params[i] = (sigma, epsilon, charge, type)
+// - Exclusion partners of each atom are sorted ascending (CSR layout)
+// - overrides holds num_types^2 (sigma, epsilon) pairs, epsilon < 0 = mixing
+//   rule; with num_types == 0 it is a one-element placeholder
+// - A box edge of 0 means open boundaries; otherwise the minimum image is used
+// - One invocation per atom accumulates the force on that atom only, so no
+//   atomics are needed; pair energies are halved per atom.
+// Units: Angstrom, kcal/mol, elementary charge.
+
+const TILE: u32 = 128u;
+
+struct Uniforms {
+    num_atoms: u32,
+    num_types: u32,
+    cutoff: f32,
+    switch_on: f32,      // >= cutoff disables switching
+    coulomb_scale: f32,  // Coulomb constant / dielectric
+    box_size: vec4<f32>, // xyz, 0 = open boundaries
+}
+
+@group(0) @binding(0) var<uniform> u: Uniforms;
+@group(0) @binding(1) var<storage, read> positions: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read> params: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read> excl_offsets: array<u32>; // num_atoms + 1
+@group(0) @binding(4) var<storage, read> excl_atoms: array<u32>;
+@group(0) @binding(5) var<storage, read> overrides: array<vec2<f32>>;
+@group(0) @binding(6) var<storage, read_write> forces: array<vec4<f32>>;
+@group(0) @binding(7) var<storage, read_write> energies: array<vec2<f32>>; // per atom (lj, coulomb)
+
+var<workgroup> tile_pos: array<vec4<f32>, TILE>;
+var<workgroup> tile_par: array<vec4<f32>, TILE>;
+
+fn minimum_image(d: vec3<f32>) -> vec3<f32> {
+    let periodic = u.box_size.xyz > vec3<f32>(0.0);
+    let edge = select(vec3<f32>(1.0), u.box_size.xyz, periodic);
+    return select(d, d - edge * round(d / edge), periodic);
+}
+
+@compute @workgroup_size(128)
+fn nonbonded_forces(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_id) lid: vec3<u32>,
+) {
+    let i = gid.x;
+    let n = u.num_atoms;
+    let in_range = i < n;
+
+    var pi = vec4<f32>(0.0);
+    var qi = vec4<f32>(0.0);
+    var ex = 0u;
+    var ex_end = 0u;
+    if (in_range) {
+        pi = positions[i];
+        qi = params[i];
+        ex = excl_offsets[i];
+        ex_end = excl_offsets[i + 1u];
+    }
+
+    let cutoff2 = u.cutoff * u.cutoff;
+    let switched = u.switch_on < u.cutoff;
+    let ron2 = u.switch_on * u.switch_on;
+    var sw_denom = 0.0;
+    if (switched) {
+        let w = cutoff2 - ron2;
+        sw_denom = 1.0 / (w * w * w);
+    }
+
+    var f = vec3<f32>(0.0);
+    var e_lj = 0.0;
+    var e_coul = 0.0;
+
+    for (var base = 0u; base < n; base += TILE) {
+        let load = base + lid.x;
+        if (load < n) {
+            tile_pos[lid.x] = positions[load];
+            tile_par[lid.x] = params[load];
+        }
+        workgroupBarrier();
+
+        let count = min(TILE, n - base);
+        if (in_range) {
+            for (var t = 0u; t < count; t++) {
+                let j = base + t;
+                if (j == i) {
+                    continue;
+                }
+                while (ex < ex_end && excl_atoms[ex] < j) {
+                    ex++;
+                }
+                if (ex < ex_end && excl_atoms[ex] == j) {
+                    continue;
+                }
+
+                let pj = tile_pos[t];
+                let qj = tile_par[t];
+                let d = minimum_image(pj.xyz - pi.xyz);
+                let r2 = dot(d, d);
+                if (r2 >= cutoff2 || r2 <= 0.0) {
+                    continue;
+                }
+
+                var sigma = 0.5 * (qi.x + qj.x);
+                var epsilon = sqrt(qi.y * qj.y);
+                if (u.num_types > 0u) {
+                    let ov = overrides[u32(qi.w) * u.num_types + u32(qj.w)];
+                    if (ov.y >= 0.0) {
+                        sigma = ov.x;
+                        epsilon = ov.y;
+                    }
+                }
+
+                let r = sqrt(r2);
+                let sr2 = sigma * sigma / r2;
+                let sr6 = sr2 * sr2 * sr2;
+                let elj = 4.0 * epsilon * (sr6 * sr6 - sr6);
+                let dlj = -24.0 * epsilon * (2.0 * sr6 * sr6 - sr6) / r;
+                let qq = u.coulomb_scale * qi.z * qj.z;
+                let ec = qq / r;
+                let dc = -qq / r2;
+
+                // CHARMM switching function S(r) and dS/dr
+                var s = 1.0;
+                var ds = 0.0;
+                if (switched && r2 > ron2) {
+                    let a = cutoff2 - r2;
+                    s = a * a * (cutoff2 + 2.0 * r2 - 3.0 * ron2) * sw_denom;
+                    ds = 12.0 * r * a * (ron2 - r2) * sw_denom;
+                }
+
+                let f_over_r = -((dlj + dc) * s + (elj + ec) * ds) / r;
+                f -= f_over_r * d;
+                e_lj += 0.5 * elj * s;
+                e_coul += 0.5 * ec * s;
+            }
+        }
+        workgroupBarrier();
+    }
+
+    if (in_range) {
+        forces[i] = vec4<f32>(f, 0.0);
+        energies[i] = vec2<f32>(e_lj, e_coul);
+    }
+}
@@ -0,0 +1,438 @@
+//! # Telemetry Stream - Live Run Statistics over WebSocket
+//! Pushes the [`MolecularDynamicsStats`] of a running engine as JSON text
+//! frames to every connected WebSocket client, so a browser can plot energy
+//! and gradient convergence while a multi-hour run is going on.
+//!
+//! `GET /ws` upgrades to a WebSocket (RFC 6455) that first replays the last
+//! [`HISTORY_FRAMES`] samples and then streams new ones; `GET /` serves a
+//! minimal dashboard plotting them. Clients that fall behind skip samples
+//! rather than slowing down the publisher, which never blocks the
+//! integration loop.
+//!
+//! Requires the `websocket` feature.
+
+use crate::molecular_dynamics::{MolecularDynamicsEngine, MolecularDynamicsStats};
+use base64::Engine as _;
+use prism_core::PrismError;
+use sha1::{Digest, Sha1};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+/// Samples replayed to a newly connected client
+pub const HISTORY_FRAMES: usize = 1024;
+
+/// Samples queued per client before it starts skipping
+const CLIENT_BACKLOG: usize = 256;
+
+/// Largest request head or client frame accepted
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Appended to the client key for `Sec-WebSocket-Accept` (RFC 6455 §1.3)
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+const DASHBOARD_HTML: &str = r##"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>PRISM-4D run</title>
+<style>body{font-family:sans-serif;margin:1em}canvas{width:100%;height:240px;border:1px solid #ccc}</style>
+</head><body>
+<h3 id="status">Connecting…</h3>
+<div>Potential energy (kcal/mol)</div><canvas id="energy"></canvas>
+<div>Gradient norm (kcal/mol/Å)</div><canvas id="gradient"></canvas>
+<script>
+const series = { energy: [], gradient: [] };
+function plot(id, points) {
+  const canvas = document.getElementById(id), ctx = canvas.getContext("2d");
+  canvas.width = canvas.clientWidth; canvas.height = canvas.clientHeight;
+  if (points.length < 2) return;
+  const xs = points.map(p => p[0]), ys = points.map(p => p[1]);
+  const x0 = Math.min(...xs), x1 = Math.max(...xs), y0 = Math.min(...ys), y1 = Math.max(...ys);
+  const sx = x => (x - x0) / ((x1 - x0) || 1) * (canvas.width - 8) + 4;
+  const sy = y => canvas.height - 4 - (y - y0) / ((y1 - y0) || 1) * (canvas.height - 8);
+  ctx.beginPath(); ctx.strokeStyle = "#1f77b4";
+  points.forEach((p, i) => i ? ctx.lineTo(sx(p[0]), sy(p[1])) : ctx.moveTo(sx(p[0]), sy(p[1])));
+  ctx.stroke();
+}
+const socket = new WebSocket(`ws://${location.host}/ws`);
+socket.onmessage = event => {
+  const stats = JSON.parse(event.data);
+  series.energy.push([stats.current_step, stats.current_energy]);
+  series.gradient.push([stats.current_step, stats.gradient_norm]);
+  document.getElementById("status").textContent =
+    `Step ${stats.current_step} / ${stats.total_steps}, T = ${stats.current_temperature.toFixed(1)}`;
+  plot("energy", series.energy); plot("gradient", series.gradient);
+};
+socket.onclose = () => document.getElementById("status").textContent += " (run ended)";
+</script></body></html>
+"##;
+
+/// State shared by the publisher and the connections
+#[derive(Debug)]
+struct Shared {
+    history: Mutex<VecDeque<Arc<str>>>,
+    frames: broadcast::Sender<Arc<str>>,
+}
+
+/// Handle pushing statistics to the connected clients
+#[derive(Debug, Clone)]
+pub struct TelemetryPublisher {
+    shared: Arc<Shared>,
+}
+
+impl TelemetryPublisher {
+    /// Send `stats` to every client and keep it for later ones
+    pub fn publish(&self, stats: &MolecularDynamicsStats) {
+        let text: Arc<str> = match serde_json::to_string(stats) {
+            Ok(text) => text.into(),
+            Err(e) => return log::warn!("⚠️ Telemetry sample not serializable: {}", e),
+        };
+        // Sent under the lock, so a client subscribing with the history
+        // snapshot neither misses nor repeats a sample
+        let mut history = self.shared.history.lock().unwrap_or_else(|e| e.into_inner());
+        if history.len() == HISTORY_FRAMES {
+            history.pop_front();
+        }
+        history.push_back(text.clone());
+        let _ = self.shared.frames.send(text);
+    }
+
+    /// Run observer publishing every sample it sees, for
+    /// [`MolecularDynamicsEngine::add_observer`]
+    pub fn observer(&self) -> impl FnMut(&MolecularDynamicsStats) -> ControlFlow<()> + Send + 'static {
+        let publisher = self.clone();
+        move |stats| {
+            publisher.publish(stats);
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+/// Running WebSocket server; dropping it closes all connections
+pub struct TelemetryServer {
+    publisher: TelemetryPublisher,
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl TelemetryServer {
+    /// Listen on `addr` (port 0 picks a free port)
+    pub fn start(addr: SocketAddr) -> Result<Self, PrismError> {
+        let listener = std::net::TcpListener::bind(addr)
+            .map_err(|e| PrismError::config(format!("Cannot bind telemetry stream {}: {}", addr, e)))?;
+        let addr = listener.local_addr().map_err(|e| PrismError::Internal(format!("Telemetry stream address: {}", e)))?;
+        listener.set_nonblocking(true).map_err(|e| PrismError::Internal(format!("Telemetry stream: {}", e)))?;
+
+        let (frames, _) = broadcast::channel(CLIENT_BACKLOG);
+        let shared = Arc::new(Shared { history: Mutex::new(VecDeque::with_capacity(HISTORY_FRAMES)), frames });
+        let connections = shared.clone();
+        let (shutdown, mut stopped) = oneshot::channel::<()>();
+        let server = std::thread::Builder::new()
+            .name("prism-telemetry-ws".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                    Ok(runtime) => runtime,
+                    Err(e) => return log::error!("Telemetry stream runtime failed: {}", e),
+                };
+                runtime.block_on(async move {
+                    let listener = match tokio::net::TcpListener::from_std(listener) {
+                        Ok(listener) => listener,
+                        Err(e) => return log::error!("Telemetry stream failed: {}", e),
+                    };
+                    loop {
+                        tokio::select! {
+                            _ = &mut stopped => break,
+                            accepted = listener.accept() => match accepted {
+                                Ok((stream, peer)) => {
+                                    let shared = connections.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(e) = serve_connection(stream, shared).await {
+                                            log::debug!("Telemetry client {} disconnected: {}", peer, e);
+                                        }
+                                    });
+                                }
+                                Err(e) => log::warn!("⚠️ Telemetry stream accept failed: {}", e),
+                            },
+                        }
+                    }
+                });
+            })
+            .map_err(|e| PrismError::Internal(format!("Failed to start telemetry stream: {}", e)))?;
+
+        log::info!("📡 Telemetry stream on ws://{}/ws (dashboard at http://{}/)", addr, addr);
+        Ok(Self { publisher: TelemetryPublisher { shared }, addr, shutdown: Some(shutdown), server: Some(server) })
+    }
+
+    /// Bound address (with the actual port when started on port 0)
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn publisher(&self) -> TelemetryPublisher {
+        self.publisher.clone()
+    }
+
+    /// Publish the statistics of `engine` every `interval` steps of its runs
+    pub fn attach(&self, engine: &mut MolecularDynamicsEngine, interval: u64) {
+        engine.add_observer(interval, self.publisher.observer());
+    }
+}
+
+impl Drop for TelemetryServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+/// `Sec-WebSocket-Accept` answering the client's `Sec-WebSocket-Key`
+fn accept_key(key: &str) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(key.trim().as_bytes());
+    sha1.update(WEBSOCKET_GUID.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(sha1.finalize())
+}
+
+/// Unmasked, unfragmented server frame
+fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Opcode and unmasked payload of the next client frame
+async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> std::io::Result<(u8, Vec<u8>)> {
+    let mut head = [0u8; 2];
+    reader.read_exact(&mut head).await?;
+    let len = match head[1] & 0x7F {
+        126 => reader.read_u16().await? as u64,
+        127 => reader.read_u64().await?,
+        len => len as u64,
+    };
+    if len > MAX_REQUEST_BYTES as u64 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("client frame of {} bytes", len)));
+    }
+    let mut mask = [0u8; 4];
+    if head[1] & 0x80 != 0 {
+        reader.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload).await?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((head[0] & 0x0F, payload))
+}
+
+/// Path and lowercase-named headers of an HTTP request head
+fn parse_request(head: &str) -> Option<(&str, Vec<(String, &str)>)> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    if request_line.next()? != "GET" {
+        return None;
+    }
+    let path = request_line.next()?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+        .collect();
+    Some((path, headers))
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn serve_connection(mut stream: TcpStream, shared: Arc<Shared>) -> std::io::Result<()> {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !head.ends_with(b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 || head.len() + n > MAX_REQUEST_BYTES {
+            return Ok(());
+        }
+        head.extend_from_slice(&chunk[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let Some((path, headers)) = parse_request(&head) else {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", "GET only\n").await;
+    };
+    let header = |name: &str| headers.iter().find(|(n, _)| n == name).map(|(_, v)| *v);
+    let upgrade = header("upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    match (path, upgrade, header("sec-websocket-key")) {
+        ("/ws", true, Some(key)) => stream_frames(stream, key, &shared).await,
+        ("/", false, _) => respond(&mut stream, "200 OK", "text/html; charset=utf-8", DASHBOARD_HTML).await,
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "Not found\n").await,
+    }
+}
+
+/// Complete the WebSocket handshake, then replay the history and forward
+/// new samples until the client or the server goes away
+async fn stream_frames(stream: TcpStream, key: &str, shared: &Shared) -> std::io::Result<()> {
+    let (mut reader, mut writer) = stream.into_split();
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    );
+    writer.write_all(response.as_bytes()).await?;
+
+    let (mut frames, history) = {
+        let history = shared.history.lock().unwrap_or_else(|e| e.into_inner());
+        (shared.frames.subscribe(), history.iter().cloned().collect::<Vec<_>>())
+    };
+    for text in history {
+        writer.write_all(&encode_frame(OP_TEXT, text.as_bytes())).await?;
+    }
+
+    // Client frames are read on their own task, so a partially received
+    // frame is never lost to the select below
+    let (control, mut client) = mpsc::channel(8);
+    let read_task = tokio::spawn(async move {
+        while let Ok(frame) = read_frame(&mut reader).await {
+            if control.send(frame).await.is_err() {
+                break;
+            }
+        }
+    });
+    let result = async {
+        loop {
+            tokio::select! {
+                frame = frames.recv() => match frame {
+                    Ok(text) => writer.write_all(&encode_frame(OP_TEXT, text.as_bytes())).await?,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => log::debug!("Telemetry client skipped {} samples", skipped),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                frame = client.recv() => match frame {
+                    Some((OP_PING, payload)) => writer.write_all(&encode_frame(OP_PONG, &payload)).await?,
+                    Some((OP_CLOSE, _)) | None => break,
+                    Some(_) => {}
+                },
+            }
+        }
+        writer.write_all(&encode_frame(OP_CLOSE, &[])).await
+    }
+    .await;
+    read_task.abort();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::molecular_dynamics::EnergyComponents;
+    use std::io::{Read, Write};
+
+    fn stats(step: u64, energy: f32) -> MolecularDynamicsStats {
+        MolecularDynamicsStats {
+            current_step: step,
+            total_steps: 1000,
+            current_energy: energy,
+            current_temperature: 300.0,
+            acceptance_rate: 1.0,
+            pimc_moves: Vec::new(),
+            gradient_norm: 1.5,
+            position_restraint_energy: 0.0,
+            geometric_restraint_energy: 0.0,
+            energy: EnergyComponents::default(),
+            pressure: None,
+            runtime_seconds: 0.1,
+            converged: false,
+        }
+    }
+
+    #[test]
+    fn test_handshake_and_framing() {
+        // RFC 6455 §1.3 example
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(encode_frame(OP_TEXT, b"Hello"), b"\x81\x05Hello");
+        let long = encode_frame(OP_TEXT, &[b'x'; 300]);
+        assert_eq!(&long[..4], &[0x81, 126, 0x01, 0x2C]);
+        assert_eq!(long.len(), 304);
+
+        // Masked "Hello" from RFC 6455 §5.7
+        let masked = [0x81u8, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let (opcode, payload) = runtime.block_on(read_frame(&mut &masked[..])).unwrap();
+        assert_eq!((opcode, payload.as_slice()), (OP_TEXT, &b"Hello"[..]));
+    }
+
+    #[test]
+    fn test_client_receives_history_and_new_samples() {
+        let server = TelemetryServer::start(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+        let publisher = server.publisher();
+        publisher.publish(&stats(100, -50.0));
+
+        let mut stream = std::net::TcpStream::connect(server.addr()).unwrap();
+        stream.set_read_timeout(Some(std::time::Duration::from_secs(10))).unwrap();
+        write!(
+            stream,
+            "GET /ws HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = Vec::new();
+        let mut byte = [0u8];
+        while !response.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            response.push(byte[0]);
+        }
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101"));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+        let mut next_sample = || {
+            let mut head = [0u8; 2];
+            stream.read_exact(&mut head).unwrap();
+            assert_eq!(head[0], 0x81);
+            let len = match head[1] {
+                126 => {
+                    let mut len = [0u8; 2];
+                    stream.read_exact(&mut len).unwrap();
+                    u16::from_be_bytes(len) as usize
+                }
+                len => len as usize,
+            };
+            let mut payload = vec![0u8; len];
+            stream.read_exact(&mut payload).unwrap();
+            serde_json::from_slice::<MolecularDynamicsStats>(&payload).unwrap()
+        };
+        let replayed = next_sample();
+        assert_eq!((replayed.current_step, replayed.current_energy), (100, -50.0));
+        // The replay is written after subscribing, so this one is not lost
+        publisher.publish(&stats(200, -60.0));
+        assert_eq!(next_sample().current_step, 200);
+    }
+}
@@ -0,0 +1,375 @@
+//! # Umbrella Sampling - Harmonic Restraints on Collective Variables
+//! [`CvRestraint`] holds a collective variable near a centre with
+//! `V = k/2 (s - s0)^2` (minimum image for periodic CVs) and records the
+//! restrained value as it runs. [`UmbrellaSampling`] walks an engine
+//! through a series of windows, each continuing from the previous one's
+//! final configuration, and writes one `time cv` time series per window
+//! plus a `metadata.dat` listing `path centre k` lines, which is the
+//! input layout of Grossfield's `wham` and easy to load for MBAR.
+//! Units: kcal/mol, CV units, ps.
+
+use crate::collective_variables::{apply_cv_force, BiasPotential, CollectiveVariable};
+use crate::molecular_dynamics::MolecularDynamicsEngine;
+use prism_core::{PhaseOutcome, PrismError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Harmonic restraint on one collective variable
+#[derive(Debug, Clone)]
+pub struct CvRestraint {
+    cv: CollectiveVariable,
+    centre: f64,
+    force_constant: f64,
+    /// Steps between recorded samples; 0 disables recording
+    sample_interval: u64,
+    samples: Vec<(u64, f64)>,
+}
+
+impl CvRestraint {
+    pub fn new(cv: CollectiveVariable, centre: f64, force_constant: f64) -> Self {
+        Self {
+            cv,
+            centre,
+            force_constant,
+            sample_interval: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Record the CV value every `interval` steps
+    pub fn with_sampling(mut self, interval: u64) -> Self {
+        self.sample_interval = interval;
+        self
+    }
+
+    pub fn cv(&self) -> &CollectiveVariable {
+        &self.cv
+    }
+
+    pub fn centre(&self) -> f64 {
+        self.centre
+    }
+
+    pub fn force_constant(&self) -> f64 {
+        self.force_constant
+    }
+
+    /// Recorded `(step, value)` samples
+    pub fn samples(&self) -> &[(u64, f64)] {
+        &self.samples
+    }
+
+    pub fn clear_samples(&mut self) {
+        self.samples.clear();
+    }
+}
+
+impl BiasPotential for CvRestraint {
+    fn name(&self) -> &str {
+        "cv_restraint"
+    }
+
+    fn compute(&self, positions: &[f32], forces: &mut [f32]) -> f64 {
+        let (s, gradient) = self.cv.evaluate(positions);
+        let d = self.cv.difference(s, self.centre);
+        apply_cv_force(forces, &gradient, self.force_constant * d);
+        0.5 * self.force_constant * d * d
+    }
+
+    fn update(&mut self, step: u64, positions: &[f32]) {
+        if self.sample_interval > 0 && step.is_multiple_of(self.sample_interval) {
+            self.samples.push((step, self.cv.value(positions)));
+        }
+    }
+
+    fn telemetry(&self) -> Vec<(String, serde_json::Value)> {
+        vec![
+            ("restraint_centre".to_string(), json!(self.centre)),
+            (
+                "restraint_last_value".to_string(),
+                json!(self.samples.last().map(|&(_, s)| s)),
+            ),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UmbrellaWindow {
+    /// Restraint centre (CV units)
+    pub centre: f64,
+    /// Force constant k in `k/2 (s - s0)^2` (kcal/mol per CV unit²)
+    pub force_constant: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UmbrellaConfig {
+    pub windows: Vec<UmbrellaWindow>,
+    /// Unrecorded steps at the start of each window
+    pub equilibration_steps: u64,
+    /// Recorded steps per window
+    pub production_steps: u64,
+    /// Steps between recorded samples
+    pub sample_interval: u64,
+    /// Directory for the time series and `metadata.dat`
+    pub output_dir: PathBuf,
+}
+
+impl Default for UmbrellaConfig {
+    fn default() -> Self {
+        Self {
+            windows: Vec::new(),
+            equilibration_steps: 5_000,
+            production_steps: 50_000,
+            sample_interval: 100,
+            output_dir: PathBuf::from("umbrella"),
+        }
+    }
+}
+
+impl UmbrellaConfig {
+    /// `count` windows evenly spaced from `min` to `max` sharing one force constant
+    pub fn evenly_spaced(
+        min: f64,
+        max: f64,
+        count: usize,
+        force_constant: f64,
+    ) -> Vec<UmbrellaWindow> {
+        let step = if count > 1 {
+            (max - min) / (count - 1) as f64
+        } else {
+            0.0
+        };
+        (0..count)
+            .map(|k| UmbrellaWindow {
+                centre: min + k as f64 * step,
+                force_constant,
+            })
+            .collect()
+    }
+}
+
+/// Per-window summary of the recorded samples
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowSummary {
+    pub centre: f64,
+    pub force_constant: f64,
+    pub samples: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct UmbrellaSampling {
+    config: UmbrellaConfig,
+    cv: CollectiveVariable,
+    summaries: Vec<WindowSummary>,
+}
+
+impl UmbrellaSampling {
+    pub fn new(config: UmbrellaConfig, cv: CollectiveVariable) -> Result<Self, PrismError> {
+        if config.windows.is_empty() {
+            return Err(PrismError::config(
+                "Umbrella sampling needs at least one window",
+            ));
+        }
+        if config.windows.iter().any(|w| w.force_constant <= 0.0) {
+            return Err(PrismError::config(
+                "Umbrella force constants must be positive",
+            ));
+        }
+        if config.sample_interval == 0 || config.production_steps < config.sample_interval {
+            return Err(PrismError::config(
+                "Umbrella production must span at least one positive sample interval",
+            ));
+        }
+        Ok(Self {
+            config,
+            cv,
+            summaries: Vec::new(),
+        })
+    }
+
+    pub fn config(&self) -> &UmbrellaConfig {
+        &self.config
+    }
+
+    /// Summaries of the windows completed so far
+    pub fn summaries(&self) -> &[WindowSummary] {
+        &self.summaries
+    }
+
+    /// Run every window on `engine` in order. Biases already attached to the
+    /// engine stay active throughout.
+    pub fn run(
+        &mut self,
+        engine: &mut MolecularDynamicsEngine,
+    ) -> Result<PhaseOutcome, PrismError> {
+        let dir = &self.config.output_dir;
+        std::fs::create_dir_all(dir).map_err(|e| {
+            PrismError::Internal(format!("Failed to create {}: {}", dir.display(), e))
+        })?;
+        log::info!(
+            "☂️ Umbrella sampling: {} windows, {} + {} steps each",
+            self.config.windows.len(),
+            self.config.equilibration_steps,
+            self.config.production_steps
+        );
+        self.summaries.clear();
+        let dt = engine.config().dt as f64;
+
+        for (index, window) in self.config.windows.iter().enumerate() {
+            let restraint = CvRestraint::new(self.cv.clone(), window.centre, window.force_constant)
+                .with_sampling(self.config.sample_interval);
+            engine.add_bias(Box::new(restraint));
+            if self.config.equilibration_steps > 0 {
+                engine.run_nlnm_breathing(self.config.equilibration_steps)?;
+            }
+            // Equilibration samples are discarded; the restraint is the last bias attached
+            let mut restraint = pop_restraint(engine)?;
+            restraint.clear_samples();
+            engine.add_bias(restraint);
+            engine.run_nlnm_breathing(self.config.production_steps)?;
+
+            let restraint = pop_restraint(engine)?;
+            let path = dir.join(format!("window_{:03}.dat", index));
+            write_time_series(&path, &restraint, dt)?;
+
+            let values: Vec<f64> = restraint.samples().iter().map(|&(_, s)| s).collect();
+            let n = values.len().max(1) as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let std_dev = (values.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n).sqrt();
+            log::info!(
+                "☂️ Window {} (s0 = {:.3}): <s> = {:.3} ± {:.3} over {} samples",
+                index,
+                window.centre,
+                mean,
+                std_dev,
+                values.len()
+            );
+            self.summaries.push(WindowSummary {
+                centre: window.centre,
+                force_constant: window.force_constant,
+                samples: values.len(),
+                mean,
+                std_dev,
+                path,
+            });
+        }
+
+        for pair in self.summaries.windows(2) {
+            if (pair[1].mean - pair[0].mean).abs() > 2.0 * (pair[0].std_dev + pair[1].std_dev) {
+                log::warn!(
+                    "⚠️ Umbrella windows at {:.3} and {:.3} barely overlap; add windows or soften k",
+                    pair[0].centre,
+                    pair[1].centre
+                );
+            }
+        }
+        self.write_metadata()?;
+
+        let mut telemetry = HashMap::new();
+        telemetry.insert("windows".to_string(), json!(self.summaries.len()));
+        telemetry.insert(
+            "window_means".to_string(),
+            json!(self.summaries.iter().map(|w| w.mean).collect::<Vec<_>>()),
+        );
+        telemetry.insert(
+            "window_std_devs".to_string(),
+            json!(self.summaries.iter().map(|w| w.std_dev).collect::<Vec<_>>()),
+        );
+        telemetry.insert("metadata".to_string(), json!(dir.join("metadata.dat")));
+        Ok(PhaseOutcome::Success {
+            message: "Umbrella sampling complete".to_string(),
+            telemetry,
+        })
+    }
+
+    /// `path centre k` per window, in run order
+    fn write_metadata(&self) -> Result<(), PrismError> {
+        let path = self.config.output_dir.join("metadata.dat");
+        let io = |e: std::io::Error| {
+            PrismError::Internal(format!("Failed to write {}: {}", path.display(), e))
+        };
+        let mut out = std::io::BufWriter::new(std::fs::File::create(&path).map_err(io)?);
+        for window in &self.summaries {
+            writeln!(
+                out,
+                "{} {:.6} {:.6}",
+                window.path.display(),
+                window.centre,
+                window.force_constant
+            )
+            .map_err(io)?;
+        }
+        out.flush().map_err(io)
+    }
+}
+
+fn pop_restraint(engine: &mut MolecularDynamicsEngine) -> Result<Box<CvRestraint>, PrismError> {
+    engine
+        .pop_bias()
+        .and_then(|b| (b as Box<dyn Any>).downcast::<CvRestraint>().ok())
+        .ok_or_else(|| PrismError::internal("Umbrella restraint missing from engine"))
+}
+
+fn write_time_series(path: &Path, restraint: &CvRestraint, dt: f64) -> Result<(), PrismError> {
+    let io = |e: std::io::Error| {
+        PrismError::Internal(format!("Failed to write {}: {}", path.display(), e))
+    };
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path).map_err(io)?);
+    writeln!(out, "#! FIELDS time cv").map_err(io)?;
+    writeln!(out, "#! SET centre {:.6}", restraint.centre()).map_err(io)?;
+    writeln!(out, "#! SET kappa {:.6}", restraint.force_constant()).map_err(io)?;
+    for &(step, s) in restraint.samples() {
+        writeln!(out, "{:.4} {:.6}", step as f64 * dt, s).map_err(io)?;
+    }
+    out.flush().map_err(io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restraint_energy_and_force() {
+        let cv = CollectiveVariable::Distance {
+            a: vec![0],
+            b: vec![1],
+        };
+        let mut restraint = CvRestraint::new(cv, 2.0, 10.0).with_sampling(2);
+        let positions = vec![0.0, 0.0, 0.0, 12.0, 2.5, 0.0, 0.0, 12.0];
+        let mut forces = vec![0.0f32; 8];
+        let energy = restraint.compute(&positions, &mut forces);
+        assert!((energy - 1.25).abs() < 1e-9);
+        // Stretched past the centre: pulled back together
+        assert!((forces[4] + 5.0).abs() < 1e-5 && (forces[0] - 5.0).abs() < 1e-5);
+
+        for step in 1..=6 {
+            restraint.update(step, &positions);
+        }
+        assert_eq!(restraint.samples().len(), 3);
+        assert_eq!(restraint.samples()[0].0, 2);
+    }
+
+    #[test]
+    fn test_evenly_spaced_windows() {
+        let windows = UmbrellaConfig::evenly_spaced(2.0, 4.0, 5, 20.0);
+        let centres: Vec<f64> = windows.iter().map(|w| w.centre).collect();
+        assert_eq!(centres, vec![2.0, 2.5, 3.0, 3.5, 4.0]);
+        let cv = CollectiveVariable::Distance {
+            a: vec![0],
+            b: vec![1],
+        };
+        let config = UmbrellaConfig {
+            windows: Vec::new(),
+            ..Default::default()
+        };
+        assert!(UmbrellaSampling::new(config, cv).is_err());
+    }
+}
@@ -0,0 +1,342 @@
+//! # Units - Typed Physical Quantities
+//! The engine works in Å, ps, kcal/mol and amu, with temperatures given as
+//! `k_B T` in kcal/mol. The quantity types here carry a value in that native
+//! unit and are only built and read through explicitly named units, so a
+//! femtosecond time step cannot be mistaken for picoseconds:
+//!
+//! ```
+//! use prism_physics::units::{Temperature, Time};
+//! let dt = Time::fs(2.0);
+//! assert_eq!(dt.as_ps(), 0.002);
+//! assert!((Temperature::kelvin(300.0).kt().as_kcal_per_mol() - 0.596).abs() < 1e-3);
+//! ```
+//!
+//! Config fields marked `#[serde(with = "units::as_ps")]` (and friends)
+//! accept either a bare number in the native unit or a string with an
+//! explicit unit, e.g. `"dt": "2 fs"` or `"temp_start": "300 K"`.
+
+use prism_core::PrismError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Boltzmann constant (kcal/mol/K)
+pub const BOLTZMANN_KCAL_MOL_K: f64 = 0.001_987_204_259;
+/// kJ per kcal
+pub const KJ_PER_KCAL: f64 = 4.184;
+
+/// Length, stored in Å
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Length(f64);
+
+/// Time, stored in ps
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Time(f64);
+
+/// Molar energy, stored in kcal/mol
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Energy(f64);
+
+/// Absolute temperature, stored in K
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Temperature(f64);
+
+impl Length {
+    pub fn angstrom(value: f64) -> Self {
+        Self(value)
+    }
+
+    pub fn nanometer(value: f64) -> Self {
+        Self(value * 10.0)
+    }
+
+    pub fn as_angstrom(self) -> f64 {
+        self.0
+    }
+
+    pub fn as_nanometer(self) -> f64 {
+        self.0 / 10.0
+    }
+}
+
+impl Time {
+    pub fn ps(value: f64) -> Self {
+        Self(value)
+    }
+
+    pub fn fs(value: f64) -> Self {
+        Self(value / 1000.0)
+    }
+
+    pub fn ns(value: f64) -> Self {
+        Self(value * 1000.0)
+    }
+
+    pub fn as_ps(self) -> f64 {
+        self.0
+    }
+
+    pub fn as_fs(self) -> f64 {
+        self.0 * 1000.0
+    }
+
+    pub fn as_ns(self) -> f64 {
+        self.0 / 1000.0
+    }
+}
+
+impl Energy {
+    pub fn kcal_per_mol(value: f64) -> Self {
+        Self(value)
+    }
+
+    pub fn kj_per_mol(value: f64) -> Self {
+        Self(value / KJ_PER_KCAL)
+    }
+
+    pub fn as_kcal_per_mol(self) -> f64 {
+        self.0
+    }
+
+    pub fn as_kj_per_mol(self) -> f64 {
+        self.0 * KJ_PER_KCAL
+    }
+}
+
+impl Temperature {
+    pub fn kelvin(value: f64) -> Self {
+        Self(value)
+    }
+
+    /// Temperature whose thermal energy `k_B T` is `kt`
+    pub fn from_kt(kt: Energy) -> Self {
+        Self(kt.0 / BOLTZMANN_KCAL_MOL_K)
+    }
+
+    pub fn as_kelvin(self) -> f64 {
+        self.0
+    }
+
+    /// Thermal energy `k_B T`, the engine's temperature scale
+    pub fn kt(self) -> Energy {
+        Energy(self.0 * BOLTZMANN_KCAL_MOL_K)
+    }
+}
+
+/// Split `"2.5 fs"` / `"2.5fs"` into value and unit
+fn split_quantity<'a>(text: &'a str, kind: &str) -> Result<(f64, &'a str), PrismError> {
+    let text = text.trim();
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+        .unwrap_or(text.len());
+    // An exponent marker must be followed by digits, not start a unit
+    let end = match text[..end].rfind(['e', 'E']) {
+        Some(e) if e + 1 == end => e,
+        _ => end,
+    };
+    let value = text[..end].parse().map_err(|_| {
+        PrismError::config(format!(
+            "Invalid {} '{}': expected a number and a unit",
+            kind, text
+        ))
+    })?;
+    Ok((value, text[end..].trim()))
+}
+
+fn unknown_unit(kind: &str, unit: &str, expected: &str) -> PrismError {
+    PrismError::config(format!(
+        "Unknown {} unit '{}' (expected {})",
+        kind, unit, expected
+    ))
+}
+
+impl FromStr for Length {
+    type Err = PrismError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split_quantity(s, "length")? {
+            (v, "A" | "Å" | "angstrom") => Ok(Self::angstrom(v)),
+            (v, "nm" | "nanometer") => Ok(Self::nanometer(v)),
+            (_, unit) => Err(unknown_unit("length", unit, "Å or nm")),
+        }
+    }
+}
+
+impl FromStr for Time {
+    type Err = PrismError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split_quantity(s, "time")? {
+            (v, "fs") => Ok(Self::fs(v)),
+            (v, "ps") => Ok(Self::ps(v)),
+            (v, "ns") => Ok(Self::ns(v)),
+            (_, unit) => Err(unknown_unit("time", unit, "fs, ps or ns")),
+        }
+    }
+}
+
+impl FromStr for Energy {
+    type Err = PrismError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split_quantity(s, "energy")? {
+            (v, "kcal/mol") => Ok(Self::kcal_per_mol(v)),
+            (v, "kJ/mol" | "kj/mol") => Ok(Self::kj_per_mol(v)),
+            (_, unit) => Err(unknown_unit("energy", unit, "kcal/mol or kJ/mol")),
+        }
+    }
+}
+
+impl FromStr for Temperature {
+    type Err = PrismError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split_quantity(s, "temperature")? {
+            (v, "K") if v >= 0.0 => Ok(Self::kelvin(v)),
+            (v, "K") => Err(PrismError::config(format!(
+                "Negative absolute temperature {} K",
+                v
+            ))),
+            (_, unit) => Err(unknown_unit("temperature", unit, "K")),
+        }
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} Å", self.0)
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ps", self.0)
+    }
+}
+
+impl fmt::Display for Energy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} kcal/mol", self.0)
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} K", self.0)
+    }
+}
+
+/// A bare number in the native unit or a string with an explicit unit
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawQuantity {
+    Number(f64),
+    Text(String),
+}
+
+fn deserialize_native<'de, D, Q>(
+    deserializer: D,
+    native: impl Fn(Q) -> f64,
+) -> Result<f32, D::Error>
+where
+    D: Deserializer<'de>,
+    Q: FromStr<Err = PrismError>,
+{
+    match RawQuantity::deserialize(deserializer)? {
+        RawQuantity::Number(value) => Ok(value as f32),
+        RawQuantity::Text(text) => text
+            .parse::<Q>()
+            .map(|q| native(q) as f32)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+macro_rules! native_field {
+    ($name:ident, $quantity:ty, $native:expr, $doc:literal) => {
+        #[doc = $doc]
+        pub mod $name {
+            use super::*;
+
+            pub fn serialize<S: Serializer>(value: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+                value.serialize(serializer)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<f32, D::Error> {
+                deserialize_native::<D, $quantity>(deserializer, $native)
+            }
+        }
+    };
+}
+
+native_field!(
+    as_ps,
+    Time,
+    Time::as_ps,
+    "`f32` field in ps; also accepts `\"2 fs\"`, `\"0.002 ps\"`"
+);
+native_field!(
+    as_angstrom,
+    Length,
+    Length::as_angstrom,
+    "`f32` field in Å; also accepts `\"1.2 nm\"`, `\"12 A\"`"
+);
+native_field!(
+    as_kt,
+    Temperature,
+    |t: Temperature| t.kt().as_kcal_per_mol(),
+    "`f32` field holding `k_B T` in kcal/mol; also accepts `\"300 K\"`"
+);
+native_field!(
+    as_kcal_per_mol,
+    Energy,
+    Energy::as_kcal_per_mol,
+    "`f32` field in kcal/mol; also accepts `\"4.184 kJ/mol\"`"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_convert() {
+        assert_eq!("2 fs".parse::<Time>().unwrap(), Time::ps(0.002));
+        assert_eq!("1e-3ps".parse::<Time>().unwrap().as_fs(), 1.0);
+        assert_eq!("1.2 nm".parse::<Length>().unwrap().as_angstrom(), 12.0);
+        assert!(("4.184 kJ/mol".parse::<Energy>().unwrap().as_kcal_per_mol() - 1.0).abs() < 1e-12);
+        let room = "300 K".parse::<Temperature>().unwrap();
+        assert!((Temperature::from_kt(room.kt()).as_kelvin() - 300.0).abs() < 1e-9);
+
+        assert!("2".parse::<Time>().is_err());
+        assert!("2 fms".parse::<Time>().is_err());
+        assert!("-5 K".parse::<Temperature>().is_err());
+    }
+
+    #[test]
+    fn test_config_fields_accept_units() {
+        #[derive(Serialize, Deserialize)]
+        struct Config {
+            #[serde(with = "as_ps")]
+            dt: f32,
+            #[serde(with = "as_kt")]
+            temperature: f32,
+        }
+
+        let config: Config =
+            serde_json::from_str(r#"{"dt": "2 fs", "temperature": "300 K"}"#).unwrap();
+        assert_eq!(config.dt, 0.002);
+        assert!((config.temperature - 0.596).abs() < 1e-3);
+        // Bare numbers are in the native unit and round-trip unchanged
+        let config: Config = serde_json::from_str(r#"{"dt": 0.001, "temperature": 2.5}"#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"dt":0.001,"temperature":2.5}"#
+        );
+        assert!(serde_json::from_str::<Config>(r#"{"dt": "2 K", "temperature": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! # WebGPU - Nonbonded Forces and Langevin Steps through `wgpu`
+//! Portable GPU backend, selected with `backend = "wgpu"` and built with the
+//! `wgpu` feature. It runs the WGSL kernels of [`crate::wgsl`] on whatever
+//! adapter `wgpu` finds (Vulkan, Metal, DirectX 12 or OpenGL), so AMD,
+//! Intel and Apple GPUs get device forces and integration without CUDA.
+//! `WGPU_BACKEND` and `WGPU_ADAPTER_NAME` narrow the adapter choice.
+//!
+//! Like the CUDA and HIP evaluators, the device only computes the cutoff
+//! pair terms: 1-4 pairs and solvation are added on the host, and the
+//! Langevin pass integrates the total force the host hands back. All pairs
+//! are scanned (O(N²)); PME and triclinic systems stay on the host.
+//! Positions, velocities and forces round-trip through host memory every
+//! step, so bonded terms, constraints and analyses see the same buffers as
+//! on the CPU path.
+
+use crate::force_field::PairTables;
+use crate::wgsl::{self, langevin_binding, nonbonded_binding, LangevinUniforms, NonbondedUniforms};
+use prism_core::PrismError;
+
+/// Storage buffers bound by `nonbonded_forces`
+const NONBONDED_STORAGE_BUFFERS: u32 = 7;
+
+/// Device, pipelines and persistent buffers of the WGSL kernels
+#[derive(Debug)]
+pub struct WgpuCompute {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    adapter: String,
+    num_atoms: usize,
+    nonbonded: wgpu::ComputePipeline,
+    langevin: wgpu::ComputePipeline,
+    nonbonded_group: wgpu::BindGroup,
+    langevin_group: wgpu::BindGroup,
+    langevin_uniforms: wgpu::Buffer,
+    positions: wgpu::Buffer,
+    velocities: wgpu::Buffer,
+    forces: wgpu::Buffer,
+    energies: wgpu::Buffer,
+    /// Host-visible copy target of every download
+    readback: wgpu::Buffer,
+}
+
+fn check<T>(result: Result<T, impl std::fmt::Display>, op: &str) -> Result<T, PrismError> {
+    result.map_err(|e| PrismError::gpu(op, e.to_string()))
+}
+
+impl WgpuCompute {
+    /// Open the preferred adapter, compile both kernels and upload the
+    /// parameter tables
+    pub(crate) fn new(tables: &PairTables, cutoff: f32, switch_distance: Option<f32>, box_lengths: Option<[f32; 3]>) -> Result<Self, PrismError> {
+        let n = tables.params.len();
+        if n == 0 {
+            return Err(PrismError::validation("Nonbonded system has no atoms"));
+        }
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle_from_env());
+        let options = wgpu::RequestAdapterOptions { power_preference: wgpu::PowerPreference::HighPerformance, ..Default::default() };
+        let adapter = check(pollster::block_on(instance.request_adapter(&options)), "wgpu_adapter")?;
+        let info = adapter.get_info();
+        if !adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS) {
+            return Err(PrismError::gpu("wgpu_adapter", format!("{} ({:?}) has no compute shaders", info.name, info.backend)));
+        }
+        let limits = adapter.limits();
+        if limits.max_storage_buffers_per_shader_stage < NONBONDED_STORAGE_BUFFERS {
+            return Err(PrismError::gpu(
+                "wgpu_adapter",
+                format!("{} binds at most {} storage buffers per stage", info.name, limits.max_storage_buffers_per_shader_stage),
+            ));
+        }
+        let descriptor = wgpu::DeviceDescriptor { label: Some("prism-physics"), required_limits: limits, ..Default::default() };
+        let (device, queue) = check(pollster::block_on(adapter.request_device(&descriptor)), "wgpu_device")?;
+
+        // Compilation and binding errors are reported through the scope
+        // instead of the default panicking handler
+        let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = |source: &str, entry: &str| {
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(entry),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry),
+                layout: None,
+                module: &module,
+                entry_point: Some(entry),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let nonbonded = pipeline(wgsl::NONBONDED_WGSL, "nonbonded_forces");
+        let langevin = pipeline(wgsl::LANGEVIN_WGSL, "langevin_step");
+
+        // CSR exclusion lists, sorted so the kernel can walk them with j
+        let mut excl_offsets = Vec::with_capacity(n + 1);
+        let mut excl_atoms = Vec::new();
+        excl_offsets.push(0u32);
+        for partners in (0..n).map(|i| tables.exclusions.get(i)) {
+            let mut partners = partners.cloned().unwrap_or_default();
+            partners.sort_unstable();
+            partners.dedup();
+            excl_atoms.extend(partners);
+            excl_offsets.push(excl_atoms.len() as u32);
+        }
+        let num_exclusions = excl_atoms.len();
+        // Storage bindings cannot be empty
+        if excl_atoms.is_empty() {
+            excl_atoms.push(u32::MAX);
+        }
+        let mut overrides: Vec<f32> = tables.overrides.iter().flatten().copied().collect();
+        if overrides.is_empty() {
+            overrides.extend_from_slice(&[0.0, -1.0]);
+        }
+        let params: Vec<f32> = tables.params.iter().flatten().copied().collect();
+
+        let init = |label: &str, contents: &[u8], usage: wgpu::BufferUsages| {
+            use wgpu::util::DeviceExt;
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: Some(label), contents, usage })
+        };
+        let storage = wgpu::BufferUsages::STORAGE;
+        let transfer = storage | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
+        let float4 = vec![0.0f32; n * 4];
+        let params = init("params", bytemuck::cast_slice(&params), storage);
+        let excl_offsets = init("exclusion_offsets", bytemuck::cast_slice(&excl_offsets), storage);
+        let excl_atoms = init("exclusion_atoms", bytemuck::cast_slice(&excl_atoms), storage);
+        let overrides = init("overrides", bytemuck::cast_slice(&overrides), storage);
+        let positions = init("positions", bytemuck::cast_slice(&float4), transfer);
+        let velocities = init("velocities", bytemuck::cast_slice(&float4), transfer);
+        let forces = init("forces", bytemuck::cast_slice(&float4), transfer);
+        let energies = init("energies", bytemuck::cast_slice(&vec![0.0f32; n * 2]), transfer);
+        let uniform = wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST;
+        let nonbonded_uniforms = NonbondedUniforms {
+            num_atoms: n as u32,
+            num_types: tables.num_types as u32,
+            cutoff,
+            switch_on: switch_distance.unwrap_or(cutoff),
+            coulomb_scale: tables.coulomb_scale,
+            box_size: box_lengths.unwrap_or([0.0; 3]),
+        };
+        let nonbonded_uniforms = init("nonbonded_uniforms", &nonbonded_uniforms.to_bytes(), uniform);
+        let langevin_uniforms = init("langevin_uniforms", &[0; 32], uniform);
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: (2 * n * 4 * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind = |pipeline: &wgpu::ComputePipeline, buffers: &[(u32, &wgpu::Buffer)]| {
+            let entries: Vec<_> = buffers
+                .iter()
+                .map(|&(binding, buffer)| wgpu::BindGroupEntry { binding, resource: buffer.as_entire_binding() })
+                .collect();
+            device.create_bind_group(&wgpu::BindGroupDescriptor { label: None, layout: &pipeline.get_bind_group_layout(0), entries: &entries })
+        };
+        let nonbonded_group = bind(
+            &nonbonded,
+            &[
+                (nonbonded_binding::UNIFORMS, &nonbonded_uniforms),
+                (nonbonded_binding::POSITIONS, &positions),
+                (nonbonded_binding::PARAMS, &params),
+                (nonbonded_binding::EXCLUSION_OFFSETS, &excl_offsets),
+                (nonbonded_binding::EXCLUSION_ATOMS, &excl_atoms),
+                (nonbonded_binding::OVERRIDES, &overrides),
+                (nonbonded_binding::FORCES, &forces),
+                (nonbonded_binding::ENERGIES, &energies),
+            ],
+        );
+        let langevin_group = bind(
+            &langevin,
+            &[
+                (langevin_binding::UNIFORMS, &langevin_uniforms),
+                (langevin_binding::POSITIONS, &positions),
+                (langevin_binding::VELOCITIES, &velocities),
+                (langevin_binding::FORCES, &forces),
+            ],
+        );
+        if let Some(error) = pollster::block_on(scope.pop()) {
+            return Err(PrismError::gpu("wgpu_pipeline", error.to_string()));
+        }
+
+        tracing::info!(adapter = %info.name, backend = ?info.backend, atoms = n, exclusions = num_exclusions, cutoff, "wgpu kernels ready");
+        Ok(Self {
+            device,
+            queue,
+            adapter: info.name,
+            num_atoms: n,
+            nonbonded,
+            langevin,
+            nonbonded_group,
+            langevin_group,
+            langevin_uniforms,
+            positions,
+            velocities,
+            forces,
+            energies,
+            readback,
+        })
+    }
+
+    pub fn num_atoms(&self) -> usize {
+        self.num_atoms
+    }
+
+    /// Name of the adapter the kernels run on
+    pub fn adapter(&self) -> &str {
+        &self.adapter
+    }
+
+    fn check_len(&self, name: &str, len: usize) -> Result<(), PrismError> {
+        if len != self.num_atoms * 4 {
+            return Err(PrismError::validation(format!("Expected Float4 {} for {} atoms, got {} values", name, self.num_atoms, len)));
+        }
+        Ok(())
+    }
+
+    /// Run one kernel over all atoms and copy `downloads` (bytes from the
+    /// start of each buffer) into the readback buffer, in order
+    fn dispatch(&self, pipeline: &wgpu::ComputePipeline, group: &wgpu::BindGroup, downloads: &[(&wgpu::Buffer, u64)]) -> Result<Vec<f32>, PrismError> {
+        let scope = self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None, timestamp_writes: None });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, group, &[]);
+            pass.dispatch_workgroups(wgsl::workgroups(self.num_atoms), 1, 1);
+        }
+        let mut offset = 0;
+        for &(buffer, bytes) in downloads {
+            encoder.copy_buffer_to_buffer(buffer, 0, &self.readback, offset, bytes);
+            offset += bytes;
+        }
+        self.queue.submit([encoder.finish()]);
+        if let Some(error) = pollster::block_on(scope.pop()) {
+            return Err(PrismError::gpu("wgpu_dispatch", error.to_string()));
+        }
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.readback.map_async(wgpu::MapMode::Read, ..offset, move |result| {
+            let _ = sender.send(result);
+        });
+        check(self.device.poll(wgpu::PollType::wait_indefinitely()), "wgpu_poll")?;
+        match receiver.recv() {
+            Ok(result) => check(result, "wgpu_download")?,
+            Err(_) => return Err(PrismError::gpu("wgpu_download", "map callback dropped".to_string())),
+        }
+        let values = {
+            let view = check(self.readback.get_mapped_range(..offset), "wgpu_download")?;
+            bytemuck::cast_slice::<u8, f32>(&view).to_vec()
+        };
+        self.readback.unmap();
+        Ok(values)
+    }
+
+    /// Evaluate forces for Float4-stride `positions`, adding them into
+    /// `forces`; returns the `(lennard_jones, coulomb)` energies (kcal/mol)
+    pub fn compute(&mut self, positions: &[f32], forces: &mut [f32]) -> Result<(f64, f64), PrismError> {
+        self.check_len("positions", positions.len())?;
+        self.check_len("forces", forces.len())?;
+        self.queue.write_buffer(&self.positions, 0, bytemuck::cast_slice(positions));
+        let float4 = std::mem::size_of_val(positions) as u64;
+        let pairs = (self.num_atoms * 2 * std::mem::size_of::<f32>()) as u64;
+        let values = self.dispatch(&self.nonbonded, &self.nonbonded_group, &[(&self.forces, float4), (&self.energies, pairs)])?;
+        let (device_forces, energies) = values.split_at(positions.len());
+        for (f, d) in forces.iter_mut().zip(device_forces) {
+            *f += d;
+        }
+        let lennard_jones = energies.iter().step_by(2).map(|&e| e as f64).sum();
+        let coulomb = energies.iter().skip(1).step_by(2).map(|&e| e as f64).sum();
+        Ok((lennard_jones, coulomb))
+    }
+
+    /// One Langevin step on the device with the total `forces`, updating
+    /// Float4-stride `positions` and `velocities` in place. The host
+    /// buffers are only written once the step has completed.
+    pub fn langevin_step(&mut self, uniforms: &LangevinUniforms, positions: &mut [f32], velocities: &mut [f32], forces: &[f32]) -> Result<(), PrismError> {
+        self.check_len("positions", positions.len())?;
+        self.check_len("velocities", velocities.len())?;
+        self.check_len("forces", forces.len())?;
+        let uniforms = LangevinUniforms { num_atoms: self.num_atoms as u32, ..*uniforms };
+        self.queue.write_buffer(&self.langevin_uniforms, 0, &uniforms.to_bytes());
+        self.queue.write_buffer(&self.positions, 0, bytemuck::cast_slice(positions));
+        self.queue.write_buffer(&self.velocities, 0, bytemuck::cast_slice(velocities));
+        self.queue.write_buffer(&self.forces, 0, bytemuck::cast_slice(forces));
+        let float4 = std::mem::size_of_val(positions) as u64;
+        let values = self.dispatch(&self.langevin, &self.langevin_group, &[(&self.positions, float4), (&self.velocities, float4)])?;
+        let (new_positions, new_velocities) = values.split_at(positions.len());
+        positions.copy_from_slice(new_positions);
+        velocities.copy_from_slice(new_velocities);
+        Ok(())
+    }
+}
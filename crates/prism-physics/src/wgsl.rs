@@ -0,0 +1,162 @@
+//! # WGSL - Portable Compute Kernels
+//! WGSL ports of the nonbonded force and Langevin integration kernels for
+//! AMD, Intel and Apple GPUs, which cannot run the CUDA path. This module
+//! holds the shader sources and the host side of their interface: the
+//! uniform blocks (packed to WGSL layout rules), binding slots and
+//! dispatch sizes.
+//!
+//! The `wgpu` feature runs them from the engine through
+//! `crate::webgpu` (`backend = "wgpu"`). Like [`crate::simd`], the kernels
+//! cover plain cutoff Coulomb; PME systems need the CUDA path.
+
+/// `nonbonded_forces` entry point (see `shaders/nonbonded.wgsl`)
+pub const NONBONDED_WGSL: &str = include_str!("shaders/nonbonded.wgsl");
+/// `langevin_step` entry point (see `shaders/langevin.wgsl`)
+pub const LANGEVIN_WGSL: &str = include_str!("shaders/langevin.wgsl");
+
+/// `@workgroup_size` of both kernels (the nonbonded tile width)
+pub const WORKGROUP_SIZE: u32 = 128;
+
+/// Bind group 0 of `nonbonded_forces`
+pub mod nonbonded_binding {
+    pub const UNIFORMS: u32 = 0;
+    pub const POSITIONS: u32 = 1;
+    pub const PARAMS: u32 = 2;
+    pub const EXCLUSION_OFFSETS: u32 = 3;
+    pub const EXCLUSION_ATOMS: u32 = 4;
+    pub const OVERRIDES: u32 = 5;
+    pub const FORCES: u32 = 6;
+    pub const ENERGIES: u32 = 7;
+}
+
+/// Bind group 0 of `langevin_step`
+pub mod langevin_binding {
+    pub const UNIFORMS: u32 = 0;
+    pub const POSITIONS: u32 = 1;
+    pub const VELOCITIES: u32 = 2;
+    pub const FORCES: u32 = 3;
+}
+
+/// Workgroups covering `num_atoms` invocations
+pub fn workgroups(num_atoms: usize) -> u32 {
+    num_atoms.div_ceil(WORKGROUP_SIZE as usize) as u32
+}
+
+/// Uniform block of `nonbonded_forces`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonbondedUniforms {
+    pub num_atoms: u32,
+    /// Type count of the override table; 0 = mixing rule only
+    pub num_types: u32,
+    pub cutoff: f32,
+    /// `>= cutoff` disables switching
+    pub switch_on: f32,
+    /// Coulomb constant / dielectric
+    pub coulomb_scale: f32,
+    /// Box edges; 0 = open boundaries
+    pub box_size: [f32; 3],
+}
+
+impl NonbondedUniforms {
+    /// Bytes in WGSL uniform layout (`box_size` is a 16-byte aligned vec4)
+    pub fn to_bytes(&self) -> [u8; 48] {
+        let mut bytes = [0u8; 48];
+        let words = [
+            (0, self.num_atoms.to_le_bytes()),
+            (4, self.num_types.to_le_bytes()),
+            (8, self.cutoff.to_le_bytes()),
+            (12, self.switch_on.to_le_bytes()),
+            (16, self.coulomb_scale.to_le_bytes()),
+            (32, self.box_size[0].to_le_bytes()),
+            (36, self.box_size[1].to_le_bytes()),
+            (40, self.box_size[2].to_le_bytes()),
+        ];
+        for (offset, word) in words {
+            bytes[offset..offset + 4].copy_from_slice(&word);
+        }
+        bytes
+    }
+}
+
+/// Uniform block of `langevin_step`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LangevinUniforms {
+    pub num_atoms: u32,
+    /// Step index; with `seed` it keys the noise
+    pub step: u32,
+    pub seed: u64,
+    /// ps
+    pub dt: f32,
+    /// 1/ps
+    pub friction: f32,
+    /// k_B T in kcal/mol
+    pub temperature: f32,
+}
+
+impl LangevinUniforms {
+    /// Bytes in WGSL uniform layout
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        let words = [
+            self.num_atoms.to_le_bytes(),
+            self.step.to_le_bytes(),
+            (self.seed as u32).to_le_bytes(),
+            ((self.seed >> 32) as u32).to_le_bytes(),
+            self.dt.to_le_bytes(),
+            self.friction.to_le_bytes(),
+            self.temperature.to_le_bytes(),
+        ];
+        for (k, word) in words.iter().enumerate() {
+            bytes[4 * k..4 * k + 4].copy_from_slice(word);
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shader_interface_matches_host() {
+        let workgroup = format!("@workgroup_size({})", WORKGROUP_SIZE);
+        for (source, entry, bindings) in [
+            (NONBONDED_WGSL, "fn nonbonded_forces(", nonbonded_binding::ENERGIES),
+            (LANGEVIN_WGSL, "fn langevin_step(", langevin_binding::FORCES),
+        ] {
+            assert!(source.contains(entry));
+            assert!(source.contains(&workgroup));
+            for binding in 0..=bindings {
+                assert!(source.contains(&format!("@group(0) @binding({})", binding)), "binding {}", binding);
+            }
+            assert!(!source.contains(&format!("@binding({})", bindings + 1)));
+        }
+        assert!(NONBONDED_WGSL.contains(&format!("const TILE: u32 = {}u;", WORKGROUP_SIZE)));
+        assert_eq!(workgroups(0), 0);
+        assert_eq!(workgroups(129), 2);
+    }
+
+    #[test]
+    fn test_uniform_layout() {
+        let nonbonded = NonbondedUniforms {
+            num_atoms: 7,
+            num_types: 2,
+            cutoff: 10.0,
+            switch_on: 8.0,
+            coulomb_scale: 332.0637,
+            box_size: [30.0, 0.0, 0.0],
+        }
+        .to_bytes();
+        assert_eq!(&nonbonded[0..4], &7u32.to_le_bytes());
+        assert_eq!(&nonbonded[16..20], &332.0637f32.to_le_bytes());
+        assert_eq!(&nonbonded[20..32], &[0; 12]);
+        assert_eq!(&nonbonded[32..36], &30.0f32.to_le_bytes());
+
+        let seed = 0x0123_4567_89ab_cdef;
+        let langevin = LangevinUniforms { num_atoms: 3, step: 9, seed, dt: 0.002, friction: 1.0, temperature: 0.596 }.to_bytes();
+        assert_eq!(&langevin[8..12], &0x89ab_cdefu32.to_le_bytes());
+        assert_eq!(&langevin[12..16], &0x0123_4567u32.to_le_bytes());
+        assert_eq!(&langevin[24..28], &0.596f32.to_le_bytes());
+        assert_eq!(&langevin[28..32], &[0; 4]);
+    }
+}
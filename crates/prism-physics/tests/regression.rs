@@ -0,0 +1,178 @@
+// Golden-output regression suite (`--features regression`)
+//
+// Every case in tests/fixtures/regression/golden.json names a PTB structure
+// checked in next to it and the science results expected from it:
+// force-field energies of the input, the minimized energy, the energy after
+// a short NLNM breathing run on the host and the lowest ANM mode
+// frequencies. A refactor of the force field, minimizer or integrator that
+// moves any of them beyond its tolerance fails the suite.
+//
+//     cargo test -p prism-physics --features regression --test regression
+//
+// The cases are small synthetic peptides, so the suite needs no downloaded
+// structures. Every golden case must find its structure: a missing one
+// fails the suite rather than being skipped. Blessing adds a case for every
+// PTB file in the fixture directory. After an intended change of results,
+// regenerate the expected values (tolerances are kept) with
+//
+//     PRISM_BLESS=1 cargo test -p prism-physics --features regression --test regression
+//
+// and review the golden.json diff like any other change.
+
+use prism_physics::elastic_network::{ElasticNetwork, ElasticNetworkConfig};
+use prism_physics::molecular_dynamics::{MolecularDynamicsConfigBuilder, MolecularDynamicsEngine};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Host breathing steps behind `nlnm_energy`
+const NLNM_STEPS: u64 = 500;
+/// Lowest non-trivial ANM modes reported as `enm_frequency_<k>`
+const ENM_MODES: usize = 5;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Expected {
+    value: f64,
+    /// Relative to `max(|value|, 1)`
+    tolerance: f64,
+}
+
+type Golden = BTreeMap<String, BTreeMap<String, Expected>>;
+
+fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/regression")
+}
+
+fn structure_path(case: &str) -> Option<PathBuf> {
+    Some(fixture_dir().join(format!("{}.ptb", case))).filter(|path| path.exists())
+}
+
+/// Tolerance of a newly blessed observable: the energies of the dynamics run
+/// are the most sensitive to summation order
+fn default_tolerance(observable: &str) -> f64 {
+    match observable {
+        "nlnm_energy" => 1e-2,
+        o if o.starts_with("enm_") => 1e-6,
+        _ => 1e-4,
+    }
+}
+
+fn observables(path: &Path) -> BTreeMap<String, f64> {
+    let data = std::fs::read(path).unwrap();
+    let config = MolecularDynamicsConfigBuilder::default()
+        .use_gpu(false)
+        .build()
+        .unwrap();
+    let mut engine = MolecularDynamicsEngine::from_sovereign_buffer(config, &data).unwrap();
+    let mut values = BTreeMap::new();
+    let energy = engine.energy_components();
+    values.insert("potential_energy".to_string(), energy.potential());
+    values.insert("lennard_jones".to_string(), energy.lennard_jones);
+    values.insert("electrostatic".to_string(), energy.electrostatic);
+
+    engine.minimize().unwrap();
+    values.insert("minimized_energy".to_string(), engine.potential_energy());
+    engine.run_nlnm_breathing(NLNM_STEPS).unwrap();
+    values.insert("nlnm_energy".to_string(), engine.potential_energy());
+
+    // PTB files carry no atom names; in PDB atom order the second atom of
+    // each residue is the CA
+    let atoms = engine.get_current_atoms().unwrap();
+    let mut nodes = Vec::new();
+    for (i, atom) in atoms.iter().enumerate().skip(1) {
+        let first_of_residue = atoms[i - 1].residue_id != atom.residue_id;
+        let second_of_residue = i >= 2 && atoms[i - 2].residue_id != atom.residue_id;
+        if !first_of_residue && (i == 1 || second_of_residue) {
+            nodes.push((i as u32, atom.residue_id, atom.coords.map(|c| c as f64)));
+        }
+    }
+    let config = ElasticNetworkConfig {
+        num_modes: ENM_MODES,
+        ..Default::default()
+    };
+    let network = ElasticNetwork::from_nodes(
+        nodes.iter().map(|n| n.0).collect(),
+        nodes.iter().map(|n| n.1).collect(),
+        nodes.iter().map(|n| n.2).collect(),
+        config,
+    )
+    .unwrap();
+    for (k, mode) in network.modes().iter().enumerate() {
+        values.insert(format!("enm_frequency_{}", k + 1), mode.eigenvalue.sqrt());
+    }
+    values
+}
+
+fn bless(golden: &Golden) -> Golden {
+    let mut cases: Vec<String> = golden.keys().cloned().collect();
+    for entry in std::fs::read_dir(fixture_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "ptb") {
+            cases.push(path.file_stem().unwrap().to_string_lossy().into_owned());
+        }
+    }
+    cases.sort();
+    cases.dedup();
+
+    let mut blessed = Golden::new();
+    for case in cases {
+        let Some(path) = structure_path(&case) else {
+            if let Some(expected) = golden.get(&case) {
+                blessed.insert(case, expected.clone());
+            }
+            continue;
+        };
+        let old = golden.get(&case);
+        let expected = observables(&path)
+            .into_iter()
+            .map(|(name, value)| {
+                let tolerance = old
+                    .and_then(|o| o.get(&name))
+                    .map_or_else(|| default_tolerance(&name), |e| e.tolerance);
+                (name, Expected { value, tolerance })
+            })
+            .collect();
+        blessed.insert(case, expected);
+    }
+    blessed
+}
+
+#[test]
+fn regression_matches_golden() {
+    let golden_path = fixture_dir().join("golden.json");
+    let golden: Golden = match std::fs::read_to_string(&golden_path) {
+        Ok(json) => serde_json::from_str(&json).unwrap(),
+        Err(_) => Golden::new(),
+    };
+    if std::env::var_os("PRISM_BLESS").is_some() {
+        let blessed = bless(&golden);
+        let json = serde_json::to_string_pretty(&blessed).unwrap();
+        std::fs::write(&golden_path, json + "\n").unwrap();
+        eprintln!("💾 Blessed {} cases into {}", blessed.len(), golden_path.display());
+        return;
+    }
+    assert!(!golden.is_empty(), "no golden values in {}", golden_path.display());
+
+    let mut failures = Vec::new();
+    for (case, expected) in &golden {
+        let Some(path) = structure_path(case) else {
+            failures.push(format!(
+                "{}: {}.ptb not found in tests/fixtures/regression",
+                case, case
+            ));
+            continue;
+        };
+        let actual = observables(&path);
+        for (name, e) in expected {
+            match actual.get(name) {
+                Some(&value) if (value - e.value).abs() <= e.tolerance * e.value.abs().max(1.0) => {}
+                Some(&value) => failures.push(format!(
+                    "{} {}: expected {} ± {}, got {}",
+                    case, name, e.value, e.tolerance, value
+                )),
+                None => failures.push(format!("{} {}: not computed", case, name)),
+            }
+        }
+    }
+    assert!(failures.is_empty(), "regression against golden results:\n{}", failures.join("\n"));
+}
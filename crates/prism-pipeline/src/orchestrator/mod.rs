@@ -1160,6 +1160,13 @@ impl PipelineOrchestrator {
  log::warn!("Phase {} escalated: {}", phase_name, reason);
  // Continue to next phase
  }
+ PhaseOutcome::Cancelled { ref reason, .. } => {
+ log::warn!("Phase {} cancelled: {}", phase_name, reason);
+ return Err(PrismError::Internal(format!(
+ "Pipeline cancelled in phase {}: {}",
+ phase_name, reason
+ )));
+ }
  PhaseOutcome::Retry { .. } => {
  // Should not reach here after retry loop
  unreachable!("Retry outcome after retry loop");
@@ -1781,7 +1788,7 @@ impl PipelineOrchestrator {
  )));
  }
  }
- PhaseOutcome::Escalate { .. } => return Ok(outcome),
+ PhaseOutcome::Escalate { .. } | PhaseOutcome::Cancelled { .. } => return Ok(outcome),
  }
  }
 
@@ -1832,6 +1839,7 @@ impl PipelineOrchestrator {
  }
  PhaseOutcome::Retry { .. } => -0.2,
  PhaseOutcome::Escalate { .. } => -0.5,
+ PhaseOutcome::Cancelled { .. } => 0.0,
  };
 
  let state_after = self.build_rl_state();
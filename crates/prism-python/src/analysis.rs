@@ -0,0 +1,67 @@
+//! # Analysis - RMSD, Shape and SASA
+//!
+//! Functional wrappers over [`prism_physics::analysis`] for coordinates
+//! already in Python, e.g. frames from `prism.read_trajectory`.
+
+use crate::arrays::{array_f64, coordinates, float4};
+use crate::prism_err;
+use prism_physics::analysis::{Analysis, RmsdAnalysis, SasaCalculator, SasaConfig, ShapeDescriptors};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::BTreeMap;
+
+/// `(rmsd, rmsf)` of `frames` after optimal superposition on
+/// `reference`: one RMSD per frame and one RMSF per atom (Å)
+#[pyfunction]
+pub fn rmsd(py: Python<'_>, reference: &PyAny, frames: &PyAny) -> PyResult<(PyObject, PyObject)> {
+    let reference = coordinates(reference)?;
+    let mut analysis = RmsdAnalysis::new(&float4(&reference), Vec::new());
+    for (i, frame) in frames.iter()?.enumerate() {
+        let frame = coordinates(frame?)?;
+        if frame.len() != reference.len() {
+            return Err(PyValueError::new_err(format!("Frame {} has {} atoms, the reference has {}", i, frame.len(), reference.len())));
+        }
+        analysis.observe(i as u64, &float4(&frame));
+    }
+    let series: Vec<f64> = analysis.series().iter().map(|&(_, r)| r).collect();
+    let rmsf = analysis.rmsf();
+    Ok((array_f64(py, &series, &[series.len()])?, array_f64(py, &rmsf, &[rmsf.len()])?))
+}
+
+/// Radius of gyration and gyration-tensor shape descriptors, mass
+/// weighted when `masses` is given
+#[pyfunction]
+#[pyo3(signature = (coordinates, masses=None))]
+pub fn shape_descriptors<'py>(py: Python<'py>, coordinates: &PyAny, masses: Option<Vec<f64>>) -> PyResult<&'py PyDict> {
+    let rows: Vec<[f64; 3]> = crate::arrays::coordinates(coordinates)?.iter().map(|r| r.map(|c| c as f64)).collect();
+    let masses = masses.unwrap_or_else(|| vec![1.0; rows.len()]);
+    if masses.len() != rows.len() {
+        return Err(PyValueError::new_err(format!("Got {} masses for {} atoms", masses.len(), rows.len())));
+    }
+    let d = ShapeDescriptors::compute(&rows, &masses);
+    let dict = PyDict::new(py);
+    dict.set_item("radius_of_gyration", d.radius_of_gyration)?;
+    dict.set_item("principal_moments", d.principal_moments.to_vec())?;
+    dict.set_item("asphericity", d.asphericity)?;
+    dict.set_item("acylindricity", d.acylindricity)?;
+    dict.set_item("anisotropy", d.anisotropy)?;
+    Ok(dict)
+}
+
+/// Shrake-Rupley solvent accessible surface area: `(total, per_atom,
+/// per_residue)` in Å², with `per_residue` keyed by `residues` (one label
+/// per atom, all 0 when omitted)
+#[pyfunction]
+#[pyo3(signature = (coordinates, radii, residues=None, probe_radius=1.4, num_points=100))]
+pub fn sasa(py: Python<'_>, coordinates: &PyAny, radii: Vec<f64>, residues: Option<Vec<u16>>, probe_radius: f64, num_points: usize) -> PyResult<(f64, PyObject, BTreeMap<u16, f64>)> {
+    let rows = crate::arrays::coordinates(coordinates)?;
+    if radii.len() != rows.len() {
+        return Err(PyValueError::new_err(format!("Got {} radii for {} atoms", radii.len(), rows.len())));
+    }
+    let residues = residues.unwrap_or_else(|| vec![0; rows.len()]);
+    let config = SasaConfig { probe_radius, num_points, use_gpu: false };
+    let mut calculator = SasaCalculator::new(&radii, residues, &config).map_err(prism_err)?;
+    let result = calculator.compute_positions(&float4(&rows));
+    Ok((result.total, array_f64(py, &result.per_atom, &[result.per_atom.len()])?, result.per_residue.into_iter().collect()))
+}
@@ -0,0 +1,126 @@
+//! # Arrays - NumPy and JSON Interop
+//!
+//! Coordinates come in as `(n, 3)` NumPy arrays (float32 or float64), any
+//! other object exposing a 2-D buffer, or sequences of triples, and go out
+//! as NumPy arrays. Statistics and telemetry cross the boundary as plain
+//! Python dicts converted from their JSON form.
+
+use pyo3::buffer::{Element, PyBuffer};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
+
+fn buffer_rows<T: Element + Copy + Into<f64>>(py: Python<'_>, buffer: PyBuffer<T>) -> PyResult<Vec<[f32; 3]>> {
+    if buffer.shape().len() != 2 || buffer.shape()[1] != 3 {
+        return Err(PyValueError::new_err(format!("Expected coordinates of shape (n, 3), got {:?}", buffer.shape())));
+    }
+    let values = buffer.to_vec(py)?;
+    Ok(values.chunks_exact(3).map(|c| [c[0].into() as f32, c[1].into() as f32, c[2].into() as f32]).collect())
+}
+
+/// `(n, 3)` coordinates (Å) from an array, buffer or sequence of triples
+pub fn coordinates(object: &PyAny) -> PyResult<Vec<[f32; 3]>> {
+    let py = object.py();
+    if let Ok(buffer) = PyBuffer::<f32>::get(object) {
+        return buffer_rows(py, buffer);
+    }
+    if let Ok(buffer) = PyBuffer::<f64>::get(object) {
+        return buffer_rows(py, buffer);
+    }
+    let rows: Vec<[f64; 3]> = object.extract().map_err(|_| PyValueError::new_err("Expected coordinates as an (n, 3) array or a sequence of [x, y, z]"))?;
+    Ok(rows.into_iter().map(|r| r.map(|c| c as f32)).collect())
+}
+
+/// Float4-stride buffer with unit weights, as the engine stores positions
+pub fn float4(coordinates: &[[f32; 3]]) -> Vec<f32> {
+    coordinates.iter().flat_map(|&[x, y, z]| [x, y, z, 1.0]).collect()
+}
+
+fn numpy_array(py: Python<'_>, bytes: Vec<u8>, dtype: &str, shape: &[usize]) -> PyResult<PyObject> {
+    let numpy = py.import("numpy")?;
+    let flat = numpy.call_method1("frombuffer", (PyBytes::new(py, &bytes), dtype))?;
+    Ok(flat.call_method1("reshape", (PyTuple::new(py, shape),))?.call_method0("copy")?.into())
+}
+
+/// NumPy float32 array of `shape` holding `data`
+pub fn array_f32(py: Python<'_>, data: &[f32], shape: &[usize]) -> PyResult<PyObject> {
+    numpy_array(py, data.iter().flat_map(|v| v.to_ne_bytes()).collect(), "float32", shape)
+}
+
+/// NumPy float64 array of `shape` holding `data`
+pub fn array_f64(py: Python<'_>, data: &[f64], shape: &[usize]) -> PyResult<PyObject> {
+    numpy_array(py, data.iter().flat_map(|v| v.to_ne_bytes()).collect(), "float64", shape)
+}
+
+/// Python object of a JSON value
+pub fn json_to_py(py: Python<'_>, value: &serde_json::Value) -> PyObject {
+    use serde_json::Value;
+    match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.into_py(py),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_py(py),
+            (None, Some(u)) => u.into_py(py),
+            _ => n.as_f64().unwrap_or(f64::NAN).into_py(py),
+        },
+        Value::String(s) => s.into_py(py),
+        Value::Array(items) => PyList::new(py, items.iter().map(|v| json_to_py(py, v))).into(),
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, v) in map {
+                let _ = dict.set_item(key, json_to_py(py, v));
+            }
+            dict.into()
+        }
+    }
+}
+
+/// JSON value of a Python object made of dicts, lists, tuples, strings,
+/// numbers, booleans and `None`
+pub fn py_to_json(object: &PyAny) -> PyResult<serde_json::Value> {
+    use serde_json::Value;
+    if object.is_none() {
+        Ok(Value::Null)
+    } else if let Ok(b) = object.downcast::<PyBool>() {
+        Ok(Value::Bool(b.is_true()))
+    } else if object.is_instance_of::<PyLong>() {
+        Ok(Value::from(object.extract::<i64>()?))
+    } else if object.is_instance_of::<PyFloat>() {
+        Ok(Value::from(object.extract::<f64>()?))
+    } else if let Ok(s) = object.downcast::<PyString>() {
+        Ok(Value::String(s.to_str()?.to_string()))
+    } else if let Ok(dict) = object.downcast::<PyDict>() {
+        dict.iter().map(|(k, v)| Ok((k.extract::<String>()?, py_to_json(v)?))).collect::<PyResult<serde_json::Map<_, _>>>().map(Value::Object)
+    } else if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        object.iter()?.map(|item| py_to_json(item?)).collect::<PyResult<Vec<_>>>().map(Value::Array)
+    } else {
+        Err(PyValueError::new_err(format!("Cannot convert {} to a configuration value", object.get_type().name()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinates_from_buffer_and_sequence() {
+        Python::with_gil(|py| {
+            let nested = py.eval("[[0.0, 1.0, 2.0], (3, 4, 5)]", None, None).unwrap();
+            assert_eq!(coordinates(nested).unwrap(), vec![[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]]);
+
+            let view = py.eval("memoryview(__import__('array').array('d', range(6))).cast('B').cast('d', (2, 3))", None, None).unwrap();
+            assert_eq!(coordinates(view).unwrap(), vec![[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]]);
+            let flat = py.eval("memoryview(__import__('array').array('f', range(6)))", None, None).unwrap();
+            assert!(coordinates(flat).is_err());
+        });
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        Python::with_gil(|py| {
+            let value = serde_json::json!({"steps": 10, "dt": 0.5, "gpu": false, "name": "run", "cell": [1, 2.5, null]});
+            let object = json_to_py(py, &value);
+            assert_eq!(py_to_json(object.as_ref(py)).unwrap(), value);
+        });
+    }
+}
@@ -0,0 +1,209 @@
+//! # Config - `prism.MolecularDynamicsConfig`
+//!
+//! Wraps [`MolecularDynamicsConfig`]. Any field of the `[engine]` section
+//! of a run configuration can be given as a keyword argument (nested
+//! sections as dicts); the values go through the same serde validation as
+//! configuration files.
+
+use crate::arrays::{json_to_py, py_to_json};
+use crate::prism_err;
+use prism_physics::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsConfigBuilder};
+use prism_physics::run_config::RunConfig;
+use prism_physics::units::Temperature;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::path::PathBuf;
+
+#[pyclass(name = "MolecularDynamicsConfig", module = "prism")]
+#[derive(Debug, Clone)]
+pub struct PyMolecularDynamicsConfig {
+    pub(crate) inner: MolecularDynamicsConfig,
+}
+
+impl PyMolecularDynamicsConfig {
+    fn wrap(builder: MolecularDynamicsConfigBuilder) -> PyResult<Self> {
+        Ok(Self { inner: builder.build().map_err(prism_err)? })
+    }
+
+    /// Replace the fields named in `fields`, keeping `self` on error
+    fn merge(&mut self, fields: &PyDict) -> PyResult<()> {
+        let mut value = serde_json::to_value(&self.inner).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        merge_json(&mut value, py_to_json(fields)?);
+        let config: MolecularDynamicsConfig = serde_json::from_value(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        config.validate().map_err(prism_err)?;
+        self.inner = config;
+        Ok(())
+    }
+}
+
+/// Overlay `overrides` on `base`, merging nested tables key by key
+fn merge_json(base: &mut serde_json::Value, overrides: serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(slot) if slot.is_object() => merge_json(slot, value),
+                    _ => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+#[pymethods]
+impl PyMolecularDynamicsConfig {
+    /// Engine defaults (GPU only when built with CUDA) with `fields`
+    /// replaced
+    #[new]
+    #[pyo3(signature = (**fields))]
+    pub fn new(fields: Option<&PyDict>) -> PyResult<Self> {
+        let mut config = Self { inner: MolecularDynamicsConfig { use_gpu: cfg!(feature = "cuda"), ..Default::default() } };
+        if let Some(fields) = fields {
+            config.merge(fields)?;
+        }
+        Ok(config)
+    }
+
+    /// Short host-only constant-temperature run for smoke tests
+    #[staticmethod]
+    pub fn quick_test() -> PyResult<Self> {
+        Self::wrap(MolecularDynamicsConfigBuilder::quick_test())
+    }
+
+    /// Annealed NLNM breathing run with shape analysis
+    #[staticmethod]
+    fn production_breathing() -> PyResult<Self> {
+        Self::wrap(MolecularDynamicsConfigBuilder::production_breathing().use_gpu(cfg!(feature = "cuda")))
+    }
+
+    /// Path-integral sampling at 300 K
+    #[staticmethod]
+    fn quantum_pimc() -> PyResult<Self> {
+        Self::wrap(MolecularDynamicsConfigBuilder::quantum_pimc())
+    }
+
+    /// `[engine]` section of a TOML/YAML/JSON run configuration
+    #[staticmethod]
+    #[pyo3(signature = (path, profile=None))]
+    fn load(path: PathBuf, profile: Option<&str>) -> PyResult<Self> {
+        Ok(Self { inner: RunConfig::load(path, profile).map_err(prism_err)?.engine })
+    }
+
+    /// Copy with `fields` replaced
+    #[pyo3(signature = (**fields))]
+    fn replace(&self, fields: Option<&PyDict>) -> PyResult<Self> {
+        let mut config = self.clone();
+        if let Some(fields) = fields {
+            config.merge(fields)?;
+        }
+        Ok(config)
+    }
+
+    /// All fields as a dict
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let value = serde_json::to_value(&self.inner).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(json_to_py(py, &value))
+    }
+
+    #[getter]
+    fn max_steps(&self) -> u64 {
+        self.inner.max_steps
+    }
+
+    #[setter]
+    fn set_max_steps(&mut self, steps: u64) {
+        self.inner.max_steps = steps;
+    }
+
+    /// Thermostat start temperature (K); setting it makes the temperature
+    /// constant
+    #[getter]
+    fn temperature(&self) -> f64 {
+        self.inner.temperatures().0.as_kelvin()
+    }
+
+    #[setter]
+    fn set_temperature(&mut self, kelvin: f64) -> PyResult<()> {
+        if !(kelvin >= 0.0 && kelvin.is_finite()) {
+            return Err(PyValueError::new_err(format!("Temperature must be non-negative, got {} K", kelvin)));
+        }
+        let kt = Temperature::kelvin(kelvin).kt().as_kcal_per_mol() as f32;
+        self.inner.temp_start = kt;
+        self.inner.temp_end = kt;
+        self.inner.temperature_schedule = None;
+        Ok(())
+    }
+
+    /// Integration timestep (fs)
+    #[getter]
+    fn timestep_fs(&self) -> f64 {
+        (self.inner.dt * 1000.0) as f64
+    }
+
+    #[setter]
+    fn set_timestep_fs(&mut self, fs: f64) {
+        self.inner.dt = (fs / 1000.0) as f32;
+    }
+
+    #[getter]
+    fn use_gpu(&self) -> bool {
+        self.inner.use_gpu
+    }
+
+    #[setter]
+    fn set_use_gpu(&mut self, use_gpu: bool) {
+        self.inner.use_gpu = use_gpu;
+    }
+
+    #[getter]
+    fn seed(&self) -> u64 {
+        self.inner.seed
+    }
+
+    #[setter]
+    fn set_seed(&mut self, seed: u64) {
+        self.inner.seed = seed;
+    }
+
+    fn __repr__(&self) -> String {
+        let (start, end) = self.inner.temperatures();
+        format!(
+            "MolecularDynamicsConfig(max_steps={}, timestep_fs={}, temperature={:.1}->{:.1} K, use_gpu={})",
+            self.inner.max_steps,
+            self.timestep_fs(),
+            start.as_kelvin(),
+            end.as_kelvin(),
+            if self.inner.use_gpu { "True" } else { "False" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_fields_are_validated() {
+        Python::with_gil(|py| {
+            let fields = py.eval("dict(max_steps=50, seed=7, minimization=dict(max_iterations=20))", None, None).unwrap().downcast::<PyDict>().unwrap();
+            let config = PyMolecularDynamicsConfig::new(Some(fields)).unwrap();
+            assert_eq!(config.inner.max_steps, 50);
+            assert_eq!(config.inner.seed, 7);
+            assert_eq!(config.inner.minimization.max_iterations, 20);
+
+            let bad = py.eval("dict(dt=-1.0)", None, None).unwrap().downcast::<PyDict>().unwrap();
+            assert!(config.replace(Some(bad)).is_err());
+            let wrong_type = py.eval("dict(max_steps='many')", None, None).unwrap().downcast::<PyDict>().unwrap();
+            assert!(config.replace(Some(wrong_type)).is_err());
+
+            let mut config = config;
+            config.set_temperature(310.0).unwrap();
+            assert!((config.temperature() - 310.0).abs() < 1e-3);
+            assert_eq!(config.inner.temp_start, config.inner.temp_end);
+        });
+    }
+}
@@ -0,0 +1,203 @@
+//! # Engine - `prism.MolecularDynamicsEngine`
+//!
+//! Runs release the GIL; observers re-acquire it for each callback, and
+//! an exception raised by a callback (including `KeyboardInterrupt`,
+//! checked at every observed step) stops the run and is re-raised once it
+//! returns.
+
+use crate::arrays::{array_f32, json_to_py};
+use crate::config::PyMolecularDynamicsConfig;
+use crate::io::PyStructure;
+use crate::{io_err, prism_err};
+use prism_core::{PhaseOutcome, PrismError};
+use prism_io::pdb::{parse_pdb, PdbStructure};
+use prism_io::structure_file::{read_structure, write_structure};
+use prism_physics::molecular_dynamics::MolecularDynamicsEngine;
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[pyclass(name = "MolecularDynamicsEngine", module = "prism")]
+pub struct PyMolecularDynamicsEngine {
+    inner: MolecularDynamicsEngine,
+    /// Metadata for written structures, when the input had it
+    template: Option<PdbStructure>,
+    callback_error: Arc<Mutex<Option<PyErr>>>,
+}
+
+/// `{"outcome", "message", "telemetry"}` of a finished run
+fn outcome_dict<'py>(py: Python<'py>, outcome: &PhaseOutcome) -> PyResult<&'py PyDict> {
+    let (name, message, telemetry) = match outcome {
+        PhaseOutcome::Success { message, telemetry } => ("success", message, Some(telemetry)),
+        PhaseOutcome::Cancelled { reason, telemetry } => ("cancelled", reason, Some(telemetry)),
+        PhaseOutcome::Retry { reason, .. } => ("retry", reason, None),
+        PhaseOutcome::Escalate { reason } => ("escalate", reason, None),
+    };
+    let dict = PyDict::new(py);
+    dict.set_item("outcome", name)?;
+    dict.set_item("message", message)?;
+    let entries = PyDict::new(py);
+    for (key, value) in telemetry.into_iter().flatten() {
+        entries.set_item(key, json_to_py(py, value))?;
+    }
+    dict.set_item("telemetry", entries)?;
+    Ok(dict)
+}
+
+impl PyMolecularDynamicsEngine {
+    fn execute(&mut self, py: Python<'_>, run: impl FnOnce(&mut MolecularDynamicsEngine) -> Result<PhaseOutcome, PrismError> + Send) -> PyResult<PyObject> {
+        let engine = &mut self.inner;
+        let outcome = py.allow_threads(|| run(engine));
+        if let Some(error) = self.callback_error.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            return Err(error);
+        }
+        Ok(outcome_dict(py, &outcome.map_err(prism_err)?)?.into())
+    }
+}
+
+#[pymethods]
+impl PyMolecularDynamicsEngine {
+    /// Engine for `structure`: a path to a `.pdb`, `.cif` or `.ptb` file,
+    /// a `prism.Structure`, or PTB/PDB bytes
+    #[new]
+    #[pyo3(signature = (structure, config=None))]
+    fn new(structure: &PyAny, config: Option<PyMolecularDynamicsConfig>) -> PyResult<Self> {
+        let config = match config {
+            Some(config) => config.inner,
+            None => PyMolecularDynamicsConfig::new(None)?.inner,
+        };
+        let (inner, template) = if let Ok(bytes) = structure.downcast::<PyBytes>() {
+            let text = std::str::from_utf8(bytes.as_bytes()).ok();
+            (MolecularDynamicsEngine::from_sovereign_buffer(config, bytes.as_bytes()), text.and_then(|t| parse_pdb(t).ok()))
+        } else if let Ok(structure) = structure.extract::<PyRef<PyStructure>>() {
+            (MolecularDynamicsEngine::from_sovereign_buffer(config, structure.inner.to_pdb_string().as_bytes()), Some(structure.inner.clone()))
+        } else if let Ok(path) = structure.extract::<PathBuf>() {
+            (MolecularDynamicsEngine::from_structure_file(config, &path), Some(read_structure(&path).map_err(io_err)?))
+        } else {
+            return Err(PyTypeError::new_err("Expected a path, a prism.Structure or bytes"));
+        };
+        let inner = inner.map_err(prism_err)?;
+        Ok(Self { inner, template, callback_error: Arc::default() })
+    }
+
+    /// Call `callback(statistics)` every `interval` steps of `run`;
+    /// returning `False` ends the run early
+    fn add_observer(&mut self, interval: u64, callback: PyObject) {
+        let error = self.callback_error.clone();
+        self.inner.add_observer(interval, move |stats| {
+            Python::with_gil(|py| {
+                let result = py.check_signals().and_then(|_| {
+                    let stats = serde_json::to_value(stats).map(|v| json_to_py(py, &v)).unwrap_or_else(|_| py.None());
+                    callback.call1(py, (stats,))
+                });
+                match result {
+                    Ok(value) if value.as_ref(py).is(PyBool::new(py, false)) => ControlFlow::Break(()),
+                    Ok(_) => ControlFlow::Continue(()),
+                    Err(e) => {
+                        *error.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+                        ControlFlow::Break(())
+                    }
+                }
+            })
+        });
+    }
+
+    fn clear_observers(&mut self) {
+        self.inner.clear_observers();
+    }
+
+    /// Minimize the energy
+    fn minimize(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        self.execute(py, |engine| engine.minimize())
+    }
+
+    /// Langevin dynamics for `steps` (default `max_steps`) following the
+    /// configured temperature schedule
+    #[pyo3(signature = (steps=None))]
+    fn run(&mut self, py: Python<'_>, steps: Option<u64>) -> PyResult<PyObject> {
+        let steps = steps.unwrap_or(self.inner.get_statistics().total_steps);
+        self.execute(py, move |engine| engine.run_nlnm_breathing(steps))
+    }
+
+    /// Path-integral Monte Carlo sweeps
+    fn run_pimc(&mut self, py: Python<'_>, sweeps: u64) -> PyResult<PyObject> {
+        self.execute(py, move |engine| engine.run_pimc(sweeps))
+    }
+
+    /// Ring-polymer molecular dynamics steps
+    fn run_rpmd(&mut self, py: Python<'_>, steps: u64) -> PyResult<PyObject> {
+        self.execute(py, move |engine| engine.run_rpmd(steps))
+    }
+
+    /// Current run statistics
+    fn statistics(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let value = serde_json::to_value(self.inner.get_statistics()).map_err(|e| prism_err(e.into()))?;
+        Ok(json_to_py(py, &value))
+    }
+
+    /// `(n, 3)` float32 positions (Å)
+    #[getter]
+    fn positions(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let atoms = self.inner.get_current_atoms().map_err(prism_err)?;
+        let flat: Vec<f32> = atoms.iter().flat_map(|a| a.coords).collect();
+        array_f32(py, &flat, &[atoms.len(), 3])
+    }
+
+    /// Current structure, with the input's metadata when it had any
+    fn structure(&mut self) -> PyResult<PyStructure> {
+        let atoms = self.inner.get_current_atoms().map_err(prism_err)?;
+        let inner = match &self.template {
+            Some(template) if template.atoms.len() == atoms.len() => {
+                let mut structure = template.clone();
+                structure.update_coordinates(&atoms).map_err(io_err)?;
+                structure
+            }
+            _ => PdbStructure::from_atoms(&atoms),
+        };
+        Ok(PyStructure { inner })
+    }
+
+    /// Write the current structure (`.pdb`, `.cif` or `.ptb`)
+    fn write(&mut self, path: PathBuf) -> PyResult<()> {
+        write_structure(path, &self.structure()?.inner).map_err(io_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+";
+
+    #[test]
+    fn test_observer_stops_run_and_errors_propagate() {
+        Python::with_gil(|py| {
+            let config = PyMolecularDynamicsConfig::quick_test().unwrap();
+            let mut engine = PyMolecularDynamicsEngine::new(PyBytes::new(py, PEPTIDE.as_bytes()), Some(config)).unwrap();
+            let seen = py.eval("[]", None, None).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("seen", seen).unwrap();
+            let stop_at_20 = py.eval("lambda stats: seen.append(stats['current_step']) or stats['current_step'] < 20", Some(globals), None).unwrap();
+            engine.add_observer(10, stop_at_20.into());
+            let outcome = engine.run(py, Some(100)).unwrap();
+            assert_eq!(seen.extract::<Vec<u64>>().unwrap(), vec![10, 20]);
+            let telemetry = outcome.as_ref(py).get_item("telemetry").unwrap();
+            assert!(telemetry.get_item("stopped_by_observer").is_ok());
+            assert_eq!(engine.structure().unwrap().inner.records[1].name, "CA");
+
+            engine.clear_observers();
+            let failing = py.eval("lambda stats: 1 / 0", None, None).unwrap();
+            engine.add_observer(5, failing.into());
+            let error = engine.run(py, Some(10)).unwrap_err();
+            assert!(error.is_instance_of::<pyo3::exceptions::PyZeroDivisionError>(py));
+        });
+    }
+}
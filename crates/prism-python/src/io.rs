@@ -0,0 +1,120 @@
+//! # IO - Structures and Trajectories
+//!
+//! `prism.Structure` wraps a [`PdbStructure`] read from `.pdb`, `.cif` or
+//! `.ptb`; `prism.read_trajectory` loads every frame of a `.dcd` or `.xtc`
+//! file.
+
+use crate::arrays::{array_f32, coordinates};
+use crate::io_err;
+use prism_io::pdb::{parse_pdb, PdbStructure};
+use prism_io::structure_file;
+use prism_io::trajectory::read_frames;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+#[pyclass(name = "Structure", module = "prism")]
+#[derive(Debug, Clone)]
+pub struct PyStructure {
+    pub(crate) inner: PdbStructure,
+}
+
+#[pymethods]
+impl PyStructure {
+    /// Read a `.pdb`, `.cif` or `.ptb` file
+    #[staticmethod]
+    fn read(path: PathBuf) -> PyResult<Self> {
+        Ok(Self { inner: structure_file::read_structure(path).map_err(io_err)? })
+    }
+
+    #[staticmethod]
+    fn from_pdb_string(text: &str) -> PyResult<Self> {
+        Ok(Self { inner: parse_pdb(text).map_err(io_err)? })
+    }
+
+    /// Write in the format given by the extension of `path`
+    fn write(&self, path: PathBuf) -> PyResult<()> {
+        structure_file::write_structure(path, &self.inner).map_err(io_err)
+    }
+
+    fn to_pdb_string(&self) -> String {
+        self.inner.to_pdb_string()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.atoms.len()
+    }
+
+    /// `(n, 3)` float32 coordinates (Å)
+    #[getter]
+    fn coordinates(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let flat: Vec<f32> = self.inner.atoms.iter().flat_map(|a| a.coords).collect();
+        array_f32(py, &flat, &[self.inner.atoms.len(), 3])
+    }
+
+    #[setter]
+    fn set_coordinates(&mut self, values: &PyAny) -> PyResult<()> {
+        let rows = coordinates(values)?;
+        if rows.len() != self.inner.atoms.len() {
+            return Err(PyValueError::new_err(format!("Structure has {} atoms, got {} coordinates", self.inner.atoms.len(), rows.len())));
+        }
+        for (atom, row) in self.inner.atoms.iter_mut().zip(rows) {
+            atom.coords = row;
+        }
+        Ok(())
+    }
+
+    /// Atomic numbers
+    #[getter]
+    fn elements(&self) -> Vec<u8> {
+        self.inner.atoms.iter().map(|a| a.element).collect()
+    }
+
+    #[getter]
+    fn atom_names(&self) -> Vec<String> {
+        self.inner.records.iter().map(|r| r.name.clone()).collect()
+    }
+
+    #[getter]
+    fn residue_names(&self) -> Vec<String> {
+        self.inner.records.iter().map(|r| r.residue_name.clone()).collect()
+    }
+
+    /// Author residue numbers
+    #[getter]
+    fn residue_numbers(&self) -> Vec<i32> {
+        self.inner.records.iter().map(|r| r.residue_seq).collect()
+    }
+
+    #[getter]
+    fn chain_ids(&self) -> Vec<String> {
+        self.inner.records.iter().map(|r| r.chain_id.to_string()).collect()
+    }
+
+    /// Van der Waals radii (Å)
+    #[getter]
+    fn radii(&self) -> Vec<f32> {
+        self.inner.atoms.iter().map(|a| a.radius).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Structure({} atoms)", self.inner.atoms.len())
+    }
+}
+
+/// Read a `.pdb`, `.cif` or `.ptb` file
+#[pyfunction]
+pub fn read_structure(path: PathBuf) -> PyResult<PyStructure> {
+    PyStructure::read(path)
+}
+
+/// `(steps, frames)` of a `.dcd` or `.xtc` file, with `frames` a float32
+/// array of shape `(num_frames, num_atoms, 3)`
+#[pyfunction]
+pub fn read_trajectory(py: Python<'_>, path: PathBuf) -> PyResult<(Vec<u64>, PyObject)> {
+    let frames = read_frames(path).map_err(io_err)?;
+    let num_atoms = frames.first().map_or(0, |(_, f)| f.len());
+    let steps = frames.iter().map(|(step, _)| *step).collect();
+    let flat: Vec<f32> = frames.iter().flat_map(|(_, f)| f.iter().flatten().copied()).collect();
+    Ok((steps, array_f32(py, &flat, &[frames.len(), num_atoms, 3])?))
+}
@@ -0,0 +1,68 @@
+//! # PRISM Python - Bindings for the MD Engine
+//!
+//! The `prism` Python module: the engine and its configuration, structure
+//! and trajectory reading, and the trajectory analyses, with coordinates
+//! exchanged as `(n, 3)` NumPy arrays in Å.
+//!
+//! ```python
+//! import prism
+//!
+//! config = prism.MolecularDynamicsConfig.quick_test().replace(max_steps=5000)
+//! engine = prism.MolecularDynamicsEngine("protein.pdb", config)
+//! engine.add_observer(500, lambda stats: print(stats["current_step"], stats["current_energy"]))
+//! engine.minimize()
+//! outcome = engine.run()
+//! engine.write("final.cif")
+//!
+//! reference = prism.read_structure("protein.pdb").coordinates
+//! steps, frames = prism.read_trajectory("trajectory.dcd")
+//! rmsd, rmsf = prism.rmsd(reference, frames)
+//! ```
+//!
+//! Build with `maturin develop --release` from this directory (the
+//! `extension-module` feature is set by `pyproject.toml`).
+
+// pyo3 0.20's #[pymethods] expansion trips this lint on current compilers
+#![allow(non_local_definitions)]
+
+pub mod analysis;
+pub mod arrays;
+pub mod config;
+pub mod engine;
+pub mod io;
+
+use prism_core::PrismError;
+use prism_io::PrismIoError;
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+/// Python exception for an engine error
+pub(crate) fn prism_err(error: PrismError) -> PyErr {
+    match error {
+        PrismError::ConfigError(_) | PrismError::ValidationError(_) | PrismError::SerializationError(_) => PyValueError::new_err(error.to_string()),
+        PrismError::IoError(_) => PyIOError::new_err(error.to_string()),
+        _ => PyRuntimeError::new_err(error.to_string()),
+    }
+}
+
+/// Python exception for a file format error
+pub(crate) fn io_err(error: PrismIoError) -> PyErr {
+    match error {
+        PrismIoError::IoError(_) => PyIOError::new_err(error.to_string()),
+        _ => PyValueError::new_err(error.to_string()),
+    }
+}
+
+#[pymodule]
+fn prism(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add_class::<config::PyMolecularDynamicsConfig>()?;
+    m.add_class::<engine::PyMolecularDynamicsEngine>()?;
+    m.add_class::<io::PyStructure>()?;
+    m.add_function(wrap_pyfunction!(io::read_structure, m)?)?;
+    m.add_function(wrap_pyfunction!(io::read_trajectory, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::rmsd, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::shape_descriptors, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::sasa, m)?)?;
+    Ok(())
+}
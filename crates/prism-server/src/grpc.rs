@@ -0,0 +1,334 @@
+//! # gRPC - `prism.server.v1.Engine` over HTTP/2
+//!
+//! Serves the methods of `proto/prism_server.proto` on plain-text HTTP/2
+//! connections: requests are a single length-prefixed message, responses
+//! one message (or a stream of them for `StreamProgress`) followed by
+//! `grpc-status` trailers. Message compression is not supported.
+
+use crate::proto::{Message, Progress, RunId, SubmitRunRequest};
+use crate::runs::{Run, RunManager};
+use bytes::{Buf, Bytes};
+use h2::server::SendResponse;
+use h2::RecvStream;
+use http::{HeaderMap, HeaderValue, Request, Response};
+use prism_core::PrismError;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Fully qualified service name
+pub const SERVICE: &str = "prism.server.v1.Engine";
+
+/// Largest request message accepted (structures are sent inline)
+pub const MAX_MESSAGE_BYTES: usize = 256 << 20;
+
+/// gRPC status codes used by the service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    Ok = 0,
+    Cancelled = 1,
+    InvalidArgument = 3,
+    NotFound = 5,
+    ResourceExhausted = 8,
+    FailedPrecondition = 9,
+    Unimplemented = 12,
+    Internal = 13,
+}
+
+/// Failed call
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    pub code: Code,
+    pub message: String,
+}
+
+impl Status {
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    fn not_found(run_id: &str) -> Self {
+        Self::new(Code::NotFound, format!("No run '{}'", run_id))
+    }
+}
+
+impl From<PrismError> for Status {
+    fn from(e: PrismError) -> Self {
+        let code = match &e {
+            PrismError::ConfigError(_) | PrismError::ValidationError(_) => Code::InvalidArgument,
+            _ => Code::Internal,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<crate::proto::DecodeError> for Status {
+    fn from(e: crate::proto::DecodeError) -> Self {
+        Self::new(Code::InvalidArgument, format!("Malformed request: {}", e))
+    }
+}
+
+impl From<h2::Error> for Status {
+    fn from(e: h2::Error) -> Self {
+        Self::new(Code::Cancelled, format!("HTTP/2: {}", e))
+    }
+}
+
+/// Length-prefixed, uncompressed gRPC message
+pub fn frame(message: &impl Message) -> Bytes {
+    let payload = message.encode();
+    let mut out = Vec::with_capacity(payload.len() + 5);
+    out.push(0);
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(&payload);
+    out.into()
+}
+
+/// Percent-encode a `grpc-message` value
+fn encode_message(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        if (0x20..0x7F).contains(&byte) && byte != b'%' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn status_headers(headers: &mut HeaderMap, code: Code, message: &str) {
+    headers.insert("grpc-status", HeaderValue::from(code as u32));
+    if !message.is_empty() {
+        if let Ok(value) = HeaderValue::from_str(&encode_message(message)) {
+            headers.insert("grpc-message", value);
+        }
+    }
+}
+
+fn response_head(trailers_only: Option<&Status>) -> Response<()> {
+    let mut response = Response::new(());
+    response.headers_mut().insert(http::header::CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+    if let Some(status) = trailers_only {
+        status_headers(response.headers_mut(), status.code, &status.message);
+    }
+    response
+}
+
+/// Read the single request message of a call
+async fn read_message(mut body: RecvStream) -> Result<Vec<u8>, Status> {
+    let mut buffer = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        let _ = body.flow_control().release_capacity(chunk.len());
+        if buffer.len() + chunk.len() > MAX_MESSAGE_BYTES + 5 {
+            return Err(Status::new(Code::ResourceExhausted, format!("Request exceeds {} bytes", MAX_MESSAGE_BYTES)));
+        }
+        buffer.extend_from_slice(chunk.chunk());
+    }
+    if buffer.len() < 5 {
+        return Err(Status::new(Code::InvalidArgument, "Missing request message"));
+    }
+    if buffer[0] != 0 {
+        return Err(Status::new(Code::Unimplemented, "Compressed messages are not supported"));
+    }
+    let len = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+    if buffer.len() != len + 5 {
+        return Err(Status::new(Code::InvalidArgument, "Expected exactly one request message"));
+    }
+    buffer.drain(..5);
+    Ok(buffer)
+}
+
+/// Serve gRPC connections accepted on `listener` until the task is dropped
+pub async fn serve(listener: TcpListener, runs: RunManager) {
+    loop {
+        match listener.accept().await {
+            Ok((socket, peer)) => {
+                let runs = runs.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(socket, runs).await {
+                        log::debug!("gRPC connection {} closed: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) => log::warn!("⚠️ gRPC accept failed: {}", e),
+        }
+    }
+}
+
+async fn serve_connection(socket: TcpStream, runs: RunManager) -> Result<(), h2::Error> {
+    let _ = socket.set_nodelay(true);
+    let mut connection = h2::server::handshake(socket).await?;
+    while let Some(call) = connection.accept().await {
+        let (request, respond) = call?;
+        let runs = runs.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(request, respond, runs).await {
+                log::debug!("gRPC call ended: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Dispatch one call and send its response
+async fn handle(request: Request<RecvStream>, mut respond: SendResponse<Bytes>, runs: RunManager) -> Result<(), h2::Error> {
+    let method = request.uri().path().strip_prefix('/').and_then(|p| p.strip_prefix(SERVICE)).and_then(|p| p.strip_prefix('/')).map(str::to_string);
+    let body = read_message(request.into_body()).await;
+    let result = match (method.as_deref(), body) {
+        (_, Err(status)) => Err(status),
+        (Some("SubmitRun"), Ok(body)) => submit_run(&runs, &body).map(|reply| frame(&reply)),
+        (Some("GetStructure"), Ok(body)) => get_structure(&runs, &body).map(|reply| frame(&reply)),
+        (Some("CancelRun"), Ok(body)) => cancel_run(&runs, &body).map(|reply| frame(&reply)),
+        (Some("StreamProgress"), Ok(body)) => {
+            return match find_run(&runs, &body) {
+                Ok(run) => stream_progress(run, respond).await,
+                Err(status) => respond.send_response(response_head(Some(&status)), true).map(|_| ()),
+            };
+        }
+        (method, Ok(_)) => Err(Status::new(Code::Unimplemented, format!("Unknown method {}", method.unwrap_or("(none)")))),
+    };
+    match result {
+        Ok(message) => {
+            let mut stream = respond.send_response(response_head(None), false)?;
+            stream.send_data(message, false)?;
+            let mut trailers = HeaderMap::new();
+            status_headers(&mut trailers, Code::Ok, "");
+            stream.send_trailers(trailers)
+        }
+        Err(status) => respond.send_response(response_head(Some(&status)), true).map(|_| ()),
+    }
+}
+
+fn find_run(runs: &RunManager, body: &[u8]) -> Result<Arc<Run>, Status> {
+    let request = RunId::decode(body)?;
+    runs.get(&request.run_id).ok_or_else(|| Status::not_found(&request.run_id))
+}
+
+fn submit_run(runs: &RunManager, body: &[u8]) -> Result<RunId, Status> {
+    let run = runs.submit(SubmitRunRequest::decode(body)?)?;
+    Ok(RunId { run_id: run.id().to_string() })
+}
+
+fn get_structure(runs: &RunManager, body: &[u8]) -> Result<crate::proto::Structure, Status> {
+    let run = find_run(runs, body)?;
+    run.structure().ok_or_else(|| Status::new(Code::FailedPrecondition, format!("Run '{}' has no structure yet", run.id())))
+}
+
+fn cancel_run(runs: &RunManager, body: &[u8]) -> Result<Progress, Status> {
+    let run = find_run(runs, body)?;
+    run.cancel();
+    Ok(run.progress())
+}
+
+/// Send the current progress and every change until the run ends
+async fn stream_progress(run: Arc<Run>, mut respond: SendResponse<Bytes>) -> Result<(), h2::Error> {
+    let mut stream = respond.send_response(response_head(None), false)?;
+    let mut progress = run.subscribe();
+    loop {
+        let latest = progress.borrow_and_update().clone();
+        stream.send_data(frame(&latest), false)?;
+        if latest.state.is_final() || progress.changed().await.is_err() {
+            break;
+        }
+    }
+    let mut trailers = HeaderMap::new();
+    status_headers(&mut trailers, Code::Ok, "");
+    stream.send_trailers(trailers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::{RunState, Structure};
+    use prism_physics::scheduler::SchedulerConfig;
+
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+";
+
+    /// Unary or streaming call; the response messages and `grpc-status`
+    async fn call(client: &mut h2::client::SendRequest<Bytes>, method: &str, message: &impl Message) -> (Vec<Vec<u8>>, u32) {
+        let request = Request::post(format!("http://localhost/{}/{}", SERVICE, method))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(())
+            .unwrap();
+        let (response, mut send) = client.clone().ready().await.unwrap().send_request(request, false).unwrap();
+        send.send_data(frame(message), true).unwrap();
+        let response = response.await.unwrap();
+        let head_status = response.headers().get("grpc-status").map(|v| v.to_str().unwrap().parse().unwrap());
+        let mut body = response.into_body();
+        let mut data = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.unwrap();
+            let _ = body.flow_control().release_capacity(chunk.len());
+            data.extend_from_slice(&chunk);
+        }
+        let status = match head_status {
+            Some(code) => code,
+            None => body.trailers().await.unwrap().unwrap()["grpc-status"].to_str().unwrap().parse().unwrap(),
+        };
+        let mut messages = Vec::new();
+        let mut rest = data.as_slice();
+        while rest.len() >= 5 {
+            let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+            messages.push(rest[5..5 + len].to_vec());
+            rest = &rest[5 + len..];
+        }
+        (messages, status)
+    }
+
+    #[test]
+    fn test_submit_stream_and_fetch_structure() {
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+        runtime.block_on(async {
+            let workdir = std::env::temp_dir().join(format!("prism_server_{}", std::process::id()));
+            let runs = RunManager::new(workdir.clone(), SchedulerConfig::default()).unwrap();
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(serve(listener, runs));
+
+            let socket = TcpStream::connect(addr).await.unwrap();
+            let (mut client, connection) = h2::client::handshake(socket).await.unwrap();
+            tokio::spawn(connection);
+
+            let bad = SubmitRunRequest { config: "[bogus]\nx = 1\n".to_string(), structure: PEPTIDE.into(), ..Default::default() };
+            let (_, status) = call(&mut client, "SubmitRun", &bad).await;
+            assert_eq!(status, Code::InvalidArgument as u32);
+            let (_, status) = call(&mut client, "GetStructure", &RunId { run_id: "nope".to_string() }).await;
+            assert_eq!(status, Code::NotFound as u32);
+
+            let submit = SubmitRunRequest {
+                config: "[engine]\nuse_gpu = false\n".to_string(),
+                structure: PEPTIDE.into(),
+                steps: 20,
+                progress_interval: 5,
+                snapshot_interval: 10,
+                ..Default::default()
+            };
+            let (messages, status) = call(&mut client, "SubmitRun", &submit).await;
+            assert_eq!(status, 0);
+            let run_id = RunId::decode(&messages[0]).unwrap();
+
+            let (messages, status) = call(&mut client, "StreamProgress", &run_id).await;
+            assert_eq!(status, 0);
+            let last = Progress::decode(messages.last().unwrap()).unwrap();
+            assert_eq!(last.state, RunState::Completed, "{}", last.message);
+            assert_eq!((last.step, last.total_steps), (20, 20));
+
+            let (messages, status) = call(&mut client, "GetStructure", &run_id).await;
+            assert_eq!(status, 0);
+            let structure = Structure::decode(&messages[0]).unwrap();
+            assert_eq!(structure.step, 20);
+            assert_eq!(structure.pdb.lines().filter(|l| l.starts_with("ATOM")).count(), 4);
+
+            let (messages, _) = call(&mut client, "CancelRun", &run_id).await;
+            assert_eq!(Progress::decode(&messages[0]).unwrap().state, RunState::Completed);
+            let _ = std::fs::remove_dir_all(&workdir);
+        });
+    }
+}
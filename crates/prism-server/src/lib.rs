@@ -0,0 +1,53 @@
+//! # PRISM Server - Remote Control Service for the MD Engine
+//!
+//! Runs the molecular dynamics engine as a shared lab service: clients
+//! submit a run configuration together with a structure, follow its
+//! progress, fetch intermediate structures and cancel it over gRPC
+//! (`prism.server.v1.Engine`, see `proto/prism_server.proto`).
+//!
+//! Runs are started by a [`prism_physics::scheduler::JobScheduler`] as CPU
+//! slots and GPU memory allow, by priority or fair share between the
+//! submitting owners; their output files are written under
+//! `<workdir>/<run id>`.
+
+pub mod grpc;
+pub mod proto;
+pub mod runs;
+
+use prism_core::PrismError;
+use prism_physics::scheduler::SchedulerConfig;
+use runs::RunManager;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+/// Service settings
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub listen: SocketAddr,
+    /// Parent of the per-run output directories
+    pub workdir: PathBuf,
+    /// CPU slots, GPU budgets and queue policy of the runs
+    pub scheduler: SchedulerConfig,
+}
+
+/// Serve until `shutdown` completes; runs still going are abandoned
+pub async fn serve(config: ServerConfig, shutdown: impl std::future::Future<Output = ()>) -> Result<(), PrismError> {
+    let runs = RunManager::new(config.workdir.clone(), config.scheduler.clone())?;
+    let listener = tokio::net::TcpListener::bind(config.listen)
+        .await
+        .map_err(|e| PrismError::config(format!("Cannot bind {}: {}", config.listen, e)))?;
+    log::info!(
+        "🛰️ {} listening on {} ({} CPU slots, {} GPUs, {:?} policy, runs in {})",
+        grpc::SERVICE,
+        config.listen,
+        config.scheduler.max_cpu_jobs,
+        config.scheduler.gpus.len(),
+        config.scheduler.policy,
+        config.workdir.display()
+    );
+    tokio::select! {
+        _ = grpc::serve(listener, runs) => {}
+        _ = shutdown => log::info!("🛑 Shutting down"),
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! # prism-server - gRPC Service for the MD Engine
+//!
+//! ```bash
+//! prism-server --listen 0.0.0.0:50051 --workdir /scratch/prism-runs --workers 2
+//! prism-server --workers 4 --gpus 0,1 --policy fair-share   # shared workstation
+//! ```
+
+use clap::{Parser, ValueEnum};
+use prism_physics::scheduler::{SchedulerConfig, SchedulingPolicy};
+use prism_server::ServerConfig;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "prism-server", about = "Run the PRISM-4D MD engine as a gRPC service")]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    listen: SocketAddr,
+
+    /// Directory for the output files of the runs
+    #[arg(long, default_value = "prism-runs")]
+    workdir: PathBuf,
+
+    /// CPU runs executed at the same time
+    #[arg(long, default_value_t = 1)]
+    workers: usize,
+
+    /// GPUs for `use_gpu` runs, which share each device while their
+    /// memory budgets fit (needs the `cuda` feature)
+    #[arg(long, value_delimiter = ',')]
+    gpus: Vec<usize>,
+
+    /// Order of queued runs
+    #[arg(long, value_enum, default_value_t = Policy::Priority)]
+    policy: Policy,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Policy {
+    /// Highest request priority first
+    Priority,
+    /// Owners with the least running and past work first
+    FairShare,
+}
+
+#[cfg(feature = "cuda")]
+fn gpu_slots(devices: &[usize]) -> anyhow::Result<Vec<prism_physics::scheduler::GpuSlot>> {
+    Ok(devices.iter().map(|&d| prism_physics::scheduler::GpuSlot::query(d)).collect::<Result<_, _>>()?)
+}
+
+#[cfg(not(feature = "cuda"))]
+fn gpu_slots(devices: &[usize]) -> anyhow::Result<Vec<prism_physics::scheduler::GpuSlot>> {
+    if !devices.is_empty() {
+        anyhow::bail!("--gpus needs a build with the `cuda` feature");
+    }
+    Ok(Vec::new())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
+    let policy = match args.policy {
+        Policy::Priority => SchedulingPolicy::Priority,
+        Policy::FairShare => SchedulingPolicy::FairShare,
+    };
+    let scheduler = SchedulerConfig { policy, max_cpu_jobs: args.workers.max(1), gpus: gpu_slots(&args.gpus)? };
+    let config = ServerConfig { listen: args.listen, workdir: args.workdir, scheduler };
+    prism_server::serve(config, async {
+        let _ = tokio::signal::ctrl_c().await;
+    })
+    .await?;
+    Ok(())
+}
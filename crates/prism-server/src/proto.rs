@@ -0,0 +1,418 @@
+//! # Protocol Buffers - Messages of `prism.server.v1`
+//!
+//! proto3 wire encoding of the messages in `proto/prism_server.proto`,
+//! written by hand for the handful of scalar, string and bytes fields the
+//! service needs. Default values are omitted on encode and unknown fields
+//! are skipped on decode, as in generated code.
+
+use thiserror::Error;
+
+/// Malformed message
+#[derive(Debug, Error, PartialEq)]
+pub enum DecodeError {
+    #[error("message truncated")]
+    Truncated,
+    #[error("varint longer than 10 bytes")]
+    VarintOverflow,
+    #[error("unsupported wire type {0}")]
+    WireType(u8),
+    #[error("field {field} has wire type {found}, expected {expected}")]
+    FieldType { field: u32, found: u8, expected: u8 },
+    #[error("field {0} is not valid UTF-8")]
+    Utf8(u32),
+}
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LENGTH_DELIMITED: u8 = 2;
+const FIXED32: u8 = 5;
+
+/// Encoder of one message
+#[derive(Debug, Default)]
+pub struct Writer(Vec<u8>);
+
+impl Writer {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u8) {
+        self.varint((field as u64) << 3 | wire_type as u64);
+    }
+
+    pub fn uint64(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.key(field, VARINT);
+            self.varint(value);
+        }
+    }
+
+    pub fn double(&mut self, field: u32, value: f64) {
+        if value.to_bits() != 0 {
+            self.key(field, FIXED64);
+            self.0.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    pub fn bytes(&mut self, field: u32, value: &[u8]) {
+        if !value.is_empty() {
+            self.key(field, LENGTH_DELIMITED);
+            self.varint(value.len() as u64);
+            self.0.extend_from_slice(value);
+        }
+    }
+
+    pub fn string(&mut self, field: u32, value: &str) {
+        self.bytes(field, value.as_bytes());
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Payload of one decoded field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Value<'a> {
+    fn wire_type(&self) -> u8 {
+        match self {
+            Self::Varint(_) => VARINT,
+            Self::Fixed64(_) => FIXED64,
+            Self::Bytes(_) => LENGTH_DELIMITED,
+            Self::Fixed32(_) => FIXED32,
+        }
+    }
+
+    fn mismatch(&self, field: u32, expected: u8) -> DecodeError {
+        DecodeError::FieldType { field, found: self.wire_type(), expected }
+    }
+
+    pub fn uint64(self, field: u32) -> Result<u64, DecodeError> {
+        match self {
+            Self::Varint(v) => Ok(v),
+            other => Err(other.mismatch(field, VARINT)),
+        }
+    }
+
+    pub fn double(self, field: u32) -> Result<f64, DecodeError> {
+        match self {
+            Self::Fixed64(v) => Ok(f64::from_bits(v)),
+            other => Err(other.mismatch(field, FIXED64)),
+        }
+    }
+
+    pub fn bytes(self, field: u32) -> Result<&'a [u8], DecodeError> {
+        match self {
+            Self::Bytes(v) => Ok(v),
+            other => Err(other.mismatch(field, LENGTH_DELIMITED)),
+        }
+    }
+
+    pub fn string(self, field: u32) -> Result<String, DecodeError> {
+        let bytes = self.bytes(field)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::Utf8(field))
+    }
+}
+
+/// Fields of an encoded message in wire order
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for i in 0..10 {
+            let (&byte, rest) = self.0.split_first().ok_or(DecodeError::Truncated)?;
+            self.0 = rest;
+            value |= ((byte & 0x7F) as u64) << (7 * i);
+            if byte < 0x80 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::VarintOverflow)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.0.len() < len {
+            return Err(DecodeError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn field(&mut self) -> Result<(u32, Value<'a>), DecodeError> {
+        let key = self.varint()?;
+        let value = match (key & 7) as u8 {
+            VARINT => Value::Varint(self.varint()?),
+            FIXED64 => Value::Fixed64(u64::from_le_bytes(self.take(8)?.try_into().unwrap_or_default())),
+            LENGTH_DELIMITED => {
+                let len = self.varint()?;
+                Value::Bytes(self.take(usize::try_from(len).map_err(|_| DecodeError::Truncated)?)?)
+            }
+            FIXED32 => Value::Fixed32(u32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default())),
+            other => return Err(DecodeError::WireType(other)),
+        };
+        Ok(((key >> 3) as u32, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u32, Value<'a>), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            self.0 = &[];
+        }
+        Some(field)
+    }
+}
+
+/// A message of the service
+pub trait Message: Default {
+    fn write(&self, writer: &mut Writer);
+
+    /// Apply one field; unknown fields are ignored
+    fn merge(&mut self, field: u32, value: Value<'_>) -> Result<(), DecodeError>;
+
+    fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        self.write(&mut writer);
+        writer.finish()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut message = Self::default();
+        for field in Fields(bytes) {
+            let (number, value) = field?;
+            message.merge(number, value)?;
+        }
+        Ok(message)
+    }
+}
+
+/// `RunState` enum of the service
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RunState {
+    #[default]
+    Unspecified = 0,
+    Queued = 1,
+    Running = 2,
+    Completed = 3,
+    Cancelled = 4,
+    Failed = 5,
+}
+
+impl RunState {
+    /// Unknown values decode as `Unspecified`
+    pub fn from_i32(value: u64) -> Self {
+        match value {
+            1 => Self::Queued,
+            2 => Self::Running,
+            3 => Self::Completed,
+            4 => Self::Cancelled,
+            5 => Self::Failed,
+            _ => Self::Unspecified,
+        }
+    }
+
+    /// No further updates follow
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Completed | Self::Cancelled | Self::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubmitRunRequest {
+    pub config: String,
+    pub profile: String,
+    pub structure: Vec<u8>,
+    pub steps: u64,
+    pub progress_interval: u64,
+    pub snapshot_interval: u64,
+    pub priority: i32,
+    pub owner: String,
+}
+
+impl Message for SubmitRunRequest {
+    fn write(&self, w: &mut Writer) {
+        w.string(1, &self.config);
+        w.string(2, &self.profile);
+        w.bytes(3, &self.structure);
+        w.uint64(4, self.steps);
+        w.uint64(5, self.progress_interval);
+        w.uint64(6, self.snapshot_interval);
+        // int32 varints carry negative values sign-extended to 64 bits
+        w.uint64(7, self.priority as i64 as u64);
+        w.string(8, &self.owner);
+    }
+
+    fn merge(&mut self, field: u32, value: Value<'_>) -> Result<(), DecodeError> {
+        match field {
+            1 => self.config = value.string(field)?,
+            2 => self.profile = value.string(field)?,
+            3 => self.structure = value.bytes(field)?.to_vec(),
+            4 => self.steps = value.uint64(field)?,
+            5 => self.progress_interval = value.uint64(field)?,
+            6 => self.snapshot_interval = value.uint64(field)?,
+            7 => self.priority = value.uint64(field)? as i64 as i32,
+            8 => self.owner = value.string(field)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Message holding only a `run_id` (`SubmitRunResponse`,
+/// `StreamProgressRequest`, `GetStructureRequest`, `CancelRunRequest`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunId {
+    pub run_id: String,
+}
+
+impl Message for RunId {
+    fn write(&self, w: &mut Writer) {
+        w.string(1, &self.run_id);
+    }
+
+    fn merge(&mut self, field: u32, value: Value<'_>) -> Result<(), DecodeError> {
+        if field == 1 {
+            self.run_id = value.string(field)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    pub run_id: String,
+    pub state: RunState,
+    pub step: u64,
+    pub total_steps: u64,
+    pub potential_energy: f64,
+    pub temperature_kelvin: f64,
+    pub gradient_norm: f64,
+    pub runtime_seconds: f64,
+    pub message: String,
+}
+
+impl Message for Progress {
+    fn write(&self, w: &mut Writer) {
+        w.string(1, &self.run_id);
+        w.uint64(2, self.state as u64);
+        w.uint64(3, self.step);
+        w.uint64(4, self.total_steps);
+        w.double(5, self.potential_energy);
+        w.double(6, self.temperature_kelvin);
+        w.double(7, self.gradient_norm);
+        w.double(8, self.runtime_seconds);
+        w.string(9, &self.message);
+    }
+
+    fn merge(&mut self, field: u32, value: Value<'_>) -> Result<(), DecodeError> {
+        match field {
+            1 => self.run_id = value.string(field)?,
+            2 => self.state = RunState::from_i32(value.uint64(field)?),
+            3 => self.step = value.uint64(field)?,
+            4 => self.total_steps = value.uint64(field)?,
+            5 => self.potential_energy = value.double(field)?,
+            6 => self.temperature_kelvin = value.double(field)?,
+            7 => self.gradient_norm = value.double(field)?,
+            8 => self.runtime_seconds = value.double(field)?,
+            9 => self.message = value.string(field)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Structure {
+    pub run_id: String,
+    pub step: u64,
+    pub pdb: String,
+}
+
+impl Message for Structure {
+    fn write(&self, w: &mut Writer) {
+        w.string(1, &self.run_id);
+        w.uint64(2, self.step);
+        w.string(3, &self.pdb);
+    }
+
+    fn merge(&mut self, field: u32, value: Value<'_>) -> Result<(), DecodeError> {
+        match field {
+            1 => self.run_id = value.string(field)?,
+            2 => self.step = value.uint64(field)?,
+            3 => self.pdb = value.string(field)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_format_matches_protobuf() {
+        // `Test1 { a: 150 }` from the protobuf encoding guide
+        let mut w = Writer::default();
+        w.uint64(1, 150);
+        assert_eq!(w.finish(), [0x08, 0x96, 0x01]);
+        let mut w = Writer::default();
+        w.string(2, "testing");
+        assert_eq!(w.finish(), b"\x12\x07testing");
+        // Negative int32 values take ten bytes
+        let submit = SubmitRunRequest { priority: -2, owner: "alice".to_string(), ..Default::default() };
+        let bytes = submit.encode();
+        assert_eq!(bytes.len(), 11 + 7);
+        assert_eq!(SubmitRunRequest::decode(&bytes).unwrap(), submit);
+        // Defaults are omitted
+        assert!(Progress::default().encode().is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_skips_unknown_fields() {
+        let progress = Progress {
+            run_id: "abc".to_string(),
+            state: RunState::Running,
+            step: 12_000,
+            total_steps: 50_000,
+            potential_energy: -1234.5,
+            temperature_kelvin: 300.0,
+            gradient_norm: 0.25,
+            runtime_seconds: 1.5,
+            message: String::new(),
+        };
+        let mut bytes = progress.encode();
+        // Field 15 (fixed32) and field 16 (bytes) from a newer schema
+        bytes.extend_from_slice(&[0x7D, 1, 2, 3, 4, 0x82, 0x01, 2, b'h', b'i']);
+        assert_eq!(Progress::decode(&bytes).unwrap(), progress);
+
+        assert_eq!(Progress::decode(&[0x1A, 0x05, b'x']), Err(DecodeError::Truncated));
+        assert_eq!(
+            Progress::decode(&[0x18, 0x01, 0x0A, 0x01]).unwrap_err(),
+            DecodeError::Truncated
+        );
+        assert!(matches!(
+            RunId::decode(&[0x08, 0x01]),
+            Err(DecodeError::FieldType { field: 1, found: 0, expected: 2 })
+        ));
+    }
+}
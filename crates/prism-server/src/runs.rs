@@ -0,0 +1,233 @@
+//! # Runs - Queue and State of Submitted Simulations
+//!
+//! Submitted runs are validated up front, then queued on a
+//! [`JobScheduler`], which starts them by priority or fair share as CPU
+//! slots and GPU memory free up. Each run keeps its latest progress in a
+//! watch channel (so any number of clients can follow it) and the last
+//! structure taken from the engine, which is refreshed every
+//! `snapshot_interval` steps and at the end of the run.
+
+use crate::proto::{Progress, RunState, Structure, SubmitRunRequest};
+use prism_core::{CancellationToken, PhaseOutcome, PrismError};
+use prism_io::pdb::PdbStructure;
+use prism_physics::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsEngine, MolecularDynamicsStats};
+use prism_physics::run_config::{ConfigFormat, RunConfig};
+use prism_physics::scheduler::{JobScheduler, JobSpec, Placement, SchedulerConfig};
+use prism_physics::units::{Energy, Temperature};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Steps between progress samples when the request leaves it unset
+pub const DEFAULT_PROGRESS_INTERVAL: u64 = 1000;
+
+/// One submitted run
+#[derive(Debug)]
+pub struct Run {
+    id: String,
+    token: CancellationToken,
+    progress: watch::Sender<Progress>,
+    structure: Mutex<Option<Structure>>,
+}
+
+impl Run {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Latest progress
+    pub fn progress(&self) -> Progress {
+        self.progress.borrow().clone()
+    }
+
+    /// Follow the progress of the run
+    pub fn subscribe(&self) -> watch::Receiver<Progress> {
+        self.progress.subscribe()
+    }
+
+    /// Last stored structure, `None` before the first snapshot
+    pub fn structure(&self) -> Option<Structure> {
+        self.structure.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Request cancellation; a queued run ends before it starts
+    pub fn cancel(&self) {
+        self.token.cancel();
+        self.progress.send_if_modified(|p| {
+            let queued = p.state == RunState::Queued;
+            if queued {
+                p.state = RunState::Cancelled;
+                p.message = "Cancelled before start".to_string();
+            }
+            queued
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Progress)) {
+        self.progress.send_modify(f);
+    }
+
+    fn finish(&self, state: RunState, message: String) {
+        self.update(|p| {
+            p.state = state;
+            p.message = message;
+        });
+    }
+}
+
+/// Validated run waiting for the scheduler
+struct Job {
+    run: Arc<Run>,
+    engine: MolecularDynamicsConfig,
+    structure: Vec<u8>,
+    steps: u64,
+    progress_interval: u64,
+    snapshot_interval: u64,
+}
+
+/// Registry and executor of runs
+#[derive(Debug, Clone)]
+pub struct RunManager {
+    runs: Arc<Mutex<HashMap<String, Arc<Run>>>>,
+    scheduler: JobScheduler,
+    workdir: PathBuf,
+}
+
+impl std::fmt::Debug for Job {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Job").field("run", &self.run.id).field("steps", &self.steps).finish_non_exhaustive()
+    }
+}
+
+impl RunManager {
+    /// Execute runs as `scheduler` allows, with their output files under
+    /// `workdir/<run id>`
+    pub fn new(workdir: PathBuf, scheduler: SchedulerConfig) -> Result<Self, PrismError> {
+        std::fs::create_dir_all(&workdir)
+            .map_err(|e| PrismError::config(format!("Cannot create work directory {}: {}", workdir.display(), e)))?;
+        Ok(Self { runs: Arc::default(), scheduler: JobScheduler::new(scheduler), workdir })
+    }
+
+    pub fn scheduler(&self) -> &JobScheduler {
+        &self.scheduler
+    }
+
+    /// Validate `request` and queue the run
+    pub fn submit(&self, request: SubmitRunRequest) -> Result<Arc<Run>, PrismError> {
+        if request.structure.is_empty() {
+            return Err(PrismError::validation("SubmitRun needs a structure"));
+        }
+        let profile = Some(request.profile.as_str()).filter(|p| !p.is_empty());
+        let mut config = RunConfig::parse(&request.config, ConfigFormat::Toml, profile, std::iter::empty())?;
+        if config.system.topology.is_some() || config.system.coordinates.is_some() {
+            return Err(PrismError::config("Remote runs take their structure from the request; remove [system]"));
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        let run_dir = self.workdir.join(&id);
+        std::fs::create_dir_all(&run_dir)
+            .map_err(|e| PrismError::Internal(format!("Cannot create {}: {}", run_dir.display(), e)))?;
+        config.resolve_paths(&run_dir);
+
+        let steps = if request.steps == 0 { config.engine.max_steps } else { request.steps };
+        let estimate = MolecularDynamicsEngine::estimate(&MolecularDynamicsConfig { max_steps: steps, ..config.engine.clone() }, &request.structure)?;
+        estimate.check(&config.engine, None)?;
+        let (progress, _) = watch::channel(Progress { run_id: id.clone(), state: RunState::Queued, total_steps: steps, ..Default::default() });
+        let run = Arc::new(Run { id: id.clone(), token: CancellationToken::new(), progress, structure: Mutex::new(None) });
+        let owner = if request.owner.is_empty() { "anonymous" } else { request.owner.as_str() };
+        let spec = JobSpec::for_estimate(&id, &estimate).owner(owner).priority(request.priority);
+        let mut job = Job {
+            run: run.clone(),
+            engine: config.engine,
+            structure: request.structure,
+            steps,
+            progress_interval: if request.progress_interval == 0 { DEFAULT_PROGRESS_INTERVAL } else { request.progress_interval },
+            snapshot_interval: request.snapshot_interval,
+        };
+        self.scheduler.submit(spec, move |placement| {
+            if let Placement::Gpu { device } = placement {
+                job.engine.devices = vec![device];
+            }
+            execute(job)
+        })?;
+        self.runs.lock().unwrap_or_else(|e| e.into_inner()).insert(id.clone(), run.clone());
+        log::info!("📥 Queued run {} ({} steps, owner {}, priority {}, ~{:.0} s)", id, steps, owner, request.priority, estimate.wall_clock_seconds);
+        Ok(run)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Run>> {
+        self.runs.lock().unwrap_or_else(|e| e.into_inner()).get(id).cloned()
+    }
+}
+
+fn kelvin(kt: f32) -> f64 {
+    Temperature::from_kt(Energy::kcal_per_mol(kt as f64)).as_kelvin()
+}
+
+fn record(progress: &mut Progress, stats: &MolecularDynamicsStats) {
+    progress.step = stats.current_step;
+    progress.potential_energy = stats.current_energy as f64;
+    progress.temperature_kelvin = kelvin(stats.current_temperature);
+    progress.gradient_norm = stats.gradient_norm as f64;
+    progress.runtime_seconds = stats.runtime_seconds as f64;
+}
+
+/// Store the current structure of `engine` on `run`
+fn snapshot(run: &Run, engine: &mut MolecularDynamicsEngine) -> Result<(), PrismError> {
+    let step = engine.get_statistics().current_step;
+    let atoms = engine.get_current_atoms()?;
+    let pdb = PdbStructure::from_atoms(&atoms).to_pdb_string();
+    *run.structure.lock().unwrap_or_else(|e| e.into_inner()) = Some(Structure { run_id: run.id.clone(), step, pdb });
+    Ok(())
+}
+
+fn execute(job: Job) {
+    let run = job.run.clone();
+    if run.token.is_cancelled() {
+        return run.finish(RunState::Cancelled, "Cancelled before start".to_string());
+    }
+    run.update(|p| p.state = RunState::Running);
+    log::info!("▶️ Starting run {}", run.id);
+    match simulate(job) {
+        Ok(PhaseOutcome::Cancelled { reason, .. }) => run.finish(RunState::Cancelled, reason),
+        Ok(_) => run.finish(RunState::Completed, String::new()),
+        Err(e) => {
+            log::warn!("⚠️ Run {} failed: {}", run.id, e);
+            run.finish(RunState::Failed, e.to_string());
+        }
+    }
+    log::info!("⏹️ Run {} ended: {:?}", run.id, run.progress().state);
+}
+
+/// Run the job in chunks of `snapshot_interval` steps, storing the
+/// structure after each
+fn simulate(job: Job) -> Result<PhaseOutcome, PrismError> {
+    let run = job.run;
+    let mut engine = MolecularDynamicsEngine::from_sovereign_buffer(job.engine, &job.structure)?;
+    engine.set_cancellation_token(run.token.clone());
+    let observed = run.clone();
+    engine.add_observer(job.progress_interval, move |stats| {
+        observed.update(|p| record(p, stats));
+        ControlFlow::Continue(())
+    });
+
+    let chunk = if job.snapshot_interval == 0 { job.steps } else { job.snapshot_interval };
+    let mut remaining = job.steps;
+    let mut outcome = PhaseOutcome::success();
+    while remaining > 0 {
+        let steps = remaining.min(chunk);
+        outcome = engine.run_nlnm_breathing(steps)?;
+        remaining -= steps;
+        snapshot(&run, &mut engine)?;
+        if matches!(outcome, PhaseOutcome::Cancelled { .. }) {
+            break;
+        }
+    }
+    if job.steps == 0 {
+        snapshot(&run, &mut engine)?;
+    }
+    let stats = engine.get_statistics();
+    run.update(|p| record(p, &stats));
+    Ok(outcome)
+}
@@ -0,0 +1,176 @@
+//! # PRISM WASM - The CPU Engine in the Browser
+//!
+//! A [`Simulation`] wraps the host-only engine for `wasm32-unknown-unknown`
+//! so teaching tools can minimize and run small structures client-side
+//! and animate the positions each frame. Runs are synchronous and block
+//! the calling thread; drive them from a Web Worker, or in short `step`
+//! batches from `requestAnimationFrame`.
+//!
+//! ```js
+//! import init, { Simulation } from "./pkg/prism_wasm.js";
+//!
+//! await init();
+//! const sim = new Simulation(pdbText, JSON.stringify({ engine: { temperature: 300 } }));
+//! sim.minimize();
+//! function frame() {
+//!     sim.step(20);
+//!     draw(sim.positions(), sim.elements());  // Float32Array x0 y0 z0 x1 ..., Uint8Array
+//!     requestAnimationFrame(frame);
+//! }
+//! requestAnimationFrame(frame);
+//! ```
+//!
+//! Build with `wasm-pack build --target web crates/prism-wasm`. The crate
+//! also builds natively, which is how its tests run.
+
+use prism_core::{PhaseOutcome, PrismError};
+use prism_io::pdb::{parse_pdb, PdbStructure};
+use prism_physics::molecular_dynamics::{MolecularDynamicsConfig, MolecularDynamicsEngine};
+use prism_physics::run_config::{ConfigFormat, RunConfig};
+use wasm_bindgen::prelude::*;
+
+/// An engine and the structure it was built from
+#[wasm_bindgen]
+pub struct Simulation {
+    engine: MolecularDynamicsEngine,
+    /// Metadata for [`Simulation::to_pdb`], when the input was PDB text
+    template: Option<PdbStructure>,
+}
+
+fn js_error(error: PrismError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+fn outcome(outcome: Result<PhaseOutcome, PrismError>) -> Result<(), JsError> {
+    match outcome.map_err(js_error)? {
+        PhaseOutcome::Success { .. } | PhaseOutcome::Cancelled { .. } => Ok(()),
+        PhaseOutcome::Retry { reason, .. } | PhaseOutcome::Escalate { reason } => Err(JsError::new(&reason)),
+    }
+}
+
+/// Engine config from the JSON run configuration `config_json` (engine
+/// defaults when absent), always on the host
+fn engine_config(config_json: Option<String>) -> Result<MolecularDynamicsConfig, PrismError> {
+    let config = match config_json {
+        Some(text) => {
+            let run = RunConfig::parse(&text, ConfigFormat::Json, None, std::iter::empty())?;
+            if run.system.topology.is_some() || run.system.coordinates.is_some() {
+                return Err(PrismError::config("The structure is passed in memory; remove \"system\" from the configuration"));
+            }
+            run.engine
+        }
+        None => MolecularDynamicsConfig::default(),
+    };
+    Ok(MolecularDynamicsConfig { use_gpu: false, ..config })
+}
+
+impl Simulation {
+    fn build(buffer: &[u8], template: Option<PdbStructure>, config_json: Option<String>) -> Result<Simulation, PrismError> {
+        let engine = MolecularDynamicsEngine::from_sovereign_buffer(engine_config(config_json)?, buffer)?;
+        Ok(Simulation { engine, template })
+    }
+}
+
+#[wasm_bindgen]
+impl Simulation {
+    /// Simulation of the PDB text `pdb`, configured by the JSON run
+    /// configuration `configJson` (`{"engine": {...}}`)
+    #[wasm_bindgen(constructor)]
+    pub fn new(pdb: &str, config_json: Option<String>) -> Result<Simulation, JsError> {
+        Self::build(pdb.as_bytes(), parse_pdb(pdb).ok(), config_json).map_err(js_error)
+    }
+
+    /// Simulation of a `.ptb` file's bytes
+    #[wasm_bindgen(js_name = fromPtb)]
+    pub fn from_ptb(bytes: &[u8], config_json: Option<String>) -> Result<Simulation, JsError> {
+        Self::build(bytes, None, config_json).map_err(js_error)
+    }
+
+    /// Number of atoms
+    #[wasm_bindgen(getter, js_name = numAtoms)]
+    pub fn num_atoms(&mut self) -> Result<usize, JsError> {
+        Ok(self.engine.get_current_atoms().map_err(js_error)?.len())
+    }
+
+    /// Minimize the energy with the configured minimizer
+    pub fn minimize(&mut self) -> Result<(), JsError> {
+        outcome(self.engine.minimize())
+    }
+
+    /// Advance the Langevin dynamics by `steps` steps
+    pub fn step(&mut self, steps: u32) -> Result<(), JsError> {
+        outcome(self.engine.run_nlnm_breathing(steps as u64))
+    }
+
+    /// Current positions (Å) as `x0 y0 z0 x1 ...`
+    pub fn positions(&mut self) -> Result<Vec<f32>, JsError> {
+        let atoms = self.engine.get_current_atoms().map_err(js_error)?;
+        Ok(atoms.iter().flat_map(|a| a.coords).collect())
+    }
+
+    /// Atomic number of each atom, for coloring
+    pub fn elements(&mut self) -> Result<Vec<u8>, JsError> {
+        Ok(self.engine.get_current_atoms().map_err(js_error)?.iter().map(|a| a.element).collect())
+    }
+
+    /// Run statistics as JSON
+    pub fn statistics(&self) -> Result<String, JsError> {
+        serde_json::to_string(&self.engine.get_statistics()).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    /// Current structure as PDB text, keeping the input's metadata when it
+    /// was PDB
+    #[wasm_bindgen(js_name = toPdb)]
+    pub fn to_pdb(&mut self) -> Result<String, JsError> {
+        let atoms = self.engine.get_current_atoms().map_err(js_error)?;
+        let structure = match &self.template {
+            Some(template) if template.atoms.len() == atoms.len() => {
+                let mut structure = template.clone();
+                structure.update_coordinates(&atoms).map_err(|e| JsError::new(&e.to_string()))?;
+                structure
+            }
+            _ => PdbStructure::from_atoms(&atoms),
+        };
+        Ok(structure.to_pdb_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEPTIDE: &str = "\
+ATOM      1  N   ALA A   1       0.000   0.000   0.000  1.00  0.00           N
+ATOM      2  CA  ALA A   1       1.458   0.000   0.000  1.00  0.00           C
+ATOM      3  C   ALA A   1       2.009   1.420   0.000  1.00  0.00           C
+ATOM      4  O   ALA A   1       1.251   2.390   0.000  1.00  0.00           O
+";
+
+    // JsError can only be constructed on wasm32, so these cover the
+    // successful paths; the config errors are checked through `build`
+    #[test]
+    fn test_simulation_steps_and_exports() {
+        let config = r#"{"engine": {"use_gpu": true, "spring_k": 0.0}}"#.to_string();
+        let mut sim = Simulation::new(PEPTIDE, Some(config)).unwrap();
+        assert_eq!(sim.num_atoms().unwrap(), 4);
+        sim.minimize().unwrap();
+        sim.step(20).unwrap();
+
+        let positions = sim.positions().unwrap();
+        assert_eq!(positions.len(), 12);
+        assert!(positions.iter().all(|c| c.is_finite()));
+        assert_eq!(sim.elements().unwrap(), vec![7, 6, 6, 8]);
+        let stats: serde_json::Value = serde_json::from_str(&sim.statistics().unwrap()).unwrap();
+        assert_eq!(stats["current_step"], 20);
+        let pdb = sim.to_pdb().unwrap();
+        assert!(pdb.contains(" CA  ALA A   1"));
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let invalid = Simulation::build(PEPTIDE.as_bytes(), None, Some(r#"{"engine": {"dt": -1.0}}"#.to_string()));
+        assert!(invalid.err().unwrap().to_string().contains("dt"));
+        let with_system = Simulation::build(PEPTIDE.as_bytes(), None, Some(r#"{"system": {"topology": "a.prmtop"}}"#.to_string()));
+        assert!(with_system.is_err());
+    }
+}